@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+"""
+Evo-lang Jupyter包装内核
+Evo-lang Jupyter wrapper kernel
+
+ipykernel 负责 ZMQ 协议，单元格交给以 jupyter 特性构建的 evo.EvoKernel 执行
+ipykernel handles the ZMQ protocol; cells are executed by evo.EvoKernel built with the jupyter feature
+
+安装内核描述 / Install the kernel spec:
+    python evo_kernel.py install
+"""
+
+import json
+import os
+import sys
+import tempfile
+
+from ipykernel.kernelbase import Kernel
+
+import evo
+
+
+class EvoJupyterKernel(Kernel):
+    implementation = "evo"
+    implementation_version = "1.0.0"
+    language = "evo"
+    language_version = "1.0.0"
+    language_info = {
+        "name": "evo",
+        "mimetype": "text/x-evo",
+        "file_extension": ".evo",
+        "codemirror_mode": "scheme",
+    }
+    banner = "Evo-lang"
+
+    def __init__(self, **kwargs):
+        super().__init__(**kwargs)
+        # EVO_KERNEL_LANGUAGE=en 使用英文解释 / EVO_KERNEL_LANGUAGE=en explains in English
+        self.evo = evo.EvoKernel(language=os.environ.get("EVO_KERNEL_LANGUAGE", "zh"))
+
+    def do_execute(self, code, silent, store_history=True, user_expressions=None,
+                   allow_stdin=False):
+        cell = self.evo.execute(code)
+        if not silent:
+            if cell["stdout"]:
+                self.send_response(self.iopub_socket, "stream",
+                                   {"name": "stdout", "text": cell["stdout"]})
+            if cell["explanation"]:
+                self.send_response(self.iopub_socket, "display_data", {
+                    "data": {"text/plain": cell["explanation"]},
+                    "metadata": {"evo": {"explanation": True}},
+                })
+            if cell["data"]:
+                self.send_response(self.iopub_socket, "execute_result", {
+                    "execution_count": self.execution_count,
+                    "data": cell["data"],
+                    "metadata": {},
+                })
+
+        error = cell["error"]
+        if error:
+            content = {
+                "ename": error["ename"],
+                "evalue": error["evalue"],
+                "traceback": ["{}: {}".format(error["ename"], error["evalue"])],
+            }
+            self.send_response(self.iopub_socket, "error", content)
+            return dict(content, status="error", execution_count=self.execution_count)
+        return {
+            "status": "ok",
+            "execution_count": self.execution_count,
+            "payload": [],
+            "user_expressions": {},
+        }
+
+    def do_is_complete(self, code):
+        status = self.evo.is_complete(code)
+        if status == "incomplete":
+            return {"status": status, "indent": "  "}
+        return {"status": status}
+
+
+def install():
+    """安装当前用户的内核描述 / Install the kernel spec for the current user"""
+    from jupyter_client.kernelspec import KernelSpecManager
+
+    spec = {
+        "argv": [sys.executable, os.path.abspath(__file__), "-f", "{connection_file}"],
+        "display_name": "Evo-lang",
+        "language": "evo",
+    }
+    with tempfile.TemporaryDirectory() as directory:
+        with open(os.path.join(directory, "kernel.json"), "w") as f:
+            json.dump(spec, f, indent=2)
+        KernelSpecManager().install_kernel_spec(directory, "evo", user=True)
+    print("Installed the Evo-lang kernel spec")
+
+
+if __name__ == "__main__":
+    if sys.argv[1:] == ["install"]:
+        install()
+    else:
+        from ipykernel.kernelapp import IPKernelApp
+
+        IPKernelApp.launch_instance(kernel_class=EvoJupyterKernel)
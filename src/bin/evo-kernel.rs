@@ -0,0 +1,22 @@
+// Evo 笔记本内核 / Evo notebook kernel
+// 通过标准输入输出按行收发 JSON 消息，由 Jupyter 包装内核转发 / Exchanges line-delimited JSON messages over stdin
+// and stdout, relayed by a Jupyter wrapper kernel
+
+use evo::kernel::NotebookKernel;
+use evo::Language;
+
+fn main() {
+    // `--en` 使用英文解释 / `--en` explains in English
+    let language = if std::env::args().any(|arg| arg == "--en") {
+        Language::English
+    } else {
+        Language::Chinese
+    };
+    let mut kernel = NotebookKernel::new(language);
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    if let Err(e) = kernel.run(stdin.lock(), stdout.lock()) {
+        eprintln!("evo-kernel: {}", e);
+        std::process::exit(1);
+    }
+}
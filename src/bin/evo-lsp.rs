@@ -0,0 +1,18 @@
+// Evo 语言服务器 / Evo language server
+// 通过标准输入输出与编辑器通信 / Talks to editors over stdin and stdout
+
+use evo::lsp::LanguageServer;
+
+fn main() {
+    let mut server = LanguageServer::new();
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    if let Err(e) = server.run(stdin.lock(), stdout.lock()) {
+        eprintln!("evo-lsp: {}", e);
+        std::process::exit(1);
+    }
+    // 未先收到 shutdown 就退出时按协议返回 1 / Exit with 1 when exiting without shutdown, as the protocol requires
+    if !server.shutdown_requested() {
+        std::process::exit(1);
+    }
+}
@@ -2,7 +2,18 @@
 // 分析代码模式，提供优化建议
 // Analyzes code patterns and provides optimization suggestions
 
-use crate::grammar::core::{BinOp, Expr, GrammarElement, Literal};
+use crate::evolution::call_graph::{definitions, CallGraph, UnusedCode};
+use crate::evolution::fix_engine::{FixEngine, TestVerification};
+use crate::evolution::purity::PurityAnalysis;
+use crate::evolution::security::{SecurityIssueKind, SecurityScan};
+use crate::evolution::similarity::{extracted_name, CloneCluster, CloneKind};
+use crate::grammar::core::{Expr, GrammarElement, Literal};
+use crate::grammar::inference::TypeInference;
+use crate::grammar::unparse::unparse;
+use crate::grammar::visit::{
+    max_depth, walk_element, walk_element_mut, walk_program, Mutator, Position, Visitor,
+};
+use crate::optimizer::passes::{Pass, PassManager};
 use serde::{Deserialize, Serialize};
 
 /// 代码分析结果 / Code analysis result
@@ -16,6 +27,18 @@ pub struct CodeAnalysis {
     pub suggestions: Vec<OptimizationSuggestion>,
     /// 代码统计 / Code statistics
     pub statistics: CodeStatistics,
+    /// 调用图 / Call graph
+    #[serde(default)]
+    pub call_graph: CallGraph,
+    /// 未使用的代码 / Unused code
+    #[serde(default)]
+    pub unused: UnusedCode,
+    /// 安全问题 / Security issues
+    #[serde(default)]
+    pub security: SecurityScan,
+    /// 纯度分析 / Purity analysis
+    #[serde(default)]
+    pub purity: PurityAnalysis,
 }
 
 /// 代码模式 / Code pattern
@@ -48,6 +71,24 @@ pub enum PatternType {
     Simplifiable,
     /// 可以合并的代码 / Mergeable code
     Mergeable,
+    /// 可能的类型错误 / Probable type error
+    TypeError,
+    /// 不可达的函数 / Unreachable function
+    UnreachableFunction,
+    /// 未使用的参数 / Unused parameter
+    UnusedParameter,
+    /// 以计算得到的路径或地址访问文件或网络 / File or network access with a computed path or URL
+    UntrustedIo,
+    /// 无界递归 / Unbounded recursion
+    UnboundedRecursion,
+    /// 无界循环 / Unbounded loop
+    UnboundedLoop,
+    /// 不受预算限制地执行生成的代码 / Evaluation of generated code without a budget
+    UnbudgetedEval,
+    /// 过于宽松的导入 / Overly permissive import
+    PermissiveImport,
+    /// 对非纯函数的缓存 / Memoizing an impure function
+    ImpureMemoization,
 }
 
 /// 优化建议 / Optimization suggestion
@@ -80,6 +121,8 @@ pub enum SuggestionType {
     RemoveDuplication,
     /// 优化性能 / Optimize performance
     OptimizePerformance,
+    /// 删除未使用的代码 / Remove unused code
+    RemoveUnused,
 }
 
 /// 代码统计 / Code statistics
@@ -109,7 +152,14 @@ impl CodeAnalyzer {
     /// 分析代码 / Analyze code
     pub fn analyze(&self, ast: &[GrammarElement]) -> CodeAnalysis {
         let mut statistics = self.collect_statistics(ast);
-        let patterns = self.detect_patterns(ast);
+        let call_graph = CallGraph::build(ast);
+        let unused = UnusedCode::find(ast, &call_graph);
+        let security = SecurityScan::scan(ast, &call_graph);
+        let purity = PurityAnalysis::analyze(ast, &call_graph);
+        let mut patterns = self.detect_patterns(ast);
+        self.detect_unused_code(&unused, &mut patterns);
+        self.detect_security_issues(&security, &mut patterns);
+        self.detect_impure_memoization(&purity, &mut patterns);
         let suggestions = self.generate_suggestions(ast, &patterns);
 
         // 计算复杂度 / Calculate complexity
@@ -120,85 +170,74 @@ impl CodeAnalyzer {
             patterns,
             suggestions,
             statistics,
+            call_graph,
+            unused,
+            security,
+            purity,
         }
     }
 
     /// 收集统计信息 / Collect statistics
     fn collect_statistics(&self, ast: &[GrammarElement]) -> CodeStatistics {
-        let mut function_count = 0;
-        let mut variable_count = 0;
-        let mut total_function_length = 0;
-        let mut max_nesting = 0;
-        let mut total_complexity = 0.0;
-
-        self.collect_statistics_recursive(
-            ast,
-            0,
-            &mut function_count,
-            &mut variable_count,
-            &mut total_function_length,
-            &mut max_nesting,
-            &mut total_complexity,
-        );
-
-        CodeStatistics {
-            function_count,
-            variable_count,
-            avg_function_length: if function_count > 0 {
-                total_function_length as f64 / function_count as f64
-            } else {
-                0.0
-            },
-            max_nesting_depth: max_nesting,
-            expression_complexity: total_complexity,
+        struct Statistics<'a> {
+            analyzer: &'a CodeAnalyzer,
+            function_count: usize,
+            variable_count: usize,
+            total_function_length: usize,
+            total_complexity: f64,
         }
-    }
 
-    /// 递归收集统计信息 / Recursively collect statistics
-    fn collect_statistics_recursive(
-        &self,
-        elements: &[GrammarElement],
-        depth: usize,
-        function_count: &mut usize,
-        variable_count: &mut usize,
-        total_function_length: &mut usize,
-        max_nesting: &mut usize,
-        total_complexity: &mut f64,
-    ) {
-        *max_nesting = (*max_nesting).max(depth);
-
-        for element in elements {
-            match element {
-                GrammarElement::List(list) => {
-                    if let Some(GrammarElement::Atom(first)) = list.first() {
-                        match first.as_str() {
-                            "def" | "function" => {
-                                *function_count += 1;
-                                *total_function_length += list.len();
-                            }
-                            "let" => {
-                                *variable_count += 1;
-                            }
-                            _ => {}
+        impl<'ast> Visitor<'ast> for Statistics<'_> {
+            fn enter_element(&mut self, element: &'ast GrammarElement, _: Position) -> bool {
+                match element {
+                    GrammarElement::List(list) => match list.first() {
+                        Some(GrammarElement::Atom(head))
+                            if matches!(head.as_str(), "def" | "function" | "defn-") =>
+                        {
+                            self.function_count += 1;
+                            self.total_function_length += list.len();
                         }
+                        Some(GrammarElement::Atom(head)) if head == "let" => {
+                            self.variable_count += 1
+                        }
+                        _ => {}
+                    },
+                    GrammarElement::Expr(expr) => {
+                        self.total_complexity += self.analyzer.expr_complexity(expr)
                     }
-                    self.collect_statistics_recursive(
-                        list,
-                        depth + 1,
-                        function_count,
-                        variable_count,
-                        total_function_length,
-                        max_nesting,
-                        total_complexity,
-                    );
+                    _ => {}
                 }
-                GrammarElement::Expr(expr) => {
-                    *total_complexity += self.expr_complexity(expr);
+                true
+            }
+
+            fn enter_expr(&mut self, expr: &'ast Expr, _: Position) -> bool {
+                if matches!(expr, Expr::Call(name, _) if name == "let") {
+                    self.variable_count += 1;
                 }
-                GrammarElement::Atom(_) => {}
-                GrammarElement::NaturalLang(_) => {}
+                true
             }
         }
+
+        let mut statistics = Statistics {
+            analyzer: self,
+            function_count: 0,
+            variable_count: 0,
+            total_function_length: 0,
+            total_complexity: 0.0,
+        };
+        walk_program(&mut statistics, ast);
+
+        CodeStatistics {
+            function_count: statistics.function_count,
+            variable_count: statistics.variable_count,
+            avg_function_length: if statistics.function_count > 0 {
+                statistics.total_function_length as f64 / statistics.function_count as f64
+            } else {
+                0.0
+            },
+            max_nesting_depth: max_depth(ast),
+            expression_complexity: statistics.total_complexity,
+        }
     }
 
     /// 计算表达式复杂度 / Calculate expression complexity
@@ -230,6 +269,9 @@ impl CodeAnalyzer {
         // 检测深度嵌套 / Detect deep nesting
         self.detect_deep_nesting(ast, &mut patterns);
 
+        // 检测可能的类型错误 / Detect probable type errors
+        self.detect_type_errors(ast, &mut patterns);
+
         patterns
     }
 
@@ -238,7 +280,7 @@ impl CodeAnalyzer {
         for element in ast {
             if let GrammarElement::List(list) = element {
                 if let Some(GrammarElement::Atom(first)) = list.first() {
-                    if first == "def" || first == "function" {
+                    if matches!(first.as_str(), "def" | "function" | "defn-") {
                         if list.len() > 10 {
                             patterns.push(CodePattern {
                                 pattern_type: PatternType::LongFunction,
@@ -272,27 +314,104 @@ impl CodeAnalyzer {
 
     /// 检测深度嵌套 / Detect deep nesting
     fn detect_deep_nesting(&self, ast: &[GrammarElement], patterns: &mut Vec<CodePattern>) {
-        let max_depth = self.max_nesting_depth(ast, 0);
-        if max_depth > 4 {
+        let depth = max_depth(ast);
+        if depth > 4 {
             patterns.push(CodePattern {
                 pattern_type: PatternType::DeepNesting,
-                description: format!("最大嵌套深度: {}", max_depth),
+                description: format!("最大嵌套深度: {}", depth),
                 location: "code".to_string(),
                 confidence: 0.8,
             });
         }
     }
 
-    /// 计算最大嵌套深度 / Calculate max nesting depth
-    fn max_nesting_depth(&self, ast: &[GrammarElement], current: usize) -> usize {
-        let mut max = current;
-        for element in ast {
-            if let GrammarElement::List(list) = element {
-                let depth = self.max_nesting_depth(list, current + 1);
-                max = max.max(depth);
-            }
+    /// 检测可能的类型错误（无需标注，静态推断）/ Detect probable type errors (statically inferred, no annotations needed)
+    fn detect_type_errors(&self, ast: &[GrammarElement], patterns: &mut Vec<CodePattern>) {
+        let result = TypeInference::new().infer_program(ast);
+        for error in result.errors {
+            patterns.push(CodePattern {
+                pattern_type: PatternType::TypeError,
+                description: format!("类型错误: {}", error.message),
+                location: match error.function {
+                    Some(function) => format!("function {}", function),
+                    None => "expression".to_string(),
+                },
+                confidence: 0.9,
+            });
+        }
+    }
+
+    /// 报告不可达的函数和未使用的变量、参数 / Report unreachable functions and unused variables and parameters
+    fn detect_unused_code(&self, unused: &UnusedCode, patterns: &mut Vec<CodePattern>) {
+        let location = |function: &Option<String>| match function {
+            Some(function) => format!("function {}", function),
+            None => "top-level".to_string(),
+        };
+        for function in &unused.unreachable_functions {
+            patterns.push(CodePattern {
+                pattern_type: PatternType::UnreachableFunction,
+                description: format!("函数 {} 不会从程序入口被调用", function),
+                location: format!("function {}", function),
+                confidence: 0.9,
+            });
+        }
+        for variable in &unused.unused_variables {
+            patterns.push(CodePattern {
+                pattern_type: PatternType::UnusedVariable,
+                description: format!("变量 {} 定义后没有被使用", variable.name),
+                location: location(&variable.function),
+                confidence: 0.9,
+            });
+        }
+        for parameter in &unused.unused_parameters {
+            patterns.push(CodePattern {
+                pattern_type: PatternType::UnusedParameter,
+                description: format!("参数 {} 在函数体中没有被使用", parameter.name),
+                location: location(&parameter.function),
+                confidence: 0.8,
+            });
+        }
+    }
+
+    /// 报告对非纯函数的 `memoize`：缓存会跳过副作用并返回过时的结果
+    /// Report `memoize` applied to impure functions: the cache skips the side effects and returns stale results
+    fn detect_impure_memoization(&self, purity: &PurityAnalysis, patterns: &mut Vec<CodePattern>) {
+        for memoization in &purity.impure_memoizations {
+            patterns.push(CodePattern {
+                pattern_type: PatternType::ImpureMemoization,
+                description: format!(
+                    "memoize 缓存了非纯函数 {}（{}），缓存命中时副作用不会发生",
+                    memoization.function, memoization.reason
+                ),
+                location: match &memoization.location {
+                    Some(function) => format!("function {}", function),
+                    None => "top-level".to_string(),
+                },
+                confidence: 0.8,
+            });
+        }
+    }
+
+    /// 检测安全问题 / Detect security issues
+    fn detect_security_issues(&self, security: &SecurityScan, patterns: &mut Vec<CodePattern>) {
+        for issue in &security.issues {
+            let (pattern_type, confidence) = match issue.kind {
+                SecurityIssueKind::UntrustedIo => (PatternType::UntrustedIo, 0.7),
+                SecurityIssueKind::UnboundedRecursion => (PatternType::UnboundedRecursion, 0.9),
+                SecurityIssueKind::UnboundedLoop => (PatternType::UnboundedLoop, 0.7),
+                SecurityIssueKind::UnbudgetedEval => (PatternType::UnbudgetedEval, 0.9),
+                SecurityIssueKind::PermissiveImport => (PatternType::PermissiveImport, 0.8),
+            };
+            patterns.push(CodePattern {
+                pattern_type,
+                description: issue.description.clone(),
+                location: match &issue.function {
+                    Some(function) => format!("function {}", function),
+                    None => "top-level".to_string(),
+                },
+                confidence,
+            });
         }
-        max
     }
 
     /// 生成优化建议 / Generate optimization suggestions
@@ -332,6 +451,15 @@ impl CodeAnalyzer {
                         improvement: 0.8,
                     });
                 }
+                PatternType::UnreachableFunction | PatternType::UnusedVariable => {
+                    suggestions.push(OptimizationSuggestion {
+                        suggestion_type: SuggestionType::RemoveUnused,
+                        description: format!("考虑删除未使用的代码（{}）", pattern.description),
+                        original: pattern.location.clone(),
+                        suggested: "removed".to_string(),
+                        improvement: 0.5,
+                    });
+                }
                 _ => {}
             }
         }
@@ -355,6 +483,48 @@ impl Default for CodeAnalyzer {
     }
 }
 
+/// 提取公共函数的结果 / Result of extracting a common function
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedFunction {
+    /// 新的公共函数名 / Name of the new shared function
+    pub name: String,
+    /// 公共函数的参数 / Parameters of the shared function
+    pub params: Vec<String>,
+    /// 改为委托给公共函数的原函数 / Original functions that now delegate to the shared function
+    pub replaced: Vec<String>,
+    /// 改写为直接调用公共函数的调用点数 / Call sites rewritten to call the shared function directly
+    pub call_sites: usize,
+    /// 重写后的程序 / Rewritten program
+    pub ast: Vec<GrammarElement>,
+    /// 生成测试的验证结果 / Verification with generated tests
+    pub verification: TestVerification,
+}
+
+/// 提取公共函数错误 / Common function extraction error
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ExtractError {
+    /// 近似克隆的差异不只是字面量，无法参数化 / Near-miss clones differ in more than literals and cannot be parameterized
+    NearMiss,
+    /// AST中找不到克隆实例的函数 / The function of a clone instance is not in the AST
+    FunctionNotFound(String),
+    /// 提取后生成的测试输出改变 / Generated tests changed their output after the extraction
+    TestRegression(Vec<String>),
+}
+
+impl std::fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExtractError::NearMiss => write!(f, "Near-miss clones cannot be extracted"),
+            ExtractError::FunctionNotFound(name) => write!(f, "Function not found: {}", name),
+            ExtractError::TestRegression(tests) => {
+                write!(f, "Tests regressed: {}", tests.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExtractError {}
+
 /// 代码重构器 / Code refactorer
 pub struct CodeRefactorer;
 
@@ -384,71 +554,180 @@ impl CodeRefactorer {
             }
         }
 
-        refactored
-    }
+        // 删除未使用的代码只作用于可安全删除的候选 / Removing unused code only touches safe-delete candidates
+        if analysis
+            .suggestions
+            .iter()
+            .any(|s| matches!(s.suggestion_type, SuggestionType::RemoveUnused))
+        {
+            refactored = analysis.unused.remove_safely(&refactored);
+        }
 
-    /// 简化表达式 / Simplify expressions
-    fn simplify_expressions(&self, ast: &[GrammarElement]) -> Vec<GrammarElement> {
-        ast.iter().map(|elem| self.simplify_element(elem)).collect()
+        refactored
     }
 
-    /// 简化元素 / Simplify element
-    fn simplify_element(&self, element: &GrammarElement) -> GrammarElement {
-        match element {
-            GrammarElement::Expr(expr) => GrammarElement::Expr(Box::new(self.simplify_expr(expr))),
-            GrammarElement::List(list) => {
-                GrammarElement::List(list.iter().map(|e| self.simplify_element(e)).collect())
+    /// 把克隆簇（`Exact` 或 `Renamed`）提取为一个公共函数：以第一个实例的函数体为模板，实例之间不同的字面量
+    /// 变为 `value` 参数；原函数改为委托给公共函数，程序中对它们的调用改为直接调用公共函数。
+    /// 用以原程序生成的测试验证，输出改变时返回错误
+    /// Extract a clone cluster (`Exact` or `Renamed`) into one common function: the body of the first instance is
+    /// the template and literals that differ between instances become `value` parameters; the original functions
+    /// delegate to the common function and calls to them in the program call it directly. The result is verified
+    /// with tests generated from the original program, and an error is returned when their output changes
+    pub fn extract_common_function(
+        &self,
+        ast: &[GrammarElement],
+        cluster: &CloneCluster,
+    ) -> Result<ExtractedFunction, ExtractError> {
+        if cluster.kind == CloneKind::NearMiss {
+            return Err(ExtractError::NearMiss);
+        }
+        let all_definitions = definitions(ast);
+        let mut instances = Vec::new();
+        for instance in &cluster.instances {
+            let definition = all_definitions
+                .iter()
+                .find(|d| d.name == instance.function)
+                .ok_or_else(|| ExtractError::FunctionNotFound(instance.function.clone()))?;
+            instances.push(definition);
+        }
+        let template = instances[0];
+
+        // 实例之间不同的字面量位置 / Literal positions that differ between instances
+        let literals: Vec<Vec<Literal>> = instances
+            .iter()
+            .map(|d| {
+                let mut literals = Vec::new();
+                for element in d.body {
+                    collect_literals(element, &mut literals);
+                }
+                literals
+            })
+            .collect();
+        let varying: Vec<usize> = (0..literals[0].len())
+            .filter(|&k| literals.iter().any(|l| l.get(k) != literals[0].get(k)))
+            .collect();
+
+        let mut params = template.params.clone();
+        let mut value_params = Vec::new();
+        for _ in &varying {
+            let mut candidate = "value".to_string();
+            let mut suffix = 2;
+            while params.contains(&candidate) {
+                candidate = format!("value{}", suffix);
+                suffix += 1;
             }
-            _ => element.clone(),
+            params.push(candidate.clone());
+            value_params.push(candidate);
         }
-    }
 
-    /// 简化表达式 / Simplify expression
-    fn simplify_expr(&self, expr: &Expr) -> Expr {
-        match expr {
-            Expr::Binary(op, left, right) => {
-                // 尝试常量折叠 / Try constant folding
-                if let (Expr::Literal(Literal::Int(a)), Expr::Literal(Literal::Int(b))) =
-                    (left.as_ref(), right.as_ref())
-                {
-                    let result = match op {
-                        BinOp::Add => a + b,
-                        BinOp::Sub => a - b,
-                        BinOp::Mul => a * b,
-                        BinOp::Div => {
-                            if *b != 0 {
-                                a / b
-                            } else {
-                                return expr.clone();
-                            }
-                        }
-                        BinOp::Mod => {
-                            if *b != 0 {
-                                a % b
-                            } else {
-                                return expr.clone();
-                            }
-                        }
-                        _ => return expr.clone(),
-                    };
-                    return Expr::Literal(Literal::Int(result));
-                }
-                expr.clone()
+        let existing: Vec<&str> = all_definitions.iter().map(|d| d.name.as_str()).collect();
+        let functions: Vec<&str> = instances.iter().map(|d| d.name.as_str()).collect();
+        let base = extracted_name(&functions);
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while existing.contains(&name.as_str()) {
+            name = format!("{}{}", base, suffix);
+            suffix += 1;
+        }
+
+        // 原函数名 → 调用公共函数时追加的字面量参数 / Original name → literal arguments appended when calling the common function
+        let delegations: Vec<(String, usize, Vec<Expr>)> = instances
+            .iter()
+            .zip(&literals)
+            .map(|(d, literals)| {
+                let extra = varying
+                    .iter()
+                    .map(|&k| Expr::Literal(literals[k].clone()))
+                    .collect();
+                (d.name.clone(), d.params.len(), extra)
+            })
+            .collect();
+
+        // 公共函数体：可变字面量替换为参数，对模板自身的递归调用改为调用公共函数
+        // Common function body: varying literals become parameters, recursion on the template calls the common function
+        let mut body = template.body.to_vec();
+        let mut position = 0;
+        for element in &mut body {
+            replace_literals(element, &mut position, &varying, &value_params);
+        }
+        let recursion = [(
+            template.name.clone(),
+            template.params.len(),
+            value_params
+                .iter()
+                .map(|param| Expr::Var(param.as_str().into()))
+                .collect::<Vec<_>>(),
+        )];
+        for element in &mut body {
+            rewrite_calls(element, &name, &recursion);
+        }
+        let mut call_sites = 0;
+        for element in &mut body {
+            call_sites += rewrite_calls(element, &name, &delegations);
+        }
+        let mut common = vec![
+            GrammarElement::Atom("def".to_string()),
+            GrammarElement::Atom(name.clone()),
+            GrammarElement::List(
+                params
+                    .iter()
+                    .map(|param| GrammarElement::Expr(Box::new(Expr::Var(param.as_str().into()))))
+                    .collect(),
+            ),
+        ];
+        common.extend(body);
+
+        let mut rewritten = Vec::with_capacity(ast.len() + 1);
+        for (index, element) in ast.iter().enumerate() {
+            if index == template.index {
+                rewritten.push(GrammarElement::List(common.clone()));
             }
-            Expr::If(cond, then_expr, else_expr) => {
-                // 简化条件表达式 / Simplify conditional expression
-                Expr::If(
-                    Box::new(self.simplify_expr(cond)),
-                    Box::new(self.simplify_expr(then_expr)),
-                    Box::new(self.simplify_expr(else_expr)),
-                )
+            match instances
+                .iter()
+                .zip(&delegations)
+                .find(|(d, _)| d.index == index)
+            {
+                Some((definition, (_, _, extra))) => {
+                    let mut args: Vec<Expr> = definition
+                        .params
+                        .iter()
+                        .map(|param| Expr::Var(param.as_str().into()))
+                        .collect();
+                    args.extend(extra.iter().cloned());
+                    rewritten.push(delegate_definition(
+                        element,
+                        definition.body.len(),
+                        Expr::Call(name.as_str().into(), args),
+                    ));
+                }
+                None => {
+                    let mut element = element.clone();
+                    call_sites += rewrite_calls(&mut element, &name, &delegations);
+                    rewritten.push(element);
+                }
             }
-            Expr::Call(name, args) => Expr::Call(
-                name.clone(),
-                args.iter().map(|a| self.simplify_expr(a)).collect(),
-            ),
-            _ => expr.clone(),
         }
+
+        let verification = FixEngine::new().verify_tests(&unparse(ast), &rewritten, &[]);
+        if !verification.regressions.is_empty() {
+            return Err(ExtractError::TestRegression(verification.regressions));
+        }
+
+        Ok(ExtractedFunction {
+            name,
+            params,
+            replaced: functions.iter().map(|name| name.to_string()).collect(),
+            call_sites,
+            ast: rewritten,
+            verification,
+        })
+    }
+
+    /// 简化表达式：常量折叠与死分支消除 / Simplify expressions: constant folding and dead branch elimination
+    fn simplify_expressions(&self, ast: &[GrammarElement]) -> Vec<GrammarElement> {
+        PassManager::with_passes(vec![Pass::ConstantFolding, Pass::DeadBranchElimination])
+            .run(ast)
+            .ast
     }
 
     /// 减少嵌套 / Reduce nesting
@@ -466,6 +745,155 @@ impl CodeRefactorer {
     }
 }
 
+/// 标量字面量：非空且不是列表或字典 / Scalar literal: not null and not a list or dict
+fn scalar_literal(expr: &Expr) -> Option<&Literal> {
+    match expr {
+        Expr::Literal(Literal::Null | Literal::List(_) | Literal::Dict(_)) => None,
+        Expr::Literal(literal) => Some(literal),
+        _ => None,
+    }
+}
+
+/// 按先序收集标量字面量（不含模式中的字面量）/ Collect scalar literals in pre-order (literals in patterns excluded)
+fn collect_literals(element: &GrammarElement, literals: &mut Vec<Literal>) {
+    struct Literals<'a>(&'a mut Vec<Literal>);
+
+    impl Visitor<'_> for Literals<'_> {
+        fn enter_expr(&mut self, expr: &Expr, _: Position) -> bool {
+            if let Some(literal) = scalar_literal(expr) {
+                self.0.push(literal.clone());
+            }
+            true
+        }
+    }
+
+    walk_element(&mut Literals(literals), element, Position::default());
+}
+
+/// 把第 `varying[i]` 个标量字面量（与 `collect_literals` 同序）替换为参数 `params[i]`
+/// Replace the `varying[i]`-th scalar literal (in `collect_literals` order) with parameter `params[i]`
+fn replace_literals(
+    element: &mut GrammarElement,
+    position: &mut usize,
+    varying: &[usize],
+    params: &[String],
+) {
+    struct Replace<'a> {
+        position: &'a mut usize,
+        varying: &'a [usize],
+        params: &'a [String],
+    }
+
+    impl Mutator for Replace<'_> {
+        fn enter_expr(&mut self, expr: &mut Expr, _: Position) -> bool {
+            if scalar_literal(expr).is_some() {
+                if let Some(i) = self.varying.iter().position(|&k| k == *self.position) {
+                    *expr = Expr::Var(self.params[i].as_str().into());
+                }
+                *self.position += 1;
+            }
+            true
+        }
+    }
+
+    let mut replace = Replace {
+        position,
+        varying,
+        params,
+    };
+    walk_element_mut(&mut replace, element, Position::default());
+}
+
+/// 把对 `(原函数名, 参数个数, 追加参数)` 的调用改为调用 `target` 并追加参数，返回改写的调用数
+/// Rewrite calls to `(original name, arity, extra arguments)` into calls to `target` with the extra arguments
+/// appended; returns the number of calls rewritten
+fn rewrite_calls(
+    element: &mut GrammarElement,
+    target: &str,
+    delegations: &[(String, usize, Vec<Expr>)],
+) -> usize {
+    struct Rewrite<'a> {
+        target: &'a str,
+        delegations: &'a [(String, usize, Vec<Expr>)],
+        count: usize,
+    }
+
+    impl Rewrite<'_> {
+        fn extra(&self, name: &str, arity: usize) -> Option<&[Expr]> {
+            self.delegations
+                .iter()
+                .find(|(original, params, _)| original == name && *params == arity)
+                .map(|(_, _, extra)| extra.as_slice())
+        }
+    }
+
+    impl Mutator for Rewrite<'_> {
+        fn enter_element(&mut self, element: &mut GrammarElement, _: Position) -> bool {
+            if let GrammarElement::List(items) = element {
+                let arity = items.len().saturating_sub(1);
+                let extra = match items.first() {
+                    Some(GrammarElement::Atom(head)) => {
+                        self.extra(head, arity).map(<[Expr]>::to_vec)
+                    }
+                    _ => None,
+                };
+                if let Some(extra) = extra {
+                    items[0] = GrammarElement::Atom(self.target.to_string());
+                    items.extend(
+                        extra
+                            .into_iter()
+                            .map(|arg| GrammarElement::Expr(Box::new(arg))),
+                    );
+                    self.count += 1;
+                }
+            }
+            true
+        }
+
+        fn enter_expr(&mut self, expr: &mut Expr, _: Position) -> bool {
+            if let Expr::Call(name, args) = expr {
+                if let Some(extra) = self.extra(name, args.len()).map(<[Expr]>::to_vec) {
+                    *name = self.target.into();
+                    args.extend(extra);
+                    self.count += 1;
+                }
+            }
+            true
+        }
+    }
+
+    let mut rewrite = Rewrite {
+        target,
+        delegations,
+        count: 0,
+    };
+    walk_element_mut(&mut rewrite, element, Position::default());
+    rewrite.count
+}
+
+/// 把定义的函数体替换为一个委托调用（保留类型标注）
+/// Replace the body of a definition with one delegating call (type annotations are kept)
+fn delegate_definition(element: &GrammarElement, body_len: usize, call: Expr) -> GrammarElement {
+    let call = GrammarElement::Expr(Box::new(call));
+    let GrammarElement::List(items) = element else {
+        return element.clone();
+    };
+    let mut items = items.clone();
+    match items.first() {
+        Some(GrammarElement::Atom(head)) if head == "let" => {
+            if let Some(GrammarElement::List(lambda)) = items.get_mut(2) {
+                lambda.truncate(2);
+                lambda.push(call);
+            }
+        }
+        _ => {
+            let end = (3 + body_len).min(items.len());
+            items.splice(3..end, [call]);
+        }
+    }
+    GrammarElement::List(items)
+}
+
 impl Default for CodeRefactorer {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,717 @@
+// 调用图与未使用代码检测 / Call graph and unused code detection
+// 收集顶层函数之间的调用关系（包括经参数传入并被调用的函数和Lambda），
+// 据此找出不可达的函数、未使用的变量和参数
+// Collects calls between top-level functions (including functions and lambdas passed as arguments and called
+// through parameters), and finds unreachable functions and unused variables and parameters from them
+
+use crate::grammar::core::{Expr, GrammarElement};
+use crate::grammar::types::{definition_docstring, element_name, string_literal};
+use crate::grammar::visit::{walk_element, walk_expr, Position, Visitor};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// 不执行代码、只引入定义的顶层形式 / Top-level forms that only introduce definitions
+const DEFINITION_FORMS: [&str; 9] = [
+    "def", "function", "defn-", "let", "defconst", "deftype", "defmacro", "import", "export",
+];
+
+/// 调用图 / Call graph
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CallGraph {
+    /// 顶层函数（`def` 和绑定Lambda的顶层 `let`），按定义顺序 / Top-level functions (`def` and top-level `let` bound to a lambda) in definition order
+    pub functions: Vec<String>,
+    /// 调用者 → 被调用者（只含本程序定义的函数）/ Caller → callees (functions defined by the program only)
+    pub calls: BTreeMap<String, BTreeSet<String>>,
+    /// 顶层代码和 `export` 直接引用的函数 / Functions referenced directly by top-level code and `export`
+    pub roots: BTreeSet<String>,
+    /// 程序是否有入口（顶层执行代码或 `export`）；没有入口的程序视为库，所有公开函数都可从外部调用
+    /// Whether the program has an entry (top-level code or `export`); programs without one are libraries whose
+    /// public functions are all callable from outside
+    pub has_entry: bool,
+    /// 通过 `defn-` 定义的私有函数 / Private functions defined via `defn-`
+    #[serde(default)]
+    pub private: BTreeSet<String>,
+}
+
+impl CallGraph {
+    /// 构建调用图 / Build the call graph
+    pub fn build(ast: &[GrammarElement]) -> Self {
+        let definitions = definitions(ast);
+        let functions: Vec<String> = definitions.iter().map(|d| d.name.clone()).collect();
+        let known: HashSet<&str> = functions.iter().map(String::as_str).collect();
+        let private = definitions
+            .iter()
+            .filter(|d| list_head(&ast[d.index]) == Some("defn-"))
+            .map(|d| d.name.clone())
+            .collect();
+
+        // 在函数体中被调用的参数位置 / Parameter positions called inside the function body
+        let called_params: HashMap<&str, Vec<usize>> = definitions
+            .iter()
+            .map(|d| {
+                let mut heads = HashSet::new();
+                for element in d.body {
+                    collect_call_heads(element, &mut heads);
+                }
+                let positions = d
+                    .params
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, param)| heads.contains(param.as_str()))
+                    .map(|(index, _)| index)
+                    .collect();
+                (d.name.as_str(), positions)
+            })
+            .collect();
+
+        let mut calls: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut record = |caller: &str, callee: &str| {
+            calls
+                .entry(caller.to_string())
+                .or_default()
+                .insert(callee.to_string());
+        };
+
+        for definition in &definitions {
+            let mut references = Vec::new();
+            for element in definition.body {
+                collect_references(element, &mut references);
+            }
+            for reference in references.iter().filter(|r| known.contains(r.as_str())) {
+                record(&definition.name, reference);
+            }
+        }
+
+        // 经参数传入的函数由高阶函数调用 / Functions passed as arguments are called by the higher-order function
+        let mut sites = Vec::new();
+        for element in ast {
+            collect_call_sites(element, &mut sites);
+        }
+        for (callee, args) in sites {
+            let Some(positions) = called_params.get(callee.as_str()) else {
+                continue;
+            };
+            for &position in positions {
+                let Some(arg) = args.get(position) else {
+                    continue;
+                };
+                let mut references = Vec::new();
+                match arg {
+                    Argument::Name(name) => references.push(name.clone()),
+                    Argument::Lambda(body) => {
+                        for element in body {
+                            collect_references(element, &mut references);
+                        }
+                    }
+                }
+                for reference in references.iter().filter(|r| known.contains(r.as_str())) {
+                    record(&callee, reference);
+                }
+            }
+        }
+
+        let mut roots = BTreeSet::new();
+        let mut has_entry = false;
+        for element in ast {
+            let head = list_head(element).or_else(|| match element {
+                GrammarElement::Expr(expr) => match expr.as_ref() {
+                    Expr::Call(name, _) => Some(name.as_str()),
+                    _ => None,
+                },
+                _ => None,
+            });
+            if is_function_definition(element) {
+                continue;
+            }
+            if head.is_none_or(|head| !DEFINITION_FORMS.contains(&head) || head == "export") {
+                has_entry = true;
+            }
+            let mut references = Vec::new();
+            collect_references(element, &mut references);
+            roots.extend(
+                references
+                    .into_iter()
+                    .filter(|reference| known.contains(reference.as_str())),
+            );
+        }
+
+        Self {
+            functions,
+            calls,
+            roots,
+            has_entry,
+            private,
+        }
+    }
+
+    /// 函数调用的函数 / Functions called by a function
+    pub fn callees(&self, function: &str) -> Vec<&str> {
+        self.calls
+            .get(function)
+            .map(|callees| callees.iter().map(String::as_str).collect())
+            .unwrap_or_default()
+    }
+
+    /// 调用函数的函数 / Functions calling a function
+    pub fn callers(&self, function: &str) -> Vec<&str> {
+        self.calls
+            .iter()
+            .filter(|(_, callees)| callees.contains(function))
+            .map(|(caller, _)| caller.as_str())
+            .collect()
+    }
+
+    /// 从入口可达的函数；没有入口时从全部公开函数出发
+    /// Functions reachable from the entry; starting from all public functions when there is none
+    pub fn reachable(&self) -> BTreeSet<String> {
+        let mut reachable = BTreeSet::new();
+        let mut pending: Vec<&String> = if self.has_entry {
+            self.roots.iter().collect()
+        } else {
+            self.functions
+                .iter()
+                .filter(|function| !self.private.contains(*function))
+                .collect()
+        };
+        while let Some(function) = pending.pop() {
+            if reachable.insert(function.clone()) {
+                if let Some(callees) = self.calls.get(function) {
+                    pending.extend(callees);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// 不可达的函数，按定义顺序 / Unreachable functions in definition order
+    pub fn unreachable_functions(&self) -> Vec<String> {
+        let reachable = self.reachable();
+        self.functions
+            .iter()
+            .filter(|function| !reachable.contains(*function))
+            .cloned()
+            .collect()
+    }
+}
+
+/// 未使用的绑定 / Unused binding
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnusedBinding {
+    /// 名称 / Name
+    pub name: String,
+    /// 所在函数，顶层绑定为None / Enclosing function; None for top-level bindings
+    pub function: Option<String>,
+    /// 初始值没有副作用，可以安全删除 / The initial value has no side effects, so the binding can be deleted safely
+    pub removable: bool,
+}
+
+/// 未使用的代码 / Unused code
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UnusedCode {
+    /// 不可达的函数 / Unreachable functions
+    pub unreachable_functions: Vec<String>,
+    /// 未使用的变量 / Unused variables
+    pub unused_variables: Vec<UnusedBinding>,
+    /// 未使用的参数 / Unused parameters
+    pub unused_parameters: Vec<UnusedBinding>,
+}
+
+impl UnusedCode {
+    /// 查找未使用的代码；以 `_` 开头的名称视为有意不使用
+    /// Find unused code; names starting with `_` are treated as intentionally unused
+    pub fn find(ast: &[GrammarElement], graph: &CallGraph) -> Self {
+        let definitions = definitions(ast);
+        let mut unused = Self {
+            unreachable_functions: graph.unreachable_functions(),
+            ..Self::default()
+        };
+
+        for definition in &definitions {
+            let mut references = Vec::new();
+            for element in definition.body {
+                collect_references(element, &mut references);
+            }
+            let used: HashSet<&str> = references.iter().map(String::as_str).collect();
+            for param in &definition.params {
+                if !param.starts_with('_') && !used.contains(param.as_str()) {
+                    unused.unused_parameters.push(UnusedBinding {
+                        name: param.clone(),
+                        function: Some(definition.name.clone()),
+                        removable: false,
+                    });
+                }
+            }
+            let mut locals = Vec::new();
+            for element in definition.body {
+                collect_local_bindings(element, &mut locals);
+            }
+            for (name, removable) in locals {
+                if !name.starts_with('_')
+                    && !used.contains(name.as_str())
+                    && !definition.params.contains(&name)
+                {
+                    let binding = UnusedBinding {
+                        name,
+                        function: Some(definition.name.clone()),
+                        removable,
+                    };
+                    if !unused.unused_variables.contains(&binding) {
+                        unused.unused_variables.push(binding);
+                    }
+                }
+            }
+        }
+
+        // 顶层变量可被任何函数引用；库没有入口，其变量视为公开
+        // Top-level variables may be referenced from any function; libraries have no entry and their variables are public
+        if graph.has_entry {
+            let mut references = Vec::new();
+            for element in ast {
+                collect_references(element, &mut references);
+            }
+            let used: HashSet<&str> = references.iter().map(String::as_str).collect();
+            for element in ast {
+                if is_function_definition(element) {
+                    continue;
+                }
+                let Some((name, value)) = let_binding(element) else {
+                    continue;
+                };
+                if !name.starts_with('_') && !used.contains(name.as_str()) {
+                    unused.unused_variables.push(UnusedBinding {
+                        name,
+                        function: None,
+                        removable: value.is_none_or(is_pure_element),
+                    });
+                }
+            }
+        }
+        unused
+    }
+
+    /// 是否没有发现 / Whether nothing was found
+    pub fn is_empty(&self) -> bool {
+        self.unreachable_functions.is_empty()
+            && self.unused_variables.is_empty()
+            && self.unused_parameters.is_empty()
+    }
+
+    /// 可安全删除的顶层定义：不可达的函数和初始值无副作用的未使用顶层变量
+    /// Top-level definitions that are safe to delete: unreachable functions and unused top-level variables without side effects
+    pub fn safe_delete_candidates(&self) -> Vec<&str> {
+        self.unreachable_functions
+            .iter()
+            .map(String::as_str)
+            .chain(
+                self.unused_variables
+                    .iter()
+                    .filter(|binding| binding.function.is_none() && binding.removable)
+                    .map(|binding| binding.name.as_str()),
+            )
+            .collect()
+    }
+
+    /// 删除可安全删除的代码：顶层候选定义，以及函数内 `begin` 中初始值无副作用、不是最后一条的未使用局部变量
+    /// Delete safely deletable code: the top-level candidates and unused local variables without side effects that
+    /// are not the last statement of a `begin` inside a function
+    pub fn remove_safely(&self, ast: &[GrammarElement]) -> Vec<GrammarElement> {
+        let candidates: HashSet<&str> = self.safe_delete_candidates().into_iter().collect();
+        ast.iter()
+            .filter(|element| {
+                let name = if is_function_definition(element) {
+                    definition_name(element)
+                } else {
+                    let_binding(element).map(|(name, _)| name)
+                };
+                !name.is_some_and(|name| candidates.contains(name.as_str()))
+            })
+            .map(|element| {
+                let Some(function) = definition_name(element) else {
+                    return element.clone();
+                };
+                let locals: HashSet<&str> = self
+                    .unused_variables
+                    .iter()
+                    .filter(|b| b.removable && b.function.as_deref() == Some(function.as_str()))
+                    .map(|b| b.name.as_str())
+                    .collect();
+                if locals.is_empty() {
+                    element.clone()
+                } else {
+                    remove_local_lets(element, &locals)
+                }
+            })
+            .collect()
+    }
+}
+
+/// 顶层函数定义 / Top-level function definition
+pub(crate) struct Definition<'a> {
+    /// 在AST中的位置 / Position in the AST
+    pub(crate) index: usize,
+    pub(crate) name: String,
+    pub(crate) params: Vec<String>,
+    pub(crate) body: &'a [GrammarElement],
+    /// 文档字符串 / Docstring
+    pub(crate) docstring: Option<&'a str>,
+}
+
+/// 收集顶层函数定义：`(def name (params) body)` 和 `(let name (lambda (params) body))`
+/// Collect top-level function definitions: `(def name (params) body)` and `(let name (lambda (params) body))`
+pub(crate) fn definitions(ast: &[GrammarElement]) -> Vec<Definition<'_>> {
+    ast.iter()
+        .enumerate()
+        .filter_map(|(index, element)| {
+            let GrammarElement::List(items) = element else {
+                return None;
+            };
+            match list_head(element)? {
+                "def" | "function" | "defn-" if items.len() >= 3 => Some(Definition {
+                    index,
+                    name: element_name(&items[1])?,
+                    params: param_names(&items[2]),
+                    body: definition_body(&items[3.min(items.len())..]),
+                    docstring: definition_docstring(items),
+                }),
+                "let" => {
+                    let GrammarElement::List(lambda) = items.get(2)? else {
+                        return None;
+                    };
+                    (list_head(&items[2]) == Some("lambda") && lambda.len() >= 3).then(|| {
+                        Some(Definition {
+                            index,
+                            name: element_name(&items[1])?,
+                            params: param_names(&lambda[1]),
+                            body: &lambda[2..],
+                            docstring: None,
+                        })
+                    })?
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// 函数体，不含类型标注 `(: ...)` 和文档字符串 / Function body without the `(: ...)` type annotation and docstring
+fn definition_body(items: &[GrammarElement]) -> &[GrammarElement] {
+    match items
+        .iter()
+        .skip(1)
+        .position(|item| list_head(item) == Some(":") || string_literal(item).is_some())
+    {
+        Some(end) => &items[..end + 1],
+        None => items,
+    }
+}
+
+fn is_function_definition(element: &GrammarElement) -> bool {
+    definition_name(element).is_some()
+}
+
+/// 函数定义（包括绑定Lambda的 `let`）的名称 / Name of a function definition (including `let` bound to a lambda)
+fn definition_name(element: &GrammarElement) -> Option<String> {
+    let GrammarElement::List(items) = element else {
+        return None;
+    };
+    match list_head(element)? {
+        "def" | "function" | "defn-" => element_name(items.get(1)?),
+        "let" if items.get(2).and_then(list_head) == Some("lambda") => element_name(&items[1]),
+        _ => None,
+    }
+}
+
+/// 顶层 `let` 的名称和初始值 / Name and initial value of a top-level `let`
+fn let_binding(element: &GrammarElement) -> Option<(String, Option<&GrammarElement>)> {
+    match element {
+        GrammarElement::List(items) if list_head(element) == Some("let") => {
+            Some((element_name(items.get(1)?)?, items.get(2)))
+        }
+        _ => None,
+    }
+}
+
+fn param_names(params: &GrammarElement) -> Vec<String> {
+    match params {
+        GrammarElement::List(params) => params.iter().filter_map(element_name).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn list_head(element: &GrammarElement) -> Option<&str> {
+    match element {
+        GrammarElement::List(items) => match items.first() {
+            Some(GrammarElement::Atom(head)) => Some(head),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 收集引用的名称，跳过绑定位置（参数、`let`、`for` 和赋值的目标）
+/// Collect referenced names, skipping binding positions (parameters and the targets of `let`, `for` and assignments)
+fn collect_references(element: &GrammarElement, references: &mut Vec<String>) {
+    struct References<'a>(&'a mut Vec<String>);
+
+    impl<'ast> Visitor<'ast> for References<'_> {
+        fn enter_element(&mut self, element: &'ast GrammarElement, position: Position) -> bool {
+            match element {
+                GrammarElement::Atom(name) => self.0.push(name.clone()),
+                GrammarElement::List(items) => {
+                    let skip: &[usize] = match list_head(element) {
+                        Some("def" | "function" | "defn-") => &[0, 1, 2],
+                        Some("lambda") => &[0, 1],
+                        Some("let" | "defconst" | "set!" | "for") => &[0, 1],
+                        Some(":") => return false,
+                        _ => return true,
+                    };
+                    for (index, item) in items.iter().enumerate() {
+                        if !skip.contains(&index) {
+                            walk_element(self, item, position.child(index));
+                        }
+                    }
+                    return false;
+                }
+                _ => {}
+            }
+            true
+        }
+
+        fn enter_expr(&mut self, expr: &'ast Expr, position: Position) -> bool {
+            match expr {
+                Expr::Var(name) => self.0.push(name.to_string()),
+                Expr::Call(name, args) if name == "let" || name == "lambda" => {
+                    for (index, arg) in args.iter().enumerate().skip(1) {
+                        walk_expr(self, arg, position.child(index));
+                    }
+                    return false;
+                }
+                Expr::Call(name, _) => self.0.push(name.to_string()),
+                _ => {}
+            }
+            true
+        }
+    }
+
+    walk_element(&mut References(references), element, Position::default());
+}
+
+/// 收集被调用的名称（调用头）/ Collect called names (call heads)
+fn collect_call_heads<'a>(element: &'a GrammarElement, heads: &mut HashSet<&'a str>) {
+    struct Heads<'h, 'a>(&'h mut HashSet<&'a str>);
+
+    impl<'a> Visitor<'a> for Heads<'_, 'a> {
+        fn enter_element(&mut self, element: &'a GrammarElement, _: Position) -> bool {
+            if let Some(head) = list_head(element) {
+                self.0.insert(head);
+            }
+            true
+        }
+
+        fn enter_expr(&mut self, expr: &'a Expr, _: Position) -> bool {
+            if let Expr::Call(name, _) = expr {
+                self.0.insert(name);
+            }
+            true
+        }
+    }
+
+    walk_element(&mut Heads(heads), element, Position::default());
+}
+
+/// 调用点的参数：名称或Lambda体 / Argument of a call site: a name or a lambda body
+enum Argument {
+    Name(String),
+    Lambda(Vec<GrammarElement>),
+}
+
+/// 收集所有调用点（被调用名称和参数）/ Collect every call site (called name and arguments)
+fn collect_call_sites(element: &GrammarElement, sites: &mut Vec<(String, Vec<Argument>)>) {
+    struct Sites<'a>(&'a mut Vec<(String, Vec<Argument>)>);
+
+    impl Visitor<'_> for Sites<'_> {
+        fn enter_element(&mut self, element: &GrammarElement, _: Position) -> bool {
+            if let (GrammarElement::List(items), Some(head)) = (element, list_head(element)) {
+                let args = items[1..]
+                    .iter()
+                    .map(|item| match item {
+                        GrammarElement::List(lambda) if list_head(item) == Some("lambda") => {
+                            Argument::Lambda(lambda.get(2..).unwrap_or_default().to_vec())
+                        }
+                        GrammarElement::Expr(expr) => expr_argument(expr),
+                        _ => Argument::Name(element_name(item).unwrap_or_default()),
+                    })
+                    .collect();
+                self.0.push((head.to_string(), args));
+            }
+            true
+        }
+
+        fn enter_expr(&mut self, expr: &Expr, _: Position) -> bool {
+            if let Expr::Call(name, args) = expr {
+                self.0
+                    .push((name.to_string(), args.iter().map(expr_argument).collect()));
+            }
+            true
+        }
+    }
+
+    walk_element(&mut Sites(sites), element, Position::default());
+}
+
+fn expr_argument(expr: &Expr) -> Argument {
+    match expr {
+        Expr::Var(name) => Argument::Name(name.to_string()),
+        Expr::Lambda { body, .. } => {
+            Argument::Lambda(vec![GrammarElement::Expr(Box::new(body.as_ref().clone()))])
+        }
+        Expr::Call(name, args) if name == "lambda" => Argument::Lambda(
+            args.iter()
+                .skip(1)
+                .map(|arg| GrammarElement::Expr(Box::new(arg.clone())))
+                .collect(),
+        ),
+        _ => Argument::Name(String::new()),
+    }
+}
+
+/// 收集函数体中 `let` 绑定的局部变量及其初始值是否无副作用
+/// Collect local variables bound by `let` in a function body, and whether their initial values are side-effect free
+fn collect_local_bindings(element: &GrammarElement, locals: &mut Vec<(String, bool)>) {
+    struct Locals<'a>(&'a mut Vec<(String, bool)>);
+
+    impl Visitor<'_> for Locals<'_> {
+        fn enter_element(&mut self, element: &GrammarElement, _: Position) -> bool {
+            if let (GrammarElement::List(items), Some("let")) = (element, list_head(element)) {
+                if let Some(name) = items.get(1).and_then(element_name) {
+                    self.0
+                        .push((name, items.get(2).is_none_or(is_pure_element)));
+                }
+            }
+            true
+        }
+
+        fn enter_expr(&mut self, expr: &Expr, _: Position) -> bool {
+            if let Expr::Call(name, args) = expr {
+                if let (true, Some(Expr::Var(variable))) = (name == "let", args.first()) {
+                    self.0
+                        .push((variable.to_string(), args.get(1).is_none_or(is_pure)));
+                }
+            }
+            true
+        }
+    }
+
+    walk_element(&mut Locals(locals), element, Position::default());
+}
+
+/// 求值没有副作用：字面量、变量、Lambda和纯运算 / Evaluation has no side effects: literals, variables, lambdas and pure operators
+fn is_pure(expr: &Expr) -> bool {
+    match expr {
+        Expr::Literal(_) | Expr::Var(_) | Expr::Lambda { .. } => true,
+        Expr::Binary(_, left, right) => is_pure(left) && is_pure(right),
+        Expr::If(condition, then_branch, else_branch) => {
+            is_pure(condition) && is_pure(then_branch) && is_pure(else_branch)
+        }
+        Expr::Call(name, args) => {
+            (name.starts_with("op:") || name == "list" || name == "lambda")
+                && args.iter().all(is_pure)
+        }
+        _ => false,
+    }
+}
+
+fn is_pure_element(element: &GrammarElement) -> bool {
+    match element {
+        GrammarElement::Expr(expr) => is_pure(expr),
+        GrammarElement::Atom(_) => true,
+        GrammarElement::List(_) => list_head(element) == Some("lambda"),
+        GrammarElement::NaturalLang(_) => false,
+    }
+}
+
+/// 删除函数体 `begin` 中未使用的局部 `let`（不删除最后一条，它是 `begin` 的值）
+/// Remove unused local `let`s from `begin` blocks in a function body (never the last statement, which is the value of the `begin`)
+fn remove_local_lets(element: &GrammarElement, locals: &HashSet<&str>) -> GrammarElement {
+    match element {
+        GrammarElement::Expr(expr) => {
+            GrammarElement::Expr(Box::new(remove_expr_lets(expr, locals)))
+        }
+        GrammarElement::List(items) => GrammarElement::List(
+            items
+                .iter()
+                .map(|item| remove_local_lets(item, locals))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn remove_expr_lets(expr: &Expr, locals: &HashSet<&str>) -> Expr {
+    let recurse = |expr: &Expr| Box::new(remove_expr_lets(expr, locals));
+    match expr {
+        Expr::Begin(exprs) => {
+            let last = exprs.len().saturating_sub(1);
+            Expr::Begin(
+                exprs
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, expr)| {
+                        *index == last
+                            || !matches!(expr, Expr::Call(name, args)
+                                if name == "let"
+                                    && matches!(args.first(), Some(Expr::Var(v)) if locals.contains(v.as_str()))
+                                    && args.get(1).is_none_or(is_pure))
+                    })
+                    .map(|(_, expr)| remove_expr_lets(expr, locals))
+                    .collect(),
+            )
+        }
+        Expr::Call(name, args) => Expr::Call(
+            name.clone(),
+            args.iter()
+                .map(|arg| remove_expr_lets(arg, locals))
+                .collect(),
+        ),
+        Expr::Binary(op, left, right) => Expr::Binary(*op, recurse(left), recurse(right)),
+        Expr::If(condition, then_branch, else_branch) => Expr::If(
+            recurse(condition),
+            recurse(then_branch),
+            recurse(else_branch),
+        ),
+        Expr::Match(value, arms) => Expr::Match(
+            recurse(value),
+            arms.iter()
+                .map(|(pattern, arm)| (pattern.clone(), remove_expr_lets(arm, locals)))
+                .collect(),
+        ),
+        Expr::For {
+            var,
+            iterable,
+            body,
+        } => Expr::For {
+            var: var.clone(),
+            iterable: recurse(iterable),
+            body: recurse(body),
+        },
+        Expr::While { condition, body } => Expr::While {
+            condition: recurse(condition),
+            body: recurse(body),
+        },
+        Expr::Try {
+            try_body,
+            catch_var,
+            catch_body,
+        } => Expr::Try {
+            try_body: recurse(try_body),
+            catch_var: catch_var.clone(),
+            catch_body: recurse(catch_body),
+        },
+        Expr::Lambda { params, body } => Expr::Lambda {
+            params: params.clone(),
+            body: recurse(body),
+        },
+        Expr::Assign(name, value) => Expr::Assign(name.clone(), recurse(value)),
+        Expr::Literal(_) | Expr::Var(_) => expr.clone(),
+    }
+}
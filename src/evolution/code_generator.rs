@@ -2,9 +2,21 @@
 // 基于上下文、使用模式和学习结果生成代码
 // Generate code based on context, usage patterns, and learning results
 
+use crate::evolution::analyzer::CodeAnalyzer;
+use crate::evolution::call_graph::definitions;
+use crate::evolution::corpus::Idiom;
 use crate::evolution::learning::UsagePatternLearner;
+use crate::evolution::mutation::SeededRng;
+use crate::evolution::sandbox::{SandboxConfig, SandboxRunner};
+use crate::parser::backend::NluBackend;
+use crate::parser::context::{NamingStyle, UserProfile};
+use crate::parser::formatter::format_source;
+use crate::parser::AdaptiveParser;
+use crate::runtime::interpreter::Value;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
 
 /// 智能代码生成器 / Intelligent code generator
 pub struct IntelligentCodeGenerator {
@@ -14,6 +26,13 @@ pub struct IntelligentCodeGenerator {
     templates: HashMap<String, CodeTemplate>,
     /// 上下文信息 / Context information
     context: GenerationContext,
+    /// 模型后端，设置时优先于模板 / Model backend, preferred over templates when set
+    backend: Option<Arc<dyn NluBackend>>,
+    /// 用户画像：命名风格、常用参数和函数 / User profile: naming style, usual parameters and functions
+    profile: UserProfile,
+    /// 随机数种子：决定同分模板的先后和沙箱验证的随机数，相同种子结果相同
+    /// Random seed: orders equally scored templates and seeds sandbox verification, so the same seed gives the same result
+    seed: u64,
 }
 
 /// 代码模板 / Code template
@@ -29,6 +48,87 @@ pub struct CodeTemplate {
     pub success_rate: f64,
     /// 适用场景 / Applicable scenarios
     pub scenarios: Vec<String>,
+    /// 槽位的默认值，填充 `{slot}` / Default slot values, filling `{slot}`
+    #[serde(default)]
+    pub slots: BTreeMap<String, String>,
+}
+
+/// 模板包（TOML）：组织把自己的惯用写法教给生成器
+/// Template pack (TOML): lets an organization teach the generator its idioms
+///
+/// ```toml
+/// name = "acme"
+///
+/// [[templates]]
+/// id = "handler"
+/// name = "请求处理函数"
+/// patterns = ["处理请求", "handler"]
+/// code = "(def {name} (request) {body})"
+/// success_rate = 0.9
+///
+/// [templates.slots]
+/// name = "handle-request"
+/// body = "(respond request 200)"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TemplatePack {
+    /// 包名 / Pack name
+    pub name: String,
+    /// 模板 / Templates
+    pub templates: Vec<TemplateDefinition>,
+}
+
+/// 模板包中的模板 / Template in a template pack
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateDefinition {
+    /// 模板ID，与已有模板同名时替换之 / Template id; replaces an existing template with the same id
+    pub id: String,
+    /// 显示名称，默认为ID / Display name; defaults to the id
+    #[serde(default)]
+    pub name: Option<String>,
+    /// 意图模式：意图包含其中之一时匹配（不区分大小写）/ Intent patterns: matches when the intent contains one (case-insensitive)
+    pub patterns: Vec<String>,
+    /// 代码骨架，`{slot}` 为槽位 / Code skeleton with `{slot}` placeholders
+    pub code: String,
+    /// 槽位默认值 / Default slot values
+    #[serde(default)]
+    pub slots: BTreeMap<String, String>,
+    /// 初始成功率 / Initial success rate
+    #[serde(default = "default_success_rate")]
+    pub success_rate: f64,
+}
+
+fn default_success_rate() -> f64 {
+    0.8
+}
+
+impl TemplatePack {
+    /// 从TOML文本解析 / Parse from TOML text
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// 从文件加载 / Load from a file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_toml(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl TemplateDefinition {
+    /// 转换为代码模板 / Convert to a code template
+    pub fn into_template(self) -> CodeTemplate {
+        CodeTemplate {
+            name: self.name.unwrap_or_else(|| self.id.clone()),
+            code: self.code,
+            usage_count: 0,
+            success_rate: self.success_rate.clamp(0.0, 1.0),
+            scenarios: self.patterns.iter().map(|p| p.to_lowercase()).collect(),
+            slots: self.slots,
+        }
+    }
 }
 
 /// 生成上下文 / Generation context
@@ -57,6 +157,105 @@ pub struct GenerationResult {
     pub suggestions: Vec<String>,
 }
 
+/// 生成约束 / Generation constraints
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationConstraints {
+    /// 输入输出示例 / Input/output examples
+    pub examples: Vec<IoExample>,
+    /// 最大复杂度 / Maximum complexity
+    pub max_complexity: Option<f64>,
+    /// 必须定义的函数 / Functions that must be defined
+    pub required_functions: Vec<String>,
+    /// 最多检查的候选数 / Maximum number of candidates to check
+    pub max_candidates: usize,
+}
+
+impl Default for GenerationConstraints {
+    fn default() -> Self {
+        Self {
+            examples: Vec::new(),
+            max_complexity: None,
+            required_functions: Vec::new(),
+            max_candidates: 5,
+        }
+    }
+}
+
+/// 输入输出示例：两者都是Evo-lang表达式，如 `(add 1 2)` 和 `3`
+/// Input/output example: both are Evo-lang expressions, such as `(add 1 2)` and `3`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IoExample {
+    /// 在候选代码之后求值的表达式 / Expression evaluated after the candidate code
+    pub input: String,
+    /// 期望的值 / Expected value
+    pub expected: String,
+}
+
+impl IoExample {
+    /// 创建示例 / Create an example
+    pub fn new(input: &str, expected: &str) -> Self {
+        Self {
+            input: input.to_string(),
+            expected: expected.to_string(),
+        }
+    }
+}
+
+/// 通过所有约束的候选 / Candidate that satisfies every constraint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifiedCandidate {
+    /// 代码 / Code
+    pub code: String,
+    /// 使用的模板，后端或已学习的代码为None / Template used; None for backend or learned code
+    pub template: Option<String>,
+    /// 分数：来源的置信度和简洁程度 / Score: confidence of the source and simplicity
+    pub score: f64,
+    /// 复杂度 / Complexity
+    pub complexity: f64,
+    /// 执行示例使用的求值步数 / Evaluation steps used running the examples
+    pub steps: u64,
+}
+
+/// 未通过约束的候选 / Candidate that violates a constraint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedCandidate {
+    /// 代码 / Code
+    pub code: String,
+    /// 原因 / Reason
+    pub reason: String,
+}
+
+/// 约束生成结果 / Constrained generation result
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConstrainedGeneration {
+    /// 通过的候选，按分数降序 / Passing candidates, by score descending
+    pub candidates: Vec<VerifiedCandidate>,
+    /// 被拒绝的候选 / Rejected candidates
+    pub rejected: Vec<RejectedCandidate>,
+}
+
+impl ConstrainedGeneration {
+    /// 分数最高的候选 / Best-scoring candidate
+    pub fn best(&self) -> Option<&VerifiedCandidate> {
+        self.candidates.first()
+    }
+
+    fn reject(&mut self, candidate: Candidate, reason: String) {
+        self.rejected.push(RejectedCandidate {
+            code: candidate.code,
+            reason,
+        });
+    }
+}
+
+/// 约束检查前的候选 / Candidate before checking constraints
+struct Candidate {
+    code: String,
+    template: Option<(String, String)>,
+    confidence: f64,
+}
+
 impl IntelligentCodeGenerator {
     /// 创建新代码生成器 / Create new code generator
     pub fn new() -> Self {
@@ -69,11 +268,21 @@ impl IntelligentCodeGenerator {
                 recent_patterns: Vec::new(),
                 intent: None,
             },
+            backend: None,
+            profile: UserProfile::new(),
+            seed: 0,
         };
         generator.initialize_templates();
         generator
     }
 
+    /// 以随机数种子创建 / Create with a random seed
+    pub fn with_seed(seed: u64) -> Self {
+        let mut generator = Self::new();
+        generator.seed = seed;
+        generator
+    }
+
     /// 初始化模板库 / Initialize template library
     fn initialize_templates(&mut self) {
         // 变量定义模板 / Variable definition template
@@ -85,6 +294,7 @@ impl IntelligentCodeGenerator {
                 usage_count: 0,
                 success_rate: 0.95,
                 scenarios: vec!["定义变量".to_string(), "初始化变量".to_string()],
+                slots: BTreeMap::new(),
             },
         );
 
@@ -97,6 +307,7 @@ impl IntelligentCodeGenerator {
                 usage_count: 0,
                 success_rate: 0.90,
                 scenarios: vec!["定义函数".to_string(), "创建函数".to_string()],
+                slots: BTreeMap::new(),
             },
         );
 
@@ -109,6 +320,7 @@ impl IntelligentCodeGenerator {
                 usage_count: 0,
                 success_rate: 0.85,
                 scenarios: vec!["条件判断".to_string(), "分支逻辑".to_string()],
+                slots: BTreeMap::new(),
             },
         );
 
@@ -121,6 +333,41 @@ impl IntelligentCodeGenerator {
                 usage_count: 0,
                 success_rate: 0.88,
                 scenarios: vec!["列表处理".to_string(), "数据操作".to_string()],
+                slots: BTreeMap::new(),
+            },
+        );
+
+        // 局部绑定模板：多个变量只在 body 中可见 / Local bindings template: several variables visible only in the body
+        self.templates.insert(
+            "local_bindings".to_string(),
+            CodeTemplate {
+                name: "局部绑定".to_string(),
+                code: "(let (({name} {value}) ({other} {other_value})) {body})".to_string(),
+                usage_count: 0,
+                success_rate: 0.9,
+                scenarios: vec![
+                    "局部变量".to_string(),
+                    "多个变量".to_string(),
+                    "局部绑定".to_string(),
+                ],
+                slots: BTreeMap::new(),
+            },
+        );
+
+        // 列表推导模板：构建集合不再需要手写追加循环 / List comprehension template: building a collection needs no hand-written append loop
+        self.templates.insert(
+            "list_comprehension".to_string(),
+            CodeTemplate {
+                name: "列表推导".to_string(),
+                code: "(for/list ({var} {list}) {body})".to_string(),
+                usage_count: 0,
+                success_rate: 0.88,
+                scenarios: vec![
+                    "列表推导".to_string(),
+                    "构建列表".to_string(),
+                    "每个元素".to_string(),
+                ],
+                slots: BTreeMap::new(),
             },
         );
     }
@@ -133,20 +380,26 @@ impl IntelligentCodeGenerator {
     ) -> GenerationResult {
         self.context = context.clone();
 
-        // 分析意图 / Analyze intent
-        let intent_lower = intent.to_lowercase();
-        let mut best_template: Option<&CodeTemplate> = None;
-        let mut best_score = 0.0;
-
-        // 查找匹配的模板 / Find matching template
-        for template in self.templates.values() {
-            let score = self.score_template(template, &intent_lower);
-            if score > best_score {
-                best_score = score;
-                best_template = Some(template);
+        // 后端可用时直接生成，失败则退回模板 / Generate with the backend when available, falling back to templates
+        if let Some(backend) = &self.backend {
+            if let Ok(code) = backend.generate_code(intent) {
+                let code = Self::format_code(code);
+                self.learner.record_success(backend.name(), &code);
+                return GenerationResult {
+                    code,
+                    confidence: 0.8,
+                    template: None,
+                    suggestions: vec![format!("由 {} 生成", backend.name())],
+                };
             }
         }
 
+        // 查找匹配的模板 / Find matching template
+        let (best_template, best_score) = match self.rank_templates(intent).first() {
+            Some((id, score)) => (self.templates.get(id), *score),
+            None => (None, 0.0),
+        };
+
         // 基于学习结果优化 / Optimize based on learning results
         let insights = self.learner.get_insights();
         let mut suggestions = Vec::new();
@@ -159,9 +412,16 @@ impl IntelligentCodeGenerator {
             }
         }
 
+        // 提醒用户反复出现的错误 / Remind the user of recurring mistakes
+        for (mistake, count) in self.profile.common_mistakes(2) {
+            if count >= 2 {
+                suggestions.push(format!("注意常见错误：{}（{}次）", mistake, count));
+            }
+        }
+
         // 生成代码 / Generate code
         if let Some(template) = best_template {
-            let code = self.fill_template(template, intent);
+            let code = Self::format_code(self.fill_template(template, intent));
             let confidence = best_score * template.success_rate;
 
             // 记录使用 / Record usage
@@ -176,7 +436,7 @@ impl IntelligentCodeGenerator {
             }
         } else {
             // 如果没有匹配的模板，生成基础代码 / If no matching template, generate basic code
-            let code = self.generate_basic_code(intent);
+            let code = Self::format_code(self.generate_basic_code(intent));
             GenerationResult {
                 code,
                 confidence: 0.5,
@@ -186,20 +446,230 @@ impl IntelligentCodeGenerator {
         }
     }
 
+    /// 按意图给模板排序：意图模式匹配、学习模块统计的使用次数（相对最常用的模板）和成功率，分数降序，同分时按种子排列
+    /// Rank templates for an intent by intent-pattern matches, usage counted by the learning module (relative to
+    /// the most used template) and success rate, by score descending; equal scores are ordered by the seed
+    pub fn rank_templates(&self, intent: &str) -> Vec<(String, f64)> {
+        let intent = intent.to_lowercase();
+        let max_usage = self
+            .templates
+            .values()
+            .map(|template| self.template_usage(template))
+            .max()
+            .unwrap_or(0);
+        let mut ranked: Vec<(String, f64)> = self
+            .templates
+            .iter()
+            .map(|(id, template)| {
+                (
+                    id.clone(),
+                    self.score_template(template, &intent, max_usage),
+                )
+            })
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+        // 同分模板按种子打乱后的顺序排列 / Equally scored templates keep their seeded shuffle order
+        ranked.sort_by(|a, b| a.0.cmp(&b.0));
+        SeededRng::new(self.seed).shuffle(&mut ranked);
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        ranked
+    }
+
+    /// 模板的使用次数：模板自身统计加上学习模块记录的使用 / Template usage: its own count plus the usage recorded by the learning module
+    fn template_usage(&self, template: &CodeTemplate) -> usize {
+        template.usage_count + self.learner.usage_count(&template.name)
+    }
+
+    /// 按约束生成：从后端、排名靠前的模板和已学习的成功代码收集候选，在沙箱解释器中执行示例，只返回通过的候选
+    /// Generate under constraints: candidates come from the backend, the top-ranked templates and learned
+    /// successful code; the examples run in a sandboxed interpreter and only passing candidates are returned
+    pub fn generate_with_constraints(
+        &mut self,
+        intent: &str,
+        context: &GenerationContext,
+        constraints: &GenerationConstraints,
+    ) -> Result<ConstrainedGeneration, String> {
+        self.context = context.clone();
+        let parser = AdaptiveParser::new(true);
+        let check = if constraints.examples.is_empty() {
+            Vec::new()
+        } else {
+            let comparisons: Vec<String> = constraints
+                .examples
+                .iter()
+                .map(|example| format!("(= {} {})", example.input, example.expected))
+                .collect();
+            parser
+                .parse(&format!("(list {})", comparisons.join(" ")))
+                .map_err(|e| format!("示例无法解析 / Examples fail to parse: {}", e))?
+        };
+
+        let analyzer = CodeAnalyzer::new();
+        let mut result = ConstrainedGeneration::default();
+        let mut candidates = Vec::new();
+        let mut programs = Vec::new();
+        for candidate in self.collect_candidates(intent, constraints) {
+            let ast = match parser.parse(&candidate.code) {
+                Ok(ast) => ast,
+                Err(e) => {
+                    result.reject(candidate, format!("无法解析: {}", e));
+                    continue;
+                }
+            };
+            let defined: Vec<String> = definitions(&ast).into_iter().map(|d| d.name).collect();
+            let missing: Vec<&str> = constraints
+                .required_functions
+                .iter()
+                .filter(|name| !defined.contains(name))
+                .map(String::as_str)
+                .collect();
+            if !missing.is_empty() {
+                result.reject(candidate, format!("缺少函数: {}", missing.join(", ")));
+                continue;
+            }
+            let complexity = analyzer.analyze(&ast).complexity;
+            if let Some(max) = constraints.max_complexity.filter(|max| complexity > *max) {
+                result.reject(
+                    candidate,
+                    format!("复杂度 {:.1} 超过上限 {:.1}", complexity, max),
+                );
+                continue;
+            }
+            programs.push(ast.into_iter().chain(check.iter().cloned()).collect());
+            candidates.push((candidate, complexity));
+        }
+
+        let runs = SandboxRunner::with_config(SandboxConfig {
+            seed: Some(self.seed),
+            ..SandboxConfig::default()
+        })
+        .run(&programs);
+        for ((candidate, complexity), run) in candidates.into_iter().zip(runs) {
+            let failure = match (&run.error, &run.value) {
+                (Some(error), _) => Some(format!("执行出错: {}", error)),
+                (None, _) if check.is_empty() => None,
+                (None, Some(Value::List(passed))) => {
+                    let failed: Vec<String> = passed
+                        .iter()
+                        .zip(&constraints.examples)
+                        .filter(|(passed, _)| !matches!(passed, Value::Bool(true)))
+                        .map(|(_, example)| format!("{} ≠ {}", example.input, example.expected))
+                        .collect();
+                    (!failed.is_empty()).then(|| format!("示例不通过: {}", failed.join("; ")))
+                }
+                (None, _) => Some("示例检查没有返回列表".to_string()),
+            };
+
+            if let Some((id, _)) = &candidate.template {
+                self.update_template_stats(id, failure.is_none());
+            }
+            match failure {
+                Some(reason) => {
+                    self.learner
+                        .record_error("ConstraintViolation", &reason, &candidate.code);
+                    result.reject(candidate, reason);
+                }
+                None => {
+                    let name = candidate.template.as_ref().map(|(_, name)| name.clone());
+                    self.learner
+                        .record_success(name.as_deref().unwrap_or(intent), &candidate.code);
+                    result.candidates.push(VerifiedCandidate {
+                        score: candidate.confidence * 0.7 + 0.3 / (1.0 + complexity / 10.0),
+                        code: candidate.code,
+                        template: name,
+                        complexity,
+                        steps: run.steps,
+                    });
+                }
+            }
+        }
+
+        result.candidates.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(result)
+    }
+
+    /// 收集不重复的候选：后端、排名靠前的模板（`{name}` 取第一个必需函数名）、定义了必需函数的已学习代码
+    /// Collect distinct candidates: the backend, the top-ranked templates (`{name}` takes the first required
+    /// function name) and learned code defining the required functions
+    fn collect_candidates(
+        &self,
+        intent: &str,
+        constraints: &GenerationConstraints,
+    ) -> Vec<Candidate> {
+        let mut candidates: Vec<Candidate> = Vec::new();
+        let mut push = |code: String, template: Option<(String, String)>, confidence: f64| {
+            let code = Self::format_code(code);
+            if !candidates.iter().any(|candidate| candidate.code == code) {
+                candidates.push(Candidate {
+                    code,
+                    template,
+                    confidence,
+                });
+            }
+        };
+
+        if let Some(backend) = &self.backend {
+            if let Ok(code) = backend.generate_code(intent) {
+                push(code, None, 0.8);
+            }
+        }
+
+        let required = constraints.required_functions.first();
+        for (id, score) in self
+            .rank_templates(intent)
+            .into_iter()
+            .take(constraints.max_candidates)
+        {
+            let Some(template) = self.templates.get(&id) else {
+                continue;
+            };
+            let mut template = template.clone();
+            if let Some(name) = required {
+                template.slots.insert("name".to_string(), name.clone());
+            }
+            let code = self.fill_template(&template, intent);
+            push(
+                code,
+                Some((id, template.name.clone())),
+                score * template.success_rate,
+            );
+        }
+
+        for pattern in self
+            .learner
+            .get_success_patterns(constraints.max_candidates * 4)
+        {
+            let defines_required = constraints
+                .required_functions
+                .iter()
+                .all(|name| pattern.code.contains(&format!("(def {} ", name)));
+            if !constraints.required_functions.is_empty() && defines_required {
+                push(pattern.code, None, 0.7);
+            }
+        }
+
+        push(self.generate_basic_code(intent), None, 0.5);
+        candidates
+    }
+
     /// 评分模板 / Score template
-    fn score_template(&self, template: &CodeTemplate, intent: &str) -> f64 {
+    fn score_template(&self, template: &CodeTemplate, intent: &str, max_usage: usize) -> f64 {
         let mut score = 0.0;
 
         // 场景匹配 / Scenario matching
         for scenario in &template.scenarios {
-            if intent.contains(scenario) {
+            if intent.contains(scenario.as_str()) {
                 score += 0.4;
             }
         }
 
         // 使用频率 / Usage frequency
-        if template.usage_count > 0 {
-            score += (template.usage_count as f64 / 100.0).min(0.3);
+        if max_usage > 0 {
+            score += self.template_usage(template) as f64 / max_usage as f64 * 0.3;
         }
 
         // 成功率 / Success rate
@@ -208,23 +678,97 @@ impl IntelligentCodeGenerator {
         score.min(1.0)
     }
 
+    /// 注册模板，同ID的模板被替换 / Register a template, replacing one with the same id
+    pub fn register_template(&mut self, id: &str, template: CodeTemplate) {
+        self.templates.insert(id.to_string(), template);
+    }
+
+    /// 加载模板包：文件，或目录中的所有 `.toml` 文件；返回注册的模板数
+    /// Load template packs: a file, or every `.toml` file in a directory; returns the number of templates registered
+    pub fn load_templates(&mut self, path: &Path) -> Result<usize, String> {
+        let files = if path.is_dir() {
+            let mut files: Vec<_> = std::fs::read_dir(path)
+                .map_err(|e| format!("{}: {}", path.display(), e))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|file| file.extension().is_some_and(|ext| ext == "toml"))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+
+        // 先全部解析，任何一个包出错都不注册 / Parse everything first, so nothing is registered if any pack fails
+        let packs = files
+            .iter()
+            .map(|file| TemplatePack::load(file))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut count = 0;
+        for pack in packs {
+            for definition in pack.templates {
+                let id = definition.id.clone();
+                self.register_template(&id, definition.into_template());
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// 已注册的模板，按ID / Registered templates, by id
+    pub fn templates(&self) -> &HashMap<String, CodeTemplate> {
+        &self.templates
+    }
+
     /// 填充模板 / Fill template
     fn fill_template(&self, template: &CodeTemplate, intent: &str) -> String {
         let mut code = template.code.clone();
 
+        // 模板声明的槽位先用默认值填充 / Slots the template declares are filled with their defaults first
+        for (slot, value) in &template.slots {
+            code = code.replace(&format!("{{{}}}", slot), value);
+        }
+
         // 简单的模板填充 / Simple template filling
         // 实际实现需要更复杂的解析 / Actual implementation needs more complex parsing
-        if intent.contains("变量") || intent.contains("variable") {
+        if ["局部", "多个变量", "local"]
+            .iter()
+            .any(|word| intent.contains(word))
+        {
             code = code.replace("{name}", "x");
+            code = code.replace("{value}", "1");
+            code = code.replace("{other}", "y");
+            code = code.replace("{other_value}", "2");
+            code = code.replace("{body}", "(+ x y)");
+        } else if intent.contains("变量") || intent.contains("variable") {
+            code = code.replace("{name}", &self.styled_name(&["my", "value"], "值", "x"));
             code = code.replace("{value}", "0");
         } else if intent.contains("函数") || intent.contains("function") {
-            code = code.replace("{name}", "func");
-            code = code.replace("{params}", "x y");
-            code = code.replace("{body}", "(+ x y)");
+            // 参数取用户最常用的参数列表 / Parameters follow the user's most common parameter list
+            let params = self
+                .profile
+                .default_parameters()
+                .unwrap_or_else(|| vec!["x".to_string(), "y".to_string()]);
+            let body = match params.as_slice() {
+                [first, second, ..] => format!("(+ {} {})", first, second),
+                [only] => only.clone(),
+                [] => "0".to_string(),
+            };
+            code = code.replace("{name}", &self.styled_name(&["my", "func"], "函数", "func"));
+            code = code.replace("{params}", &params.join(" "));
+            code = code.replace("{body}", &body);
         } else if intent.contains("条件") || intent.contains("if") {
             code = code.replace("{condition}", "(> x 0)");
             code = code.replace("{then}", "x");
             code = code.replace("{else}", "0");
+        } else if ["推导", "每个", "comprehension", "each"]
+            .iter()
+            .any(|word| intent.contains(word))
+        {
+            code = code.replace("{var}", "x");
+            code = code.replace("{list}", "(list 1 2 3)");
+            code = code.replace("{body}", "(* x x)");
+            code = code.replace("{op}", "map");
+            code = code.replace("{args}", "");
         } else if intent.contains("列表") || intent.contains("list") {
             code = code.replace("{op}", "sum");
             code = code.replace("{list}", "(list 1 2 3)");
@@ -234,6 +778,23 @@ impl IntelligentCodeGenerator {
         code
     }
 
+    /// 按用户偏好的命名风格给出名称，没有明确偏好时使用默认名称
+    /// Name in the user's preferred naming style, or the default name without a clear preference
+    fn styled_name(&self, words: &[&str], chinese: &str, default: &str) -> String {
+        match self.profile.preferred_naming_style() {
+            Some(NamingStyle::Lower) | None => default.to_string(),
+            Some(style) => style.join(words, chinese),
+        }
+    }
+
+    /// 规范化生成代码的格式，无法解析时保持原样 / Normalize generated code formatting, leaving it as-is if it fails to parse
+    fn format_code(code: String) -> String {
+        match format_source(&code) {
+            Ok(formatted) => formatted.trim_end().to_string(),
+            Err(_) => code,
+        }
+    }
+
     /// 生成基础代码 / Generate basic code
     fn generate_basic_code(&self, intent: &str) -> String {
         // 基于意图生成基础代码结构 / Generate basic code structure based on intent
@@ -246,6 +807,31 @@ impl IntelligentCodeGenerator {
         }
     }
 
+    /// 设置模型后端，`None` 只使用模板 / Set the model backend; `None` uses templates only
+    pub fn set_backend(&mut self, backend: Option<Arc<dyn NluBackend>>) {
+        self.backend = backend;
+    }
+
+    /// 随机数种子 / Random seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// 设置随机数种子 / Set the random seed
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// 设置用户画像 / Set the user profile
+    pub fn set_profile(&mut self, profile: UserProfile) {
+        self.profile = profile;
+    }
+
+    /// 当前使用的用户画像 / User profile in use
+    pub fn profile(&self) -> &UserProfile {
+        &self.profile
+    }
+
     /// 提供代码补全建议 / Provide code completion suggestions
     pub fn suggest_completion(
         &self,
@@ -275,6 +861,14 @@ impl IntelligentCodeGenerator {
             }
         }
 
+        // 用户常用的函数 / Functions the user uses often
+        for (func, _) in self.profile.frequent_functions(5) {
+            let suggestion = format!("({} ...)", func);
+            if func.starts_with(partial_code) && !suggestions.contains(&suggestion) {
+                suggestions.push(suggestion);
+            }
+        }
+
         suggestions
     }
 
@@ -303,6 +897,27 @@ impl IntelligentCodeGenerator {
         optimized
     }
 
+    /// 从语料惯用法学习：示例计入使用模式以供补全，同类模板的使用次数随频率增加
+    /// Learn from corpus idioms: examples count as usage patterns for completion, and templates of the same kind
+    /// gain usage in proportion to the idiom's frequency
+    pub fn learn_idioms(&mut self, idioms: &[Idiom]) {
+        for idiom in idioms {
+            for _ in 0..idiom.frequency {
+                self.learner.record_usage(&idiom.example);
+            }
+            let template = match idiom.kind.as_str() {
+                "let" => "variable_definition",
+                "def" | "function" | "defn-" => "function_definition",
+                "if" => "conditional",
+                "list" => "list_operation",
+                _ => continue,
+            };
+            if let Some(template) = self.templates.get_mut(template) {
+                template.usage_count += idiom.frequency;
+            }
+        }
+    }
+
     /// 更新模板使用统计 / Update template usage statistics
     pub fn update_template_stats(&mut self, template_name: &str, success: bool) {
         if let Some(template) = self.templates.get_mut(template_name) {
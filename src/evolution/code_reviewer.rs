@@ -1,34 +1,615 @@
 // 代码审查器 / Code reviewer
-// 自动审查代码，提供详细的审查报告
-// Automatically review code and provide detailed review reports
+// 自动审查代码，提供详细的审查报告；规则可注册、启用/禁用、调整严重程度，并可由 review.toml 配置
+// Automatically review code and provide detailed review reports; rules can be registered, enabled/disabled and
+// re-graded, and configured through review.toml
 
-use crate::evolution::analyzer::CodeAnalysis;
+use crate::evolution::analyzer::{CodeAnalysis, PatternType};
 use crate::evolution::quality_assessor::QualityAssessment;
-use crate::grammar::core::GrammarElement;
+use crate::grammar::core::{Expr, GrammarElement};
+use crate::grammar::types::TypeChecker;
+use crate::parser::context::NamingStyle;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// 代码审查器 / Code reviewer
 pub struct CodeReviewer {
-    /// 审查规则库 / Review rules library
-    review_rules: HashMap<String, ReviewRule>,
+    /// 审查规则注册表 / Review rule registry
+    registry: ReviewRuleRegistry,
     /// 审查历史 / Review history
     review_history: Vec<ReviewRecord>,
 }
 
-/// 审查规则 / Review rule
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ReviewRule {
+/// 审查规则：针对AST和分析结果检查代码 / Review rule: checks code against the AST and analysis results
+pub trait ReviewRule: Send + Sync {
+    /// 规则标识，用于配置 / Rule id, used in configuration
+    fn id(&self) -> &str;
     /// 规则名称 / Rule name
-    pub name: String,
+    fn name(&self) -> &str;
     /// 规则描述 / Rule description
-    pub description: String,
+    fn description(&self) -> &str;
     /// 规则类型 / Rule type
-    pub rule_type: ReviewRuleType,
+    fn rule_type(&self) -> ReviewRuleType;
+    /// 默认严重程度 / Default severity
+    fn default_severity(&self) -> ReviewSeverity;
+    /// 检查代码，返回发现的问题 / Check code, returning the findings
+    fn check(&self, context: &ReviewContext) -> Vec<ReviewFinding>;
+}
+
+/// 规则检查的输入 / Input to a rule check
+pub struct ReviewContext<'a> {
+    /// 语法树 / Syntax tree
+    pub ast: &'a [GrammarElement],
+    /// 代码分析结果 / Code analysis
+    pub analysis: &'a CodeAnalysis,
+    /// 质量评估结果 / Quality assessment
+    pub quality: &'a QualityAssessment,
+}
+
+/// 规则发现的问题，严重程度由注册表决定 / A finding of a rule; the registry decides its severity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewFinding {
+    /// 问题描述 / Description
+    pub description: String,
+    /// 位置 / Location
+    pub location: String,
+    /// 建议 / Suggestion
+    pub suggestion: String,
+    /// 置信度 / Confidence
+    pub confidence: f64,
+}
+
+impl ReviewFinding {
+    /// 创建问题 / Create a finding
+    pub fn new(
+        description: impl Into<String>,
+        location: impl Into<String>,
+        suggestion: impl Into<String>,
+        confidence: f64,
+    ) -> Self {
+        Self {
+            description: description.into(),
+            location: location.into(),
+            suggestion: suggestion.into(),
+            confidence,
+        }
+    }
+}
+
+/// 注册的规则及其配置 / A registered rule and its configuration
+#[derive(Clone)]
+pub struct RegisteredRule {
+    /// 规则 / Rule
+    pub rule: Arc<dyn ReviewRule>,
+    /// 是否启用 / Whether enabled
+    pub enabled: bool,
     /// 严重程度 / Severity
     pub severity: ReviewSeverity,
-    /// 检查函数 / Check function (simplified: just a description)
-    pub check_description: String,
+}
+
+/// 审查规则注册表 / Review rule registry
+#[derive(Clone, Default)]
+pub struct ReviewRuleRegistry {
+    /// 按注册顺序排列的规则 / Rules in registration order
+    rules: Vec<RegisteredRule>,
+}
+
+impl ReviewRuleRegistry {
+    /// 创建空注册表 / Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 包含内置规则的注册表 / Registry with the built-in rules
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        for rule in builtin_rules() {
+            registry.register(Arc::new(rule));
+        }
+        registry
+    }
+
+    /// 注册规则，替换同一标识的规则 / Register a rule, replacing one with the same id
+    pub fn register(&mut self, rule: Arc<dyn ReviewRule>) {
+        let registered = RegisteredRule {
+            enabled: true,
+            severity: rule.default_severity(),
+            rule,
+        };
+        match self.position(registered.rule.id()) {
+            Some(index) => self.rules[index] = registered,
+            None => self.rules.push(registered),
+        }
+    }
+
+    /// 启用规则，规则不存在时返回false / Enable a rule; false when it does not exist
+    pub fn enable(&mut self, id: &str) -> bool {
+        self.get_mut(id).map(|rule| rule.enabled = true).is_some()
+    }
+
+    /// 禁用规则，规则不存在时返回false / Disable a rule; false when it does not exist
+    pub fn disable(&mut self, id: &str) -> bool {
+        self.get_mut(id).map(|rule| rule.enabled = false).is_some()
+    }
+
+    /// 设置严重程度，规则不存在时返回false / Set a rule's severity; false when it does not exist
+    pub fn set_severity(&mut self, id: &str, severity: ReviewSeverity) -> bool {
+        self.get_mut(id)
+            .map(|rule| rule.severity = severity)
+            .is_some()
+    }
+
+    /// 按标识查找规则 / Find a rule by id
+    pub fn get(&self, id: &str) -> Option<&RegisteredRule> {
+        self.position(id).map(|index| &self.rules[index])
+    }
+
+    /// 全部规则 / All rules
+    pub fn rules(&self) -> &[RegisteredRule] {
+        &self.rules
+    }
+
+    /// 应用配置；配置了未注册的规则时返回错误 / Apply a configuration; unknown rule ids are an error
+    pub fn apply_config(&mut self, config: &ReviewConfig) -> Result<(), String> {
+        for (id, rule_config) in &config.rules {
+            let rule = self
+                .get_mut(id)
+                .ok_or_else(|| format!("未知的审查规则 / Unknown review rule: {}", id))?;
+            if let Some(enabled) = rule_config.enabled {
+                rule.enabled = enabled;
+            }
+            if let Some(severity) = rule_config.severity {
+                rule.severity = severity;
+            }
+        }
+        Ok(())
+    }
+
+    fn position(&self, id: &str) -> Option<usize> {
+        self.rules.iter().position(|rule| rule.rule.id() == id)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Option<&mut RegisteredRule> {
+        self.position(id).map(|index| &mut self.rules[index])
+    }
+}
+
+/// 审查配置（`review.toml`）/ Review configuration (`review.toml`)
+///
+/// ```toml
+/// fail_on = "warning"
+///
+/// [rules.naming_convention]
+/// enabled = false
+///
+/// [rules.maintainability]
+/// severity = "error"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReviewConfig {
+    /// 达到该严重程度的问题使检查失败，默认为错误 / Issues at this severity fail the check; defaults to error
+    pub fail_on: Option<ReviewSeverity>,
+    /// 各规则的配置 / Per-rule configuration
+    pub rules: BTreeMap<String, RuleConfig>,
+}
+
+/// 单条规则的配置 / Configuration of a single rule
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RuleConfig {
+    /// 是否启用 / Whether enabled
+    pub enabled: Option<bool>,
+    /// 严重程度 / Severity
+    pub severity: Option<ReviewSeverity>,
+}
+
+impl ReviewConfig {
+    /// 配置文件名 / Configuration file name
+    pub const FILE_NAME: &'static str = "review.toml";
+
+    /// 从TOML文本解析 / Parse from TOML text
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// 从文件加载 / Load from a file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_toml(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// 从目录向上查找 `review.toml` / Search for `review.toml` from a directory upwards
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        dir.ancestors()
+            .map(|dir| dir.join(Self::FILE_NAME))
+            .find(|path| path.is_file())
+    }
+
+    /// 导致检查失败的最低严重程度 / Lowest severity that fails the check
+    pub fn fail_threshold(&self) -> ReviewSeverity {
+        self.fail_on.unwrap_or(ReviewSeverity::Error)
+    }
+}
+
+/// 内置规则：以函数实现检查 / Built-in rule: the check is a plain function
+struct BuiltinRule {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    rule_type: ReviewRuleType,
+    severity: ReviewSeverity,
+    check: fn(&ReviewContext) -> Vec<ReviewFinding>,
+}
+
+impl ReviewRule for BuiltinRule {
+    fn id(&self) -> &str {
+        self.id
+    }
+
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn description(&self) -> &str {
+        self.description
+    }
+
+    fn rule_type(&self) -> ReviewRuleType {
+        self.rule_type.clone()
+    }
+
+    fn default_severity(&self) -> ReviewSeverity {
+        self.severity
+    }
+
+    fn check(&self, context: &ReviewContext) -> Vec<ReviewFinding> {
+        (self.check)(context)
+    }
+}
+
+/// 内置审查规则 / Built-in review rules
+fn builtin_rules() -> Vec<BuiltinRule> {
+    vec![
+        // 代码风格规则 / Code style rules
+        BuiltinRule {
+            id: "naming_convention",
+            name: "命名规范",
+            description: "检查变量和函数命名是否符合规范",
+            rule_type: ReviewRuleType::CodeStyle,
+            severity: ReviewSeverity::Warning,
+            check: check_naming,
+        },
+        // 性能规则 / Performance rules
+        BuiltinRule {
+            id: "performance_issue",
+            name: "性能问题",
+            description: "检查是否存在性能问题",
+            rule_type: ReviewRuleType::Performance,
+            severity: ReviewSeverity::Warning,
+            check: check_performance,
+        },
+        // 安全规则 / Security rules
+        BuiltinRule {
+            id: "security_issue",
+            name: "安全问题",
+            description: "检查是否存在安全问题",
+            rule_type: ReviewRuleType::Security,
+            severity: ReviewSeverity::Error,
+            check: check_security,
+        },
+        BuiltinRule {
+            id: "untrusted_io",
+            name: "不受信任的输入输出",
+            description: "检查以计算得到的路径或地址访问文件和网络",
+            rule_type: ReviewRuleType::Security,
+            severity: ReviewSeverity::Warning,
+            check: check_untrusted_io,
+        },
+        BuiltinRule {
+            id: "unbounded_recursion",
+            name: "无界递归",
+            description: "检查每条路径都会递归、没有终止条件的函数",
+            rule_type: ReviewRuleType::Security,
+            severity: ReviewSeverity::Error,
+            check: check_unbounded_recursion,
+        },
+        BuiltinRule {
+            id: "unbounded_loop",
+            name: "无界循环",
+            description: "检查条件恒为真或永远不会改变的循环",
+            rule_type: ReviewRuleType::Security,
+            severity: ReviewSeverity::Warning,
+            check: check_unbounded_loop,
+        },
+        BuiltinRule {
+            id: "unbudgeted_eval",
+            name: "无预算执行",
+            description: "检查不受执行预算限制地执行生成的代码",
+            rule_type: ReviewRuleType::Security,
+            severity: ReviewSeverity::Critical,
+            check: check_unbudgeted_eval,
+        },
+        BuiltinRule {
+            id: "permissive_import",
+            name: "宽松导入",
+            description: "检查计算得到的、绝对的或逃出项目目录的导入路径",
+            rule_type: ReviewRuleType::Security,
+            severity: ReviewSeverity::Warning,
+            check: check_permissive_import,
+        },
+        // 最佳实践规则 / Best practice rules
+        BuiltinRule {
+            id: "best_practice",
+            name: "最佳实践",
+            description: "检查是否遵循最佳实践",
+            rule_type: ReviewRuleType::BestPractice,
+            severity: ReviewSeverity::Info,
+            check: check_best_practice,
+        },
+        BuiltinRule {
+            id: "impure_memoization",
+            name: "缓存非纯函数",
+            description: "检查用 memoize 缓存有副作用的函数",
+            rule_type: ReviewRuleType::BestPractice,
+            severity: ReviewSeverity::Warning,
+            check: check_impure_memoization,
+        },
+        // 未使用代码规则 / Unused code rules
+        BuiltinRule {
+            id: "unused_code",
+            name: "未使用的代码",
+            description: "检查不可达的函数和未使用的变量、参数",
+            rule_type: ReviewRuleType::BestPractice,
+            severity: ReviewSeverity::Warning,
+            check: check_unused_code,
+        },
+        // 可维护性规则 / Maintainability rules
+        BuiltinRule {
+            id: "maintainability",
+            name: "可维护性",
+            description: "检查复杂度、嵌套深度、函数长度",
+            rule_type: ReviewRuleType::Maintainability,
+            severity: ReviewSeverity::Warning,
+            check: check_maintainability,
+        },
+        // 类型安全规则 / Type safety rules
+        BuiltinRule {
+            id: "type_safety",
+            name: "类型安全",
+            description: "检查类型标注与实际用法是否一致",
+            rule_type: ReviewRuleType::TypeSafety,
+            severity: ReviewSeverity::Error,
+            check: check_types,
+        },
+    ]
+}
+
+/// 定义的名称应使用短横线或中文风格 / Defined names should be kebab case or Chinese
+fn check_naming(context: &ReviewContext) -> Vec<ReviewFinding> {
+    let mut names = Vec::new();
+    collect_defined_names(context.ast, &mut names);
+    names
+        .into_iter()
+        .filter(|name| {
+            matches!(
+                NamingStyle::of(name),
+                Some(NamingStyle::Snake | NamingStyle::Camel)
+            )
+        })
+        .map(|name| {
+            ReviewFinding::new(
+                format!("名称不符合短横线命名: {}", name),
+                format!("名称 {}", name),
+                format!("改为 {}", split_words(name).join("-")),
+                0.9,
+            )
+        })
+        .collect()
+}
+
+/// 收集 def/defn-/let 定义的名称和参数 / Collect names and parameters defined by def/defn-/let
+fn collect_defined_names<'a>(elements: &'a [GrammarElement], names: &mut Vec<&'a str>) {
+    for element in elements {
+        let GrammarElement::List(list) = element else {
+            continue;
+        };
+        if let [GrammarElement::Atom(head), GrammarElement::Atom(name), rest @ ..] = list.as_slice()
+        {
+            // 常量常用全大写名称，不参与检查 / Constants are often all caps and are not checked
+            if matches!(head.as_str(), "def" | "defn-" | "let") {
+                names.push(name);
+                if let (true, Some(GrammarElement::List(params))) = (head != "let", rest.first()) {
+                    names.extend(params.iter().filter_map(|param| match param {
+                        GrammarElement::Atom(param) => Some(param.as_str()),
+                        GrammarElement::Expr(expr) => match expr.as_ref() {
+                            Expr::Var(param) => Some(param.as_str()),
+                            _ => None,
+                        },
+                        _ => None,
+                    }));
+                }
+            }
+        }
+        collect_defined_names(list, names);
+    }
+}
+
+/// 按下划线和大小写拆分单词 / Split words at underscores and case changes
+fn split_words(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for part in name.split('_').filter(|part| !part.is_empty()) {
+        let mut word = String::new();
+        for c in part.chars() {
+            if c.is_ascii_uppercase() && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            word.push(c.to_ascii_lowercase());
+        }
+        words.push(word);
+    }
+    words
+}
+
+fn check_performance(context: &ReviewContext) -> Vec<ReviewFinding> {
+    let mut findings = patterns_of(context, |pattern_type| {
+        matches!(pattern_type, PatternType::ComplexExpression)
+            .then_some(("发现复杂表达式", "简化表达式，提取中间变量"))
+    });
+    if context.quality.dimension_scores.performance < 60.0 {
+        findings.push(ReviewFinding::new(
+            "代码性能需要优化",
+            "整体",
+            "优化性能，减少不必要的计算",
+            0.8,
+        ));
+    }
+    findings
+}
+
+fn check_security(context: &ReviewContext) -> Vec<ReviewFinding> {
+    if context.quality.dimension_scores.security < 60.0 {
+        vec![ReviewFinding::new(
+            "代码安全性需要改进",
+            "整体",
+            "加强输入验证和错误处理",
+            0.8,
+        )]
+    } else {
+        Vec::new()
+    }
+}
+
+fn check_untrusted_io(context: &ReviewContext) -> Vec<ReviewFinding> {
+    patterns_of(context, |pattern_type| {
+        matches!(pattern_type, PatternType::UntrustedIo).then_some((
+            "发现不受信任的输入输出",
+            "校验路径或地址，并用 InterpreterOptions 的 allowed_paths 限制可访问的目录",
+        ))
+    })
+}
+
+fn check_unbounded_recursion(context: &ReviewContext) -> Vec<ReviewFinding> {
+    patterns_of(context, |pattern_type| {
+        matches!(pattern_type, PatternType::UnboundedRecursion).then_some((
+            "发现无界递归",
+            "添加不递归的基本情况，如 (if (= n 0) 0 (f (- n 1)))",
+        ))
+    })
+}
+
+fn check_unbounded_loop(context: &ReviewContext) -> Vec<ReviewFinding> {
+    patterns_of(context, |pattern_type| {
+        matches!(pattern_type, PatternType::UnboundedLoop).then_some((
+            "发现无界循环",
+            "在循环体中用 set! 更新条件中的变量，或改用遍历列表的 for 循环",
+        ))
+    })
+}
+
+fn check_unbudgeted_eval(context: &ReviewContext) -> Vec<ReviewFinding> {
+    patterns_of(context, |pattern_type| {
+        matches!(pattern_type, PatternType::UnbudgetedEval).then_some((
+            "发现无预算执行",
+            "在设置了 ExecutionBudget::for_generated_code() 的解释器中执行生成的代码",
+        ))
+    })
+}
+
+fn check_permissive_import(context: &ReviewContext) -> Vec<ReviewFinding> {
+    patterns_of(context, |pattern_type| {
+        matches!(pattern_type, PatternType::PermissiveImport).then_some((
+            "发现宽松导入",
+            "用项目内的相对字面量路径导入模块，如 (import \"./lib/geo\" \"g\")",
+        ))
+    })
+}
+
+fn check_best_practice(context: &ReviewContext) -> Vec<ReviewFinding> {
+    patterns_of(context, |pattern_type| match pattern_type {
+        PatternType::Duplicate => Some(("发现重复代码", "提取公共函数消除重复")),
+        PatternType::Simplifiable => Some(("发现可简化的代码", "简化代码")),
+        PatternType::Mergeable => Some(("发现可合并的代码", "合并相似的代码")),
+        _ => None,
+    })
+}
+
+fn check_impure_memoization(context: &ReviewContext) -> Vec<ReviewFinding> {
+    patterns_of(context, |pattern_type| {
+        matches!(pattern_type, PatternType::ImpureMemoization).then_some((
+            "发现对非纯函数的缓存",
+            "只缓存结果只取决于参数的函数，或把副作用移到被缓存的函数之外",
+        ))
+    })
+}
+
+fn check_unused_code(context: &ReviewContext) -> Vec<ReviewFinding> {
+    patterns_of(context, |pattern_type| match pattern_type {
+        PatternType::UnreachableFunction => Some(("发现不可达的函数", "删除函数，或从入口调用它")),
+        PatternType::UnusedVariable => Some(("发现未使用的变量", "移除未使用的变量")),
+        PatternType::UnusedParameter => {
+            Some(("发现未使用的参数", "移除参数，或以 _ 开头表示有意不使用"))
+        }
+        _ => None,
+    })
+}
+
+fn check_maintainability(context: &ReviewContext) -> Vec<ReviewFinding> {
+    let mut findings = patterns_of(context, |pattern_type| match pattern_type {
+        PatternType::LongFunction => Some(("发现长函数", "考虑将长函数拆分为多个小函数")),
+        PatternType::DeepNesting => Some(("发现深度嵌套", "减少嵌套深度，使用早期返回或提取函数")),
+        _ => None,
+    });
+    if context.quality.dimension_scores.readability < 60.0 {
+        findings.push(ReviewFinding::new(
+            "代码可读性较低",
+            "整体",
+            "提高代码可读性，添加注释，简化结构",
+            0.8,
+        ));
+    }
+    findings
+}
+
+fn check_types(context: &ReviewContext) -> Vec<ReviewFinding> {
+    TypeChecker::new()
+        .check(context.ast)
+        .into_iter()
+        .map(|error| {
+            ReviewFinding::new(
+                format!("类型不匹配: {}", error.message),
+                error
+                    .function
+                    .map(|function| format!("函数 {}", function))
+                    .unwrap_or_else(|| "整体".to_string()),
+                "修正参数或返回值的类型，或调整类型标注",
+                0.95,
+            )
+        })
+        .collect()
+}
+
+/// 把分析发现的模式转为问题 / Turn analysis patterns into findings
+fn patterns_of(
+    context: &ReviewContext,
+    select: impl Fn(&PatternType) -> Option<(&'static str, &'static str)>,
+) -> Vec<ReviewFinding> {
+    context
+        .analysis
+        .patterns
+        .iter()
+        .filter_map(|pattern| {
+            let (label, suggestion) = select(&pattern.pattern_type)?;
+            Some(ReviewFinding::new(
+                format!("{}: {}", label, pattern.description),
+                pattern.location.clone(),
+                suggestion,
+                pattern.confidence,
+            ))
+        })
+        .collect()
 }
 
 /// 审查规则类型 / Review rule type
@@ -46,18 +627,24 @@ pub enum ReviewRuleType {
     Maintainability,
     /// 错误处理 / Error handling
     ErrorHandling,
+    /// 类型安全 / Type safety
+    TypeSafety,
 }
 
-/// 审查严重程度 / Review severity
-#[derive(Debug, Clone, Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq)]
+/// 审查严重程度；配置文件中可写小写名称 / Review severity; configuration files may use lowercase names
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialOrd, Ord, PartialEq, Eq, Hash)]
 pub enum ReviewSeverity {
     /// 信息 / Info
+    #[serde(alias = "info")]
     Info,
     /// 警告 / Warning
+    #[serde(alias = "warning")]
     Warning,
     /// 错误 / Error
+    #[serde(alias = "error")]
     Error,
     /// 严重 / Critical
+    #[serde(alias = "critical")]
     Critical,
 }
 
@@ -66,6 +653,9 @@ pub enum ReviewSeverity {
 pub struct ReviewIssue {
     /// 问题ID / Issue ID
     pub id: String,
+    /// 规则标识 / Rule id
+    #[serde(default)]
+    pub rule_id: String,
     /// 规则名称 / Rule name
     pub rule_name: String,
     /// 问题描述 / Issue description
@@ -143,75 +733,35 @@ pub enum ReviewGrade {
 impl CodeReviewer {
     /// 创建新代码审查器 / Create new code reviewer
     pub fn new() -> Self {
-        let mut reviewer = Self {
-            review_rules: HashMap::new(),
-            review_history: Vec::new(),
-        };
-        reviewer.initialize_rules();
-        reviewer
+        Self::with_registry(ReviewRuleRegistry::builtin())
     }
 
-    /// 初始化审查规则 / Initialize review rules
-    fn initialize_rules(&mut self) {
-        // 代码风格规则 / Code style rules
-        self.review_rules.insert(
-            "naming_convention".to_string(),
-            ReviewRule {
-                name: "命名规范".to_string(),
-                description: "检查变量和函数命名是否符合规范".to_string(),
-                rule_type: ReviewRuleType::CodeStyle,
-                severity: ReviewSeverity::Warning,
-                check_description: "检查命名是否符合规范".to_string(),
-            },
-        );
+    /// 使用指定规则注册表 / Use the given rule registry
+    pub fn with_registry(registry: ReviewRuleRegistry) -> Self {
+        Self {
+            registry,
+            review_history: Vec::new(),
+        }
+    }
 
-        // 性能规则 / Performance rules
-        self.review_rules.insert(
-            "performance_issue".to_string(),
-            ReviewRule {
-                name: "性能问题".to_string(),
-                description: "检查是否存在性能问题".to_string(),
-                rule_type: ReviewRuleType::Performance,
-                severity: ReviewSeverity::Warning,
-                check_description: "检查复杂表达式和重复计算".to_string(),
-            },
-        );
+    /// 规则注册表 / Rule registry
+    pub fn registry(&self) -> &ReviewRuleRegistry {
+        &self.registry
+    }
 
-        // 安全规则 / Security rules
-        self.review_rules.insert(
-            "security_issue".to_string(),
-            ReviewRule {
-                name: "安全问题".to_string(),
-                description: "检查是否存在安全问题".to_string(),
-                rule_type: ReviewRuleType::Security,
-                severity: ReviewSeverity::Error,
-                check_description: "检查输入验证和错误处理".to_string(),
-            },
-        );
+    /// 可修改的规则注册表，用于启用、禁用规则或调整严重程度 / Mutable registry for enabling, disabling or re-grading rules
+    pub fn registry_mut(&mut self) -> &mut ReviewRuleRegistry {
+        &mut self.registry
+    }
 
-        // 最佳实践规则 / Best practice rules
-        self.review_rules.insert(
-            "best_practice".to_string(),
-            ReviewRule {
-                name: "最佳实践".to_string(),
-                description: "检查是否遵循最佳实践".to_string(),
-                rule_type: ReviewRuleType::BestPractice,
-                severity: ReviewSeverity::Info,
-                check_description: "检查代码结构和组织".to_string(),
-            },
-        );
+    /// 注册自定义规则 / Register a custom rule
+    pub fn register_rule(&mut self, rule: Arc<dyn ReviewRule>) {
+        self.registry.register(rule);
+    }
 
-        // 可维护性规则 / Maintainability rules
-        self.review_rules.insert(
-            "maintainability".to_string(),
-            ReviewRule {
-                name: "可维护性".to_string(),
-                description: "检查代码可维护性".to_string(),
-                rule_type: ReviewRuleType::Maintainability,
-                severity: ReviewSeverity::Warning,
-                check_description: "检查复杂度、嵌套深度、函数长度".to_string(),
-            },
-        );
+    /// 应用审查配置 / Apply a review configuration
+    pub fn apply_config(&mut self, config: &ReviewConfig) -> Result<(), String> {
+        self.registry.apply_config(config)
     }
 
     /// 审查代码 / Review code
@@ -221,106 +771,23 @@ impl CodeReviewer {
         analysis: &CodeAnalysis,
         quality: &QualityAssessment,
     ) -> CodeReviewResult {
+        let context = ReviewContext {
+            ast,
+            analysis,
+            quality,
+        };
         let mut issues = Vec::new();
-
-        // 基于代码分析审查 / Review based on code analysis
-        for pattern in &analysis.patterns {
-            match pattern.pattern_type {
-                crate::evolution::analyzer::PatternType::LongFunction => {
-                    if let Some(rule) = self.review_rules.get("maintainability") {
-                        issues.push(ReviewIssue {
-                            id: uuid::Uuid::new_v4().to_string(),
-                            rule_name: rule.name.clone(),
-                            description: format!("发现长函数: {}", pattern.description),
-                            severity: ReviewSeverity::Warning,
-                            location: pattern.location.clone(),
-                            suggestion: "考虑将长函数拆分为多个小函数".to_string(),
-                            confidence: pattern.confidence,
-                        });
-                    }
-                }
-                crate::evolution::analyzer::PatternType::DeepNesting => {
-                    if let Some(rule) = self.review_rules.get("maintainability") {
-                        issues.push(ReviewIssue {
-                            id: uuid::Uuid::new_v4().to_string(),
-                            rule_name: rule.name.clone(),
-                            description: format!("发现深度嵌套: {}", pattern.description),
-                            severity: ReviewSeverity::Warning,
-                            location: pattern.location.clone(),
-                            suggestion: "减少嵌套深度，使用早期返回或提取函数".to_string(),
-                            confidence: pattern.confidence,
-                        });
-                    }
-                }
-                crate::evolution::analyzer::PatternType::ComplexExpression => {
-                    if let Some(rule) = self.review_rules.get("performance_issue") {
-                        issues.push(ReviewIssue {
-                            id: uuid::Uuid::new_v4().to_string(),
-                            rule_name: rule.name.clone(),
-                            description: format!("发现复杂表达式: {}", pattern.description),
-                            severity: ReviewSeverity::Warning,
-                            location: pattern.location.clone(),
-                            suggestion: "简化表达式，提取中间变量".to_string(),
-                            confidence: pattern.confidence,
-                        });
-                    }
-                }
-                crate::evolution::analyzer::PatternType::UnusedVariable => {
-                    if let Some(rule) = self.review_rules.get("best_practice") {
-                        issues.push(ReviewIssue {
-                            id: uuid::Uuid::new_v4().to_string(),
-                            rule_name: rule.name.clone(),
-                            description: format!("发现未使用的变量: {}", pattern.description),
-                            severity: ReviewSeverity::Info,
-                            location: pattern.location.clone(),
-                            suggestion: "移除未使用的变量".to_string(),
-                            confidence: pattern.confidence,
-                        });
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        // 基于质量评估审查 / Review based on quality assessment
-        if quality.dimension_scores.readability < 60.0 {
-            if let Some(rule) = self.review_rules.get("maintainability") {
-                issues.push(ReviewIssue {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    rule_name: rule.name.clone(),
-                    description: "代码可读性较低".to_string(),
-                    severity: ReviewSeverity::Warning,
-                    location: "整体".to_string(),
-                    suggestion: "提高代码可读性，添加注释，简化结构".to_string(),
-                    confidence: 0.8,
-                });
-            }
-        }
-
-        if quality.dimension_scores.performance < 60.0 {
-            if let Some(rule) = self.review_rules.get("performance_issue") {
-                issues.push(ReviewIssue {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    rule_name: rule.name.clone(),
-                    description: "代码性能需要优化".to_string(),
-                    severity: ReviewSeverity::Warning,
-                    location: "整体".to_string(),
-                    suggestion: "优化性能，减少不必要的计算".to_string(),
-                    confidence: 0.8,
-                });
-            }
-        }
-
-        if quality.dimension_scores.security < 60.0 {
-            if let Some(rule) = self.review_rules.get("security_issue") {
+        for registered in self.registry.rules().iter().filter(|rule| rule.enabled) {
+            for finding in registered.rule.check(&context) {
                 issues.push(ReviewIssue {
                     id: uuid::Uuid::new_v4().to_string(),
-                    rule_name: rule.name.clone(),
-                    description: "代码安全性需要改进".to_string(),
-                    severity: ReviewSeverity::Error,
-                    location: "整体".to_string(),
-                    suggestion: "加强输入验证和错误处理".to_string(),
-                    confidence: 0.8,
+                    rule_id: registered.rule.id().to_string(),
+                    rule_name: registered.rule.name().to_string(),
+                    description: finding.description,
+                    severity: registered.severity,
+                    location: finding.location,
+                    suggestion: finding.suggestion,
+                    confidence: finding.confidence,
                 });
             }
         }
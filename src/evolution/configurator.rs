@@ -0,0 +1,327 @@
+// 运行时配置器 / Runtime configurator
+// 持有运行中的解析器和解释器，以事务方式应用语法/语义进化，并在每次进化前保存检查点，回滚时恢复进化前的规则集和内置函数
+// Owns the live parser and interpreter, applies syntax/semantic evolutions transactionally, and checkpoints
+// before every evolution so rollback restores the pre-evolution rule set and builtins
+
+use crate::evolution::conflicts::ConflictDetector;
+use crate::evolution::engine::EvolutionError;
+use crate::grammar::core::GrammarElement;
+use crate::grammar::rule::GrammarRule;
+use crate::parser::{AdaptiveParser, RuleRegistry};
+use crate::runtime::interpreter::{HostFunction, Interpreter, InterpreterSnapshot, Value};
+use std::collections::HashMap;
+use std::fmt;
+use uuid::Uuid;
+
+/// 运行时变更 / Runtime change
+#[derive(Clone)]
+pub enum RuntimeChange {
+    /// 添加语法规则，同名规则被替换（语法进化）；与其他规则或核心语法有阻止性冲突时失败
+    /// Add a grammar rule, replacing one with the same name (syntax evolution); fails on a blocking conflict with
+    /// other rules or core syntax
+    AddRule(Box<GrammarRule>),
+    /// 按名称移除语法规则 / Remove a grammar rule by name
+    RemoveRule(String),
+    /// 用Evo源码定义或替换内置函数（语义进化），如 `(def double (x) (* x 2))`
+    /// Define or replace a builtin from Evo source (semantic evolution), e.g. `(def double (x) (* x 2))`
+    DefineFunction { name: String, source: String },
+    /// 注册宿主内置函数 / Register a host builtin
+    HostFunction(String, HostFunction),
+    /// 移除内置函数（`def` 定义的或宿主的）/ Remove a builtin (defined with `def` or a host function)
+    RemoveFunction(String),
+}
+
+impl RuntimeChange {
+    /// 是否改变语法规则（否则改变内置函数）/ Whether it changes grammar rules (otherwise it changes builtins)
+    pub fn is_syntax(&self) -> bool {
+        matches!(
+            self,
+            RuntimeChange::AddRule(_) | RuntimeChange::RemoveRule(_)
+        )
+    }
+
+    /// 简短描述 / Short description
+    pub fn describe(&self) -> String {
+        match self {
+            RuntimeChange::AddRule(rule) => format!("add rule {}", rule.name),
+            RuntimeChange::RemoveRule(name) => format!("remove rule {}", name),
+            RuntimeChange::DefineFunction { name, .. } => format!("define function {}", name),
+            RuntimeChange::HostFunction(name, _) => format!("register host function {}", name),
+            RuntimeChange::RemoveFunction(name) => format!("remove function {}", name),
+        }
+    }
+}
+
+impl fmt::Debug for RuntimeChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.describe())
+    }
+}
+
+/// 进化前的运行时状态 / Runtime state before an evolution
+struct Checkpoint {
+    id: Uuid,
+    rules: Vec<GrammarRule>,
+    interpreter: InterpreterSnapshot,
+    host_functions: HashMap<String, HostFunction>,
+}
+
+/// 运行时配置器 / Runtime configurator
+pub struct RuntimeConfigurator {
+    /// 当前生效的语法规则 / Grammar rules currently in effect
+    rules: Vec<GrammarRule>,
+    /// 发布当前规则集的共享注册表 / Shared registry the current rule set is published to
+    registry: RuleRegistry,
+    /// 挂接注册表的解析器 / Parser attached to the registry
+    parser: AdaptiveParser,
+    /// 运行中的解释器 / Live interpreter
+    interpreter: Interpreter,
+    /// 通过配置器注册的宿主函数（快照无法序列化闭包，单独保存）
+    /// Host functions registered through the configurator (kept separately since snapshots cannot hold closures)
+    host_functions: HashMap<String, HostFunction>,
+    /// 按应用顺序排列的检查点 / Checkpoints in application order
+    checkpoints: Vec<Checkpoint>,
+    /// 是否允许添加与已有规则或核心语法冲突的规则 / Whether rules conflicting with existing rules or core syntax may be added
+    allow_conflicts: bool,
+}
+
+impl RuntimeConfigurator {
+    /// 创建没有进化规则的配置器 / Create a configurator without evolved rules
+    pub fn new() -> Self {
+        Self::with_rules(Vec::new())
+    }
+
+    /// 以初始规则集创建配置器（初始规则不可回滚）/ Create a configurator with an initial rule set (not subject to rollback)
+    pub fn with_rules(rules: Vec<GrammarRule>) -> Self {
+        let registry = RuleRegistry::with_rules(rules.clone());
+        Self {
+            rules,
+            parser: AdaptiveParser::with_registry(true, registry.clone()),
+            registry,
+            interpreter: Interpreter::new(),
+            host_functions: HashMap::new(),
+            checkpoints: Vec::new(),
+            allow_conflicts: false,
+        }
+    }
+
+    /// 当前生效的语法规则 / Grammar rules currently in effect
+    pub fn rules(&self) -> &[GrammarRule] {
+        &self.rules
+    }
+
+    /// 运行中的解析器 / Live parser
+    pub fn parser(&self) -> &AdaptiveParser {
+        &self.parser
+    }
+
+    /// 共享规则注册表；挂接它的解析器随规则变更热加载 / Shared rule registry; parsers attached to it hot-reload on rule changes
+    pub fn registry(&self) -> &RuleRegistry {
+        &self.registry
+    }
+
+    /// 设置是否允许添加有阻止性冲突的规则（默认不允许）/ Set whether rules with blocking conflicts may be added (off by default)
+    pub fn set_allow_conflicts(&mut self, allow: bool) {
+        self.allow_conflicts = allow;
+    }
+
+    /// 运行中的解释器 / Live interpreter
+    pub fn interpreter(&self) -> &Interpreter {
+        &self.interpreter
+    }
+
+    /// 运行中的解释器（可变）；直接注册的宿主函数不在检查点中
+    /// Live interpreter (mutable); host functions registered directly are not checkpointed
+    pub fn interpreter_mut(&mut self) -> &mut Interpreter {
+        &mut self.interpreter
+    }
+
+    /// 用当前规则解析并执行代码，解释器记录所用的语法版本
+    /// Parse and execute code with the current rules; the interpreter records the grammar version used
+    pub fn execute(&mut self, source: &str) -> Result<Value, EvolutionError> {
+        let ast = self.parse(source)?;
+        self.interpreter
+            .set_grammar_version(Some(self.registry.version()));
+        self.execute_ast(&ast)
+    }
+
+    /// 执行AST / Execute an AST
+    pub fn execute_ast(&mut self, ast: &[GrammarElement]) -> Result<Value, EvolutionError> {
+        self.interpreter.execute(ast).map_err(|e| {
+            EvolutionError::IntegrationFailed(format!("Failed to execute evo code: {}", e))
+        })
+    }
+
+    /// 以事务方式应用一组变更：全部成功才生效，任一失败时恢复原状态
+    /// Apply a set of changes as one transaction: all take effect or, on any failure, the prior state is restored
+    ///
+    /// 返回检查点ID，`rollback_to` 可据此回到应用之前的状态
+    /// Returns the checkpoint ID that `rollback_to` accepts to return to the state before the changes
+    pub fn apply(&mut self, changes: Vec<RuntimeChange>) -> Result<Uuid, EvolutionError> {
+        self.apply_with_id(Uuid::new_v4(), changes)
+    }
+
+    /// 以指定的检查点ID应用变更（通常为进化事件ID）/ Apply changes under the given checkpoint ID (usually an evolution event ID)
+    pub fn apply_with_id(
+        &mut self,
+        id: Uuid,
+        changes: Vec<RuntimeChange>,
+    ) -> Result<Uuid, EvolutionError> {
+        let checkpoint = self.checkpoint(id);
+        for change in changes {
+            if let Err(error) = self.apply_change(change) {
+                self.restore(&checkpoint);
+                return Err(error);
+            }
+        }
+        self.checkpoints.push(checkpoint);
+        Ok(id)
+    }
+
+    /// 是否有该ID的检查点 / Whether a checkpoint with the ID exists
+    pub fn has_checkpoint(&self, id: Uuid) -> bool {
+        self.checkpoints
+            .iter()
+            .any(|checkpoint| checkpoint.id == id)
+    }
+
+    /// 检查点ID（按应用顺序）/ Checkpoint IDs in application order
+    pub fn checkpoint_ids(&self) -> Vec<Uuid> {
+        self.checkpoints
+            .iter()
+            .map(|checkpoint| checkpoint.id)
+            .collect()
+    }
+
+    /// 回滚到指定检查点之前的状态：重新发布规则集、恢复解释器状态和内置函数，并丢弃之后的检查点
+    /// Roll back to the state before the given checkpoint: republish the rule set, restore the interpreter
+    /// state and builtins, and discard later checkpoints
+    pub fn rollback_to(&mut self, id: Uuid) -> Result<(), EvolutionError> {
+        let index = self
+            .checkpoints
+            .iter()
+            .position(|checkpoint| checkpoint.id == id)
+            .ok_or_else(|| {
+                EvolutionError::IntegrationFailed(format!("Checkpoint {} not found", id))
+            })?;
+        let checkpoint = self.checkpoints.swap_remove(index);
+        self.checkpoints.truncate(index);
+        self.restore(&checkpoint);
+        Ok(())
+    }
+
+    /// 替换整个规则集并发布到注册表（不保存检查点，用于恢复没有检查点的历史状态）
+    /// Replace the whole rule set and publish it to the registry (no checkpoint; used to restore history without one)
+    pub fn reset_rules(&mut self, rules: Vec<GrammarRule>) {
+        self.rules = rules;
+        self.publish_rules();
+    }
+
+    fn apply_change(&mut self, change: RuntimeChange) -> Result<(), EvolutionError> {
+        match change {
+            RuntimeChange::AddRule(rule) => {
+                let rule = *rule;
+                let report = ConflictDetector::new().check(&self.rules, &rule);
+                if report.is_blocking() && !self.allow_conflicts {
+                    return Err(EvolutionError::RuleConflict(Box::new(report)));
+                }
+                match self
+                    .rules
+                    .iter()
+                    .position(|existing| existing.name == rule.name)
+                {
+                    Some(index) => self.rules[index] = rule,
+                    None => self.rules.push(rule),
+                }
+                self.publish_rules();
+            }
+            RuntimeChange::RemoveRule(name) => {
+                let before = self.rules.len();
+                self.rules.retain(|rule| rule.name != name);
+                if self.rules.len() == before {
+                    return Err(EvolutionError::IntegrationFailed(format!(
+                        "Rule `{}` is not defined",
+                        name
+                    )));
+                }
+                self.publish_rules();
+            }
+            RuntimeChange::DefineFunction { name, source } => {
+                // 宿主函数优先于 `def` 函数，重新定义时先移除
+                // Host functions take precedence over `def` functions, so drop one being redefined
+                if self.host_functions.remove(&name).is_some() {
+                    self.interpreter.remove_host_function(&name);
+                }
+                self.execute(&source)?;
+                if !self.interpreter.has_function(&name) {
+                    return Err(EvolutionError::IntegrationFailed(format!(
+                        "Source does not define function `{}`",
+                        name
+                    )));
+                }
+            }
+            RuntimeChange::HostFunction(name, function) => {
+                self.register_host(&name, &function);
+                self.host_functions.insert(name, function);
+            }
+            RuntimeChange::RemoveFunction(name) => {
+                self.host_functions.remove(&name);
+                let host = self.interpreter.remove_host_function(&name);
+                let defined = self.interpreter.remove_function(&name);
+                if !host && !defined {
+                    return Err(EvolutionError::IntegrationFailed(format!(
+                        "Function `{}` is not defined",
+                        name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn checkpoint(&self, id: Uuid) -> Checkpoint {
+        Checkpoint {
+            id,
+            rules: self.rules.clone(),
+            interpreter: self.interpreter.snapshot(),
+            host_functions: self.host_functions.clone(),
+        }
+    }
+
+    fn restore(&mut self, checkpoint: &Checkpoint) {
+        self.rules = checkpoint.rules.clone();
+        self.publish_rules();
+        self.interpreter.restore(checkpoint.interpreter.clone());
+        for name in self.host_functions.keys() {
+            self.interpreter.remove_host_function(name);
+        }
+        for (name, function) in &checkpoint.host_functions {
+            self.register_host(name, function);
+        }
+        self.host_functions = checkpoint.host_functions.clone();
+    }
+
+    fn register_host(&mut self, name: &str, function: &HostFunction) {
+        let function = function.clone();
+        self.interpreter
+            .register_host_function(name, move |args| function(args));
+    }
+
+    /// 发布当前规则集，挂接注册表的解析器在下一次解析时使用
+    /// Publish the current rule set; parsers attached to the registry use it from their next parse
+    fn publish_rules(&mut self) {
+        self.registry.replace_rules(self.rules.clone());
+    }
+
+    fn parse(&self, source: &str) -> Result<Vec<GrammarElement>, EvolutionError> {
+        self.parser.parse(source).map_err(|e| {
+            EvolutionError::IntegrationFailed(format!("Failed to parse evo code: {}", e))
+        })
+    }
+}
+
+impl Default for RuntimeConfigurator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,311 @@
+// 语法规则冲突检测 / Grammar rule conflict detection
+// 安装进化出的语法规则前，把它与已有规则比较：找出共用的关键字，用由规则生成的示例输入分别在两条规则下解析，
+// 解析结果不同即为歧义；关键字原本有内置含义且被改变时为覆盖核心语法。报告附带解析不同的示例输入
+// Before an evolved grammar rule is installed it is compared with the existing rules: shared keywords are found,
+// example inputs generated from the rules are parsed under each of the two rules, and differing parses mark an
+// ambiguity; a keyword whose built-in meaning changes shadows core syntax. The report carries the example inputs
+// that parse differently
+
+use crate::grammar::core::{Expr, GrammarElement};
+use crate::grammar::rule::{GrammarRule, PatternElement, Stability};
+use crate::grammar::unparse::unparse;
+use crate::parser::macros::Macro;
+use crate::parser::AdaptiveParser;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// 冲突种类 / Conflict kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConflictKind {
+    /// 与已有规则共用关键字，同一输入在两条规则下解析结果不同
+    /// Shares a keyword with an existing rule, and the same input parses differently under the two rules
+    Ambiguous,
+    /// 关键字原本有内置含义（核心形式、运算词等），规则改变了它的解析结果
+    /// The keyword already has a built-in meaning (core form, operator word, ...) that the rule changes
+    ShadowsCoreSyntax,
+    /// 与已有规则共用关键字，但示例输入的解析结果相同（冗余，不阻止安装）
+    /// Shares a keyword with an existing rule, but the example inputs parse the same (redundant; does not block installation)
+    Overlap,
+}
+
+/// 在两种规则下解析结果不同的示例输入 / Example input that parses differently under two rules
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseDivergence {
+    /// 示例输入 / Example input
+    pub input: String,
+    /// 已有规则（或没有规则时）的解析结果，以源码或 `error: ...` 表示
+    /// Parse under the existing rule (or without one), as source or `error: ...`
+    pub existing: String,
+    /// 候选规则下的解析结果 / Parse under the candidate rule
+    pub candidate: String,
+}
+
+/// 一处冲突 / One conflict
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleConflict {
+    /// 冲突种类 / Conflict kind
+    pub kind: ConflictKind,
+    /// 冲突的关键字 / Keyword in conflict
+    pub keyword: String,
+    /// 冲突的已有规则名（覆盖核心语法时为 None）/ Name of the conflicting existing rule (None when shadowing core syntax)
+    pub existing_rule: Option<String>,
+    /// 解析不同的示例输入 / Example inputs that parse differently
+    pub examples: Vec<ParseDivergence>,
+}
+
+impl RuleConflict {
+    /// 是否阻止安装规则 / Whether it blocks installing the rule
+    pub fn is_blocking(&self) -> bool {
+        self.kind != ConflictKind::Overlap
+    }
+
+    /// 简短描述 / Short description
+    pub fn describe(&self) -> String {
+        let subject = match (&self.kind, &self.existing_rule) {
+            (ConflictKind::ShadowsCoreSyntax, _) | (_, None) => {
+                format!("`{}` shadows core syntax", self.keyword)
+            }
+            (ConflictKind::Ambiguous, Some(rule)) => {
+                format!("`{}` is ambiguous with rule {}", self.keyword, rule)
+            }
+            (ConflictKind::Overlap, Some(rule)) => {
+                format!("`{}` overlaps rule {}", self.keyword, rule)
+            }
+        };
+        match self.examples.first() {
+            Some(example) => format!(
+                "{}: {} parses as {} instead of {}",
+                subject, example.input, example.candidate, example.existing
+            ),
+            None => subject,
+        }
+    }
+}
+
+/// 候选规则的冲突报告 / Conflict report of a candidate rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConflictReport {
+    /// 候选规则名 / Candidate rule name
+    pub rule: String,
+    /// 发现的冲突 / Conflicts found
+    pub conflicts: Vec<RuleConflict>,
+}
+
+impl ConflictReport {
+    /// 是否没有任何冲突 / Whether there are no conflicts at all
+    pub fn is_empty(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+
+    /// 是否有阻止安装的冲突 / Whether any conflict blocks installation
+    pub fn is_blocking(&self) -> bool {
+        self.conflicts.iter().any(RuleConflict::is_blocking)
+    }
+
+    /// 阻止安装的冲突 / Conflicts that block installation
+    pub fn blocking(&self) -> impl Iterator<Item = &RuleConflict> {
+        self.conflicts
+            .iter()
+            .filter(|conflict| conflict.is_blocking())
+    }
+
+    /// 简短描述 / Short description
+    pub fn describe(&self) -> String {
+        if self.is_empty() {
+            return format!("rule {} has no conflicts", self.rule);
+        }
+        let conflicts: Vec<String> = self.conflicts.iter().map(RuleConflict::describe).collect();
+        format!("rule {} conflicts: {}", self.rule, conflicts.join("; "))
+    }
+}
+
+/// 规则冲突检测器 / Rule conflict detector
+#[derive(Debug, Clone, Default)]
+pub struct ConflictDetector;
+
+impl ConflictDetector {
+    /// 创建检测器 / Create a detector
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 检查候选规则与已有规则的冲突；同名规则视为被候选规则替换，已弃用的规则不生效，不参与比较
+    /// Check a candidate rule against the existing rules; a rule with the same name counts as replaced by the
+    /// candidate, and deprecated rules never apply, so they are not compared
+    pub fn check(&self, rules: &[GrammarRule], candidate: &GrammarRule) -> ConflictReport {
+        let mut report = ConflictReport {
+            rule: candidate.name.clone(),
+            conflicts: Vec::new(),
+        };
+        if candidate.meta.stability == Stability::Deprecated {
+            return report;
+        }
+        let existing: Vec<&GrammarRule> = rules
+            .iter()
+            .filter(|rule| rule.name != candidate.name)
+            .filter(|rule| rule.meta.stability != Stability::Deprecated)
+            .collect();
+        let candidate_keywords = keywords(candidate);
+
+        let mut shared = BTreeSet::new();
+        for rule in &existing {
+            for keyword in keywords(rule).intersection(&candidate_keywords) {
+                shared.insert(keyword.clone());
+                let others: Vec<&GrammarRule> = existing
+                    .iter()
+                    .copied()
+                    .filter(|other| other.name != rule.name)
+                    .collect();
+                let inputs = examples(keyword, &[rule, candidate]);
+                let examples = divergences(
+                    &inputs,
+                    &parser_with(&others, Some(rule)),
+                    &parser_with(&others, Some(candidate)),
+                );
+                report.conflicts.push(RuleConflict {
+                    kind: if examples.is_empty() {
+                        ConflictKind::Overlap
+                    } else {
+                        ConflictKind::Ambiguous
+                    },
+                    keyword: keyword.clone(),
+                    existing_rule: Some(rule.name.clone()),
+                    examples,
+                });
+            }
+        }
+
+        // 其余关键字：没有规则时解析为普通调用的是新语法，否则比较加入规则前后的解析结果
+        // Remaining keywords: one that parses as a plain call without rules is new syntax; otherwise compare the
+        // parses before and after adding the rule
+        let baseline = parser_with(&existing, None);
+        let with_candidate = parser_with(&existing, Some(candidate));
+        for keyword in candidate_keywords.difference(&shared) {
+            let plain_call = matches!(
+                baseline.parse(&prefix_example(keyword, 2)).as_deref(),
+                Ok([GrammarElement::Expr(expr)])
+                    if matches!(expr.as_ref(), Expr::Call(name, _) if name == keyword)
+            );
+            if plain_call {
+                continue;
+            }
+            let inputs = examples(keyword, &[candidate]);
+            let examples = divergences(&inputs, &baseline, &with_candidate);
+            if !examples.is_empty() {
+                report.conflicts.push(RuleConflict {
+                    kind: ConflictKind::ShadowsCoreSyntax,
+                    keyword: keyword.clone(),
+                    existing_rule: None,
+                    examples,
+                });
+            }
+        }
+        report
+    }
+
+    /// 审查整个规则集：每条规则与它之前的规则比较，返回有冲突的报告
+    /// Audit a whole rule set: each rule is checked against the rules before it, returning the reports with conflicts
+    pub fn audit(&self, rules: &[GrammarRule]) -> Vec<ConflictReport> {
+        rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| self.check(&rules[..index], rule))
+            .filter(|report| !report.is_empty())
+            .collect()
+    }
+}
+
+/// 规则模式中的关键字（含可选、重复和分组内的）/ Keywords in a rule's pattern (including optional, repeated and grouped ones)
+fn keywords(rule: &GrammarRule) -> BTreeSet<String> {
+    fn collect(element: &PatternElement, out: &mut BTreeSet<String>) {
+        match element {
+            PatternElement::Keyword(word) | PatternElement::NaturalLang(word) => {
+                out.insert(word.clone());
+            }
+            PatternElement::Optional(inner) | PatternElement::Repeat(inner) => collect(inner, out),
+            PatternElement::Group(elements) => {
+                for element in elements {
+                    collect(element, out);
+                }
+            }
+            PatternElement::Identifier(_)
+            | PatternElement::Literal(_)
+            | PatternElement::Wildcard(_) => {}
+        }
+    }
+    let mut out = BTreeSet::new();
+    for element in &rule.pattern.elements {
+        collect(element, &mut out);
+    }
+    out
+}
+
+/// 以关键字开头（中缀宏还有中缀写法）的示例输入，参数个数取自规则生成的宏
+/// Example inputs headed by the keyword (plus the infix spelling for infix macros), with argument counts taken
+/// from the macros the rules generate
+fn examples(keyword: &str, rules: &[&GrammarRule]) -> Vec<String> {
+    let mut inputs = Vec::new();
+    for definition in rules
+        .iter()
+        .flat_map(|rule| Macro::from_rule(rule))
+        .filter(|definition| definition.name == keyword)
+    {
+        let count = definition.params.len() + if definition.rest.is_some() { 2 } else { 0 };
+        inputs.push(prefix_example(keyword, count));
+        if definition.infix {
+            inputs.push(format!("(x {} 1)", keyword));
+        }
+    }
+    if inputs.is_empty() {
+        inputs.push(prefix_example(keyword, 2));
+    }
+    inputs.dedup();
+    inputs
+}
+
+/// `(keyword x 1 2 ...)`：第一个参数是符号，其余是数字 / `(keyword x 1 2 ...)`: a symbol first, then numbers
+fn prefix_example(keyword: &str, count: usize) -> String {
+    let mut parts = vec![keyword.to_string()];
+    parts.extend((0..count).map(|i| match i {
+        0 => "x".to_string(),
+        _ => i.to_string(),
+    }));
+    format!("({})", parts.join(" "))
+}
+
+/// 在两个解析器下解析结果不同的输入 / Inputs whose parses differ between two parsers
+fn divergences(
+    inputs: &[String],
+    existing: &AdaptiveParser,
+    candidate: &AdaptiveParser,
+) -> Vec<ParseDivergence> {
+    inputs
+        .iter()
+        .filter_map(|input| {
+            let existing = render(existing, input);
+            let candidate = render(candidate, input);
+            (existing != candidate).then(|| ParseDivergence {
+                input: input.clone(),
+                existing,
+                candidate,
+            })
+        })
+        .collect()
+}
+
+/// 解析结果的源码形式，出错时为 `error: ...` / Parse result as source, or `error: ...` on failure
+fn render(parser: &AdaptiveParser, input: &str) -> String {
+    match parser.parse(input) {
+        Ok(elements) => unparse(&elements),
+        Err(error) => format!("error: {}", error.message()),
+    }
+}
+
+/// 应用了给定规则（及额外一条规则）的解析器 / Parser with the given rules (and one extra rule) applied
+fn parser_with(rules: &[&GrammarRule], extra: Option<&GrammarRule>) -> AdaptiveParser {
+    let mut parser = AdaptiveParser::new(true);
+    for rule in rules.iter().copied().chain(extra) {
+        parser.add_rule(rule.clone());
+    }
+    parser
+}
@@ -0,0 +1,363 @@
+// 语料模式挖掘 / Corpus pattern mining
+// 遍历目录中的 .evo 文件，解析后交给代码分析器和相似度检测器，并把每个复合表达式抽象为惯用法形状统计出现频率
+// Walks the .evo files in a directory, runs the parsed ASTs through the code analyzer and similarity detector,
+// and abstracts every compound expression into an idiom shape whose frequency is counted
+
+use crate::evolution::analyzer::CodeAnalyzer;
+use crate::evolution::similarity::SimilarityDetector;
+use crate::grammar::core::{Expr, GrammarElement, Literal};
+use crate::grammar::unparse::{unparse_element, unparse_expr};
+use crate::grammar::visit::preorder;
+use crate::parser::AdaptiveParser;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// 示例代码的最大长度（字符）/ Maximum example length in characters
+const EXAMPLE_LIMIT: usize = 80;
+
+/// 惯用法：抽象掉名称和常量后反复出现的代码形状
+/// Idiom: a code shape that recurs once names and constants are abstracted away
+///
+/// 形状中 `_` 代表变量或名称，`#` 代表数字，`"_"` 代表字符串，`…` 代表超出深度的子表达式
+/// In a shape `_` stands for a variable or name, `#` for a number, `"_"` for a string, and `…` for a subexpression
+/// beyond the depth limit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Idiom {
+    /// 形状，如 `(if (> _ #) _ _)` / Shape, e.g. `(if (> _ #) _ _)`
+    pub shape: String,
+    /// 种类（形状的首个符号），如 `if`、`def`、`+` / Kind (the shape's head symbol), e.g. `if`, `def`, `+`
+    pub kind: String,
+    /// 总出现次数 / Total occurrences
+    pub frequency: usize,
+    /// 每个文件中的出现次数 / Occurrences per file
+    pub occurrences: BTreeMap<String, usize>,
+    /// 第一次出现的源码 / Source of the first occurrence
+    pub example: String,
+}
+
+impl Idiom {
+    /// 出现该惯用法的文件数 / Number of files the idiom appears in
+    pub fn file_count(&self) -> usize {
+        self.occurrences.len()
+    }
+}
+
+/// 无法解析或读取的文件 / File that could not be read or parsed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorpusFailure {
+    /// 文件路径 / File path
+    pub path: PathBuf,
+    /// 错误信息 / Error message
+    pub error: String,
+}
+
+/// 语料挖掘报告 / Corpus mining report
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CorpusReport {
+    /// 找到的 .evo 文件数 / Number of .evo files found
+    pub files: usize,
+    /// 成功解析的文件数 / Number of files parsed successfully
+    pub parsed: usize,
+    /// 失败的文件 / Files that failed
+    pub failures: Vec<CorpusFailure>,
+    /// 顶层形式总数 / Total number of top-level forms
+    pub forms: usize,
+    /// 达到最低频率的惯用法，按频率降序 / Idioms meeting the minimum frequency, by descending frequency
+    pub idioms: Vec<Idiom>,
+    /// 分析器发现的模式类型及次数 / Analyzer pattern types and their counts
+    pub analyzer_patterns: BTreeMap<String, usize>,
+    /// 文件内重复的顶层形式数 / Top-level forms duplicated within a file
+    pub duplicates: usize,
+    /// 文件内相似的顶层形式对数 / Pairs of similar top-level forms within a file
+    pub similar_pairs: usize,
+    /// 平均复杂度 / Average complexity
+    pub average_complexity: f64,
+}
+
+/// 语料挖掘器 / Corpus miner
+#[derive(Debug, Clone)]
+pub struct CorpusMiner {
+    /// 惯用法进入报告所需的最低频率 / Minimum frequency for an idiom to be reported
+    min_frequency: usize,
+    /// 形状保留的嵌套深度 / Nesting depth kept in a shape
+    depth: usize,
+}
+
+impl CorpusMiner {
+    /// 创建挖掘器（最低频率2，深度2）/ Create a miner (minimum frequency 2, depth 2)
+    pub fn new() -> Self {
+        Self {
+            min_frequency: 2,
+            depth: 2,
+        }
+    }
+
+    /// 设置最低频率 / Set the minimum frequency
+    pub fn with_min_frequency(mut self, min_frequency: usize) -> Self {
+        self.min_frequency = min_frequency.max(1);
+        self
+    }
+
+    /// 设置形状深度 / Set the shape depth
+    pub fn with_depth(mut self, depth: usize) -> Self {
+        self.depth = depth.max(1);
+        self
+    }
+
+    /// 递归挖掘目录中的所有 .evo 文件（路径也可以是单个文件）
+    /// Mine every .evo file under a directory recursively (the path may also be a single file)
+    pub fn mine(&self, parser: &AdaptiveParser, path: &Path) -> std::io::Result<CorpusReport> {
+        let mut files = Vec::new();
+        collect_evo_files(path, &mut files)?;
+
+        let analyzer = CodeAnalyzer::new();
+        let mut detector = SimilarityDetector::new();
+        let mut report = CorpusReport {
+            files: files.len(),
+            ..CorpusReport::default()
+        };
+        let mut idioms: HashMap<String, Idiom> = HashMap::new();
+        let mut complexity = 0.0;
+
+        for file in files {
+            let source = match std::fs::read_to_string(&file) {
+                Ok(source) => source,
+                Err(error) => {
+                    report.failures.push(CorpusFailure {
+                        path: file,
+                        error: error.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let ast = match parser.parse(&source) {
+                Ok(ast) => ast,
+                Err(error) => {
+                    report.failures.push(CorpusFailure {
+                        path: file,
+                        error: error.to_string(),
+                    });
+                    continue;
+                }
+            };
+            report.parsed += 1;
+            report.forms += ast.len();
+
+            let analysis = analyzer.analyze(&ast);
+            complexity += analysis.complexity;
+            for pattern in &analysis.patterns {
+                *report
+                    .analyzer_patterns
+                    .entry(format!("{:?}", pattern.pattern_type))
+                    .or_insert(0) += 1;
+            }
+            let similarity = detector.detect_similarity(&ast, &analysis);
+            report.duplicates += similarity
+                .duplicates
+                .iter()
+                .map(|duplicate| duplicate.count - 1)
+                .sum::<usize>();
+            report.similar_pairs += similarity.similar_pairs.len();
+
+            let name = file.display().to_string();
+            for element in &ast {
+                self.collect_element(element, &name, &mut idioms);
+            }
+        }
+
+        if report.parsed > 0 {
+            report.average_complexity = complexity / report.parsed as f64;
+        }
+        report.idioms = idioms
+            .into_values()
+            .filter(|idiom| idiom.frequency >= self.min_frequency)
+            .collect();
+        report.idioms.sort_by(|a, b| {
+            b.frequency
+                .cmp(&a.frequency)
+                .then_with(|| a.shape.cmp(&b.shape))
+        });
+        Ok(report)
+    }
+
+    /// 记录元素及其所有复合子表达式 / Record an element and all of its compound subexpressions
+    fn collect_element(
+        &self,
+        element: &GrammarElement,
+        file: &str,
+        idioms: &mut HashMap<String, Idiom>,
+    ) {
+        match element {
+            GrammarElement::List(items) => {
+                if let Some(GrammarElement::Atom(head)) = items.first() {
+                    let shape = unparse_element(&abstract_element(element, self.depth));
+                    record(idioms, shape, head, file, || unparse_element(element));
+                }
+                for item in items {
+                    self.collect_element(item, file, idioms);
+                }
+            }
+            GrammarElement::Expr(expr) => {
+                for node in preorder(expr) {
+                    if let Some(kind) = expr_kind(node) {
+                        let shape = unparse_expr(&abstract_expr(node, self.depth));
+                        record(idioms, shape, kind, file, || unparse_expr(node));
+                    }
+                }
+            }
+            GrammarElement::Atom(_) | GrammarElement::NaturalLang(_) => {}
+        }
+    }
+}
+
+impl Default for CorpusMiner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn record(
+    idioms: &mut HashMap<String, Idiom>,
+    shape: String,
+    kind: &str,
+    file: &str,
+    example: impl FnOnce() -> String,
+) {
+    let idiom = idioms.entry(shape.clone()).or_insert_with(|| Idiom {
+        shape,
+        kind: kind.strip_prefix("op:").unwrap_or(kind).to_string(),
+        frequency: 0,
+        occurrences: BTreeMap::new(),
+        example: example().chars().take(EXAMPLE_LIMIT).collect(),
+    });
+    idiom.frequency += 1;
+    *idiom.occurrences.entry(file.to_string()).or_insert(0) += 1;
+}
+
+/// 按路径排序收集 .evo 文件 / Collect .evo files sorted by path
+pub(crate) fn collect_evo_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            collect_evo_files(&entry, files)?;
+        } else if entry.extension().is_some_and(|ext| ext == "evo") {
+            files.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// 复合表达式的种类，变量和标量字面量返回 `None`
+/// Kind of a compound expression; `None` for variables and scalar literals
+fn expr_kind(expr: &Expr) -> Option<&str> {
+    Some(match expr {
+        Expr::Literal(Literal::List(_)) => "list",
+        Expr::Literal(Literal::Dict(_)) => "dict",
+        Expr::Literal(_) | Expr::Var(_) => return None,
+        Expr::Call(name, _) => name,
+        Expr::Binary(..) => "binary",
+        Expr::If(..) => "if",
+        Expr::Match(..) => "match",
+        Expr::For { .. } => "for",
+        Expr::While { .. } => "while",
+        Expr::Try { .. } => "try",
+        Expr::Lambda { .. } => "lambda",
+        Expr::Begin(_) => "begin",
+        Expr::Assign(..) => "set!",
+    })
+}
+
+/// 抽象掉名称和常量，超出深度的子表达式记为 `…`
+/// Abstract away names and constants, writing subexpressions beyond the depth as `…`
+fn abstract_expr(expr: &Expr, depth: usize) -> Expr {
+    let placeholder = |text: &str| Expr::Var(text.into());
+    if expr_kind(expr).is_some() && depth == 0 {
+        return placeholder("…");
+    }
+    let inner = |expr: &Expr| Box::new(abstract_expr(expr, depth - 1));
+    match expr {
+        Expr::Literal(Literal::Int(_) | Literal::BigInt(_) | Literal::Float(_)) => placeholder("#"),
+        Expr::Literal(Literal::String(_)) => placeholder("\"_\""),
+        // 集合内容因程序而异，只保留集合本身 / Collection contents vary per program, keep only the collection
+        Expr::Literal(Literal::List(_)) => placeholder("(list …)"),
+        Expr::Literal(Literal::Dict(_)) => placeholder("(dict …)"),
+        Expr::Literal(literal) => Expr::Literal(literal.clone()),
+        Expr::Var(_) => placeholder("_"),
+        Expr::Call(name, args) => Expr::Call(
+            name.clone(),
+            args.iter()
+                .map(|arg| abstract_expr(arg, depth - 1))
+                .collect(),
+        ),
+        Expr::Binary(op, left, right) => Expr::Binary(*op, inner(left), inner(right)),
+        Expr::If(cond, then_expr, else_expr) => {
+            Expr::If(inner(cond), inner(then_expr), inner(else_expr))
+        }
+        Expr::Match(value, cases) => Expr::Match(
+            inner(value),
+            cases
+                .iter()
+                .map(|(pattern, body)| (pattern.clone(), abstract_expr(body, depth - 1)))
+                .collect(),
+        ),
+        Expr::For { iterable, body, .. } => Expr::For {
+            var: "_".into(),
+            iterable: inner(iterable),
+            body: inner(body),
+        },
+        Expr::While { condition, body } => Expr::While {
+            condition: inner(condition),
+            body: inner(body),
+        },
+        Expr::Try {
+            try_body,
+            catch_var,
+            catch_body,
+        } => Expr::Try {
+            try_body: inner(try_body),
+            catch_var: catch_var.as_ref().map(|_| "_".into()),
+            catch_body: inner(catch_body),
+        },
+        Expr::Lambda { params, body } => Expr::Lambda {
+            params: vec!["_".to_string(); params.len()],
+            body: inner(body),
+        },
+        Expr::Begin(exprs) => Expr::Begin(
+            exprs
+                .iter()
+                .map(|expr| abstract_expr(expr, depth - 1))
+                .collect(),
+        ),
+        Expr::Assign(_, value) => Expr::Assign("_".into(), inner(value)),
+    }
+}
+
+/// 抽象列表形式：保留首个关键字，其余名称记为 `_`
+/// Abstract a list form: keep the leading keyword and write other names as `_`
+fn abstract_element(element: &GrammarElement, depth: usize) -> GrammarElement {
+    match element {
+        GrammarElement::List(_) if depth == 0 => GrammarElement::Atom("…".to_string()),
+        GrammarElement::List(items) => GrammarElement::List(
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| match item {
+                    GrammarElement::Atom(_) if index == 0 => item.clone(),
+                    _ => abstract_element(item, depth - 1),
+                })
+                .collect(),
+        ),
+        GrammarElement::Atom(_) | GrammarElement::NaturalLang(_) => {
+            GrammarElement::Atom("_".to_string())
+        }
+        GrammarElement::Expr(expr) => GrammarElement::Expr(Box::new(abstract_expr(expr, depth))),
+    }
+}
@@ -3,9 +3,19 @@
 // Analyze code dependencies and detect circular dependencies
 
 use crate::evolution::analyzer::CodeAnalysis;
-use crate::grammar::core::GrammarElement;
+use crate::grammar::core::{Expr, GrammarElement, Literal};
+use crate::grammar::types::element_name;
+use crate::grammar::visit::{walk_element, Position, Visitor};
+use crate::parser::AdaptiveParser;
+use crate::runtime::interpreter::find_module_file;
+use crate::runtime::stdlib;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// 导入的模块及其 `:only` 选择（`None` 表示导入全部）/ Imported module and its `:only` selection (`None` imports everything)
+type ImportSelection = (String, Option<HashSet<String>>);
 
 /// 代码依赖分析器 / Code dependency analyzer
 pub struct DependencyAnalyzer {
@@ -76,6 +86,100 @@ pub enum Severity {
     Low,
 }
 
+impl CircularDependency {
+    /// 由循环路径创建，路径越短越严重 / Create from a cycle path; shorter cycles are more severe
+    fn from_path(path: Vec<String>, description: &str) -> Self {
+        let severity = if path.len() <= 2 {
+            Severity::Critical
+        } else if path.len() <= 3 {
+            Severity::High
+        } else if path.len() <= 5 {
+            Severity::Medium
+        } else {
+            Severity::Low
+        };
+        Self {
+            description: format!("{}: {}", description, path.join(" -> ")),
+            path,
+            severity,
+        }
+    }
+}
+
+/// 项目依赖图：沿 `import` 跨文件收集的模块、导入关系和函数调用
+/// Project dependency graph: modules, imports and function calls collected across files by following `import`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectGraph {
+    /// 入口模块 / Entry module
+    pub entry: String,
+    /// 模块，按发现顺序（入口在前）/ Modules in discovery order (entry first)
+    pub modules: Vec<ModuleNode>,
+    /// 模块导入关系 / Module imports
+    pub imports: Vec<ModuleImport>,
+    /// 函数调用关系（包括跨模块调用）/ Function calls, including calls across modules
+    pub calls: Vec<FunctionCall>,
+    /// 模块之间的循环导入 / Circular imports between modules
+    pub circular_dependencies: Vec<CircularDependency>,
+    /// 无法找到、读取或解析的模块 / Modules that could not be found, read or parsed
+    pub errors: Vec<ModuleError>,
+}
+
+/// 项目中的模块 / Module of a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleNode {
+    /// 模块标识：相对入口目录的路径（不含 `.evo`），其他目录中的模块为导入名，原生模块为模块名
+    /// Module id: path relative to the entry's directory (without `.evo`); the import name for modules in other
+    /// directories and the module name for native modules
+    pub id: String,
+    /// 模块文件 / Module file
+    pub path: Option<PathBuf>,
+    /// 是否为原生标准库模块 / Whether it is a native standard library module
+    pub native: bool,
+    /// 顶层定义的函数，按定义顺序 / Top-level functions in definition order
+    pub functions: Vec<String>,
+    /// 通过 `defn-` 定义、导入者看不到的函数 / Functions defined via `defn-`, invisible to importers
+    #[serde(default)]
+    pub private: Vec<String>,
+}
+
+/// 模块导入 / Module import
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleImport {
+    /// 导入者 / Importing module
+    pub from: String,
+    /// 被导入者 / Imported module
+    pub to: String,
+    /// 导入别名 / Import alias
+    pub alias: String,
+}
+
+/// 模块中的函数 / Function of a module
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FunctionRef {
+    /// 模块标识 / Module id
+    pub module: String,
+    /// 函数名 / Function name
+    pub function: String,
+}
+
+/// 函数调用 / Function call
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionCall {
+    /// 调用者 / Caller
+    pub caller: FunctionRef,
+    /// 被调用者 / Callee
+    pub callee: FunctionRef,
+}
+
+/// 模块错误 / Module error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleError {
+    /// 出错的模块，导入失败时为导入者 / Module with the error; the importer when an import fails
+    pub module: String,
+    /// 错误信息 / Error message
+    pub message: String,
+}
+
 /// 依赖分析结果 / Dependency analysis result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DependencyAnalysis {
@@ -173,7 +277,7 @@ impl DependencyAnalyzer {
         for element in ast {
             if let GrammarElement::List(list) = element {
                 if let Some(GrammarElement::Atom(first)) = list.first() {
-                    if first == "def" || first == "function" {
+                    if matches!(first.as_str(), "def" | "function" | "defn-") {
                         if list.len() >= 3 {
                             if let GrammarElement::Atom(function_name) = &list[1] {
                                 let mut deps = Vec::new();
@@ -223,7 +327,7 @@ impl DependencyAnalyzer {
         for (i, element) in ast.iter().enumerate() {
             if let GrammarElement::List(list) = element {
                 if let Some(GrammarElement::Atom(first)) = list.first() {
-                    if first == "def" || first == "function" {
+                    if matches!(first.as_str(), "def" | "function" | "defn-") {
                         if list.len() >= 3 {
                             if let GrammarElement::Atom(function_name) = &list[1] {
                                 // 获取该函数的依赖 / Get dependencies for this function
@@ -249,64 +353,10 @@ impl DependencyAnalyzer {
 
     /// 检测循环依赖 / Detect circular dependencies
     fn detect_circular_dependencies(&self) -> Vec<CircularDependency> {
-        let mut circular = Vec::new();
-        let mut visited = HashSet::new();
-        let mut rec_stack = HashSet::new();
-
-        for node in self.dependency_graph.keys() {
-            if !visited.contains(node) {
-                let mut path = Vec::new();
-                self.dfs_detect_cycle(node, &mut visited, &mut rec_stack, &mut path, &mut circular);
-            }
-        }
-
-        circular
-    }
-
-    /// 深度优先搜索检测循环 / DFS to detect cycles
-    fn dfs_detect_cycle(
-        &self,
-        node: &String,
-        visited: &mut HashSet<String>,
-        rec_stack: &mut HashSet<String>,
-        path: &mut Vec<String>,
-        circular: &mut Vec<CircularDependency>,
-    ) {
-        visited.insert(node.clone());
-        rec_stack.insert(node.clone());
-        path.push(node.clone());
-
-        if let Some(deps) = self.dependency_graph.get(node) {
-            for dep in deps {
-                if !visited.contains(dep) {
-                    self.dfs_detect_cycle(dep, visited, rec_stack, path, circular);
-                } else if rec_stack.contains(dep) {
-                    // 发现循环 / Found cycle
-                    let cycle_start = path.iter().position(|x| x == dep).unwrap_or(0);
-                    let mut cycle_path: Vec<String> = path[cycle_start..].to_vec();
-                    cycle_path.push(dep.clone());
-
-                    let severity = if cycle_path.len() <= 2 {
-                        Severity::Critical
-                    } else if cycle_path.len() <= 3 {
-                        Severity::High
-                    } else if cycle_path.len() <= 5 {
-                        Severity::Medium
-                    } else {
-                        Severity::Low
-                    };
-
-                    circular.push(CircularDependency {
-                        path: cycle_path.clone(),
-                        severity,
-                        description: format!("检测到循环依赖: {}", cycle_path.join(" -> ")),
-                    });
-                }
-            }
-        }
-
-        rec_stack.remove(node);
-        path.pop();
+        find_cycles(self.dependency_graph.keys(), &self.dependency_graph)
+            .into_iter()
+            .map(|path| CircularDependency::from_path(path, "检测到循环依赖"))
+            .collect()
     }
 
     /// 计算统计信息 / Calculate statistics
@@ -419,6 +469,282 @@ impl DependencyAnalyzer {
         suggestions
     }
 
+    /// 查找名称的顶层定义（`def`、`function`、`defn-`、`let` 或 `defconst`），返回其在AST中的索引
+    /// Find the top-level definition of a name (`def`, `function`, `defn-`, `let` or `defconst`), returning its index
+    /// in the AST
+    pub fn find_definition(&self, ast: &[GrammarElement], name: &str) -> Option<usize> {
+        ast.iter().position(|element| {
+            let GrammarElement::List(list) = element else {
+                return false;
+            };
+            matches!(
+                list.first(),
+                Some(GrammarElement::Atom(head))
+                    if matches!(head.as_str(), "def" | "function" | "defn-" | "let" | "defconst")
+            ) && list.get(1).and_then(element_name).as_deref() == Some(name)
+        })
+    }
+
+    /// 分析整个项目：从入口文件沿 `import` 跨文件建立模块和函数依赖图，检测模块之间的循环导入。
+    /// 模块按解释器的规则查找：`./`、`../` 相对于导入者所在目录，其余在 `search_paths` 中查找
+    /// Analyze a whole project: follow `import` across files from the entry file to build the module and function
+    /// dependency graph and detect circular imports between modules. Modules are found with the interpreter's rules:
+    /// `./` and `../` relative to the importing file's directory, other names in `search_paths`
+    pub fn analyze_project(&self, entry: &Path, search_paths: &[PathBuf]) -> ProjectGraph {
+        let root_dir = fs_canonical(entry)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let native_modules = stdlib::native_modules();
+        let parser = AdaptiveParser::new(true);
+
+        let mut graph = ProjectGraph::default();
+        let mut known: HashMap<PathBuf, String> = HashMap::new();
+        let mut pending = VecDeque::new();
+        // 原生模块排在文件模块之后 / Native modules come after file modules
+        let mut natives: Vec<ModuleNode> = Vec::new();
+        // 模块导出的名称、按导入顺序排列的文件模块及其 `:only` 选择，以及待所有模块分析完后再解析的不带命名空间的调用
+        // Names each module exports, the file modules each module imports (in import order, with their `:only`
+        // selections), and unqualified calls resolved once every module has been analyzed
+        let mut exports: HashMap<String, Option<HashSet<String>>> = HashMap::new();
+        let mut file_imports: HashMap<String, Vec<ImportSelection>> = HashMap::new();
+        let mut unresolved: Vec<(FunctionRef, String)> = Vec::new();
+
+        let entry_id = module_id(entry, &root_dir, entry.to_string_lossy().as_ref());
+        known.insert(fs_canonical(entry), entry_id.clone());
+        pending.push_back((entry.to_path_buf(), entry_id.clone()));
+        graph.entry = entry_id;
+
+        while let Some((path, id)) = pending.pop_front() {
+            let mut node = ModuleNode {
+                id: id.clone(),
+                path: Some(path.clone()),
+                native: false,
+                functions: Vec::new(),
+                private: Vec::new(),
+            };
+            let ast = match std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|code| parser.parse(&code).map_err(|e| format!("{:?}", e)))
+            {
+                Ok(ast) => ast,
+                Err(message) => {
+                    graph.errors.push(ModuleError {
+                        module: id,
+                        message,
+                    });
+                    graph.modules.push(node);
+                    continue;
+                }
+            };
+
+            exports.insert(id.clone(), collect_exports(&ast));
+
+            // 导入：别名 → 模块标识 / Imports: alias → module id
+            let mut aliases = HashMap::new();
+            let mut imports = Vec::new();
+            for element in &ast {
+                collect_imports(element, &mut imports);
+            }
+            let base_dir = path.parent();
+            for ImportForm { name, alias, only } in imports {
+                let alias = alias.unwrap_or_else(|| {
+                    Path::new(&name)
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().to_string())
+                        .unwrap_or_else(|| name.clone())
+                });
+                let target = match find_module_file(&name, base_dir, search_paths) {
+                    Ok(file) => {
+                        let canonical = fs_canonical(&file);
+                        let target = match known.get(&canonical) {
+                            Some(target) => target.clone(),
+                            None => {
+                                let target = module_id(&file, &root_dir, &name);
+                                known.insert(canonical, target.clone());
+                                pending.push_back((file, target.clone()));
+                                target
+                            }
+                        };
+                        file_imports
+                            .entry(id.clone())
+                            .or_default()
+                            .push((target.clone(), only));
+                        target
+                    }
+                    Err(_) if native_modules.contains_key(&name) => {
+                        if !natives.iter().any(|m| m.id == name) {
+                            let mut functions: Vec<String> = native_modules[&name]
+                                .functions
+                                .keys()
+                                .map(|f| f.to_string())
+                                .collect();
+                            functions.sort();
+                            natives.push(ModuleNode {
+                                id: name.clone(),
+                                path: None,
+                                native: true,
+                                functions,
+                                private: Vec::new(),
+                            });
+                        }
+                        name.clone()
+                    }
+                    Err(searched) => {
+                        let searched: Vec<String> = searched
+                            .iter()
+                            .map(|dir| dir.display().to_string())
+                            .collect();
+                        graph.errors.push(ModuleError {
+                            module: id.clone(),
+                            message: format!(
+                                "Module '{}' not found in search path: {}",
+                                name,
+                                searched.join(", ")
+                            ),
+                        });
+                        continue;
+                    }
+                };
+                aliases.insert(alias.clone(), target.clone());
+                graph.imports.push(ModuleImport {
+                    from: id.clone(),
+                    to: target,
+                    alias,
+                });
+            }
+
+            // 函数及其调用 / Functions and their calls
+            let definitions: Vec<(String, &[GrammarElement])> = ast
+                .iter()
+                .filter_map(|element| {
+                    let GrammarElement::List(list) = element else {
+                        return None;
+                    };
+                    match list.first() {
+                        Some(GrammarElement::Atom(head))
+                            if matches!(head.as_str(), "def" | "function" | "defn-")
+                                && list.len() >= 3 =>
+                        {
+                            if head == "defn-" {
+                                node.private.push(element_name(&list[1])?);
+                            }
+                            Some((element_name(&list[1])?, list.get(3..).unwrap_or_default()))
+                        }
+                        _ => None,
+                    }
+                })
+                .collect();
+            node.functions = definitions.iter().map(|(name, _)| name.clone()).collect();
+            for (name, body) in &definitions {
+                let mut references = Vec::new();
+                for element in *body {
+                    collect_references(element, &mut references);
+                }
+                let caller = FunctionRef {
+                    module: id.clone(),
+                    function: name.clone(),
+                };
+                for reference in references {
+                    let callee = match reference.split_once('.') {
+                        Some((alias, function)) if aliases.contains_key(alias) => FunctionRef {
+                            module: aliases[alias].clone(),
+                            function: function.to_string(),
+                        },
+                        _ if node.functions.contains(&reference) => FunctionRef {
+                            module: id.clone(),
+                            function: reference,
+                        },
+                        // 不带命名空间的名称留待所有模块分析完后在导入的模块中查找
+                        // Unqualified names are looked up in imported modules once every module is analyzed
+                        _ if !reference.contains('.') && file_imports.contains_key(&id) => {
+                            unresolved.push((caller.clone(), reference));
+                            continue;
+                        }
+                        _ => continue,
+                    };
+                    let call = FunctionCall {
+                        caller: caller.clone(),
+                        callee,
+                    };
+                    if !graph.calls.contains(&call) {
+                        graph.calls.push(call);
+                    }
+                }
+            }
+            graph.modules.push(node);
+        }
+
+        graph.modules.extend(natives);
+
+        // 与解释器相同：不带命名空间的调用依次在导入的模块中查找导出且被 `:only` 选中的函数
+        // As in the interpreter, an unqualified call resolves to the first imported module whose exported function of
+        // that name is also selected by `:only`
+        for (caller, function) in unresolved {
+            let target = file_imports[&caller.module].iter().find(|(target, only)| {
+                only.as_ref().is_none_or(|only| only.contains(&function))
+                    && exports
+                        .get(target)
+                        .and_then(Option::as_ref)
+                        .is_none_or(|exports| exports.contains(&function))
+                    && graph.modules.iter().any(|m| {
+                        m.id == *target
+                            && m.functions.contains(&function)
+                            && !m.private.contains(&function)
+                    })
+            });
+            if let Some((target, _)) = target {
+                let call = FunctionCall {
+                    caller,
+                    callee: FunctionRef {
+                        module: target.clone(),
+                        function,
+                    },
+                };
+                if !graph.calls.contains(&call) {
+                    graph.calls.push(call);
+                }
+            }
+        }
+
+        // 跨模块引用可能是变量，只保留被调用模块中定义的函数；私有函数只能在模块内部调用
+        // Cross-module references may be variables, so keep only functions the target module defines; private
+        // functions can only be called from inside their module
+        let functions: HashSet<(&str, &str)> = graph
+            .modules
+            .iter()
+            .flat_map(|m| m.functions.iter().map(move |f| (m.id.as_str(), f.as_str())))
+            .collect();
+        let private: HashSet<(&str, &str)> = graph
+            .modules
+            .iter()
+            .flat_map(|m| m.private.iter().map(move |f| (m.id.as_str(), f.as_str())))
+            .collect();
+        let calls = std::mem::take(&mut graph.calls);
+        graph.calls = calls
+            .into_iter()
+            .filter(|call| {
+                let callee = (call.callee.module.as_str(), call.callee.function.as_str());
+                functions.contains(&callee)
+                    && (call.caller.module == call.callee.module || !private.contains(&callee))
+            })
+            .collect();
+
+        let mut import_graph: HashMap<String, Vec<String>> = HashMap::new();
+        for import in &graph.imports {
+            import_graph
+                .entry(import.from.clone())
+                .or_default()
+                .push(import.to.clone());
+        }
+        let order: Vec<String> = graph.modules.iter().map(|m| m.id.clone()).collect();
+        graph.circular_dependencies = find_cycles(order.iter(), &import_graph)
+            .into_iter()
+            .map(|path| CircularDependency::from_path(path, "检测到模块循环导入"))
+            .collect();
+        graph
+    }
+
     /// 获取分析历史 / Get analysis history
     pub fn get_analysis_history(&self) -> &[DependencyRecord] {
         &self.analysis_history
@@ -455,8 +781,324 @@ impl DependencyAnalyzer {
     }
 }
 
+impl ProjectGraph {
+    /// 按标识查找模块 / Find a module by id
+    pub fn module(&self, id: &str) -> Option<&ModuleNode> {
+        self.modules.iter().find(|module| module.id == id)
+    }
+
+    /// 调用指定函数的函数 / Functions calling the given function
+    pub fn callers_of(&self, module: &str, function: &str) -> Vec<&FunctionRef> {
+        self.calls
+            .iter()
+            .filter(|call| call.callee.module == module && call.callee.function == function)
+            .map(|call| &call.caller)
+            .collect()
+    }
+
+    /// 导出为格式化的JSON / Export as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("依赖图可序列化 / graphs are serializable")
+    }
+
+    /// 导出为Graphviz DOT：每个模块一个子图，包含模块节点和函数节点；
+    /// 导入边连接模块节点，调用边连接函数节点，循环导入标红
+    /// Export as Graphviz DOT: one cluster per module holding the module node and its function nodes; import edges
+    /// connect module nodes, call edges connect function nodes and circular imports are red
+    pub fn to_dot(&self) -> String {
+        let module_node = |id: &str| quote_dot(&format!("module:{}", id));
+        let function_node = |f: &FunctionRef| quote_dot(&format!("{}:{}", f.module, f.function));
+        let in_cycle = |from: &str, to: &str| {
+            self.circular_dependencies.iter().any(|cycle| {
+                cycle
+                    .path
+                    .windows(2)
+                    .any(|pair| pair[0] == from && pair[1] == to)
+            })
+        };
+
+        let mut dot = String::from(
+            "digraph dependencies {\n    rankdir=LR;\n    node [fontname=\"Helvetica\"];\n",
+        );
+        for (index, module) in self.modules.iter().enumerate() {
+            if module.native {
+                let _ = writeln!(
+                    dot,
+                    "    {} [label={}, shape=component, style=dashed];",
+                    module_node(&module.id),
+                    quote_dot(&module.id)
+                );
+                continue;
+            }
+            let _ = writeln!(dot, "    subgraph \"cluster_{}\" {{", index);
+            let _ = writeln!(dot, "        label={};", quote_dot(&module.id));
+            let _ = writeln!(
+                dot,
+                "        {} [label={}, shape=folder];",
+                module_node(&module.id),
+                quote_dot(&module.id)
+            );
+            for function in &module.functions {
+                // 私有函数用虚线表示 / Private functions are drawn dashed
+                let style = if module.private.contains(function) {
+                    ", style=dashed"
+                } else {
+                    ""
+                };
+                let function = FunctionRef {
+                    module: module.id.clone(),
+                    function: function.clone(),
+                };
+                let _ = writeln!(
+                    dot,
+                    "        {} [label={}, shape=ellipse{}];",
+                    function_node(&function),
+                    quote_dot(&function.function),
+                    style
+                );
+            }
+            dot.push_str("    }\n");
+        }
+        for import in &self.imports {
+            let color = if in_cycle(&import.from, &import.to) {
+                ", color=red"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                dot,
+                "    {} -> {} [label={}, style=bold{}];",
+                module_node(&import.from),
+                module_node(&import.to),
+                quote_dot(&import.alias),
+                color
+            );
+        }
+        for call in &self.calls {
+            let native = self.module(&call.callee.module).is_some_and(|m| m.native);
+            // 原生模块没有函数节点，调用指向模块节点 / Native modules have no function nodes, so calls point at the module
+            let callee = if native {
+                module_node(&call.callee.module)
+            } else {
+                function_node(&call.callee)
+            };
+            let _ = writeln!(dot, "    {} -> {};", function_node(&call.caller), callee);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
 impl Default for DependencyAnalyzer {
     fn default() -> Self {
         Self::new()
     }
 }
+
+/// 在有向图中查找循环，按 `nodes` 的顺序开始搜索；每个循环的路径首尾相同
+/// Find cycles in a directed graph, starting searches in the order of `nodes`; every cycle path starts and ends with the same node
+fn find_cycles<'a>(
+    nodes: impl Iterator<Item = &'a String>,
+    graph: &HashMap<String, Vec<String>>,
+) -> Vec<Vec<String>> {
+    let mut cycles = Vec::new();
+    let mut visited = HashSet::new();
+    let mut rec_stack = HashSet::new();
+
+    for node in nodes {
+        if !visited.contains(node) {
+            let mut path = Vec::new();
+            dfs_detect_cycle(
+                node,
+                graph,
+                &mut visited,
+                &mut rec_stack,
+                &mut path,
+                &mut cycles,
+            );
+        }
+    }
+
+    cycles
+}
+
+/// 深度优先搜索检测循环 / DFS to detect cycles
+fn dfs_detect_cycle(
+    node: &String,
+    graph: &HashMap<String, Vec<String>>,
+    visited: &mut HashSet<String>,
+    rec_stack: &mut HashSet<String>,
+    path: &mut Vec<String>,
+    cycles: &mut Vec<Vec<String>>,
+) {
+    visited.insert(node.clone());
+    rec_stack.insert(node.clone());
+    path.push(node.clone());
+
+    if let Some(deps) = graph.get(node) {
+        for dep in deps {
+            if !visited.contains(dep) {
+                dfs_detect_cycle(dep, graph, visited, rec_stack, path, cycles);
+            } else if rec_stack.contains(dep) {
+                // 发现循环 / Found cycle
+                let cycle_start = path.iter().position(|x| x == dep).unwrap_or(0);
+                let mut cycle_path: Vec<String> = path[cycle_start..].to_vec();
+                cycle_path.push(dep.clone());
+                cycles.push(cycle_path);
+            }
+        }
+    }
+
+    rec_stack.remove(node);
+    path.pop();
+}
+
+/// 规范化路径，失败时原样返回 / Canonicalize a path, returning it unchanged on failure
+fn fs_canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// 模块标识：入口目录下的文件用相对路径，其他用导入名 / Module id: relative path under the entry directory, otherwise the import name
+fn module_id(file: &Path, root_dir: &Path, import_name: &str) -> String {
+    let canonical = fs_canonical(file);
+    let id = match canonical.strip_prefix(root_dir) {
+        Ok(relative) => relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => import_name.trim_start_matches("./").to_string(),
+    };
+    id.strip_suffix(".evo").map(str::to_string).unwrap_or(id)
+}
+
+/// `import` 形式：模块名、别名和 `:only` 选中的名称
+/// An `import` form: module name, alias and the names selected with `:only`
+struct ImportForm {
+    name: String,
+    alias: Option<String>,
+    only: Option<HashSet<String>>,
+}
+
+/// 收集 `import` 形式 / Collect `import` forms
+fn collect_imports(element: &GrammarElement, imports: &mut Vec<ImportForm>) {
+    struct Imports<'a>(&'a mut Vec<ImportForm>);
+
+    impl Visitor<'_> for Imports<'_> {
+        fn enter_element(&mut self, element: &GrammarElement, _: Position) -> bool {
+            let GrammarElement::List(list) = element else {
+                return true;
+            };
+            if !matches!(list.first(), Some(GrammarElement::Atom(head)) if head == "import") {
+                return true;
+            }
+            let names: Vec<String> = list[1..]
+                .iter()
+                .filter_map(|item| match item {
+                    GrammarElement::Expr(expr) => import_name(expr),
+                    GrammarElement::Atom(name) => Some(name.clone()),
+                    _ => None,
+                })
+                .collect();
+            if let Some(name) = names.first() {
+                self.0.push(ImportForm {
+                    name: name.clone(),
+                    alias: names.get(1).cloned(),
+                    only: None,
+                });
+            }
+            false
+        }
+
+        fn enter_expr(&mut self, expr: &Expr, _: Position) -> bool {
+            match expr {
+                Expr::Call(name, args) if name == "import" => {
+                    if let Some(module) = args.first().and_then(import_name) {
+                        // `:as` 选项或旧的位置别名 / The `:as` option or the older positional alias
+                        let alias = args.iter().skip(1).find_map(|arg| match arg {
+                            Expr::Call(option, values) if option == ":as" => {
+                                values.first().and_then(import_name)
+                            }
+                            Expr::Call(..) => None,
+                            arg => import_name(arg),
+                        });
+                        let only = args.iter().skip(1).find_map(|arg| match arg {
+                            Expr::Call(option, names) if option == ":only" => {
+                                Some(names.iter().filter_map(import_name).collect())
+                            }
+                            _ => None,
+                        });
+                        self.0.push(ImportForm {
+                            name: module,
+                            alias,
+                            only,
+                        });
+                    }
+                    false
+                }
+                _ => true,
+            }
+        }
+    }
+
+    walk_element(&mut Imports(imports), element, Position::default());
+}
+
+/// `import` 的参数可以是字符串或名称 / `import` arguments may be strings or names
+fn import_name(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Literal(Literal::String(name)) => Some(name.clone()),
+        Expr::Var(name) => Some(name.to_string()),
+        _ => None,
+    }
+}
+
+/// 收集顶层 `export` 列出的名称，没有 `export` 时为 None（全部导出）
+/// Collect the names listed by top-level `export` forms; None without `export` (everything is exported)
+fn collect_exports(ast: &[GrammarElement]) -> Option<HashSet<String>> {
+    let mut exports: Option<HashSet<String>> = None;
+    for element in ast {
+        let names: Vec<String> = match element {
+            GrammarElement::Expr(expr) => match expr.as_ref() {
+                Expr::Call(name, args) if name == "export" => {
+                    args.iter().filter_map(import_name).collect()
+                }
+                _ => continue,
+            },
+            GrammarElement::List(list) if matches!(list.first(), Some(GrammarElement::Atom(head)) if head == "export") => {
+                list[1..].iter().filter_map(element_name).collect()
+            }
+            _ => continue,
+        };
+        exports.get_or_insert_with(HashSet::new).extend(names);
+    }
+    exports
+}
+
+/// 收集元素中调用和引用的名称 / Collect names called and referenced in an element
+fn collect_references(element: &GrammarElement, references: &mut Vec<String>) {
+    struct References<'a>(&'a mut Vec<String>);
+
+    impl Visitor<'_> for References<'_> {
+        fn enter_element(&mut self, element: &GrammarElement, _: Position) -> bool {
+            if let GrammarElement::Atom(name) = element {
+                self.0.push(name.clone());
+            }
+            true
+        }
+
+        fn enter_expr(&mut self, expr: &Expr, _: Position) -> bool {
+            if let Expr::Var(name) | Expr::Call(name, _) = expr {
+                self.0.push(name.to_string());
+            }
+            true
+        }
+    }
+
+    walk_element(&mut References(references), element, Position::default());
+}
+
+/// DOT标识符加引号并转义 / Quote and escape a DOT identifier
+pub(crate) fn quote_dot(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
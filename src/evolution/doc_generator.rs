@@ -1,11 +1,26 @@
 // 代码文档生成器 / Code documentation generator
-// 基于代码分析自动生成代码文档
-// Automatically generate code documentation based on code analysis
+// 基于代码分析自动生成代码文档；HTML站点每个模块一页，函数签名由类型推断得出，调用关系经依赖分析交叉链接
+// Automatically generate code documentation based on code analysis; HTML sites get one page per module, with
+// function signatures from type inference and call references cross-linked through dependency analysis
 
 use crate::evolution::analyzer::CodeAnalysis;
+use crate::evolution::call_graph::{definitions, CallGraph};
+use crate::evolution::corpus::{collect_evo_files, CorpusFailure};
+use crate::evolution::dependency::DependencyAnalyzer;
 use crate::grammar::core::GrammarElement;
+use crate::grammar::inference::TypeInference;
+use crate::grammar::types::{definition_docstring, FunctionSignature, Type};
+use crate::parser::AdaptiveParser;
+use crate::runtime::interpreter::Interpreter;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// HTML页面的样式表 / Stylesheet of the HTML pages
+const HTML_STYLE: &str =
+    "body{font-family:sans-serif;max-width:56em;margin:2em auto;padding:0 1em;line-height:1.5}\
+code{background:#f4f4f4;padding:0 .2em}section{border-top:1px solid #ddd;padding:.5em 0}\
+nav{margin-bottom:1em}table{border-collapse:collapse}td,th{padding:.2em .8em;text-align:left}";
 
 /// 代码文档生成器 / Code documentation generator
 pub struct DocumentationGenerator {
@@ -41,6 +56,30 @@ pub enum DocFormat {
     ApiDoc,
 }
 
+/// 文档站点 / Documentation site
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DocSite {
+    /// 索引页 / Index page
+    pub index: PathBuf,
+    /// 模块页面，按源文件路径排序 / Module pages, sorted by source path
+    pub pages: Vec<DocPage>,
+    /// 无法读取或解析的文件 / Files that could not be read or parsed
+    pub failures: Vec<CorpusFailure>,
+}
+
+/// 模块页面 / Module page
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocPage {
+    /// 模块标识：相对站点目录的路径（不含 `.evo`）/ Module id: path relative to the site directory (without `.evo`)
+    pub module: String,
+    /// 源文件 / Source file
+    pub source: PathBuf,
+    /// 生成的页面 / Generated page
+    pub page: PathBuf,
+    /// 记录的函数数 / Functions documented
+    pub functions: usize,
+}
+
 /// 文档记录 / Documentation record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocRecord {
@@ -180,9 +219,20 @@ impl DocumentationGenerator {
         analysis: &CodeAnalysis,
         format: DocFormat,
     ) -> GeneratedDocumentation {
-        let mut content = String::new();
-        let mut function_docs = 0;
-        let mut variable_docs = 0;
+        self.generate_documentation_with_comments(ast, &[], analysis, format)
+    }
+
+    /// 生成代码文档，`comments` 为各顶层元素前的注释（`AdaptiveParser::parse_with_comments`），
+    /// 在函数没有文档字符串时用作函数描述
+    /// Generate code documentation; `comments` holds the comment before each top-level element
+    /// (`AdaptiveParser::parse_with_comments`) and describes functions that have no docstring
+    pub fn generate_documentation_with_comments(
+        &mut self,
+        ast: &[GrammarElement],
+        comments: &[Option<String>],
+        analysis: &CodeAnalysis,
+        format: DocFormat,
+    ) -> GeneratedDocumentation {
         let mut example_count = 0;
 
         // 根据格式选择模板 / Select template based on format
@@ -193,26 +243,28 @@ impl DocumentationGenerator {
         };
 
         // 生成文档内容 / Generate document content
-        match format {
-            DocFormat::Markdown => {
-                content = self.generate_markdown_doc(ast, analysis);
-                function_docs = analysis.statistics.function_count;
-                variable_docs = analysis.statistics.variable_count;
-            }
-            DocFormat::ApiDoc => {
-                content = self.generate_api_doc(ast, analysis);
-                function_docs = analysis.statistics.function_count;
-                variable_docs = analysis.statistics.variable_count;
-            }
-            DocFormat::PlainText => {
-                content = self.generate_plain_doc(ast, analysis);
-                function_docs = analysis.statistics.function_count;
-                variable_docs = analysis.statistics.variable_count;
-            }
-            _ => {
-                content = self.generate_markdown_doc(ast, analysis);
-            }
-        }
+        let (content, function_docs, variable_docs) = match format {
+            DocFormat::Markdown => (
+                self.generate_markdown_doc(ast, comments, analysis),
+                analysis.statistics.function_count,
+                analysis.statistics.variable_count,
+            ),
+            DocFormat::ApiDoc => (
+                self.generate_api_doc(ast, comments, analysis),
+                analysis.statistics.function_count,
+                analysis.statistics.variable_count,
+            ),
+            DocFormat::PlainText => (
+                self.generate_plain_doc(ast, comments, analysis),
+                analysis.statistics.function_count,
+                analysis.statistics.variable_count,
+            ),
+            DocFormat::Html => (
+                self.generate_html_doc(ast, comments, analysis),
+                analysis.statistics.function_count,
+                analysis.statistics.variable_count,
+            ),
+        };
 
         // 统计文档信息 / Calculate document statistics
         let total_lines = content.lines().count();
@@ -244,7 +296,12 @@ impl DocumentationGenerator {
     }
 
     /// 生成Markdown文档 / Generate Markdown documentation
-    fn generate_markdown_doc(&self, ast: &[GrammarElement], analysis: &CodeAnalysis) -> String {
+    fn generate_markdown_doc(
+        &self,
+        ast: &[GrammarElement],
+        comments: &[Option<String>],
+        analysis: &CodeAnalysis,
+    ) -> String {
         let mut doc = String::from("# 代码文档 / Code Documentation\n\n");
         doc.push_str(&format!("## 概览 / Overview\n\n"));
         doc.push_str(&format!(
@@ -263,14 +320,19 @@ impl DocumentationGenerator {
         doc.push_str("## 函数文档 / Function Documentation\n\n");
 
         // 遍历AST生成函数文档 / Traverse AST to generate function documentation
-        for element in ast {
+        for (index, element) in ast.iter().enumerate() {
+            let comment = comments.get(index).and_then(|comment| comment.as_deref());
             if let GrammarElement::List(list) = element {
+                let comment = definition_docstring(list).or(comment);
                 if let Some(GrammarElement::Atom(first)) = list.first() {
-                    if first == "def" || first == "function" {
+                    if matches!(first.as_str(), "def" | "function" | "defn-") {
                         if list.len() >= 3 {
                             if let GrammarElement::Atom(name) = &list[1] {
                                 doc.push_str(&format!("### {}\n\n", name));
-                                doc.push_str(&format!("**描述 / Description**: 函数定义\n\n"));
+                                doc.push_str(&format!(
+                                    "**描述 / Description**: {}\n\n",
+                                    comment.unwrap_or("函数定义")
+                                ));
 
                                 // 提取参数 / Extract parameters
                                 if let GrammarElement::List(params) = &list[2] {
@@ -306,18 +368,25 @@ impl DocumentationGenerator {
     }
 
     /// 生成API文档 / Generate API documentation
-    fn generate_api_doc(&self, ast: &[GrammarElement], analysis: &CodeAnalysis) -> String {
+    fn generate_api_doc(
+        &self,
+        ast: &[GrammarElement],
+        comments: &[Option<String>],
+        analysis: &CodeAnalysis,
+    ) -> String {
         let mut doc = String::from("# API 文档 / API Documentation\n\n");
 
         doc.push_str("## 函数 / Functions\n\n");
-        for element in ast {
+        for (index, element) in ast.iter().enumerate() {
+            let comment = comments.get(index).and_then(|comment| comment.as_deref());
             if let GrammarElement::List(list) = element {
+                let comment = definition_docstring(list).or(comment);
                 if let Some(GrammarElement::Atom(first)) = list.first() {
-                    if first == "def" || first == "function" {
+                    if matches!(first.as_str(), "def" | "function" | "defn-") {
                         if list.len() >= 3 {
                             if let GrammarElement::Atom(name) = &list[1] {
                                 doc.push_str(&format!("### `{}`\n\n", name));
-                                doc.push_str("函数定义\n\n");
+                                doc.push_str(&format!("{}\n\n", comment.unwrap_or("函数定义")));
                             }
                         }
                     }
@@ -328,18 +397,240 @@ impl DocumentationGenerator {
         doc
     }
 
+    /// 生成单页HTML文档，函数调用链接到页内的定义 / Generate a single-page HTML document, linking calls to definitions on the page
+    fn generate_html_doc(
+        &self,
+        ast: &[GrammarElement],
+        comments: &[Option<String>],
+        analysis: &CodeAnalysis,
+    ) -> String {
+        let graph = CallGraph::build(ast);
+        let local = |name: &str| FunctionLink {
+            page: String::new(),
+            function: name.to_string(),
+            label: name.to_string(),
+        };
+        let entries = function_entries(ast, comments, |name| {
+            (
+                graph.callees(name).into_iter().map(local).collect(),
+                graph.callers(name).into_iter().map(local).collect(),
+            )
+        });
+        let overview = format!(
+            "<ul><li>函数数量 / Function Count: {}</li><li>变量数量 / Variable Count: {}</li>\
+             <li>复杂度 / Complexity: {:.2}</li></ul>\n",
+            analysis.statistics.function_count,
+            analysis.statistics.variable_count,
+            analysis.complexity
+        );
+        render_page("代码文档 / Code Documentation", None, &overview, &entries)
+    }
+
+    /// 为目录中的所有 .evo 文件（路径也可以是单个文件）生成HTML站点：每个模块一页，外加函数索引页。
+    /// 调用和导入关系来自以每个模块为入口的 `DependencyAnalyzer::analyze_project`，跨模块的调用链接到对方页面
+    /// Generate an HTML site for every .evo file under a directory (the path may also be a single file): one page
+    /// per module plus a function index page. Calls and imports come from `DependencyAnalyzer::analyze_project`
+    /// with each module as the entry, and cross-module calls link to the other module's page
+    pub fn generate_site(&mut self, dir: &Path, out_dir: &Path) -> std::io::Result<DocSite> {
+        let mut files = Vec::new();
+        collect_evo_files(dir, &mut files)?;
+        let root = if dir.is_file() {
+            dir.parent().unwrap_or(Path::new(""))
+        } else {
+            dir
+        };
+
+        let parser = AdaptiveParser::new(true);
+        let mut site = DocSite::default();
+        let mut modules: Vec<SiteModule> = Vec::new();
+        for file in files {
+            match std::fs::read_to_string(&file)
+                .map_err(|error| error.to_string())
+                .and_then(|source| {
+                    parser
+                        .parse_with_comments(&source)
+                        .map_err(|error| error.to_string())
+                }) {
+                Ok((ast, comments)) => modules.push(SiteModule {
+                    id: site_module_id(&file, root),
+                    source: file,
+                    ast,
+                    comments,
+                }),
+                Err(error) => site.failures.push(CorpusFailure { path: file, error }),
+            }
+        }
+        let by_path: HashMap<PathBuf, usize> = modules
+            .iter()
+            .enumerate()
+            .map(|(index, module)| (canonical(&module.source), index))
+            .collect();
+
+        // 合并以各模块为入口的依赖图；已出现在之前图中的模块不再作为入口
+        // Merge the dependency graphs with each module as entry; modules already in an earlier graph are not entries again
+        let mut search_paths = vec![root.to_path_buf()];
+        search_paths.extend(Interpreter::new().module_paths());
+        let analyzer = DependencyAnalyzer::new();
+        let mut calls: BTreeSet<((usize, String), (usize, String))> = BTreeSet::new();
+        let mut imports: BTreeSet<(usize, usize)> = BTreeSet::new();
+        let mut covered: HashSet<usize> = HashSet::new();
+        for (entry, module) in modules.iter().enumerate() {
+            if covered.contains(&entry) {
+                continue;
+            }
+            let graph = analyzer.analyze_project(&module.source, &search_paths);
+            let index_of = |id: &str| {
+                graph
+                    .module(id)
+                    .and_then(|node| node.path.as_deref())
+                    .and_then(|path| by_path.get(&canonical(path)).copied())
+            };
+            covered.extend(graph.modules.iter().filter_map(|node| index_of(&node.id)));
+            for call in &graph.calls {
+                if let (Some(caller), Some(callee)) =
+                    (index_of(&call.caller.module), index_of(&call.callee.module))
+                {
+                    calls.insert((
+                        (caller, call.caller.function.clone()),
+                        (callee, call.callee.function.clone()),
+                    ));
+                }
+            }
+            for import in &graph.imports {
+                if let (Some(from), Some(to)) = (index_of(&import.from), index_of(&import.to)) {
+                    imports.insert((from, to));
+                }
+            }
+        }
+
+        std::fs::create_dir_all(out_dir)?;
+        let pages: Vec<String> = modules.iter().map(|m| page_name(&m.id)).collect();
+        let link = |index: usize, function: &str| FunctionLink {
+            page: pages[index].clone(),
+            function: function.to_string(),
+            label: format!("{}.{}", modules[index].id, function),
+        };
+        let mut index_rows = Vec::new();
+        let mut total_lines = 0;
+        let mut total_functions = 0;
+        for (index, module) in modules.iter().enumerate() {
+            let entries = function_entries(&module.ast, &module.comments, |name| {
+                let link_from = |other: usize, function: &str| {
+                    let mut target = link(other, function);
+                    if other == index {
+                        target.page.clear();
+                        target.label = function.to_string();
+                    }
+                    target
+                };
+                (
+                    calls
+                        .iter()
+                        .filter(|((caller, function), _)| *caller == index && function == name)
+                        .map(|(_, (callee, function))| link_from(*callee, function))
+                        .collect(),
+                    calls
+                        .iter()
+                        .filter(|(_, (callee, function))| *callee == index && function == name)
+                        .map(|((caller, function), _)| link_from(*caller, function))
+                        .collect(),
+                )
+            });
+            let imported: Vec<String> = imports
+                .iter()
+                .filter(|(from, _)| *from == index)
+                .map(|(_, to)| {
+                    format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape_html(&pages[*to]),
+                        escape_html(&modules[*to].id)
+                    )
+                })
+                .collect();
+            let mut overview = format!(
+                "<p>源文件 / Source: <code>{}</code></p>\n",
+                escape_html(&module.source.display().to_string())
+            );
+            if !imported.is_empty() {
+                overview.push_str(&format!("<p>导入 / Imports: {}</p>\n", imported.join(", ")));
+            }
+            let html = render_page(&module.id, Some("index.html"), &overview, &entries);
+            let page = out_dir.join(&pages[index]);
+            std::fs::write(&page, &html)?;
+
+            total_lines += html.lines().count();
+            total_functions += entries.len();
+            for entry in &entries {
+                index_rows.push((entry.name.clone(), index, entry.signature.clone()));
+            }
+            site.pages.push(DocPage {
+                module: module.id.clone(),
+                source: module.source.clone(),
+                page,
+                functions: entries.len(),
+            });
+        }
+
+        // 索引页：模块列表和按名称排序的函数表 / Index page: module list and a function table sorted by name
+        let mut body = String::from("<h2>模块 / Modules</h2>\n<ul>\n");
+        for (index, page) in site.pages.iter().enumerate() {
+            body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a> ({} 个函数 / functions)</li>\n",
+                escape_html(&pages[index]),
+                escape_html(&page.module),
+                page.functions
+            ));
+        }
+        body.push_str("</ul>\n<h2>函数 / Functions</h2>\n<table>\n");
+        index_rows.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.cmp(&b.1)));
+        for (name, module, signature) in &index_rows {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td><code>{}</code></td></tr>\n",
+                FunctionLink {
+                    label: name.clone(),
+                    ..link(*module, name)
+                }
+                .to_html(),
+                escape_html(&modules[*module].id),
+                escape_html(signature)
+            ));
+        }
+        body.push_str("</table>\n");
+        let index_html = html_document("文档索引 / Documentation Index", None, &body);
+        site.index = out_dir.join("index.html");
+        std::fs::write(&site.index, &index_html)?;
+
+        self.doc_history.push(DocRecord {
+            timestamp: chrono::Utc::now(),
+            doc_type: "Site".to_string(),
+            doc_length: total_lines + index_html.lines().count(),
+            functions_covered: total_functions,
+        });
+        Ok(site)
+    }
+
     /// 生成纯文本文档 / Generate plain text documentation
-    fn generate_plain_doc(&self, ast: &[GrammarElement], analysis: &CodeAnalysis) -> String {
+    fn generate_plain_doc(
+        &self,
+        ast: &[GrammarElement],
+        comments: &[Option<String>],
+        analysis: &CodeAnalysis,
+    ) -> String {
         let mut doc = String::new();
 
-        for element in ast {
+        for (index, element) in ast.iter().enumerate() {
+            let comment = comments.get(index).and_then(|comment| comment.as_deref());
             if let GrammarElement::List(list) = element {
+                let comment = definition_docstring(list).or(comment);
                 if let Some(GrammarElement::Atom(first)) = list.first() {
-                    if first == "def" || first == "function" {
+                    if matches!(first.as_str(), "def" | "function" | "defn-") {
                         if list.len() >= 3 {
                             if let GrammarElement::Atom(name) = &list[1] {
                                 doc.push_str(&format!(";; 函数: {}\n", name));
-                                doc.push_str(";; 描述: 函数定义\n");
+                                doc.push_str(&format!(
+                                    ";; 描述: {}\n",
+                                    comment.unwrap_or("函数定义").replace('\n', "\n;; ")
+                                ));
                                 doc.push_str("\n");
                             }
                         }
@@ -414,6 +705,189 @@ impl DocumentationGenerator {
     }
 }
 
+/// 站点中的模块 / Module of a site
+struct SiteModule {
+    id: String,
+    source: PathBuf,
+    ast: Vec<GrammarElement>,
+    comments: Vec<Option<String>>,
+}
+
+/// 函数的文档条目 / Documentation entry of a function
+struct FunctionEntry {
+    name: String,
+    signature: String,
+    description: Option<String>,
+    calls: Vec<FunctionLink>,
+    callers: Vec<FunctionLink>,
+}
+
+/// 指向函数定义的链接，`page` 为空表示当前页 / Link to a function definition; an empty `page` means the current page
+struct FunctionLink {
+    page: String,
+    function: String,
+    label: String,
+}
+
+impl FunctionLink {
+    fn to_html(&self) -> String {
+        format!(
+            "<a href=\"{}#{}\"><code>{}</code></a>",
+            escape_html(&self.page),
+            function_anchor(&self.function),
+            escape_html(&self.label)
+        )
+    }
+}
+
+/// 收集AST中函数的文档条目，`relations` 给出函数的调用和调用者链接
+/// Collect the documentation entries of the functions in an AST; `relations` gives the call and caller links of a function
+fn function_entries(
+    ast: &[GrammarElement],
+    comments: &[Option<String>],
+    relations: impl Fn(&str) -> (Vec<FunctionLink>, Vec<FunctionLink>),
+) -> Vec<FunctionEntry> {
+    let inferred = TypeInference::new().infer_program(ast).functions;
+    definitions(ast)
+        .into_iter()
+        .map(|definition| {
+            let signature = match inferred.get(&definition.name) {
+                Some(signature) => format_signature(&definition.name, signature),
+                None => format_signature(
+                    &definition.name,
+                    &FunctionSignature {
+                        params: definition
+                            .params
+                            .iter()
+                            .map(|param| (param.clone(), Type::Any))
+                            .collect(),
+                        return_type: Type::Any,
+                        annotated: false,
+                    },
+                ),
+            };
+            let (calls, callers) = relations(&definition.name);
+            FunctionEntry {
+                description: definition
+                    .docstring
+                    .map(str::to_string)
+                    .or_else(|| comments.get(definition.index).cloned().flatten()),
+                name: definition.name,
+                signature,
+                calls,
+                callers,
+            }
+        })
+        .collect()
+}
+
+/// 函数签名，如 `(area r: Int) -> Int` / Function signature, e.g. `(area r: Int) -> Int`
+fn format_signature(name: &str, signature: &FunctionSignature) -> String {
+    let mut parts = vec![name.to_string()];
+    parts.extend(
+        signature
+            .params
+            .iter()
+            .map(|(param, ty)| format!("{}: {}", param, ty)),
+    );
+    format!("({}) -> {}", parts.join(" "), signature.return_type)
+}
+
+/// 渲染带函数列表的页面 / Render a page with a function list
+fn render_page(
+    title: &str,
+    index: Option<&str>,
+    overview: &str,
+    entries: &[FunctionEntry],
+) -> String {
+    let mut body = String::from(overview);
+    body.push_str("<h2>函数 / Functions</h2>\n<ul>\n");
+    for entry in entries {
+        body.push_str(&format!(
+            "<li><a href=\"#{}\"><code>{}</code></a></li>\n",
+            function_anchor(&entry.name),
+            escape_html(&entry.name)
+        ));
+    }
+    body.push_str("</ul>\n");
+    for entry in entries {
+        body.push_str(&format!(
+            "<section id=\"{}\">\n<h3><code>{}</code></h3>\n",
+            function_anchor(&entry.name),
+            escape_html(&entry.signature)
+        ));
+        if let Some(description) = &entry.description {
+            body.push_str(&format!("<p>{}</p>\n", escape_html(description)));
+        }
+        for (label, links) in [
+            ("调用 / Calls", &entry.calls),
+            ("被调用 / Called by", &entry.callers),
+        ] {
+            if !links.is_empty() {
+                let links: Vec<String> = links.iter().map(FunctionLink::to_html).collect();
+                body.push_str(&format!("<p>{}: {}</p>\n", label, links.join(", ")));
+            }
+        }
+        body.push_str("</section>\n");
+    }
+    html_document(title, index, &body)
+}
+
+fn html_document(title: &str, index: Option<&str>, body: &str) -> String {
+    let nav = index
+        .map(|index| {
+            format!(
+                "<nav><a href=\"{}\">索引 / Index</a></nav>\n",
+                escape_html(index)
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+         <style>{HTML_STYLE}</style>\n</head>\n<body>\n{nav}<h1>{title}</h1>\n{body}</body>\n</html>\n",
+        title = escape_html(title),
+    )
+}
+
+/// 函数在页面中的锚点，非字母数字字符编码为 `_xx` / Anchor of a function on its page; non-alphanumeric characters become `_xx`
+fn function_anchor(name: &str) -> String {
+    let mut anchor = String::from("fn-");
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '-' {
+            anchor.push(c);
+        } else {
+            anchor.push_str(&format!("_{:x}", c as u32));
+        }
+    }
+    anchor
+}
+
+/// 模块页面的文件名：模块标识中的 `/` 换成 `.` / File name of a module page: `/` in the module id becomes `.`
+fn page_name(module: &str) -> String {
+    format!("{}.html", module.replace('/', "."))
+}
+
+/// 相对站点目录、不含 `.evo` 的路径 / Path relative to the site directory, without `.evo`
+fn site_module_id(file: &Path, root: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file).with_extension("");
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 impl Default for DocumentationGenerator {
     fn default() -> Self {
         Self::new()
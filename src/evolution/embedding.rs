@@ -0,0 +1,350 @@
+// 特征向量嵌入与相似度检索 / Feature-vector embeddings and similarity search
+// 把语法规则和AST编码为定长的哈希特征向量（节点类型袋、规范化标识符、结构n元组哈希），用余弦相似度比较，
+// 并用随机超平面局部敏感哈希建立可扩展到数千条目的索引
+// Encodes grammar rules and ASTs as fixed-length hashed feature vectors (bag of node kinds, normalized identifiers,
+// structural n-gram hashes), compares them by cosine similarity, and indexes them with random-hyperplane
+// locality-sensitive hashing so search scales to thousands of entries
+
+use crate::evolution::call_graph::Definition;
+use crate::evolution::similarity::{code_tokens, function_tokens};
+use crate::grammar::rule::{GrammarRule, PatternElement};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// 嵌入向量的维数 / Number of embedding dimensions
+pub const EMBEDDING_DIMENSIONS: usize = 256;
+
+/// 局部敏感哈希的分段数，每段8个超平面 / Number of LSH bands, with 8 hyperplanes each
+const BANDS: usize = 16;
+
+/// 条目不超过此数时逐一比较，不使用哈希桶 / Up to this many entries every entry is compared, without buckets
+const EXACT_SEARCH_LIMIT: usize = 512;
+
+/// 超平面的随机种子 / Random seed of the hyperplanes
+const HYPERPLANE_SEED: u64 = 0x5eed_e3b0;
+
+/// 归一化的特征向量 / Normalized feature vector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Embedding {
+    values: Vec<f32>,
+}
+
+impl Embedding {
+    /// 由带权特征构建：每个特征哈希到一个维度和符号，结果按L2范数归一化
+    /// Build from weighted features: each feature hashes to one dimension and sign, and the result is L2-normalized
+    pub fn from_features<S: AsRef<str>>(features: impl IntoIterator<Item = (S, f32)>) -> Self {
+        let mut values = vec![0.0f32; EMBEDDING_DIMENSIONS];
+        for (feature, weight) in features {
+            let hash = fnv1a(feature.as_ref().as_bytes());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            values[(hash % EMBEDDING_DIMENSIONS as u64) as usize] += sign * weight;
+        }
+        let norm = values.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            for value in &mut values {
+                *value /= norm;
+            }
+        }
+        Self { values }
+    }
+
+    /// 由规范化词法单元序列构建：单个单元表示节点类型和标识符，相邻的二元组和三元组表示结构
+    /// Build from a normalized token sequence: single tokens stand for node kinds and identifiers, adjacent pairs
+    /// and triples for structure
+    pub fn from_tokens(tokens: &[String]) -> Self {
+        let unigrams = tokens.iter().map(|token| (format!("1:{}", token), 1.0));
+        let bigrams = tokens
+            .windows(2)
+            .map(|pair| (format!("2:{}|{}", pair[0], pair[1]), 0.5));
+        let trigrams = tokens
+            .windows(3)
+            .map(|triple| (format!("3:{}|{}|{}", triple[0], triple[1], triple[2]), 0.25));
+        Self::from_features(unigrams.chain(bigrams).chain(trigrams))
+    }
+
+    /// 代码的嵌入：局部变量按绑定顺序编号，字面量只保留类型
+    /// Embedding of code: locals are numbered in binding order and literals keep only their type
+    pub fn of_code(elements: &[crate::grammar::core::GrammarElement]) -> Self {
+        Self::from_tokens(&code_tokens(elements))
+    }
+
+    /// 函数定义的嵌入，参数和局部变量规范化，递归调用记为 `$self`
+    /// Embedding of a function definition, with parameters and locals normalized and recursive calls as `$self`
+    pub(crate) fn of_function(definition: &Definition<'_>) -> Self {
+        Self::from_tokens(&function_tokens(definition))
+    }
+
+    /// 语法规则的嵌入：模式元素、产生式目标的结构，以及规则名和同义词中的词
+    /// Embedding of a grammar rule: pattern elements, the structure of the production target, and the words of
+    /// the rule name and synonyms
+    pub fn of_rule(rule: &GrammarRule) -> Self {
+        let mut tokens = Vec::new();
+        for element in &rule.pattern.elements {
+            pattern_tokens(element, &mut tokens);
+        }
+        if rule.pattern.variadic {
+            tokens.push("variadic".to_string());
+        }
+        tokens.push("=>".to_string());
+        tokens.extend(code_tokens(std::slice::from_ref(&rule.production.target)));
+
+        let words: Vec<(String, f32)> = std::iter::once(rule.name.as_str())
+            .chain(rule.meta.natural_lang_synonyms.iter().map(String::as_str))
+            .flat_map(|text| text.split(|c: char| !c.is_alphanumeric()))
+            .filter(|word| !word.is_empty())
+            .map(|word| (format!("word:{}", word.to_lowercase()), 1.0))
+            .collect();
+
+        Self::from_tokens(&tokens).combine(&Self::from_features(words), 0.3)
+    }
+
+    /// 余弦相似度（向量已归一化，即点积）/ Cosine similarity (the dot product, as vectors are normalized)
+    pub fn cosine(&self, other: &Embedding) -> f64 {
+        self.values
+            .iter()
+            .zip(&other.values)
+            .map(|(a, b)| f64::from(a * b))
+            .sum::<f64>()
+            .clamp(-1.0, 1.0)
+    }
+
+    /// 是否为零向量（没有任何特征）/ Whether this is the zero vector (no features at all)
+    pub fn is_zero(&self) -> bool {
+        self.values.iter().all(|value| *value == 0.0)
+    }
+
+    /// 按权重混合另一个向量后重新归一化 / Mix in another vector with a weight and normalize again
+    fn combine(mut self, other: &Embedding, weight: f32) -> Self {
+        for (value, extra) in self.values.iter_mut().zip(&other.values) {
+            *value += extra * weight;
+        }
+        let norm = self.values.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if norm > 0.0 {
+            for value in &mut self.values {
+                *value /= norm;
+            }
+        }
+        self
+    }
+}
+
+/// 模式元素的词法单元：关键字和自然语言保留原文，标识符和通配符只保留种类
+/// Tokens of a pattern element: keywords and natural language keep their text, identifiers and wildcards only
+/// their kind
+fn pattern_tokens(element: &PatternElement, tokens: &mut Vec<String>) {
+    match element {
+        PatternElement::Keyword(keyword) => tokens.push(format!("kw:{}", keyword)),
+        PatternElement::Identifier(_) => tokens.push("ident".to_string()),
+        PatternElement::Literal(literal) => {
+            tokens.push("literal".to_string());
+            tokens.extend(code_tokens(std::slice::from_ref(literal)));
+        }
+        PatternElement::Wildcard(_) => tokens.push("wildcard".to_string()),
+        PatternElement::Optional(inner) => {
+            tokens.push("optional".to_string());
+            pattern_tokens(inner, tokens);
+        }
+        PatternElement::Repeat(inner) => {
+            tokens.push("repeat".to_string());
+            pattern_tokens(inner, tokens);
+        }
+        PatternElement::Group(items) => {
+            tokens.push(format!("group/{}", items.len()));
+            for item in items {
+                pattern_tokens(item, tokens);
+            }
+        }
+        PatternElement::NaturalLang(text) => tokens.push(format!("nl:{}", text)),
+    }
+}
+
+/// 嵌入索引：条目少时逐一比较；条目多时用随机超平面签名分段分桶，只比较至少有一段相同的条目
+/// Embedding index: with few entries every entry is compared; with many, random-hyperplane signatures are split
+/// into bands and only entries sharing at least one band with the query are compared
+#[derive(Debug, Clone)]
+pub struct EmbeddingIndex {
+    entries: Vec<IndexEntry>,
+    positions: HashMap<String, usize>,
+    /// 每段签名到条目的桶 / Buckets from each band's signature to entries
+    buckets: Vec<HashMap<u8, Vec<usize>>>,
+    hyperplanes: Vec<Vec<f32>>,
+}
+
+#[derive(Debug, Clone)]
+struct IndexEntry {
+    id: String,
+    embedding: Embedding,
+    signature: [u8; BANDS],
+}
+
+impl EmbeddingIndex {
+    /// 创建空索引，超平面由固定种子生成，结果可复现 / Create an empty index; hyperplanes come from a fixed seed, so results are reproducible
+    pub fn new() -> Self {
+        let mut state = HYPERPLANE_SEED;
+        let hyperplanes = (0..BANDS * 8)
+            .map(|_| {
+                (0..EMBEDDING_DIMENSIONS)
+                    .map(|_| {
+                        // 均匀分布的和近似正态分布 / A sum of uniforms approximates a normal distribution
+                        (0..4)
+                            .map(|_| (splitmix64(&mut state) >> 40) as f32 / (1u64 << 24) as f32)
+                            .sum::<f32>()
+                            - 2.0
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            entries: Vec::new(),
+            positions: HashMap::new(),
+            buckets: vec![HashMap::new(); BANDS],
+            hyperplanes,
+        }
+    }
+
+    /// 条目数 / Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 是否为空 / Whether the index is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 插入条目，同ID的条目被替换 / Insert an entry, replacing one with the same id
+    pub fn insert(&mut self, id: impl Into<String>, embedding: Embedding) {
+        let id = id.into();
+        let signature = self.signature(&embedding);
+        let position = match self.positions.get(&id) {
+            Some(&position) => {
+                let old = self.entries[position].signature;
+                for (band, key) in old.iter().enumerate() {
+                    if let Some(bucket) = self.buckets[band].get_mut(key) {
+                        bucket.retain(|entry| *entry != position);
+                    }
+                }
+                self.entries[position] = IndexEntry {
+                    id,
+                    embedding,
+                    signature,
+                };
+                position
+            }
+            None => {
+                let position = self.entries.len();
+                self.positions.insert(id.clone(), position);
+                self.entries.push(IndexEntry {
+                    id,
+                    embedding,
+                    signature,
+                });
+                position
+            }
+        };
+        for (band, key) in signature.iter().enumerate() {
+            self.buckets[band].entry(*key).or_default().push(position);
+        }
+    }
+
+    /// 条目的嵌入 / Embedding of an entry
+    pub fn get(&self, id: &str) -> Option<&Embedding> {
+        self.positions
+            .get(id)
+            .map(|&position| &self.entries[position].embedding)
+    }
+
+    /// 与查询最相似的条目，相似度不低于 `threshold`，按相似度降序，最多 `limit` 个
+    /// Entries most similar to the query with similarity at least `threshold`, by similarity descending, at most
+    /// `limit` of them
+    pub fn search(&self, query: &Embedding, limit: usize, threshold: f64) -> Vec<(String, f64)> {
+        self.search_excluding(query, limit, threshold, None)
+    }
+
+    /// 与某个条目最相似的其他条目 / Other entries most similar to an entry
+    pub fn similar_to(&self, id: &str, limit: usize, threshold: f64) -> Vec<(String, f64)> {
+        match self.get(id) {
+            Some(embedding) => self.search_excluding(embedding, limit, threshold, Some(id)),
+            None => Vec::new(),
+        }
+    }
+
+    fn search_excluding(
+        &self,
+        query: &Embedding,
+        limit: usize,
+        threshold: f64,
+        exclude: Option<&str>,
+    ) -> Vec<(String, f64)> {
+        let score = |position: usize| {
+            let entry = &self.entries[position];
+            (exclude != Some(entry.id.as_str()))
+                .then(|| (entry.id.clone(), query.cosine(&entry.embedding)))
+                .filter(|(_, similarity)| *similarity >= threshold)
+        };
+
+        let mut results: Vec<(String, f64)> = if self.entries.len() <= EXACT_SEARCH_LIMIT {
+            (0..self.entries.len()).filter_map(score).collect()
+        } else {
+            let signature = self.signature(query);
+            let candidates: BTreeSet<usize> = signature
+                .iter()
+                .enumerate()
+                .filter_map(|(band, key)| self.buckets[band].get(key))
+                .flatten()
+                .copied()
+                .collect();
+            let found: Vec<(String, f64)> = candidates.into_iter().filter_map(score).collect();
+            // 候选不足时退回逐一比较 / Fall back to comparing every entry when there are too few candidates
+            if found.len() < limit {
+                (0..self.entries.len()).filter_map(score).collect()
+            } else {
+                found
+            }
+        };
+        results.sort_by(|a, b| {
+            b.1.partial_cmp(&a.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        results.truncate(limit);
+        results
+    }
+
+    /// 随机超平面签名：每个超平面一位，每8位一段 / Random-hyperplane signature: one bit per hyperplane, 8 bits per band
+    fn signature(&self, embedding: &Embedding) -> [u8; BANDS] {
+        let mut signature = [0u8; BANDS];
+        for (bit, plane) in self.hyperplanes.iter().enumerate() {
+            let side: f32 = plane
+                .iter()
+                .zip(&embedding.values)
+                .map(|(p, v)| p * v)
+                .sum();
+            if side >= 0.0 {
+                signature[bit / 8] |= 1 << (bit % 8);
+            }
+        }
+        signature
+    }
+}
+
+impl Default for EmbeddingIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// FNV-1a哈希，跨进程和版本稳定 / FNV-1a hash, stable across processes and versions
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
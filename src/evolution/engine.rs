@@ -2,15 +2,32 @@
 // 驱动语言的自进化过程
 // Drives the self-evolution process of the language
 
-use crate::evolution::tracker::{EvolutionEvent, EvolutionTracker, EvolutionType, TriggerSource};
+use crate::evolution::code_generator::IntelligentCodeGenerator;
+use crate::evolution::configurator::{RuntimeChange, RuntimeConfigurator};
+use crate::evolution::conflicts::{ConflictDetector, ConflictReport};
+use crate::evolution::corpus::{CorpusMiner, CorpusReport, Idiom};
+use crate::evolution::evaluation::{EvaluationCorpus, RuleEvaluation, RuleEvaluator};
+use crate::evolution::event_manager::{
+    EventBus, EvolutionNotification, EvolutionSubscriber, NotificationFilter, NotificationKind,
+    SubscriptionId,
+};
+use crate::evolution::genealogy_report::{GenealogyFormat, GenealogyReport};
+use crate::evolution::genetic::{EvolvedProgram, FitnessSpec, ProgramEvolver};
+use crate::evolution::mutation::SeededRng;
+use crate::evolution::sandbox::{SandboxConfig, SandboxResult, SandboxRunner};
+use crate::evolution::tracker::{
+    EvolutionEvent, EvolutionMetrics, EvolutionTracker, EvolutionType, TriggerSource,
+};
+use crate::evolution::verification::{GateReport, RegressionCorpus, VerificationGate};
 use crate::grammar::core::GrammarElement;
 use crate::grammar::rule::{
     DefinitionMethod, GrammarRule, Pattern, PatternElement, Production, RuleMetadata, Stability,
 };
+use crate::parser::backend::{HttpBackend, NluBackend};
 use crate::parser::nlu::NLUParser;
-use crate::parser::AdaptiveParser;
-use crate::poetry::PoetryParser;
-use crate::runtime::interpreter::{Interpreter, Value};
+use crate::parser::{AdaptiveParser, RuleRegistry};
+use crate::poetry::{EmotionAnalyzer, EmotionMapping, PoetryCodegen, PoetryParser, ProgramForm};
+use crate::runtime::interpreter::{DictMap, ExecutionBudget, Interpreter, Value};
 use std::collections::HashMap;
 
 /// 进化引擎 / Evolution engine
@@ -25,10 +42,24 @@ pub struct EvolutionEngine {
     nlu_parser: NLUParser,
     /// 诗歌解析器 / Poetry parser
     poetry_parser: PoetryParser,
+    /// 诗歌程序生成器 / Poetry program generator
+    poetry_codegen: PoetryCodegen,
     /// 知识图谱 / Knowledge graph
     knowledge_graph: crate::evolution::knowledge::EvolutionKnowledgeGraph,
     /// 使用模式学习器 / Usage pattern learner
-    learner: crate::evolution::learning::UsagePatternLearner,
+    learner: crate::evolution::learning::LearningHandle,
+    /// 运行时配置器：应用了已进化规则的解析器和解释器 / Runtime configurator: parser and interpreter with evolved rules applied
+    runtime: RuntimeConfigurator,
+    /// 候选语法规则的A/B评估器 / A/B evaluator for candidate syntax rules
+    evaluator: RuleEvaluator,
+    /// 进化通知的订阅者 / Subscribers to evolution notifications
+    event_bus: EventBus,
+    /// 自我进化的验证关卡 / Verification gate for self-evolution
+    verification: Option<VerificationGate>,
+    /// 随机数种子：预测排序、代码生成、程序进化和沙箱评估的种子都由它派生，并记录在每个进化事件中
+    /// Random seed: the seeds of prediction ranking, code generation, program evolution and sandbox evaluation
+    /// are derived from it, and it is recorded in every evolution event
+    seed: u64,
 }
 
 impl EvolutionEngine {
@@ -36,13 +67,23 @@ impl EvolutionEngine {
     pub fn new() -> Self {
         let bootstrap_rules = Self::load_bootstrap_rules();
         let mut engine = Self {
+            runtime: RuntimeConfigurator::with_rules(bootstrap_rules.clone()),
             syntax_mutations: bootstrap_rules,
             semantic_adaptations: Vec::new(),
             tracker: EvolutionTracker::new(),
-            nlu_parser: NLUParser::new(crate::parser::nlu::ModelType::LocalLightweight, true),
+            // 设置了 EVO_LLM_ENDPOINT 时使用HTTP模型后端 / Use the HTTP model backend when EVO_LLM_ENDPOINT is set
+            nlu_parser: match HttpBackend::from_env() {
+                Some(backend) => NLUParser::with_backend(std::sync::Arc::new(backend)),
+                None => NLUParser::new(crate::parser::nlu::ModelType::LocalLightweight, true),
+            },
             poetry_parser: PoetryParser::new(),
+            poetry_codegen: PoetryCodegen::new(),
             knowledge_graph: crate::evolution::knowledge::EvolutionKnowledgeGraph::new(),
-            learner: crate::evolution::learning::UsagePatternLearner::new(),
+            learner: crate::evolution::learning::LearningHandle::new(),
+            evaluator: RuleEvaluator::default(),
+            event_bus: EventBus::new(),
+            verification: None,
+            seed: uuid::Uuid::new_v4().as_u64_pair().0,
         };
 
         // 从历史构建知识图谱 / Build knowledge graph from history
@@ -51,10 +92,48 @@ impl EvolutionEngine {
         engine
     }
 
+    /// 以随机数种子创建，相同种子和相同操作序列得到相同的进化结果
+    /// Create with a random seed; the same seed and the same sequence of operations give the same evolution outcome
+    pub fn with_seed(seed: u64) -> Self {
+        let mut engine = Self::new();
+        engine.seed = seed;
+        engine
+    }
+
+    /// 随机数种子 / Random seed
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// 设置随机数种子，例如重放进化事件中记录的种子 / Set the random seed, e.g. to replay the seed recorded in an evolution event
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// 为一类随机选择派生种子：由引擎种子、用途和已记录的事件数决定，重放同样的操作序列得到同样的种子
+    /// Derive the seed of one kind of random choice from the engine seed, its purpose and the number of recorded
+    /// events, so replaying the same sequence of operations yields the same seeds
+    fn derived_seed(&self, purpose: &str) -> u64 {
+        let label = format!("{}#{}", purpose, self.tracker.get_history().len());
+        SeededRng::derive(self.seed, &label)
+    }
+
+    /// 在事件的触发环境中记录引擎种子 / Record the engine seed in an event's trigger environment
+    fn stamp_seed(&self, event: &mut EvolutionEvent) {
+        let environment = &mut event.trigger.environment;
+        if !environment.is_object() {
+            *environment = serde_json::json!({});
+        }
+        if let Some(environment) = environment.as_object_mut() {
+            environment.insert("seed".to_string(), serde_json::json!(self.seed));
+        }
+    }
+
     /// 重建知识图谱 / Rebuild knowledge graph
     fn rebuild_knowledge(&mut self) {
         let history = self.tracker.get_history();
         self.knowledge_graph.build_from_history(history);
+        self.knowledge_graph.index_rules(&self.syntax_mutations);
     }
 
     /// 从自然语言进化 / Evolve from natural language
@@ -80,6 +159,11 @@ impl EvolutionEngine {
         Ok(vec![optimal])
     }
 
+    /// 运行时切换意图识别后端，`None` 恢复为规则库 / Switch the intent recognition backend at runtime; `None` returns to the rule database
+    pub fn set_nlu_backend(&mut self, backend: Option<std::sync::Arc<dyn NluBackend>>) {
+        self.nlu_parser.set_backend(backend);
+    }
+
     /// 生成语法变体 / Generate syntax variants
     fn generate_syntax_variants(
         &self,
@@ -94,14 +178,48 @@ impl EvolutionEngine {
         self.rules_from_value(&value)
     }
 
-    /// 测试变体 / Test variants
+    /// 测试变体：在评估语料上A/B比较，返回被接受且提升最大的变体
+    /// Test variants: A/B compare them on the evaluation corpus and return the accepted variant with the largest gain
     fn test_variants(&self, variants: Vec<GrammarRule>) -> Result<GrammarRule, EvolutionError> {
-        // TODO: 实现变体测试逻辑 / Implement variant testing logic
-        // 暂时返回第一个变体 / Temporarily return first variant
-        variants
-            .into_iter()
-            .next()
-            .ok_or(EvolutionError::NoVariants)
+        if variants.is_empty() {
+            return Err(EvolutionError::NoVariants);
+        }
+        let (best, evaluations) = self.evaluator.select(&self.syntax_mutations, &variants);
+        match best {
+            Some(index) => Ok(variants[index].clone()),
+            None => Err(EvolutionError::TestFailed(
+                evaluations
+                    .iter()
+                    .map(|evaluation| {
+                        format!("{}: {}", evaluation.rule, evaluation.reasons.join("; "))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+            )),
+        }
+    }
+
+    /// 设置评估候选语法规则的语料 / Set the corpus candidate syntax rules are evaluated on
+    pub fn set_evaluation_corpus(&mut self, corpus: EvaluationCorpus) {
+        self.evaluator.set_corpus(corpus);
+    }
+
+    /// 设置自我进化的回归语料，`self_evolve()` 之后运行，通过率低于阈值时回滚
+    /// Set the regression corpus for self-evolution, run after `self_evolve()` with a rollback when the pass rate is below the threshold
+    pub fn set_regression_corpus(&mut self, corpus: RegressionCorpus) {
+        self.verification = Some(VerificationGate::new(corpus));
+    }
+
+    /// 在评估语料上A/B比较带和不带该规则的解析器 / A/B compare parsers with and without the rule on the evaluation corpus
+    pub fn evaluate_rule(&self, rule: &GrammarRule) -> RuleEvaluation {
+        self.evaluator.evaluate(&self.syntax_mutations, rule)
+    }
+
+    /// 检查规则与当前语法规则的冲突（安装时运行时配置器会拒绝有阻止性冲突的规则）
+    /// Check a rule for conflicts with the current grammar rules (the runtime configurator refuses rules with
+    /// blocking conflicts on installation)
+    pub fn check_rule_conflicts(&self, rule: &GrammarRule) -> ConflictReport {
+        ConflictDetector::new().check(&self.syntax_mutations, rule)
     }
 
     /// 集成新特性 / Integrate new feature
@@ -140,8 +258,12 @@ impl EvolutionEngine {
             success_metrics: None,
         };
 
-        self.tracker.record(event.clone());
-        self.syntax_mutations.push(rule);
+        self.runtime.apply_with_id(
+            event.id,
+            vec![RuntimeChange::AddRule(Box::new(rule.clone()))],
+        )?;
+        self.record_event(event.clone());
+        self.syntax_mutations = self.runtime.rules().to_vec();
 
         // 更新知识图谱 / Update knowledge graph
         self.knowledge_graph.build_from_history(&[event]);
@@ -162,7 +284,42 @@ impl EvolutionEngine {
             goals,
             constraints: Vec::new(),
         };
-        self.knowledge_graph.predict_evolutions(&context)
+        self.knowledge_graph
+            .predict_evolutions_with_seed(&context, self.derived_seed("predict"))
+    }
+
+    /// 知识图谱，用于查询和导出 / Knowledge graph, for queries and export
+    pub fn knowledge_graph(&self) -> &crate::evolution::knowledge::EvolutionKnowledgeGraph {
+        &self.knowledge_graph
+    }
+
+    /// 知识图谱（可变），用于订阅新模式 / Knowledge graph (mutable), for subscribing to new patterns
+    pub fn knowledge_graph_mut(
+        &mut self,
+    ) -> &mut crate::evolution::knowledge::EvolutionKnowledgeGraph {
+        &mut self.knowledge_graph
+    }
+
+    /// 订阅进化通知（添加规则、回滚、自我进化完成等）/ Subscribe to evolution notifications (rule added, rollback, self-evolution completed, ...)
+    pub fn subscribe(
+        &mut self,
+        filter: NotificationFilter,
+        subscriber: Box<dyn EvolutionSubscriber>,
+    ) -> SubscriptionId {
+        self.event_bus.subscribe(filter, subscriber)
+    }
+
+    /// 事件总线 / Event bus
+    pub fn event_bus_mut(&mut self) -> &mut EventBus {
+        &mut self.event_bus
+    }
+
+    /// 记录进化事件并通知订阅者 / Record an evolution event and notify subscribers
+    fn record_event(&mut self, mut event: EvolutionEvent) {
+        self.stamp_seed(&mut event);
+        let notification = EvolutionNotification::from_event(&event);
+        self.tracker.record(event);
+        self.event_bus.publish(&notification);
     }
 
     /// 获取知识图谱统计 / Get knowledge graph statistics
@@ -170,9 +327,45 @@ impl EvolutionEngine {
         serde_json::json!({
             "nodes_count": self.knowledge_graph.get_node_count(),
             "patterns_count": self.knowledge_graph.get_patterns_count(),
+            "idioms_count": self.knowledge_graph.idioms().len(),
         })
     }
 
+    /// 从 .evo 文件目录学习：递归解析每个文件，经分析器和相似度检测器处理后，把常见惯用法及其频率写入知识图谱
+    /// Learn from a directory of .evo files: parse every file recursively, run it through the analyzer and
+    /// similarity detector, and record common idioms and their frequencies in the knowledge graph
+    pub fn learn_from_corpus(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+    ) -> Result<CorpusReport, EvolutionError> {
+        self.learn_from_corpus_with(path, &CorpusMiner::new())
+    }
+
+    /// 用指定的挖掘器从语料学习 / Learn from a corpus with the given miner
+    pub fn learn_from_corpus_with(
+        &mut self,
+        path: impl AsRef<std::path::Path>,
+        miner: &CorpusMiner,
+    ) -> Result<CorpusReport, EvolutionError> {
+        let path = path.as_ref();
+        let report = miner.mine(self.runtime.parser(), path).map_err(|e| {
+            EvolutionError::IntegrationFailed(format!(
+                "Failed to read corpus {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        for idiom in &report.idioms {
+            self.knowledge_graph.record_idiom(idiom);
+        }
+        Ok(report)
+    }
+
+    /// 知识图谱中的语料惯用法，按频率降序 / Corpus idioms in the knowledge graph by descending frequency
+    pub fn corpus_idioms(&self) -> Vec<Idiom> {
+        self.knowledge_graph.idioms()
+    }
+
     /// 分析代码并提供优化建议 / Analyze code and provide optimization suggestions
     pub fn analyze_code(&self, ast: &[GrammarElement]) -> crate::evolution::analyzer::CodeAnalysis {
         let analyzer = crate::evolution::analyzer::CodeAnalyzer::new();
@@ -181,12 +374,20 @@ impl EvolutionEngine {
 
     /// 自动重构代码 / Automatically refactor code
     pub fn refactor_code(&self, ast: &[GrammarElement]) -> Vec<GrammarElement> {
-        // 先分析代码 / First analyze code
-        let analysis = self.analyze_code(ast);
+        // 先运行优化遍 / First run the optimizer passes
+        let optimized = self.optimize_code(ast).ast;
+
+        // 再分析代码 / Then analyze code
+        let analysis = self.analyze_code(&optimized);
 
         // 根据分析结果重构 / Refactor based on analysis
         let refactorer = crate::evolution::analyzer::CodeRefactorer::new();
-        refactorer.refactor(ast, &analysis)
+        refactorer.refactor(&optimized, &analysis)
+    }
+
+    /// 运行全部优化遍，返回优化后的AST和改动记录 / Run all optimizer passes, returning the optimized AST and the change records
+    pub fn optimize_code(&self, ast: &[GrammarElement]) -> crate::optimizer::passes::PassResult {
+        crate::optimizer::passes::optimize(ast)
     }
 
     /// 自我进化：自动改进自身实现 / Self-evolution: automatically improve own implementation
@@ -213,7 +414,14 @@ impl EvolutionEngine {
             }
         }
 
+        // 进化前在当前配置上准备回归用例 / Prepare regression cases under the pre-evolution configuration
+        let regression_cases = self
+            .verification
+            .as_ref()
+            .map(|gate| gate.prepare(self.runtime.parser()));
+
         // 如果发现可改进的规则，触发进化 / If improvable rules found, trigger evolution
+        let mut recorded = None;
         if improvement_count > 0 {
             // 记录自我进化事件 / Record self-evolution event
             let event = EvolutionEvent {
@@ -248,15 +456,39 @@ impl EvolutionEngine {
                 },
             };
 
-            self.tracker.record(event);
+            recorded = Some(event.id);
+            self.record_event(event);
             self.rebuild_knowledge();
+        } else {
+            self.event_bus.publish(&EvolutionNotification::new(
+                NotificationKind::SelfEvolveCompleted,
+                "自我进化：当前实现已是最优",
+            ));
         }
 
+        // 在进化后的配置上验证，通过率低于阈值时回滚记录的事件
+        // Verify under the post-evolution configuration, rolling back the recorded event below the threshold
+        let verification: Option<GateReport> = self
+            .verification
+            .as_ref()
+            .zip(regression_cases)
+            .map(|(gate, cases)| gate.verify(self.runtime.parser(), &cases));
+        let rolled_back = match (&verification, recorded) {
+            (Some(report), Some(event_id)) if !report.accepted => {
+                self.rollback_to_event(event_id)?;
+                true
+            }
+            _ => false,
+        };
         Ok(serde_json::json!({
-            "self_evolution_performed": improvement_count > 0,
+            "self_evolution_performed": improvement_count > 0 && !rolled_back,
             "improvement_count": improvement_count,
             "improvements": improvements,
-            "message": if improvement_count > 0 {
+            "verification": verification,
+            "rolled_back": rolled_back,
+            "message": if rolled_back {
+                "回归语料通过率低于阈值，已回滚自我进化".to_string()
+            } else if improvement_count > 0 {
                 format!("发现并记录了 {} 个可改进的规则", improvement_count)
             } else {
                 "当前实现已是最优".to_string()
@@ -266,34 +498,44 @@ impl EvolutionEngine {
 
     /// 记录使用模式 / Record usage pattern
     pub fn record_usage(&mut self, pattern: &str) {
-        self.learner.record_usage(pattern);
+        self.learner.lock().record_usage(pattern);
     }
 
     /// 记录错误 / Record error
     pub fn record_error(&mut self, error_type: &str, message: &str, context: &str) {
-        self.learner.record_error(error_type, message, context);
+        self.learner
+            .lock()
+            .record_error(error_type, message, context);
     }
 
     /// 记录成功 / Record success
     pub fn record_success(&mut self, description: &str, code: &str) {
-        self.learner.record_success(description, code);
+        self.learner.lock().record_success(description, code);
     }
 
     /// 从学习中获取洞察 / Get insights from learning
     pub fn get_learning_insights(&self) -> Vec<crate::evolution::learning::LearningInsight> {
-        self.learner.get_insights()
+        self.learner.lock().get_insights()
     }
 
     /// 获取使用统计 / Get usage statistics
     pub fn get_usage_statistics(&self) -> crate::evolution::learning::UsageStatistics {
-        self.learner.analyze_usage()
+        self.learner.lock().analyze_usage()
+    }
+
+    /// 学习器句柄：`Interpreter::with_learning(engine.learning_handle())` 创建的解释器自动报告使用情况
+    /// Learner handle; interpreters created with `Interpreter::with_learning(engine.learning_handle())` report usage automatically
+    pub fn learning_handle(&self) -> crate::evolution::learning::LearningHandle {
+        self.learner.clone()
     }
 
     /// 从使用模式中学习并改进 / Learn from usage patterns and improve
     pub fn learn_from_usage(&mut self) -> Result<serde_json::Value, EvolutionError> {
         // 先获取洞察和统计，避免借用冲突 / Get insights and statistics first to avoid borrow conflicts
-        let insights = self.learner.get_insights();
-        let stats = self.learner.analyze_usage();
+        let (insights, stats) = {
+            let learner = self.learner.lock();
+            (learner.get_insights(), learner.analyze_usage())
+        };
         let high_priority_count = insights
             .iter()
             .filter(|insight| insight.priority > 5)
@@ -345,7 +587,7 @@ impl EvolutionEngine {
                 success_metrics: None,
             };
 
-            self.tracker.record(event);
+            self.record_event(event);
             self.rebuild_knowledge();
         }
 
@@ -373,6 +615,7 @@ impl EvolutionEngine {
                 "total_successes": stats.total_successes,
                 "error_rate": stats.error_rate,
                 "success_rate": stats.success_rate,
+                "failing_functions": stats.failing_functions,
             }),
             "insights": insights_json,
         }))
@@ -437,26 +680,51 @@ impl EvolutionEngine {
         reflection
     }
 
-    /// 查找相似规则 / Find similar rules
+    /// 查找相似规则（按规则嵌入向量的余弦相似度）/ Find similar rules (by cosine similarity of rule embeddings)
     pub fn find_similar_rules(&self, rule_name: &str) -> Vec<(String, f64)> {
         let entity_id = format!("rule:{}", rule_name);
         self.knowledge_graph.find_similar_entities(&entity_id, 0.3)
     }
 
-    /// 回滚到指定事件 / Rollback to specified event
+    /// 回滚到指定事件，运行中的解析器和解释器恢复到事件之前的状态
+    /// Rollback to specified event, restoring the live parser and interpreter to their state before it
     pub fn rollback_to_event(&mut self, event_id: uuid::Uuid) -> Result<(), EvolutionError> {
+        // 被移除的事件：该事件及其之后的事件 / Removed events: this event and every later one
+        let removed: Vec<EvolutionEvent> = self
+            .tracker
+            .get_history()
+            .iter()
+            .skip_while(|event| event.id != event_id)
+            .cloned()
+            .collect();
+
         // 回滚到指定事件之前的状态 / Rollback to state before specified event
         let rollback_state = self
             .tracker
             .rollback_to(event_id)
             .map_err(|e| EvolutionError::IntegrationFailed(e))?;
 
+        // 恢复最早被移除的检查点；从磁盘加载的事件没有检查点，只能按快照重建规则集
+        // Restore the earliest removed checkpoint; events loaded from disk have none, so only the rule set is rebuilt from the snapshot
+        match removed
+            .iter()
+            .map(|event| event.id)
+            .find(|id| self.runtime.has_checkpoint(*id))
+        {
+            Some(checkpoint) => self.runtime.rollback_to(checkpoint)?,
+            None => self
+                .runtime
+                .reset_rules(rollback_state.grammar_rules.clone()),
+        }
+
         // 恢复语法规则 / Restore grammar rules
         self.syntax_mutations = rollback_state.grammar_rules.clone();
 
         // 重建知识图谱 / Rebuild knowledge graph
         self.rebuild_knowledge();
 
+        self.event_bus
+            .publish(&EvolutionNotification::rollback(event_id, &removed));
         Ok(())
     }
 
@@ -496,6 +764,28 @@ impl EvolutionEngine {
         }
     }
 
+    /// 进化谱系报告 / Evolution genealogy report
+    pub fn genealogy_report(&self) -> GenealogyReport<'_> {
+        GenealogyReport::new(&self.tracker)
+    }
+
+    /// 导出进化谱系报告（HTML或DOT）：事件元数据、引入规则的差异和受影响的程序
+    /// Export the evolution genealogy report (HTML or DOT): event metadata, diffs of the rules introduced and the affected programs
+    pub fn export_report(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        format: GenealogyFormat,
+    ) -> Result<(), EvolutionError> {
+        let path = path.as_ref();
+        std::fs::write(path, self.genealogy_report().render(format)).map_err(|e| {
+            EvolutionError::IntegrationFailed(format!(
+                "Failed to write report {}: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
     /// 获取事件的祖先链 / Get ancestor chain of an event
     pub fn get_event_ancestors(&self, event_id: uuid::Uuid) -> Vec<uuid::Uuid> {
         self.tracker.get_ancestors(event_id)
@@ -612,13 +902,19 @@ impl EvolutionEngine {
                 success_metrics: None,
             };
 
-            self.tracker.record(event.clone());
-            self.knowledge_graph.build_from_history(&[event]);
-
             // 集成生成的规则 / Integrate generated rules
-            for rule in &generated_rules {
-                self.syntax_mutations.push(rule.clone());
-            }
+            self.runtime.apply_with_id(
+                event.id,
+                generated_rules
+                    .iter()
+                    .cloned()
+                    .map(|rule| RuntimeChange::AddRule(Box::new(rule)))
+                    .collect(),
+            )?;
+            self.syntax_mutations = self.runtime.rules().to_vec();
+
+            self.record_event(event.clone());
+            self.knowledge_graph.build_from_history(&[event]);
         }
 
         Ok(generated_rules)
@@ -791,58 +1087,35 @@ impl EvolutionEngine {
         Ok(rules)
     }
 
-    /// 从诗歌理解生成可执行代码 / Generate executable code from poetry understanding
+    /// 从诗歌理解生成可执行代码：包含ASCII图案、旋律和动画三种主题程序
+    /// Generate executable code from poetry understanding: ASCII art, melody and animation programs on the poem's theme
     pub fn generate_code_from_poetry(&self, poem: &str) -> Result<String, EvolutionError> {
-        // 解析诗歌 / Parse poetry
         let analysis = self.poetry_parser.parse(poem).map_err(|e| {
             EvolutionError::IntegrationFailed(format!("Failed to parse poetry: {:?}", e))
         })?;
+        Ok(self.poetry_codegen.generate_all(&analysis))
+    }
 
-        // 生成代码片段 / Generate code snippets
-        let mut code_parts = Vec::new();
-
-        // 基于情感生成代码 / Generate code based on emotion
-        let emotion_code = match analysis.emotion_analysis.primary_emotion {
-            crate::poetry::emotion::Emotion::Nostalgia => {
-                format!("(def nostalgia () \"思念故乡的情感\")")
-            }
-            crate::poetry::emotion::Emotion::Tranquility => {
-                format!("(def tranquility () \"夜晚的宁静，内心的平和\")")
-            }
-            crate::poetry::emotion::Emotion::Loneliness => {
-                format!("(def loneliness () \"孤独感，缺少陪伴\")")
-            }
-            _ => String::new(),
-        };
-        if !emotion_code.is_empty() {
-            code_parts.push(emotion_code);
-        }
-
-        // 基于主题生成代码 / Generate code based on themes
-        for theme in &analysis.themes {
-            if theme.confidence > 0.7 {
-                code_parts.push(format!(
-                    "(def {} () \"{}\")",
-                    theme.name.to_lowercase(),
-                    theme.description
-                ));
-            }
-        }
+    /// 从诗歌生成一种形式的程序 / Generate a program of one form from poetry
+    pub fn generate_program_from_poetry(
+        &self,
+        poem: &str,
+        form: ProgramForm,
+    ) -> Result<String, EvolutionError> {
+        let analysis = self.poetry_parser.parse(poem).map_err(|e| {
+            EvolutionError::IntegrationFailed(format!("Failed to parse poetry: {:?}", e))
+        })?;
+        Ok(self.poetry_codegen.generate(&analysis, form))
+    }
 
-        // 基于意象生成数据结构 / Generate data structures based on imagery
-        for img in &analysis.imagery {
-            if img.frequency > 0 {
-                code_parts.push(format!(
-                    "(let {} (dict \"element\" \"{}\" \"meaning\" \"{}\" \"frequency\" {}))",
-                    img.element.to_lowercase(),
-                    img.element,
-                    img.meaning,
-                    img.frequency
-                ));
-            }
-        }
+    /// 注册情感到程序结构的映射，替换该情感的内置映射 / Register an emotion-to-construct mapping, replacing the built-in one
+    pub fn register_emotion_mapping(&mut self, mapping: std::sync::Arc<dyn EmotionMapping>) {
+        self.poetry_codegen.register(mapping);
+    }
 
-        Ok(code_parts.join("\n"))
+    /// 诗歌情感分析器，可加载词典或注册自定义情感 / Poetry emotion analyzer, for loading lexicons or registering custom emotions
+    pub fn emotion_analyzer_mut(&mut self) -> &mut EmotionAnalyzer {
+        self.poetry_parser.emotion_analyzer_mut()
     }
 
     /// 获取进化历史 / Get evolution history
@@ -855,6 +1128,102 @@ impl EvolutionEngine {
         &self.syntax_mutations
     }
 
+    /// 运行时配置器（运行中的解析器和解释器）/ Runtime configurator (live parser and interpreter)
+    pub fn runtime(&self) -> &RuntimeConfigurator {
+        &self.runtime
+    }
+
+    /// 运行时配置器（可变），用于在运行中的解释器上执行代码；直接应用的变更不会被追踪
+    /// Runtime configurator (mutable) for running code on the live interpreter; changes applied directly are not tracked
+    pub fn runtime_mut(&mut self) -> &mut RuntimeConfigurator {
+        &mut self.runtime
+    }
+
+    /// 以事务方式应用语法/语义变更并记录为进化事件，返回事件ID（`rollback_to_event` 可撤销）
+    /// Apply syntax/semantic changes as one transaction and record them as an evolution event, returning the event
+    /// ID (undone by `rollback_to_event`)
+    pub fn apply_evolution(
+        &mut self,
+        changes: Vec<RuntimeChange>,
+        description: impl Into<String>,
+    ) -> Result<uuid::Uuid, EvolutionError> {
+        let event_type = if changes.iter().all(RuntimeChange::is_syntax) {
+            EvolutionType::SyntaxEvolution
+        } else {
+            EvolutionType::SemanticEvolution
+        };
+        let described: Vec<String> = changes.iter().map(RuntimeChange::describe).collect();
+        let before = self.syntax_mutations.clone();
+        let id = self.runtime.apply(changes)?;
+        let after = self.runtime.rules().to_vec();
+
+        let mut delta = crate::evolution::tracker::EvolutionDelta {
+            added_rules: Vec::new(),
+            modified_rules: Vec::new(),
+            removed_rules: Vec::new(),
+            description: description.into(),
+        };
+        for rule in &after {
+            match before.iter().find(|old| old.name == rule.name) {
+                None => delta.added_rules.push(rule.clone()),
+                Some(old) if old.id != rule.id => {
+                    delta.modified_rules.push((old.clone(), rule.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        delta.removed_rules = before
+            .iter()
+            .filter(|old| !after.iter().any(|rule| rule.name == old.name))
+            .cloned()
+            .collect();
+
+        let event = EvolutionEvent {
+            id,
+            timestamp: chrono::Utc::now(),
+            event_type,
+            before_state: crate::evolution::tracker::StateSnapshot {
+                grammar_rules: before,
+                version: "0.1.0".to_string(),
+                metadata: serde_json::json!({}),
+            },
+            after_state: crate::evolution::tracker::StateSnapshot {
+                grammar_rules: after.clone(),
+                version: "0.1.0".to_string(),
+                metadata: serde_json::json!({ "changes": described }),
+            },
+            delta,
+            trigger: crate::evolution::tracker::TriggerContext {
+                source: TriggerSource::UserRequest,
+                conditions: Vec::new(),
+                environment: serde_json::json!({}),
+            },
+            author: None,
+            success_metrics: None,
+        };
+        self.record_event(event.clone());
+        self.syntax_mutations = after;
+        self.knowledge_graph.build_from_history(&[event]);
+        Ok(id)
+    }
+
+    /// 共享语法规则注册表，引擎接受的语法规则都发布到这里 / Shared grammar rule registry every syntax rule the engine accepts is published to
+    pub fn rule_registry(&self) -> &RuleRegistry {
+        self.runtime.registry()
+    }
+
+    /// 当前语法版本，每次语法规则变更（包括回滚）递增 / Current grammar version, bumped by every rule change (rollbacks included)
+    pub fn grammar_version(&self) -> u64 {
+        self.runtime.registry().version()
+    }
+
+    /// 创建挂接规则注册表的解析器：应用全部已进化语法规则，之后接受的规则也会热加载
+    /// Create a parser attached to the rule registry: every evolved syntax rule applies, and rules accepted later
+    /// are hot-reloaded
+    pub fn create_parser(&self) -> AdaptiveParser {
+        AdaptiveParser::with_registry(true, self.runtime.registry().clone())
+    }
+
     /// 加载自举规则 / Load bootstrap rules
     fn load_bootstrap_rules() -> Vec<GrammarRule> {
         let code = "(import \"evolution\")\n(evolution.bootstrap_rules)";
@@ -894,9 +1263,9 @@ impl EvolutionEngine {
         match value {
             Value::List(tools) => {
                 let mut result = Vec::new();
-                for tool in tools {
+                for tool in tools.iter() {
                     if let Value::String(s) = tool {
-                        result.push(s);
+                        result.push(s.to_string());
                     }
                 }
                 Ok(result)
@@ -917,9 +1286,108 @@ impl EvolutionEngine {
         }
     }
 
+    /// 在沙箱中并行评估候选程序（使用已进化的语法规则解析），结果按输入顺序返回
+    /// Evaluate candidate programs in parallel in the sandbox (parsed with the evolved syntax rules), returning results in input order
+    pub fn evaluate_candidates(&self, programs: &[String]) -> Vec<SandboxResult> {
+        SandboxRunner::with_config(SandboxConfig {
+            seed: Some(self.derived_seed("evaluate")),
+            ..SandboxConfig::default()
+        })
+        .run_code(&self.create_parser(), programs)
+    }
+
+    /// 创建代码生成器，其种子由引擎种子派生 / Create a code generator seeded from the engine seed
+    pub fn create_code_generator(&self) -> IntelligentCodeGenerator {
+        IntelligentCodeGenerator::with_seed(self.derived_seed("generate"))
+    }
+
+    /// 按适应度进化程序：用已进化的语法解析初始程序，搜索结束后把最优程序的谱系记录到追踪器
+    /// Evolve a program by fitness: parse the initial program with the evolved syntax rules and, once the
+    /// search ends, record the best program's lineage in the tracker
+    pub fn evolve_program(
+        &mut self,
+        mut spec: FitnessSpec,
+    ) -> Result<EvolvedProgram, EvolutionError> {
+        // 未指定种子时由引擎种子派生 / Derive the seed from the engine seed when none is given
+        spec.seed = spec
+            .seed
+            .or_else(|| Some(self.derived_seed("evolve_program")));
+        let entry = spec.entry.clone();
+        let evolved = ProgramEvolver::new(spec).run(&self.create_parser())?;
+
+        let fitness_of: HashMap<uuid::Uuid, f64> = evolved
+            .lineage
+            .iter()
+            .map(|ancestor| (ancestor.id, ancestor.fitness))
+            .collect();
+        let mut events = Vec::with_capacity(evolved.lineage.len());
+        for ancestor in &evolved.lineage {
+            // 相对最优父个体的适应度提升 / Fitness gain over the fittest parent
+            let improvement = ancestor
+                .parents
+                .iter()
+                .filter_map(|parent| fitness_of.get(parent))
+                .copied()
+                .reduce(f64::max)
+                .map_or(0.0, |parent| ancestor.fitness - parent);
+            let snapshot = |metadata| crate::evolution::tracker::StateSnapshot {
+                grammar_rules: self.syntax_mutations.clone(),
+                version: "0.1.0".to_string(),
+                metadata,
+            };
+            let mut event = EvolutionEvent {
+                id: ancestor.id,
+                timestamp: chrono::Utc::now(),
+                event_type: EvolutionType::ProgramEvolution,
+                before_state: snapshot(serde_json::json!({})),
+                after_state: snapshot(serde_json::json!({
+                    "entry": entry,
+                    "program": ancestor.source,
+                    "generation": ancestor.generation,
+                    "operation": ancestor.operation,
+                    "parents": ancestor.parents,
+                    "seed": evolved.seed,
+                })),
+                delta: crate::evolution::tracker::EvolutionDelta {
+                    added_rules: Vec::new(),
+                    modified_rules: Vec::new(),
+                    removed_rules: Vec::new(),
+                    description: format!(
+                        "Evolved `{}` by {} (fitness {:.3})",
+                        entry, ancestor.operation, ancestor.fitness
+                    ),
+                },
+                trigger: crate::evolution::tracker::TriggerContext {
+                    source: TriggerSource::AutomaticOptimization,
+                    conditions: vec![format!("generation {}", ancestor.generation)],
+                    environment: serde_json::json!({}),
+                },
+                author: None,
+                success_metrics: Some(EvolutionMetrics {
+                    success_rate: ancestor.score,
+                    user_satisfaction_delta: 0.0,
+                    performance_improvement: improvement,
+                    compatibility_impact: 0.0,
+                }),
+            };
+            self.stamp_seed(&mut event);
+            self.tracker
+                .record_with_parents(event.clone(), ancestor.parents.clone());
+            events.push(event);
+        }
+
+        // 更新知识图谱 / Update knowledge graph
+        self.knowledge_graph.build_from_history(&events);
+
+        Ok(evolved)
+    }
+
     /// 执行Evo-lang代码 / Execute Evo-lang code
     fn execute_evo_code(&self, code: &str) -> Result<Value, EvolutionError> {
         let mut interpreter = Interpreter::new();
+        // 自动执行的代码必须受预算限制，避免挂起进化循环
+        // Automatically executed code must be budgeted so it cannot hang the evolution loop
+        interpreter.set_budget(ExecutionBudget::for_generated_code());
         let parser = AdaptiveParser::new(true);
         let ast = parser.parse(code).map_err(|e| {
             EvolutionError::IntegrationFailed(format!("Failed to parse evo code: {:?}", e))
@@ -992,7 +1460,7 @@ impl EvolutionEngine {
         Self::rule_from_dict(dict)
     }
 
-    fn rule_from_dict(dict: &HashMap<String, Value>) -> Result<GrammarRule, EvolutionError> {
+    fn rule_from_dict(dict: &DictMap) -> Result<GrammarRule, EvolutionError> {
         let name = Self::dict_string(dict, "name").unwrap_or_else(|| "unnamed".to_string());
         let production =
             Self::dict_string(dict, "production").unwrap_or_else(|| "Unknown".to_string());
@@ -1029,26 +1497,26 @@ impl EvolutionEngine {
         Ok(GrammarRule::new(name, pattern, production, meta))
     }
 
-    fn dict_string(dict: &HashMap<String, Value>, key: &str) -> Option<String> {
+    fn dict_string(dict: &DictMap, key: &str) -> Option<String> {
         match dict.get(key) {
-            Some(Value::String(value)) => Some(value.clone()),
+            Some(Value::String(value)) => Some(value.to_string()),
             _ => None,
         }
     }
 
-    fn dict_bool(dict: &HashMap<String, Value>, key: &str) -> Option<bool> {
+    fn dict_bool(dict: &DictMap, key: &str) -> Option<bool> {
         match dict.get(key) {
             Some(Value::Bool(value)) => Some(*value),
             _ => None,
         }
     }
 
-    fn dict_string_list(dict: &HashMap<String, Value>, key: &str) -> Vec<String> {
+    fn dict_string_list(dict: &DictMap, key: &str) -> Vec<String> {
         match dict.get(key) {
             Some(Value::List(items)) => items
                 .iter()
                 .filter_map(|item| match item {
-                    Value::String(value) => Some(value.clone()),
+                    Value::String(value) => Some(value.to_string()),
                     _ => None,
                 })
                 .collect(),
@@ -1074,4 +1542,8 @@ pub enum EvolutionError {
     TestFailed(String),
     /// 集成失败 / Integration failed
     IntegrationFailed(String),
+    /// 无效的进化参数 / Invalid evolution specification
+    InvalidSpec(String),
+    /// 语法规则与已有规则或核心语法冲突 / Grammar rule conflicts with existing rules or core syntax
+    RuleConflict(Box<ConflictReport>),
 }
@@ -163,6 +163,10 @@ impl ErrorRecoverer {
                         FixMethod::FixSyntax(fix) => {
                             suggestions.push(fix.clone());
                             recovery_method = Some("语法修复".to_string());
+                            if let Some(fixed) = self.balance_parentheses(code_context) {
+                                fixed_code = Some(fixed);
+                                recovery_method = Some("补全缺失的右括号".to_string());
+                            }
                         }
                     }
                 }
@@ -183,6 +187,20 @@ impl ErrorRecoverer {
         }
     }
 
+    /// 与报错信息真正匹配的第一条规则给出的建议（不参考代码上下文）
+    /// Suggestion from the first rule that actually matches the error message (ignores code context)
+    pub fn suggestion_for(&self, error: &InterpreterError) -> Option<String> {
+        let rules = self.fix_rules.get(&self.extract_error_type(error))?;
+        let rule = rules.iter().find(|rule| self.matches_error(error, rule))?;
+        Some(match &rule.fix_method {
+            FixMethod::SuggestFix(suggestion) => suggestion.clone(),
+            FixMethod::AddDefinition(_) => rule.description.clone(),
+            FixMethod::FixType(from, to) => format!("将类型从 {} 改为 {}", from, to),
+            FixMethod::AddNullCheck => "添加除数检查，确保除数不为0".to_string(),
+            FixMethod::FixSyntax(fix) => fix.clone(),
+        })
+    }
+
     /// 提取错误类型 / Extract error type
     fn extract_error_type(&self, error: &InterpreterError) -> String {
         match error {
@@ -200,12 +218,26 @@ impl ErrorRecoverer {
             InterpreterError::UndefinedVariable { .. } => {
                 rule.error_pattern.contains("未定义") || rule.error_pattern.contains("变量")
             }
-            InterpreterError::TypeError { .. } => {
-                rule.error_pattern.contains("类型") || rule.error_pattern.contains("Type")
-            }
+            InterpreterError::TypeError { message, .. } => match &rule.fix_method {
+                // 只有报错信息同时提到两种类型时才建议类型替换
+                // Only suggest a type swap when the message names both types
+                FixMethod::FixType(from, to) => {
+                    let mentions = |name: &str| {
+                        message
+                            .split(|c: char| !c.is_alphanumeric())
+                            .any(|word| word == name)
+                    };
+                    mentions(from) && mentions(to)
+                }
+                _ => message.contains(&rule.error_pattern),
+            },
             InterpreterError::DivisionByZero { .. } => {
                 rule.error_pattern.contains("除零") || rule.error_pattern.contains("Division")
             }
+            InterpreterError::RuntimeError { message, .. } => {
+                (message.contains("语法") || message.contains("Syntax"))
+                    && rule.error_pattern.contains("语法")
+            }
             _ => false,
         }
     }
@@ -220,6 +252,48 @@ impl ErrorRecoverer {
         )
     }
 
+    /// 在末尾补全缺失的右括号（跳过字符串和注释），括号已平衡或多余时返回 None
+    /// Append missing closing parentheses (skipping strings and comments); None when already balanced or over-closed
+    fn balance_parentheses(&self, code: &str) -> Option<String> {
+        let mut depth = 0usize;
+        let mut chars = code.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.checked_sub(1)?,
+                '"' => {
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '"' => break,
+                            _ => {}
+                        }
+                    }
+                }
+                ';' => {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if depth == 0 {
+            return None;
+        }
+        let trimmed = code.trim_end();
+        Some(format!(
+            "{}{}{}",
+            trimmed,
+            ")".repeat(depth),
+            &code[trimmed.len()..]
+        ))
+    }
+
     /// 获取常见错误的修复建议 / Get fix suggestions for common errors
     pub fn get_common_fixes(&self) -> Vec<FixRule> {
         self.fix_rules
@@ -0,0 +1,317 @@
+// 运行时错误报告 / Runtime error report
+// 把未捕获的解释器错误渲染为可读的报告：出错的源码行和插入符、Evo-lang 调用栈（函数名和位置）
+// 以及错误恢复器的建议，终端中可带 ANSI 颜色
+// Renders an uncaught interpreter error as a readable report: the offending source line with a caret, the
+// Evo-lang call stack (function names and locations) and a suggestion from the error recoverer, optionally
+// with ANSI colours in a terminal
+
+use crate::evolution::error_recovery::ErrorRecoverer;
+use crate::parser::incremental::{ParsedDocument, SyntaxKind, SyntaxNode};
+use crate::runtime::interpreter::{ErrorTrace, InterpreterError};
+
+/// 错误报告 / Error report
+pub struct ErrorReport<'a> {
+    /// 错误 / Error
+    error: &'a InterpreterError,
+    /// 解释器记录的调用栈 / Call stack recorded by the interpreter
+    trace: &'a ErrorTrace,
+    /// 出错的文档（没有时只输出错误和调用栈）/ Document that failed (without it only the error and call stack are shown)
+    document: Option<&'a ParsedDocument>,
+    /// 显示用的文件名 / File name to display
+    file: Option<&'a str>,
+    /// 是否使用 ANSI 颜色 / Whether to use ANSI colours
+    color: bool,
+}
+
+/// 调用栈中的一帧 / One frame of the call stack
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportFrame {
+    /// 函数名（顶层为 `<top-level>`）/ Function name (`<top-level>` for the top level)
+    pub name: String,
+    /// 该帧正在执行的位置（行、列，从1开始）/ Position the frame is executing (line and column, 1-based)
+    pub position: Option<(usize, usize)>,
+}
+
+/// 源码中被标出的范围 / Marked range in the source
+struct Marker {
+    /// 起始字节偏移 / Start byte offset
+    start: usize,
+    /// 结束字节偏移 / End byte offset
+    end: usize,
+}
+
+/// 调用栈过深时两端各显示的栈帧数 / Frames shown at each end of an overly deep call stack
+const SHOWN_FRAMES: usize = 10;
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+impl<'a> ErrorReport<'a> {
+    /// 创建错误报告 / Create error report
+    pub fn new(error: &'a InterpreterError, trace: &'a ErrorTrace) -> Self {
+        Self {
+            error,
+            trace,
+            document: None,
+            file: None,
+            color: false,
+        }
+    }
+
+    /// 附带出错的源码 / Attach the failing source
+    pub fn with_document(mut self, document: &'a ParsedDocument) -> Self {
+        self.document = Some(document);
+        self
+    }
+
+    /// 附带显示用的文件名 / Attach the file name to display
+    pub fn with_file(mut self, file: &'a str) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    /// 设置是否使用 ANSI 颜色 / Set whether to use ANSI colours
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// 调用栈，最内层在前，最后一帧是出错的顶层形式
+    /// Call stack, innermost first; the last frame is the failing top-level form
+    pub fn frames(&self) -> Vec<ReportFrame> {
+        let mut frames: Vec<ReportFrame> = self
+            .trace
+            .frames
+            .iter()
+            .enumerate()
+            .map(|(index, name)| ReportFrame {
+                name: name.clone(),
+                position: self
+                    .frame_marker(index)
+                    .map(|marker| self.position(marker.start)),
+            })
+            .collect();
+        if self.trace.form.is_some() {
+            frames.push(ReportFrame {
+                name: "<top-level>".to_string(),
+                position: self
+                    .frame_marker(self.trace.frames.len())
+                    .map(|marker| self.position(marker.start)),
+            });
+        }
+        frames
+    }
+
+    /// 错误恢复器给出的建议（没有规则匹配报错信息时为 None）
+    /// Suggestion from the error recoverer (None when no rule matches the error message)
+    pub fn suggestion(&self) -> Option<String> {
+        ErrorRecoverer::new().suggestion_for(self.error)
+    }
+
+    /// 渲染报告 / Render the report
+    pub fn render(&self) -> String {
+        let mut out = format!(
+            "{}: {}\n",
+            self.paint(
+                RED,
+                &format!("执行错误 / Execution error [{}]", self.error.kind())
+            ),
+            self.paint(BOLD, &self.error.to_string())
+        );
+
+        if let (Some(marker), Some((line, text))) = (self.frame_marker(0), self.source_line()) {
+            let (_, column) = self.position(marker.start);
+            let gutter = " ".repeat(line.to_string().len());
+            out.push_str(&format!(
+                "{}{} {}:{}:{}\n",
+                gutter,
+                self.paint(BLUE, "-->"),
+                self.file.unwrap_or("<input>"),
+                line,
+                column
+            ));
+            out.push_str(&format!("{} {}\n", gutter, self.paint(BLUE, "|")));
+            out.push_str(&format!(
+                "{} {} {}\n",
+                self.paint(BLUE, &line.to_string()),
+                self.paint(BLUE, "|"),
+                text
+            ));
+            // 插入符覆盖标记范围在该行内的部分 / The caret covers the part of the marked range on this line
+            let width = self.line_part(&marker).chars().count().max(1);
+            out.push_str(&format!(
+                "{} {} {}{}\n",
+                gutter,
+                self.paint(BLUE, "|"),
+                " ".repeat(column - 1),
+                self.paint(RED, &"^".repeat(width))
+            ));
+        }
+
+        let frames = self.frames();
+        if !frames.is_empty() {
+            out.push_str("调用栈 / Call stack (innermost first):\n");
+            for (index, frame) in frames.iter().enumerate() {
+                // 深递归只显示两端的栈帧 / Deep recursion shows only the frames at either end
+                if frames.len() > 2 * SHOWN_FRAMES && index >= SHOWN_FRAMES {
+                    if index == SHOWN_FRAMES {
+                        let omitted = frames.len() - 2 * SHOWN_FRAMES;
+                        out.push_str(&format!(
+                            "  ... 省略 {} 帧 / {} frames omitted\n",
+                            omitted, omitted
+                        ));
+                    }
+                    if index < frames.len() - SHOWN_FRAMES {
+                        continue;
+                    }
+                }
+                let location = match frame.position {
+                    Some((line, column)) => {
+                        format!(" at {}:{}:{}", self.file.unwrap_or("<input>"), line, column)
+                    }
+                    None => String::new(),
+                };
+                out.push_str(&format!(
+                    "  {}: {}{}\n",
+                    index,
+                    self.paint(BOLD, &frame.name),
+                    location
+                ));
+            }
+        }
+
+        if let Some(suggestion) = self.suggestion() {
+            out.push_str(&format!(
+                "{}: {}\n",
+                self.paint(CYAN, "建议 / Suggestion"),
+                suggestion
+            ));
+        }
+        out
+    }
+
+    /// 第 `index` 帧正在执行的位置：该帧函数体（或顶层形式）中对内一层调用的语法节点，
+    /// 最内层帧使用解释器记录的出错调用；找不到时退回函数定义或顶层形式本身
+    /// Position frame `index` is executing: the node calling the next inner frame inside the frame's body (or
+    /// top-level form), with the failing call the interpreter recorded for the innermost frame; falls back to
+    /// the function definition or top-level form itself
+    fn frame_marker(&self, index: usize) -> Option<Marker> {
+        let document = self.document?;
+        let scope = match self.trace.frames.get(index) {
+            Some(name) => document
+                .nodes
+                .iter()
+                .find_map(|node| definition_of(node, name, &document.source))?,
+            None => self.form_node(document)?,
+        };
+        let target = match index {
+            0 => self.trace.site.as_deref(),
+            _ => self.trace.frames.get(index - 1).map(String::as_str),
+        };
+        let body = match self.trace.frames.get(index) {
+            Some(_) => scope.children.get(3..).unwrap_or(&[]),
+            None => std::slice::from_ref(scope),
+        };
+        let node = target
+            .and_then(|target| {
+                body.iter()
+                    .find_map(|node| find_site(node, target, &document.source))
+            })
+            .unwrap_or(scope);
+        Some(Marker {
+            start: node.span.start,
+            end: node.span.end,
+        })
+    }
+
+    /// 出错的顶层形式的语法节点 / Syntax node of the failing top-level form
+    fn form_node<'d>(&self, document: &'d ParsedDocument) -> Option<&'d SyntaxNode> {
+        let form = self.trace.form?;
+        // 宏定义不产生顶层元素时下标对不上 / Indices do not line up when macro definitions produce no element
+        if document.elements.len() != document.nodes.len() {
+            return None;
+        }
+        document.nodes.get(form)
+    }
+
+    /// 出错位置所在的行号和行文本 / Line number and text of the line containing the error
+    fn source_line(&self) -> Option<(usize, &'a str)> {
+        let document = self.document?;
+        let marker = self.frame_marker(0)?;
+        let (line, _) = self.position(marker.start);
+        Some((line, document.source.lines().nth(line - 1).unwrap_or("")))
+    }
+
+    /// 标记范围落在起始行内的文本 / Text of the marked range that lies on its first line
+    fn line_part(&self, marker: &Marker) -> &'a str {
+        let source = self
+            .document
+            .map_or("", |document| document.source.as_str());
+        let text = source.get(marker.start..marker.end).unwrap_or("");
+        text.lines().next().unwrap_or("")
+    }
+
+    /// 字节偏移对应的行号和列号（从1开始，列按字符计）
+    /// Line and column (1-based, columns counted in characters) of a byte offset
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let source = self
+            .document
+            .map_or("", |document| document.source.as_str());
+        let before = source.get(..offset).unwrap_or(source);
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        (line, before[line_start..].chars().count() + 1)
+    }
+
+    /// 按需加上颜色 / Add colour when enabled
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// 标准错误输出是否应使用颜色：是终端且未设置 `NO_COLOR`
+/// Whether standard error output should be coloured: it is a terminal and `NO_COLOR` is not set
+pub fn stderr_supports_color() -> bool {
+    use std::io::IsTerminal;
+    std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        && std::io::stderr().is_terminal()
+}
+
+/// 定义名为 `name` 的函数的 `(def name ...)` 节点 / The `(def name ...)` node defining function `name`
+fn definition_of<'n>(node: &'n SyntaxNode, name: &str, source: &str) -> Option<&'n SyntaxNode> {
+    if node.kind != SyntaxKind::List {
+        return None;
+    }
+    let is_definition = matches!(
+        node.children.first().map(|head| head.text(source)),
+        Some("def" | "function" | "defn-")
+    ) && node.children.get(1).map(|child| child.text(source)) == Some(name);
+    if is_definition {
+        return Some(node);
+    }
+    node.children
+        .iter()
+        .find_map(|child| definition_of(child, name, source))
+}
+
+/// 第一个以 `site` 开头的列表，或文本为 `site` 的原子 / First list headed by `site`, or atom whose text is `site`
+fn find_site<'n>(node: &'n SyntaxNode, site: &str, source: &str) -> Option<&'n SyntaxNode> {
+    let matches = match node.kind {
+        SyntaxKind::List => node.children.first().map(|head| head.text(source)) == Some(site),
+        SyntaxKind::Atom => node.text(source) == site,
+        SyntaxKind::Quote => false,
+    };
+    if matches {
+        return Some(node);
+    }
+    node.children
+        .iter()
+        .find_map(|child| find_site(child, site, source))
+}
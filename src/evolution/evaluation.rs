@@ -0,0 +1,400 @@
+// 语法规则A/B评估 / A/B evaluation of grammar rules
+// 用带和不带候选规则的解析器分别解析并执行样例程序和自然语言语句，比较解析成功率、歧义和执行结果，据此决定是否接受规则
+// Parses and executes sample programs and natural-language utterances with parsers with and without a candidate
+// rule, comparing parse success rate, ambiguity and execution results to decide whether the rule is accepted
+
+use crate::evolution::sandbox::{SandboxConfig, SandboxRunner};
+use crate::grammar::core::GrammarElement;
+use crate::grammar::rule::GrammarRule;
+use crate::parser::macros::Macro;
+use crate::parser::AdaptiveParser;
+use crate::runtime::interpreter::Value;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 评估语料 / Evaluation corpus
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EvaluationCorpus {
+    /// 样例程序源码 / Sample program sources
+    pub programs: Vec<String>,
+    /// 自然语言语句，按代码解析，未以括号开头时整体作为一个形式
+    /// Natural-language utterances, parsed as code; wrapped as a single form when not starting with a parenthesis
+    pub utterances: Vec<String>,
+}
+
+impl EvaluationCorpus {
+    /// 创建空语料 / Create an empty corpus
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加样例程序 / Add a sample program
+    pub fn with_program(mut self, source: impl Into<String>) -> Self {
+        self.programs.push(source.into());
+        self
+    }
+
+    /// 添加自然语言语句 / Add a natural-language utterance
+    pub fn with_utterance(mut self, utterance: impl Into<String>) -> Self {
+        self.utterances.push(utterance.into());
+        self
+    }
+
+    /// 加载目录中的全部 `.evo` 文件作为样例程序 / Load every `.evo` file in a directory as a sample program
+    pub fn load_programs(mut self, dir: impl AsRef<Path>) -> std::io::Result<Self> {
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "evo"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            self.programs.push(std::fs::read_to_string(path)?);
+        }
+        Ok(self)
+    }
+
+    /// 样本总数 / Total number of samples
+    pub fn len(&self) -> usize {
+        self.programs.len() + self.utterances.len()
+    }
+
+    /// 是否为空 / Whether it is empty
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 按序号排列的样本源码（程序在前，语句在后）/ Sample sources by index (programs first, then utterances)
+    pub fn sources(&self) -> Vec<String> {
+        self.programs
+            .iter()
+            .cloned()
+            .chain(self.utterances.iter().map(|utterance| {
+                let trimmed = utterance.trim();
+                if trimmed.starts_with('(') {
+                    trimmed.to_string()
+                } else {
+                    format!("({})", trimmed)
+                }
+            }))
+            .collect()
+    }
+}
+
+/// 一组规则在语料上的统计 / Metrics of one rule set over the corpus
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorpusMetrics {
+    /// 样本数 / Number of samples
+    pub total: usize,
+    /// 解析成功数 / Samples parsed successfully
+    pub parsed: usize,
+    /// 执行成功数 / Samples executed successfully
+    pub executed: usize,
+}
+
+impl CorpusMetrics {
+    /// 解析成功率 / Parse success rate
+    pub fn parse_success_rate(&self) -> f64 {
+        rate(self.parsed, self.total)
+    }
+
+    /// 执行成功率 / Execution success rate
+    pub fn execution_success_rate(&self) -> f64 {
+        rate(self.executed, self.total)
+    }
+}
+
+/// 接受规则的条件 / Criteria for accepting a rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AcceptanceCriteria {
+    /// 解析成功率的最小提升 / Minimum gain in parse success rate
+    pub min_parse_gain: f64,
+    /// 执行成功率的最小提升 / Minimum gain in execution success rate
+    pub min_execution_gain: f64,
+    /// 最大歧义率：原本可用的样本中解析结果被改变的比例
+    /// Maximum ambiguity: share of previously working samples whose parse the rule changes
+    pub max_ambiguity: f64,
+    /// 允许的回归数：原本执行成功、加入规则后失败或结果不同的样本
+    /// Regressions allowed: samples that executed before but fail or give a different result with the rule
+    pub max_regressions: usize,
+    /// 是否允许规则关键字与已有规则冲突 / Whether the rule's keywords may clash with existing rules
+    pub allow_conflicts: bool,
+}
+
+impl Default for AcceptanceCriteria {
+    fn default() -> Self {
+        Self {
+            min_parse_gain: 0.0,
+            min_execution_gain: 0.0,
+            max_ambiguity: 0.0,
+            max_regressions: 0,
+            allow_conflicts: false,
+        }
+    }
+}
+
+/// 候选规则的评估结果 / Evaluation result of a candidate rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleEvaluation {
+    /// 规则名 / Rule name
+    pub rule: String,
+    /// 不带候选规则的统计 / Metrics without the candidate rule
+    pub baseline: CorpusMetrics,
+    /// 带候选规则的统计 / Metrics with the candidate rule
+    pub candidate: CorpusMetrics,
+    /// 歧义率 / Ambiguity rate
+    pub ambiguity: f64,
+    /// 原本可用、解析结果被改变的样本序号 / Indices of previously working samples whose parse changed
+    pub reinterpreted: Vec<usize>,
+    /// 回归的样本序号 / Indices of regressed samples
+    pub regressions: Vec<usize>,
+    /// 原本失败、加入规则后执行成功的样本序号 / Indices of samples that failed before and execute with the rule
+    pub improvements: Vec<usize>,
+    /// 与已有规则冲突的关键字 / Keywords clashing with existing rules
+    pub conflicts: Vec<String>,
+    /// 是否接受 / Whether the rule is accepted
+    pub accepted: bool,
+    /// 拒绝原因 / Reasons for rejection
+    pub reasons: Vec<String>,
+}
+
+impl RuleEvaluation {
+    /// 解析成功率的提升 / Gain in parse success rate
+    pub fn parse_gain(&self) -> f64 {
+        self.candidate.parse_success_rate() - self.baseline.parse_success_rate()
+    }
+
+    /// 执行成功率的提升 / Gain in execution success rate
+    pub fn execution_gain(&self) -> f64 {
+        self.candidate.execution_success_rate() - self.baseline.execution_success_rate()
+    }
+}
+
+/// 单个样本在一组规则下的结果 / Outcome of one sample under a rule set
+struct Outcome {
+    ast: Option<Vec<GrammarElement>>,
+    value: Option<Value>,
+    output: String,
+    executed: bool,
+}
+
+/// 规则评估器 / Rule evaluator
+#[derive(Debug, Clone, Default)]
+pub struct RuleEvaluator {
+    corpus: EvaluationCorpus,
+    criteria: AcceptanceCriteria,
+    sandbox: SandboxConfig,
+}
+
+impl RuleEvaluator {
+    /// 以语料创建评估器，使用默认条件和沙箱配置 / Create an evaluator over a corpus with default criteria and sandbox
+    pub fn new(corpus: EvaluationCorpus) -> Self {
+        Self {
+            corpus,
+            ..Self::default()
+        }
+    }
+
+    /// 设置接受条件 / Set acceptance criteria
+    pub fn with_criteria(mut self, criteria: AcceptanceCriteria) -> Self {
+        self.criteria = criteria;
+        self
+    }
+
+    /// 设置执行样本的沙箱配置 / Set the sandbox configuration samples run under
+    pub fn with_sandbox(mut self, sandbox: SandboxConfig) -> Self {
+        self.sandbox = sandbox;
+        self
+    }
+
+    /// 评估语料 / Evaluation corpus
+    pub fn corpus(&self) -> &EvaluationCorpus {
+        &self.corpus
+    }
+
+    /// 替换评估语料 / Replace the evaluation corpus
+    pub fn set_corpus(&mut self, corpus: EvaluationCorpus) {
+        self.corpus = corpus;
+    }
+
+    /// 接受条件 / Acceptance criteria
+    pub fn criteria(&self) -> &AcceptanceCriteria {
+        &self.criteria
+    }
+
+    /// 在已有规则之上评估候选规则；规则自带的示例也加入语料
+    /// Evaluate a candidate rule on top of the existing rules; the rule's own examples join the corpus
+    pub fn evaluate(&self, rules: &[GrammarRule], candidate: &GrammarRule) -> RuleEvaluation {
+        let mut sources = self.corpus.sources();
+        sources.extend(candidate.meta.examples.iter().cloned());
+
+        // 同名规则被候选规则替换 / A rule with the same name is replaced by the candidate
+        let existing: Vec<GrammarRule> = rules
+            .iter()
+            .filter(|rule| rule.name != candidate.name)
+            .cloned()
+            .collect();
+        let baseline_parser = parser_with(&existing);
+        let mut with_candidate = existing;
+        with_candidate.push(candidate.clone());
+        let candidate_parser = parser_with(&with_candidate);
+
+        let conflicts: Vec<String> = Macro::from_rule(candidate)
+            .into_iter()
+            .map(|definition| definition.name)
+            .filter(|name| baseline_parser.get_macro(name).is_some())
+            .collect();
+
+        let baseline = self.run(&baseline_parser, &sources);
+        let trial = self.run(&candidate_parser, &sources);
+
+        let mut evaluation = RuleEvaluation {
+            rule: candidate.name.clone(),
+            baseline: metrics(&baseline),
+            candidate: metrics(&trial),
+            ambiguity: 0.0,
+            reinterpreted: Vec::new(),
+            regressions: Vec::new(),
+            improvements: Vec::new(),
+            conflicts,
+            accepted: false,
+            reasons: Vec::new(),
+        };
+        for (index, (before, after)) in baseline.iter().zip(&trial).enumerate() {
+            if before.executed {
+                if before.ast != after.ast {
+                    evaluation.reinterpreted.push(index);
+                }
+                if !after.executed || before.value != after.value || before.output != after.output {
+                    evaluation.regressions.push(index);
+                }
+            } else if after.executed {
+                evaluation.improvements.push(index);
+            }
+        }
+        evaluation.ambiguity = rate(evaluation.reinterpreted.len(), evaluation.baseline.executed);
+        evaluation.reasons = self.rejections(&evaluation);
+        evaluation.accepted = evaluation.reasons.is_empty();
+        evaluation
+    }
+
+    /// 评估多个候选规则，返回被接受且执行成功率提升最大（其次解析成功率）的一个
+    /// Evaluate several candidates and return the accepted one with the largest execution gain (then parse gain)
+    pub fn select(
+        &self,
+        rules: &[GrammarRule],
+        candidates: &[GrammarRule],
+    ) -> (Option<usize>, Vec<RuleEvaluation>) {
+        let evaluations: Vec<RuleEvaluation> = candidates
+            .iter()
+            .map(|candidate| self.evaluate(rules, candidate))
+            .collect();
+        let best = evaluations
+            .iter()
+            .enumerate()
+            .filter(|(_, evaluation)| evaluation.accepted)
+            .max_by(|(i, a), (j, b)| {
+                a.execution_gain()
+                    .total_cmp(&b.execution_gain())
+                    .then(a.parse_gain().total_cmp(&b.parse_gain()))
+                    // 分数相同时偏好靠前的候选 / Prefer the earlier candidate on ties
+                    .then(j.cmp(i))
+            })
+            .map(|(index, _)| index);
+        (best, evaluations)
+    }
+
+    /// 解析全部样本并在沙箱中执行解析成功的样本 / Parse every sample and run the parsed ones in the sandbox
+    fn run(&self, parser: &AdaptiveParser, sources: &[String]) -> Vec<Outcome> {
+        let asts: Vec<Option<Vec<GrammarElement>>> = sources
+            .iter()
+            .map(|source| parser.parse(source).ok())
+            .collect();
+        let parsed: Vec<Vec<GrammarElement>> = asts.iter().flatten().cloned().collect();
+        let mut results = SandboxRunner::with_config(self.sandbox.clone())
+            .run(&parsed)
+            .into_iter();
+        asts.into_iter()
+            .map(|ast| {
+                let result = ast.as_ref().and_then(|_| results.next());
+                Outcome {
+                    executed: result.as_ref().is_some_and(|result| result.succeeded()),
+                    value: result.as_ref().and_then(|result| result.value.clone()),
+                    output: result.map(|result| result.output).unwrap_or_default(),
+                    ast,
+                }
+            })
+            .collect()
+    }
+
+    /// 不满足的条件 / Criteria that are not met
+    fn rejections(&self, evaluation: &RuleEvaluation) -> Vec<String> {
+        let criteria = &self.criteria;
+        let mut reasons = Vec::new();
+        if evaluation.parse_gain() < criteria.min_parse_gain {
+            reasons.push(format!(
+                "parse success rate changed by {:+.3}, below {:+.3}",
+                evaluation.parse_gain(),
+                criteria.min_parse_gain
+            ));
+        }
+        if evaluation.execution_gain() < criteria.min_execution_gain {
+            reasons.push(format!(
+                "execution success rate changed by {:+.3}, below {:+.3}",
+                evaluation.execution_gain(),
+                criteria.min_execution_gain
+            ));
+        }
+        if evaluation.ambiguity > criteria.max_ambiguity {
+            reasons.push(format!(
+                "ambiguity {:.3} exceeds {:.3} ({} samples reparsed)",
+                evaluation.ambiguity,
+                criteria.max_ambiguity,
+                evaluation.reinterpreted.len()
+            ));
+        }
+        if evaluation.regressions.len() > criteria.max_regressions {
+            reasons.push(format!(
+                "{} regressions exceed the {} allowed",
+                evaluation.regressions.len(),
+                criteria.max_regressions
+            ));
+        }
+        if !criteria.allow_conflicts && !evaluation.conflicts.is_empty() {
+            reasons.push(format!(
+                "keywords already defined: {}",
+                evaluation.conflicts.join(", ")
+            ));
+        }
+        reasons
+    }
+}
+
+/// 应用了给定规则的解析器 / Parser with the given rules applied
+fn parser_with(rules: &[GrammarRule]) -> AdaptiveParser {
+    let mut parser = AdaptiveParser::new(true);
+    for rule in rules {
+        parser.add_rule(rule.clone());
+    }
+    parser
+}
+
+/// 统计解析和执行成功数 / Count parse and execution successes
+fn metrics(outcomes: &[Outcome]) -> CorpusMetrics {
+    CorpusMetrics {
+        total: outcomes.len(),
+        parsed: outcomes
+            .iter()
+            .filter(|outcome| outcome.ast.is_some())
+            .count(),
+        executed: outcomes.iter().filter(|outcome| outcome.executed).count(),
+    }
+}
+
+/// 比例（分母为0时为0）/ Ratio (0 when the denominator is 0)
+fn rate(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
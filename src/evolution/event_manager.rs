@@ -3,11 +3,18 @@
 //! 负责进化事件的保存、加载、合并、验证等功能
 //! Responsible for saving, loading, merging, and validating evolution events
 
+use crate::evolution::tracker::EvolutionType;
 use crate::evolution::tracker::{EvolutionDelta, EvolutionEvent, StateSnapshot};
 use crate::grammar::rule::GrammarRule;
+use crate::runtime::http::post_json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use serde_json;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use uuid::Uuid;
 
 /// 进化事件管理器 / Evolution event manager
@@ -324,6 +331,374 @@ impl EvolutionEventManager {
     }
 }
 
+impl EvolutionEventManager {
+    /// 按时间顺序把持久化的事件日志重放给订阅者，返回通过过滤器的通知数
+    /// Replay the persisted event log to a subscriber in time order, returning the number of notifications that
+    /// passed the filter
+    pub fn replay(
+        &self,
+        filter: &NotificationFilter,
+        subscriber: &mut dyn EvolutionSubscriber,
+    ) -> Result<usize, EventManagerError> {
+        let mut delivered = 0;
+        for event in self.load_all_events()? {
+            let mut notification = EvolutionNotification::from_event(&event);
+            notification.replayed = true;
+            if filter.matches(&notification) {
+                subscriber.notify(&notification);
+                delivered += 1;
+            }
+        }
+        subscriber.flush();
+        Ok(delivered)
+    }
+}
+
+/// 通知种类 / Notification kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NotificationKind {
+    /// 记录了添加规则的事件 / An event adding rules was recorded
+    RuleAdded,
+    /// 回滚到某个事件之前 / Rolled back to before an event
+    RolledBack,
+    /// 自我进化完成（无论是否有改进）/ Self-evolution completed (with or without improvements)
+    SelfEvolveCompleted,
+    /// 记录了其他进化事件，如程序进化 / Another evolution event was recorded, e.g. program evolution
+    EventRecorded,
+}
+
+/// 发给订阅者的进化通知 / Evolution notification sent to subscribers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvolutionNotification {
+    /// 种类 / Kind
+    pub kind: NotificationKind,
+    /// 时间 / Time
+    pub timestamp: DateTime<Utc>,
+    /// 相关事件：记录的事件，或回滚的目标事件 / Related event: the recorded event, or the rollback target
+    pub event_id: Option<Uuid>,
+    /// 事件类型 / Event type
+    pub event_type: Option<EvolutionType>,
+    /// 添加的规则，回滚时为被撤销的规则 / Rules added, or for a rollback the rules undone
+    pub rules: Vec<String>,
+    /// 描述 / Description
+    pub description: String,
+    /// 是否从持久化日志重放 / Whether replayed from the persisted log
+    pub replayed: bool,
+}
+
+impl EvolutionNotification {
+    /// 创建不关联事件的通知 / Create a notification not tied to an event
+    pub fn new(kind: NotificationKind, description: impl Into<String>) -> Self {
+        Self {
+            kind,
+            timestamp: Utc::now(),
+            event_id: None,
+            event_type: None,
+            rules: Vec::new(),
+            description: description.into(),
+            replayed: false,
+        }
+    }
+
+    /// 由记录的事件生成通知 / Build the notification for a recorded event
+    pub fn from_event(event: &EvolutionEvent) -> Self {
+        let kind = if event.after_state.metadata["self_evolution"] == true {
+            NotificationKind::SelfEvolveCompleted
+        } else if !event.delta.added_rules.is_empty() {
+            NotificationKind::RuleAdded
+        } else {
+            NotificationKind::EventRecorded
+        };
+        Self {
+            kind,
+            timestamp: event.timestamp,
+            event_id: Some(event.id),
+            event_type: Some(event.event_type.clone()),
+            rules: event
+                .delta
+                .added_rules
+                .iter()
+                .map(|rule| rule.name.clone())
+                .collect(),
+            description: event.delta.description.clone(),
+            replayed: false,
+        }
+    }
+
+    /// 回滚通知：`removed` 为被撤销的事件 / Rollback notification: `removed` are the events undone
+    pub fn rollback(target: Uuid, removed: &[EvolutionEvent]) -> Self {
+        Self {
+            event_id: Some(target),
+            rules: removed
+                .iter()
+                .flat_map(|event| &event.delta.added_rules)
+                .map(|rule| rule.name.clone())
+                .collect(),
+            ..Self::new(
+                NotificationKind::RolledBack,
+                format!("Rolled back {} event(s)", removed.len()),
+            )
+        }
+    }
+}
+
+/// 通知过滤器，空条件表示不限 / Notification filter; an empty condition matches everything
+#[derive(Debug, Clone, Default)]
+pub struct NotificationFilter {
+    /// 只接收这些种类 / Only these kinds
+    pub kinds: Vec<NotificationKind>,
+    /// 只接收涉及这些规则之一的通知 / Only notifications involving one of these rules
+    pub rules: Vec<String>,
+    /// 只接收此时间之后的通知 / Only notifications after this time
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl NotificationFilter {
+    /// 接收所有通知 / Accept every notification
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// 只接收指定种类 / Accept only the given kinds
+    pub fn kinds(kinds: &[NotificationKind]) -> Self {
+        Self {
+            kinds: kinds.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// 是否接收该通知 / Whether the notification is accepted
+    pub fn matches(&self, notification: &EvolutionNotification) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&notification.kind))
+            && (self.rules.is_empty()
+                || notification
+                    .rules
+                    .iter()
+                    .any(|rule| self.rules.contains(rule)))
+            && self
+                .since
+                .is_none_or(|since| notification.timestamp > since)
+    }
+}
+
+/// 进化通知的订阅者 / Subscriber to evolution notifications
+pub trait EvolutionSubscriber: Send {
+    /// 收到通知 / A notification arrived
+    fn notify(&mut self, notification: &EvolutionNotification);
+
+    /// 等待已收到的通知处理完毕，异步订阅者需要实现 / Wait until received notifications are handled; needed by asynchronous subscribers
+    fn flush(&mut self) {}
+}
+
+impl<F: FnMut(&EvolutionNotification) + Send> EvolutionSubscriber for F {
+    fn notify(&mut self, notification: &EvolutionNotification) {
+        self(notification)
+    }
+}
+
+enum AsyncMessage {
+    Notify(EvolutionNotification),
+    Flush(Sender<()>),
+}
+
+/// 异步订阅者：在独立线程上按顺序处理通知，不阻塞引擎
+/// Asynchronous subscriber: handles notifications in order on its own thread without blocking the engine
+pub struct AsyncSubscriber {
+    sender: Sender<AsyncMessage>,
+}
+
+impl AsyncSubscriber {
+    /// 在新线程上运行订阅者，本对象被丢弃后线程处理完剩余通知即退出
+    /// Run the subscriber on a new thread, which exits after the remaining notifications once this is dropped
+    pub fn new(mut subscriber: impl EvolutionSubscriber + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for message in receiver {
+                match message {
+                    AsyncMessage::Notify(notification) => subscriber.notify(&notification),
+                    AsyncMessage::Flush(done) => {
+                        subscriber.flush();
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+        Self { sender }
+    }
+}
+
+impl EvolutionSubscriber for AsyncSubscriber {
+    fn notify(&mut self, notification: &EvolutionNotification) {
+        let _ = self.sender.send(AsyncMessage::Notify(notification.clone()));
+    }
+
+    fn flush(&mut self) {
+        let (done, wait) = mpsc::channel();
+        if self.sender.send(AsyncMessage::Flush(done)).is_ok() {
+            let _ = wait.recv();
+        }
+    }
+}
+
+/// 网络钩子的投递统计，可在订阅者移入线程后读取 / Webhook delivery statistics, readable after the subscriber moved to a thread
+#[derive(Debug, Clone, Default)]
+pub struct WebhookStats {
+    inner: Arc<Mutex<WebhookCounts>>,
+}
+
+#[derive(Debug, Default)]
+struct WebhookCounts {
+    delivered: usize,
+    errors: Vec<String>,
+}
+
+impl WebhookStats {
+    /// 成功投递的通知数 / Notifications delivered successfully
+    pub fn delivered(&self) -> usize {
+        self.inner
+            .lock()
+            .map(|counts| counts.delivered)
+            .unwrap_or(0)
+    }
+
+    /// 投递失败的错误信息 / Error messages of failed deliveries
+    pub fn errors(&self) -> Vec<String> {
+        self.inner
+            .lock()
+            .map(|counts| counts.errors.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// 网络钩子：把每个通知以JSON POST到 http:// 地址；请求是阻塞的，通常包装在 `AsyncSubscriber` 中
+/// Webhook: POSTs each notification as JSON to an http:// URL; requests block, so it is usually wrapped in an
+/// `AsyncSubscriber`
+pub struct WebhookNotifier {
+    url: String,
+    api_key: Option<String>,
+    timeout: Duration,
+    stats: WebhookStats,
+}
+
+impl WebhookNotifier {
+    /// 创建网络钩子，默认超时10秒 / Create a webhook with a 10 second default timeout
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            api_key: None,
+            timeout: Duration::from_secs(10),
+            stats: WebhookStats::default(),
+        }
+    }
+
+    /// 以Bearer令牌认证 / Authenticate with a bearer token
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// 设置请求超时 / Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 投递统计 / Delivery statistics
+    pub fn stats(&self) -> WebhookStats {
+        self.stats.clone()
+    }
+}
+
+impl EvolutionSubscriber for WebhookNotifier {
+    fn notify(&mut self, notification: &EvolutionNotification) {
+        let result = serde_json::to_string(notification)
+            .map_err(|e| e.to_string())
+            .and_then(|body| post_json(&self.url, &body, self.api_key.as_deref(), self.timeout));
+        if let Ok(mut counts) = self.stats.inner.lock() {
+            match result {
+                Ok(_) => counts.delivered += 1,
+                Err(error) => counts.errors.push(error),
+            }
+        }
+    }
+}
+
+/// 订阅标识 / Subscription identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(usize);
+
+/// 事件总线：把引擎的通知分发给通过过滤器的订阅者 / Event bus: dispatches engine notifications to subscribers whose filter accepts them
+#[derive(Default)]
+pub struct EventBus {
+    subscriptions: Vec<(
+        SubscriptionId,
+        NotificationFilter,
+        Box<dyn EvolutionSubscriber>,
+    )>,
+    next_id: usize,
+}
+
+impl EventBus {
+    /// 创建空总线 / Create an empty bus
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 订阅，订阅者在发布通知的线程上同步调用 / Subscribe; the subscriber is called synchronously on the publishing thread
+    pub fn subscribe(
+        &mut self,
+        filter: NotificationFilter,
+        subscriber: Box<dyn EvolutionSubscriber>,
+    ) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.subscriptions.push((id, filter, subscriber));
+        id
+    }
+
+    /// 订阅，订阅者在独立线程上调用 / Subscribe with the subscriber called on its own thread
+    pub fn subscribe_async(
+        &mut self,
+        filter: NotificationFilter,
+        subscriber: impl EvolutionSubscriber + 'static,
+    ) -> SubscriptionId {
+        self.subscribe(filter, Box::new(AsyncSubscriber::new(subscriber)))
+    }
+
+    /// 取消订阅 / Unsubscribe
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.subscriptions.len();
+        self.subscriptions.retain(|(other, _, _)| *other != id);
+        self.subscriptions.len() != before
+    }
+
+    /// 订阅数 / Number of subscriptions
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    /// 是否没有订阅 / Whether there are no subscriptions
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// 发布通知 / Publish a notification
+    pub fn publish(&mut self, notification: &EvolutionNotification) {
+        for (_, filter, subscriber) in &mut self.subscriptions {
+            if filter.matches(notification) {
+                subscriber.notify(notification);
+            }
+        }
+    }
+
+    /// 等待所有异步订阅者处理完已发布的通知 / Wait for every asynchronous subscriber to handle the published notifications
+    pub fn flush(&mut self) {
+        for (_, _, subscriber) in &mut self.subscriptions {
+            subscriber.flush();
+        }
+    }
+}
+
 /// 事件管理器错误 / Event manager error
 #[derive(Debug)]
 pub enum EventManagerError {
@@ -358,7 +733,7 @@ pub enum EventValidationError {
 }
 
 /// 事件冲突 / Event conflict
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventConflict {
     pub event1_id: Uuid,
     pub event2_id: Uuid,
@@ -367,7 +742,7 @@ pub struct EventConflict {
 }
 
 /// 冲突类型 / Conflict type
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConflictType {
     RuleModification,
     StateIncompatibility,
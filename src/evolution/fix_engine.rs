@@ -0,0 +1,540 @@
+// 自动修复引擎 / Auto-fix engine
+// 把审查器和错误恢复器的建议表示为带字节范围的文本编辑或AST编辑，应用到源码后验证能否重新解析、
+// 生成的测试是否退化，并输出统一diff
+// Represents reviewer and error-recovery suggestions as span-based text edits or AST edits, applies them
+// to source, verifies the result reparses and that generated tests do not regress, and returns a unified diff
+
+use crate::evolution::analyzer::CodeAnalyzer;
+use crate::evolution::code_reviewer::ReviewIssue;
+use crate::evolution::error_recovery::RecoveryResult;
+use crate::evolution::sandbox::SandboxRunner;
+use crate::evolution::test_generator::{value_literal, TestGenerator};
+use crate::grammar::core::GrammarElement;
+use crate::grammar::unparse::unparse_element;
+use crate::parser::incremental::{ParsedDocument, Span, SyntaxKind, SyntaxNode};
+use crate::parser::AdaptiveParser;
+use serde::{Deserialize, Serialize};
+
+/// 单个编辑 / A single edit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FixEdit {
+    /// 将字节范围替换为文本（起止相同即插入）/ Replace a byte span with text (an empty span inserts)
+    Text { span: Span, replacement: String },
+    /// 替换语法树路径处的节点：首个下标为顶层形式，其余为子节点下标
+    /// Replace the node at a syntax tree path: the first index is the top-level form, the rest are child indices
+    ReplaceNode {
+        path: Vec<usize>,
+        element: GrammarElement,
+    },
+    /// 重命名符号的所有出现（不含字符串和注释）/ Rename every occurrence of a symbol (strings and comments excluded)
+    Rename { from: String, to: String },
+}
+
+/// 一个修复：一组一起应用的编辑 / A fix: a group of edits applied together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fix {
+    /// 描述 / Description
+    pub description: String,
+    /// 来源规则或恢复方法 / Originating rule or recovery method
+    pub source: String,
+    /// 编辑 / Edits
+    pub edits: Vec<FixEdit>,
+}
+
+impl Fix {
+    /// 创建修复 / Create fix
+    pub fn new(description: impl Into<String>, source: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            source: source.into(),
+            edits: Vec::new(),
+        }
+    }
+
+    /// 添加编辑 / Add edit
+    pub fn with_edit(mut self, edit: FixEdit) -> Self {
+        self.edits.push(edit);
+        self
+    }
+}
+
+/// 修复选项 / Fix options
+#[derive(Debug, Clone)]
+pub struct FixOptions {
+    /// 修复后的源码必须能完整解析 / The fixed source must parse completely
+    pub require_parse: bool,
+    /// 运行生成的测试，修复后的输出必须与修复前一致
+    /// Run generated tests; the fixed program must produce the same outputs as before the fix
+    pub run_tests: bool,
+}
+
+impl Default for FixOptions {
+    fn default() -> Self {
+        Self {
+            require_parse: true,
+            run_tests: false,
+        }
+    }
+}
+
+/// 测试验证结果 / Test verification result
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TestVerification {
+    /// 运行的测试数 / Tests run
+    pub total: usize,
+    /// 修复后通过的测试数 / Tests passing after the fix
+    pub passed: usize,
+    /// 修复后输出改变的测试名 / Tests whose output changed after the fix
+    pub regressions: Vec<String>,
+}
+
+/// 修复结果 / Fix outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixOutcome {
+    /// 修复后的源码 / Fixed source
+    pub source: String,
+    /// 统一diff / Unified diff
+    pub diff: String,
+    /// 已应用的修复描述 / Descriptions of the applied fixes
+    pub applied: Vec<String>,
+    /// 测试验证（未运行测试时为None）/ Test verification (None when tests were not run)
+    pub tests: Option<TestVerification>,
+}
+
+impl FixOutcome {
+    /// 源码是否有变化 / Whether the source changed
+    pub fn changed(&self) -> bool {
+        !self.diff.is_empty()
+    }
+}
+
+/// 修复错误 / Fix error
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixError {
+    /// 编辑范围越界或不在字符边界 / Edit span out of bounds or not on a char boundary
+    InvalidSpan(Span),
+    /// 两个编辑范围重叠 / Two edit spans overlap
+    OverlappingEdits(Span, Span),
+    /// 语法树路径不存在 / Syntax tree path does not exist
+    InvalidPath(Vec<usize>),
+    /// 原始源码无法解析，不能解析AST编辑 / The original source does not parse, so AST edits cannot be resolved
+    SourceParse(String),
+    /// 修复后的源码无法解析 / The fixed source does not parse
+    Reparse(String),
+    /// 修复导致测试退化 / The fix made tests regress
+    TestRegression(Vec<String>),
+}
+
+impl std::fmt::Display for FixError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixError::InvalidSpan(span) => {
+                write!(f, "Invalid edit span {}..{}", span.start, span.end)
+            }
+            FixError::OverlappingEdits(a, b) => write!(
+                f,
+                "Overlapping edits {}..{} and {}..{}",
+                a.start, a.end, b.start, b.end
+            ),
+            FixError::InvalidPath(path) => write!(f, "No syntax node at path {:?}", path),
+            FixError::SourceParse(e) => write!(f, "Source does not parse: {}", e),
+            FixError::Reparse(e) => write!(f, "Fixed source does not parse: {}", e),
+            FixError::TestRegression(tests) => {
+                write!(f, "Fix breaks tests: {}", tests.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for FixError {}
+
+/// 自动修复引擎 / Auto-fix engine
+pub struct FixEngine {
+    parser: AdaptiveParser,
+    runner: SandboxRunner,
+    options: FixOptions,
+}
+
+impl FixEngine {
+    /// 使用默认选项创建 / Create with default options
+    pub fn new() -> Self {
+        Self::with_options(FixOptions::default())
+    }
+
+    /// 使用指定选项创建 / Create with the given options
+    pub fn with_options(options: FixOptions) -> Self {
+        Self {
+            parser: AdaptiveParser::new(true),
+            runner: SandboxRunner::new(),
+            options,
+        }
+    }
+
+    /// 使用指定解析器（如已加载宏或进化规则）/ Use the given parser (e.g. with macros or evolved rules loaded)
+    pub fn with_parser(mut self, parser: AdaptiveParser) -> Self {
+        self.parser = parser;
+        self
+    }
+
+    /// 获取选项 / Get options
+    pub fn options(&self) -> &FixOptions {
+        &self.options
+    }
+
+    /// 将错误恢复结果转为修复：整体替换为恢复后的代码，只保留变化的部分
+    /// Turn a recovery result into a fix: the recovered code replaces the source, trimmed to the changed region
+    pub fn fix_from_recovery(&self, recovery: &RecoveryResult, source: &str) -> Option<Fix> {
+        let fixed = recovery.fixed_code.as_deref()?;
+        let (span, replacement) = changed_region(source, fixed)?;
+        let description = recovery
+            .method
+            .clone()
+            .or_else(|| recovery.suggestions.first().cloned())
+            .unwrap_or_else(|| "错误恢复".to_string());
+        Some(Fix::new(description, "error_recovery").with_edit(FixEdit::Text { span, replacement }))
+    }
+
+    /// 将审查问题转为修复（目前支持 `naming_convention` 的重命名建议）
+    /// Turn a review issue into a fix (currently the rename suggestions of `naming_convention`)
+    pub fn fix_for_issue(&self, issue: &ReviewIssue) -> Option<Fix> {
+        match issue.rule_id.as_str() {
+            "naming_convention" => {
+                let from = issue.location.strip_prefix("名称 ")?.trim();
+                let to = issue.suggestion.strip_prefix("改为 ")?.trim();
+                (!from.is_empty() && !to.is_empty() && from != to).then(|| {
+                    Fix::new(format!("重命名 {} 为 {}", from, to), issue.rule_id.clone()).with_edit(
+                        FixEdit::Rename {
+                            from: from.to_string(),
+                            to: to.to_string(),
+                        },
+                    )
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// 为所有可自动修复的审查问题生成修复 / Generate fixes for every auto-fixable review issue
+    pub fn fixes_for_issues(&self, issues: &[ReviewIssue]) -> Vec<Fix> {
+        let mut fixes: Vec<Fix> = Vec::new();
+        for fix in issues.iter().filter_map(|issue| self.fix_for_issue(issue)) {
+            if !fixes
+                .iter()
+                .any(|known| known.description == fix.description)
+            {
+                fixes.push(fix);
+            }
+        }
+        fixes
+    }
+
+    /// 把所有编辑解析为针对原始源码的文本编辑，按位置排序并检查重叠
+    /// Resolve every edit into text edits against the original source, sorted by position and checked for overlap
+    pub fn resolve(&self, source: &str, fixes: &[Fix]) -> Result<Vec<(Span, String)>, FixError> {
+        let needs_tree = fixes
+            .iter()
+            .flat_map(|fix| &fix.edits)
+            .any(|edit| !matches!(edit, FixEdit::Text { .. }));
+        let document = if needs_tree {
+            Some(
+                self.parser
+                    .parse_document(source)
+                    .map_err(|e| FixError::SourceParse(e.to_string()))?,
+            )
+        } else {
+            None
+        };
+
+        let mut edits = Vec::new();
+        for edit in fixes.iter().flat_map(|fix| &fix.edits) {
+            match edit {
+                FixEdit::Text { span, replacement } => edits.push((*span, replacement.clone())),
+                FixEdit::ReplaceNode { path, element } => {
+                    let node = document
+                        .as_ref()
+                        .and_then(|document| node_at_path(document, path))
+                        .ok_or_else(|| FixError::InvalidPath(path.clone()))?;
+                    edits.push((node.span, unparse_element(element)));
+                }
+                FixEdit::Rename { from, to } => {
+                    let mut spans = Vec::new();
+                    for node in document.iter().flat_map(|document| &document.nodes) {
+                        collect_symbol_spans(node, source, from, &mut spans);
+                    }
+                    edits.extend(spans.into_iter().map(|span| (span, to.clone())));
+                }
+            }
+        }
+
+        for (span, _) in &edits {
+            if span.start > span.end
+                || span.end > source.len()
+                || !source.is_char_boundary(span.start)
+                || !source.is_char_boundary(span.end)
+            {
+                return Err(FixError::InvalidSpan(*span));
+            }
+        }
+        edits.sort_by_key(|(span, _)| (span.start, span.end));
+        edits.dedup();
+        for pair in edits.windows(2) {
+            let (a, b) = (pair[0].0, pair[1].0);
+            if b.start < a.end {
+                return Err(FixError::OverlappingEdits(a, b));
+            }
+        }
+        Ok(edits)
+    }
+
+    /// 应用修复并验证，返回修复后的源码和diff / Apply fixes and verify, returning the fixed source and diff
+    pub fn apply(&self, source: &str, fixes: &[Fix]) -> Result<FixOutcome, FixError> {
+        self.apply_to(source, fixes, "source")
+    }
+
+    /// 同 [`FixEngine::apply`]，diff 中使用 `path` 作为文件名 / Same as [`FixEngine::apply`], using `path` as the file name in the diff
+    pub fn apply_to(
+        &self,
+        source: &str,
+        fixes: &[Fix],
+        path: &str,
+    ) -> Result<FixOutcome, FixError> {
+        let edits = self.resolve(source, fixes)?;
+        let fixed = apply_edits(source, &edits);
+
+        let fixed_ast = if self.options.require_parse || self.options.run_tests {
+            Some(
+                self.parser
+                    .parse(&fixed)
+                    .map_err(|e| FixError::Reparse(e.to_string()))?,
+            )
+        } else {
+            None
+        };
+
+        let tests = match fixed_ast {
+            Some(ast) if self.options.run_tests => {
+                let verification = self.verify_tests(source, &ast, fixes);
+                if !verification.regressions.is_empty() {
+                    return Err(FixError::TestRegression(verification.regressions));
+                }
+                Some(verification)
+            }
+            _ => None,
+        };
+
+        Ok(FixOutcome {
+            diff: unified_diff(source, &fixed, path),
+            source: fixed,
+            applied: fixes.iter().map(|fix| fix.description.clone()).collect(),
+            tests,
+        })
+    }
+
+    /// 以修复前的程序生成测试并记录实际输出，再在修复后的程序上运行（测试输入同样应用重命名），
+    /// 输出不同即为退化；修复前无法解析时以修复后的程序生成测试，只统计通过数
+    /// Generate tests from the program before the fix, capturing its outputs, then run them against the fixed
+    /// program (with renames applied to the test inputs too); a differing output is a regression. When the
+    /// original does not parse, tests come from the fixed program and only the pass count is reported
+    pub fn verify_tests(
+        &self,
+        original: &str,
+        fixed_ast: &[GrammarElement],
+        fixes: &[Fix],
+    ) -> TestVerification {
+        let original_ast = self.parser.parse(original).ok();
+        let baseline = original_ast.as_deref().unwrap_or(fixed_ast);
+        let analysis = CodeAnalyzer::new().analyze(baseline);
+        let suite = TestGenerator::new().generate_tests(baseline, &analysis);
+        let renames: Vec<Fix> = fixes
+            .iter()
+            .map(|fix| Fix {
+                edits: fix
+                    .edits
+                    .iter()
+                    .filter(|edit| matches!(edit, FixEdit::Rename { .. }))
+                    .cloned()
+                    .collect(),
+                ..fix.clone()
+            })
+            .collect();
+
+        let mut tests = Vec::new();
+        let mut programs = Vec::new();
+        for test in &suite.test_cases {
+            let input = match original_ast {
+                Some(_) => match self.resolve(&test.input, &renames) {
+                    Ok(edits) => apply_edits(&test.input, &edits),
+                    Err(_) => continue,
+                },
+                None => test.input.clone(),
+            };
+            let Ok(call) = self.parser.parse(&input) else {
+                continue;
+            };
+            tests.push(test);
+            programs.push([fixed_ast, call.as_slice()].concat());
+        }
+
+        let results = self.runner.run(&programs);
+        let mut verification = TestVerification {
+            total: tests.len(),
+            ..TestVerification::default()
+        };
+        for (test, result) in tests.into_iter().zip(results) {
+            let actual = result.value.as_ref().and_then(value_literal);
+            if actual.as_deref() == Some(test.expected_result.as_str()) {
+                verification.passed += 1;
+            } else if original_ast.is_some() {
+                verification.regressions.push(test.name.clone());
+            }
+        }
+        verification
+    }
+}
+
+impl Default for FixEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 按语法树路径查找节点 / Find a node by syntax tree path
+fn node_at_path<'a>(document: &'a ParsedDocument, path: &[usize]) -> Option<&'a SyntaxNode> {
+    let (first, rest) = path.split_first()?;
+    rest.iter()
+        .try_fold(document.nodes.get(*first)?, |node, index| {
+            node.children.get(*index)
+        })
+}
+
+/// 收集文本等于 `name` 的原子节点范围 / Collect the spans of atom nodes whose text equals `name`
+fn collect_symbol_spans(node: &SyntaxNode, source: &str, name: &str, spans: &mut Vec<Span>) {
+    match node.kind {
+        SyntaxKind::Atom if node.text(source) == name => spans.push(node.span),
+        _ => {
+            for child in &node.children {
+                collect_symbol_spans(child, source, name, spans);
+            }
+        }
+    }
+}
+
+/// 应用已排序且不重叠的编辑 / Apply sorted, non-overlapping edits
+fn apply_edits(source: &str, edits: &[(Span, String)]) -> String {
+    let mut result = String::with_capacity(source.len());
+    let mut cursor = 0;
+    for (span, replacement) in edits {
+        result.push_str(&source[cursor..span.start]);
+        result.push_str(replacement);
+        cursor = span.end;
+    }
+    result.push_str(&source[cursor..]);
+    result
+}
+
+/// 两段文本去掉公共前后缀后的差异区域 / Differing region of two texts after removing the common prefix and suffix
+fn changed_region(old: &str, new: &str) -> Option<(Span, String)> {
+    if old == new {
+        return None;
+    }
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(old.len().min(new.len()), |((i, _), _)| i);
+    let suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum::<usize>();
+    Some((
+        Span::new(prefix, old.len() - suffix),
+        new[prefix..new.len() - suffix].to_string(),
+    ))
+}
+
+/// 生成统一diff（每个hunk带3行上下文），文本相同时为空字符串
+/// Produce a unified diff with 3 lines of context per hunk; empty when the texts are equal
+pub fn unified_diff(old: &str, new: &str, path: &str) -> String {
+    const CONTEXT: usize = 3;
+    if old == new {
+        return String::new();
+    }
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // 最长公共子序列表 / Longest common subsequence table
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    // 行操作：(标记, 旧行下标, 新行下标) / Line operations: (marker, old index, new index)
+    let mut ops: Vec<(char, usize, usize)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', i, j));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', i, j));
+            i += 1;
+        } else {
+            ops.push(('+', i, j));
+            j += 1;
+        }
+    }
+
+    let mut out = format!("--- a/{}\n+++ b/{}\n", path, path);
+    let changes: Vec<usize> = (0..ops.len()).filter(|&k| ops[k].0 != ' ').collect();
+    if changes.is_empty() {
+        // 只有行尾差异 / Only line ending differences
+        out.push_str("@@ -1 +1 @@\n\\ 仅行尾不同 / Line endings differ\n");
+        return out;
+    }
+    let mut index = 0;
+    while index < changes.len() {
+        let start = changes[index].saturating_sub(CONTEXT);
+        let mut end = changes[index] + CONTEXT + 1;
+        while index + 1 < changes.len() && changes[index + 1] <= end + CONTEXT {
+            index += 1;
+            end = changes[index] + CONTEXT + 1;
+        }
+        let end = end.min(ops.len());
+        let hunk = &ops[start..end];
+        let old_count = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_count = hunk.iter().filter(|op| op.0 != '-').count();
+        let (old_start, new_start) = (hunk[0].1, hunk[0].2);
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_count),
+            hunk_range(new_start, new_count)
+        ));
+        for &(op, i, j) in hunk {
+            let line = if op == '+' { b[j] } else { a[i] };
+            out.push(op);
+            out.push_str(line);
+            out.push('\n');
+        }
+        index += 1;
+    }
+    out
+}
+
+/// hunk头中的范围：空范围指向前一行 / Range in a hunk header: an empty range points at the preceding line
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
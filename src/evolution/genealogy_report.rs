@@ -0,0 +1,533 @@
+// 进化谱系报告 / Evolution genealogy report
+// 把追踪器记录的进化事件导出为可交互的HTML页面或Graphviz DOT图：谱系树、每个事件的元数据、引入的语法规则及其
+// 修改前后的差异，以及受影响的程序，供用户审查系统随时间对自身做了哪些改动
+// Exports the evolution events recorded by the tracker as an interactive HTML page or a Graphviz DOT graph: the
+// genealogy tree, each event's metadata, the grammar rules it introduced with before/after diffs, and the
+// affected programs, so users can audit what the system changed about itself over time
+
+use crate::evolution::dependency::quote_dot;
+use crate::evolution::doc_generator::escape_html;
+use crate::evolution::fix_engine::unified_diff;
+use crate::evolution::tracker::{EvolutionEvent, EvolutionTracker, EvolutionType};
+use crate::grammar::self_desc::describe_rule;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use uuid::Uuid;
+
+/// 报告页面的样式表 / Stylesheet of the report page
+const REPORT_STYLE: &str =
+    "body{font-family:sans-serif;max-width:64em;margin:2em auto;padding:0 1em;line-height:1.5}\
+code,pre{background:#f4f4f4}pre{padding:.5em;overflow-x:auto}section{border-top:1px solid #ddd;padding:.5em 0}\
+section.event{border-left:6px solid var(--color);padding-left:.8em}section:target{outline:2px solid #e90}\
+table{border-collapse:collapse}td,th{padding:.2em .8em;text-align:left;vertical-align:top}\
+.add{color:#1a7f37}.del{color:#cf222e}.hunk{color:#8250df}#controls{margin:1em 0}";
+
+/// 按文字和事件类型过滤事件的脚本 / Script filtering the events by text and event type
+const REPORT_SCRIPT: &str =
+    "const q=document.getElementById('filter'),t=document.getElementById('type');\
+function apply(){const s=q.value.toLowerCase(),k=t.value;\
+document.querySelectorAll('section.event').forEach(e=>{\
+e.hidden=!((!k||e.dataset.type===k)&&e.textContent.toLowerCase().includes(s))})}\
+q.oninput=apply;t.onchange=apply;";
+
+/// 谱系报告格式 / Genealogy report format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GenealogyFormat {
+    /// 可交互的HTML页面 / Interactive HTML page
+    Html,
+    /// Graphviz DOT
+    Dot,
+}
+
+impl GenealogyFormat {
+    /// 按文件扩展名推断：`.dot` 和 `.gv` 为DOT，其余为HTML / Infer from the file extension: `.dot` and `.gv` are DOT, anything else HTML
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("dot") | Some("gv") => GenealogyFormat::Dot,
+            _ => GenealogyFormat::Html,
+        }
+    }
+}
+
+/// 进化谱系报告 / Evolution genealogy report
+pub struct GenealogyReport<'a> {
+    /// 按记录顺序的事件 / Events in recording order
+    events: &'a [EvolutionEvent],
+    /// 每个事件的父事件和子事件，按记录顺序 / Parents and children of every event, in recording order
+    parents: HashMap<Uuid, Vec<Uuid>>,
+    children: HashMap<Uuid, Vec<Uuid>>,
+}
+
+impl<'a> GenealogyReport<'a> {
+    /// 由追踪器的历史和谱系创建 / Create from a tracker's history and genealogy
+    pub fn new(tracker: &'a EvolutionTracker) -> Self {
+        let events = tracker.get_history();
+        let genealogy = tracker.get_genealogy();
+        let position: HashMap<Uuid, usize> = events
+            .iter()
+            .enumerate()
+            .map(|(index, event)| (event.id, index))
+            .collect();
+        // 只保留仍在历史中的事件，按记录顺序排列 / Keep events still in the history, in recording order
+        let ordered = |mut ids: Vec<Uuid>| {
+            ids.retain(|id| position.contains_key(id));
+            ids.sort_by_key(|id| position[id]);
+            ids.dedup();
+            ids
+        };
+        let mut parents = HashMap::new();
+        let mut children = HashMap::new();
+        for event in events {
+            parents.insert(event.id, ordered(genealogy.get_parents(event.id)));
+            children.insert(event.id, ordered(genealogy.get_children(event.id)));
+        }
+        Self {
+            events,
+            parents,
+            children,
+        }
+    }
+
+    /// 按格式渲染 / Render in the given format
+    pub fn render(&self, format: GenealogyFormat) -> String {
+        match format {
+            GenealogyFormat::Html => self.to_html(),
+            GenealogyFormat::Dot => self.to_dot(),
+        }
+    }
+
+    /// 没有父事件的事件，即谱系树的根 / Events without parents, i.e. the roots of the genealogy tree
+    pub fn roots(&self) -> Vec<&EvolutionEvent> {
+        self.events
+            .iter()
+            .filter(|event| self.parents_of(event.id).is_empty())
+            .collect()
+    }
+
+    fn parents_of(&self, id: Uuid) -> &[Uuid] {
+        self.parents.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn children_of(&self, id: Uuid) -> &[Uuid] {
+        self.children
+            .get(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    fn event(&self, id: Uuid) -> Option<&EvolutionEvent> {
+        self.events.iter().find(|event| event.id == id)
+    }
+
+    /// 导出为Graphviz DOT：每个事件一个节点，按事件类型着色，标签含时间和规则变化，边从父事件指向子事件
+    /// Export as Graphviz DOT: one node per event colored by event type, labelled with the time and rule changes,
+    /// with edges from parent to child events
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from(
+            "digraph genealogy {\n    rankdir=TB;\n    node [shape=box, style=\"rounded,filled\", fontname=\"Helvetica\"];\n",
+        );
+        for event in self.events {
+            let mut lines = vec![
+                format!("{:?} {}", event.event_type, short_id(event.id)),
+                event.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+            ];
+            let changes = rule_changes(event);
+            if !changes.is_empty() {
+                lines.push(changes);
+            }
+            // 换行在引号转义之后替换为DOT的 `\n` / Newlines become DOT's `\n` after quoting
+            let label = quote_dot(&lines.join("\n")).replace('\n', "\\n");
+            dot.push_str(&format!(
+                "    {} [label={}, fillcolor=\"{}\", tooltip={}];\n",
+                quote_dot(&event.id.to_string()),
+                label,
+                type_color(&event.event_type),
+                quote_dot(&event.delta.description)
+            ));
+        }
+        for event in self.events {
+            for child in self.children_of(event.id) {
+                dot.push_str(&format!(
+                    "    {} -> {};\n",
+                    quote_dot(&event.id.to_string()),
+                    quote_dot(&child.to_string())
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// 导出为可交互的HTML页面：概要、可按文字和类型过滤的事件、谱系树和受影响的程序，事件之间互相链接
+    /// Export as an interactive HTML page: a summary, events filterable by text and type, the genealogy tree and
+    /// the affected programs, with events linked to each other
+    pub fn to_html(&self) -> String {
+        let mut body = String::new();
+        body.push_str(&self.summary_html());
+
+        body.push_str("<h2>谱系树 / Genealogy Tree</h2>\n");
+        if self.events.is_empty() {
+            body.push_str("<p>没有进化事件 / No evolution events</p>\n");
+        } else {
+            let mut expanded = HashSet::new();
+            body.push_str("<ul>\n");
+            for root in self.roots() {
+                self.tree_html(root, &mut expanded, &mut body);
+            }
+            // 父事件成环而没有根的事件也列出 / Also list events whose parents form a cycle and have no root
+            for event in self.events {
+                if !expanded.contains(&event.id) {
+                    self.tree_html(event, &mut expanded, &mut body);
+                }
+            }
+            body.push_str("</ul>\n");
+        }
+
+        body.push_str("<h2>事件 / Events</h2>\n<div id=\"controls\">\
+             <input id=\"filter\" type=\"search\" placeholder=\"过滤 / Filter\"> <select id=\"type\">\
+             <option value=\"\">全部类型 / All types</option>");
+        let types: Vec<String> = self
+            .events
+            .iter()
+            .map(|event| format!("{:?}", event.event_type))
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        for kind in &types {
+            body.push_str(&format!("<option>{}</option>", escape_html(kind)));
+        }
+        body.push_str("</select></div>\n");
+        for event in self.events {
+            body.push_str(&self.event_html(event));
+        }
+
+        let programs = self.programs();
+        if !programs.is_empty() {
+            body.push_str("<h2>受影响的程序 / Affected Programs</h2>\n");
+            for (entry, events) in &programs {
+                body.push_str(&format!("<h3><code>{}</code></h3>\n", escape_html(entry)));
+                for (event, program) in events {
+                    body.push_str(&format!(
+                        "<section id=\"program-{id}\"><p><a href=\"#event-{id}\">{short}</a> {time}</p>\
+                         <pre>{program}</pre></section>\n",
+                        id = event.id,
+                        short = short_id(event.id),
+                        time = event.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                        program = escape_html(program)
+                    ));
+                }
+            }
+        }
+
+        format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>{REPORT_STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n{body}\
+             <script>{REPORT_SCRIPT}</script>\n</body>\n</html>\n",
+            title = "进化谱系报告 / Evolution Genealogy Report",
+        )
+    }
+
+    /// 概要：事件数、各类型事件数、规则变化总数和时间范围 / Summary: event count, events per type, rule change totals and time span
+    fn summary_html(&self) -> String {
+        let mut per_type: BTreeMap<String, usize> = BTreeMap::new();
+        for event in self.events {
+            *per_type
+                .entry(format!("{:?}", event.event_type))
+                .or_default() += 1;
+        }
+        let count = |f: fn(&EvolutionEvent) -> usize| self.events.iter().map(f).sum::<usize>();
+        let mut rows = vec![
+            ("事件 / Events".to_string(), self.events.len().to_string()),
+            (
+                "新增规则 / Rules added".to_string(),
+                count(|event| event.delta.added_rules.len()).to_string(),
+            ),
+            (
+                "修改规则 / Rules modified".to_string(),
+                count(|event| event.delta.modified_rules.len()).to_string(),
+            ),
+            (
+                "移除规则 / Rules removed".to_string(),
+                count(|event| event.delta.removed_rules.len()).to_string(),
+            ),
+        ];
+        if let (Some(first), Some(last)) = (self.events.first(), self.events.last()) {
+            rows.push((
+                "时间范围 / Time span".to_string(),
+                format!(
+                    "{} – {}",
+                    first.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+                    last.timestamp.format("%Y-%m-%d %H:%M:%S UTC")
+                ),
+            ));
+        }
+        rows.extend(per_type.into_iter().map(|(kind, n)| (kind, n.to_string())));
+        table_html(&rows)
+    }
+
+    /// 谱系树的一项；多个父事件的事件只在第一次出现时展开 / One genealogy tree item; an event with several parents is expanded only where it first appears
+    fn tree_html(&self, event: &EvolutionEvent, expanded: &mut HashSet<Uuid>, out: &mut String) {
+        out.push_str(&format!(
+            "<li>{} {}",
+            event_link(event.id),
+            escape_html(&event.delta.description)
+        ));
+        if !expanded.insert(event.id) {
+            out.push_str(" (见上 / see above)</li>\n");
+            return;
+        }
+        let children: Vec<&EvolutionEvent> = self
+            .children_of(event.id)
+            .iter()
+            .filter_map(|id| self.event(*id))
+            .collect();
+        if !children.is_empty() {
+            out.push_str("\n<ul>\n");
+            for child in children {
+                self.tree_html(child, expanded, out);
+            }
+            out.push_str("</ul>\n");
+        }
+        out.push_str("</li>\n");
+    }
+
+    /// 一个事件的段落 / Section of one event
+    fn event_html(&self, event: &EvolutionEvent) -> String {
+        let kind = format!("{:?}", event.event_type);
+        let links = |ids: &[Uuid]| {
+            if ids.is_empty() {
+                return "-".to_string();
+            }
+            ids.iter()
+                .map(|id| event_link(*id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut rows = vec![
+            ("ID".to_string(), event.id.to_string()),
+            (
+                "时间 / Time".to_string(),
+                event.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            ),
+            (
+                "触发 / Trigger".to_string(),
+                format!("{:?}", event.trigger.source),
+            ),
+            (
+                "版本 / Version".to_string(),
+                format!(
+                    "{} → {}",
+                    event.before_state.version, event.after_state.version
+                ),
+            ),
+        ];
+        if !event.trigger.conditions.is_empty() {
+            rows.push((
+                "条件 / Conditions".to_string(),
+                event.trigger.conditions.join("; "),
+            ));
+        }
+        if let Some(seed) = event.seed() {
+            rows.push(("种子 / Seed".to_string(), seed.to_string()));
+        }
+        rows.push((
+            "作者 / Author".to_string(),
+            event
+                .author
+                .clone()
+                .unwrap_or_else(|| "自动 / automatic".to_string()),
+        ));
+        if let Some(metrics) = &event.success_metrics {
+            rows.push((
+                "指标 / Metrics".to_string(),
+                format!(
+                    "success {:.3}, performance {:+.3}, satisfaction {:+.3}, compatibility {:+.3}",
+                    metrics.success_rate,
+                    metrics.performance_improvement,
+                    metrics.user_satisfaction_delta,
+                    metrics.compatibility_impact
+                ),
+            ));
+        }
+        // 其余元数据；程序和父事件另行链接 / Remaining metadata; programs and parents are linked separately
+        let sources = [
+            (&event.after_state.metadata, &["program", "parents"][..]),
+            (&event.trigger.environment, &["seed"][..]),
+        ];
+        for (source, skipped) in sources {
+            if let Some(entries) = source.as_object() {
+                for (key, value) in entries {
+                    if !skipped.contains(&key.as_str()) {
+                        rows.push((key.clone(), metadata_text(value)));
+                    }
+                }
+            }
+        }
+        let mut table = table_html(&rows);
+        let link_rows = format!(
+            "<tr><th>父事件 / Parents</th><td>{}</td></tr>\n<tr><th>子事件 / Children</th><td>{}</td></tr>\n",
+            links(self.parents_of(event.id)),
+            links(self.children_of(event.id))
+        );
+        table.insert_str(table.len() - "</table>\n".len(), &link_rows);
+
+        let mut html = format!(
+            "<section class=\"event\" id=\"event-{id}\" data-type=\"{kind}\" style=\"--color:{color}\">\n\
+             <h3>{kind} {short}</h3>\n<p>{description}</p>\n{table}",
+            id = event.id,
+            kind = escape_html(&kind),
+            color = type_color(&event.event_type),
+            short = short_id(event.id),
+            description = escape_html(&event.delta.description),
+        );
+        if event.after_state.metadata.get("program").is_some() {
+            html.push_str(&format!(
+                "<p>受影响的程序 / Affected program: <a href=\"#program-{}\">{}</a></p>\n",
+                event.id,
+                escape_html(
+                    event.after_state.metadata["entry"]
+                        .as_str()
+                        .unwrap_or("program")
+                )
+            ));
+        }
+
+        // 规则变化：新增和移除的规则写成自描述代码，修改的规则给出前后差异
+        // Rule changes: added and removed rules as self-describing code, modified rules as before/after diffs
+        for rule in &event.delta.added_rules {
+            html.push_str(&format!(
+                "<details open><summary class=\"add\">+ {}</summary><pre class=\"add\">{}</pre></details>\n",
+                escape_html(&rule.name),
+                escape_html(&describe_rule(rule))
+            ));
+        }
+        for (before, after) in &event.delta.modified_rules {
+            let diff = unified_diff(&describe_rule(before), &describe_rule(after), &after.name);
+            html.push_str(&format!(
+                "<details open><summary class=\"hunk\">~ {}</summary><pre>{}</pre></details>\n",
+                escape_html(&after.name),
+                diff_html(&diff)
+            ));
+        }
+        for rule in &event.delta.removed_rules {
+            html.push_str(&format!(
+                "<details><summary class=\"del\">- {}</summary><pre class=\"del\">{}</pre></details>\n",
+                escape_html(&rule.name),
+                escape_html(&describe_rule(rule))
+            ));
+        }
+        html.push_str("</section>\n");
+        html
+    }
+
+    /// 事件中记录的程序，按被进化的函数分组 / Programs recorded in the events, grouped by the evolved function
+    fn programs(&self) -> BTreeMap<String, Vec<(&EvolutionEvent, &str)>> {
+        let mut programs: BTreeMap<String, Vec<(&EvolutionEvent, &str)>> = BTreeMap::new();
+        for event in self.events {
+            if let Some(program) = event.after_state.metadata["program"].as_str() {
+                let entry = event.after_state.metadata["entry"]
+                    .as_str()
+                    .unwrap_or("program");
+                programs
+                    .entry(entry.to_string())
+                    .or_default()
+                    .push((event, program));
+            }
+        }
+        programs
+    }
+}
+
+/// 事件ID的前8位 / First 8 characters of an event id
+fn short_id(id: Uuid) -> String {
+    id.to_string()[..8].to_string()
+}
+
+/// 指向事件段落的链接 / Link to an event's section
+fn event_link(id: Uuid) -> String {
+    format!("<a href=\"#event-{}\">{}</a>", id, short_id(id))
+}
+
+/// 规则变化的简写：`+新增 ~修改 -移除` / Rule changes in short: `+added ~modified -removed`
+fn rule_changes(event: &EvolutionEvent) -> String {
+    let delta = &event.delta;
+    delta
+        .added_rules
+        .iter()
+        .map(|rule| format!("+{}", rule.name))
+        .chain(
+            delta
+                .modified_rules
+                .iter()
+                .map(|(_, rule)| format!("~{}", rule.name)),
+        )
+        .chain(
+            delta
+                .removed_rules
+                .iter()
+                .map(|rule| format!("-{}", rule.name)),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 元数据值的文本：字符串不加引号，字符串列表以分号连接 / Text of a metadata value: strings unquoted, string lists joined by semicolons
+fn metadata_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(items) if items.iter().all(serde_json::Value::is_string) => items
+            .iter()
+            .filter_map(serde_json::Value::as_str)
+            .collect::<Vec<_>>()
+            .join("; "),
+        other => other.to_string(),
+    }
+}
+
+/// 事件类型的颜色 / Color of an event type
+fn type_color(kind: &EvolutionType) -> &'static str {
+    match kind {
+        EvolutionType::SyntaxEvolution => "#cfe2ff",
+        EvolutionType::SemanticEvolution => "#fff3cd",
+        EvolutionType::PerformanceEvolution => "#f8d7da",
+        EvolutionType::EcosystemEvolution => "#e2d9f3",
+        EvolutionType::InteractionEvolution => "#d2f4ea",
+        EvolutionType::ProgramEvolution => "#d1e7dd",
+    }
+}
+
+/// 两列表格，键和值都转义 / Two-column table with escaped keys and values
+fn table_html(rows: &[(String, String)]) -> String {
+    let mut html = String::from("<table>\n");
+    for (key, value) in rows {
+        html.push_str(&format!(
+            "<tr><th>{}</th><td>{}</td></tr>\n",
+            escape_html(key),
+            escape_html(value)
+        ));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+/// 统一diff按行着色 / Unified diff colored by line
+fn diff_html(diff: &str) -> String {
+    diff.lines()
+        .map(|line| {
+            let class =
+                if line.starts_with("---") || line.starts_with("+++") || line.starts_with("@@") {
+                    "hunk"
+                } else if line.starts_with('+') {
+                    "add"
+                } else if line.starts_with('-') {
+                    "del"
+                } else {
+                    return escape_html(line);
+                };
+            format!("<span class=\"{}\">{}</span>", class, escape_html(line))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
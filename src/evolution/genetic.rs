@@ -0,0 +1,601 @@
+// 遗传编程 / Genetic programming
+// 以适应度为目标搜索程序：对函数体表达式做变异和交叉，在沙箱中按测试用例或适应度函数评估候选，保留最优程序及其谱系
+// Searches for programs driven by fitness: mutates and crosses over function body expressions, evaluates
+// candidates in the sandbox against test cases or a fitness function, and keeps the best program with its lineage
+
+use crate::evolution::engine::EvolutionError;
+use crate::evolution::mutation::{find_definition, MutationKind, MutationOperator, Mutator};
+use crate::evolution::sandbox::{SandboxConfig, SandboxResult, SandboxRunner};
+use crate::grammar::core::{Expr, GrammarElement, Literal};
+use crate::grammar::unparse::unparse;
+use crate::grammar::visit::count_nodes;
+use crate::parser::AdaptiveParser;
+use crate::runtime::interpreter::{ExecutionBudget, Interpreter, Value};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// 自定义适应度函数：接收沙箱执行结果，返回分数（越大越好）
+/// Custom fitness function: receives the sandbox result and returns a score (higher is better)
+pub type FitnessFn = Arc<dyn Fn(&SandboxResult) -> f64 + Send + Sync>;
+
+/// 测试用例：用参数调用目标函数并与期望值比较 / Test case: call the target function with arguments and compare with the expected value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FitnessCase {
+    /// 参数 / Arguments
+    pub args: Vec<Value>,
+    /// 期望返回值 / Expected return value
+    pub expected: Value,
+}
+
+impl FitnessCase {
+    /// 创建测试用例 / Create test case
+    pub fn new(args: Vec<Value>, expected: Value) -> Self {
+        Self { args, expected }
+    }
+}
+
+/// 遗传编程的搜索目标和参数 / Search goal and parameters of genetic programming
+#[derive(Clone)]
+pub struct FitnessSpec {
+    /// 初始程序源码，必须定义 `entry` 函数 / Initial program source, must define the `entry` function
+    pub program: String,
+    /// 被进化的函数名 / Name of the function being evolved
+    pub entry: String,
+    /// 测试用例；设置后程序的值为各用例返回值组成的列表（出错的用例为null）
+    /// Test cases; when set, the program value is the list of the cases' return values (null for failing cases)
+    pub test_cases: Vec<FitnessCase>,
+    /// 自定义适应度函数（None时每个通过的用例得1分，数值答案按误差得部分分，取平均）
+    /// Custom fitness function (None scores 1 per passing case plus partial credit for close numeric answers, averaged)
+    pub fitness: Option<FitnessFn>,
+    /// 种群大小 / Population size
+    pub population_size: usize,
+    /// 最大代数 / Maximum number of generations
+    pub generations: usize,
+    /// 锦标赛选择的参赛数 / Entrants per tournament selection
+    pub tournament_size: usize,
+    /// 每代直接保留的最优个体数 / Best individuals carried over unchanged each generation
+    pub elitism: usize,
+    /// 后代由交叉产生的概率（否则由变异产生）/ Probability that offspring come from crossover (otherwise mutation)
+    pub crossover_rate: f64,
+    /// 变异算子及其触发概率，变异只作用于被进化函数的函数体
+    /// Mutation operators with their firing probabilities; mutations only touch the evolved function's body
+    pub operators: Vec<MutationOperator>,
+    /// 函数体的最大节点数 / Maximum node count of the function body
+    pub max_nodes: usize,
+    /// 每个节点扣除的适应度，偏好更小的程序 / Fitness deducted per node, preferring smaller programs
+    pub size_penalty: f64,
+    /// 分数达到此值时提前停止（None时仅在全部测试通过时停止）
+    /// Stop early once the score reaches this value (None stops only when every test case passes)
+    pub target_score: Option<f64>,
+    /// 随机数种子（None时随机选择，实际种子记录在结果中）
+    /// Random seed (None picks one at random; the seed used is recorded in the result)
+    pub seed: Option<u64>,
+    /// 评估候选程序的沙箱配置 / Sandbox configuration for evaluating candidates
+    pub sandbox: SandboxConfig,
+}
+
+impl FitnessSpec {
+    /// 以初始程序和被进化的函数名创建，其他参数取默认值
+    /// Create from the initial program and the name of the function to evolve, with default parameters
+    pub fn new(program: impl Into<String>, entry: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            entry: entry.into(),
+            test_cases: Vec::new(),
+            fitness: None,
+            population_size: 32,
+            generations: 30,
+            tournament_size: 3,
+            elitism: 2,
+            crossover_rate: 0.3,
+            // 偏重子树替换以引入新结构；函数提取会把节点移出函数体、绕过大小限制，默认不启用
+            // Subtree replacement is weighted up to introduce new structure; function extraction moves nodes
+            // out of the body, dodging the size limit, so it is off by default
+            operators: vec![
+                MutationOperator::new(MutationKind::ConstantPerturbation, 0.25),
+                MutationOperator::new(MutationKind::OperatorSubstitution, 0.25),
+                MutationOperator::new(MutationKind::SubtreeSwap, 0.1),
+                MutationOperator::new(MutationKind::SubtreeReplacement, 0.5),
+                MutationOperator::new(MutationKind::Hoist, 0.25),
+                MutationOperator::new(MutationKind::FunctionInlining, 0.05),
+            ],
+            max_nodes: 40,
+            size_penalty: 0.001,
+            target_score: None,
+            seed: None,
+            // 候选程序通常很小，收紧预算使发散的变异尽快失败
+            // Candidates are usually small, so a tight budget makes diverging mutations fail fast
+            sandbox: SandboxConfig {
+                budget: ExecutionBudget {
+                    max_steps: Some(100_000),
+                    max_collection_size: Some(10_000),
+                    timeout: Some(std::time::Duration::from_secs(1)),
+                    max_call_depth: Some(1_000),
+                },
+                ..SandboxConfig::default()
+            },
+        }
+    }
+
+    /// 添加测试用例 / Add test case
+    pub fn with_test_case(mut self, args: Vec<Value>, expected: Value) -> Self {
+        self.test_cases.push(FitnessCase::new(args, expected));
+        self
+    }
+
+    /// 设置自定义适应度函数 / Set custom fitness function
+    pub fn with_fitness<F>(mut self, fitness: F) -> Self
+    where
+        F: Fn(&SandboxResult) -> f64 + Send + Sync + 'static,
+    {
+        self.fitness = Some(Arc::new(fitness));
+        self
+    }
+
+    /// 设置最大代数 / Set maximum number of generations
+    pub fn with_generations(mut self, generations: usize) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    /// 设置种群大小 / Set population size
+    pub fn with_population_size(mut self, population_size: usize) -> Self {
+        self.population_size = population_size;
+        self
+    }
+
+    /// 设置随机数种子 / Set random seed
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl std::fmt::Debug for FitnessSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FitnessSpec")
+            .field("program", &self.program)
+            .field("entry", &self.entry)
+            .field("test_cases", &self.test_cases)
+            .field("fitness", &self.fitness.as_ref().map(|_| "<fn>"))
+            .field("population_size", &self.population_size)
+            .field("generations", &self.generations)
+            .field("seed", &self.seed)
+            .finish_non_exhaustive()
+    }
+}
+
+/// 谱系条目：一个候选程序及其来源 / Lineage entry: a candidate program and where it came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LineageEntry {
+    /// 个体ID（记录到追踪器时即事件ID）/ Individual ID (the event ID once recorded in the tracker)
+    pub id: Uuid,
+    /// 父个体ID（初始程序为空，交叉有两个）/ Parent IDs (empty for the initial program, two for crossover)
+    pub parents: Vec<Uuid>,
+    /// 产生的代数 / Generation it was created in
+    pub generation: usize,
+    /// 产生方式：seed、crossover、copy，或以 `+` 连接的变异算子名
+    /// How it was created: seed, crossover, copy, or the mutation operator names joined with `+`
+    pub operation: String,
+    /// 程序源码 / Program source
+    pub source: String,
+    /// 分数（未扣除大小惩罚）/ Score (before the size penalty)
+    pub score: f64,
+    /// 适应度 / Fitness
+    pub fitness: f64,
+}
+
+/// 遗传编程的结果 / Result of genetic programming
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvolvedProgram {
+    /// 最优程序源码 / Source of the best program
+    pub source: String,
+    /// 最优程序AST / AST of the best program
+    pub ast: Vec<GrammarElement>,
+    /// 分数（未扣除大小惩罚）/ Score (before the size penalty)
+    pub score: f64,
+    /// 适应度 / Fitness
+    pub fitness: f64,
+    /// 通过的测试用例数 / Test cases passed
+    pub passed: usize,
+    /// 测试用例总数 / Total test cases
+    pub total: usize,
+    /// 实际运行的代数 / Generations actually run
+    pub generations_run: usize,
+    /// 执行的评估次数（相同源码只评估一次）/ Evaluations run (identical sources are evaluated once)
+    pub evaluations: usize,
+    /// 使用的随机数种子，可用于重放 / Random seed used, for replay
+    pub seed: u64,
+    /// 最优程序的全部祖先（含自身），按产生顺序排列 / All ancestors of the best program (itself included), in creation order
+    pub lineage: Vec<LineageEntry>,
+}
+
+/// 种群中的个体 / Individual in the population
+#[derive(Clone)]
+struct Individual {
+    id: Uuid,
+    program: Vec<GrammarElement>,
+    source: String,
+    score: f64,
+    fitness: f64,
+    passed: usize,
+}
+
+/// 程序进化器 / Program evolver
+pub struct ProgramEvolver {
+    spec: FitnessSpec,
+    /// 变异器（其随机数生成器也用于选择和交叉）/ Mutator (its random number generator also drives selection and crossover)
+    mutator: Mutator,
+    seed: u64,
+    /// 已产生个体的谱系（分数在评估后填入）/ Lineage of every individual created (scores filled in after evaluation)
+    history: HashMap<Uuid, LineageEntry>,
+    /// 按源码缓存的评估结果：(分数, 通过数) / Evaluation results cached by source: (score, passed)
+    evaluated: HashMap<String, (f64, usize)>,
+}
+
+impl ProgramEvolver {
+    /// 创建进化器 / Create evolver
+    pub fn new(spec: FitnessSpec) -> Self {
+        let seed = spec.seed.unwrap_or_else(|| Uuid::new_v4().as_u64_pair().0);
+        Self {
+            mutator: Mutator::with_operators(spec.operators.clone(), seed),
+            spec,
+            seed,
+            history: HashMap::new(),
+            evaluated: HashMap::new(),
+        }
+    }
+
+    /// 解析初始程序并运行搜索 / Parse the initial program and run the search
+    pub fn run(mut self, parser: &AdaptiveParser) -> Result<EvolvedProgram, EvolutionError> {
+        let program = parser
+            .parse(&self.spec.program)
+            .map_err(|e| EvolutionError::InvalidSpec(format!("Failed to parse program: {}", e)))?;
+        self.locate_entry(&program)?;
+        if self.spec.population_size == 0 {
+            return Err(EvolutionError::InvalidSpec(
+                "Population size must be positive".to_string(),
+            ));
+        }
+        if self.spec.test_cases.is_empty() && self.spec.fitness.is_none() {
+            return Err(EvolutionError::InvalidSpec(
+                "Either test cases or a fitness function is required".to_string(),
+            ));
+        }
+        let harness = self.harness()?;
+        let runner = SandboxRunner::with_config(SandboxConfig {
+            seed: Some(self.spec.sandbox.seed.unwrap_or(self.seed)),
+            ..self.spec.sandbox.clone()
+        });
+
+        // 初始种群：初始程序及其变异体 / Initial population: the initial program and its mutants
+        let seed_individual = self.create(program, Vec::new(), 0, "seed");
+        let mut population = vec![seed_individual.clone()];
+        while population.len() < self.spec.population_size {
+            let child = self.mutate(&seed_individual, 0);
+            population.push(child);
+        }
+
+        let mut best = seed_individual;
+        best.fitness = f64::NEG_INFINITY;
+        let mut generations_run = 0;
+        for generation in 0..=self.spec.generations {
+            self.evaluate(&runner, &harness, &mut population);
+            population.sort_by(|a, b| b.fitness.total_cmp(&a.fitness));
+            if population[0].fitness > best.fitness {
+                best = population[0].clone();
+            }
+            generations_run = generation;
+            if self.reached_target(&best) || generation == self.spec.generations {
+                break;
+            }
+
+            let mut next: Vec<Individual> = population
+                .iter()
+                .take(self.spec.elitism.min(self.spec.population_size))
+                .cloned()
+                .collect();
+            while next.len() < self.spec.population_size {
+                let parent = self.select(&population).clone();
+                let child = if self.mutator.rng().chance(self.spec.crossover_rate) {
+                    let other = self.select(&population).clone();
+                    self.crossover(&parent, &other, generation + 1)
+                } else {
+                    self.mutate(&parent, generation + 1)
+                };
+                next.push(child);
+            }
+            population = next;
+        }
+
+        Ok(EvolvedProgram {
+            ast: best.program.clone(),
+            score: best.score,
+            fitness: best.fitness,
+            passed: best.passed,
+            total: self.spec.test_cases.len(),
+            generations_run,
+            evaluations: self.evaluated.len(),
+            seed: self.seed,
+            lineage: self.lineage(best.id),
+            source: best.source,
+        })
+    }
+
+    /// 检查被进化函数已定义且函数体是表达式 / Check that the evolved function is defined with an expression body
+    fn locate_entry(&self, program: &[GrammarElement]) -> Result<(), EvolutionError> {
+        if self.body_size(program) > 0 {
+            return Ok(());
+        }
+        Err(EvolutionError::InvalidSpec(format!(
+            "Program does not define function `{}` with an expression body",
+            self.spec.entry
+        )))
+    }
+
+    /// 测试用例调用表达式：`(list (try (entry args...) null) ...)`
+    /// Test case call expression: `(list (try (entry args...) null) ...)`
+    fn harness(&self) -> Result<Option<GrammarElement>, EvolutionError> {
+        if self.spec.test_cases.is_empty() {
+            return Ok(None);
+        }
+        let mut calls = Vec::new();
+        for case in &self.spec.test_cases {
+            let args = case
+                .args
+                .iter()
+                .map(|arg| Interpreter::value_to_expr(arg.clone()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| EvolutionError::InvalidSpec(e.to_string()))?;
+            calls.push(Expr::Try {
+                try_body: Box::new(Expr::Call(self.spec.entry.as_str().into(), args)),
+                catch_var: None,
+                catch_body: Box::new(Expr::Literal(Literal::Null)),
+            });
+        }
+        Ok(Some(GrammarElement::Expr(Box::new(Expr::Literal(
+            Literal::List(calls),
+        )))))
+    }
+
+    /// 评估尚未评估的个体 / Evaluate individuals not evaluated yet
+    fn evaluate(
+        &mut self,
+        runner: &SandboxRunner,
+        harness: &Option<GrammarElement>,
+        population: &mut [Individual],
+    ) {
+        let mut pending: Vec<String> = Vec::new();
+        let mut programs = Vec::new();
+        for individual in population.iter() {
+            if !self.evaluated.contains_key(&individual.source)
+                && !pending.contains(&individual.source)
+            {
+                pending.push(individual.source.clone());
+                let mut program = individual.program.clone();
+                program.extend(harness.iter().cloned());
+                programs.push(program);
+            }
+        }
+
+        let spec = &self.spec;
+        let results = runner.run_with_fitness(&programs, |result| match &spec.fitness {
+            Some(fitness) => fitness(result),
+            None => test_score(spec, result),
+        });
+        for (source, result) in pending.into_iter().zip(results) {
+            let score = if result.fitness.is_finite() {
+                result.fitness
+            } else {
+                f64::MIN
+            };
+            self.evaluated
+                .insert(source, (score, passed_count(&self.spec, &result)));
+        }
+
+        for individual in population.iter_mut() {
+            let (score, passed) = self.evaluated[&individual.source];
+            individual.score = score;
+            individual.passed = passed;
+            individual.fitness =
+                score - self.spec.size_penalty * self.body_size(&individual.program) as f64;
+            if let Some(entry) = self.history.get_mut(&individual.id) {
+                entry.score = individual.score;
+                entry.fitness = individual.fitness;
+            }
+        }
+    }
+
+    /// 是否达到目标分数 / Whether the target score has been reached
+    fn reached_target(&self, best: &Individual) -> bool {
+        match self.spec.target_score {
+            Some(target) => best.score >= target,
+            None => !self.spec.test_cases.is_empty() && best.passed == self.spec.test_cases.len(),
+        }
+    }
+
+    /// 锦标赛选择 / Tournament selection
+    fn select<'a>(&mut self, population: &'a [Individual]) -> &'a Individual {
+        let mut winner = &population[self.mutator.rng().below(population.len())];
+        for _ in 1..self.spec.tournament_size.max(1) {
+            let entrant = &population[self.mutator.rng().below(population.len())];
+            if entrant.fitness > winner.fitness {
+                winner = entrant;
+            }
+        }
+        winner
+    }
+
+    /// 变异：在函数体内按概率加权应用一个算子，没有发生变异或超出大小限制时重试
+    /// Mutation: apply one probability-weighted operator to the function body, retrying when nothing changed or the size limit is exceeded
+    fn mutate(&mut self, parent: &Individual, generation: usize) -> Individual {
+        for _ in 0..8 {
+            let result = self
+                .mutator
+                .mutate_once(&parent.program, Some(&self.spec.entry));
+            if result.mutated() && self.within_limit(&result.ast) {
+                let operation = result.summary();
+                return self.create(result.ast, vec![parent.id], generation, &operation);
+            }
+        }
+        self.create(parent.program.clone(), vec![parent.id], generation, "copy")
+    }
+
+    /// 交叉：用另一个父个体的随机子树替换随机节点 / Crossover: replace a random node with a random subtree of the other parent
+    fn crossover(
+        &mut self,
+        parent: &Individual,
+        other: &Individual,
+        generation: usize,
+    ) -> Individual {
+        match self
+            .mutator
+            .crossover(&parent.program, &other.program, Some(&self.spec.entry))
+        {
+            Some(program) if self.within_limit(&program) => {
+                self.create(program, vec![parent.id, other.id], generation, "crossover")
+            }
+            _ => self.mutate(parent, generation),
+        }
+    }
+
+    /// 被进化函数的函数体节点数 / Node count of the evolved function's body
+    fn body_size(&self, program: &[GrammarElement]) -> usize {
+        match find_definition(program, &self.spec.entry).map(|index| &program[index]) {
+            Some(GrammarElement::List(items)) => items[3..]
+                .iter()
+                .filter_map(|item| match item {
+                    GrammarElement::Expr(expr) => Some(count_nodes(expr)),
+                    _ => None,
+                })
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    /// 程序是否在大小限制内 / Whether the program is within the size limit
+    fn within_limit(&self, program: &[GrammarElement]) -> bool {
+        self.body_size(program) <= self.spec.max_nodes
+    }
+
+    /// 创建个体并记录谱系 / Create an individual and record its lineage
+    fn create(
+        &mut self,
+        program: Vec<GrammarElement>,
+        parents: Vec<Uuid>,
+        generation: usize,
+        operation: &str,
+    ) -> Individual {
+        let id = Uuid::new_v4();
+        let source = unparse(&program);
+        self.history.insert(
+            id,
+            LineageEntry {
+                id,
+                parents,
+                generation,
+                operation: operation.to_string(),
+                source: source.clone(),
+                score: 0.0,
+                fitness: 0.0,
+            },
+        );
+        Individual {
+            id,
+            program,
+            source,
+            score: 0.0,
+            fitness: f64::NEG_INFINITY,
+            passed: 0,
+        }
+    }
+
+    /// 个体的全部祖先（含自身），按代数和产生顺序排列
+    /// All ancestors of an individual (itself included), ordered by generation and creation
+    fn lineage(&self, id: Uuid) -> Vec<LineageEntry> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![id];
+        let mut entries = Vec::new();
+        while let Some(id) = stack.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            if let Some(entry) = self.history.get(&id) {
+                stack.extend(entry.parents.iter().copied());
+                entries.push(entry.clone());
+            }
+        }
+        // 父个体总是早于子个体产生，同代内按拓扑顺序排列 / Parents are always created before children; order topologically within a generation
+        let mut ordered: Vec<LineageEntry> = Vec::with_capacity(entries.len());
+        entries.sort_by_key(|entry| entry.generation);
+        while !entries.is_empty() {
+            let ready = entries
+                .iter()
+                .position(|entry| {
+                    entry
+                        .parents
+                        .iter()
+                        .all(|parent| ordered.iter().any(|done| done.id == *parent))
+                })
+                .unwrap_or(0);
+            ordered.push(entries.remove(ready));
+        }
+        ordered
+    }
+}
+
+/// 通过的测试用例数 / Number of test cases passed
+fn passed_count(spec: &FitnessSpec, result: &SandboxResult) -> usize {
+    match &result.value {
+        Some(Value::List(outputs)) if !spec.test_cases.is_empty() => outputs
+            .iter()
+            .zip(&spec.test_cases)
+            .filter(|(output, case)| values_match(output, &case.expected))
+            .count(),
+        _ => 0,
+    }
+}
+
+/// 默认分数：每个通过的用例得1分，数值答案按误差得部分分（最多0.5），取平均
+/// Default score: each passing case scores 1 and numeric answers earn partial credit by error (up to 0.5), averaged
+fn test_score(spec: &FitnessSpec, result: &SandboxResult) -> f64 {
+    let Some(Value::List(outputs)) = &result.value else {
+        return 0.0;
+    };
+    let total: f64 = outputs
+        .iter()
+        .zip(&spec.test_cases)
+        .map(|(output, case)| {
+            if values_match(output, &case.expected) {
+                return 1.0;
+            }
+            match (as_number(output), as_number(&case.expected)) {
+                (Some(actual), Some(expected)) => 0.5 / (1.0 + (actual - expected).abs()),
+                _ => 0.0,
+            }
+        })
+        .sum();
+    total / spec.test_cases.len() as f64
+}
+
+/// 数值（整数或浮点数）/ Numeric value (integer or float)
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int(n) => Some(*n as f64),
+        Value::Float(x) if x.is_finite() => Some(*x),
+        _ => None,
+    }
+}
+
+/// 比较测试输出，整数和浮点数按数值比较 / Compare test output, integers and floats compared numerically
+fn values_match(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Float(a), Value::Float(b)) => (a - b).abs() <= 1e-9 * b.abs().max(1.0),
+        (Value::Int(a), Value::Float(b)) | (Value::Float(b), Value::Int(a)) => {
+            (*a as f64 - b).abs() <= 1e-9 * b.abs().max(1.0)
+        }
+        _ => actual == expected,
+    }
+}
@@ -2,8 +2,24 @@
 // 构建和维护进化知识图谱，支持进化预测和学习
 // Builds and maintains evolution knowledge graph, supports evolution prediction and learning
 
+use crate::evolution::corpus::Idiom;
+use crate::evolution::embedding::{Embedding, EmbeddingIndex};
+use crate::evolution::mutation::SeededRng;
 use crate::evolution::tracker::EvolutionEvent;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet, VecDeque};
+
+/// 模式观察者：挖掘出此前没有的模式时调用 / Pattern observer: called when mining finds a pattern not seen before
+pub trait PatternObserver: Send {
+    /// 发现新模式 / A new pattern was discovered
+    fn pattern_discovered(&mut self, pattern: &EvolutionPattern);
+}
+
+impl<F: FnMut(&EvolutionPattern) + Send> PatternObserver for F {
+    fn pattern_discovered(&mut self, pattern: &EvolutionPattern) {
+        self(pattern)
+    }
+}
 
 /// 进化知识图谱 / Evolution knowledge graph
 pub struct EvolutionKnowledgeGraph {
@@ -11,6 +27,10 @@ pub struct EvolutionKnowledgeGraph {
     graph: std::collections::HashMap<String, KnowledgeNode>,
     /// 模式挖掘器 / Pattern miner
     pattern_miner: PatternMiner,
+    /// 新模式的订阅者 / Subscribers to new patterns
+    observers: Vec<Box<dyn PatternObserver>>,
+    /// 规则的嵌入索引 / Embedding index of rules
+    rule_index: EmbeddingIndex,
 }
 
 impl EvolutionKnowledgeGraph {
@@ -19,12 +39,17 @@ impl EvolutionKnowledgeGraph {
         Self {
             graph: std::collections::HashMap::new(),
             pattern_miner: PatternMiner::new(),
+            observers: Vec::new(),
+            rule_index: EmbeddingIndex::new(),
         }
     }
 
     /// 从历史构建知识 / Build knowledge from history
     pub fn build_from_history(&mut self, history: &[EvolutionEvent]) {
         for event in history {
+            self.index_rules(&event.before_state.grammar_rules);
+            self.index_rules(&event.delta.added_rules);
+
             // 提取实体和关系 / Extract entities and relations
             let entities = self.extract_entities(event);
             let relations = self.extract_relations(event);
@@ -63,8 +88,21 @@ impl EvolutionKnowledgeGraph {
             }
         }
 
-        // 挖掘模式 / Mine patterns after building graph
-        let _ = self.pattern_miner.mine_from_graph(&self.graph);
+        // 挖掘模式，新发现的模式通知订阅者 / Mine patterns after building graph and notify subscribers of new ones
+        let known: HashSet<String> = self
+            .pattern_miner
+            .patterns
+            .iter()
+            .map(|pattern| pattern.id.clone())
+            .collect();
+        let mut discovered = self.pattern_miner.mine_from_graph(&self.graph);
+        discovered.retain(|pattern| !known.contains(&pattern.id));
+        discovered.sort_by(|a, b| a.id.cmp(&b.id));
+        for pattern in &discovered {
+            for observer in &mut self.observers {
+                observer.pattern_discovered(pattern);
+            }
+        }
     }
 
     /// 提取实体 / Extract entities
@@ -131,8 +169,19 @@ impl EvolutionKnowledgeGraph {
         relations
     }
 
-    /// 预测可能的进化 / Predict possible evolutions
+    /// 预测可能的进化（种子为0）/ Predict possible evolutions (with seed 0)
     pub fn predict_evolutions(&self, context: &EvolutionContext) -> Vec<EvolutionPrediction> {
+        self.predict_evolutions_with_seed(context, 0)
+    }
+
+    /// 预测可能的进化：置信度相同的预测按种子决定先后，相同种子和图谱总得到相同结果
+    /// Predict possible evolutions: the seed orders predictions of equal confidence, so the same seed and graph
+    /// always give the same result
+    pub fn predict_evolutions_with_seed(
+        &self,
+        context: &EvolutionContext,
+        seed: u64,
+    ) -> Vec<EvolutionPrediction> {
         let mut predictions = Vec::new();
 
         // 基于历史模式进行预测 / Predict based on historical patterns
@@ -162,6 +211,43 @@ impl EvolutionKnowledgeGraph {
             }
         }
 
+        // 基于语料惯用法的预测 / Predict based on corpus idioms
+        for idiom in self.idioms() {
+            let matched_goal = context
+                .goals
+                .iter()
+                .find(|goal| idiom.shape.contains(goal.as_str()) || idiom.kind == **goal);
+            if let Some(goal) = matched_goal {
+                predictions.push(EvolutionPrediction {
+                    predicted_evolution: format!("以惯用法 `{}` 实现目标", idiom.shape),
+                    confidence: (0.5 + idiom.frequency as f64 / 20.0).min(0.9),
+                    reasoning: format!(
+                        "目标 '{}' 与用户代码中出现 {} 次的惯用法匹配，例如 {}",
+                        goal, idiom.frequency, idiom.example
+                    ),
+                });
+            } else if idiom.file_count() > 1 {
+                predictions.push(EvolutionPrediction {
+                    predicted_evolution: format!(
+                        "将惯用法 `{}` 抽象为语法规则或库函数",
+                        idiom.shape
+                    ),
+                    confidence: (idiom.frequency as f64 / 20.0).min(0.8),
+                    reasoning: format!(
+                        "该惯用法在 {} 个文件中出现 {} 次",
+                        idiom.file_count(),
+                        idiom.frequency
+                    ),
+                });
+            }
+        }
+
+        // 先消除图谱遍历顺序的影响，再按种子打乱，稳定排序后同置信度的预测保持打乱后的顺序
+        // First remove the graph's iteration order, then shuffle by seed; the stable sort keeps the shuffled order
+        // among predictions of equal confidence
+        predictions.sort_by(|a, b| a.predicted_evolution.cmp(&b.predicted_evolution));
+        SeededRng::new(seed).shuffle(&mut predictions);
+
         // 按置信度排序 / Sort by confidence
         predictions.sort_by(|a, b| {
             b.confidence
@@ -173,57 +259,15 @@ impl EvolutionKnowledgeGraph {
         predictions
     }
 
-    /// 计算规则相似度 / Calculate rule similarity
+    /// 计算规则相似度：规则嵌入向量的余弦相似度 / Calculate rule similarity: cosine similarity of rule embeddings
     fn calculate_rule_similarity(
         &self,
         rule1: &crate::grammar::rule::GrammarRule,
         rule2: &crate::grammar::rule::GrammarRule,
     ) -> f64 {
-        let mut similarity = 0.0;
-        let mut factors = 0;
-
-        // 名称相似度 / Name similarity
-        if rule1.name == rule2.name {
-            similarity += 1.0;
-        } else if rule1.name.contains(&rule2.name) || rule2.name.contains(&rule1.name) {
-            similarity += 0.6;
-        } else {
-            // 简单的编辑距离相似度 / Simple edit distance similarity
-            let name_sim = self.string_similarity(&rule1.name, &rule2.name);
-            similarity += name_sim * 0.4;
-        }
-        factors += 1;
-
-        // 模式相似度 / Pattern similarity
-        if rule1.pattern.elements.len() == rule2.pattern.elements.len() {
-            let mut pattern_match = 0;
-            for (e1, e2) in rule1
-                .pattern
-                .elements
-                .iter()
-                .zip(rule2.pattern.elements.iter())
-            {
-                if self.pattern_elements_similar(e1, e2) {
-                    pattern_match += 1;
-                }
-            }
-            if rule1.pattern.elements.len() > 0 {
-                similarity += (pattern_match as f64 / rule1.pattern.elements.len() as f64) * 0.4;
-            }
-        }
-        factors += 1;
-
-        // 产生式相似度 / Production similarity
-        let prod_sim = self.production_similarity(&rule1.production, &rule2.production);
-        similarity += prod_sim * 0.2;
-        factors += 1;
-
-        // 归一化 / Normalize
-        if factors > 0 {
-            similarity / factors as f64
-        } else {
-            0.0
-        }
+        Embedding::of_rule(rule1)
+            .cosine(&Embedding::of_rule(rule2))
+            .max(0.0)
     }
 
     /// 字符串相似度（简单的编辑距离） / String similarity (simple edit distance)
@@ -245,47 +289,41 @@ impl EvolutionKnowledgeGraph {
         }
     }
 
-    /// 模式元素相似度 / Pattern element similarity
-    fn pattern_elements_similar(
-        &self,
-        e1: &crate::grammar::rule::PatternElement,
-        e2: &crate::grammar::rule::PatternElement,
-    ) -> bool {
-        match (e1, e2) {
-            (
-                crate::grammar::rule::PatternElement::Keyword(k1),
-                crate::grammar::rule::PatternElement::Keyword(k2),
-            ) => k1 == k2,
-            (
-                crate::grammar::rule::PatternElement::Identifier(i1),
-                crate::grammar::rule::PatternElement::Identifier(i2),
-            ) => i1 == i2,
-            (
-                crate::grammar::rule::PatternElement::NaturalLang(n1),
-                crate::grammar::rule::PatternElement::NaturalLang(n2),
-            ) => n1 == n2 || self.string_similarity(n1, n2) > 0.7,
-            _ => false,
-        }
-    }
-
-    /// 产生式相似度 / Production similarity
-    fn production_similarity(
-        &self,
-        p1: &crate::grammar::rule::Production,
-        p2: &crate::grammar::rule::Production,
-    ) -> f64 {
-        // 简化：比较目标结构 / Simplified: compare target structure
-        let t1 = serde_json::to_string(&p1.target).unwrap_or_default();
-        let t2 = serde_json::to_string(&p2.target).unwrap_or_default();
-        if t1 == t2 {
-            1.0
-        } else {
-            self.string_similarity(&t1, &t2) * 0.5
+    /// 索引语法规则，之后可按嵌入向量检索相似规则；同名规则被替换
+    /// Index grammar rules so similar ones can be found by embedding; a rule with the same name is replaced
+    pub fn index_rules(&mut self, rules: &[crate::grammar::rule::GrammarRule]) {
+        for rule in rules {
+            self.rule_index
+                .insert(format!("rule:{}", rule.name), Embedding::of_rule(rule));
         }
     }
 
-    /// 查找相似实体 / Find similar entities
+    /// 与给定规则最相似的已索引规则（实体ID和余弦相似度），规则本身已索引时不包括它
+    /// Indexed rules most similar to a rule (entity id and cosine similarity), excluding the rule itself when indexed
+    pub fn similar_rules(
+        &self,
+        rule: &crate::grammar::rule::GrammarRule,
+        limit: usize,
+        threshold: f64,
+    ) -> Vec<(String, f64)> {
+        let id = format!("rule:{}", rule.name);
+        let mut similar = self
+            .rule_index
+            .search(&Embedding::of_rule(rule), limit + 1, threshold);
+        similar.retain(|(other, _)| *other != id);
+        similar.truncate(limit);
+        similar
+    }
+
+    /// 查找相似实体；已索引的规则按嵌入向量与其他规则比较
+    /// Find similar entities; indexed rules are compared with other rules by embedding
     pub fn find_similar_entities(&self, entity_id: &str, threshold: f64) -> Vec<(String, f64)> {
+        if self.rule_index.get(entity_id).is_some() {
+            return self
+                .rule_index
+                .similar_to(entity_id, self.rule_index.len(), threshold);
+        }
+
         let mut similar = Vec::new();
 
         if let Some(node) = self.graph.get(entity_id) {
@@ -352,6 +390,84 @@ impl EvolutionKnowledgeGraph {
         self.pattern_miner.patterns.len()
     }
 
+    /// 记录语料中的惯用法；同一文件再次挖掘时替换该文件的计数
+    /// Record a corpus idiom; mining the same file again replaces that file's counts
+    pub fn record_idiom(&mut self, idiom: &Idiom) {
+        let id = format!("idiom:{}", idiom.shape);
+        let node = self.graph.entry(id.clone()).or_insert_with(|| {
+            let mut n = KnowledgeNode::new(id);
+            n.node_type = NodeType::Idiom;
+            n.attributes
+                .insert("kind".to_string(), serde_json::json!(idiom.kind));
+            n.attributes
+                .insert("example".to_string(), serde_json::json!(idiom.example));
+            n
+        });
+        let occurrences = node
+            .attributes
+            .entry("occurrences".to_string())
+            .or_insert_with(|| serde_json::json!({}));
+        if let Some(occurrences) = occurrences.as_object_mut() {
+            for (file, count) in &idiom.occurrences {
+                occurrences.insert(file.clone(), serde_json::json!(count));
+            }
+        }
+        let frequency: u64 = occurrences
+            .as_object()
+            .map(|occurrences| occurrences.values().filter_map(|c| c.as_u64()).sum())
+            .unwrap_or(0);
+        node.attributes
+            .insert("frequency".to_string(), serde_json::json!(frequency));
+    }
+
+    /// 已记录的惯用法，按频率降序 / Recorded idioms by descending frequency
+    pub fn idioms(&self) -> Vec<Idiom> {
+        let mut idioms: Vec<Idiom> = self
+            .graph
+            .values()
+            .filter(|node| node.node_type == NodeType::Idiom)
+            .map(|node| {
+                let text = |key: &str| {
+                    node.attributes
+                        .get(key)
+                        .and_then(|value| value.as_str())
+                        .unwrap_or_default()
+                        .to_string()
+                };
+                let occurrences: std::collections::BTreeMap<String, usize> = node
+                    .attributes
+                    .get("occurrences")
+                    .and_then(|value| value.as_object())
+                    .map(|occurrences| {
+                        occurrences
+                            .iter()
+                            .map(|(file, count)| {
+                                (file.clone(), count.as_u64().unwrap_or(0) as usize)
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Idiom {
+                    shape: node
+                        .id
+                        .strip_prefix("idiom:")
+                        .unwrap_or(&node.id)
+                        .to_string(),
+                    kind: text("kind"),
+                    frequency: occurrences.values().sum(),
+                    occurrences,
+                    example: text("example"),
+                }
+            })
+            .collect();
+        idioms.sort_by(|a, b| {
+            b.frequency
+                .cmp(&a.frequency)
+                .then_with(|| a.shape.cmp(&b.shape))
+        });
+        idioms
+    }
+
     /// 添加实体和关系 / Add entities and relations
     pub fn add_entities_and_relations(&mut self, entities: &[String], relations: &[Relation]) {
         // 添加实体节点 / Add entity nodes
@@ -391,6 +507,225 @@ impl EvolutionKnowledgeGraph {
     }
 }
 
+/// 查询与导出 / Queries and export
+impl EvolutionKnowledgeGraph {
+    /// 订阅新发现的模式 / Subscribe to newly discovered patterns
+    pub fn subscribe(&mut self, observer: Box<dyn PatternObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// 已发现的模式 / Discovered patterns
+    pub fn patterns(&self) -> &[EvolutionPattern] {
+        &self.pattern_miner.patterns
+    }
+
+    /// 按ID查找节点 / Find a node by id
+    pub fn node(&self, id: &str) -> Option<&KnowledgeNode> {
+        self.graph.get(id)
+    }
+
+    /// 某种类型的实体，按ID排序 / Entities of one kind, sorted by id
+    pub fn find_entities(&self, kind: NodeType) -> Vec<&KnowledgeNode> {
+        let mut nodes: Vec<&KnowledgeNode> = self
+            .graph
+            .values()
+            .filter(|node| node.node_type == kind)
+            .collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        nodes
+    }
+
+    /// 节点出发的关系，`relation` 为None时不限类型；同一目标和类型只保留最大权重，按权重降序
+    /// Relations leaving a node, of any type when `relation` is None; one per target and type with the largest
+    /// weight, by weight descending
+    pub fn neighbors(&self, id: &str, relation: Option<RelationType>) -> Vec<Relation> {
+        let mut neighbors: Vec<Relation> = self
+            .graph
+            .get(id)
+            .map(node_relations)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|rel| {
+                relation
+                    .as_ref()
+                    .is_none_or(|kind| rel.relation_type == *kind)
+            })
+            .collect();
+        neighbors.sort_by(|a, b| {
+            b.weight
+                .partial_cmp(&a.weight)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.to.cmp(&b.to))
+        });
+        neighbors
+    }
+
+    /// 全部关系，同一两端和类型只保留最大权重，按源、目标排序 / All relations, one per endpoints and type with the largest weight, sorted by source and target
+    pub fn relations(&self) -> Vec<Relation> {
+        let mut relations: Vec<Relation> = self.graph.values().flat_map(node_relations).collect();
+        relations.sort_by(|a, b| {
+            a.from
+                .cmp(&b.from)
+                .then_with(|| a.to.cmp(&b.to))
+                .then_with(|| relation_name(&a.relation_type).cmp(relation_name(&b.relation_type)))
+        });
+        relations
+    }
+
+    /// 两个节点之间跳数最少的路径（忽略关系方向），含两端；不连通时为None
+    /// Path with the fewest hops between two nodes (ignoring relation direction), including both ends; None when
+    /// they are not connected
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        if !self.graph.contains_key(from) || !self.graph.contains_key(to) {
+            return None;
+        }
+        let mut adjacent: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for relation in self.relations() {
+            adjacent
+                .entry(relation.to.clone())
+                .or_default()
+                .push(relation.from.clone());
+            adjacent.entry(relation.from).or_default().push(relation.to);
+        }
+
+        let mut previous: BTreeMap<String, String> = BTreeMap::new();
+        let mut queue = VecDeque::from([from.to_string()]);
+        let mut visited = HashSet::from([from.to_string()]);
+        while let Some(current) = queue.pop_front() {
+            if current == to {
+                let mut path = vec![current];
+                while let Some(step) = previous.get(path.last()?) {
+                    path.push(step.clone());
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in adjacent.get(&current).into_iter().flatten() {
+                if visited.insert(next.clone()) {
+                    previous.insert(next.clone(), current.clone());
+                    queue.push_back(next.clone());
+                }
+            }
+        }
+        None
+    }
+
+    /// 导出为JSON：节点（不含关系属性）和关系 / Export as JSON: nodes (without the relations attribute) and relations
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut nodes: Vec<&KnowledgeNode> = self.graph.values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        let nodes: Vec<serde_json::Value> = nodes
+            .into_iter()
+            .map(|node| {
+                let attributes: BTreeMap<&String, &serde_json::Value> = node
+                    .attributes
+                    .iter()
+                    .filter(|(key, _)| key.as_str() != "relations")
+                    .collect();
+                serde_json::json!({
+                    "id": node.id,
+                    "type": node.node_type,
+                    "attributes": attributes,
+                    "events": node.events,
+                })
+            })
+            .collect();
+        serde_json::json!({
+            "nodes": nodes,
+            "relations": self.relations(),
+            "patterns": self.patterns(),
+        })
+    }
+
+    /// 导出为GraphML，可用 Gephi、yEd 等工具查看 / Export as GraphML for tools such as Gephi and yEd
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"type\" for=\"node\" attr.name=\"type\" attr.type=\"string\"/>\n",
+            "  <key id=\"events\" for=\"node\" attr.name=\"events\" attr.type=\"int\"/>\n",
+            "  <key id=\"relation\" for=\"edge\" attr.name=\"relation\" attr.type=\"string\"/>\n",
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"double\"/>\n",
+            "  <graph id=\"knowledge\" edgedefault=\"directed\">\n",
+        ));
+        let mut nodes: Vec<&KnowledgeNode> = self.graph.values().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        for node in nodes {
+            out.push_str(&format!(
+                "    <node id=\"{}\"><data key=\"type\">{:?}</data><data key=\"events\">{}</data></node>\n",
+                escape_xml(&node.id),
+                node.node_type,
+                node.events.len()
+            ));
+        }
+        for relation in self.relations() {
+            // GraphML要求边的两端都是已声明的节点 / GraphML requires both ends of an edge to be declared nodes
+            if !self.graph.contains_key(&relation.to) {
+                continue;
+            }
+            out.push_str(&format!(
+                "    <edge source=\"{}\" target=\"{}\"><data key=\"relation\">{}</data><data key=\"weight\">{}</data></edge>\n",
+                escape_xml(&relation.from),
+                escape_xml(&relation.to),
+                relation_name(&relation.relation_type),
+                relation.weight
+            ));
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+/// 节点属性中保存的关系，同一目标和类型只保留最大权重 / Relations stored in a node's attributes, one per target and type with the largest weight
+fn node_relations(node: &KnowledgeNode) -> Vec<Relation> {
+    let mut relations: Vec<Relation> = Vec::new();
+    let stored = node
+        .attributes
+        .get("relations")
+        .and_then(|value| value.as_array());
+    for value in stored.into_iter().flatten() {
+        let (Some(to), Some(relation_type)) = (
+            value.get("to").and_then(|to| to.as_str()),
+            value
+                .get("type")
+                .and_then(|kind| kind.as_str())
+                .and_then(RelationType::from_name),
+        ) else {
+            continue;
+        };
+        let weight = value.get("weight").and_then(|w| w.as_f64()).unwrap_or(0.0);
+        match relations
+            .iter_mut()
+            .find(|rel| rel.to == to && rel.relation_type == relation_type)
+        {
+            Some(existing) => existing.weight = existing.weight.max(weight),
+            None => relations.push(Relation {
+                from: node.id.clone(),
+                to: to.to_string(),
+                relation_type,
+                weight,
+            }),
+        }
+    }
+    relations
+}
+
+fn relation_name(kind: &RelationType) -> &'static str {
+    match kind {
+        RelationType::EvolvedFrom => "EvolvedFrom",
+        RelationType::Influences => "Influences",
+        RelationType::Similar => "Similar",
+        RelationType::Conflicts => "Conflicts",
+    }
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
 impl Default for EvolutionKnowledgeGraph {
     fn default() -> Self {
         Self::new()
@@ -438,6 +773,8 @@ pub enum NodeType {
     User,
     /// 上下文 / Context
     Context,
+    /// 语料惯用法 / Corpus idiom
+    Idiom,
 }
 
 /// 关系 / Relation
@@ -466,6 +803,19 @@ pub enum RelationType {
     Conflicts,
 }
 
+impl RelationType {
+    /// 按名称解析（与存储和导出使用的名称相同）/ Parse by name (the name used for storage and export)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "EvolvedFrom" => Some(Self::EvolvedFrom),
+            "Influences" => Some(Self::Influences),
+            "Similar" => Some(Self::Similar),
+            "Conflicts" => Some(Self::Conflicts),
+            _ => None,
+        }
+    }
+}
+
 /// 模式挖掘器 / Pattern miner
 pub struct PatternMiner {
     /// 发现的模式 / Discovered patterns
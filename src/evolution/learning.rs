@@ -2,8 +2,10 @@
 // 从使用模式和错误中学习，改进语言能力
 // Learn from usage patterns and errors to improve language capabilities
 
+use crate::runtime::trace::UsageObserver;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 /// 使用模式学习器 / Usage pattern learner
 pub struct UsagePatternLearner {
@@ -13,6 +15,8 @@ pub struct UsagePatternLearner {
     error_patterns: HashMap<String, Vec<ErrorPattern>>,
     /// 成功模式统计 / Success pattern statistics
     success_patterns: HashMap<String, Vec<SuccessPattern>>,
+    /// 各用户函数中发生未捕获错误的次数 / Number of uncaught errors raised inside each user function
+    function_failures: HashMap<String, usize>,
 }
 
 /// 错误模式 / Error pattern
@@ -50,6 +54,7 @@ impl UsagePatternLearner {
             usage_frequency: HashMap::new(),
             error_patterns: HashMap::new(),
             success_patterns: HashMap::new(),
+            function_failures: HashMap::new(),
         }
     }
 
@@ -85,6 +90,26 @@ impl UsagePatternLearner {
             });
     }
 
+    /// 记录未捕获错误发生所在的函数 / Record the function an uncaught error happened in
+    pub fn record_function_failure(&mut self, function: &str) {
+        *self
+            .function_failures
+            .entry(function.to_string())
+            .or_insert(0) += 1;
+    }
+
+    /// 失败最多的函数及失败次数 / Functions that fail most, with their failure counts
+    pub fn get_failing_functions(&self, limit: usize) -> Vec<(String, usize)> {
+        let mut functions: Vec<(String, usize)> = self
+            .function_failures
+            .iter()
+            .map(|(function, &count)| (function.clone(), count))
+            .collect();
+        functions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        functions.truncate(limit);
+        functions
+    }
+
     /// 记录成功 / Record success
     pub fn record_success(&mut self, description: &str, code: &str) {
         // 先检查是否存在 / Check if exists first
@@ -137,6 +162,11 @@ impl UsagePatternLearner {
         patterns
     }
 
+    /// 某个模式的使用次数 / Usage count of a pattern
+    pub fn usage_count(&self, pattern: &str) -> usize {
+        self.usage_frequency.get(pattern).copied().unwrap_or(0)
+    }
+
     /// 获取常见错误 / Get common errors
     pub fn get_common_errors(&self, limit: usize) -> Vec<ErrorPattern> {
         let mut all_errors: Vec<ErrorPattern> = self
@@ -177,6 +207,17 @@ impl UsagePatternLearner {
             }
         }
 
+        for (function, count) in self.get_failing_functions(10) {
+            if count > 3 {
+                insights.push(LearningInsight {
+                    insight_type: InsightType::ErrorPrevention,
+                    description: format!("经常失败的函数: {} (失败{}次)", function, count),
+                    suggestion: Some(format!("为 {} 添加参数检查或 try/catch 错误处理", function)),
+                    priority: count,
+                });
+            }
+        }
+
         insights.sort_by(|a, b| b.priority.cmp(&a.priority));
         insights
     }
@@ -244,6 +285,7 @@ impl UsagePatternLearner {
             } else {
                 0.0
             },
+            failing_functions: self.get_failing_functions(5),
         }
     }
 }
@@ -254,6 +296,45 @@ impl Default for UsagePatternLearner {
     }
 }
 
+/// 可共享的学习器句柄：安装到解释器后自动记录执行过的构造、运行时错误（及其所在函数）和成功模式
+/// Shareable learner handle; installed in an interpreter, it records executed constructs, runtime errors (with the
+/// function they happened in) and successful patterns automatically
+#[derive(Clone, Default)]
+pub struct LearningHandle(Arc<Mutex<UsagePatternLearner>>);
+
+impl LearningHandle {
+    /// 创建持有新学习器的句柄 / Create a handle holding a new learner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 锁定学习器 / Lock the learner
+    pub fn lock(&self) -> MutexGuard<'_, UsagePatternLearner> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl UsageObserver for LearningHandle {
+    fn constructs(&mut self, kinds: &[&str]) {
+        let mut learner = self.lock();
+        for kind in kinds {
+            learner.record_usage(kind);
+        }
+    }
+
+    fn success(&mut self, pattern: &str, code: &str) {
+        self.lock().record_success(pattern, code);
+    }
+
+    fn error(&mut self, kind: &str, message: &str, code: &str) {
+        self.lock().record_error(kind, message, code);
+    }
+
+    fn failed_in(&mut self, function: &str) {
+        self.lock().record_function_failure(function);
+    }
+}
+
 /// 学习洞察 / Learning insight
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LearningInsight {
@@ -295,4 +376,6 @@ pub struct UsageStatistics {
     pub error_rate: f64,
     /// 成功率 / Success rate
     pub success_rate: f64,
+    /// 失败最多的函数（最多5个）及失败次数 / Functions that fail most (up to 5) with their failure counts
+    pub failing_functions: Vec<(String, usize)>,
 }
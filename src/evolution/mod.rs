@@ -7,25 +7,42 @@
 //!
 //! ### 核心组件 (Core Components)
 //! - `engine.rs` - **引擎核心** - 主入口: `EvolutionEngine::new()`, `start_evolution()`, `self_evolve()`
+//! - `genetic.rs` - **遗传编程** - 按适应度进化程序: `EvolutionEngine::evolve_program()`
+//! - `mutation.rs` - **变异算子** - 可组合、可设种子的AST变异: `Mutator::mutate()`
+//! - `sandbox.rs` - **沙箱批量执行** - 并行评估候选程序: `SandboxRunner::run_code()`
 //! - `knowledge.rs` - **知识图谱** - 实体提取、关系挖掘、模式发现
+//! - `corpus.rs` - **语料模式挖掘** - 从 .evo 文件目录中挖掘惯用法及其频率: `EvolutionEngine::learn_from_corpus()`
 //! - `tracker.rs` - **进化历史追踪** - 事件记录、谱系树、回滚机制
+//! - `genealogy_report.rs` - **谱系报告** - 进化事件、规则差异和受影响程序导出为可交互HTML或DOT: `EvolutionEngine::export_report()`
+//! - `evaluation.rs` - **规则A/B评估** - 比较带/不带候选规则的解析与执行结果: `RuleEvaluator::evaluate()`
+//! - `conflicts.rs` - **规则冲突检测** - 安装前找出共用关键字、歧义和被覆盖的核心语法，附解析不同的示例: `ConflictDetector::check()`
+//! - `verification.rs` - **进化验证关卡** - 进化后运行回归语料，通过率低于阈值时回滚: `VerificationGate::verify()`
+//! - `configurator.rs` - **运行时配置器** - 事务式应用进化，回滚时恢复解析器和解释器: `RuntimeConfigurator::apply()`
 //!
 //! ### 分析工具 (Analysis Tools)
 //! - `analyzer.rs` - **代码分析器** - 模式识别、复杂度分析
+//! - `call_graph.rs` - **调用图** - 函数调用关系、不可达函数与未使用的变量和参数: `CallGraph::build()`, `UnusedCode::find()`
 //! - `learning.rs` - **使用模式学习** - 使用频率跟踪、错误模式学习
-//! - `similarity.rs` - **相似度检测** - 代码重复检测、重构建议
-//! - `dependency.rs` - **依赖分析** - 依赖图构建、循环依赖检测
+//! - `similarity.rs` - **相似度检测** - 代码重复检测、跨文件函数克隆检测、重构建议
+//! - `embedding.rs` - **嵌入向量检索** - 规则和AST的特征向量、余弦相似度、局部敏感哈希索引: `EmbeddingIndex::search()`
+//! - `dependency.rs` - **依赖分析** - 依赖图构建、循环依赖检测、跨文件项目依赖图（DOT/JSON导出）
+//! - `security.rs` - **安全分析** - 不受信任的文件/网络访问、无界递归和循环、无预算执行、宽松导入: `SecurityScan::scan()`
+//! - `purity.rs` - **纯度分析** - 函数副作用经调用图传递、对非纯函数的 `memoize`: `PurityAnalysis::analyze()`
 //!
 //! ### 代码生成和优化 (Code Generation & Optimization)
 //! - `code_generator.rs` - **智能代码生成** - 基于意图生成代码、代码补全
 //! - `optimizer.rs` - **优化建议器** - 优化策略、效果预测
 //! - `error_recovery.rs` - **错误恢复** - 自动修复常见错误
+//! - `error_report.rs` - **错误报告** - 运行时错误的源码行与插入符、调用栈和修复建议，可带颜色: `ErrorReport::render()`
+//! - `fix_engine.rs` - **自动修复引擎** - 以范围/AST编辑应用修复，重新解析验证并输出diff: `FixEngine::apply()`
 //!
 //! ### 质量评估 (Quality Assessment)
 //! - `quality_assessor.rs` - **代码质量评估** - 多维度评估、改进建议
 //! - `code_reviewer.rs` - **代码审查** - 自动问题检测、审查报告
+//! - `report.rs` - **报告导出** - 分析、质量和审查结果导出为JSON/SARIF: `ReportExport::to_sarif()`
+//! - `project.rs` - **项目批量分析** - 汇总目录中所有文件的分析、质量、审查、依赖和克隆，按阈值判定: `ProjectAnalyzer::analyze()`
 //! - `performance.rs` - **性能分析** - 性能瓶颈识别、优化建议
-//! - `doc_generator.rs` - **文档生成** - 自动生成代码文档
+//! - `doc_generator.rs` - **文档生成** - 自动生成代码文档、跨模块链接的HTML站点
 //! - `test_generator.rs` - **测试生成** - 自动生成测试用例
 //!
 //! ## 依赖关系 / Dependencies
@@ -38,35 +55,69 @@
 //! ```
 
 pub mod analyzer;
+pub mod call_graph;
 pub mod code_generator;
 pub mod code_reviewer;
+pub mod configurator;
+pub mod conflicts;
+pub mod corpus;
 pub mod dependency;
 pub mod doc_generator;
+pub mod embedding;
 pub mod engine;
 pub mod error_recovery;
+pub mod error_report;
+pub mod evaluation;
 pub mod event_manager;
+pub mod fix_engine;
+pub mod genealogy_report;
+pub mod genetic;
 pub mod knowledge;
 pub mod learning;
+pub mod mutation;
 pub mod optimizer;
 pub mod performance;
+pub mod project;
+pub mod purity;
 pub mod quality_assessor;
+pub mod report;
+pub mod sandbox;
+pub mod security;
 pub mod similarity;
 pub mod test_generator;
 pub mod tracker;
+pub mod verification;
 
 pub use analyzer::*;
+pub use call_graph::*;
 pub use code_generator::*;
 pub use code_reviewer::*;
+pub use configurator::*;
+pub use conflicts::*;
+pub use corpus::*;
 pub use dependency::*;
 pub use doc_generator::*;
+pub use embedding::*;
 pub use engine::*;
 pub use error_recovery::*;
+pub use error_report::*;
+pub use evaluation::*;
 pub use event_manager::*;
+pub use fix_engine::*;
+pub use genealogy_report::*;
+pub use genetic::*;
 pub use knowledge::*;
 pub use learning::*;
+pub use mutation::*;
 pub use optimizer::*;
 pub use performance::*;
+pub use project::*;
+pub use purity::*;
 pub use quality_assessor::*;
+pub use report::*;
+pub use sandbox::*;
+pub use security::*;
 pub use similarity::*;
 pub use test_generator::*;
 pub use tracker::*;
+pub use verification::*;
@@ -0,0 +1,887 @@
+// 变异算子库 / Mutation operator library
+// 可组合的AST变异算子，每个算子有独立的触发概率，随机性来自可设种子的生成器，进化运行可复现；
+// 变异不保证语义不变，记录每处改动（改动前后的源码）
+// Composable AST mutation operators, each with its own firing probability; randomness comes from a seedable
+// generator so evolution runs are reproducible. Mutations do not preserve semantics; every change is recorded
+// (source before and after)
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::grammar::core::{Expr, GrammarElement, Literal};
+use crate::grammar::unparse::{unparse_element, unparse_expr};
+use crate::grammar::visit::{
+    self, count_nodes, expr_children, expr_children_mut, preorder, walk_expr_mut, Position,
+};
+
+/// 变异算子种类 / Kind of mutation operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationKind {
+    /// 常数扰动：整数和浮点数小幅增减或换成新的小整数，布尔值取反
+    /// Constant perturbation: nudge integers and floats or replace them with a fresh small integer, flip booleans
+    ConstantPerturbation,
+    /// 运算符替换：在同类运算符（算术或比较）之间替换
+    /// Operator substitution: swap among operators of the same kind (arithmetic or comparison)
+    OperatorSubstitution,
+    /// 子树交换：交换同一表达式中两棵不相交的子树 / Subtree swap: exchange two disjoint subtrees of the same expression
+    SubtreeSwap,
+    /// 子树替换：用参数、小整数和算术组成的随机表达式替换子树
+    /// Subtree replacement: replace a subtree with a random expression of parameters, small integers and arithmetic
+    SubtreeReplacement,
+    /// 提升：用节点的某个子表达式替换该节点 / Hoist: replace a node with one of its subexpressions
+    Hoist,
+    /// 函数内联：把对程序中非递归单表达式函数的调用替换为代入参数后的函数体
+    /// Function inlining: replace a call to a non-recursive single-expression function of the program with its body, arguments substituted
+    FunctionInlining,
+    /// 函数提取：把函数体中的子表达式提取为新函数，原处改为调用
+    /// Function extraction: move a subexpression of a function body into a new function and call it in place
+    FunctionExtraction,
+}
+
+impl MutationKind {
+    /// 全部算子种类 / All operator kinds
+    pub fn all() -> Vec<MutationKind> {
+        vec![
+            MutationKind::ConstantPerturbation,
+            MutationKind::OperatorSubstitution,
+            MutationKind::SubtreeSwap,
+            MutationKind::SubtreeReplacement,
+            MutationKind::Hoist,
+            MutationKind::FunctionInlining,
+            MutationKind::FunctionExtraction,
+        ]
+    }
+
+    /// 名称（与序列化形式相同）/ Name (same as the serialized form)
+    pub fn name(&self) -> &'static str {
+        match self {
+            MutationKind::ConstantPerturbation => "constant_perturbation",
+            MutationKind::OperatorSubstitution => "operator_substitution",
+            MutationKind::SubtreeSwap => "subtree_swap",
+            MutationKind::SubtreeReplacement => "subtree_replacement",
+            MutationKind::Hoist => "hoist",
+            MutationKind::FunctionInlining => "function_inlining",
+            MutationKind::FunctionExtraction => "function_extraction",
+        }
+    }
+
+    /// 默认触发概率 / Default firing probability
+    pub fn default_probability(&self) -> f64 {
+        match self {
+            MutationKind::ConstantPerturbation => 0.3,
+            MutationKind::OperatorSubstitution => 0.2,
+            MutationKind::SubtreeSwap => 0.1,
+            MutationKind::SubtreeReplacement => 0.2,
+            MutationKind::Hoist => 0.1,
+            MutationKind::FunctionInlining => 0.05,
+            MutationKind::FunctionExtraction => 0.05,
+        }
+    }
+}
+
+/// 带触发概率的变异算子 / Mutation operator with its firing probability
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MutationOperator {
+    /// 算子种类 / Operator kind
+    pub kind: MutationKind,
+    /// 每次变异时触发的概率 / Probability of firing on each mutation
+    pub probability: f64,
+}
+
+impl MutationOperator {
+    /// 创建算子 / Create operator
+    pub fn new(kind: MutationKind, probability: f64) -> Self {
+        Self { kind, probability }
+    }
+
+    /// 全部算子，使用默认概率 / All operators with their default probabilities
+    pub fn defaults() -> Vec<MutationOperator> {
+        MutationKind::all()
+            .into_iter()
+            .map(|kind| Self::new(kind, kind.default_probability()))
+            .collect()
+    }
+}
+
+/// 一处变异 / One mutation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MutationRecord {
+    /// 应用的算子 / Operator applied
+    pub kind: MutationKind,
+    /// 所在顶层形式的索引（变异前）/ Index of the enclosing top-level form (before the mutation)
+    pub form: usize,
+    /// 变异前的源码 / Source before the mutation
+    pub before: String,
+    /// 变异后的源码 / Source after the mutation
+    pub after: String,
+}
+
+/// 变异结果：变异后的AST和变异列表 / Mutation result: the mutated AST and the list of mutations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MutationResult {
+    /// 变异后的AST / Mutated AST
+    pub ast: Vec<GrammarElement>,
+    /// 按应用顺序的变异 / Mutations in the order they were applied
+    pub mutations: Vec<MutationRecord>,
+}
+
+impl MutationResult {
+    /// 是否发生了变异 / Whether anything was mutated
+    pub fn mutated(&self) -> bool {
+        !self.mutations.is_empty()
+    }
+
+    /// 应用的算子名，以 `+` 连接 / Names of the operators applied, joined with `+`
+    pub fn summary(&self) -> String {
+        self.mutations
+            .iter()
+            .map(|mutation| mutation.kind.name())
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+}
+
+/// 可设种子的xorshift随机数生成器 / Seedable xorshift random number generator
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// 以种子创建，相同种子产生相同序列 / Create from a seed; the same seed yields the same sequence
+    pub fn new(seed: u64) -> Self {
+        // 状态不能为0 / State must be non-zero
+        let state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        Self {
+            state: if state == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                state
+            },
+        }
+    }
+
+    /// 下一个随机数 / Next random number
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// [0, n) 内的随机数（n为0时返回0）/ Random number in [0, n) (0 when n is 0)
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+
+    /// [0, 1) 内的随机浮点数 / Random float in [0, 1)
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// 以概率p返回true / Return true with probability p
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.unit() < p
+    }
+
+    /// 就地打乱切片（Fisher-Yates）/ Shuffle a slice in place (Fisher-Yates)
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+
+    /// 由种子和标签派生子种子，不同标签得到互不相关的序列，相同输入总得到相同结果
+    /// Derive a sub-seed from a seed and a label: different labels give unrelated sequences, and the same
+    /// inputs always give the same result
+    pub fn derive(seed: u64, label: &str) -> u64 {
+        // FNV-1a 散列标签 / FNV-1a hash of the label
+        let hash = label.bytes().fold(0xCBF2_9CE4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01B3)
+        });
+        Self::new(seed ^ hash).next_u64()
+    }
+}
+
+/// 变异器：按各算子的概率依次尝试变异 / Mutator: tries each operator in turn with its probability
+#[derive(Debug, Clone)]
+pub struct Mutator {
+    operators: Vec<MutationOperator>,
+    rng: SeededRng,
+}
+
+impl Mutator {
+    /// 使用全部算子和默认概率 / Use all operators with their default probabilities
+    pub fn new(seed: u64) -> Self {
+        Self::with_operators(MutationOperator::defaults(), seed)
+    }
+
+    /// 只使用指定的算子 / Use only the given operators
+    pub fn with_operators(operators: Vec<MutationOperator>, seed: u64) -> Self {
+        Self {
+            operators,
+            rng: SeededRng::new(seed),
+        }
+    }
+
+    /// 启用的算子 / Enabled operators
+    pub fn operators(&self) -> &[MutationOperator] {
+        &self.operators
+    }
+
+    /// 随机数生成器（与变异共享同一序列）/ Random number generator (shares its sequence with the mutations)
+    pub fn rng(&mut self) -> &mut SeededRng {
+        &mut self.rng
+    }
+
+    /// 对整个程序变异：每个算子按概率触发，可能不发生任何变异
+    /// Mutate the whole program: each operator fires with its probability, so nothing may change
+    pub fn mutate(&mut self, program: &[GrammarElement]) -> MutationResult {
+        self.mutate_in(program, None)
+    }
+
+    /// 只在指定函数的函数体内变异 / Mutate only inside the body of the given function
+    pub fn mutate_function(
+        &mut self,
+        program: &[GrammarElement],
+        function: &str,
+    ) -> MutationResult {
+        self.mutate_in(program, Some(function))
+    }
+
+    /// 只应用一个算子：按概率加权选择，无法变异时在剩余算子中重新选择
+    /// Apply exactly one operator: chosen with odds proportional to its probability, re-drawing among the remaining
+    /// operators when it finds nothing to mutate
+    pub fn mutate_once(
+        &mut self,
+        program: &[GrammarElement],
+        function: Option<&str>,
+    ) -> MutationResult {
+        let mut ast = program.to_vec();
+        let mut remaining: Vec<MutationOperator> = self
+            .operators
+            .iter()
+            .filter(|operator| operator.probability > 0.0)
+            .copied()
+            .collect();
+        while !remaining.is_empty() {
+            let total: f64 = remaining.iter().map(|operator| operator.probability).sum();
+            let mut target = self.rng.unit() * total;
+            let mut chosen = remaining.len() - 1;
+            for (index, operator) in remaining.iter().enumerate() {
+                if target < operator.probability {
+                    chosen = index;
+                    break;
+                }
+                target -= operator.probability;
+            }
+            let operator = remaining.remove(chosen);
+            if let Some(record) = self.apply(operator.kind, &mut ast, function) {
+                return MutationResult {
+                    ast,
+                    mutations: vec![record],
+                };
+            }
+        }
+        MutationResult {
+            ast,
+            mutations: Vec::new(),
+        }
+    }
+
+    /// 立即应用一个算子（忽略概率），没有可变异的位置时返回None
+    /// Apply one operator right away (ignoring its probability); None when there is nothing it can mutate
+    pub fn apply(
+        &mut self,
+        kind: MutationKind,
+        program: &mut Vec<GrammarElement>,
+        function: Option<&str>,
+    ) -> Option<MutationRecord> {
+        let sites = collect_sites(program, function);
+        match kind {
+            MutationKind::ConstantPerturbation => self.perturb_constant(program, &sites),
+            MutationKind::OperatorSubstitution => self.substitute_operator(program, &sites),
+            MutationKind::SubtreeSwap => self.swap_subtrees(program, &sites),
+            MutationKind::SubtreeReplacement => self.replace_subtree(program, &sites),
+            MutationKind::Hoist => self.hoist(program, &sites),
+            MutationKind::FunctionInlining => self.inline_function(program, &sites),
+            MutationKind::FunctionExtraction => self.extract_function(program, &sites),
+        }
+    }
+
+    /// 交叉：用 `donor` 中的随机子树替换 `program` 中的随机节点（可限定在指定函数内）
+    /// Crossover: replace a random node of `program` with a random subtree of `donor` (optionally within the given function)
+    pub fn crossover(
+        &mut self,
+        program: &[GrammarElement],
+        donor: &[GrammarElement],
+        function: Option<&str>,
+    ) -> Option<Vec<GrammarElement>> {
+        let donor_sites = collect_sites(donor, function);
+        let (site, index) = self.pick(donor, &donor_sites, |_, _, _| true)?;
+        let subtree = preorder(site_ref(donor, &donor_sites[site])?)[index].clone();
+
+        let mut program = program.to_vec();
+        let sites = collect_sites(&program, function);
+        let (site, index) = self.pick(&program, &sites, |_, _, _| true)?;
+        *node_mut(site_mut(&mut program, &sites[site])?, index)? = subtree;
+        Some(program)
+    }
+
+    fn mutate_in(&mut self, program: &[GrammarElement], function: Option<&str>) -> MutationResult {
+        let mut ast = program.to_vec();
+        let mut mutations = Vec::new();
+        for operator in self.operators.clone() {
+            if self.rng.chance(operator.probability) {
+                mutations.extend(self.apply(operator.kind, &mut ast, function));
+            }
+        }
+        MutationResult { ast, mutations }
+    }
+
+    /// 在所有位置中随机选择一个满足条件的节点，返回 (位置, 先序编号)
+    /// Pick a random node satisfying the condition across all sites, returning (site, preorder index)
+    fn pick(
+        &mut self,
+        program: &[GrammarElement],
+        sites: &[Site],
+        accept: impl Fn(&Site, &Expr, usize) -> bool,
+    ) -> Option<(usize, usize)> {
+        let mut candidates = Vec::new();
+        for (position, site) in sites.iter().enumerate() {
+            if let Some(root) = site_ref(program, site) {
+                for (index, expr) in preorder(root).into_iter().enumerate() {
+                    if accept(site, expr, index) {
+                        candidates.push((position, index));
+                    }
+                }
+            }
+        }
+        (!candidates.is_empty()).then(|| candidates[self.rng.below(candidates.len())])
+    }
+
+    fn perturb_constant(
+        &mut self,
+        program: &mut [GrammarElement],
+        sites: &[Site],
+    ) -> Option<MutationRecord> {
+        let (site, index) = self.pick(program, sites, |_, expr, _| {
+            matches!(
+                expr,
+                Expr::Literal(Literal::Int(_) | Literal::Float(_) | Literal::Bool(_))
+            )
+        })?;
+        let delta = [-2i64, -1, 1, 2][self.rng.below(4)];
+        let fresh = self.rng.chance(0.2).then(|| self.rng.below(11) as i64);
+        edit(
+            program,
+            &sites[site],
+            MutationKind::ConstantPerturbation,
+            |root| match node_mut(root, index) {
+                Some(Expr::Literal(Literal::Int(n))) => {
+                    *n = fresh.unwrap_or_else(|| n.saturating_add(delta))
+                }
+                Some(Expr::Literal(Literal::Float(x))) => {
+                    *x = fresh.map_or(*x + delta as f64 * 0.5, |fresh| fresh as f64)
+                }
+                Some(Expr::Literal(Literal::Bool(b))) => *b = !*b,
+                _ => {}
+            },
+        )
+    }
+
+    fn substitute_operator(
+        &mut self,
+        program: &mut [GrammarElement],
+        sites: &[Site],
+    ) -> Option<MutationRecord> {
+        let (site, index) = self.pick(program, sites, |_, expr, _| {
+            matches!(expr, Expr::Call(name, args) if args.len() == 2 && operator_group(name).is_some())
+        })?;
+        let Expr::Call(name, _) = preorder(site_ref(program, &sites[site])?)[index] else {
+            return None;
+        };
+        let choices: Vec<&str> = operator_group(name)?
+            .iter()
+            .copied()
+            .filter(|op| name != op)
+            .collect();
+        let replacement = choices[self.rng.below(choices.len())].to_string();
+        edit(
+            program,
+            &sites[site],
+            MutationKind::OperatorSubstitution,
+            |root| {
+                if let Some(Expr::Call(name, _)) = node_mut(root, index) {
+                    *name = replacement.into();
+                }
+            },
+        )
+    }
+
+    fn swap_subtrees(
+        &mut self,
+        program: &mut [GrammarElement],
+        sites: &[Site],
+    ) -> Option<MutationRecord> {
+        let (site, first) = self.pick(program, sites, |_, _, index| index > 0)?;
+        let root = site_ref(program, &sites[site])?;
+        let nodes = preorder(root);
+        let first_end = first + count_nodes(nodes[first]);
+        // 不相交：一个在另一个的子树之外 / Disjoint: neither lies within the other's subtree
+        let others: Vec<usize> = (1..nodes.len())
+            .filter(|&other| other >= first_end || other + count_nodes(nodes[other]) <= first)
+            .collect();
+        if others.is_empty() {
+            return None;
+        }
+        let second = others[self.rng.below(others.len())];
+        let (low, high) = (first.min(second), first.max(second));
+        let (low_tree, high_tree) = (nodes[low].clone(), nodes[high].clone());
+        // 先替换靠后的节点，靠前节点的编号不受影响 / Replace the later node first so the earlier index stays valid
+        edit(program, &sites[site], MutationKind::SubtreeSwap, |root| {
+            if let Some(node) = node_mut(root, high) {
+                *node = low_tree;
+            }
+            if let Some(node) = node_mut(root, low) {
+                *node = high_tree;
+            }
+        })
+    }
+
+    fn replace_subtree(
+        &mut self,
+        program: &mut [GrammarElement],
+        sites: &[Site],
+    ) -> Option<MutationRecord> {
+        let (site, index) = self.pick(program, sites, |_, _, _| true)?;
+        let terminals = if sites[site].params.is_empty() {
+            let mut names = Vec::new();
+            collect_vars(site_ref(program, &sites[site])?, &mut names);
+            names
+        } else {
+            sites[site].params.clone()
+        };
+        let replacement = self.random_expr(&terminals, 2);
+        edit(
+            program,
+            &sites[site],
+            MutationKind::SubtreeReplacement,
+            |root| {
+                if let Some(node) = node_mut(root, index) {
+                    *node = replacement;
+                }
+            },
+        )
+    }
+
+    fn hoist(&mut self, program: &mut [GrammarElement], sites: &[Site]) -> Option<MutationRecord> {
+        let (site, index) =
+            self.pick(program, sites, |_, expr, _| !expr_children(expr).is_empty())?;
+        let parent = preorder(site_ref(program, &sites[site])?)[index];
+        let subexpressions = expr_children(parent);
+        let child = subexpressions[self.rng.below(subexpressions.len())].clone();
+        edit(program, &sites[site], MutationKind::Hoist, |root| {
+            if let Some(node) = node_mut(root, index) {
+                *node = child;
+            }
+        })
+    }
+
+    fn inline_function(
+        &mut self,
+        program: &mut [GrammarElement],
+        sites: &[Site],
+    ) -> Option<MutationRecord> {
+        let inlinable = inlinable_functions(program);
+        let (site, index) = self.pick(program, sites, |site, expr, _| match expr {
+            Expr::Call(name, args) => {
+                site.function.as_deref() != Some(name.as_str())
+                    && inlinable
+                        .get(name.as_str())
+                        .is_some_and(|(params, _)| params.len() == args.len())
+            }
+            _ => false,
+        })?;
+        let Expr::Call(name, args) = preorder(site_ref(program, &sites[site])?)[index] else {
+            return None;
+        };
+        let (params, body) = &inlinable[name.as_str()];
+        let bindings: HashMap<&str, &Expr> =
+            params.iter().map(String::as_str).zip(args.iter()).collect();
+        let inlined = substitute(body, &bindings);
+        edit(
+            program,
+            &sites[site],
+            MutationKind::FunctionInlining,
+            |root| {
+                if let Some(node) = node_mut(root, index) {
+                    *node = inlined;
+                }
+            },
+        )
+    }
+
+    fn extract_function(
+        &mut self,
+        program: &mut Vec<GrammarElement>,
+        sites: &[Site],
+    ) -> Option<MutationRecord> {
+        let (site, index) = self.pick(program, sites, |site, expr, index| {
+            index > 0
+                && site.function.is_some()
+                && !expr_children(expr).is_empty()
+                && !contains_assignment(expr)
+        })?;
+        let site = &sites[site];
+        let root = site_ref(program, site)?;
+        let before = unparse_expr(root);
+        let subtree = preorder(root)[index].clone();
+
+        // 新函数的参数：子表达式用到的外层函数参数 / Parameters of the new function: outer parameters the subexpression uses
+        let mut used = Vec::new();
+        collect_vars(&subtree, &mut used);
+        let params: Vec<String> = site
+            .params
+            .iter()
+            .filter(|param| used.contains(param))
+            .cloned()
+            .collect();
+        let defined = defined_functions(program);
+        let base = site.function.as_deref().unwrap_or("extracted");
+        let name = (1..)
+            .map(|n| format!("{}_part{}", base, n))
+            .find(|name| !defined.contains(name))?;
+
+        let call = Expr::Call(
+            name.as_str().into(),
+            params
+                .iter()
+                .map(|param| Expr::Var(param.as_str().into()))
+                .collect(),
+        );
+        let root = site_mut(program, site)?;
+        *node_mut(root, index)? = call;
+        let after = unparse_expr(root);
+        let definition = GrammarElement::List(vec![
+            GrammarElement::Atom("def".to_string()),
+            GrammarElement::Atom(name),
+            GrammarElement::List(
+                params
+                    .into_iter()
+                    .map(|param| GrammarElement::Expr(Box::new(Expr::Var(param.into()))))
+                    .collect(),
+            ),
+            GrammarElement::Expr(Box::new(subtree)),
+        ]);
+        let record = MutationRecord {
+            kind: MutationKind::FunctionExtraction,
+            form: site.form,
+            before,
+            after: format!("{}\n{}", unparse_element(&definition), after),
+        };
+        program.insert(site.form, definition);
+        Some(record)
+    }
+
+    /// 由终结符、小整数和算术运算组成的随机表达式 / Random expression of terminals, small integers and arithmetic
+    fn random_expr(&mut self, terminals: &[String], depth: usize) -> Expr {
+        if depth == 0 || self.rng.chance(0.5) {
+            if !terminals.is_empty() && self.rng.chance(0.6) {
+                Expr::Var(terminals[self.rng.below(terminals.len())].as_str().into())
+            } else {
+                Expr::Literal(Literal::Int(self.rng.below(4) as i64))
+            }
+        } else {
+            let op = ARITHMETIC[self.rng.below(ARITHMETIC.len())];
+            Expr::Call(
+                op.into(),
+                vec![
+                    self.random_expr(terminals, depth - 1),
+                    self.random_expr(terminals, depth - 1),
+                ],
+            )
+        }
+    }
+}
+
+/// 算术运算符 / Arithmetic operators
+const ARITHMETIC: [&str; 3] = ["op:+", "op:-", "op:*"];
+/// 比较运算符 / Comparison operators
+const COMPARISON: [&str; 6] = ["op:<", "op:>", "op:<=", "op:>=", "op:=", "op:!="];
+/// 引入绑定的列表形式，其中的表达式不作为变异位置
+/// List forms that introduce bindings; expressions inside them are not mutation sites
+const BINDING_FORMS: [&str; 8] = [
+    "let", "let*", "lambda", "for", "set!", "deftype", "defmacro", "import",
+];
+
+/// 运算符所属的可互换组 / Interchangeable group an operator belongs to
+fn operator_group(name: &str) -> Option<&'static [&'static str]> {
+    if ARITHMETIC.contains(&name) {
+        Some(&ARITHMETIC)
+    } else if COMPARISON.contains(&name) {
+        Some(&COMPARISON)
+    } else {
+        None
+    }
+}
+
+/// 变异位置：程序中的一个表达式根 / Mutation site: an expression root in the program
+struct Site {
+    /// 顶层形式索引 / Top-level form index
+    form: usize,
+    /// 从顶层形式到表达式的列表下标路径 / List index path from the top-level form to the expression
+    path: Vec<usize>,
+    /// 所在函数 / Enclosing function
+    function: Option<String>,
+    /// 所在函数的参数 / Parameters of the enclosing function
+    params: Vec<String>,
+}
+
+/// 收集变异位置；指定函数时只收集该函数体内的位置
+/// Collect mutation sites; with a function given, only sites inside its body
+fn collect_sites(program: &[GrammarElement], function: Option<&str>) -> Vec<Site> {
+    let mut sites = Vec::new();
+    for (form, element) in program.iter().enumerate() {
+        collect_element(element, form, &mut Vec::new(), None, function, &mut sites);
+    }
+    sites
+}
+
+fn collect_element(
+    element: &GrammarElement,
+    form: usize,
+    path: &mut Vec<usize>,
+    enclosing: Option<&(String, Vec<String>)>,
+    function: Option<&str>,
+    sites: &mut Vec<Site>,
+) {
+    match element {
+        GrammarElement::Expr(_) => {
+            let inside = match function {
+                Some(function) => enclosing.is_some_and(|(name, _)| name == function),
+                None => true,
+            };
+            if inside {
+                sites.push(Site {
+                    form,
+                    path: path.clone(),
+                    function: enclosing.map(|(name, _)| name.clone()),
+                    params: enclosing
+                        .map(|(_, params)| params.clone())
+                        .unwrap_or_default(),
+                });
+            }
+        }
+        GrammarElement::List(items) => {
+            if let Some(GrammarElement::Atom(head)) = items.first() {
+                if BINDING_FORMS.contains(&head.as_str()) {
+                    return;
+                }
+            }
+            let definition = definition_parts(items);
+            let (start, enclosing) = match &definition {
+                Some(definition) => (3, Some(definition)),
+                None => (0, enclosing),
+            };
+            for (index, item) in items.iter().enumerate().skip(start) {
+                path.push(index);
+                collect_element(item, form, path, enclosing, function, sites);
+                path.pop();
+            }
+        }
+        GrammarElement::Atom(_) | GrammarElement::NaturalLang(_) => {}
+    }
+}
+
+/// 函数定义 `(def name (params...) body...)` 的名称和参数
+/// Name and parameters of a function definition `(def name (params...) body...)`
+fn definition_parts(items: &[GrammarElement]) -> Option<(String, Vec<String>)> {
+    match items {
+        [GrammarElement::Atom(keyword), GrammarElement::Atom(name), GrammarElement::List(params), ..]
+            if matches!(keyword.as_str(), "def" | "function" | "defn-") =>
+        {
+            let params = params
+                .iter()
+                .filter_map(|param| match param {
+                    GrammarElement::Atom(name) => Some(name.clone()),
+                    GrammarElement::Expr(expr) => match expr.as_ref() {
+                        Expr::Var(name) => Some(name.to_string()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect();
+            Some((name.clone(), params))
+        }
+        _ => None,
+    }
+}
+
+/// 顶层函数定义的位置 / Position of a top-level function definition
+pub fn find_definition(program: &[GrammarElement], function: &str) -> Option<usize> {
+    program.iter().position(|element| match element {
+        GrammarElement::List(items) => {
+            definition_parts(items).is_some_and(|(name, _)| name == function)
+        }
+        _ => false,
+    })
+}
+
+/// 程序中定义的顶层函数名 / Names of the top-level functions defined in the program
+fn defined_functions(program: &[GrammarElement]) -> HashSet<String> {
+    program
+        .iter()
+        .filter_map(|element| match element {
+            GrammarElement::List(items) => definition_parts(items).map(|(name, _)| name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// 可内联的函数：单表达式函数体、非递归、不引入绑定
+/// Inlinable functions: single-expression body, not recursive, introducing no bindings
+fn inlinable_functions(program: &[GrammarElement]) -> HashMap<String, (Vec<String>, Expr)> {
+    let mut functions = HashMap::new();
+    for element in program {
+        let GrammarElement::List(items) = element else {
+            continue;
+        };
+        let (Some((name, params)), [_, _, _, GrammarElement::Expr(body)]) =
+            (definition_parts(items), items.as_slice())
+        else {
+            continue;
+        };
+        let recursive = preorder(body)
+            .iter()
+            .any(|expr| matches!(expr, Expr::Call(callee, _) if *callee == name));
+        let binds = preorder(body).iter().any(|expr| {
+            matches!(
+                expr,
+                Expr::Lambda { .. }
+                    | Expr::For { .. }
+                    | Expr::Match(..)
+                    | Expr::Assign(..)
+                    | Expr::Try {
+                        catch_var: Some(_),
+                        ..
+                    }
+            ) || matches!(expr, Expr::Call(callee, _) if BINDING_FORMS.contains(&callee.as_str()))
+        });
+        if !recursive && !binds {
+            functions.insert(name, (params, body.as_ref().clone()));
+        }
+    }
+    functions
+}
+
+/// 用实参替换函数体中的形参 / Replace parameters in a function body with the arguments
+fn substitute(expr: &Expr, bindings: &HashMap<&str, &Expr>) -> Expr {
+    struct Substitute<'a, 'b>(&'a HashMap<&'b str, &'b Expr>);
+
+    impl visit::Mutator for Substitute<'_, '_> {
+        fn enter_expr(&mut self, expr: &mut Expr, _: Position) -> bool {
+            match expr {
+                Expr::Var(name) => match self.0.get(name.as_str()) {
+                    // 不进入替换后的实参 / Do not descend into the substituted argument
+                    Some(argument) => {
+                        *expr = (*argument).clone();
+                        false
+                    }
+                    None => true,
+                },
+                _ => true,
+            }
+        }
+    }
+
+    let mut result = expr.clone();
+    walk_expr_mut(&mut Substitute(bindings), &mut result, Position::default());
+    result
+}
+
+/// 是否包含赋值 / Whether the expression contains an assignment
+fn contains_assignment(expr: &Expr) -> bool {
+    preorder(expr)
+        .iter()
+        .any(|expr| matches!(expr, Expr::Assign(..)))
+}
+
+/// 收集表达式中出现的变量名（去重，按出现顺序）/ Collect variable names in the expression (deduplicated, in order of appearance)
+fn collect_vars(expr: &Expr, names: &mut Vec<String>) {
+    for expr in preorder(expr) {
+        if let Expr::Var(name) = expr {
+            if !names.iter().any(|n| name == n) {
+                names.push(name.to_string());
+            }
+        }
+    }
+}
+
+/// 修改位置上的表达式并记录改动；没有实际变化时返回None
+/// Modify the expression at a site and record the change; None when nothing actually changed
+fn edit(
+    program: &mut [GrammarElement],
+    site: &Site,
+    kind: MutationKind,
+    change: impl FnOnce(&mut Expr),
+) -> Option<MutationRecord> {
+    let root = site_mut(program, site)?;
+    let before = unparse_expr(root);
+    change(root);
+    let after = unparse_expr(root);
+    (before != after).then_some(MutationRecord {
+        kind,
+        form: site.form,
+        before,
+        after,
+    })
+}
+
+fn site_ref<'a>(program: &'a [GrammarElement], site: &Site) -> Option<&'a Expr> {
+    let mut element = program.get(site.form)?;
+    for &index in &site.path {
+        element = match element {
+            GrammarElement::List(items) => items.get(index)?,
+            _ => return None,
+        };
+    }
+    match element {
+        GrammarElement::Expr(expr) => Some(expr),
+        _ => None,
+    }
+}
+
+fn site_mut<'a>(program: &'a mut [GrammarElement], site: &Site) -> Option<&'a mut Expr> {
+    let mut element = program.get_mut(site.form)?;
+    for &index in &site.path {
+        element = match element {
+            GrammarElement::List(items) => items.get_mut(index)?,
+            _ => return None,
+        };
+    }
+    match element {
+        GrammarElement::Expr(expr) => Some(expr),
+        _ => None,
+    }
+}
+
+/// 按先序编号取可变节点 / Mutable node by preorder index
+fn node_mut(expr: &mut Expr, mut index: usize) -> Option<&mut Expr> {
+    if index == 0 {
+        return Some(expr);
+    }
+    index -= 1;
+    for child in expr_children_mut(expr) {
+        let size = count_nodes(child);
+        if index < size {
+            return node_mut(child, index);
+        }
+        index -= size;
+    }
+    None
+}
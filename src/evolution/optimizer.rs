@@ -3,10 +3,22 @@
 // Provide intelligent optimization suggestions based on quality assessment and learning results
 
 use crate::evolution::analyzer::CodeAnalysis;
+use crate::evolution::call_graph::{definitions, CallGraph};
 use crate::evolution::learning::UsagePatternLearner;
 use crate::evolution::quality_assessor::QualityAssessment;
+use crate::grammar::core::GrammarElement;
+use crate::runtime::bench::{BenchConfig, BenchStats};
+use crate::runtime::interpreter::{Interpreter, InterpreterError};
+use crate::runtime::trace::ProfileReport;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
+
+/// 热点至少占总耗时的百分比才会生成建议 / Minimum percentage of the total time for a hot spot to get a suggestion
+const HOT_SPOT_THRESHOLD: f64 = 5.0;
+
+/// 顶层代码区域的名称 / Name of the top-level code region
+const TOP_LEVEL: &str = "<top-level>";
 
 /// 智能优化建议器 / Intelligent optimization advisor
 pub struct OptimizationAdvisor {
@@ -40,14 +52,39 @@ pub struct OptimizationStrategy {
 pub struct OptimizationRecord {
     /// 时间戳 / Timestamp
     pub timestamp: chrono::DateTime<chrono::Utc>,
-    /// 优化前质量分数 / Quality score before optimization
+    /// 优化前质量分数（基准测试记录中为平均耗时，毫秒）/ Quality score before optimization (mean time in ms for benchmarked records)
     pub before_score: f64,
-    /// 优化后质量分数 / Quality score after optimization
+    /// 优化后质量分数（基准测试记录中为平均耗时，毫秒）/ Quality score after optimization (mean time in ms for benchmarked records)
     pub after_score: f64,
     /// 使用的策略 / Strategy used
     pub strategy: String,
     /// 改进程度 / Improvement
     pub improvement: f64,
+    /// 优化前的基准测试 / Benchmark before optimization
+    #[serde(default)]
+    pub before_bench: Option<BenchStats>,
+    /// 优化后的基准测试 / Benchmark after optimization
+    #[serde(default)]
+    pub after_bench: Option<BenchStats>,
+}
+
+/// 实测热点：一个代码区域（函数定义或顶层代码）在剖析中的耗时
+/// Measured hot spot: the time one code region (a function definition or the top-level code) took in a profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotSpot {
+    /// 函数名，顶层代码为 `<top-level>` / Function name; `<top-level>` for top-level code
+    pub function: String,
+    /// 定义在AST中的位置，顶层代码为None / Position of the definition in the AST; None for top-level code
+    pub index: Option<usize>,
+    /// 调用次数 / Number of calls
+    pub calls: u64,
+    /// 区域耗时：函数自身及其直接调用的内置函数的自身耗时，不含调用的其他用户函数
+    /// Region time: the self time of the function and of the builtins it calls directly, excluding other user functions it calls
+    pub cost: Duration,
+    /// 占总耗时的百分比 / Percentage of the total time
+    pub share: f64,
+    /// 是否递归 / Whether the function recurses
+    pub recursive: bool,
 }
 
 /// 优化建议 / Optimization suggestion
@@ -67,6 +104,9 @@ pub struct OptimizationSuggestion {
     pub specific_suggestion: String,
     /// 置信度 / Confidence
     pub confidence: f64,
+    /// 依据的实测热点，静态建议为None / Measured hot spot it is based on; None for static suggestions
+    #[serde(default)]
+    pub hot_spot: Option<HotSpot>,
 }
 
 /// 优化优先级 / Optimization priority
@@ -82,6 +122,22 @@ pub enum OptimizationPriority {
     Critical,
 }
 
+/// 实测验证结果：把建议的预期改进与基准测试测得的改进对比
+/// Measured validation: compares a suggestion's expected improvement with the benchmarked one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImprovementValidation {
+    /// 策略名称 / Strategy name
+    pub strategy: String,
+    /// 预期改进（百分比）/ Expected improvement (percent)
+    pub expected_improvement: f64,
+    /// 实测改进（平均耗时降低的百分比）/ Measured improvement (percent reduction of mean time)
+    pub measured_improvement: f64,
+    /// 差异是否超出测量噪声 / Whether the difference exceeds measurement noise
+    pub significant: bool,
+    /// 实测改进显著且达到预期的一半 / The measured improvement is significant and at least half the expected one
+    pub confirmed: bool,
+}
+
 /// 优化建议结果 / Optimization suggestion result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptimizationResult {
@@ -263,6 +319,7 @@ impl OptimizationAdvisor {
                         expected_improvement: strategy.avg_improvement,
                         specific_suggestion: insight.suggestion.clone().unwrap_or_default(),
                         confidence: strategy.success_rate,
+                        hot_spot: None,
                     });
                 }
             }
@@ -319,6 +376,184 @@ impl OptimizationAdvisor {
             expected_improvement,
             specific_suggestion: strategy.description.clone(),
             confidence: strategy.success_rate,
+            hot_spot: None,
+        }
+    }
+
+    /// 把剖析耗时归到AST区域：每个函数定义得到自身及所调用内置函数的耗时，其余归顶层代码；按耗时降序
+    /// Attribute profiled time to AST regions: each function definition gets the time of its body and the builtins
+    /// it calls, the rest goes to the top-level code; by cost descending
+    pub fn hot_spots(ast: &[GrammarElement], profile: &ProfileReport) -> Vec<HotSpot> {
+        let graph = CallGraph::build(ast);
+        let definitions = definitions(ast);
+        let defined: Vec<&str> = definitions.iter().map(|d| d.name.as_str()).collect();
+
+        let mut spots: Vec<HotSpot> = definitions
+            .iter()
+            .filter_map(|definition| {
+                let function = profile.function(&definition.name)?;
+                let builtin_time: Duration = function
+                    .callee_self_time
+                    .iter()
+                    .filter(|(callee, _)| !defined.contains(&callee.as_str()))
+                    .map(|(_, time)| *time)
+                    .sum();
+                Some(HotSpot {
+                    function: definition.name.clone(),
+                    index: Some(definition.index),
+                    calls: function.calls,
+                    cost: function.self_time + builtin_time,
+                    share: 0.0,
+                    recursive: graph
+                        .callees(&definition.name)
+                        .contains(&definition.name.as_str()),
+                })
+            })
+            .collect();
+
+        let attributed: Duration = spots.iter().map(|spot| spot.cost).sum();
+        let top_level = profile.total_time.saturating_sub(attributed);
+        if !top_level.is_zero() {
+            spots.push(HotSpot {
+                function: TOP_LEVEL.to_string(),
+                index: None,
+                calls: 1,
+                cost: top_level,
+                share: 0.0,
+                recursive: false,
+            });
+        }
+
+        let total = profile.total_time.max(attributed).as_secs_f64();
+        for spot in &mut spots {
+            if total > 0.0 {
+                spot.share = spot.cost.as_secs_f64() / total * 100.0;
+            }
+        }
+        spots.sort_by(|a, b| {
+            b.cost
+                .cmp(&a.cost)
+                .then_with(|| a.function.cmp(&b.function))
+        });
+        spots
+    }
+
+    /// 结合运行时剖析生成建议：实测热点的建议在前，按耗时排序，预期改进是可节省的总耗时百分比；静态建议排在其后
+    /// Generate suggestions with a runtime profile: suggestions for measured hot spots come first, ranked by their
+    /// cost, with the expected improvement being the percentage of total time that can be saved; static suggestions follow
+    pub fn suggest_from_profile(
+        &mut self,
+        ast: &[GrammarElement],
+        analysis: &CodeAnalysis,
+        quality: &QualityAssessment,
+        profile: &ProfileReport,
+    ) -> OptimizationResult {
+        let static_result = self.suggest_optimizations(analysis, quality);
+
+        let mut suggestions = Vec::new();
+        let mut recommended_strategies = Vec::new();
+        if let Some(strategy) = self.strategies.get("performance") {
+            for spot in Self::hot_spots(ast, profile)
+                .into_iter()
+                .filter(|spot| spot.share >= HOT_SPOT_THRESHOLD)
+            {
+                suggestions.push(Self::hot_spot_suggestion(strategy, spot));
+            }
+            if !suggestions.is_empty() {
+                recommended_strategies.push("performance".to_string());
+            }
+        }
+
+        // 实测建议互不重叠，可节省的耗时可以相加 / Measured suggestions do not overlap, so their savings add up
+        let measured_improvement = suggestions
+            .iter()
+            .map(|s| s.expected_improvement)
+            .sum::<f64>()
+            .min(100.0);
+        let overall_improvement = if suggestions.is_empty() {
+            static_result.overall_improvement
+        } else {
+            measured_improvement
+        };
+
+        for strategy in static_result.recommended_strategies {
+            if !recommended_strategies.contains(&strategy) {
+                recommended_strategies.push(strategy);
+            }
+        }
+        suggestions.extend(static_result.suggestions);
+
+        OptimizationResult {
+            suggestions,
+            overall_improvement,
+            recommended_strategies,
+        }
+    }
+
+    /// 为实测热点创建建议 / Create a suggestion for a measured hot spot
+    fn hot_spot_suggestion(
+        strategy: &OptimizationStrategy,
+        spot: HotSpot,
+    ) -> OptimizationSuggestion {
+        let millis = spot.cost.as_secs_f64() * 1000.0;
+        let (description, specific_suggestion) = if spot.index.is_none() {
+            (
+                format!("顶层代码占用 {:.0}% 的时间（{:.2} ms）", spot.share, millis),
+                "把顶层的计算移入函数，减少重复求值".to_string(),
+            )
+        } else if spot.recursive && spot.calls >= 1000 {
+            (
+                format!(
+                    "递归函数 {} 调用 {} 次，占用 {:.0}% 的时间（{:.2} ms）",
+                    spot.function, spot.calls, spot.share, millis
+                ),
+                format!(
+                    "缓存 {} 相同参数的结果（记忆化）或改写为迭代实现",
+                    spot.function
+                ),
+            )
+        } else if spot.calls >= 1000 {
+            (
+                format!(
+                    "函数 {} 调用 {} 次，占用 {:.0}% 的时间（{:.2} ms）",
+                    spot.function, spot.calls, spot.share, millis
+                ),
+                format!(
+                    "减少对 {} 的调用次数，或把循环不变的计算移出",
+                    spot.function
+                ),
+            )
+        } else {
+            (
+                format!(
+                    "函数 {} 占用 {:.0}% 的时间（{:.2} ms，调用 {} 次）",
+                    spot.function, spot.share, millis, spot.calls
+                ),
+                format!(
+                    "检查 {} 中的循环和集合操作，改用更高效的算法",
+                    spot.function
+                ),
+            )
+        };
+        let priority = if spot.share >= 50.0 {
+            OptimizationPriority::Critical
+        } else if spot.share >= 25.0 {
+            OptimizationPriority::High
+        } else if spot.share >= 10.0 {
+            OptimizationPriority::Medium
+        } else {
+            OptimizationPriority::Low
+        };
+
+        OptimizationSuggestion {
+            id: uuid::Uuid::new_v4().to_string(),
+            strategy: "performance".to_string(),
+            description,
+            priority,
+            expected_improvement: spot.share * strategy.avg_improvement / 100.0,
+            specific_suggestion,
+            confidence: strategy.success_rate,
+            hot_spot: Some(spot),
         }
     }
 
@@ -331,10 +566,75 @@ impl OptimizationAdvisor {
             after_score,
             strategy: strategy.to_string(),
             improvement,
+            before_bench: None,
+            after_bench: None,
         };
         self.optimization_history.push(record);
 
-        // 更新策略统计 / Update strategy statistics
+        self.update_strategy(strategy, improvement);
+    }
+
+    /// 用优化前后的基准测试验证建议：实测改进计入策略统计，之后的预期改进随之校准
+    /// Validate a suggestion with benchmarks from before and after applying it: the measured improvement feeds the
+    /// strategy statistics, so later expected improvements are calibrated by it
+    pub fn validate_suggestion(
+        &mut self,
+        suggestion: &OptimizationSuggestion,
+        before: &BenchStats,
+        after: &BenchStats,
+    ) -> ImprovementValidation {
+        let measured_improvement = after.improvement_over(before);
+        let significant = after.differs_significantly(before);
+        // 噪声范围内的差异按没有改进计 / Differences within noise count as no improvement
+        self.update_strategy(
+            &suggestion.strategy,
+            if significant {
+                measured_improvement
+            } else {
+                0.0
+            },
+        );
+        ImprovementValidation {
+            strategy: suggestion.strategy.clone(),
+            expected_improvement: suggestion.expected_improvement,
+            measured_improvement,
+            significant,
+            confirmed: significant
+                && measured_improvement > 0.0
+                && measured_improvement >= suggestion.expected_improvement / 2.0,
+        }
+    }
+
+    /// 应用优化：对优化前后的代码做基准测试，验证建议并把两次测量记入优化历史
+    /// Apply an optimization: benchmark the code before and after it, validate the suggestion and record both
+    /// measurements in the optimization history
+    pub fn apply_optimization(
+        &mut self,
+        interpreter: &mut Interpreter,
+        suggestion: &OptimizationSuggestion,
+        original: &[GrammarElement],
+        optimized: &[GrammarElement],
+        config: &BenchConfig,
+    ) -> Result<ImprovementValidation, InterpreterError> {
+        let before = interpreter.benchmark(original, config)?;
+        let after = interpreter.benchmark(optimized, config)?;
+        let validation = self.validate_suggestion(suggestion, &before, &after);
+
+        let millis = |stats: &BenchStats| stats.mean.as_secs_f64() * 1000.0;
+        self.optimization_history.push(OptimizationRecord {
+            timestamp: chrono::Utc::now(),
+            before_score: millis(&before),
+            after_score: millis(&after),
+            strategy: suggestion.strategy.clone(),
+            improvement: validation.measured_improvement,
+            before_bench: Some(before),
+            after_bench: Some(after),
+        });
+        Ok(validation)
+    }
+
+    /// 更新策略的使用次数、成功率和平均改进 / Update a strategy's usage count, success rate and average improvement
+    fn update_strategy(&mut self, strategy: &str, improvement: f64) {
         if let Some(strategy_obj) = self.strategies.get_mut(strategy) {
             strategy_obj.usage_count += 1;
             if improvement > 0.0 {
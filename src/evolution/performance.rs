@@ -4,6 +4,8 @@
 
 use crate::evolution::analyzer::CodeAnalysis;
 use crate::grammar::core::GrammarElement;
+use crate::grammar::types::{element_name, is_definition};
+use crate::runtime::trace::ProfileReport;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -195,6 +197,33 @@ impl PerformanceAnalyzer {
         // 识别性能瓶颈 / Identify performance bottlenecks
         let bottlenecks = self.identify_bottlenecks(ast, analysis, &metrics);
 
+        self.finish_analysis(metrics, bottlenecks, analysis)
+    }
+
+    /// 结合运行时剖析报告分析性能：用实测耗时代替估算，并把耗时占比高的函数标为瓶颈
+    /// Analyze performance with a runtime profile: measured time replaces the estimate, and functions taking a large share of the time become bottlenecks
+    pub fn analyze_with_profile(
+        &mut self,
+        ast: &[GrammarElement],
+        analysis: &CodeAnalysis,
+        profile: &ProfileReport,
+    ) -> PerformanceAnalysis {
+        let mut metrics = self.calculate_metrics(ast, analysis);
+        metrics.estimated_execution_time = profile.total_time.as_secs_f64() * 1000.0;
+
+        let mut bottlenecks = self.identify_bottlenecks(ast, analysis, &metrics);
+        bottlenecks.extend(self.measured_bottlenecks(ast, profile));
+
+        self.finish_analysis(metrics, bottlenecks, analysis)
+    }
+
+    /// 由指标和瓶颈生成建议、评分并记录历史 / Generate suggestions and score from metrics and bottlenecks, and record history
+    fn finish_analysis(
+        &mut self,
+        metrics: PerformanceMetrics,
+        bottlenecks: Vec<Bottleneck>,
+        analysis: &CodeAnalysis,
+    ) -> PerformanceAnalysis {
         // 生成优化建议 / Generate optimization suggestions
         let suggestions = self.generate_suggestions(&metrics, &bottlenecks, analysis);
 
@@ -368,6 +397,63 @@ impl PerformanceAnalyzer {
         bottlenecks
     }
 
+    /// 实测瓶颈：自身耗时占比至少 20% 的用户函数 / Measured bottlenecks: user functions with at least 20% of the self time
+    fn measured_bottlenecks(
+        &self,
+        ast: &[GrammarElement],
+        profile: &ProfileReport,
+    ) -> Vec<Bottleneck> {
+        let total = profile.total_time.as_secs_f64();
+        if total <= 0.0 {
+            return Vec::new();
+        }
+        let defined: Vec<String> = ast
+            .iter()
+            .filter_map(|element| match element {
+                GrammarElement::List(items) if is_definition(items) => {
+                    items.get(1).and_then(element_name)
+                }
+                _ => None,
+            })
+            .collect();
+
+        profile
+            .functions
+            .iter()
+            .filter(|function| defined.contains(&function.name))
+            .filter_map(|function| {
+                let share = function.self_time.as_secs_f64() / total * 100.0;
+                if share < 20.0 {
+                    return None;
+                }
+                let time_ms = function.total_time.as_secs_f64() * 1000.0;
+                let (bottleneck_type, description) = if function.calls >= 1000 {
+                    (
+                        BottleneckType::RepeatedComputation,
+                        format!(
+                            "实测调用 {} 次，占用 {:.0}% 的时间（{:.2} ms），可能存在重复计算",
+                            function.calls, share, time_ms
+                        ),
+                    )
+                } else {
+                    (
+                        BottleneckType::InefficientAlgorithm,
+                        format!(
+                            "实测占用 {:.0}% 的时间（{:.2} ms，调用 {} 次）",
+                            share, time_ms, function.calls
+                        ),
+                    )
+                };
+                Some(Bottleneck {
+                    bottleneck_type,
+                    location: function.name.clone(),
+                    description,
+                    impact: share / 2.0,
+                })
+            })
+            .collect()
+    }
+
     /// 生成优化建议 / Generate optimization suggestions
     fn generate_suggestions(
         &self,
@@ -404,6 +490,17 @@ impl PerformanceAnalyzer {
                         priority: 2,
                     });
                 }
+                BottleneckType::RepeatedComputation => {
+                    suggestions.push(OptimizationSuggestion {
+                        suggestion_type: "缓存优化".to_string(),
+                        content: format!(
+                            "函数 {} 被频繁调用，考虑缓存相同参数的结果（记忆化）或改为迭代实现",
+                            bottleneck.location
+                        ),
+                        expected_improvement: 30.0,
+                        priority: 1,
+                    });
+                }
                 _ => {}
             }
         }
@@ -0,0 +1,431 @@
+// 项目批量分析 / Batch project analysis
+// 对目录中的全部 .evo 文件运行分析器、质量评估、代码审查、依赖分析和克隆检测，汇总为一份项目报告，
+// 并按阈值判断是否通过，供 `evo analyze` 和 CI 使用
+// Runs the analyzer, quality assessor, code reviewer, dependency analysis and clone detection over every .evo file
+// in a directory, aggregates them into one project report and checks it against thresholds, for `evo analyze`
+// and CI
+
+use crate::evolution::analyzer::CodeAnalyzer;
+use crate::evolution::code_reviewer::{CodeReviewer, ReviewConfig, ReviewIssue, ReviewSeverity};
+use crate::evolution::corpus::{collect_evo_files, CorpusFailure};
+use crate::evolution::dependency::{CircularDependency, DependencyAnalyzer, ModuleError};
+use crate::evolution::quality_assessor::{ProjectQuality, QualityAssessor};
+use crate::evolution::similarity::{CloneReport, SimilarityDetector};
+use crate::parser::AdaptiveParser;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+/// 项目分析阈值，任一项不满足时报告不通过 / Project analysis thresholds; the report fails when any is not met
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectThresholds {
+    /// 项目质量分数下限 / Minimum project quality score
+    pub min_quality: Option<f64>,
+    /// 达到该严重程度的审查问题使分析失败 / Review issues at this severity fail the analysis
+    pub fail_on: ReviewSeverity,
+    /// 克隆簇数量上限 / Maximum number of clone clusters
+    pub max_clone_clusters: Option<usize>,
+    /// 是否允许模块之间的循环导入 / Whether circular imports between modules are allowed
+    pub allow_circular_dependencies: bool,
+    /// 是否允许无法解析的文件 / Whether files that fail to parse are allowed
+    pub allow_parse_failures: bool,
+}
+
+impl Default for ProjectThresholds {
+    fn default() -> Self {
+        Self {
+            min_quality: None,
+            fail_on: ReviewSeverity::Error,
+            max_clone_clusters: None,
+            allow_circular_dependencies: false,
+            allow_parse_failures: false,
+        }
+    }
+}
+
+/// 单个文件的分析结果 / Analysis result of a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileReport {
+    /// 文件路径 / File path
+    pub path: PathBuf,
+    /// 函数数 / Number of functions
+    pub functions: usize,
+    /// 复杂度 / Complexity
+    pub complexity: f64,
+    /// 审查问题 / Review issues
+    pub issues: Vec<ReviewIssue>,
+}
+
+/// 跨文件依赖的汇总 / Summary of cross-file dependencies
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DependencySummary {
+    /// 作为入口分析的文件（没有被先前的入口导入的文件）/ Files analyzed as entries (files not imported from an
+    /// earlier entry)
+    pub entries: Vec<PathBuf>,
+    /// 模块数 / Number of modules
+    pub modules: usize,
+    /// 导入数 / Number of imports
+    pub imports: usize,
+    /// 函数调用数 / Number of function calls
+    pub calls: usize,
+    /// 模块之间的循环导入 / Circular imports between modules
+    pub circular_dependencies: Vec<CircularDependency>,
+    /// 无法找到、读取或解析的模块 / Modules that could not be found, read or parsed
+    pub errors: Vec<ModuleError>,
+}
+
+/// 项目报告摘要 / Project report summary
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectSummary {
+    /// 文件数 / Number of files
+    pub files: usize,
+    /// 无法解析的文件数 / Number of files that failed to parse
+    pub parse_failures: usize,
+    /// 函数数 / Number of functions
+    pub functions: usize,
+    /// 各严重程度的审查问题数 / Review issues per severity
+    pub issues: BTreeMap<ReviewSeverity, usize>,
+    /// 克隆簇数 / Number of clone clusters
+    pub clone_clusters: usize,
+    /// 循环导入数 / Number of circular imports
+    pub circular_dependencies: usize,
+    /// 项目质量分数 / Project quality score
+    pub quality_score: f64,
+}
+
+/// 项目报告 / Project report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectReport {
+    /// 分析的目录或文件 / Analyzed directory or file
+    pub root: PathBuf,
+    /// 摘要 / Summary
+    pub summary: ProjectSummary,
+    /// 各文件的分析和审查结果 / Analysis and review results per file
+    pub files: Vec<FileReport>,
+    /// 项目质量 / Project quality
+    pub quality: ProjectQuality,
+    /// 克隆检测结果 / Clone detection result
+    pub clones: CloneReport,
+    /// 依赖汇总 / Dependency summary
+    pub dependencies: DependencySummary,
+    /// 无法读取或解析的文件 / Files that could not be read or parsed
+    pub failures: Vec<CorpusFailure>,
+    /// 不满足的阈值 / Thresholds not met
+    pub violations: Vec<String>,
+}
+
+impl ProjectReport {
+    /// 是否满足全部阈值 / Whether every threshold is met
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// 转为JSON / Convert to JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// 人类可读的摘要 / Human-readable summary
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        for file in &self.files {
+            let _ = writeln!(
+                text,
+                "{}: {} 个函数 / functions, 复杂度 / complexity {:.1}, {} 个问题 / issues",
+                file.path.display(),
+                file.functions,
+                file.complexity,
+                file.issues.len()
+            );
+            for issue in &file.issues {
+                let _ = writeln!(
+                    text,
+                    "  {:?} [{}] {}: {}",
+                    issue.severity, issue.rule_id, issue.location, issue.description
+                );
+            }
+        }
+        for failure in &self.failures {
+            let _ = writeln!(
+                text,
+                "✗ 解析错误 / Parse error: {}: {}",
+                failure.path.display(),
+                failure.error
+            );
+        }
+        for cluster in &self.clones.clusters {
+            let locations: Vec<&str> = cluster
+                .instances
+                .iter()
+                .map(|instance| instance.location.as_str())
+                .collect();
+            let _ = writeln!(
+                text,
+                "克隆 / Clone {:?}: {}",
+                cluster.kind,
+                locations.join(", ")
+            );
+        }
+        for cycle in &self.dependencies.circular_dependencies {
+            let _ = writeln!(text, "✗ {}", cycle.description);
+        }
+        for error in &self.dependencies.errors {
+            let _ = writeln!(
+                text,
+                "✗ 模块错误 / Module error: {}: {}",
+                error.module, error.message
+            );
+        }
+
+        let summary = &self.summary;
+        let issues: Vec<String> = summary
+            .issues
+            .iter()
+            .map(|(severity, count)| format!("{:?} {}", severity, count))
+            .collect();
+        let _ = writeln!(
+            text,
+            "{} 个文件 / files, {} 个函数 / functions, 质量 / quality {:.1} ({:?})",
+            summary.files, summary.functions, summary.quality_score, self.quality.grade
+        );
+        let _ = writeln!(
+            text,
+            "问题 / Issues: {}; {} 个克隆簇 / clone clusters, {} 个循环导入 / circular imports, {} 个解析错误 / parse errors",
+            if issues.is_empty() {
+                "0".to_string()
+            } else {
+                issues.join(", ")
+            },
+            summary.clone_clusters,
+            summary.circular_dependencies,
+            summary.parse_failures
+        );
+        if self.passed() {
+            text.push_str("✓ 项目分析通过 / Project analysis passed\n");
+        } else {
+            for violation in &self.violations {
+                let _ = writeln!(text, "✗ {}", violation);
+            }
+        }
+        text
+    }
+}
+
+/// 项目分析器 / Project analyzer
+pub struct ProjectAnalyzer {
+    reviewer: CodeReviewer,
+    detector: SimilarityDetector,
+    thresholds: ProjectThresholds,
+    search_paths: Vec<PathBuf>,
+}
+
+impl ProjectAnalyzer {
+    /// 创建项目分析器 / Create a project analyzer
+    pub fn new() -> Self {
+        Self {
+            reviewer: CodeReviewer::new(),
+            detector: SimilarityDetector::new(),
+            thresholds: ProjectThresholds::default(),
+            search_paths: Vec::new(),
+        }
+    }
+
+    /// 设置阈值 / Set the thresholds
+    pub fn with_thresholds(mut self, thresholds: ProjectThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+
+    /// 设置非相对导入的模块搜索路径 / Set the module search paths for non-relative imports
+    pub fn with_search_paths(mut self, search_paths: Vec<PathBuf>) -> Self {
+        self.search_paths = search_paths;
+        self
+    }
+
+    /// 应用审查配置 / Apply a review configuration
+    pub fn apply_config(&mut self, config: &ReviewConfig) -> Result<(), String> {
+        self.reviewer.apply_config(config)
+    }
+
+    /// 克隆检测器，可调整阈值和最小词法单元数 / Clone detector, for adjusting its threshold and minimum tokens
+    pub fn detector_mut(&mut self) -> &mut SimilarityDetector {
+        &mut self.detector
+    }
+
+    /// 阈值 / Thresholds
+    pub fn thresholds(&self) -> &ProjectThresholds {
+        &self.thresholds
+    }
+
+    /// 分析目录（或单个文件）中的全部 .evo 文件 / Analyze every .evo file in a directory (or a single file)
+    pub fn analyze(
+        &mut self,
+        parser: &AdaptiveParser,
+        path: &Path,
+    ) -> std::io::Result<ProjectReport> {
+        let mut paths = Vec::new();
+        collect_evo_files(path, &mut paths)?;
+
+        let mut files = Vec::new();
+        let mut failures = Vec::new();
+        for file in &paths {
+            let ast = match std::fs::read_to_string(file)
+                .map_err(|error| error.to_string())
+                .and_then(|source| parser.parse(&source).map_err(|error| error.to_string()))
+            {
+                Ok(ast) => ast,
+                Err(error) => {
+                    failures.push(CorpusFailure {
+                        path: file.clone(),
+                        error,
+                    });
+                    continue;
+                }
+            };
+            let analysis = CodeAnalyzer::new().analyze(&ast);
+            let quality = QualityAssessor::new().assess(&analysis);
+            let review = self.reviewer.review_code(&ast, &analysis, &quality);
+            files.push(FileReport {
+                path: file.clone(),
+                functions: analysis.statistics.function_count,
+                complexity: analysis.complexity,
+                issues: review.issues,
+            });
+        }
+
+        let quality = QualityAssessor::new().assess_project(parser, path)?;
+        let clones = self.detector.detect_project_clones(parser, path)?;
+        let dependencies = self.analyze_dependencies(&paths);
+
+        let mut issues = BTreeMap::new();
+        for issue in files.iter().flat_map(|file| &file.issues) {
+            *issues.entry(issue.severity).or_insert(0) += 1;
+        }
+        let summary = ProjectSummary {
+            files: paths.len(),
+            parse_failures: failures.len(),
+            functions: files.iter().map(|file| file.functions).sum(),
+            issues,
+            clone_clusters: clones.clusters.len(),
+            circular_dependencies: dependencies.circular_dependencies.len(),
+            quality_score: quality.overall_score,
+        };
+        let mut report = ProjectReport {
+            root: path.to_path_buf(),
+            summary,
+            files,
+            quality,
+            clones,
+            dependencies,
+            failures,
+            violations: Vec::new(),
+        };
+        report.violations = self.violations(&report);
+        Ok(report)
+    }
+
+    /// 依次以尚未被导入的文件为入口分析依赖，合并循环导入和模块错误
+    /// Analyze dependencies from each file not yet reached by an import, merging circular imports and module errors
+    fn analyze_dependencies(&self, paths: &[PathBuf]) -> DependencySummary {
+        let analyzer = DependencyAnalyzer::new();
+        let mut summary = DependencySummary::default();
+        let mut reached = HashSet::new();
+        let mut natives = HashSet::new();
+        let mut cycles = HashSet::new();
+        let mut errors = HashSet::new();
+        for path in paths {
+            let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.clone());
+            if reached.contains(&canonical) {
+                continue;
+            }
+            let graph = analyzer.analyze_project(path, &self.search_paths);
+            summary.entries.push(path.clone());
+            for module in &graph.modules {
+                let new = match &module.path {
+                    Some(path) => {
+                        reached.insert(std::fs::canonicalize(path).unwrap_or_else(|_| path.clone()))
+                    }
+                    None => natives.insert(module.id.clone()),
+                };
+                if new {
+                    summary.modules += 1;
+                }
+            }
+            summary.imports += graph.imports.len();
+            summary.calls += graph.calls.len();
+            for cycle in graph.circular_dependencies {
+                if cycles.insert(cycle.description.clone()) {
+                    summary.circular_dependencies.push(cycle);
+                }
+            }
+            for error in graph.errors {
+                if errors.insert((error.module.clone(), error.message.clone())) {
+                    summary.errors.push(error);
+                }
+            }
+        }
+        summary
+    }
+
+    /// 不满足的阈值 / Thresholds the report does not meet
+    fn violations(&self, report: &ProjectReport) -> Vec<String> {
+        let thresholds = &self.thresholds;
+        let mut violations = Vec::new();
+        let failing: usize = report
+            .summary
+            .issues
+            .iter()
+            .filter(|(severity, _)| **severity >= thresholds.fail_on)
+            .map(|(_, count)| count)
+            .sum();
+        if failing > 0 {
+            violations.push(format!(
+                "{} 个问题达到 {:?} / {} issues at {:?} or above",
+                failing, thresholds.fail_on, failing, thresholds.fail_on
+            ));
+        }
+        if let Some(min_quality) = thresholds.min_quality {
+            if report.summary.quality_score < min_quality {
+                violations.push(format!(
+                    "质量 {:.1} 低于 {:.1} / Quality {:.1} is below {:.1}",
+                    report.summary.quality_score,
+                    min_quality,
+                    report.summary.quality_score,
+                    min_quality
+                ));
+            }
+        }
+        if let Some(max_clusters) = thresholds.max_clone_clusters {
+            if report.summary.clone_clusters > max_clusters {
+                violations.push(format!(
+                    "{} 个克隆簇超过上限 {} / {} clone clusters exceed the limit of {}",
+                    report.summary.clone_clusters,
+                    max_clusters,
+                    report.summary.clone_clusters,
+                    max_clusters
+                ));
+            }
+        }
+        if !thresholds.allow_circular_dependencies && report.summary.circular_dependencies > 0 {
+            violations.push(format!(
+                "{} 个循环导入 / {} circular imports",
+                report.summary.circular_dependencies, report.summary.circular_dependencies
+            ));
+        }
+        if !thresholds.allow_parse_failures && report.summary.parse_failures > 0 {
+            violations.push(format!(
+                "{} 个文件无法解析 / {} files failed to parse",
+                report.summary.parse_failures, report.summary.parse_failures
+            ));
+        }
+        violations
+    }
+}
+
+impl Default for ProjectAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
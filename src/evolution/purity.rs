@@ -0,0 +1,232 @@
+// 纯度分析 / Purity analysis
+// 判断顶层函数是否有副作用（输出、文件和网络访问、随机数和时间、修改外部变量），经调用图传递到调用者，
+// 并找出对非纯函数的 `memoize`
+// Decides whether top-level functions have side effects (output, file and network access, randomness and time,
+// mutating outer variables), propagates impurity to callers through the call graph, and finds `memoize` applied
+// to impure functions
+
+use crate::evolution::call_graph::{definitions, CallGraph};
+use crate::grammar::core::{Expr, GrammarElement};
+use crate::grammar::visit::preorder;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 有副作用的内置函数 / Builtins with side effects
+const IMPURE_BUILTINS: &[&str] = &[
+    "print",
+    "file-read",
+    "file-write",
+    "file-append",
+    "file-exists",
+    "dir-list",
+    "http-get",
+    "http-post",
+    "http-request",
+    "import",
+    "reload",
+    "next",
+    "gc",
+    "gc-threshold",
+    "stacktrace",
+    "current-function",
+    "call-depth",
+    "set-max-call-depth",
+    "deftest",
+    "run-tests",
+];
+
+/// 结果取决于外部状态的原生模块 / Native modules whose results depend on outside state
+const IMPURE_MODULES: &[&str] = &["random", "time"];
+
+/// 对非纯函数的 `memoize` / `memoize` applied to an impure function
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImpureMemoization {
+    /// 被缓存的函数 / Memoized function
+    pub function: String,
+    /// `memoize` 所在函数，顶层代码为None / Function containing the `memoize`; None for top-level code
+    pub location: Option<String>,
+    /// 函数非纯的原因 / Why the function is impure
+    pub reason: String,
+}
+
+/// 程序的纯度分析 / Purity analysis of a program
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PurityAnalysis {
+    /// 非纯的顶层函数及原因 / Impure top-level functions with the reason
+    pub impure: BTreeMap<String, String>,
+    /// 对非纯函数的 `memoize`，按出现顺序 / `memoize` applied to impure functions, in order of appearance
+    pub impure_memoizations: Vec<ImpureMemoization>,
+}
+
+impl PurityAnalysis {
+    /// 分析程序；调用非纯函数的函数也是非纯的 / Analyze a program; functions calling impure functions are impure too
+    pub fn analyze(ast: &[GrammarElement], graph: &CallGraph) -> Self {
+        let definitions = definitions(ast);
+        let mut impure = BTreeMap::new();
+        for definition in &definitions {
+            let mut effects = Effects::default();
+            for element in definition.body {
+                effects.collect(element);
+            }
+            if let Some(reason) = effects.reason(&definition.params) {
+                impure.insert(definition.name.clone(), reason);
+            }
+        }
+
+        // 沿调用图传递到不动点 / Propagate along the call graph to a fixed point
+        loop {
+            let mut changed = false;
+            for definition in &definitions {
+                if impure.contains_key(&definition.name) {
+                    continue;
+                }
+                let callee = graph
+                    .callees(&definition.name)
+                    .into_iter()
+                    .find(|callee| impure.contains_key(*callee));
+                if let Some(callee) = callee {
+                    impure.insert(definition.name.clone(), format!("调用非纯函数 {}", callee));
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        let defined: BTreeSet<usize> = definitions.iter().map(|d| d.index).collect();
+        let scopes = definitions
+            .iter()
+            .map(|d| (Some(d.name.clone()), d.body))
+            .chain(
+                ast.iter()
+                    .enumerate()
+                    .filter(|(index, _)| !defined.contains(index))
+                    .map(|(_, element)| (None, std::slice::from_ref(element))),
+            );
+        let mut impure_memoizations = Vec::new();
+        for (location, body) in scopes {
+            let mut memoized = Vec::new();
+            for element in body {
+                collect_memoized(element, &mut memoized);
+            }
+            for function in memoized {
+                if let Some(reason) = impure.get(&function) {
+                    impure_memoizations.push(ImpureMemoization {
+                        reason: reason.clone(),
+                        function,
+                        location: location.clone(),
+                    });
+                }
+            }
+        }
+
+        Self {
+            impure,
+            impure_memoizations,
+        }
+    }
+
+    /// 函数是否没有已知的副作用 / Whether the function has no known side effects
+    pub fn is_pure(&self, function: &str) -> bool {
+        !self.impure.contains_key(function)
+    }
+}
+
+/// 函数体中的调用、赋值和局部绑定 / Calls, assignments and local bindings in a function body
+#[derive(Default)]
+struct Effects {
+    calls: Vec<String>,
+    assigned: Vec<String>,
+    locals: BTreeSet<String>,
+}
+
+impl Effects {
+    fn collect(&mut self, element: &GrammarElement) {
+        match element {
+            GrammarElement::Expr(expr) => {
+                for node in preorder(expr) {
+                    match node {
+                        Expr::Call(name, args) if name == "let" || name == "set!" => {
+                            if let Some(Expr::Var(variable)) = args.first() {
+                                self.bind(name, variable);
+                            }
+                        }
+                        Expr::Call(name, _) => self.calls.push(name.to_string()),
+                        Expr::Assign(variable, _) => self.assigned.push(variable.to_string()),
+                        Expr::For { var, .. } => {
+                            self.locals.insert(var.to_string());
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            GrammarElement::List(items) => {
+                if let [GrammarElement::Atom(head), rest @ ..] = items.as_slice() {
+                    match (head.as_str(), rest.first()) {
+                        ("let" | "set!", Some(GrammarElement::Atom(variable))) => {
+                            self.bind(head, variable)
+                        }
+                        _ => self.calls.push(head.clone()),
+                    }
+                }
+                for item in items {
+                    self.collect(item);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn bind(&mut self, form: &str, variable: &str) {
+        if form == "let" {
+            self.locals.insert(variable.to_string());
+        } else {
+            self.assigned.push(variable.to_string());
+        }
+    }
+
+    /// 第一个副作用的描述 / Description of the first side effect
+    fn reason(&self, params: &[String]) -> Option<String> {
+        if let Some(call) = self.calls.iter().find(|call| is_impure_builtin(call)) {
+            return Some(format!("调用有副作用的 {}", call));
+        }
+        self.assigned
+            .iter()
+            .find(|variable| !params.contains(variable) && !self.locals.contains(*variable))
+            .map(|variable| format!("修改外部变量 {}", variable))
+    }
+}
+
+fn is_impure_builtin(name: &str) -> bool {
+    IMPURE_BUILTINS.contains(&name)
+        || name
+            .split_once('.')
+            .is_some_and(|(module, _)| IMPURE_MODULES.contains(&module))
+}
+
+/// 收集 `(memoize f)` 中按名称给出的函数 / Collect functions given by name in `(memoize f)`
+fn collect_memoized(element: &GrammarElement, memoized: &mut Vec<String>) {
+    match element {
+        GrammarElement::Expr(expr) => {
+            for node in preorder(expr) {
+                if let Expr::Call(name, args) = node {
+                    if let (true, [Expr::Var(function)]) = (name == "memoize", args.as_slice()) {
+                        memoized.push(function.to_string());
+                    }
+                }
+            }
+        }
+        GrammarElement::List(items) => {
+            if let [GrammarElement::Atom(head), GrammarElement::Atom(function)] = items.as_slice() {
+                if head == "memoize" {
+                    memoized.push(function.clone());
+                }
+            }
+            for item in items {
+                collect_memoized(item, memoized);
+            }
+        }
+        _ => {}
+    }
+}
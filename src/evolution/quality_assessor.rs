@@ -2,10 +2,15 @@
 // 评估代码质量，提供改进建议
 // Assess code quality and provide improvement suggestions
 
-use crate::evolution::analyzer::CodeAnalysis;
+use crate::evolution::analyzer::{CodeAnalysis, CodeAnalyzer, PatternType};
+use crate::evolution::corpus::{collect_evo_files, CorpusFailure};
 use crate::grammar::core::GrammarElement;
+use crate::parser::AdaptiveParser;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::RangeBounds;
+use std::path::{Path, PathBuf};
 
 /// 代码质量评估器 / Code quality assessor
 pub struct QualityAssessor {
@@ -13,6 +18,10 @@ pub struct QualityAssessor {
     thresholds: QualityThresholds,
     /// 质量历史 / Quality history
     quality_history: Vec<QualitySnapshot>,
+    /// 项目质量历史 / Project quality history
+    project_history: Vec<ProjectSnapshot>,
+    /// 项目历史的JSON存储文件 / JSON store file for the project history
+    store: Option<PathBuf>,
 }
 
 /// 质量阈值 / Quality thresholds
@@ -140,6 +149,62 @@ pub enum QualityTrend {
     NoHistory,
 }
 
+/// 项目中单个文件的质量 / Quality of a single file in a project
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileQuality {
+    /// 文件路径 / File path
+    pub path: PathBuf,
+    /// 代码行数（不含空行和注释行），作为项目分数中的权重
+    /// Lines of code (excluding blank and comment lines), the file's weight in the project score
+    pub lines: usize,
+    /// 评估结果，趋势与上一次项目快照中该文件的分数比较
+    /// Assessment; the trend compares with the file's score in the previous project snapshot
+    pub assessment: QualityAssessment,
+}
+
+/// 项目质量评估结果 / Project quality assessment result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectQuality {
+    /// 各文件的质量 / Quality of each file
+    pub files: Vec<FileQuality>,
+    /// 按代码行数加权的总体分数 / Overall score weighted by lines of code
+    pub overall_score: f64,
+    /// 按代码行数加权的维度分数 / Dimension scores weighted by lines of code
+    pub dimension_scores: QualityDimensions,
+    /// 质量等级 / Quality grade
+    pub grade: QualityGrade,
+    /// 项目历史中的质量趋势 / Quality trend over the project history
+    pub trend: QualityTrend,
+    /// 无法读取或解析的文件 / Files that could not be read or parsed
+    pub failures: Vec<CorpusFailure>,
+}
+
+/// 项目质量快照，持久化在JSON存储中 / Project quality snapshot, persisted in the JSON store
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectSnapshot {
+    /// 时间戳 / Timestamp
+    pub timestamp: DateTime<Utc>,
+    /// 总体质量分数 / Overall quality score
+    pub overall_score: f64,
+    /// 各维度分数 / Dimension scores
+    pub dimension_scores: HashMap<String, f64>,
+    /// 各文件的总体分数 / Overall score of each file
+    pub files: BTreeMap<String, f64>,
+    /// 项目代码行数 / Lines of code in the project
+    pub lines: usize,
+}
+
+/// 时间范围内的项目质量趋势 / Project quality trend within a time range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityTrendReport {
+    /// 范围内的快照（按时间排序）/ Snapshots in the range (in chronological order)
+    pub snapshots: Vec<ProjectSnapshot>,
+    /// 最后与第一个快照的分数差 / Score difference between the last and first snapshots
+    pub change: f64,
+    /// 质量趋势 / Quality trend
+    pub trend: QualityTrend,
+}
+
 impl QualityAssessor {
     /// 创建新质量评估器 / Create new quality assessor
     pub fn new() -> Self {
@@ -151,7 +216,21 @@ impl QualityAssessor {
                 expression_complexity_threshold: 10.0,
             },
             quality_history: Vec::new(),
+            project_history: Vec::new(),
+            store: None,
+        }
+    }
+
+    /// 使用JSON文件持久化项目质量历史：已有的历史会被加载，每次 `assess_project()` 后写回
+    /// Persist the project quality history in a JSON file: existing history is loaded and written back after
+    /// every `assess_project()`
+    pub fn with_store(mut self, path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let path = path.into();
+        if path.exists() {
+            self.project_history = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
         }
+        self.store = Some(path);
+        Ok(self)
     }
 
     /// 评估代码质量 / Assess code quality
@@ -175,13 +254,7 @@ impl QualityAssessor {
         let snapshot = QualitySnapshot {
             timestamp: chrono::Utc::now(),
             overall_score,
-            dimension_scores: HashMap::from([
-                ("readability".to_string(), dimensions.readability),
-                ("maintainability".to_string(), dimensions.maintainability),
-                ("performance".to_string(), dimensions.performance),
-                ("security".to_string(), dimensions.security),
-                ("simplicity".to_string(), dimensions.simplicity),
-            ]),
+            dimension_scores: dimension_map(&dimensions),
             analysis: analysis.clone(),
         };
         self.quality_history.push(snapshot);
@@ -195,6 +268,124 @@ impl QualityAssessor {
         }
     }
 
+    /// 评估目录（或单个文件）中所有.evo文件，按代码行数加权得到项目分数，并记录到项目历史
+    /// Assess every .evo file in a directory (or a single file), weight the scores by lines of code into a project
+    /// score and record it in the project history
+    pub fn assess_project(
+        &mut self,
+        parser: &AdaptiveParser,
+        path: &Path,
+    ) -> std::io::Result<ProjectQuality> {
+        let mut paths = Vec::new();
+        collect_evo_files(path, &mut paths)?;
+
+        let previous = self.project_history.last();
+        let mut files = Vec::new();
+        let mut failures = Vec::new();
+        for file in paths {
+            let (source, ast) = match std::fs::read_to_string(&file)
+                .map_err(|error| error.to_string())
+                .and_then(|source| {
+                    let ast = parser.parse(&source).map_err(|error| error.to_string())?;
+                    Ok((source, ast))
+                }) {
+                Ok(parsed) => parsed,
+                Err(error) => {
+                    failures.push(CorpusFailure { path: file, error });
+                    continue;
+                }
+            };
+            let analysis = CodeAnalyzer::new().analyze(&ast);
+            let dimensions = self.calculate_dimensions(&analysis);
+            let overall_score = self.calculate_overall_score(&dimensions);
+            let trend = match previous.and_then(|s| s.files.get(&file.display().to_string())) {
+                Some(&score) => change_trend(overall_score - score),
+                None => QualityTrend::NoHistory,
+            };
+            files.push(FileQuality {
+                lines: lines_of_code(&source),
+                assessment: QualityAssessment {
+                    overall_score,
+                    grade: self.determine_grade(overall_score),
+                    suggestions: self.generate_suggestions(&analysis, &dimensions),
+                    dimension_scores: dimensions,
+                    trend,
+                },
+                path: file,
+            });
+        }
+
+        // 加权平均，空文件至少占一行的权重 / Weighted average; an empty file weighs at least one line
+        let total: f64 = files.iter().map(|f| f.lines.max(1) as f64).sum();
+        let weighted = |score: fn(&QualityAssessment) -> f64| {
+            if total == 0.0 {
+                return 0.0;
+            }
+            files
+                .iter()
+                .map(|f| score(&f.assessment) * f.lines.max(1) as f64)
+                .sum::<f64>()
+                / total
+        };
+        let dimensions = QualityDimensions {
+            readability: weighted(|a| a.dimension_scores.readability),
+            maintainability: weighted(|a| a.dimension_scores.maintainability),
+            performance: weighted(|a| a.dimension_scores.performance),
+            security: weighted(|a| a.dimension_scores.security),
+            simplicity: weighted(|a| a.dimension_scores.simplicity),
+        };
+        let overall_score = weighted(|a| a.overall_score);
+
+        self.project_history.push(ProjectSnapshot {
+            timestamp: Utc::now(),
+            overall_score,
+            dimension_scores: dimension_map(&dimensions),
+            files: files
+                .iter()
+                .map(|f| (f.path.display().to_string(), f.assessment.overall_score))
+                .collect(),
+            lines: files.iter().map(|f| f.lines).sum(),
+        });
+        if let Some(store) = &self.store {
+            std::fs::write(store, serde_json::to_string_pretty(&self.project_history)?)?;
+        }
+
+        let scores: Vec<f64> = self
+            .project_history
+            .iter()
+            .map(|s| s.overall_score)
+            .collect();
+        Ok(ProjectQuality {
+            files,
+            overall_score,
+            grade: self.determine_grade(overall_score),
+            dimension_scores: dimensions,
+            trend: score_trend(&scores),
+            failures,
+        })
+    }
+
+    /// 时间范围内的项目质量趋势，如 `quality_trend(Utc::now() - Duration::days(30)..)`
+    /// Project quality trend within a time range, e.g. `quality_trend(Utc::now() - Duration::days(30)..)`
+    pub fn quality_trend(&self, range: impl RangeBounds<DateTime<Utc>>) -> QualityTrendReport {
+        let mut snapshots: Vec<ProjectSnapshot> = self
+            .project_history
+            .iter()
+            .filter(|s| range.contains(&s.timestamp))
+            .cloned()
+            .collect();
+        snapshots.sort_by_key(|s| s.timestamp);
+        let scores: Vec<f64> = snapshots.iter().map(|s| s.overall_score).collect();
+        QualityTrendReport {
+            change: match (scores.first(), scores.last()) {
+                (Some(first), Some(last)) => last - first,
+                _ => 0.0,
+            },
+            trend: score_trend(&scores),
+            snapshots,
+        }
+    }
+
     /// 计算各维度分数 / Calculate dimension scores
     fn calculate_dimensions(&self, analysis: &CodeAnalysis) -> QualityDimensions {
         // 可读性：基于复杂度、嵌套深度、函数长度 / Readability: based on complexity, nesting depth, function length
@@ -290,16 +481,20 @@ impl QualityAssessor {
 
     /// 计算安全性 / Calculate security
     fn calculate_security(&self, analysis: &CodeAnalysis) -> f64 {
-        // 简化版本：基于错误模式 / Simplified version: based on error patterns
+        // 基于错误模式和安全分析发现的问题 / Based on error patterns and the issues found by security analysis
         let mut score = 100.0;
 
         for pattern in &analysis.patterns {
-            if matches!(
-                pattern.pattern_type,
-                crate::evolution::analyzer::PatternType::UnusedVariable
-            ) {
-                score -= 2.0;
-            }
+            score -= match pattern.pattern_type {
+                PatternType::UnusedVariable => 2.0,
+                PatternType::TypeError
+                | PatternType::UntrustedIo
+                | PatternType::UnboundedLoop
+                | PatternType::PermissiveImport => 10.0,
+                PatternType::UnboundedRecursion => 15.0,
+                PatternType::UnbudgetedEval => 20.0,
+                _ => 0.0,
+            };
         }
 
         (score as f64).max(0.0_f64).min(100.0_f64)
@@ -429,49 +624,72 @@ impl QualityAssessor {
 
     /// 分析质量趋势 / Analyze quality trend
     fn analyze_trend(&self, _current_score: f64) -> QualityTrend {
-        if self.quality_history.len() < 2 {
-            return QualityTrend::NoHistory;
-        }
-
-        let recent_scores: Vec<f64> = self
+        let scores: Vec<f64> = self
             .quality_history
             .iter()
-            .rev()
-            .take(5)
             .map(|s| s.overall_score)
             .collect();
-
-        if recent_scores.len() < 2 {
-            return QualityTrend::NoHistory;
-        }
-
-        let avg_recent = recent_scores.iter().sum::<f64>() / recent_scores.len() as f64;
-        let avg_older = if self.quality_history.len() > 5 {
-            self.quality_history
-                .iter()
-                .rev()
-                .skip(5)
-                .take(5)
-                .map(|s| s.overall_score)
-                .sum::<f64>()
-                / 5.0
-        } else {
-            recent_scores[recent_scores.len() - 1]
-        };
-
-        if avg_recent > avg_older + 2.0 {
-            QualityTrend::Improving
-        } else if avg_recent < avg_older - 2.0 {
-            QualityTrend::Declining
-        } else {
-            QualityTrend::Stable
-        }
+        score_trend(&scores)
     }
 
     /// 获取质量历史 / Get quality history
     pub fn get_quality_history(&self) -> &[QualitySnapshot] {
         &self.quality_history
     }
+
+    /// 获取项目质量历史 / Get project quality history
+    pub fn get_project_history(&self) -> &[ProjectSnapshot] {
+        &self.project_history
+    }
+}
+
+/// 按时间排序的分数序列的趋势：最近5个分数的均值与之前5个（或第一个）比较，变化超过2分才算改善或下降
+/// Trend of a chronological score series: the mean of the last 5 scores is compared with the 5 before them (or the
+/// first score); only a change above 2 points counts as improving or declining
+fn score_trend(scores: &[f64]) -> QualityTrend {
+    if scores.len() < 2 {
+        return QualityTrend::NoHistory;
+    }
+    let recent = &scores[scores.len().saturating_sub(5)..];
+    let older = &scores[scores.len().saturating_sub(10)..scores.len() - recent.len()];
+    let avg_recent = recent.iter().sum::<f64>() / recent.len() as f64;
+    let avg_older = if older.is_empty() {
+        scores[0]
+    } else {
+        older.iter().sum::<f64>() / older.len() as f64
+    };
+    change_trend(avg_recent - avg_older)
+}
+
+/// 分数变化对应的趋势 / Trend for a score change
+fn change_trend(change: f64) -> QualityTrend {
+    if change > 2.0 {
+        QualityTrend::Improving
+    } else if change < -2.0 {
+        QualityTrend::Declining
+    } else {
+        QualityTrend::Stable
+    }
+}
+
+/// 维度分数按名称索引 / Dimension scores keyed by name
+fn dimension_map(dimensions: &QualityDimensions) -> HashMap<String, f64> {
+    HashMap::from([
+        ("readability".to_string(), dimensions.readability),
+        ("maintainability".to_string(), dimensions.maintainability),
+        ("performance".to_string(), dimensions.performance),
+        ("security".to_string(), dimensions.security),
+        ("simplicity".to_string(), dimensions.simplicity),
+    ])
+}
+
+/// 代码行数，不含空行和注释行 / Lines of code, excluding blank and comment lines
+fn lines_of_code(source: &str) -> usize {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(';'))
+        .count()
 }
 
 impl Default for QualityAssessor {
@@ -0,0 +1,406 @@
+// 报告导出 / Report export
+// 把代码分析、质量评估和代码审查的结果导出为JSON或SARIF 2.1.0，供CI系统和编辑器读取
+// Exports code analysis, quality assessment and code review results as JSON or SARIF 2.1.0 for CI systems and editors
+
+use crate::evolution::analyzer::CodeAnalysis;
+use crate::evolution::code_reviewer::{CodeReviewResult, ReviewSeverity};
+use crate::evolution::quality_assessor::{Priority, QualityAssessment};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// SARIF版本 / SARIF version
+pub const SARIF_VERSION: &str = "2.1.0";
+/// SARIF模式 / SARIF schema
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// 可导出的结果 / An exportable result
+pub trait ReportExport: Serialize {
+    /// 导出为格式化的JSON / Export as pretty-printed JSON
+    fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("报告可序列化 / reports are serializable")
+    }
+
+    /// 导出为SARIF；`artifact` 为被检查文件的URI / Export as SARIF; `artifact` is the URI of the checked file
+    fn to_sarif(&self, artifact: Option<&str>) -> SarifLog;
+}
+
+/// SARIF日志 / SARIF log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SarifLog {
+    /// 模式 / Schema
+    #[serde(rename = "$schema")]
+    pub schema: String,
+    /// 版本 / Version
+    pub version: String,
+    /// 每个工具一次运行 / One run per tool
+    pub runs: Vec<SarifRun>,
+}
+
+impl SarifLog {
+    /// 由若干运行创建日志 / Create a log from runs
+    pub fn new(runs: Vec<SarifRun>) -> Self {
+        Self {
+            schema: SARIF_SCHEMA.to_string(),
+            version: SARIF_VERSION.to_string(),
+            runs,
+        }
+    }
+
+    /// 合并多个日志的运行，如同时导出分析、质量和审查结果 / Merge the runs of several logs, e.g. analysis, quality and review together
+    pub fn combine(logs: impl IntoIterator<Item = SarifLog>) -> Self {
+        Self::new(logs.into_iter().flat_map(|log| log.runs).collect())
+    }
+
+    /// 全部结果数 / Total number of results
+    pub fn result_count(&self) -> usize {
+        self.runs.iter().map(|run| run.results.len()).sum()
+    }
+
+    /// 导出为格式化的JSON / Export as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("报告可序列化 / reports are serializable")
+    }
+}
+
+/// 一个工具的一次运行 / One run of a tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SarifRun {
+    /// 工具 / Tool
+    pub tool: SarifTool,
+    /// 结果 / Results
+    pub results: Vec<SarifResult>,
+    /// 运行级属性，如质量分数 / Run-level properties such as quality scores
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, serde_json::Value>,
+}
+
+/// 工具 / Tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SarifTool {
+    /// 驱动 / Driver
+    pub driver: SarifDriver,
+}
+
+/// 工具驱动及其规则 / Tool driver and its rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifDriver {
+    /// 工具名称 / Tool name
+    pub name: String,
+    /// 版本 / Version
+    pub version: String,
+    /// 规则 / Rules
+    pub rules: Vec<SarifRule>,
+}
+
+/// 规则描述 / Rule descriptor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifRule {
+    /// 规则标识 / Rule id
+    pub id: String,
+    /// 规则名称 / Rule name
+    pub name: String,
+    /// 简短描述 / Short description
+    pub short_description: SarifMessage,
+}
+
+/// 结果 / Result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    /// 规则标识 / Rule id
+    pub rule_id: String,
+    /// 级别：error、warning、note / Level: error, warning or note
+    pub level: String,
+    /// 消息 / Message
+    pub message: SarifMessage,
+    /// 位置 / Locations
+    pub locations: Vec<SarifLocation>,
+    /// 附加属性，如建议和置信度 / Extra properties such as suggestion and confidence
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, serde_json::Value>,
+}
+
+/// 消息 / Message
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SarifMessage {
+    /// 文本 / Text
+    pub text: String,
+}
+
+/// 位置：被检查的文件和逻辑位置（如函数）/ Location: the checked file and a logical location such as a function
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifLocation {
+    /// 物理位置 / Physical location
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub physical_location: Option<SarifPhysicalLocation>,
+    /// 逻辑位置 / Logical locations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logical_locations: Vec<SarifLogicalLocation>,
+}
+
+/// 物理位置 / Physical location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifPhysicalLocation {
+    /// 文件 / Artifact
+    pub artifact_location: SarifArtifactLocation,
+}
+
+/// 文件位置 / Artifact location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SarifArtifactLocation {
+    /// 文件URI / Artifact URI
+    pub uri: String,
+}
+
+/// 逻辑位置 / Logical location
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SarifLogicalLocation {
+    /// 名称 / Name
+    pub name: String,
+    /// 类别，如 function / Kind such as function
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+}
+
+impl SarifMessage {
+    fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into() }
+    }
+}
+
+impl SarifResult {
+    fn new(
+        rule_id: String,
+        level: &str,
+        message: String,
+        artifact: Option<&str>,
+        location: &str,
+    ) -> Self {
+        Self {
+            rule_id,
+            level: level.to_string(),
+            message: SarifMessage::new(message),
+            locations: vec![sarif_location(artifact, location)],
+            properties: BTreeMap::new(),
+        }
+    }
+
+    fn with_property(mut self, key: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.properties.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+/// 由工具名称、规则和结果组成一次运行 / Build a run from a tool name, rules and results
+fn sarif_run(
+    tool: &str,
+    rules: BTreeMap<String, (String, String)>,
+    results: Vec<SarifResult>,
+) -> SarifRun {
+    SarifRun {
+        tool: SarifTool {
+            driver: SarifDriver {
+                name: tool.to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                rules: rules
+                    .into_iter()
+                    .map(|(id, (name, description))| SarifRule {
+                        id,
+                        name,
+                        short_description: SarifMessage::new(description),
+                    })
+                    .collect(),
+            },
+        },
+        results,
+        properties: BTreeMap::new(),
+    }
+}
+
+/// 把位置字符串（如 `函数 add`、`function add`、`整体`）转为SARIF位置
+/// Turn a location string (such as `函数 add`, `function add` or `整体`) into a SARIF location
+fn sarif_location(artifact: Option<&str>, location: &str) -> SarifLocation {
+    let logical = match location.split_once(' ') {
+        Some(("函数" | "function", name)) => Some(SarifLogicalLocation {
+            name: name.to_string(),
+            kind: Some("function".to_string()),
+        }),
+        Some(("名称", name)) => Some(SarifLogicalLocation {
+            name: name.to_string(),
+            kind: Some("variable".to_string()),
+        }),
+        // 整体或泛指的位置没有逻辑位置 / Whole-program or generic locations have no logical location
+        _ if matches!(location, "整体" | "code" | "expression" | "function" | "") => None,
+        _ => Some(SarifLogicalLocation {
+            name: location.to_string(),
+            kind: None,
+        }),
+    };
+    SarifLocation {
+        physical_location: artifact.map(|uri| SarifPhysicalLocation {
+            artifact_location: SarifArtifactLocation {
+                uri: uri.to_string(),
+            },
+        }),
+        logical_locations: logical.into_iter().collect(),
+    }
+}
+
+/// 枚举变体名转为规则标识，如 `LongFunction` → `long_function` / Variant name to rule id, e.g. `LongFunction` → `long_function`
+fn snake_case(variant: &impl std::fmt::Debug) -> String {
+    let name = format!("{:?}", variant);
+    let mut id = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            id.push('_');
+        }
+        id.push(c.to_ascii_lowercase());
+    }
+    id
+}
+
+/// 审查严重程度对应的SARIF级别 / SARIF level for a review severity
+pub fn sarif_level(severity: ReviewSeverity) -> &'static str {
+    match severity {
+        ReviewSeverity::Critical | ReviewSeverity::Error => "error",
+        ReviewSeverity::Warning => "warning",
+        ReviewSeverity::Info => "note",
+    }
+}
+
+impl ReportExport for CodeReviewResult {
+    fn to_sarif(&self, artifact: Option<&str>) -> SarifLog {
+        let mut rules = BTreeMap::new();
+        let results = self
+            .issues
+            .iter()
+            .map(|issue| {
+                rules
+                    .entry(issue.rule_id.clone())
+                    .or_insert_with(|| (issue.rule_name.clone(), issue.rule_name.clone()));
+                SarifResult::new(
+                    issue.rule_id.clone(),
+                    sarif_level(issue.severity),
+                    issue.description.clone(),
+                    artifact,
+                    &issue.location,
+                )
+                .with_property("suggestion", issue.suggestion.clone())
+                .with_property("confidence", issue.confidence)
+            })
+            .collect();
+        let mut run = sarif_run("evo-reviewer", rules, results);
+        run.properties
+            .insert("grade".to_string(), serde_json::json!(self.grade));
+        run.properties.insert(
+            "passRate".to_string(),
+            serde_json::json!(self.summary.pass_rate),
+        );
+        SarifLog::new(vec![run])
+    }
+}
+
+impl ReportExport for CodeAnalysis {
+    fn to_sarif(&self, artifact: Option<&str>) -> SarifLog {
+        use crate::evolution::analyzer::PatternType;
+
+        let mut rules = BTreeMap::new();
+        let mut results = Vec::new();
+        for pattern in &self.patterns {
+            let id = snake_case(&pattern.pattern_type);
+            let level = match pattern.pattern_type {
+                PatternType::TypeError
+                | PatternType::UnboundedRecursion
+                | PatternType::UnbudgetedEval => "error",
+                PatternType::LongFunction
+                | PatternType::DeepNesting
+                | PatternType::ComplexExpression
+                | PatternType::UnusedVariable
+                | PatternType::UnusedParameter
+                | PatternType::UnreachableFunction
+                | PatternType::UntrustedIo
+                | PatternType::UnboundedLoop
+                | PatternType::PermissiveImport
+                | PatternType::ImpureMemoization => "warning",
+                PatternType::Duplicate | PatternType::Simplifiable | PatternType::Mergeable => {
+                    "note"
+                }
+            };
+            rules.entry(id.clone()).or_insert_with(|| {
+                (
+                    format!("{:?}", pattern.pattern_type),
+                    format!("{:?}", pattern.pattern_type),
+                )
+            });
+            results.push(
+                SarifResult::new(
+                    id,
+                    level,
+                    pattern.description.clone(),
+                    artifact,
+                    &pattern.location,
+                )
+                .with_property("confidence", pattern.confidence),
+            );
+        }
+        for suggestion in &self.suggestions {
+            let id = format!("optimization/{}", snake_case(&suggestion.suggestion_type));
+            rules.entry(id.clone()).or_insert_with(|| {
+                let name = format!("{:?}", suggestion.suggestion_type);
+                (name.clone(), name)
+            });
+            results.push(
+                SarifResult::new(id, "note", suggestion.description.clone(), artifact, "整体")
+                    .with_property("original", suggestion.original.clone())
+                    .with_property("suggested", suggestion.suggested.clone())
+                    .with_property("improvement", suggestion.improvement),
+            );
+        }
+        let mut run = sarif_run("evo-analyzer", rules, results);
+        run.properties
+            .insert("complexity".to_string(), serde_json::json!(self.complexity));
+        run.properties
+            .insert("statistics".to_string(), serde_json::json!(self.statistics));
+        SarifLog::new(vec![run])
+    }
+}
+
+impl ReportExport for QualityAssessment {
+    fn to_sarif(&self, artifact: Option<&str>) -> SarifLog {
+        let mut rules = BTreeMap::new();
+        let results = self
+            .suggestions
+            .iter()
+            .map(|suggestion| {
+                let id = format!("quality/{}", snake_case(&suggestion.suggestion_type));
+                rules.entry(id.clone()).or_insert_with(|| {
+                    let name = format!("{:?}", suggestion.suggestion_type);
+                    (name.clone(), name)
+                });
+                let level = match suggestion.priority {
+                    Priority::Critical => "error",
+                    Priority::High | Priority::Medium => "warning",
+                    Priority::Low => "note",
+                };
+                SarifResult::new(id, level, suggestion.description.clone(), artifact, "整体")
+                    .with_property("improvement", suggestion.improvement.clone())
+            })
+            .collect();
+        let mut run = sarif_run("evo-quality", rules, results);
+        run.properties.insert(
+            "overallScore".to_string(),
+            serde_json::json!(self.overall_score),
+        );
+        run.properties
+            .insert("grade".to_string(), serde_json::json!(self.grade));
+        run.properties.insert(
+            "dimensions".to_string(),
+            serde_json::json!(self.dimension_scores),
+        );
+        SarifLog::new(vec![run])
+    }
+}
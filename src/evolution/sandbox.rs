@@ -0,0 +1,267 @@
+// 沙箱批量执行器 / Sandbox batch runner
+// 在线程池中并行评估大量候选程序，每个程序使用独立的解释器和执行预算，汇总结果与适应度
+// Evaluates many candidate programs in parallel on a thread pool, each with its own interpreter and
+// execution budget, and collects results with fitness scores
+
+use crate::grammar::core::GrammarElement;
+use crate::parser::AdaptiveParser;
+use crate::runtime::coverage::ProbeHits;
+use crate::runtime::interpreter::{ExecutionBudget, Interpreter, InterpreterOptions, Value};
+use crate::runtime::trace::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 沙箱配置 / Sandbox configuration
+#[derive(Debug, Clone)]
+pub struct SandboxConfig {
+    /// 工作线程数（0表示使用可用核心数）/ Worker threads (0 uses the available cores)
+    pub threads: usize,
+    /// 每个程序的执行预算 / Execution budget of each program
+    pub budget: ExecutionBudget,
+    /// 每个程序的解释器选项（默认禁止文件访问）/ Interpreter options of each program (file access denied by default)
+    pub options: InterpreterOptions,
+    /// 随机数种子，程序按序号偏移，结果可复现（None表示不固定）
+    /// Random seed, offset by program index so results are reproducible (None leaves it unseeded)
+    pub seed: Option<u64>,
+    /// 工作线程栈大小（字节），深度递归的生成程序需要较大的栈
+    /// Worker thread stack size in bytes; deeply recursive generated programs need a large stack
+    pub stack_size: usize,
+    /// 记录覆盖率探针命中次数（程序需先用 `CoverageMap` 插桩）/ Record coverage probe hits (programs must be instrumented with `CoverageMap` first)
+    pub coverage: bool,
+}
+
+impl Default for SandboxConfig {
+    fn default() -> Self {
+        Self {
+            threads: 0,
+            budget: ExecutionBudget::for_generated_code(),
+            options: InterpreterOptions::sandboxed(),
+            seed: None,
+            stack_size: 256 * 1024 * 1024,
+            coverage: false,
+        }
+    }
+}
+
+/// 单个程序的执行结果 / Execution result of a single program
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxResult {
+    /// 程序在批次中的序号 / Index of the program in the batch
+    pub index: usize,
+    /// 返回值（出错时为None）/ Returned value (None on error)
+    pub value: Option<Value>,
+    /// 解析或执行错误 / Parse or execution error
+    pub error: Option<String>,
+    /// `print` 等内置函数的输出 / Output of `print` and similar builtins
+    pub output: String,
+    /// 使用的求值步数 / Evaluation steps used
+    pub steps: u64,
+    /// 执行耗时 / Execution time
+    pub duration: Duration,
+    /// 适应度分数 / Fitness score
+    pub fitness: f64,
+    /// 覆盖率探针命中次数（未开启覆盖率时为空）/ Coverage probe hit counts (empty unless coverage is on)
+    #[serde(default)]
+    pub coverage: ProbeHits,
+}
+
+impl SandboxResult {
+    /// 是否执行成功 / Whether execution succeeded
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// 沙箱批量执行器 / Sandbox batch runner
+#[derive(Debug, Clone, Default)]
+pub struct SandboxRunner {
+    config: SandboxConfig,
+}
+
+impl SandboxRunner {
+    /// 使用默认配置创建 / Create with the default configuration
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用指定配置创建 / Create with the given configuration
+    pub fn with_config(config: SandboxConfig) -> Self {
+        Self { config }
+    }
+
+    /// 获取配置 / Get configuration
+    pub fn config(&self) -> &SandboxConfig {
+        &self.config
+    }
+
+    /// 实际使用的工作线程数 / Number of worker threads actually used
+    pub fn thread_count(&self) -> usize {
+        match self.config.threads {
+            0 => std::thread::available_parallelism().map_or(1, usize::from),
+            threads => threads,
+        }
+    }
+
+    /// 解析并执行源码，适应度按默认规则计算 / Parse and run source code, scoring fitness by the default rule
+    pub fn run_code(&self, parser: &AdaptiveParser, programs: &[String]) -> Vec<SandboxResult> {
+        self.run_code_with_fitness(parser, programs, default_fitness)
+    }
+
+    /// 解析并执行源码，用 `fitness` 计算适应度；解析在调用线程完成，解析失败的程序适应度为0
+    /// Parse and run source code, scoring with `fitness`; parsing happens on the calling thread and programs
+    /// that fail to parse score 0
+    pub fn run_code_with_fitness<F>(
+        &self,
+        parser: &AdaptiveParser,
+        programs: &[String],
+        fitness: F,
+    ) -> Vec<SandboxResult>
+    where
+        F: Fn(&SandboxResult) -> f64 + Sync,
+    {
+        let parsed: Vec<Result<Vec<GrammarElement>, String>> = programs
+            .iter()
+            .map(|code| parser.parse(code).map_err(|e| e.to_string()))
+            .collect();
+        self.run_batch(&parsed, &fitness)
+    }
+
+    /// 执行已解析的程序，适应度按默认规则计算 / Run parsed programs, scoring fitness by the default rule
+    pub fn run(&self, programs: &[Vec<GrammarElement>]) -> Vec<SandboxResult> {
+        self.run_with_fitness(programs, default_fitness)
+    }
+
+    /// 执行已解析的程序，用 `fitness` 计算适应度 / Run parsed programs, scoring with `fitness`
+    pub fn run_with_fitness<F>(
+        &self,
+        programs: &[Vec<GrammarElement>],
+        fitness: F,
+    ) -> Vec<SandboxResult>
+    where
+        F: Fn(&SandboxResult) -> f64 + Sync,
+    {
+        let programs: Vec<Result<Vec<GrammarElement>, String>> =
+            programs.iter().cloned().map(Ok).collect();
+        self.run_batch(&programs, &fitness)
+    }
+
+    /// 工作线程从共享计数器领取程序，结果按序号排序返回；即使只有一个线程也不在调用线程上执行
+    /// Worker threads claim programs from a shared counter; results are returned sorted by index. Programs never
+    /// run on the calling thread, even with a single worker
+    fn run_batch<F>(
+        &self,
+        programs: &[Result<Vec<GrammarElement>, String>],
+        fitness: &F,
+    ) -> Vec<SandboxResult>
+    where
+        F: Fn(&SandboxResult) -> f64 + Sync,
+    {
+        // WebAssembly中没有线程，串行执行 / WebAssembly has no threads, so run serially
+        if cfg!(target_arch = "wasm32") {
+            return programs
+                .iter()
+                .enumerate()
+                .map(|(index, program)| self.run_one(index, program, fitness))
+                .collect();
+        }
+
+        let threads = self.thread_count().clamp(1, programs.len().max(1));
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<SandboxResult> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .filter_map(|_| {
+                    std::thread::Builder::new()
+                        .stack_size(self.config.stack_size)
+                        .spawn_scoped(scope, || {
+                            let mut done = Vec::new();
+                            loop {
+                                let index = next.fetch_add(1, Ordering::Relaxed);
+                                let Some(program) = programs.get(index) else {
+                                    break;
+                                };
+                                done.push(self.run_one(index, program, fitness));
+                            }
+                            done
+                        })
+                        .ok()
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_default())
+                .collect()
+        });
+        results.sort_by_key(|result| result.index);
+        results
+    }
+
+    /// 在独立解释器中执行一个程序；解释器的panic记为错误，不影响其他程序
+    /// Run one program in its own interpreter; an interpreter panic is recorded as an error without affecting other programs
+    fn run_one<F>(
+        &self,
+        index: usize,
+        program: &Result<Vec<GrammarElement>, String>,
+        fitness: &F,
+    ) -> SandboxResult
+    where
+        F: Fn(&SandboxResult) -> f64 + Sync,
+    {
+        let mut result = SandboxResult {
+            index,
+            value: None,
+            error: None,
+            output: String::new(),
+            steps: 0,
+            duration: Duration::ZERO,
+            fitness: 0.0,
+            coverage: ProbeHits::new(),
+        };
+        let ast = match program {
+            Ok(ast) => ast,
+            Err(error) => {
+                result.error = Some(format!("Parse error: {}", error));
+                return result;
+            }
+        };
+
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let mut interpreter = Interpreter::with_options(self.config.options.clone());
+        interpreter.set_budget(self.config.budget.clone());
+        interpreter.set_output_sink(Some(output.clone()));
+        if let Some(seed) = self.config.seed {
+            interpreter.seed_random(seed.wrapping_add(index as u64));
+        }
+        if self.config.coverage {
+            interpreter.enable_coverage();
+        }
+
+        let started_at = Timestamp::now();
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| interpreter.execute(ast)));
+        result.duration = started_at.elapsed();
+        result.steps = interpreter.steps();
+        result.coverage = interpreter.take_coverage().unwrap_or_default();
+        match outcome {
+            Ok(Ok(value)) => result.value = Some(value),
+            Ok(Err(error)) => result.error = Some(error.to_string()),
+            Err(_) => result.error = Some("Interpreter panicked".to_string()),
+        }
+        result.output = output
+            .lock()
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or_default();
+        result.fitness = fitness(&result);
+        result
+    }
+}
+
+/// 默认适应度：成功为1，出错为0 / Default fitness: 1 on success, 0 on error
+pub fn default_fitness(result: &SandboxResult) -> f64 {
+    if result.succeeded() {
+        1.0
+    } else {
+        0.0
+    }
+}
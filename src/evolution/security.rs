@@ -0,0 +1,308 @@
+// 安全分析 / Security analysis
+// 找出以不受信任的数据访问文件和网络、无界递归和循环、不受预算限制地执行生成的代码以及过于宽松的导入
+// Finds file and network access driven by untrusted data, unbounded recursion and loops, evaluation of generated
+// code without a budget and overly permissive imports
+
+use crate::evolution::call_graph::{definitions, CallGraph};
+use crate::grammar::core::{Expr, GrammarElement, Literal};
+use crate::grammar::visit::preorder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// 文件访问内置函数，第一个参数是路径 / File access builtins; the first argument is the path
+const FILE_BUILTINS: &[&str] = &[
+    "file-read",
+    "file-write",
+    "file-append",
+    "file-exists",
+    "dir-list",
+];
+
+/// 网络访问内置函数，第一个参数是地址 / Network access builtins; the first argument is the URL
+const NETWORK_BUILTINS: &[&str] = &["http-get", "http-post", "http-request"];
+
+/// 不受执行预算限制地执行代码字符串的内置函数 / Builtins that evaluate a code string outside any execution budget
+const EVAL_BUILTINS: &[&str] = &["eval", "eval-string"];
+
+/// 安全问题类型 / Security issue kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityIssueKind {
+    /// 以计算得到的路径或地址访问文件或网络 / File or network access with a computed path or URL
+    UntrustedIo,
+    /// 每条路径都会递归的函数 / Function that recurses on every path
+    UnboundedRecursion,
+    /// 条件不会改变的循环 / Loop whose condition never changes
+    UnboundedLoop,
+    /// 不受预算限制地执行生成的代码 / Evaluation of generated code without a budget
+    UnbudgetedEval,
+    /// 计算得到的、绝对的或逃出项目目录的导入路径 / Computed, absolute or project-escaping import path
+    PermissiveImport,
+}
+
+/// 安全问题 / Security issue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityIssue {
+    /// 问题类型 / Issue kind
+    pub kind: SecurityIssueKind,
+    /// 所在函数，顶层代码为None / Enclosing function; None for top-level code
+    pub function: Option<String>,
+    /// 问题描述 / Description
+    pub description: String,
+}
+
+/// 程序的安全问题 / Security issues of a program
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecurityScan {
+    /// 发现的问题，按出现顺序 / Issues found, in order of appearance
+    pub issues: Vec<SecurityIssue>,
+}
+
+impl SecurityScan {
+    /// 扫描程序；递归通过调用图判断 / Scan a program; recursion is judged through the call graph
+    pub fn scan(ast: &[GrammarElement], graph: &CallGraph) -> Self {
+        let mut scan = Self::default();
+        let definitions = definitions(ast);
+        let defined: BTreeSet<usize> = definitions.iter().map(|d| d.index).collect();
+
+        for definition in &definitions {
+            let function = Some(definition.name.clone());
+            for element in definition.body {
+                scan.scan_element(element, &function);
+            }
+
+            let cycle = recursion_cycle(graph, &definition.name);
+            if cycle.is_empty() {
+                continue;
+            }
+            let recurses = definition.body.iter().any(|element| match element {
+                GrammarElement::Expr(expr) => always_recurses(expr, &cycle),
+                _ => false,
+            });
+            if recurses {
+                let others: Vec<&str> = cycle
+                    .iter()
+                    .map(String::as_str)
+                    .filter(|name| *name != definition.name)
+                    .collect();
+                let description = if others.is_empty() {
+                    format!(
+                        "函数 {} 的每条路径都会递归调用自身，没有终止条件",
+                        definition.name
+                    )
+                } else {
+                    format!(
+                        "函数 {} 的每条路径都会经 {} 递归，没有终止条件",
+                        definition.name,
+                        others.join("、")
+                    )
+                };
+                scan.push(
+                    SecurityIssueKind::UnboundedRecursion,
+                    &function,
+                    description,
+                );
+            }
+        }
+
+        for (index, element) in ast.iter().enumerate() {
+            if !defined.contains(&index) {
+                scan.scan_element(element, &None);
+            }
+        }
+        scan
+    }
+
+    /// 是否没有问题 / Whether no issue was found
+    pub fn is_empty(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// 某类问题 / Issues of one kind
+    pub fn of_kind(&self, kind: SecurityIssueKind) -> impl Iterator<Item = &SecurityIssue> {
+        self.issues.iter().filter(move |issue| issue.kind == kind)
+    }
+
+    fn push(&mut self, kind: SecurityIssueKind, function: &Option<String>, description: String) {
+        self.issues.push(SecurityIssue {
+            kind,
+            function: function.clone(),
+            description,
+        });
+    }
+
+    fn scan_element(&mut self, element: &GrammarElement, function: &Option<String>) {
+        match element {
+            GrammarElement::Expr(expr) => {
+                for node in preorder(expr) {
+                    self.scan_expr(node, function);
+                }
+            }
+            GrammarElement::List(items) => {
+                for item in items {
+                    self.scan_element(item, function);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn scan_expr(&mut self, expr: &Expr, function: &Option<String>) {
+        match expr {
+            Expr::Call(name, args)
+                if FILE_BUILTINS.contains(&name.as_str())
+                    && !args.first().is_some_and(is_string_literal) =>
+            {
+                self.push(
+                    SecurityIssueKind::UntrustedIo,
+                    function,
+                    format!("{} 使用计算得到的路径，可能访问任意文件", name),
+                );
+            }
+            Expr::Call(name, args)
+                if NETWORK_BUILTINS.contains(&name.as_str())
+                    && !args.first().is_some_and(is_string_literal) =>
+            {
+                self.push(
+                    SecurityIssueKind::UntrustedIo,
+                    function,
+                    format!("{} 使用计算得到的地址，可能请求任意主机", name),
+                );
+            }
+            Expr::Call(name, _) if EVAL_BUILTINS.contains(&name.as_str()) => {
+                self.push(
+                    SecurityIssueKind::UnbudgetedEval,
+                    function,
+                    format!(
+                        "{} 在没有执行预算的情况下执行生成的代码，可改用 sandbox-eval",
+                        name
+                    ),
+                );
+            }
+            Expr::Call(name, args) if name == "import" || name == "reload" => {
+                let description = match args.first() {
+                    Some(Expr::Literal(Literal::String(path))) => permissive_path(path)
+                        .map(|reason| format!("{} \"{}\" {}", name, path, reason)),
+                    Some(_) => Some(format!("{} 的模块路径是计算得到的，可以加载任意代码", name)),
+                    None => None,
+                };
+                if let Some(description) = description {
+                    self.push(SecurityIssueKind::PermissiveImport, function, description);
+                }
+            }
+            Expr::While { condition, body } => {
+                if let Some(reason) = unbounded_loop(condition, body) {
+                    self.push(
+                        SecurityIssueKind::UnboundedLoop,
+                        function,
+                        format!("while 循环{}", reason),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_string_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Literal(Literal::String(_)))
+}
+
+/// 导入路径过于宽松的原因 / Why an import path is overly permissive
+fn permissive_path(path: &str) -> Option<&'static str> {
+    if path.starts_with('/') || path.starts_with('\\') || path.get(1..3) == Some(":\\") {
+        Some("是绝对路径，依赖项目以外的文件")
+    } else if path.split(['/', '\\']).any(|segment| segment == "..") {
+        Some("经 .. 逃出了项目目录")
+    } else {
+        None
+    }
+}
+
+/// 与函数互相递归的函数（含自身），不递归时为空 / Functions mutually recursive with a function (including itself); empty when it does not recurse
+fn recursion_cycle(graph: &CallGraph, function: &str) -> BTreeSet<String> {
+    let reachable = reachable_from(graph, function);
+    reachable
+        .iter()
+        .filter(|callee| reachable_from(graph, callee).contains(function))
+        .cloned()
+        .collect()
+}
+
+/// 从函数出发经一次或多次调用可到达的函数 / Functions reachable from a function through one or more calls
+fn reachable_from(graph: &CallGraph, function: &str) -> BTreeSet<String> {
+    let mut reachable = BTreeSet::new();
+    let mut stack: Vec<&str> = graph.callees(function);
+    while let Some(name) = stack.pop() {
+        if reachable.insert(name.to_string()) {
+            stack.extend(graph.callees(name));
+        }
+    }
+    reachable
+}
+
+/// 求值表达式是否必然调用环中的函数 / Whether evaluating an expression necessarily calls a function in the cycle
+fn always_recurses(expr: &Expr, cycle: &BTreeSet<String>) -> bool {
+    let any = |exprs: &[Expr]| exprs.iter().any(|expr| always_recurses(expr, cycle));
+    match expr {
+        Expr::Call(name, args) => cycle.contains(name.as_str()) || any(args),
+        Expr::Binary(_, left, right) => {
+            always_recurses(left, cycle) || always_recurses(right, cycle)
+        }
+        Expr::If(condition, then_branch, else_branch) => {
+            always_recurses(condition, cycle)
+                || (always_recurses(then_branch, cycle) && always_recurses(else_branch, cycle))
+        }
+        Expr::Match(value, arms) => {
+            always_recurses(value, cycle)
+                || (!arms.is_empty() && arms.iter().all(|(_, arm)| always_recurses(arm, cycle)))
+        }
+        Expr::For { iterable, .. } => always_recurses(iterable, cycle),
+        Expr::While { condition, .. } => always_recurses(condition, cycle),
+        Expr::Try { try_body, .. } => always_recurses(try_body, cycle),
+        Expr::Begin(exprs) => any(exprs),
+        Expr::Assign(_, value) => always_recurses(value, cycle),
+        Expr::Literal(Literal::List(items)) => any(items),
+        Expr::Literal(_) | Expr::Var(_) | Expr::Lambda { .. } => false,
+    }
+}
+
+/// 循环无界的原因：条件恒为真，或只由循环体不修改的变量和操作符组成
+/// Why a loop is unbounded: its condition is always true, or consists only of operators and variables the body
+/// never changes
+fn unbounded_loop(condition: &Expr, body: &Expr) -> Option<String> {
+    let nodes = preorder(condition);
+    // 条件调用了函数时可能读取外部状态 / A condition calling functions may read outside state
+    if nodes
+        .iter()
+        .any(|node| matches!(node, Expr::Call(name, _) if !name.starts_with("op:")))
+    {
+        return None;
+    }
+    let variables: BTreeSet<&str> = nodes
+        .iter()
+        .filter_map(|node| match node {
+            Expr::Var(name) => Some(name.as_str()),
+            _ => None,
+        })
+        .collect();
+    if variables.is_empty() {
+        return match condition {
+            Expr::Literal(Literal::Bool(false) | Literal::Null) => None,
+            Expr::Literal(_) => Some("的条件恒为真，永远不会结束".to_string()),
+            _ => None,
+        };
+    }
+    let assigned = preorder(body).into_iter().any(|node| match node {
+        Expr::Assign(name, _) => variables.contains(name.as_str()),
+        Expr::Call(name, args) if name == "let" => {
+            matches!(args.first(), Some(Expr::Var(var)) if variables.contains(var.as_str()))
+        }
+        _ => false,
+    });
+    (!assigned).then(|| {
+        format!(
+            "体不会修改条件中的变量 {}，条件永远不会改变",
+            variables.into_iter().collect::<Vec<_>>().join("、")
+        )
+    })
+}
@@ -1,16 +1,33 @@
 // 代码相似度检测器 / Code similarity detector
-// 检测代码重复和相似模式
-// Detect code duplication and similar patterns
+// 检测代码重复和相似模式，以及跨文件的函数级克隆（改名的类型2克隆和近似的类型3克隆）
+// Detect code duplication and similar patterns, and function-level clones across files (renamed type-2 and near-miss type-3 clones)
 
 use crate::evolution::analyzer::CodeAnalysis;
-use crate::grammar::core::GrammarElement;
+use crate::evolution::call_graph::{definitions, Definition};
+use crate::evolution::corpus::{collect_evo_files, CorpusFailure};
+use crate::evolution::embedding::{Embedding, EmbeddingIndex};
+use crate::grammar::core::{Expr, GrammarElement, Literal, Pattern};
+use crate::grammar::types::{binop_symbol, element_name};
+use crate::parser::AdaptiveParser;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// 参与克隆检测的函数至少需要的规范化词法单元数 / Minimum normalized tokens for a function to take part in clone detection
+const DEFAULT_MIN_CLONE_TOKENS: usize = 6;
+
+/// 超过此组数时用嵌入索引筛选近似克隆的候选 / Above this many groups, near-miss candidates are chosen through an embedding index
+const CLONE_INDEX_THRESHOLD: usize = 256;
+
+/// 每组从嵌入索引取出的候选数 / Candidates taken from the embedding index per group
+const CLONE_CANDIDATES: usize = 32;
 
 /// 代码相似度检测器 / Code similarity detector
 pub struct SimilarityDetector {
     /// 相似度阈值 / Similarity threshold
     similarity_threshold: f64,
+    /// 克隆检测的最小规范化词法单元数 / Minimum normalized tokens for clone detection
+    min_clone_tokens: usize,
     /// 检测历史 / Detection history
     detection_history: Vec<SimilarityRecord>,
 }
@@ -85,6 +102,63 @@ pub struct SimilarityAnalysis {
     pub duplicates: Vec<DuplicateBlock>,
     /// 建议 / Suggestions
     pub suggestions: Vec<SimilaritySuggestion>,
+    /// 函数级克隆簇 / Function-level clone clusters
+    #[serde(default)]
+    pub clones: Vec<CloneCluster>,
+}
+
+/// 克隆类型 / Clone kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CloneKind {
+    /// 完全相同（类型1）/ Identical (type 1)
+    Exact,
+    /// 只有变量名和字面量不同（类型2）/ Differs only in variable names and literals (type 2)
+    Renamed,
+    /// 有少量增删改的近似克隆（类型3）/ Near miss with a few added, removed or changed tokens (type 3)
+    NearMiss,
+}
+
+/// 克隆实例 / Clone instance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloneInstance {
+    /// 所在文件，检测单个AST时为空 / Source file; `None` when detecting in a single AST
+    pub file: Option<PathBuf>,
+    /// 函数名 / Function name
+    pub function: String,
+    /// 参数名 / Parameter names
+    pub params: Vec<String>,
+    /// 位置（`文件:函数` 或 `AST[i]:函数`）/ Location (`file:function` or `AST[i]:function`)
+    pub location: String,
+    /// 规范化词法单元数 / Normalized token count
+    pub tokens: usize,
+}
+
+/// 克隆簇：同一段代码的所有实例 / Clone cluster: every instance of one piece of code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloneCluster {
+    /// 克隆类型（簇内最弱的关系）/ Clone kind (the weakest link in the cluster)
+    pub kind: CloneKind,
+    /// 第一个实例的规范化哈希 / Normalized hash of the first instance
+    pub hash: String,
+    /// 相连实例之间的最低相似度 / Lowest similarity between linked instances
+    pub similarity: f64,
+    /// 所有实例 / All instances
+    pub instances: Vec<CloneInstance>,
+    /// 建议提取的公共函数签名 / Suggested signature of the extracted common function
+    pub suggested_signature: String,
+}
+
+/// 项目克隆检测报告 / Project clone detection report
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CloneReport {
+    /// 扫描的文件数 / Files scanned
+    pub files: usize,
+    /// 参与检测的函数数 / Functions compared
+    pub functions: usize,
+    /// 克隆簇，实例多的在前 / Clone clusters, largest first
+    pub clusters: Vec<CloneCluster>,
+    /// 无法读取或解析的文件 / Files that could not be read or parsed
+    pub failures: Vec<CorpusFailure>,
 }
 
 /// 相似度建议 / Similarity suggestion
@@ -103,6 +177,7 @@ impl SimilarityDetector {
     pub fn new() -> Self {
         Self {
             similarity_threshold: 0.7, // 默认阈值70% / Default threshold 70%
+            min_clone_tokens: DEFAULT_MIN_CLONE_TOKENS,
             detection_history: Vec::new(),
         }
     }
@@ -112,6 +187,12 @@ impl SimilarityDetector {
         self.similarity_threshold = threshold.max(0.0).min(1.0);
     }
 
+    /// 设置克隆检测的最小规范化词法单元数，更短的函数不参与检测
+    /// Set the minimum normalized token count for clone detection; shorter functions are skipped
+    pub fn set_min_clone_tokens(&mut self, tokens: usize) {
+        self.min_clone_tokens = tokens.max(1);
+    }
+
     /// 检测代码相似度 / Detect code similarity
     pub fn detect_similarity(
         &mut self,
@@ -127,8 +208,13 @@ impl SimilarityDetector {
         // 检测重复代码块 / Detect duplicate blocks
         let duplicates = self.detect_duplicates(&code_blocks);
 
+        // 检测函数级克隆 / Detect function-level clones
+        let clones = self.detect_clones(ast);
+
         // 生成建议 / Generate suggestions
-        let suggestions = self.generate_suggestions(&similar_pairs, &duplicates);
+        let mut suggestions = self.generate_suggestions(&similar_pairs, &duplicates);
+        suggestions.extend(clones.iter().map(clone_suggestion));
+        suggestions.sort_by_key(|s| s.priority);
 
         // 计算相似度评分 / Calculate similarity score
         let similarity_score =
@@ -139,6 +225,7 @@ impl SimilarityDetector {
             similar_pairs,
             duplicates,
             suggestions,
+            clones,
         };
 
         // 记录检测历史 / Record detection history
@@ -152,6 +239,203 @@ impl SimilarityDetector {
         result
     }
 
+    /// 检测单个AST中的函数克隆：变量改名和字面量不同的函数（类型2）按规范化词法单元的哈希分组，
+    /// 规范化序列相似度不低于阈值的组合并为近似克隆（类型3）
+    /// Detect function clones in one AST: functions differing only in variable names and literals (type 2) are
+    /// grouped by the hash of their normalized tokens, and groups whose normalized sequences are at least as
+    /// similar as the threshold are merged as near misses (type 3)
+    pub fn detect_clones(&self, ast: &[GrammarElement]) -> Vec<CloneCluster> {
+        let fingerprints = definitions(ast)
+            .iter()
+            .map(|definition| {
+                let location = format!("AST[{}]:{}", definition.index, definition.name);
+                Fingerprint::new(definition, None, location)
+            })
+            .collect();
+        self.cluster_clones(fingerprints)
+    }
+
+    /// 为AST中的每个函数建立嵌入索引，条目ID形如 `AST[i]:name`
+    /// Build an embedding index of every function in the AST, with entry ids like `AST[i]:name`
+    pub fn function_index(&self, ast: &[GrammarElement]) -> EmbeddingIndex {
+        let mut index = EmbeddingIndex::new();
+        for definition in definitions(ast) {
+            index.insert(
+                format!("AST[{}]:{}", definition.index, definition.name),
+                Embedding::of_function(&definition),
+            );
+        }
+        index
+    }
+
+    /// 在AST中查找与给定代码片段结构相似的函数，相似度为嵌入向量的余弦相似度
+    /// Find functions in the AST structurally similar to a code snippet, by cosine similarity of embeddings
+    pub fn find_similar_code(
+        &self,
+        snippet: &[GrammarElement],
+        ast: &[GrammarElement],
+        limit: usize,
+    ) -> Vec<(String, f64)> {
+        // 片段本身是函数定义时按函数规范化，参数编号与索引一致
+        // A snippet that is itself a definition is normalized as a function, so parameters are numbered alike
+        let query = match definitions(snippet).first() {
+            Some(definition) if snippet.len() == 1 => Embedding::of_function(definition),
+            _ => Embedding::of_code(snippet),
+        };
+        self.function_index(ast)
+            .search(&query, limit, self.similarity_threshold)
+    }
+
+    /// 递归扫描目录中的所有 .evo 文件（路径也可以是单个文件），检测跨文件的函数克隆
+    /// Scan every .evo file under a directory recursively (the path may also be a single file) and detect
+    /// function clones across files
+    pub fn detect_project_clones(
+        &self,
+        parser: &AdaptiveParser,
+        path: &Path,
+    ) -> std::io::Result<CloneReport> {
+        let mut files = Vec::new();
+        collect_evo_files(path, &mut files)?;
+
+        let mut report = CloneReport {
+            files: files.len(),
+            ..CloneReport::default()
+        };
+        let mut fingerprints = Vec::new();
+        for file in files {
+            let ast = match std::fs::read_to_string(&file)
+                .map_err(|error| error.to_string())
+                .and_then(|source| parser.parse(&source).map_err(|error| error.to_string()))
+            {
+                Ok(ast) => ast,
+                Err(error) => {
+                    report.failures.push(CorpusFailure { path: file, error });
+                    continue;
+                }
+            };
+            for definition in definitions(&ast) {
+                let location = format!("{}:{}", file.display(), definition.name);
+                fingerprints.push(Fingerprint::new(&definition, Some(file.clone()), location));
+            }
+        }
+        report.functions = fingerprints
+            .iter()
+            .filter(|f| f.normalized.len() >= self.min_clone_tokens)
+            .count();
+        report.clusters = self.cluster_clones(fingerprints);
+        Ok(report)
+    }
+
+    /// 将函数指纹聚类为克隆簇 / Cluster function fingerprints into clone clusters
+    fn cluster_clones(&self, fingerprints: Vec<Fingerprint>) -> Vec<CloneCluster> {
+        // 按规范化哈希分组（类型1和类型2）/ Group by normalized hash (types 1 and 2)
+        let mut groups: Vec<Vec<Fingerprint>> = Vec::new();
+        let mut by_hash: HashMap<String, usize> = HashMap::new();
+        for fingerprint in fingerprints {
+            if fingerprint.normalized.len() < self.min_clone_tokens {
+                continue;
+            }
+            match by_hash.get(&fingerprint.hash) {
+                Some(&group) => groups[group].push(fingerprint),
+                None => {
+                    by_hash.insert(fingerprint.hash.clone(), groups.len());
+                    groups.push(vec![fingerprint]);
+                }
+            }
+        }
+
+        // 合并代表序列足够相似的组（类型3）/ Merge groups whose representatives are similar enough (type 3)
+        // 组很多时先用嵌入索引找候选，只对候选计算LCS
+        // With many groups, candidates come from an embedding index and only they get the LCS comparison
+        let candidates: Option<Vec<Vec<usize>>> =
+            (groups.len() > CLONE_INDEX_THRESHOLD).then(|| {
+                let mut index = EmbeddingIndex::new();
+                let embeddings: Vec<Embedding> = groups
+                    .iter()
+                    .map(|group| Embedding::from_tokens(&group[0].normalized))
+                    .collect();
+                for (i, embedding) in embeddings.iter().enumerate() {
+                    index.insert(i.to_string(), embedding.clone());
+                }
+                embeddings
+                    .iter()
+                    .enumerate()
+                    .map(|(i, embedding)| {
+                        index
+                            .search(embedding, CLONE_CANDIDATES, self.similarity_threshold / 2.0)
+                            .into_iter()
+                            .filter_map(|(id, _)| id.parse().ok())
+                            .filter(|&j| j > i)
+                            .collect()
+                    })
+                    .collect()
+            });
+
+        let mut parent: Vec<usize> = (0..groups.len()).collect();
+        let mut links = Vec::new();
+        for i in 0..groups.len() {
+            let others: Vec<usize> = match &candidates {
+                Some(candidates) => candidates[i].clone(),
+                None => ((i + 1)..groups.len()).collect(),
+            };
+            for j in others {
+                let (a, b) = (&groups[i][0].normalized, &groups[j][0].normalized);
+                // 2·LCS/(m+n) 不超过 2·min/(m+n) / 2·LCS/(m+n) is at most 2·min/(m+n)
+                let bound = 2.0 * a.len().min(b.len()) as f64 / (a.len() + b.len()) as f64;
+                if bound < self.similarity_threshold {
+                    continue;
+                }
+                let similarity = token_similarity(a, b);
+                if similarity >= self.similarity_threshold {
+                    let (root_i, root_j) = (find_root(&mut parent, i), find_root(&mut parent, j));
+                    parent[root_j] = root_i;
+                    links.push((i, similarity));
+                }
+            }
+        }
+
+        let mut components: Vec<(usize, Vec<usize>)> = Vec::new();
+        for group in 0..groups.len() {
+            let root = find_root(&mut parent, group);
+            match components.iter_mut().find(|(r, _)| *r == root) {
+                Some((_, members)) => members.push(group),
+                None => components.push((root, vec![group])),
+            }
+        }
+
+        let mut clusters: Vec<CloneCluster> = components
+            .into_iter()
+            .filter_map(|(root, members)| {
+                let instances: Vec<&Fingerprint> =
+                    members.iter().flat_map(|&group| &groups[group]).collect();
+                if instances.len() < 2 {
+                    return None;
+                }
+                let (kind, similarity) = if members.len() > 1 {
+                    let similarity = links
+                        .iter()
+                        .filter(|(group, _)| find_root(&mut parent, *group) == root)
+                        .map(|(_, similarity)| *similarity)
+                        .fold(1.0, f64::min);
+                    (CloneKind::NearMiss, similarity)
+                } else if instances.iter().all(|f| f.raw == instances[0].raw) {
+                    (CloneKind::Exact, 1.0)
+                } else {
+                    (CloneKind::Renamed, 1.0)
+                };
+                Some(CloneCluster {
+                    kind,
+                    hash: instances[0].hash.clone(),
+                    similarity,
+                    suggested_signature: suggest_signature(&instances),
+                    instances: instances.iter().map(|f| f.instance.clone()).collect(),
+                })
+            })
+            .collect();
+        clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.instances.len()));
+        clusters
+    }
+
     /// 提取代码块 / Extract code blocks
     fn extract_code_blocks(&self, ast: &[GrammarElement]) -> Vec<CodeBlock> {
         let mut blocks = Vec::new();
@@ -477,6 +761,524 @@ impl SimilarityDetector {
     }
 }
 
+/// 函数指纹：规范化和原始词法单元序列 / Function fingerprint: normalized and raw token sequences
+struct Fingerprint {
+    instance: CloneInstance,
+    /// 局部变量替换为位置占位符、字面量替换为类型的序列 / Locals replaced by positional placeholders, literals by their type
+    normalized: Vec<String>,
+    /// 保留原名和字面量值的序列，与 `normalized` 一一对应 / Original names and literal values, aligned with `normalized`
+    raw: Vec<String>,
+    hash: String,
+}
+
+impl Fingerprint {
+    fn new(definition: &Definition<'_>, file: Option<PathBuf>, location: String) -> Self {
+        let tokenizer = Tokenizer::function(definition);
+
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let mut hasher = DefaultHasher::new();
+        tokenizer.normalized.hash(&mut hasher);
+
+        Self {
+            instance: CloneInstance {
+                file,
+                function: definition.name.clone(),
+                params: definition.params.clone(),
+                location,
+                tokens: tokenizer.normalized.len(),
+            },
+            normalized: tokenizer.normalized,
+            raw: tokenizer.raw,
+            hash: format!("{:x}", hasher.finish()),
+        }
+    }
+}
+
+/// 函数体的规范化词法分析：局部变量按首次绑定顺序编号为 `$0`、`$1`…，
+/// 对自身的递归调用记为 `$self`，全局函数和内置函数保留原名
+/// Normalizing tokenizer for function bodies: locals are numbered `$0`, `$1`… in binding order,
+/// recursive calls become `$self`, global and builtin names are kept
+struct Tokenizer<'a> {
+    function: &'a str,
+    locals: HashMap<String, usize>,
+    normalized: Vec<String>,
+    raw: Vec<String>,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(function: &'a str) -> Self {
+        Self {
+            function,
+            locals: HashMap::new(),
+            normalized: Vec::new(),
+            raw: Vec::new(),
+        }
+    }
+
+    /// 函数定义：参数先登记为局部变量 / A function definition, with parameters registered as locals first
+    fn function(definition: &'a Definition<'_>) -> Self {
+        let mut tokenizer = Self::new(&definition.name);
+        tokenizer.push(format!("params/{}", definition.params.len()));
+        for param in &definition.params {
+            tokenizer.declare(param);
+        }
+        for element in definition.body {
+            tokenizer.element(element);
+        }
+        tokenizer
+    }
+
+    fn push(&mut self, token: String) {
+        self.normalized.push(token.clone());
+        self.raw.push(token);
+    }
+
+    fn push_pair(&mut self, normalized: String, raw: String) {
+        self.normalized.push(normalized);
+        self.raw.push(raw);
+    }
+
+    /// 登记局部变量，返回其编号 / Register a local variable and return its number
+    fn declare(&mut self, name: &str) -> usize {
+        let next = self.locals.len();
+        *self.locals.entry(name.to_string()).or_insert(next)
+    }
+
+    fn bind(&mut self, name: &str) {
+        let index = self.declare(name);
+        self.push_pair(format!("${}", index), name.to_string());
+    }
+
+    fn name(&mut self, name: &str) {
+        match self.locals.get(name) {
+            Some(index) => self.push_pair(format!("${}", index), name.to_string()),
+            None if name == self.function => self.push("$self".to_string()),
+            None => self.push(name.to_string()),
+        }
+    }
+
+    fn call_head(&mut self, name: &str, arity: usize) {
+        match self.locals.get(name) {
+            Some(index) => self.push_pair(
+                format!("call:${}/{}", index, arity),
+                format!("call:{}/{}", name, arity),
+            ),
+            None if name == self.function => self.push(format!("call:$self/{}", arity)),
+            None => self.push(format!("call:{}/{}", name, arity)),
+        }
+    }
+
+    fn element(&mut self, element: &GrammarElement) {
+        match element {
+            GrammarElement::Atom(name) => self.name(name),
+            GrammarElement::NaturalLang(text) => {
+                self.push_pair("nl".to_string(), format!("nl:{}", text))
+            }
+            GrammarElement::Expr(expr) => self.expr(expr),
+            GrammarElement::List(items) => {
+                let head = match items.first() {
+                    Some(GrammarElement::Atom(head)) => head.as_str(),
+                    _ => "",
+                };
+                self.push(format!("list/{}", items.len()));
+                match head {
+                    "let" | "defconst" | "for" | "def" | "function" | "defn-"
+                        if items.len() >= 2 =>
+                    {
+                        self.push(head.to_string());
+                        match element_name(&items[1]) {
+                            Some(name) => self.bind(&name),
+                            None => self.element(&items[1]),
+                        }
+                        for item in &items[2..] {
+                            self.element(item);
+                        }
+                    }
+                    "lambda" if items.len() >= 2 => {
+                        self.push(head.to_string());
+                        if let GrammarElement::List(params) = &items[1] {
+                            self.push(format!("params/{}", params.len()));
+                            for name in params.iter().filter_map(element_name) {
+                                self.bind(&name);
+                            }
+                        }
+                        for item in &items[2..] {
+                            self.element(item);
+                        }
+                    }
+                    _ => {
+                        for item in items {
+                            self.element(item);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Literal(literal) => self.literal(literal),
+            Expr::Var(name) => self.name(name),
+            Expr::Call(name, args) if matches!(name.as_str(), "let" | "lambda") => {
+                self.push(format!("{}/{}", name, args.len()));
+                if let Some(target) = args.first() {
+                    self.bind_expr(target);
+                }
+                for arg in args.iter().skip(1) {
+                    self.expr(arg);
+                }
+            }
+            Expr::Call(name, args) => {
+                self.call_head(name, args.len());
+                for arg in args {
+                    self.expr(arg);
+                }
+            }
+            Expr::Binary(op, left, right) => {
+                // 与 `op:` 调用形式一致 / Same as the `op:` call form
+                self.push(format!("call:op:{}/2", binop_symbol(op)));
+                self.expr(left);
+                self.expr(right);
+            }
+            Expr::If(condition, then_branch, else_branch) => {
+                self.push("if".to_string());
+                self.expr(condition);
+                self.expr(then_branch);
+                self.expr(else_branch);
+            }
+            Expr::Match(value, arms) => {
+                self.push(format!("match/{}", arms.len()));
+                self.expr(value);
+                for (pattern, arm) in arms {
+                    self.pattern(pattern);
+                    self.expr(arm);
+                }
+            }
+            Expr::For {
+                var,
+                iterable,
+                body,
+            } => {
+                self.push("for".to_string());
+                self.bind(var);
+                self.expr(iterable);
+                self.expr(body);
+            }
+            Expr::While { condition, body } => {
+                self.push("while".to_string());
+                self.expr(condition);
+                self.expr(body);
+            }
+            Expr::Try {
+                try_body,
+                catch_var,
+                catch_body,
+            } => {
+                self.push("try".to_string());
+                self.expr(try_body);
+                match catch_var {
+                    Some(name) => self.bind(name),
+                    None => self.push("catch".to_string()),
+                }
+                self.expr(catch_body);
+            }
+            Expr::Lambda { params, body } => {
+                self.push(format!("lambda/{}", params.len()));
+                for param in params {
+                    self.bind(param);
+                }
+                self.expr(body);
+            }
+            Expr::Begin(exprs) => {
+                self.push(format!("begin/{}", exprs.len()));
+                for expr in exprs {
+                    self.expr(expr);
+                }
+            }
+            Expr::Assign(name, value) => {
+                self.push("set!".to_string());
+                self.name(name);
+                self.expr(value);
+            }
+        }
+    }
+
+    /// 绑定位置上的表达式：变量或变量列表 / Expression in binding position: a variable or a list of variables
+    fn bind_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Var(name) => self.bind(name),
+            Expr::Literal(Literal::List(items)) => {
+                self.push(format!("params/{}", items.len()));
+                for item in items {
+                    self.bind_expr(item);
+                }
+            }
+            Expr::Call(name, args) => {
+                self.push(format!("params/{}", args.len() + 1));
+                self.bind(name);
+                for arg in args {
+                    self.bind_expr(arg);
+                }
+            }
+            _ => self.expr(expr),
+        }
+    }
+
+    fn literal(&mut self, literal: &Literal) {
+        match literal {
+            Literal::Int(value) => {
+                self.push_pair("lit:int".to_string(), format!("lit:int:{}", value))
+            }
+            Literal::BigInt(value) => {
+                self.push_pair("lit:int".to_string(), format!("lit:int:{}", value))
+            }
+            Literal::Float(value) => {
+                self.push_pair("lit:float".to_string(), format!("lit:float:{}", value))
+            }
+            Literal::String(value) => {
+                self.push_pair("lit:string".to_string(), format!("lit:string:{:?}", value))
+            }
+            Literal::Bool(value) => {
+                self.push_pair("lit:bool".to_string(), format!("lit:bool:{}", value))
+            }
+            Literal::Null => self.push("null".to_string()),
+            Literal::List(items) => {
+                self.push(format!("list-literal/{}", items.len()));
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            Literal::Dict(entries) => {
+                self.push(format!("dict/{}", entries.len()));
+                for (key, value) in entries {
+                    self.push(format!("key:{}", key));
+                    self.expr(value);
+                }
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Literal(literal) => self.literal(literal),
+            Pattern::Var(name) => self.bind(name),
+            Pattern::Wildcard => self.push("_".to_string()),
+            Pattern::List(items) => {
+                self.push(format!("pattern-list/{}", items.len()));
+                for item in items {
+                    self.pattern(item);
+                }
+            }
+            Pattern::Dict(entries) => {
+                self.push(format!("pattern-dict/{}", entries.len()));
+                for (key, value) in entries {
+                    self.push(format!("key:{}", key));
+                    self.pattern(value);
+                }
+            }
+            Pattern::Rest(name) => {
+                self.push("rest".to_string());
+                if let Some(name) = name {
+                    self.bind(name);
+                }
+            }
+            Pattern::Or(alternatives) => {
+                self.push(format!("or/{}", alternatives.len()));
+                for alternative in alternatives {
+                    self.pattern(alternative);
+                }
+            }
+            Pattern::Guard(pattern, guard) => {
+                self.push("when".to_string());
+                self.pattern(pattern);
+                self.expr(guard);
+            }
+            Pattern::Prefix(prefix, rest) => {
+                self.push(format!("prefix:{}", prefix));
+                self.pattern(rest);
+            }
+            Pattern::Suffix(suffix, rest) => {
+                self.push(format!("suffix:{}", suffix));
+                self.pattern(rest);
+            }
+        }
+    }
+}
+
+/// 函数定义的规范化词法单元 / Normalized tokens of a function definition
+pub(crate) fn function_tokens(definition: &Definition<'_>) -> Vec<String> {
+    Tokenizer::function(definition).normalized
+}
+
+/// 任意代码的规范化词法单元，代码中绑定的变量同样编号 / Normalized tokens of arbitrary code, numbering the variables it binds too
+pub(crate) fn code_tokens(elements: &[GrammarElement]) -> Vec<String> {
+    let mut tokenizer = Tokenizer::new("");
+    for element in elements {
+        tokenizer.element(element);
+    }
+    tokenizer.normalized
+}
+
+/// 规范化序列的相似度 2·LCS/(m+n) / Similarity of normalized sequences, 2·LCS/(m+n)
+fn token_similarity(a: &[String], b: &[String]) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let mut previous = vec![0usize; b.len() + 1];
+    let mut current = vec![0usize; b.len() + 1];
+    for token in a {
+        for (j, other) in b.iter().enumerate() {
+            current[j + 1] = if token == other {
+                previous[j] + 1
+            } else {
+                previous[j + 1].max(current[j])
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    2.0 * previous[b.len()] as f64 / (a.len() + b.len()) as f64
+}
+
+fn find_root(parent: &mut [usize], mut node: usize) -> usize {
+    while parent[node] != node {
+        parent[node] = parent[parent[node]];
+        node = parent[node];
+    }
+    node
+}
+
+/// 建议提取的公共函数签名：沿用第一个实例（近似克隆取参数最多的实例）的参数，
+/// 实例之间不同的字面量各加一个 `value` 参数，近似克隆的其他差异加 `variant` 参数
+/// Suggested signature of the extracted function: the parameters of the first instance (the one with the most
+/// parameters for near misses), plus a `value` parameter for each literal that differs between instances and
+/// `variant` parameters for the other differences of near misses
+fn suggest_signature(instances: &[&Fingerprint]) -> String {
+    let base = instances
+        .iter()
+        .rev()
+        .max_by_key(|f| f.instance.params.len())
+        .unwrap_or(&instances[0]);
+    let mut params = base.instance.params.clone();
+    let add_param = |params: &mut Vec<String>, name: &str| {
+        let mut candidate = name.to_string();
+        let mut suffix = 2;
+        while params.contains(&candidate) {
+            candidate = format!("{}{}", name, suffix);
+            suffix += 1;
+        }
+        params.push(candidate);
+    };
+
+    let first = instances[0];
+    if instances
+        .iter()
+        .all(|f| f.normalized.len() == first.normalized.len())
+    {
+        for position in 0..first.normalized.len() {
+            let literal = first.normalized[position].starts_with("lit:");
+            if instances
+                .iter()
+                .any(|f| f.normalized[position] != first.normalized[position])
+            {
+                add_param(&mut params, if literal { "value" } else { "variant" });
+            } else if literal
+                && instances
+                    .iter()
+                    .any(|f| f.raw[position] != first.raw[position])
+            {
+                add_param(&mut params, "value");
+            }
+        }
+    } else {
+        add_param(&mut params, "variant");
+    }
+
+    let functions: Vec<&str> = instances
+        .iter()
+        .map(|f| f.instance.function.as_str())
+        .collect();
+    format!(
+        "(def {} ({}))",
+        extracted_name(&functions),
+        params.join(" ")
+    )
+}
+
+/// 提取函数的名称：实例函数名共同的开头或结尾部分（按 `-`、`_` 分段），都没有时用第一个函数名，加 `-common`
+/// Name of the extracted function: the segments (split on `-` and `_`) all instance names start or end with,
+/// or the first name, plus `-common`
+pub(crate) fn extracted_name(functions: &[&str]) -> String {
+    let names: Vec<Vec<&str>> = functions
+        .iter()
+        .map(|function| function.split(['-', '_']).collect())
+        .collect();
+    let base = [
+        common_segments(&names, false),
+        common_segments(&names, true),
+    ]
+    .into_iter()
+    .find(|segments| !segments.is_empty())
+    .map(|segments| segments.join("-"))
+    .unwrap_or_else(|| functions[0].to_string());
+    format!("{}-common", base)
+}
+
+/// 所有名称共同的开头（`reversed` 时为结尾）分段 / Leading (trailing when `reversed`) segments shared by all names
+fn common_segments<'a>(names: &[Vec<&'a str>], reversed: bool) -> Vec<&'a str> {
+    let ordered = |segments: &Vec<&'a str>| -> Vec<&'a str> {
+        let mut segments = segments.clone();
+        if reversed {
+            segments.reverse();
+        }
+        segments
+    };
+    let mut common = ordered(&names[0]);
+    for name in &names[1..] {
+        let other = ordered(name);
+        let len = common
+            .iter()
+            .zip(&other)
+            .take_while(|(a, b)| a == b)
+            .count();
+        common.truncate(len);
+    }
+    if reversed {
+        common.reverse();
+    }
+    common
+}
+
+/// 克隆簇对应的重构建议 / Refactoring suggestion for a clone cluster
+fn clone_suggestion(cluster: &CloneCluster) -> SimilaritySuggestion {
+    let kind = match cluster.kind {
+        CloneKind::Exact => "完全相同",
+        CloneKind::Renamed => "仅变量名或字面量不同",
+        CloneKind::NearMiss => "近似",
+    };
+    let functions: Vec<&str> = cluster
+        .instances
+        .iter()
+        .map(|instance| instance.function.as_str())
+        .collect();
+    SimilaritySuggestion {
+        suggestion_type: "克隆提取".to_string(),
+        content: format!(
+            "{} 个函数{}（{}），建议提取为 {}",
+            functions.len(),
+            kind,
+            functions.join(", "),
+            cluster.suggested_signature
+        ),
+        priority: if cluster.kind == CloneKind::NearMiss {
+            2
+        } else {
+            1
+        },
+    }
+}
+
 impl Default for SimilarityDetector {
     fn default() -> Self {
         Self::new()
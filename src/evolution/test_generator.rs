@@ -1,11 +1,20 @@
 // 测试生成器 / Test generator
-// 自动生成测试用例
-// Automatically generate test cases
+// 按参数类型合成常规、边界和随机输入，在沙箱中执行得到预期输出，生成可运行的断言测试文件
+// Synthesizes typical, boundary and random inputs from parameter types, runs them in the sandbox to capture
+// the expected outputs, and emits a runnable test file of assertions
 
 use crate::evolution::analyzer::CodeAnalysis;
-use crate::grammar::core::GrammarElement;
+use crate::evolution::mutation::SeededRng;
+use crate::evolution::sandbox::{SandboxConfig, SandboxRunner};
+use crate::grammar::core::{GrammarElement, Literal};
+use crate::grammar::inference::TypeInference;
+use crate::grammar::types::{element_name, is_definition, FunctionSignature, Type};
+use crate::grammar::unparse::unparse_literal;
+use crate::parser::AdaptiveParser;
+use crate::runtime::coverage::{CoverageMap, CoverageReport, ProbeHits};
+use crate::runtime::interpreter::{ExecutionBudget, Value};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 /// 测试生成器 / Test generator
 pub struct TestGenerator {
@@ -13,6 +22,12 @@ pub struct TestGenerator {
     strategies: HashMap<String, TestStrategy>,
     /// 测试历史 / Test history
     test_history: Vec<TestRecord>,
+    /// 随机输入的种子 / Seed of the random inputs
+    seed: u64,
+    /// 每个函数的性质测试数 / Property tests per function
+    property_cases: usize,
+    /// 执行被测函数的沙箱 / Sandbox running the functions under test
+    runner: SandboxRunner,
 }
 
 /// 测试策略 / Test strategy
@@ -31,7 +46,7 @@ pub struct TestStrategy {
 }
 
 /// 测试策略类型 / Test strategy type
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TestStrategyType {
     /// 单元测试 / Unit test
     UnitTest,
@@ -43,6 +58,8 @@ pub enum TestStrategyType {
     PerformanceTest,
     /// 回归测试 / Regression test
     RegressionTest,
+    /// 性质测试（随机输入）/ Property test (random inputs)
+    PropertyTest,
 }
 
 /// 测试用例 / Test case
@@ -54,6 +71,9 @@ pub struct TestCase {
     pub name: String,
     /// 测试代码 / Test code
     pub test_code: String,
+    /// 被测调用表达式 / Call expression under test
+    #[serde(default)]
+    pub input: String,
     /// 预期结果 / Expected result
     pub expected_result: String,
     /// 测试类型 / Test type
@@ -84,6 +104,9 @@ pub struct TestSuite {
     pub statistics: TestStatistics,
     /// 测试覆盖率 / Test coverage
     pub coverage: TestCoverage,
+    /// 测试在插桩程序上测得的逐函数覆盖率 / Per-function coverage measured by running the tests on the instrumented program
+    #[serde(default)]
+    pub coverage_report: CoverageReport,
 }
 
 /// 测试统计 / Test statistics
@@ -97,6 +120,9 @@ pub struct TestStatistics {
     pub integration_tests: usize,
     /// 边界测试数 / Boundary tests
     pub boundary_tests: usize,
+    /// 性质测试数 / Property tests
+    #[serde(default)]
+    pub property_tests: usize,
 }
 
 /// 测试覆盖率 / Test coverage
@@ -118,6 +144,20 @@ impl TestGenerator {
         let mut generator = Self {
             strategies: HashMap::new(),
             test_history: Vec::new(),
+            seed: 0,
+            property_cases: 5,
+            // 测试应当很快结束，预算比一般生成代码更紧 / Tests should finish quickly, so the budget is tighter than for generated code
+            runner: SandboxRunner::with_config(SandboxConfig {
+                seed: Some(0),
+                budget: ExecutionBudget {
+                    max_steps: Some(1_000_000),
+                    max_call_depth: Some(1_000),
+                    timeout: Some(std::time::Duration::from_secs(5)),
+                    ..ExecutionBudget::for_generated_code()
+                },
+                coverage: true,
+                ..SandboxConfig::default()
+            }),
         };
         generator.initialize_strategies();
         generator
@@ -160,56 +200,127 @@ impl TestGenerator {
                 usage_count: 0,
             },
         );
+
+        // 性质测试策略 / Property test strategy
+        self.strategies.insert(
+            "property_test".to_string(),
+            TestStrategy {
+                name: "性质测试".to_string(),
+                description: "按参数类型生成随机输入".to_string(),
+                strategy_type: TestStrategyType::PropertyTest,
+                success_rate: 0.80,
+                usage_count: 0,
+            },
+        );
+    }
+
+    /// 设置随机种子，相同种子生成相同的性质测试 / Set the random seed; the same seed yields the same property tests
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
     }
 
-    /// 生成测试套件 / Generate test suite
-    pub fn generate_tests(&mut self, ast: &[GrammarElement], analysis: &CodeAnalysis) -> TestSuite {
-        let mut test_cases = Vec::new();
+    /// 设置每个函数的性质测试数 / Set the number of property tests per function
+    pub fn with_property_cases(mut self, cases: usize) -> Self {
+        self.property_cases = cases;
+        self
+    }
 
-        // 为每个函数生成测试 / Generate tests for each function
+    /// 生成测试套件：按参数类型构造常规、边界和随机输入，在沙箱中执行插桩后的函数，以实际输出作为预期结果，
+    /// 并由保留下来的测试的探针命中计算覆盖率；执行出错或返回值无法写成字面量的输入被丢弃
+    /// Generate a test suite: build typical, boundary and random inputs from the parameter types, run the
+    /// instrumented function in the sandbox and use the actual output as the expected result, measuring coverage
+    /// from the probe hits of the tests kept; inputs that fail or return values with no literal form are dropped
+    pub fn generate_tests(
+        &mut self,
+        ast: &[GrammarElement],
+        _analysis: &CodeAnalysis,
+    ) -> TestSuite {
+        let inferred = TypeInference::new().infer_program(ast).functions;
+        let mut rng = SeededRng::new(self.seed);
+        let mut candidates = Vec::new();
+
+        // 为每个函数生成候选输入 / Generate candidate inputs for each function
         for element in ast {
-            if let GrammarElement::List(list) = element {
-                if let Some(GrammarElement::Atom(first)) = list.first() {
-                    if first == "def" || first == "function" {
-                        if list.len() >= 3 {
-                            if let GrammarElement::Atom(name) = &list[1] {
-                                // 生成单元测试 / Generate unit tests
-                                let unit_tests = self.generate_unit_tests(name, list);
-                                test_cases.extend(unit_tests);
-
-                                // 生成边界测试 / Generate boundary tests
-                                let boundary_tests = self.generate_boundary_tests(name, list);
-                                test_cases.extend(boundary_tests);
-                            }
-                        }
-                    }
-                }
+            let GrammarElement::List(list) = element else {
+                continue;
+            };
+            if !is_definition(list) {
+                continue;
             }
+            let Some(name) = list.get(1).and_then(element_name) else {
+                continue;
+            };
+            let signature = FunctionSignature::from_definition(list)
+                .ok()
+                .filter(|signature| signature.annotated)
+                .or_else(|| inferred.get(&name).cloned());
+            let params: Vec<(String, Type)> = match signature {
+                Some(signature) => signature.params,
+                None => match list.get(2) {
+                    Some(GrammarElement::List(params)) => params
+                        .iter()
+                        .filter_map(element_name)
+                        .map(|param| (param, Type::Any))
+                        .collect(),
+                    _ => continue,
+                },
+            };
+            candidates.extend(self.function_cases(&name, &params, &mut rng));
         }
 
-        // 统计测试 / Count tests
-        let unit_tests = test_cases
-            .iter()
-            .filter(|t| matches!(t.test_type, TestStrategyType::UnitTest))
-            .count();
-        let integration_tests = test_cases
-            .iter()
-            .filter(|t| matches!(t.test_type, TestStrategyType::IntegrationTest))
-            .count();
-        let boundary_tests = test_cases
+        // 在沙箱中执行插桩后的程序，记录实际输出和探针命中
+        // Run the instrumented program in the sandbox, recording the actual output and the probe hits
+        let (instrumented, coverage_map) = CoverageMap::instrument(ast);
+        let parser = AdaptiveParser::new(true);
+        let programs: Vec<Vec<GrammarElement>> = candidates
             .iter()
-            .filter(|t| matches!(t.test_type, TestStrategyType::BoundaryTest))
-            .count();
+            .map(|candidate| {
+                let call = parser.parse(&candidate.input).unwrap_or_default();
+                [instrumented.as_slice(), call.as_slice()].concat()
+            })
+            .collect();
+        let results = self.runner.run(&programs);
+        let mut hits = ProbeHits::new();
+        let test_cases: Vec<TestCase> = candidates
+            .into_iter()
+            .zip(results)
+            .filter_map(|(candidate, result)| {
+                let expected = value_literal(result.value.as_ref()?)
+                    .filter(|literal| literal.len() <= MAX_LITERAL_LEN)?;
+                for (probe, count) in result.coverage {
+                    *hits.entry(probe).or_default() += count;
+                }
+                Some(TestCase {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    name: candidate.name,
+                    test_code: format!("(assert-eq {} {})", candidate.input, expected),
+                    input: candidate.input,
+                    expected_result: expected,
+                    test_type: candidate.test_type,
+                    description: candidate.description,
+                })
+            })
+            .collect();
 
+        // 统计测试 / Count tests
+        let count = |test_type: TestStrategyType| {
+            test_cases
+                .iter()
+                .filter(|t| t.test_type == test_type)
+                .count()
+        };
         let statistics = TestStatistics {
             total_tests: test_cases.len(),
-            unit_tests,
-            integration_tests,
-            boundary_tests,
+            unit_tests: count(TestStrategyType::UnitTest),
+            integration_tests: count(TestStrategyType::IntegrationTest),
+            boundary_tests: count(TestStrategyType::BoundaryTest),
+            property_tests: count(TestStrategyType::PropertyTest),
         };
 
         // 计算测试覆盖率 / Calculate test coverage
-        let coverage = self.calculate_coverage(&test_cases, analysis);
+        let coverage_report = CoverageReport::new(&coverage_map, &hits);
+        let coverage = calculate_coverage(&coverage_report);
 
         // 记录测试生成历史 / Record test generation history
         let record = TestRecord {
@@ -224,129 +335,92 @@ impl TestGenerator {
             test_cases,
             statistics,
             coverage,
+            coverage_report,
         }
     }
 
-    /// 生成单元测试 / Generate unit tests
-    fn generate_unit_tests(
+    /// 为一个函数生成常规、边界和随机输入；有参数无法构造时不生成
+    /// Generate typical, boundary and random inputs for one function; none when a parameter cannot be constructed
+    fn function_cases(
         &self,
-        function_name: &str,
-        _function_def: &[GrammarElement],
-    ) -> Vec<TestCase> {
-        let mut tests = Vec::new();
-
-        // 根据函数名生成测试用例 / Generate test cases based on function name
-        match function_name {
-            "add" | "+" => {
-                tests.push(TestCase {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    name: format!("test_{}_basic", function_name),
-                    test_code: format!("({} 2 3)", function_name),
-                    expected_result: "5".to_string(),
-                    test_type: TestStrategyType::UnitTest,
-                    description: "基本加法测试".to_string(),
-                });
-                tests.push(TestCase {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    name: format!("test_{}_zero", function_name),
-                    test_code: format!("({} 0 5)", function_name),
-                    expected_result: "5".to_string(),
-                    test_type: TestStrategyType::UnitTest,
-                    description: "零值测试".to_string(),
-                });
-            }
-            "multiply" | "*" => {
-                tests.push(TestCase {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    name: format!("test_{}_basic", function_name),
-                    test_code: format!("({} 3 4)", function_name),
-                    expected_result: "12".to_string(),
-                    test_type: TestStrategyType::UnitTest,
-                    description: "基本乘法测试".to_string(),
-                });
-            }
-            _ => {
-                // 通用测试用例 / Generic test case
-                tests.push(TestCase {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    name: format!("test_{}_basic", function_name),
-                    test_code: format!("({} 1 2)", function_name),
-                    expected_result: "结果待验证".to_string(),
-                    test_type: TestStrategyType::UnitTest,
-                    description: format!("{} 函数基本测试", function_name),
-                });
+        name: &str,
+        params: &[(String, Type)],
+        rng: &mut SeededRng,
+    ) -> Vec<Candidate> {
+        let Some(typical) = params
+            .iter()
+            .enumerate()
+            .map(|(index, (_, ty))| typical_value(ty, index))
+            .collect::<Option<Vec<String>>>()
+        else {
+            return Vec::new();
+        };
+        let call = |args: &[String]| {
+            if args.is_empty() {
+                format!("({})", name)
+            } else {
+                format!("({} {})", name, args.join(" "))
             }
-        }
-
-        tests
-    }
-
-    /// 生成边界测试 / Generate boundary tests
-    fn generate_boundary_tests(
-        &self,
-        function_name: &str,
-        _function_def: &[GrammarElement],
-    ) -> Vec<TestCase> {
-        let mut tests = Vec::new();
-
-        // 生成边界值测试 / Generate boundary value tests
-        tests.push(TestCase {
-            id: uuid::Uuid::new_v4().to_string(),
-            name: format!("test_{}_boundary_zero", function_name),
-            test_code: format!("({} 0 0)", function_name),
-            expected_result: "0".to_string(),
-            test_type: TestStrategyType::BoundaryTest,
-            description: "零值边界测试".to_string(),
-        });
-
-        tests.push(TestCase {
-            id: uuid::Uuid::new_v4().to_string(),
-            name: format!("test_{}_boundary_negative", function_name),
-            test_code: format!("({} -1 1)", function_name),
-            expected_result: "结果待验证".to_string(),
-            test_type: TestStrategyType::BoundaryTest,
-            description: "负值边界测试".to_string(),
-        });
-
-        tests
-    }
-
-    /// 计算测试覆盖率 / Calculate test coverage
-    fn calculate_coverage(&self, test_cases: &[TestCase], analysis: &CodeAnalysis) -> TestCoverage {
-        // 函数覆盖率：测试覆盖的函数比例 / Function coverage: ratio of functions covered by tests
-        let function_coverage = if analysis.statistics.function_count > 0 {
-            let covered_functions = test_cases.len().min(analysis.statistics.function_count);
-            (covered_functions as f64 / analysis.statistics.function_count as f64) * 100.0
-        } else {
-            0.0
         };
 
-        // 分支覆盖率：简化版本 / Branch coverage: simplified version
-        let branch_coverage = if analysis.complexity > 0.0 {
-            (100.0 - (analysis.complexity / 10.0).min(10.0) * 10.0).max(0.0)
-        } else {
-            100.0
+        let mut seen = HashSet::new();
+        let mut cases = Vec::new();
+        let mut push = |cases: &mut Vec<Candidate>, candidate: Candidate| {
+            if seen.insert(candidate.input.clone()) {
+                cases.push(candidate);
+            }
         };
 
-        // 语句覆盖率：基于测试数量 / Statement coverage: based on test count
-        let statement_coverage = if test_cases.len() > 0 {
-            (test_cases.len() as f64 / (analysis.statistics.function_count.max(1) as f64 * 3.0))
-                .min(1.0)
-                * 100.0
-        } else {
-            0.0
-        };
+        // 单元测试：常规输入 / Unit test: typical inputs
+        push(
+            &mut cases,
+            Candidate {
+                name: format!("test_{}_basic", name),
+                input: call(&typical),
+                test_type: TestStrategyType::UnitTest,
+                description: format!("{} 函数基本测试", name),
+            },
+        );
 
-        // 总体覆盖率 / Overall coverage
-        let overall_coverage =
-            (function_coverage * 0.4 + branch_coverage * 0.3 + statement_coverage * 0.3).min(100.0);
+        // 边界测试：每次只把一个参数换成边界值 / Boundary tests: replace one parameter at a time with a boundary value
+        for (index, (param, ty)) in params.iter().enumerate() {
+            for (case, value) in boundary_values(ty).into_iter().enumerate() {
+                let mut args = typical.clone();
+                args[index] = value.clone();
+                push(
+                    &mut cases,
+                    Candidate {
+                        name: format!("test_{}_boundary_{}_{}", name, param, case),
+                        input: call(&args),
+                        test_type: TestStrategyType::BoundaryTest,
+                        description: format!("参数 {} 取边界值 {}", param, value),
+                    },
+                );
+            }
+        }
 
-        TestCoverage {
-            function_coverage,
-            branch_coverage,
-            statement_coverage,
-            overall_coverage,
+        // 性质测试：随机输入 / Property tests: random inputs
+        if !params.is_empty() {
+            for case in 0..self.property_cases {
+                let Some(args) = params
+                    .iter()
+                    .map(|(_, ty)| random_value(ty, rng, 0))
+                    .collect::<Option<Vec<String>>>()
+                else {
+                    break;
+                };
+                push(
+                    &mut cases,
+                    Candidate {
+                        name: format!("test_{}_property_{}", name, case),
+                        input: call(&args),
+                        test_type: TestStrategyType::PropertyTest,
+                        description: format!("{} 函数随机输入测试", name),
+                    },
+                );
+            }
         }
+        cases
     }
 
     /// 记录测试结果 / Record test results
@@ -395,3 +469,208 @@ impl Default for TestGenerator {
         Self::new()
     }
 }
+
+/// 预期结果字面量的最大长度，更长的结果（如大数阶乘）不生成测试
+/// Maximum length of an expected-result literal; longer results (such as huge factorials) produce no test
+const MAX_LITERAL_LEN: usize = 256;
+
+/// 待执行的测试输入 / Test input awaiting execution
+struct Candidate {
+    name: String,
+    input: String,
+    test_type: TestStrategyType,
+    description: String,
+}
+
+impl TestSuite {
+    /// 生成可运行的测试文件：源程序、每个用例一个 `deftest` 以及 `run-tests` 入口；全部通过时返回 true
+    /// Emit a runnable test file: the program, one `deftest` per case and a `run-tests` entry point; the file
+    /// evaluates to true when every test passes
+    pub fn to_test_file(&self, source: &str) -> String {
+        let mut lines = vec![
+            "; 由 TestGenerator 生成的测试 / Tests generated by TestGenerator".to_string(),
+            source.trim().to_string(),
+            String::new(),
+        ];
+        for test in &self.test_cases {
+            lines.push(format!("(deftest {} {})", test.name, test.test_code));
+        }
+        lines.extend([
+            String::new(),
+            "; 测试运行入口 / Test-runner entry point".to_string(),
+            "(= (dict-get (run-tests) \"failed\") 0)".to_string(),
+        ]);
+        lines.join("\n") + "\n"
+    }
+}
+
+/// 由覆盖率报告得到测试覆盖率；语句覆盖率取表达式覆盖率，没有函数时全部为0
+/// Test coverage from the coverage report; statement coverage is the expression coverage, and everything is 0
+/// when there are no functions
+fn calculate_coverage(report: &CoverageReport) -> TestCoverage {
+    if report.functions.is_empty() {
+        return TestCoverage {
+            function_coverage: 0.0,
+            branch_coverage: 0.0,
+            statement_coverage: 0.0,
+            overall_coverage: 0.0,
+        };
+    }
+    let function_coverage = report.function_coverage();
+    let branch_coverage = report.branch_coverage();
+    let statement_coverage = report.expression_coverage();
+    TestCoverage {
+        function_coverage,
+        branch_coverage,
+        statement_coverage,
+        overall_coverage: function_coverage * 0.4
+            + branch_coverage * 0.3
+            + statement_coverage * 0.3,
+    }
+}
+
+/// 常规参数值，不同位置的参数取不同的值 / Typical argument value, differing by parameter position
+fn typical_value(ty: &Type, index: usize) -> Option<String> {
+    Some(match ty {
+        Type::Int | Type::Number | Type::Any | Type::Var(_) => (index + 2).to_string(),
+        Type::Float => format!("{:?}", index as f64 + 1.5),
+        Type::String => string_literal(&"abc"[..1 + index % 3]),
+        Type::Bool => "true".to_string(),
+        Type::Null => "null".to_string(),
+        Type::List(inner) => {
+            let items: Option<Vec<String>> = (0..3).map(|i| typical_value(inner, i)).collect();
+            format!("(list {})", items?.join(" "))
+        }
+        Type::Dict(inner) => format!("(dict \"a\" {})", typical_value(inner, 0)?),
+        Type::Fn(..) | Type::Named(_) => return None,
+    })
+}
+
+/// 按类型的边界值：零、负数、超大整数、空字符串、空列表等
+/// Boundary values by type: zero, negatives, huge integers, empty strings, empty lists, etc.
+fn boundary_values(ty: &Type) -> Vec<String> {
+    let huge = i64::MAX.to_string();
+    match ty {
+        Type::Int => vec!["0".into(), "-1".into(), huge, format!("-{}", i64::MAX)],
+        Type::Float => vec![
+            "0.0".into(),
+            "-1.0".into(),
+            "0.5".into(),
+            "1000000000.5".into(),
+        ],
+        Type::Number => vec!["0".into(), "-1".into(), huge, "0.5".into()],
+        Type::String => vec![
+            string_literal(""),
+            string_literal(" "),
+            string_literal("中文"),
+        ],
+        Type::Bool => vec!["false".into()],
+        Type::List(inner) => {
+            let mut values = vec!["(list)".to_string()];
+            values.extend(typical_value(inner, 0).map(|item| format!("(list {})", item)));
+            values
+        }
+        Type::Dict(_) => vec!["(dict)".into()],
+        Type::Any | Type::Var(_) => vec![
+            "0".into(),
+            "-1".into(),
+            huge,
+            string_literal(""),
+            "(list)".into(),
+            "null".into(),
+        ],
+        Type::Null | Type::Fn(..) | Type::Named(_) => Vec::new(),
+    }
+}
+
+/// 按类型的随机值 / Random value by type
+fn random_value(ty: &Type, rng: &mut SeededRng, depth: usize) -> Option<String> {
+    Some(match ty {
+        Type::Int | Type::Any | Type::Var(_) => (rng.below(201) as i64 - 100).to_string(),
+        Type::Float => format!("{:?}", (rng.below(20001) as f64 - 10000.0) / 100.0),
+        Type::Number if rng.chance(0.5) => (rng.below(201) as i64 - 100).to_string(),
+        Type::Number => format!("{:?}", (rng.below(20001) as f64 - 10000.0) / 100.0),
+        Type::String => {
+            const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz ";
+            let text: String = (0..rng.below(9))
+                .map(|_| ALPHABET[rng.below(ALPHABET.len())] as char)
+                .collect();
+            string_literal(&text)
+        }
+        Type::Bool => rng.chance(0.5).to_string(),
+        Type::Null => "null".to_string(),
+        Type::List(inner) => {
+            let len = if depth > 2 { 0 } else { rng.below(6) };
+            let items: Option<Vec<String>> = (0..len)
+                .map(|_| random_value(inner, rng, depth + 1))
+                .collect();
+            match items?.join(" ") {
+                items if items.is_empty() => "(list)".to_string(),
+                items => format!("(list {})", items),
+            }
+        }
+        Type::Dict(inner) => {
+            let len = if depth > 2 { 0 } else { rng.below(3) };
+            let mut entries = Vec::new();
+            for i in 0..len {
+                entries.push(format!(
+                    "\"k{}\" {}",
+                    i,
+                    random_value(inner, rng, depth + 1)?
+                ));
+            }
+            match entries.join(" ") {
+                entries if entries.is_empty() => "(dict)".to_string(),
+                entries => format!("(dict {})", entries),
+            }
+        }
+        Type::Fn(..) | Type::Named(_) => return None,
+    })
+}
+
+/// 字符串字面量 / String literal
+fn string_literal(text: &str) -> String {
+    unparse_literal(&Literal::String(text.to_string()))
+}
+
+/// 值的源码字面量，求值后得到相等的值；函数、生成器、错误等没有字面量时返回 None
+/// Source literal of a value that evaluates to an equal value; None for functions, generators, errors
+/// and other values without a literal form
+pub fn value_literal(value: &Value) -> Option<String> {
+    Some(match value {
+        Value::Int(i) => i.to_string(),
+        Value::BigInt(n) => n.to_string(),
+        Value::Float(f) if f.is_finite() => unparse_literal(&Literal::Float(*f)),
+        Value::String(s) => string_literal(s),
+        Value::Bool(b) => b.to_string(),
+        Value::Null => "null".to_string(),
+        Value::List(items) if items.is_empty() => "(list)".to_string(),
+        Value::List(items) => {
+            let items: Option<Vec<String>> = items.iter().map(value_literal).collect();
+            format!("(list {})", items?.join(" "))
+        }
+        Value::Dict(entries) if entries.is_empty() => "(dict)".to_string(),
+        // 按插入顺序输出键，重建的字典打印结果与原值一致
+        // Keys in insertion order, so the rebuilt dict prints the same as the original
+        Value::Dict(entries) => {
+            let entries: Option<Vec<String>> = entries
+                .iter()
+                .map(|(key, value)| {
+                    Some(format!("{} {}", string_literal(key), value_literal(value)?))
+                })
+                .collect();
+            format!("(dict {})", entries?.join(" "))
+        }
+        Value::Set(items) if items.is_empty() => "(set)".to_string(),
+        Value::Set(items) => {
+            let items: Option<Vec<String>> = items.iter().map(value_literal).collect();
+            format!("(set {})", items?.join(" "))
+        }
+        Value::Variant { tag, fields } if fields.is_empty() => tag.clone(),
+        Value::Variant { tag, fields } => {
+            let fields: Option<Vec<String>> = fields.iter().map(value_literal).collect();
+            format!("({} {})", tag, fields?.join(" "))
+        }
+        _ => return None,
+    })
+}
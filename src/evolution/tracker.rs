@@ -34,6 +34,13 @@ impl EvolutionTracker {
         self.event_log.push(event);
     }
 
+    /// 以指定的父事件记录进化事件（父子关系已知时使用，如程序谱系）
+    /// Record an evolution event with the given parents (used when lineage is known, e.g. program genealogy)
+    pub fn record_with_parents(&mut self, event: EvolutionEvent, parents: Vec<Uuid>) {
+        self.genealogy.add_lineage(&event, parents);
+        self.event_log.push(event);
+    }
+
     /// 查找父事件 / Find parent events
     fn find_parent_events(&self, event: &EvolutionEvent) -> Vec<Uuid> {
         let mut parents = Vec::new();
@@ -129,7 +136,14 @@ impl EvolutionTracker {
 
     /// 获取事件的祖先链 / Get ancestor chain of an event
     pub fn get_ancestors(&self, event_id: Uuid) -> Vec<Uuid> {
-        self.get_ancestors_recursive(event_id, 0, &mut std::collections::HashSet::new(), 100)
+        let ancestors =
+            self.get_ancestors_recursive(event_id, 0, &mut std::collections::HashSet::new(), 100);
+        // 多个父事件可能有共同祖先，只保留第一次出现 / Parents may share ancestors, keep the first occurrence only
+        let mut seen = std::collections::HashSet::new();
+        ancestors
+            .into_iter()
+            .filter(|id| seen.insert(*id))
+            .collect()
     }
 
     /// 递归获取祖先链（带深度限制和循环检测）/ Recursively get ancestors (with depth limit and cycle detection)
@@ -148,20 +162,17 @@ impl EvolutionTracker {
         visited.insert(event_id);
         let mut ancestors = Vec::new();
 
-        // 在事件日志中查找父事件 / Find parent events in event log
-        if let Some(event) = self.event_log.iter().find(|e| e.id == event_id) {
-            let parents = self.find_parent_events(event);
-            for parent_id in parents {
-                if !ancestors.contains(&parent_id) && !visited.contains(&parent_id) {
-                    ancestors.push(parent_id);
-                    // 递归查找祖先 / Recursively find ancestors
-                    ancestors.extend(self.get_ancestors_recursive(
-                        parent_id,
-                        current_depth + 1,
-                        visited,
-                        max_depth,
-                    ));
-                }
+        // 使用记录时确定的父事件 / Use the parents determined when the event was recorded
+        for parent_id in self.genealogy.get_parents(event_id) {
+            if !ancestors.contains(&parent_id) && !visited.contains(&parent_id) {
+                ancestors.push(parent_id);
+                // 递归查找祖先 / Recursively find ancestors
+                ancestors.extend(self.get_ancestors_recursive(
+                    parent_id,
+                    current_depth + 1,
+                    visited,
+                    max_depth,
+                ));
             }
         }
 
@@ -201,7 +212,22 @@ impl EvolutionTracker {
             .map_err(|e| format!("Failed to load events: {}", e))?;
 
         for event in events {
-            self.record(event);
+            // 程序进化事件在元数据中保存了确切的父个体 / Program evolution events keep their exact parents in metadata
+            let parents = match event.event_type {
+                EvolutionType::ProgramEvolution => event.after_state.metadata["parents"]
+                    .as_array()
+                    .map(|parents| {
+                        parents
+                            .iter()
+                            .filter_map(|parent| parent.as_str()?.parse().ok())
+                            .collect()
+                    }),
+                _ => None,
+            };
+            match parents {
+                Some(parents) => self.record_with_parents(event, parents),
+                None => self.record(event),
+            }
         }
 
         Ok(())
@@ -242,6 +268,14 @@ pub struct EvolutionEvent {
     pub success_metrics: Option<EvolutionMetrics>,
 }
 
+impl EvolutionEvent {
+    /// 记录事件时进化引擎的随机数种子，用 `EvolutionEngine::with_seed` 重放 / Evolution engine random seed when
+    /// the event was recorded; replay it with `EvolutionEngine::with_seed`
+    pub fn seed(&self) -> Option<u64> {
+        self.trigger.environment.get("seed")?.as_u64()
+    }
+}
+
 /// 进化类型 / Evolution type
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EvolutionType {
@@ -255,6 +289,8 @@ pub enum EvolutionType {
     EcosystemEvolution,
     /// 交互进化 / Interaction evolution
     InteractionEvolution,
+    /// 程序进化（遗传编程）/ Program evolution (genetic programming)
+    ProgramEvolution,
 }
 
 /// 状态快照 / State snapshot
@@ -352,6 +388,15 @@ impl EvolutionGenealogy {
         self.lineages.get(&event_id).cloned().unwrap_or_default()
     }
 
+    /// 获取父事件 / Get parent events
+    pub fn get_parents(&self, event_id: Uuid) -> Vec<Uuid> {
+        self.lineages
+            .iter()
+            .filter(|(_, children)| children.contains(&event_id))
+            .map(|(parent, _)| *parent)
+            .collect()
+    }
+
     /// 移除事件及其后代关系 / Remove event and its descendant relationships
     pub fn remove_event_and_descendants(&mut self, event_id: Uuid) {
         // 移除该事件作为父的所有关系 / Remove all relationships where this event is parent
@@ -0,0 +1,275 @@
+// 进化验证关卡 / Evolution verification gate
+// 在进化后的配置上运行回归语料（程序及其期望结果，加上由程序生成并在进化前记录期望输出的测试），
+// 通过率低于阈值时拒绝进化
+// Runs a regression corpus (programs with expected results, plus tests generated from the programs whose
+// expected outputs are recorded before evolving) against the post-evolution configuration, rejecting the
+// evolution when the pass rate drops below a threshold
+
+use crate::evolution::analyzer::CodeAnalyzer;
+use crate::evolution::sandbox::SandboxRunner;
+use crate::evolution::test_generator::{value_literal, TestGenerator};
+use crate::parser::AdaptiveParser;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// 回归用例：程序和期望结果，未给出的期望不检查，程序必须执行成功
+/// Regression case: a program and its expected results; expectations left out are not checked, but the program
+/// must execute successfully
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegressionCase {
+    /// 名称 / Name
+    pub name: String,
+    /// 程序源码 / Program source
+    pub source: String,
+    /// 期望的结果值（字面量形式，如 `3`、`"a"`、`(list 1 2)`）/ Expected result value (as a literal, e.g. `3`, `"a"`, `(list 1 2)`)
+    #[serde(default)]
+    pub expected: Option<String>,
+    /// 期望的打印输出 / Expected printed output
+    #[serde(default)]
+    pub output: Option<String>,
+}
+
+impl RegressionCase {
+    /// 创建期望结果值的用例 / Create a case expecting a result value
+    pub fn new(
+        name: impl Into<String>,
+        source: impl Into<String>,
+        expected: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            source: source.into(),
+            expected: Some(expected.into()),
+            output: None,
+        }
+    }
+}
+
+/// 回归语料 / Regression corpus
+///
+/// ```toml
+/// threshold = 0.9
+///
+/// [[cases]]
+/// name = "sum"
+/// source = "(+ 1 2)"
+/// expected = "3"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionCorpus {
+    /// 用例 / Cases
+    #[serde(default)]
+    pub cases: Vec<RegressionCase>,
+    /// 接受进化所需的最低通过率 / Minimum pass rate for accepting an evolution
+    #[serde(default = "default_threshold")]
+    pub threshold: f64,
+    /// 是否为用例中的函数生成测试 / Whether to generate tests for the functions in the cases
+    #[serde(default = "default_generate_tests")]
+    pub generate_tests: bool,
+}
+
+fn default_threshold() -> f64 {
+    1.0
+}
+
+fn default_generate_tests() -> bool {
+    true
+}
+
+impl Default for RegressionCorpus {
+    fn default() -> Self {
+        Self {
+            cases: Vec::new(),
+            threshold: default_threshold(),
+            generate_tests: default_generate_tests(),
+        }
+    }
+}
+
+impl RegressionCorpus {
+    /// 创建空语料，阈值为1（不允许任何回归）/ Create an empty corpus with threshold 1 (no regression allowed)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加用例 / Add a case
+    pub fn with_case(mut self, case: RegressionCase) -> Self {
+        self.cases.push(case);
+        self
+    }
+
+    /// 设置最低通过率 / Set the minimum pass rate
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// 从TOML解析 / Parse from TOML
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    /// 从文件加载 / Load from a file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_toml(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// 未通过的用例 / Failed case
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateFailure {
+    /// 用例名 / Case name
+    pub name: String,
+    /// 期望 / Expected
+    pub expected: String,
+    /// 实际结果或错误 / Actual result or error
+    pub actual: String,
+}
+
+/// 关卡判定 / Gate decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GateReport {
+    /// 运行的用例数（含生成的测试）/ Cases run (including generated tests)
+    pub total: usize,
+    /// 通过数 / Cases passed
+    pub passed: usize,
+    /// 其中生成的测试数 / Generated tests among them
+    pub generated: usize,
+    /// 通过率，没有用例时为1 / Pass rate, 1 when there are no cases
+    pub pass_rate: f64,
+    /// 最低通过率 / Minimum pass rate
+    pub threshold: f64,
+    /// 是否接受 / Whether accepted
+    pub accepted: bool,
+    /// 未通过的用例 / Failed cases
+    pub failures: Vec<GateFailure>,
+}
+
+/// 验证关卡：进化前用 `prepare` 在当前配置上记录生成测试的期望输出，进化后用 `verify` 在新配置上运行全部用例
+/// Verification gate: before evolving, `prepare` records the expected outputs of generated tests under the
+/// current configuration; after evolving, `verify` runs every case under the new one
+#[derive(Debug, Clone)]
+pub struct VerificationGate {
+    corpus: RegressionCorpus,
+    runner: SandboxRunner,
+}
+
+impl VerificationGate {
+    /// 用回归语料创建关卡，用例在沙箱中以生成代码的预算执行 / Create a gate for a corpus; cases run sandboxed with the generated-code budget
+    pub fn new(corpus: RegressionCorpus) -> Self {
+        Self {
+            corpus,
+            runner: SandboxRunner::new(),
+        }
+    }
+
+    /// 回归语料 / Regression corpus
+    pub fn corpus(&self) -> &RegressionCorpus {
+        &self.corpus
+    }
+
+    /// 在当前（进化前的）配置上准备用例：语料用例加上生成的测试，生成测试的期望为当前的执行结果，
+    /// 当前就失败的测试不参与验证
+    /// Prepare cases under the current (pre-evolution) configuration: the corpus cases plus generated tests whose
+    /// expectation is their current result; tests already failing now are left out
+    pub fn prepare(&self, parser: &AdaptiveParser) -> Vec<RegressionCase> {
+        let mut cases = self.corpus.cases.clone();
+        if !self.corpus.generate_tests {
+            return cases;
+        }
+
+        let mut generated = Vec::new();
+        for case in &self.corpus.cases {
+            let Ok(ast) = parser.parse(&case.source) else {
+                continue;
+            };
+            let analysis = CodeAnalyzer::new().analyze(&ast);
+            let suite = TestGenerator::new().generate_tests(&ast, &analysis);
+            for test in suite.test_cases {
+                if test.input.is_empty() {
+                    continue;
+                }
+                generated.push(RegressionCase {
+                    name: format!("{}::{}", case.name, test.name),
+                    source: format!("{}\n{}", case.source, test.input),
+                    expected: None,
+                    output: None,
+                });
+            }
+        }
+
+        let sources: Vec<String> = generated.iter().map(|case| case.source.clone()).collect();
+        for (mut case, result) in generated
+            .into_iter()
+            .zip(self.runner.run_code(parser, &sources))
+        {
+            if let Some(expected) = result.value.as_ref().and_then(value_literal) {
+                case.expected = Some(expected);
+                case.output = Some(result.output);
+                cases.push(case);
+            }
+        }
+        cases
+    }
+
+    /// 在当前（进化后的）配置上运行用例并判定 / Run the cases under the current (post-evolution) configuration and decide
+    pub fn verify(&self, parser: &AdaptiveParser, cases: &[RegressionCase]) -> GateReport {
+        let sources: Vec<String> = cases.iter().map(|case| case.source.clone()).collect();
+        let results = self.runner.run_code(parser, &sources);
+
+        let mut failures = Vec::new();
+        for (case, result) in cases.iter().zip(&results) {
+            let actual = match (&result.error, result.value.as_ref()) {
+                (Some(error), _) => Err(format!("error: {}", error)),
+                (None, value) => Ok(value
+                    .and_then(value_literal)
+                    .unwrap_or_else(|| "<unprintable>".to_string())),
+            };
+            let failure = match actual {
+                Err(error) => Some((describe_expectation(case), error)),
+                Ok(value) if case.expected.as_ref().is_some_and(|e| *e != value) => {
+                    Some((describe_expectation(case), value))
+                }
+                Ok(_) if case.output.as_ref().is_some_and(|o| *o != result.output) => Some((
+                    format!("output {:?}", case.output.as_deref().unwrap_or_default()),
+                    format!("output {:?}", result.output),
+                )),
+                Ok(_) => None,
+            };
+            if let Some((expected, actual)) = failure {
+                failures.push(GateFailure {
+                    name: case.name.clone(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        let total = cases.len();
+        let passed = total - failures.len();
+        let pass_rate = if total == 0 {
+            1.0
+        } else {
+            passed as f64 / total as f64
+        };
+        GateReport {
+            total,
+            passed,
+            generated: total.saturating_sub(self.corpus.cases.len()),
+            pass_rate,
+            threshold: self.corpus.threshold,
+            accepted: pass_rate >= self.corpus.threshold,
+            failures,
+        }
+    }
+}
+
+fn describe_expectation(case: &RegressionCase) -> String {
+    match (&case.expected, &case.output) {
+        (Some(expected), _) => expected.clone(),
+        (None, Some(output)) => format!("output {:?}", output),
+        (None, None) => "successful execution".to_string(),
+    }
+}
@@ -2,6 +2,8 @@
 // 定义语言的最小核心语法元素
 // Defines the minimal core grammar elements of the language
 
+use crate::runtime::symbol::Symbol;
+use num_bigint::BigInt;
 use serde::{Deserialize, Serialize};
 
 /// 语法元素类型 / Grammar element type
@@ -22,10 +24,10 @@ pub enum GrammarElement {
 pub enum Expr {
     /// 字面量 / Literal value
     Literal(Literal),
-    /// 变量引用 / Variable reference
-    Var(String),
-    /// 函数调用 / Function call
-    Call(String, Vec<Expr>),
+    /// 变量引用（名称在解析时驻留）/ Variable reference (the name is interned at parse time)
+    Var(Symbol),
+    /// 函数调用（函数名在解析时驻留）/ Function call (the function name is interned at parse time)
+    Call(Symbol, Vec<Expr>),
     /// 二元运算 / Binary operation
     Binary(BinOp, Box<Expr>, Box<Expr>),
     /// 条件表达式 / Conditional expression
@@ -35,7 +37,7 @@ pub enum Expr {
     /// For循环 / For loop
     For {
         /// 循环变量 / Loop variable
-        var: String,
+        var: Symbol,
         /// 迭代对象（列表或范围）/ Iterable (list or range)
         iterable: Box<Expr>,
         /// 循环体 / Loop body
@@ -53,7 +55,7 @@ pub enum Expr {
         /// Try块 / Try block
         try_body: Box<Expr>,
         /// Catch变量名（可选）/ Catch variable name (optional)
-        catch_var: Option<String>,
+        catch_var: Option<Symbol>,
         /// Catch处理块 / Catch handler block
         catch_body: Box<Expr>,
     },
@@ -68,7 +70,7 @@ pub enum Expr {
     /// Expression block (execute multiple expressions in sequence, return result of last one)
     Begin(Vec<Expr>),
     /// 赋值表达式 / Assignment expression (set!)
-    Assign(String, Box<Expr>),
+    Assign(Symbol, Box<Expr>),
 }
 
 /// 字面量类型 / Literal type
@@ -76,6 +78,8 @@ pub enum Expr {
 pub enum Literal {
     /// 整数 / Integer
     Int(i64),
+    /// 大整数（超出i64范围）/ Big integer (outside i64 range)
+    BigInt(BigInt),
     /// 浮点数 / Float
     Float(f64),
     /// 字符串 / String
@@ -117,19 +121,127 @@ pub enum BinOp {
     Ge,
 }
 
+impl BinOp {
+    /// 运算符名（可带 `op:` 前缀）对应的二元运算 / Binary operation named by an operator (optionally `op:`-prefixed)
+    pub fn from_operator(name: &str) -> Option<BinOp> {
+        match name.strip_prefix("op:").unwrap_or(name) {
+            "+" => Some(BinOp::Add),
+            "-" => Some(BinOp::Sub),
+            "*" => Some(BinOp::Mul),
+            "/" => Some(BinOp::Div),
+            "%" => Some(BinOp::Mod),
+            "=" | "==" => Some(BinOp::Eq),
+            "!=" | "<>" => Some(BinOp::Ne),
+            "<" => Some(BinOp::Lt),
+            ">" => Some(BinOp::Gt),
+            "<=" => Some(BinOp::Le),
+            ">=" => Some(BinOp::Ge),
+            _ => None,
+        }
+    }
+}
+
 /// 模式 / Pattern
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Pattern {
     /// 字面量模式 / Literal pattern
     Literal(Literal),
     /// 变量绑定模式 / Variable binding pattern
-    Var(String),
+    Var(Symbol),
     /// 通配符模式 / Wildcard pattern
     Wildcard,
     /// 列表模式 / List pattern
     List(Vec<Pattern>),
     /// 字典模式 / Dictionary pattern
     Dict(Vec<(String, Pattern)>),
+    /// 剩余模式（仅用于列表模式中，`..rest` 或 `..`）/ Rest pattern (list patterns only, `..rest` or `..`)
+    Rest(Option<Symbol>),
+    /// 或模式 `(or p1 p2 ...)` / Or-pattern `(or p1 p2 ...)`
+    Or(Vec<Pattern>),
+    /// 带守卫的模式 `(pattern when guard body)` / Guarded pattern `(pattern when guard body)`
+    Guard(Box<Pattern>, Box<Expr>),
+    /// 字符串前缀模式 `(prefix "http://" rest)`，其余部分交给内部模式
+    /// String prefix pattern `(prefix "http://" rest)`; the remainder goes to the inner pattern
+    Prefix(String, Box<Pattern>),
+    /// 字符串后缀模式 `(suffix ".evo" stem)`，其余部分交给内部模式
+    /// String suffix pattern `(suffix ".evo" stem)`; the remainder goes to the inner pattern
+    Suffix(String, Box<Pattern>),
+}
+
+impl Pattern {
+    /// 解构绑定的目标表达式（`(let (((q r) v)) ...)` 中的 `(q r)`）对应的模式：
+    /// 变量、`_`、`..rest`、嵌套列表和字典，其余返回None
+    /// Pattern of a destructuring binding target (the `(q r)` in `(let (((q r) v)) ...)`): variables, `_`, `..rest`,
+    /// nested lists and dictionaries; None for anything else
+    pub fn from_binding(expr: &Expr) -> Option<Pattern> {
+        match expr {
+            Expr::Var(name) if name == "_" => Some(Pattern::Wildcard),
+            Expr::Var(name) => Some(match name.strip_prefix("..") {
+                Some(rest) => Pattern::Rest((!rest.is_empty()).then(|| rest.into())),
+                None => Pattern::Var(name.into()),
+            }),
+            Expr::Literal(Literal::List(items)) => items
+                .iter()
+                .map(Pattern::from_binding)
+                .collect::<Option<Vec<_>>>()
+                .map(Pattern::List),
+            Expr::Literal(Literal::Dict(pairs)) => pairs
+                .iter()
+                .map(|(key, value)| Some((key.clone(), Pattern::from_binding(value)?)))
+                .collect::<Option<Vec<_>>>()
+                .map(Pattern::Dict),
+            _ => None,
+        }
+    }
+
+    /// `from_binding` 的逆变换：只含变量、通配符、剩余、列表和字典的模式转换为解构绑定目标
+    /// Inverse of `from_binding`: a pattern made only of variables, wildcards, rests, lists and dictionaries becomes a
+    /// destructuring binding target
+    pub fn to_binding(&self) -> Option<Expr> {
+        match self {
+            Pattern::Var(name) => Some(Expr::Var(name.clone())),
+            Pattern::Wildcard => Some(Expr::Var("_".into())),
+            Pattern::Rest(name) => Some(Expr::Var(
+                format!("..{}", name.as_deref().unwrap_or_default()).into(),
+            )),
+            Pattern::List(items) => items
+                .iter()
+                .map(Pattern::to_binding)
+                .collect::<Option<Vec<_>>>()
+                .map(|items| Expr::Literal(Literal::List(items))),
+            Pattern::Dict(pairs) => pairs
+                .iter()
+                .map(|(key, value)| Some((key.clone(), value.to_binding()?)))
+                .collect::<Option<Vec<_>>>()
+                .map(|pairs| Expr::Literal(Literal::Dict(pairs))),
+            _ => None,
+        }
+    }
+
+    /// 模式绑定的变量名（按出现顺序）/ Variable names the pattern binds, in order of appearance
+    pub fn bound_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        self.collect_names(&mut names);
+        names
+    }
+
+    fn collect_names(&self, names: &mut Vec<String>) {
+        match self {
+            Pattern::Var(name) | Pattern::Rest(Some(name)) => {
+                if !names.iter().any(|n| name == n) {
+                    names.push(name.to_string());
+                }
+            }
+            Pattern::List(items) | Pattern::Or(items) => {
+                items.iter().for_each(|item| item.collect_names(names))
+            }
+            Pattern::Dict(pairs) => pairs.iter().for_each(|(_, item)| item.collect_names(names)),
+            Pattern::Guard(inner, _) | Pattern::Prefix(_, inner) | Pattern::Suffix(_, inner) => {
+                inner.collect_names(names)
+            }
+            Pattern::Literal(_) | Pattern::Wildcard | Pattern::Rest(None) => {}
+        }
+    }
 }
 
 /// 核心语法常量 / Core grammar constants
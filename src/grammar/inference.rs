@@ -0,0 +1,761 @@
+// 类型推断 / Type inference
+// 无需标注的 Hindley-Milner 风格推断：类型变量 + 合一 + 顶层函数的泛化
+// Hindley-Milner style inference without annotations: type variables, unification and generalization of top-level functions
+
+use std::collections::{HashMap, HashSet};
+
+use crate::grammar::core::{Expr, GrammarElement, Literal, Pattern};
+use crate::grammar::types::{
+    arithmetic_result, binop_symbol, builtin_return_type, destructuring_pattern, element_name,
+    is_definition, FunctionSignature, Type, TypeCheckError,
+};
+use crate::parser::adaptive::Location;
+
+/// 推断结果 / Inference result
+#[derive(Debug, Clone, Default)]
+pub struct InferenceResult {
+    /// 推断出的顶层函数签名 / Inferred signatures of top-level functions
+    pub functions: HashMap<String, FunctionSignature>,
+    /// 可能的类型错误 / Probable type errors
+    pub errors: Vec<TypeCheckError>,
+}
+
+/// 类型方案：被泛化的类型变量和类型 / Type scheme: generalized variables and type
+#[derive(Debug, Clone)]
+struct Scheme {
+    quantified: Vec<usize>,
+    ty: Type,
+}
+
+/// 类型推断器 / Type inference engine
+#[derive(Debug, Default)]
+pub struct TypeInference {
+    /// 类型变量的绑定 / Bindings of type variables
+    bindings: Vec<Option<Type>>,
+    /// 作为 `+` 操作数但未确定的变量，显示为 Number
+    /// Variables used as `+` operands but left undetermined; displayed as Number
+    numeric: HashSet<usize>,
+    /// 顶层函数的类型方案 / Type schemes of top-level functions
+    schemes: HashMap<String, Scheme>,
+    /// 参数名（用于输出签名）/ Parameter names (for reporting signatures)
+    param_names: HashMap<String, Vec<String>>,
+    /// 变体构造器：标签 -> 类型名 / Variant constructors: tag -> type name
+    constructors: HashMap<String, (usize, String)>,
+    /// 当前作用域 / Current scope
+    scope: Vec<(String, Type)>,
+    /// 收集的错误 / Collected errors
+    errors: Vec<TypeCheckError>,
+    location: Option<Location>,
+    function: Option<String>,
+}
+
+impl TypeInference {
+    /// 创建类型推断器 / Create type inference engine
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 推断整个程序 / Infer a whole program
+    pub fn infer_program(&mut self, elements: &[GrammarElement]) -> InferenceResult {
+        self.infer_program_with_locations(elements, &[])
+    }
+
+    /// 推断整个程序，`locations` 为各顶层元素的起始位置
+    /// Infer a whole program, `locations` being the start location of each top-level element
+    pub fn infer_program_with_locations(
+        &mut self,
+        elements: &[GrammarElement],
+        locations: &[Location],
+    ) -> InferenceResult {
+        for element in elements {
+            self.collect_constructors(element);
+        }
+        for (index, element) in elements.iter().enumerate() {
+            self.location = locations.get(index).copied();
+            self.infer_element(element);
+        }
+        self.location = None;
+
+        let names: Vec<String> = self.schemes.keys().cloned().collect();
+        let functions = names
+            .into_iter()
+            .filter_map(|name| {
+                let signature = self.signature(&name)?;
+                Some((name, signature))
+            })
+            .collect();
+        InferenceResult {
+            functions,
+            errors: std::mem::take(&mut self.errors),
+        }
+    }
+
+    /// 推断单个函数定义的签名 / Infer the signature of a single function definition
+    pub fn infer_function(definition: &[GrammarElement]) -> Option<FunctionSignature> {
+        let name = definition.get(1).and_then(element_name)?;
+        let program = [GrammarElement::List(definition.to_vec())];
+        TypeInference::new()
+            .infer_program(&program)
+            .functions
+            .remove(&name)
+    }
+
+    /// 函数签名，未确定的类型变量显示为 Any（`+` 的操作数显示为 Number）
+    /// Function signature; undetermined variables show as Any (`+` operands as Number)
+    fn signature(&self, name: &str) -> Option<FunctionSignature> {
+        let Type::Fn(params, ret) = self.display(&self.schemes.get(name)?.ty) else {
+            return None;
+        };
+        let names = self.param_names.get(name).cloned().unwrap_or_default();
+        Some(FunctionSignature {
+            params: names.into_iter().zip(params).collect(),
+            return_type: *ret,
+            annotated: false,
+        })
+    }
+
+    fn collect_constructors(&mut self, element: &GrammarElement) {
+        if let GrammarElement::Expr(expr) = element {
+            if let Expr::Call(name, args) = expr.as_ref() {
+                if let (true, Some((Expr::Var(type_name), variants))) =
+                    (name == "deftype", args.split_first())
+                {
+                    for variant in variants {
+                        match variant {
+                            Expr::Var(tag) => {
+                                self.constructors
+                                    .insert(tag.to_string(), (0, type_name.to_string()));
+                            }
+                            Expr::Call(tag, fields) => {
+                                self.constructors
+                                    .insert(tag.to_string(), (fields.len(), type_name.to_string()));
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn fresh(&mut self) -> Type {
+        self.bindings.push(None);
+        Type::Var(self.bindings.len() - 1)
+    }
+
+    /// 沿绑定链找到代表类型 / Follow bindings to the representative type
+    fn prune(&self, ty: &Type) -> Type {
+        let mut current = ty.clone();
+        while let Type::Var(id) = current {
+            match &self.bindings[id] {
+                Some(bound) => current = bound.clone(),
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// 完全代入绑定 / Fully substitute bindings
+    fn resolve(&self, ty: &Type) -> Type {
+        match self.prune(ty) {
+            Type::List(inner) => Type::List(Box::new(self.resolve(&inner))),
+            Type::Dict(inner) => Type::Dict(Box::new(self.resolve(&inner))),
+            Type::Fn(params, ret) => Type::Fn(
+                params.iter().map(|p| self.resolve(p)).collect(),
+                Box::new(self.resolve(&ret)),
+            ),
+            other => other,
+        }
+    }
+
+    /// 用于展示的类型：去掉类型变量 / Type for display: without type variables
+    fn display(&self, ty: &Type) -> Type {
+        match self.prune(ty) {
+            Type::Var(id) if self.numeric.contains(&id) => Type::Number,
+            Type::Var(_) => Type::Any,
+            Type::List(inner) => Type::List(Box::new(self.display(&inner))),
+            Type::Dict(inner) => Type::Dict(Box::new(self.display(&inner))),
+            Type::Fn(params, ret) => Type::Fn(
+                params.iter().map(|p| self.display(p)).collect(),
+                Box::new(self.display(&ret)),
+            ),
+            other => other,
+        }
+    }
+
+    fn occurs(&self, id: usize, ty: &Type) -> bool {
+        match self.prune(ty) {
+            Type::Var(other) => other == id,
+            Type::List(inner) | Type::Dict(inner) => self.occurs(id, &inner),
+            Type::Fn(params, ret) => {
+                params.iter().any(|p| self.occurs(id, p)) || self.occurs(id, &ret)
+            }
+            _ => false,
+        }
+    }
+
+    /// 合一两个类型，失败时撤销部分绑定 / Unify two types, undoing partial bindings on failure
+    fn unify(&mut self, a: &Type, b: &Type) -> bool {
+        let snapshot = self.bindings.clone();
+        let unified = self.unify_inner(a, b);
+        if !unified {
+            self.bindings = snapshot;
+        }
+        unified
+    }
+
+    fn unify_inner(&mut self, a: &Type, b: &Type) -> bool {
+        match (self.prune(a), self.prune(b)) {
+            (Type::Var(x), Type::Var(y)) if x == y => true,
+            (Type::Var(x), other) | (other, Type::Var(x)) => {
+                if self.occurs(x, &other) {
+                    return false;
+                }
+                let other = match other {
+                    Type::Var(y) => {
+                        if self.numeric.contains(&x) {
+                            self.numeric.insert(y);
+                        }
+                        other
+                    }
+                    // `+` 允许整数和浮点数混合 / `+` mixes integers and floats
+                    Type::Int | Type::Float if self.numeric.contains(&x) => Type::Number,
+                    other => other,
+                };
+                self.bindings[x] = Some(other);
+                true
+            }
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Number, t) | (t, Type::Number) if t.is_numeric() => true,
+            (Type::List(x), Type::List(y)) | (Type::Dict(x), Type::Dict(y)) => {
+                self.unify_inner(&x, &y)
+            }
+            (Type::Fn(xs, x), Type::Fn(ys, y)) => {
+                xs.len() == ys.len()
+                    && xs.iter().zip(&ys).all(|(p, q)| self.unify_inner(p, q))
+                    && self.unify_inner(&x, &y)
+            }
+            (x, y) => x == y,
+        }
+    }
+
+    /// 实例化类型方案 / Instantiate a type scheme
+    fn instantiate(&mut self, scheme: &Scheme) -> Type {
+        let mapping: HashMap<usize, Type> = scheme
+            .quantified
+            .iter()
+            .map(|id| {
+                let fresh = self.fresh();
+                if let (true, Type::Var(fresh_id)) = (self.numeric.contains(id), &fresh) {
+                    self.numeric.insert(*fresh_id);
+                }
+                (*id, fresh)
+            })
+            .collect();
+        substitute(&self.resolve(&scheme.ty), &mapping)
+    }
+
+    fn free_vars(&self, ty: &Type, vars: &mut Vec<usize>) {
+        match self.prune(ty) {
+            Type::Var(id) if !vars.contains(&id) => vars.push(id),
+            Type::List(inner) | Type::Dict(inner) => self.free_vars(&inner, vars),
+            Type::Fn(params, ret) => {
+                params.iter().for_each(|p| self.free_vars(p, vars));
+                self.free_vars(&ret, vars);
+            }
+            _ => {}
+        }
+    }
+
+    fn report(&mut self, message: String) {
+        self.errors.push(TypeCheckError {
+            message,
+            location: self.location,
+            function: self.function.clone(),
+        });
+    }
+
+    fn lookup(&mut self, name: &str) -> Option<Type> {
+        if let Some((_, ty)) = self.scope.iter().rev().find(|(n, _)| n == name) {
+            return Some(ty.clone());
+        }
+        if let Some((0, type_name)) = self.constructors.get(name) {
+            return Some(Type::Named(type_name.clone()));
+        }
+        let scheme = self.schemes.get(name)?.clone();
+        Some(self.instantiate(&scheme))
+    }
+
+    /// 推断函数定义并泛化 / Infer a function definition and generalize it
+    fn infer_definition(&mut self, items: &[GrammarElement]) {
+        let Some(name) = items.get(1).and_then(element_name) else {
+            return;
+        };
+        let names: Vec<String> = match items.get(2) {
+            Some(GrammarElement::List(params)) => params.iter().filter_map(element_name).collect(),
+            _ => Vec::new(),
+        };
+        // 已有标注作为初始约束 / Existing annotations act as initial constraints
+        let annotated = FunctionSignature::from_definition(items)
+            .ok()
+            .filter(|signature| signature.annotated);
+        let mut params = Vec::new();
+        for (index, _) in names.iter().enumerate() {
+            let param = self.fresh();
+            if let Some(signature) = &annotated {
+                if let Some((_, ty)) = signature.params.get(index) {
+                    if *ty != Type::Any {
+                        self.unify(&param, ty);
+                    }
+                }
+            }
+            params.push(param);
+        }
+        let ret = self.fresh();
+        if let Some(signature) = &annotated {
+            if signature.return_type != Type::Any {
+                self.unify(&ret, &signature.return_type);
+            }
+        }
+
+        // 递归调用使用单态类型 / Recursive calls use the monomorphic type
+        let fn_type = Type::Fn(params.clone(), Box::new(ret.clone()));
+        self.schemes.insert(
+            name.clone(),
+            Scheme {
+                quantified: Vec::new(),
+                ty: fn_type.clone(),
+            },
+        );
+        self.param_names.insert(name.clone(), names.clone());
+
+        let previous_function = self.function.replace(name.clone());
+        let scope_len = self.scope.len();
+        self.scope.extend(names.into_iter().zip(params));
+        if let Some(body) = items.get(3) {
+            let body_type = self.infer_element(body);
+            self.unify(&ret, &body_type);
+        }
+        self.scope.truncate(scope_len);
+        self.function = previous_function;
+
+        let mut quantified = Vec::new();
+        self.free_vars(&fn_type, &mut quantified);
+        self.schemes.insert(
+            name,
+            Scheme {
+                quantified,
+                ty: self.resolve(&fn_type),
+            },
+        );
+    }
+
+    fn infer_element(&mut self, element: &GrammarElement) -> Type {
+        match element {
+            GrammarElement::Expr(expr) => self.infer_expr(expr),
+            GrammarElement::Atom(name) => self.lookup(name).unwrap_or(Type::Any),
+            GrammarElement::NaturalLang(_) => Type::Any,
+            GrammarElement::List(items) if is_definition(items) => {
+                self.infer_definition(items);
+                Type::Null
+            }
+            GrammarElement::List(items) => {
+                let head = items.first().and_then(element_name);
+                match (head.as_deref(), items.as_slice()) {
+                    (Some("let"), [_, name, value, body]) => {
+                        let value_type = self.infer_element(value);
+                        let scope_len = self.scope.len();
+                        match (element_name(name), destructuring_pattern(name)) {
+                            (Some(name), _) => self.scope.push((name, value_type.clone())),
+                            (None, Some(pattern)) => self.bind_pattern(&pattern),
+                            (None, None) => return Type::Any,
+                        }
+                        // 没有body的let绑定到外层作用域 / A let without body binds in the enclosing scope
+                        if matches!(body, GrammarElement::Expr(e) if **e == Expr::Literal(Literal::Null))
+                        {
+                            return value_type;
+                        }
+                        let body_type = self.infer_element(body);
+                        self.scope.truncate(scope_len);
+                        body_type
+                    }
+                    // 常量和无body的let一样绑定到外层作用域 / A constant binds in the enclosing scope like a body-less let
+                    (Some("defconst"), [_, name, value]) => {
+                        let value_type = self.infer_element(value);
+                        if let Some(name) = element_name(name) {
+                            self.scope.push((name, value_type));
+                        }
+                        Type::Null
+                    }
+                    _ => {
+                        for item in items.iter().skip(usize::from(head.is_some())) {
+                            self.infer_element(item);
+                        }
+                        Type::Any
+                    }
+                }
+            }
+        }
+    }
+
+    fn infer_expr(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Literal(literal) => self.infer_literal(literal),
+            Expr::Var(name) => self.lookup(name).unwrap_or(Type::Any),
+            Expr::Call(name, args) => {
+                let arg_types: Vec<Type> = args.iter().map(|arg| self.infer_expr(arg)).collect();
+                self.infer_call(name, &arg_types)
+            }
+            Expr::Binary(op, left, right) => {
+                let left = self.infer_expr(left);
+                let right = self.infer_expr(right);
+                self.infer_operator(binop_symbol(op), &[left, right])
+            }
+            Expr::If(condition, then_expr, else_expr) => {
+                self.infer_expr(condition);
+                // 类型谓词判断的变量在分支中视为动态类型
+                // A variable tested by a type predicate is dynamic inside the branches
+                let scope_len = self.scope.len();
+                if let Expr::Call(predicate, args) = condition.as_ref() {
+                    if let (true, [Expr::Var(var)]) =
+                        (predicate.starts_with("is-"), args.as_slice())
+                    {
+                        self.scope.push((var.to_string(), Type::Any));
+                    }
+                }
+                let then_type = self.infer_expr(then_expr);
+                let else_type = self.infer_expr(else_expr);
+                self.scope.truncate(scope_len);
+                self.join(&then_type, &else_type)
+            }
+            Expr::Match(value, cases) => {
+                self.infer_expr(value);
+                let mut result: Option<Type> = None;
+                for (pattern, body) in cases {
+                    let scope_len = self.scope.len();
+                    self.bind_pattern(pattern);
+                    let body_type = self.infer_expr(body);
+                    self.scope.truncate(scope_len);
+                    result = Some(match result {
+                        Some(ty) => self.join(&ty, &body_type),
+                        None => body_type,
+                    });
+                }
+                result.unwrap_or(Type::Null)
+            }
+            Expr::For {
+                var,
+                iterable,
+                body,
+            } => {
+                let iterable_type = self.infer_expr(iterable);
+                let element_type = match self.prune(&iterable_type) {
+                    Type::List(inner) => *inner,
+                    _ => self.fresh(),
+                };
+                self.scope.push((var.to_string(), element_type));
+                self.infer_expr(body);
+                self.scope.pop();
+                Type::Any
+            }
+            Expr::While { condition, body } => {
+                self.infer_expr(condition);
+                self.infer_expr(body);
+                Type::Any
+            }
+            Expr::Try {
+                try_body,
+                catch_var,
+                catch_body,
+            } => {
+                let try_type = self.infer_expr(try_body);
+                let scope_len = self.scope.len();
+                if let Some(var) = catch_var {
+                    self.scope
+                        .push((var.to_string(), Type::Named("Error".to_string())));
+                }
+                let catch_type = self.infer_expr(catch_body);
+                self.scope.truncate(scope_len);
+                self.join(&try_type, &catch_type)
+            }
+            Expr::Lambda { params, body } => {
+                let param_types: Vec<Type> = params.iter().map(|_| self.fresh()).collect();
+                let scope_len = self.scope.len();
+                self.scope
+                    .extend(params.iter().cloned().zip(param_types.iter().cloned()));
+                let body_type = self.infer_expr(body);
+                self.scope.truncate(scope_len);
+                Type::Fn(param_types, Box::new(body_type))
+            }
+            Expr::Begin(exprs) => exprs
+                .iter()
+                .map(|expr| self.infer_expr(expr))
+                .last()
+                .unwrap_or(Type::Null),
+            // 变量可被重新赋值，类型取两者的合并 / Variables may be reassigned, so their type becomes the join
+            Expr::Assign(var, value) => {
+                let value_type = self.infer_expr(value);
+                if let Some(index) = self.scope.iter().rposition(|(name, _)| var == name) {
+                    let joined = self.join(&self.scope[index].1, &value_type);
+                    self.scope[index].1 = joined;
+                }
+                value_type
+            }
+        }
+    }
+
+    fn infer_literal(&mut self, literal: &Literal) -> Type {
+        match literal {
+            Literal::Int(_) | Literal::BigInt(_) => Type::Int,
+            Literal::Float(_) => Type::Float,
+            Literal::String(_) => Type::String,
+            Literal::Bool(_) => Type::Bool,
+            Literal::Null => Type::Null,
+            Literal::List(items) => {
+                let types: Vec<Type> = items.iter().map(|item| self.infer_expr(item)).collect();
+                Type::List(Box::new(self.join_all(types)))
+            }
+            Literal::Dict(pairs) => {
+                let types: Vec<Type> = pairs
+                    .iter()
+                    .map(|(_, value)| self.infer_expr(value))
+                    .collect();
+                Type::Dict(Box::new(self.join_all(types)))
+            }
+        }
+    }
+
+    /// 分支类型的合并，不绑定类型变量 / Join of branch types, without binding type variables
+    fn join(&self, a: &Type, b: &Type) -> Type {
+        let (a, b) = (self.resolve(a), self.resolve(b));
+        if matches!(a, Type::Var(_)) || matches!(b, Type::Var(_)) {
+            return if a == b { a } else { Type::Any };
+        }
+        a.join(&b)
+    }
+
+    fn join_all(&mut self, types: Vec<Type>) -> Type {
+        let mut types = types.into_iter();
+        match types.next() {
+            Some(first) => types.fold(first, |acc, ty| self.join(&acc, &ty)),
+            None => self.fresh(),
+        }
+    }
+
+    fn infer_call(&mut self, name: &str, arg_types: &[Type]) -> Type {
+        if let Some(op) = name.strip_prefix("op:") {
+            return self.infer_operator(op, arg_types);
+        }
+        if let Some((_, type_name)) = self.constructors.get(name) {
+            return Type::Named(type_name.clone());
+        }
+        // 局部变量中的函数未泛化，只做静默约束 / Functions held in locals are not generalized, so constrain them quietly
+        let local = self.scope.iter().any(|(n, _)| n == name);
+        if let Some(callee) = self.lookup(name) {
+            return self.apply(name, &callee, arg_types, !local);
+        }
+        if let Some((params, ret)) = builtin_signature(name) {
+            for (index, (expected, actual)) in params.iter().zip(arg_types).enumerate() {
+                if !self.unify(actual, expected) {
+                    let actual = self.display(actual);
+                    self.report(format!(
+                        "argument {} of '{}' expects {}, got {}",
+                        index + 1,
+                        name,
+                        expected,
+                        actual
+                    ));
+                }
+            }
+            return ret;
+        }
+        builtin_return_type(name).unwrap_or(Type::Any)
+    }
+
+    /// 应用函数类型到参数 / Apply a function type to arguments
+    fn apply(&mut self, name: &str, callee: &Type, arg_types: &[Type], report: bool) -> Type {
+        match self.prune(callee) {
+            Type::Fn(params, ret) => {
+                if !report {
+                    if params.len() == arg_types.len() {
+                        for (expected, actual) in params.iter().zip(arg_types) {
+                            self.unify(expected, actual);
+                        }
+                    }
+                    return *ret;
+                }
+                if params.len() != arg_types.len() {
+                    self.report(format!(
+                        "'{}' expects {} argument(s), got {}",
+                        name,
+                        params.len(),
+                        arg_types.len()
+                    ));
+                    return *ret;
+                }
+                for (index, (expected, actual)) in params.iter().zip(arg_types).enumerate() {
+                    if !self.unify(expected, actual) {
+                        let (expected, actual) = (self.display(expected), self.display(actual));
+                        self.report(format!(
+                            "argument {} of '{}' expects {}, got {}",
+                            index + 1,
+                            name,
+                            expected,
+                            actual
+                        ));
+                    }
+                }
+                *ret
+            }
+            // 调用未知类型的变量：推断为函数 / Calling a variable of unknown type: infer a function
+            Type::Var(_) => {
+                let ret = self.fresh();
+                let fn_type = Type::Fn(arg_types.to_vec(), Box::new(ret.clone()));
+                self.unify(callee, &fn_type);
+                ret
+            }
+            _ => Type::Any,
+        }
+    }
+
+    /// 运算符：类型变量按另一操作数约束 / Operators: type variables are constrained by the other operand
+    fn infer_operator(&mut self, op: &str, arg_types: &[Type]) -> Type {
+        match op {
+            "+" | "-" | "*" | "/" | "%" => {
+                let mut operands = arg_types.iter();
+                let Some(first) = operands.next() else {
+                    return Type::Any;
+                };
+                let mut result = first.clone();
+                for operand in operands {
+                    result = self.arithmetic(op, &result, operand);
+                }
+                result
+            }
+            "<" | ">" | "<=" | ">=" | "=" | "!=" => Type::Bool,
+            _ => Type::Any,
+        }
+    }
+
+    fn arithmetic(&mut self, op: &str, left: &Type, right: &Type) -> Type {
+        let (a, b) = (self.prune(left), self.prune(right));
+        match (&a, &b) {
+            (Type::Var(_), Type::Var(_)) => {
+                self.unify(&a, &b);
+                if op == "+" {
+                    // `+` 也可连接字符串和列表，保持未定 / `+` also joins strings and lists, so leave it open
+                    let merged = self.prune(&a);
+                    if let Type::Var(id) = merged {
+                        self.numeric.insert(id);
+                    }
+                    merged
+                } else {
+                    self.unify(&a, &Type::Number);
+                    Type::Number
+                }
+            }
+            (Type::Var(_), other) | (other, Type::Var(_)) => {
+                let var = if matches!(a, Type::Var(_)) { &a } else { &b };
+                let constraint = match other {
+                    Type::String | Type::List(_) if op == "+" => other.clone(),
+                    Type::Int | Type::Float if op == "+" => Type::Number,
+                    Type::Int | Type::Float | Type::Number => other.clone(),
+                    Type::Any => return Type::Any,
+                    Type::Named(_) if op == "+" => return Type::Any,
+                    _ => {
+                        self.report(format!("cannot apply '{}' to {}", op, other));
+                        return Type::Any;
+                    }
+                };
+                self.unify(var, &constraint);
+                let resolved = self.prune(var);
+                arithmetic_result(op, &resolved, other).unwrap_or(Type::Any)
+            }
+            _ => match arithmetic_result(op, &a, &b) {
+                Some(ty) => ty,
+                None => {
+                    self.report(format!("cannot apply '{}' to {} and {}", op, a, b));
+                    Type::Any
+                }
+            },
+        }
+    }
+
+    fn bind_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Var(name) if !self.constructors.contains_key(name.as_str()) => {
+                let ty = self.fresh();
+                self.scope.push((name.to_string(), ty));
+            }
+            Pattern::List(items) => items.iter().for_each(|item| self.bind_pattern(item)),
+            Pattern::Dict(pairs) => pairs.iter().for_each(|(_, item)| self.bind_pattern(item)),
+            Pattern::Rest(Some(name)) => {
+                let inner = self.fresh();
+                self.scope
+                    .push((name.to_string(), Type::List(Box::new(inner))));
+            }
+            Pattern::Or(alternatives) => {
+                alternatives.iter().for_each(|item| self.bind_pattern(item))
+            }
+            Pattern::Guard(inner, guard) => {
+                self.bind_pattern(inner);
+                self.infer_expr(guard);
+            }
+            // 前缀和后缀之外的部分是字符串 / What remains besides the prefix or suffix is a string
+            Pattern::Prefix(_, rest) | Pattern::Suffix(_, rest) => match rest.as_ref() {
+                Pattern::Var(name) if !self.constructors.contains_key(name.as_str()) => {
+                    self.scope.push((name.to_string(), Type::String));
+                }
+                rest => self.bind_pattern(rest),
+            },
+            _ => {}
+        }
+    }
+}
+
+/// 替换类型变量 / Substitute type variables
+fn substitute(ty: &Type, mapping: &HashMap<usize, Type>) -> Type {
+    match ty {
+        Type::Var(id) => mapping.get(id).cloned().unwrap_or(Type::Var(*id)),
+        Type::List(inner) => Type::List(Box::new(substitute(inner, mapping))),
+        Type::Dict(inner) => Type::Dict(Box::new(substitute(inner, mapping))),
+        Type::Fn(params, ret) => Type::Fn(
+            params.iter().map(|p| substitute(p, mapping)).collect(),
+            Box::new(substitute(ret, mapping)),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// 对参数类型有要求的内置函数 / Builtins with parameter type requirements
+fn builtin_signature(name: &str) -> Option<(Vec<Type>, Type)> {
+    let list = || Type::List(Box::new(Type::Any));
+    let dict = || Type::Dict(Box::new(Type::Any));
+    Some(match name {
+        "string-upper" | "upper" | "string-lower" | "lower" | "string-trim" | "trim" => {
+            (vec![Type::String], Type::String)
+        }
+        "string-length" | "strlen" => (vec![Type::String], Type::Int),
+        "string-split" | "split" => (
+            vec![Type::String, Type::String],
+            Type::List(Box::new(Type::String)),
+        ),
+        "string-replace" | "replace" | "regex-replace" => {
+            (vec![Type::String, Type::String, Type::String], Type::String)
+        }
+        "regex-split" => (
+            vec![Type::String, Type::String],
+            Type::List(Box::new(Type::String)),
+        ),
+        "regex-match" => (vec![Type::String, Type::String], Type::Any),
+        "parse-date" => (vec![Type::String], Type::Named("DateTime".to_string())),
+        "list-reverse" | "reverse" | "list-sort" | "sort" | "list-unique" | "unique" => {
+            (vec![list()], list())
+        }
+        "dict-keys" => (vec![dict()], Type::List(Box::new(Type::String))),
+        "dict-values" => (vec![dict()], list()),
+        "dict-get" => (vec![dict(), Type::String], Type::Any),
+        "dict-has" => (vec![dict(), Type::String], Type::Bool),
+        _ => return None,
+    })
+}
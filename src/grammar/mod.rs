@@ -8,6 +8,10 @@
 //! - `core.rs` - **核心语法定义** - AST节点类型 (`GrammarElement`)、数据类型 (`Value`)
 //! - `rule.rs` - **语法规则系统** - 规则定义 (`GrammarRule`)、规则匹配和应用
 //! - `self_desc.rs` - **自描述语法机制** - 用语言自身描述语法规则
+//! - `unparse.rs` - **反解析器** - AST转回可执行源代码: `unparse()`
+//! - `types.rs` - **类型检查** - 可选类型标注与渐进式检查: `TypeChecker::check_source()`
+//! - `inference.rs` - **类型推断** - 无标注代码的类型推断: `TypeInference::infer_program()`
+//! - `visit.rs` - **AST遍历** - `Visitor`/`Mutator` 与带深度和位置的遍历: `visit::walk_program()`
 //!
 //! ## 关键类型 / Key Types
 //!
@@ -16,9 +20,16 @@
 //! - `GrammarRule` - 语法规则（在 `rule.rs` 定义）
 
 pub mod core;
+pub mod inference;
 pub mod rule;
 pub mod self_desc;
+pub mod types;
+pub mod unparse;
+pub mod visit;
 
 pub use core::*;
+pub use inference::*;
 pub use rule::*;
 pub use self_desc::*;
+pub use types::*;
+pub use unparse::*;
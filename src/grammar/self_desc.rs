@@ -1,10 +1,15 @@
 // 自描述语法 / Self-describing syntax
-// 允许语言用自身描述自身的语法规则
-// Allows the language to describe its own grammar rules using itself
+// 允许语言用自身描述自身的语法规则：`describe_rule` 生成规则的自描述代码，`parse_self_description` 把代码读回规则
+// Allows the language to describe its own grammar rules using itself: `describe_rule` generates a rule's
+// self-describing code and `parse_self_description` reads the code back into a rule
 
+use crate::grammar::core::GrammarElement;
 use crate::grammar::rule::{
-    DefinitionMethod, GrammarRule, Pattern, Production, RuleMetadata, Stability,
+    Condition, DefinitionMethod, GrammarRule, Pattern, PatternElement, Production, RuleMetadata,
+    Stability, TransformRule,
 };
+use crate::grammar::unparse::{quote_string, unparse_expr};
+use crate::parser::adaptive::{AdaptiveParser, Location, ParseError};
 use serde::{Deserialize, Serialize};
 
 /// 自描述语法规则 / Self-describing syntax rule
@@ -28,6 +33,12 @@ impl SelfDescribingRule {
         }
     }
 
+    /// 由规则生成自描述代码 / Generate the self-describing code of a rule
+    pub fn from_rule(rule: GrammarRule) -> Self {
+        let self_describing_code = describe_rule(&rule);
+        Self::new(rule, self_describing_code)
+    }
+
     /// 创建递归自描述规则 / Create recursive self-describing rule
     pub fn recursive(
         rule: GrammarRule,
@@ -81,15 +92,847 @@ pub fn syntax_definition_rule() -> SelfDescribingRule {
 
     let rule = GrammarRule::new("语法定义".to_string(), pattern, production, meta);
 
-    let self_describing_code = r#"
-语法 语法定义:
-    模式: "语法" 标识符 ":" 模式列表 "=>" 产生式
-    产生: 创建语法规则(标识符, 模式列表, 产生式)
-    版本: "1.0"
-    定义方式: "自描述"
-    稳定性: "稳定"
-"#
-    .to_string();
-
-    SelfDescribingRule::new(rule, self_describing_code)
+    SelfDescribingRule::from_rule(rule)
+}
+
+/// 生成规则的自描述代码，`parse_self_description` 可读回除ID和时间戳外相同的规则
+/// Generate the self-describing code of a rule; `parse_self_description` reads back a rule identical apart from
+/// its ID and timestamps
+///
+/// ```text
+/// 语法 变量声明:
+///     模式: 自然语言(变量) 标识符(name) "=" 通配(value)
+///     产生: VariableDeclaration
+///     版本: "1.0"
+///     定义方式: 自描述
+///     稳定性: 稳定
+///     示例: "变量 x = 1"
+/// ```
+///
+/// 模式元素写作 `"关键字"`、`自然语言(词)`、`标识符(名)`、`通配(名)`、`字面量(元素)`、`可选(元素)`、`重复(元素)`、
+/// `分组(元素...)`；产生式写作符号（原子）、`原子("...")`、`自然语言("...")`、`列表(元素...)` 或 `表达式("源码")`
+/// Pattern elements are written `"keyword"`, `自然语言(word)`, `标识符(name)`, `通配(name)`, `字面量(element)`,
+/// `可选(element)`, `重复(element)` and `分组(element...)`; productions are a symbol (atom), `原子("...")`,
+/// `自然语言("...")`, `列表(element...)` or `表达式("source")`
+pub fn describe_rule(rule: &GrammarRule) -> String {
+    let mut fields = vec![(
+        "模式",
+        rule.pattern
+            .elements
+            .iter()
+            .map(pattern_term)
+            .collect::<Vec<_>>()
+            .join(" "),
+    )];
+    if rule.pattern.variadic {
+        fields.push(("可变参数", "是".to_string()));
+    }
+    fields.push(("产生", element_term(&rule.production.target)));
+    for transform in &rule.production.transform {
+        fields.push((
+            "转换",
+            format!(
+                "{} => {}",
+                pattern_term(&transform.from),
+                element_term(&transform.to)
+            ),
+        ));
+    }
+    for condition in &rule.production.conditions {
+        fields.push(("条件", condition_term(condition)));
+    }
+    let meta = &rule.meta;
+    fields.push(("版本", quote_string(&meta.version)));
+    fields.push(("定义方式", method_term(&meta.defined_by)));
+    fields.push(("稳定性", stability_word(meta.stability).to_string()));
+    if !meta.description.is_empty() {
+        fields.push(("描述", quote_string(&meta.description)));
+    }
+    for (key, items) in [
+        ("示例", &meta.examples),
+        ("同义词", &meta.natural_lang_synonyms),
+    ] {
+        if !items.is_empty() {
+            let items: Vec<String> = items.iter().map(|item| quote_string(item)).collect();
+            fields.push((key, items.join(" ")));
+        }
+    }
+
+    let mut code = format!("语法 {}:\n", name_term(&rule.name));
+    for (key, value) in fields {
+        if value.is_empty() {
+            code.push_str(&format!("    {}:\n", key));
+        } else {
+            code.push_str(&format!("    {}: {}\n", key, value));
+        }
+    }
+    code
+}
+
+/// 把一段自描述代码读回语法规则（见 `describe_rule` 的格式），代码中必须恰好有一条规则
+/// Read a piece of self-describing code back into a grammar rule (format as in `describe_rule`); the code must
+/// hold exactly one rule
+pub fn parse_self_description(code: &str) -> Result<GrammarRule, ParseError> {
+    let mut rules = parse_self_descriptions(code)?;
+    if rules.len() != 1 {
+        return Err(ParseError::syntax_error(
+            format!("Expected one self-described rule, found {}", rules.len()),
+            None,
+        ));
+    }
+    Ok(rules.remove(0))
+}
+
+/// 读取自描述代码中的全部规则，用于从Evo-lang写成的描述自举整个语法；`;` 开头的行是注释
+/// Read every rule in self-describing code, for bootstrapping a whole grammar from descriptions written in
+/// Evo-lang; lines starting with `;` are comments
+pub fn parse_self_descriptions(code: &str) -> Result<Vec<GrammarRule>, ParseError> {
+    let mut rules = Vec::new();
+    let mut current: Option<RuleBuilder> = None;
+    for (index, line) in code.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with(';') {
+            continue;
+        }
+        let indent = line.chars().take_while(|ch| ch.is_whitespace()).count();
+        let location = Location::new(index + 1, indent + 1);
+        if !line.starts_with(char::is_whitespace) {
+            if let Some(builder) = current.take() {
+                rules.push(builder.build()?);
+            }
+            let header = trimmed
+                .strip_prefix("语法")
+                .and_then(|rest| rest.strip_suffix(':'))
+                .filter(|rest| rest.starts_with(char::is_whitespace))
+                .ok_or_else(|| {
+                    ParseError::syntax_error(
+                        format!("Expected `语法 <name>:`, found `{}`", trimmed),
+                        Some(location),
+                    )
+                })?;
+            let name = match terms(header, location)?.as_slice() {
+                [term] => text(term, location)?,
+                _ => {
+                    return Err(ParseError::syntax_error(
+                        "Rule name must be a single symbol or string".to_string(),
+                        Some(location),
+                    ))
+                }
+            };
+            current = Some(RuleBuilder::new(name, location));
+            continue;
+        }
+        let builder = current.as_mut().ok_or_else(|| {
+            ParseError::syntax_error("Field outside of a `语法` rule".to_string(), Some(location))
+        })?;
+        let (key, value) = trimmed.split_once(':').ok_or_else(|| {
+            ParseError::syntax_error(
+                format!("Expected `<field>: <value>`, found `{}`", trimmed),
+                Some(location),
+            )
+        })?;
+        builder.field(key.trim(), &terms(value, location)?, location)?;
+    }
+    if let Some(builder) = current {
+        rules.push(builder.build()?);
+    }
+    Ok(rules)
+}
+
+/// 自描述代码中的项 / Term in self-describing code
+#[derive(Debug, Clone, PartialEq)]
+enum Term {
+    /// 字符串 / String
+    Str(String),
+    /// 符号 / Symbol
+    Symbol(String),
+    /// 带参数的构造，如 `标识符(name)` / Constructor with arguments, such as `标识符(name)`
+    Apply(String, Vec<Term>),
+}
+
+/// 读取中的规则 / Rule being read
+struct RuleBuilder {
+    name: String,
+    location: Location,
+    elements: Option<Vec<PatternElement>>,
+    variadic: bool,
+    target: Option<GrammarElement>,
+    transform: Vec<TransformRule>,
+    conditions: Vec<Condition>,
+    version: Option<String>,
+    defined_by: Option<DefinitionMethod>,
+    stability: Option<Stability>,
+    description: Option<String>,
+    examples: Vec<String>,
+    synonyms: Vec<String>,
+}
+
+impl RuleBuilder {
+    fn new(name: String, location: Location) -> Self {
+        Self {
+            name,
+            location,
+            elements: None,
+            variadic: false,
+            target: None,
+            transform: Vec::new(),
+            conditions: Vec::new(),
+            version: None,
+            defined_by: None,
+            stability: None,
+            description: None,
+            examples: Vec::new(),
+            synonyms: Vec::new(),
+        }
+    }
+
+    /// 读取一个字段 / Read one field
+    fn field(&mut self, key: &str, value: &[Term], location: Location) -> Result<(), ParseError> {
+        let error = |message: String| ParseError::syntax_error(message, Some(location));
+        let duplicate = || error(format!("Duplicate field `{}`", key));
+        match key {
+            "模式" => {
+                let elements = value
+                    .iter()
+                    .map(|term| pattern_element(term, location))
+                    .collect::<Result<_, _>>()?;
+                set_once(&mut self.elements, elements).ok_or_else(duplicate)
+            }
+            "可变参数" => {
+                self.variadic = match single(value, key, location)? {
+                    Term::Symbol(word) if word == "是" => true,
+                    Term::Symbol(word) if word == "否" => false,
+                    other => return Err(error(format!("Expected 是 or 否, found {:?}", other))),
+                };
+                Ok(())
+            }
+            "产生" => {
+                let target = grammar_element(single(value, key, location)?, location)?;
+                set_once(&mut self.target, target).ok_or_else(duplicate)
+            }
+            "转换" => {
+                let arrow = value
+                    .iter()
+                    .position(|term| *term == Term::Symbol("=>".to_string()))
+                    .ok_or_else(|| error("Expected `<pattern> => <element>`".to_string()))?;
+                self.transform.push(TransformRule {
+                    from: pattern_element(single(&value[..arrow], key, location)?, location)?,
+                    to: grammar_element(single(&value[arrow + 1..], key, location)?, location)?,
+                });
+                Ok(())
+            }
+            "条件" => {
+                let condition = match single(value, key, location)? {
+                    Term::Apply(kind, args) => match (kind.as_str(), args.as_slice()) {
+                        ("类型检查", [a, b]) => {
+                            Condition::TypeCheck(text(a, location)?, text(b, location)?)
+                        }
+                        ("值检查", [a, b]) => {
+                            Condition::ValueCheck(text(a, location)?, text(b, location)?)
+                        }
+                        ("上下文检查", [a]) => Condition::ContextCheck(text(a, location)?),
+                        _ => return Err(error(format!("Unknown condition `{}`", kind))),
+                    },
+                    other => return Err(error(format!("Unknown condition {:?}", other))),
+                };
+                self.conditions.push(condition);
+                Ok(())
+            }
+            "版本" => {
+                let version = text(single(value, key, location)?, location)?;
+                set_once(&mut self.version, version).ok_or_else(duplicate)
+            }
+            "定义方式" => {
+                let method = definition_method(single(value, key, location)?, location)?;
+                set_once(&mut self.defined_by, method).ok_or_else(duplicate)
+            }
+            "稳定性" => {
+                let word = text(single(value, key, location)?, location)?;
+                let stability = match word.as_str() {
+                    "实验" => Stability::Experimental,
+                    "稳定中" => Stability::Stabilizing,
+                    "稳定" => Stability::Stable,
+                    "弃用" => Stability::Deprecated,
+                    _ => return Err(error(format!("Unknown stability `{}`", word))),
+                };
+                set_once(&mut self.stability, stability).ok_or_else(duplicate)
+            }
+            "描述" => {
+                let description = text(single(value, key, location)?, location)?;
+                set_once(&mut self.description, description).ok_or_else(duplicate)
+            }
+            "示例" | "同义词" => {
+                let items = value
+                    .iter()
+                    .map(|term| text(term, location))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match key {
+                    "示例" => self.examples.extend(items),
+                    _ => self.synonyms.extend(items),
+                }
+                Ok(())
+            }
+            _ => Err(error(format!("Unknown field `{}`", key))),
+        }
+    }
+
+    fn build(self) -> Result<GrammarRule, ParseError> {
+        let missing = |field: &str| {
+            ParseError::syntax_error(
+                format!("Rule `{}` is missing field `{}`", self.name, field),
+                Some(self.location),
+            )
+        };
+        let elements = self.elements.clone().ok_or_else(|| missing("模式"))?;
+        let target = self.target.clone().ok_or_else(|| missing("产生"))?;
+        let meta = RuleMetadata {
+            version: self.version.unwrap_or_else(|| "1.0".to_string()),
+            defined_by: self.defined_by.unwrap_or(DefinitionMethod::SelfDescribing),
+            stability: self.stability.unwrap_or(Stability::Experimental),
+            description: self.description.unwrap_or_default(),
+            examples: self.examples,
+            natural_lang_synonyms: self.synonyms,
+        };
+        Ok(GrammarRule::new(
+            self.name,
+            Pattern {
+                elements,
+                variadic: self.variadic,
+            },
+            Production {
+                target,
+                transform: self.transform,
+                conditions: self.conditions,
+            },
+            meta,
+        ))
+    }
+}
+
+/// 只能设置一次的字段，重复时返回 None / Field that may be set once; None when set again
+fn set_once<T>(slot: &mut Option<T>, value: T) -> Option<()> {
+    if slot.is_some() {
+        return None;
+    }
+    *slot = Some(value);
+    Some(())
+}
+
+/// 字段值必须是单个项 / A field value that must be a single term
+fn single<'t>(value: &'t [Term], key: &str, location: Location) -> Result<&'t Term, ParseError> {
+    match value {
+        [term] => Ok(term),
+        _ => Err(ParseError::syntax_error(
+            format!("Field `{}` takes exactly one value", key),
+            Some(location),
+        )),
+    }
+}
+
+/// 字符串或符号的文本 / Text of a string or symbol
+fn text(term: &Term, location: Location) -> Result<String, ParseError> {
+    match term {
+        Term::Str(text) | Term::Symbol(text) => Ok(text.clone()),
+        Term::Apply(name, _) => Err(ParseError::syntax_error(
+            format!("Expected a string or symbol, found `{}(...)`", name),
+            Some(location),
+        )),
+    }
+}
+
+fn pattern_element(term: &Term, location: Location) -> Result<PatternElement, ParseError> {
+    let element = match term {
+        Term::Str(word) => PatternElement::Keyword(word.clone()),
+        Term::Apply(kind, args) => match (kind.as_str(), args.as_slice()) {
+            ("自然语言", [word]) => PatternElement::NaturalLang(text(word, location)?),
+            ("标识符", [name]) => PatternElement::Identifier(text(name, location)?),
+            ("通配", [name]) => PatternElement::Wildcard(text(name, location)?),
+            ("字面量", [element]) => {
+                PatternElement::Literal(grammar_element(element, location)?)
+            }
+            ("可选", [inner]) => {
+                PatternElement::Optional(Box::new(pattern_element(inner, location)?))
+            }
+            ("重复", [inner]) => {
+                PatternElement::Repeat(Box::new(pattern_element(inner, location)?))
+            }
+            ("分组", elements) => PatternElement::Group(
+                elements
+                    .iter()
+                    .map(|element| pattern_element(element, location))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => {
+                return Err(ParseError::syntax_error(
+                    format!("Unknown pattern element `{}`", kind),
+                    Some(location),
+                ))
+            }
+        },
+        Term::Symbol(symbol) => {
+            return Err(ParseError::syntax_error(
+                format!(
+                    "Unknown pattern element `{}`; keywords are quoted, such as \"{}\"",
+                    symbol, symbol
+                ),
+                Some(location),
+            ))
+        }
+    };
+    Ok(element)
+}
+
+fn grammar_element(term: &Term, location: Location) -> Result<GrammarElement, ParseError> {
+    let element = match term {
+        Term::Symbol(atom) => GrammarElement::Atom(atom.clone()),
+        Term::Apply(kind, args) => match (kind.as_str(), args.as_slice()) {
+            ("原子", [atom]) => GrammarElement::Atom(text(atom, location)?),
+            ("自然语言", [fragment]) => GrammarElement::NaturalLang(text(fragment, location)?),
+            ("列表", items) => GrammarElement::List(
+                items
+                    .iter()
+                    .map(|item| grammar_element(item, location))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ("表达式", [source]) => {
+                let source = text(source, location)?;
+                let mut elements = AdaptiveParser::new(false)
+                    .parse(&source)
+                    .map_err(|error| error.with_location(location))?;
+                if elements.len() != 1 {
+                    return Err(ParseError::syntax_error(
+                        format!("Expected one expression, found `{}`", source),
+                        Some(location),
+                    ));
+                }
+                elements.remove(0)
+            }
+            _ => {
+                return Err(ParseError::syntax_error(
+                    format!("Unknown grammar element `{}`", kind),
+                    Some(location),
+                ))
+            }
+        },
+        Term::Str(string) => {
+            return Err(ParseError::syntax_error(
+                format!(
+                    "Unexpected string \"{}\"; write atoms as symbols or 原子(...)",
+                    string
+                ),
+                Some(location),
+            ))
+        }
+    };
+    Ok(element)
+}
+
+fn definition_method(term: &Term, location: Location) -> Result<DefinitionMethod, ParseError> {
+    let method = match term {
+        Term::Str(word) | Term::Symbol(word) if word == "自描述" => {
+            DefinitionMethod::SelfDescribing
+        }
+        Term::Str(word) | Term::Symbol(word) if word == "进化" => DefinitionMethod::Evolutionary,
+        Term::Apply(kind, args) => match (kind.as_str(), args.as_slice()) {
+            ("外部", [language]) => DefinitionMethod::External(text(language, location)?),
+            ("混合", methods) => DefinitionMethod::Hybrid(
+                methods
+                    .iter()
+                    .map(|method| definition_method(method, location))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => {
+                return Err(ParseError::syntax_error(
+                    format!("Unknown definition method `{}`", kind),
+                    Some(location),
+                ))
+            }
+        },
+        other => {
+            return Err(ParseError::syntax_error(
+                format!("Unknown definition method {:?}", other),
+                Some(location),
+            ))
+        }
+    };
+    Ok(method)
+}
+
+/// 把字段值切分为项：字符串、符号和 `名(项...)`，逗号视同空白
+/// Split a field value into terms: strings, symbols and `name(term...)`, with commas treated as whitespace
+fn terms(source: &str, location: Location) -> Result<Vec<Term>, ParseError> {
+    let error = |message: &str| ParseError::syntax_error(message.to_string(), Some(location));
+    // 栈底是顶层，其余每层是一个未闭合的构造 / The bottom is the top level; each other level is an unclosed constructor
+    let mut stack: Vec<(Option<String>, Vec<Term>)> = vec![(None, Vec::new())];
+    let mut chars = source.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            c if c.is_whitespace() || c == ',' => {
+                chars.next();
+            }
+            '"' => {
+                chars.next();
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => string.push('\n'),
+                            Some('t') => string.push('\t'),
+                            Some('r') => string.push('\r'),
+                            Some(other) => string.push(other),
+                            None => return Err(error("Unterminated string")),
+                        },
+                        Some(other) => string.push(other),
+                        None => return Err(error("Unterminated string")),
+                    }
+                }
+                stack.last_mut().unwrap().1.push(Term::Str(string));
+            }
+            '(' => return Err(error("Expected a constructor name before '('")),
+            ')' => {
+                chars.next();
+                if stack.len() == 1 {
+                    return Err(error("Unexpected ')'"));
+                }
+                let (name, args) = stack.pop().unwrap();
+                let term = Term::Apply(name.unwrap_or_default(), args);
+                stack.last_mut().unwrap().1.push(term);
+            }
+            _ => {
+                let mut symbol = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || matches!(ch, '(' | ')' | '"' | ',') {
+                        break;
+                    }
+                    symbol.push(ch);
+                    chars.next();
+                }
+                if chars.peek() == Some(&'(') {
+                    chars.next();
+                    stack.push((Some(symbol), Vec::new()));
+                } else {
+                    stack.last_mut().unwrap().1.push(Term::Symbol(symbol));
+                }
+            }
+        }
+    }
+    if stack.len() != 1 {
+        return Err(error("Unclosed '(': expected ')'"));
+    }
+    Ok(stack.pop().unwrap().1)
+}
+
+/// 可以不加引号写出的符号 / Symbol that can be written without quotes
+fn is_bare_symbol(text: &str) -> bool {
+    !text.is_empty()
+        && text != "=>"
+        && !text
+            .chars()
+            .any(|ch| ch.is_whitespace() || matches!(ch, '(' | ')' | '"' | ',' | ':' | ';' | '\\'))
+}
+
+/// 名称或引号字符串 / Name, or a quoted string
+fn name_term(name: &str) -> String {
+    if is_bare_symbol(name) {
+        name.to_string()
+    } else {
+        quote_string(name)
+    }
+}
+
+fn pattern_term(element: &PatternElement) -> String {
+    match element {
+        PatternElement::Keyword(word) => quote_string(word),
+        PatternElement::NaturalLang(word) => format!("自然语言({})", name_term(word)),
+        PatternElement::Identifier(name) => format!("标识符({})", name_term(name)),
+        PatternElement::Wildcard(name) => format!("通配({})", name_term(name)),
+        PatternElement::Literal(element) => format!("字面量({})", element_term(element)),
+        PatternElement::Optional(inner) => format!("可选({})", pattern_term(inner)),
+        PatternElement::Repeat(inner) => format!("重复({})", pattern_term(inner)),
+        PatternElement::Group(elements) => format!(
+            "分组({})",
+            elements
+                .iter()
+                .map(pattern_term)
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+    }
+}
+
+fn element_term(element: &GrammarElement) -> String {
+    match element {
+        GrammarElement::Atom(atom) if is_bare_symbol(atom) => atom.clone(),
+        GrammarElement::Atom(atom) => format!("原子({})", quote_string(atom)),
+        GrammarElement::NaturalLang(fragment) => format!("自然语言({})", quote_string(fragment)),
+        GrammarElement::List(items) => format!(
+            "列表({})",
+            items.iter().map(element_term).collect::<Vec<_>>().join(" ")
+        ),
+        GrammarElement::Expr(expr) => format!("表达式({})", quote_string(&unparse_expr(expr))),
+    }
+}
+
+fn condition_term(condition: &Condition) -> String {
+    match condition {
+        Condition::TypeCheck(a, b) => {
+            format!("类型检查({} {})", quote_string(a), quote_string(b))
+        }
+        Condition::ValueCheck(a, b) => format!("值检查({} {})", quote_string(a), quote_string(b)),
+        Condition::ContextCheck(context) => format!("上下文检查({})", quote_string(context)),
+    }
+}
+
+fn method_term(method: &DefinitionMethod) -> String {
+    match method {
+        DefinitionMethod::SelfDescribing => "自描述".to_string(),
+        DefinitionMethod::Evolutionary => "进化".to_string(),
+        DefinitionMethod::External(language) => format!("外部({})", quote_string(language)),
+        DefinitionMethod::Hybrid(methods) => format!(
+            "混合({})",
+            methods
+                .iter()
+                .map(method_term)
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+    }
+}
+
+fn stability_word(stability: Stability) -> &'static str {
+    match stability {
+        Stability::Experimental => "实验",
+        Stability::Stabilizing => "稳定中",
+        Stability::Stable => "稳定",
+        Stability::Deprecated => "弃用",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(elements: Vec<PatternElement>, target: GrammarElement) -> GrammarRule {
+        GrammarRule::new(
+            "测试规则".to_string(),
+            Pattern {
+                elements,
+                variadic: false,
+            },
+            Production {
+                target,
+                transform: vec![],
+                conditions: vec![],
+            },
+            RuleMetadata {
+                version: "1.0".to_string(),
+                defined_by: DefinitionMethod::SelfDescribing,
+                stability: Stability::Experimental,
+                description: String::new(),
+                examples: vec![],
+                natural_lang_synonyms: vec![],
+            },
+        )
+    }
+
+    fn atom(text: &str) -> GrammarElement {
+        GrammarElement::Atom(text.to_string())
+    }
+
+    /// 生成自描述代码再读回，除ID和时间戳外应与原规则相同
+    /// Generate the self-describing code and read it back; apart from ID and timestamps it must equal the original
+    fn assert_round_trip(original: &GrammarRule) {
+        let code = describe_rule(original);
+        let mut parsed = parse_self_description(&code)
+            .unwrap_or_else(|error| panic!("failed to parse:\n{}\n{:?}", code, error));
+        parsed.id = original.id;
+        parsed.created_at = original.created_at;
+        parsed.updated_at = original.updated_at;
+        assert_eq!(
+            serde_json::to_value(&parsed).unwrap(),
+            serde_json::to_value(original).unwrap(),
+            "round trip changed the rule:\n{}",
+            code
+        );
+    }
+
+    #[test]
+    fn test_round_trip_keyword() {
+        assert_round_trip(&rule(
+            vec![
+                PatternElement::Keyword("让".to_string()),
+                PatternElement::Keyword("=>".to_string()),
+                PatternElement::Keyword("带 \"引号\" 的词".to_string()),
+            ],
+            atom("Let"),
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_natural_lang() {
+        assert_round_trip(&rule(
+            vec![
+                PatternElement::NaturalLang("变量".to_string()),
+                PatternElement::NaturalLang("two words".to_string()),
+            ],
+            atom("Var"),
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_identifier() {
+        assert_round_trip(&rule(
+            vec![
+                PatternElement::Identifier("name".to_string()),
+                PatternElement::Identifier("a:b".to_string()),
+            ],
+            atom("Name"),
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_wildcard() {
+        assert_round_trip(&rule(
+            vec![PatternElement::Wildcard("value".to_string())],
+            atom("Any"),
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_literal() {
+        assert_round_trip(&rule(
+            vec![
+                PatternElement::Literal(atom("nil")),
+                PatternElement::Literal(GrammarElement::List(vec![atom("a"), atom("b")])),
+            ],
+            atom("Lit"),
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_optional() {
+        assert_round_trip(&rule(
+            vec![PatternElement::Optional(Box::new(PatternElement::Keyword(
+                "否则".to_string(),
+            )))],
+            atom("Opt"),
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_repeat() {
+        assert_round_trip(&rule(
+            vec![PatternElement::Repeat(Box::new(
+                PatternElement::Identifier("item".to_string()),
+            ))],
+            atom("Rep"),
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_group() {
+        assert_round_trip(&rule(
+            vec![PatternElement::Group(vec![
+                PatternElement::Keyword(",".to_string()),
+                PatternElement::Optional(Box::new(PatternElement::Wildcard("x".to_string()))),
+                PatternElement::Group(vec![]),
+            ])],
+            atom("Group"),
+        ));
+    }
+
+    #[test]
+    fn test_round_trip_productions() {
+        let expression = AdaptiveParser::new(false)
+            .parse("(+ x 1)")
+            .unwrap()
+            .remove(0);
+        for target in [
+            atom("VariableDeclaration"),
+            atom("带 空格"),
+            atom("=>"),
+            GrammarElement::NaturalLang("打印 结果".to_string()),
+            GrammarElement::List(vec![atom("Call"), GrammarElement::List(vec![])]),
+            expression,
+        ] {
+            assert_round_trip(&rule(
+                vec![PatternElement::Wildcard("value".to_string())],
+                target,
+            ));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_transform_and_conditions() {
+        let mut original = rule(
+            vec![PatternElement::Identifier("name".to_string())],
+            atom("Assign"),
+        );
+        original.pattern.variadic = true;
+        original.production.transform = vec![TransformRule {
+            from: PatternElement::Identifier("name".to_string()),
+            to: GrammarElement::List(vec![atom("Var"), atom("name")]),
+        }];
+        original.production.conditions = vec![
+            Condition::TypeCheck("name".to_string(), "String".to_string()),
+            Condition::ValueCheck("value".to_string(), "> 0".to_string()),
+            Condition::ContextCheck("top-level".to_string()),
+        ];
+        assert_round_trip(&original);
+    }
+
+    #[test]
+    fn test_round_trip_metadata() {
+        let methods = [
+            DefinitionMethod::SelfDescribing,
+            DefinitionMethod::Evolutionary,
+            DefinitionMethod::External("Rust".to_string()),
+            DefinitionMethod::Hybrid(vec![
+                DefinitionMethod::External("Python".to_string()),
+                DefinitionMethod::Evolutionary,
+            ]),
+        ];
+        let stabilities = [
+            Stability::Experimental,
+            Stability::Stabilizing,
+            Stability::Stable,
+            Stability::Deprecated,
+        ];
+        for (method, stability) in methods.into_iter().zip(stabilities) {
+            let mut original = rule(vec![PatternElement::Keyword("让".to_string())], atom("Let"));
+            original.name = "变量 声明".to_string();
+            original.meta = RuleMetadata {
+                version: "2.1".to_string(),
+                defined_by: method,
+                stability,
+                description: "多行\n描述".to_string(),
+                examples: vec!["让 x = 1".to_string(), "让 y = \"s\"".to_string()],
+                natural_lang_synonyms: vec!["声明变量".to_string()],
+            };
+            assert_round_trip(&original);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_syntax_definition_rule() {
+        assert_round_trip(&syntax_definition_rule().rule);
+    }
+
+    #[test]
+    fn test_parse_self_descriptions_reads_every_rule() {
+        let first = rule(vec![PatternElement::Keyword("a".to_string())], atom("A"));
+        let second = rule(vec![PatternElement::Wildcard("b".to_string())], atom("B"));
+        let code = format!(
+            "; 注释 / comment\n{}\n{}",
+            describe_rule(&first),
+            describe_rule(&second)
+        );
+        let targets: Vec<String> = parse_self_descriptions(&code)
+            .unwrap()
+            .into_iter()
+            .map(|rule| element_term(&rule.production.target))
+            .collect();
+        assert_eq!(targets, ["A", "B"]);
+    }
 }
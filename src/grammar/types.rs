@@ -0,0 +1,748 @@
+// 类型系统 / Type system
+// 可选的类型标注与渐进式静态类型检查：未标注的部分视为 Any，不会报错
+// Optional type annotations and gradual static type checking: unannotated parts are Any and never reported
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grammar::core::{BinOp, Expr, GrammarElement, Literal, Pattern};
+use crate::parser::adaptive::{AdaptiveParser, Location, ParseError};
+
+/// 静态类型 / Static type
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Type {
+    /// 动态类型，与任何类型兼容 / Dynamic type, compatible with every type
+    Any,
+    /// 整数 / Integer
+    Int,
+    /// 浮点数 / Float
+    Float,
+    /// 整数或浮点数 / Integer or float
+    Number,
+    /// 字符串 / String
+    String,
+    /// 布尔值 / Boolean
+    Bool,
+    /// 空值 / Null
+    Null,
+    /// 列表（元素类型）/ List (element type)
+    List(Box<Type>),
+    /// 字典（值类型）/ Dictionary (value type)
+    Dict(Box<Type>),
+    /// 函数（参数类型，返回类型）/ Function (parameter types, return type)
+    Fn(Vec<Type>, Box<Type>),
+    /// 命名类型，如 `deftype` 定义的类型 / Named type, such as one defined by `deftype`
+    Named(String),
+    /// 推断中的类型变量 / Type variable during inference
+    Var(usize),
+}
+
+impl Type {
+    /// 从类型标注解析：`Int`、`(List Int)`、`(Dict String)`、`(Fn (Int Int) Int)`
+    /// Parse from an annotation: `Int`, `(List Int)`, `(Dict String)`, `(Fn (Int Int) Int)`
+    pub fn from_annotation(element: &GrammarElement) -> Result<Type, String> {
+        match element {
+            GrammarElement::Atom(name) => Ok(match name.as_str() {
+                "Any" => Type::Any,
+                "Int" => Type::Int,
+                "Float" => Type::Float,
+                "Number" => Type::Number,
+                "String" => Type::String,
+                "Bool" => Type::Bool,
+                "Null" => Type::Null,
+                "List" => Type::List(Box::new(Type::Any)),
+                "Dict" => Type::Dict(Box::new(Type::Any)),
+                _ => Type::Named(name.clone()),
+            }),
+            GrammarElement::List(items) => match items.as_slice() {
+                [GrammarElement::Atom(head), inner] if head == "List" => {
+                    Ok(Type::List(Box::new(Type::from_annotation(inner)?)))
+                }
+                [GrammarElement::Atom(head), inner] if head == "Dict" => {
+                    Ok(Type::Dict(Box::new(Type::from_annotation(inner)?)))
+                }
+                [GrammarElement::Atom(head), GrammarElement::List(params), ret] if head == "Fn" => {
+                    Ok(Type::Fn(
+                        params
+                            .iter()
+                            .map(Type::from_annotation)
+                            .collect::<Result<_, _>>()?,
+                        Box::new(Type::from_annotation(ret)?),
+                    ))
+                }
+                _ => Err(format!(
+                    "Invalid type annotation: {}",
+                    crate::grammar::unparse::unparse_element(element)
+                )),
+            },
+            _ => Err("Invalid type annotation".to_string()),
+        }
+    }
+
+    /// 该类型的值能否用在期望类型处 / Whether a value of this type can be used where `expected` is required
+    pub fn is_assignable_to(&self, expected: &Type) -> bool {
+        match (self, expected) {
+            (Type::Any, _) | (_, Type::Any) => true,
+            (Type::Int | Type::Float | Type::Number, Type::Number) => true,
+            (Type::List(a), Type::List(b)) | (Type::Dict(a), Type::Dict(b)) => {
+                a.is_assignable_to(b)
+            }
+            (Type::Fn(params, ret), Type::Fn(expected_params, expected_ret)) => {
+                params.len() == expected_params.len()
+                    && expected_params
+                        .iter()
+                        .zip(params)
+                        .all(|(expected, actual)| expected.is_assignable_to(actual))
+                    && ret.is_assignable_to(expected_ret)
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// 两个分支类型的合并 / Join of two branch types
+    pub fn join(&self, other: &Type) -> Type {
+        match (self, other) {
+            (a, b) if a == b => a.clone(),
+            (a, b) if a.is_numeric() && b.is_numeric() => Type::Number,
+            (Type::List(a), Type::List(b)) => Type::List(Box::new(a.join(b))),
+            (Type::Dict(a), Type::Dict(b)) => Type::Dict(Box::new(a.join(b))),
+            _ => Type::Any,
+        }
+    }
+
+    /// 是否为数值类型 / Whether it is a numeric type
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Float | Type::Number)
+    }
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Any => write!(f, "Any"),
+            Type::Int => write!(f, "Int"),
+            Type::Float => write!(f, "Float"),
+            Type::Number => write!(f, "Number"),
+            Type::String => write!(f, "String"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Null => write!(f, "Null"),
+            Type::List(inner) => write!(f, "(List {})", inner),
+            Type::Dict(inner) => write!(f, "(Dict {})", inner),
+            Type::Fn(params, ret) => {
+                let params: Vec<String> = params.iter().map(Type::to_string).collect();
+                write!(f, "(Fn ({}) {})", params.join(" "), ret)
+            }
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Var(id) => write!(f, "t{}", id),
+        }
+    }
+}
+
+/// 函数签名 / Function signature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionSignature {
+    /// 参数名和类型 / Parameter names and types
+    pub params: Vec<(String, Type)>,
+    /// 返回类型 / Return type
+    pub return_type: Type,
+    /// 是否带有类型标注 / Whether it carries type annotations
+    pub annotated: bool,
+}
+
+impl FunctionSignature {
+    /// 从 `(def name (params...) body [(: (types...) ret)])` 提取签名
+    /// Extract the signature from `(def name (params...) body [(: (types...) ret)])`
+    pub fn from_definition(items: &[GrammarElement]) -> Result<FunctionSignature, String> {
+        let names: Vec<String> = match items.get(2) {
+            Some(GrammarElement::List(params)) => params.iter().filter_map(element_name).collect(),
+            _ => Vec::new(),
+        };
+        let Some(GrammarElement::List(signature)) = items.get(4) else {
+            return Ok(FunctionSignature {
+                params: names.into_iter().map(|name| (name, Type::Any)).collect(),
+                return_type: Type::Any,
+                annotated: false,
+            });
+        };
+        let (Some(GrammarElement::List(param_types)), Some(return_type)) =
+            (signature.get(1), signature.get(2))
+        else {
+            return Err("Malformed function signature".to_string());
+        };
+        Ok(FunctionSignature {
+            params: names
+                .into_iter()
+                .zip(param_types)
+                .map(|(name, annotation)| Ok((name, Type::from_annotation(annotation)?)))
+                .collect::<Result<_, String>>()?,
+            return_type: Type::from_annotation(return_type)?,
+            annotated: true,
+        })
+    }
+
+    /// 作为函数类型 / As a function type
+    pub fn as_type(&self) -> Type {
+        Type::Fn(
+            self.params.iter().map(|(_, t)| t.clone()).collect(),
+            Box::new(self.return_type.clone()),
+        )
+    }
+}
+
+/// 类型检查错误 / Type check error
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeCheckError {
+    /// 错误信息 / Error message
+    pub message: String,
+    /// 所在顶层表达式的位置 / Location of the enclosing top-level form
+    pub location: Option<Location>,
+    /// 所在函数 / Enclosing function
+    pub function: Option<String>,
+}
+
+impl std::fmt::Display for TypeCheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location.format())?;
+        }
+        if let Some(function) = &self.function {
+            write!(f, "in function '{}': ", function)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// 渐进式类型检查器，在解释执行之前运行
+/// Gradual type checker, run before interpretation
+#[derive(Debug, Default)]
+pub struct TypeChecker {
+    /// 已知函数签名 / Known function signatures
+    signatures: HashMap<String, FunctionSignature>,
+    /// 变体构造器：标签 -> (字段数, 类型名) / Variant constructors: tag -> (field count, type name)
+    constructors: HashMap<String, (usize, String)>,
+    /// 当前作用域中的变量类型 / Variable types in the current scope
+    scope: Vec<(String, Type)>,
+    /// 收集的错误 / Collected errors
+    errors: Vec<TypeCheckError>,
+    /// 当前顶层表达式的位置 / Location of the current top-level form
+    location: Option<Location>,
+    /// 当前检查的函数 / Function currently being checked
+    function: Option<String>,
+}
+
+impl TypeChecker {
+    /// 创建类型检查器 / Create type checker
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 解析并检查源代码，错误带有位置 / Parse and check source code, with located errors
+    pub fn check_source(source: &str) -> Result<Vec<TypeCheckError>, ParseError> {
+        let (elements, locations) = AdaptiveParser::new(true).parse_with_locations(source)?;
+        Ok(TypeChecker::new().check_with_locations(&elements, &locations))
+    }
+
+    /// 检查AST / Check AST
+    pub fn check(&mut self, elements: &[GrammarElement]) -> Vec<TypeCheckError> {
+        self.check_with_locations(elements, &[])
+    }
+
+    /// 检查AST，`locations` 为各顶层元素的起始位置
+    /// Check AST, `locations` being the start location of each top-level element
+    pub fn check_with_locations(
+        &mut self,
+        elements: &[GrammarElement],
+        locations: &[Location],
+    ) -> Vec<TypeCheckError> {
+        // 先收集所有签名，使函数可以先使用后定义 / Collect signatures first so functions can be used before their definition
+        for (index, element) in elements.iter().enumerate() {
+            self.location = locations.get(index).copied();
+            self.collect_declarations(element);
+        }
+        for (index, element) in elements.iter().enumerate() {
+            self.location = locations.get(index).copied();
+            self.infer_element(element);
+        }
+        self.location = None;
+        std::mem::take(&mut self.errors)
+    }
+
+    /// 收集函数签名和变体构造器 / Collect function signatures and variant constructors
+    fn collect_declarations(&mut self, element: &GrammarElement) {
+        match element {
+            GrammarElement::List(items) if is_definition(items) => {
+                let Some(name) = items.get(1).and_then(element_name) else {
+                    return;
+                };
+                match FunctionSignature::from_definition(items) {
+                    Ok(signature) => {
+                        self.signatures.insert(name, signature);
+                    }
+                    Err(message) => {
+                        self.function = Some(name);
+                        self.report(message);
+                        self.function = None;
+                    }
+                }
+            }
+            GrammarElement::Expr(expr) => {
+                if let Expr::Call(name, args) = expr.as_ref() {
+                    if name == "deftype" {
+                        if let Some((Expr::Var(type_name), variants)) = args.split_first() {
+                            for variant in variants {
+                                let (tag, arity) = match variant {
+                                    Expr::Var(tag) => (tag, 0),
+                                    Expr::Call(tag, fields) => (tag, fields.len()),
+                                    _ => continue,
+                                };
+                                self.constructors
+                                    .insert(tag.to_string(), (arity, type_name.to_string()));
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn report(&mut self, message: String) {
+        self.errors.push(TypeCheckError {
+            message,
+            location: self.location,
+            function: self.function.clone(),
+        });
+    }
+
+    fn lookup(&self, name: &str) -> Option<Type> {
+        if let Some((_, t)) = self.scope.iter().rev().find(|(n, _)| n == name) {
+            return Some(t.clone());
+        }
+        if let Some((0, type_name)) = self.constructors.get(name) {
+            return Some(Type::Named(type_name.clone()));
+        }
+        self.signatures.get(name).map(FunctionSignature::as_type)
+    }
+
+    /// 推断语法元素的类型 / Infer the type of a grammar element
+    fn infer_element(&mut self, element: &GrammarElement) -> Type {
+        match element {
+            GrammarElement::Expr(expr) => self.infer_expr(expr),
+            GrammarElement::Atom(name) => self.lookup(name).unwrap_or(Type::Any),
+            GrammarElement::NaturalLang(_) => Type::Any,
+            GrammarElement::List(items) if is_definition(items) => {
+                self.check_definition(items);
+                Type::Null
+            }
+            GrammarElement::List(items) => {
+                let head = items.first().and_then(element_name);
+                match (head.as_deref(), items.as_slice()) {
+                    (Some("let"), [_, name, value, body]) => {
+                        let value_type = self.infer_element(value);
+                        let scope_len = self.scope.len();
+                        match (element_name(name), destructuring_pattern(name)) {
+                            (Some(name), _) => self.scope.push((name, value_type.clone())),
+                            (None, Some(pattern)) => self.bind_pattern(&pattern),
+                            (None, None) => return Type::Any,
+                        }
+                        // 没有body的let绑定到外层作用域 / A let without body binds in the enclosing scope
+                        if matches!(body, GrammarElement::Expr(e) if **e == Expr::Literal(Literal::Null))
+                        {
+                            return value_type;
+                        }
+                        let body_type = self.infer_element(body);
+                        self.scope.truncate(scope_len);
+                        body_type
+                    }
+                    // 常量和无body的let一样绑定到外层作用域 / A constant binds in the enclosing scope like a body-less let
+                    (Some("defconst"), [_, name, value]) => {
+                        let value_type = self.infer_element(value);
+                        if let Some(name) = element_name(name) {
+                            self.scope.push((name, value_type));
+                        }
+                        Type::Null
+                    }
+                    _ => {
+                        for item in items.iter().skip(usize::from(head.is_some())) {
+                            self.infer_element(item);
+                        }
+                        Type::Any
+                    }
+                }
+            }
+        }
+    }
+
+    /// 检查函数定义的函数体是否符合返回类型 / Check that a function body matches its return type
+    fn check_definition(&mut self, items: &[GrammarElement]) {
+        let Some(name) = items.get(1).and_then(element_name) else {
+            return;
+        };
+        let signature = match self.signatures.get(&name) {
+            Some(signature) => signature.clone(),
+            None => match FunctionSignature::from_definition(items) {
+                Ok(signature) => {
+                    self.signatures.insert(name.clone(), signature.clone());
+                    signature
+                }
+                Err(_) => return,
+            },
+        };
+        let Some(body) = items.get(3) else {
+            return;
+        };
+
+        let previous_function = self.function.replace(name.clone());
+        let scope_len = self.scope.len();
+        self.scope.extend(signature.params.iter().cloned());
+        let body_type = self.infer_element(body);
+        self.scope.truncate(scope_len);
+
+        if !body_type.is_assignable_to(&signature.return_type) {
+            self.report(format!(
+                "declared return type {} but body has type {}",
+                signature.return_type, body_type
+            ));
+        }
+        self.function = previous_function;
+    }
+
+    /// 推断表达式类型 / Infer expression type
+    fn infer_expr(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Literal(literal) => self.infer_literal(literal),
+            Expr::Var(name) => self.lookup(name).unwrap_or(Type::Any),
+            Expr::Call(name, args) => {
+                let arg_types: Vec<Type> = args.iter().map(|arg| self.infer_expr(arg)).collect();
+                self.infer_call(name, &arg_types)
+            }
+            Expr::Binary(op, left, right) => {
+                let left = self.infer_expr(left);
+                let right = self.infer_expr(right);
+                self.infer_operator(binop_symbol(op), &[left, right])
+            }
+            Expr::If(condition, then_expr, else_expr) => {
+                self.infer_expr(condition);
+                let then_type = self.infer_expr(then_expr);
+                let else_type = self.infer_expr(else_expr);
+                then_type.join(&else_type)
+            }
+            Expr::Match(value, cases) => {
+                self.infer_expr(value);
+                let mut result: Option<Type> = None;
+                for (pattern, body) in cases {
+                    let scope_len = self.scope.len();
+                    self.bind_pattern(pattern);
+                    let body_type = self.infer_expr(body);
+                    self.scope.truncate(scope_len);
+                    result = Some(match result {
+                        Some(t) => t.join(&body_type),
+                        None => body_type,
+                    });
+                }
+                result.unwrap_or(Type::Null)
+            }
+            Expr::For {
+                var,
+                iterable,
+                body,
+            } => {
+                let element_type = match self.infer_expr(iterable) {
+                    Type::List(inner) => *inner,
+                    _ => Type::Any,
+                };
+                self.scope.push((var.to_string(), element_type));
+                self.infer_expr(body);
+                self.scope.pop();
+                Type::Any
+            }
+            Expr::While { condition, body } => {
+                self.infer_expr(condition);
+                self.infer_expr(body);
+                Type::Any
+            }
+            Expr::Try {
+                try_body,
+                catch_var,
+                catch_body,
+            } => {
+                let try_type = self.infer_expr(try_body);
+                let scope_len = self.scope.len();
+                if let Some(var) = catch_var {
+                    self.scope
+                        .push((var.to_string(), Type::Named("Error".to_string())));
+                }
+                let catch_type = self.infer_expr(catch_body);
+                self.scope.truncate(scope_len);
+                try_type.join(&catch_type)
+            }
+            Expr::Lambda { params, body } => {
+                let scope_len = self.scope.len();
+                self.scope
+                    .extend(params.iter().map(|param| (param.clone(), Type::Any)));
+                let body_type = self.infer_expr(body);
+                self.scope.truncate(scope_len);
+                Type::Fn(vec![Type::Any; params.len()], Box::new(body_type))
+            }
+            Expr::Begin(exprs) => exprs
+                .iter()
+                .map(|expr| self.infer_expr(expr))
+                .last()
+                .unwrap_or(Type::Null),
+            Expr::Assign(var, value) => {
+                let value_type = self.infer_expr(value);
+                if let Some(declared) = self.lookup(var) {
+                    if !value_type.is_assignable_to(&declared) {
+                        self.report(format!(
+                            "cannot assign {} to '{}' of type {}",
+                            value_type, var, declared
+                        ));
+                    }
+                }
+                value_type
+            }
+        }
+    }
+
+    fn infer_literal(&mut self, literal: &Literal) -> Type {
+        match literal {
+            Literal::Int(_) | Literal::BigInt(_) => Type::Int,
+            Literal::Float(_) => Type::Float,
+            Literal::String(_) => Type::String,
+            Literal::Bool(_) => Type::Bool,
+            Literal::Null => Type::Null,
+            Literal::List(items) => {
+                let types: Vec<Type> = items.iter().map(|item| self.infer_expr(item)).collect();
+                Type::List(Box::new(join_all(types)))
+            }
+            Literal::Dict(pairs) => {
+                let types: Vec<Type> = pairs
+                    .iter()
+                    .map(|(_, value)| self.infer_expr(value))
+                    .collect();
+                Type::Dict(Box::new(join_all(types)))
+            }
+        }
+    }
+
+    /// 推断调用结果类型并检查参数 / Infer call result type and check arguments
+    fn infer_call(&mut self, name: &str, arg_types: &[Type]) -> Type {
+        if let Some(op) = name.strip_prefix("op:") {
+            return self.infer_operator(op, arg_types);
+        }
+        if let Some(signature) = self.signatures.get(name).cloned() {
+            if signature.annotated {
+                self.check_arguments(name, &signature, arg_types);
+            }
+            return signature.return_type;
+        }
+        if let Some((_, type_name)) = self.constructors.get(name) {
+            return Type::Named(type_name.clone());
+        }
+        if let Some(Type::Fn(params, ret)) = self.lookup(name) {
+            if params.len() == arg_types.len() {
+                return *ret;
+            }
+        }
+        builtin_return_type(name).unwrap_or(Type::Any)
+    }
+
+    fn check_arguments(&mut self, name: &str, signature: &FunctionSignature, arg_types: &[Type]) {
+        if signature.params.len() != arg_types.len() {
+            self.report(format!(
+                "'{}' expects {} argument(s), got {}",
+                name,
+                signature.params.len(),
+                arg_types.len()
+            ));
+            return;
+        }
+        for ((param, expected), actual) in signature.params.iter().zip(arg_types) {
+            if !actual.is_assignable_to(expected) {
+                self.report(format!(
+                    "argument '{}' of '{}' expects {}, got {}",
+                    param, name, expected, actual
+                ));
+            }
+        }
+    }
+
+    /// 推断运算符结果类型，具体类型不兼容时报告错误
+    /// Infer operator result type, reporting incompatible concrete types
+    fn infer_operator(&mut self, op: &str, arg_types: &[Type]) -> Type {
+        match op {
+            "+" | "-" | "*" | "/" | "%" => {
+                let mut operands = arg_types.iter();
+                let Some(first) = operands.next() else {
+                    return Type::Any;
+                };
+                let mut result = first.clone();
+                for operand in operands {
+                    result = match arithmetic_result(op, &result, operand) {
+                        Some(t) => t,
+                        None => {
+                            self.report(format!(
+                                "cannot apply '{}' to {} and {}",
+                                op, result, operand
+                            ));
+                            Type::Any
+                        }
+                    };
+                }
+                result
+            }
+            "<" | ">" | "<=" | ">=" | "=" | "!=" => Type::Bool,
+            _ => Type::Any,
+        }
+    }
+
+    /// 将模式中的变量加入作用域 / Bring pattern variables into scope
+    fn bind_pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Var(name) if !self.constructors.contains_key(name.as_str()) => {
+                self.scope.push((name.to_string(), Type::Any));
+            }
+            Pattern::List(items) => items.iter().for_each(|item| self.bind_pattern(item)),
+            Pattern::Dict(pairs) => pairs.iter().for_each(|(_, item)| self.bind_pattern(item)),
+            Pattern::Rest(Some(name)) => self
+                .scope
+                .push((name.to_string(), Type::List(Box::new(Type::Any)))),
+            Pattern::Or(alternatives) => {
+                alternatives.iter().for_each(|item| self.bind_pattern(item))
+            }
+            Pattern::Guard(inner, guard) => {
+                self.bind_pattern(inner);
+                self.infer_expr(guard);
+            }
+            // 前缀和后缀之外的部分是字符串 / What remains besides the prefix or suffix is a string
+            Pattern::Prefix(_, rest) | Pattern::Suffix(_, rest) => match rest.as_ref() {
+                Pattern::Var(name) if !self.constructors.contains_key(name.as_str()) => {
+                    self.scope.push((name.to_string(), Type::String));
+                }
+                rest => self.bind_pattern(rest),
+            },
+            _ => {}
+        }
+    }
+}
+
+/// 算术运算的结果类型，不兼容时返回None（与解释器的运算规则一致）
+/// Result type of an arithmetic operation, None if incompatible (matching the interpreter's rules)
+pub(crate) fn arithmetic_result(op: &str, left: &Type, right: &Type) -> Option<Type> {
+    match (left, right) {
+        (Type::Any, _) | (_, Type::Any) => Some(Type::Any),
+        (Type::Int, Type::Int) => Some(Type::Int),
+        (Type::Float, Type::Float) => Some(Type::Float),
+        // 只有加法允许整数和浮点数混合 / Only addition mixes integers and floats
+        (Type::Int, Type::Float) | (Type::Float, Type::Int) if op == "+" => Some(Type::Float),
+        (Type::Number, r) if r.is_numeric() => Some(Type::Number),
+        (l, Type::Number) if l.is_numeric() => Some(Type::Number),
+        (Type::String, Type::String) if op == "+" => Some(Type::String),
+        (Type::List(a), Type::List(b)) if op == "+" => Some(Type::List(Box::new(a.join(b)))),
+        // 命名类型的加法经 Add 协议分派，结果由实现决定 / Addition on named types dispatches through the Add protocol
+        (Type::Named(_), _) if op == "+" => Some(Type::Any),
+        _ => None,
+    }
+}
+
+/// 内置函数的返回类型 / Return type of builtin functions
+pub(crate) fn builtin_return_type(name: &str) -> Option<Type> {
+    Some(match name {
+        "print" => Type::Null,
+        "length" | "list-length" | "string-length" | "strlen" | "dict-size" | "dict-length"
+        | "to-int" | "date-diff" | "parse-int" | "call-depth" => Type::Int,
+        "to-float" | "parse-float" => Type::Float,
+        "divmod" => Type::List(Box::new(Type::Number)),
+        "parse-number" => Type::Number,
+        "to-string" | "string-concat" | "string-join" | "join" | "string-trim" | "trim"
+        | "string-replace" | "replace" | "regex-replace" | "string-substring" | "substring"
+        | "string-upper" | "upper" | "string-lower" | "lower" | "json-stringify" | "file-read"
+        | "variant-tag" | "variant-type" | "error-kind" | "error-message" | "format-date"
+        | "format-number" | "to-fixed" => Type::String,
+        "is-string" | "is-int" | "is-float" | "is-bool" | "is-list" | "is-dict" | "is-null"
+        | "is-error" | "is-generator" | "is-done" | "dict-has" | "file-exists" | "assert"
+        | "assert-eq" | "is-date" | "is-set" | "contains" => Type::Bool,
+        "string-split" | "split" | "regex-split" | "dict-keys" | "dir-list" | "stacktrace" => {
+            Type::List(Box::new(Type::String))
+        }
+        "error" => Type::Named("Error".to_string()),
+        "now" | "parse-date" | "date-add" => Type::Named("DateTime".to_string()),
+        "set" | "to-set" | "union" | "intersection" | "difference" | "set-add" | "set-remove" => {
+            Type::Named("Set".to_string())
+        }
+        "to-list" => Type::List(Box::new(Type::Any)),
+        "run-tests" | "bench" | "http-get" | "http-post" => Type::Dict(Box::new(Type::Any)),
+        _ => return None,
+    })
+}
+
+fn join_all(types: Vec<Type>) -> Type {
+    let mut types = types.into_iter();
+    match types.next() {
+        Some(first) => types.fold(first, |acc, t| acc.join(&t)),
+        None => Type::Any,
+    }
+}
+
+/// 是否为函数定义 / Whether it is a function definition
+pub(crate) fn is_definition(items: &[GrammarElement]) -> bool {
+    matches!(
+        items.first(),
+        Some(GrammarElement::Atom(head)) if matches!(head.as_str(), "def" | "function" | "defn-")
+    )
+}
+
+/// 函数定义的文档字符串，位于函数体（和类型签名）之后
+/// Docstring of a function definition, stored after the body (and type signature)
+pub(crate) fn definition_docstring(items: &[GrammarElement]) -> Option<&str> {
+    items.iter().skip(4).find_map(string_literal)
+}
+
+/// 字符串字面量的内容 / Contents of a string literal
+pub(crate) fn string_literal(element: &GrammarElement) -> Option<&str> {
+    match element {
+        GrammarElement::Expr(expr) => match expr.as_ref() {
+            Expr::Literal(Literal::String(text)) => Some(text),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 解构let的目标模式（列表或字典）/ Target pattern of a destructuring let (a list or dictionary)
+pub(crate) fn destructuring_pattern(element: &GrammarElement) -> Option<Pattern> {
+    match element {
+        GrammarElement::Expr(expr) => Pattern::from_binding(expr)
+            .filter(|pattern| matches!(pattern, Pattern::List(_) | Pattern::Dict(_))),
+        _ => None,
+    }
+}
+
+/// 原子或变量的名称 / Name of an atom or variable
+pub(crate) fn element_name(element: &GrammarElement) -> Option<String> {
+    match element {
+        GrammarElement::Atom(name) => Some(name.clone()),
+        GrammarElement::Expr(expr) => match expr.as_ref() {
+            Expr::Var(name) => Some(name.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn binop_symbol(op: &BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Mod => "%",
+        BinOp::Eq => "=",
+        BinOp::Ne => "!=",
+        BinOp::Lt => "<",
+        BinOp::Gt => ">",
+        BinOp::Le => "<=",
+        BinOp::Ge => ">=",
+    }
+}
@@ -0,0 +1,363 @@
+// 反解析器 / Unparser
+// 将AST转换回可被解析器重新读取的源代码
+// Turns an AST back into source code that the parser can read again
+
+use crate::grammar::core::{BinOp, Expr, GrammarElement, Literal, Pattern};
+use crate::grammar::types::{destructuring_pattern, string_literal};
+
+/// 将AST转换为源代码，每个顶层元素占一行
+/// Convert AST to source code, one top-level element per line
+///
+/// 输出为紧凑的单行形式，需要缩进时可交给 `parser::format_source`
+/// Output is compact; pass it to `parser::format_source` for indentation
+pub fn unparse(elements: &[GrammarElement]) -> String {
+    elements
+        .iter()
+        .map(unparse_element)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// 将单个语法元素转换为源代码 / Convert a single grammar element to source code
+pub fn unparse_element(element: &GrammarElement) -> String {
+    match element {
+        GrammarElement::Atom(atom) => atom.strip_prefix("op:").unwrap_or(atom).to_string(),
+        GrammarElement::List(items) => match items.as_slice() {
+            // 带类型签名或文档字符串的函数定义 / Function definition with a type signature or docstring
+            [GrammarElement::Atom(keyword), name, GrammarElement::List(params), body, trailing @ ..]
+                if matches!(keyword.as_str(), "def" | "function" | "defn-")
+                    && !trailing.is_empty()
+                    && trailing
+                        .iter()
+                        .all(|item| is_signature(item) || string_literal(item).is_some()) =>
+            {
+                unparse_definition(keyword, name, params, body, trailing)
+            }
+            // 解构let还原为绑定列表形式 / Destructuring lets are restored to their binding-list form
+            [GrammarElement::Atom(keyword), target, value, body] if keyword == "let" => {
+                match destructuring_pattern(target) {
+                    Some(pattern) => unparse_destructuring_let(
+                        &pattern,
+                        unparse_element(value),
+                        match body {
+                            GrammarElement::Expr(expr) => unparse_body(expr),
+                            body => vec![unparse_element(body)],
+                        },
+                    ),
+                    None => form("let", items[1..].iter().map(unparse_element)),
+                }
+            }
+            // 协议实现中的方法省略 `def` / Methods in a protocol implementation omit `def`
+            [GrammarElement::Atom(keyword), protocol, type_name, methods @ ..]
+                if keyword == "defimpl" =>
+            {
+                let mut parts = vec![unparse_element(protocol), unparse_element(type_name)];
+                parts.extend(methods.iter().map(|method| {
+                    let source = unparse_element(method);
+                    match source.strip_prefix("(def ") {
+                        Some(rest) => format!("({}", rest),
+                        None => source,
+                    }
+                }));
+                form("defimpl", parts)
+            }
+            _ => {
+                let parts: Vec<String> = items.iter().map(unparse_element).collect();
+                format!("({})", parts.join(" "))
+            }
+        },
+        // 自然语言片段无法执行，保留为字符串 / Natural language cannot run, keep it as a string
+        GrammarElement::NaturalLang(text) => quote_string(text),
+        GrammarElement::Expr(expr) => unparse_expr(expr),
+    }
+}
+
+/// 将表达式转换为源代码 / Convert expression to source code
+pub fn unparse_expr(expr: &Expr) -> String {
+    match expr {
+        Expr::Literal(literal) => unparse_literal(literal),
+        Expr::Var(name) => name.to_string(),
+        // 推导式还原为绑定列表形式 / Comprehensions are restored to their binding-list form
+        Expr::Call(name, args) if name == "for/list" || name == "for/dict" => match args.as_slice()
+        {
+            [Expr::For {
+                var,
+                iterable,
+                body,
+            }, value_var @ ..] => {
+                let mut binding = vec![var.to_string()];
+                binding.extend(value_var.iter().map(unparse_expr));
+                binding.push(unparse_expr(iterable));
+                let mut parts = vec![format!("({})", binding.join(" "))];
+                parts.extend(unparse_body(body));
+                form(name, parts)
+            }
+            _ => form(name, args.iter().map(unparse_expr)),
+        },
+        Expr::Call(name, args) if name == "let" => match args.as_slice() {
+            [target, value, body] => match Pattern::from_binding(target) {
+                Some(pattern @ (Pattern::List(_) | Pattern::Dict(_))) => {
+                    unparse_destructuring_let(&pattern, unparse_expr(value), unparse_body(body))
+                }
+                _ => form(name, args.iter().map(unparse_expr)),
+            },
+            _ => form(name, args.iter().map(unparse_expr)),
+        },
+        // 导入选项还原为关键字形式 / Import options are restored to their keyword form
+        Expr::Call(name, args) if name == "import" => form(
+            name,
+            args.iter().map(|arg| match arg {
+                Expr::Call(option, values) if option == ":as" || option == ":only" => {
+                    let values: Vec<String> = values.iter().map(unparse_expr).collect();
+                    match option.as_str() {
+                        ":as" => format!(":as {}", values.join(" ")),
+                        _ => format!(":only ({})", values.join(" ")),
+                    }
+                }
+                arg => unparse_expr(arg),
+            }),
+        ),
+        Expr::Call(name, args) => {
+            let name = name.strip_prefix("op:").unwrap_or(name);
+            form(name, args.iter().map(unparse_expr))
+        }
+        Expr::Binary(op, left, right) => {
+            form(binop_symbol(op), [unparse_expr(left), unparse_expr(right)])
+        }
+        Expr::If(condition, then_expr, else_expr) => form(
+            "if",
+            [
+                unparse_expr(condition),
+                unparse_expr(then_expr),
+                unparse_expr(else_expr),
+            ],
+        ),
+        Expr::Match(value, cases) => {
+            let mut parts = vec![unparse_expr(value)];
+            for (pattern, body) in cases {
+                parts.push(format!(
+                    "({} {})",
+                    unparse_pattern(pattern),
+                    unparse_expr(body)
+                ));
+            }
+            form("match", parts)
+        }
+        Expr::For {
+            var,
+            iterable,
+            body,
+        } => {
+            let mut parts = vec![var.to_string(), unparse_expr(iterable)];
+            parts.extend(unparse_body(body));
+            form("for", parts)
+        }
+        Expr::While { condition, body } => {
+            let mut parts = vec![unparse_expr(condition)];
+            parts.extend(unparse_body(body));
+            form("while", parts)
+        }
+        Expr::Try {
+            try_body,
+            catch_var,
+            catch_body,
+        } => {
+            let mut parts = vec![unparse_expr(try_body), "catch".to_string()];
+            parts.extend(catch_var.as_deref().map(str::to_string));
+            parts.push(unparse_expr(catch_body));
+            form("try", parts)
+        }
+        Expr::Lambda { params, body } => form(
+            "lambda",
+            [format!("({})", params.join(" ")), unparse_expr(body)],
+        ),
+        Expr::Begin(exprs) => form("begin", exprs.iter().map(unparse_expr)),
+        Expr::Assign(var, value) => form("set!", [var.to_string(), unparse_expr(value)]),
+    }
+}
+
+/// 将字面量转换为源代码 / Convert literal to source code
+pub fn unparse_literal(literal: &Literal) -> String {
+    match literal {
+        Literal::Int(i) => i.to_string(),
+        Literal::BigInt(b) => b.to_string(),
+        Literal::Float(f) => unparse_float(*f),
+        Literal::String(s) => quote_string(s),
+        Literal::Bool(b) => b.to_string(),
+        Literal::Null => "null".to_string(),
+        Literal::List(items) => form("list", items.iter().map(unparse_expr)),
+        Literal::Dict(pairs) => form(
+            "dict",
+            pairs
+                .iter()
+                .flat_map(|(key, value)| [quote_string(key), unparse_expr(value)]),
+        ),
+    }
+}
+
+/// 将模式转换为源代码 / Convert pattern to source code
+pub fn unparse_pattern(pattern: &Pattern) -> String {
+    match pattern {
+        Pattern::Literal(literal) => unparse_literal(literal),
+        Pattern::Var(name) => name.to_string(),
+        Pattern::Wildcard => "_".to_string(),
+        Pattern::List(items) => {
+            let parts: Vec<String> = items.iter().map(unparse_pattern).collect();
+            format!("({})", parts.join(" "))
+        }
+        Pattern::Dict(pairs) => form(
+            "dict",
+            pairs
+                .iter()
+                .flat_map(|(key, value)| [quote_string(key), unparse_pattern(value)]),
+        ),
+        Pattern::Rest(name) => format!("..{}", name.as_deref().unwrap_or_default()),
+        Pattern::Or(alternatives) => form("or", alternatives.iter().map(unparse_pattern)),
+        Pattern::Prefix(prefix, rest) => {
+            form("prefix", [quote_string(prefix), unparse_pattern(rest)])
+        }
+        Pattern::Suffix(suffix, rest) => {
+            form("suffix", [quote_string(suffix), unparse_pattern(rest)])
+        }
+        // 守卫写在匹配分支中: (pattern when guard body) / Guards live in the match case: (pattern when guard body)
+        Pattern::Guard(inner, guard) => {
+            format!("{} when {}", unparse_pattern(inner), unparse_expr(guard))
+        }
+    }
+}
+
+/// 输出 `(def name ((x : Int) y) : Int "doc" body)`，Any 标注省略
+/// Emit `(def name ((x : Int) y) : Int "doc" body)`, omitting Any annotations
+fn unparse_definition(
+    keyword: &str,
+    name: &GrammarElement,
+    params: &[GrammarElement],
+    body: &GrammarElement,
+    trailing: &[GrammarElement],
+) -> String {
+    let signature = trailing
+        .iter()
+        .find_map(|item| match item {
+            GrammarElement::List(signature) if is_signature(item) => Some(signature.as_slice()),
+            _ => None,
+        })
+        .unwrap_or_default();
+    let is_any = |t: &GrammarElement| matches!(t, GrammarElement::Atom(a) if a == "Any");
+    let param_types = match signature.get(1) {
+        Some(GrammarElement::List(types)) => types.as_slice(),
+        _ => &[],
+    };
+    let params: Vec<String> = params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| match param_types.get(i) {
+            Some(t) if !is_any(t) => {
+                format!("({} : {})", unparse_element(param), unparse_element(t))
+            }
+            _ => unparse_element(param),
+        })
+        .collect();
+    let mut result = format!(
+        "({} {} ({})",
+        keyword,
+        unparse_element(name),
+        params.join(" ")
+    );
+    if let Some(return_type) = signature.get(2).filter(|t| !is_any(t)) {
+        result.push_str(" : ");
+        result.push_str(&unparse_element(return_type));
+    }
+    if let Some(doc) = trailing.iter().find_map(string_literal) {
+        result.push(' ');
+        result.push_str(&quote_string(doc));
+    }
+    result.push(' ');
+    result.push_str(&unparse_element(body));
+    result.push(')');
+    result
+}
+
+/// 是否为类型签名 `(: (参数类型...) 返回类型)` / Whether it is a type signature `(: (param types...) return type)`
+fn is_signature(element: &GrammarElement) -> bool {
+    matches!(element, GrammarElement::List(items) if matches!(items.first(), Some(GrammarElement::Atom(colon)) if colon == ":"))
+}
+
+/// 循环体：多表达式的begin块展开为多个参数，与解析器的处理对称
+/// Loop body: a multi-expression begin block is spliced, mirroring the parser
+fn unparse_body(body: &Expr) -> Vec<String> {
+    match body {
+        Expr::Begin(exprs) if exprs.len() != 1 => exprs.iter().map(unparse_expr).collect(),
+        _ => vec![unparse_expr(body)],
+    }
+}
+
+/// 解构let `(let ((pattern value)) body...)`，省略null body / Destructuring let `(let ((pattern value)) body...)`, omitting a null body
+fn unparse_destructuring_let(pattern: &Pattern, value: String, body: Vec<String>) -> String {
+    let mut parts = vec![format!("(({} {}))", unparse_pattern(pattern), value)];
+    parts.extend(body.into_iter().filter(|part| part != "null"));
+    form("let", parts)
+}
+
+/// 构造 `(head arg...)` 形式 / Build a `(head arg...)` form
+fn form(head: &str, args: impl IntoIterator<Item = String>) -> String {
+    let mut result = format!("({}", head);
+    for arg in args {
+        result.push(' ');
+        result.push_str(&arg);
+    }
+    result.push(')');
+    result
+}
+
+/// 转义字符串，花括号也需转义以免被当作插值
+/// Escape a string; braces are escaped too so they are not read as interpolation
+pub(crate) fn quote_string(s: &str) -> String {
+    let mut result = String::with_capacity(s.len() + 2);
+    result.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '{' => result.push_str("\\{"),
+            '}' => result.push_str("\\}"),
+            _ => result.push(ch),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// 浮点数必须带小数点才能被读回为浮点数；非有限值通过 `to-float` 构造
+/// Floats need a decimal point to read back as floats; non-finite values are built with `to-float`
+fn unparse_float(f: f64) -> String {
+    if !f.is_finite() {
+        format!("(to-float \"{}\")", f)
+    } else {
+        let text = f.to_string();
+        if text.contains('.') {
+            text
+        } else {
+            format!("{}.0", text)
+        }
+    }
+}
+
+/// 二元运算符的源码符号 / Source symbol of a binary operator
+fn binop_symbol(op: &BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Mod => "%",
+        BinOp::Eq => "=",
+        BinOp::Ne => "!=",
+        BinOp::Lt => "<",
+        BinOp::Gt => ">",
+        BinOp::Le => "<=",
+        BinOp::Ge => ">=",
+    }
+}
@@ -0,0 +1,247 @@
+// AST遍历 / AST traversal
+// `Visitor` 只读遍历、`Mutator` 原地改写，二者覆盖 `GrammarElement` 和 `Expr` 的全部变体并记录深度和位置；
+// 分析、重构、文档和相似度工具共用这里的遍历，不再各自递归
+// `Visitor` walks read-only and `Mutator` rewrites in place; both cover every `GrammarElement` and `Expr` variant
+// and track depth and position, so the analysis, refactoring, documentation and similarity tools share one
+// traversal instead of each recursing on their own
+
+use crate::grammar::core::{Expr, GrammarElement, Literal};
+
+/// 节点在树中的位置 / Position of a node in the tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Position {
+    /// 嵌套深度，顶层形式为0；`GrammarElement::Expr` 包装的表达式与包装同一深度
+    /// Nesting depth, 0 for top-level forms; the expression inside `GrammarElement::Expr` has the wrapper's depth
+    pub depth: usize,
+    /// 在父节点的子节点中的序号，顶层形式为其在程序中的序号
+    /// Index among the parent's children; for top-level forms, the index in the program
+    pub index: usize,
+}
+
+impl Position {
+    /// 第 `index` 个子节点的位置 / Position of the `index`-th child
+    pub fn child(self, index: usize) -> Self {
+        Self {
+            depth: self.depth + 1,
+            index,
+        }
+    }
+}
+
+/// 只读访问者：`enter_*` 在子节点之前调用，返回false时跳过子节点；`exit_*` 在子节点之后调用
+/// Read-only visitor: `enter_*` runs before the children and skips them when it returns false; `exit_*` runs after
+/// the children
+pub trait Visitor<'ast> {
+    fn enter_element(&mut self, _element: &'ast GrammarElement, _position: Position) -> bool {
+        true
+    }
+
+    fn exit_element(&mut self, _element: &'ast GrammarElement, _position: Position) {}
+
+    fn enter_expr(&mut self, _expr: &'ast Expr, _position: Position) -> bool {
+        true
+    }
+
+    fn exit_expr(&mut self, _expr: &'ast Expr, _position: Position) {}
+}
+
+/// 原地改写的访问者，调用顺序与 `Visitor` 相同；`enter_*` 替换节点后遍历的是新节点的子节点
+/// In-place rewriting visitor, called in the same order as `Visitor`; when `enter_*` replaces a node, the walk
+/// continues into the children of the new node
+pub trait Mutator {
+    fn enter_element(&mut self, _element: &mut GrammarElement, _position: Position) -> bool {
+        true
+    }
+
+    fn exit_element(&mut self, _element: &mut GrammarElement, _position: Position) {}
+
+    fn enter_expr(&mut self, _expr: &mut Expr, _position: Position) -> bool {
+        true
+    }
+
+    fn exit_expr(&mut self, _expr: &mut Expr, _position: Position) {}
+}
+
+/// 遍历程序的全部顶层形式 / Walk every top-level form of a program
+pub fn walk_program<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    elements: &'ast [GrammarElement],
+) {
+    for (index, element) in elements.iter().enumerate() {
+        walk_element(visitor, element, Position { depth: 0, index });
+    }
+}
+
+/// 遍历语法元素 / Walk a grammar element
+pub fn walk_element<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    element: &'ast GrammarElement,
+    position: Position,
+) {
+    if visitor.enter_element(element, position) {
+        match element {
+            GrammarElement::List(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    walk_element(visitor, item, position.child(index));
+                }
+            }
+            GrammarElement::Expr(expr) => walk_expr(visitor, expr, position),
+            GrammarElement::Atom(_) | GrammarElement::NaturalLang(_) => {}
+        }
+    }
+    visitor.exit_element(element, position);
+}
+
+/// 遍历表达式 / Walk an expression
+pub fn walk_expr<'ast, V: Visitor<'ast> + ?Sized>(
+    visitor: &mut V,
+    expr: &'ast Expr,
+    position: Position,
+) {
+    if visitor.enter_expr(expr, position) {
+        for (index, child) in expr_children(expr).into_iter().enumerate() {
+            walk_expr(visitor, child, position.child(index));
+        }
+    }
+    visitor.exit_expr(expr, position);
+}
+
+/// 改写程序的全部顶层形式 / Rewrite every top-level form of a program
+pub fn walk_program_mut<M: Mutator + ?Sized>(mutator: &mut M, elements: &mut [GrammarElement]) {
+    for (index, element) in elements.iter_mut().enumerate() {
+        walk_element_mut(mutator, element, Position { depth: 0, index });
+    }
+}
+
+/// 改写语法元素 / Rewrite a grammar element
+pub fn walk_element_mut<M: Mutator + ?Sized>(
+    mutator: &mut M,
+    element: &mut GrammarElement,
+    position: Position,
+) {
+    if mutator.enter_element(element, position) {
+        match element {
+            GrammarElement::List(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    walk_element_mut(mutator, item, position.child(index));
+                }
+            }
+            GrammarElement::Expr(expr) => walk_expr_mut(mutator, expr, position),
+            GrammarElement::Atom(_) | GrammarElement::NaturalLang(_) => {}
+        }
+    }
+    mutator.exit_element(element, position);
+}
+
+/// 改写表达式 / Rewrite an expression
+pub fn walk_expr_mut<M: Mutator + ?Sized>(mutator: &mut M, expr: &mut Expr, position: Position) {
+    if mutator.enter_expr(expr, position) {
+        for (index, child) in expr_children_mut(expr).into_iter().enumerate() {
+            walk_expr_mut(mutator, child, position.child(index));
+        }
+    }
+    mutator.exit_expr(expr, position);
+}
+
+/// 以先序对元素中的每个表达式调用闭包 / Call a closure on every expression in an element, in preorder
+pub fn for_each_expr<'ast>(element: &'ast GrammarElement, f: impl FnMut(&'ast Expr, Position)) {
+    struct Exprs<F>(F);
+
+    impl<'ast, F: FnMut(&'ast Expr, Position)> Visitor<'ast> for Exprs<F> {
+        fn enter_expr(&mut self, expr: &'ast Expr, position: Position) -> bool {
+            (self.0)(expr, position);
+            true
+        }
+    }
+
+    walk_element(&mut Exprs(f), element, Position::default());
+}
+
+/// 表达式的直接子表达式，包括列表和字典字面量的元素 / Direct subexpressions of an expression, including the items
+/// of list and dict literals
+pub fn expr_children(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::Literal(Literal::List(items)) => items.iter().collect(),
+        Expr::Literal(Literal::Dict(entries)) => entries.iter().map(|(_, value)| value).collect(),
+        Expr::Literal(_) | Expr::Var(_) => Vec::new(),
+        Expr::Call(_, args) | Expr::Begin(args) => args.iter().collect(),
+        Expr::Binary(_, left, right) => vec![left, right],
+        Expr::If(condition, then_branch, else_branch) => vec![condition, then_branch, else_branch],
+        Expr::Match(value, arms) => std::iter::once(value.as_ref())
+            .chain(arms.iter().map(|(_, arm)| arm))
+            .collect(),
+        Expr::For { iterable, body, .. } => vec![iterable, body],
+        Expr::While { condition, body } => vec![condition, body],
+        Expr::Try {
+            try_body,
+            catch_body,
+            ..
+        } => vec![try_body, catch_body],
+        Expr::Lambda { body, .. } => vec![body],
+        Expr::Assign(_, value) => vec![value],
+    }
+}
+
+/// 直接子表达式（可变）/ Direct subexpressions (mutable)
+pub fn expr_children_mut(expr: &mut Expr) -> Vec<&mut Expr> {
+    match expr {
+        Expr::Literal(Literal::List(items)) => items.iter_mut().collect(),
+        Expr::Literal(Literal::Dict(entries)) => {
+            entries.iter_mut().map(|(_, value)| value).collect()
+        }
+        Expr::Literal(_) | Expr::Var(_) => Vec::new(),
+        Expr::Call(_, args) | Expr::Begin(args) => args.iter_mut().collect(),
+        Expr::Binary(_, left, right) => vec![left, right],
+        Expr::If(condition, then_branch, else_branch) => vec![condition, then_branch, else_branch],
+        Expr::Match(value, arms) => std::iter::once(value.as_mut())
+            .chain(arms.iter_mut().map(|(_, arm)| arm))
+            .collect(),
+        Expr::For { iterable, body, .. } => vec![iterable, body],
+        Expr::While { condition, body } => vec![condition, body],
+        Expr::Try {
+            try_body,
+            catch_body,
+            ..
+        } => vec![try_body, catch_body],
+        Expr::Lambda { body, .. } => vec![body],
+        Expr::Assign(_, value) => vec![value],
+    }
+}
+
+/// 先序排列的全部节点 / All nodes in preorder
+pub fn preorder(expr: &Expr) -> Vec<&Expr> {
+    let mut nodes = Vec::new();
+    let mut stack = vec![expr];
+    while let Some(expr) = stack.pop() {
+        nodes.push(expr);
+        stack.extend(expr_children(expr).into_iter().rev());
+    }
+    nodes
+}
+
+/// 表达式树的节点数 / Node count of an expression tree
+pub fn count_nodes(expr: &Expr) -> usize {
+    preorder(expr).len()
+}
+
+/// 程序中最深节点的深度，只有原子的顶层形式为0 / Depth of the deepest node in a program; 0 when every top-level form
+/// is a leaf
+pub fn max_depth(elements: &[GrammarElement]) -> usize {
+    struct Depth(usize);
+
+    impl Visitor<'_> for Depth {
+        fn enter_element(&mut self, _element: &GrammarElement, position: Position) -> bool {
+            self.0 = self.0.max(position.depth);
+            true
+        }
+
+        fn enter_expr(&mut self, _expr: &Expr, position: Position) -> bool {
+            self.0 = self.0.max(position.depth);
+            true
+        }
+    }
+
+    let mut depth = Depth(0);
+    walk_program(&mut depth, elements);
+    depth.0
+}
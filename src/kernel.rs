@@ -0,0 +1,394 @@
+// 笔记本内核 / Notebook kernel
+// Jupyter 内核协议的无 ZMQ 简化层：每行一条 JSON 消息，单元格在同一个解释器中执行以保留状态，
+// 字典和列表渲染为 HTML 表格，代码解释器的解释与结果一同显示；由 `evo-kernel` 或 Python 包装内核转发到 Jupyter
+// ZMQ-less simplified layer of the Jupyter kernel protocol: one JSON message per line, cells run in one interpreter
+// so state persists, dicts and lists render as HTML tables, and code explainer explanations are shown alongside
+// results; `evo-kernel` or a Python wrapper kernel relays it to Jupyter
+
+use std::io::{self, BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value as Json};
+
+use crate::evolution::doc_generator::escape_html;
+use crate::parser::{AdaptiveParser, CodeExplainer, Language};
+use crate::runtime::{Interpreter, OutputSink, Value};
+
+/// 执行出错时的错误信息 / Error information of a failed execution
+#[derive(Debug, Clone)]
+pub struct CellError {
+    /// 错误类型，如 `ParseError`、`TypeError` / Error kind, such as `ParseError` or `TypeError`
+    pub ename: String,
+    /// 错误消息 / Error message
+    pub evalue: String,
+}
+
+/// 单元格执行结果 / Result of executing a cell
+#[derive(Debug, Clone)]
+pub struct CellOutput {
+    /// 执行序号 / Execution count
+    pub execution_count: u64,
+    /// 执行期间 `print` 的输出 / Output printed during execution
+    pub stdout: String,
+    /// 按 MIME 类型给出的结果表示，结果为 null 时为空 / Result representations by MIME type; empty when the result is null
+    pub data: Vec<(String, String)>,
+    /// 代码的自然语言解释 / Natural-language explanation of the code
+    pub explanation: Option<String>,
+    /// 执行错误 / Execution error
+    pub error: Option<CellError>,
+}
+
+/// 笔记本内核：在执行之间保留解释器状态 / Notebook kernel: keeps interpreter state between executions
+pub struct NotebookKernel {
+    interpreter: Interpreter,
+    parser: AdaptiveParser,
+    explainer: CodeExplainer,
+    output: Arc<Mutex<Vec<u8>>>,
+    explain: bool,
+    execution_count: u64,
+    shutdown: bool,
+}
+
+impl NotebookKernel {
+    /// 创建内核，解释使用给定语言 / Create a kernel whose explanations use the given language
+    pub fn new(language: Language) -> Self {
+        let mut interpreter = Interpreter::new();
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let sink: OutputSink = output.clone();
+        interpreter.set_output_sink(Some(sink));
+        Self {
+            interpreter,
+            parser: AdaptiveParser::new(false),
+            explainer: CodeExplainer::new(language),
+            output,
+            explain: true,
+            execution_count: 0,
+            shutdown: false,
+        }
+    }
+
+    /// 是否在结果旁显示代码解释（默认开启）/ Whether to show code explanations alongside results (on by default)
+    pub fn set_explain(&mut self, explain: bool) {
+        self.explain = explain;
+    }
+
+    /// 是否已收到 `shutdown_request` / Whether a `shutdown_request` was received
+    pub fn shutdown_requested(&self) -> bool {
+        self.shutdown
+    }
+
+    /// 执行一个单元格 / Execute one cell
+    pub fn execute(&mut self, code: &str) -> CellOutput {
+        self.execute_cell(code, self.explain)
+    }
+
+    fn execute_cell(&mut self, code: &str, explain: bool) -> CellOutput {
+        self.execution_count += 1;
+        let mut cell = CellOutput {
+            execution_count: self.execution_count,
+            stdout: String::new(),
+            data: Vec::new(),
+            explanation: None,
+            error: None,
+        };
+        let (ast, comments) = match self.parser.parse_with_comments(code) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                cell.error = Some(CellError {
+                    ename: "ParseError".to_string(),
+                    evalue: e.message().to_string(),
+                });
+                return cell;
+            }
+        };
+        if explain && !ast.is_empty() {
+            cell.explanation = Some(
+                ast.iter()
+                    .zip(&comments)
+                    .map(|(element, comment)| {
+                        self.explainer
+                            .explain_with_comment(element, comment.as_deref())
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            );
+        }
+
+        let result = self.interpreter.execute(&ast);
+        cell.stdout = self.take_output();
+        match result {
+            Ok(Value::Null) => {}
+            Ok(value) => cell.data = display_data(&value),
+            Err(e) => {
+                cell.error = Some(CellError {
+                    ename: e.kind().to_string(),
+                    evalue: e.to_string(),
+                })
+            }
+        }
+        cell
+    }
+
+    /// 单元格代码是否完整：`complete`、`incomplete`（括号未闭合）或 `invalid`
+    /// Whether cell code is complete: `complete`, `incomplete` (unclosed parentheses) or `invalid`
+    pub fn is_complete(&self, code: &str) -> &'static str {
+        let (_, errors) = self.parser.parse_recovering(code);
+        if errors.is_empty() {
+            "complete"
+        } else if errors.iter().any(|e| e.message().starts_with("Unclosed")) {
+            "incomplete"
+        } else {
+            "invalid"
+        }
+    }
+
+    /// 处理一条消息（`header`、`content`，可选 `parent_header`），返回要发送的消息；
+    /// 每条回复的 `channel` 为 `shell` 或 `iopub`，由转发方发到对应的 Jupyter 套接字
+    /// Handle one message (`header`, `content` and an optional `parent_header`), returning the messages to send;
+    /// each reply's `channel` is `shell` or `iopub`, for the relay to send on the matching Jupyter socket
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let header = &message["header"];
+        let content = &message["content"];
+        let reply = |channel: &str, msg_type: &str, content: Json| {
+            json!({
+                "channel": channel,
+                "header": {
+                    "msg_id": uuid::Uuid::new_v4().to_string(),
+                    "msg_type": msg_type,
+                    "session": header["session"],
+                    "version": "5.3",
+                },
+                "parent_header": header,
+                "content": content,
+            })
+        };
+
+        match header["msg_type"].as_str().unwrap_or("") {
+            "kernel_info_request" => vec![reply(
+                "shell",
+                "kernel_info_reply",
+                json!({
+                    "status": "ok",
+                    "protocol_version": "5.3",
+                    "implementation": "evo",
+                    "implementation_version": env!("CARGO_PKG_VERSION"),
+                    "language_info": {
+                        "name": "evo",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "mimetype": "text/x-evo",
+                        "file_extension": ".evo",
+                        "codemirror_mode": "scheme",
+                    },
+                    "banner": "Evo-lang",
+                }),
+            )],
+            "execute_request" => {
+                // 非标准的 `explain` 字段只对本次执行覆盖解释开关 / The non-standard `explain` field overrides explanations for this execution only
+                let explain = content["explain"].as_bool().unwrap_or(self.explain);
+                let code = content["code"].as_str().unwrap_or("");
+                let silent = content["silent"].as_bool().unwrap_or(false);
+                let cell = self.execute_cell(code, explain);
+                let count = cell.execution_count;
+
+                let mut replies = vec![reply(
+                    "iopub",
+                    "execute_input",
+                    json!({ "code": code, "execution_count": count }),
+                )];
+                if !silent {
+                    if !cell.stdout.is_empty() {
+                        replies.push(reply(
+                            "iopub",
+                            "stream",
+                            json!({ "name": "stdout", "text": cell.stdout }),
+                        ));
+                    }
+                    if let Some(explanation) = &cell.explanation {
+                        replies.push(reply(
+                            "iopub",
+                            "display_data",
+                            json!({
+                                "data": { "text/plain": explanation },
+                                "metadata": { "evo": { "explanation": true } },
+                            }),
+                        ));
+                    }
+                    if !cell.data.is_empty() {
+                        let data: serde_json::Map<String, Json> = cell
+                            .data
+                            .iter()
+                            .map(|(mime, text)| (mime.clone(), Json::String(text.clone())))
+                            .collect();
+                        replies.push(reply(
+                            "iopub",
+                            "execute_result",
+                            json!({ "execution_count": count, "data": data, "metadata": {} }),
+                        ));
+                    }
+                }
+                let status = match &cell.error {
+                    Some(error) => {
+                        let error = json!({
+                            "ename": error.ename,
+                            "evalue": error.evalue,
+                            "traceback": [format!("{}: {}", error.ename, error.evalue)],
+                        });
+                        replies.push(reply("iopub", "error", error.clone()));
+                        let mut content = error;
+                        content["status"] = json!("error");
+                        content["execution_count"] = json!(count);
+                        content
+                    }
+                    None => json!({
+                        "status": "ok",
+                        "execution_count": count,
+                        "user_expressions": {},
+                    }),
+                };
+                replies.push(reply("shell", "execute_reply", status));
+                replies
+            }
+            "is_complete_request" => {
+                let code = content["code"].as_str().unwrap_or("");
+                let status = self.is_complete(code);
+                let content = if status == "incomplete" {
+                    json!({ "status": status, "indent": "  " })
+                } else {
+                    json!({ "status": status })
+                };
+                vec![reply("shell", "is_complete_reply", content)]
+            }
+            "shutdown_request" => {
+                self.shutdown = true;
+                vec![reply(
+                    "shell",
+                    "shutdown_reply",
+                    json!({ "status": "ok", "restart": content["restart"].as_bool().unwrap_or(false) }),
+                )]
+            }
+            other => vec![reply(
+                "shell",
+                &other.replace("_request", "_reply"),
+                json!({
+                    "status": "error",
+                    "ename": "UnsupportedMessage",
+                    "evalue": format!("Unsupported message type '{}'", other),
+                    "traceback": [],
+                }),
+            )],
+        }
+    }
+
+    /// 在输入输出流上按行收发 JSON 消息，直到收到 `shutdown_request` 或输入结束
+    /// Exchange line-delimited JSON messages over the given streams until `shutdown_request` or the end of input
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let replies = match serde_json::from_str::<Json>(&line) {
+                Ok(message) => self.handle(&message),
+                Err(e) => vec![json!({
+                    "channel": "shell",
+                    "header": { "msg_type": "error" },
+                    "content": { "ename": "InvalidMessage", "evalue": e.to_string(), "traceback": [] },
+                })],
+            };
+            for reply in replies {
+                writeln!(output, "{}", reply)?;
+            }
+            output.flush()?;
+            if self.shutdown {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// 取出并清空已缓存的输出 / Take and clear the buffered output
+    fn take_output(&mut self) -> String {
+        let mut output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        String::from_utf8_lossy(&std::mem::take(&mut *output)).to_string()
+    }
+}
+
+impl Default for NotebookKernel {
+    fn default() -> Self {
+        Self::new(Language::Chinese)
+    }
+}
+
+/// 值的显示数据：总有 `text/plain`，字典和列表另有 HTML 表格
+/// Display data of a value: always `text/plain`, plus an HTML table for dicts and lists
+pub fn display_data(value: &Value) -> Vec<(String, String)> {
+    let mut data = vec![("text/plain".to_string(), value.to_string())];
+    if let Some(table) = html_table(value) {
+        data.push(("text/html".to_string(), table));
+    }
+    data
+}
+
+/// 字典渲染为键值两列；元素都是字典的列表以键的并集（按首次出现的顺序）为列，其他列表渲染为序号和值两列
+/// Dicts render as key and value columns; lists of dicts use the union of their keys (in first-seen order) as
+/// columns, and other lists render as index and value columns
+fn html_table(value: &Value) -> Option<String> {
+    let (columns, rows): (Vec<String>, Vec<Vec<String>>) = match value {
+        Value::Dict(dict) => (
+            vec!["key".to_string(), "value".to_string()],
+            dict.iter()
+                .map(|(key, value)| vec![key.clone(), value.to_string()])
+                .collect(),
+        ),
+        Value::List(items) if items.is_empty() => return None,
+        Value::List(items) if items.iter().all(|item| matches!(item, Value::Dict(_))) => {
+            let mut columns: Vec<String> = Vec::new();
+            for item in items.iter() {
+                if let Value::Dict(dict) = item {
+                    for key in dict.keys() {
+                        if !columns.contains(key) {
+                            columns.push(key.clone());
+                        }
+                    }
+                }
+            }
+            let rows = items
+                .iter()
+                .filter_map(|item| match item {
+                    Value::Dict(dict) => Some(
+                        columns
+                            .iter()
+                            .map(|column| dict.get(column).map_or(String::new(), Value::to_string))
+                            .collect(),
+                    ),
+                    _ => None,
+                })
+                .collect();
+            (columns, rows)
+        }
+        Value::List(items) => (
+            vec!["#".to_string(), "value".to_string()],
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| vec![index.to_string(), item.to_string()])
+                .collect(),
+        ),
+        _ => return None,
+    };
+
+    let mut html = String::from("<table>\n<thead><tr>");
+    for column in &columns {
+        html.push_str(&format!("<th>{}</th>", escape_html(column)));
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            html.push_str(&format!("<td>{}</td>", escape_html(&cell)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>");
+    Some(html)
+}
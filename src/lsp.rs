@@ -0,0 +1,720 @@
+// 语言服务器 / Language server
+// 基于标准输入输出的 LSP 前端：诊断来自解析器、类型推断和代码分析器，悬停来自代码解释器（含文档注释），
+// 补全来自代码生成器，跳转定义来自依赖分析器，快速修复来自错误恢复器
+// LSP front-end over stdio: diagnostics from the parser, type inference and code analyzer, hover from the code explainer (with doc comments),
+// completions from the code generator, go-to-definition from the dependency analyzer and quick fixes from the error recoverer
+
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value as Json};
+
+use crate::evolution::{
+    CodeAnalyzer, DependencyAnalyzer, ErrorRecoverer, GenerationContext, IntelligentCodeGenerator,
+    PatternType,
+};
+use crate::grammar::core::GrammarElement;
+use crate::grammar::inference::TypeInference;
+use crate::grammar::types::element_name;
+use crate::parser::{
+    leading_comment, AdaptiveParser, CodeExplainer, Language, Location, ParseError, ParsedDocument,
+    SyntaxKind, SyntaxNode,
+};
+use crate::runtime::InterpreterError;
+
+/// 诊断严重程度（LSP 编码）/ Diagnostic severity (LSP codes)
+const SEVERITY_ERROR: u8 = 1;
+const SEVERITY_WARNING: u8 = 2;
+const SEVERITY_INFORMATION: u8 = 3;
+
+/// 补全项种类（LSP 编码）/ Completion item kinds (LSP codes)
+const COMPLETION_FUNCTION: u8 = 3;
+const COMPLETION_VARIABLE: u8 = 6;
+const COMPLETION_SNIPPET: u8 = 15;
+
+/// 打开的文档 / Open document
+struct Document {
+    /// 当前文本 / Current text
+    text: String,
+    /// 与当前文本对应的解析结果（解析失败时为空）/ Parse result for the current text (empty when parsing fails)
+    parsed: Option<ParsedDocument>,
+    /// 供编辑中的不完整代码补全的定义 / Definitions for completing incomplete code
+    context: GenerationContext,
+}
+
+/// Evo 语言服务器 / Evo language server
+pub struct LanguageServer {
+    parser: AdaptiveParser,
+    explainer: CodeExplainer,
+    generator: IntelligentCodeGenerator,
+    recoverer: ErrorRecoverer,
+    documents: HashMap<String, Document>,
+    shutdown_requested: bool,
+    exited: bool,
+}
+
+impl LanguageServer {
+    /// 创建语言服务器 / Create language server
+    pub fn new() -> Self {
+        Self {
+            parser: AdaptiveParser::new(false),
+            explainer: CodeExplainer::new(Language::Chinese),
+            generator: IntelligentCodeGenerator::new(),
+            recoverer: ErrorRecoverer::new(),
+            documents: HashMap::new(),
+            shutdown_requested: false,
+            exited: false,
+        }
+    }
+
+    /// 在输入输出流上运行，直到收到 `exit` 或输入结束
+    /// Run over the given streams until `exit` is received or the input ends
+    pub fn run<R: BufRead, W: Write>(&mut self, mut input: R, mut output: W) -> io::Result<()> {
+        while let Some(body) = read_message(&mut input)? {
+            let replies = match serde_json::from_slice::<Json>(&body) {
+                Ok(message) => self.handle(&message),
+                Err(e) => vec![error_response(Json::Null, -32700, &e.to_string())],
+            };
+            for reply in replies {
+                write_message(&mut output, &reply)?;
+            }
+            if self.exited {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// 是否已按协议先收到 `shutdown` 再退出 / Whether `shutdown` was received before exiting, as the protocol requires
+    pub fn shutdown_requested(&self) -> bool {
+        self.shutdown_requested
+    }
+
+    /// 处理一条 JSON-RPC 消息，返回要发送的响应和通知
+    /// Handle one JSON-RPC message, returning the responses and notifications to send
+    pub fn handle(&mut self, message: &Json) -> Vec<Json> {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let result = match method {
+            "initialize" => {
+                if let Some(locale) = params["locale"].as_str() {
+                    let language = if locale.starts_with("zh") {
+                        Language::Chinese
+                    } else {
+                        Language::English
+                    };
+                    self.explainer = CodeExplainer::new(language);
+                }
+                json!({
+                    "capabilities": {
+                        "textDocumentSync": { "openClose": true, "change": 2 },
+                        "hoverProvider": true,
+                        "completionProvider": { "triggerCharacters": ["("] },
+                        "definitionProvider": true,
+                        "codeActionProvider": true,
+                    },
+                    "serverInfo": { "name": "evo-lsp", "version": env!("CARGO_PKG_VERSION") },
+                })
+            }
+            "shutdown" => {
+                self.shutdown_requested = true;
+                Json::Null
+            }
+            "exit" => {
+                self.exited = true;
+                return Vec::new();
+            }
+            "textDocument/didOpen" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                let text = params["textDocument"]["text"].as_str().unwrap_or("");
+                return vec![self.open(uri, text)];
+            }
+            "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                let changes = params["contentChanges"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default();
+                return self.change(uri, &changes).into_iter().collect();
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                self.documents.remove(uri);
+                return vec![notification(
+                    "textDocument/publishDiagnostics",
+                    json!({ "uri": uri, "diagnostics": [] }),
+                )];
+            }
+            "textDocument/hover" => self.hover(params),
+            "textDocument/completion" => self.completion(params),
+            "textDocument/definition" => self.definition(params),
+            "textDocument/codeAction" => self.code_actions(params),
+            _ => match id {
+                // 未知请求需要回复错误，未知通知直接忽略
+                // Unknown requests need an error reply; unknown notifications are ignored
+                Some(id) => {
+                    return vec![error_response(
+                        id,
+                        -32601,
+                        &format!("Method not found: {}", method),
+                    )]
+                }
+                None => return Vec::new(),
+            },
+        };
+
+        match id {
+            Some(id) => vec![json!({ "jsonrpc": "2.0", "id": id, "result": result })],
+            None => Vec::new(),
+        }
+    }
+
+    /// 打开文档并发布诊断 / Open a document and publish diagnostics
+    fn open(&mut self, uri: &str, text: &str) -> Json {
+        let parsed = self.parser.parse_document(text);
+        self.store(uri, text.to_string(), parsed, None)
+    }
+
+    /// 应用增量或整体修改并发布诊断 / Apply incremental or full changes and publish diagnostics
+    fn change(&mut self, uri: &str, changes: &[Json]) -> Option<Json> {
+        let document = self.documents.remove(uri)?;
+        let mut text = document.text;
+        let mut parsed = document.parsed;
+        for change in changes {
+            let new_text = change["text"].as_str().unwrap_or("");
+            match change.get("range") {
+                Some(range) => {
+                    let start = position_to_offset(&text, &range["start"]);
+                    let end = position_to_offset(&text, &range["end"]).max(start);
+                    parsed = parsed.and_then(|old| {
+                        self.parser
+                            .parse_incremental(&old, start..end, new_text)
+                            .ok()
+                    });
+                    text.replace_range(start..end, new_text);
+                }
+                None => {
+                    text = new_text.to_string();
+                    parsed = None;
+                }
+            }
+        }
+        // 增量解析失败或不可用时整体重新解析，以得到带位置的错误
+        // Reparse in full when incremental parsing fails or is unavailable, to get a located error
+        let parsed = match parsed {
+            Some(parsed) => Ok(parsed),
+            None => self.parser.parse_document(&text),
+        };
+        Some(self.store(uri, text, parsed, Some(document.context)))
+    }
+
+    /// 保存文档状态并生成诊断通知，解析失败时补全上下文合并之前的定义
+    /// Store document state and build the diagnostics notification; when parsing fails the completion context keeps earlier definitions
+    fn store(
+        &mut self,
+        uri: &str,
+        text: String,
+        parsed: Result<ParsedDocument, ParseError>,
+        previous: Option<GenerationContext>,
+    ) -> Json {
+        let (diagnostics, context) = match &parsed {
+            Ok(document) => (
+                self.diagnose(document),
+                generation_context(&document.elements),
+            ),
+            Err(error) => {
+                // 容错解析报告文件中的每个语法错误，补全上下文也取自可解析的形式
+                // Recovering parse reports every syntax error in the file; completions also use the forms that parse
+                let (elements, errors) = self.parser.parse_recovering(&text);
+                let errors = if errors.is_empty() {
+                    vec![parse_diagnostic(&text, error)]
+                } else {
+                    errors
+                        .iter()
+                        .map(|error| parse_diagnostic(&text, error))
+                        .collect()
+                };
+                let mut context = generation_context(&elements);
+                if let Some(previous) = previous {
+                    for name in previous.functions {
+                        if !context.functions.contains(&name) {
+                            context.functions.push(name);
+                        }
+                    }
+                    for name in previous.variables {
+                        if !context.variables.contains(&name) {
+                            context.variables.push(name);
+                        }
+                    }
+                }
+                (errors, context)
+            }
+        };
+        self.documents.insert(
+            uri.to_string(),
+            Document {
+                text,
+                parsed: parsed.ok(),
+                context,
+            },
+        );
+        notification(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        )
+    }
+
+    /// 类型推断错误和代码分析模式 / Type inference errors and code analysis patterns
+    fn diagnose(&self, document: &ParsedDocument) -> Vec<Json> {
+        let source = &document.source;
+        let locations: Vec<Location> = document
+            .nodes
+            .iter()
+            .map(|node| offset_to_location(source, node.span.start))
+            .collect();
+        let mut diagnostics = Vec::new();
+
+        let inference =
+            TypeInference::new().infer_program_with_locations(&document.elements, &locations);
+        for error in inference.errors {
+            let node = error
+                .location
+                .and_then(|location| locations.iter().position(|l| *l == location))
+                .and_then(|index| document.nodes.get(index));
+            diagnostics.push(diagnostic(
+                source,
+                node,
+                SEVERITY_ERROR,
+                &match &error.function {
+                    Some(function) => format!("in function '{}': {}", function, error.message),
+                    None => error.message.clone(),
+                },
+                "TypeError",
+            ));
+        }
+
+        // 逐个顶层形式分析，使模式能定位到具体形式；类型错误已由上面的推断报告
+        // Analyze each top-level form so patterns are located; type errors were already reported by inference above
+        let analyzer = CodeAnalyzer::new();
+        for (element, node) in document.elements.iter().zip(&document.nodes) {
+            for pattern in analyzer.analyze(std::slice::from_ref(element)).patterns {
+                let severity = match pattern.pattern_type {
+                    PatternType::TypeError => continue,
+                    PatternType::UnusedVariable
+                    | PatternType::UnusedParameter
+                    | PatternType::UntrustedIo
+                    | PatternType::UnboundedRecursion
+                    | PatternType::UnboundedLoop
+                    | PatternType::UnbudgetedEval
+                    | PatternType::PermissiveImport
+                    | PatternType::ImpureMemoization => SEVERITY_WARNING,
+                    _ => SEVERITY_INFORMATION,
+                };
+                diagnostics.push(diagnostic(
+                    source,
+                    Some(node),
+                    severity,
+                    &pattern.description,
+                    &format!("{:?}", pattern.pattern_type),
+                ));
+            }
+        }
+        diagnostics
+    }
+
+    /// 悬停：定义名显示定义的解释，其他位置解释所在的最内层列表
+    /// Hover: a defined name shows the explanation of its definition; elsewhere the innermost enclosing list is explained
+    fn hover(&self, params: &Json) -> Json {
+        let Some((document, offset)) = self.locate(params) else {
+            return Json::Null;
+        };
+        let source = &document.source;
+
+        if let Some((index, node)) = self.definition_at(document, offset) {
+            let location = offset_to_location(source, document.nodes[index].span.start);
+            let comment = leading_comment(source, location);
+            let explanation = self
+                .explainer
+                .explain_with_comment(&document.elements[index], comment.as_deref());
+            return json!({
+                "contents": { "kind": "plaintext", "value": explanation },
+                "range": range(source, node),
+            });
+        }
+
+        let Some(node) = document
+            .nodes
+            .iter()
+            .find_map(|node| innermost_list(node, offset))
+        else {
+            return Json::Null;
+        };
+        match self.parser.parse(node.text(source)) {
+            Ok(elements) if !elements.is_empty() => json!({
+                "contents": { "kind": "plaintext", "value": self.explainer.explain(&elements[0]) },
+                "range": range(source, node),
+            }),
+            _ => Json::Null,
+        }
+    }
+
+    /// 补全光标前的符号 / Complete the symbol before the cursor
+    fn completion(&self, params: &Json) -> Json {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+        let Some(document) = self.documents.get(uri) else {
+            return json!([]);
+        };
+        let offset = position_to_offset(&document.text, &params["position"]);
+        let prefix_start = document.text[..offset]
+            .rfind(|c: char| !is_symbol_char(c))
+            .map_or(0, |index| index + 1);
+        let prefix = &document.text[prefix_start..offset];
+
+        let mut seen = HashSet::new();
+        let mut items = Vec::new();
+        for suggestion in self.generator.suggest_completion(prefix, &document.context) {
+            if !seen.insert(suggestion.clone()) {
+                continue;
+            }
+            // 上下文建议形如 `(name ...)` 或 `(let name ...)`，插入名称本身
+            // Context suggestions look like `(name ...)` or `(let name ...)`; the name itself is inserted
+            let name = suggestion
+                .strip_suffix(" ...)")
+                .and_then(|head| head.rsplit(['(', ' ']).next())
+                .filter(|name| name.starts_with(prefix));
+            items.push(match name {
+                Some(name) => {
+                    let kind = if document.context.functions.iter().any(|f| f == name) {
+                        COMPLETION_FUNCTION
+                    } else {
+                        COMPLETION_VARIABLE
+                    };
+                    json!({ "label": name, "kind": kind, "detail": suggestion })
+                }
+                None => json!({ "label": suggestion, "kind": COMPLETION_SNIPPET }),
+            });
+        }
+        Json::Array(items)
+    }
+
+    /// 跳转到光标处名称的顶层定义 / Go to the top-level definition of the name under the cursor
+    fn definition(&self, params: &Json) -> Json {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+        let Some((document, offset)) = self.locate(params) else {
+            return Json::Null;
+        };
+        let Some(node) = document
+            .node_at(offset)
+            .filter(|node| node.kind == SyntaxKind::Atom)
+        else {
+            return Json::Null;
+        };
+        let name = node.text(&document.source);
+        match DependencyAnalyzer::new().find_definition(&document.elements, name) {
+            Some(index) => {
+                let target = definition_name(&document.nodes[index]);
+                json!({ "uri": uri, "range": range(&document.source, target) })
+            }
+            None => Json::Null,
+        }
+    }
+
+    /// 为诊断生成错误恢复器的修复建议 / Build error-recoverer fix suggestions for diagnostics
+    fn code_actions(&self, params: &Json) -> Json {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+        let Some(document) = self.documents.get(uri) else {
+            return json!([]);
+        };
+        let mut actions = Vec::new();
+        for diagnostic in params["context"]["diagnostics"]
+            .as_array()
+            .into_iter()
+            .flatten()
+        {
+            let message = diagnostic["message"].as_str().unwrap_or("").to_string();
+            let error = match diagnostic["data"]["kind"].as_str() {
+                Some("TypeError") => InterpreterError::TypeError {
+                    message,
+                    location: None,
+                },
+                Some("SyntaxError") => InterpreterError::RuntimeError {
+                    message: format!("语法错误 / Syntax error: {}", message),
+                    location: None,
+                },
+                _ => continue,
+            };
+            let start = position_to_offset(&document.text, &diagnostic["range"]["start"]);
+            let end = position_to_offset(&document.text, &diagnostic["range"]["end"]);
+            // 语法错误的修复作用于整个文档，其他错误作用于诊断所在的形式
+            // Syntax fixes apply to the whole document, other fixes to the form the diagnostic covers
+            let (start, end) = match error {
+                InterpreterError::RuntimeError { .. } => (0, document.text.len()),
+                _ => (start, end.max(start)),
+            };
+            let code = &document.text[start..end];
+            let recovery = self.recoverer.recover_from_error(&error, code);
+
+            if let Some(fixed) = &recovery.fixed_code {
+                let edit_range = json!({
+                    "start": offset_to_position(&document.text, start),
+                    "end": offset_to_position(&document.text, end),
+                });
+                actions.push(json!({
+                    "title": recovery.method.clone().unwrap_or_else(|| "修复 / Fix".to_string()),
+                    "kind": "quickfix",
+                    "diagnostics": [diagnostic],
+                    "isPreferred": true,
+                    "edit": { "changes": { uri: [{ "range": edit_range, "newText": fixed }] } },
+                }));
+            }
+            for suggestion in recovery.suggestions {
+                actions.push(json!({
+                    "title": suggestion,
+                    "kind": "quickfix",
+                    "diagnostics": [diagnostic],
+                }));
+            }
+        }
+        Json::Array(actions)
+    }
+
+    /// 查找请求所指文档的解析结果和光标偏移 / Find the parsed document and cursor offset a request refers to
+    fn locate(&self, params: &Json) -> Option<(&ParsedDocument, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let document = self.documents.get(uri)?.parsed.as_ref()?;
+        let offset = position_to_offset(&document.source, &params["position"]);
+        Some((document, offset))
+    }
+
+    /// 光标处的名称若有顶层定义，返回定义索引和名称节点
+    /// If the name under the cursor has a top-level definition, return its index and the name node
+    fn definition_at<'a>(
+        &self,
+        document: &'a ParsedDocument,
+        offset: usize,
+    ) -> Option<(usize, &'a SyntaxNode)> {
+        let node = document
+            .node_at(offset)
+            .filter(|node| node.kind == SyntaxKind::Atom)?;
+        let index = DependencyAnalyzer::new()
+            .find_definition(&document.elements, node.text(&document.source))?;
+        Some((index, node))
+    }
+}
+
+impl Default for LanguageServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 由顶层定义构建补全上下文 / Build the completion context from top-level definitions
+fn generation_context(elements: &[GrammarElement]) -> GenerationContext {
+    let mut context = GenerationContext {
+        variables: Vec::new(),
+        functions: Vec::new(),
+        recent_patterns: Vec::new(),
+        intent: None,
+    };
+    for element in elements {
+        let GrammarElement::List(list) = element else {
+            continue;
+        };
+        let (Some(GrammarElement::Atom(head)), Some(name)) =
+            (list.first(), list.get(1).and_then(element_name))
+        else {
+            continue;
+        };
+        match head.as_str() {
+            "def" | "function" | "defn-" => context.functions.push(name),
+            "let" | "defconst" => context.variables.push(name),
+            _ => {}
+        }
+    }
+    context
+}
+
+/// 包含偏移的最内层列表节点 / Innermost list node containing the offset
+fn innermost_list(node: &SyntaxNode, offset: usize) -> Option<&SyntaxNode> {
+    if !node.span.contains(offset) {
+        return None;
+    }
+    node.children
+        .iter()
+        .find_map(|child| innermost_list(child, offset))
+        .or((node.kind == SyntaxKind::List).then_some(node))
+}
+
+/// 定义形式中的名称节点，缺失时为整个形式 / Name node of a definition form, or the whole form when missing
+fn definition_name(node: &SyntaxNode) -> &SyntaxNode {
+    node.children.get(1).unwrap_or(node)
+}
+
+/// 是否可出现在符号中 / Whether the character may appear in a symbol
+fn is_symbol_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | '\'' | '"' | ';')
+}
+
+/// 构建诊断，`kind` 记录在 `data` 中供快速修复使用
+/// Build a diagnostic; `kind` is kept in `data` for quick fixes
+fn diagnostic(
+    source: &str,
+    node: Option<&SyntaxNode>,
+    severity: u8,
+    message: &str,
+    kind: &str,
+) -> Json {
+    let range = match node {
+        Some(node) => range(source, node),
+        None => json!({
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": 0, "character": 0 },
+        }),
+    };
+    json!({
+        "range": range,
+        "severity": severity,
+        "source": "evo",
+        "message": message,
+        "data": { "kind": kind },
+    })
+}
+
+/// 解析错误的诊断，定位到出错的字符 / Diagnostic for a parse error, placed at the offending character
+fn parse_diagnostic(source: &str, error: &ParseError) -> Json {
+    // 多数解析错误不带位置，缺少右括号时错误在文档末尾
+    // Most parse errors carry no location; a missing closing parenthesis errs at the end of the document
+    let start = match error.location() {
+        Some(location) => location_to_offset(source, location),
+        None if error.message().contains("EOF") => source.trim_end().len(),
+        None => 0,
+    };
+    let end = source[start..]
+        .chars()
+        .next()
+        .map_or(start, |c| start + c.len_utf8());
+    json!({
+        "range": {
+            "start": offset_to_position(source, start),
+            "end": offset_to_position(source, end),
+        },
+        "severity": SEVERITY_ERROR,
+        "source": "evo",
+        "message": error.to_string(),
+        "data": { "kind": "SyntaxError" },
+    })
+}
+
+/// 节点的 LSP 范围 / LSP range of a node
+fn range(source: &str, node: &SyntaxNode) -> Json {
+    json!({
+        "start": offset_to_position(source, node.span.start),
+        "end": offset_to_position(source, node.span.end),
+    })
+}
+
+/// LSP 位置（行号、UTF-16 列号，均从0开始）转字节偏移
+/// LSP position (line and UTF-16 column, both 0-based) to byte offset
+fn position_to_offset(source: &str, position: &Json) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let Some(line_start) = line_start(source, line) else {
+        return source.len();
+    };
+    let mut units = 0;
+    for (index, c) in source[line_start..].char_indices() {
+        if c == '\n' || units >= character {
+            return line_start + index;
+        }
+        units += c.len_utf16();
+    }
+    source.len()
+}
+
+/// 字节偏移转 LSP 位置 / Byte offset to LSP position
+fn offset_to_position(source: &str, offset: usize) -> Json {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let character = before[line_start..].encode_utf16().count();
+    json!({ "line": line, "character": character })
+}
+
+/// 解析器位置（从1开始的行号和字符列号）转字节偏移
+/// Parser location (1-based line and character column) to byte offset
+fn location_to_offset(source: &str, location: Location) -> usize {
+    let Some(line_start) = line_start(source, location.line.saturating_sub(1)) else {
+        return source.len();
+    };
+    source[line_start..]
+        .char_indices()
+        .nth(location.column.saturating_sub(1))
+        .map_or(source.len(), |(index, _)| line_start + index)
+}
+
+/// 字节偏移转解析器位置 / Byte offset to parser location
+fn offset_to_location(source: &str, offset: usize) -> Location {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Location::new(
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// 第 `line` 行（从0开始）的起始字节偏移 / Start byte offset of line `line` (0-based)
+fn line_start(source: &str, line: usize) -> Option<usize> {
+    if line == 0 {
+        return Some(0);
+    }
+    source
+        .match_indices('\n')
+        .nth(line - 1)
+        .map(|(index, _)| index + 1)
+}
+
+/// 通知消息 / Notification message
+fn notification(method: &str, params: Json) -> Json {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+/// 错误响应 / Error response
+fn error_response(id: Json, code: i64, message: &str) -> Json {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// 读取一条带 `Content-Length` 头的消息，输入结束时返回 `None`
+/// Read one message framed by a `Content-Length` header; `None` at the end of input
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body = vec![0; length.unwrap_or(0)];
+    input.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+/// 写出一条消息 / Write one message
+fn write_message<W: Write>(output: &mut W, message: &Json) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
@@ -6,12 +6,12 @@
 
 mod evolution;
 mod grammar;
+mod optimizer;
 mod parser;
 mod poetry;
-mod python;
 mod runtime;
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use evolution::*;
 use grammar::*;
 use parser::*;
@@ -43,15 +43,236 @@ enum Commands {
         /// 进化迭代次数 / Evolution iterations
         #[arg(short, long, default_value = "10")]
         iterations: usize,
+        /// 回归语料TOML，每次自我进化后运行，通过率低于阈值时回滚 / Regression corpus TOML run after each self-evolution, rolling back below its threshold
+        #[arg(long, value_name = "FILE")]
+        regression: Option<PathBuf>,
     },
     /// 运行Evo-lang文件 / Run Evo-lang file
     Run {
         /// 要运行的.evo文件路径 / Path to .evo file to run
         #[arg(value_name = "FILE")]
         file: PathBuf,
+        /// 插桩执行，结束后打印每个函数的行、分支和表达式覆盖率 / Run instrumented and print per-function line, branch and expression coverage
+        #[arg(long)]
+        coverage: bool,
+    },
+    /// 监视文件，每次保存后重新解析、检查并运行 / Watch a file and re-parse, check and run it on every save
+    Watch {
+        /// 要监视的.evo文件路径 / Path to the .evo file to watch
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+        /// 把每次运行的构造、错误和成功记录到进化引擎的学习模块 / Feed each run's constructs, errors and successes into the evolution engine's learning module
+        #[arg(long)]
+        learn: bool,
     },
     /// 交互式REPL / Interactive REPL
     Repl,
+    /// 格式化Evo-lang文件 / Format Evo-lang file
+    Fmt {
+        /// 要格式化的.evo文件路径 / Path to .evo file to format
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+        /// 最大行宽 / Maximum line width
+        #[arg(short, long, default_value = "80")]
+        width: usize,
+        /// 写回文件而不是输出到标准输出 / Write back to the file instead of stdout
+        #[arg(long)]
+        write: bool,
+    },
+    /// 为文件中的函数生成可运行的测试文件 / Generate a runnable test file for the functions in a file
+    GenTests {
+        /// 被测的.evo文件路径 / Path to the .evo file under test
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+        /// 测试文件输出路径，默认输出到标准输出 / Output path of the test file; defaults to stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// 随机输入的种子 / Seed of the random inputs
+        #[arg(long, default_value = "0")]
+        seed: u64,
+        /// 每个函数的性质测试数 / Property tests per function
+        #[arg(long, default_value = "5")]
+        cases: usize,
+    },
+    /// 对Evo-lang文件进行类型检查；找到 review.toml 时按其配置审查代码
+    /// Type-check Evo-lang file; when a review.toml is found, review the code with its configuration
+    Check {
+        /// 要检查的.evo文件路径 / Path to .evo file to check
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+        /// 审查配置，默认从文件所在目录向上查找 review.toml / Review configuration; defaults to the nearest review.toml above the file
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// 输出格式；json 和 sarif 输出分析、质量和审查结果 / Output format; json and sarif emit analysis, quality and review results
+        #[arg(long, value_enum, default_value_t = ReportFormat::Text)]
+        format: ReportFormat,
+        /// 自动应用可修复的建议并写回文件，打印diff / Apply auto-fixable suggestions, write the file back and print the diff
+        #[arg(long)]
+        fix: bool,
+    },
+    /// 从入口文件沿 import 分析整个项目的模块和函数依赖 / Analyze module and function dependencies of a project by following imports from an entry file
+    Deps {
+        /// 入口.evo文件路径 / Path to the entry .evo file
+        #[arg(value_name = "FILE")]
+        file: PathBuf,
+        /// 输出格式 / Output format
+        #[arg(long, value_enum, default_value_t = GraphFormat::Text)]
+        format: GraphFormat,
+        /// 输出路径，默认输出到标准输出 / Output path; defaults to stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// 为目录（或单个文件）中的所有.evo文件生成HTML文档站点 / Generate an HTML documentation site for all .evo files in a directory (or a single file)
+    Doc {
+        /// 目录或.evo文件路径 / Path to a directory or .evo file
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+        /// 输出目录 / Output directory
+        #[arg(short, long, default_value = "doc")]
+        output: PathBuf,
+    },
+    /// 把进化事件目录导出为谱系报告：事件元数据、引入规则的差异和受影响的程序
+    /// Export an evolution events directory as a genealogy report: event metadata, diffs of the rules introduced
+    /// and the affected programs
+    Genealogy {
+        /// 进化事件目录（`evo evolve` 的输出）/ Evolution events directory (the output of `evo evolve`)
+        #[arg(value_name = "DIR", default_value = "evolution_events")]
+        events: PathBuf,
+        /// 输出路径 / Output path
+        #[arg(short, long, default_value = "genealogy.html")]
+        output: PathBuf,
+        /// 输出格式，默认按输出文件扩展名推断 / Output format; inferred from the output extension by default
+        #[arg(long, value_enum)]
+        format: Option<GenealogyFormatArg>,
+    },
+    /// 检测目录（或单个文件）中所有.evo文件之间的函数克隆 / Detect function clones across all .evo files in a directory (or a single file)
+    Clones {
+        /// 目录或.evo文件路径 / Path to a directory or .evo file
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+        /// 近似克隆的相似度阈值（0到1）/ Similarity threshold for near-miss clones (0 to 1)
+        #[arg(long, default_value_t = 0.7)]
+        threshold: f64,
+        /// 参与检测的函数至少需要的规范化词法单元数 / Minimum normalized tokens for a function to be compared
+        #[arg(long, default_value_t = 6)]
+        min_tokens: usize,
+        /// 以JSON格式输出 / Output as JSON
+        #[arg(long)]
+        json: bool,
+        /// 把单个文件中完全相同或改名的克隆提取为公共函数，经生成的测试验证后输出重写的程序（忽略 --threshold）
+        /// Extract exact and renamed clones in a single file into common functions and print the rewritten program
+        /// once generated tests verify it (--threshold is ignored)
+        #[arg(long)]
+        extract: bool,
+    },
+    /// 评估目录（或单个文件）的项目质量，记录历史并显示趋势 / Assess the project quality of a directory (or a single file), record the history and show the trend
+    Quality {
+        /// 目录或.evo文件路径 / Path to a directory or .evo file
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+        /// 质量历史的JSON文件，默认为目录中的 .evo-quality.json / JSON file of the quality history; defaults to .evo-quality.json in the directory
+        #[arg(long)]
+        store: Option<PathBuf>,
+        /// 趋势统计最近的天数 / Number of recent days covered by the trend
+        #[arg(long, default_value_t = 30)]
+        days: i64,
+        /// 以JSON格式输出 / Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// 分析整个项目目录：分析、质量、审查、依赖和克隆汇总为一份报告，未达阈值时以非零状态退出
+    /// Analyze a whole project directory: analysis, quality, review, dependencies and clones in one report, exiting
+    /// nonzero when a threshold is not met
+    Analyze {
+        /// 目录或.evo文件路径 / Path to a directory or .evo file
+        #[arg(value_name = "PATH")]
+        path: PathBuf,
+        /// 审查配置，默认从目录向上查找 review.toml / Review configuration; defaults to the nearest review.toml above the directory
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// 达到该严重程度的审查问题使分析失败，默认取审查配置的 fail_on / Review issues at this severity fail the analysis; defaults to fail_on of the review configuration
+        #[arg(long, value_enum)]
+        fail_on: Option<SeverityArg>,
+        /// 项目质量分数下限 / Minimum project quality score
+        #[arg(long)]
+        min_quality: Option<f64>,
+        /// 克隆簇数量上限 / Maximum number of clone clusters
+        #[arg(long)]
+        max_clones: Option<usize>,
+        /// 允许循环导入 / Allow circular imports
+        #[arg(long)]
+        allow_cycles: bool,
+        /// 允许无法解析的文件 / Allow files that fail to parse
+        #[arg(long)]
+        allow_parse_errors: bool,
+        /// 以JSON格式输出 / Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// `evo genealogy` 的输出格式 / Output format of `evo genealogy`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GenealogyFormatArg {
+    /// 可交互的HTML页面 / Interactive HTML page
+    Html,
+    /// Graphviz DOT
+    Dot,
+}
+
+impl From<GenealogyFormatArg> for GenealogyFormat {
+    fn from(format: GenealogyFormatArg) -> Self {
+        match format {
+            GenealogyFormatArg::Html => GenealogyFormat::Html,
+            GenealogyFormatArg::Dot => GenealogyFormat::Dot,
+        }
+    }
+}
+
+/// `evo analyze --fail-on` 的严重程度 / Severity of `evo analyze --fail-on`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SeverityArg {
+    /// 信息 / Info
+    Info,
+    /// 警告 / Warning
+    Warning,
+    /// 错误 / Error
+    Error,
+    /// 严重 / Critical
+    Critical,
+}
+
+impl From<SeverityArg> for ReviewSeverity {
+    fn from(severity: SeverityArg) -> Self {
+        match severity {
+            SeverityArg::Info => ReviewSeverity::Info,
+            SeverityArg::Warning => ReviewSeverity::Warning,
+            SeverityArg::Error => ReviewSeverity::Error,
+            SeverityArg::Critical => ReviewSeverity::Critical,
+        }
+    }
+}
+
+/// `evo deps` 的输出格式 / Output format of `evo deps`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum GraphFormat {
+    /// 文本 / Text
+    Text,
+    /// JSON
+    Json,
+    /// Graphviz DOT
+    Dot,
+}
+
+/// `evo check` 的输出格式 / Output format of `evo check`
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ReportFormat {
+    /// 文本 / Text
+    Text,
+    /// JSON
+    Json,
+    /// SARIF 2.1.0
+    Sarif,
 }
 
 fn main() {
@@ -62,15 +283,101 @@ fn main() {
             output,
             prompt,
             iterations,
+            regression,
         }) => {
-            run_evolution_mode(&output, &prompt, iterations);
+            run_evolution_mode(&output, &prompt, iterations, regression.as_deref());
         }
-        Some(Commands::Run { file }) => {
-            run_file(&file);
+        Some(Commands::Run { file, coverage }) => {
+            run_file(&file, coverage);
+        }
+        Some(Commands::Watch { file, learn }) => {
+            watch_file(&file, learn);
         }
         Some(Commands::Repl) => {
             run_repl();
         }
+        Some(Commands::Fmt { file, width, write }) => {
+            format_file(&file, width, write);
+        }
+        Some(Commands::GenTests {
+            file,
+            output,
+            seed,
+            cases,
+        }) => {
+            generate_test_file(&file, output.as_deref(), seed, cases);
+        }
+        Some(Commands::Check {
+            file,
+            config,
+            format,
+            fix,
+        }) => {
+            check_file(&file, config.as_deref(), format, fix);
+        }
+        Some(Commands::Deps {
+            file,
+            format,
+            output,
+        }) => {
+            analyze_project_dependencies(&file, format, output.as_deref());
+        }
+        Some(Commands::Doc { path, output }) => {
+            generate_doc_site(&path, &output);
+        }
+        Some(Commands::Genealogy {
+            events,
+            output,
+            format,
+        }) => {
+            export_genealogy_report(&events, &output, format);
+        }
+        Some(Commands::Clones {
+            path,
+            threshold,
+            min_tokens,
+            json,
+            extract,
+        }) => {
+            if extract {
+                extract_clones(&path, min_tokens);
+            } else {
+                detect_project_clones(&path, threshold, min_tokens, json);
+            }
+        }
+        Some(Commands::Quality {
+            path,
+            store,
+            days,
+            json,
+        }) => {
+            assess_project_quality(&path, store, days, json);
+        }
+        Some(Commands::Analyze {
+            path,
+            config,
+            fail_on,
+            min_quality,
+            max_clones,
+            allow_cycles,
+            allow_parse_errors,
+            json,
+        }) => {
+            let thresholds = ProjectThresholds {
+                min_quality,
+                max_clone_clusters: max_clones,
+                allow_circular_dependencies: allow_cycles,
+                allow_parse_failures: allow_parse_errors,
+                ..ProjectThresholds::default()
+            };
+            analyze_project(
+                &path,
+                config.as_deref(),
+                fail_on.map(ReviewSeverity::from),
+                thresholds,
+                json,
+            );
+        }
         Some(Commands::Demo) | None => {
             run_demo();
         }
@@ -292,6 +599,33 @@ fn demonstrate_parser() {
             }
         }
     }
+
+    // 增量解析：只重新解析被编辑的顶层形式
+    println!("\n增量解析 / Incremental Parsing:");
+    let source = "(def add (x y) (+ x y))\n(add 1 2)";
+    if let Ok(document) = parser.parse_document(source) {
+        let edit = source.find("2)").unwrap_or(0);
+        match parser.parse_incremental(&document, edit..edit + 1, "40") {
+            Ok(updated) => {
+                println!("编辑后 / After edit: {}", updated.source.replace('\n', " "));
+                for node in &updated.nodes {
+                    println!(
+                        "  {}..{} {}",
+                        node.span.start,
+                        node.span.end,
+                        node.text(&updated.source)
+                    );
+                }
+                if let Some(node) = updated.node_at(edit) {
+                    println!(
+                        "光标处节点 / Node at cursor: {:?}",
+                        node.text(&updated.source)
+                    );
+                }
+            }
+            Err(e) => println!("解析错误 / Parse Error: {:?}", e),
+        }
+    }
 }
 
 /// 打印AST / Print AST
@@ -368,6 +702,23 @@ fn demonstrate_poetry_understanding() {
                     img.element, img.meaning, img.frequency
                 );
             }
+            let form = &analysis.form;
+            println!("\n诗体 / Form: {}", form.form.name());
+            println!(
+                "韵式 / Rhyme scheme: {} (韵部: {})",
+                form.rhyme_scheme,
+                form.rhyme.as_deref().unwrap_or("-")
+            );
+            if let (Some(pattern), Some(compliance)) = (&form.pattern, form.tonal_compliance) {
+                println!(
+                    "格律 / Pattern: {} (合律 {:.0}%)",
+                    pattern,
+                    compliance * 100.0
+                );
+            }
+            for (line, tones) in form.lines.iter().zip(&form.tone_patterns) {
+                println!("  {}  {}", line, tones);
+            }
 
             // 演示从诗歌生成代码 / Demonstrate code generation from poetry
             println!("\n从诗歌生成代码 / Code Generation from Poetry:");
@@ -416,6 +767,16 @@ fn demonstrate_grammar_definition() {
     );
     println!("\n自描述代码 / Self-describing Code:");
     println!("{}", syntax_def_rule.self_describing_code);
+
+    // 从自描述代码读回规则 / Read the rule back from its self-describing code
+    match parse_self_description(&syntax_def_rule.self_describing_code) {
+        Ok(rule) => println!(
+            "读回规则 / Rule read back: {} ({} 个模式元素 / pattern elements)",
+            rule.name,
+            rule.pattern.elements.len()
+        ),
+        Err(e) => println!("读回失败 / Failed to read back: {}", e),
+    }
 }
 
 /// 演示进化引擎 / Demonstrate evolution engine
@@ -432,6 +793,28 @@ fn demonstrate_evolution_engine() {
         engine.get_syntax_rules().len()
     );
 
+    // 自举规则改变解析结果 / Bootstrap rules change what programs parse
+    let evolved_code = "(变量 area (3 乘 4))";
+    match engine.create_parser().parse(evolved_code) {
+        Ok(ast) => println!(
+            "进化语法 / Evolved syntax: {} => {}",
+            evolved_code,
+            unparse(&ast)
+        ),
+        Err(e) => println!("进化语法解析失败 / Evolved syntax failed: {}", e),
+    }
+    let unknown_code = "(定义 double (x) (x plus x))";
+    let plain_parser = AdaptiveParser::new(true);
+    if let Ok(ast) = plain_parser.parse(unknown_code) {
+        let proposals = plain_parser.propose_syntax_expansion(&ast);
+        let names: Vec<&str> = proposals.iter().map(|rule| rule.name.as_str()).collect();
+        println!(
+            "语法扩展提议 / Proposed syntax for {}: {}",
+            unknown_code,
+            names.join(", ")
+        );
+    }
+
     // 知识图谱统计 / Knowledge graph statistics
     let stats = engine.get_knowledge_stats();
     println!(
@@ -638,7 +1021,10 @@ fn demonstrate_nlu() {
         println!("自然语言 / Natural Language: {}", input);
 
         match nlu_parser.parse(input) {
-            Ok(parsed_intent) => {
+            Ok(ParseOutcome::NeedsClarification { question, .. }) => {
+                println!("需要澄清 / Needs Clarification: {}", question);
+            }
+            Ok(ParseOutcome::Parsed(parsed_intent)) => {
                 println!(
                     "识别意图 / Detected Intent: {:?}",
                     parsed_intent.intent_type
@@ -697,183 +1083,34 @@ fn demonstrate_nlu() {
             }
         }
     }
-}
-
-/// 将代码结构转换为可执行代码（简化版本）
-/// Convert code structure to executable code (simplified version)
-fn convert_to_executable(elements: &[crate::grammar::core::GrammarElement]) -> Option<String> {
-    if elements.is_empty() {
-        return None;
-    }
 
-    // 简化实现：只处理第一个元素
-    match &elements[0] {
-        crate::grammar::core::GrammarElement::List(list) => {
-            let mut result = String::from("(");
-            for (i, elem) in list.iter().enumerate() {
-                if i > 0 {
-                    result.push(' ');
-                }
-                match elem {
-                    crate::grammar::core::GrammarElement::Atom(s) => {
-                        result.push_str(s);
-                    }
-                    crate::grammar::core::GrammarElement::List(l) => {
-                        result.push('(');
-                        for (j, sub_elem) in l.iter().enumerate() {
-                            if j > 0 {
-                                result.push(' ');
-                            }
-                            if let crate::grammar::core::GrammarElement::Atom(s) = sub_elem {
-                                result.push_str(s);
-                            }
-                        }
-                        result.push(')');
-                    }
-                    crate::grammar::core::GrammarElement::Expr(e) => {
-                        result.push_str(&format_expr(e));
-                    }
-                    _ => {}
-                }
-            }
-            result.push(')');
-            Some(result)
+    // 演示加载其他语言的短语表
+    println!("\n--- 多语言短语表演示 / Multi-language Phrase Table Demo ---");
+    let mut multilingual = NLUParser::new_rule_based();
+    for locale in ["ja", "es"] {
+        if let Some(table) = PhraseTable::builtin(locale) {
+            multilingual.add_locale(locale, table);
         }
-        crate::grammar::core::GrammarElement::Expr(e) => Some(format_expr(e)),
-        _ => None,
     }
-}
-
-/// 格式化字面量为字符串
-/// Format literal as string
-fn format_literal(lit: &crate::grammar::core::Literal) -> String {
-    match lit {
-        crate::grammar::core::Literal::Int(i) => i.to_string(),
-        crate::grammar::core::Literal::Float(f) => f.to_string(),
-        crate::grammar::core::Literal::String(s) => format!("\"{}\"", s),
-        crate::grammar::core::Literal::Bool(b) => b.to_string(),
-        crate::grammar::core::Literal::Null => "null".to_string(),
-        crate::grammar::core::Literal::List(items) => {
-            let items_str: Vec<String> = items.iter().map(|item| format_expr(item)).collect();
-            format!("[{}]", items_str.join(", "))
-        }
-        crate::grammar::core::Literal::Dict(pairs) => {
-            let pairs_str: Vec<String> = pairs
-                .iter()
-                .map(|(k, v)| format!("\"{}\": {}", k, format_expr(v)))
-                .collect();
-            format!("{{{}}}", pairs_str.join(", "))
+    println!("已加载语言 / Loaded locales: {:?}", multilingual.locales());
+    for input in [
+        "関数add、引数はxとy、戻り値はxたすy",
+        "define una función suma con parámetros x, y que devuelve x más y",
+        "si 5 mayor que 3 entonces 10 sino 20",
+    ] {
+        match multilingual.parse_intent(input) {
+            Ok(parsed) => println!("{} => {}", input, unparse(&parsed.code_structure)),
+            Err(e) => println!("{} => NLU解析错误 / NLU Parse Error: {:?}", input, e),
         }
     }
 }
 
-/// 格式化表达式为字符串
-/// Format expression as string
-fn format_expr(expr: &crate::grammar::core::Expr) -> String {
-    match expr {
-        crate::grammar::core::Expr::Literal(lit) => format_literal(lit),
-        crate::grammar::core::Expr::Var(v) => v.clone(),
-        crate::grammar::core::Expr::Call(name, args) => {
-            let mut result = format!("({}", name);
-            for arg in args {
-                result.push(' ');
-                result.push_str(&format_expr(arg));
-            }
-            result.push(')');
-            result
-        }
-        crate::grammar::core::Expr::Binary(op, left, right) => {
-            let op_str = match op {
-                crate::grammar::core::BinOp::Add => "+",
-                crate::grammar::core::BinOp::Sub => "-",
-                crate::grammar::core::BinOp::Mul => "*",
-                crate::grammar::core::BinOp::Div => "/",
-                crate::grammar::core::BinOp::Mod => "%",
-                crate::grammar::core::BinOp::Eq => "=",
-                crate::grammar::core::BinOp::Ne => "!=",
-                crate::grammar::core::BinOp::Lt => "<",
-                crate::grammar::core::BinOp::Gt => ">",
-                crate::grammar::core::BinOp::Le => "<=",
-                crate::grammar::core::BinOp::Ge => ">=",
-            };
-            format!("({} {} {})", op_str, format_expr(left), format_expr(right))
-        }
-        crate::grammar::core::Expr::If(cond, then_expr, else_expr) => {
-            format!(
-                "(if {} {} {})",
-                format_expr(cond),
-                format_expr(then_expr),
-                format_expr(else_expr)
-            )
-        }
-        crate::grammar::core::Expr::Match(value, cases) => {
-            let mut result = format!("(match {}", format_expr(value));
-            for (pattern, expr) in cases {
-                result.push_str(&format!(" ({:?} {})", pattern, format_expr(expr)));
-            }
-            result.push(')');
-            result
-        }
-        crate::grammar::core::Expr::For {
-            var,
-            iterable,
-            body,
-        } => {
-            format!(
-                "(for {} {} {})",
-                var,
-                format_expr(iterable),
-                format_expr(body)
-            )
-        }
-        crate::grammar::core::Expr::While { condition, body } => {
-            format!("(while {} {})", format_expr(condition), format_expr(body))
-        }
-        crate::grammar::core::Expr::Try {
-            try_body,
-            catch_var,
-            catch_body,
-        } => {
-            if let Some(var) = catch_var {
-                format!(
-                    "(try {} (catch {} {}))",
-                    format_expr(try_body),
-                    var,
-                    format_expr(catch_body)
-                )
-            } else {
-                format!(
-                    "(try {} {})",
-                    format_expr(try_body),
-                    format_expr(catch_body)
-                )
-            }
-        }
-        crate::grammar::core::Expr::Lambda { params, body } => {
-            let mut result = "(lambda (".to_string();
-            for (i, param) in params.iter().enumerate() {
-                if i > 0 {
-                    result.push(' ');
-                }
-                result.push_str(param);
-            }
-            result.push_str(") ");
-            result.push_str(&format_expr(body));
-            result.push(')');
-            result
-        }
-        crate::grammar::core::Expr::Begin(exprs) => {
-            let mut result = "(begin".to_string();
-            for expr in exprs {
-                result.push(' ');
-                result.push_str(&format_expr(expr));
-            }
-            result.push(')');
-            result
-        }
-        crate::grammar::core::Expr::Assign(var, expr) => {
-            format!("(set! {} {})", var, format_expr(expr))
-        }
+/// 将代码结构转换为可执行代码（只取第一个元素）
+/// Convert code structure to executable code (first element only)
+fn convert_to_executable(elements: &[crate::grammar::core::GrammarElement]) -> Option<String> {
+    match elements.first()? {
+        crate::grammar::core::GrammarElement::NaturalLang(_) => None,
+        element => Some(unparse_element(element)),
     }
 }
 
@@ -952,6 +1189,12 @@ fn demonstrate_jit() {
         stats.compilation_threshold
     );
     println!("JIT启用状态 / JIT enabled: {}", stats.enabled);
+    println!(
+        "调用缓存命中 / Call cache hits: {}/{} ({:.1}%)",
+        stats.call_cache.hits,
+        stats.call_cache.hits + stats.call_cache.misses,
+        stats.call_cache.hit_rate() * 100.0
+    );
 
     // 显示热点代码列表
     // Display hot spot code list
@@ -1363,8 +1606,9 @@ fn demonstrate_code_refactoring() {
             let refactored = engine.refactor_code(&ast);
 
             println!("\n重构后的代码 / Refactored Code:");
-            // 简化显示：显示重构后的AST结构 / Simplified display: show refactored AST structure
-            println!("  重构完成，代码已优化 / Refactoring complete, code optimized");
+            for line in unparse(&refactored).lines() {
+                println!("  {}", line);
+            }
             println!("  原始AST元素数 / Original AST elements: {}", ast.len());
             println!(
                 "  重构后AST元素数 / Refactored AST elements: {}",
@@ -1379,11 +1623,7 @@ fn demonstrate_code_refactoring() {
             match parser.parse(constant_code) {
                 Ok(constant_ast) => {
                     let refactored_const = engine.refactor_code(&constant_ast);
-                    println!(
-                        "  重构后 / Refactored: {} 个元素 / {} elements",
-                        refactored_const.len(),
-                        refactored_const.len()
-                    );
+                    println!("  重构后 / Refactored: {}", unparse(&refactored_const));
                 }
                 Err(e) => {
                     println!("  解析错误 / Parse error: {:?}", e);
@@ -1459,18 +1699,25 @@ fn demonstrate_context_understanding() {
         ("上面的x加上3", "第二轮：引用之前的变量"),
         ("定义函数add，参数是x和y，返回x加y", "第三轮：定义函数"),
         ("调用上面的add函数，参数是2和3", "第四轮：引用之前的函数"),
+        ("x 和 y", "第五轮：意图不明确"),
+        ("2", "第六轮：回答澄清问题"),
     ];
 
     for (input, description) in conversations {
         println!("\n{} / {}", description, description);
         println!("输入 / Input: {}", input);
 
-        // 解析输入 / Parse input
-        match nlu_parser.parse(input) {
-            Ok(intent) => {
-                // 添加上下文 / Add context
-                let turn_id = context.add_turn(input.to_string(), Some(intent.clone()));
-                println!("  解析成功 / Parse success, turn ID: {}", turn_id);
+        // 解析输入并记入上下文 / Parse input and record it in the context
+        match context.parse_turn(&nlu_parser, input) {
+            Ok(ParseOutcome::NeedsClarification { question, .. }) => {
+                println!("  需要澄清 / Needs clarification: {}", question);
+            }
+            Ok(ParseOutcome::Parsed(intent)) => {
+                let turn_id = context.get_history().len() - 1;
+                println!(
+                    "  解析成功 / Parse success, turn ID: {}, intent: {:?}",
+                    turn_id, intent.intent_type
+                );
 
                 // 使用上下文解析 / Parse with context
                 match context.parse_with_context(input) {
@@ -1512,6 +1759,39 @@ fn demonstrate_context_understanding() {
         );
     }
 
+    // 把对话生成的代码和意图对应起来 / Align the code generated by the conversation with its intents
+    println!("\n代码与意图对照 / Code-to-intent alignment:");
+    let source = "(def add (x y) (+ x y))\n(print (add 2 3))";
+    match parser.parse_document(source) {
+        Ok(document) => {
+            let explainer = crate::parser::CodeExplainer::new(crate::parser::Language::Chinese);
+            for explanation in explainer.explain_document(&document, Some(&context)) {
+                println!("{}", explanation.render());
+            }
+        }
+        Err(e) => println!("  解析错误 / Parse error: {:?}", e),
+    }
+
+    // 对话累积的用户画像 / User profile accumulated from the conversation
+    let profile = context.profile();
+    println!("\n用户画像 / User profile:");
+    println!(
+        "  命名风格 / Naming style: {:?}",
+        profile.preferred_naming_style()
+    );
+    println!(
+        "  常用参数 / Usual parameters: {:?}",
+        profile.default_parameters()
+    );
+    println!(
+        "  常用函数 / Frequent functions: {:?}",
+        profile.frequent_functions(3)
+    );
+    println!(
+        "  常见错误 / Common mistakes: {:?}",
+        profile.common_mistakes(3)
+    );
+
     println!(
         "\n提示 / Note: 上下文理解功能让语言能够理解多轮对话，记住之前的上下文，实现更自然的交互"
     );
@@ -1578,29 +1858,31 @@ fn demonstrate_usage_learning() {
 
     let mut engine = EvolutionEngine::new();
 
-    // 模拟使用模式 / Simulate usage patterns
-    println!("模拟使用模式 / Simulating Usage Patterns:");
-    engine.record_usage("变量定义");
-    engine.record_usage("函数调用");
-    engine.record_usage("变量定义");
-    engine.record_usage("函数定义");
-    engine.record_usage("变量定义");
-    engine.record_usage("列表操作");
-    engine.record_usage("变量定义");
-
-    // 模拟错误记录 / Simulate error recording
-    println!("\n记录错误 / Recording Errors:");
-    engine.record_error("UndefinedVariable", "变量x未定义", "(let y (+ x 1))");
-    engine.record_error("UndefinedVariable", "变量y未定义", "(let z (+ y 1))");
-    engine.record_error("TypeError", "类型不匹配", "(+ \"hello\" 5)");
-    engine.record_error("UndefinedVariable", "变量x未定义", "(let y (+ x 1))");
-
-    // 模拟成功记录 / Simulate success recording
-    println!("\n记录成功 / Recording Successes:");
-    engine.record_success("变量定义", "(let x 5)");
-    engine.record_success("函数定义", "(def add (x y) (+ x y))");
-    engine.record_success("变量定义", "(let x 5)");
-    engine.record_success("列表求和", "(sum (list 1 2 3))");
+    // 解释器自动向学习模块报告执行过的构造、运行时错误和成功模式
+    // The interpreter reports executed constructs, runtime errors and successful patterns to the learning module
+    println!("运行程序并收集使用情况 / Running programs and collecting usage:");
+    let mut interpreter = Interpreter::with_learning(engine.learning_handle());
+    let parser = AdaptiveParser::new(true);
+    let programs = [
+        "(let x 5)",
+        "(def add (x y) (+ x y))",
+        "(add x 2)",
+        "(let y (+ z 1))",
+        "(+ \"hello\" 5)",
+        "(let z (+ w 1))",
+        "(if (> x 3) (add x 1) 0)",
+        "(let total (add 1 (add 2 3)))",
+    ];
+    for code in programs {
+        let outcome = match parser.parse(code) {
+            Ok(ast) => match interpreter.execute(&ast) {
+                Ok(value) => format!("{:?}", value),
+                Err(e) => format!("{} ({})", e, e.kind()),
+            },
+            Err(e) => format!("{:?}", e),
+        };
+        println!("  {} => {}", code, outcome);
+    }
 
     // 获取学习洞察 / Get learning insights
     println!("\n学习洞察 / Learning Insights:");
@@ -2159,6 +2441,7 @@ fn demonstrate_documentation_generation() {
 
     // 测试代码 / Test code
     let test_code = r#"
+        ;;; 两数相加 / Add two numbers
         (def add (x y) (+ x y))
         (def multiply (x y) (* x y))
         (let result (add 3 4))
@@ -2166,15 +2449,20 @@ fn demonstrate_documentation_generation() {
 
     println!("测试代码 / Test Code:\n{}", test_code);
 
-    match parser.parse(test_code) {
-        Ok(ast) => {
+    // 文档注释作为函数描述 / Doc comments become function descriptions
+    match parser.parse_with_comments(test_code) {
+        Ok((ast, comments)) => {
             // 分析代码 / Analyze code
             let analysis = analyzer.analyze(&ast);
 
             // 生成Markdown文档 / Generate Markdown documentation
             println!("\n生成Markdown文档 / Generating Markdown Documentation:");
-            let markdown_doc =
-                doc_generator.generate_documentation(&ast, &analysis, DocFormat::Markdown);
+            let markdown_doc = doc_generator.generate_documentation_with_comments(
+                &ast,
+                &comments,
+                &analysis,
+                DocFormat::Markdown,
+            );
 
             println!("\n生成的文档 / Generated Documentation:");
             println!("{}", markdown_doc.content);
@@ -2212,8 +2500,12 @@ fn demonstrate_documentation_generation() {
 
             // 生成纯文本文档 / Generate plain text documentation
             println!("\n生成纯文本文档 / Generating Plain Text Documentation:");
-            let plain_doc =
-                doc_generator.generate_documentation(&ast, &analysis, DocFormat::PlainText);
+            let plain_doc = doc_generator.generate_documentation_with_comments(
+                &ast,
+                &comments,
+                &analysis,
+                DocFormat::PlainText,
+            );
             println!("纯文本文档 / Plain Text Doc:\n{}", plain_doc.content);
         }
         Err(e) => {
@@ -2309,6 +2601,10 @@ fn demonstrate_test_generation() {
                 "  集成测试数 / Integration Tests: {}",
                 test_suite.statistics.integration_tests
             );
+            println!(
+                "  性质测试数 / Property Tests: {}",
+                test_suite.statistics.property_tests
+            );
 
             println!("\n测试覆盖率 / Test Coverage:");
             println!(
@@ -2340,31 +2636,22 @@ fn demonstrate_test_generation() {
                     println!("     描述 / Description: {}", test_case.description);
                     println!("     测试代码 / Test Code: {}", test_case.test_code);
                     println!("     预期结果 / Expected: {}", test_case.expected_result);
+                }
+            }
 
-                    // 尝试执行测试 / Try to execute test
-                    match parser.parse(&test_case.test_code) {
-                        Ok(test_ast) => match interpreter.execute(&test_ast) {
-                            Ok(result) => {
-                                let passed = result.to_string() == test_case.expected_result
-                                    || test_case.expected_result == "结果待验证";
-                                println!(
-                                    "     执行结果 / Result: {} ({})",
-                                    result,
-                                    if passed {
-                                        "通过 / Pass"
-                                    } else {
-                                        "失败 / Fail"
-                                    }
-                                );
-                            }
-                            Err(e) => {
-                                println!("     执行错误 / Execution Error: {:?}", e);
-                            }
-                        },
-                        Err(e) => {
-                            println!("     解析错误 / Parse Error: {:?}", e);
-                        }
+            // 运行生成的测试文件 / Run the generated test file
+            println!("\n运行生成的测试文件 / Running Generated Test File:");
+            match parser.parse(&test_suite.to_test_file(test_code)) {
+                Ok(test_ast) => match interpreter.execute(&test_ast) {
+                    Ok(result) => {
+                        println!("  全部通过 / All passed: {}", result);
+                    }
+                    Err(e) => {
+                        println!("  执行错误 / Execution Error: {:?}", e);
                     }
+                },
+                Err(e) => {
+                    println!("  解析错误 / Parse Error: {:?}", e);
                 }
             }
         }
@@ -2501,6 +2788,51 @@ fn demonstrate_performance_analysis() {
         }
     }
 
+    // 基于实测剖析的分析 / Analysis grounded in a measured profile
+    println!("\n实测剖析 / Measured Profile:");
+    let profiled_code = r#"
+        (def fib (n) (if (< n 2) n (+ (fib (- n 1)) (fib (- n 2)))))
+        (def add (x y) (+ x y))
+        (add (fib 16) 1)
+    "#;
+    if let Ok(ast) = parser.parse(profiled_code) {
+        use crate::runtime::{Interpreter, Profiler};
+
+        let profiler = Profiler::new();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_tracer(Box::new(profiler.clone()));
+        let started_at = std::time::Instant::now();
+        let result = interpreter.execute(&ast);
+        let mut report = profiler.report();
+        report.total_time = started_at.elapsed();
+
+        if let Ok(value) = result {
+            println!("  结果 / Result: {}", value);
+        }
+        for function in report.functions.iter().take(3) {
+            println!(
+                "  {} - 调用 / calls: {}, 总耗时 / total: {:.2} ms",
+                function.name,
+                function.calls,
+                function.total_time.as_secs_f64() * 1000.0
+            );
+        }
+        let performance =
+            performance_analyzer.analyze_with_profile(&ast, &analyzer.analyze(&ast), &report);
+        for bottleneck in &performance.bottlenecks {
+            println!(
+                "  瓶颈 / Bottleneck: {} - {}",
+                bottleneck.location, bottleneck.description
+            );
+        }
+        for suggestion in &performance.suggestions {
+            println!(
+                "  建议 / Suggestion: [{}] {}",
+                suggestion.suggestion_type, suggestion.content
+            );
+        }
+    }
+
     // 显示性能历史 / Show performance history
     println!("\n性能分析历史 / Performance Analysis History:");
     let history = performance_analyzer.get_performance_history();
@@ -2552,6 +2884,8 @@ fn demonstrate_similarity_detection() {
         (def multiply (x y) (* x y))
         (def add2 (a b) (+ a b))
         (def add3 (m n) (+ m n))
+        (def circle-area (r) (* 3.14 (* r r)))
+        (def square-area (s) (* 1.0 (* s s)))
     "#;
 
     println!("测试代码 / Test Code:\n{}", test_code);
@@ -2590,6 +2924,22 @@ fn demonstrate_similarity_detection() {
                 }
             }
 
+            if !similarity.clones.is_empty() {
+                println!("\n函数克隆 / Function Clones:");
+                for (i, cluster) in similarity.clones.iter().enumerate() {
+                    let locations: Vec<&str> = cluster
+                        .instances
+                        .iter()
+                        .map(|instance| instance.location.as_str())
+                        .collect();
+                    println!("  {}. {:?}: {:?}", i + 1, cluster.kind, locations);
+                    println!(
+                        "     建议提取 / Suggested extraction: {}",
+                        cluster.suggested_signature
+                    );
+                }
+            }
+
             if !similarity.suggestions.is_empty() {
                 println!("\n重构建议 / Refactoring Suggestions:");
                 for (i, suggestion) in similarity.suggestions.iter().enumerate() {
@@ -2778,7 +3128,7 @@ fn demonstrate_dependency_analysis() {
 }
 
 /// 运行Evo-lang文件 / Run Evo-lang file
-fn run_file(file_path: &PathBuf) {
+fn run_file(file_path: &PathBuf, coverage: bool) {
     use std::fs;
 
     // 读取文件 / Read file
@@ -2797,22 +3147,47 @@ fn run_file(file_path: &PathBuf) {
     // 创建解析器和解释器 / Create parser and interpreter
     let parser = AdaptiveParser::new(true);
     let mut interpreter = Interpreter::new();
+    // 相对导入以脚本所在目录为基准 / Relative imports resolve against the script's directory
+    if let Some(dir) = file_path.parent() {
+        interpreter.set_base_dir(dir);
+    }
 
     // 解析代码 / Parse code
-    match parser.parse(&code) {
-        Ok(ast) => {
-            // 执行代码 / Execute code
-            match interpreter.execute(&ast) {
-                Ok(value) => {
-                    println!("{}", value);
-                    // 强制刷新输出缓冲区 / Force flush output buffer
-                    use std::io::Write;
-                    std::io::stdout().flush().unwrap();
-                }
-                Err(e) => {
-                    eprintln!("执行错误 / Execution error: {:?}", e);
-                    std::process::exit(1);
+    match parser.parse_with_locations(&code) {
+        Ok((ast, locations)) => {
+            // 执行前进行类型检查 / Type-check before execution
+            let type_errors = TypeChecker::new().check_with_locations(&ast, &locations);
+            if !type_errors.is_empty() {
+                report_type_errors(&type_errors);
+                std::process::exit(1);
+            }
+
+            // 插桩执行需要语法树来确定行号 / Instrumented runs need the syntax tree for line numbers
+            let (ast, coverage_map) = match coverage.then(|| parser.parse_document(&code)) {
+                Some(Ok(document)) => {
+                    let (ast, map) = CoverageMap::instrument_document(&document);
+                    interpreter.enable_coverage();
+                    (ast, Some(map))
                 }
+                _ => (ast, None),
+            };
+
+            // 执行代码 / Execute code
+            let result = interpreter.execute(&ast);
+            if let Ok(value) = &result {
+                println!("{}", value);
+            }
+            if let (Some(map), Some(hits)) = (coverage_map, interpreter.take_coverage()) {
+                println!("\n覆盖率 / Coverage:");
+                print!("{}", CoverageReport::new(&map, &hits).to_text());
+            }
+            // 强制刷新输出缓冲区 / Force flush output buffer
+            use std::io::Write;
+            std::io::stdout().flush().unwrap();
+            if let Err(e) = result {
+                let file = file_path.display().to_string();
+                report_execution_error(&parser, &interpreter, &e, &code, Some(&file));
+                std::process::exit(1);
             }
         }
         Err(e) => {
@@ -2822,55 +3197,942 @@ fn run_file(file_path: &PathBuf) {
     }
 }
 
-/// 运行进化模式 / Run evolution mode
-fn run_evolution_mode(output_dir: &PathBuf, prompt_file: &PathBuf, iterations: usize) {
-    println!("Evo-lang 进化模式 / Evolution Mode");
-    println!("============================================================");
-    println!("输出目录 / Output directory: {:?}", output_dir);
-    println!("Prompt文件 / Prompt file: {:?}", prompt_file);
-    println!("迭代次数 / Iterations: {}", iterations);
-    println!();
-
-    // 创建进化引擎 / Create evolution engine
-    let mut engine = EvolutionEngine::new();
+/// 监视文件：启动时运行一次，之后每次保存都重新运行 / Watch a file: run it once at startup and again after every save
+#[cfg(feature = "watch")]
+fn watch_file(file_path: &PathBuf, learn: bool) {
+    use notify::{EventKind, RecursiveMode, Watcher};
+    use std::sync::mpsc;
+    use std::time::Duration;
 
-    // 读取prompt文件获取目标 / Read prompt file to get goals
-    let goals = match read_goals_from_prompt(prompt_file) {
-        Ok(g) => {
-            println!("从prompt文件读取目标 / Goals from prompt file:");
-            for goal in &g {
-                println!("  - {}", goal);
-            }
-            println!();
-            g
-        }
+    let target = match std::fs::canonicalize(file_path) {
+        Ok(path) => path,
         Err(e) => {
             eprintln!(
-                "警告：无法读取prompt文件 / Warning: Cannot read prompt file: {}",
-                e
+                "错误：无法读取文件 / Error: Cannot read file: {:?}",
+                file_path
             );
-            vec!["自进化能力的持续完善".to_string()]
+            eprintln!("详细信息 / Details: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let dir = target
+        .parent()
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."));
+
+    let (tx, rx) = mpsc::channel();
+    let watcher = notify::recommended_watcher(tx).and_then(|mut watcher| {
+        // 监视所在目录：编辑器常以替换文件的方式保存 / Watch the directory: editors often save by replacing the file
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    });
+    let _watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            eprintln!("错误：无法监视文件 / Error: Cannot watch file: {}", e);
+            std::process::exit(1);
         }
     };
 
-    // 确保输出目录存在 / Ensure output directory exists
-    std::fs::create_dir_all(output_dir).expect("Failed to create output directory");
+    let mut engine = learn.then(EvolutionEngine::new);
+    let mut last = None;
+    println!(
+        "监视 {:?}，按 Ctrl-C 退出 / Watching {:?}, press Ctrl-C to quit",
+        file_path, file_path
+    );
+    watch_run(&target, &mut engine, &mut last);
+
+    while let Ok(event) = rx.recv() {
+        match event {
+            Ok(event)
+                if !matches!(event.kind, EventKind::Access(_))
+                    && event.paths.iter().any(|path| path == &target) =>
+            {
+                // 合并一次保存产生的连串事件 / Coalesce the burst of events a single save produces
+                while rx.recv_timeout(Duration::from_millis(100)).is_ok() {}
+                watch_run(&target, &mut engine, &mut last);
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("监视错误 / Watch error: {}", e),
+        }
+    }
+}
 
-    // 执行进化迭代 / Execute evolution iterations
-    for i in 1..=iterations {
-        println!("迭代 {} / Iteration {}: ", i, i);
+#[cfg(not(feature = "watch"))]
+fn watch_file(_file_path: &PathBuf, _learn: bool) {
+    eprintln!("错误：evo watch 需要 watch 特性 / Error: evo watch requires the watch feature (build with --features watch)");
+    std::process::exit(1);
+}
 
-        // 执行自我进化 / Perform self-evolution
-        match engine.self_evolve() {
-            Ok(result) => {
-                println!("  自我进化完成 / Self-evolution completed");
-                if let Some(improvements) = result.get("improvement_count") {
-                    println!("  改进数量 / Improvements: {}", improvements);
-                }
-            }
-            Err(e) => {
-                eprintln!("  自我进化错误 / Self-evolution error: {:?}", e);
-            }
+/// 重新解析、检查并运行被监视的文件；内容未变时跳过
+/// Re-parse, check and run the watched file, skipping runs where the content is unchanged
+#[cfg(feature = "watch")]
+fn watch_run(
+    file_path: &std::path::Path,
+    engine: &mut Option<EvolutionEngine>,
+    last: &mut Option<String>,
+) {
+    let code = match std::fs::read_to_string(file_path) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("错误：无法读取文件 / Error: Cannot read file: {}", e);
+            return;
+        }
+    };
+    if last.as_deref() == Some(code.as_str()) {
+        return;
+    }
+    println!(
+        "\n[{}] {:?}",
+        chrono::Local::now().format("%H:%M:%S"),
+        file_path
+    );
+
+    let parser = AdaptiveParser::new(true);
+    let (_, parse_errors) = parser.parse_recovering(&code);
+    let diagnostics = match parser.parse_with_locations(&code) {
+        Ok(_) if !parse_errors.is_empty() => Err(parse_errors
+            .iter()
+            .map(|error| ("ParseError", format!("解析错误 / Parse error: {}", error)))
+            .collect()),
+        Ok((ast, locations)) => {
+            let type_errors = TypeChecker::new().check_with_locations(&ast, &locations);
+            if type_errors.is_empty() {
+                Ok(ast)
+            } else {
+                Err(type_errors
+                    .iter()
+                    .map(|error| ("TypeError", format!("类型错误 / Type error: {}", error)))
+                    .collect::<Vec<_>>())
+            }
+        }
+        Err(e) => Err(vec![(
+            "ParseError",
+            format!("解析错误 / Parse error: {:?}", e),
+        )]),
+    };
+
+    match diagnostics {
+        Ok(ast) => {
+            let analysis = CodeAnalyzer::new().analyze(&ast);
+            for suggestion in &analysis.suggestions {
+                println!("建议 / Suggestion: {}", suggestion.description);
+            }
+
+            let mut interpreter = match engine {
+                Some(engine) => Interpreter::with_learning(engine.learning_handle()),
+                None => Interpreter::new(),
+            };
+            interpreter.set_base_dir(file_path.parent().unwrap_or(std::path::Path::new(".")));
+            match interpreter.execute(&ast) {
+                Ok(value) => println!("{}", value),
+                Err(e) => {
+                    let file = file_path.display().to_string();
+                    report_execution_error(&parser, &interpreter, &e, &code, Some(&file));
+                }
+            }
+        }
+        Err(errors) => {
+            for (kind, message) in &errors {
+                eprintln!("{}", message);
+                if let Some(engine) = engine {
+                    engine.record_error(kind, message, &code);
+                }
+            }
+        }
+    }
+    *last = Some(code);
+
+    if let Some(engine) = engine {
+        if let Ok(result) = engine.learn_from_usage() {
+            if result["learning_performed"] == true {
+                println!("从使用模式学习完成 / Learning from usage completed");
+            }
+        }
+        let stats = engine.get_usage_statistics();
+        println!(
+            "学习 / Learning: {} 次使用, {} 个错误 / {} uses, {} errors",
+            stats.total_usage, stats.total_errors, stats.total_usage, stats.total_errors
+        );
+        if !stats.failing_functions.is_empty() {
+            let functions: Vec<String> = stats
+                .failing_functions
+                .iter()
+                .map(|(function, count)| format!("{} ({})", function, count))
+                .collect();
+            println!(
+                "失败最多的函数 / Most failing functions: {}",
+                functions.join(", ")
+            );
+        }
+    }
+}
+
+/// 格式化文件 / Format file
+/// 类型检查文件 / Type-check file
+fn check_file(
+    file_path: &PathBuf,
+    config: Option<&std::path::Path>,
+    format: ReportFormat,
+    fix: bool,
+) {
+    let mut code = match std::fs::read_to_string(file_path) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!(
+                "错误：无法读取文件 / Error: Cannot read file: {:?}",
+                file_path
+            );
+            eprintln!("详细信息 / Details: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let config_path = config.map(PathBuf::from).or_else(|| {
+        let dir = std::fs::canonicalize(file_path)
+            .ok()?
+            .parent()?
+            .to_path_buf();
+        ReviewConfig::find(&dir)
+    });
+    if fix {
+        code = fix_file(&code, file_path, config_path.as_deref());
+    }
+
+    // 先报告全部语法错误 / Report every syntax error first
+    let (_, parse_errors) = AdaptiveParser::new(true).parse_recovering(&code);
+    if !parse_errors.is_empty() {
+        for error in &parse_errors {
+            eprintln!("解析错误 / Parse error: {}", error);
+        }
+        std::process::exit(1);
+    }
+
+    if config_path.is_some() || format != ReportFormat::Text {
+        review_file(&code, file_path, config_path.as_deref(), format);
+        return;
+    }
+
+    match TypeChecker::check_source(&code) {
+        Ok(errors) if errors.is_empty() => {
+            println!("✓ 没有类型错误 / No type errors");
+        }
+        Ok(errors) => {
+            report_type_errors(&errors);
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("解析错误 / Parse error: {:?}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// 按审查配置审查代码；类型错误由 type_safety 规则报告 / Review code with a review configuration; type errors come from the type_safety rule
+/// 应用语法恢复和审查建议中可自动修复的部分，写回文件并打印diff，返回修复后的源码
+/// Apply the auto-fixable parts of syntax recovery and review suggestions, write the file back,
+/// print the diff and return the fixed source
+fn fix_file(
+    code: &str,
+    file_path: &std::path::Path,
+    config_path: Option<&std::path::Path>,
+) -> String {
+    let engine = FixEngine::new();
+    let path = file_path.to_string_lossy().replace('\\', "/");
+    let mut fixed = code.to_string();
+    let mut diff = String::new();
+
+    let (_, parse_errors) = AdaptiveParser::new(true).parse_recovering(&fixed);
+    if let Some(error) = parse_errors.first() {
+        let error = InterpreterError::RuntimeError {
+            message: format!("语法错误 / Syntax error: {}", error),
+            location: None,
+        };
+        let recovery = ErrorRecoverer::new().recover_from_error(&error, &fixed);
+        if let Some(fix) = engine.fix_from_recovery(&recovery, &fixed) {
+            match engine.apply_to(&fixed, &[fix], &path) {
+                Ok(outcome) => {
+                    diff.push_str(&outcome.diff);
+                    fixed = outcome.source;
+                }
+                Err(e) => eprintln!("无法修复 / Cannot fix: {}", e),
+            }
+        }
+    }
+
+    if let Ok(ast) = AdaptiveParser::new(true).parse(&fixed) {
+        let config = config_path
+            .and_then(|path| ReviewConfig::load(path).ok())
+            .unwrap_or_default();
+        let mut reviewer = CodeReviewer::new();
+        if reviewer.apply_config(&config).is_ok() {
+            let analysis = CodeAnalyzer::new().analyze(&ast);
+            let quality = QualityAssessor::new().assess(&analysis);
+            let result = reviewer.review_code(&ast, &analysis, &quality);
+            let fixes = engine.fixes_for_issues(&result.issues);
+            if !fixes.is_empty() {
+                match engine.apply_to(&fixed, &fixes, &path) {
+                    Ok(outcome) => {
+                        diff = unified_diff(code, &outcome.source, &path);
+                        fixed = outcome.source;
+                    }
+                    Err(e) => eprintln!("无法修复 / Cannot fix: {}", e),
+                }
+            }
+        }
+    }
+
+    if fixed == code {
+        eprintln!("没有可自动修复的问题 / Nothing to fix");
+        return fixed;
+    }
+    if let Err(e) = std::fs::write(file_path, &fixed) {
+        eprintln!("错误：无法写入文件 / Error: Cannot write file: {}", e);
+        std::process::exit(1);
+    }
+    eprint!("{}", diff);
+    fixed
+}
+
+fn review_file(
+    code: &str,
+    file_path: &std::path::Path,
+    config_path: Option<&std::path::Path>,
+    format: ReportFormat,
+) {
+    let config = match config_path {
+        Some(config_path) => ReviewConfig::load(config_path).unwrap_or_else(|e| {
+            eprintln!("审查配置错误 / Review config error: {}", e);
+            std::process::exit(1);
+        }),
+        None => ReviewConfig::default(),
+    };
+    let mut reviewer = CodeReviewer::new();
+    if let Err(e) = reviewer.apply_config(&config) {
+        eprintln!("审查配置错误 / Review config error: {}", e);
+        std::process::exit(1);
+    }
+    let ast = match AdaptiveParser::new(true).parse(code) {
+        Ok(ast) => ast,
+        Err(e) => {
+            eprintln!("解析错误 / Parse error: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+    let analysis = CodeAnalyzer::new().analyze(&ast);
+    let quality = QualityAssessor::new().assess(&analysis);
+    let result = reviewer.review_code(&ast, &analysis, &quality);
+
+    let threshold = config.fail_threshold();
+    let failed = result
+        .issues
+        .iter()
+        .any(|issue| issue.severity >= threshold);
+    match format {
+        ReportFormat::Json => {
+            let report = serde_json::json!({
+                "analysis": analysis,
+                "quality": quality,
+                "review": result,
+            });
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).unwrap_or_default()
+            );
+        }
+        ReportFormat::Sarif => {
+            let artifact = file_path.to_string_lossy().replace('\\', "/");
+            let log = SarifLog::combine([
+                analysis.to_sarif(Some(&artifact)),
+                quality.to_sarif(Some(&artifact)),
+                result.to_sarif(Some(&artifact)),
+            ]);
+            println!("{}", log.to_json());
+        }
+        ReportFormat::Text => {
+            for issue in &result.issues {
+                eprintln!(
+                    "{:?} [{}] {}: {} ({})",
+                    issue.severity,
+                    issue.rule_id,
+                    issue.location,
+                    issue.description,
+                    issue.suggestion
+                );
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    if format != ReportFormat::Text {
+        return;
+    }
+    println!(
+        "✓ 审查通过 / Review passed ({} 个问题低于 {:?} / issues below {:?})",
+        result.issues.len(),
+        threshold,
+        threshold
+    );
+}
+
+/// 输出运行时错误：出错的源码行、调用栈和修复建议（终端中带颜色）
+/// Report a runtime error: the offending source line, call stack and suggestion (coloured in a terminal)
+fn report_execution_error(
+    parser: &AdaptiveParser,
+    interpreter: &Interpreter,
+    error: &InterpreterError,
+    source: &str,
+    file: Option<&str>,
+) {
+    let document = parser.parse_document(source).ok();
+    let mut report =
+        ErrorReport::new(error, interpreter.error_trace()).with_color(stderr_supports_color());
+    if let Some(document) = &document {
+        report = report.with_document(document);
+    }
+    if let Some(file) = file {
+        report = report.with_file(file);
+    }
+    eprint!("{}", report.render());
+}
+
+/// 输出类型错误 / Report type errors
+fn report_type_errors(errors: &[TypeCheckError]) {
+    for error in errors {
+        eprintln!("类型错误 / Type error: {}", error);
+    }
+}
+
+fn generate_test_file(
+    file_path: &PathBuf,
+    output: Option<&std::path::Path>,
+    seed: u64,
+    cases: usize,
+) {
+    let code = match std::fs::read_to_string(file_path) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!(
+                "错误：无法读取文件 / Error: Cannot read file: {:?}",
+                file_path
+            );
+            eprintln!("详细信息 / Details: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let ast = match AdaptiveParser::new(true).parse(&code) {
+        Ok(ast) => ast,
+        Err(e) => {
+            eprintln!("解析错误 / Parse error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let analysis = CodeAnalyzer::new().analyze(&ast);
+    let suite = TestGenerator::new()
+        .with_seed(seed)
+        .with_property_cases(cases)
+        .generate_tests(&ast, &analysis);
+    let test_file = suite.to_test_file(&code);
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, test_file) {
+                eprintln!("写入错误 / Write error: {}", e);
+                std::process::exit(1);
+            }
+            eprintln!(
+                "✓ 生成 {} 个测试 / Generated {} tests: {}",
+                suite.statistics.total_tests,
+                suite.statistics.total_tests,
+                path.display()
+            );
+        }
+        None => print!("{}", test_file),
+    }
+}
+
+/// 分析项目依赖并输出；有循环导入或无法加载的模块时以状态1退出
+/// Analyze project dependencies and print them; exits with status 1 on circular imports or modules that fail to load
+fn analyze_project_dependencies(
+    file_path: &PathBuf,
+    format: GraphFormat,
+    output: Option<&std::path::Path>,
+) {
+    if !file_path.is_file() {
+        eprintln!(
+            "错误：无法读取文件 / Error: Cannot read file: {:?}",
+            file_path
+        );
+        std::process::exit(1);
+    }
+    let search_paths = Interpreter::new().module_paths();
+    let graph = DependencyAnalyzer::new().analyze_project(file_path, &search_paths);
+
+    let text = match format {
+        GraphFormat::Json => graph.to_json() + "\n",
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::Text => {
+            let mut text = String::new();
+            for module in &graph.modules {
+                let kind = if module.native { " (native)" } else { "" };
+                text.push_str(&format!(
+                    "{}{}: {} 个函数 / functions\n",
+                    module.id,
+                    kind,
+                    module.functions.len()
+                ));
+                for import in graph.imports.iter().filter(|i| i.from == module.id) {
+                    text.push_str(&format!("  import {} as {}\n", import.to, import.alias));
+                }
+            }
+            let cross_module = graph
+                .calls
+                .iter()
+                .filter(|call| call.caller.module != call.callee.module)
+                .count();
+            text.push_str(&format!(
+                "{} 个模块 / modules, {} 个导入 / imports, {} 个调用 / calls ({} 跨模块 / cross-module)\n",
+                graph.modules.len(),
+                graph.imports.len(),
+                graph.calls.len(),
+                cross_module
+            ));
+            for cycle in &graph.circular_dependencies {
+                text.push_str(&format!("✗ {}\n", cycle.description));
+            }
+            text
+        }
+    };
+    match output {
+        Some(path) => {
+            if let Err(e) = std::fs::write(path, &text) {
+                eprintln!("写入错误 / Write error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        None => print!("{}", text),
+    }
+    for error in &graph.errors {
+        eprintln!(
+            "模块错误 / Module error: {}: {}",
+            error.module, error.message
+        );
+    }
+    if !graph.circular_dependencies.is_empty() || !graph.errors.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+fn generate_doc_site(path: &std::path::Path, output: &std::path::Path) {
+    let site = match DocumentationGenerator::new().generate_site(path, output) {
+        Ok(site) => site,
+        Err(e) => {
+            eprintln!(
+                "错误：无法生成文档 / Error: Cannot generate documentation: {}",
+                e
+            );
+            std::process::exit(1);
+        }
+    };
+    for page in &site.pages {
+        println!(
+            "{} -> {} ({} 个函数 / functions)",
+            page.source.display(),
+            page.page.display(),
+            page.functions
+        );
+    }
+    println!("索引 / Index: {}", site.index.display());
+    for failure in &site.failures {
+        eprintln!(
+            "解析错误 / Parse error: {}: {}",
+            failure.path.display(),
+            failure.error
+        );
+    }
+}
+
+fn export_genealogy_report(
+    events_dir: &std::path::Path,
+    output: &std::path::Path,
+    format: Option<GenealogyFormatArg>,
+) {
+    let format = format
+        .map(GenealogyFormat::from)
+        .unwrap_or_else(|| GenealogyFormat::from_path(output));
+    let mut engine = EvolutionEngine::new();
+    let exported = engine
+        .load_events_from_dir(events_dir)
+        .and_then(|_| engine.export_report(output, format));
+    if let Err(e) = exported {
+        eprintln!(
+            "错误：无法导出谱系报告 / Error: Cannot export genealogy report: {:?}",
+            e
+        );
+        std::process::exit(1);
+    }
+    println!(
+        "{} 个事件 / events -> {}",
+        engine.get_history().len(),
+        output.display()
+    );
+}
+
+fn detect_project_clones(path: &PathBuf, threshold: f64, min_tokens: usize, json: bool) {
+    let mut detector = SimilarityDetector::new();
+    detector.set_threshold(threshold);
+    detector.set_min_clone_tokens(min_tokens);
+    let report = match detector.detect_project_clones(&AdaptiveParser::new(true), path) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!(
+                "错误：无法读取路径 / Error: Cannot read path {:?}: {}",
+                path, e
+            );
+            std::process::exit(1);
+        }
+    };
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).unwrap_or_default()
+        );
+    } else {
+        for (i, cluster) in report.clusters.iter().enumerate() {
+            println!(
+                "克隆簇 / Clone cluster {}: {:?}, {} 个实例 / instances, 相似度 / similarity {:.0}%",
+                i + 1,
+                cluster.kind,
+                cluster.instances.len(),
+                cluster.similarity * 100.0
+            );
+            for instance in &cluster.instances {
+                println!("  {} ({} tokens)", instance.location, instance.tokens);
+            }
+            println!(
+                "  建议提取 / Suggested extraction: {}",
+                cluster.suggested_signature
+            );
+        }
+        println!(
+            "{} 个文件 / files, {} 个函数 / functions, {} 个克隆簇 / clone clusters",
+            report.files,
+            report.functions,
+            report.clusters.len()
+        );
+    }
+    for failure in &report.failures {
+        eprintln!(
+            "解析错误 / Parse error: {}: {}",
+            failure.path.display(),
+            failure.error
+        );
+    }
+}
+
+fn extract_clones(file_path: &PathBuf, min_tokens: usize) {
+    let source = match std::fs::read_to_string(file_path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!(
+                "错误：无法读取文件 / Error: Cannot read file {:?}: {}",
+                file_path, e
+            );
+            std::process::exit(1);
+        }
+    };
+    let mut ast = match AdaptiveParser::new(true).parse(&source) {
+        Ok(ast) => ast,
+        Err(e) => {
+            eprintln!("解析错误 / Parse error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // 只能提取完全相同或改名的克隆，阈值1.0使近似克隆不会与它们合并
+    // Only exact and renamed clones can be extracted; a threshold of 1.0 keeps near misses from merging with them
+    let mut detector = SimilarityDetector::new();
+    detector.set_threshold(1.0);
+    detector.set_min_clone_tokens(min_tokens);
+    let refactorer = CodeRefactorer::new();
+    // 每次提取后重新检测，后面的簇依赖重写后的程序；已处理的函数（包括委托后的原函数）不再提取
+    // Re-detect after each extraction since later clusters refer to the rewritten program; functions already
+    // handled (including the delegating originals) are not extracted again
+    let mut handled: Vec<String> = Vec::new();
+    loop {
+        let cluster = detector.detect_clones(&ast).into_iter().find(|cluster| {
+            cluster.kind != CloneKind::NearMiss
+                && cluster
+                    .instances
+                    .iter()
+                    .all(|instance| !handled.contains(&instance.function))
+        });
+        let Some(cluster) = cluster else {
+            break;
+        };
+        match refactorer.extract_common_function(&ast, &cluster) {
+            Ok(extracted) => {
+                eprintln!(
+                    "提取 / Extracted {} ({}) from {}; {} 个调用点 / call sites, {}/{} 个测试通过 / tests passed",
+                    extracted.name,
+                    extracted.params.join(" "),
+                    extracted.replaced.join(", "),
+                    extracted.call_sites,
+                    extracted.verification.passed,
+                    extracted.verification.total
+                );
+                handled.extend(extracted.replaced);
+                ast = extracted.ast;
+            }
+            Err(e) => {
+                eprintln!("跳过 / Skipped {}: {}", cluster.instances[0].location, e);
+                handled.extend(cluster.instances.iter().map(|i| i.function.clone()));
+            }
+        }
+    }
+    println!("{}", unparse(&ast));
+}
+
+fn assess_project_quality(path: &std::path::Path, store: Option<PathBuf>, days: i64, json: bool) {
+    let store = store.unwrap_or_else(|| {
+        let dir = if path.is_file() {
+            path.parent().unwrap_or(std::path::Path::new("."))
+        } else {
+            path
+        };
+        dir.join(".evo-quality.json")
+    });
+    let result = QualityAssessor::new()
+        .with_store(&store)
+        .and_then(|mut assessor| {
+            let quality = assessor.assess_project(&AdaptiveParser::new(true), path)?;
+            Ok((quality, assessor))
+        });
+    let (quality, assessor) = match result {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!(
+                "错误：无法评估项目质量 / Error: Cannot assess project quality: {}",
+                e
+            );
+            std::process::exit(1);
+        }
+    };
+    let trend = assessor.quality_trend(chrono::Utc::now() - chrono::Duration::days(days)..);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "quality": quality,
+                "trend": trend,
+            }))
+            .unwrap_or_default()
+        );
+    } else {
+        for file in &quality.files {
+            println!(
+                "{}: {:.1} ({:?}, {} 行 / lines, {:?})",
+                file.path.display(),
+                file.assessment.overall_score,
+                file.assessment.grade,
+                file.lines,
+                file.assessment.trend
+            );
+        }
+        println!(
+            "项目质量 / Project quality: {:.1} ({:?})",
+            quality.overall_score, quality.grade
+        );
+        println!(
+            "最近 {} 天趋势 / Trend over the last {} days: {:?}, {:+.1} ({} 个快照 / snapshots)",
+            days,
+            days,
+            trend.trend,
+            trend.change,
+            trend.snapshots.len()
+        );
+        println!("历史 / History: {}", store.display());
+    }
+    for failure in &quality.failures {
+        eprintln!(
+            "解析错误 / Parse error: {}: {}",
+            failure.path.display(),
+            failure.error
+        );
+    }
+}
+
+fn analyze_project(
+    path: &std::path::Path,
+    config: Option<&std::path::Path>,
+    fail_on: Option<ReviewSeverity>,
+    mut thresholds: ProjectThresholds,
+    json: bool,
+) {
+    let config_path = config.map(PathBuf::from).or_else(|| {
+        let dir = std::fs::canonicalize(path).ok()?;
+        let dir = if dir.is_file() {
+            dir.parent()?.to_path_buf()
+        } else {
+            dir
+        };
+        ReviewConfig::find(&dir)
+    });
+    let config = match config_path {
+        Some(config_path) => ReviewConfig::load(&config_path).unwrap_or_else(|e| {
+            eprintln!("审查配置错误 / Review config error: {}", e);
+            std::process::exit(1);
+        }),
+        None => ReviewConfig::default(),
+    };
+    thresholds.fail_on = fail_on.unwrap_or_else(|| config.fail_threshold());
+
+    let mut analyzer = ProjectAnalyzer::new()
+        .with_thresholds(thresholds)
+        .with_search_paths(Interpreter::new().module_paths());
+    if let Err(e) = analyzer.apply_config(&config) {
+        eprintln!("审查配置错误 / Review config error: {}", e);
+        std::process::exit(1);
+    }
+    let report = match analyzer.analyze(&AdaptiveParser::new(true), path) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!(
+                "错误：无法读取路径 / Error: Cannot read path {:?}: {}",
+                path, e
+            );
+            std::process::exit(1);
+        }
+    };
+
+    if json {
+        println!("{}", report.to_json());
+    } else {
+        print!("{}", report.render());
+    }
+    if !report.passed() {
+        std::process::exit(1);
+    }
+}
+
+fn format_file(file_path: &PathBuf, width: usize, write: bool) {
+    use std::fs;
+
+    let code = match fs::read_to_string(file_path) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!(
+                "错误：无法读取文件 / Error: Cannot read file: {:?}",
+                file_path
+            );
+            eprintln!("详细信息 / Details: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let options = FormatOptions {
+        line_width: width,
+        ..FormatOptions::default()
+    };
+    match format_source_with(&code, &options) {
+        Ok(formatted) if write => {
+            if let Err(e) = fs::write(file_path, formatted) {
+                eprintln!("写入错误 / Write error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Ok(formatted) => print!("{}", formatted),
+        Err(e) => {
+            eprintln!("解析错误 / Parse error: {:?}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// 运行进化模式 / Run evolution mode
+fn run_evolution_mode(
+    output_dir: &PathBuf,
+    prompt_file: &PathBuf,
+    iterations: usize,
+    regression: Option<&std::path::Path>,
+) {
+    println!("Evo-lang 进化模式 / Evolution Mode");
+    println!("============================================================");
+    println!("输出目录 / Output directory: {:?}", output_dir);
+    println!("Prompt文件 / Prompt file: {:?}", prompt_file);
+    println!("迭代次数 / Iterations: {}", iterations);
+    println!();
+
+    // 创建进化引擎 / Create evolution engine
+    let mut engine = EvolutionEngine::new();
+    if let Some(path) = regression {
+        match RegressionCorpus::load(path) {
+            Ok(corpus) => {
+                println!(
+                    "回归语料 / Regression corpus: {} 个用例, 阈值 / threshold {}",
+                    corpus.cases.len(),
+                    corpus.threshold
+                );
+                engine.set_regression_corpus(corpus);
+            }
+            Err(e) => {
+                eprintln!("错误 / Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // 读取prompt文件获取目标 / Read prompt file to get goals
+    let goals = match read_goals_from_prompt(prompt_file) {
+        Ok(g) => {
+            println!("从prompt文件读取目标 / Goals from prompt file:");
+            for goal in &g {
+                println!("  - {}", goal);
+            }
+            println!();
+            g
+        }
+        Err(e) => {
+            eprintln!(
+                "警告：无法读取prompt文件 / Warning: Cannot read prompt file: {}",
+                e
+            );
+            vec!["自进化能力的持续完善".to_string()]
+        }
+    };
+
+    // 确保输出目录存在 / Ensure output directory exists
+    std::fs::create_dir_all(output_dir).expect("Failed to create output directory");
+
+    // 执行进化迭代 / Execute evolution iterations
+    for i in 1..=iterations {
+        println!("迭代 {} / Iteration {}: ", i, i);
+
+        // 执行自我进化 / Perform self-evolution
+        match engine.self_evolve() {
+            Ok(result) => {
+                println!("  自我进化完成 / Self-evolution completed");
+                if let Some(improvements) = result.get("improvement_count") {
+                    println!("  改进数量 / Improvements: {}", improvements);
+                }
+                if let Some(report) = result.get("verification").filter(|r| !r.is_null()) {
+                    println!(
+                        "  回归验证 / Regression gate: {}/{} 通过 / passed{}",
+                        report["passed"],
+                        report["total"],
+                        if result["rolled_back"] == true {
+                            "，已回滚 / rolled back"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+            }
+            Err(e) => {
+                eprintln!("  自我进化错误 / Self-evolution error: {:?}", e);
+            }
         }
 
         // 从使用模式学习 / Learn from usage patterns
@@ -2991,7 +4253,7 @@ fn run_repl() {
     println!();
 
     // 创建解析器和解释器 / Create parser and interpreter
-    let parser = AdaptiveParser::new(true);
+    let mut parser = AdaptiveParser::new(true);
     let mut interpreter = Interpreter::new();
 
     // REPL循环 / REPL loop
@@ -3011,6 +4273,22 @@ fn run_repl() {
             continue;
         }
 
+        // 保存/加载会话状态 / Save/load session state
+        if let Some(path) = trimmed.strip_prefix(":save ") {
+            match interpreter.save_state(path.trim()) {
+                Ok(()) => println!("状态已保存 / State saved: {}", path.trim()),
+                Err(e) => eprintln!("保存失败 / Save failed: {}", e),
+            }
+            continue;
+        }
+        if let Some(path) = trimmed.strip_prefix(":load ") {
+            match interpreter.load_state(path.trim()) {
+                Ok(()) => println!("状态已加载 / State loaded: {}", path.trim()),
+                Err(e) => eprintln!("加载失败 / Load failed: {}", e),
+            }
+            continue;
+        }
+
         // 检查是否是REPL命令 / Check if it's a REPL command
         match trimmed {
             ":quit" | ":exit" | ":q" => {
@@ -3038,8 +4316,8 @@ fn run_repl() {
             _ => {}
         }
 
-        // 解析代码 / Parse code
-        match parser.parse(&input) {
+        // 解析代码，保留定义的宏 / Parse code, keeping defined macros
+        match parser.parse_and_keep_macros(&input) {
             Ok(ast) => {
                 // 执行代码 / Execute code
                 match interpreter.execute(&ast) {
@@ -3050,7 +4328,7 @@ fn run_repl() {
                         }
                     }
                     Err(e) => {
-                        eprintln!("执行错误 / Execution error: {:?}", e);
+                        report_execution_error(&parser, &interpreter, &e, &input, None);
                     }
                 }
             }
@@ -3149,6 +4427,8 @@ fn print_help() {
     println!("  :help, :h    - 显示帮助信息 / Show help");
     println!("  :quit, :exit, :q  - 退出REPL / Exit REPL");
     println!("  :clear, :c   - 清屏 / Clear screen");
+    println!("  :save <file> - 保存会话状态 / Save session state");
+    println!("  :load <file> - 加载会话状态 / Load session state");
     println!();
     println!("使用示例 / Usage examples:");
     println!("  evo> (+ 1 2)");
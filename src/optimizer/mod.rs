@@ -0,0 +1,13 @@
+//! # Optimizer Module / 优化器模块
+//!
+//! AST优化遍，以及进化引擎的优化建议器
+//! AST optimization passes, plus the evolution engine's optimization advisor
+//!
+//! ## 快速导航 / Quick Navigation
+//!
+//! - `passes/` - **优化遍** - 解释器、JIT和进化引擎共用的AST变换: `optimizer::passes::optimize()`
+//! - `evolution::optimizer` - **优化建议器** - 在此重新导出，保留 `optimizer::` 下原有的路径
+
+pub mod passes;
+
+pub use crate::evolution::optimizer::*;
@@ -0,0 +1,12 @@
+//! # Passes Module / 优化遍模块
+//!
+//! AST层面的优化遍，供解释器、JIT和进化引擎共用
+//! AST-level optimization passes shared by the interpreter, the JIT and the evolution engine
+//!
+//! ## 快速导航 / Quick Navigation
+//!
+//! - `pipeline.rs` - **优化遍** - 常量折叠、死分支消除、未使用let删除: `PassManager::run()`
+
+pub mod pipeline;
+
+pub use pipeline::*;
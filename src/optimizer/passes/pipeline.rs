@@ -0,0 +1,412 @@
+// 优化遍 / Optimization passes
+// 在AST上做保持语义的变换，并记录每处改动（改动前后的源码），便于审阅和回放
+// Semantics-preserving transformations on the AST, recording every change (source before and after) for review and replay
+
+use serde::{Deserialize, Serialize};
+
+use crate::grammar::core::{BinOp, Expr, GrammarElement, Literal};
+use crate::grammar::unparse::{unparse_element, unparse_expr};
+
+/// 反复运行各遍直到不再变化的最大轮数 / Maximum rounds of running the passes until nothing changes
+const MAX_ROUNDS: usize = 8;
+
+/// 优化遍 / Optimization pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pass {
+    /// 常量折叠：字面量之间的算术和比较（溢出和除零不折叠，留给运行时处理）
+    /// Constant folding: arithmetic and comparisons between literals (overflow and division by zero are left to the runtime)
+    ConstantFolding,
+    /// 死分支消除：条件为字面量的 `if` 只保留会执行的分支
+    /// Dead branch elimination: an `if` with a literal condition keeps only the branch that runs
+    DeadBranchElimination,
+    /// 删除未使用的let：值为标量字面量、体观察不到变量的带体 `let` 替换为其体
+    /// Unused-let pruning: a `let` with a body, whose value is a scalar literal and whose variable the body cannot observe, is replaced by the body
+    UnusedLetPruning,
+}
+
+impl Pass {
+    /// 全部优化遍（按运行顺序）/ All passes, in running order
+    pub fn all() -> Vec<Pass> {
+        vec![
+            Pass::ConstantFolding,
+            Pass::DeadBranchElimination,
+            Pass::UnusedLetPruning,
+        ]
+    }
+}
+
+/// 一处改动 / One change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassChange {
+    /// 做出改动的优化遍 / Pass that made the change
+    pub pass: Pass,
+    /// 所在顶层形式的索引 / Index of the enclosing top-level form
+    pub form: usize,
+    /// 改动前的源码 / Source before the change
+    pub before: String,
+    /// 改动后的源码 / Source after the change
+    pub after: String,
+}
+
+/// 优化结果：变换后的AST和改动列表 / Optimization result: the transformed AST and the list of changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassResult {
+    /// 变换后的AST / Transformed AST
+    pub ast: Vec<GrammarElement>,
+    /// 按发生顺序的改动 / Changes in the order they were made
+    pub changes: Vec<PassChange>,
+}
+
+impl PassResult {
+    /// 是否有改动 / Whether anything changed
+    pub fn changed(&self) -> bool {
+        !self.changes.is_empty()
+    }
+}
+
+/// 优化遍管理器 / Pass manager
+#[derive(Debug, Clone)]
+pub struct PassManager {
+    passes: Vec<Pass>,
+}
+
+impl PassManager {
+    /// 使用全部优化遍 / Use all passes
+    pub fn new() -> Self {
+        Self::with_passes(Pass::all())
+    }
+
+    /// 只使用指定的优化遍 / Use only the given passes
+    pub fn with_passes(passes: Vec<Pass>) -> Self {
+        Self { passes }
+    }
+
+    /// 启用的优化遍 / Enabled passes
+    pub fn passes(&self) -> &[Pass] {
+        &self.passes
+    }
+
+    /// 运行各遍直到不再变化（折叠可能暴露死分支，删除分支可能让let不再被使用）
+    /// Run the passes until nothing changes (folding may expose dead branches, removing branches may leave lets unused)
+    pub fn run(&self, ast: &[GrammarElement]) -> PassResult {
+        let mut ast = ast.to_vec();
+        let mut changes = Vec::new();
+        for _ in 0..MAX_ROUNDS {
+            let before = changes.len();
+            for &pass in &self.passes {
+                for (form, element) in ast.iter_mut().enumerate() {
+                    let mut rewriter = Rewriter {
+                        pass,
+                        form,
+                        changes: &mut changes,
+                    };
+                    *element = rewriter.element(element);
+                }
+            }
+            if changes.len() == before {
+                break;
+            }
+        }
+        PassResult { ast, changes }
+    }
+}
+
+impl Default for PassManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 用全部优化遍优化AST / Optimize an AST with all passes
+pub fn optimize(ast: &[GrammarElement]) -> PassResult {
+    PassManager::new().run(ast)
+}
+
+/// 单个优化遍的自底向上改写 / Bottom-up rewriting for a single pass
+struct Rewriter<'a> {
+    pass: Pass,
+    form: usize,
+    changes: &'a mut Vec<PassChange>,
+}
+
+impl Rewriter<'_> {
+    fn record(&mut self, before: String, after: String) {
+        self.changes.push(PassChange {
+            pass: self.pass,
+            form: self.form,
+            before,
+            after,
+        });
+    }
+
+    fn element(&mut self, element: &GrammarElement) -> GrammarElement {
+        match element {
+            GrammarElement::Expr(expr) => GrammarElement::Expr(Box::new(self.expr(expr))),
+            GrammarElement::List(items) => {
+                let items: Vec<GrammarElement> =
+                    items.iter().map(|item| self.element(item)).collect();
+                if self.pass == Pass::UnusedLetPruning {
+                    if let Some(body) = prune_let_element(&items) {
+                        let list = GrammarElement::List(items);
+                        self.record(unparse_element(&list), unparse_element(&body));
+                        return body;
+                    }
+                }
+                GrammarElement::List(items)
+            }
+            GrammarElement::Atom(_) | GrammarElement::NaturalLang(_) => element.clone(),
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Expr {
+        let expr = self.children(expr);
+        let rewritten = match self.pass {
+            Pass::ConstantFolding => fold(&expr),
+            Pass::DeadBranchElimination => match &expr {
+                Expr::If(cond, then_expr, else_expr) => match cond.as_ref() {
+                    Expr::Literal(literal) => truthiness(literal).map(|truthy| {
+                        if truthy {
+                            then_expr.as_ref().clone()
+                        } else {
+                            else_expr.as_ref().clone()
+                        }
+                    }),
+                    _ => None,
+                },
+                _ => None,
+            },
+            Pass::UnusedLetPruning => prune_let_expr(&expr),
+        };
+        match rewritten {
+            Some(rewritten) => {
+                self.record(unparse_expr(&expr), unparse_expr(&rewritten));
+                rewritten
+            }
+            None => expr,
+        }
+    }
+
+    /// 先改写子表达式 / Rewrite subexpressions first
+    fn children(&mut self, expr: &Expr) -> Expr {
+        let boxed = |rewriter: &mut Self, expr: &Expr| Box::new(rewriter.expr(expr));
+        match expr {
+            Expr::Literal(Literal::List(items)) => Expr::Literal(Literal::List(
+                items.iter().map(|item| self.expr(item)).collect(),
+            )),
+            Expr::Literal(Literal::Dict(pairs)) => Expr::Literal(Literal::Dict(
+                pairs
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.expr(value)))
+                    .collect(),
+            )),
+            Expr::Literal(_) | Expr::Var(_) => expr.clone(),
+            Expr::Call(name, args) => Expr::Call(
+                name.clone(),
+                args.iter().map(|arg| self.expr(arg)).collect(),
+            ),
+            Expr::Binary(op, left, right) => {
+                Expr::Binary(*op, boxed(self, left), boxed(self, right))
+            }
+            Expr::If(cond, then_expr, else_expr) => Expr::If(
+                boxed(self, cond),
+                boxed(self, then_expr),
+                boxed(self, else_expr),
+            ),
+            Expr::Match(scrutinee, arms) => Expr::Match(
+                boxed(self, scrutinee),
+                arms.iter()
+                    .map(|(pattern, arm)| (pattern.clone(), self.expr(arm)))
+                    .collect(),
+            ),
+            Expr::For {
+                var,
+                iterable,
+                body,
+            } => Expr::For {
+                var: var.clone(),
+                iterable: boxed(self, iterable),
+                body: boxed(self, body),
+            },
+            Expr::While { condition, body } => Expr::While {
+                condition: boxed(self, condition),
+                body: boxed(self, body),
+            },
+            Expr::Try {
+                try_body,
+                catch_var,
+                catch_body,
+            } => Expr::Try {
+                try_body: boxed(self, try_body),
+                catch_var: catch_var.clone(),
+                catch_body: boxed(self, catch_body),
+            },
+            Expr::Lambda { params, body } => Expr::Lambda {
+                params: params.clone(),
+                body: boxed(self, body),
+            },
+            Expr::Begin(exprs) => Expr::Begin(exprs.iter().map(|e| self.expr(e)).collect()),
+            Expr::Assign(name, value) => Expr::Assign(name.clone(), boxed(self, value)),
+        }
+    }
+}
+
+/// 折叠操作数全为字面量的运算 / Fold an operation whose operands are all literals
+fn fold(expr: &Expr) -> Option<Expr> {
+    let literal = |expr: &Expr| match expr {
+        Expr::Literal(literal) => Some(literal.clone()),
+        _ => None,
+    };
+    let folded = match expr {
+        Expr::Binary(op, left, right) => fold_binary(*op, &literal(left)?, &literal(right)?)?,
+        Expr::Call(name, args) => {
+            let op = BinOp::from_operator(name)?;
+            let literals = args.iter().map(literal).collect::<Option<Vec<_>>>()?;
+            match (op, literals.as_slice()) {
+                // + 可变参数，单个参数原样返回 / + is variadic and returns a single argument unchanged
+                (BinOp::Add, [first, rest @ ..]) => rest
+                    .iter()
+                    .try_fold(first.clone(), |sum, literal| fold_binary(op, &sum, literal))?,
+                (_, [left, right]) => fold_binary(op, left, right)?,
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    Some(Expr::Literal(folded))
+}
+
+/// 按解释器的规则计算二元运算；结果依赖溢出模式或会出错时返回 None
+/// Compute a binary operation with the interpreter's rules; None when the result depends on the overflow mode or would be an error
+fn fold_binary(op: BinOp, left: &Literal, right: &Literal) -> Option<Literal> {
+    use Literal::{Bool, Float, Int};
+    Some(match (op, left, right) {
+        (BinOp::Add, Int(a), Int(b)) => Int(a.checked_add(*b)?),
+        (BinOp::Sub, Int(a), Int(b)) => Int(a.checked_sub(*b)?),
+        (BinOp::Mul, Int(a), Int(b)) => Int(a.checked_mul(*b)?),
+        (BinOp::Div, Int(a), Int(b)) if *b != 0 => Int(a.checked_div(*b)?),
+        (BinOp::Mod, Int(a), Int(b)) if *b != 0 => Int(a.checked_rem(*b).unwrap_or(0)),
+        (BinOp::Add, Float(a), Float(b)) => Float(a + b),
+        (BinOp::Sub, Float(a), Float(b)) => Float(a - b),
+        (BinOp::Mul, Float(a), Float(b)) => Float(a * b),
+        (BinOp::Div, Float(a), Float(b)) if *b != 0.0 => Float(a / b),
+        (BinOp::Mod, Float(a), Float(b)) if *b != 0.0 => Float(a % b),
+        (BinOp::Add, Int(a), Float(b)) => Float(*a as f64 + b),
+        (BinOp::Add, Float(a), Int(b)) => Float(a + *b as f64),
+        (BinOp::Add, Literal::String(a), Literal::String(b)) => {
+            Literal::String(format!("{}{}", a, b))
+        }
+        (BinOp::Eq | BinOp::Ne, _, _) if is_scalar(left) && is_scalar(right) => {
+            Bool((left == right) == (op == BinOp::Eq))
+        }
+        (BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge, _, _) => {
+            let ordering = match (left, right) {
+                (Int(a), Int(b)) => a.partial_cmp(b),
+                (Float(a), Float(b)) => a.partial_cmp(b),
+                (Literal::String(a), Literal::String(b)) => a.partial_cmp(b),
+                _ => return None,
+            };
+            Bool(match (op, ordering) {
+                (BinOp::Lt, Some(ordering)) => ordering.is_lt(),
+                (BinOp::Gt, Some(ordering)) => ordering.is_gt(),
+                (BinOp::Le, Some(ordering)) => ordering.is_le(),
+                (BinOp::Ge, Some(ordering)) => ordering.is_ge(),
+                // 与NaN比较总为假 / Comparisons with NaN are always false
+                _ => false,
+            })
+        }
+        _ => return None,
+    })
+}
+
+/// 标量字面量（求值没有副作用且不会出错）/ Scalar literal (evaluating it has no side effects and cannot fail)
+fn is_scalar(literal: &Literal) -> bool {
+    matches!(
+        literal,
+        Literal::Int(_) | Literal::Float(_) | Literal::String(_) | Literal::Bool(_) | Literal::Null
+    )
+}
+
+/// 标量字面量的真值，与解释器一致 / Truthiness of a scalar literal, as in the interpreter
+fn truthiness(literal: &Literal) -> Option<bool> {
+    match literal {
+        Literal::Bool(b) => Some(*b),
+        Literal::Int(i) => Some(*i != 0),
+        Literal::BigInt(_) => Some(true),
+        Literal::Float(f) => Some(*f != 0.0),
+        Literal::String(s) => Some(!s.is_empty()),
+        Literal::Null => Some(false),
+        Literal::List(_) | Literal::Dict(_) => None,
+    }
+}
+
+/// 表达式形式的未使用let：`(let x <标量> body)` 且body观察不到x时替换为body
+/// Unused let in expression form: `(let x <scalar> body)` is replaced by body when body cannot observe x
+fn prune_let_expr(expr: &Expr) -> Option<Expr> {
+    let Expr::Call(name, args) = expr else {
+        return None;
+    };
+    let [Expr::Var(var), Expr::Literal(value), body] = args.as_slice() else {
+        return None;
+    };
+    let unused = name == "let" && is_scalar(value) && !might_observe(body, var);
+    (unused && !matches!(body, Expr::Literal(Literal::Null))).then(|| body.clone())
+}
+
+/// 列表形式的未使用let / Unused let in list form
+fn prune_let_element(items: &[GrammarElement]) -> Option<GrammarElement> {
+    let [GrammarElement::Atom(keyword), name, GrammarElement::Expr(value), GrammarElement::Expr(body)] =
+        items
+    else {
+        return None;
+    };
+    let var = match name {
+        GrammarElement::Atom(var) => var.as_str(),
+        GrammarElement::Expr(expr) => match expr.as_ref() {
+            Expr::Var(var) => var.as_str(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let Expr::Literal(value) = value.as_ref() else {
+        return None;
+    };
+    let unused = keyword == "let" && is_scalar(value) && !might_observe(body, var);
+    (unused && !matches!(body.as_ref(), Expr::Literal(Literal::Null)))
+        .then(|| GrammarElement::Expr(body.clone()))
+}
+
+/// 表达式是否可能读到或改写变量；环境是动态的，被调用的函数也能读到，所以调用运算符以外的函数都算
+/// Whether an expression might read or write a variable; the environment is dynamic, so functions it calls can
+/// see the variable too, and any call other than an operator counts
+fn might_observe(expr: &Expr, var: &str) -> bool {
+    let observe = |expr: &Expr| might_observe(expr, var);
+    match expr {
+        Expr::Literal(Literal::List(items)) => items.iter().any(observe),
+        Expr::Literal(Literal::Dict(pairs)) => pairs.iter().any(|(_, value)| observe(value)),
+        Expr::Literal(_) => false,
+        Expr::Var(name) => name == var,
+        Expr::Call(name, args) => BinOp::from_operator(name).is_none() || args.iter().any(observe),
+        Expr::Binary(_, left, right) => observe(left) || observe(right),
+        Expr::If(cond, then_expr, else_expr) => {
+            observe(cond) || observe(then_expr) || observe(else_expr)
+        }
+        Expr::Match(scrutinee, arms) => {
+            observe(scrutinee) || arms.iter().any(|(_, arm)| observe(arm))
+        }
+        Expr::For {
+            var: loop_var,
+            iterable,
+            body,
+        } => loop_var == var || observe(iterable) || observe(body),
+        Expr::While { condition, body } => observe(condition) || observe(body),
+        Expr::Try {
+            try_body,
+            catch_body,
+            ..
+        } => observe(try_body) || observe(catch_body),
+        Expr::Lambda { body, .. } => observe(body),
+        Expr::Begin(exprs) => exprs.iter().any(observe),
+        Expr::Assign(name, value) => name == var || observe(value),
+    }
+}
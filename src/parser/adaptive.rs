@@ -2,8 +2,21 @@
 // 能够根据扩展的语法规则动态调整解析行为
 // Can dynamically adjust parsing behavior based on extended grammar rules
 
+use std::collections::{HashMap, HashSet};
+
 use crate::grammar::core::{BinOp, Expr, GrammarElement, Literal, Pattern};
-use crate::grammar::rule::GrammarRule;
+use crate::grammar::rule::{
+    DefinitionMethod, GrammarRule, PatternElement, Production, RuleMetadata, Stability,
+};
+use crate::parser::incremental::{syntax_tree, ParsedDocument, Span};
+use crate::parser::macros::{gensym, Macro, FORM_WORDS, OPERATOR_WORDS};
+use crate::parser::normalize::parse_datetime;
+use crate::parser::registry::RuleRegistry;
+use serde::{Deserialize, Serialize};
+
+/// 宏展开的最大嵌套深度，防止递归宏无限展开
+/// Maximum nesting depth of macro expansions, guarding against infinitely recursive macros
+const MAX_MACRO_DEPTH: usize = 256;
 
 /// 自适应解析器 / Adaptive parser
 pub struct AdaptiveParser {
@@ -11,6 +24,10 @@ pub struct AdaptiveParser {
     rules: Vec<GrammarRule>,
     /// 是否允许实验性语法 / Whether experimental syntax is allowed
     allow_experimental: bool,
+    /// 已注册的宏 / Registered macros
+    macros: HashMap<String, Macro>,
+    /// 挂接的共享规则注册表，每次解析时读取其最新规则 / Attached shared rule registry, whose latest rules every parse reads
+    registry: Option<RuleRegistry>,
 }
 
 impl AdaptiveParser {
@@ -19,11 +36,76 @@ impl AdaptiveParser {
         Self {
             rules: Vec::new(),
             allow_experimental,
+            macros: HashMap::new(),
+            registry: None,
         }
     }
 
-    /// 添加语法规则 / Add grammar rule
+    /// 创建挂接共享规则注册表的解析器 / Create a parser attached to a shared rule registry
+    pub fn with_registry(allow_experimental: bool, registry: RuleRegistry) -> Self {
+        let mut parser = Self::new(allow_experimental);
+        parser.attach_registry(registry);
+        parser
+    }
+
+    /// 挂接共享规则注册表：之后每次解析都使用注册表的当前规则，同名时自身的宏优先
+    /// Attach a shared rule registry: every later parse uses the registry's current rules, with the parser's own
+    /// macros taking precedence on a name clash
+    pub fn attach_registry(&mut self, registry: RuleRegistry) {
+        self.registry = Some(registry);
+    }
+
+    /// 挂接的共享规则注册表 / Attached shared rule registry
+    pub fn registry(&self) -> Option<&RuleRegistry> {
+        self.registry.as_ref()
+    }
+
+    /// 挂接的注册表的当前语法版本（未挂接时为 None）/ Current grammar version of the attached registry (None when detached)
+    pub fn grammar_version(&self) -> Option<u64> {
+        self.registry.as_ref().map(RuleRegistry::version)
+    }
+
+    /// 本次解析使用的宏：注册表的宏加上自身的宏 / Macros for a parse: the registry's macros plus the parser's own
+    fn active_macros(&self) -> HashMap<String, Macro> {
+        let mut macros = self.shared_macros();
+        macros.extend(self.macros.clone());
+        macros
+    }
+
+    /// 注册表当前规则生成的宏 / Macros generated from the registry's current rules
+    fn shared_macros(&self) -> HashMap<String, Macro> {
+        self.registry
+            .as_ref()
+            .map(|registry| registry.macros(self.allow_experimental))
+            .unwrap_or_default()
+    }
+
+    /// 注册宏，之后解析的代码中的调用会被展开 / Register a macro; calls in subsequently parsed code are expanded
+    pub fn define_macro(&mut self, definition: Macro) {
+        self.macros.insert(definition.name.clone(), definition);
+    }
+
+    /// 查找已注册的宏 / Look up a registered macro
+    pub fn get_macro(&self, name: &str) -> Option<&Macro> {
+        self.macros.get(name)
+    }
+
+    /// 添加语法规则，规则生成的宏在之后的解析中生效（见 `Macro::from_rule`）
+    /// Add grammar rule; macros generated from it take effect in later parses (see `Macro::from_rule`)
+    ///
+    /// 实验性规则只在允许实验性语法时生效，已弃用的规则不生效
+    /// Experimental rules only apply when experimental syntax is allowed; deprecated rules never apply
     pub fn add_rule(&mut self, rule: GrammarRule) {
+        let active = match rule.meta.stability {
+            Stability::Experimental => self.allow_experimental,
+            Stability::Deprecated => false,
+            Stability::Stabilizing | Stability::Stable => true,
+        };
+        if active {
+            for definition in Macro::from_rule(&rule) {
+                self.define_macro(definition);
+            }
+        }
         self.rules.push(rule);
     }
 
@@ -32,9 +114,137 @@ impl AdaptiveParser {
         let mut tokenizer = Tokenizer::new(source);
         let tokens = tokenizer.tokenize()?;
         let mut parser = ParserState::new(tokens);
+        parser.macros = self.active_macros();
         parser.parse_all()
     }
 
+    /// 解析源代码，并保留其中 `defmacro` 定义的宏供后续解析使用（如REPL）
+    /// Parse source code, keeping macros defined by its `defmacro` forms for later parses (e.g. the REPL)
+    pub fn parse_and_keep_macros(
+        &mut self,
+        source: &str,
+    ) -> Result<Vec<GrammarElement>, ParseError> {
+        let mut tokenizer = Tokenizer::new(source);
+        let tokens = tokenizer.tokenize()?;
+        let mut parser = ParserState::new(tokens);
+        let shared = self.shared_macros();
+        let own: HashSet<String> = self.macros.keys().cloned().collect();
+        parser.macros = self.active_macros();
+        let result = parser.parse_all();
+        // 注册表的宏留在注册表中，规则更新后不会被旧副本遮蔽
+        // Registry macros stay in the registry so stale copies do not shadow updated rules
+        self.macros = parser
+            .macros
+            .into_iter()
+            .filter(|(name, definition)| own.contains(name) || shared.get(name) != Some(definition))
+            .collect();
+        result
+    }
+
+    /// 解析源代码并返回每个顶层元素的起始位置
+    /// Parse source code, also returning the start location of each top-level element
+    pub fn parse_with_locations(
+        &self,
+        source: &str,
+    ) -> Result<(Vec<GrammarElement>, Vec<Location>), ParseError> {
+        let mut tokenizer = Tokenizer::new(source);
+        let tokens = tokenizer.tokenize()?;
+        let mut parser = ParserState::new(tokens);
+        parser.locations = tokenizer.locations;
+        parser.macros = self.active_macros();
+        let elements = parser.parse_all()?;
+        Ok((elements, parser.top_level_locations))
+    }
+
+    /// 解析源代码并返回每个顶层元素前紧邻的注释（见 `leading_comment`）
+    /// Parse source code, also returning the comment directly preceding each top-level element (see `leading_comment`)
+    pub fn parse_with_comments(
+        &self,
+        source: &str,
+    ) -> Result<(Vec<GrammarElement>, Vec<Option<String>>), ParseError> {
+        let (elements, locations) = self.parse_with_locations(source)?;
+        let comments = locations
+            .iter()
+            .map(|location| leading_comment(source, *location))
+            .collect();
+        Ok((elements, comments))
+    }
+
+    /// 解析源代码为带字节范围语法树的文档，可用 `parse_incremental` 增量更新
+    /// Parse source code into a document with a byte-span syntax tree, updatable with `parse_incremental`
+    pub fn parse_document(&self, source: &str) -> Result<ParsedDocument, ParseError> {
+        let mut tokenizer = Tokenizer::new(source);
+        let tokens = tokenizer.tokenize()?;
+        let nodes = syntax_tree(&tokens, &tokenizer.spans);
+        let mut parser = ParserState::new(tokens);
+        parser.locations = tokenizer.locations;
+        parser.macros = self.active_macros();
+        let elements = parser.parse_all()?;
+        Ok(ParsedDocument {
+            source: source.to_string(),
+            elements,
+            nodes,
+        })
+    }
+
+    /// 容错解析：在顶层形式的右括号处同步，出错的形式被跳过，返回其余形式的AST和全部错误
+    /// 未闭合的形式在下一个位于行首的 `(` 处截断
+    /// Error-recovering parse: synchronizes on the closing paren of each top-level form, skipping forms that fail,
+    /// and returns the AST of the others together with every error. An unclosed form is cut at the next `(` starting a line
+    pub fn parse_recovering(&self, source: &str) -> (Vec<GrammarElement>, Vec<ParseError>) {
+        let mut tokenizer = Tokenizer::new(source);
+        let tokens = match tokenizer.tokenize() {
+            Ok(tokens) => tokens,
+            Err(error) => return (Vec::new(), vec![error]),
+        };
+        let locations = tokenizer.locations;
+
+        let mut elements = Vec::new();
+        let mut errors = Vec::new();
+        let mut macros = self.active_macros();
+        let mut start = 0;
+        while start < tokens.len() && tokens[start] != Token::EOF {
+            let location = locations[start];
+            if tokens[start] == Token::RightParen {
+                errors.push(ParseError::syntax_error(
+                    "Unexpected ')'".to_string(),
+                    Some(location),
+                ));
+                start += 1;
+                continue;
+            }
+            let (end, closed) = form_end(&tokens, &locations, start);
+            if !closed {
+                errors.push(ParseError::syntax_error(
+                    "Unclosed '(': expected ')'".to_string(),
+                    Some(location),
+                ));
+                start = end;
+                continue;
+            }
+
+            let mut form = tokens[start..end].to_vec();
+            form.push(Token::EOF);
+            let mut parser = ParserState::new(form);
+            parser.locations = locations[start..=end].to_vec();
+            parser.macros = macros;
+            match parser.parse_element() {
+                Ok(element) => elements.push(element),
+                Err(error) => {
+                    let failed_at = parser
+                        .locations
+                        .get(parser.current)
+                        .copied()
+                        .unwrap_or(location);
+                    errors.push(error.with_location(failed_at));
+                }
+            }
+            macros = parser.macros;
+            start = end;
+        }
+        (elements, errors)
+    }
+
     /// 检查未知语法 / Check for unknown syntax
     pub fn found_unknown_syntax(&self, ast: &[GrammarElement]) -> bool {
         // 检查是否有未识别的语法元素
@@ -58,44 +268,268 @@ impl AdaptiveParser {
             && !atom.parse::<f64>().is_ok()
     }
 
-    /// 提议语法扩展 / Propose syntax expansion
-    pub fn propose_syntax_expansion(&self, _ast: &[GrammarElement]) -> Vec<GrammarRule> {
-        // TODO: 实现语法扩展提议 / Implement syntax expansion proposal
-        Vec::new()
+    /// 提议语法扩展：程序中作为调用出现、未被定义且可识别的词（如 `定义`、`加`）生成别名规则
+    /// Propose syntax expansion: recognizable words used as calls but never defined (such as `定义`, `加`) yield alias rules
+    pub fn propose_syntax_expansion(&self, ast: &[GrammarElement]) -> Vec<GrammarRule> {
+        let mut heads = Vec::new();
+        let mut defined = HashSet::new();
+        for element in ast {
+            collect_heads(element, &mut heads, &mut defined);
+        }
+
+        let macros = self.active_macros();
+        let mut proposed = HashSet::new();
+        let mut rules = Vec::new();
+        for word in heads {
+            if defined.contains(&word)
+                || macros.contains_key(&word)
+                || !proposed.insert(word.clone())
+            {
+                continue;
+            }
+            let production = if OPERATOR_WORDS.iter().any(|(w, _)| *w == word) {
+                "BinaryOperation"
+            } else if let Some((_, production)) = FORM_WORDS.iter().find(|(w, _)| *w == word) {
+                production
+            } else {
+                continue;
+            };
+            rules.push(GrammarRule::new(
+                format!("{}_syntax", word),
+                crate::grammar::rule::Pattern {
+                    elements: vec![PatternElement::Keyword(word.clone())],
+                    variadic: true,
+                },
+                Production {
+                    target: GrammarElement::Atom(production.to_string()),
+                    transform: Vec::new(),
+                    conditions: Vec::new(),
+                },
+                RuleMetadata {
+                    version: "0.1.0".to_string(),
+                    defined_by: DefinitionMethod::Evolutionary,
+                    stability: Stability::Experimental,
+                    description: format!(
+                        "将 {} 识别为 {} / Recognize {} as {}",
+                        word, production, word, production
+                    ),
+                    examples: Vec::new(),
+                    natural_lang_synonyms: vec![word],
+                },
+            ));
+        }
+        rules
+    }
+}
+
+/// 位于 `location` 处、独占行首的形式前紧邻的注释行（去掉 `;` 前缀），遇到空行或代码停止；
+/// 其中有 `;;;` 文档注释时只取文档注释
+/// Comment lines (with the `;` prefix removed) directly preceding a form that starts its line at `location`,
+/// stopping at a blank line or code; when `;;;` doc comments are among them, only those are kept
+pub fn leading_comment(source: &str, location: Location) -> Option<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let index = location.line.checked_sub(1)?;
+    let starts_line = lines
+        .get(index)?
+        .chars()
+        .take(location.column.saturating_sub(1))
+        .all(char::is_whitespace);
+    if !starts_line {
+        return None;
+    }
+
+    let mut block: Vec<&str> = lines[..index]
+        .iter()
+        .rev()
+        .map(|line| line.trim())
+        .take_while(|line| line.starts_with(';'))
+        .collect();
+    block.reverse();
+    if block.iter().any(|line| line.starts_with(";;;")) {
+        block.retain(|line| line.starts_with(";;;"));
+    }
+    let text = block
+        .iter()
+        .map(|line| {
+            let line = line.trim_start_matches(';');
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// 收集调用头部的名称和被定义的名称 / Collect names in call head position and names that are defined
+fn collect_heads(element: &GrammarElement, heads: &mut Vec<String>, defined: &mut HashSet<String>) {
+    match element {
+        GrammarElement::List(items) => {
+            let head = items.first().and_then(|item| match item {
+                GrammarElement::Atom(name) => Some(name.clone()),
+                GrammarElement::Expr(expr) => match expr.as_ref() {
+                    Expr::Var(name) => Some(name.to_string()),
+                    _ => None,
+                },
+                _ => None,
+            });
+            if let (
+                Some("def" | "function" | "defn-" | "let" | "defconst"),
+                Some(GrammarElement::Atom(name)),
+            ) = (head.as_deref(), items.get(1))
+            {
+                defined.insert(name.clone());
+            }
+            if let Some(head) = head {
+                heads.push(head);
+            }
+            for item in items.iter().skip(1) {
+                collect_heads(item, heads, defined);
+            }
+        }
+        GrammarElement::Expr(expr) => collect_expr_heads(expr, heads),
+        _ => {}
+    }
+}
+
+fn collect_expr_heads(expr: &Expr, heads: &mut Vec<String>) {
+    let mut visit = |e: &Expr| collect_expr_heads(e, heads);
+    match expr {
+        Expr::Call(name, args) => {
+            args.iter().for_each(visit);
+            heads.push(name.to_string());
+            // `(a plus b)` 中的中缀运算词 / Infix operator word in `(a plus b)`
+            if let [Expr::Var(word), _] = args.as_slice() {
+                if OPERATOR_WORDS.iter().any(|(w, _)| word == w) {
+                    heads.push(word.to_string());
+                }
+            }
+        }
+        Expr::Binary(_, left, right) => {
+            visit(left);
+            visit(right);
+        }
+        Expr::If(condition, then_expr, else_expr) => {
+            visit(condition);
+            visit(then_expr);
+            visit(else_expr);
+        }
+        Expr::Match(value, cases) => {
+            visit(value);
+            cases.iter().for_each(|(_, body)| visit(body));
+        }
+        Expr::For { iterable, body, .. } => {
+            visit(iterable);
+            visit(body);
+        }
+        Expr::While { condition, body } => {
+            visit(condition);
+            visit(body);
+        }
+        Expr::Try {
+            try_body,
+            catch_body,
+            ..
+        } => {
+            visit(try_body);
+            visit(catch_body);
+        }
+        Expr::Lambda { body, .. } => visit(body),
+        Expr::Begin(exprs) => exprs.iter().for_each(visit),
+        Expr::Assign(_, value) => visit(value),
+        Expr::Literal(Literal::List(items)) => items.iter().for_each(visit),
+        Expr::Literal(Literal::Dict(pairs)) => pairs.iter().for_each(|(_, value)| visit(value)),
+        Expr::Var(_) | Expr::Literal(_) => {}
+    }
+}
+
+/// 元素中是否出现某个名称（不区分绑定位置，偏保守）
+/// Whether a name appears anywhere in an element (binding positions included, so it errs on the safe side)
+fn element_mentions(element: &GrammarElement, name: &str) -> bool {
+    match element {
+        GrammarElement::Atom(atom) => atom == name,
+        GrammarElement::List(items) => items.iter().any(|item| element_mentions(item, name)),
+        GrammarElement::Expr(expr) => expr_mentions(expr, name),
+        GrammarElement::NaturalLang(_) => false,
+    }
+}
+
+fn expr_mentions(expr: &Expr, name: &str) -> bool {
+    let visit = |e: &Expr| expr_mentions(e, name);
+    match expr {
+        Expr::Var(var) => var == name,
+        Expr::Call(head, args) => head == name || args.iter().any(visit),
+        Expr::Binary(_, left, right) => visit(left) || visit(right),
+        Expr::If(condition, then_expr, else_expr) => {
+            visit(condition) || visit(then_expr) || visit(else_expr)
+        }
+        Expr::Match(value, cases) => visit(value) || cases.iter().any(|(_, body)| visit(body)),
+        Expr::For { iterable, body, .. } => visit(iterable) || visit(body),
+        Expr::While { condition, body } => visit(condition) || visit(body),
+        Expr::Try {
+            try_body,
+            catch_body,
+            ..
+        } => visit(try_body) || visit(catch_body),
+        Expr::Lambda { body, .. } => visit(body),
+        Expr::Begin(exprs) => exprs.iter().any(visit),
+        Expr::Assign(var, value) => var == name || visit(value),
+        Expr::Literal(Literal::List(items)) => items.iter().any(visit),
+        Expr::Literal(Literal::Dict(pairs)) => pairs.iter().any(|(_, value)| visit(value)),
+        Expr::Literal(_) => false,
     }
 }
 
 /// 词法分析器 / Tokenizer
-struct Tokenizer {
+pub(crate) struct Tokenizer {
     input: Vec<char>,
     position: usize,
     line: usize,
     column: usize,
+    /// 当前字节偏移 / Current byte offset
+    offset: usize,
+    /// 每个词法单元的起始位置 / Start location of each token
+    locations: Vec<Location>,
+    /// 每个词法单元的字节范围 / Byte span of each token
+    spans: Vec<Span>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
-enum Token {
-    LeftParen,       // (
-    RightParen,      // )
-    String(String),  // "string"
-    Number(String),  // 数字（整数或浮点数）
-    Symbol(String),  // 标识符或关键字
-    Quote,           // '
-    Comment(String), // ; 注释
+pub(crate) enum Token {
+    LeftParen,                           // (
+    RightParen,                          // )
+    String(String),                      // "string"
+    InterpolatedString(Vec<StringPart>), // "Hello, {name}!"
+    DateTime(String),                    // #2024-01-01
+    Number(String),                      // 数字（整数或浮点数）
+    Symbol(String),                      // 标识符或关键字
+    Quote,                               // '
+    Comment(String),                     // ; 注释
     EOF,
 }
 
+/// 插值字符串片段 / Interpolated string part
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum StringPart {
+    /// 字面文本 / Literal text
+    Text(String),
+    /// 插值表达式源码 / Interpolated expression source
+    Expr(String),
+}
+
 impl Tokenizer {
-    fn new(input: &str) -> Self {
+    pub(crate) fn new(input: &str) -> Self {
         Self {
             input: input.chars().collect(),
             position: 0,
             line: 1,
             column: 1,
+            offset: 0,
+            locations: Vec::new(),
+            spans: Vec::new(),
         }
     }
 
-    fn tokenize(&mut self) -> Result<Vec<Token>, ParseError> {
+    pub(crate) fn tokenize(&mut self) -> Result<Vec<Token>, ParseError> {
         let mut tokens = Vec::new();
 
         while !self.is_at_end() {
@@ -104,17 +538,25 @@ impl Tokenizer {
                 break;
             }
 
+            let location = Location::new(self.line, self.column);
+            let start = self.offset;
             let token = self.next_token()?;
             match token {
                 Token::Comment(_) => {
                     // 跳过注释
                     continue;
                 }
-                _ => tokens.push(token),
+                _ => {
+                    tokens.push(token);
+                    self.locations.push(location);
+                    self.spans.push(Span::new(start, self.offset));
+                }
             }
         }
 
         tokens.push(Token::EOF);
+        self.locations.push(Location::new(self.line, self.column));
+        self.spans.push(Span::new(self.offset, self.offset));
         Ok(tokens)
     }
 
@@ -134,9 +576,29 @@ impl Tokenizer {
                 self.advance();
                 Ok(Token::Quote)
             }
+            ':' => {
+                // 类型标注分隔符 / Type annotation separator
+                self.advance();
+                Ok(Token::Symbol(":".to_string()))
+            }
             '"' => {
                 self.advance();
-                self.read_string()
+                if self.peek() == '"' && self.peek_at(1) == '"' {
+                    // 三引号多行字符串 / Triple-quoted multi-line string
+                    self.advance();
+                    self.advance();
+                    self.read_string(true)
+                } else {
+                    self.read_string(false)
+                }
+            }
+            'r' if self.peek_at(1) == '"' || (self.peek_at(1) == '#' && self.peek_at(2) == '"') => {
+                self.advance();
+                self.read_raw_string()
+            }
+            '#' if self.peek_at(1).is_ascii_digit() => {
+                self.advance();
+                self.read_datetime()
             }
             ';' => {
                 self.advance();
@@ -179,13 +641,23 @@ impl Tokenizer {
         }
     }
 
-    fn read_string(&mut self) -> Result<Token, ParseError> {
+    /// 读取字符串（支持 `{expr}` 插值）/ Read string (supports `{expr}` interpolation)
+    /// `triple` 表示三引号字符串，以 `"""` 结束 / `triple` marks a triple-quoted string ending in `"""`
+    fn read_string(&mut self, triple: bool) -> Result<Token, ParseError> {
         let mut string = String::new();
+        let mut parts = Vec::new();
         let start_line = self.line;
         let start_column = self.column;
 
-        while !self.is_at_end() && self.peek() != '"' {
-            if self.peek() == '\\' {
+        while !self.is_at_end() && !self.at_string_end(triple) {
+            if self.peek() == '{' {
+                // 插值表达式 / Interpolated expression
+                self.advance();
+                if !string.is_empty() {
+                    parts.push(StringPart::Text(std::mem::take(&mut string)));
+                }
+                parts.push(StringPart::Expr(self.read_interpolation()?));
+            } else if self.peek() == '\\' {
                 self.advance(); // 跳过反斜杠
                 if self.is_at_end() {
                     let location = Location::new(self.line, self.column);
@@ -200,6 +672,8 @@ impl Tokenizer {
                     'r' => string.push('\r'),
                     '\\' => string.push('\\'),
                     '"' => string.push('"'),
+                    '{' => string.push('{'),
+                    '}' => string.push('}'),
                     ch => {
                         return Err(ParseError::syntax_error(
                             format!(
@@ -211,7 +685,7 @@ impl Tokenizer {
                     }
                 }
             } else {
-                string.push(self.advance());
+                string.push(self.advance_char());
             }
         }
 
@@ -223,8 +697,99 @@ impl Tokenizer {
             ));
         }
 
-        self.advance(); // 跳过结束引号
-        Ok(Token::String(string))
+        // 跳过结束引号 / Skip closing quote(s)
+        for _ in 0..if triple { 3 } else { 1 } {
+            self.advance();
+        }
+
+        if parts.is_empty() {
+            Ok(Token::String(string))
+        } else {
+            if !string.is_empty() {
+                parts.push(StringPart::Text(string));
+            }
+            Ok(Token::InterpolatedString(parts))
+        }
+    }
+
+    /// 是否到达字符串结尾 / Whether at the end of a string
+    fn at_string_end(&self, triple: bool) -> bool {
+        if triple {
+            self.peek() == '"' && self.peek_at(1) == '"' && self.peek_at(2) == '"'
+        } else {
+            self.peek() == '"'
+        }
+    }
+
+    /// 读取插值表达式源码（已消费 `{`）/ Read interpolation source (`{` already consumed)
+    fn read_interpolation(&mut self) -> Result<String, ParseError> {
+        let start = Location::new(self.line, self.column);
+        let mut source = String::new();
+        let mut depth = 0;
+        let mut in_string = false;
+
+        while !self.is_at_end() {
+            let ch = self.peek();
+            if in_string {
+                if ch == '\\' {
+                    source.push(self.advance());
+                } else if ch == '"' {
+                    in_string = false;
+                }
+            } else if ch == '"' {
+                in_string = true;
+            } else if ch == '{' {
+                depth += 1;
+            } else if ch == '}' {
+                if depth == 0 {
+                    self.advance();
+                    if source.trim().is_empty() {
+                        return Err(ParseError::syntax_error(
+                            "Empty interpolation in string".to_string(),
+                            Some(start),
+                        ));
+                    }
+                    return Ok(source);
+                }
+                depth -= 1;
+            }
+            if !self.is_at_end() {
+                source.push(self.advance_char());
+            }
+        }
+
+        Err(ParseError::syntax_error(
+            "Unterminated interpolation in string".to_string(),
+            Some(start),
+        ))
+    }
+
+    /// 读取原始字符串 `r"..."` 或 `r#"..."#`（无转义、无插值）
+    /// Read raw string `r"..."` or `r#"..."#` (no escapes, no interpolation)
+    fn read_raw_string(&mut self) -> Result<Token, ParseError> {
+        let start = Location::new(self.line, self.column);
+        let hashed = self.peek() == '#';
+        if hashed {
+            self.advance();
+        }
+        self.advance(); // 跳过开始引号 / Skip opening quote
+
+        let mut string = String::new();
+        while !self.is_at_end() {
+            if self.peek() == '"' && (!hashed || self.peek_at(1) == '#') {
+                self.advance();
+                if hashed {
+                    self.advance();
+                }
+                return Ok(Token::String(string));
+            }
+            string.push(self.advance_char());
+        }
+
+        Err(ParseError::syntax_error(
+            "Unterminated raw string".to_string(),
+            Some(start),
+        ))
     }
 
     fn read_number(&mut self, first_char: Option<char>) -> Result<Token, ParseError> {
@@ -272,9 +837,42 @@ impl Tokenizer {
             symbol.push(self.advance());
         }
 
+        // 顺序绑定的 `let*` / Sequential-binding `let*`
+        if symbol == "let" && self.peek() == '*' {
+            symbol.push(self.advance());
+        }
+
+        // 推导式 `for/list`、`for/dict`：其他位置的 / 仍是除法
+        // Comprehensions `for/list` and `for/dict`; a / anywhere else is still division
+        if symbol == "for" && self.peek() == '/' && self.peek_at(1).is_alphabetic() {
+            symbol.push(self.advance());
+            while !self.is_at_end() && self.peek().is_alphanumeric() {
+                symbol.push(self.advance());
+            }
+        }
+
         Ok(Token::Symbol(symbol))
     }
 
+    /// 读取日期时间字面量 `#2024-01-01` 或 `#2024-01-01T10:30:00`，格式无效时报错
+    /// Read a date-time literal `#2024-01-01` or `#2024-01-01T10:30:00`, failing on an invalid format
+    fn read_datetime(&mut self) -> Result<Token, ParseError> {
+        let location = Location::new(self.line, self.column);
+        let mut text = String::new();
+        while !self.is_at_end()
+            && (self.peek().is_ascii_alphanumeric() || matches!(self.peek(), '-' | ':' | '.' | '+'))
+        {
+            text.push(self.advance());
+        }
+        if parse_datetime(&text).is_none() {
+            return Err(ParseError::syntax_error(
+                format!("Invalid date literal: #{}", text),
+                Some(location),
+            ));
+        }
+        Ok(Token::DateTime(text))
+    }
+
     fn read_comment(&mut self) -> Result<Token, ParseError> {
         let mut comment = String::new();
         while !self.is_at_end() && self.peek() != '\n' {
@@ -303,9 +901,27 @@ impl Tokenizer {
         let ch = self.input[self.position];
         self.position += 1;
         self.column += 1;
+        self.offset += ch.len_utf8();
+        ch
+    }
+
+    /// 前进一个字符并跟踪换行（用于字符串内部）/ Advance one char tracking newlines (inside strings)
+    fn advance_char(&mut self) -> char {
+        let ch = self.advance();
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        }
         ch
     }
 
+    fn peek_at(&self, offset: usize) -> char {
+        self.input
+            .get(self.position + offset)
+            .copied()
+            .unwrap_or('\0')
+    }
+
     fn peek(&self) -> char {
         if self.is_at_end() {
             '\0'
@@ -319,15 +935,65 @@ impl Tokenizer {
     }
 }
 
+/// 从 `start` 开始的顶层形式的结束位置（不含）及其是否闭合；
+/// 括号到文件末尾仍未闭合时，在下一个位于行首的 `(` 处截断
+/// End (exclusive) of the top-level form starting at `start`, and whether it is closed;
+/// when its parentheses stay open until the end of input, it is cut at the next `(` starting a line
+fn form_end(tokens: &[Token], locations: &[Location], start: usize) -> (usize, bool) {
+    match tokens[start] {
+        Token::Quote if start + 1 < tokens.len() && tokens[start + 1] != Token::EOF => {
+            form_end(tokens, locations, start + 1)
+        }
+        Token::LeftParen => {
+            let mut depth = 0usize;
+            for (index, token) in tokens.iter().enumerate().skip(start) {
+                match token {
+                    Token::LeftParen => depth += 1,
+                    Token::RightParen => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return (index + 1, true);
+                        }
+                    }
+                    Token::EOF => {
+                        let cut = (start + 1..index)
+                            .find(|&i| tokens[i] == Token::LeftParen && locations[i].column == 1)
+                            .unwrap_or(index);
+                        return (cut, false);
+                    }
+                    _ => {}
+                }
+            }
+            (tokens.len(), false)
+        }
+        _ => (start + 1, true),
+    }
+}
+
 /// 解析器状态 / Parser state
 struct ParserState {
     tokens: Vec<Token>,
     current: usize,
+    /// 词法单元位置（可能为空）/ Token locations (may be empty)
+    locations: Vec<Location>,
+    /// 已解析顶层元素的起始位置 / Start locations of parsed top-level elements
+    top_level_locations: Vec<Location>,
+    /// 可展开的宏 / Macros available for expansion
+    macros: HashMap<String, Macro>,
+    /// 当前宏展开的嵌套深度 / Current nesting depth of macro expansions
+    macro_depth: usize,
 }
 
 impl ParserState {
     fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            locations: Vec::new(),
+            top_level_locations: Vec::new(),
+            macros: HashMap::new(),
+            macro_depth: 0,
+        }
     }
 
     fn parse_all(&mut self) -> Result<Vec<GrammarElement>, ParseError> {
@@ -337,6 +1003,9 @@ impl ParserState {
             if self.check(&Token::EOF) {
                 break;
             }
+            if let Some(location) = self.locations.get(self.current) {
+                self.top_level_locations.push(*location);
+            }
             elements.push(self.parse_element()?);
         }
 
@@ -348,6 +1017,8 @@ impl ParserState {
             Token::LeftParen => self.parse_list(),
             Token::Quote => self.parse_quoted(),
             Token::String(_) => self.parse_string(),
+            Token::InterpolatedString(_) => self.parse_interpolated_string(),
+            Token::DateTime(_) => self.parse_date_literal(),
             Token::Number(_) => self.parse_number(),
             Token::Symbol(_) => self.parse_symbol(),
             _ => Err(ParseError::syntax_error(
@@ -360,6 +1031,36 @@ impl ParserState {
     fn parse_list(&mut self) -> Result<GrammarElement, ParseError> {
         self.consume(&Token::LeftParen, "Expected '('")?;
 
+        // 宏定义和宏调用在构建AST之前处理，展开结果仍是宏调用时继续展开
+        // Macro definitions and calls are handled before building the AST; expansions that are again macro calls expand in turn
+        let mut expanded = 0;
+        let result = loop {
+            match self.peek() {
+                Token::Symbol(head) if head == "defmacro" => break self.parse_defmacro(),
+                Token::Symbol(head) if self.macros.contains_key(head) => {
+                    let name = head.clone();
+                    let open = self.current - 1;
+                    expanded += 1;
+                    self.macro_depth += 1;
+                    self.expand_macro(&name, open)?;
+                    if self.tokens[open] != Token::LeftParen {
+                        break self.parse_element();
+                    }
+                    self.current = open + 1;
+                }
+                _ => {
+                    if !self.move_infix_macro() {
+                        break self.parse_list_contents();
+                    }
+                }
+            }
+        };
+        self.macro_depth -= expanded;
+        result
+    }
+
+    /// 解析 `(` 之后的列表内容 / Parse list contents after `(`
+    fn parse_list_contents(&mut self) -> Result<GrammarElement, ParseError> {
         if self.check(&Token::RightParen) {
             self.advance_token();
             return Ok(GrammarElement::List(Vec::new()));
@@ -382,12 +1083,27 @@ impl ParserState {
 
         if let Some(keyword) = keyword {
             match keyword {
-                "def" | "function" => {
+                "def" | "function" | "defn-" => {
                     return self.parse_function_def(keyword.to_string());
                 }
                 "let" => {
                     return self.parse_let();
                 }
+                "defconst" => {
+                    return self.parse_defconst();
+                }
+                "import" => {
+                    return self.parse_import();
+                }
+                "defprotocol" => {
+                    return self.parse_defprotocol();
+                }
+                "defimpl" => {
+                    return self.parse_defimpl();
+                }
+                "let*" => {
+                    return self.parse_let_bindings("let*");
+                }
                 "if" => {
                     return self.parse_if();
                 }
@@ -400,12 +1116,18 @@ impl ParserState {
                 "for" => {
                     return self.parse_for();
                 }
+                "for/list" | "for/dict" => {
+                    return self.parse_comprehension(keyword.to_string());
+                }
                 "while" => {
                     return self.parse_while();
                 }
                 "try" => {
                     return self.parse_try();
                 }
+                "begin" => {
+                    return self.parse_begin();
+                }
                 "list" | "vec" => {
                     return self.parse_list_literal();
                 }
@@ -419,7 +1141,7 @@ impl ParserState {
                         GrammarElement::Atom(s) => s.clone(),
                         GrammarElement::Expr(boxed_expr) => {
                             if let Expr::Var(s) = boxed_expr.as_ref() {
-                                s.clone()
+                                s.to_string()
                             } else {
                                 return Err(ParseError::syntax_error(
                                     "set! variable must be an atom or variable".to_string(),
@@ -440,7 +1162,7 @@ impl ParserState {
 
                     let value_expr = self.element_to_expr(&value_elem)?;
                     return Ok(GrammarElement::Expr(Box::new(Expr::Assign(
-                        var_str,
+                        var_str.into(),
                         Box::new(value_expr),
                     ))));
                 }
@@ -490,7 +1212,8 @@ impl ParserState {
                         .map(|e| self.element_to_expr(e))
                         .collect::<Result<Vec<_>, _>>()?;
                     return Ok(GrammarElement::Expr(Box::new(Expr::Call(
-                        func_name, expr_args,
+                        func_name.into(),
+                        expr_args,
                     ))));
                 }
             }
@@ -514,7 +1237,7 @@ impl ParserState {
             GrammarElement::Atom(s) => s,
             GrammarElement::Expr(boxed_expr) => {
                 if let crate::grammar::core::Expr::Var(s) = boxed_expr.as_ref() {
-                    s.clone()
+                    s.to_string()
                 } else {
                     return Err(ParseError::syntax_error(
                         "Function name must be an atom or variable".to_string(),
@@ -531,12 +1254,24 @@ impl ParserState {
         };
 
         // 解析参数列表（直接解析，不进行关键字检查）
+        // 参数可带类型标注 `(x : Int)` / Parameters may carry a type annotation `(x : Int)`
+        let mut param_types = Vec::new();
         let args_list = if self.check(&Token::LeftParen) {
             self.consume(&Token::LeftParen, "Expected '(' for parameter list")?;
             let mut params = Vec::new();
             while !self.check(&Token::RightParen) {
-                let param_elem = self.parse_element()?;
-                params.push(param_elem);
+                if self.check(&Token::LeftParen)
+                    && matches!(self.tokens.get(self.current + 2), Some(Token::Symbol(s)) if s == ":")
+                {
+                    self.advance_token();
+                    params.push(self.parse_element()?);
+                    self.advance_token();
+                    param_types.push(Some(self.parse_type()?));
+                    self.consume(&Token::RightParen, "Expected ')' after parameter type")?;
+                } else {
+                    params.push(self.parse_element()?);
+                    param_types.push(None);
+                }
             }
             self.consume(&Token::RightParen, "Expected ')' after parameter list")?;
             params
@@ -544,13 +1279,21 @@ impl ParserState {
             Vec::new()
         };
 
+        // 可选的返回类型 `: Type` / Optional return type `: Type`
+        let return_type = if self.check_symbol(":") {
+            self.advance_token();
+            Some(self.parse_type()?)
+        } else {
+            None
+        };
+
         let _arg_names: Vec<String> = args_list
             .iter()
             .filter_map(|e| match e {
                 GrammarElement::Atom(s) => Some(s.clone()),
                 GrammarElement::Expr(boxed_expr) => {
                     if let crate::grammar::core::Expr::Var(s) = boxed_expr.as_ref() {
-                        Some(s.clone())
+                        Some(s.to_string())
                     } else {
                         None
                     }
@@ -559,46 +1302,240 @@ impl ParserState {
             })
             .collect();
 
-        // 解析函数体
-        let body = self.parse_element()?;
+        // 解析函数体；函数体前的字符串字面量是文档字符串
+        // Parse the body; a string literal before the body is the docstring
+        let mut body = self.parse_element()?;
+        let mut docstring = None;
+        if !self.check(&Token::RightParen) {
+            if let GrammarElement::Expr(expr) = &body {
+                if let Expr::Literal(Literal::String(_)) = expr.as_ref() {
+                    docstring = Some(std::mem::replace(&mut body, self.parse_element()?));
+                }
+            }
+        }
 
         // 消费结束括号
         self.consume(&Token::RightParen, "Expected ')' after function definition")?;
 
         // 转换为表达式（这里简化处理，实际应该创建函数定义节点）
-        Ok(GrammarElement::List(vec![
+        let mut definition = vec![
             GrammarElement::Atom(keyword),
             GrammarElement::Atom(name_str),
             GrammarElement::List(args_list),
             body,
-        ]))
+        ];
+
+        // 有类型标注时追加签名 `(: (参数类型...) 返回类型)`，未标注处为 Any
+        // With annotations, append the signature `(: (param types...) return type)`, Any where unannotated
+        if return_type.is_some() || param_types.iter().any(Option::is_some) {
+            let any = || GrammarElement::Atom("Any".to_string());
+            definition.push(GrammarElement::List(vec![
+                GrammarElement::Atom(":".to_string()),
+                GrammarElement::List(
+                    param_types
+                        .into_iter()
+                        .map(|t| t.unwrap_or_else(any))
+                        .collect(),
+                ),
+                return_type.unwrap_or_else(any),
+            ]));
+        }
+        definition.extend(docstring);
+        Ok(GrammarElement::List(definition))
     }
 
-    fn parse_let(&mut self) -> Result<GrammarElement, ParseError> {
-        // (let name value body...) 或 (let name value) - body 是可选的，但至少需要 name 和 value
-        let name = self.parse_element()?;
+    /// 解析 `(defconst NAME value)`：模块级常量，之后不能再赋值
+    /// Parse `(defconst NAME value)`: a module-level constant that cannot be reassigned afterwards
+    fn parse_defconst(&mut self) -> Result<GrammarElement, ParseError> {
+        let location = self.locations.get(self.current).copied();
+        let name = self.expect_symbol("constant name", location)?;
         let value = self.parse_element()?;
+        self.consume(&Token::RightParen, "Expected ')' after constant value")?;
+        Ok(GrammarElement::List(vec![
+            GrammarElement::Atom("defconst".to_string()),
+            GrammarElement::Atom(name),
+            value,
+        ]))
+    }
 
-        // 检查是否有 body（如果下一个token是右括号，则没有body）
-        let body = if self.check(&Token::RightParen) {
-            // 没有body，使用 null 作为默认值
-            GrammarElement::Expr(Box::new(Expr::Literal(Literal::Null)))
-        } else {
-            // 有body，解析所有剩余的表达式作为body
-            let mut body_exprs = Vec::new();
+    /// 解析 `(import "module" :as alias :only (name ...))`，选项规范化为 `(:as alias)` 和 `(:only name ...)` 参数；
+    /// 旧的位置别名 `(import "module" "alias")` 等同于 `:as`
+    /// Parse `(import "module" :as alias :only (name ...))`, normalizing the options into `(:as alias)` and
+    /// `(:only name ...)` arguments; the older positional alias `(import "module" "alias")` is the same as `:as`
+    fn parse_import(&mut self) -> Result<GrammarElement, ParseError> {
+        let location = self.locations.get(self.current).copied();
+        let module = self.parse_element()?;
+        let mut args = vec![self.element_to_expr(&module)?];
+        let colon = Token::Symbol(":".to_string());
+        if !self.check(&Token::RightParen) && !self.check(&colon) {
+            let alias = self.parse_element()?;
+            args.push(Expr::Call(
+                ":as".into(),
+                vec![self.element_to_expr(&alias)?],
+            ));
+        }
+        while self.check(&colon) {
+            self.advance_token();
+            let (option, values) = match self.expect_symbol("import option", location)?.as_str() {
+                "as" => {
+                    let alias = self.parse_element()?;
+                    (":as", vec![self.element_to_expr(&alias)?])
+                }
+                "only" => {
+                    self.consume(&Token::LeftParen, "Expected '(' after :only")?;
+                    let mut names = Vec::new();
+                    while !self.check(&Token::RightParen) {
+                        names.push(Expr::Var(
+                            self.expect_symbol("imported name", location)?.into(),
+                        ));
+                    }
+                    self.consume(&Token::RightParen, "Expected ')' after imported names")?;
+                    (":only", names)
+                }
+                other => {
+                    return Err(ParseError::syntax_error(
+                        format!("Unknown import option :{}, expected :as or :only", other),
+                        location,
+                    ))
+                }
+            };
+            if args
+                .iter()
+                .any(|arg| matches!(arg, Expr::Call(name, _) if name == option))
+            {
+                return Err(ParseError::syntax_error(
+                    format!("Duplicate import option {}", option),
+                    location,
+                ));
+            }
+            args.push(Expr::Call(option.into(), values));
+        }
+        self.consume(&Token::RightParen, "Expected ')' after import")?;
+        Ok(GrammarElement::Expr(Box::new(Expr::Call(
+            "import".into(),
+            args,
+        ))))
+    }
+
+    /// 解析 `(defprotocol Name (method (self args...)) ...)`：每个方法声明参数列表，第一个参数是分派所依据的接收者
+    /// Parse `(defprotocol Name (method (self args...)) ...)`: each method declares its parameters, the first one
+    /// being the receiver that calls dispatch on
+    fn parse_defprotocol(&mut self) -> Result<GrammarElement, ParseError> {
+        let location = self.locations.get(self.current).copied();
+        let name = self.expect_symbol("protocol name", location)?;
+        let mut form = vec![
+            GrammarElement::Atom("defprotocol".to_string()),
+            GrammarElement::Atom(name.clone()),
+        ];
+        while !self.check(&Token::RightParen) {
+            self.consume(&Token::LeftParen, "Expected '(' before protocol method")?;
+            let method = self.expect_symbol("method name", location)?;
+            self.consume(&Token::LeftParen, "Expected '(' before method parameters")?;
+            let mut params = Vec::new();
             while !self.check(&Token::RightParen) {
-                body_exprs.push(self.parse_element()?);
+                params.push(GrammarElement::Atom(
+                    self.expect_symbol("method parameter", location)?,
+                ));
             }
+            self.consume(&Token::RightParen, "Expected ')' after method parameters")?;
+            // 可选的文档字符串 / Optional docstring
+            if self.check(&Token::String(String::new())) {
+                self.advance_token();
+            }
+            self.consume(&Token::RightParen, "Expected ')' after protocol method")?;
+            if params.is_empty() {
+                return Err(ParseError::syntax_error(
+                    format!("Protocol method {} needs a receiver parameter", method),
+                    location,
+                ));
+            }
+            form.push(GrammarElement::List(vec![
+                GrammarElement::Atom(method),
+                GrammarElement::List(params),
+            ]));
+        }
+        self.consume(&Token::RightParen, "Expected ')' after defprotocol")?;
+        if form.len() == 2 {
+            return Err(ParseError::syntax_error(
+                format!("defprotocol {} requires at least one method", name),
+                location,
+            ));
+        }
+        Ok(GrammarElement::List(form))
+    }
 
-            if body_exprs.is_empty() {
-                GrammarElement::Expr(Box::new(Expr::Literal(Literal::Null)))
-            } else if body_exprs.len() == 1 {
-                body_exprs.into_iter().next().unwrap()
-            } else {
-                // 多个表达式，包装在一个列表中
-                GrammarElement::List(body_exprs)
+    /// 解析 `(defimpl Protocol Type (method (self args...) body) ...)`，每个方法按 `def` 解析
+    /// Parse `(defimpl Protocol Type (method (self args...) body) ...)`, each method parsed like a `def`
+    fn parse_defimpl(&mut self) -> Result<GrammarElement, ParseError> {
+        let location = self.locations.get(self.current).copied();
+        let protocol = self.expect_symbol("protocol name", location)?;
+        let type_name = self.expect_symbol("type name", location)?;
+        let mut form = vec![
+            GrammarElement::Atom("defimpl".to_string()),
+            GrammarElement::Atom(protocol),
+            GrammarElement::Atom(type_name),
+        ];
+        while !self.check(&Token::RightParen) {
+            self.consume(
+                &Token::LeftParen,
+                "Expected '(' before method implementation",
+            )?;
+            form.push(self.parse_function_def("def".to_string())?);
+        }
+        self.consume(&Token::RightParen, "Expected ')' after defimpl")?;
+        Ok(GrammarElement::List(form))
+    }
+
+    /// 读取一个符号，否则报告期望的内容 / Read a symbol, otherwise report what was expected
+    fn expect_symbol(
+        &mut self,
+        what: &str,
+        location: Option<Location>,
+    ) -> Result<String, ParseError> {
+        match self.advance_token() {
+            Token::Symbol(name) => Ok(name),
+            other => Err(ParseError::syntax_error(
+                format!("Expected {}, got {:?}", what, other),
+                location,
+            )),
+        }
+    }
+
+    /// 解析类型标注：`Int` 或 `(List Int)` 等 / Parse a type annotation: `Int`, `(List Int)`, etc.
+    fn parse_type(&mut self) -> Result<GrammarElement, ParseError> {
+        match self.advance_token() {
+            Token::Symbol(name) => Ok(GrammarElement::Atom(name)),
+            Token::LeftParen => {
+                let mut parts = Vec::new();
+                while !self.check(&Token::RightParen) {
+                    if self.is_at_end() {
+                        return Err(ParseError::syntax_error(
+                            "Unterminated type annotation".to_string(),
+                            None,
+                        ));
+                    }
+                    parts.push(self.parse_type()?);
+                }
+                self.consume(&Token::RightParen, "Expected ')' after type")?;
+                Ok(GrammarElement::List(parts))
             }
-        };
+            token => Err(ParseError::syntax_error(
+                format!("Expected type, got {:?}", token),
+                None,
+            )),
+        }
+    }
+
+    fn parse_let(&mut self) -> Result<GrammarElement, ParseError> {
+        // (let ((x 1) (y 2)) body...) 多绑定形式 / Multi-binding form
+        if self.check(&Token::LeftParen) {
+            return self.parse_let_bindings("let");
+        }
+
+        // (let name value body...) 或 (let name value) - body 是可选的，但至少需要 name 和 value
+        let name = self.parse_element()?;
+        let value = self.parse_element()?;
+        let body = self.parse_let_body()?;
 
         // 消费结束括号
         self.consume(&Token::RightParen, "Expected ')' after let expression")?;
@@ -611,6 +1548,193 @@ impl ParserState {
         ]))
     }
 
+    /// 解析 let 的 body：没有 body 时为 null，多个表达式包装为 `begin`（含 lambda 等无法转换的元素时保留为列表）
+    /// Parse the body of a let: null when absent, several expressions are wrapped in a `begin` (kept as a list when
+    /// an element such as a lambda cannot be converted)
+    fn parse_let_body(&mut self) -> Result<GrammarElement, ParseError> {
+        let mut body_exprs = Vec::new();
+        while !self.check(&Token::RightParen) {
+            body_exprs.push(self.parse_element()?);
+        }
+
+        if body_exprs.len() <= 1 {
+            return Ok(body_exprs.pop().unwrap_or_else(|| {
+                // 没有body，使用 null 作为默认值
+                GrammarElement::Expr(Box::new(Expr::Literal(Literal::Null)))
+            }));
+        }
+        match body_exprs
+            .iter()
+            .map(|elem| self.element_to_expr(elem))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(exprs) => Ok(GrammarElement::Expr(Box::new(Expr::Begin(exprs)))),
+            // 多个表达式，包装在一个列表中
+            Err(_) => Ok(GrammarElement::List(body_exprs)),
+        }
+    }
+
+    /// 多绑定 let：`(let ((x 1) (y 2)) body...)` 先求出所有值再绑定，`(let* ((x 1) (y x)) body...)` 依次绑定，
+    /// 后面的值可以引用前面的变量。两者都脱糖为嵌套的单绑定 let；`let` 的值引用了同一列表中前面绑定的名称时，
+    /// 先绑定到 gensym 临时变量，保证所有值都在外层作用域中求值。绑定名可以是解构目标，如
+    /// `(let (((q r) (divmod 7 2))) ...)`，由解释器按列表模式绑定；读不成绑定列表时，`(let ((q r) (divmod 7 2)) ...)`
+    /// 读作单个解构绑定
+    /// Multi-binding let: `(let ((x 1) (y 2)) body...)` evaluates every value before binding, while
+    /// `(let* ((x 1) (y x)) body...)` binds in order, so later values may refer to earlier variables. Both desugar
+    /// into nested single-binding lets; when a `let` value refers to a name bound earlier in the same list, the values
+    /// are first bound to gensym temporaries so that all of them are evaluated in the outer scope. A binding name may
+    /// be a destructuring target such as `(let (((q r) (divmod 7 2))) ...)`, which the interpreter binds as a list
+    /// pattern; when the list does not read as bindings, `(let ((q r) (divmod 7 2)) ...)` is a single destructuring
+    /// binding
+    fn parse_let_bindings(&mut self, keyword: &str) -> Result<GrammarElement, ParseError> {
+        let location = self.locations.get(self.current).copied();
+        self.consume(
+            &Token::LeftParen,
+            &format!("Expected '(' for {} bindings", keyword),
+        )?;
+        let start = self.current;
+        let mut bindings = match self.parse_binding_list(keyword, location) {
+            Ok(bindings) => bindings,
+            // 读不成绑定列表时按单个解构绑定读取 / Not a binding list, so read it as a single destructuring binding
+            Err(error) => {
+                self.current = start;
+                self.parse_destructuring_binding(keyword, location)
+                    .map_err(|_| error)?
+            }
+        };
+        let mut body = self.parse_let_body()?;
+        self.consume(&Token::RightParen, "Expected ')' after let expression")?;
+
+        if bindings.is_empty() {
+            return Err(ParseError::syntax_error(
+                format!("{} requires at least one binding", keyword),
+                location,
+            ));
+        }
+
+        let parallel = keyword == "let"
+            && bindings.iter().enumerate().any(|(index, (_, _, value))| {
+                bindings[..index]
+                    .iter()
+                    .any(|(_, names, _)| names.iter().any(|name| element_mentions(value, name)))
+            });
+        if parallel {
+            let temporaries: Vec<String> = bindings
+                .iter()
+                .map(|(_, names, _)| gensym(names.first().map_or("tuple", String::as_str)))
+                .collect();
+            let renamed = bindings
+                .iter()
+                .zip(&temporaries)
+                .map(|((target, names, _), temporary)| {
+                    let value =
+                        GrammarElement::Expr(Box::new(Expr::Var(temporary.as_str().into())));
+                    (target.clone(), names.clone(), value)
+                })
+                .collect::<Vec<_>>();
+            bindings = temporaries
+                .into_iter()
+                .zip(bindings)
+                .map(|(temporary, (_, _, value))| {
+                    let target =
+                        GrammarElement::Expr(Box::new(Expr::Var(temporary.as_str().into())));
+                    (target, vec![temporary], value)
+                })
+                .chain(renamed)
+                .collect();
+        }
+
+        for (target, _, value) in bindings.into_iter().rev() {
+            body = GrammarElement::List(vec![
+                GrammarElement::Atom("let".to_string()),
+                target,
+                value,
+                body,
+            ]);
+        }
+        Ok(body)
+    }
+
+    /// 解析绑定列表 `((x 1) (y 2))` 中左括号之后的部分 / Parse a binding list `((x 1) (y 2))` after its opening paren
+    fn parse_binding_list(
+        &mut self,
+        keyword: &str,
+        location: Option<Location>,
+    ) -> Result<Vec<(GrammarElement, Vec<String>, GrammarElement)>, ParseError> {
+        let mut bindings = Vec::new();
+        while !self.check(&Token::RightParen) {
+            self.consume(
+                &Token::LeftParen,
+                &format!("Expected '(' for {} binding", keyword),
+            )?;
+            let (target, names) = if self.check(&Token::LeftParen) {
+                self.parse_binding_target(keyword, location)?
+            } else {
+                match self.advance_token() {
+                    Token::Symbol(name) => (
+                        GrammarElement::Expr(Box::new(Expr::Var(name.as_str().into()))),
+                        vec![name],
+                    ),
+                    other => {
+                        return Err(ParseError::syntax_error(
+                            format!("{} binding name must be a symbol, got {:?}", keyword, other),
+                            location,
+                        ))
+                    }
+                }
+            };
+            let value = self.parse_element()?;
+            self.consume(&Token::RightParen, "Expected ')' after let binding")?;
+            bindings.push((target, names, value));
+        }
+        self.consume(&Token::RightParen, "Expected ')' after let bindings")?;
+        Ok(bindings)
+    }
+
+    /// 解析单个解构绑定 `((q r) (divmod 7 2))` 中左括号之后的部分
+    /// Parse a single destructuring binding `((q r) (divmod 7 2))` after its opening paren
+    fn parse_destructuring_binding(
+        &mut self,
+        keyword: &str,
+        location: Option<Location>,
+    ) -> Result<Vec<(GrammarElement, Vec<String>, GrammarElement)>, ParseError> {
+        if !self.check(&Token::LeftParen) {
+            return Err(ParseError::syntax_error(
+                format!("Expected a {} destructuring target", keyword),
+                location,
+            ));
+        }
+        let (target, names) = self.parse_binding_target(keyword, location)?;
+        let value = self.parse_element()?;
+        self.consume(&Token::RightParen, "Expected ')' after let binding")?;
+        Ok(vec![(target, names, value)])
+    }
+
+    /// 解析解构目标 `(q r ..rest)` 或 `(dict "k" v)`，返回列表字面量形式的目标和它绑定的变量名
+    /// Parse a destructuring target `(q r ..rest)` or `(dict "k" v)`, returning the target as a list literal along
+    /// with the names it binds
+    fn parse_binding_target(
+        &mut self,
+        keyword: &str,
+        location: Option<Location>,
+    ) -> Result<(GrammarElement, Vec<String>), ParseError> {
+        let element = self.parse_element()?;
+        let pattern = self.element_to_pattern(&element).ok();
+        match pattern.as_ref().and_then(Pattern::to_binding) {
+            Some(target) => Ok((
+                GrammarElement::Expr(Box::new(target)),
+                pattern.map(|pattern| pattern.bound_names()).unwrap_or_default(),
+            )),
+            None => Err(ParseError::syntax_error(
+                format!(
+                    "{} destructuring target may only contain variables, _, ..rest, lists and dicts",
+                    keyword
+                ),
+                location,
+            )),
+        }
+    }
+
     fn parse_if(&mut self) -> Result<GrammarElement, ParseError> {
         // (if condition then_expr else_expr)
         let condition = self.parse_element()?;
@@ -684,21 +1808,17 @@ impl ParserState {
 
             // 解析模式
             let pattern_elem = self.parse_element()?;
-            // 特殊处理：如果模式被解析为 Expr::Call("_", ...)，直接转换为通配符模式
-            // 这可以避免 _ 被错误地解析为函数调用
-            let pattern = if let GrammarElement::Expr(boxed_expr) = &pattern_elem {
-                if let Expr::Call(name, _) = boxed_expr.as_ref() {
-                    if name == "_" {
-                        use crate::grammar::core::Pattern::Wildcard;
-                        Wildcard
-                    } else {
-                        self.element_to_pattern(&pattern_elem)?
-                    }
-                } else {
-                    self.element_to_pattern(&pattern_elem)?
-                }
+            let pattern = self.element_to_pattern(&pattern_elem)?;
+
+            // 可选的守卫子句: (pattern when guard expr)
+            // Optional guard clause: (pattern when guard expr)
+            let pattern = if self.check_symbol("when") {
+                self.advance_token();
+                let guard_elem = self.parse_element()?;
+                let guard = self.element_to_expr(&guard_elem)?;
+                Pattern::Guard(Box::new(pattern), Box::new(guard))
             } else {
-                self.element_to_pattern(&pattern_elem)?
+                pattern
             };
 
             // 解析表达式
@@ -735,7 +1855,7 @@ impl ParserState {
             GrammarElement::Atom(s) => s.clone(),
             GrammarElement::Expr(boxed_expr) => {
                 if let Expr::Var(s) = boxed_expr.as_ref() {
-                    s.clone()
+                    s.to_string()
                 } else {
                     return Err(ParseError::syntax_error(
                         "For loop variable must be an identifier".to_string(),
@@ -766,12 +1886,94 @@ impl ParserState {
         };
 
         Ok(GrammarElement::Expr(Box::new(Expr::For {
-            var,
+            var: var.into(),
             iterable: Box::new(iterable_expr),
             body: Box::new(body_expr),
         })))
     }
 
+    /// 推导式：`(for/list (x xs) body...)`、`(for/dict (k v d) body...)`，脱糖为包在推导式调用中的 `for` 循环，
+    /// 两个变量时第二个变量作为调用的第二个参数
+    /// Comprehension: `(for/list (x xs) body...)` or `(for/dict (k v d) body...)`, desugared into a `for` loop
+    /// wrapped in a call to the comprehension; with two variables, the second becomes the call's second argument
+    fn parse_comprehension(&mut self, keyword: String) -> Result<GrammarElement, ParseError> {
+        self.consume(
+            &Token::LeftParen,
+            &format!("Expected '(' for {} binding", keyword),
+        )?;
+        let mut binding = Vec::new();
+        while !self.check(&Token::RightParen) {
+            binding.push(self.parse_element()?);
+        }
+        self.consume(
+            &Token::RightParen,
+            "Expected ')' after comprehension binding",
+        )?;
+
+        let mut body_elements = Vec::new();
+        while !self.check(&Token::RightParen) {
+            body_elements.push(self.parse_element()?);
+        }
+        self.consume(&Token::RightParen, "Expected ')' after comprehension")?;
+
+        let Some((iterable_elem, variables)) = binding.split_last() else {
+            return Err(ParseError::syntax_error(
+                format!("{} binding requires a variable and an iterable", keyword),
+                None,
+            ));
+        };
+        let variables = variables
+            .iter()
+            .map(|variable| match variable {
+                GrammarElement::Atom(name) => Ok(name.clone()),
+                GrammarElement::Expr(boxed_expr) => match boxed_expr.as_ref() {
+                    Expr::Var(name) => Ok(name.to_string()),
+                    _ => Err(()),
+                },
+                _ => Err(()),
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .filter(|variables| matches!(variables.len(), 1 | 2))
+            .ok_or_else(|| {
+                ParseError::syntax_error(
+                    format!(
+                        "{} binding must be (var iterable) or (key value iterable)",
+                        keyword
+                    ),
+                    None,
+                )
+            })?;
+        if body_elements.is_empty() {
+            return Err(ParseError::syntax_error(
+                format!("{} requires a body", keyword),
+                None,
+            ));
+        }
+
+        let iterable_expr = self.element_to_expr(iterable_elem)?;
+        let body_exprs = body_elements
+            .iter()
+            .map(|elem| self.element_to_expr(elem))
+            .collect::<Result<Vec<_>, _>>()?;
+        let body_expr = match <[Expr; 1]>::try_from(body_exprs) {
+            Ok([body]) => body,
+            Err(body_exprs) => Expr::Begin(body_exprs),
+        };
+
+        let mut variables = variables.into_iter();
+        let mut args = vec![Expr::For {
+            var: variables.next().unwrap_or_default().into(),
+            iterable: Box::new(iterable_expr),
+            body: Box::new(body_expr),
+        }];
+        args.extend(variables.map(|variable| Expr::Var(variable.into())));
+        Ok(GrammarElement::Expr(Box::new(Expr::Call(
+            keyword.into(),
+            args,
+        ))))
+    }
+
     fn parse_while(&mut self) -> Result<GrammarElement, ParseError> {
         // (while condition body...)
         let condition_elem = self.parse_element()?;
@@ -803,6 +2005,21 @@ impl ParserState {
         })))
     }
 
+    fn parse_begin(&mut self) -> Result<GrammarElement, ParseError> {
+        // (begin expr1 expr2 ...)
+        let mut body_elements = Vec::new();
+        while !self.check(&Token::RightParen) {
+            body_elements.push(self.parse_element()?);
+        }
+        self.consume(&Token::RightParen, "Expected ')' after begin expression")?;
+
+        let body_exprs: Vec<Expr> = body_elements
+            .iter()
+            .map(|elem| self.element_to_expr(elem))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(GrammarElement::Expr(Box::new(Expr::Begin(body_exprs))))
+    }
+
     fn parse_try(&mut self) -> Result<GrammarElement, ParseError> {
         // (try try_body catch [var] catch_body)
         let try_body_elem = self.parse_element()?;
@@ -837,7 +2054,7 @@ impl ParserState {
                             if let Expr::Var(name) = boxed_expr.as_ref() {
                                 // 检查后面是否还有元素
                                 if !self.check(&Token::RightParen) {
-                                    Some(name.clone())
+                                    Some(name.to_string())
                                 } else {
                                     // 后面是右括号，这是 catch_body
                                     return Ok(GrammarElement::Expr(Box::new(Expr::Try {
@@ -884,63 +2101,89 @@ impl ParserState {
 
         Ok(GrammarElement::Expr(Box::new(Expr::Try {
             try_body: Box::new(try_body_expr),
-            catch_var,
+            catch_var: catch_var.map(Into::into),
             catch_body: Box::new(catch_body_expr),
         })))
     }
 
     fn element_to_pattern(&self, elem: &GrammarElement) -> Result<Pattern, ParseError> {
-        use crate::grammar::core::Pattern::*;
         match elem {
-            GrammarElement::Atom(s) => {
-                if s == "_" {
-                    Ok(Wildcard)
-                } else {
-                    Ok(Var(s.clone()))
+            GrammarElement::Atom(s) => Ok(Self::symbol_to_pattern(s)),
+            GrammarElement::Expr(boxed_expr) => self.expr_to_pattern(boxed_expr),
+            GrammarElement::List(list) => {
+                let mut patterns = Vec::new();
+                for item in list {
+                    patterns.push(self.element_to_pattern(item)?);
                 }
+                Ok(Pattern::List(patterns))
             }
-            GrammarElement::Expr(boxed_expr) => match boxed_expr.as_ref() {
-                Expr::Literal(lit) => Ok(Literal(lit.clone())),
-                Expr::Var(name) => {
-                    // 如果变量名是 "_"，这是通配符模式
-                    if name == "_" {
-                        Ok(Wildcard)
-                    } else {
-                        Ok(Var(name.clone()))
-                    }
-                }
-                // 允许Call表达式，因为在某些情况下列表可能被解析为Call
-                // 如果模式是 (list ...) 这样的，需要特殊处理
-                // 如果函数名是 "_"，这可能是错误解析导致的，应该被忽略
-                Expr::Call(name, _) => {
-                    // 如果函数名是 "_"，这可能是错误解析，应该返回通配符模式
-                    if name == "_" {
-                        Ok(Wildcard)
-                    } else if name == "list" || name == "vec" {
-                        // 对于 list/vec 模式，需要在 parse_match 中特殊处理
-                        // 这里暂时返回错误，因为它应该已经在 parse_list 中处理
-                        Err(ParseError::syntax_error(
-                            "List pattern should not be parsed as function call".to_string(),
-                            None,
-                        ))
+            _ => Err(ParseError::syntax_error(
+                "Invalid pattern in match expression".to_string(),
+                None,
+            )),
+        }
+    }
+
+    /// 符号模式：`_` 为通配符，`..name` 为剩余模式，其余为变量绑定
+    /// Symbol pattern: `_` is a wildcard, `..name` a rest pattern, anything else binds a variable
+    fn symbol_to_pattern(name: &str) -> Pattern {
+        if name == "_" {
+            Pattern::Wildcard
+        } else if let Some(rest) = name.strip_prefix("..") {
+            Pattern::Rest((!rest.is_empty()).then(|| rest.into()))
+        } else {
+            Pattern::Var(name.into())
+        }
+    }
+
+    fn expr_to_pattern(&self, expr: &Expr) -> Result<Pattern, ParseError> {
+        match expr {
+            // (list a b ..rest) 和 (dict "k" p) 作为结构模式
+            // (list a b ..rest) and (dict "k" p) act as structural patterns
+            Expr::Literal(Literal::List(items)) => Ok(Pattern::List(
+                items
+                    .iter()
+                    .map(|item| self.expr_to_pattern(item))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            Expr::Literal(Literal::Dict(pairs)) => Ok(Pattern::Dict(
+                pairs
+                    .iter()
+                    .map(|(key, value)| Ok((key.clone(), self.expr_to_pattern(value)?)))
+                    .collect::<Result<Vec<_>, ParseError>>()?,
+            )),
+            Expr::Literal(lit) => Ok(Pattern::Literal(lit.clone())),
+            Expr::Var(name) => Ok(Self::symbol_to_pattern(name)),
+            Expr::Call(name, args) if name == "or" => Ok(Pattern::Or(
+                args.iter()
+                    .map(|arg| self.expr_to_pattern(arg))
+                    .collect::<Result<Vec<_>, _>>()?,
+            )),
+            // (prefix "http://" rest) 和 (suffix ".evo" stem) 匹配字符串的开头和结尾
+            // (prefix "http://" rest) and (suffix ".evo" stem) match the start and end of a string
+            Expr::Call(name, args) if name == "prefix" || name == "suffix" => match args.as_slice()
+            {
+                [Expr::Literal(Literal::String(affix)), rest] => {
+                    let rest = Box::new(self.expr_to_pattern(rest)?);
+                    Ok(if name == "prefix" {
+                        Pattern::Prefix(affix.clone(), rest)
                     } else {
-                        Err(ParseError::syntax_error(
-                            "Invalid pattern in match expression".to_string(),
-                            None,
-                        ))
-                    }
+                        Pattern::Suffix(affix.clone(), rest)
+                    })
                 }
                 _ => Err(ParseError::syntax_error(
-                    "Invalid pattern in match expression".to_string(),
+                    format!("{} pattern must be ({} \"text\" pattern)", name, name),
                     None,
                 )),
             },
-            GrammarElement::List(list) => {
-                let mut patterns = Vec::new();
-                for item in list {
-                    patterns.push(self.element_to_pattern(item)?);
+            // 以符号开头的列表 (a b ..rest) 被解析为调用，还原为列表模式
+            // A list starting with a symbol (a b ..rest) parses as a call; restore it as a list pattern
+            Expr::Call(name, args) => {
+                let mut patterns = vec![Self::symbol_to_pattern(name)];
+                for arg in args {
+                    patterns.push(self.expr_to_pattern(arg)?);
                 }
-                Ok(List(patterns))
+                Ok(Pattern::List(patterns))
             }
             _ => Err(ParseError::syntax_error(
                 "Invalid pattern in match expression".to_string(),
@@ -989,7 +2232,7 @@ impl ParserState {
                     if let Expr::Literal(Literal::String(s)) = boxed_expr.as_ref() {
                         s.clone()
                     } else if let Expr::Var(s) = boxed_expr.as_ref() {
-                        s.clone()
+                        s.to_string()
                     } else {
                         return Err(ParseError::syntax_error(
                             "Dictionary key must be a string or identifier".to_string(),
@@ -1033,6 +2276,60 @@ impl ParserState {
         }
     }
 
+    /// 插值字符串脱糖为 `(string-concat ...)` 调用
+    /// Desugar interpolated string into a `(string-concat ...)` call
+    fn parse_interpolated_string(&mut self) -> Result<GrammarElement, ParseError> {
+        let parts = match self.advance_token() {
+            Token::InterpolatedString(parts) => parts,
+            _ => {
+                return Err(ParseError::syntax_error(
+                    "Expected interpolated string".to_string(),
+                    None,
+                ))
+            }
+        };
+
+        let mut args = Vec::new();
+        for part in parts {
+            match part {
+                StringPart::Text(text) => args.push(Expr::Literal(Literal::String(text))),
+                StringPart::Expr(source) => {
+                    let tokens = Tokenizer::new(&source).tokenize()?;
+                    let elements = ParserState::new(tokens).parse_all()?;
+                    if elements.len() != 1 {
+                        return Err(ParseError::syntax_error(
+                            format!(
+                                "String interpolation must contain exactly one expression: {{{}}}",
+                                source
+                            ),
+                            None,
+                        ));
+                    }
+                    args.push(self.element_to_expr(&elements[0])?);
+                }
+            }
+        }
+
+        Ok(GrammarElement::Expr(Box::new(Expr::Call(
+            "string-concat".into(),
+            args,
+        ))))
+    }
+
+    /// 日期字面量脱糖为 `(parse-date "...")` 调用 / Desugar a date literal into a `(parse-date "...")` call
+    fn parse_date_literal(&mut self) -> Result<GrammarElement, ParseError> {
+        match self.advance_token() {
+            Token::DateTime(text) => Ok(GrammarElement::Expr(Box::new(Expr::Call(
+                "parse-date".into(),
+                vec![Expr::Literal(Literal::String(text))],
+            )))),
+            _ => Err(ParseError::syntax_error(
+                "Expected date literal".to_string(),
+                None,
+            )),
+        }
+    }
+
     fn parse_number(&mut self) -> Result<GrammarElement, ParseError> {
         match self.advance_token() {
             Token::Number(n) => {
@@ -1043,9 +2340,15 @@ impl ParserState {
                         .map_err(|_| {
                             ParseError::syntax_error(format!("Invalid float: {}", n), None)
                         })
+                } else if let Ok(i) = n.parse::<i64>() {
+                    Ok(GrammarElement::Expr(Box::new(Expr::Literal(Literal::Int(
+                        i,
+                    )))))
                 } else {
-                    n.parse::<i64>()
-                        .map(|i| GrammarElement::Expr(Box::new(Expr::Literal(Literal::Int(i)))))
+                    // 超出i64范围的整数字面量解析为大整数
+                    // Integer literals outside i64 range are parsed as big integers
+                    n.parse::<num_bigint::BigInt>()
+                        .map(|b| GrammarElement::Expr(Box::new(Expr::Literal(Literal::BigInt(b)))))
                         .map_err(|_| {
                             ParseError::syntax_error(format!("Invalid integer: {}", n), None)
                         })
@@ -1077,7 +2380,7 @@ impl ParserState {
                         if self.parse_binop(&s).is_some() {
                             Ok(GrammarElement::Atom(format!("op:{}", s)))
                         } else {
-                            Ok(GrammarElement::Expr(Box::new(Expr::Var(s))))
+                            Ok(GrammarElement::Expr(Box::new(Expr::Var(s.into()))))
                         }
                     }
                 }
@@ -1114,9 +2417,9 @@ impl ParserState {
                 if s.starts_with("op:") {
                     // 操作符可以作为函数参数使用，将其转换为变量
                     let op_name = s.strip_prefix("op:").unwrap_or(s);
-                    Ok(Expr::Var(op_name.to_string()))
+                    Ok(Expr::Var(op_name.into()))
                 } else {
-                    Ok(Expr::Var(s.clone()))
+                    Ok(Expr::Var(s.as_str().into()))
                 }
             }
             GrammarElement::List(l) => {
@@ -1174,7 +2477,7 @@ impl ParserState {
                                     GrammarElement::Atom(s) => s.clone(),
                                     GrammarElement::Expr(boxed_expr) => {
                                         if let Expr::Var(s) = boxed_expr.as_ref() {
-                                            s.clone()
+                                            s.to_string()
                                         } else if let Expr::Literal(Literal::String(s)) =
                                             boxed_expr.as_ref()
                                         {
@@ -1229,7 +2532,7 @@ impl ParserState {
                                 GrammarElement::Atom(s) => s.clone(),
                                 GrammarElement::Expr(boxed_expr) => {
                                     if let Expr::Var(s) = boxed_expr.as_ref() {
-                                        s.clone()
+                                        s.to_string()
                                     } else {
                                         return Err(ParseError::syntax_error(
                                             "Function name must be an atom or variable".to_string(),
@@ -1248,7 +2551,7 @@ impl ParserState {
                                 .iter()
                                 .map(|e| self.element_to_expr(e))
                                 .collect::<Result<Vec<_>, _>>()?;
-                            Ok(Expr::Call(func_name, args))
+                            Ok(Expr::Call(func_name.into(), args))
                         }
                     }
                 }
@@ -1260,6 +2563,162 @@ impl ParserState {
         }
     }
 
+    /// 解析 `(defmacro name (params) template)` 并注册宏，定义本身求值为null
+    /// Parse `(defmacro name (params) template)` and register the macro; the definition itself evaluates to null
+    fn parse_defmacro(&mut self) -> Result<GrammarElement, ParseError> {
+        let location = self.locations.get(self.current).copied();
+        self.advance_token(); // defmacro
+        let name = match self.advance_token() {
+            Token::Symbol(name) => name,
+            other => {
+                return Err(ParseError::syntax_error(
+                    format!("Expected macro name, got {:?}", other),
+                    location,
+                ))
+            }
+        };
+        self.consume(&Token::LeftParen, "Expected '(' before macro parameters")?;
+        let mut params = Vec::new();
+        while !self.check(&Token::RightParen) {
+            match self.advance_token() {
+                Token::Symbol(param) => params.push(param),
+                other => {
+                    return Err(ParseError::syntax_error(
+                        format!("Macro parameter must be a symbol, got {:?}", other),
+                        location,
+                    ))
+                }
+            }
+        }
+        self.consume(&Token::RightParen, "Expected ')' after macro parameters")?;
+
+        let start = self.current;
+        let end = self.element_end(start)?;
+        let template = self.tokens[start..end].to_vec();
+        self.current = end;
+        self.consume(&Token::RightParen, "Expected ')' after macro template")?;
+
+        let definition = Macro::from_tokens(name.clone(), params, template)
+            .map_err(|e| ParseError::syntax_error(e.message().to_string(), location))?;
+        self.macros.insert(name, definition);
+        Ok(GrammarElement::Expr(Box::new(Expr::Literal(Literal::Null))))
+    }
+
+    /// 将 `open` 处开始的宏调用替换为展开后的词法单元，展开结果中的宏会在继续解析时展开
+    /// Replace the macro call starting at `open` with its expansion; macros in the result expand as parsing continues
+    fn expand_macro(&mut self, name: &str, open: usize) -> Result<(), ParseError> {
+        let location = self.locations.get(open).copied();
+        if self.macro_depth > MAX_MACRO_DEPTH {
+            return Err(ParseError::syntax_error(
+                format!(
+                    "Macro expansion depth limit exceeded while expanding '{}'",
+                    name
+                ),
+                location,
+            ));
+        }
+
+        let mut args = Vec::new();
+        let mut index = self.current + 1;
+        while self.tokens.get(index) != Some(&Token::RightParen) {
+            let end = self.element_end(index)?;
+            args.push(self.tokens[index..end].to_vec());
+            index = end;
+        }
+        let end = index + 1;
+
+        let definition = &self.macros[name];
+        if !definition.accepts(args.len()) {
+            return Err(ParseError::syntax_error(
+                format!(
+                    "Macro '{}' expects {}{} argument(s), got {}",
+                    name,
+                    if definition.rest.is_some() {
+                        "at least "
+                    } else {
+                        ""
+                    },
+                    definition.params.len(),
+                    args.len()
+                ),
+                location,
+            ));
+        }
+        let expansion = definition.expand(&args);
+
+        if !self.locations.is_empty() {
+            let filler = location.unwrap_or_else(|| Location::new(0, 0));
+            self.locations
+                .splice(open..end, std::iter::repeat_n(filler, expansion.len()));
+        }
+        self.tokens.splice(open..end, expansion);
+        self.current = open;
+        Ok(())
+    }
+
+    /// `(a name b)` 中的中缀宏移到列表开头，成为 `(name a b)`，返回是否移动
+    /// Move an infix macro in `(a name b)` to the head of the list, giving `(name a b)`; returns whether it moved
+    fn move_infix_macro(&mut self) -> bool {
+        if !self.macros.values().any(|definition| definition.infix)
+            || self.check(&Token::RightParen)
+        {
+            return false;
+        }
+        let Ok(end) = self.element_end(self.current) else {
+            return false;
+        };
+        match self.tokens.get(end) {
+            Some(Token::Symbol(name)) if self.macros.get(name).is_some_and(|m| m.infix) => {
+                let operator = self.tokens.remove(end);
+                self.tokens.insert(self.current, operator);
+                if end < self.locations.len() {
+                    let location = self.locations.remove(end);
+                    self.locations.insert(self.current, location);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// 从 `start` 开始的一个完整元素之后的位置 / Position just past the complete element starting at `start`
+    fn element_end(&self, start: usize) -> Result<usize, ParseError> {
+        let mut index = start;
+        while self.tokens.get(index) == Some(&Token::Quote) {
+            index += 1;
+        }
+        let mut depth = 0usize;
+        loop {
+            match self.tokens.get(index) {
+                None | Some(Token::EOF) => {
+                    return Err(ParseError::syntax_error(
+                        "Unexpected end of input in macro form".to_string(),
+                        self.locations.get(start).copied(),
+                    ))
+                }
+                Some(Token::RightParen) if depth == 0 => {
+                    return Err(ParseError::syntax_error(
+                        "Unexpected ')' in macro form".to_string(),
+                        self.locations.get(index).copied(),
+                    ))
+                }
+                Some(Token::LeftParen) => depth += 1,
+                Some(Token::RightParen) => depth -= 1,
+                _ => {}
+            }
+            index += 1;
+            if depth == 0 {
+                return Ok(index);
+            }
+        }
+    }
+
+    /// 检查当前token是否为指定名称的符号（`check` 只比较token种类）
+    /// Check whether the current token is the named symbol (`check` only compares token kinds)
+    fn check_symbol(&self, name: &str) -> bool {
+        matches!(self.tokens.get(self.current), Some(Token::Symbol(s)) if s == name)
+    }
+
     fn check(&self, token: &Token) -> bool {
         if self.is_at_end() {
             false
@@ -1309,7 +2768,7 @@ impl ParserState {
 }
 
 /// 源代码位置 / Source code location
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Location {
     /// 行号（从1开始）/ Line number (1-based)
     pub line: usize,
@@ -1368,6 +2827,34 @@ impl ParseError {
         }
     }
 
+    /// 缺少位置时补上位置 / Fill in the location when missing
+    pub(crate) fn with_location(self, fallback: Location) -> Self {
+        match self {
+            Self::SyntaxError {
+                message,
+                location: None,
+            } => Self::SyntaxError {
+                message,
+                location: Some(fallback),
+            },
+            Self::UnknownSyntax {
+                message,
+                location: None,
+            } => Self::UnknownSyntax {
+                message,
+                location: Some(fallback),
+            },
+            Self::RuleConflict {
+                message,
+                location: None,
+            } => Self::RuleConflict {
+                message,
+                location: Some(fallback),
+            },
+            error => error,
+        }
+    }
+
     /// 获取位置信息 / Get location
     pub fn location(&self) -> Option<Location> {
         match self {
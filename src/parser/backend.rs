@@ -0,0 +1,240 @@
+// NLU后端 / NLU backends
+// 意图识别、代码生成和代码解释的可插拔后端：内置规则引擎，以及兼容OpenAI接口的HTTP模型服务
+// Pluggable backends for intent recognition, code generation and code explanation: the built-in rule engine and
+// an HTTP model service with an OpenAI-compatible API
+
+use crate::grammar::core::GrammarElement;
+use crate::parser::adaptive::AdaptiveParser;
+use crate::parser::explainer::{CodeExplainer, Language};
+use crate::parser::nlu::{IntentType, NLUError, NLUParser, ParsedIntent};
+use crate::runtime::http::post_json;
+use std::time::Duration;
+
+/// NLU后端 / NLU backend
+pub trait NluBackend: Send + Sync {
+    /// 后端名称 / Backend name
+    fn name(&self) -> &str;
+
+    /// 识别自然语言的意图并给出代码结构 / Recognize the intent of natural language and produce its code structure
+    fn parse_intent(&self, input: &str) -> Result<ParsedIntent, NLUError>;
+
+    /// 把自然语言描述转换为Evo源码 / Turn a natural-language description into Evo source code
+    fn generate_code(&self, description: &str) -> Result<String, NLUError>;
+
+    /// 用自然语言解释Evo源码 / Explain Evo source code in natural language
+    fn explain(&self, code: &str) -> Result<String, NLUError>;
+}
+
+/// 基于规则的后端（默认）/ Rule-based backend (the default)
+pub struct RuleBasedBackend {
+    parser: NLUParser,
+    language: Language,
+}
+
+impl RuleBasedBackend {
+    /// 创建规则后端，解释使用中文 / Create a rule-based backend that explains in Chinese
+    pub fn new() -> Self {
+        Self {
+            parser: NLUParser::new_rule_based(),
+            language: Language::Chinese,
+        }
+    }
+
+    /// 设置解释语言 / Set the explanation language
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+}
+
+impl Default for RuleBasedBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NluBackend for RuleBasedBackend {
+    fn name(&self) -> &str {
+        "rule-based"
+    }
+
+    fn parse_intent(&self, input: &str) -> Result<ParsedIntent, NLUError> {
+        self.parser.parse_intent(input)
+    }
+
+    fn generate_code(&self, description: &str) -> Result<String, NLUError> {
+        self.parser.generate_code(description)
+    }
+
+    fn explain(&self, code: &str) -> Result<String, NLUError> {
+        let ast = parse_code(code)?;
+        Ok(CodeExplainer::new(self.language).explain_ast(&ast))
+    }
+}
+
+/// 意图识别的系统提示 / System prompt for intent recognition
+const INTENT_PROMPT: &str =
+    "You translate programming requests into Evo-lang, a Lisp-like language: \
+(def name (params) body), (let name value), (if cond then else), (+ a b), (list ...), (print x). \
+Reply with a single JSON object and nothing else: {\"intent\": one of \"define_function\", \
+\"define_variable\", \"execute_operation\", \"conditional\", \"extend_syntax\", \"other\"; \
+\"code\": the Evo-lang code; \"confidence\": a number between 0 and 1}.";
+
+/// 代码生成的系统提示 / System prompt for code generation
+const CODE_PROMPT: &str = "You write Evo-lang, a Lisp-like language: (def name (params) body), \
+(let name value), (if cond then else), (+ a b), (list ...), (print x). \
+Reply with Evo-lang code only, without explanations or Markdown.";
+
+/// 代码解释的系统提示 / System prompt for code explanation
+const EXPLAIN_PROMPT: &str =
+    "Explain what the following Evo-lang (a Lisp-like language) code does, \
+briefly, in the language of any comments in it, otherwise in Chinese.";
+
+/// 兼容OpenAI接口的HTTP后端（`POST {endpoint}/chat/completions`）
+/// HTTP backend for OpenAI-compatible APIs (`POST {endpoint}/chat/completions`)
+///
+/// 共用的 `runtime::http` 客户端不支持TLS，因此只支持 `http://` 地址（如本地的 Ollama、llama.cpp、vLLM 服务）；
+/// `https://` 地址的每次请求都返回 `NLUError::ModelError`，HTTPS 服务需经本地代理访问
+/// The shared `runtime::http` client has no TLS, so only `http://` endpoints are supported (such as local Ollama,
+/// llama.cpp or vLLM servers); every request to an `https://` endpoint returns `NLUError::ModelError`, so reach
+/// HTTPS services through a local proxy
+#[derive(Debug, Clone)]
+pub struct HttpBackend {
+    /// 接口根地址，如 `http://localhost:11434/v1` / API base URL, e.g. `http://localhost:11434/v1`
+    endpoint: String,
+    /// 模型名称 / Model name
+    model: String,
+    /// API密钥（可选）/ API key (optional)
+    api_key: Option<String>,
+    /// 请求超时 / Request timeout
+    timeout: Duration,
+}
+
+impl HttpBackend {
+    /// 创建HTTP后端（超时30秒）/ Create an HTTP backend (30 second timeout)
+    pub fn new(endpoint: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            model: model.into(),
+            api_key: None,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// 从环境变量 `EVO_LLM_ENDPOINT`、`EVO_LLM_MODEL`、`EVO_LLM_API_KEY` 创建，未设置地址时返回 `None`
+    /// Create from the `EVO_LLM_ENDPOINT`, `EVO_LLM_MODEL` and `EVO_LLM_API_KEY` environment variables; `None`
+    /// when no endpoint is set
+    pub fn from_env() -> Option<Self> {
+        let endpoint = std::env::var("EVO_LLM_ENDPOINT").ok()?;
+        let model = std::env::var("EVO_LLM_MODEL").unwrap_or_else(|_| "default".to_string());
+        let backend = Self::new(endpoint, model);
+        Some(match std::env::var("EVO_LLM_API_KEY") {
+            Ok(key) => backend.with_api_key(key),
+            Err(_) => backend,
+        })
+    }
+
+    /// 设置API密钥 / Set the API key
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// 设置请求超时 / Set the request timeout
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// 发送一轮对话，返回模型回复 / Send one chat turn and return the model's reply
+    fn complete(&self, system: &str, user: &str) -> Result<String, NLUError> {
+        let request = serde_json::json!({
+            "model": self.model,
+            "temperature": 0,
+            "messages": [
+                {"role": "system", "content": system},
+                {"role": "user", "content": user},
+            ],
+        });
+        let url = format!("{}/chat/completions", self.endpoint);
+        let body = post_json(
+            &url,
+            &request.to_string(),
+            self.api_key.as_deref(),
+            self.timeout,
+        )
+        .map_err(NLUError::ModelError)?;
+        let response: serde_json::Value = serde_json::from_str(&body)
+            .map_err(|e| NLUError::ModelError(format!("Invalid response JSON: {}", e)))?;
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|content| content.trim().to_string())
+            .ok_or_else(|| {
+                NLUError::ModelError(format!("Response has no message content: {}", body))
+            })
+    }
+}
+
+impl NluBackend for HttpBackend {
+    fn name(&self) -> &str {
+        &self.model
+    }
+
+    fn parse_intent(&self, input: &str) -> Result<ParsedIntent, NLUError> {
+        let reply = self.complete(INTENT_PROMPT, input)?;
+        let json = strip_fence(&reply);
+        let json = json
+            .find('{')
+            .zip(json.rfind('}'))
+            .map(|(start, end)| &json[start..=end])
+            .unwrap_or(json);
+        let value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| NLUError::ModelError(format!("Invalid intent JSON: {}", e)))?;
+        let code = value["code"].as_str().unwrap_or_default();
+        let intent_type = match value["intent"].as_str().unwrap_or_default() {
+            "define_function" => IntentType::DefineFunction,
+            "define_variable" => IntentType::DefineVariable,
+            "execute_operation" => IntentType::ExecuteOperation,
+            "conditional" => IntentType::Conditional,
+            "extend_syntax" => IntentType::ExtendSyntax,
+            other => IntentType::Other(other.to_string()),
+        };
+        Ok(ParsedIntent {
+            intent_type,
+            code_structure: parse_code(code)?,
+            confidence: value["confidence"].as_f64().unwrap_or(0.5).clamp(0.0, 1.0),
+            suggested_rules: vec![],
+        })
+    }
+
+    fn generate_code(&self, description: &str) -> Result<String, NLUError> {
+        let reply = self.complete(CODE_PROMPT, description)?;
+        let code = strip_fence(&reply).to_string();
+        // 确认回复是可解析的代码 / Make sure the reply is parseable code
+        parse_code(&code)?;
+        Ok(code)
+    }
+
+    fn explain(&self, code: &str) -> Result<String, NLUError> {
+        self.complete(EXPLAIN_PROMPT, code)
+    }
+}
+
+/// 解析模型或规则给出的代码 / Parse code produced by a model or the rules
+fn parse_code(code: &str) -> Result<Vec<GrammarElement>, NLUError> {
+    AdaptiveParser::new(false)
+        .parse(code)
+        .map_err(|e| NLUError::ModelError(format!("Generated code does not parse: {}", e)))
+}
+
+/// 去掉Markdown代码块围栏 / Strip a Markdown code fence
+fn strip_fence(reply: &str) -> &str {
+    let reply = reply.trim();
+    match reply.strip_prefix("```") {
+        Some(rest) => {
+            let rest = rest.split_once('\n').map_or("", |(_, body)| body);
+            rest.trim_end().trim_end_matches("```").trim()
+        }
+        None => reply,
+    }
+}
@@ -2,10 +2,14 @@
 // 维护对话历史和状态，支持多轮对话
 // Maintains conversation history and state, supports multi-turn conversations
 
-use crate::grammar::core::GrammarElement;
-use crate::parser::nlu::{ParsedIntent, ProgrammingIntent};
+use crate::grammar::core::{Expr, GrammarElement};
+use crate::grammar::visit::preorder;
+use crate::parser::nlu::{
+    IntentType, NLUError, NLUParser, ParseOutcome, ParsedIntent, ProgrammingIntent,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
 /// 上下文管理器 / Context manager
 pub struct ContextManager {
@@ -17,6 +21,36 @@ pub struct ContextManager {
     functions: HashMap<String, ParsedIntent>,
     /// 当前会话ID / Current session ID
     session_id: String,
+    /// 等待用户回答的澄清 / Clarification awaiting the user's answer
+    pending: Option<PendingClarification>,
+    /// 跨会话的用户画像 / Cross-session user profile
+    profile: UserProfile,
+}
+
+/// 等待回答的澄清 / Clarification awaiting an answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingClarification {
+    /// 提出澄清的轮次 / Turn that asked for clarification
+    pub turn_id: usize,
+    /// 原始输入 / Original input
+    pub input: String,
+    /// 提出的问题 / Question asked
+    pub question: String,
+    /// 候选意图 / Candidate intents
+    pub candidates: Vec<ParsedIntent>,
+}
+
+/// 指向生成某段代码的对话意图 / Link to the conversation intent that generated a piece of code
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IntentLink {
+    /// 轮次ID / Turn ID
+    pub turn_id: usize,
+    /// 该轮的用户输入 / User input of the turn
+    pub input: String,
+    /// 意图类型 / Intent type
+    pub intent_type: IntentType,
+    /// 置信度 / Confidence
+    pub confidence: f64,
 }
 
 /// 对话轮次 / Conversation turn
@@ -34,19 +68,309 @@ pub struct ConversationTurn {
     pub execution_result: Option<String>,
     /// 时间戳 / Timestamp
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// 本轮向用户提出的澄清问题 / Clarification question asked in this turn
+    #[serde(default)]
+    pub clarification: Option<String>,
+}
+
+/// 命名风格 / Naming style
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NamingStyle {
+    /// 短横线连接（`my-func`）/ Kebab case (`my-func`)
+    Kebab,
+    /// 下划线连接（`my_func`）/ Snake case (`my_func`)
+    Snake,
+    /// 驼峰（`myFunc`）/ Camel case (`myFunc`)
+    Camel,
+    /// 中文名称（`求和`）/ Chinese names (`求和`)
+    Chinese,
+    /// 单个小写单词（`sum`）/ A single lowercase word (`sum`)
+    Lower,
+}
+
+impl NamingStyle {
+    /// 判断名称的风格 / Classify the style of a name
+    pub fn of(name: &str) -> Option<Self> {
+        if name.chars().any(|c| !c.is_ascii() && c.is_alphabetic()) {
+            Some(NamingStyle::Chinese)
+        } else if !name.chars().any(|c| c.is_ascii_alphabetic()) {
+            None
+        } else if name.contains('-') {
+            Some(NamingStyle::Kebab)
+        } else if name.contains('_') {
+            Some(NamingStyle::Snake)
+        } else if name.chars().skip(1).any(|c| c.is_ascii_uppercase()) {
+            Some(NamingStyle::Camel)
+        } else {
+            Some(NamingStyle::Lower)
+        }
+    }
+
+    /// 按该风格连接英文单词；中文风格使用给出的中文名称
+    /// Join English words in this style; the Chinese style uses the given Chinese name
+    pub fn join(self, words: &[&str], chinese: &str) -> String {
+        match self {
+            NamingStyle::Kebab => words.join("-"),
+            NamingStyle::Snake => words.join("_"),
+            NamingStyle::Camel => words
+                .iter()
+                .enumerate()
+                .map(|(i, word)| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) if i > 0 => first.to_uppercase().chain(chars).collect(),
+                        _ => word.to_string(),
+                    }
+                })
+                .collect(),
+            NamingStyle::Chinese => chinese.to_string(),
+            NamingStyle::Lower => words.last().copied().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+/// 跨会话的用户画像：从对话历史中累积词汇、命名风格、常用函数和常见错误，
+/// 供代码生成器和NLU的默认参数猜测使用；只保存计数，可随时查看和清除
+/// Cross-session user profile: accumulates vocabulary, naming style, frequently used functions and common mistakes
+/// from the conversation history, for the code generator and the NLU's default parameter guesses; it stores counts
+/// only and can be inspected and reset at any time
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserProfile {
+    /// 参与累积的会话数 / Number of sessions that contributed
+    pub sessions: usize,
+    /// 输入中的词及次数 / Words in the input and their counts
+    pub vocabulary: BTreeMap<String, usize>,
+    /// 定义的名称的风格及次数 / Styles of defined names and their counts
+    pub naming: BTreeMap<NamingStyle, usize>,
+    /// 定义或调用的函数及次数 / Functions defined or called and their counts
+    pub functions: BTreeMap<String, usize>,
+    /// 参数列表（空格分隔）及次数 / Parameter lists (space-separated) and their counts
+    pub parameter_lists: BTreeMap<String, usize>,
+    /// 错误类别及次数 / Mistake categories and their counts
+    pub mistakes: BTreeMap<String, usize>,
+}
+
+/// 不计入常用函数的特殊形式 / Special forms not counted as used functions
+const SPECIAL_FORMS: &[&str] = &[
+    "def", "function", "let", "if", "lambda", "fn", "match", "import", "export", "deftype",
+    "defmacro", "begin", "do", "set!", "cond", "quote", "yield", "try", "deftest", "let*",
+    "defconst", "defn-",
+];
+
+impl UserProfile {
+    /// 创建空画像 / Create an empty profile
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 从JSON文件加载 / Load from a JSON file
+    pub fn load(path: &Path) -> Result<Self, ContextError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| ContextError::ProfileError(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_str(&json)
+            .map_err(|e| ContextError::ProfileError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// 保存为JSON文件 / Save as a JSON file
+    pub fn save(&self, path: &Path) -> Result<(), ContextError> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| ContextError::ProfileError(e.to_string()))?;
+        std::fs::write(path, json)
+            .map_err(|e| ContextError::ProfileError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// 清除全部记录，用于保护隐私 / Clear every record, for privacy
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// 是否还没有任何记录 / Whether nothing has been recorded yet
+    pub fn is_empty(&self) -> bool {
+        self.vocabulary.is_empty()
+            && self.naming.is_empty()
+            && self.functions.is_empty()
+            && self.parameter_lists.is_empty()
+            && self.mistakes.is_empty()
+    }
+
+    /// 记录一条输入中的词：连续的字母数字（含 `-`、`_`）或连续的汉字各算一个词
+    /// Record the words of an input: runs of ASCII alphanumerics (with `-` and `_`) or of other letters each count
+    /// as one word
+    pub fn observe_input(&mut self, input: &str) {
+        let mut word = String::new();
+        let mut ascii = true;
+        for c in input.chars().chain(std::iter::once(' ')) {
+            let is_word = c.is_alphanumeric() || c == '-' || c == '_';
+            if is_word && (word.is_empty() || c.is_ascii() == ascii) {
+                ascii = c.is_ascii();
+                word.push(c);
+                continue;
+            }
+            // 单个ASCII字母多是变量名，不计入词汇 / Single ASCII letters are mostly variable names and are not counted
+            if word.len() == 1 && ascii {
+                word.clear();
+            }
+            if !word.is_empty() {
+                *self
+                    .vocabulary
+                    .entry(std::mem::take(&mut word).to_lowercase())
+                    .or_insert(0) += 1;
+            }
+            if is_word {
+                ascii = c.is_ascii();
+                word.push(c);
+            }
+        }
+    }
+
+    /// 记录代码中定义的名称、参数列表和调用的函数 / Record defined names, parameter lists and called functions in code
+    pub fn observe_code(&mut self, code: &[GrammarElement]) {
+        for element in code {
+            self.observe_element(element);
+        }
+    }
+
+    fn observe_element(&mut self, element: &GrammarElement) {
+        match element {
+            GrammarElement::List(list) => {
+                let head = match list.first() {
+                    Some(GrammarElement::Atom(head)) => head.as_str(),
+                    _ => "",
+                };
+                if let Some(name) = defined_name(element) {
+                    if let Some(style) = NamingStyle::of(name) {
+                        *self.naming.entry(style).or_insert(0) += 1;
+                    }
+                    if !matches!(head, "let" | "defconst") {
+                        *self.functions.entry(name.to_string()).or_insert(0) += 1;
+                    }
+                }
+                if matches!(head, "def" | "function" | "defn-") {
+                    if let Some(GrammarElement::List(params)) = list.get(2) {
+                        let names: Vec<&str> = params
+                            .iter()
+                            .filter_map(|param| match param {
+                                GrammarElement::Atom(name) => Some(name.as_str()),
+                                GrammarElement::Expr(expr) => match expr.as_ref() {
+                                    Expr::Var(name) => Some(name.as_str()),
+                                    _ => None,
+                                },
+                                _ => None,
+                            })
+                            .collect();
+                        if !names.is_empty() {
+                            *self.parameter_lists.entry(names.join(" ")).or_insert(0) += 1;
+                        }
+                    }
+                } else if !head.is_empty() && !SPECIAL_FORMS.contains(&head) {
+                    *self.functions.entry(head.to_string()).or_insert(0) += 1;
+                }
+                let skip = if matches!(head, "def" | "function" | "defn-") {
+                    3
+                } else {
+                    1
+                };
+                for child in list.iter().skip(skip) {
+                    self.observe_element(child);
+                }
+            }
+            GrammarElement::Expr(expr) => {
+                for expr in preorder(expr) {
+                    if let Expr::Call(name, _) = expr {
+                        *self.functions.entry(name.to_string()).or_insert(0) += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 记录一次错误，类别取冒号前的部分（如 `Runtime error`）
+    /// Record a mistake; the category is the part before the first colon (such as `Runtime error`)
+    pub fn record_mistake(&mut self, message: &str) {
+        let category = message.split([':', '：']).next().unwrap_or(message).trim();
+        if !category.is_empty() {
+            *self.mistakes.entry(category.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// 最常用的命名风格；单个小写单词只在没有其他风格时算作偏好
+    /// Preferred naming style; single lowercase words count as a preference only when no other style was seen
+    pub fn preferred_naming_style(&self) -> Option<NamingStyle> {
+        self.naming
+            .iter()
+            .filter(|(style, _)| **style != NamingStyle::Lower)
+            .max_by_key(|(_, count)| **count)
+            .or_else(|| self.naming.iter().max_by_key(|(_, count)| **count))
+            .map(|(style, _)| *style)
+    }
+
+    /// 最常用的参数列表，作为NLU的默认参数猜测 / Most common parameter list, the NLU's default parameter guess
+    pub fn default_parameters(&self) -> Option<Vec<String>> {
+        self.parameter_lists
+            .iter()
+            .max_by_key(|(_, count)| **count)
+            .map(|(list, _)| list.split(' ').map(str::to_string).collect())
+    }
+
+    /// 最常见的词 / Most frequent words
+    pub fn top_vocabulary(&self, limit: usize) -> Vec<(String, usize)> {
+        top_entries(&self.vocabulary, limit)
+    }
+
+    /// 最常用的函数 / Most frequently used functions
+    pub fn frequent_functions(&self, limit: usize) -> Vec<(String, usize)> {
+        top_entries(&self.functions, limit)
+    }
+
+    /// 最常见的错误类别 / Most common mistake categories
+    pub fn common_mistakes(&self, limit: usize) -> Vec<(String, usize)> {
+        top_entries(&self.mistakes, limit)
+    }
+}
+
+/// 按次数降序取前若干项，次数相同按名称排列 / Top entries by count descending, ties ordered by name
+fn top_entries(counts: &BTreeMap<String, usize>, limit: usize) -> Vec<(String, usize)> {
+    let mut entries: Vec<(String, usize)> = counts
+        .iter()
+        .map(|(key, count)| (key.clone(), *count))
+        .collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    entries.truncate(limit);
+    entries
 }
 
 impl ContextManager {
     /// 创建新上下文管理器 / Create new context manager
     pub fn new(session_id: String) -> Self {
+        Self::with_profile(session_id, UserProfile::new())
+    }
+
+    /// 以之前会话累积的用户画像创建上下文管理器 / Create a context manager with a profile accumulated in earlier sessions
+    pub fn with_profile(session_id: String, mut profile: UserProfile) -> Self {
+        profile.sessions += 1;
         Self {
             history: Vec::new(),
             variables: HashMap::new(),
             functions: HashMap::new(),
             session_id,
+            pending: None,
+            profile,
         }
     }
 
+    /// 用户画像 / User profile
+    pub fn profile(&self) -> &UserProfile {
+        &self.profile
+    }
+
+    /// 清除用户画像，对话历史保持不变 / Reset the user profile, keeping the conversation history
+    pub fn reset_profile(&mut self) {
+        self.profile.reset();
+    }
+
     /// 添加上下文 / Add context
     pub fn add_turn(&mut self, user_input: String, intent: Option<ParsedIntent>) -> usize {
         let turn_id = self.history.len();
@@ -57,11 +381,14 @@ impl ContextManager {
             generated_code: None,
             execution_result: None,
             timestamp: chrono::Utc::now(),
+            clarification: None,
         };
         self.history.push(turn);
+        self.profile.observe_input(&user_input);
 
         // 更新变量和函数上下文 / Update variable and function context
         if let Some(intent) = intent {
+            self.profile.observe_code(&intent.code_structure);
             match intent.intent_type {
                 crate::parser::nlu::IntentType::DefineVariable => {
                     // 提取变量名和值 / Extract variable name and value
@@ -89,6 +416,121 @@ impl ContextManager {
         turn_id
     }
 
+    /// 解析一轮输入：有待回答的澄清时，先把输入当作回答（选项编号、序数或意图名称）来解决歧义；
+    /// 回答无法对应候选时把它当作新的说法重新解析。解析出的意图和澄清问题都记入历史
+    /// Parse one turn of input: with a clarification pending, first treat the input as its answer (an option number,
+    /// an ordinal or an intent name) to resolve the ambiguity; an answer matching no candidate is parsed afresh as a
+    /// rephrasing. Both recognized intents and clarification questions are recorded in the history
+    pub fn parse_turn(&mut self, nlu: &NLUParser, input: &str) -> Result<ParseOutcome, NLUError> {
+        if let Some(pending) = self.pending.take() {
+            if let Some(intent) = Self::resolve_answer(&pending, input) {
+                self.add_turn(input.to_string(), Some(intent.clone()));
+                return Ok(ParseOutcome::Parsed(intent));
+            }
+        }
+        let outcome = nlu.parse(input)?;
+        match &outcome {
+            ParseOutcome::Parsed(intent) => {
+                self.add_turn(input.to_string(), Some(intent.clone()));
+            }
+            ParseOutcome::NeedsClarification {
+                question,
+                candidates,
+            } => {
+                let turn_id = self.add_turn(input.to_string(), None);
+                self.history[turn_id].clarification = Some(question.clone());
+                self.profile.record_mistake("Ambiguous input");
+                self.pending = Some(PendingClarification {
+                    turn_id,
+                    input: input.to_string(),
+                    question: question.clone(),
+                    candidates: candidates.clone(),
+                });
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// 记录某轮生成的代码 / Record the code generated for a turn
+    pub fn set_generated_code(&mut self, turn_id: usize, code: Vec<GrammarElement>) {
+        if let Some(turn) = self.history.get_mut(turn_id) {
+            // 意图的代码结构已在 add_turn 中记录 / The intent's code structure was recorded by add_turn
+            if turn.intent.as_ref().map(|intent| &intent.code_structure) != Some(&code) {
+                self.profile.observe_code(&code);
+            }
+            turn.generated_code = Some(code);
+        }
+    }
+
+    /// 查找生成该顶层形式的最近一轮意图：先比较生成的代码和意图的代码结构，再比较定义的名称
+    /// Find the latest turn whose intent generated this top-level form: generated code and the intent's code structure
+    /// are compared first, then the defined name
+    pub fn intent_for(&self, element: &GrammarElement) -> Option<IntentLink> {
+        let name = defined_name(element);
+        self.history.iter().rev().find_map(|turn| {
+            let intent = turn.intent.as_ref()?;
+            let generated = turn
+                .generated_code
+                .iter()
+                .flatten()
+                .chain(&intent.code_structure);
+            let matches = generated.clone().any(|form| form == element)
+                || (name.is_some() && generated.clone().any(|form| defined_name(form) == name));
+            matches.then(|| IntentLink {
+                turn_id: turn.turn_id,
+                input: turn.user_input.clone(),
+                intent_type: intent.intent_type.clone(),
+                confidence: intent.confidence,
+            })
+        })
+    }
+
+    /// 等待回答的澄清 / Clarification awaiting an answer
+    pub fn pending_clarification(&self) -> Option<&PendingClarification> {
+        self.pending.as_ref()
+    }
+
+    /// 把回答对应到候选意图 / Match an answer to a candidate intent
+    fn resolve_answer(pending: &PendingClarification, answer: &str) -> Option<ParsedIntent> {
+        let answer = answer
+            .trim()
+            .trim_end_matches(['.', '。', '、'])
+            .to_lowercase();
+        let ordinals = [
+            ["1", "一", "第一个", "第1个", "first"],
+            ["2", "二", "第二个", "第2个", "second"],
+            ["3", "三", "第三个", "第3个", "third"],
+            ["4", "四", "第四个", "第4个", "fourth"],
+            ["5", "五", "第五个", "第5个", "fifth"],
+        ];
+        if let Some(index) = ordinals
+            .iter()
+            .position(|names| names.contains(&answer.as_str()))
+        {
+            return pending.candidates.get(index).cloned();
+        }
+        let intent_type = if answer.contains("函数") || answer.contains("function") {
+            IntentType::DefineFunction
+        } else if answer.contains("变量") || answer.contains("variable") {
+            IntentType::DefineVariable
+        } else if answer.contains("条件") || answer.contains("condition") {
+            IntentType::Conditional
+        } else if answer.contains("运算")
+            || answer.contains("计算")
+            || answer.contains("operation")
+            || answer.contains("calculat")
+        {
+            IntentType::ExecuteOperation
+        } else {
+            return None;
+        };
+        pending
+            .candidates
+            .iter()
+            .find(|candidate| candidate.intent_type == intent_type)
+            .cloned()
+    }
+
     /// 获取上下文 / Get context
     pub fn get_context(&self, lookback: usize) -> Vec<ConversationTurn> {
         let start = if self.history.len() > lookback {
@@ -289,16 +731,22 @@ impl ContextManager {
         &self.history
     }
 
-    /// 清除上下文 / Clear context
+    /// 清除上下文；用户画像跨会话保留，用 `reset_profile()` 清除
+    /// Clear the context; the user profile persists across sessions and is cleared with `reset_profile()`
     pub fn clear(&mut self) {
         self.history.clear();
         self.variables.clear();
         self.functions.clear();
+        self.pending = None;
     }
 
     /// 更新执行结果 / Update execution result
     pub fn update_execution_result(&mut self, turn_id: usize, result: String) {
         if let Some(turn) = self.history.get_mut(turn_id) {
+            let lower = result.to_lowercase();
+            if lower.contains("error") || result.contains("错误") {
+                self.profile.record_mistake(&result);
+            }
             turn.execution_result = Some(result);
         }
     }
@@ -348,6 +796,8 @@ pub enum ContextError {
     ReferenceNotFound(String),
     /// 解析错误 / Parse error
     ParseError(String),
+    /// 用户画像读写失败 / Failed to read or write the user profile
+    ProfileError(String),
 }
 
 impl std::fmt::Display for ContextError {
@@ -355,8 +805,31 @@ impl std::fmt::Display for ContextError {
         match self {
             ContextError::ReferenceNotFound(msg) => write!(f, "Reference not found: {}", msg),
             ContextError::ParseError(msg) => write!(f, "Parse error: {}", msg),
+            ContextError::ProfileError(msg) => write!(f, "Profile error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for ContextError {}
+
+/// `(def name ...)` 或 `(let name ...)` 定义的名称 / Name defined by `(def name ...)` or `(let name ...)`
+fn defined_name(element: &GrammarElement) -> Option<&str> {
+    let GrammarElement::List(list) = element else {
+        return None;
+    };
+    let head = match list.first() {
+        Some(GrammarElement::Atom(head)) => head.as_str(),
+        _ => return None,
+    };
+    if !matches!(head, "def" | "function" | "defn-" | "let" | "defconst") {
+        return None;
+    }
+    match list.get(1)? {
+        GrammarElement::Atom(name) => Some(name),
+        GrammarElement::Expr(expr) => match expr.as_ref() {
+            Expr::Var(name) => Some(name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
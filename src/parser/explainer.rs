@@ -3,6 +3,11 @@
 // Converts code structures to natural language descriptions
 
 use crate::grammar::core::{BinOp, Expr, GrammarElement, Literal};
+use crate::grammar::inference::TypeInference;
+use crate::grammar::types::{definition_docstring, FunctionSignature, Type};
+use crate::parser::context::{ContextManager, IntentLink};
+use crate::parser::incremental::{ParsedDocument, Span, SyntaxKind, SyntaxNode};
+use serde::{Deserialize, Serialize};
 
 /// 代码解释器 / Code explainer
 pub struct CodeExplainer {
@@ -19,6 +24,110 @@ pub enum Language {
     English,
 }
 
+/// 结构化解释节点的种类 / Kind of a structured explanation node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExplanationKind {
+    /// 函数定义 / Function definition
+    FunctionDefinition,
+    /// 变量定义 / Variable definition
+    VariableDefinition,
+    /// 条件 / Conditional
+    Conditional,
+    /// 函数调用 / Function call
+    Call,
+    /// 运算 / Operation
+    Operation,
+    /// 循环 / Loop
+    Loop,
+    /// Lambda / Lambda
+    Lambda,
+    /// 模式匹配 / Pattern match
+    Match,
+    /// 异常处理 / Exception handling
+    Try,
+    /// 表达式块 / Expression block
+    Block,
+    /// 赋值 / Assignment
+    Assignment,
+    /// 字面量 / Literal
+    Literal,
+    /// 变量引用 / Variable reference
+    Variable,
+    /// 原子 / Atom
+    Atom,
+    /// 其他列表 / Other list
+    List,
+    /// 自然语言 / Natural language
+    NaturalLanguage,
+}
+
+/// 结构化解释：一个AST节点的源码范围、一句话说明、生成它的NLU意图和子节点的解释
+/// Structured explanation: an AST node's source span, one-line gloss, the NLU intent that generated it and its children's explanations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    /// 节点种类 / Node kind
+    pub kind: ExplanationKind,
+    /// 源码中的字节范围（没有源码时为空）/ Byte span in the source (None without source)
+    pub span: Option<Span>,
+    /// 一句话说明 / One-line gloss
+    pub gloss: String,
+    /// 生成该节点的意图 / Intent that generated the node
+    pub intent: Option<IntentLink>,
+    /// 子节点 / Children
+    pub children: Vec<Explanation>,
+}
+
+impl Explanation {
+    fn leaf(kind: ExplanationKind, span: Option<Span>, gloss: String) -> Self {
+        Self {
+            kind,
+            span,
+            gloss,
+            intent: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// 包含偏移的最内层节点 / Innermost node containing the offset
+    pub fn node_at(&self, offset: usize) -> Option<&Explanation> {
+        if !self.span.is_some_and(|span| span.contains(offset)) {
+            return None;
+        }
+        self.children
+            .iter()
+            .find_map(|child| child.node_at(offset))
+            .or(Some(self))
+    }
+
+    /// 按缩进渲染为多行文本 / Render as indented lines of text
+    pub fn render(&self) -> String {
+        let mut lines = Vec::new();
+        self.render_into(0, &mut lines);
+        lines.join("\n")
+    }
+
+    fn render_into(&self, depth: usize, lines: &mut Vec<String>) {
+        let mut line = format!("{}{}", "  ".repeat(depth), self.gloss);
+        if let Some(span) = self.span {
+            line.push_str(&format!(" [{}..{}]", span.start, span.end));
+        }
+        if let Some(intent) = &self.intent {
+            line.push_str(&format!(" <- #{} \"{}\"", intent.turn_id, intent.input));
+        }
+        lines.push(line);
+        for child in &self.children {
+            child.render_into(depth + 1, lines);
+        }
+    }
+}
+
+/// 语法节点的第 `index` 个子节点（仅当它是列表时）/ The `index`-th child of a syntax node (only when it is a list)
+fn syntax_child(node: Option<&SyntaxNode>, index: usize) -> Option<&SyntaxNode> {
+    node.filter(|node| node.kind == SyntaxKind::List)
+        .and_then(|node| node.children.get(index))
+}
+
 impl CodeExplainer {
     /// 创建新代码解释器 / Create new code explainer
     pub fn new(language: Language) -> Self {
@@ -35,6 +144,160 @@ impl CodeExplainer {
         }
     }
 
+    /// 解释代码结构，并附上函数的文档字符串或其前面的文档注释
+    /// Explain code structure, followed by the function's docstring or its leading doc comment
+    pub fn explain_with_comment(&self, element: &GrammarElement, comment: Option<&str>) -> String {
+        let explanation = self.explain(element);
+        let comment = match element {
+            GrammarElement::List(items) => definition_docstring(items).or(comment),
+            _ => comment,
+        };
+        match (comment, self.language) {
+            (Some(comment), Language::Chinese) => format!("{}\n文档：{}", explanation, comment),
+            (Some(comment), Language::English) => format!("{}\nDoc: {}", explanation, comment),
+            (None, _) => explanation,
+        }
+    }
+
+    /// 生成结构化解释树（不含源码范围）/ Build a structured explanation tree (without source spans)
+    pub fn explain_tree(&self, element: &GrammarElement) -> Explanation {
+        self.element_tree(element, None)
+    }
+
+    /// 为解析后的文档生成结构化解释：节点带字节范围，顶层形式链接到 `context` 中生成它的意图
+    /// Build structured explanations for a parsed document: nodes carry byte spans, and top-level forms link to the
+    /// intent in `context` that generated them
+    pub fn explain_document(
+        &self,
+        document: &ParsedDocument,
+        context: Option<&ContextManager>,
+    ) -> Vec<Explanation> {
+        document
+            .elements
+            .iter()
+            .enumerate()
+            .map(|(index, element)| {
+                let mut explanation = self.element_tree(element, document.nodes.get(index));
+                explanation.intent = context.and_then(|context| context.intent_for(element));
+                explanation
+            })
+            .collect()
+    }
+
+    /// AST元素的解释树，`node` 为对应的语法节点 / Explanation tree of an AST element, `node` is its syntax node
+    fn element_tree(&self, element: &GrammarElement, node: Option<&SyntaxNode>) -> Explanation {
+        let span = node.map(|node| node.span);
+        match element {
+            GrammarElement::Expr(expr) => self.expr_tree(expr, node),
+            GrammarElement::Atom(atom) => {
+                Explanation::leaf(ExplanationKind::Atom, span, self.explain_atom(atom))
+            }
+            GrammarElement::NaturalLang(nl) => {
+                Explanation::leaf(ExplanationKind::NaturalLanguage, span, nl.clone())
+            }
+            GrammarElement::List(list) => {
+                let head = match list.first() {
+                    Some(GrammarElement::Atom(head)) => head.as_str(),
+                    _ => "",
+                };
+                // 函数说明只含签名，函数体由子节点解释；变量定义只解释值
+                // The function gloss only has the signature and children explain the body; a variable definition only explains its value
+                let (kind, gloss, children) = match head {
+                    "def" | "function" | "defn-" => (
+                        ExplanationKind::FunctionDefinition,
+                        self.describe_function_definition(list, false),
+                        3..list.len(),
+                    ),
+                    "let" | "defconst" => (
+                        ExplanationKind::VariableDefinition,
+                        self.explain_list(list),
+                        2..list.len().min(3),
+                    ),
+                    "if" => (
+                        ExplanationKind::Conditional,
+                        self.explain_list(list),
+                        1..list.len(),
+                    ),
+                    "" => (
+                        ExplanationKind::List,
+                        self.explain_list(list),
+                        0..list.len(),
+                    ),
+                    _ => (
+                        ExplanationKind::Call,
+                        self.explain_list(list),
+                        1..list.len(),
+                    ),
+                };
+                let children = children
+                    .map(|index| self.element_tree(&list[index], syntax_child(node, index)))
+                    .collect();
+                Explanation {
+                    kind,
+                    span,
+                    gloss,
+                    intent: None,
+                    children,
+                }
+            }
+        }
+    }
+
+    /// 表达式的解释树，子表达式按其在 `(关键字 ...)` 形式中的位置对应语法节点
+    /// Explanation tree of an expression; subexpressions map to syntax nodes by their position in the `(keyword ...)` form
+    fn expr_tree(&self, expr: &Expr, node: Option<&SyntaxNode>) -> Explanation {
+        let positioned: Vec<(usize, &Expr)> = match expr {
+            Expr::Literal(Literal::List(items)) => {
+                items.iter().enumerate().map(|(i, e)| (i + 1, e)).collect()
+            }
+            Expr::Literal(_) | Expr::Var(_) => Vec::new(),
+            Expr::Call(_, args) => args.iter().enumerate().map(|(i, e)| (i + 1, e)).collect(),
+            Expr::Binary(_, left, right) => vec![(1, left.as_ref()), (2, right.as_ref())],
+            Expr::If(cond, then_expr, else_expr) => vec![
+                (1, cond.as_ref()),
+                (2, then_expr.as_ref()),
+                (3, else_expr.as_ref()),
+            ],
+            Expr::Match(value, arms) => std::iter::once((1, value.as_ref()))
+                .chain(arms.iter().map(|(_, arm)| (usize::MAX, arm)))
+                .collect(),
+            Expr::For { iterable, body, .. } => vec![(2, iterable.as_ref()), (3, body.as_ref())],
+            Expr::While { condition, body } => vec![(1, condition.as_ref()), (2, body.as_ref())],
+            Expr::Try {
+                try_body,
+                catch_body,
+                ..
+            } => vec![(1, try_body.as_ref()), (usize::MAX, catch_body.as_ref())],
+            Expr::Lambda { body, .. } => vec![(2, body.as_ref())],
+            Expr::Begin(exprs) => exprs.iter().enumerate().map(|(i, e)| (i + 1, e)).collect(),
+            Expr::Assign(_, value) => vec![(2, value.as_ref())],
+        };
+        let kind = match expr {
+            Expr::Literal(_) => ExplanationKind::Literal,
+            Expr::Var(_) => ExplanationKind::Variable,
+            Expr::Call(name, _) if name.starts_with("op:") => ExplanationKind::Operation,
+            Expr::Call(..) => ExplanationKind::Call,
+            Expr::Binary(..) => ExplanationKind::Operation,
+            Expr::If(..) => ExplanationKind::Conditional,
+            Expr::Match(..) => ExplanationKind::Match,
+            Expr::For { .. } | Expr::While { .. } => ExplanationKind::Loop,
+            Expr::Try { .. } => ExplanationKind::Try,
+            Expr::Lambda { .. } => ExplanationKind::Lambda,
+            Expr::Begin(_) => ExplanationKind::Block,
+            Expr::Assign(..) => ExplanationKind::Assignment,
+        };
+        Explanation {
+            kind,
+            span: node.map(|node| node.span),
+            gloss: self.explain_expr(expr),
+            intent: None,
+            children: positioned
+                .into_iter()
+                .map(|(index, child)| self.expr_tree(child, syntax_child(node, index)))
+                .collect(),
+        }
+    }
+
     /// 解释表达式 / Explain expression
     fn explain_expr(&self, expr: &Expr) -> String {
         match expr {
@@ -67,10 +330,10 @@ impl CodeExplainer {
         // 检查是否是特殊形式 / Check if special form
         if let Some(GrammarElement::Atom(first)) = list.first() {
             match first.as_str() {
-                "def" | "function" => {
+                "def" | "function" | "defn-" => {
                     return self.explain_function_definition(list);
                 }
-                "let" => {
+                "let" | "defconst" => {
                     return self.explain_variable_definition(list);
                 }
                 "if" => {
@@ -102,6 +365,11 @@ impl CodeExplainer {
 
     /// 解释函数定义 / Explain function definition
     fn explain_function_definition(&self, list: &[GrammarElement]) -> String {
+        self.describe_function_definition(list, true)
+    }
+
+    /// 描述函数定义，`with_body` 为假时省略函数体 / Describe a function definition, omitting the body unless `with_body`
+    fn describe_function_definition(&self, list: &[GrammarElement], with_body: bool) -> String {
         if list.len() < 3 {
             return match self.language {
                 Language::Chinese => "不完整的函数定义".to_string(),
@@ -119,6 +387,10 @@ impl CodeExplainer {
                 .iter()
                 .filter_map(|p| match p {
                     GrammarElement::Atom(name) => Some(name.clone()),
+                    GrammarElement::Expr(expr) => match expr.as_ref() {
+                        Expr::Var(name) => Some(name.to_string()),
+                        _ => None,
+                    },
                     _ => None,
                 })
                 .collect::<Vec<_>>(),
@@ -134,7 +406,23 @@ impl CodeExplainer {
             }
         };
 
-        match self.language {
+        let signature = TypeInference::infer_function(list)
+            .and_then(|signature| self.describe_signature(&name, &signature))
+            .unwrap_or_default();
+
+        let explanation = match self.language {
+            _ if !with_body => match (self.language, params.is_empty()) {
+                (Language::Chinese, true) => format!("定义函数 {}", name),
+                (Language::Chinese, false) => {
+                    format!("定义函数 {}，参数：{}", name, params.join("、"))
+                }
+                (Language::English, true) => format!("define function {}", name),
+                (Language::English, false) => format!(
+                    "define function {} with parameters {}",
+                    name,
+                    params.join(", ")
+                ),
+            },
             Language::Chinese => {
                 if params.is_empty() {
                     format!("定义函数 {}，函数体：{}", name, body)
@@ -159,6 +447,109 @@ impl CodeExplainer {
                     )
                 }
             }
+        };
+        explanation + &signature
+    }
+
+    /// 描述推断出的函数签名，类型全部未知时返回None
+    /// Describe an inferred function signature, None if every type is unknown
+    fn describe_signature(&self, name: &str, signature: &FunctionSignature) -> Option<String> {
+        if signature.return_type == Type::Any
+            && signature.params.iter().all(|(_, t)| *t == Type::Any)
+        {
+            return None;
+        }
+
+        // 相邻的同类型参数合并计数 / Adjacent parameters of the same type are counted together
+        let mut groups: Vec<(&Type, usize)> = Vec::new();
+        for (_, ty) in &signature.params {
+            match groups.last_mut() {
+                Some((last, count)) if *last == ty => *count += 1,
+                _ => groups.push((ty, 1)),
+            }
+        }
+        let parts: Vec<String> = groups
+            .iter()
+            .map(|(ty, count)| self.describe_type(ty, *count))
+            .collect();
+        let returns = self.describe_type(&signature.return_type, 1);
+
+        Some(match self.language {
+            Language::Chinese if parts.is_empty() => format!("；{} 返回{}", name, returns),
+            Language::Chinese => format!("；{} 接受{}，返回{}", name, parts.join("和"), returns),
+            Language::English if parts.is_empty() => format!("; {} returns {}", name, returns),
+            Language::English => {
+                let taken = match parts.split_last() {
+                    Some((last, rest)) if !rest.is_empty() => {
+                        format!("{} and {}", rest.join(", "), last)
+                    }
+                    _ => parts.join(""),
+                };
+                format!("; {} takes {} and returns {}", name, taken, returns)
+            }
+        })
+    }
+
+    /// 带数量的类型描述，如 "two numbers" / Type description with a count, such as "two numbers"
+    fn describe_type(&self, ty: &Type, count: usize) -> String {
+        match self.language {
+            Language::Chinese => {
+                let noun = match ty {
+                    Type::Any | Type::Var(_) => "任意值".to_string(),
+                    Type::Int => "整数".to_string(),
+                    Type::Float => "浮点数".to_string(),
+                    Type::Number => "数字".to_string(),
+                    Type::String => "字符串".to_string(),
+                    Type::Bool => "布尔值".to_string(),
+                    Type::Null => "空值".to_string(),
+                    Type::List(inner) if **inner != Type::Any => {
+                        format!("{}列表", self.describe_type(inner, 0))
+                    }
+                    Type::List(_) => "列表".to_string(),
+                    Type::Dict(_) => "字典".to_string(),
+                    Type::Fn(..) => "函数".to_string(),
+                    Type::Named(name) => format!("{} 值", name),
+                };
+                let number = match count {
+                    0 => return noun,
+                    1 => "一".to_string(),
+                    2 => "两".to_string(),
+                    3 => "三".to_string(),
+                    4 => "四".to_string(),
+                    5 => "五".to_string(),
+                    n => n.to_string(),
+                };
+                format!("{}个{}", number, noun)
+            }
+            Language::English => {
+                let (singular, plural) = match ty {
+                    Type::Any | Type::Var(_) => ("any value".to_string(), "values".to_string()),
+                    Type::Int => ("an integer".to_string(), "integers".to_string()),
+                    Type::Float => ("a float".to_string(), "floats".to_string()),
+                    Type::Number => ("a number".to_string(), "numbers".to_string()),
+                    Type::String => ("a string".to_string(), "strings".to_string()),
+                    Type::Bool => ("a boolean".to_string(), "booleans".to_string()),
+                    Type::Null => ("null".to_string(), "nulls".to_string()),
+                    Type::List(inner) if **inner != Type::Any => {
+                        let of = self.describe_type(inner, 2);
+                        let of = of.trim_start_matches("two ");
+                        (format!("a list of {}", of), format!("lists of {}", of))
+                    }
+                    Type::List(_) => ("a list".to_string(), "lists".to_string()),
+                    Type::Dict(_) => ("a dictionary".to_string(), "dictionaries".to_string()),
+                    Type::Fn(..) => ("a function".to_string(), "functions".to_string()),
+                    Type::Named(name) => (format!("a {} value", name), format!("{} values", name)),
+                };
+                let number = match count {
+                    1 => return singular,
+                    2 => "two".to_string(),
+                    3 => "three".to_string(),
+                    4 => "four".to_string(),
+                    5 => "five".to_string(),
+                    n => n.to_string(),
+                };
+                format!("{} {}", number, plural)
+            }
         }
     }
 
@@ -173,14 +564,22 @@ impl CodeExplainer {
 
         let name = match &list[1] {
             GrammarElement::Atom(name) => name.clone(),
+            GrammarElement::Expr(expr) => match expr.as_ref() {
+                Expr::Var(name) => name.to_string(),
+                _ => "unknown".into(),
+            },
             _ => "unknown".to_string(),
         };
 
         let value = self.explain(&list[2]);
 
-        match self.language {
-            Language::Chinese => format!("定义变量 {}，值为 {}", name, value),
-            Language::English => format!("define variable {} with value {}", name, value),
+        let constant =
+            matches!(list.first(), Some(GrammarElement::Atom(head)) if head == "defconst");
+        match (self.language, constant) {
+            (Language::Chinese, false) => format!("定义变量 {}，值为 {}", name, value),
+            (Language::Chinese, true) => format!("定义常量 {}，值为 {}", name, value),
+            (Language::English, false) => format!("define variable {} with value {}", name, value),
+            (Language::English, true) => format!("define constant {} with value {}", name, value),
         }
     }
 
@@ -236,6 +635,7 @@ impl CodeExplainer {
     fn explain_literal(&self, lit: &Literal) -> String {
         match lit {
             Literal::Int(n) => n.to_string(),
+            Literal::BigInt(n) => n.to_string(),
             Literal::Float(f) => f.to_string(),
             Literal::String(s) => format!("\"{}\"", s),
             Literal::Bool(b) => match self.language {
@@ -288,6 +688,9 @@ impl CodeExplainer {
 
     /// 解释函数调用 / Explain function call
     fn explain_call(&self, name: &str, args: &[Expr]) -> String {
+        if let (Some(op), [left, right]) = (BinOp::from_operator(name), args) {
+            return self.explain_binary(&op, left, right);
+        }
         let args_str: Vec<String> = args.iter().map(|a| self.explain_expr(a)).collect();
         match self.language {
             Language::Chinese => {
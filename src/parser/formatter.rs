@@ -0,0 +1,391 @@
+// 代码格式化器 / Code formatter
+// 将Evo-lang源代码重新输出为规范缩进的S表达式，保留注释
+// Re-emits Evo-lang source as canonically indented S-expressions, preserving comments
+
+use crate::parser::adaptive::{AdaptiveParser, Location, ParseError};
+
+/// 格式化选项 / Format options
+#[derive(Debug, Clone)]
+pub struct FormatOptions {
+    /// 最大行宽 / Maximum line width
+    pub line_width: usize,
+    /// 每级缩进的空格数 / Spaces per indentation level
+    pub indent: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            line_width: 80,
+            indent: 2,
+        }
+    }
+}
+
+/// 使用默认选项格式化源代码 / Format source code with default options
+pub fn format_source(code: &str) -> Result<String, ParseError> {
+    format_source_with(code, &FormatOptions::default())
+}
+
+/// 使用指定选项格式化源代码 / Format source code with the given options
+pub fn format_source_with(code: &str, options: &FormatOptions) -> Result<String, ParseError> {
+    // 先用真正的解析器校验，保证只格式化合法代码
+    // Validate with the real parser first so only valid code is formatted
+    AdaptiveParser::new(false).parse(code)?;
+
+    let nodes = Reader::new(code).read_all()?;
+    let formatter = Formatter { options };
+    let mut output = String::new();
+    let mut previous_was_code = false;
+    for node in &nodes {
+        match node {
+            Node::BlankLine => {
+                if !output.is_empty() && !output.ends_with("\n\n") {
+                    output.push('\n');
+                }
+                previous_was_code = false;
+                continue;
+            }
+            Node::Comment {
+                text,
+                trailing: true,
+            } if previous_was_code => {
+                // 行尾注释接在上一行末尾 / Trailing comment stays at the end of the previous line
+                output.pop();
+                output.push_str("  ");
+                output.push_str(text);
+                output.push('\n');
+                previous_was_code = false;
+                continue;
+            }
+            _ => {}
+        }
+        output.push_str(&formatter.render(node, 0));
+        output.push('\n');
+        previous_was_code = !matches!(node, Node::Comment { .. });
+    }
+    Ok(output.trim_end().to_string() + "\n")
+}
+
+/// 源代码的S表达式结构 / S-expression structure of source code
+#[derive(Debug, Clone)]
+enum Node {
+    /// 原子（符号、数字、字符串原文）/ Atom (symbol, number, or verbatim string)
+    Atom(String),
+    /// 列表 / List
+    List(Vec<Node>),
+    /// 引用 / Quoted node
+    Quote(Box<Node>),
+    /// 注释（trailing表示与前面的代码在同一行）/ Comment (trailing means on the same line as preceding code)
+    Comment { text: String, trailing: bool },
+    /// 顶层空行 / Blank line between top-level forms
+    BlankLine,
+}
+
+impl Node {
+    /// 是否包含注释（包含注释的列表必须换行）/ Whether it contains comments (lists with comments must break)
+    fn has_comment(&self) -> bool {
+        match self {
+            Node::Comment { .. } => true,
+            Node::List(items) => items.iter().any(Node::has_comment),
+            Node::Quote(inner) => inner.has_comment(),
+            _ => false,
+        }
+    }
+
+    /// 单行渲染 / Render on a single line
+    fn flat(&self) -> String {
+        match self {
+            Node::Atom(atom) => atom.clone(),
+            Node::List(items) => {
+                let parts: Vec<String> = items.iter().map(Node::flat).collect();
+                format!("({})", parts.join(" "))
+            }
+            Node::Quote(inner) => format!("'{}", inner.flat()),
+            Node::Comment { text, .. } => text.clone(),
+            Node::BlankLine => String::new(),
+        }
+    }
+}
+
+/// 格式化器 / Formatter
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+}
+
+impl Formatter<'_> {
+    /// 在指定列渲染节点 / Render node starting at the given column
+    fn render(&self, node: &Node, column: usize) -> String {
+        match node {
+            Node::List(items) => self.render_list(items, column),
+            Node::Quote(inner) => format!("'{}", self.render(inner, column + 1)),
+            _ => node.flat(),
+        }
+    }
+
+    /// 渲染列表：能放下则单行，否则头部参数留在首行、其余缩进
+    /// Render list: single line if it fits, otherwise header arguments stay on the first line and the rest are indented
+    fn render_list(&self, items: &[Node], column: usize) -> String {
+        let flat = Node::List(items.to_vec()).flat();
+        let has_comment = items.iter().any(Node::has_comment);
+        if !has_comment && column + flat.chars().count() <= self.options.line_width {
+            return flat;
+        }
+
+        let header_len = match items.first() {
+            // 返回类型标注 `: Type` 与参数列表同行 / A return annotation `: Type` stays on the parameter line
+            Some(Node::Atom(head))
+                if matches!(head.as_str(), "def" | "function" | "defn-")
+                    && matches!(items.get(3), Some(Node::Atom(colon)) if colon == ":") =>
+            {
+                5
+            }
+            Some(Node::Atom(head)) => 1 + header_arguments(head),
+            _ => 1,
+        }
+        .min(items.len());
+
+        let mut result = String::from("(");
+        let mut line_column = column + 1;
+        for (i, item) in items[..header_len].iter().enumerate() {
+            if i > 0 {
+                result.push(' ');
+                line_column += 1;
+            }
+            let rendered = self.render(item, line_column);
+            line_column += rendered.chars().count();
+            result.push_str(&rendered);
+        }
+
+        let body_column = column + self.options.indent;
+        let body_indent = " ".repeat(body_column);
+        for item in &items[header_len..] {
+            if let Node::Comment {
+                text,
+                trailing: true,
+            } = item
+            {
+                result.push_str("  ");
+                result.push_str(text);
+                continue;
+            }
+            result.push('\n');
+            result.push_str(&body_indent);
+            result.push_str(&self.render(item, body_column));
+        }
+
+        // 最后一项为注释时右括号需另起一行 / Closing paren goes on its own line after a final comment
+        if matches!(items.last(), Some(Node::Comment { .. })) {
+            result.push('\n');
+            result.push_str(&" ".repeat(column));
+        }
+        result.push(')');
+        result
+    }
+}
+
+/// 特殊形式中与头部保持在同一行的参数个数 / Number of arguments kept on the head line for special forms
+fn header_arguments(head: &str) -> usize {
+    match head {
+        "def" | "function" | "defn" | "defn-" | "defmacro" | "let" | "defconst" | "for" => 2,
+        "begin" | "try" => 0,
+        _ => 1,
+    }
+}
+
+/// S表达式读取器 / S-expression reader
+struct Reader {
+    chars: Vec<char>,
+    position: usize,
+    line: usize,
+    column: usize,
+    /// 上一个代码单元所在的行 / Line of the previous code token
+    last_code_line: Option<usize>,
+    /// 是否已读取过任何节点 / Whether any node has been read yet
+    seen_item: bool,
+}
+
+impl Reader {
+    fn new(code: &str) -> Self {
+        Self {
+            chars: code.chars().collect(),
+            position: 0,
+            line: 1,
+            column: 1,
+            last_code_line: None,
+            seen_item: false,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.position += 1;
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(ch)
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError::syntax_error(
+            message.to_string(),
+            Some(Location::new(self.line, self.column)),
+        )
+    }
+
+    /// 读取所有顶层节点 / Read all top-level nodes
+    fn read_all(&mut self) -> Result<Vec<Node>, ParseError> {
+        let mut nodes = Vec::new();
+        while let Some(node) = self.read_item(true)? {
+            nodes.push(node);
+        }
+        Ok(nodes)
+    }
+
+    /// 读取下一个节点；遇到右括号或输入结束时返回None
+    /// Read the next node; returns None at a closing paren or end of input
+    fn read_item(&mut self, top_level: bool) -> Result<Option<Node>, ParseError> {
+        // 跳过空白，顶层记录空行 / Skip whitespace, recording blank lines at top level
+        let mut newlines = 0;
+        while let Some(ch) = self.peek() {
+            if !ch.is_whitespace() {
+                break;
+            }
+            if ch == '\n' {
+                newlines += 1;
+            }
+            self.advance();
+        }
+        if top_level && newlines >= 2 && self.seen_item && self.peek().is_some() {
+            return Ok(Some(Node::BlankLine));
+        }
+
+        let Some(ch) = self.peek() else {
+            return Ok(None);
+        };
+        let start_line = self.line;
+        self.seen_item = true;
+        let node = match ch {
+            ')' => return Ok(None),
+            ';' => {
+                let mut text = String::new();
+                while let Some(ch) = self.peek() {
+                    if ch == '\n' {
+                        break;
+                    }
+                    text.push(ch);
+                    self.advance();
+                }
+                let trailing = self.last_code_line == Some(start_line);
+                return Ok(Some(Node::Comment {
+                    text: text.trim_end().to_string(),
+                    trailing,
+                }));
+            }
+            '(' => {
+                self.advance();
+                let mut items = Vec::new();
+                while let Some(item) = self.read_item(false)? {
+                    items.push(item);
+                }
+                if self.advance() != Some(')') {
+                    return Err(self.error("Unterminated list"));
+                }
+                Node::List(items)
+            }
+            '\'' => {
+                self.advance();
+                match self.read_item(false)? {
+                    Some(inner) => Node::Quote(Box::new(inner)),
+                    None => return Err(self.error("Expected expression after quote")),
+                }
+            }
+            '"' => Node::Atom(self.read_string()?),
+            'r' if self.peek_at(1) == Some('"')
+                || (self.peek_at(1) == Some('#') && self.peek_at(2) == Some('"')) =>
+            {
+                Node::Atom(self.read_raw_string()?)
+            }
+            _ => {
+                let mut atom = String::new();
+                while let Some(ch) = self.peek() {
+                    if ch.is_whitespace() || ch == '(' || ch == ')' || ch == ';' || ch == '"' {
+                        break;
+                    }
+                    atom.push(ch);
+                    self.advance();
+                }
+                Node::Atom(atom)
+            }
+        };
+        self.last_code_line = Some(self.line);
+        Ok(Some(node))
+    }
+
+    /// 原样读取字符串字面量（含引号和转义）/ Read string literal verbatim (including quotes and escapes)
+    fn read_string(&mut self) -> Result<String, ParseError> {
+        let triple = self.peek_at(1) == Some('"') && self.peek_at(2) == Some('"');
+        let delimiter = if triple { "\"\"\"" } else { "\"" };
+        let mut text = String::new();
+        for _ in 0..delimiter.len() {
+            text.push(self.advance().unwrap_or('"'));
+        }
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string")),
+                Some('\\') => {
+                    text.push(self.advance().unwrap_or('\\'));
+                    if let Some(escaped) = self.advance() {
+                        text.push(escaped);
+                    }
+                }
+                Some('"')
+                    if !triple
+                        || (self.peek_at(1) == Some('"') && self.peek_at(2) == Some('"')) =>
+                {
+                    for _ in 0..delimiter.len() {
+                        text.push(self.advance().unwrap_or('"'));
+                    }
+                    return Ok(text);
+                }
+                Some(_) => text.push(self.advance().unwrap_or_default()),
+            }
+        }
+    }
+
+    /// 原样读取原始字符串 / Read raw string verbatim
+    fn read_raw_string(&mut self) -> Result<String, ParseError> {
+        let mut text = String::new();
+        text.push(self.advance().unwrap_or('r'));
+        let mut hashes = 0;
+        while self.peek() == Some('#') {
+            hashes += 1;
+            text.push(self.advance().unwrap_or('#'));
+        }
+        text.push(self.advance().unwrap_or('"'));
+        loop {
+            match self.advance() {
+                None => return Err(self.error("Unterminated raw string")),
+                Some('"') if (0..hashes).all(|i| self.peek_at(i) == Some('#')) => {
+                    text.push('"');
+                    for _ in 0..hashes {
+                        text.push(self.advance().unwrap_or('#'));
+                    }
+                    return Ok(text);
+                }
+                Some(ch) => text.push(ch),
+            }
+        }
+    }
+}
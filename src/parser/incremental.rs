@@ -0,0 +1,265 @@
+// 增量解析 / Incremental parsing
+// 记录每个语法节点的字节范围，编辑后只重新解析受影响的顶层形式，其余子树直接复用
+// Records the byte span of every syntax node; after an edit only the affected top-level forms are reparsed and the other subtrees are reused
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+use crate::grammar::core::GrammarElement;
+use crate::parser::adaptive::{AdaptiveParser, ParseError, Token};
+
+/// 字节范围（左闭右开）/ Byte span (half-open)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Span {
+    /// 起始字节偏移 / Start byte offset
+    pub start: usize,
+    /// 结束字节偏移 / End byte offset
+    pub end: usize,
+}
+
+impl Span {
+    /// 创建字节范围 / Create byte span
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// 是否包含偏移（含结束位置，便于光标定位）/ Whether it contains the offset (end inclusive, for cursor positions)
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset <= self.end
+    }
+
+    fn shifted(self, delta: isize) -> Self {
+        Self::new(
+            self.start.saturating_add_signed(delta),
+            self.end.saturating_add_signed(delta),
+        )
+    }
+}
+
+/// 语法节点种类 / Syntax node kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyntaxKind {
+    /// 列表 `(...)` / List `(...)`
+    List,
+    /// 原子（符号、数字、字符串）/ Atom (symbol, number, string)
+    Atom,
+    /// 引用 `'x` / Quote `'x`
+    Quote,
+}
+
+/// 带字节范围的语法树节点 / Syntax tree node with byte span
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyntaxNode {
+    /// 节点种类 / Node kind
+    pub kind: SyntaxKind,
+    /// 源码中的字节范围 / Byte span in the source
+    pub span: Span,
+    /// 子节点 / Child nodes
+    pub children: Vec<SyntaxNode>,
+}
+
+impl SyntaxNode {
+    /// 包含偏移的最内层节点 / Innermost node containing the offset
+    pub fn node_at(&self, offset: usize) -> Option<&SyntaxNode> {
+        if !self.span.contains(offset) {
+            return None;
+        }
+        self.children
+            .iter()
+            .find_map(|child| child.node_at(offset))
+            .or(Some(self))
+    }
+
+    /// 节点对应的源码文本 / Source text of the node
+    pub fn text<'a>(&self, source: &'a str) -> &'a str {
+        source.get(self.span.start..self.span.end).unwrap_or("")
+    }
+
+    fn shift(&mut self, delta: isize) {
+        self.span = self.span.shifted(delta);
+        for child in &mut self.children {
+            child.shift(delta);
+        }
+    }
+}
+
+/// 解析后的文档：源码、AST 和与顶层元素一一对应的语法树
+/// Parsed document: source, AST and a syntax tree whose top-level nodes match the top-level elements
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedDocument {
+    /// 源代码 / Source code
+    pub source: String,
+    /// 顶层 AST 元素 / Top-level AST elements
+    pub elements: Vec<GrammarElement>,
+    /// 顶层语法节点 / Top-level syntax nodes
+    pub nodes: Vec<SyntaxNode>,
+}
+
+impl ParsedDocument {
+    /// 包含偏移的最内层节点 / Innermost node containing the offset
+    pub fn node_at(&self, offset: usize) -> Option<&SyntaxNode> {
+        self.nodes.iter().find_map(|node| node.node_at(offset))
+    }
+
+    /// 包含偏移的顶层元素及其语法节点 / Top-level element containing the offset, with its syntax node
+    pub fn element_at(&self, offset: usize) -> Option<(&GrammarElement, &SyntaxNode)> {
+        let index = self
+            .nodes
+            .iter()
+            .position(|node| node.span.contains(offset))?;
+        Some((self.elements.get(index)?, &self.nodes[index]))
+    }
+
+    /// 顶层元素与语法节点是否一一对应 / Whether top-level elements and syntax nodes correspond one to one
+    fn is_aligned(&self) -> bool {
+        self.elements.len() == self.nodes.len()
+    }
+}
+
+impl AdaptiveParser {
+    /// 将 `edit_range`（旧源码中的字节范围）替换为 `new_text` 后重新解析，
+    /// 编辑范围之前的顶层形式和换行之后的顶层形式直接复用，只重新解析中间部分；
+    /// 涉及 `defmacro` 时宏定义会影响后续解析，退回完整解析
+    /// Reparse after replacing `edit_range` (a byte range in the old source) with `new_text`.
+    /// Top-level forms before the edit, and those after it that start on a later line, are reused; only the part in between is reparsed.
+    /// Falls back to a full parse when `defmacro` is involved, since macro definitions affect later forms
+    pub fn parse_incremental(
+        &self,
+        old: &ParsedDocument,
+        edit_range: Range<usize>,
+        new_text: &str,
+    ) -> Result<ParsedDocument, ParseError> {
+        let Range { start, end } = edit_range;
+        if start > end || !old.source.is_char_boundary(start) || !old.source.is_char_boundary(end) {
+            return Err(ParseError::syntax_error(
+                format!("Invalid edit range {}..{}", start, end),
+                None,
+            ));
+        }
+
+        let mut source = String::with_capacity(old.source.len() - (end - start) + new_text.len());
+        source.push_str(&old.source[..start]);
+        source.push_str(new_text);
+        source.push_str(&old.source[end..]);
+
+        if !old.is_aligned() || old.source.contains("defmacro") || new_text.contains("defmacro") {
+            return self.parse_document(&source);
+        }
+
+        let delta = new_text.len() as isize - (end - start) as isize;
+        let edit_end = start + new_text.len();
+
+        // 编辑之前结束的节点不受影响；相邻的节点可能与新文本合并为同一个词法单元
+        // Nodes ending before the edit are unaffected; an adjacent one could merge with the new text into one token
+        let prefix = old
+            .nodes
+            .iter()
+            .take_while(|node| node.span.end < start)
+            .count();
+        // 编辑之后的节点需与编辑隔开一个换行，否则新文本中的注释可能吞掉它
+        // Nodes after the edit must be separated from it by a newline, otherwise a comment in the new text could swallow them
+        let suffix = old.nodes[prefix..]
+            .iter()
+            .position(|node| {
+                node.span.start > end
+                    && source[edit_end..node.span.start.saturating_add_signed(delta)].contains('\n')
+            })
+            .map_or(old.nodes.len(), |index| prefix + index);
+
+        let middle_start = prefix
+            .checked_sub(1)
+            .map_or(0, |last| old.nodes[last].span.end);
+        let middle_end = old.nodes.get(suffix).map_or(source.len(), |node| {
+            node.span.start.saturating_add_signed(delta)
+        });
+
+        let middle = match self.parse_document(&source[middle_start..middle_end]) {
+            Ok(middle) if middle.is_aligned() => middle,
+            _ => return self.parse_document(&source),
+        };
+
+        let mut elements = old.elements[..prefix].to_vec();
+        elements.extend(middle.elements);
+        elements.extend(old.elements[suffix..].iter().cloned());
+
+        let mut nodes = old.nodes[..prefix].to_vec();
+        nodes.extend(middle.nodes.into_iter().map(|mut node| {
+            node.shift(middle_start as isize);
+            node
+        }));
+        nodes.extend(old.nodes[suffix..].iter().map(|node| {
+            let mut node = node.clone();
+            node.shift(delta);
+            node
+        }));
+
+        Ok(ParsedDocument {
+            source,
+            elements,
+            nodes,
+        })
+    }
+}
+
+/// 由词法单元及其字节范围构建语法树，括号不匹配时尽量恢复而不报错
+/// Build the syntax tree from tokens and their byte spans, recovering rather than failing on unbalanced parentheses
+pub(crate) fn syntax_tree(tokens: &[Token], spans: &[Span]) -> Vec<SyntaxNode> {
+    let mut nodes = Vec::new();
+    let mut index = 0;
+    while index < tokens.len().min(spans.len()) {
+        match read_node(tokens, spans, &mut index) {
+            Some(node) => nodes.push(node),
+            None if tokens[index] == Token::RightParen => index += 1,
+            None => break,
+        }
+    }
+    nodes
+}
+
+/// 读取一个节点，遇到 `)` 或结尾时不前进并返回 `None`
+/// Read one node; returns `None` without advancing at `)` or the end
+fn read_node(tokens: &[Token], spans: &[Span], index: &mut usize) -> Option<SyntaxNode> {
+    let span = *spans.get(*index)?;
+    match tokens.get(*index)? {
+        Token::EOF | Token::RightParen => None,
+        Token::LeftParen => {
+            *index += 1;
+            let mut children = Vec::new();
+            let end = loop {
+                match read_node(tokens, spans, index) {
+                    Some(child) => children.push(child),
+                    None => match tokens.get(*index) {
+                        Some(Token::RightParen) => {
+                            *index += 1;
+                            break spans[*index - 1].end;
+                        }
+                        _ => break children.last().map_or(span.end, |child| child.span.end),
+                    },
+                }
+            };
+            Some(SyntaxNode {
+                kind: SyntaxKind::List,
+                span: Span::new(span.start, end),
+                children,
+            })
+        }
+        Token::Quote => {
+            *index += 1;
+            let children: Vec<SyntaxNode> = read_node(tokens, spans, index).into_iter().collect();
+            let end = children.first().map_or(span.end, |child| child.span.end);
+            Some(SyntaxNode {
+                kind: SyntaxKind::Quote,
+                span: Span::new(span.start, end),
+                children,
+            })
+        }
+        _ => {
+            *index += 1;
+            Some(SyntaxNode {
+                kind: SyntaxKind::Atom,
+                span,
+                children: Vec::new(),
+            })
+        }
+    }
+}
@@ -0,0 +1,124 @@
+// 自然语言短语表 / Natural-language phrase tables
+// 每种语言一张JSON短语表，NLU规则从已加载短语表合并出的关键词中匹配；新语言在运行时加载，无需重新编译
+// One JSON phrase table per language; the NLU rules match keywords merged from the loaded tables, and new languages
+// are loaded at runtime without recompiling
+
+use crate::grammar::core::BinOp;
+use crate::parser::nlu::NLUError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// 内置短语表的语言代码 / Locale codes of the built-in phrase tables
+pub const BUILTIN_LOCALES: &[&str] = &["zh", "en", "ja", "es"];
+
+/// 默认加载的语言 / Locales loaded by default
+pub const DEFAULT_LOCALES: &[&str] = &["zh", "en"];
+
+/// 一种语言的短语表，列表中靠前的短语优先匹配
+/// Phrase table of one language; earlier phrases in a list are matched first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhraseTable {
+    /// 语言名称 / Language name
+    pub name: String,
+    /// 表示定义函数的短语 / Phrases meaning "define a function"
+    pub function: Vec<String>,
+    /// 表示定义变量的短语 / Phrases meaning "define a variable"
+    pub variable: Vec<String>,
+    /// 函数名前的短语 / Phrases before a function name
+    pub function_name: Vec<String>,
+    /// 变量名前的短语 / Phrases before a variable name
+    pub variable_name: Vec<String>,
+    /// 参数列表前的短语 / Phrases before a parameter list
+    pub parameters: Vec<String>,
+    /// 连接参数的词 / Words joining parameters
+    pub conjunctions: Vec<String>,
+    /// 函数体前的短语 / Phrases before a function body
+    pub body: Vec<String>,
+    /// 变量值前的短语 / Phrases before a variable value
+    pub value: Vec<String>,
+    /// 条件开头 / Start of a condition
+    #[serde(rename = "if")]
+    pub if_markers: Vec<String>,
+    /// 条件成立的分支 / Branch taken when the condition holds
+    #[serde(rename = "then")]
+    pub then_markers: Vec<String>,
+    /// 条件不成立的分支 / Branch taken otherwise
+    #[serde(rename = "else")]
+    pub else_markers: Vec<String>,
+    /// 分隔多条语句的连接词 / Connectives separating statements
+    pub connectors: Vec<String>,
+    /// 运算短语 / Operation phrases
+    pub operations: BTreeMap<String, BinOp>,
+    /// 数词 / Number words
+    pub numbers: BTreeMap<String, i64>,
+    /// 布尔值 / Boolean words
+    pub booleans: BTreeMap<String, bool>,
+}
+
+impl PhraseTable {
+    /// 内置短语表 / Built-in phrase table
+    pub fn builtin(locale: &str) -> Option<Self> {
+        let json = match locale {
+            "zh" => include_str!("locales/zh.json"),
+            "en" => include_str!("locales/en.json"),
+            "ja" => include_str!("locales/ja.json"),
+            "es" => include_str!("locales/es.json"),
+            _ => return None,
+        };
+        Self::from_json(json).ok()
+    }
+
+    /// 从JSON解析短语表 / Parse a phrase table from JSON
+    pub fn from_json(json: &str) -> Result<Self, NLUError> {
+        serde_json::from_str(json).map_err(|e| NLUError::InvalidPhraseTable(e.to_string()))
+    }
+
+    /// 从JSON文件加载短语表 / Load a phrase table from a JSON file
+    pub fn from_file(path: &Path) -> Result<Self, NLUError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| NLUError::InvalidPhraseTable(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_str(&json)
+            .map_err(|e| NLUError::InvalidPhraseTable(format!("{}: {}", path.display(), e)))
+    }
+
+    /// 序列化为JSON，可作为新语言的模板 / Serialize to JSON, usable as a template for a new language
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// 把另一张表的短语追加到本表，已有的短语保持原位 / Append another table's phrases, keeping existing phrases in place
+    pub fn merge(&mut self, other: &PhraseTable) {
+        let lists = [
+            (&mut self.function, &other.function),
+            (&mut self.variable, &other.variable),
+            (&mut self.function_name, &other.function_name),
+            (&mut self.variable_name, &other.variable_name),
+            (&mut self.parameters, &other.parameters),
+            (&mut self.conjunctions, &other.conjunctions),
+            (&mut self.body, &other.body),
+            (&mut self.value, &other.value),
+            (&mut self.if_markers, &other.if_markers),
+            (&mut self.then_markers, &other.then_markers),
+            (&mut self.else_markers, &other.else_markers),
+            (&mut self.connectors, &other.connectors),
+        ];
+        for (list, extra) in lists {
+            for phrase in extra {
+                if !list.contains(phrase) {
+                    list.push(phrase.clone());
+                }
+            }
+        }
+        for (phrase, op) in &other.operations {
+            self.operations.entry(phrase.clone()).or_insert(*op);
+        }
+        for (word, number) in &other.numbers {
+            self.numbers.entry(word.clone()).or_insert(*number);
+        }
+        for (word, value) in &other.booleans {
+            self.booleans.entry(word.clone()).or_insert(*value);
+        }
+    }
+}
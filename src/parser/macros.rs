@@ -0,0 +1,364 @@
+// 宏系统 / Macro system
+// 模板宏在解析阶段按词法单元展开，模板引入的绑定名用 gensym 重命名以保证卫生
+// Template macros expand at the token level during parsing; names bound by the template are renamed with gensym for hygiene
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::grammar::core::GrammarElement;
+use crate::grammar::rule::{GrammarRule, PatternElement};
+use crate::grammar::unparse::unparse_element;
+use crate::parser::adaptive::{ParseError, Token, Tokenizer};
+
+/// 规则产生式与其表示的核心特殊形式 / Rule productions and the core special forms they denote
+pub(crate) const PRODUCTION_FORMS: &[(&str, &str)] = &[
+    ("FunctionDefinition", "def"),
+    ("VariableDeclaration", "let"),
+    ("Conditional", "if"),
+    ("Lambda", "lambda"),
+    ("PatternMatch", "match"),
+    ("ForLoop", "for"),
+    ("WhileLoop", "while"),
+    ("Block", "begin"),
+];
+
+/// `BinaryOperation` 规则中表示运算符的词 / Words denoting operators in `BinaryOperation` rules
+pub(crate) const OPERATOR_WORDS: &[(&str, &str)] = &[
+    ("加", "+"),
+    ("plus", "+"),
+    ("减", "-"),
+    ("minus", "-"),
+    ("乘", "*"),
+    ("times", "*"),
+    ("除", "/"),
+    ("divide", "/"),
+];
+
+/// 可被提议为新语法的词及其产生式 / Words that may be proposed as new syntax, with their productions
+pub(crate) const FORM_WORDS: &[(&str, &str)] = &[
+    ("定义", "FunctionDefinition"),
+    ("函数", "FunctionDefinition"),
+    ("define", "FunctionDefinition"),
+    ("变量", "VariableDeclaration"),
+    ("设", "VariableDeclaration"),
+    ("如果", "Conditional"),
+    ("匿名函数", "Lambda"),
+    ("匹配", "PatternMatch"),
+    ("遍历", "ForLoop"),
+    ("当", "WhileLoop"),
+];
+
+/// 规则不能覆盖的核心关键字 / Core keywords that rules may not override
+const RESERVED_KEYWORDS: &[&str] = &[
+    "def",
+    "function",
+    "let",
+    "if",
+    "lambda",
+    "match",
+    "for",
+    "while",
+    "try",
+    "begin",
+    "list",
+    "vec",
+    "dict",
+    "map",
+    "set!",
+    "defmacro",
+    "import",
+    "export",
+    "deftype",
+    "deftest",
+    "for/list",
+    "for/dict",
+    "let*",
+    "defprotocol",
+    "defimpl",
+    "defconst",
+    "defn-",
+];
+
+/// gensym 计数器 / gensym counter
+static GENSYM_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// 生成唯一符号名，`#` 不能出现在源码标识符中，因此不会与用户变量冲突
+/// Generate a unique symbol name; `#` cannot appear in source identifiers, so it never collides with user variables
+pub fn gensym(base: &str) -> String {
+    let id = GENSYM_COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{}#{}", base, id)
+}
+
+/// 模板宏，如 `(defmacro unless (cond body) (if cond null body))`
+/// Template macro, such as `(defmacro unless (cond body) (if cond null body))`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Macro {
+    /// 宏名称 / Macro name
+    pub name: String,
+    /// 参数名 / Parameter names
+    pub params: Vec<String>,
+    /// 收集剩余参数的 `..rest` 参数名 / Name of the `..rest` parameter collecting remaining arguments
+    pub rest: Option<String>,
+    /// 是否以中缀形式 `(a name b)` 调用 / Whether it is invoked infix, as `(a name b)`
+    pub infix: bool,
+    /// 模板的词法单元 / Template tokens
+    template: Vec<Token>,
+}
+
+impl Macro {
+    /// 从模板源码创建宏，以 `..` 开头的最后一个参数收集剩余参数
+    /// Create a macro from template source; a final parameter starting with `..` collects remaining arguments
+    pub fn new(name: &str, params: &[&str], template: &str) -> Result<Self, ParseError> {
+        let mut tokens = Tokenizer::new(template).tokenize()?;
+        tokens.pop(); // EOF
+        if tokens.is_empty() {
+            return Err(ParseError::syntax_error(
+                format!("Macro '{}' has an empty template", name),
+                None,
+            ));
+        }
+        Self::from_tokens(
+            name.to_string(),
+            params.iter().map(|p| p.to_string()).collect(),
+            tokens,
+        )
+    }
+
+    pub(crate) fn from_tokens(
+        name: String,
+        mut params: Vec<String>,
+        template: Vec<Token>,
+    ) -> Result<Self, ParseError> {
+        let rest = match params.last() {
+            Some(last) if last.starts_with("..") => params
+                .pop()
+                .map(|last| last.trim_start_matches('.').to_string()),
+            _ => None,
+        };
+        if params.iter().any(|p| p.starts_with("..")) {
+            return Err(ParseError::syntax_error(
+                format!("Macro '{}': rest parameter must be the last one", name),
+                None,
+            ));
+        }
+        Ok(Self {
+            name,
+            params,
+            rest,
+            infix: false,
+            template,
+        })
+    }
+
+    /// 由语法规则生成宏：产生式为核心形式名时，规则关键字成为该形式的别名；
+    /// 产生式为语法结构时，第一个关键字成为以其余模式元素为参数的特殊形式，位于第二位时为中缀形式
+    /// Macros generated from a grammar rule: when the production names a core form, the rule's keywords become aliases of it;
+    /// when the production is a syntax structure, the first keyword becomes a special form taking the other pattern elements,
+    /// written infix when it sits in second position
+    pub fn from_rule(rule: &GrammarRule) -> Vec<Macro> {
+        let elements = &rule.pattern.elements;
+        let keywords: Vec<(usize, &str)> = elements
+            .iter()
+            .enumerate()
+            .filter_map(|(i, element)| match element {
+                PatternElement::Keyword(word) | PatternElement::NaturalLang(word) => {
+                    Some((i, word.as_str()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        match &rule.production.target {
+            GrammarElement::Atom(production) if production == "BinaryOperation" => keywords
+                .iter()
+                .filter_map(|(_, word)| {
+                    let (_, operator) = OPERATOR_WORDS.iter().find(|(w, _)| w == word)?;
+                    let mut alias = Self::alias(word, operator)?;
+                    alias.infix = true;
+                    Some(alias)
+                })
+                .collect(),
+            GrammarElement::Atom(production) => {
+                let form = PRODUCTION_FORMS
+                    .iter()
+                    .find(|(p, _)| p == production)
+                    .map(|(_, form)| *form)
+                    .or_else(|| {
+                        RESERVED_KEYWORDS
+                            .iter()
+                            .find(|form| *form == production)
+                            .copied()
+                    });
+                match form {
+                    Some(form) => keywords
+                        .iter()
+                        .filter_map(|(_, word)| Self::alias(word, form))
+                        .collect(),
+                    None => Vec::new(),
+                }
+            }
+            target => {
+                let Some(&(position, keyword)) = keywords.first() else {
+                    return Vec::new();
+                };
+                let mut params = Vec::new();
+                for (i, element) in elements.iter().enumerate() {
+                    if i == position {
+                        continue;
+                    }
+                    match element {
+                        PatternElement::Wildcard(name) | PatternElement::Identifier(name) => {
+                            params.push(name.clone())
+                        }
+                        PatternElement::Repeat(inner) => match inner.as_ref() {
+                            PatternElement::Wildcard(name) | PatternElement::Identifier(name) => {
+                                params.push(format!("..{}", name))
+                            }
+                            _ => return Vec::new(),
+                        },
+                        _ => return Vec::new(),
+                    }
+                }
+                if rule.pattern.variadic && !params.iter().any(|p| p.starts_with("..")) {
+                    params.push("..rest".to_string());
+                }
+                if !is_symbol(keyword) || RESERVED_KEYWORDS.contains(&keyword) {
+                    return Vec::new();
+                }
+                let params: Vec<&str> = params.iter().map(String::as_str).collect();
+                match Self::new(keyword, &params, &unparse_element(target)) {
+                    Ok(mut definition) => {
+                        definition.infix = position == 1 && elements.len() == 3;
+                        vec![definition]
+                    }
+                    Err(_) => Vec::new(),
+                }
+            }
+        }
+    }
+
+    /// `(word args...)` 展开为 `(form args...)` / `(word args...)` expands to `(form args...)`
+    fn alias(word: &str, form: &str) -> Option<Macro> {
+        if word == form || !is_symbol(word) || RESERVED_KEYWORDS.contains(&word) {
+            return None;
+        }
+        Self::new(word, &["..args"], &format!("({} ..args)", form)).ok()
+    }
+
+    /// 参数个数是否可接受 / Whether the argument count is acceptable
+    pub fn accepts(&self, count: usize) -> bool {
+        count == self.params.len() || (self.rest.is_some() && count > self.params.len())
+    }
+
+    /// 用参数的词法单元替换模板中的参数，并重命名模板引入的绑定
+    /// Substitute argument tokens for parameters and rename bindings introduced by the template
+    pub(crate) fn expand(&self, args: &[Vec<Token>]) -> Vec<Token> {
+        let mut bindings: HashMap<&str, Vec<Token>> = self
+            .params
+            .iter()
+            .zip(args)
+            .map(|(param, arg)| (param.as_str(), arg.clone()))
+            .collect();
+        let rest_symbol = self.rest.as_ref().map(|rest| format!("..{}", rest));
+        if let Some(rest_symbol) = &rest_symbol {
+            let rest: Vec<Token> = args[self.params.len()..].concat();
+            bindings.insert(rest_symbol.as_str(), rest);
+        }
+
+        let renames: HashMap<String, String> = self
+            .binders()
+            .into_iter()
+            .filter(|name| !bindings.contains_key(name.as_str()))
+            .map(|name| {
+                let renamed = gensym(&name);
+                (name, renamed)
+            })
+            .collect();
+
+        let mut expansion = Vec::new();
+        for token in &self.template {
+            match token {
+                Token::Symbol(symbol) => {
+                    if let Some(arg) = bindings.get(symbol.as_str()) {
+                        expansion.extend(arg.iter().cloned());
+                    } else if let Some(renamed) = renames.get(symbol) {
+                        expansion.push(Token::Symbol(renamed.clone()));
+                    } else {
+                        expansion.push(token.clone());
+                    }
+                }
+                _ => expansion.push(token.clone()),
+            }
+        }
+        expansion
+    }
+
+    /// 模板中 `let`、`let*`、`for`、`lambda` 和 `catch` 引入的绑定名
+    /// Names bound in the template by `let`, `let*`, `for`, `lambda` and `catch`
+    fn binders(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let tokens = &self.template;
+        for (i, token) in tokens.iter().enumerate() {
+            let Token::Symbol(keyword) = token else {
+                continue;
+            };
+            let follows_paren = i > 0 && tokens[i - 1] == Token::LeftParen;
+            match (keyword.as_str(), tokens.get(i + 1)) {
+                ("let" | "for", Some(Token::Symbol(name))) if follows_paren => {
+                    names.push(name.clone())
+                }
+                // 多绑定 let 中每个绑定的第一个符号 / The first symbol of every binding in a multi-binding let
+                ("let" | "let*", Some(Token::LeftParen)) if follows_paren => {
+                    let mut depth = 0;
+                    for (offset, t) in tokens[i + 1..].iter().enumerate() {
+                        match t {
+                            Token::LeftParen => {
+                                depth += 1;
+                                if let (2, Some(Token::Symbol(name))) =
+                                    (depth, tokens.get(i + 2 + offset))
+                                {
+                                    names.push(name.clone());
+                                }
+                            }
+                            Token::RightParen if depth == 1 => break,
+                            Token::RightParen => depth -= 1,
+                            _ => {}
+                        }
+                    }
+                }
+                ("catch", Some(Token::Symbol(name))) => names.push(name.clone()),
+                ("lambda", Some(Token::LeftParen)) if follows_paren => {
+                    names.extend(tokens[i + 2..].iter().map_while(|t| match t {
+                        Token::Symbol(name) => Some(name.clone()),
+                        _ => None,
+                    }))
+                }
+                // 推导式绑定列表中最后一个是迭代对象 / The last item of a comprehension binding is the iterable
+                ("for/list" | "for/dict", Some(Token::LeftParen)) if follows_paren => {
+                    let symbols: Vec<String> = tokens[i + 2..]
+                        .iter()
+                        .map_while(|t| match t {
+                            Token::Symbol(name) => Some(name.clone()),
+                            _ => None,
+                        })
+                        .collect();
+                    let bound = match tokens.get(i + 2 + symbols.len()) {
+                        Some(Token::RightParen) => symbols.len().saturating_sub(1),
+                        _ => symbols.len(),
+                    };
+                    names.extend(symbols.into_iter().take(bound));
+                }
+                _ => {}
+            }
+        }
+        names
+    }
+}
+
+/// 是否为单个合法符号 / Whether it is a single valid symbol
+fn is_symbol(word: &str) -> bool {
+    matches!(
+        Tokenizer::new(word).tokenize().as_deref(),
+        Ok([Token::Symbol(symbol), Token::EOF]) if symbol == word
+    )
+}
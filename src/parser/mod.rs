@@ -6,9 +6,16 @@
 //! ## 快速导航 / Quick Navigation
 //!
 //! - `adaptive.rs` - **自适应解析器主逻辑** - 主入口: `AdaptiveParser::new()`, `parse()`
-//! - `nlu.rs` - **自然语言理解** - 意图识别、中英文转代码: `NLU::parse_intent()`
+//! - `nlu.rs` - **自然语言理解** - 意图识别、自然语言转代码: `NLU::parse_intent()`
+//! - `locale.rs` - **短语表** - 各语言的NLU关键词（JSON），运行时加载: `PhraseTable`, `NLUParser::add_locale()`
+//! - `normalize.rs` - **数字规范化** - 中文大数、分数、百分数、日期: `parse_number()`, `normalize_numerals()`
+//! - `backend.rs` - **NLU后端** - 规则引擎或兼容OpenAI接口的HTTP模型: `NluBackend`, `HttpBackend`
 //! - `context.rs` - **上下文管理** - 多轮对话、变量引用解析: `ContextManager`
 //! - `explainer.rs` - **代码解释器** - 代码转自然语言、中英文双语解释
+//! - `formatter.rs` - **代码格式化** - 规范缩进、可配置行宽: `format_source()`
+//! - `incremental.rs` - **增量解析** - 字节范围语法树、编辑后复用子树: `parse_incremental()`
+//! - `macros.rs` - **宏系统** - `defmacro` 模板宏、卫生展开: `Macro`, `gensym()`
+//! - `registry.rs` - **共享规则注册表** - 进化规则热加载到运行中的解析器、语法版本: `RuleRegistry`
 //!
 //! ## 数据流 / Data Flow
 //! ```
@@ -20,11 +27,25 @@
 //! ```
 
 pub mod adaptive;
+pub mod backend;
 pub mod context;
 pub mod explainer;
+pub mod formatter;
+pub mod incremental;
+pub mod locale;
+pub mod macros;
 pub mod nlu;
+pub mod normalize;
+pub mod registry;
 
 pub use adaptive::*;
+pub use backend::*;
 pub use context::*;
 pub use explainer::*;
+pub use formatter::*;
+pub use incremental::*;
+pub use locale::*;
+pub use macros::*;
 pub use nlu::*;
+pub use normalize::*;
+pub use registry::*;
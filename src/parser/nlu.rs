@@ -3,8 +3,13 @@
 // Converts natural language input to code structures
 
 use crate::grammar::core::{BinOp, Expr, GrammarElement, Literal};
+use crate::grammar::visit::preorder;
+use crate::parser::backend::NluBackend;
+use crate::parser::context::UserProfile;
+use crate::parser::locale::{PhraseTable, DEFAULT_LOCALES};
+use crate::parser::normalize::{find_numbers, parse_number, ParsedNumber};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::sync::Arc;
 
 /// 自然语言解析器 / Natural language parser
 pub struct NLUParser {
@@ -12,10 +17,22 @@ pub struct NLUParser {
     model_type: ModelType,
     /// 是否使用本地模型 / Whether to use local model
     use_local: bool,
-    /// 规则库 / Rule database
-    rules: RuleDatabase,
+    /// 已加载的短语表（语言代码, 短语表）/ Loaded phrase tables (locale, table)
+    locales: Vec<(String, PhraseTable)>,
+    /// 规则库：已加载短语表的合并 / Rule database: the merge of the loaded phrase tables
+    rules: PhraseTable,
+    /// 可插拔后端，未设置时使用规则库 / Pluggable backend; the rule database is used when unset
+    backend: Option<Arc<dyn NluBackend>>,
+    /// 置信度低于该值时请求澄清 / Ask for clarification when confidence is below this value
+    clarification_threshold: f64,
+    /// 输入没有给出参数时猜测的参数，来自用户画像 / Parameters guessed when the input names none, from the user profile
+    default_parameters: Vec<String>,
 }
 
+/// 默认澄清阈值：只有没有任何关键词支持的猜测低于它
+/// Default clarification threshold: only guesses without any supporting keyword fall below it
+pub const DEFAULT_CLARIFICATION_THRESHOLD: f64 = 0.6;
+
 /// 模型类型 / Model type
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ModelType {
@@ -31,106 +48,253 @@ pub enum ModelType {
     FineTuned,
 }
 
-/// 规则数据库 / Rule database
-struct RuleDatabase {
-    /// 函数定义关键词 / Function definition keywords
-    function_keywords: Vec<&'static str>,
-    /// 变量定义关键词 / Variable definition keywords
-    variable_keywords: Vec<&'static str>,
-    /// 操作关键词映射 / Operation keyword mapping
-    operation_keywords: HashMap<&'static str, BinOp>,
-    /// 数字提取模式 / Number extraction patterns
-    number_patterns: Vec<&'static str>,
+/// 默认语言的内置短语表 / Built-in phrase tables of the default locales
+fn default_locales() -> Vec<(String, PhraseTable)> {
+    DEFAULT_LOCALES
+        .iter()
+        .filter_map(|locale| Some((locale.to_string(), PhraseTable::builtin(locale)?)))
+        .collect()
 }
 
-impl RuleDatabase {
-    fn new() -> Self {
-        let mut operation_keywords = HashMap::new();
-        // 中文操作符
-        operation_keywords.insert("加", BinOp::Add);
-        operation_keywords.insert("加上", BinOp::Add);
-        operation_keywords.insert("减", BinOp::Sub);
-        operation_keywords.insert("减去", BinOp::Sub);
-        operation_keywords.insert("乘", BinOp::Mul);
-        operation_keywords.insert("乘以", BinOp::Mul);
-        operation_keywords.insert("除", BinOp::Div);
-        operation_keywords.insert("除以", BinOp::Div);
-        operation_keywords.insert("等于", BinOp::Eq);
-        operation_keywords.insert("大于", BinOp::Gt);
-        operation_keywords.insert("小于", BinOp::Lt);
-        operation_keywords.insert("小于等于", BinOp::Le);
-        operation_keywords.insert("大于等于", BinOp::Ge);
-        operation_keywords.insert("不等于", BinOp::Ne);
-        // 英文操作符
-        operation_keywords.insert("plus", BinOp::Add);
-        operation_keywords.insert("add", BinOp::Add);
-        operation_keywords.insert("minus", BinOp::Sub);
-        operation_keywords.insert("subtract", BinOp::Sub);
-        operation_keywords.insert("multiply", BinOp::Mul);
-        operation_keywords.insert("times", BinOp::Mul);
-        operation_keywords.insert("divide", BinOp::Div);
-        operation_keywords.insert("equals", BinOp::Eq);
-        operation_keywords.insert("equal", BinOp::Eq);
-        operation_keywords.insert("greater than", BinOp::Gt);
-        operation_keywords.insert("less than", BinOp::Lt);
-
-        Self {
-            function_keywords: vec![
-                "定义函数",
-                "创建一个函数",
-                "写一个函数",
-                "函数",
-                "def",
-                "define",
-                "function",
-                "create function",
-                "make function",
-                "定义一个函数",
-                "写函数",
-            ],
-            variable_keywords: vec![
-                "定义变量",
-                "创建一个变量",
-                "变量",
-                "let",
-                "variable",
-                "var",
-                "set",
-                "create variable",
-                "定义一个变量",
-                "设置变量",
-                "赋值",
-            ],
-            operation_keywords,
-            number_patterns: vec![
-                "零", "一", "二", "三", "四", "五", "六", "七", "八", "九", "十",
-            ],
-        }
+/// 按加载顺序合并短语表 / Merge phrase tables in load order
+fn merge_locales(locales: &[(String, PhraseTable)]) -> PhraseTable {
+    let mut rules = PhraseTable::default();
+    for (_, table) in locales {
+        rules.merge(table);
     }
+    rules
 }
 
 impl NLUParser {
     /// 创建新NLU解析器（基于规则） / Create new NLU parser (rule-based)
     pub fn new_rule_based() -> Self {
+        let locales = default_locales();
         Self {
             model_type: ModelType::RuleBased,
             use_local: true,
-            rules: RuleDatabase::new(),
+            rules: merge_locales(&locales),
+            locales,
+            backend: None,
+            clarification_threshold: DEFAULT_CLARIFICATION_THRESHOLD,
+            default_parameters: Vec::new(),
+        }
+    }
+
+    /// 创建使用指定后端的NLU解析器 / Create an NLU parser that uses the given backend
+    pub fn with_backend(backend: Arc<dyn NluBackend>) -> Self {
+        let mut parser = Self::new_rule_based();
+        parser.backend = Some(backend);
+        parser
+    }
+
+    /// 运行时切换后端，`None` 恢复为规则库 / Switch the backend at runtime; `None` returns to the rule database
+    pub fn set_backend(&mut self, backend: Option<Arc<dyn NluBackend>>) {
+        self.backend = backend;
+    }
+
+    /// 当前后端 / Current backend
+    pub fn backend(&self) -> Option<&Arc<dyn NluBackend>> {
+        self.backend.as_ref()
+    }
+
+    /// 添加或替换一种语言的短语表，之后的解析也匹配该语言的说法
+    /// Add or replace the phrase table of a locale; later parses also match that language's phrasings
+    pub fn add_locale(&mut self, locale: &str, table: PhraseTable) {
+        match self.locales.iter_mut().find(|(name, _)| name == locale) {
+            Some((_, existing)) => *existing = table,
+            None => self.locales.push((locale.to_string(), table)),
         }
+        self.rules = merge_locales(&self.locales);
+    }
+
+    /// 添加短语表（构建器形式）/ Add a phrase table (builder form)
+    pub fn with_locale(mut self, locale: &str, table: PhraseTable) -> Self {
+        self.add_locale(locale, table);
+        self
+    }
+
+    /// 移除一种语言的短语表 / Remove the phrase table of a locale
+    pub fn remove_locale(&mut self, locale: &str) -> bool {
+        let before = self.locales.len();
+        self.locales.retain(|(name, _)| name != locale);
+        self.rules = merge_locales(&self.locales);
+        self.locales.len() != before
+    }
+
+    /// 加载目录中的所有 `<语言代码>.json` 短语表，返回加载的语言代码
+    /// Load every `<locale>.json` phrase table in a directory, returning the loaded locales
+    pub fn load_locale_dir(&mut self, dir: &std::path::Path) -> Result<Vec<String>, NLUError> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| NLUError::InvalidPhraseTable(format!("{}: {}", dir.display(), e)))?;
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        let mut loaded = Vec::new();
+        for path in paths {
+            let Some(locale) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            self.add_locale(locale, PhraseTable::from_file(&path)?);
+            loaded.push(locale.to_string());
+        }
+        Ok(loaded)
+    }
+
+    /// 已加载的语言代码 / Loaded locales
+    pub fn locales(&self) -> Vec<&str> {
+        self.locales.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// 设置澄清阈值（0 表示从不请求澄清）/ Set the clarification threshold (0 never asks for clarification)
+    pub fn with_clarification_threshold(mut self, threshold: f64) -> Self {
+        self.set_clarification_threshold(threshold);
+        self
+    }
+
+    /// 设置澄清阈值 / Set the clarification threshold
+    pub fn set_clarification_threshold(&mut self, threshold: f64) {
+        self.clarification_threshold = threshold.clamp(0.0, 1.0);
+    }
+
+    /// 澄清阈值 / Clarification threshold
+    pub fn clarification_threshold(&self) -> f64 {
+        self.clarification_threshold
+    }
+
+    /// 采用用户画像中最常用的参数列表作为默认参数猜测 / Use the profile's most common parameter list as the default parameter guess
+    pub fn apply_profile(&mut self, profile: &UserProfile) {
+        self.default_parameters = profile.default_parameters().unwrap_or_default();
+    }
+
+    /// 解析自然语言；置信度低于阈值时不猜测，而是返回澄清问题和候选意图
+    /// Parse natural language; below the threshold, return a clarification question and candidate intents instead
+    /// of guessing
+    pub fn parse(&self, input: &str) -> Result<ParseOutcome, NLUError> {
+        let intent = self.parse_intent(input)?;
+        if intent.confidence >= self.clarification_threshold {
+            return Ok(ParseOutcome::Parsed(intent));
+        }
+        let mut candidates = self.candidate_intents(input);
+        if !candidates.iter().any(|candidate| {
+            candidate.intent_type == intent.intent_type
+                && candidate.code_structure == intent.code_structure
+        }) {
+            candidates.insert(0, intent);
+        }
+        let options = |english: bool| {
+            candidates
+                .iter()
+                .enumerate()
+                .map(|(index, candidate)| {
+                    let (chinese_label, english_label) = intent_label(&candidate.intent_type);
+                    let label = if english {
+                        english_label
+                    } else {
+                        chinese_label
+                    };
+                    format!("{}. {}", index + 1, label)
+                })
+                .collect::<Vec<_>>()
+        };
+        Ok(ParseOutcome::NeedsClarification {
+            question: format!(
+                "无法确定“{}”的意图，你是想：{}？/ Could not tell what \"{}\" means, did you mean: {}?",
+                input.trim(),
+                options(false).join("；"),
+                input.trim(),
+                options(true).join("; ")
+            ),
+            candidates,
+        })
+    }
+
+    /// 按每种意图尝试解析，得到可作为澄清选项的候选 / Try each intent type to get candidates for a clarification
+    fn candidate_intents(&self, input: &str) -> Vec<ParsedIntent> {
+        let input = input.trim();
+        [
+            IntentType::DefineFunction,
+            IntentType::DefineVariable,
+            IntentType::ExecuteOperation,
+            IntentType::Conditional,
+        ]
+        .into_iter()
+        .filter_map(|intent_type| {
+            let code_structure = self.generate_code_structure(input, &intent_type).ok()?;
+            if code_structure.is_empty() {
+                return None;
+            }
+            Some(ParsedIntent {
+                confidence: self.calculate_confidence(input, &intent_type),
+                intent_type,
+                code_structure,
+                suggested_rules: vec![],
+            })
+        })
+        .collect()
     }
 
     /// 创建新NLU解析器 / Create new NLU parser
     #[allow(dead_code)]
     pub fn new(model_type: ModelType, use_local: bool) -> Self {
+        let locales = default_locales();
         Self {
             model_type,
             use_local,
-            rules: RuleDatabase::new(),
+            rules: merge_locales(&locales),
+            locales,
+            backend: None,
+            clarification_threshold: DEFAULT_CLARIFICATION_THRESHOLD,
+            default_parameters: Vec::new(),
         }
     }
 
-    /// 解析自然语言 / Parse natural language
-    pub fn parse(&self, input: &str) -> Result<ParsedIntent, NLUError> {
+    /// 识别意图，总是给出最佳猜测；后端返回模型错误（如服务不可用）时退回规则库
+    /// Recognize the intent, always returning the best guess; falls back to the rule database when the backend
+    /// reports a model error (such as an unavailable service)
+    pub fn parse_intent(&self, input: &str) -> Result<ParsedIntent, NLUError> {
+        match &self.backend {
+            Some(backend) => match backend.parse_intent(input) {
+                Err(NLUError::ModelError(_)) => self.parse_with_rules(input),
+                result => result,
+            },
+            None => self.parse_with_rules(input),
+        }
+    }
+
+    /// 把自然语言描述转换为Evo源码 / Turn a natural-language description into Evo source code
+    pub fn generate_code(&self, description: &str) -> Result<String, NLUError> {
+        if let Some(backend) = &self.backend {
+            match backend.generate_code(description) {
+                Err(NLUError::ModelError(_)) => {}
+                result => return result,
+            }
+        }
+        Ok(crate::grammar::unparse::unparse(
+            &self.parse_with_rules(description)?.code_structure,
+        ))
+    }
+
+    /// 用自然语言解释Evo源码 / Explain Evo source code in natural language
+    pub fn explain(&self, code: &str) -> Result<String, NLUError> {
+        if let Some(backend) = &self.backend {
+            match backend.explain(code) {
+                Err(NLUError::ModelError(_)) => {}
+                result => return result,
+            }
+        }
+        let ast = crate::parser::adaptive::AdaptiveParser::new(false)
+            .parse(code)
+            .map_err(|e| NLUError::UnsupportedOperation(e.to_string()))?;
+        Ok(crate::parser::explainer::CodeExplainer::new(
+            crate::parser::explainer::Language::Chinese,
+        )
+        .explain_ast(&ast))
+    }
+
+    /// 用规则库解析自然语言 / Parse natural language with the rule database
+    fn parse_with_rules(&self, input: &str) -> Result<ParsedIntent, NLUError> {
         let input_trimmed = input.trim();
         let statements = self.split_into_statements(input_trimmed);
 
@@ -171,8 +335,71 @@ impl NLUParser {
         })
     }
 
-    /// 提取编程意图 / Extract programming intent
+    /// 提取编程意图；设置了后端时由后端识别，从其代码结构中取出名称和参数
+    /// Extract programming intent; with a backend set, the backend recognizes it and names and parameters are taken
+    /// from its code structure
     pub fn extract_intent(&self, input: &str) -> Result<ProgrammingIntent, NLUError> {
+        if let Some(backend) = &self.backend {
+            match backend.parse_intent(input) {
+                Ok(parsed) => return Ok(Self::intent_from_structure(input, &parsed)),
+                Err(NLUError::ModelError(_)) => {}
+                Err(error) => return Err(error),
+            }
+        }
+        self.extract_intent_with_rules(input)
+    }
+
+    /// 从代码结构构造编程意图（动作、实体和参数）/ Build a programming intent (action, entities and parameters)
+    /// from a code structure
+    pub fn intent_from_structure(input: &str, parsed: &ParsedIntent) -> ProgrammingIntent {
+        let name = |element: &GrammarElement| match element {
+            GrammarElement::Atom(name) => Some(name.clone()),
+            GrammarElement::Expr(expr) => match expr.as_ref() {
+                Expr::Var(name) => Some(name.to_string()),
+                _ => None,
+            },
+            _ => None,
+        };
+        let form = parsed
+            .code_structure
+            .iter()
+            .find_map(|element| match element {
+                GrammarElement::List(items) => Some(items.as_slice()),
+                _ => None,
+            });
+        let (action, entities, parameters) = match (&parsed.intent_type, form) {
+            (IntentType::DefineFunction, Some([_, function, GrammarElement::List(params), ..])) => {
+                (
+                    "define_function",
+                    name(function).into_iter().collect(),
+                    params
+                        .iter()
+                        .filter_map(name)
+                        .map(|param| ("param".to_string(), param))
+                        .collect(),
+                )
+            }
+            (IntentType::DefineVariable, Some([_, variable, ..])) => (
+                "define_variable",
+                name(variable).into_iter().collect(),
+                vec![],
+            ),
+            (IntentType::DefineFunction, _) => ("define_function", vec![], vec![]),
+            (IntentType::DefineVariable, _) => ("define_variable", vec![], vec![]),
+            (IntentType::ExecuteOperation, _) => ("execute_operation", vec![], vec![]),
+            (IntentType::Conditional, _) => ("conditional_expression", vec![], vec![]),
+            _ => ("unknown", vec![], vec![]),
+        };
+        ProgrammingIntent {
+            action: action.to_string(),
+            entities,
+            parameters,
+            context: Some(input.to_string()),
+        }
+    }
+
+    /// 用规则库提取编程意图 / Extract programming intent with the rule database
+    fn extract_intent_with_rules(&self, input: &str) -> Result<ProgrammingIntent, NLUError> {
         let input_lower = input.trim().to_lowercase();
         let intent_type = self.detect_intent_type(&input_lower)?;
 
@@ -213,29 +440,32 @@ impl NLUParser {
     /// 检测意图类型 / Detect intent type
     fn detect_intent_type(&self, input: &str) -> Result<IntentType, NLUError> {
         // 条件表达式检测
-        if (input.contains("如果") && (input.contains("否则") || input.contains("不然")))
-            || (input.contains("if") && input.contains("else"))
-        {
+        if self.is_conditional(input) {
             return Ok(IntentType::Conditional);
         }
 
-        // 检查是否是函数定义
-        for keyword in &self.rules.function_keywords {
-            if input.contains(keyword) {
-                return Ok(IntentType::DefineFunction);
-            }
-        }
-
-        // 检查是否是变量定义
-        for keyword in &self.rules.variable_keywords {
-            if input.contains(keyword) {
+        // 函数定义和变量定义取匹配到的较长关键词，"define la variable" 是变量而不是 "define" 函数
+        // Function and variable definitions go by the longer matched keyword: "define la variable" is a variable,
+        // not a "define" function
+        let longest = |keywords: &[String]| {
+            keywords
+                .iter()
+                .filter(|keyword| input.contains(keyword.as_str()))
+                .map(|keyword| keyword.chars().count())
+                .max()
+        };
+        match (longest(&self.rules.function), longest(&self.rules.variable)) {
+            (Some(function), Some(variable)) if variable > function => {
                 return Ok(IntentType::DefineVariable);
             }
+            (Some(_), _) => return Ok(IntentType::DefineFunction),
+            (None, Some(_)) => return Ok(IntentType::DefineVariable),
+            (None, None) => {}
         }
 
         // 检查是否是操作
-        for keyword in self.rules.operation_keywords.keys() {
-            if input.contains(keyword) {
+        for keyword in self.rules.operations.keys() {
+            if input.contains(keyword.as_str()) {
                 return Ok(IntentType::ExecuteOperation);
             }
         }
@@ -244,6 +474,19 @@ impl NLUParser {
         Ok(IntentType::ExecuteOperation)
     }
 
+    /// 是否同时含有条件开头和否则分支的标记（输入已转小写）/ Whether both if and else markers appear (input is lowercased)
+    fn is_conditional(&self, input: &str) -> bool {
+        let contains_any = |phrases: &[String]| phrases.iter().any(|p| input.contains(p.as_str()));
+        contains_any(&self.rules.if_markers) && contains_any(&self.rules.else_markers)
+    }
+
+    /// 按长度从长到短排列的短语，较具体的说法优先 / Phrases from longest to shortest, so more specific phrasings win
+    fn longest_first(phrases: &[String]) -> Vec<&String> {
+        let mut sorted: Vec<&String> = phrases.iter().collect();
+        sorted.sort_by_key(|phrase| std::cmp::Reverse(phrase.chars().count()));
+        sorted
+    }
+
     /// 生成代码结构 / Generate code structure
     fn generate_code_structure(
         &self,
@@ -262,8 +505,11 @@ impl NLUParser {
     /// 生成函数定义 / Generate function definition
     fn generate_function_definition(&self, input: &str) -> Result<Vec<GrammarElement>, NLUError> {
         let func_name = self.extract_function_name(input)?;
-        let params = self.extract_function_params(input)?;
+        let mut params = self.extract_function_params(input)?;
         let body = self.extract_function_body(input)?;
+        if params.is_empty() {
+            params = self.guess_parameters(&body);
+        }
 
         // 构建函数定义: (def func_name (param1 param2 ...) body)
         let mut elements = vec![
@@ -284,6 +530,27 @@ impl NLUParser {
         Ok(vec![GrammarElement::List(elements)])
     }
 
+    /// 输入没有给出参数时按用户画像猜测：没有函数体时取默认参数，否则取函数体用到的默认参数
+    /// Guess parameters from the user profile when the input names none: the default parameters without a body,
+    /// otherwise those the body uses
+    fn guess_parameters(&self, body: &GrammarElement) -> Vec<String> {
+        let GrammarElement::Expr(body) = body else {
+            return Vec::new();
+        };
+        if matches!(body.as_ref(), Expr::Literal(Literal::Null)) {
+            return self.default_parameters.clone();
+        }
+        let used = preorder(body);
+        self.default_parameters
+            .iter()
+            .filter(|param| {
+                used.iter()
+                    .any(|expr| matches!(expr, Expr::Var(name) if name == *param))
+            })
+            .cloned()
+            .collect()
+    }
+
     /// 生成变量定义 / Generate variable definition
     fn generate_variable_definition(&self, input: &str) -> Result<Vec<GrammarElement>, NLUError> {
         let var_name = self.extract_variable_name(input)?;
@@ -294,7 +561,7 @@ impl NLUParser {
             GrammarElement::Atom("let".to_string()),
             GrammarElement::Atom(var_name.clone()),
             value,
-            GrammarElement::Expr(Box::new(Expr::Var(var_name))),
+            GrammarElement::Expr(Box::new(Expr::Var(var_name.into()))),
         ];
 
         Ok(vec![GrammarElement::List(elements)])
@@ -325,29 +592,10 @@ impl NLUParser {
     /// 提取函数名 / Extract function name
     fn extract_function_name(&self, input: &str) -> Result<String, NLUError> {
         // 增强的模式匹配：查找"函数名"、"叫"、"名为"等关键词后的名称
-        let patterns = vec![
-            ("函数叫", "function called"),
-            ("函数名为", "function named"),
-            ("函数", "function"),
-            ("叫", "called"),
-            ("名为", "named"),
-            ("def", "def"),
-            ("define", "define"),
-        ];
-
-        for (cn_pattern, en_pattern) in patterns {
-            // 尝试中文模式
-            if let Some(pos) = input.find(cn_pattern) {
-                let after = &input[pos + cn_pattern.len()..];
-                // 提取函数名，支持中英文和数字
-                let name = self.extract_identifier(after);
-                if !name.is_empty() && name != "func" {
-                    return Ok(name);
-                }
-            }
-            // 尝试英文模式
-            if let Some(pos) = input.find(en_pattern) {
-                let after = &input[pos + en_pattern.len()..];
+        for pattern in Self::longest_first(&self.rules.function_name) {
+            if let Some(pos) = input.find(pattern.as_str()) {
+                let after = &input[pos + pattern.len()..];
+                // 提取函数名，支持各种文字和数字
                 let name = self.extract_identifier(after);
                 if !name.is_empty() && name != "func" {
                     return Ok(name);
@@ -356,7 +604,7 @@ impl NLUParser {
         }
 
         // 尝试从"定义一个函数add"这样的模式中提取
-        for keyword in &self.rules.function_keywords {
+        for keyword in &self.rules.function {
             if let Some(pos) = input.find(keyword) {
                 let after = &input[pos + keyword.len()..];
                 // 跳过"一个"、"个"等词
@@ -406,11 +654,10 @@ impl NLUParser {
 
     /// 提取变量名（在值关键词前截断） / Extract identifier before value keyword
     fn extract_identifier_before_value(&self, text: &str) -> String {
-        let keywords = ["等于", "=", "是", "为", "equals", "is", "be"];
         let mut candidate = text.trim();
         let mut cut_pos: Option<usize> = None;
-        for keyword in &keywords {
-            if let Some(pos) = candidate.find(keyword) {
+        for keyword in &self.rules.value {
+            if let Some(pos) = candidate.find(keyword.as_str()) {
                 cut_pos = match cut_pos {
                     Some(existing) => Some(existing.min(pos)),
                     None => Some(pos),
@@ -426,24 +673,9 @@ impl NLUParser {
     /// 提取函数参数 / Extract function parameters
     fn extract_function_params(&self, input: &str) -> Result<Vec<String>, NLUError> {
         // 查找参数列表，通常在"参数"、"parameters"、"接受"等词后
-        let patterns = vec![
-            ("参数是", "parameters are"),
-            ("参数", "parameters"),
-            ("接受", "takes"),
-            ("输入", "input"),
-            ("参数为", "params"),
-        ];
-
-        for (cn_pattern, en_pattern) in patterns {
-            if let Some(pos) = input.find(cn_pattern) {
-                let after = &input[pos + cn_pattern.len()..];
-                let params = self.parse_parameter_list(after);
-                if !params.is_empty() {
-                    return Ok(params);
-                }
-            }
-            if let Some(pos) = input.find(en_pattern) {
-                let after = &input[pos + en_pattern.len()..];
+        for pattern in &self.rules.parameters {
+            if let Some(pos) = input.find(pattern.as_str()) {
+                let after = &input[pos + pattern.len()..];
                 let params = self.parse_parameter_list(after);
                 if !params.is_empty() {
                     return Ok(params);
@@ -492,10 +724,24 @@ impl NLUParser {
     fn parse_parameter_list(&self, text: &str) -> Vec<String> {
         let mut params = Vec::new();
 
-        // 分割参数：支持中文逗号、英文逗号、空格、"和"、"and"等
-        let parts: Vec<&str> = text
-            .split(|c: char| c == '，' || c == ',' || c == ' ' || c == '(' || c == ')')
-            .collect();
+        // 分割参数：支持中文逗号、英文逗号、顿号、空格；不用空格分词的连接词（如"和"）直接作为分隔符
+        // Split parameters on commas, enumeration commas and spaces; conjunctions of languages written without
+        // spaces (such as "和") are separators themselves
+        // 参数列表在函数体短语（如"返回"、"que devuelve"）处结束 / The parameter list ends at a body phrase
+        let end = self
+            .rules
+            .body
+            .iter()
+            .filter_map(|phrase| find_phrase(text, phrase))
+            .min()
+            .unwrap_or(text.len());
+        let mut text = text[..end].to_string();
+        for conjunction in &self.rules.conjunctions {
+            if !conjunction.is_ascii() {
+                text = text.replace(conjunction.as_str(), ",");
+            }
+        }
+        let parts: Vec<&str> = text.split(['，', ',', '、', ' ', '(', ')']).collect();
 
         for part in parts {
             let trimmed = part.trim();
@@ -503,11 +749,21 @@ impl NLUParser {
                 continue;
             }
 
-            // 跳过"和"、"and"等连接词
-            if trimmed == "和" || trimmed == "and" || trimmed == "与" {
+            // 跳过"and"等连接词
+            if self.rules.conjunctions.iter().any(|c| c == trimmed) {
                 continue;
             }
 
+            // 运算开始时参数列表结束 / The parameter list ends where an operation starts
+            if self
+                .rules
+                .operations
+                .keys()
+                .any(|op| trimmed.contains(op.as_str()))
+            {
+                break;
+            }
+
             // 提取标识符
             let param = self.extract_identifier(trimmed);
             if !param.is_empty() && !params.contains(&param) {
@@ -526,28 +782,28 @@ impl NLUParser {
     /// 提取函数体 / Extract function body
     fn extract_function_body(&self, input: &str) -> Result<GrammarElement, NLUError> {
         // 查找函数体的关键词
-        let body_patterns = vec![
-            ("返回", "return"),
-            ("结果是", "result is"),
-            ("计算", "calculate"),
-            ("执行", "execute"),
-            ("做", "do"),
-            ("等于", "equals"),
-            ("是", "is"),
-        ];
-
-        // 尝试提取函数体表达式
-        for (cn_pattern, en_pattern) in body_patterns {
-            // 中文模式
-            if let Some(pos) = input.find(cn_pattern) {
-                let after = &input[pos + cn_pattern.len()..];
-                if let Ok(expr) = self.parse_expression_from_text(after) {
-                    return Ok(GrammarElement::Expr(Box::new(expr)));
+        // 尝试提取函数体表达式；参数分句（如"参数是x和y"）中的"是"不是函数体
+        // Try to extract the body expression; the "是" in a parameter clause (such as "参数是x和y") is not a body
+        let clauses: Vec<&str> = input
+            .split(['，', ',', '、'])
+            .map(str::trim)
+            .filter(|clause| {
+                !self
+                    .rules
+                    .parameters
+                    .iter()
+                    .any(|p| clause.starts_with(p.as_str()))
+            })
+            .collect();
+        for pattern in &self.rules.body {
+            for clause in &clauses {
+                let Some(pos) = clause.find(pattern.as_str()) else {
+                    continue;
+                };
+                let after = clause[pos + pattern.len()..].trim();
+                if after.is_empty() {
+                    continue;
                 }
-            }
-            // 英文模式
-            if let Some(pos) = input.find(en_pattern) {
-                let after = &input[pos + en_pattern.len()..];
                 if let Ok(expr) = self.parse_expression_from_text(after) {
                     return Ok(GrammarElement::Expr(Box::new(expr)));
                 }
@@ -555,8 +811,9 @@ impl NLUParser {
         }
 
         // 如果没有找到明确的函数体，尝试从整个输入中提取操作
-        // 例如："定义一个函数add，参数是x和y，x加y"
-        if let Ok((op, left, right)) = self.extract_operation(input) {
+        // 例如："定义一个函数add，参数是x和y，x加y"，运算在最后一个分句中
+        let clause = input.rsplit(['，', ',', '、']).next().unwrap_or(input);
+        if let Ok((op, left, right)) = self.extract_operation(clause) {
             let left_expr = self.parse_value_to_expr(&left)?;
             let right_expr = self.parse_value_to_expr(&right)?;
             let expr = Expr::Binary(op, Box::new(left_expr), Box::new(right_expr));
@@ -584,16 +841,16 @@ impl NLUParser {
 
     /// 拆分多条语句 / Split multiple statements
     fn split_into_statements(&self, input: &str) -> Vec<String> {
-        let connectors = [
-            "然后", "并且", "同时", "接着", "再", "then", "and then", "and",
-        ];
-
+        // 条件中的 "then" 不是语句连接词 / The "then" of a conditional is not a statement connective
+        if self.is_conditional(&input.to_lowercase()) {
+            return vec![input.to_string()];
+        }
         let mut parts = vec![input.to_string()];
-        for connector in connectors.iter() {
+        for connector in &self.rules.connectors {
             let mut new_parts = Vec::new();
             for part in parts {
-                if part.contains(connector) {
-                    let split: Vec<&str> = part.split(connector).collect();
+                if part.contains(connector.as_str()) {
+                    let split: Vec<&str> = part.split(connector.as_str()).collect();
                     for item in split {
                         let trimmed = item.trim();
                         if !trimmed.is_empty() {
@@ -610,53 +867,45 @@ impl NLUParser {
         parts.into_iter().filter(|p| !p.is_empty()).collect()
     }
 
-    /// 提取条件表达式 / Extract conditional parts
+    /// 提取条件表达式：如果/if ... [那么/then] ... 否则/else ...
+    /// Extract conditional parts: if ... [then] ... else ...
     fn extract_conditional_parts(&self, input: &str) -> Result<(String, String, String), NLUError> {
-        let input_lower = input.to_lowercase();
-
-        // 中文模式：如果 ... [那么/则] ... 否则/不然 ...
-        if let Some(if_pos) = input.find("如果") {
-            let after_if = &input[if_pos + "如果".len()..];
-            if let Some(else_pos) = after_if.find("否则") {
-                let cond_then = &after_if[..else_pos];
-                let else_part = &after_if[else_pos + "否则".len()..];
-                let then_part = cond_then
-                    .trim_start_matches("那么")
-                    .trim_start_matches("则")
-                    .trim();
-                return Ok((
-                    cond_then.trim().to_string(),
-                    then_part.to_string(),
-                    else_part.trim().to_string(),
-                ));
-            }
-            if let Some(else_pos) = after_if.find("不然") {
-                let cond_then = &after_if[..else_pos];
-                let else_part = &after_if[else_pos + "不然".len()..];
-                let then_part = cond_then
-                    .trim_start_matches("那么")
-                    .trim_start_matches("则")
-                    .trim();
-                return Ok((
-                    cond_then.trim().to_string(),
-                    then_part.to_string(),
-                    else_part.trim().to_string(),
-                ));
-            }
-        }
+        // 小写后长度不变时才用小写匹配，保证偏移可用于原文 / Match in lowercase only when that keeps the byte offsets valid
+        let lower = input.to_lowercase();
+        let haystack = if lower.len() == input.len() {
+            lower.as_str()
+        } else {
+            input
+        };
+        // 最早出现的短语，同一位置取较长者 / Earliest phrase, the longer one at the same position
+        let find_first = |text: &str, phrases: &[String]| {
+            phrases
+                .iter()
+                .filter_map(|phrase| Some((text.find(phrase.as_str())?, phrase.len())))
+                .min_by_key(|&(pos, len)| (pos, std::cmp::Reverse(len)))
+        };
 
-        // 英文模式：if ... then ... else ...
-        if let Some(if_pos) = input_lower.find("if") {
-            let after_if = &input[if_pos + 2..];
-            if let Some(else_pos) = after_if.to_lowercase().find("else") {
-                let cond_then = &after_if[..else_pos];
-                let else_part = &after_if[else_pos + 4..];
-                let then_part = cond_then.trim_start_matches("then").trim();
-                return Ok((
-                    cond_then.trim().to_string(),
-                    then_part.to_string(),
-                    else_part.trim().to_string(),
-                ));
+        if let Some((if_pos, if_len)) = find_first(haystack, &self.rules.if_markers) {
+            let start = if_pos + if_len;
+            if let Some((else_pos, else_len)) =
+                find_first(&haystack[start..], &self.rules.else_markers)
+            {
+                let cond_then = &input[start..start + else_pos];
+                let else_part = &input[start + else_pos + else_len..];
+                let (cond, then_part) = match find_first(
+                    &haystack[start..start + else_pos],
+                    &self.rules.then_markers,
+                ) {
+                    Some((then_pos, then_len)) => {
+                        (&cond_then[..then_pos], &cond_then[then_pos + then_len..])
+                    }
+                    None => (cond_then, cond_then),
+                };
+                let trim = |text: &str| {
+                    text.trim_matches(|c: char| c.is_whitespace() || matches!(c, ',' | '，' | '、'))
+                        .to_string()
+                };
+                return Ok((trim(cond), trim(then_part), trim(else_part)));
             }
         }
 
@@ -667,28 +916,9 @@ impl NLUParser {
 
     /// 提取变量名 / Extract variable name
     fn extract_variable_name(&self, input: &str) -> Result<String, NLUError> {
-        let patterns = vec![
-            ("变量叫", "variable called"),
-            ("变量名为", "variable named"),
-            ("变量", "variable"),
-            ("叫", "called"),
-            ("名为", "named"),
-            ("let", "let"),
-            ("var", "var"),
-            ("等于", "equals"),
-            ("=", "="),
-        ];
-
-        for (cn_pattern, en_pattern) in patterns {
-            if let Some(pos) = input.find(cn_pattern) {
-                let after = &input[pos + cn_pattern.len()..];
-                let name = self.extract_identifier_before_value(after);
-                if !name.is_empty() && name != "x" {
-                    return Ok(name);
-                }
-            }
-            if let Some(pos) = input.find(en_pattern) {
-                let after = &input[pos + en_pattern.len()..];
+        for pattern in Self::longest_first(&self.rules.variable_name) {
+            if let Some(pos) = input.find(pattern.as_str()) {
+                let after = &input[pos + pattern.len()..];
                 let name = self.extract_identifier_before_value(after);
                 if !name.is_empty() && name != "x" {
                     return Ok(name);
@@ -697,7 +927,7 @@ impl NLUParser {
         }
 
         // 尝试从"定义一个变量x等于10"这样的模式中提取
-        for keyword in &self.rules.variable_keywords {
+        for keyword in &self.rules.variable {
             if let Some(pos) = input.find(keyword) {
                 let after = &input[pos + keyword.len()..];
                 let cleaned = after.trim_start_matches(|c: char| {
@@ -716,17 +946,9 @@ impl NLUParser {
     /// 提取变量值 / Extract variable value
     fn extract_variable_value(&self, input: &str) -> Result<GrammarElement, NLUError> {
         // 查找"等于"、"="等关键词后的值
-        let value_patterns = vec![("等于", "equals"), ("=", "="), ("是", "is")];
-
-        for (cn_pattern, en_pattern) in value_patterns {
-            if let Some(pos) = input.find(cn_pattern) {
-                let after = &input[pos + cn_pattern.len()..].trim();
-                if let Ok(expr) = self.parse_expression_from_text(after) {
-                    return Ok(GrammarElement::Expr(Box::new(expr)));
-                }
-            }
-            if let Some(pos) = input.find(en_pattern) {
-                let after = &input[pos + en_pattern.len()..].trim();
+        for pattern in &self.rules.value {
+            if let Some(pos) = input.find(pattern.as_str()) {
+                let after = input[pos + pattern.len()..].trim();
                 if let Ok(expr) = self.parse_expression_from_text(after) {
                     return Ok(GrammarElement::Expr(Box::new(expr)));
                 }
@@ -735,9 +957,7 @@ impl NLUParser {
 
         // 尝试提取数字
         if let Ok(num) = self.extract_number(input) {
-            return Ok(GrammarElement::Expr(Box::new(Expr::Literal(Literal::Int(
-                num,
-            )))));
+            return Ok(GrammarElement::Expr(Box::new(Expr::Literal(num))));
         }
 
         // 尝试提取字符串（如果输入包含引号）
@@ -769,14 +989,14 @@ impl NLUParser {
         // 按优先级查找操作关键词（先查找更长的关键词）
         let mut found_ops: Vec<(usize, BinOp, &str)> = Vec::new();
 
-        for (keyword, op) in &self.rules.operation_keywords {
-            if let Some(pos) = input.find(keyword) {
+        for (keyword, op) in &self.rules.operations {
+            if let Some(pos) = input.find(keyword.as_str()) {
                 found_ops.push((pos, *op, keyword));
             }
         }
 
-        // 按位置排序，找到第一个操作符
-        found_ops.sort_by_key(|(pos, _, _)| *pos);
+        // 按位置排序，找到第一个操作符；同一位置取较长的关键词（"小于等于"而非"小于"）
+        found_ops.sort_by_key(|(pos, _, keyword)| (*pos, std::cmp::Reverse(keyword.len())));
 
         if let Some((_pos, op, keyword)) = found_ops.first() {
             // 提取左右操作数
@@ -840,101 +1060,25 @@ impl NLUParser {
     }
 
     /// 提取数字 / Extract number
-    fn extract_number(&self, input: &str) -> Result<i64, NLUError> {
+    fn extract_number(&self, input: &str) -> Result<Literal, NLUError> {
         // 尝试直接解析数字
         for word in input.split_whitespace() {
             // 移除可能的标点符号
             let cleaned = word.trim_matches(|c: char| !c.is_alphanumeric());
             if let Ok(num) = cleaned.parse::<i64>() {
-                return Ok(num);
+                return Ok(Literal::Int(num));
             }
-        }
-
-        // 尝试解析中文数字（支持简单组合）
-        if let Ok(num) = self.parse_chinese_number(input) {
-            return Ok(num);
-        }
-
-        Ok(0)
-    }
-
-    /// 解析中文数字 / Parse Chinese number
-    fn parse_chinese_number(&self, input: &str) -> Result<i64, NLUError> {
-        let cn_digits: HashMap<&str, i64> = [
-            ("零", 0),
-            ("一", 1),
-            ("二", 2),
-            ("三", 3),
-            ("四", 4),
-            ("五", 5),
-            ("六", 6),
-            ("七", 7),
-            ("八", 8),
-            ("九", 9),
-        ]
-        .iter()
-        .cloned()
-        .collect();
-
-        let cn_units: HashMap<&str, i64> = [("十", 10), ("百", 100), ("千", 1000), ("万", 10000)]
-            .iter()
-            .cloned()
-            .collect();
-
-        let mut result = 0i64;
-        let mut temp = 0i64;
-        let mut found_digit = false;
-
-        let chars: Vec<char> = input.chars().collect();
-        let mut i = 0;
-
-        while i < chars.len() {
-            let ch = chars[i];
-            let ch_str = &ch.to_string();
-
-            // 检查是否是数字
-            if let Some(&digit) = cn_digits.get(ch_str.as_str()) {
-                temp = digit;
-                found_digit = true;
-                i += 1;
-                continue;
-            }
-
-            // 检查是否是单位
-            if let Some(&unit) = cn_units.get(ch_str.as_str()) {
-                if found_digit {
-                    result += temp * unit;
-                    temp = 0;
-                } else if unit == 10 {
-                    // "十"可以单独使用，表示10
-                    result += 10;
-                }
-                found_digit = false;
-                i += 1;
-                continue;
-            }
-
-            // 如果遇到非数字字符，且已经找到数字，返回结果
-            if found_digit && temp > 0 {
-                result += temp;
-                break;
+            if let Some(&num) = self.rules.numbers.get(&cleaned.to_lowercase()) {
+                return Ok(Literal::Int(num));
             }
-
-            i += 1;
         }
 
-        // 处理末尾的数字
-        if found_digit && temp > 0 {
-            result += temp;
+        // 尝试解析中文数字（大数、小数、分数、百分数）
+        if let Some((_, num)) = find_numbers(input).into_iter().next() {
+            return Ok(number_literal(num));
         }
 
-        if result > 0 {
-            Ok(result)
-        } else {
-            Err(NLUError::UnsupportedOperation(
-                "无法解析中文数字".to_string(),
-            ))
-        }
+        Ok(Literal::Int(0))
     }
 
     /// 将值解析为表达式 / Parse value to expression
@@ -949,17 +1093,18 @@ impl NLUParser {
             return Ok(Expr::Literal(Literal::Float(num)));
         }
 
-        // 尝试解析为布尔值
-        if value == "true" || value == "真" {
-            return Ok(Expr::Literal(Literal::Bool(true)));
+        // 尝试解析为布尔值和数词
+        let word = value.to_lowercase();
+        if let Some(&b) = self.rules.booleans.get(&word) {
+            return Ok(Expr::Literal(Literal::Bool(b)));
         }
-        if value == "false" || value == "假" {
-            return Ok(Expr::Literal(Literal::Bool(false)));
+        if let Some(&num) = self.rules.numbers.get(&word) {
+            return Ok(Expr::Literal(Literal::Int(num)));
         }
 
         // 尝试解析中文数字
-        if let Ok(num) = self.parse_chinese_number(value) {
-            return Ok(Expr::Literal(Literal::Int(num)));
+        if let Some(num) = parse_number(value) {
+            return Ok(Expr::Literal(number_literal(num)));
         }
 
         // 尝试解析为字符串字面量
@@ -976,7 +1121,7 @@ impl NLUParser {
 
         // 尝试作为变量
         if value.chars().all(|c| c.is_alphanumeric() || c == '_') {
-            return Ok(Expr::Var(value.to_string()));
+            return Ok(Expr::Var(value.into()));
         }
 
         // 默认返回0
@@ -990,26 +1135,28 @@ impl NLUParser {
         // 根据关键词匹配提高置信度
         match intent_type {
             IntentType::DefineFunction => {
-                for keyword in &self.rules.function_keywords {
-                    if input.contains(keyword) {
+                for keyword in &self.rules.function {
+                    if input.contains(keyword.as_str()) {
                         score += 0.2;
                     }
                 }
             }
             IntentType::DefineVariable => {
-                for keyword in &self.rules.variable_keywords {
-                    if input.contains(keyword) {
+                for keyword in &self.rules.variable {
+                    if input.contains(keyword.as_str()) {
                         score += 0.2;
                     }
                 }
             }
             IntentType::ExecuteOperation => {
-                for keyword in self.rules.operation_keywords.keys() {
-                    if input.contains(keyword) {
+                for keyword in self.rules.operations.keys() {
+                    if input.contains(keyword.as_str()) {
                         score += 0.2;
                     }
                 }
             }
+            // 条件需要同时出现条件和否则两个标记 / A conditional needs both the condition and else markers
+            IntentType::Conditional => score += 0.3,
             _ => {}
         }
 
@@ -1017,6 +1164,67 @@ impl NLUParser {
     }
 }
 
+/// 查找短语；由ASCII字母组成的短语须整词匹配，其他文字（如中文）直接匹配子串
+/// Find a phrase; phrases of ASCII letters must match whole words, other scripts (such as Chinese) match substrings
+fn find_phrase(text: &str, phrase: &str) -> Option<usize> {
+    if phrase.is_empty() {
+        return None;
+    }
+    if !phrase.chars().all(|c| c.is_ascii_alphabetic() || c == ' ') {
+        return text.find(phrase);
+    }
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    text.match_indices(phrase).map(|(pos, _)| pos).find(|&pos| {
+        !is_word(text[..pos].chars().next_back())
+            && !is_word(text[pos + phrase.len()..].chars().next())
+    })
+}
+
+/// 数值转为字面量 / Convert a parsed number to a literal
+fn number_literal(number: ParsedNumber) -> Literal {
+    match number {
+        ParsedNumber::Int(n) => Literal::Int(n),
+        ParsedNumber::Float(f) => Literal::Float(f),
+    }
+}
+
+/// 意图的中英文简短描述 / Chinese and English short descriptions of an intent
+fn intent_label(intent_type: &IntentType) -> (&str, &str) {
+    match intent_type {
+        IntentType::DefineFunction => ("定义函数", "define a function"),
+        IntentType::DefineVariable => ("定义变量", "define a variable"),
+        IntentType::ExecuteOperation => ("执行运算", "run an operation"),
+        IntentType::Conditional => ("条件表达式", "a conditional"),
+        IntentType::ExtendSyntax => ("扩展语法", "extend the syntax"),
+        IntentType::Other(name) => (name, name),
+    }
+}
+
+/// 解析结果 / Parse outcome
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ParseOutcome {
+    /// 识别出的意图 / Recognized intent
+    Parsed(ParsedIntent),
+    /// 置信度过低，需要用户在候选中选择或换种说法
+    /// Confidence too low; the user needs to pick a candidate or rephrase
+    NeedsClarification {
+        /// 向用户提出的问题 / Question for the user
+        question: String,
+        /// 候选意图，按问题中的编号排列 / Candidate intents, in the order numbered in the question
+        candidates: Vec<ParsedIntent>,
+    },
+}
+
+impl ParseOutcome {
+    /// 识别出的意图 / Recognized intent
+    pub fn intent(&self) -> Option<&ParsedIntent> {
+        match self {
+            ParseOutcome::Parsed(intent) => Some(intent),
+            ParseOutcome::NeedsClarification { .. } => None,
+        }
+    }
+}
+
 /// 解析后的意图 / Parsed intent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParsedIntent {
@@ -1047,6 +1255,20 @@ pub enum IntentType {
     Other(String),
 }
 
+impl IntentType {
+    /// 蛇形命名的类型名，`Other` 为其自身名称 / Snake-case type name; `Other` uses its own name
+    pub fn name(&self) -> &str {
+        match self {
+            IntentType::DefineFunction => "define_function",
+            IntentType::DefineVariable => "define_variable",
+            IntentType::ExecuteOperation => "execute_operation",
+            IntentType::Conditional => "conditional",
+            IntentType::ExtendSyntax => "extend_syntax",
+            IntentType::Other(name) => name,
+        }
+    }
+}
+
 /// 编程意图 / Programming intent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProgrammingIntent {
@@ -1061,7 +1283,7 @@ pub struct ProgrammingIntent {
 }
 
 /// NLU错误 / NLU error
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum NLUError {
     /// 未实现 / Not implemented
     NotImplemented,
@@ -1071,4 +1293,6 @@ pub enum NLUError {
     AmbiguousIntent(String),
     /// 不支持的操作 / Unsupported operation
     UnsupportedOperation(String),
+    /// 短语表无效 / Invalid phrase table
+    InvalidPhraseTable(String),
 }
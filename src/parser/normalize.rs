@@ -0,0 +1,424 @@
+// 数字、数量与日期规范化 / Numeral, quantity and date normalization
+// 把中文数字（大数、大写数字、小数、分数、百分数、中阿混写）和日期转换为数值，供NLU和 `parse-number` 内置函数使用
+// Converts Chinese numerals (large numbers, financial digits, decimals, fractions, percentages, mixed Chinese and
+// Arabic forms) and dates to values, for the NLU and the `parse-number` builtin
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// 解析出的数值 / Parsed number
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ParsedNumber {
+    /// 整数 / Integer
+    Int(i64),
+    /// 小数（分数、百分数等）/ Decimal (fractions, percentages, ...)
+    Float(f64),
+}
+
+impl ParsedNumber {
+    /// 转为浮点数 / Convert to a float
+    pub fn as_f64(self) -> f64 {
+        match self {
+            ParsedNumber::Int(n) => n as f64,
+            ParsedNumber::Float(f) => f,
+        }
+    }
+
+    /// 整数值的浮点数还原为整数 / Floats with an integral value become integers
+    fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+            Some(ParsedNumber::Int(value as i64))
+        } else {
+            Some(ParsedNumber::Float(value))
+        }
+    }
+
+    fn negate(self) -> Option<Self> {
+        match self {
+            ParsedNumber::Int(n) => n.checked_neg().map(ParsedNumber::Int),
+            ParsedNumber::Float(f) => Some(ParsedNumber::Float(-f)),
+        }
+    }
+}
+
+/// 单个中文数字的值（含大写数字）/ Value of a single Chinese digit (financial forms included)
+fn digit_value(c: char) -> Option<i64> {
+    Some(match c {
+        '零' | '〇' => 0,
+        '一' | '壹' | '幺' => 1,
+        '二' | '贰' | '两' => 2,
+        '三' | '叁' => 3,
+        '四' | '肆' => 4,
+        '五' | '伍' => 5,
+        '六' | '陆' => 6,
+        '七' | '柒' => 7,
+        '八' | '捌' => 8,
+        '九' | '玖' => 9,
+        _ => c
+            .to_digit(10)
+            .map(i64::from)
+            .or_else(|| fullwidth_digit(c))?,
+    })
+}
+
+/// 全角阿拉伯数字 / Full-width Arabic digit
+fn fullwidth_digit(c: char) -> Option<i64> {
+    ('０'..='９')
+        .contains(&c)
+        .then(|| i64::from(c as u32 - '０' as u32))
+}
+
+/// 十、百、千 / Ten, hundred, thousand
+fn small_unit(c: char) -> Option<i64> {
+    match c {
+        '十' | '拾' => Some(10),
+        '百' | '佰' => Some(100),
+        '千' | '仟' => Some(1000),
+        _ => None,
+    }
+}
+
+/// 万、亿 / Ten thousand, hundred million
+fn large_unit(c: char) -> Option<i64> {
+    match c {
+        '万' | '萬' => Some(10_000),
+        '亿' | '億' => Some(100_000_000),
+        _ => None,
+    }
+}
+
+/// 解析整数：中文数字（"三百零五"、"一万二千"、"两万三" 即 23000）、逐位读法（"二〇二六"）、
+/// 阿拉伯数字（可带千位逗号或全角）以及混写（"3万5千"）
+/// Parse an integer: Chinese numerals ("三百零五", "一万二千", "两万三" meaning 23000), digit-by-digit readings
+/// ("二〇二六"), Arabic digits (with thousands commas or full-width) and mixed forms ("3万5千")
+pub fn parse_integer(text: &str) -> Option<i64> {
+    let text: String = text
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ',' | '，' | '_' | ' '))
+        .collect();
+    if text.is_empty() {
+        return None;
+    }
+    let chars: Vec<char> = text.chars().collect();
+
+    // 没有单位时逐位读取 / Without units, read digit by digit
+    if chars.iter().all(|&c| digit_value(c).is_some()) {
+        return chars.iter().try_fold(0i64, |acc, &c| {
+            acc.checked_mul(10)?.checked_add(digit_value(c)?)
+        });
+    }
+
+    let mut total: i64 = 0; // 亿以上 / Hundred-million level and above
+    let mut wan: i64 = 0; // 万级 / Ten-thousand level
+    let mut section: i64 = 0; // 千以下 / Below ten thousand
+    let mut number: Option<i64> = None;
+    let mut last_unit: i64 = 1;
+    let mut zero_after_unit = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if let Some(digit) = digit_value(c) {
+            // 阿拉伯数字连续读取 / Consecutive Arabic digits form one number
+            let mut value = digit;
+            if c.is_ascii_digit() || fullwidth_digit(c).is_some() {
+                while let Some(next) = chars
+                    .get(i + 1)
+                    .filter(|n| n.is_ascii_digit() || fullwidth_digit(**n).is_some())
+                {
+                    value = value.checked_mul(10)?.checked_add(digit_value(*next)?)?;
+                    i += 1;
+                }
+            }
+            if value == 0 && number.is_none() {
+                zero_after_unit = true;
+            }
+            number = Some(value);
+        } else if let Some(unit) = small_unit(c) {
+            // "十三" 的十前没有数字，视为一十 / The 十 of "十三" has no digit before it and means 一十
+            section = section.checked_add(number.unwrap_or(1).checked_mul(unit)?)?;
+            number = None;
+            last_unit = unit;
+            zero_after_unit = false;
+        } else if c == '廿' {
+            section = section.checked_add(20)?;
+            number = None;
+            last_unit = 10;
+        } else if let Some(unit) = large_unit(c) {
+            // 万、亿前必须有数（"万一" 不是数）/ 万 and 亿 need a number before them ("万一" is not a number)
+            let value = section.checked_add(number.unwrap_or(0))?;
+            if unit == 10_000 {
+                if value == 0 {
+                    return None;
+                }
+                wan = wan.checked_add(value)?.checked_mul(unit)?;
+            } else {
+                let value = total.checked_add(wan)?.checked_add(value)?;
+                if value == 0 {
+                    return None;
+                }
+                total = value.checked_mul(unit)?;
+                wan = 0;
+            }
+            section = 0;
+            number = None;
+            last_unit = unit;
+            zero_after_unit = false;
+        } else {
+            return None;
+        }
+        i += 1;
+    }
+
+    // 口语省略末位单位："两万三" = 23000，"三千五" = 3500；"一百零五" 中有零则不省略
+    // Colloquial omission of the last unit: "两万三" = 23000, "三千五" = 3500; not when a zero intervenes ("一百零五")
+    let mut tail = number.unwrap_or(0);
+    if tail > 0 && tail < 10 && last_unit >= 100 && !zero_after_unit {
+        tail = tail.checked_mul(last_unit / 10)?;
+    }
+    total
+        .checked_add(wan)?
+        .checked_add(section)?
+        .checked_add(tail)
+}
+
+/// 解析小数部分的数字串（"一四" → 0.14）/ Parse the digits after a decimal point ("一四" → 0.14)
+fn parse_fraction_digits(text: &str) -> Option<f64> {
+    let digits: String = text
+        .chars()
+        .map(|c| digit_value(c).and_then(|d| char::from_digit(d as u32, 10)))
+        .collect::<Option<_>>()?;
+    if digits.is_empty() {
+        return None;
+    }
+    format!("0.{}", digits).parse().ok()
+}
+
+/// 解析数：整数、小数（"三点一四"、"1.5"）、大数单位前的小数（"1.5万"、"三点五亿"）、分数（"三分之一"、"1/3"）、
+/// 百分数（"百分之五十"、"50%"）、千分数、半（"一半"、"三个半"）以及正负号（"负三"）
+/// Parse a number: integers, decimals ("三点一四", "1.5"), decimals before a large unit ("1.5万", "三点五亿"),
+/// fractions ("三分之一", "1/3"), percentages ("百分之五十", "50%"), per-mille, halves ("一半", "三个半") and signs ("负三")
+pub fn parse_number(text: &str) -> Option<ParsedNumber> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    for prefix in ["负", "-", "－"] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            return parse_number(rest)?.negate();
+        }
+    }
+    for prefix in ["正", "+"] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            return parse_number(rest);
+        }
+    }
+
+    // 百分数和千分数 / Percentages and per-mille
+    for (prefix, scale) in [("百分之", 100.0), ("千分之", 1000.0)] {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            return ParsedNumber::from_f64(parse_number(rest)?.as_f64() / scale);
+        }
+    }
+    for suffix in ['%', '％'] {
+        if let Some(rest) = text.strip_suffix(suffix) {
+            return ParsedNumber::from_f64(parse_number(rest)?.as_f64() / 100.0);
+        }
+    }
+
+    // 分数："A分之B" 为 B/A，"a/b" / Fractions: "A分之B" is B/A, and "a/b"
+    if let Some((denominator, numerator)) = text.split_once("分之") {
+        return divide(parse_number(numerator)?, parse_number(denominator)?);
+    }
+    if let Some((numerator, denominator)) = text.split_once('/') {
+        return divide(parse_number(numerator)?, parse_number(denominator)?);
+    }
+
+    // 半 / Halves
+    if text == "半" || text == "一半" {
+        return Some(ParsedNumber::Float(0.5));
+    }
+    if let Some(whole) = text
+        .strip_suffix("个半")
+        .or_else(|| text.strip_suffix('半'))
+    {
+        return ParsedNumber::from_f64(parse_integer(whole)? as f64 + 0.5);
+    }
+
+    // 大数单位前的小数："1.5万"、"三点五亿" / Decimals before a large unit: "1.5万", "三点五亿"
+    if let Some(unit) = text.chars().last().and_then(large_unit) {
+        let mantissa = &text[..text.len() - text.chars().last()?.len_utf8()];
+        if mantissa.contains(['.', '点']) {
+            return ParsedNumber::from_f64(parse_number(mantissa)?.as_f64() * unit as f64);
+        }
+    }
+
+    // 小数 / Decimals
+    if let Some((whole, fraction)) = text.split_once('点') {
+        let whole = if whole.is_empty() {
+            0
+        } else {
+            parse_integer(whole)?
+        };
+        return Some(ParsedNumber::Float(
+            whole as f64 + parse_fraction_digits(fraction)?,
+        ));
+    }
+    if text.contains('.') {
+        return text.parse::<f64>().ok().and_then(ParsedNumber::from_f64);
+    }
+
+    parse_integer(text).map(ParsedNumber::Int)
+}
+
+fn divide(numerator: ParsedNumber, denominator: ParsedNumber) -> Option<ParsedNumber> {
+    if denominator.as_f64() == 0.0 {
+        return None;
+    }
+    ParsedNumber::from_f64(numerator.as_f64() / denominator.as_f64())
+}
+
+/// 可以开始一个数的字符 / Characters that can start a number
+fn starts_number(c: char) -> bool {
+    digit_value(c).is_some() || matches!(c, '十' | '拾' | '廿' | '负')
+}
+
+/// 可以出现在数中的字符 / Characters that can appear inside a number
+fn continues_number(c: char) -> bool {
+    starts_number(c)
+        || small_unit(c).is_some()
+        || large_unit(c).is_some()
+        || matches!(c, '点' | '.' | '分' | '之' | '半' | '个' | '%' | '％' | '/')
+}
+
+/// 找出文本中的所有数及其字节范围，每段取能解析的最长前缀
+/// Find every number in the text with its byte range, taking the longest parsable prefix of each run
+pub fn find_numbers(text: &str) -> Vec<(Range<usize>, ParsedNumber)> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let rest = &text[start..];
+        let Some(first) = rest.chars().next() else {
+            break;
+        };
+        // "百分之" 也可以开始一个数 / "百分之" can also start a number
+        if !starts_number(first) && !rest.starts_with("百分之") && !rest.starts_with("千分之")
+        {
+            start += first.len_utf8();
+            continue;
+        }
+        let run_len: usize = rest
+            .char_indices()
+            .take_while(|&(i, c)| continues_number(c) || (i < "千分之".len() && c == '百'))
+            .map(|(_, c)| c.len_utf8())
+            .sum();
+        let mut end = start + run_len;
+        let parsed = loop {
+            if end <= start {
+                break None;
+            }
+            if let Some(number) = parse_number(&text[start..end]) {
+                break Some(number);
+            }
+            end -= text[start..end].chars().last().map_or(1, char::len_utf8);
+        };
+        match parsed {
+            Some(number) => {
+                found.push((start..end, number));
+                start = end;
+            }
+            None => start += first.len_utf8(),
+        }
+    }
+    found
+}
+
+/// 把文本中的数替换为阿拉伯数字（"三百零五个" → "305个"）/ Replace numbers in text with Arabic numerals ("三百零五个" → "305个")
+pub fn normalize_numerals(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for (range, number) in find_numbers(text) {
+        result.push_str(&text[last..range.start]);
+        match number {
+            ParsedNumber::Int(n) => result.push_str(&n.to_string()),
+            ParsedNumber::Float(f) => result.push_str(&f.to_string()),
+        }
+        last = range.end;
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+/// 解析数量：开头的数和其后的量词/单位（"三个苹果" → (3, "个苹果")，"2.5公斤" → (2.5, "公斤")）
+/// Parse a quantity: the leading number and the measure word or unit after it ("三个苹果" → (3, "个苹果"),
+/// "2.5公斤" → (2.5, "公斤"))
+pub fn parse_quantity(text: &str) -> Option<(ParsedNumber, String)> {
+    let text = text.trim();
+    let (range, number) = find_numbers(text).into_iter().next()?;
+    if range.start != 0 {
+        return None;
+    }
+    Some((number, text[range.end..].trim().to_string()))
+}
+
+/// 解析日期：相对日期（今天、明天、后天、昨天、前天）、"Y年M月D日/号"、"M月D日"（年份取自 `today`）和 "YYYY-MM-DD"
+/// Parse a date: relative days (今天, 明天, 后天, 昨天, 前天), "Y年M月D日/号", "M月D日" (year taken from `today`) and
+/// "YYYY-MM-DD"
+pub fn parse_date(text: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let text = text.trim();
+    let relative = match text {
+        "今天" | "今日" => Some(0),
+        "明天" | "明日" => Some(1),
+        "后天" => Some(2),
+        "昨天" | "昨日" => Some(-1),
+        "前天" => Some(-2),
+        _ => None,
+    };
+    if let Some(days) = relative {
+        return today.checked_add_signed(Duration::days(days));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Some(date);
+    }
+
+    let text = text.trim_end_matches(['日', '号']);
+    let (year, rest) = match text.split_once('年') {
+        Some((year, rest)) => (parse_integer(year)?, rest),
+        None => (i64::from(today.year()), text),
+    };
+    let (month, day) = rest.split_once('月')?;
+    NaiveDate::from_ymd_opt(
+        i32::try_from(year).ok()?,
+        u32::try_from(parse_integer(month)?).ok()?,
+        u32::try_from(parse_integer(day)?).ok()?,
+    )
+}
+
+/// 解析日期时间字面量（UTC）："YYYY-MM-DD"、"YYYY-MM-DDTHH:MM[:SS[.fff]]"（`T` 也可以是空格）和带时区的RFC 3339
+/// Parse a date-time literal (UTC): "YYYY-MM-DD", "YYYY-MM-DDTHH:MM[:SS[.fff]]" (`T` may also be a space) and RFC 3339
+/// with an offset
+pub fn parse_datetime(text: &str) -> Option<DateTime<Utc>> {
+    let text = text.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    let text = text.strip_suffix('Z').unwrap_or(text);
+    [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)
+    })
+    .map(|datetime| datetime.and_utc())
+}
@@ -0,0 +1,131 @@
+// 共享语法规则注册表 / Shared grammar rule registry
+// 进化引擎接受的语法规则发布到注册表，挂接了注册表的解析器在下一次解析时即使用新规则；每次变更递增版本号，
+// 并保留各版本的规则集，解释器据此记录执行程序时的语法版本，用于复现进化实验
+// Grammar rules accepted by the evolution engine are published to the registry, and parsers attached to it use
+// them from their next parse on; every change bumps the version and keeps that version's rule set, so the
+// interpreter can record which grammar version executed a program and evolution experiments can be reproduced
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::grammar::rule::{GrammarRule, Stability};
+use crate::parser::macros::Macro;
+
+/// 共享语法规则注册表，克隆后共享同一份规则集 / Shared grammar rule registry; clones share the same rule set
+#[derive(Debug, Clone, Default)]
+pub struct RuleRegistry {
+    inner: Arc<RwLock<RegistryState>>,
+}
+
+/// 注册表内部状态 / Registry internal state
+#[derive(Debug, Default)]
+struct RegistryState {
+    /// 当前版本，每次变更加一，空注册表为 0 / Current version, bumped by every change; 0 for an empty registry
+    version: u64,
+    /// 各版本的规则集 / Rule set of every version
+    history: BTreeMap<u64, Arc<Vec<GrammarRule>>>,
+    /// 当前规则集生成的宏，不含实验性规则 / Macros generated from the current rule set, excluding experimental rules
+    stable_macros: HashMap<String, Macro>,
+    /// 当前规则集生成的宏，含实验性规则 / Macros generated from the current rule set, including experimental rules
+    all_macros: HashMap<String, Macro>,
+}
+
+impl RegistryState {
+    /// 发布新版本的规则集 / Publish a new version of the rule set
+    fn publish(&mut self, rules: Vec<GrammarRule>) -> u64 {
+        self.stable_macros = rule_macros(&rules, false);
+        self.all_macros = rule_macros(&rules, true);
+        self.version += 1;
+        self.history.insert(self.version, Arc::new(rules));
+        self.version
+    }
+}
+
+impl RuleRegistry {
+    /// 创建空注册表 / Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以初始规则集创建注册表（版本 1）/ Create a registry with an initial rule set (version 1)
+    pub fn with_rules(rules: Vec<GrammarRule>) -> Self {
+        let registry = Self::new();
+        registry.replace_rules(rules);
+        registry
+    }
+
+    /// 当前语法版本 / Current grammar version
+    pub fn version(&self) -> u64 {
+        self.read().version
+    }
+
+    /// 当前规则集 / Current rule set
+    pub fn rules(&self) -> Vec<GrammarRule> {
+        self.rules_at(self.version()).unwrap_or_default()
+    }
+
+    /// 指定版本的规则集，用于复现该版本下执行的程序 / Rule set of the given version, for reproducing programs run under it
+    pub fn rules_at(&self, version: u64) -> Option<Vec<GrammarRule>> {
+        if version == 0 {
+            return Some(Vec::new());
+        }
+        let state = self.read();
+        state.history.get(&version).map(|rules| rules.to_vec())
+    }
+
+    /// 添加语法规则，同名规则被替换，返回新版本号 / Add a grammar rule, replacing one with the same name; returns the new version
+    pub fn add_rule(&self, rule: GrammarRule) -> u64 {
+        let mut state = self.write();
+        let mut rules = state
+            .history
+            .get(&state.version)
+            .map(|rules| rules.to_vec())
+            .unwrap_or_default();
+        match rules.iter().position(|existing| existing.name == rule.name) {
+            Some(index) => rules[index] = rule,
+            None => rules.push(rule),
+        }
+        state.publish(rules)
+    }
+
+    /// 替换整个规则集，返回新版本号 / Replace the whole rule set; returns the new version
+    pub fn replace_rules(&self, rules: Vec<GrammarRule>) -> u64 {
+        self.write().publish(rules)
+    }
+
+    /// 当前规则集生成的宏 / Macros generated from the current rule set
+    pub(crate) fn macros(&self, allow_experimental: bool) -> HashMap<String, Macro> {
+        let state = self.read();
+        match allow_experimental {
+            true => state.all_macros.clone(),
+            false => state.stable_macros.clone(),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, RegistryState> {
+        self.inner.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, RegistryState> {
+        self.inner.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 规则集生成的宏，规则稳定性的处理同 `AdaptiveParser::add_rule`
+/// Macros generated from a rule set, handling rule stability as `AdaptiveParser::add_rule` does
+fn rule_macros(rules: &[GrammarRule], allow_experimental: bool) -> HashMap<String, Macro> {
+    let mut macros = HashMap::new();
+    for rule in rules {
+        let active = match rule.meta.stability {
+            Stability::Experimental => allow_experimental,
+            Stability::Deprecated => false,
+            Stability::Stabilizing | Stability::Stable => true,
+        };
+        if active {
+            for definition in Macro::from_rule(rule) {
+                macros.insert(definition.name.clone(), definition);
+            }
+        }
+    }
+    macros
+}
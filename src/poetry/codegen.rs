@@ -0,0 +1,373 @@
+// 诗歌到程序的映射 / Poetry-to-program mapping
+// 把意象和情感分析结果映射为可运行的主题程序：ASCII图案、旋律序列和动画帧流
+// Maps imagery and emotion analysis results to runnable themed programs: ASCII art, melody sequences and
+// animation frame streams
+
+use crate::poetry::emotion::Emotion;
+use crate::poetry::parser::PoemAnalysis;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// 生成的程序形式 / Form of the generated program
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgramForm {
+    /// ASCII图案，逐行打印 / ASCII art, printed row by row
+    AsciiArt,
+    /// 旋律：产出 `(音高 时长毫秒)` 的生成器 / Melody: a generator of `(pitch duration-ms)` pairs
+    Melody,
+    /// 动画：产出帧字符串的生成器 / Animation: a generator of frame strings
+    Animation,
+}
+
+impl ProgramForm {
+    /// 全部形式 / All forms
+    pub const ALL: [ProgramForm; 3] = [
+        ProgramForm::AsciiArt,
+        ProgramForm::Melody,
+        ProgramForm::Animation,
+    ];
+}
+
+/// 运动轨迹：把时间 t 映射到 [0, n) 中的位置，图案的行宽、旋律的音阶位置和动画的帧位置都由它决定
+/// Motion curve: maps time t to a position in [0, n); it drives the row widths of the art, the scale degrees of
+/// the melody and the frame positions of the animation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Motion {
+    /// 上升 / Rising
+    Rise,
+    /// 下落 / Falling
+    Fall,
+    /// 往复 / Back and forth
+    Wave,
+    /// 静止 / Still
+    Still,
+    /// 跳动 / Pulsing
+    Pulse,
+}
+
+impl Motion {
+    /// `(def motion (t n) ...)` 的定义 / Definition of `(def motion (t n) ...)`
+    pub fn definition(self) -> String {
+        let body = match self {
+            Motion::Rise => "(% t n)",
+            Motion::Fall => "(- (- n 1) (% t n))",
+            Motion::Wave => "(let p (% t (* 2 n)) (if (< p n) p (- (- (* 2 n) 1) p)))",
+            Motion::Still => "(/ n 2)",
+            Motion::Pulse => "(if (= (% t 2) 0) 0 (- n 1))",
+        };
+        format!("(def motion (t n) {})", body)
+    }
+}
+
+/// 程序风格：情感映射的结果 / Program style: the result of an emotion mapping
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgramStyle {
+    /// 图案字符（ASCII）/ Glyphs (ASCII)
+    pub glyphs: Vec<String>,
+    /// 音阶（MIDI音高）/ Scale (MIDI pitches)
+    pub scale: Vec<i64>,
+    /// 速度（每分钟拍数）/ Tempo (beats per minute)
+    pub tempo: u32,
+    /// 运动轨迹 / Motion curve
+    pub motion: Motion,
+}
+
+/// 情感到程序结构的映射，注册到 `PoetryCodegen` 后替换该情感的内置映射
+/// Mapping from an emotion to program constructs; registering it with `PoetryCodegen` replaces the built-in
+/// mapping of that emotion
+pub trait EmotionMapping: Send + Sync {
+    /// 处理的情感 / Emotion handled
+    fn emotion(&self) -> Emotion;
+
+    /// 该情感的程序风格 / Program style for the emotion
+    fn style(&self) -> ProgramStyle;
+
+    /// 自定义某种形式的程序；返回 `None` 时使用内置生成器。生成的代码可以使用前导定义中的
+    /// `palette`、`scale`、`rhythm`、`motion` 和 `repeat-glyph`
+    /// Custom program for a form; `None` uses the built-in generator. The code may use `palette`, `scale`,
+    /// `rhythm`, `motion` and `repeat-glyph` from the prelude
+    fn generate(
+        &self,
+        _form: ProgramForm,
+        _analysis: &PoemAnalysis,
+        _style: &ProgramStyle,
+    ) -> Option<String> {
+        None
+    }
+}
+
+/// 只给出风格的映射 / Mapping that only provides a style
+#[derive(Debug, Clone)]
+pub struct StyleMapping {
+    /// 情感 / Emotion
+    pub emotion: Emotion,
+    /// 风格 / Style
+    pub style: ProgramStyle,
+}
+
+impl EmotionMapping for StyleMapping {
+    fn emotion(&self) -> Emotion {
+        self.emotion.clone()
+    }
+
+    fn style(&self) -> ProgramStyle {
+        self.style.clone()
+    }
+}
+
+/// 内置风格：情感决定字符、调式、速度和运动 / Built-in style: the emotion decides glyphs, mode, tempo and motion
+pub fn builtin_style(emotion: Emotion) -> ProgramStyle {
+    let (glyphs, scale, tempo, motion): (&[&str], &[i64], u32, Motion) = match emotion {
+        // 回望故乡：小调五声音阶，往复 / Looking back home: minor pentatonic, back and forth
+        Emotion::Nostalgia => (&["o", ".", "~"], &[57, 60, 62, 64, 67], 66, Motion::Wave),
+        // 宁静：大调五声音阶，静止 / Tranquility: major pentatonic, still
+        Emotion::Tranquility => (&[".", "-", "~"], &[60, 62, 64, 67, 69], 56, Motion::Still),
+        // 孤独：稀疏的字符，下行 / Loneliness: sparse glyphs, descending
+        Emotion::Loneliness => (&[".", "'", " "], &[57, 59, 60, 64, 65], 50, Motion::Fall),
+        // 忧伤：自然小调，下行 / Melancholy: natural minor, descending
+        Emotion::Melancholy => (
+            &["~", ",", "."],
+            &[57, 59, 60, 62, 64, 65, 67],
+            60,
+            Motion::Fall,
+        ),
+        // 喜悦：大调，上行 / Joy: major, ascending
+        Emotion::Joy => (
+            &["*", "+", "o"],
+            &[60, 62, 64, 65, 67, 69, 71, 72],
+            120,
+            Motion::Rise,
+        ),
+        // 愤怒：半音，跳动 / Anger: semitones, pulsing
+        Emotion::Anger => (&["#", "!", "X"], &[48, 49, 52, 53, 55], 144, Motion::Pulse),
+        // 恐惧：减七和弦，跳动 / Fear: diminished seventh, pulsing
+        Emotion::Fear => (&[":", ";", "."], &[60, 63, 66, 69], 96, Motion::Pulse),
+        // 惊讶：全音音阶，上行 / Surprise: whole-tone scale, ascending
+        Emotion::Surprise => (
+            &["!", "?", "*"],
+            &[60, 62, 64, 66, 68, 70],
+            108,
+            Motion::Rise,
+        ),
+        // 自定义情感没有注册映射时沿用中性风格 / Custom emotions without a registered mapping use the neutral style
+        Emotion::Neutral | Emotion::Custom(_) => (
+            &["-", "=", "o"],
+            &[60, 62, 64, 65, 67, 69, 71],
+            90,
+            Motion::Wave,
+        ),
+    };
+    ProgramStyle {
+        glyphs: glyphs.iter().map(|glyph| glyph.to_string()).collect(),
+        scale: scale.to_vec(),
+        tempo,
+        motion,
+    }
+}
+
+/// 意象对应的图案字符 / Glyph for an image
+fn imagery_glyph(element: &str) -> Option<&'static str> {
+    Some(match element {
+        "明月" | "月" => "O",
+        "霜" | "雪" => "*",
+        "光" | "星" => ".",
+        "地" => "_",
+        "床" => "=",
+        "头" | "山" => "^",
+        "故乡" => "#",
+        "水" | "江" | "河" => "~",
+        _ => return None,
+    })
+}
+
+/// 诗歌程序生成器 / Poetry program generator
+pub struct PoetryCodegen {
+    /// 情感映射 / Emotion mappings
+    mappings: HashMap<Emotion, Arc<dyn EmotionMapping>>,
+    /// 图案高度、动画宽度和旋律长度 / Art height, animation width and melody length
+    size: usize,
+}
+
+impl PoetryCodegen {
+    /// 创建使用内置映射的生成器 / Create a generator with the built-in mappings
+    pub fn new() -> Self {
+        let mut codegen = Self {
+            mappings: HashMap::new(),
+            size: 6,
+        };
+        for emotion in Emotion::BUILTIN {
+            codegen.register(Arc::new(StyleMapping {
+                style: builtin_style(emotion.clone()),
+                emotion,
+            }));
+        }
+        codegen
+    }
+
+    /// 设置图案高度、动画宽度和旋律长度（至少为2）/ Set the art height, animation width and melody length (at least 2)
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size.max(2);
+        self
+    }
+
+    /// 注册映射，替换同一情感已有的映射 / Register a mapping, replacing any existing mapping of the same emotion
+    pub fn register(&mut self, mapping: Arc<dyn EmotionMapping>) {
+        self.mappings.insert(mapping.emotion(), mapping);
+    }
+
+    /// 分析结果对应的映射 / Mapping for an analysis result
+    fn mapping(&self, analysis: &PoemAnalysis) -> Option<&Arc<dyn EmotionMapping>> {
+        self.mappings
+            .get(&analysis.emotion_analysis.primary_emotion)
+            .or_else(|| self.mappings.get(&Emotion::Neutral))
+    }
+
+    /// 分析结果对应的风格：情感映射的风格，意象字符排在调色板前面
+    /// Style for an analysis result: the emotion mapping's style with imagery glyphs at the front of the palette
+    pub fn style_for(&self, analysis: &PoemAnalysis) -> ProgramStyle {
+        let mut style = self
+            .mapping(analysis)
+            .map(|mapping| mapping.style())
+            .unwrap_or_else(|| builtin_style(Emotion::Neutral));
+        let mut glyphs: Vec<String> = analysis
+            .imagery
+            .iter()
+            .filter_map(|image| imagery_glyph(&image.element))
+            .map(str::to_string)
+            .collect();
+        glyphs.extend(style.glyphs);
+        let mut seen = Vec::new();
+        glyphs.retain(|glyph| {
+            let new = !seen.contains(glyph);
+            seen.push(glyph.clone());
+            new
+        });
+        if glyphs.is_empty() {
+            glyphs.push("*".to_string());
+        }
+        style.glyphs = glyphs;
+        if style.scale.is_empty() {
+            style.scale = builtin_style(Emotion::Neutral).scale;
+        }
+        style.tempo = style.tempo.max(1);
+        style
+    }
+
+    /// 生成一种形式的完整程序（含前导定义）/ Generate a complete program of one form (prelude included)
+    pub fn generate(&self, analysis: &PoemAnalysis, form: ProgramForm) -> String {
+        let style = self.style_for(analysis);
+        [
+            self.header(analysis, &style),
+            self.prelude(analysis, &style),
+            self.section(analysis, &style, form),
+        ]
+        .join("\n")
+    }
+
+    /// 生成包含全部形式的程序 / Generate a program with every form
+    pub fn generate_all(&self, analysis: &PoemAnalysis) -> String {
+        let style = self.style_for(analysis);
+        let mut parts = vec![
+            self.header(analysis, &style),
+            self.prelude(analysis, &style),
+        ];
+        for form in ProgramForm::ALL {
+            parts.push(self.section(analysis, &style, form));
+        }
+        parts.join("\n")
+    }
+
+    /// 注释头：情感、主题和意象 / Comment header: emotion, themes and imagery
+    fn header(&self, analysis: &PoemAnalysis, style: &ProgramStyle) -> String {
+        let themes: Vec<&str> = analysis
+            .themes
+            .iter()
+            .map(|theme| theme.name.as_str())
+            .collect();
+        let imagery: Vec<&str> = analysis
+            .imagery
+            .iter()
+            .map(|image| image.element.as_str())
+            .collect();
+        format!(
+            "; 情感 / Emotion: {}, 运动 / Motion: {:?}, 速度 / Tempo: {}\n; 主题 / Themes: {}\n; 意象 / Imagery: {}",
+            analysis.emotion_analysis.primary_emotion,
+            style.motion,
+            style.tempo,
+            themes.join(" "),
+            imagery.join(" ")
+        )
+    }
+
+    /// 前导定义：调色板、音阶、节奏、运动轨迹和重复字符 / Prelude: palette, scale, rhythm, motion and glyph repetition
+    fn prelude(&self, analysis: &PoemAnalysis, style: &ProgramStyle) -> String {
+        let palette: Vec<String> = style
+            .glyphs
+            .iter()
+            .map(|glyph| format!("{:?}", glyph))
+            .collect();
+        let scale: Vec<String> = style.scale.iter().map(i64::to_string).collect();
+        // 节奏来自诗句字数：五字一拍 / Rhythm comes from verse lengths: five characters make one beat
+        let beat = 60_000 / i64::from(style.tempo);
+        let mut rhythm: Vec<String> = analysis
+            .verses
+            .iter()
+            .map(|verse| {
+                let characters = verse
+                    .text
+                    .chars()
+                    .filter(|c| c.is_alphanumeric())
+                    .count()
+                    .max(1) as i64;
+                (beat * characters / 5).max(1).to_string()
+            })
+            .collect();
+        if rhythm.is_empty() {
+            rhythm.push(beat.to_string());
+        }
+        [
+            format!("(let palette (list {}))", palette.join(" ")),
+            format!("(let scale (list {}))", scale.join(" ")),
+            format!("(let rhythm (list {}))", rhythm.join(" ")),
+            style.motion.definition(),
+            "(def repeat-glyph (glyph n)\n  (let line \"\"\n    (for i n (set! line (string-concat line glyph)))\n    line))"
+                .to_string(),
+        ]
+        .join("\n")
+    }
+
+    /// 一种形式的程序段：自定义映射优先 / Program section of one form: custom mappings come first
+    fn section(&self, analysis: &PoemAnalysis, style: &ProgramStyle, form: ProgramForm) -> String {
+        if let Some(code) = self
+            .mapping(analysis)
+            .and_then(|mapping| mapping.generate(form, analysis, style))
+        {
+            return code;
+        }
+        let size = self.size;
+        match form {
+            ProgramForm::AsciiArt => format!(
+                "; ASCII图案 / ASCII art\n(def ascii-art (height)\n  (for i height\n    (let width (+ 1 (motion i height))\n      (print (string-concat (repeat-glyph \" \" (- height width)) (repeat-glyph (get palette (% i (length palette))) (* 2 width)))))))\n(ascii-art {})",
+                size
+            ),
+            ProgramForm::Melody => format!(
+                "; 旋律 / Melody\n(def melody ()\n  (let t 0\n    (while true\n      (begin\n        (yield (list (get scale (motion t (length scale))) (get rhythm (% t (length rhythm)))))\n        (set! t (+ t 1))))))\n(print (take {} (melody)))",
+                size * 2
+            ),
+            ProgramForm::Animation => format!(
+                "; 动画 / Animation\n(def frames (width)\n  (let t 0\n    (while true\n      (begin\n        (yield (string-concat \"|\" (repeat-glyph \" \" (motion t width)) (get palette 0)))\n        (set! t (+ t 1))))))\n(for frame (take {} (frames {})) (print frame))",
+                size * 2,
+                size
+            ),
+        }
+    }
+}
+
+impl Default for PoetryCodegen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,97 +1,238 @@
 // 情感理解 / Emotion understanding
-// 分析和理解文本中的情感
-// Analyzes and understands emotions in text
+// 分析和理解文本中的情感；情感词典（带权重的词表、否定词、程度副词）可从文件加载，并可注册新的情感类别
+// Analyzes and understands emotions in text; the lexicon (weighted word lists, negations, intensifiers) can be
+// loaded from files, and new emotion categories can be registered
 
+use crate::runtime::symbol::Symbol;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// 内置情感词典 / Built-in emotion lexicon
+const BUILTIN_LEXICON: &str = include_str!("lexicons/zh.json");
 
 /// 情感分析器 / Emotion analyzer
 pub struct EmotionAnalyzer {
-    /// 情感词典 / Emotion dictionary
-    emotion_dict: std::collections::HashMap<String, Emotion>,
+    /// 情感词典：词 → (情感, 权重) / Emotion dictionary: word → (emotion, weight)
+    emotion_dict: HashMap<String, Vec<(Emotion, f64)>>,
+    /// 否定词 / Negation words
+    negations: Vec<String>,
+    /// 程度副词及倍数 / Intensifiers and their factors
+    intensifiers: HashMap<String, f64>,
+    /// 注册的自定义情感及说明 / Registered custom emotions and their descriptions
+    custom_emotions: BTreeMap<Emotion, String>,
+}
+
+/// 情感词典文件格式 / Emotion lexicon file format
+///
+/// ```json
+/// {
+///   "emotions": { "awe": "敬畏" },
+///   "words": { "nostalgia": { "乡愁": 1.5 }, "awe": { "壮阔": 1.0 } },
+///   "negations": ["不"],
+///   "intensifiers": { "很": 1.5 }
+/// }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmotionLexicon {
+    /// 新的情感类别及说明 / New emotion categories and their descriptions
+    pub emotions: BTreeMap<String, String>,
+    /// 各情感的带权重词表 / Weighted word list per emotion
+    pub words: BTreeMap<String, BTreeMap<String, f64>>,
+    /// 否定词 / Negation words
+    pub negations: Vec<String>,
+    /// 程度副词及倍数 / Intensifiers and their factors
+    pub intensifiers: BTreeMap<String, f64>,
 }
 
 impl EmotionAnalyzer {
-    /// 创建新情感分析器 / Create new emotion analyzer
+    /// 创建使用内置词典的情感分析器 / Create an emotion analyzer with the built-in lexicon
     pub fn new() -> Self {
-        let mut analyzer = Self {
-            emotion_dict: std::collections::HashMap::new(),
-        };
+        let mut analyzer = Self::empty();
         analyzer.initialize_emotion_dict();
         analyzer
     }
 
+    /// 创建没有任何词的情感分析器，用于完全自定义的词典 / Create an analyzer without words, for a fully custom lexicon
+    pub fn empty() -> Self {
+        Self {
+            emotion_dict: HashMap::new(),
+            negations: Vec::new(),
+            intensifiers: HashMap::new(),
+            custom_emotions: BTreeMap::new(),
+        }
+    }
+
     /// 初始化情感词典 / Initialize emotion dictionary
     fn initialize_emotion_dict(&mut self) {
-        // 初始化基础情感词汇 / Initialize basic emotion vocabulary
-
-        // 思乡 / Nostalgia - 《静夜思》核心情感
-        self.emotion_dict
-            .insert("思".to_string(), Emotion::Nostalgia);
-        self.emotion_dict
-            .insert("故乡".to_string(), Emotion::Nostalgia);
-        self.emotion_dict
-            .insert("家乡".to_string(), Emotion::Nostalgia);
-        self.emotion_dict
-            .insert("乡".to_string(), Emotion::Nostalgia);
-        self.emotion_dict
-            .insert("归".to_string(), Emotion::Nostalgia);
-
-        // 孤独 / Loneliness
-        self.emotion_dict
-            .insert("孤独".to_string(), Emotion::Loneliness);
-        self.emotion_dict
-            .insert("寂寞".to_string(), Emotion::Loneliness);
-        self.emotion_dict
-            .insert("独".to_string(), Emotion::Loneliness);
-
-        // 宁静 / Tranquility - 《静夜思》夜晚氛围
-        self.emotion_dict
-            .insert("静".to_string(), Emotion::Tranquility);
-        self.emotion_dict
-            .insert("安静".to_string(), Emotion::Tranquility);
-        self.emotion_dict
-            .insert("夜".to_string(), Emotion::Tranquility);
-
-        // 忧伤 / Melancholy
-        self.emotion_dict
-            .insert("忧伤".to_string(), Emotion::Melancholy);
-        self.emotion_dict
-            .insert("愁".to_string(), Emotion::Melancholy);
-        self.emotion_dict
-            .insert("悲".to_string(), Emotion::Melancholy);
-        self.emotion_dict
-            .insert("哀".to_string(), Emotion::Melancholy);
-
-        // 喜悦 / Joy
-        self.emotion_dict.insert("喜".to_string(), Emotion::Joy);
-        self.emotion_dict.insert("乐".to_string(), Emotion::Joy);
-        self.emotion_dict.insert("欢".to_string(), Emotion::Joy);
-
-        // 愤怒 / Anger
-        self.emotion_dict.insert("怒".to_string(), Emotion::Anger);
-        self.emotion_dict.insert("愤".to_string(), Emotion::Anger);
-
-        // 惊讶 / Surprise
-        self.emotion_dict
-            .insert("惊".to_string(), Emotion::Surprise);
-        self.emotion_dict
-            .insert("疑".to_string(), Emotion::Surprise);
-    }
-
-    /// 分析情感 / Analyze emotions
+        // 内置词典以《静夜思》的思乡与宁静为起点 / The built-in lexicon starts from the nostalgia and tranquility of 《静夜思》
+        if let Ok(lexicon) = serde_json::from_str::<EmotionLexicon>(BUILTIN_LEXICON) {
+            self.apply_lexicon(lexicon);
+        }
+    }
+
+    /// 从JSON文件加载词典，追加到已有词典；词表中未知的情感名称会注册为新情感。返回加载的词数
+    /// Load a lexicon from a JSON file, adding to the existing one; unknown emotion names in the word lists are
+    /// registered as new emotions. Returns the number of words loaded
+    pub fn load_lexicon(&mut self, path: &Path) -> Result<usize, EmotionError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| EmotionError::LexiconError(format!("{}: {}", path.display(), e)))?;
+        let lexicon: EmotionLexicon = serde_json::from_str(&json)
+            .map_err(|e| EmotionError::LexiconError(format!("{}: {}", path.display(), e)))?;
+        Ok(self.apply_lexicon(lexicon))
+    }
+
+    /// 从JSON字符串加载词典 / Load a lexicon from a JSON string
+    pub fn load_lexicon_json(&mut self, json: &str) -> Result<usize, EmotionError> {
+        let lexicon: EmotionLexicon =
+            serde_json::from_str(json).map_err(|e| EmotionError::LexiconError(e.to_string()))?;
+        Ok(self.apply_lexicon(lexicon))
+    }
+
+    /// 把词典并入分析器 / Merge a lexicon into the analyzer
+    fn apply_lexicon(&mut self, lexicon: EmotionLexicon) -> usize {
+        for (name, description) in &lexicon.emotions {
+            self.register_emotion(name, description);
+        }
+        let mut loaded = 0;
+        for (name, words) in &lexicon.words {
+            let emotion = self.emotion_named(name);
+            for (word, weight) in words {
+                self.add_word(word, emotion.clone(), *weight);
+                loaded += 1;
+            }
+        }
+        for word in &lexicon.negations {
+            self.add_negation(word);
+        }
+        for (word, factor) in &lexicon.intensifiers {
+            self.add_intensifier(word, *factor);
+        }
+        loaded
+    }
+
+    /// 按名称查找情感，未知名称注册为新情感 / Find an emotion by name, registering unknown names as new emotions
+    fn emotion_named(&mut self, name: &str) -> Emotion {
+        Emotion::from_name(name).unwrap_or_else(|| self.register_emotion(name, ""))
+    }
+
+    /// 注册新的情感类别；名称与内置情感相同时返回内置情感 / Register a new emotion category; built-in names return the built-in emotion
+    pub fn register_emotion(&mut self, name: &str, description: &str) -> Emotion {
+        if let Some(emotion) = Emotion::from_name(name) {
+            return emotion;
+        }
+        let emotion = Emotion::Custom(Symbol::intern(name));
+        let entry = self.custom_emotions.entry(emotion.clone()).or_default();
+        if !description.is_empty() {
+            *entry = description.to_string();
+        }
+        emotion
+    }
+
+    /// 已注册的自定义情感及说明 / Registered custom emotions and their descriptions
+    pub fn custom_emotions(&self) -> &BTreeMap<Emotion, String> {
+        &self.custom_emotions
+    }
+
+    /// 添加情感词；同一个词可以指向多个情感 / Add an emotion word; one word may point to several emotions
+    pub fn add_word(&mut self, word: &str, emotion: Emotion, weight: f64) {
+        let entries = self.emotion_dict.entry(word.to_string()).or_default();
+        match entries
+            .iter_mut()
+            .find(|(existing, _)| *existing == emotion)
+        {
+            Some(entry) => entry.1 = weight,
+            None => entries.push((emotion, weight)),
+        }
+    }
+
+    /// 添加否定词：紧邻情感词之前时该词不计入 / Add a negation: an emotion word right after it is not counted
+    pub fn add_negation(&mut self, word: &str) {
+        if !self.negations.iter().any(|existing| existing == word) {
+            self.negations.push(word.to_string());
+        }
+    }
+
+    /// 添加程度副词：紧邻情感词之前时权重乘以倍数 / Add an intensifier: an emotion word right after it has its weight multiplied
+    pub fn add_intensifier(&mut self, word: &str, factor: f64) {
+        self.intensifiers.insert(word.to_string(), factor);
+    }
+
+    /// 情感词前的修饰：从紧邻的位置向前剥离程度副词和否定词，返回（倍数, 是否被否定）；双重否定相互抵消
+    /// Modifiers before an emotion word: intensifiers and negations are stripped backwards from the adjacent position,
+    /// returning (factor, negated); a double negation cancels out
+    fn modifiers(&self, before: &str) -> (f64, bool) {
+        // 修饰只在同一分句内生效 / Modifiers only apply within the same clause
+        let mut prefix = before
+            .rsplit(|c: char| {
+                matches!(
+                    c,
+                    '，' | '。' | '、' | '；' | '！' | '？' | ',' | '.' | ';' | '!' | '?' | '\n'
+                )
+            })
+            .next()
+            .unwrap_or(before);
+        let mut phrases: Vec<(&str, Option<f64>)> = self
+            .intensifiers
+            .iter()
+            .map(|(word, factor)| (word.as_str(), Some(*factor)))
+            .chain(self.negations.iter().map(|word| (word.as_str(), None)))
+            .collect();
+        // 较长的短语优先，如 "非常" 先于否定词 "非" / Longer phrases first, so "非常" wins over the negation "非"
+        phrases.sort_by_key(|(word, _)| std::cmp::Reverse(word.chars().count()));
+
+        let mut factor = 1.0;
+        let mut negated = false;
+        'strip: loop {
+            prefix = prefix.trim_end();
+            for (word, modifier) in &phrases {
+                let Some(rest) = prefix.strip_suffix(word) else {
+                    continue;
+                };
+                // 英文等ASCII修饰词须整词匹配 / ASCII modifiers must match whole words
+                if word.is_ascii() && rest.chars().last().is_some_and(|c| c.is_alphanumeric()) {
+                    continue;
+                }
+                match modifier {
+                    Some(multiplier) => factor *= multiplier,
+                    None => negated = !negated,
+                }
+                prefix = rest;
+                continue 'strip;
+            }
+            break;
+        }
+        (factor, negated)
+    }
+
+    /// 分析情感：每处匹配按词的权重计分，程度副词放大权重，被否定的匹配不计
+    /// Analyze emotions: each match scores the word's weight, intensifiers scale it and negated matches are not counted
     pub fn analyze(&self, text: &str) -> Result<EmotionAnalysis, EmotionError> {
         let mut detected_emotions = Vec::new();
         let mut emotion_scores = std::collections::HashMap::new();
 
-        // 简单的关键词匹配 / Simple keyword matching
-        for (keyword, emotion) in &self.emotion_dict {
-            if text.contains(keyword) {
-                let count = text.matches(keyword).count();
-                let score = emotion_scores.entry(*emotion).or_insert(0.0);
-                *score += count as f64 * 0.3; // 每个匹配增加0.3分 / Each match adds 0.3 points
-                detected_emotions.push(*emotion);
+        // 关键词匹配 / Keyword matching
+        let mut words: Vec<&String> = self.emotion_dict.keys().collect();
+        words.sort();
+        for keyword in words {
+            for (pos, _) in text.match_indices(keyword.as_str()) {
+                if keyword.is_ascii() && !is_whole_word(text, pos, keyword.len()) {
+                    continue;
+                }
+                let (factor, negated) = self.modifiers(&text[..pos]);
+                if negated {
+                    continue;
+                }
+                for (emotion, weight) in &self.emotion_dict[keyword] {
+                    *emotion_scores.entry(emotion.clone()).or_insert(0.0) += weight * factor;
+                    if !detected_emotions.contains(emotion) {
+                        detected_emotions.push(emotion.clone());
+                    }
+                }
             }
         }
+        emotion_scores.retain(|_, score| *score > 0.0);
 
         // 归一化分数 / Normalize scores
         let total_score: f64 = emotion_scores.values().sum();
@@ -104,8 +245,12 @@ impl EmotionAnalyzer {
         // 确定主要情感 / Determine primary emotion
         let primary_emotion = emotion_scores
             .iter()
-            .max_by(|a, b| a.1.partial_cmp(b.1).unwrap())
-            .map(|(emotion, _)| *emotion)
+            .max_by(|a, b| {
+                // 分数相同时取先声明的情感，结果与哈希顺序无关
+                // Ties go to the emotion declared first, independent of hash order
+                a.1.partial_cmp(b.1).unwrap().then_with(|| b.0.cmp(a.0))
+            })
+            .map(|(emotion, _)| emotion.clone())
             .unwrap_or(Emotion::Neutral);
 
         Ok(EmotionAnalysis {
@@ -117,6 +262,13 @@ impl EmotionAnalyzer {
     }
 }
 
+/// 匹配是否为整词 / Whether the match is a whole word
+fn is_whole_word(text: &str, pos: usize, len: usize) -> bool {
+    let before = text[..pos].chars().last();
+    let after = text[pos + len..].chars().next();
+    !before.is_some_and(|c| c.is_alphanumeric()) && !after.is_some_and(|c| c.is_alphanumeric())
+}
+
 impl Default for EmotionAnalyzer {
     fn default() -> Self {
         Self::new()
@@ -124,7 +276,7 @@ impl Default for EmotionAnalyzer {
 }
 
 /// 情感类型 / Emotion type
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Emotion {
     /// 中性 / Neutral
     Neutral,
@@ -144,6 +296,52 @@ pub enum Emotion {
     Fear,
     /// 惊讶 / Surprise
     Surprise,
+    /// 注册的自定义情感 / Registered custom emotion
+    Custom(Symbol),
+}
+
+impl Emotion {
+    /// 内置情感 / Built-in emotions
+    pub const BUILTIN: [Emotion; 9] = [
+        Emotion::Neutral,
+        Emotion::Nostalgia,
+        Emotion::Loneliness,
+        Emotion::Tranquility,
+        Emotion::Melancholy,
+        Emotion::Joy,
+        Emotion::Anger,
+        Emotion::Fear,
+        Emotion::Surprise,
+    ];
+
+    /// 情感名称（词典文件中使用）/ Emotion name (as used in lexicon files)
+    pub fn name(&self) -> &str {
+        match self {
+            Emotion::Neutral => "neutral",
+            Emotion::Nostalgia => "nostalgia",
+            Emotion::Loneliness => "loneliness",
+            Emotion::Tranquility => "tranquility",
+            Emotion::Melancholy => "melancholy",
+            Emotion::Joy => "joy",
+            Emotion::Anger => "anger",
+            Emotion::Fear => "fear",
+            Emotion::Surprise => "surprise",
+            Emotion::Custom(symbol) => symbol.as_str(),
+        }
+    }
+
+    /// 按名称查找内置情感 / Find a built-in emotion by name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::BUILTIN
+            .into_iter()
+            .find(|emotion| emotion.name().eq_ignore_ascii_case(name))
+    }
+}
+
+impl std::fmt::Display for Emotion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 /// 情感分析结果 / Emotion analysis result
@@ -164,4 +362,6 @@ pub struct EmotionAnalysis {
 pub enum EmotionError {
     /// 分析错误 / Analysis error
     AnalysisError(String),
+    /// 词典无法读取或格式错误 / Lexicon unreadable or malformed
+    LexiconError(String),
 }
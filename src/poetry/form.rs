@@ -0,0 +1,442 @@
+// 诗体识别 / Poetry form detection
+// 识别近体诗的体裁（五言/七言、绝句/律诗）、押韵（按拼音韵表）和平仄合律情况
+// Detects the form of regulated verse (five/seven characters, quatrain/regulated), its rhymes (via a pinyin rhyme
+// table) and how well it follows the tonal pattern
+
+use crate::poetry::parser::Verse;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// 内置拼音表 / Built-in pinyin table
+const BUILTIN_PINYIN: &str = include_str!("lexicons/pinyin.txt");
+
+/// 平仄 / Tone class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Tone {
+    /// 平声 / Level tone
+    Ping,
+    /// 仄声 / Oblique tone
+    Ze,
+}
+
+impl Tone {
+    /// 平仄符号 / Tone symbol
+    pub fn symbol(self) -> char {
+        match self {
+            Tone::Ping => '平',
+            Tone::Ze => '仄',
+        }
+    }
+
+    /// 相反的平仄 / The opposite tone
+    pub fn opposite(self) -> Self {
+        match self {
+            Tone::Ping => Tone::Ze,
+            Tone::Ze => Tone::Ping,
+        }
+    }
+}
+
+/// 拼音表：字的读音、平仄和韵部 / Pinyin table: readings, tones and rhyme groups of characters
+pub struct PinyinTable {
+    /// 字 → 带声调的拼音 / Character → pinyin with tone number
+    readings: HashMap<char, String>,
+    /// 入声字 / Entering-tone characters
+    entering: HashSet<char>,
+}
+
+impl PinyinTable {
+    /// 内置的诗歌常用字拼音表 / Built-in table of characters common in poetry
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_PINYIN)
+    }
+
+    /// 解析拼音表：每行 "拼音声调 字…"，`ru` 行列出入声字，`#` 开头为注释
+    /// Parse a pinyin table: "syllable+tone characters…" per line, the `ru` line lists entering-tone characters and
+    /// `#` starts a comment
+    pub fn parse(text: &str) -> Self {
+        let mut table = Self {
+            readings: HashMap::new(),
+            entering: HashSet::new(),
+        };
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((syllable, characters)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            if syllable == "ru" {
+                table.entering.extend(characters.trim().chars());
+                continue;
+            }
+            for ch in characters.trim().chars() {
+                table
+                    .readings
+                    .entry(ch)
+                    .or_insert_with(|| syllable.to_string());
+            }
+        }
+        table
+    }
+
+    /// 带声调的拼音，如 `guang1` / Pinyin with tone number, e.g. `guang1`
+    pub fn pinyin(&self, ch: char) -> Option<&str> {
+        self.readings.get(&ch).map(String::as_str)
+    }
+
+    /// 平仄：一二声为平，三四声和入声为仄 / Tone class: tones 1 and 2 are level, tones 3, 4 and entering tones are oblique
+    pub fn tone(&self, ch: char) -> Option<Tone> {
+        if self.entering.contains(&ch) {
+            return Some(Tone::Ze);
+        }
+        match self.pinyin(ch)?.chars().last()? {
+            '1' | '2' => Some(Tone::Ping),
+            '3' | '4' => Some(Tone::Ze),
+            _ => None,
+        }
+    }
+
+    /// 韵部（中华新韵十四韵）/ Rhyme group (the fourteen groups of the New Chinese Rhyme)
+    pub fn rhyme(&self, ch: char) -> Option<&'static str> {
+        let pinyin = self.pinyin(ch)?;
+        rhyme_group(pinyin.trim_end_matches(|c: char| c.is_ascii_digit()))
+    }
+}
+
+impl Default for PinyinTable {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+/// 拼音音节的韵部 / Rhyme group of a pinyin syllable
+fn rhyme_group(syllable: &str) -> Option<&'static str> {
+    let initial = ["zh", "ch", "sh"]
+        .into_iter()
+        .find(|initial| syllable.starts_with(initial))
+        .map(str::len)
+        .unwrap_or_else(|| {
+            usize::from(
+                syllable
+                    .chars()
+                    .next()
+                    .is_some_and(|c| "bpmfdtnlgkhjqxrzcsyw".contains(c)),
+            )
+        });
+    let (initial, rest) = syllable.split_at(initial);
+    let final_ = match initial {
+        "zh" | "ch" | "sh" | "r" | "z" | "c" | "s" if rest == "i" => return Some("支"),
+        // j、q、x、y 后的 u 是 ü / After j, q, x and y, u is ü
+        "j" | "q" | "x" | "y" if rest.starts_with('u') => rest.replacen('u', "ü", 1),
+        "y" if rest.starts_with('i') => rest.to_string(),
+        "y" => format!("i{}", rest),
+        "w" if rest == "u" => rest.to_string(),
+        "w" => format!("u{}", rest),
+        _ => rest.to_string(),
+    };
+    Some(match final_.as_str() {
+        "a" | "ia" | "ua" => "麻",
+        "o" | "e" | "uo" => "波",
+        "ie" | "üe" => "皆",
+        "ai" | "uai" => "开",
+        "ei" | "ui" | "uei" => "微",
+        "ao" | "iao" => "豪",
+        "ou" | "iu" | "iou" => "尤",
+        "an" | "ian" | "uan" | "üan" => "寒",
+        "en" | "in" | "un" | "uen" | "ün" => "文",
+        "ang" | "iang" | "uang" => "唐",
+        "eng" | "ing" | "ong" | "iong" | "ueng" => "庚",
+        "i" | "er" | "ü" => "齐",
+        "u" => "姑",
+        _ => return None,
+    })
+}
+
+/// 诗体 / Poem form
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PoemForm {
+    /// 五言绝句 / Five-character quatrain
+    WuyanJueju,
+    /// 七言绝句 / Seven-character quatrain
+    QiyanJueju,
+    /// 五言律诗 / Five-character regulated verse
+    WuyanLushi,
+    /// 七言律诗 / Seven-character regulated verse
+    QiyanLushi,
+    /// 其他（古体、杂言、词等）/ Other (ancient style, irregular lines, ci, …)
+    Other,
+}
+
+impl PoemForm {
+    /// 诗体名称 / Form name
+    pub fn name(self) -> &'static str {
+        match self {
+            PoemForm::WuyanJueju => "五言绝句",
+            PoemForm::QiyanJueju => "七言绝句",
+            PoemForm::WuyanLushi => "五言律诗",
+            PoemForm::QiyanLushi => "七言律诗",
+            PoemForm::Other => "其他",
+        }
+    }
+
+    /// 由句数和每句字数判断诗体 / Determine the form from the number of lines and characters per line
+    fn detect(lines: &[Vec<char>]) -> Self {
+        let length = lines.first().map(Vec::len).unwrap_or(0);
+        if lines.iter().any(|line| line.len() != length) {
+            return PoemForm::Other;
+        }
+        match (lines.len(), length) {
+            (4, 5) => PoemForm::WuyanJueju,
+            (4, 7) => PoemForm::QiyanJueju,
+            (8, 5) => PoemForm::WuyanLushi,
+            (8, 7) => PoemForm::QiyanLushi,
+            _ => PoemForm::Other,
+        }
+    }
+}
+
+/// 不合平仄的字 / A character that breaks the tonal pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToneViolation {
+    /// 句号（从1开始）/ Line number (1-based)
+    pub line: usize,
+    /// 字在句中的位置（从1开始）/ Position in the line (1-based)
+    pub position: usize,
+    /// 字 / Character
+    pub character: char,
+    /// 格律要求的平仄 / Tone required by the pattern
+    pub expected: Tone,
+    /// 实际平仄 / Actual tone
+    pub actual: Tone,
+}
+
+/// 诗体分析结果 / Form analysis result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormAnalysis {
+    /// 诗体 / Form
+    pub form: PoemForm,
+    /// 按标点切分后的诗句 / Lines split at punctuation
+    pub lines: Vec<String>,
+    /// 每句句末字的韵部 / Rhyme group of each line's last character
+    pub rhymes: Vec<Option<String>>,
+    /// 韵式，如 `AABA`，未知读音记为 `?` / Rhyme scheme such as `AABA`, `?` for unknown readings
+    pub rhyme_scheme: String,
+    /// 偶数句押的韵部 / Rhyme group of the even lines
+    pub rhyme: Option<String>,
+    /// 偶数句是否同押一韵 / Whether all even lines share one rhyme
+    pub rhymes_regular: bool,
+    /// 各句实际平仄，如 `平平仄仄平`，未知读音记为 `?` / Actual tones per line such as `平平仄仄平`, `?` for unknown readings
+    pub tone_patterns: Vec<String>,
+    /// 最接近的格律名称，如 `仄起首句入韵` / Closest pattern name, e.g. `仄起首句入韵`
+    pub pattern: Option<String>,
+    /// 该格律要求的平仄 / Tones required by that pattern
+    pub expected_patterns: Vec<String>,
+    /// 关键位置（一三五不论，二四六分明，句末）合律的比例 / Share of key positions (2nd, 4th, 6th and last) that follow the pattern
+    pub tonal_compliance: Option<f64>,
+    /// 不合平仄的字 / Characters that break the pattern
+    pub violations: Vec<ToneViolation>,
+}
+
+/// 诗体分析器 / Form analyzer
+pub struct FormAnalyzer {
+    /// 拼音表 / Pinyin table
+    table: PinyinTable,
+}
+
+impl FormAnalyzer {
+    /// 创建使用内置拼音表的分析器 / Create an analyzer with the built-in pinyin table
+    pub fn new() -> Self {
+        Self::with_table(PinyinTable::builtin())
+    }
+
+    /// 使用自定义拼音表 / Use a custom pinyin table
+    pub fn with_table(table: PinyinTable) -> Self {
+        Self { table }
+    }
+
+    /// 拼音表 / Pinyin table
+    pub fn table(&self) -> &PinyinTable {
+        &self.table
+    }
+
+    /// 分析诗体、押韵和平仄 / Analyze form, rhyme and tonal pattern
+    pub fn analyze(&self, verses: &[Verse]) -> FormAnalysis {
+        let lines: Vec<Vec<char>> = verses
+            .iter()
+            .flat_map(|verse| {
+                verse
+                    .text
+                    .split(|c: char| !is_han(c))
+                    .filter(|line| !line.is_empty())
+                    .map(|line| line.chars().collect())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let form = PoemForm::detect(&lines);
+
+        let rhymes: Vec<Option<&'static str>> = lines
+            .iter()
+            .map(|line| line.last().and_then(|ch| self.table.rhyme(*ch)))
+            .collect();
+        let mut seen: Vec<&'static str> = Vec::new();
+        let rhyme_scheme = rhymes
+            .iter()
+            .map(|rhyme| match rhyme {
+                Some(group) => {
+                    let index = seen.iter().position(|g| g == group).unwrap_or_else(|| {
+                        seen.push(group);
+                        seen.len() - 1
+                    });
+                    char::from(b'A' + (index % 26) as u8)
+                }
+                None => '?',
+            })
+            .collect();
+        let even: Vec<Option<&'static str>> = rhymes.iter().skip(1).step_by(2).copied().collect();
+        let rhyme = even.first().copied().flatten();
+        let rhymes_regular = rhyme.is_some() && even.iter().all(|group| *group == rhyme);
+
+        let tones: Vec<Vec<Option<Tone>>> = lines
+            .iter()
+            .map(|line| line.iter().map(|ch| self.table.tone(*ch)).collect())
+            .collect();
+        let tone_patterns = tones
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|tone| tone.map(Tone::symbol).unwrap_or('?'))
+                    .collect()
+            })
+            .collect();
+
+        let mut analysis = FormAnalysis {
+            form,
+            lines: lines.iter().map(|line| line.iter().collect()).collect(),
+            rhymes: rhymes
+                .iter()
+                .map(|rhyme| rhyme.map(str::to_string))
+                .collect(),
+            rhyme_scheme,
+            rhyme: rhyme.map(str::to_string),
+            rhymes_regular,
+            tone_patterns,
+            pattern: None,
+            expected_patterns: Vec::new(),
+            tonal_compliance: None,
+            violations: Vec::new(),
+        };
+        if form == PoemForm::Other {
+            return analysis;
+        }
+
+        // 首句入韵时优先入韵的格律 / Prefer the rhyming-first-line patterns when the first line rhymes
+        let first_rhymes = rhymes_regular && rhymes[0] == rhyme;
+        let best = [true, false]
+            .into_iter()
+            .flat_map(|ze_start| [first_rhymes, !first_rhymes].map(|rhymed| (ze_start, rhymed)))
+            .map(|(ze_start, rhymed)| {
+                let expected = regulated_pattern(lines.len(), lines[0].len(), ze_start, rhymed);
+                let (checked, violations) = self.check(&lines, &tones, &expected);
+                (ze_start, rhymed, expected, checked, violations)
+            })
+            .min_by_key(|(_, _, _, _, violations)| violations.len());
+        if let Some((ze_start, rhymed, expected, checked, violations)) = best {
+            analysis.pattern = Some(format!(
+                "{}起首句{}",
+                if ze_start { "仄" } else { "平" },
+                if rhymed { "入韵" } else { "不入韵" }
+            ));
+            analysis.expected_patterns = expected
+                .iter()
+                .map(|line| line.iter().map(|tone| tone.symbol()).collect())
+                .collect();
+            analysis.tonal_compliance =
+                (checked > 0).then(|| (checked - violations.len()) as f64 / checked as f64);
+            analysis.violations = violations;
+        }
+        analysis
+    }
+
+    /// 对照格律检查关键位置，返回（检查的字数, 不合律的字）
+    /// Check the key positions against a pattern, returning (characters checked, violations)
+    fn check(
+        &self,
+        lines: &[Vec<char>],
+        tones: &[Vec<Option<Tone>>],
+        expected: &[Vec<Tone>],
+    ) -> (usize, Vec<ToneViolation>) {
+        let mut checked = 0;
+        let mut violations = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let length = line.len();
+            // 一三五不论，二四六分明；句末字也须合律 / Odd positions are free, even positions and the last character must follow
+            let positions =
+                (1..length).filter(|position| position % 2 == 1 || *position == length - 1);
+            for position in positions {
+                let Some(actual) = tones[index][position] else {
+                    continue;
+                };
+                checked += 1;
+                if actual != expected[index][position] {
+                    violations.push(ToneViolation {
+                        line: index + 1,
+                        position: position + 1,
+                        character: line[position],
+                        expected: expected[index][position],
+                        actual,
+                    });
+                }
+            }
+        }
+        (checked, violations)
+    }
+}
+
+impl Default for FormAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 是否为汉字 / Whether the character is a Han character
+fn is_han(c: char) -> bool {
+    matches!(c, '\u{4E00}'..='\u{9FFF}' | '\u{3400}'..='\u{4DBF}')
+}
+
+/// 近体诗格律：`ze_start` 为首句第二字是仄声，`rhymed` 为首句入韵
+/// Regulated verse pattern: `ze_start` means the first line's second character is oblique, `rhymed` that the first line rhymes
+fn regulated_pattern(lines: usize, length: usize, ze_start: bool, rhymed: bool) -> Vec<Vec<Tone>> {
+    use Tone::{Ping as P, Ze as Z};
+    // 五言四种句式 / The four five-character line types
+    let a = [Z, Z, P, P, Z];
+    let b = [P, P, Z, Z, P];
+    let c = [P, P, P, Z, Z];
+    let d = [Z, Z, Z, P, P];
+    // 七言句在五言句前加两个相反的字，第二字平仄随之相反
+    // A seven-character line puts two opposite tones before the five-character one, flipping the second character
+    let five_ze_start = if length == 7 { !ze_start } else { ze_start };
+    // 对：出句与对句相反；粘：下联出句第二字与上联对句相同
+    // Contrast within a couplet, and each couplet's opening line sticks to the previous closing line
+    let couplets = if five_ze_start {
+        [(a, b), (c, d)]
+    } else {
+        [(c, d), (a, b)]
+    };
+    (0..lines)
+        .map(|index| {
+            let (opening, closing) = couplets[(index / 2) % 2];
+            let mut five = if index % 2 == 0 { opening } else { closing };
+            if index == 0 && rhymed {
+                five = if five_ze_start { d } else { b };
+            }
+            let mut line = Vec::with_capacity(length);
+            if length == 7 {
+                line.extend([five[0].opposite(); 2]);
+            }
+            line.extend(five);
+            line
+        })
+        .collect()
+}
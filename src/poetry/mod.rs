@@ -2,8 +2,12 @@
 // 提供对诗歌和人类情感的理解能力
 // Provides understanding of poetry and human emotions
 
+pub mod codegen;
 pub mod emotion;
+pub mod form;
 pub mod parser;
 
+pub use codegen::*;
 pub use emotion::*;
+pub use form::*;
 pub use parser::*;
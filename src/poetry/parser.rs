@@ -3,12 +3,15 @@
 // Parses poetry text, extracts emotions and artistic conception
 
 use crate::poetry::emotion::{Emotion, EmotionAnalysis};
+use crate::poetry::form::{FormAnalysis, FormAnalyzer};
 use serde::{Deserialize, Serialize};
 
 /// 诗歌解析器 / Poetry parser
 pub struct PoetryParser {
     /// 情感分析器 / Emotion analyzer
     emotion_analyzer: crate::poetry::emotion::EmotionAnalyzer,
+    /// 诗体分析器 / Form analyzer
+    form_analyzer: FormAnalyzer,
 }
 
 impl PoetryParser {
@@ -16,9 +19,23 @@ impl PoetryParser {
     pub fn new() -> Self {
         Self {
             emotion_analyzer: crate::poetry::emotion::EmotionAnalyzer::new(),
+            form_analyzer: FormAnalyzer::new(),
         }
     }
 
+    /// 使用自定义情感分析器创建解析器 / Create a parser with a custom emotion analyzer
+    pub fn with_analyzer(emotion_analyzer: crate::poetry::emotion::EmotionAnalyzer) -> Self {
+        Self {
+            emotion_analyzer,
+            form_analyzer: FormAnalyzer::new(),
+        }
+    }
+
+    /// 情感分析器，可加载词典或注册情感 / Emotion analyzer, for loading lexicons or registering emotions
+    pub fn emotion_analyzer_mut(&mut self) -> &mut crate::poetry::emotion::EmotionAnalyzer {
+        &mut self.emotion_analyzer
+    }
+
     /// 解析诗歌 / Parse poetry
     pub fn parse(&self, poem: &str) -> Result<PoemAnalysis, PoetryError> {
         // 提取诗句 / Extract verses
@@ -33,11 +50,15 @@ impl PoetryParser {
         // 提取意象 / Extract imagery
         let imagery = self.extract_imagery(&verses);
 
+        // 识别诗体、押韵和平仄 / Detect form, rhyme and tonal pattern
+        let form = self.form_analyzer.analyze(&verses);
+
         Ok(PoemAnalysis {
             verses,
             emotion_analysis,
             themes,
             imagery,
+            form,
         })
     }
 
@@ -152,8 +173,12 @@ impl PoetryParser {
             })
             .collect();
 
-        // 按频率排序 / Sort by frequency
-        imagery.sort_by(|a, b| b.frequency.cmp(&a.frequency));
+        // 按频率排序，频率相同按元素排列 / Sort by frequency, ties ordered by element
+        imagery.sort_by(|a, b| {
+            b.frequency
+                .cmp(&a.frequency)
+                .then_with(|| a.element.cmp(&b.element))
+        });
 
         imagery
     }
@@ -176,6 +201,8 @@ pub struct PoemAnalysis {
     pub themes: Vec<Theme>,
     /// 意象 / Imagery
     pub imagery: Vec<Imagery>,
+    /// 诗体、押韵和平仄 / Form, rhyme and tonal pattern
+    pub form: FormAnalysis,
 }
 
 /// 诗句 / Verse
@@ -221,7 +248,8 @@ pub enum PoetryError {
 impl From<crate::poetry::emotion::EmotionError> for PoetryError {
     fn from(err: crate::poetry::emotion::EmotionError) -> Self {
         match err {
-            crate::poetry::emotion::EmotionError::AnalysisError(msg) => {
+            crate::poetry::emotion::EmotionError::AnalysisError(msg)
+            | crate::poetry::emotion::EmotionError::LexiconError(msg) => {
                 PoetryError::EmotionAnalysisError(msg)
             }
         }
@@ -0,0 +1,1177 @@
+// PyO3 Python模块导出 / PyO3 Python module exports
+// 将解析器、解释器和进化引擎暴露给Python
+// Exposes the parser, interpreter and evolution engine to Python
+
+use crate::{evolution, grammar, parser, runtime};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+
+// 异常层次：EvoError 继承 ValueError 以兼容旧代码
+// Exception hierarchy: EvoError derives from ValueError for compatibility with existing code
+mod exceptions {
+    // pyo3 0.22 的 create_exception! 会引用未声明的 gil-refs 特性
+    // pyo3 0.22's create_exception! references the undeclared gil-refs feature
+    #![allow(unexpected_cfgs)]
+
+    pyo3::create_exception!(
+        evo,
+        EvoError,
+        pyo3::exceptions::PyValueError,
+        "Evo-lang错误基类 / Base Evo-lang error"
+    );
+    pyo3::create_exception!(evo, EvoParseError, EvoError, "解析错误 / Parse error");
+    pyo3::create_exception!(evo, EvoRuntimeError, EvoError, "执行错误 / Execution error");
+    pyo3::create_exception!(
+        evo,
+        EvoNameError,
+        EvoRuntimeError,
+        "未定义变量 / Undefined variable"
+    );
+    pyo3::create_exception!(evo, EvoTypeError, EvoRuntimeError, "类型错误 / Type error");
+    pyo3::create_exception!(
+        evo,
+        EvoZeroDivisionError,
+        EvoRuntimeError,
+        "除以零 / Division by zero"
+    );
+    pyo3::create_exception!(
+        evo,
+        EvoOverflowError,
+        EvoRuntimeError,
+        "整数溢出 / Integer overflow"
+    );
+    pyo3::create_exception!(
+        evo,
+        EvoImportError,
+        EvoRuntimeError,
+        "循环导入 / Circular import"
+    );
+    pyo3::create_exception!(
+        evo,
+        EvoBudgetError,
+        EvoRuntimeError,
+        "超出执行预算 / Budget exceeded"
+    );
+    pyo3::create_exception!(
+        evo,
+        EvoRaisedError,
+        EvoRuntimeError,
+        "脚本抛出的错误 / Error raised by a script"
+    );
+}
+use exceptions::*;
+
+/// Python模块：Evo-lang解析器和解释器
+/// Python module: Evo-lang parser and interpreter
+#[pymodule]
+fn evo(_py: Python, m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<EvoInterpreter>()?;
+    m.add_class::<EvoParser>()?;
+    m.add_class::<EvoEvolution>()?;
+    m.add_class::<EvoDebugger>()?;
+    #[cfg(feature = "jupyter")]
+    m.add_class::<EvoKernel>()?;
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
+    m.add_function(wrap_pyfunction!(execute, m)?)?;
+    m.add_function(wrap_pyfunction!(eval, m)?)?;
+    let py = m.py();
+    m.add("EvoError", py.get_type_bound::<EvoError>())?;
+    m.add("EvoParseError", py.get_type_bound::<EvoParseError>())?;
+    m.add("EvoRuntimeError", py.get_type_bound::<EvoRuntimeError>())?;
+    m.add("EvoNameError", py.get_type_bound::<EvoNameError>())?;
+    m.add("EvoTypeError", py.get_type_bound::<EvoTypeError>())?;
+    m.add(
+        "EvoZeroDivisionError",
+        py.get_type_bound::<EvoZeroDivisionError>(),
+    )?;
+    m.add("EvoOverflowError", py.get_type_bound::<EvoOverflowError>())?;
+    m.add("EvoImportError", py.get_type_bound::<EvoImportError>())?;
+    m.add("EvoBudgetError", py.get_type_bound::<EvoBudgetError>())?;
+    m.add("EvoRaisedError", py.get_type_bound::<EvoRaisedError>())?;
+    Ok(())
+}
+
+/// Evo-lang解释器Python包装类
+/// Evo-lang interpreter Python wrapper class
+#[pyclass]
+pub struct EvoInterpreter {
+    interpreter: runtime::Interpreter,
+}
+
+#[pymethods]
+impl EvoInterpreter {
+    /// 创建新解释器 / Create new interpreter
+    /// allow_fs=False 禁用文件I/O内置函数，allowed_paths 限制可访问的目录，allow_modules=False 禁止导入模块文件，
+    /// allow_net=False 禁用HTTP内置函数，allowed_hosts 限制可访问的主机
+    /// allow_fs=False disables file I/O builtins, allowed_paths restricts accessible directories,
+    /// allow_modules=False forbids importing module files, allow_net=False disables HTTP builtins,
+    /// allowed_hosts restricts reachable hosts
+    #[new]
+    #[pyo3(signature = (allow_fs = true, allowed_paths = None, allow_modules = true, allow_net = true, allowed_hosts = None))]
+    fn new(
+        allow_fs: bool,
+        allowed_paths: Option<Vec<String>>,
+        allow_modules: bool,
+        allow_net: bool,
+        allowed_hosts: Option<Vec<String>>,
+    ) -> Self {
+        let options = runtime::InterpreterOptions {
+            allow_fs,
+            allowed_paths: allowed_paths
+                .unwrap_or_default()
+                .into_iter()
+                .map(std::path::PathBuf::from)
+                .collect(),
+            allow_modules,
+            allow_net,
+            allowed_hosts: allowed_hosts.unwrap_or_default(),
+            ..Default::default()
+        };
+        Self {
+            interpreter: runtime::Interpreter::with_options(options),
+        }
+    }
+
+    /// 注册Python函数，脚本中以 `py.` 前缀调用，如 `(py.fetch url)`
+    /// Register Python function, called from scripts with the `py.` prefix, e.g. `(py.fetch url)`
+    fn register_function(&mut self, name: &str, callable: PyObject) -> PyResult<()> {
+        Python::with_gil(|py| {
+            if !callable.bind(py).is_callable() {
+                return Err(PyTypeError::new_err(format!(
+                    "register_function: '{}' is not callable",
+                    name
+                )));
+            }
+            Ok(())
+        })?;
+        let function_name = name.to_string();
+        self.interpreter
+            .register_host_function(format!("py.{}", name), move |args| {
+                Python::with_gil(|py| {
+                    let py_args: Vec<PyObject> =
+                        args.iter().map(|arg| value_to_pyobject(py, arg)).collect();
+                    let result = callable
+                        .call1(py, pyo3::types::PyTuple::new_bound(py, py_args))
+                        .and_then(|result| pyobject_to_value(result.bind(py)));
+                    // Python异常以其类型名作为错误类别，脚本可用 try/catch 区分
+                    // Python exceptions use their type name as the error kind so scripts can tell them apart
+                    result.map_err(|e| {
+                        let kind = e
+                            .get_type_bound(py)
+                            .name()
+                            .map(|name| name.to_string())
+                            .unwrap_or_else(|_| "PythonError".to_string());
+                        runtime::InterpreterError::raised(
+                            kind,
+                            format!("Python function '{}' failed: {}", function_name, e),
+                            runtime::interpreter::Value::Null,
+                            None,
+                        )
+                    })
+                })
+            });
+        Ok(())
+    }
+
+    /// 执行Evo-lang代码 / Execute Evo-lang code
+    fn execute(&mut self, code: &str) -> PyResult<String> {
+        let parser = parser::AdaptiveParser::new(true);
+        match parser.parse(code) {
+            Ok(ast) => match self.interpreter.execute(&ast) {
+                Ok(value) => Ok(value.to_string()),
+                Err(e) => Err(reported_execution_error(&e, &self.interpreter, code)),
+            },
+            Err(e) => Err(parse_error(&e)),
+        }
+    }
+
+    /// 设置执行资源预算（None表示不限制）/ Set execution resource budget (None means unlimited)
+    #[pyo3(signature = (max_steps = None, max_collection_size = None, timeout_ms = None))]
+    fn set_budget(
+        &mut self,
+        max_steps: Option<u64>,
+        max_collection_size: Option<usize>,
+        timeout_ms: Option<u64>,
+    ) {
+        self.interpreter.set_budget(runtime::ExecutionBudget {
+            max_steps,
+            max_collection_size,
+            timeout: timeout_ms.map(std::time::Duration::from_millis),
+            ..Default::default()
+        });
+    }
+
+    /// 执行代码并捕获输出，返回 (结果字符串, 输出) / Execute code capturing output, returning (result string, output)
+    fn execute_capture(&mut self, code: &str) -> PyResult<(String, String)> {
+        let buffer = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let previous = self.interpreter.output_sink();
+        self.interpreter.set_output(CaptureBuffer(buffer.clone()));
+        let result = self.execute(code);
+        self.interpreter.set_output_sink(previous);
+        let output = String::from_utf8_lossy(&buffer.lock().unwrap()).to_string();
+        Ok((result?, output))
+    }
+
+    /// 执行代码并返回结果值 / Execute code and return result value
+    fn eval(&mut self, code: &str) -> PyResult<PyObject> {
+        let parser = parser::AdaptiveParser::new(true);
+        match parser.parse(code) {
+            Ok(ast) => match self.interpreter.execute(&ast) {
+                Ok(value) => Python::with_gil(|py| Ok(value_to_pyobject(py, &value))),
+                Err(e) => Err(reported_execution_error(&e, &self.interpreter, code)),
+            },
+            Err(e) => Err(parse_error(&e)),
+        }
+    }
+
+    /// 保存解释器状态到文件 / Save interpreter state to file
+    fn save_state(&self, path: &str) -> PyResult<()> {
+        self.interpreter
+            .save_state(path)
+            .map_err(|e| PyValueError::new_err(format!("Save state error: {}", e)))
+    }
+
+    /// 从文件加载解释器状态 / Load interpreter state from file
+    fn load_state(&mut self, path: &str) -> PyResult<()> {
+        self.interpreter
+            .load_state(path)
+            .map_err(|e| PyValueError::new_err(format!("Load state error: {}", e)))
+    }
+}
+
+/// 捕获输出的共享缓冲区 / Shared buffer capturing output
+struct CaptureBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for CaptureBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Evo-lang解析器Python包装类
+/// Evo-lang parser Python wrapper class
+#[pyclass]
+pub struct EvoParser {
+    parser: parser::AdaptiveParser,
+    nlu: parser::NLUParser,
+}
+
+#[pymethods]
+impl EvoParser {
+    /// 创建新解析器 / Create new parser
+    #[new]
+    fn new(enable_nlu: bool) -> Self {
+        Self {
+            parser: parser::AdaptiveParser::new(enable_nlu),
+            nlu: parser::NLUParser::new_rule_based(),
+        }
+    }
+
+    /// 解析Evo-lang代码 / Parse Evo-lang code
+    fn parse(&self, code: &str) -> PyResult<PyObject> {
+        match self.parser.parse(code) {
+            Ok(ast) => Python::with_gil(|py| Ok(ast_to_pyobject(py, &ast))),
+            Err(e) => Err(parse_error(&e)),
+        }
+    }
+
+    /// 识别自然语言意图。识别成功时 `status` 为 "parsed"，并带有 type、action、entities、parameters、confidence、
+    /// code（Evo源码）和 ast；置信度过低时 `status` 为 "needs_clarification"，带有 question 和 candidates
+    /// Recognize a natural-language intent. On success `status` is "parsed", with type, action, entities, parameters,
+    /// confidence, code (Evo source) and ast; when the confidence is too low `status` is "needs_clarification", with
+    /// question and candidates
+    fn parse_intent(&self, text: &str) -> PyResult<PyObject> {
+        let outcome = self
+            .nlu
+            .parse(text)
+            .map_err(|e| EvoParseError::new_err(format!("NLU error: {:?}", e)))?;
+        Python::with_gil(|py| {
+            let result = match &outcome {
+                parser::ParseOutcome::Parsed(intent) => {
+                    let result = intent_to_pydict(py, text, intent);
+                    result.set_item("status", "parsed")?;
+                    result
+                }
+                parser::ParseOutcome::NeedsClarification {
+                    question,
+                    candidates,
+                } => {
+                    let result = pyo3::types::PyDict::new_bound(py);
+                    result.set_item("status", "needs_clarification")?;
+                    result.set_item("question", question)?;
+                    let py_candidates = pyo3::types::PyList::empty_bound(py);
+                    for candidate in candidates {
+                        py_candidates.append(intent_to_pydict(py, text, candidate))?;
+                    }
+                    result.set_item("candidates", py_candidates)?;
+                    result
+                }
+            };
+            Ok(result.into())
+        })
+    }
+
+    /// 结构化解释代码，返回每个顶层形式的解释树（kind、span、gloss、intent、children）
+    /// Explain code structurally, returning an explanation tree (kind, span, gloss, intent, children) per top-level form
+    #[pyo3(signature = (code, language = "zh"))]
+    fn explain(&self, code: &str, language: &str) -> PyResult<PyObject> {
+        let language = match language {
+            "en" | "english" => parser::Language::English,
+            _ => parser::Language::Chinese,
+        };
+        let document = self
+            .parser
+            .parse_document(code)
+            .map_err(|e| parse_error(&e))?;
+        serializable_to_pyobject(
+            &parser::CodeExplainer::new(language).explain_document(&document, None),
+        )
+    }
+}
+
+/// Evo-lang进化引擎Python包装类
+/// Evo-lang evolution engine Python wrapper class
+#[pyclass]
+pub struct EvoEvolution {
+    engine: evolution::EvolutionEngine,
+}
+
+#[pymethods]
+impl EvoEvolution {
+    /// 创建新进化引擎 / Create new evolution engine
+    #[new]
+    fn new() -> Self {
+        Self {
+            engine: evolution::EvolutionEngine::new(),
+        }
+    }
+
+    /// 从自然语言进化，返回新语法规则列表 / Evolve from natural language, returning the new grammar rules
+    fn evolve_from_natural_language(&mut self, text: &str) -> PyResult<PyObject> {
+        let rules = self
+            .engine
+            .evolve_from_natural_language(text)
+            .map_err(|e| PyValueError::new_err(format!("Evolution error: {:?}", e)))?;
+        serializable_to_pyobject(&rules)
+    }
+
+    /// 分析代码，返回复杂度、模式、建议和统计 / Analyze code, returning complexity, patterns, suggestions and statistics
+    fn analyze_code(&self, code: &str) -> PyResult<PyObject> {
+        let parser = parser::AdaptiveParser::new(true);
+        let ast = parser.parse(code).map_err(|e| parse_error(&e))?;
+        serializable_to_pyobject(&self.engine.analyze_code(&ast))
+    }
+
+    /// 自我反思，返回进化状态报告 / Self-reflect, returning an evolution status report
+    fn self_reflect(&self) -> PyObject {
+        Python::with_gil(|py| json_to_pyobject(py, &self.engine.self_reflect()))
+    }
+
+    /// 获取进化历史 / Get evolution history
+    fn get_history(&self) -> PyResult<PyObject> {
+        serializable_to_pyobject(self.engine.get_history())
+    }
+
+    /// 回滚到指定事件（事件ID为UUID字符串）/ Roll back to the given event (event ID as UUID string)
+    fn rollback_to_event(&mut self, event_id: &str) -> PyResult<()> {
+        let event_id = uuid::Uuid::parse_str(event_id)
+            .map_err(|e| PyValueError::new_err(format!("Invalid event ID: {}", e)))?;
+        self.engine
+            .rollback_to_event(event_id)
+            .map_err(|e| PyValueError::new_err(format!("Rollback error: {:?}", e)))
+    }
+}
+
+/// Evo-lang调试器Python包装类，代码在后台线程执行，等待期间释放GIL
+/// Evo-lang debugger Python wrapper class; code runs on a background thread and the GIL is released while waiting
+#[pyclass]
+pub struct EvoDebugger {
+    debugger: runtime::DebugInterpreter,
+}
+
+#[pymethods]
+impl EvoDebugger {
+    /// 创建新调试器 / Create new debugger
+    #[new]
+    #[pyo3(signature = (stop_on_entry = false))]
+    fn new(stop_on_entry: bool) -> Self {
+        let mut debugger = runtime::DebugInterpreter::new();
+        debugger.set_stop_on_entry(stop_on_entry);
+        Self { debugger }
+    }
+
+    /// 添加函数或行断点 / Add a function or line breakpoint
+    #[pyo3(signature = (function = None, line = None))]
+    fn add_breakpoint(&mut self, function: Option<String>, line: Option<usize>) -> PyResult<()> {
+        for breakpoint in breakpoints(function, line)? {
+            self.debugger.add_breakpoint(breakpoint);
+        }
+        Ok(())
+    }
+
+    /// 移除函数或行断点 / Remove a function or line breakpoint
+    #[pyo3(signature = (function = None, line = None))]
+    fn remove_breakpoint(
+        &mut self,
+        function: Option<String>,
+        line: Option<usize>,
+    ) -> PyResult<bool> {
+        let mut removed = false;
+        for breakpoint in breakpoints(function, line)? {
+            removed |= self.debugger.remove_breakpoint(&breakpoint);
+        }
+        Ok(removed)
+    }
+
+    /// 开始执行代码，返回状态字典 / Start executing code, returning a status dict
+    fn start(&mut self, py: Python, code: &str) -> PyResult<PyObject> {
+        let debugger = &mut self.debugger;
+        let status = py
+            .allow_threads(|| debugger.start(code))
+            .map_err(|e| parse_error(&e))?;
+        debug_status_to_pyobject(py, status)
+    }
+
+    /// 单步执行，进入函数调用 / Step, entering function calls
+    fn step(&mut self, py: Python) -> PyResult<PyObject> {
+        let debugger = &mut self.debugger;
+        let status = py.allow_threads(|| debugger.step_into());
+        debug_status_to_pyobject(py, status.map_err(|e| execution_error(&e))?)
+    }
+
+    /// 单步执行，不进入函数调用 / Step without entering function calls
+    fn step_over(&mut self, py: Python) -> PyResult<PyObject> {
+        let debugger = &mut self.debugger;
+        let status = py.allow_threads(|| debugger.step_over());
+        debug_status_to_pyobject(py, status.map_err(|e| execution_error(&e))?)
+    }
+
+    /// 运行到当前函数返回 / Run until the current function returns
+    fn step_out(&mut self, py: Python) -> PyResult<PyObject> {
+        let debugger = &mut self.debugger;
+        let status = py.allow_threads(|| debugger.step_out());
+        debug_status_to_pyobject(py, status.map_err(|e| execution_error(&e))?)
+    }
+
+    /// 继续运行到下一个断点 / Continue to the next breakpoint
+    fn resume(&mut self, py: Python) -> PyResult<PyObject> {
+        let debugger = &mut self.debugger;
+        let status = py.allow_threads(|| debugger.resume());
+        debug_status_to_pyobject(py, status.map_err(|e| execution_error(&e))?)
+    }
+
+    /// 读取变量（未定义或未暂停时为 None）/ Read a variable (None when undefined or not paused)
+    fn variable(&mut self, py: Python, name: &str) -> PyObject {
+        match self.debugger.variable(name) {
+            Some(value) => value_to_pyobject(py, &value),
+            None => py.None(),
+        }
+    }
+
+    /// 修改变量 / Modify a variable
+    fn set_variable(&mut self, name: &str, value: &Bound<PyAny>) -> PyResult<()> {
+        let value = pyobject_to_value(value)?;
+        self.debugger
+            .set_variable(name, value)
+            .map_err(|e| execution_error(&e))
+    }
+
+    /// 当前可见的全部变量（字典）/ All currently visible variables (dict)
+    fn variables(&mut self, py: Python) -> PyResult<PyObject> {
+        let dict = pyo3::types::PyDict::new_bound(py);
+        for (name, value) in self.debugger.variables() {
+            dict.set_item(name, value_to_pyobject(py, &value))?;
+        }
+        Ok(dict.into())
+    }
+
+    /// 在暂停位置求值代码 / Evaluate code at the pause point
+    fn evaluate(&mut self, py: Python, code: &str) -> PyResult<PyObject> {
+        let debugger = &mut self.debugger;
+        let value = py
+            .allow_threads(|| debugger.evaluate(code))
+            .map_err(|e| execution_error(&e))?;
+        Ok(value_to_pyobject(py, &value))
+    }
+
+    /// 停止当前会话 / Stop the current session
+    fn stop(&mut self, py: Python) {
+        let debugger = &mut self.debugger;
+        py.allow_threads(|| debugger.stop());
+    }
+}
+
+/// 笔记本内核Python包装类，供 `python/evo_kernel.py` 的 Jupyter 包装内核使用（jupyter特性）
+/// Notebook kernel Python wrapper class, used by the Jupyter wrapper kernel in `python/evo_kernel.py` (jupyter feature)
+#[cfg(feature = "jupyter")]
+#[pyclass]
+pub struct EvoKernel {
+    kernel: crate::kernel::NotebookKernel,
+}
+
+#[cfg(feature = "jupyter")]
+#[pymethods]
+impl EvoKernel {
+    /// 创建内核，language 为 "zh" 或 "en" / Create a kernel; language is "zh" or "en"
+    #[new]
+    #[pyo3(signature = (language = "zh", explain = true))]
+    fn new(language: &str, explain: bool) -> Self {
+        let language = match language {
+            "en" | "english" => parser::Language::English,
+            _ => parser::Language::Chinese,
+        };
+        let mut kernel = crate::kernel::NotebookKernel::new(language);
+        kernel.set_explain(explain);
+        Self { kernel }
+    }
+
+    /// 执行单元格，返回 {execution_count, stdout, data, explanation, error} 字典；状态在调用之间保留
+    /// Execute a cell, returning an {execution_count, stdout, data, explanation, error} dict; state persists
+    /// between calls
+    fn execute(&mut self, py: Python, code: &str) -> PyResult<PyObject> {
+        let cell = self.kernel.execute(code);
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("execution_count", cell.execution_count)?;
+        dict.set_item("stdout", cell.stdout)?;
+        let data = pyo3::types::PyDict::new_bound(py);
+        for (mime, text) in cell.data {
+            data.set_item(mime, text)?;
+        }
+        dict.set_item("data", data)?;
+        dict.set_item("explanation", cell.explanation)?;
+        let error = match cell.error {
+            Some(error) => {
+                let error_dict = pyo3::types::PyDict::new_bound(py);
+                error_dict.set_item("ename", error.ename)?;
+                error_dict.set_item("evalue", error.evalue)?;
+                error_dict.into_py(py)
+            }
+            None => py.None(),
+        };
+        dict.set_item("error", error)?;
+        Ok(dict.into())
+    }
+
+    /// 代码是否完整："complete"、"incomplete" 或 "invalid" / Whether code is complete: "complete", "incomplete" or "invalid"
+    fn is_complete(&self, code: &str) -> &'static str {
+        self.kernel.is_complete(code)
+    }
+}
+
+/// 由关键字参数构建断点 / Build breakpoints from keyword arguments
+fn breakpoints(
+    function: Option<String>,
+    line: Option<usize>,
+) -> PyResult<Vec<runtime::Breakpoint>> {
+    let breakpoints: Vec<runtime::Breakpoint> = function
+        .map(runtime::Breakpoint::Function)
+        .into_iter()
+        .chain(line.map(runtime::Breakpoint::Line))
+        .collect();
+    if breakpoints.is_empty() {
+        return Err(PyValueError::new_err(
+            "Breakpoint requires a function name or a line",
+        ));
+    }
+    Ok(breakpoints)
+}
+
+/// 将调试状态转换为字典：暂停时含 `event`、`depth`、`stack`、`line`、`reason`，结束时含 `value`，出错时抛出异常
+/// Convert a debug status to a dict: `event`, `depth`, `stack`, `line` and `reason` when paused, `value` when finished; errors are raised
+fn debug_status_to_pyobject(py: Python, status: runtime::DebugStatus) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    match status {
+        runtime::DebugStatus::Paused(pause) => {
+            dict.set_item("status", "paused")?;
+            let (event, function) = match &pause.event {
+                runtime::DebugEvent::Form { .. } => ("form", None),
+                runtime::DebugEvent::Call { name } => ("call", Some(name.clone())),
+                runtime::DebugEvent::Statement { .. } => ("statement", None),
+                runtime::DebugEvent::Probe { .. } => ("line", None),
+            };
+            dict.set_item("event", event)?;
+            dict.set_item("function", function)?;
+            dict.set_item("depth", pause.depth)?;
+            dict.set_item("stack", pause.stack)?;
+            dict.set_item("line", pause.line)?;
+            let reason = match pause.reason {
+                runtime::PauseReason::Entry => "entry",
+                runtime::PauseReason::Breakpoint => "breakpoint",
+                runtime::PauseReason::Step => "step",
+            };
+            dict.set_item("reason", reason)?;
+        }
+        runtime::DebugStatus::Finished(result) => {
+            let value = result.map_err(|e| execution_error(&e))?;
+            dict.set_item("status", "finished")?;
+            dict.set_item("value", value_to_pyobject(py, &value))?;
+        }
+    }
+    Ok(dict.into())
+}
+
+/// 解析Evo-lang代码并返回AST（Python字典格式）
+/// Parse Evo-lang code and return AST (as Python dict)
+#[pyfunction]
+fn parse(code: &str) -> PyResult<PyObject> {
+    let parser = parser::AdaptiveParser::new(true);
+    match parser.parse(code) {
+        Ok(ast) => Python::with_gil(|py| Ok(ast_to_pyobject(py, &ast))),
+        Err(e) => Err(parse_error(&e)),
+    }
+}
+
+/// 执行Evo-lang代码并返回结果字符串
+/// Execute Evo-lang code and return result string
+#[pyfunction]
+fn execute(code: &str) -> PyResult<String> {
+    let parser = parser::AdaptiveParser::new(true);
+    let mut interpreter = runtime::Interpreter::new();
+    match parser.parse(code) {
+        Ok(ast) => match interpreter.execute(&ast) {
+            Ok(value) => Ok(value.to_string()),
+            Err(e) => Err(reported_execution_error(&e, &interpreter, code)),
+        },
+        Err(e) => Err(parse_error(&e)),
+    }
+}
+
+/// 执行Evo-lang代码并返回Python对象
+/// Execute Evo-lang code and return Python object
+#[pyfunction]
+fn eval(code: &str) -> PyResult<PyObject> {
+    let parser = parser::AdaptiveParser::new(true);
+    let mut interpreter = runtime::Interpreter::new();
+    match parser.parse(code) {
+        Ok(ast) => match interpreter.execute(&ast) {
+            Ok(value) => Python::with_gil(|py| Ok(value_to_pyobject(py, &value))),
+            Err(e) => Err(reported_execution_error(&e, &interpreter, code)),
+        },
+        Err(e) => Err(parse_error(&e)),
+    }
+}
+
+/// 将解析错误转换为Python异常 / Convert parse error to Python exception
+fn parse_error(error: &parser::ParseError) -> PyErr {
+    let exception = EvoParseError::new_err(format!("Parse error: {}", error));
+    Python::with_gil(|py| {
+        let _ = exception.value_bound(py).setattr("kind", "ParseError");
+    });
+    exception
+}
+
+/// 将解释器错误转换为对应类型的Python异常，附带 `kind` 和 `data` 属性
+/// Convert interpreter error to the matching typed Python exception, with `kind` and `data` attributes
+fn execution_error(error: &runtime::InterpreterError) -> PyErr {
+    typed_execution_error(error, format!("Execution error: {}", error))
+}
+
+/// 将执行 `code` 时的错误转换为Python异常，消息为带源码行、调用栈和建议的错误报告
+/// Convert an error raised while executing `code` to a Python exception whose message is the error report
+/// with the source line, call stack and suggestion
+fn reported_execution_error(
+    error: &runtime::InterpreterError,
+    interpreter: &runtime::Interpreter,
+    code: &str,
+) -> PyErr {
+    let document = parser::AdaptiveParser::new(true).parse_document(code).ok();
+    let mut report = evolution::ErrorReport::new(error, interpreter.error_trace());
+    if let Some(document) = &document {
+        report = report.with_document(document);
+    }
+    typed_execution_error(error, report.render().trim_end().to_string())
+}
+
+/// 按错误类别创建Python异常并设置 `kind` 和 `data` 属性
+/// Create the Python exception for the error kind and set its `kind` and `data` attributes
+fn typed_execution_error(error: &runtime::InterpreterError, message: String) -> PyErr {
+    use runtime::InterpreterError as E;
+    let exception = match error {
+        E::UndefinedVariable { .. } => EvoNameError::new_err(message),
+        E::TypeError { .. } => EvoTypeError::new_err(message),
+        E::DivisionByZero { .. } => EvoZeroDivisionError::new_err(message),
+        E::IntegerOverflow { .. } => EvoOverflowError::new_err(message),
+        E::CircularImport { .. } => EvoImportError::new_err(message),
+        E::BudgetExceeded { .. } => EvoBudgetError::new_err(message),
+        E::Raised { .. } => EvoRaisedError::new_err(message),
+        E::NotImplemented | E::RuntimeError { .. } => EvoRuntimeError::new_err(message),
+    };
+    Python::with_gil(|py| {
+        let value = exception.value_bound(py);
+        let data = match error.to_value() {
+            runtime::interpreter::Value::Error { data, .. } => value_to_pyobject(py, &data),
+            _ => py.None(),
+        };
+        // 设置属性失败时仍返回异常本身 / Still return the exception if setting attributes fails
+        let _ = value.setattr("kind", error.kind());
+        let _ = value.setattr("data", data);
+    });
+    exception
+}
+
+/// 将Evo-lang Value转换为Python对象
+/// Convert Evo-lang Value to Python object
+fn value_to_pyobject(py: Python, value: &runtime::interpreter::Value) -> PyObject {
+    match value {
+        runtime::interpreter::Value::Int(i) => i.to_object(py),
+        runtime::interpreter::Value::BigInt(n) => n.to_object(py),
+        runtime::interpreter::Value::Float(f) => f.to_object(py),
+        runtime::interpreter::Value::String(s) => s.to_object(py),
+        runtime::interpreter::Value::Bool(b) => b.to_object(py),
+        runtime::interpreter::Value::Null => py.None(),
+        runtime::interpreter::Value::Lambda { params, .. } => {
+            format!("<lambda({})>", params.join(", ")).to_object(py)
+        }
+        runtime::interpreter::Value::Generator { .. } => "<generator>".to_object(py),
+        runtime::interpreter::Value::List(list) => {
+            let py_list = pyo3::types::PyList::empty_bound(py);
+            for item in list.iter() {
+                py_list.append(value_to_pyobject(py, item)).unwrap();
+            }
+            py_list.into()
+        }
+        runtime::interpreter::Value::Dict(dict) => {
+            let py_dict = pyo3::types::PyDict::new_bound(py);
+            for (key, val) in dict.iter() {
+                py_dict.set_item(key, value_to_pyobject(py, val)).unwrap();
+            }
+            py_dict.into()
+        }
+        // 集合转换为 Python set，含不可哈希元素（如列表）时转换为列表
+        // Sets become Python sets, or lists when an element (such as a list) is unhashable in Python
+        runtime::interpreter::Value::Set(set) => {
+            let items: Vec<PyObject> = set.iter().map(|item| value_to_pyobject(py, item)).collect();
+            match pyo3::types::PySet::new_bound(py, &items) {
+                Ok(py_set) => py_set.into(),
+                Err(_) => pyo3::types::PyList::new_bound(py, items).into(),
+            }
+        }
+        runtime::interpreter::Value::Variant { tag, fields } => {
+            // 变体转换为 {"tag": ..., "fields": [...]} / Variants become {"tag": ..., "fields": [...]}
+            let py_dict = pyo3::types::PyDict::new_bound(py);
+            let py_fields = pyo3::types::PyList::empty_bound(py);
+            for field in fields {
+                py_fields.append(value_to_pyobject(py, field)).unwrap();
+            }
+            py_dict.set_item("tag", tag).unwrap();
+            py_dict.set_item("fields", py_fields).unwrap();
+            py_dict.into()
+        }
+        // 日期转换为 ISO 8601 字符串 / Dates become ISO 8601 strings
+        runtime::interpreter::Value::DateTime(date) => date.to_rfc3339().into_py(py),
+        runtime::interpreter::Value::Error {
+            kind,
+            message,
+            data,
+        } => {
+            // 错误对象转换为 {"kind", "message", "data"} 字典 / Error objects become {"kind", "message", "data"} dicts
+            let py_dict = pyo3::types::PyDict::new_bound(py);
+            py_dict.set_item("kind", kind).unwrap();
+            py_dict.set_item("message", message).unwrap();
+            py_dict
+                .set_item("data", value_to_pyobject(py, data))
+                .unwrap();
+            py_dict.into()
+        }
+    }
+}
+
+/// 将解析后的意图转换为Python字典 / Convert a parsed intent to a Python dict
+fn intent_to_pydict<'py>(
+    py: Python<'py>,
+    input: &str,
+    intent: &parser::ParsedIntent,
+) -> Bound<'py, pyo3::types::PyDict> {
+    let programming = parser::NLUParser::intent_from_structure(input, intent);
+    let py_dict = pyo3::types::PyDict::new_bound(py);
+    py_dict.set_item("type", intent.intent_type.name()).unwrap();
+    py_dict.set_item("action", programming.action).unwrap();
+    py_dict.set_item("entities", programming.entities).unwrap();
+    py_dict
+        .set_item("parameters", programming.parameters)
+        .unwrap();
+    py_dict.set_item("confidence", intent.confidence).unwrap();
+    py_dict
+        .set_item("code", grammar::unparse::unparse(&intent.code_structure))
+        .unwrap();
+    py_dict
+        .set_item("ast", ast_to_pyobject(py, &intent.code_structure))
+        .unwrap();
+    py_dict
+        .set_item("suggested_rules", &intent.suggested_rules)
+        .unwrap();
+    py_dict
+}
+
+/// 将可序列化的值转换为Python对象（经由JSON）/ Convert serializable value to Python object (via JSON)
+fn serializable_to_pyobject<T: serde::Serialize + ?Sized>(value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_value(value)
+        .map_err(|e| PyValueError::new_err(format!("Serialization error: {}", e)))?;
+    Ok(Python::with_gil(|py| json_to_pyobject(py, &json)))
+}
+
+/// 将JSON值转换为Python原生对象 / Convert JSON value to native Python object
+fn json_to_pyobject(py: Python, json: &serde_json::Value) -> PyObject {
+    match json {
+        serde_json::Value::Null => py.None(),
+        serde_json::Value::Bool(b) => b.to_object(py),
+        serde_json::Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.to_object(py)
+            } else if let Some(u) = n.as_u64() {
+                u.to_object(py)
+            } else {
+                n.as_f64().unwrap_or(f64::NAN).to_object(py)
+            }
+        }
+        serde_json::Value::String(s) => s.to_object(py),
+        serde_json::Value::Array(items) => {
+            let py_list = pyo3::types::PyList::empty_bound(py);
+            for item in items {
+                py_list.append(json_to_pyobject(py, item)).unwrap();
+            }
+            py_list.into()
+        }
+        serde_json::Value::Object(map) => {
+            let py_dict = pyo3::types::PyDict::new_bound(py);
+            for (key, value) in map {
+                py_dict.set_item(key, json_to_pyobject(py, value)).unwrap();
+            }
+            py_dict.into()
+        }
+    }
+}
+
+/// 将Python对象转换为Evo-lang Value
+/// Convert Python object to Evo-lang Value
+pub fn pyobject_to_value(obj: &Bound<PyAny>) -> PyResult<runtime::interpreter::Value> {
+    use runtime::interpreter::Value;
+    // bool 是 int 的子类，必须先检查 / bool is a subclass of int, so check it first
+    if let Ok(b) = obj.downcast::<pyo3::types::PyBool>() {
+        return Ok(Value::Bool(b.is_true()));
+    }
+    if obj.is_none() {
+        return Ok(Value::Null);
+    }
+    if obj.is_instance_of::<pyo3::types::PyInt>() {
+        if let Ok(i) = obj.extract::<i64>() {
+            return Ok(Value::Int(i));
+        }
+        return Ok(Value::from_bigint(obj.extract::<num_bigint::BigInt>()?));
+    }
+    if let Ok(f) = obj.downcast::<pyo3::types::PyFloat>() {
+        return Ok(Value::Float(f.value()));
+    }
+    if let Ok(s) = obj.downcast::<pyo3::types::PyString>() {
+        return Ok(Value::String(s.to_str()?.into()));
+    }
+    if let Ok(list) = obj.downcast::<pyo3::types::PyList>() {
+        return list
+            .iter()
+            .map(|item| pyobject_to_value(&item))
+            .collect::<PyResult<Vec<_>>>()
+            .map(|items| Value::List(items.into()));
+    }
+    if let Ok(tuple) = obj.downcast::<pyo3::types::PyTuple>() {
+        return tuple
+            .iter()
+            .map(|item| pyobject_to_value(&item))
+            .collect::<PyResult<Vec<_>>>()
+            .map(|items| Value::List(items.into()));
+    }
+    if let Ok(dict) = obj.downcast::<pyo3::types::PyDict>() {
+        let mut map = runtime::interpreter::DictMap::new();
+        for (key, value) in dict.iter() {
+            // 非字符串键使用其字符串形式 / Non-string keys use their string form
+            let key = match key.downcast::<pyo3::types::PyString>() {
+                Ok(s) => s.to_str()?.to_string(),
+                Err(_) => key.str()?.to_str()?.to_string(),
+            };
+            map.insert(key, pyobject_to_value(&value)?);
+        }
+        return Ok(Value::Dict(map.into()));
+    }
+    Err(PyTypeError::new_err(format!(
+        "Cannot convert Python object of type '{}' to Evo-lang value",
+        obj.get_type().name()?
+    )))
+}
+
+/// 将AST转换为Python对象：每个节点为带 `type` 标签的字典
+/// Convert AST to Python object: each node is a dict with a `type` tag
+fn ast_to_pyobject(py: Python, ast: &[grammar::core::GrammarElement]) -> PyObject {
+    let py_list = pyo3::types::PyList::empty_bound(py);
+    for element in ast {
+        py_list.append(element_to_pyobject(py, element)).unwrap();
+    }
+    py_list.into()
+}
+
+/// 创建带类型标签的Python字典 / Create a Python dict with a type tag
+fn tagged_dict<'py>(py: Python<'py>, tag: &str) -> Bound<'py, pyo3::types::PyDict> {
+    let py_dict = pyo3::types::PyDict::new_bound(py);
+    py_dict.set_item("type", tag).unwrap();
+    py_dict
+}
+
+/// 将表达式列表转换为Python列表 / Convert expression list to Python list
+fn exprs_to_pyobject(py: Python, exprs: &[grammar::core::Expr]) -> PyObject {
+    let py_list = pyo3::types::PyList::empty_bound(py);
+    for expr in exprs {
+        py_list.append(expr_to_pyobject(py, expr)).unwrap();
+    }
+    py_list.into()
+}
+
+/// 将语法元素转换为Python对象 / Convert grammar element to Python object
+fn element_to_pyobject(py: Python, element: &grammar::core::GrammarElement) -> PyObject {
+    use grammar::core::GrammarElement;
+    match element {
+        GrammarElement::Atom(atom) => {
+            let node = tagged_dict(py, "atom");
+            node.set_item("value", atom).unwrap();
+            node.into()
+        }
+        GrammarElement::List(items) => {
+            let node = tagged_dict(py, "list");
+            let py_items = pyo3::types::PyList::empty_bound(py);
+            for item in items {
+                py_items.append(element_to_pyobject(py, item)).unwrap();
+            }
+            node.set_item("items", py_items).unwrap();
+            node.into()
+        }
+        GrammarElement::NaturalLang(text) => {
+            let node = tagged_dict(py, "natural_lang");
+            node.set_item("text", text).unwrap();
+            node.into()
+        }
+        GrammarElement::Expr(expr) => expr_to_pyobject(py, expr),
+    }
+}
+
+/// 将表达式转换为Python对象 / Convert expression to Python object
+fn expr_to_pyobject(py: Python, expr: &grammar::core::Expr) -> PyObject {
+    use grammar::core::Expr;
+    let node = match expr {
+        Expr::Literal(literal) => return literal_to_pyobject(py, literal),
+        Expr::Var(name) => {
+            let node = tagged_dict(py, "var");
+            node.set_item("name", name.as_str()).unwrap();
+            node
+        }
+        Expr::Call(name, args) => {
+            let node = tagged_dict(py, "call");
+            node.set_item("name", name.as_str()).unwrap();
+            node.set_item("args", exprs_to_pyobject(py, args)).unwrap();
+            node
+        }
+        Expr::Binary(op, left, right) => {
+            let node = tagged_dict(py, "binary");
+            node.set_item("op", binop_symbol(op)).unwrap();
+            node.set_item("left", expr_to_pyobject(py, left)).unwrap();
+            node.set_item("right", expr_to_pyobject(py, right)).unwrap();
+            node
+        }
+        Expr::If(condition, then_branch, else_branch) => {
+            let node = tagged_dict(py, "if");
+            node.set_item("condition", expr_to_pyobject(py, condition))
+                .unwrap();
+            node.set_item("then", expr_to_pyobject(py, then_branch))
+                .unwrap();
+            node.set_item("else", expr_to_pyobject(py, else_branch))
+                .unwrap();
+            node
+        }
+        Expr::Match(value, cases) => {
+            let node = tagged_dict(py, "match");
+            node.set_item("value", expr_to_pyobject(py, value)).unwrap();
+            let py_cases = pyo3::types::PyList::empty_bound(py);
+            for (pattern, body) in cases {
+                let case = pyo3::types::PyDict::new_bound(py);
+                case.set_item("pattern", pattern_to_pyobject(py, pattern))
+                    .unwrap();
+                case.set_item("body", expr_to_pyobject(py, body)).unwrap();
+                py_cases.append(case).unwrap();
+            }
+            node.set_item("cases", py_cases).unwrap();
+            node
+        }
+        Expr::For {
+            var,
+            iterable,
+            body,
+        } => {
+            let node = tagged_dict(py, "for");
+            node.set_item("var", var.as_str()).unwrap();
+            node.set_item("iterable", expr_to_pyobject(py, iterable))
+                .unwrap();
+            node.set_item("body", expr_to_pyobject(py, body)).unwrap();
+            node
+        }
+        Expr::While { condition, body } => {
+            let node = tagged_dict(py, "while");
+            node.set_item("condition", expr_to_pyobject(py, condition))
+                .unwrap();
+            node.set_item("body", expr_to_pyobject(py, body)).unwrap();
+            node
+        }
+        Expr::Try {
+            try_body,
+            catch_var,
+            catch_body,
+        } => {
+            let node = tagged_dict(py, "try");
+            node.set_item("try_body", expr_to_pyobject(py, try_body))
+                .unwrap();
+            node.set_item("catch_var", catch_var.as_deref()).unwrap();
+            node.set_item("catch_body", expr_to_pyobject(py, catch_body))
+                .unwrap();
+            node
+        }
+        Expr::Lambda { params, body } => {
+            let node = tagged_dict(py, "lambda");
+            node.set_item("params", params).unwrap();
+            node.set_item("body", expr_to_pyobject(py, body)).unwrap();
+            node
+        }
+        Expr::Begin(exprs) => {
+            let node = tagged_dict(py, "begin");
+            node.set_item("exprs", exprs_to_pyobject(py, exprs))
+                .unwrap();
+            node
+        }
+        Expr::Assign(name, value) => {
+            let node = tagged_dict(py, "assign");
+            node.set_item("name", name.as_str()).unwrap();
+            node.set_item("value", expr_to_pyobject(py, value)).unwrap();
+            node
+        }
+    };
+    node.into()
+}
+
+/// 将字面量转换为Python对象 / Convert literal to Python object
+fn literal_to_pyobject(py: Python, literal: &grammar::core::Literal) -> PyObject {
+    use grammar::core::Literal;
+    let node = tagged_dict(py, "literal");
+    let (kind, value) = match literal {
+        Literal::Int(i) => ("int", i.to_object(py)),
+        Literal::BigInt(n) => ("int", n.to_object(py)),
+        Literal::Float(f) => ("float", f.to_object(py)),
+        Literal::String(s) => ("string", s.to_object(py)),
+        Literal::Bool(b) => ("bool", b.to_object(py)),
+        Literal::Null => ("null", py.None()),
+        Literal::List(items) => ("list", exprs_to_pyobject(py, items)),
+        Literal::Dict(entries) => {
+            let py_dict = pyo3::types::PyDict::new_bound(py);
+            for (key, value) in entries {
+                py_dict.set_item(key, expr_to_pyobject(py, value)).unwrap();
+            }
+            ("dict", py_dict.into())
+        }
+    };
+    node.set_item("kind", kind).unwrap();
+    node.set_item("value", value).unwrap();
+    node.into()
+}
+
+/// 将模式转换为Python对象 / Convert pattern to Python object
+fn pattern_to_pyobject(py: Python, pattern: &grammar::core::Pattern) -> PyObject {
+    use grammar::core::Pattern;
+    let node = match pattern {
+        Pattern::Literal(literal) => {
+            let node = tagged_dict(py, "literal_pattern");
+            node.set_item("literal", literal_to_pyobject(py, literal))
+                .unwrap();
+            node
+        }
+        Pattern::Var(name) => {
+            let node = tagged_dict(py, "var_pattern");
+            node.set_item("name", name.as_str()).unwrap();
+            node
+        }
+        Pattern::Wildcard => tagged_dict(py, "wildcard_pattern"),
+        Pattern::List(items) => {
+            let node = tagged_dict(py, "list_pattern");
+            let py_items = pyo3::types::PyList::empty_bound(py);
+            for item in items {
+                py_items.append(pattern_to_pyobject(py, item)).unwrap();
+            }
+            node.set_item("items", py_items).unwrap();
+            node
+        }
+        Pattern::Dict(entries) => {
+            let node = tagged_dict(py, "dict_pattern");
+            let py_entries = pyo3::types::PyDict::new_bound(py);
+            for (key, value) in entries {
+                py_entries
+                    .set_item(key, pattern_to_pyobject(py, value))
+                    .unwrap();
+            }
+            node.set_item("entries", py_entries).unwrap();
+            node
+        }
+        Pattern::Rest(name) => {
+            let node = tagged_dict(py, "rest_pattern");
+            node.set_item("name", name.as_deref()).unwrap();
+            node
+        }
+        Pattern::Or(alternatives) => {
+            let node = tagged_dict(py, "or_pattern");
+            let py_alternatives = pyo3::types::PyList::empty_bound(py);
+            for alternative in alternatives {
+                py_alternatives
+                    .append(pattern_to_pyobject(py, alternative))
+                    .unwrap();
+            }
+            node.set_item("alternatives", py_alternatives).unwrap();
+            node
+        }
+        Pattern::Guard(inner, guard) => {
+            let node = tagged_dict(py, "guard_pattern");
+            node.set_item("pattern", pattern_to_pyobject(py, inner))
+                .unwrap();
+            node.set_item("guard", expr_to_pyobject(py, guard)).unwrap();
+            node
+        }
+        Pattern::Prefix(prefix, rest) => {
+            let node = tagged_dict(py, "prefix_pattern");
+            node.set_item("prefix", prefix).unwrap();
+            node.set_item("rest", pattern_to_pyobject(py, rest))
+                .unwrap();
+            node
+        }
+        Pattern::Suffix(suffix, rest) => {
+            let node = tagged_dict(py, "suffix_pattern");
+            node.set_item("suffix", suffix).unwrap();
+            node.set_item("rest", pattern_to_pyobject(py, rest))
+                .unwrap();
+            node
+        }
+    };
+    node.into()
+}
+
+/// 二元操作符的源代码符号 / Source symbol of a binary operator
+fn binop_symbol(op: &grammar::core::BinOp) -> &'static str {
+    use grammar::core::BinOp;
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Mod => "%",
+        BinOp::Eq => "=",
+        BinOp::Ne => "!=",
+        BinOp::Lt => "<",
+        BinOp::Gt => ">",
+        BinOp::Le => "<=",
+        BinOp::Ge => ">=",
+    }
+}
@@ -2,10 +2,11 @@
 // 实现Evo-lang与Python之间的互操作
 // Implements interoperability between Evo-lang and Python
 
+use indexmap::IndexMap;
+use num_bigint::BigInt;
 use pyo3::prelude::*;
 use pyo3::types::{PyDict, PyList, PyModule as PyModuleType, PyTuple};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 /// Python桥接器 / Python bridge
 pub struct PyBridge {
@@ -169,6 +170,8 @@ pub struct PyModuleInfo {
 pub enum PyValue {
     /// 整数 / Integer
     Int(i64),
+    /// 大整数 / Big integer
+    BigInt(BigInt),
     /// 浮点数 / Float
     Float(f64),
     /// 字符串 / String
@@ -176,7 +179,7 @@ pub enum PyValue {
     /// 列表 / List
     List(Vec<PyValue>),
     /// 字典 / Dictionary
-    Dict(HashMap<String, PyValue>),
+    Dict(IndexMap<String, PyValue>),
     /// 布尔值 / Boolean
     Bool(bool),
     /// None
@@ -188,13 +191,17 @@ impl PyValue {
     pub fn from_evo_value(value: &crate::runtime::interpreter::Value) -> Self {
         match value {
             crate::runtime::interpreter::Value::Int(i) => PyValue::Int(*i),
+            crate::runtime::interpreter::Value::BigInt(n) => PyValue::BigInt(n.clone()),
             crate::runtime::interpreter::Value::Float(f) => PyValue::Float(*f),
-            crate::runtime::interpreter::Value::String(s) => PyValue::String(s.clone()),
+            crate::runtime::interpreter::Value::String(s) => PyValue::String(s.to_string()),
             crate::runtime::interpreter::Value::Bool(b) => PyValue::Bool(*b),
             crate::runtime::interpreter::Value::Null => PyValue::None,
             crate::runtime::interpreter::Value::Lambda { params, .. } => {
                 PyValue::String(format!("<lambda({})>", params.join(", ")))
             }
+            crate::runtime::interpreter::Value::Generator { .. } => {
+                PyValue::String("<generator>".to_string())
+            }
             crate::runtime::interpreter::Value::List(list) => {
                 PyValue::List(list.iter().map(|v| PyValue::from_evo_value(v)).collect())
             }
@@ -203,6 +210,36 @@ impl PyValue {
                     .map(|(k, v)| (k.clone(), PyValue::from_evo_value(v)))
                     .collect(),
             ),
+            crate::runtime::interpreter::Value::Set(set) => {
+                PyValue::List(set.iter().map(PyValue::from_evo_value).collect())
+            }
+            crate::runtime::interpreter::Value::Variant { tag, fields } => PyValue::Dict(
+                [
+                    ("tag".to_string(), PyValue::String(tag.clone())),
+                    (
+                        "fields".to_string(),
+                        PyValue::List(fields.iter().map(PyValue::from_evo_value).collect()),
+                    ),
+                ]
+                .into_iter()
+                .collect(),
+            ),
+            crate::runtime::interpreter::Value::DateTime(date) => {
+                PyValue::String(date.to_rfc3339())
+            }
+            crate::runtime::interpreter::Value::Error {
+                kind,
+                message,
+                data,
+            } => PyValue::Dict(
+                [
+                    ("kind".to_string(), PyValue::String(kind.clone())),
+                    ("message".to_string(), PyValue::String(message.clone())),
+                    ("data".to_string(), PyValue::from_evo_value(data)),
+                ]
+                .into_iter()
+                .collect(),
+            ),
         }
     }
 
@@ -210,17 +247,22 @@ impl PyValue {
     pub fn to_evo_value(&self) -> crate::runtime::interpreter::Value {
         match self {
             PyValue::Int(i) => crate::runtime::interpreter::Value::Int(*i),
+            PyValue::BigInt(n) => crate::runtime::interpreter::Value::from_bigint(n.clone()),
             PyValue::Float(f) => crate::runtime::interpreter::Value::Float(*f),
-            PyValue::String(s) => crate::runtime::interpreter::Value::String(s.clone()),
+            PyValue::String(s) => crate::runtime::interpreter::Value::String(s.as_str().into()),
             PyValue::Bool(b) => crate::runtime::interpreter::Value::Bool(*b),
             PyValue::None => crate::runtime::interpreter::Value::Null,
             PyValue::List(list) => crate::runtime::interpreter::Value::List(
-                list.iter().map(|v| v.to_evo_value()).collect(),
+                list.iter()
+                    .map(|v| v.to_evo_value())
+                    .collect::<Vec<_>>()
+                    .into(),
             ),
             PyValue::Dict(dict) => crate::runtime::interpreter::Value::Dict(
                 dict.iter()
                     .map(|(k, v)| (k.clone(), v.to_evo_value()))
-                    .collect(),
+                    .collect::<crate::runtime::interpreter::DictMap>()
+                    .into(),
             ),
         }
     }
@@ -265,6 +307,7 @@ fn py_value_to_pyobject_bound<'py>(
 ) -> Result<Bound<'py, PyAny>, PyBridgeError> {
     match value {
         PyValue::Int(i) => Ok(i.to_object(py).into_bound(py)),
+        PyValue::BigInt(n) => Ok(n.to_object(py).into_bound(py)),
         PyValue::Float(f) => Ok(f.to_object(py).into_bound(py)),
         PyValue::String(s) => Ok(s.to_object(py).into_bound(py)),
         PyValue::Bool(b) => Ok(b.to_object(py).into_bound(py)),
@@ -301,6 +344,11 @@ fn pyobject_to_py_value_bound<'py>(
         return Ok(PyValue::Int(i));
     }
 
+    // 大整数（超出i64范围的Python int）
+    if let Ok(n) = obj.extract::<BigInt>() {
+        return Ok(PyValue::BigInt(n));
+    }
+
     // 浮点数
     if let Ok(f) = obj.extract::<f64>() {
         return Ok(PyValue::Float(f));
@@ -332,7 +380,7 @@ fn pyobject_to_py_value_bound<'py>(
 
     // 字典
     if let Ok(py_dict) = obj.downcast::<PyDict>() {
-        let mut dict = HashMap::new();
+        let mut dict = IndexMap::new();
         for (key, value) in py_dict.iter() {
             let key_str = key.extract::<String>().map_err(|_| {
                 PyBridgeError::TypeConversionError("Dict key must be string".to_string())
@@ -2,6 +2,8 @@
 // 提供与Python生态系统的互操作能力
 // Provides interoperability with Python ecosystem
 
+pub mod bindings;
 pub mod bridge;
 
+pub use bindings::*;
 pub use bridge::*;
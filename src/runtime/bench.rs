@@ -0,0 +1,158 @@
+// 基准测试 / Benchmarking
+// 预热后重复执行代码并记录每次耗时，汇总为均值、中位数和标准差
+// Run code repeatedly after a warmup, timing every iteration and summarising mean, median and standard deviation
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::trace::Timestamp;
+use crate::runtime::interpreter::{DictMap, Value};
+
+/// 基准测试配置 / Benchmark configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BenchConfig {
+    /// 计时的迭代次数（至少1次）/ Timed iterations (at least 1)
+    pub iterations: usize,
+    /// 计时前不计时的预热次数 / Untimed warmup iterations before timing
+    pub warmup: usize,
+}
+
+impl Default for BenchConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 1000,
+            warmup: 100,
+        }
+    }
+}
+
+impl BenchConfig {
+    /// 指定迭代次数，预热次数为其十分之一 / Given iteration count, with a tenth of it as warmup
+    pub fn with_iterations(iterations: usize) -> Self {
+        Self {
+            iterations,
+            warmup: iterations / 10,
+        }
+    }
+}
+
+/// 基准测试统计 / Benchmark statistics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BenchStats {
+    /// 计时的迭代次数 / Timed iterations
+    pub iterations: usize,
+    /// 预热次数 / Warmup iterations
+    pub warmup: usize,
+    /// 平均耗时 / Mean time
+    pub mean: Duration,
+    /// 中位耗时 / Median time
+    pub median: Duration,
+    /// 样本标准差 / Sample standard deviation
+    pub stddev: Duration,
+    /// 最短耗时 / Fastest iteration
+    pub min: Duration,
+    /// 最长耗时 / Slowest iteration
+    pub max: Duration,
+}
+
+impl BenchStats {
+    /// 由每次迭代的耗时计算统计 / Compute statistics from per-iteration times
+    pub fn from_samples(samples: &[Duration], warmup: usize) -> Self {
+        if samples.is_empty() {
+            return Self {
+                warmup,
+                ..Self::default()
+            };
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let n = sorted.len();
+        let secs: Vec<f64> = sorted.iter().map(Duration::as_secs_f64).collect();
+        let mean = secs.iter().sum::<f64>() / n as f64;
+        let median = if n % 2 == 1 {
+            secs[n / 2]
+        } else {
+            (secs[n / 2 - 1] + secs[n / 2]) / 2.0
+        };
+        let variance = if n > 1 {
+            secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / (n - 1) as f64
+        } else {
+            0.0
+        };
+        Self {
+            iterations: n,
+            warmup,
+            mean: Duration::from_secs_f64(mean),
+            median: Duration::from_secs_f64(median),
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+            min: sorted[0],
+            max: sorted[n - 1],
+        }
+    }
+
+    /// 均值的标准误差 / Standard error of the mean
+    pub fn standard_error(&self) -> Duration {
+        if self.iterations == 0 {
+            return Duration::ZERO;
+        }
+        self.stddev.div_f64((self.iterations as f64).sqrt())
+    }
+
+    /// 相对基线的改进百分比（正数表示更快）/ Improvement over a baseline in percent (positive means faster)
+    pub fn improvement_over(&self, baseline: &BenchStats) -> f64 {
+        let base = baseline.mean.as_secs_f64();
+        if base == 0.0 {
+            return 0.0;
+        }
+        (base - self.mean.as_secs_f64()) / base * 100.0
+    }
+
+    /// 均值之差是否超过两倍合并标准误差（约95%置信）
+    /// Whether the difference of means exceeds twice the combined standard error (about 95% confidence)
+    pub fn differs_significantly(&self, other: &BenchStats) -> bool {
+        let se = self.standard_error().as_secs_f64();
+        let other_se = other.standard_error().as_secs_f64();
+        let combined = (se * se + other_se * other_se).sqrt();
+        (self.mean.as_secs_f64() - other.mean.as_secs_f64()).abs() > 2.0 * combined
+    }
+
+    /// 转换为运行时字典（供 `bench` 内置函数返回，时间单位为毫秒）
+    /// Convert to a runtime dictionary (returned by the `bench` builtin, times in milliseconds)
+    pub fn to_value(&self) -> Value {
+        Value::Dict(Arc::new(self.to_dict()))
+    }
+
+    pub(crate) fn to_dict(&self) -> DictMap {
+        let millis = |duration: Duration| Value::Float(duration.as_secs_f64() * 1000.0);
+        DictMap::from([
+            ("iterations".to_string(), Value::Int(self.iterations as i64)),
+            ("warmup".to_string(), Value::Int(self.warmup as i64)),
+            ("mean_ms".to_string(), millis(self.mean)),
+            ("median_ms".to_string(), millis(self.median)),
+            ("stddev_ms".to_string(), millis(self.stddev)),
+            ("min_ms".to_string(), millis(self.min)),
+            ("max_ms".to_string(), millis(self.max)),
+        ])
+    }
+}
+
+/// 预热后重复执行 `f` 并计时；任何一次出错即返回该错误
+/// Run `f` repeatedly after a warmup and time it; the first error is returned
+pub fn bench<E>(
+    config: &BenchConfig,
+    mut f: impl FnMut() -> Result<(), E>,
+) -> Result<BenchStats, E> {
+    for _ in 0..config.warmup {
+        f()?;
+    }
+    let iterations = config.iterations.max(1);
+    let mut samples = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let started_at = Timestamp::now();
+        f()?;
+        samples.push(started_at.elapsed());
+    }
+    Ok(BenchStats::from_samples(&samples, config.warmup))
+}
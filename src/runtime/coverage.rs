@@ -0,0 +1,662 @@
+// 覆盖率测量 / Coverage measurement
+// 插桩把函数体中的表达式和分支包在探针调用 `(cov:N expr)` 中，解释器记录每个探针的命中次数；
+// CoverageReport 按函数汇总行、分支和表达式覆盖率
+// Instrumentation wraps the expressions and branches of function bodies in probe calls `(cov:N expr)`, the
+// interpreter counts the hits of every probe, and CoverageReport summarizes line, branch and expression
+// coverage per function
+
+use std::collections::{BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use super::interpreter::{element_contains_yield, expr_contains_yield};
+use crate::grammar::core::{Expr, GrammarElement, Literal};
+use crate::grammar::types::{element_name, is_definition};
+use crate::grammar::unparse::unparse_pattern;
+use crate::parser::incremental::{ParsedDocument, SyntaxKind, SyntaxNode};
+
+/// 探针调用名前缀，后接探针编号 / Prefix of probe call names, followed by the probe id
+pub(crate) const PROBE_PREFIX: &str = "cov:";
+
+/// 参数不插桩的形式：参数是名称、声明、延迟求值的代码，或者断言消息要引用的源码
+/// Forms whose arguments are not instrumented: names, declarations, deferred code, or source quoted by assertion messages
+const OPAQUE_FORMS: [&str; 10] = [
+    "def",
+    "function",
+    "export",
+    "import",
+    "deftype",
+    "deftest",
+    "defmacro",
+    "quote",
+    "assert",
+    "assert-eq",
+];
+
+/// 探针命中次数（探针编号 → 次数）/ Probe hit counts (probe id → count)
+pub type ProbeHits = HashMap<usize, u64>;
+
+/// 探针种类 / Probe kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProbeKind {
+    /// 函数体入口，命中次数即调用次数 / Function body entry; its hit count is the call count
+    Entry,
+    /// 复合表达式 / Compound expression
+    Expression,
+    /// 条件、匹配或异常处理的一个分支 / One branch of a conditional, match or exception handler
+    Branch,
+}
+
+/// 插桩探针 / Instrumentation probe
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Probe {
+    /// 所在函数 / Enclosing function
+    pub function: String,
+    /// 种类 / Kind
+    pub kind: ProbeKind,
+    /// 源码行号（从1开始，没有源码时为 None）/ Source line (1-based; None without source)
+    pub line: Option<usize>,
+    /// 分支标签（`then`、`else`、`catch`、`case 模式`），入口探针为函数名，表达式探针为空
+    /// Branch label (`then`, `else`, `catch`, `case pattern`); the function name for entries, empty for expressions
+    pub label: String,
+}
+
+/// 插桩程序的探针表，下标即探针编号 / Probe table of an instrumented program, indexed by probe id
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverageMap {
+    /// 探针 / Probes
+    pub probes: Vec<Probe>,
+}
+
+impl CoverageMap {
+    /// 插桩程序：函数定义的函数体改写为带探针的形式，其余顶层形式不变；探针没有行号。
+    /// 生成器函数按帧逐步求值，不插桩
+    /// Instrument a program: function bodies are rewritten with probes and other top-level forms are left as is;
+    /// probes carry no line numbers. Generator functions are stepped frame by frame and are not instrumented
+    pub fn instrument(ast: &[GrammarElement]) -> (Vec<GrammarElement>, Self) {
+        Instrumenter::new(None).program(ast, &[])
+    }
+
+    /// 插桩解析后的文档，探针带有源码行号 / Instrument a parsed document; probes carry source line numbers
+    pub fn instrument_document(document: &ParsedDocument) -> (Vec<GrammarElement>, Self) {
+        Instrumenter::new(Some(&document.source)).program(&document.elements, &document.nodes)
+    }
+}
+
+/// 插桩器 / Instrumenter
+struct Instrumenter {
+    /// 每行起始的字节偏移（没有源码时为 None）/ Byte offset of each line start (None without source)
+    line_starts: Option<Vec<usize>>,
+    /// 已分配的探针 / Probes allocated so far
+    probes: Vec<Probe>,
+    /// 正在插桩的函数 / Function being instrumented
+    function: String,
+}
+
+impl Instrumenter {
+    fn new(source: Option<&str>) -> Self {
+        let line_starts = source.map(|source| {
+            std::iter::once(0)
+                .chain(source.match_indices('\n').map(|(index, _)| index + 1))
+                .collect()
+        });
+        Self {
+            line_starts,
+            probes: Vec::new(),
+            function: String::new(),
+        }
+    }
+
+    /// 语法节点与顶层元素不一一对应时（例如宏定义）放弃行号
+    /// Line numbers are dropped when syntax nodes do not match the top-level elements one to one (as with macros)
+    fn program(
+        mut self,
+        ast: &[GrammarElement],
+        nodes: &[SyntaxNode],
+    ) -> (Vec<GrammarElement>, CoverageMap) {
+        let nodes = if nodes.len() == ast.len() { nodes } else { &[] };
+        let ast = ast
+            .iter()
+            .enumerate()
+            .map(|(index, element)| match element {
+                GrammarElement::List(items) if is_definition(items) => {
+                    self.definition(items, nodes.get(index))
+                }
+                element => element.clone(),
+            })
+            .collect();
+        (
+            ast,
+            CoverageMap {
+                probes: self.probes,
+            },
+        )
+    }
+
+    /// 节点起始行，没有节点时沿用外层的行 / Start line of the node, falling back to the enclosing line
+    fn line(&self, node: Option<&SyntaxNode>, fallback: Option<usize>) -> Option<usize> {
+        let starts = self.line_starts.as_ref()?;
+        node.map(|node| starts.partition_point(|&start| start <= node.span.start))
+            .or(fallback)
+    }
+
+    /// 分配探针，返回探针调用名 / Allocate a probe and return its call name
+    fn probe(&mut self, kind: ProbeKind, line: Option<usize>, label: String) -> String {
+        self.probes.push(Probe {
+            function: self.function.clone(),
+            kind,
+            line,
+            label,
+        });
+        format!("{}{}", PROBE_PREFIX, self.probes.len() - 1)
+    }
+
+    /// `(def name (params) body [annotation])`：函数体整体带入口探针；语法树中函数体总是最后一个子节点
+    /// `(def name (params) body [annotation])`: the whole body gets an entry probe; in the syntax tree the body is
+    /// always the last child
+    fn definition(
+        &mut self,
+        items: &[GrammarElement],
+        node: Option<&SyntaxNode>,
+    ) -> GrammarElement {
+        let (Some(name), Some(body)) = (items.get(1).and_then(element_name), items.get(3)) else {
+            return GrammarElement::List(items.to_vec());
+        };
+        if element_contains_yield(body) {
+            return GrammarElement::List(items.to_vec());
+        }
+        self.function = name.clone();
+        let line = self.line(node, None);
+        let body_node =
+            list_children(node, |count| count >= 4).and_then(|children| children.last());
+        let entry = self.probe(ProbeKind::Entry, line, name);
+        let body = self.element(body, body_node, line);
+        let mut items = items.to_vec();
+        items[3] = wrap_element(entry, body);
+        GrammarElement::List(items)
+    }
+
+    fn element(
+        &mut self,
+        element: &GrammarElement,
+        node: Option<&SyntaxNode>,
+        line: Option<usize>,
+    ) -> GrammarElement {
+        match element {
+            GrammarElement::Expr(expr) => {
+                GrammarElement::Expr(Box::new(self.expr(expr, node, line)))
+            }
+            GrammarElement::List(items) => self.list(items, node, line),
+            element => element.clone(),
+        }
+    }
+
+    /// 列表形式：插桩求值的子元素后整体包上表达式探针 / List form: instrument the evaluated children, then wrap it in an expression probe
+    fn list(
+        &mut self,
+        items: &[GrammarElement],
+        node: Option<&SyntaxNode>,
+        line: Option<usize>,
+    ) -> GrammarElement {
+        let head = match items.first() {
+            Some(GrammarElement::Atom(head)) => head.as_str(),
+            _ => "",
+        };
+        if items.is_empty() || OPAQUE_FORMS.contains(&head) || head.starts_with(PROBE_PREFIX) {
+            return GrammarElement::List(items.to_vec());
+        }
+        let line = self.line(node, line);
+        let children = list_children(node, |count| count == items.len());
+        let child = |index: usize| children.and_then(|children| children.get(index));
+
+        // lambda 本身只是创建闭包，只插桩函数体 / A lambda merely creates a closure, so only its body is instrumented
+        if head == "lambda" {
+            if items[2..].iter().any(element_contains_yield) {
+                return GrammarElement::List(items.to_vec());
+            }
+            let mut instrumented = items.to_vec();
+            for (index, item) in items.iter().enumerate().skip(2) {
+                instrumented[index] = self.element(item, child(index), line);
+            }
+            return GrammarElement::List(instrumented);
+        }
+
+        let mut instrumented = items.to_vec();
+        for (index, item) in items.iter().enumerate().skip(1) {
+            instrumented[index] = match (head, index) {
+                // 绑定名和字典键不求值 / Bound names and dictionary keys are not evaluated
+                ("let" | "defconst" | "set!" | "for", 1) => continue,
+                ("dict", index) if index % 2 == 1 => continue,
+                ("if", 2) => self.branch_element(item, child(index), line, "then"),
+                ("if", 3) => self.branch_element(item, child(index), line, "else"),
+                _ => self.element(item, child(index), line),
+            };
+        }
+        let probe = self.probe(ProbeKind::Expression, line, String::new());
+        wrap_element(probe, GrammarElement::List(instrumented))
+    }
+
+    /// 表达式：复合表达式插桩子表达式后包上表达式探针，变量和字面量保持原样
+    /// Expression: compound expressions instrument their subexpressions and get an expression probe; variables and
+    /// literals stay as they are
+    fn expr(&mut self, expr: &Expr, node: Option<&SyntaxNode>, line: Option<usize>) -> Expr {
+        let line = self.line(node, line);
+        let instrumented = match expr {
+            Expr::Var(_) => return expr.clone(),
+            Expr::Literal(Literal::List(items)) => {
+                let children = list_children(node, |count| count == items.len() + 1);
+                let items = items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| self.expr(item, child_at(children, index + 1), line))
+                    .collect();
+                return Expr::Literal(Literal::List(items));
+            }
+            Expr::Literal(Literal::Dict(pairs)) => {
+                let children = list_children(node, |count| count == pairs.len() * 2 + 1);
+                let pairs = pairs
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (key, value))| {
+                        let value = self.expr(value, child_at(children, index * 2 + 2), line);
+                        (key.clone(), value)
+                    })
+                    .collect();
+                return Expr::Literal(Literal::Dict(pairs));
+            }
+            Expr::Literal(_) => return expr.clone(),
+            Expr::Lambda { params, body } => {
+                if expr_contains_yield(body) {
+                    return expr.clone();
+                }
+                let children = list_children(node, |count| count == 3);
+                return Expr::Lambda {
+                    params: params.clone(),
+                    body: Box::new(self.expr(body, child_at(children, 2), line)),
+                };
+            }
+            Expr::Call(name, _) if OPAQUE_FORMS.contains(&name.as_str()) || name == "lambda" => {
+                return expr.clone()
+            }
+            Expr::Call(name, _) if name.starts_with(PROBE_PREFIX) => return expr.clone(),
+            // 推导式的 `for` 循环由推导式驱动，不能被探针包住 / A comprehension drives its `for` loop, which must not be wrapped in a probe
+            Expr::Call(name, args) if name == "for/list" || name == "for/dict" => {
+                let args = args
+                    .iter()
+                    .map(|arg| match self.expr(arg, None, line) {
+                        Expr::Call(probe, mut inner)
+                            if probe.starts_with(PROBE_PREFIX) && inner.len() == 1 =>
+                        {
+                            inner.remove(0)
+                        }
+                        instrumented => instrumented,
+                    })
+                    .collect();
+                Expr::Call(name.clone(), args)
+            }
+            Expr::Call(name, args) => {
+                let children = list_children(node, |count| count == args.len() + 1);
+                // let 和 defconst 的第一个参数是绑定名 / The first argument of let and defconst is the bound name
+                let bound = usize::from(name == "let" || name == "defconst");
+                let args = args
+                    .iter()
+                    .enumerate()
+                    .map(|(index, arg)| match index < bound {
+                        true => arg.clone(),
+                        false => self.expr(arg, child_at(children, index + 1), line),
+                    })
+                    .collect();
+                Expr::Call(name.clone(), args)
+            }
+            Expr::Binary(op, left, right) => {
+                let children = list_children(node, |count| count == 3);
+                Expr::Binary(
+                    *op,
+                    Box::new(self.expr(left, child_at(children, 1), line)),
+                    Box::new(self.expr(right, child_at(children, 2), line)),
+                )
+            }
+            Expr::If(condition, then_expr, else_expr) => {
+                let children = list_children(node, |count| count == 3 || count == 4);
+                Expr::If(
+                    Box::new(self.expr(condition, child_at(children, 1), line)),
+                    Box::new(self.branch(then_expr, child_at(children, 2), line, "then")),
+                    Box::new(self.branch(else_expr, child_at(children, 3), line, "else")),
+                )
+            }
+            Expr::Match(value, cases) => {
+                let children = list_children(node, |count| count == cases.len() + 2);
+                let value = self.expr(value, child_at(children, 1), line);
+                let cases = cases
+                    .iter()
+                    .enumerate()
+                    .map(|(index, (pattern, body))| {
+                        let case = list_children(child_at(children, index + 2), |count| count == 2);
+                        let label = format!("case {}", unparse_pattern(pattern));
+                        let body = self.branch(body, child_at(case, 1), line, &label);
+                        (pattern.clone(), body)
+                    })
+                    .collect();
+                Expr::Match(Box::new(value), cases)
+            }
+            Expr::For {
+                var,
+                iterable,
+                body,
+            } => {
+                let children = list_children(node, |count| count == 4);
+                Expr::For {
+                    var: var.clone(),
+                    iterable: Box::new(self.expr(iterable, child_at(children, 2), line)),
+                    body: Box::new(self.expr(body, child_at(children, 3), line)),
+                }
+            }
+            Expr::While { condition, body } => {
+                let children = list_children(node, |count| count == 3);
+                Expr::While {
+                    condition: Box::new(self.expr(condition, child_at(children, 1), line)),
+                    body: Box::new(self.expr(body, child_at(children, 2), line)),
+                }
+            }
+            Expr::Try {
+                try_body,
+                catch_var,
+                catch_body,
+            } => {
+                // `(try X catch e Y)` 或 `(try X catch Y)` / `(try X catch e Y)` or `(try X catch Y)`
+                let children = list_children(node, |count| count == 4 || count == 5);
+                let catch_node = children.and_then(|children| children.last());
+                Expr::Try {
+                    try_body: Box::new(self.expr(try_body, child_at(children, 1), line)),
+                    catch_var: catch_var.clone(),
+                    catch_body: Box::new(self.branch(catch_body, catch_node, line, "catch")),
+                }
+            }
+            Expr::Begin(exprs) => {
+                let children = list_children(node, |count| count == exprs.len() + 1);
+                Expr::Begin(
+                    exprs
+                        .iter()
+                        .enumerate()
+                        .map(|(index, expr)| self.expr(expr, child_at(children, index + 1), line))
+                        .collect(),
+                )
+            }
+            Expr::Assign(name, value) => {
+                let children = list_children(node, |count| count == 3);
+                Expr::Assign(
+                    name.clone(),
+                    Box::new(self.expr(value, child_at(children, 2), line)),
+                )
+            }
+        };
+        let probe = self.probe(ProbeKind::Expression, line, String::new());
+        wrap_expr(probe, instrumented)
+    }
+
+    /// 分支：插桩分支体后包上分支探针 / Branch: instrument the body, then wrap it in a branch probe
+    fn branch(
+        &mut self,
+        expr: &Expr,
+        node: Option<&SyntaxNode>,
+        line: Option<usize>,
+        label: &str,
+    ) -> Expr {
+        let line = self.line(node, line);
+        let body = self.expr(expr, node, line);
+        let probe = self.probe(ProbeKind::Branch, line, label.to_string());
+        wrap_expr(probe, body)
+    }
+
+    /// 列表形式中的分支 / Branch of a list form
+    fn branch_element(
+        &mut self,
+        element: &GrammarElement,
+        node: Option<&SyntaxNode>,
+        line: Option<usize>,
+        label: &str,
+    ) -> GrammarElement {
+        let line = self.line(node, line);
+        let body = self.element(element, node, line);
+        let probe = self.probe(ProbeKind::Branch, line, label.to_string());
+        wrap_element(probe, body)
+    }
+}
+
+/// 列表语法节点的子节点；子节点数不满足 `arity` 时 AST 与源码形状不一致（例如宏展开），返回 None
+/// Children of a list syntax node; when the count fails `arity` the AST and the source differ in shape (as after
+/// macro expansion) and None is returned
+fn list_children(
+    node: Option<&SyntaxNode>,
+    arity: impl Fn(usize) -> bool,
+) -> Option<&[SyntaxNode]> {
+    node.filter(|node| node.kind == SyntaxKind::List && arity(node.children.len()))
+        .map(|node| node.children.as_slice())
+}
+
+fn child_at(children: Option<&[SyntaxNode]>, index: usize) -> Option<&SyntaxNode> {
+    children.and_then(|children| children.get(index))
+}
+
+fn wrap_expr(probe: String, expr: Expr) -> Expr {
+    Expr::Call(probe.into(), vec![expr])
+}
+
+fn wrap_element(probe: String, element: GrammarElement) -> GrammarElement {
+    match element {
+        GrammarElement::Expr(expr) => GrammarElement::Expr(Box::new(wrap_expr(probe, *expr))),
+        element => GrammarElement::List(vec![GrammarElement::Atom(probe), element]),
+    }
+}
+
+/// 单个函数的覆盖率 / Coverage of one function
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCoverage {
+    /// 函数名 / Function name
+    pub name: String,
+    /// 定义所在行 / Line of the definition
+    pub line: Option<usize>,
+    /// 调用次数 / Number of calls
+    pub calls: u64,
+    /// 可执行的行数（带探针的行）/ Executable lines (lines holding probes)
+    pub lines: usize,
+    /// 执行过的行数 / Lines executed
+    pub covered_lines: usize,
+    /// 未执行的行 / Lines never executed
+    pub uncovered_lines: Vec<usize>,
+    /// 分支数 / Branches
+    pub branches: usize,
+    /// 走过的分支数 / Branches taken
+    pub covered_branches: usize,
+    /// 未走过的分支：行号和标签 / Branches never taken: line and label
+    pub uncovered_branches: Vec<(Option<usize>, String)>,
+    /// 复合表达式数 / Compound expressions
+    pub expressions: usize,
+    /// 求值过的复合表达式数 / Compound expressions evaluated
+    pub covered_expressions: usize,
+}
+
+impl FunctionCoverage {
+    /// 行覆盖率（百分比）/ Line coverage (percent)
+    pub fn line_coverage(&self) -> f64 {
+        percent(self.covered_lines, self.lines)
+    }
+
+    /// 分支覆盖率（百分比）/ Branch coverage (percent)
+    pub fn branch_coverage(&self) -> f64 {
+        percent(self.covered_branches, self.branches)
+    }
+
+    /// 表达式覆盖率（百分比）/ Expression coverage (percent)
+    pub fn expression_coverage(&self) -> f64 {
+        percent(self.covered_expressions, self.expressions)
+    }
+}
+
+/// 覆盖率报告 / Coverage report
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    /// 按定义顺序的函数覆盖率 / Function coverage in definition order
+    pub functions: Vec<FunctionCoverage>,
+}
+
+impl CoverageReport {
+    /// 由探针表和命中次数汇总 / Summarize from the probe table and the hit counts
+    pub fn new(map: &CoverageMap, hits: &ProbeHits) -> Self {
+        let mut functions = Vec::new();
+        // 每个函数的行：行号 → 是否执行过 / Lines of each function: line → whether executed
+        let mut lines: Vec<HashMap<usize, bool>> = Vec::new();
+        for (id, probe) in map.probes.iter().enumerate() {
+            let count = hits.get(&id).copied().unwrap_or(0);
+            if probe.kind == ProbeKind::Entry {
+                functions.push(FunctionCoverage {
+                    name: probe.function.clone(),
+                    line: probe.line,
+                    calls: count,
+                    ..FunctionCoverage::default()
+                });
+                lines.push(HashMap::new());
+            }
+            let (Some(function), Some(function_lines)) = (functions.last_mut(), lines.last_mut())
+            else {
+                continue;
+            };
+            if let Some(line) = probe.line {
+                *function_lines.entry(line).or_default() |= count > 0;
+            }
+            match probe.kind {
+                ProbeKind::Entry => {}
+                ProbeKind::Expression => {
+                    function.expressions += 1;
+                    function.covered_expressions += usize::from(count > 0);
+                }
+                ProbeKind::Branch if count > 0 => {
+                    function.branches += 1;
+                    function.covered_branches += 1;
+                }
+                ProbeKind::Branch => {
+                    function.branches += 1;
+                    function
+                        .uncovered_branches
+                        .push((probe.line, probe.label.clone()));
+                }
+            }
+        }
+        for (function, function_lines) in functions.iter_mut().zip(lines) {
+            function.lines = function_lines.len();
+            function.covered_lines = function_lines.values().filter(|covered| **covered).count();
+            function.uncovered_lines = function_lines
+                .into_iter()
+                .filter(|(_, covered)| !covered)
+                .map(|(line, _)| line)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+        }
+        Self { functions }
+    }
+
+    /// 按名称查找函数 / Look up a function by name
+    pub fn function(&self, name: &str) -> Option<&FunctionCoverage> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+
+    /// 从未调用过的函数 / Functions never called
+    pub fn uncovered_functions(&self) -> Vec<&str> {
+        self.functions
+            .iter()
+            .filter(|function| function.calls == 0)
+            .map(|function| function.name.as_str())
+            .collect()
+    }
+
+    /// 函数覆盖率：调用过的函数占比（百分比）/ Function coverage: share of functions called (percent)
+    pub fn function_coverage(&self) -> f64 {
+        let called = self.functions.iter().filter(|f| f.calls > 0).count();
+        percent(called, self.functions.len())
+    }
+
+    /// 总行覆盖率（百分比）/ Overall line coverage (percent)
+    pub fn line_coverage(&self) -> f64 {
+        self.total(|f| (f.covered_lines, f.lines))
+    }
+
+    /// 总分支覆盖率（百分比）/ Overall branch coverage (percent)
+    pub fn branch_coverage(&self) -> f64 {
+        self.total(|f| (f.covered_branches, f.branches))
+    }
+
+    /// 总表达式覆盖率（百分比）/ Overall expression coverage (percent)
+    pub fn expression_coverage(&self) -> f64 {
+        self.total(|f| (f.covered_expressions, f.expressions))
+    }
+
+    fn total(&self, counts: impl Fn(&FunctionCoverage) -> (usize, usize)) -> f64 {
+        let (covered, total) = self
+            .functions
+            .iter()
+            .map(counts)
+            .fold((0, 0), |(c, t), (covered, total)| (c + covered, t + total));
+        percent(covered, total)
+    }
+
+    /// 文本表格，之后列出未执行的行和分支 / Text table, followed by the lines and branches never executed
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "{:<24} {:>8} {:>16} {:>16} {:>16}\n",
+            "函数 / Function", "调用 / Calls", "行 / Lines", "分支 / Branches", "表达式 / Exprs"
+        );
+        let ratio = |covered: usize, total: usize| match total {
+            0 => "-".to_string(),
+            _ => format!("{}/{} {:>5.1}%", covered, total, percent(covered, total)),
+        };
+        for function in &self.functions {
+            text.push_str(&format!(
+                "{:<24} {:>8} {:>16} {:>16} {:>16}\n",
+                function.name,
+                function.calls,
+                ratio(function.covered_lines, function.lines),
+                ratio(function.covered_branches, function.branches),
+                ratio(function.covered_expressions, function.expressions),
+            ));
+        }
+        text.push_str(&format!(
+            "总计 / Total: 函数 / functions {:.1}%, 行 / lines {:.1}%, 分支 / branches {:.1}%, 表达式 / expressions {:.1}%\n",
+            self.function_coverage(),
+            self.line_coverage(),
+            self.branch_coverage(),
+            self.expression_coverage()
+        ));
+        for function in &self.functions {
+            if !function.uncovered_lines.is_empty() {
+                let lines: Vec<String> = function
+                    .uncovered_lines
+                    .iter()
+                    .map(|line| line.to_string())
+                    .collect();
+                text.push_str(&format!(
+                    "  {}: 未执行的行 / lines not executed: {}\n",
+                    function.name,
+                    lines.join(", ")
+                ));
+            }
+            for (line, label) in &function.uncovered_branches {
+                let location = line.map_or(String::new(), |line| format!(" (line {})", line));
+                text.push_str(&format!(
+                    "  {}: 未走过的分支 / branch not taken: {}{}\n",
+                    function.name, label, location
+                ));
+            }
+        }
+        text
+    }
+}
+
+/// 百分比，没有可覆盖的项时为100 / Percentage; 100 when there is nothing to cover
+fn percent(covered: usize, total: usize) -> f64 {
+    if total == 0 {
+        100.0
+    } else {
+        covered as f64 / total as f64 * 100.0
+    }
+}
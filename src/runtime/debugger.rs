@@ -0,0 +1,691 @@
+// 调试器 / Debugger
+// 在后台线程中执行代码，在断点和单步位置暂停，暂停期间可检查和修改变量
+// Runs code on a background thread, pausing at breakpoints and steps; variables can be inspected and modified while paused
+
+use std::collections::HashSet;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::JoinHandle;
+
+use serde::{Deserialize, Serialize};
+
+use super::coverage::{CoverageMap, PROBE_PREFIX};
+use super::interpreter::{Interpreter, InterpreterError, Value};
+use super::trace::{DebugEvent, DebugHook};
+use crate::grammar::core::{Expr, GrammarElement};
+use crate::grammar::types::{element_name, is_definition};
+use crate::grammar::visit::{walk_program, Position, Visitor};
+use crate::parser::{AdaptiveParser, ParseError};
+
+/// 断点 / Breakpoint
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Breakpoint {
+    /// 进入指定函数时暂停 / Pause on entering the named function
+    Function(String),
+    /// 执行到指定行（从1开始）时暂停：顶层形式的起始行在执行该形式前暂停，函数定义的首行在进入函数时暂停，
+    /// 函数体内的行在每次执行到该行的代码时暂停（没有代码的行顺延到函数体内的下一行代码）；
+    /// 生成器函数不插桩，其中的行在进入函数时暂停
+    /// Pause at the given line (1-based): the first line of a top-level form pauses before the form runs, the first
+    /// line of a function definition pauses on entering the function, and a line in a function body pauses every time
+    /// code on that line is about to run (a line without code moves to the next line of code in the body); generator
+    /// functions are not instrumented, so their lines pause on entering the function
+    Line(usize),
+}
+
+/// 暂停原因 / Pause reason
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PauseReason {
+    /// 开始执行时暂停 / Paused on entry
+    Entry,
+    /// 命中断点 / Hit a breakpoint
+    Breakpoint,
+    /// 单步完成 / Step completed
+    Step,
+}
+
+/// 暂停时的位置信息 / Where execution is paused
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DebugPause {
+    /// 暂停点 / Pause point
+    pub event: DebugEvent,
+    /// 函数调用深度 / Function call depth
+    pub depth: usize,
+    /// 调用栈中的函数名，最内层在前 / Function names on the call stack, innermost first
+    pub stack: Vec<String>,
+    /// 所在行：顶层形式或函数定义的起始行，行断点暂停时为函数体内代码所在的行
+    /// Line: start of the top-level form or function definition, or the line of the body code for line breakpoints
+    pub line: Option<usize>,
+    /// 暂停原因 / Pause reason
+    pub reason: PauseReason,
+}
+
+/// 调试会话状态 / Debug session status
+#[derive(Debug, Clone, PartialEq)]
+pub enum DebugStatus {
+    /// 已暂停 / Paused
+    Paused(DebugPause),
+    /// 执行结束 / Finished
+    Finished(Result<Value, InterpreterError>),
+}
+
+/// 恢复执行的方式 / How to resume execution
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunMode {
+    /// 运行到下一个断点 / Run to the next breakpoint
+    Continue,
+    /// 在下一个暂停点停下 / Stop at the next pause point
+    StepInto,
+    /// 在深度不超过给定值的下一个暂停点停下 / Stop at the next pause point no deeper than the given depth
+    StepOver(usize),
+    /// 在深度小于给定值的下一个暂停点停下 / Stop at the next pause point shallower than the given depth
+    StepOut(usize),
+}
+
+/// 发往执行线程的命令 / Command sent to the execution thread
+enum Command {
+    Resume(RunMode),
+    Stop,
+    Variable(String),
+    Variables,
+    SetVariable(String, Value),
+    Evaluate(Vec<GrammarElement>),
+    SetBreakpoints(Vec<Breakpoint>),
+}
+
+/// 执行线程的回复 / Reply from the execution thread
+enum Reply {
+    Paused(DebugPause),
+    Finished(Result<Value, InterpreterError>),
+    Variable(Option<Value>),
+    Variables(Vec<(String, Value)>),
+    Evaluated(Result<Value, InterpreterError>),
+    Done,
+}
+
+/// 源码中顶层形式和函数体代码的位置 / Positions of top-level forms and function body code in the source
+#[derive(Debug, Clone, Default)]
+struct SourceMap {
+    /// 每个顶层形式的起止行 / Start and end line of each top-level form
+    form_lines: Vec<(usize, usize)>,
+    /// 每个顶层形式定义的函数名 / Function defined by each top-level form
+    form_functions: Vec<Option<String>>,
+    /// 每个探针所在的行 / Line of each probe
+    probe_lines: Vec<Option<usize>>,
+    /// 开始一行代码的探针：外层探针位于其他行 / Probes starting a line of code: the enclosing probe is on another line
+    line_probes: HashSet<usize>,
+}
+
+impl SourceMap {
+    /// 解析并插桩代码，函数体带覆盖率探针以支持行断点
+    /// Parse and instrument code; function bodies carry coverage probes to support line breakpoints
+    fn new(parser: &AdaptiveParser, code: &str) -> Result<(Vec<GrammarElement>, Self), ParseError> {
+        let document = parser.parse_document(code)?;
+        let (ast, coverage) = CoverageMap::instrument_document(&document);
+        let probe_lines: Vec<Option<usize>> =
+            coverage.probes.iter().map(|probe| probe.line).collect();
+        let mut starts = LineStarts {
+            probe_lines: &probe_lines,
+            enclosing: Vec::new(),
+            starts: HashSet::new(),
+        };
+        walk_program(&mut starts, &ast);
+        let line_probes = starts.starts;
+        let line_of = |offset: usize| code[..offset.min(code.len())].matches('\n').count() + 1;
+        let form_lines = document
+            .nodes
+            .iter()
+            .map(|node| (line_of(node.span.start), line_of(node.span.end)))
+            .collect();
+        let form_functions = document
+            .elements
+            .iter()
+            .map(|element| match element {
+                GrammarElement::List(items) if is_definition(items) => {
+                    items.get(1).and_then(element_name)
+                }
+                _ => None,
+            })
+            .collect();
+        Ok((
+            ast,
+            Self {
+                form_lines,
+                form_functions,
+                probe_lines,
+                line_probes,
+            },
+        ))
+    }
+
+    /// 包含该行的顶层形式 / Top-level form containing the line
+    fn form_at_line(&self, line: usize) -> Option<usize> {
+        self.form_lines
+            .iter()
+            .position(|(start, end)| *start <= line && line <= *end)
+    }
+
+    /// 暂停点所在的行 / Line of a pause point
+    fn line_of(&self, event: &DebugEvent) -> Option<usize> {
+        let index = match event {
+            DebugEvent::Form { index } => *index,
+            DebugEvent::Call { name } => self
+                .form_functions
+                .iter()
+                .position(|function| function.as_deref() == Some(name.as_str()))?,
+            DebugEvent::Statement { probe: None } => return None,
+            DebugEvent::Statement { probe: Some(id) } | DebugEvent::Probe { id } => {
+                return self.probe_lines.get(*id).copied().flatten()
+            }
+        };
+        self.form_lines.get(index).map(|(start, _)| *start)
+    }
+
+    /// 函数体内某行的行断点对应的探针：该行或其后第一行代码上开始一行的探针；
+    /// 函数没有带行号的探针（生成器函数或缺少行号）时返回 None
+    /// Probes for a line breakpoint in a function body: the line-starting probes on that line or the first line of
+    /// code after it; None when the function has no probes with lines (generator functions or missing lines)
+    fn probes_at_line(&self, form: usize, line: usize) -> Option<Vec<usize>> {
+        let (start, end) = self.form_lines[form];
+        let in_form = |id: &usize| {
+            self.probe_lines[*id].is_some_and(|probe_line| start <= probe_line && probe_line <= end)
+        };
+        let probes: Vec<usize> = self.line_probes.iter().copied().filter(in_form).collect();
+        if probes.is_empty() {
+            return None;
+        }
+        let target = probes
+            .iter()
+            .filter_map(|id| self.probe_lines[*id])
+            .filter(|probe_line| *probe_line >= line)
+            .min();
+        Some(
+            probes
+                .into_iter()
+                .filter(|id| self.probe_lines[*id] == target)
+                .collect(),
+        )
+    }
+}
+
+/// 找出开始一行代码的探针 / Find the probes that start a line of code
+struct LineStarts<'a> {
+    probe_lines: &'a [Option<usize>],
+    /// 外层探针的行 / Lines of the enclosing probes
+    enclosing: Vec<Option<usize>>,
+    starts: HashSet<usize>,
+}
+
+impl LineStarts<'_> {
+    fn enter(&mut self, name: &str) {
+        let Some(id) = name
+            .strip_prefix(PROBE_PREFIX)
+            .and_then(|id| id.parse::<usize>().ok())
+        else {
+            return;
+        };
+        let line = self.probe_lines.get(id).copied().flatten();
+        if line.is_some() && self.enclosing.last() != Some(&line) {
+            self.starts.insert(id);
+        }
+        self.enclosing.push(line);
+    }
+
+    fn exit(&mut self, name: &str) {
+        if name.starts_with(PROBE_PREFIX) {
+            self.enclosing.pop();
+        }
+    }
+}
+
+/// 探针包装的列表形式 `(cov:N form)` 的探针名 / Probe name of a probe-wrapped list form `(cov:N form)`
+fn probe_head(element: &GrammarElement) -> Option<&str> {
+    match element {
+        GrammarElement::List(items) => match items.as_slice() {
+            [GrammarElement::Atom(head), _] => Some(head.as_str()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl<'ast> Visitor<'ast> for LineStarts<'_> {
+    fn enter_element(&mut self, element: &'ast GrammarElement, _: Position) -> bool {
+        if let Some(head) = probe_head(element) {
+            self.enter(head);
+        }
+        true
+    }
+
+    fn exit_element(&mut self, element: &'ast GrammarElement, _: Position) {
+        if let Some(head) = probe_head(element) {
+            self.exit(head);
+        }
+    }
+
+    fn enter_expr(&mut self, expr: &'ast Expr, _: Position) -> bool {
+        if let Expr::Call(name, _) = expr {
+            self.enter(name);
+        }
+        true
+    }
+
+    fn exit_expr(&mut self, expr: &'ast Expr, _: Position) {
+        if let Expr::Call(name, _) = expr {
+            self.exit(name);
+        }
+    }
+}
+
+/// 在执行线程中运行的调试钩子 / Debug hook running on the execution thread
+struct Controller {
+    commands: Receiver<Command>,
+    replies: Sender<Reply>,
+    source: SourceMap,
+    /// 函数断点 / Function breakpoints
+    functions: Vec<String>,
+    /// 顶层形式断点（按索引）/ Top-level form breakpoints (by index)
+    forms: Vec<usize>,
+    /// 函数体内的行断点（按探针编号）/ Line breakpoints in function bodies (by probe id)
+    probes: HashSet<usize>,
+    mode: RunMode,
+    /// 尚未发生过暂停（用于区分入口暂停）/ No pause has happened yet (to tell entry pauses apart)
+    at_entry: bool,
+}
+
+impl Controller {
+    /// 将断点解析为函数名、顶层形式索引和探针编号 / Resolve breakpoints to function names, top-level form indexes and probe ids
+    fn set_breakpoints(&mut self, breakpoints: &[Breakpoint]) {
+        self.functions.clear();
+        self.forms.clear();
+        self.probes.clear();
+        for breakpoint in breakpoints {
+            match breakpoint {
+                Breakpoint::Function(name) => self.functions.push(name.clone()),
+                Breakpoint::Line(line) => {
+                    let Some(index) = self.source.form_at_line(*line) else {
+                        continue;
+                    };
+                    let function = &self.source.form_functions[index];
+                    let body_probes = match function {
+                        Some(_) if self.source.form_lines[index].0 < *line => {
+                            self.source.probes_at_line(index, *line)
+                        }
+                        _ => None,
+                    };
+                    match (function, body_probes) {
+                        (_, Some(probes)) => self.probes.extend(probes),
+                        (Some(function), None) => self.functions.push(function.clone()),
+                        (None, None) => self.forms.push(index),
+                    }
+                }
+            }
+        }
+    }
+
+    /// 单步只在形式、调用和语句处暂停，探针只用于行断点
+    /// Stepping only pauses at forms, calls and statements; probes only serve line breakpoints
+    fn pause_reason(&self, event: &DebugEvent, depth: usize) -> Option<PauseReason> {
+        let steppable = !matches!(event, DebugEvent::Probe { .. });
+        let stepped = steppable
+            && match self.mode {
+                RunMode::Continue => false,
+                RunMode::StepInto => true,
+                RunMode::StepOver(limit) => depth <= limit,
+                RunMode::StepOut(limit) => depth < limit,
+            };
+        let hit = match event {
+            DebugEvent::Form { index } => self.forms.contains(index),
+            DebugEvent::Call { name } => self.functions.contains(name),
+            DebugEvent::Statement { .. } => false,
+            DebugEvent::Probe { id } => self.probes.contains(id),
+        };
+        if stepped {
+            Some(if self.at_entry {
+                PauseReason::Entry
+            } else {
+                PauseReason::Step
+            })
+        } else if hit {
+            Some(PauseReason::Breakpoint)
+        } else {
+            None
+        }
+    }
+}
+
+impl DebugHook for Controller {
+    fn on_event(
+        &mut self,
+        interpreter: &mut Interpreter,
+        event: &DebugEvent,
+        depth: usize,
+    ) -> Result<(), InterpreterError> {
+        let Some(reason) = self.pause_reason(event, depth) else {
+            return Ok(());
+        };
+        self.at_entry = false;
+        let pause = DebugPause {
+            event: event.clone(),
+            depth,
+            stack: interpreter.stacktrace(),
+            line: self.source.line_of(event),
+            reason,
+        };
+        if self.replies.send(Reply::Paused(pause)).is_err() {
+            return Err(stopped());
+        }
+
+        // 暂停期间处理命令，直到恢复或停止 / While paused, serve commands until resumed or stopped
+        loop {
+            let reply = match self.commands.recv() {
+                Ok(Command::Resume(mode)) => {
+                    self.mode = mode;
+                    return Ok(());
+                }
+                Ok(Command::Stop) | Err(_) => return Err(stopped()),
+                Ok(Command::Variable(name)) => {
+                    Reply::Variable(interpreter.get_variable(&name).cloned())
+                }
+                Ok(Command::Variables) => Reply::Variables(interpreter.variables()),
+                Ok(Command::SetVariable(name, value)) => {
+                    interpreter.set_variable(name, value);
+                    Reply::Done
+                }
+                Ok(Command::Evaluate(ast)) => {
+                    let mut result = Ok(Value::Null);
+                    for element in &ast {
+                        result = interpreter.evaluate_paused(element);
+                        if result.is_err() {
+                            break;
+                        }
+                    }
+                    Reply::Evaluated(result)
+                }
+                Ok(Command::SetBreakpoints(breakpoints)) => {
+                    self.set_breakpoints(&breakpoints);
+                    Reply::Done
+                }
+            };
+            if self.replies.send(reply).is_err() {
+                return Err(stopped());
+            }
+        }
+    }
+}
+
+/// 调试会话被停止 / The debug session was stopped
+fn stopped() -> InterpreterError {
+    InterpreterError::runtime_error("Debugging session stopped".to_string(), None)
+}
+
+/// 正在执行的调试会话 / Running debug session
+struct Session {
+    commands: Sender<Command>,
+    replies: Receiver<Reply>,
+    thread: JoinHandle<Interpreter>,
+}
+
+/// 可调试的解释器：支持函数和行断点、单步执行，以及暂停时检查和修改变量
+/// Debuggable interpreter: function and line breakpoints, single stepping, and inspecting or modifying variables while paused
+///
+/// ```ignore
+/// let mut debugger = DebugInterpreter::new();
+/// debugger.add_breakpoint(Breakpoint::Function("fib".to_string()));
+/// let status = debugger.start("(def fib (n) ...) (fib 5)")?;
+/// let n = debugger.variable("n");
+/// let status = debugger.step_over()?;
+/// ```
+pub struct DebugInterpreter {
+    /// 空闲时的解释器（执行期间移入执行线程）/ Interpreter while idle (moved into the execution thread while running)
+    interpreter: Option<Interpreter>,
+    parser: AdaptiveParser,
+    breakpoints: Vec<Breakpoint>,
+    stop_on_entry: bool,
+    session: Option<Session>,
+    /// 当前暂停位置 / Current pause
+    paused: Option<DebugPause>,
+}
+
+impl DebugInterpreter {
+    /// 创建调试器 / Create debugger
+    pub fn new() -> Self {
+        Self::with_interpreter(Interpreter::new())
+    }
+
+    /// 使用已配置的解释器创建调试器（保留其选项、宿主函数和状态）
+    /// Create a debugger around a configured interpreter (keeping its options, host functions and state)
+    pub fn with_interpreter(interpreter: Interpreter) -> Self {
+        Self {
+            interpreter: Some(interpreter),
+            parser: AdaptiveParser::new(true),
+            breakpoints: Vec::new(),
+            stop_on_entry: false,
+            session: None,
+            paused: None,
+        }
+    }
+
+    /// 开始执行时是否立即暂停 / Whether to pause as soon as execution starts
+    pub fn set_stop_on_entry(&mut self, stop: bool) {
+        self.stop_on_entry = stop;
+    }
+
+    /// 添加断点（暂停期间添加会立即生效）/ Add a breakpoint (takes effect immediately when added while paused)
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+            self.sync_breakpoints();
+        }
+    }
+
+    /// 移除断点 / Remove a breakpoint
+    pub fn remove_breakpoint(&mut self, breakpoint: &Breakpoint) -> bool {
+        let before = self.breakpoints.len();
+        self.breakpoints.retain(|existing| existing != breakpoint);
+        let removed = self.breakpoints.len() != before;
+        if removed {
+            self.sync_breakpoints();
+        }
+        removed
+    }
+
+    /// 当前断点 / Current breakpoints
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// 当前暂停位置（未暂停时为 None）/ Current pause (None when not paused)
+    pub fn paused(&self) -> Option<&DebugPause> {
+        self.paused.as_ref()
+    }
+
+    /// 解析并开始执行代码，运行到第一个暂停点或结束；已有会话会先被停止
+    /// Parse and start executing code, running to the first pause or the end; an existing session is stopped first
+    pub fn start(&mut self, code: &str) -> Result<DebugStatus, ParseError> {
+        self.stop();
+        let (ast, source) = SourceMap::new(&self.parser, code)?;
+        let mut interpreter = self.interpreter.take().unwrap_or_default();
+
+        let (command_sender, command_receiver) = mpsc::channel();
+        let (reply_sender, reply_receiver) = mpsc::channel();
+        let mut controller = Controller {
+            commands: command_receiver,
+            replies: reply_sender.clone(),
+            source,
+            functions: Vec::new(),
+            forms: Vec::new(),
+            probes: HashSet::new(),
+            mode: if self.stop_on_entry {
+                RunMode::StepInto
+            } else {
+                RunMode::Continue
+            },
+            at_entry: true,
+        };
+        controller.set_breakpoints(&self.breakpoints);
+
+        let thread = std::thread::spawn(move || {
+            interpreter.set_debug_hook(Box::new(controller));
+            let result = interpreter.execute(&ast);
+            interpreter.take_debug_hook();
+            let _ = reply_sender.send(Reply::Finished(result));
+            interpreter
+        });
+        self.session = Some(Session {
+            commands: command_sender,
+            replies: reply_receiver,
+            thread,
+        });
+        Ok(self.wait())
+    }
+
+    /// 继续运行到下一个断点 / Continue to the next breakpoint
+    pub fn resume(&mut self) -> Result<DebugStatus, InterpreterError> {
+        self.run(RunMode::Continue)
+    }
+
+    /// 单步执行，进入函数调用 / Step, entering function calls
+    pub fn step_into(&mut self) -> Result<DebugStatus, InterpreterError> {
+        self.run(RunMode::StepInto)
+    }
+
+    /// 单步执行，不进入函数调用 / Step without entering function calls
+    pub fn step_over(&mut self) -> Result<DebugStatus, InterpreterError> {
+        let depth = self.paused_depth()?;
+        self.run(RunMode::StepOver(depth))
+    }
+
+    /// 运行到当前函数返回 / Run until the current function returns
+    pub fn step_out(&mut self) -> Result<DebugStatus, InterpreterError> {
+        let depth = self.paused_depth()?;
+        self.run(RunMode::StepOut(depth))
+    }
+
+    /// 读取变量（仅在暂停时可用）/ Read a variable (only while paused)
+    pub fn variable(&mut self, name: &str) -> Option<Value> {
+        match self.request(Command::Variable(name.to_string())) {
+            Ok(Reply::Variable(value)) => value,
+            _ => None,
+        }
+    }
+
+    /// 当前可见的全部变量 / All currently visible variables
+    pub fn variables(&mut self) -> Vec<(String, Value)> {
+        match self.request(Command::Variables) {
+            Ok(Reply::Variables(variables)) => variables,
+            _ => Vec::new(),
+        }
+    }
+
+    /// 修改变量，恢复执行后生效 / Modify a variable; takes effect when execution resumes
+    pub fn set_variable(&mut self, name: &str, value: Value) -> Result<(), InterpreterError> {
+        self.request(Command::SetVariable(name.to_string(), value))
+            .map(|_| ())
+    }
+
+    /// 在暂停位置求值代码（可读写当前变量）/ Evaluate code at the pause point (may read and write current variables)
+    pub fn evaluate(&mut self, code: &str) -> Result<Value, InterpreterError> {
+        let ast = self.parser.parse(code).map_err(|error| {
+            InterpreterError::runtime_error(format!("Parse error: {}", error), None)
+        })?;
+        match self.request(Command::Evaluate(ast))? {
+            Reply::Evaluated(result) => result,
+            _ => Err(not_paused()),
+        }
+    }
+
+    /// 停止当前会话 / Stop the current session
+    pub fn stop(&mut self) {
+        if let Some(session) = &self.session {
+            let _ = session.commands.send(Command::Stop);
+            while self.session.is_some() {
+                self.wait();
+            }
+        }
+    }
+
+    /// 空闲时访问解释器（执行期间为 None）/ Access the interpreter while idle (None while running)
+    pub fn interpreter(&mut self) -> Option<&mut Interpreter> {
+        self.interpreter.as_mut()
+    }
+
+    fn paused_depth(&self) -> Result<usize, InterpreterError> {
+        self.paused
+            .as_ref()
+            .map(|pause| pause.depth)
+            .ok_or_else(not_paused)
+    }
+
+    fn run(&mut self, mode: RunMode) -> Result<DebugStatus, InterpreterError> {
+        let session = self.session.as_ref().filter(|_| self.paused.is_some());
+        let session = session.ok_or_else(not_paused)?;
+        session
+            .commands
+            .send(Command::Resume(mode))
+            .map_err(|_| not_paused())?;
+        self.paused = None;
+        Ok(self.wait())
+    }
+
+    /// 暂停期间发送命令并等待回复 / Send a command while paused and wait for the reply
+    fn request(&mut self, command: Command) -> Result<Reply, InterpreterError> {
+        let session = self.session.as_ref().filter(|_| self.paused.is_some());
+        let session = session.ok_or_else(not_paused)?;
+        session.commands.send(command).map_err(|_| not_paused())?;
+        session.replies.recv().map_err(|_| not_paused())
+    }
+
+    fn sync_breakpoints(&mut self) {
+        if self.paused.is_some() {
+            let _ = self.request(Command::SetBreakpoints(self.breakpoints.clone()));
+        }
+    }
+
+    /// 等待下一次暂停或结束 / Wait for the next pause or the end
+    fn wait(&mut self) -> DebugStatus {
+        let Some(session) = &self.session else {
+            return DebugStatus::Finished(Err(not_paused()));
+        };
+        match session.replies.recv() {
+            Ok(Reply::Paused(pause)) => {
+                self.paused = Some(pause.clone());
+                DebugStatus::Paused(pause)
+            }
+            Ok(Reply::Finished(result)) => {
+                self.finish();
+                DebugStatus::Finished(result)
+            }
+            Ok(_) | Err(_) => {
+                self.finish();
+                DebugStatus::Finished(Err(InterpreterError::runtime_error(
+                    "Debugger execution thread terminated".to_string(),
+                    None,
+                )))
+            }
+        }
+    }
+
+    /// 回收执行线程中的解释器 / Take the interpreter back from the execution thread
+    fn finish(&mut self) {
+        self.paused = None;
+        if let Some(session) = self.session.take() {
+            self.interpreter = Some(session.thread.join().unwrap_or_default());
+        }
+    }
+}
+
+impl Default for DebugInterpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for DebugInterpreter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// 没有暂停中的会话 / No paused session
+fn not_paused() -> InterpreterError {
+    InterpreterError::runtime_error("No paused debugging session".to_string(), None)
+}
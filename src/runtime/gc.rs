@@ -0,0 +1,139 @@
+// 垃圾回收 / Garbage collection
+// Lambda和生成器的状态存放在解释器的注册表中，值只保存其ID；标记-清除回收从变量不再可达的注册表条目
+// Lambda and generator state lives in the interpreter's registries and values only hold their IDs;
+// mark-and-sweep frees the registry entries no longer reachable from variables
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::interpreter::{DictMap, Value};
+
+/// 垃圾回收配置 / Garbage collection configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GcConfig {
+    /// 注册表对象数达到此值时在下一个顶层形式之后自动回收（None表示只在 `(gc)` 时回收）
+    /// Registry object count that triggers a collection after the next top-level form (None collects only on `(gc)`)
+    pub threshold: Option<usize>,
+    /// 回收后下次阈值至少为存活对象数乘以此系数 / After a collection the next threshold is at least the live object count times this factor
+    pub growth_factor: f64,
+}
+
+impl Default for GcConfig {
+    fn default() -> Self {
+        Self {
+            threshold: Some(10_000),
+            growth_factor: 2.0,
+        }
+    }
+}
+
+impl GcConfig {
+    /// 按存活对象数计算下次回收阈值 / Compute the next collection threshold from the live object count
+    pub(crate) fn next_threshold(&self, live: usize) -> Option<usize> {
+        self.threshold
+            .map(|threshold| threshold.max((live as f64 * self.growth_factor).ceil() as usize))
+    }
+}
+
+/// 垃圾回收统计 / Garbage collection statistics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GcStats {
+    /// 已执行的回收次数 / Number of collections run
+    pub collections: u64,
+    /// 累计释放的对象数 / Total objects freed
+    pub freed: u64,
+    /// 上次回收释放的对象数 / Objects freed by the last collection
+    pub last_freed: usize,
+    /// 注册表中的Lambda数 / Lambdas in the registry
+    pub lambdas: usize,
+    /// 注册表中的生成器数 / Generators in the registry
+    pub generators: usize,
+    /// 下次自动回收的阈值 / Threshold of the next automatic collection
+    pub next_threshold: Option<usize>,
+    /// 是否有 `(gc)` 请求等待当前顶层形式结束 / Whether a `(gc)` request is waiting for the current top-level form to finish
+    pub pending: bool,
+}
+
+impl GcStats {
+    /// 转换为运行时字典（供 `gc-stats` 内置函数返回）/ Convert to a runtime dictionary (returned by the `gc-stats` builtin)
+    pub fn to_dict(&self) -> DictMap {
+        let count = |n: u64| Value::Int(n.min(i64::MAX as u64) as i64);
+        DictMap::from([
+            ("collections".to_string(), count(self.collections)),
+            ("freed".to_string(), count(self.freed)),
+            ("last_freed".to_string(), count(self.last_freed as u64)),
+            ("lambdas".to_string(), count(self.lambdas as u64)),
+            ("generators".to_string(), count(self.generators as u64)),
+            (
+                "next_threshold".to_string(),
+                self.next_threshold
+                    .map_or(Value::Null, |threshold| count(threshold as u64)),
+            ),
+            ("pending".to_string(), Value::Bool(self.pending)),
+        ])
+    }
+}
+
+/// 标记阶段：记录已到达的Lambda和生成器，新到达的ID放入工作队列等待扫描其引用的值
+/// Mark phase: records reached lambdas and generators; newly reached IDs are queued so the values they reference get scanned
+#[derive(Default)]
+pub(crate) struct Marker {
+    lambdas: HashSet<String>,
+    generators: HashSet<String>,
+    lambda_queue: Vec<String>,
+    generator_queue: Vec<String>,
+}
+
+impl Marker {
+    /// 标记值及其包含的值引用的对象 / Mark the objects referenced by a value and the values it contains
+    pub(crate) fn mark(&mut self, value: &Value) {
+        let mut stack = vec![value];
+        while let Some(value) = stack.pop() {
+            match value {
+                Value::Lambda { id, .. } => {
+                    if self.lambdas.insert(id.clone()) {
+                        self.lambda_queue.push(id.clone());
+                    }
+                }
+                Value::Generator { id } => {
+                    if self.generators.insert(id.clone()) {
+                        self.generator_queue.push(id.clone());
+                    }
+                }
+                Value::List(items) => stack.extend(items.iter()),
+                Value::Dict(dict) => stack.extend(dict.values()),
+                Value::Set(set) => stack.extend(set.iter()),
+                Value::Variant { fields, .. } => stack.extend(fields.iter()),
+                Value::Error { data, .. } => stack.push(data),
+                Value::Int(_)
+                | Value::DateTime(_)
+                | Value::BigInt(_)
+                | Value::Float(_)
+                | Value::String(_)
+                | Value::Bool(_)
+                | Value::Null => {}
+            }
+        }
+    }
+
+    /// 取出下一个待扫描的Lambda / Take the next lambda to scan
+    pub(crate) fn next_lambda(&mut self) -> Option<String> {
+        self.lambda_queue.pop()
+    }
+
+    /// 取出下一个待扫描的生成器 / Take the next generator to scan
+    pub(crate) fn next_generator(&mut self) -> Option<String> {
+        self.generator_queue.pop()
+    }
+
+    /// Lambda是否可达 / Whether the lambda is reachable
+    pub(crate) fn is_lambda_live(&self, id: &str) -> bool {
+        self.lambdas.contains(id)
+    }
+
+    /// 生成器是否可达 / Whether the generator is reachable
+    pub(crate) fn is_generator_live(&self, id: &str) -> bool {
+        self.generators.contains(id)
+    }
+}
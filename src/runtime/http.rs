@@ -0,0 +1,239 @@
+// HTTP客户端 / HTTP client
+// 基于标准库 TcpStream 的最小 HTTP/1.1 客户端，NLU后端、进化网络钩子和 `http` 特性的内置函数共用。
+// 不支持TLS：只接受 http:// 地址，https:// 地址在连接前即报错，HTTPS服务需经本地代理访问
+// A minimal HTTP/1.1 client on top of the standard library's TcpStream, shared by the NLU backend, evolution
+// webhooks and the builtins of the `http` feature. There is no TLS: only http:// URLs are accepted, https:// URLs
+// fail before connecting, and HTTPS services must be reached through a local proxy
+
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// HTTP响应 / HTTP response
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    /// 状态码 / Status code
+    pub status: u16,
+    /// 响应头，名称为小写 / Response headers with lowercase names
+    pub headers: Vec<(String, String)>,
+    /// 响应正文 / Response body
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// 是否为2xx响应 / Whether it is a 2xx response
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// 拆分后的 http:// 地址 / A split http:// URL
+struct HttpUrl<'a> {
+    /// 主机和端口，用于 Host 头 / Host and port, used for the Host header
+    authority: &'a str,
+    path: &'a str,
+}
+
+impl<'a> HttpUrl<'a> {
+    fn parse(url: &'a str) -> Result<Self, String> {
+        let rest = url.strip_prefix("http://").ok_or_else(|| {
+            format!(
+                "Unsupported endpoint {}: only http:// is supported, use a local proxy for https",
+                url
+            )
+        })?;
+        let (authority, path) = match rest.find('/') {
+            Some(index) => (&rest[..index], &rest[index..]),
+            None => (rest, "/"),
+        };
+        if authority.is_empty() {
+            return Err(format!("Missing host in {}", url));
+        }
+        // 空白和控制字符会破坏请求行 / Whitespace and control characters would break the request line
+        if rest.chars().any(|ch| ch.is_whitespace() || ch.is_control()) {
+            return Err(format!("Invalid character in URL {:?}", url));
+        }
+        Ok(Self { authority, path })
+    }
+
+    fn host(&self) -> &'a str {
+        self.authority
+            .rsplit_once(':')
+            .map_or(self.authority, |(host, _)| host)
+    }
+
+    fn address(&self) -> String {
+        if self.authority.contains(':') {
+            self.authority.to_string()
+        } else {
+            format!("{}:80", self.authority)
+        }
+    }
+}
+
+/// 地址中的主机名（不含端口）/ Host name of a URL, without the port
+pub fn url_host(url: &str) -> Result<&str, String> {
+    HttpUrl::parse(url).map(|url| url.host())
+}
+
+/// 连接地址的每个解析结果，每次尝试最多等待 `timeout` / Connect to each resolved address, waiting at most `timeout` per attempt
+fn connect(address: &str, timeout: Duration) -> Result<TcpStream, String> {
+    let mut last_error = format!("Cannot resolve {}", address);
+    let addresses = address
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {}: {}", address, e))?;
+    for socket in addresses {
+        match TcpStream::connect_timeout(&socket, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = format!("Cannot connect to {}: {}", address, e),
+        }
+    }
+    Err(last_error)
+}
+
+/// 检查调用方提供的请求头：名称必须是非空的 token，名称和值都不能含 CR 或 LF（防止注入额外的头或请求）
+/// Check a caller-supplied header: the name must be a non-empty token, and neither the name nor the value may
+/// contain CR or LF (which would inject extra headers or requests)
+fn check_header(name: &str, value: &str) -> Result<(), String> {
+    let valid_name = !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_graphic() && !matches!(ch, ':' | '(' | ')' | '"' | ','));
+    if !valid_name {
+        return Err(format!("Invalid header name {:?}", name));
+    }
+    if value.contains(['\r', '\n']) {
+        return Err(format!(
+            "Invalid value for header {}: CR and LF are not allowed",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// 发送HTTP/1.1请求；Host、Content-Length 和 Connection 头由客户端设置，调用方提供的同名头被忽略。
+/// 只支持 http:// 地址；连接、读和写各自以 `timeout` 为限；含 CR/LF 的请求头在连接前即报错
+/// Send an HTTP/1.1 request; the client sets the Host, Content-Length and Connection headers, and caller headers
+/// with those names are ignored. Only http:// URLs are supported; connecting, reading and writing are each bounded by
+/// `timeout`; headers containing CR or LF are rejected before connecting
+pub fn send(
+    method: &str,
+    url: &str,
+    headers: &[(String, String)],
+    body: Option<&str>,
+    timeout: Duration,
+) -> Result<HttpResponse, String> {
+    let url = HttpUrl::parse(url)?;
+    for (name, value) in headers {
+        check_header(name, value)?;
+    }
+    let address = url.address();
+    let mut stream = connect(&address, timeout)?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| stream.set_write_timeout(Some(timeout)))
+        .map_err(|e| e.to_string())?;
+
+    let mut request = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n",
+        method, url.path, url.authority
+    );
+    for (name, value) in headers {
+        if !matches!(
+            name.to_ascii_lowercase().as_str(),
+            "host" | "content-length" | "connection"
+        ) {
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    if let Some(body) = body {
+        request.push_str(&format!("Content-Length: {}\r\n", body.len()));
+    }
+    request.push_str("\r\n");
+    request.push_str(body.unwrap_or(""));
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let mut response = Vec::new();
+    stream
+        .read_to_end(&mut response)
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    parse_response(&response)
+}
+
+/// 发送JSON的POST请求，返回2xx响应的正文 / Send a POST with a JSON body, returning the body of a 2xx response
+pub fn post_json(
+    url: &str,
+    body: &str,
+    api_key: Option<&str>,
+    timeout: Duration,
+) -> Result<String, String> {
+    let mut headers = vec![
+        ("Content-Type".to_string(), "application/json".to_string()),
+        ("Accept".to_string(), "application/json".to_string()),
+    ];
+    if let Some(key) = api_key {
+        headers.push(("Authorization".to_string(), format!("Bearer {}", key)));
+    }
+    let response = send("POST", url, &headers, Some(body), timeout)?;
+    if response.is_success() {
+        Ok(response.body)
+    } else {
+        Err(format!(
+            "HTTP {}: {}",
+            response.status,
+            response.body.trim()
+        ))
+    }
+}
+
+/// 解析原始响应 / Parse a raw response
+fn parse_response(response: &[u8]) -> Result<HttpResponse, String> {
+    let split = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| "Malformed HTTP response".to_string())?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let payload = &response[split + 4..];
+    let mut lines = head.lines();
+    let status: u16 = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| "Malformed HTTP status line".to_string())?;
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+        .collect();
+    let chunked = headers.iter().any(|(name, value)| {
+        name == "transfer-encoding" && value.to_ascii_lowercase().contains("chunked")
+    });
+    let body = if chunked {
+        String::from_utf8_lossy(&decode_chunked(payload)).into_owned()
+    } else {
+        String::from_utf8_lossy(payload).into_owned()
+    };
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+/// 解码分块传输的正文 / Decode a chunked transfer body
+fn decode_chunked(mut payload: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    while let Some(line_end) = payload.windows(2).position(|window| window == b"\r\n") {
+        let size = String::from_utf8_lossy(&payload[..line_end]);
+        let size =
+            usize::from_str_radix(size.split(';').next().unwrap_or("").trim(), 16).unwrap_or(0);
+        let rest = &payload[line_end + 2..];
+        if size == 0 || rest.len() < size {
+            break;
+        }
+        body.extend_from_slice(&rest[..size]);
+        payload = rest[size..].strip_prefix(b"\r\n").unwrap_or(&rest[size..]);
+    }
+    body
+}
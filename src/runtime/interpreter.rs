@@ -2,153 +2,1494 @@
 // 执行Evo-lang代码的解释器
 // Interpreter for executing Evo-lang code
 
+use super::bench::{self, BenchConfig, BenchStats};
+use super::coverage::{ProbeHits, PROBE_PREFIX};
+use super::gc::{GcConfig, GcStats, Marker};
+use super::jit::NativeBackend;
+use super::stdlib::{self, NativeModule};
+use super::symbol::Symbol;
+use super::trace::{
+    DebugEvent, DebugHook, ProfileReport, Profiler, Timestamp, Tracer, UsageObserver,
+};
+use super::value_set::{UnhashableValue, ValueSet};
 use crate::grammar::core::{BinOp, Expr, GrammarElement, Literal, Pattern};
+use crate::grammar::types::{binop_symbol, destructuring_pattern, element_name, string_literal};
+use crate::grammar::visit::expr_children;
+use crate::parser::normalize::{parse_date, parse_datetime, parse_number, ParsedNumber};
 use crate::parser::AdaptiveParser;
+use indexmap::IndexMap;
+use num_bigint::{BigInt, Sign};
+use num_traits::{ToPrimitive, Zero};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Lambda注册表条目：参数、函数体、捕获的环境 / Lambda registry entry: params, body, captured env
+type LambdaEntry = (Vec<Symbol>, GrammarElement, HashMap<Symbol, Value>);
+
+/// `memoize` 返回的Lambda的函数体调用的内部形式，第一个参数是缓存表的ID
+/// Internal form called by the body of lambdas returned by `memoize`; the first argument is the cache table id
+const MEMO_CALL: &str = "memo:call";
+
+/// `memoize` 返回的Lambda捕获的被缓存函数（Lambda值或函数名）
+/// Memoized function captured by lambdas returned by `memoize` (a lambda value or a function name)
+const MEMO_TARGET: &str = "memo:target";
+
+/// 正则表达式缓存的最大条目数，超出时清空重建 / Maximum entries in the regex cache; it is cleared once exceeded
+const REGEX_CACHE_LIMIT: usize = 256;
+
+/// 宿主函数：由嵌入方（如Python）提供、可从脚本调用的函数
+/// Host function: provided by the embedder (e.g. Python) and callable from scripts
+pub type HostFunction = Arc<dyn Fn(&[Value]) -> Result<Value, InterpreterError> + Send + Sync>;
+
+/// 输出目标：`print` 等内置函数写入的位置 / Output sink: where `print` and similar builtins write
+pub type OutputSink = Arc<Mutex<dyn Write + Send>>;
 
 /// 解释器 / Interpreter
 pub struct Interpreter {
     /// 环境 / Environment (变量存储 / Variable storage)
-    environment: HashMap<String, Value>,
+    environment: HashMap<Symbol, Value>,
     /// 函数定义 / Function definitions
-    functions: HashMap<String, Function>,
+    functions: HashMap<Symbol, Function>,
     /// 模块缓存 / Module cache
     modules: HashMap<String, Module>,
     /// Lambda注册表 / Lambda registry (用于存储Lambda函数体和捕获的环境)
-    lambda_registry: HashMap<String, (Vec<String>, GrammarElement, HashMap<String, Value>)>,
+    lambda_registry: HashMap<String, LambdaEntry>,
     /// Lambda计数器 / Lambda counter (用于生成唯一ID)
     lambda_counter: u64,
     /// 当前执行的函数所属的模块名（用于递归调用时查找模块内函数）
     /// Current executing function's module name (for finding functions in module during recursive calls)
     current_module: Option<String>,
+    /// 整数溢出处理模式 / Integer overflow handling mode
+    overflow_mode: OverflowMode,
+    /// 模块搜索路径（按优先级，不含默认目录）/ Module search paths (by priority, excluding defaults)
+    module_paths: Vec<PathBuf>,
+    /// 当前代码所在目录（用于相对导入）/ Directory of the executing code (for relative imports)
+    base_dir: Option<PathBuf>,
+    /// 当前代码通过 `(export ...)` 声明的导出名 / Names declared via `(export ...)` in the executing code
+    exports: Option<HashSet<String>>,
+    /// 当前代码通过 `defn-` 定义的私有函数名 / Private function names defined via `defn-` in the executing code
+    private: HashSet<String>,
+    /// 通过 `defconst` 定义或随模块导入的常量 / Constants defined via `defconst` or imported with modules
+    constants: HashSet<Symbol>,
+    /// 模块名到导入别名的映射（用于重新加载）/ Module name to import aliases (for reloading)
+    module_aliases: HashMap<String, HashSet<String>>,
+    /// 正在加载的模块文件链（用于检测循环导入）/ Chain of module files being loaded (for circular import detection)
+    import_chain: Vec<PathBuf>,
+    /// 原生标准库模块（写时复制，沙箱与父解释器共享）/ Native standard library modules (copy-on-write, shared with sandboxes)
+    native_modules: Arc<HashMap<String, NativeModule>>,
+    /// 导入别名到原生模块名的映射 / Import alias to native module name
+    native_aliases: HashMap<String, String>,
+    /// 通过 `:only` 导入的别名及其选中的名称 / Aliases imported with `:only` and their selected names
+    selective_imports: HashMap<String, HashSet<String>>,
+    /// 随机数生成器状态（xorshift64*）/ Random number generator state (xorshift64*)
+    rng_state: u64,
+    /// 解释器选项 / Interpreter options
+    options: InterpreterOptions,
+    /// 宿主函数（写时复制，沙箱与父解释器共享）/ Host functions (copy-on-write, shared with sandboxes)
+    host_functions: Arc<HashMap<String, HostFunction>>,
+    /// 输出目标（None表示标准输出）/ Output sink (None means stdout)
+    output: Option<OutputSink>,
+    /// 执行资源预算 / Execution resource budget
+    budget: ExecutionBudget,
+    /// 本次执行已用的步数 / Steps used by the current execution
+    steps: u64,
+    /// 本次执行的开始时间 / Start time of the current execution
+    started_at: Option<std::time::Instant>,
+    /// `deftype` 定义的变体构造器（按标签）/ Variant constructors defined by `deftype` (by tag)
+    variant_constructors: HashMap<String, VariantConstructor>,
+    /// 协议（含内置协议）及其实现 / Protocols (built-in ones included) and their implementations
+    protocols: HashMap<String, Protocol>,
+    /// 生成器注册表（挂起的生成器状态）/ Generator registry (suspended generator states)
+    generators: HashMap<String, GeneratorState>,
+    /// 生成器计数器（用于生成唯一ID）/ Generator counter (for generating unique IDs)
+    generator_counter: u64,
+    /// 函数调用追踪器 / Function call tracer
+    tracer: Option<Box<dyn Tracer>>,
+    /// 被追踪调用的嵌套深度 / Nesting depth of traced calls
+    trace_depth: usize,
+    /// 调试钩子 / Debug hook
+    debug_hook: Option<Box<dyn DebugHook>>,
+    /// 使用情况观察者 / Usage observer
+    usage_observer: Option<Box<dyn UsageObserver>>,
+    /// 当前顶层形式执行过的构造种类（仅在安装观察者时记录）
+    /// Construct kinds the current top-level form executed (recorded only with an observer installed)
+    usage_constructs: BTreeSet<&'static str>,
+    /// 用户函数和Lambda的调用栈，最外层在前 / Call stack of user functions and lambdas, outermost first
+    call_stack: Vec<Symbol>,
+    /// 最近一次未捕获错误的调用栈和出错位置 / Call stack and site of the most recent uncaught error
+    error_trace: ErrorTrace,
+    /// 原生代码后端 / Native code backend
+    native_backend: Option<Box<dyn NativeBackend>>,
+    /// 函数表版本，每次定义或替换函数时递增（原生代码据此失效）
+    /// Function table generation, bumped whenever functions are defined or replaced (invalidates native code)
+    function_generation: u64,
+    /// 调用分派的内联缓存：函数名解析出的调用目标 / Inline cache for call dispatch: call targets resolved per function name
+    call_cache: HashMap<Symbol, CachedCall>,
+    /// 内联缓存命中统计 / Inline cache hit statistics
+    call_cache_stats: CallCacheStats,
+    /// 垃圾回收配置 / Garbage collection configuration
+    gc_config: GcConfig,
+    /// 垃圾回收累计统计 / Cumulative garbage collection statistics
+    gc_stats: GcStats,
+    /// 下次自动回收的阈值 / Threshold of the next automatic collection
+    gc_threshold: Option<usize>,
+    /// `(gc)` 请求在当前顶层形式结束后回收 / `(gc)` requests a collection once the current top-level form finishes
+    gc_requested: bool,
+    /// `deftest` 注册的测试（名称、测试体），按定义顺序 / Tests registered by `deftest` (name, body), in definition order
+    tests: Vec<(String, Vec<Expr>)>,
+    /// 覆盖率探针命中次数，None 表示未开启记录 / Coverage probe hit counts; None when recording is off
+    coverage: Option<ProbeHits>,
+    /// `memoize` 的缓存表：Lambda ID → 参数 → 结果 / `memoize` caches: lambda id → arguments → result
+    memo_tables: HashMap<String, HashMap<String, Value>>,
+    /// 正在执行的 let 块，每块记录块内无 body 的 let 覆盖的变量及其旧值，块结束时撤销
+    /// Let blocks being executed; each records the variables overwritten by body-less lets inside it, with their old
+    /// values, undone when the block ends
+    let_scopes: Vec<Vec<(Symbol, Option<Value>)>>,
+    /// 已编译的正则表达式（按模式字符串）/ Compiled regular expressions (by pattern string)
+    regex_cache: HashMap<String, Regex>,
+    /// 解析所执行程序的语法版本（见 `RuleRegistry`），宿主未记录时为 None
+    /// Grammar version that parsed the executed program (see `RuleRegistry`); None when the host has not recorded one
+    grammar_version: Option<u64>,
 }
 
-/// 函数定义 / Function definition
+/// 调用分派内联缓存统计 / Call dispatch inline cache statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallCacheStats {
+    /// 直接使用缓存目标的调用数 / Calls dispatched straight from a cached target
+    pub hits: u64,
+    /// 需要重新解析目标的调用数 / Calls whose target had to be resolved
+    pub misses: u64,
+    /// 当前缓存的函数名数 / Number of function names currently cached
+    pub entries: usize,
+}
+
+impl CallCacheStats {
+    /// 命中率（没有调用时为0）/ Hit rate (0 when there were no calls)
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.0
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+/// 代数数据类型的变体构造器 / Variant constructor of an algebraic data type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VariantConstructor {
+    /// 所属类型名 / Name of the owning type
+    pub type_name: String,
+    /// 字段名 / Field names
+    pub fields: Vec<String>,
+}
+
+/// `defprotocol` 定义的协议：方法签名和按类型注册的实现
+/// Protocol defined by `defprotocol`: method signatures and implementations registered per type
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Protocol {
+    /// 方法名和参数个数（含接收者）/ Method names and parameter counts (receiver included)
+    methods: Vec<(String, usize)>,
+    /// 类型名到该类型的方法实现 / Type name to that type's method implementations
+    impls: HashMap<String, HashMap<String, Arc<Function>>>,
+}
+
+/// 内置协议：`+` 经 Add 分派，`to-string` 和 `print` 经 Show，比较运算经 Ord
+/// Built-in protocols: `+` dispatches through Add, `to-string` and `print` through Show, comparisons through Ord
+const BUILTIN_PROTOCOLS: &[(&str, &[(&str, usize)])] = &[
+    ("Add", &[("add", 2)]),
+    ("Show", &[("to-string", 1)]),
+    ("Ord", &[("compare", 2)]),
+];
+
+/// 内置协议（尚无用户实现）/ The built-in protocols, without user implementations yet
+fn builtin_protocols() -> HashMap<String, Protocol> {
+    BUILTIN_PROTOCOLS
+        .iter()
+        .map(|(name, methods)| {
+            let methods = methods
+                .iter()
+                .map(|(method, arity)| (method.to_string(), *arity))
+                .collect();
+            (
+                name.to_string(),
+                Protocol {
+                    methods,
+                    impls: HashMap::new(),
+                },
+            )
+        })
+        .collect()
+}
+
+/// 协议方法的实现：用户的 `defimpl` 或内置类型的原生实现
+/// Implementation of a protocol method: a user `defimpl` or a built-in type's native implementation
+enum MethodImpl {
+    User(Arc<Function>),
+    Native(stdlib::NativeFunction),
+}
+
+/// 内置类型的协议方法实现 / Protocol method implementations of built-in types
+fn native_method(method: &str, type_name: &str) -> Option<stdlib::NativeFunction> {
+    let native: stdlib::NativeFunction = match (method, type_name) {
+        ("add", "String") => concat_strings,
+        ("add", "List") => concat_lists,
+        ("add", "Int" | "BigInt" | "Float") => add_numbers,
+        ("to-string", _) => display_value,
+        ("compare", "Int" | "BigInt" | "Float" | "String" | "DateTime") => compare_ordered,
+        _ => return None,
+    };
+    Some(native)
+}
+
+/// String 的 Add 实现 / Add implementation of String
+fn concat_strings(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    match args {
+        [Value::String(a), Value::String(b)] => Ok(Value::String(format!("{}{}", a, b).into())),
+        _ => Err(InterpreterError::type_error(
+            "Invalid types for addition".to_string(),
+            None,
+        )),
+    }
+}
+
+/// List 的 Add 实现 / Add implementation of List
+fn concat_lists(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    match args {
+        [Value::List(a), Value::List(b)] => {
+            let mut result = a.to_vec();
+            result.extend_from_slice(b);
+            Ok(Value::List(result.into()))
+        }
+        _ => Err(InterpreterError::type_error(
+            "Invalid types for addition".to_string(),
+            None,
+        )),
+    }
+}
+
+/// 数值的 Add 实现 / Add implementation of numbers
+fn add_numbers(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    match args {
+        [a, b] if is_number(a) && is_number(b) => interpreter.add_values(a, b),
+        _ => Err(InterpreterError::type_error(
+            "Invalid types for addition".to_string(),
+            None,
+        )),
+    }
+}
+
+/// 内置的 Show 实现：容器的元素仍经用户的 Show 实现显示 / Built-in Show implementation: container elements still go through user Show implementations
+fn display_value(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    Ok(Value::String(interpreter.show_value(&args[0])?.into()))
+}
+
+/// 有序内置类型的 Ord 实现：返回 -1、0 或 1 / Ord implementation of ordered built-in types: returns -1, 0 or 1
+fn compare_ordered(
+    interpreter: &mut Interpreter,
+    args: &[Value],
+) -> Result<Value, InterpreterError> {
+    let (left, right) = (&args[0], &args[1]);
+    let ordering = if interpreter.compare_values(BinOp::Lt, left, right)? == Value::Bool(true) {
+        -1
+    } else if interpreter.compare_values(BinOp::Gt, left, right)? == Value::Bool(true) {
+        1
+    } else {
+        0
+    };
+    Ok(Value::Int(ordering))
+}
+
+/// 值不能放入集合时的错误 / Error for a value that cannot go into a set
+fn set_error(error: UnhashableValue) -> InterpreterError {
+    InterpreterError::type_error(error.to_string(), None)
+}
+
+/// 是否为数值 / Whether a value is a number
+fn is_number(value: &Value) -> bool {
+    matches!(value, Value::Int(_) | Value::BigInt(_) | Value::Float(_))
+}
+
+/// 执行资源预算（None表示不限制）/ Execution resource budget (None means unlimited)
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionBudget {
+    /// 最大求值步数 / Maximum evaluation steps
+    pub max_steps: Option<u64>,
+    /// 列表/字典的最大元素数 / Maximum number of list/dict elements
+    pub max_collection_size: Option<usize>,
+    /// 墙钟超时 / Wall-clock timeout
+    pub timeout: Option<std::time::Duration>,
+    /// 用户函数和Lambda的最大调用深度 / Maximum call depth of user functions and lambdas
+    pub max_call_depth: Option<usize>,
+}
+
+impl ExecutionBudget {
+    /// 自动执行生成代码时使用的默认预算 / Default budget for automatically executed generated code
+    pub fn for_generated_code() -> Self {
+        Self {
+            max_steps: Some(10_000_000),
+            max_collection_size: Some(1_000_000),
+            timeout: Some(std::time::Duration::from_secs(30)),
+            max_call_depth: Some(10_000),
+        }
+    }
+}
+
+/// 超出预算的资源类型 / Kind of resource whose budget was exceeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetKind {
+    /// 求值步数 / Evaluation steps
+    Steps,
+    /// 集合大小 / Collection size
+    CollectionSize,
+    /// 执行时间（毫秒）/ Execution time (milliseconds)
+    Time,
+    /// 调用深度 / Call depth
+    CallDepth,
+}
+
+impl std::fmt::Display for BudgetKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetKind::Steps => write!(f, "evaluation steps"),
+            BudgetKind::CollectionSize => write!(f, "collection size"),
+            BudgetKind::Time => write!(f, "execution time (ms)"),
+            BudgetKind::CallDepth => write!(f, "call depth"),
+        }
+    }
+}
+
+/// 解释器选项（沙箱控制）/ Interpreter options (sandboxing controls)
 #[derive(Debug, Clone)]
+pub struct InterpreterOptions {
+    /// 是否允许文件I/O内置函数 / Whether file I/O builtins are allowed
+    pub allow_fs: bool,
+    /// 允许访问的目录（为空时不限制）/ Directories file access is restricted to (unrestricted when empty)
+    pub allowed_paths: Vec<PathBuf>,
+    /// 是否允许导入模块文件（原生标准库模块总是可用）/ Whether module files may be imported (native standard library modules are always available)
+    pub allow_modules: bool,
+    /// 是否允许HTTP内置函数（`http` 特性）/ Whether HTTP builtins are allowed (`http` feature)
+    pub allow_net: bool,
+    /// 允许访问的主机（为空时不限制）/ Hosts HTTP requests are restricted to (unrestricted when empty)
+    pub allowed_hosts: Vec<String>,
+    /// HTTP请求超时 / HTTP request timeout
+    pub http_timeout: Duration,
+}
+
+impl Default for InterpreterOptions {
+    fn default() -> Self {
+        Self {
+            allow_fs: true,
+            allowed_paths: Vec::new(),
+            allow_modules: true,
+            allow_net: true,
+            allowed_hosts: Vec::new(),
+            http_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl InterpreterOptions {
+    /// 禁止所有文件和网络访问的选项（用于运行不受信任的脚本）/ Options denying all file and network access (for untrusted scripts)
+    pub fn sandboxed() -> Self {
+        Self {
+            allow_fs: false,
+            allow_net: false,
+            ..Self::default()
+        }
+    }
+
+    /// 同时禁止文件访问和导入模块文件的选项（子沙箱的默认值）
+    /// Options denying both file access and module file imports (the default of sandbox children)
+    pub fn isolated() -> Self {
+        Self {
+            allow_modules: false,
+            ..Self::sandboxed()
+        }
+    }
+}
+
+/// 默认模块搜索目录 / Default module search directories
+const DEFAULT_MODULE_DIRS: [&str; 3] = ["modules", "examples", "."];
+
+/// 模块搜索路径环境变量 / Module search path environment variable
+pub const MODULE_PATH_ENV: &str = "EVO_PATH";
+
+/// 按导入规则查找模块文件：`./`、`../` 开头的名称相对于 `base_dir`，其余在 `search_paths` 中查找；
+/// 找不到时返回查找过的目录
+/// Find a module file by the import rules: names starting with `./` or `../` resolve against `base_dir`, others
+/// against `search_paths`; returns the searched directories when nothing is found
+pub(crate) fn find_module_file(
+    module_name: &str,
+    base_dir: Option<&Path>,
+    search_paths: &[PathBuf],
+) -> Result<PathBuf, Vec<PathBuf>> {
+    let name = if module_name.ends_with(".evo") {
+        module_name.to_string()
+    } else {
+        format!("{}.evo", module_name)
+    };
+
+    let is_relative = module_name.starts_with("./") || module_name.starts_with("../");
+    let search_dirs = if is_relative {
+        vec![base_dir.map_or_else(|| PathBuf::from("."), Path::to_path_buf)]
+    } else {
+        search_paths.to_vec()
+    };
+
+    for dir in &search_dirs {
+        let path = dir.join(&name);
+        if path.is_file() {
+            return Ok(path);
+        }
+    }
+    Err(search_dirs)
+}
+
+/// 整数溢出处理模式 / Integer overflow handling mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OverflowMode {
+    /// 溢出时自动提升为大整数 / Promote to big integer on overflow
+    #[default]
+    Promote,
+    /// 溢出时报告错误 / Report an error on overflow
+    Error,
+}
+
+/// 函数定义 / Function definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Function {
     /// 参数名列表 / Parameter names
-    params: Vec<String>,
+    params: Vec<Symbol>,
     /// 函数体 / Function body
     body: GrammarElement,
     /// 捕获的环境 / Captured environment (for closures)
-    captured_env: Option<std::collections::HashMap<String, Value>>,
+    captured_env: Option<HashMap<Symbol, Value>>,
     /// 所属模块名 / Module name (None for functions defined in main scope)
     module_name: Option<String>,
+    /// 文档字符串 / Docstring
+    #[serde(default)]
+    doc: Option<String>,
+}
+
+/// 函数名解析出的调用目标 / Call target resolved for a function name
+#[derive(Clone)]
+enum CallTarget {
+    /// 主作用域用户函数 / Main-scope user function
+    Function(Arc<Function>),
+    /// 模块内或已导入模块导出的函数 / Function of the current module or exported by an imported module
+    ModuleFunction(Arc<Function>),
+    /// 协议方法 / Protocol method
+    Method,
+    /// 变体构造器（字段数）/ Variant constructor (field count)
+    Constructor(usize),
+    /// 原生模块函数 / Native module function
+    Native(stdlib::NativeFunction),
+    /// 宿主函数 / Host function
+    Host(HostFunction),
+    /// 内置函数 / Built-in function
+    Builtin,
+}
+
+/// 内联缓存项：解析目标时所在的模块会影响查找顺序 / Inline cache entry: the module a target was resolved in affects lookup order
+#[derive(Clone)]
+struct CachedCall {
+    /// 解析时的当前模块 / Current module at resolution time
+    module: Option<String>,
+    /// 调用目标 / Call target
+    target: CallTarget,
+}
+
+/// 挂起的生成器：局部变量和待执行的帧，每次恢复运行到下一个 `yield`
+/// Suspended generator: local variables and pending frames; each resume runs to the next `yield`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GeneratorState {
+    /// 局部变量名（参数、捕获变量和生成器体内绑定的变量）/ Local names (params, captured variables and names bound in the body)
+    locals: Vec<Symbol>,
+    /// 局部变量的当前值 / Current values of the locals
+    env: HashMap<Symbol, Value>,
+    /// 待执行的帧（栈顶在末尾），为空表示已结束 / Pending frames (top at the end); empty once finished
+    stack: Vec<GeneratorFrame>,
+    /// 所属模块名 / Module name
+    module_name: Option<String>,
+}
+
+/// 生成器执行帧 / Generator execution frame
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum GeneratorFrame {
+    /// 执行一个元素 / Execute an element
+    Eval(GrammarElement),
+    /// while 循环：条件为真时先执行循环体再回到本帧 / While loop: while the condition holds, run the body and come back
+    While { condition: Expr, body: Expr },
+    /// 遍历列表或范围 / Iterate over a list or range
+    For {
+        var: Symbol,
+        items: Vec<Value>,
+        index: usize,
+        body: Expr,
+    },
+    /// 遍历另一个生成器 / Iterate over another generator
+    ForGenerator { var: Symbol, id: String, body: Expr },
+    /// 离开作用域时恢复变量 / Restore a variable when leaving its scope
+    Restore { name: Symbol, old: Option<Value> },
 }
 
 /// 模块 / Module
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct Module {
     /// 模块名称 / Module name
     name: String,
     /// 模块变量 / Module environment
-    environment: HashMap<String, Value>,
+    environment: HashMap<Symbol, Value>,
     /// 模块函数 / Module functions
-    functions: HashMap<String, Function>,
+    functions: HashMap<Symbol, Function>,
+    /// 导出名列表（None表示全部导出）/ Exported names (None exports everything)
+    #[serde(default)]
+    exports: Option<HashSet<String>>,
+    /// 通过 `defn-` 定义的私有函数，即使出现在 `export` 中也不导出
+    /// Private functions defined via `defn-`, never exported even when listed in `export`
+    #[serde(default)]
+    private: HashSet<String>,
+    /// 通过 `defconst` 定义的常量 / Constants defined via `defconst`
+    #[serde(default)]
+    constants: HashSet<String>,
+    /// 模块导入的原生模块别名 / Native module aliases imported by the module
+    #[serde(default)]
+    native_aliases: HashMap<String, String>,
+    /// 模块通过 `:only` 导入的别名及其选中的名称 / Aliases the module imported with `:only` and their selected names
+    #[serde(default)]
+    selective_imports: HashMap<String, HashSet<String>>,
+    /// 模块自身导入的模块名到别名的映射 / Module name to the aliases the module imported it under
+    #[serde(default)]
+    module_aliases: HashMap<String, HashSet<String>>,
+    /// 模块定义的变体构造器 / Variant constructors defined by the module
+    #[serde(default)]
+    variant_constructors: HashMap<String, VariantConstructor>,
+    /// 模块定义的协议和实现 / Protocols and implementations defined by the module
+    #[serde(default)]
+    protocols: HashMap<String, Protocol>,
+}
+
+impl Module {
+    /// 检查名称是否对导入者可见 / Check whether a name is visible to importers
+    fn is_exported(&self, name: &str) -> bool {
+        !self.private.contains(name)
+            && self
+                .exports
+                .as_ref()
+                .map(|exports| exports.contains(name))
+                .unwrap_or(true)
+    }
+}
+
+/// 解释器状态快照 / Interpreter state snapshot
+/// 包含环境、函数表、模块缓存和Lambda注册表，可序列化为JSON用于检查点和恢复
+/// Holds environment, function table, module cache and lambda registry; serializable to JSON for checkpoint/resume
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterpreterSnapshot {
+    environment: HashMap<Symbol, Value>,
+    functions: HashMap<Symbol, Function>,
+    modules: HashMap<String, Module>,
+    lambda_registry: HashMap<String, LambdaEntry>,
+    lambda_counter: u64,
+    overflow_mode: OverflowMode,
+    #[serde(default)]
+    module_aliases: HashMap<String, HashSet<String>>,
+    #[serde(default)]
+    native_aliases: HashMap<String, String>,
+    #[serde(default)]
+    selective_imports: HashMap<String, HashSet<String>>,
+    #[serde(default)]
+    variant_constructors: HashMap<String, VariantConstructor>,
+    #[serde(default)]
+    protocols: HashMap<String, Protocol>,
+    #[serde(default)]
+    generators: HashMap<String, GeneratorState>,
+    #[serde(default)]
+    generator_counter: u64,
+    #[serde(default)]
+    grammar_version: Option<u64>,
+    #[serde(default)]
+    constants: HashSet<Symbol>,
+    #[serde(default)]
+    private: HashSet<String>,
 }
 
 impl Interpreter {
     /// 创建新解释器 / Create new interpreter
     pub fn new() -> Self {
-        let mut interpreter = Self {
+        let mut interpreter = Self::empty();
+        // 注册内置函数 / Register built-in functions
+        interpreter.register_builtins();
+        interpreter
+    }
+
+    /// 不含内置函数的空解释器 / Empty interpreter without built-ins
+    fn empty() -> Self {
+        Self {
             environment: HashMap::new(),
             functions: HashMap::new(),
             modules: HashMap::new(),
             lambda_registry: HashMap::new(),
             lambda_counter: 0,
             current_module: None,
-        };
-        // 注册内置函数 / Register built-in functions
-        interpreter.register_builtins();
+            overflow_mode: OverflowMode::default(),
+            module_paths: std::env::var_os(MODULE_PATH_ENV)
+                .map(|paths| std::env::split_paths(&paths).collect())
+                .unwrap_or_default(),
+            base_dir: None,
+            exports: None,
+            private: HashSet::new(),
+            constants: HashSet::new(),
+            module_aliases: HashMap::new(),
+            import_chain: Vec::new(),
+            native_modules: Arc::default(),
+            native_aliases: HashMap::new(),
+            selective_imports: HashMap::new(),
+            rng_state: 0,
+            options: InterpreterOptions::default(),
+            host_functions: Arc::default(),
+            output: None,
+            budget: ExecutionBudget::default(),
+            steps: 0,
+            started_at: None,
+            variant_constructors: HashMap::new(),
+            protocols: builtin_protocols(),
+            generators: HashMap::new(),
+            generator_counter: 0,
+            tracer: None,
+            trace_depth: 0,
+            debug_hook: None,
+            usage_observer: None,
+            usage_constructs: BTreeSet::new(),
+            call_stack: Vec::new(),
+            error_trace: ErrorTrace::default(),
+            native_backend: None,
+            function_generation: 0,
+            call_cache: HashMap::new(),
+            call_cache_stats: CallCacheStats::default(),
+            gc_config: GcConfig::default(),
+            gc_stats: GcStats::default(),
+            gc_threshold: GcConfig::default().threshold,
+            gc_requested: false,
+            tests: Vec::new(),
+            coverage: None,
+            memo_tables: HashMap::new(),
+            let_scopes: Vec::new(),
+            regex_cache: HashMap::new(),
+            grammar_version: None,
+        }
+    }
+
+    /// 创建隔离的子解释器：写时复制地共享原生模块和宿主函数，拥有独立的环境、函数表和生成代码预算，
+    /// 默认禁止文件访问和导入模块；输出写到与本解释器相同的位置
+    /// Spawn an isolated child interpreter: native modules and host functions are shared copy-on-write, while the
+    /// environment, function table and generated-code budget are its own; file and module access are disabled
+    /// by default, and output goes wherever this interpreter's does
+    pub fn spawn_sandbox(&mut self) -> Interpreter {
+        let mut child = Self::empty();
+        child.native_modules = Arc::clone(&self.native_modules);
+        child.host_functions = Arc::clone(&self.host_functions);
+        child.overflow_mode = self.overflow_mode;
+        child.output = self.output.clone();
+        child.options = InterpreterOptions::isolated();
+        child.budget = ExecutionBudget::for_generated_code();
+        child.module_paths = Vec::new();
+        let seed = self.next_random();
+        child.seed_random(seed);
+        child
+    }
+
+    /// 设置整数溢出处理模式 / Set integer overflow handling mode
+    pub fn set_overflow_mode(&mut self, mode: OverflowMode) {
+        self.overflow_mode = mode;
+    }
+
+    /// 获取整数溢出处理模式 / Get integer overflow handling mode
+    pub fn overflow_mode(&self) -> OverflowMode {
+        self.overflow_mode
+    }
+
+    /// 记录解析所执行程序的语法版本，随快照保存以便复现 / Record the grammar version that parsed the executed program, saved with snapshots for reproduction
+    pub fn set_grammar_version(&mut self, version: Option<u64>) {
+        self.grammar_version = version;
+    }
+
+    /// 最近记录的语法版本 / Most recently recorded grammar version
+    pub fn grammar_version(&self) -> Option<u64> {
+        self.grammar_version
+    }
+
+    /// 使用指定选项创建解释器 / Create interpreter with the given options
+    pub fn with_options(options: InterpreterOptions) -> Self {
+        let mut interpreter = Self::new();
+        interpreter.options = options;
         interpreter
     }
 
-    /// 注册内置函数 / Register built-in functions
-    fn register_builtins(&mut self) {
-        // 内置函数会在函数调用时处理
+    /// 获取解释器选项 / Get interpreter options
+    pub fn options(&self) -> &InterpreterOptions {
+        &self.options
     }
 
-    /// 执行代码 / Execute code
-    pub fn execute(&mut self, ast: &[GrammarElement]) -> Result<Value, InterpreterError> {
-        let mut last_value = Value::Null;
+    /// 注册宿主函数，脚本可按名称调用 / Register host function callable from scripts by name
+    pub fn register_host_function<F>(&mut self, name: impl Into<String>, function: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, InterpreterError> + Send + Sync + 'static,
+    {
+        Arc::make_mut(&mut self.host_functions).insert(name.into(), Arc::new(function));
+        self.dispatch_changed();
+    }
 
-        for element in ast {
-            last_value = self.eval_element(element)?;
+    /// 移除宿主函数，返回它是否存在 / Remove a host function, returning whether it existed
+    pub fn remove_host_function(&mut self, name: &str) -> bool {
+        let removed = self.host_functions.contains_key(name)
+            && Arc::make_mut(&mut self.host_functions)
+                .remove(name)
+                .is_some();
+        if removed {
+            self.dispatch_changed();
         }
+        removed
+    }
 
-        Ok(last_value)
+    /// 移除用 `def` 定义的主作用域函数，返回它是否存在
+    /// Remove a main-scope function defined with `def`, returning whether it existed
+    pub fn remove_function(&mut self, name: &str) -> bool {
+        let removed =
+            Symbol::lookup(name).is_some_and(|symbol| self.functions.remove(&symbol).is_some());
+        if removed {
+            self.dispatch_changed();
+        }
+        removed
     }
 
-    /// 执行单个表达式 / Execute single expression
-    pub fn execute_expr(&mut self, expr: &Expr) -> Result<Value, InterpreterError> {
-        self.eval_expr(expr)
+    /// 是否存在指定名称的 `def` 函数或宿主函数 / Whether a `def` function or host function with the name exists
+    pub fn has_function(&self, name: &str) -> bool {
+        self.host_functions.contains_key(name)
+            || Symbol::lookup(name).is_some_and(|symbol| self.functions.contains_key(&symbol))
     }
 
-    /// 评估语法元素 / Evaluate grammar element
-    fn eval_element(&mut self, element: &GrammarElement) -> Result<Value, InterpreterError> {
-        match element {
-            GrammarElement::Expr(expr) => self.eval_expr(expr),
-            GrammarElement::List(list) => self.eval_list(list),
-            GrammarElement::Atom(atom) => {
-                // 检查是否是关键字或变量
-                match atom.as_str() {
-                    "def" | "function" | "let" | "if" => Err(InterpreterError::runtime_error(
-                        "Special forms must be in a list".to_string(),
-                        None,
-                    )),
-                    _ => {
-                        // 尝试作为变量查找
-                        self.environment
-                            .get(atom)
-                            .cloned()
-                            .ok_or_else(|| InterpreterError::undefined_variable(atom.clone(), None))
-                    }
+    /// 设置执行资源预算 / Set execution resource budget
+    pub fn set_budget(&mut self, budget: ExecutionBudget) {
+        self.budget = budget;
+    }
+
+    /// 上次（或当前）执行已用的求值步数 / Evaluation steps used by the last (or current) execution
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// 获取执行资源预算 / Get execution resource budget
+    pub fn budget(&self) -> &ExecutionBudget {
+        &self.budget
+    }
+
+    /// 安装函数调用追踪器（替换已有的）/ Install a function call tracer (replacing any existing one)
+    pub fn set_tracer(&mut self, tracer: Box<dyn Tracer>) {
+        self.tracer = Some(tracer);
+    }
+
+    /// 移除并返回追踪器 / Remove and return the tracer
+    pub fn take_tracer(&mut self) -> Option<Box<dyn Tracer>> {
+        self.tracer.take()
+    }
+
+    /// 开始记录覆盖率探针（清空已有的命中次数），配合 `CoverageMap` 插桩的程序使用
+    /// Start recording coverage probes (clearing existing hit counts), for programs instrumented with `CoverageMap`
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(ProbeHits::new());
+    }
+
+    /// 停止记录并返回探针命中次数 / Stop recording and return the probe hit counts
+    pub fn take_coverage(&mut self) -> Option<ProbeHits> {
+        self.coverage.take()
+    }
+
+    /// 安装调试钩子（替换已有的）/ Install a debug hook (replacing any existing one)
+    pub fn set_debug_hook(&mut self, hook: Box<dyn DebugHook>) {
+        self.debug_hook = Some(hook);
+    }
+
+    /// 移除并返回调试钩子 / Remove and return the debug hook
+    pub fn take_debug_hook(&mut self) -> Option<Box<dyn DebugHook>> {
+        self.debug_hook.take()
+    }
+
+    /// 创建向学习模块报告使用情况的解释器，如 `Interpreter::with_learning(engine.learning_handle())`
+    /// Create an interpreter reporting usage to the learning module, e.g. `Interpreter::with_learning(engine.learning_handle())`
+    pub fn with_learning(observer: impl UsageObserver + 'static) -> Self {
+        let mut interpreter = Self::new();
+        interpreter.set_usage_observer(Box::new(observer));
+        interpreter
+    }
+
+    /// 安装使用情况观察者（替换已有的）/ Install a usage observer (replacing any existing one)
+    pub fn set_usage_observer(&mut self, observer: Box<dyn UsageObserver>) {
+        self.usage_observer = Some(observer);
+    }
+
+    /// 移除并返回使用情况观察者 / Remove and return the usage observer
+    pub fn take_usage_observer(&mut self) -> Option<Box<dyn UsageObserver>> {
+        self.usage_constructs.clear();
+        self.usage_observer.take()
+    }
+
+    /// 记录当前顶层形式执行过的构造 / Note a construct executed by the current top-level form
+    fn note_construct(&mut self, kind: &'static str) {
+        if self.usage_observer.is_some() {
+            self.usage_constructs.insert(kind);
+        }
+    }
+
+    /// 记录表达式的构造种类 / Note the construct kind of an expression
+    fn note_expr(&mut self, expr: &Expr) {
+        let kind = match expr {
+            Expr::Literal(Literal::List(_) | Literal::Dict(_)) => "collection",
+            Expr::Literal(_) | Expr::Var(_) => return,
+            Expr::Call(name, _) if name.starts_with("op:") => "operator",
+            Expr::Call(name, _) if name == "let" => "let",
+            Expr::Call(..) => "call",
+            Expr::Binary(..) => "operator",
+            Expr::If(..) => "if",
+            Expr::Match(..) => "match",
+            Expr::For { .. } => "for",
+            Expr::While { .. } => "while",
+            Expr::Try { .. } => "try",
+            Expr::Lambda { .. } => "lambda",
+            Expr::Begin(_) => "begin",
+            Expr::Assign(..) => "assign",
+        };
+        self.note_construct(kind);
+    }
+
+    /// 向观察者报告一个顶层形式的构造种类和结果 / Report a top-level form's construct kinds and outcome to the observer
+    fn report_usage(&mut self, element: &GrammarElement, result: &Result<Value, InterpreterError>) {
+        let Some(observer) = self.usage_observer.as_mut() else {
+            return;
+        };
+        let kinds: Vec<&str> = std::mem::take(&mut self.usage_constructs)
+            .into_iter()
+            .collect();
+        // 只保留代码开头，避免长程序占满学习记录 / Keep only the start of the code so long programs do not flood the records
+        let mut code = crate::grammar::unparse::unparse_element(element);
+        if let Some((cut, _)) = code.char_indices().nth(120) {
+            code.truncate(cut);
+            code.push_str("...");
+        }
+        if !kinds.is_empty() {
+            observer.constructs(&kinds);
+        }
+        match result {
+            Ok(_) if !kinds.is_empty() => observer.success(&kinds.join("+"), &code),
+            Ok(_) => {}
+            Err(error) => {
+                observer.error(error.kind(), &error.to_string(), &code);
+                if let Some(function) = self.error_trace.frames.first() {
+                    observer.failed_in(function);
                 }
             }
-            GrammarElement::NaturalLang(_) => Err(InterpreterError::runtime_error(
-                "Natural language not supported in execution".to_string(),
-                None,
-            )),
         }
     }
 
-    /// 评估表达式列表（用于处理多个表达式） / Evaluate expression list
-    fn eval_expr_list(&mut self, exprs: &[Expr]) -> Result<Value, InterpreterError> {
-        let mut last_value = Value::Null;
-        for expr in exprs {
-            last_value = self.eval_expr(expr)?;
+    /// 安装原生代码后端（替换已有的）/ Install a native code backend (replacing any existing one)
+    pub(crate) fn set_native_backend(&mut self, backend: Box<dyn NativeBackend>) {
+        self.native_backend = Some(backend);
+    }
+
+    /// 移除并返回原生代码后端 / Remove and return the native code backend
+    pub(crate) fn take_native_backend(&mut self) -> Option<Box<dyn NativeBackend>> {
+        self.native_backend.take()
+    }
+
+    /// 原生代码后端 / Native code backend
+    pub(crate) fn native_backend(&self) -> Option<&dyn NativeBackend> {
+        self.native_backend.as_deref()
+    }
+
+    /// 函数表版本 / Function table generation
+    #[cfg(feature = "cranelift")]
+    pub(crate) fn function_generation(&self) -> u64 {
+        self.function_generation
+    }
+
+    /// 可编译为原生代码的主作用域函数：参数和函数体（闭包、模块函数和被Lambda变量遮蔽的函数除外）
+    /// Main-scope function eligible for native code: its parameters and body (closures, module functions
+    /// and functions shadowed by a lambda variable are excluded)
+    #[cfg(feature = "cranelift")]
+    pub(crate) fn native_source(&self, name: &str) -> Option<(&[Symbol], &GrammarElement)> {
+        if let Some(Value::Lambda { .. }) = self.lookup_var(name) {
+            return None;
         }
-        Ok(last_value)
+        let function = self.functions.get(&Symbol::lookup(name)?)?;
+        if function.module_name.is_some() || function.captured_env.is_some() {
+            return None;
+        }
+        Some((&function.params, &function.body))
     }
 
-    /// 评估列表 / Evaluate list
-    fn eval_list(&mut self, list: &[GrammarElement]) -> Result<Value, InterpreterError> {
-        if list.is_empty() {
-            return Ok(Value::Null);
+    /// 调用分派内联缓存的命中统计 / Hit statistics of the call dispatch inline cache
+    pub fn call_cache_stats(&self) -> CallCacheStats {
+        CallCacheStats {
+            entries: self.call_cache.len(),
+            ..self.call_cache_stats
         }
+    }
 
-        // 检查是否是特殊形式（支持 Atom 和 Expr(Var(...)) 两种形式）
-        let keyword: Option<&str> = match &list[0] {
-            GrammarElement::Atom(s) => Some(s.as_str()),
-            GrammarElement::Expr(boxed_expr) => {
-                if let Expr::Var(s) = boxed_expr.as_ref() {
-                    Some(s.as_str())
-                } else {
-                    None
+    /// 设置垃圾回收配置 / Set garbage collection configuration
+    pub fn set_gc_config(&mut self, config: GcConfig) {
+        self.gc_threshold = config.threshold;
+        self.gc_config = config;
+    }
+
+    /// 获取垃圾回收配置 / Get garbage collection configuration
+    pub fn gc_config(&self) -> &GcConfig {
+        &self.gc_config
+    }
+
+    /// 垃圾回收统计 / Garbage collection statistics
+    pub fn gc_stats(&self) -> GcStats {
+        GcStats {
+            lambdas: self.lambda_registry.len(),
+            generators: self.generators.len(),
+            next_threshold: self.gc_threshold,
+            pending: self.gc_requested,
+            ..self.gc_stats.clone()
+        }
+    }
+
+    /// 立即回收不可达的Lambda和生成器，返回释放的对象数；
+    /// 应在两次执行之间调用，宿主在变量之外持有的Lambda或生成器值不算作根
+    /// Immediately free unreachable lambdas and generators, returning the number of objects freed;
+    /// call it between executions, as lambda or generator values the host holds outside variables are not roots
+    pub fn collect_garbage(&mut self) -> usize {
+        self.collect_garbage_with(None)
+    }
+
+    /// 标记-清除：根为全局环境、函数的捕获环境、模块和 `extra_root`（刚求值的顶层形式结果）
+    /// Mark-and-sweep: roots are the global environment, captured environments of functions, modules and `extra_root`
+    /// (the result of the top-level form just evaluated)
+    fn collect_garbage_with(&mut self, extra_root: Option<&Value>) -> usize {
+        let mut marker = Marker::default();
+        let functions = self.functions.values().chain(
+            self.modules
+                .values()
+                .flat_map(|module| module.functions.values()),
+        );
+        for function in functions {
+            for value in function.captured_env.iter().flat_map(HashMap::values) {
+                marker.mark(value);
+            }
+        }
+        let environments = std::iter::once(&self.environment)
+            .chain(self.modules.values().map(|module| &module.environment));
+        for value in environments.flat_map(HashMap::values).chain(extra_root) {
+            marker.mark(value);
+        }
+
+        loop {
+            if let Some(id) = marker.next_lambda() {
+                if let Some((_, _, captured_env)) = self.lambda_registry.get(&id) {
+                    for value in captured_env.values() {
+                        marker.mark(value);
+                    }
+                }
+            } else if let Some(id) = marker.next_generator() {
+                let Some(state) = self.generators.get(&id) else {
+                    continue;
+                };
+                for value in state.env.values() {
+                    marker.mark(value);
                 }
+                for frame in &state.stack {
+                    match frame {
+                        GeneratorFrame::For { items, .. } => {
+                            for item in items {
+                                marker.mark(item);
+                            }
+                        }
+                        GeneratorFrame::ForGenerator { id, .. } => {
+                            marker.mark(&Value::Generator { id: id.clone() })
+                        }
+                        GeneratorFrame::Restore { old: Some(old), .. } => marker.mark(old),
+                        GeneratorFrame::Eval(_)
+                        | GeneratorFrame::While { .. }
+                        | GeneratorFrame::Restore { old: None, .. } => {}
+                    }
+                }
+            } else {
+                break;
             }
-            _ => None,
-        };
+        }
 
-        if let Some(keyword) = keyword {
-            match keyword {
-                "def" | "function" => self.eval_def(&list[1..]),
-                "let" => self.eval_let(&list[1..]),
-                "set!" => self.eval_set(&list[1..]),
-                "if" => self.eval_if_special(&list[1..]),
+        let before = self.lambda_registry.len() + self.generators.len();
+        self.lambda_registry
+            .retain(|id, _| marker.is_lambda_live(id));
+        self.generators.retain(|id, _| marker.is_generator_live(id));
+        self.memo_tables.retain(|id, _| marker.is_lambda_live(id));
+        let live = self.lambda_registry.len() + self.generators.len();
+        let freed = before - live;
+
+        self.gc_stats.collections += 1;
+        self.gc_stats.freed += freed as u64;
+        self.gc_stats.last_freed = freed;
+        self.gc_threshold = self.gc_config.next_threshold(live);
+        self.gc_requested = false;
+        freed
+    }
+
+    /// 函数、模块、构造器或宿主函数发生变化：清空内联缓存并使原生代码失效
+    /// Functions, modules, constructors or host functions changed: clear the inline cache and invalidate native code
+    fn dispatch_changed(&mut self) {
+        self.function_generation += 1;
+        self.call_cache.clear();
+    }
+
+    /// 尝试以原生代码调用主作用域函数；没有后端、正在追踪或调试、或后端放弃时返回 None，由解释器执行
+    /// Try to call a main-scope function as native code; returns None when there is no backend, a tracer or
+    /// debug hook is installed, or the backend declines, in which case the interpreter runs the call
+    fn call_native(&mut self, name: &str, args: &[Value]) -> Option<Value> {
+        if self.tracer.is_some() || self.debug_hook.is_some() {
+            return None;
+        }
+        let mut backend = self.native_backend.take()?;
+        let fuel = self
+            .budget
+            .max_steps
+            .map(|max_steps| max_steps.saturating_sub(self.steps));
+        let result = backend.call(self, name, args, fuel);
+        self.native_backend = Some(backend);
+        let (value, steps) = result?;
+        self.steps += steps;
+        Some(value)
+    }
+
+    /// 通知调试钩子；钩子运行期间被取出，因此可以自由使用解释器
+    /// Notify the debug hook; it is taken out while running, so it may use the interpreter freely
+    fn debug_event(&mut self, event: DebugEvent) -> Result<(), InterpreterError> {
+        let Some(mut hook) = self.debug_hook.take() else {
+            return Ok(());
+        };
+        let depth = self.call_stack.len();
+        let result = hook.on_event(self, &event, depth);
+        self.debug_hook = Some(hook);
+        result
+    }
+
+    /// 获取变量值 / Get variable value
+    pub fn get_variable(&self, name: &str) -> Option<&Value> {
+        self.lookup_var(name)
+    }
+
+    /// 设置变量值 / Set variable value
+    pub fn set_variable(&mut self, name: impl AsRef<str>, value: Value) {
+        self.environment
+            .insert(Symbol::intern(name.as_ref()), value);
+    }
+
+    /// 当前可见的变量（按名称排序，不含内部临时变量）/ Currently visible variables (sorted by name, excluding internal temporaries)
+    pub fn variables(&self) -> Vec<(String, Value)> {
+        let mut variables: Vec<(String, Value)> = self
+            .environment
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+            .filter(|(name, _)| !name.starts_with("__"))
+            .map(|(name, value)| (name.to_string(), value.clone()))
+            .collect();
+        variables.sort_by(|a, b| a.0.cmp(&b.0));
+        variables
+    }
+
+    /// 按名称查找变量；模块函数执行时还能看到模块自身的顶层变量和常量
+    /// Look up a variable by name; while a module function runs, the module's own top-level variables and
+    /// constants are visible as well
+    fn lookup_var(&self, name: &str) -> Option<&Value> {
+        self.lookup_symbol(&Symbol::lookup(name)?)
+    }
+
+    /// 按符号查找变量（解析时已驻留的名称不再查符号表）
+    /// Look up a variable by symbol (names interned at parse time skip the symbol table)
+    fn lookup_symbol(&self, symbol: &Symbol) -> Option<&Value> {
+        self.environment.get(symbol).or_else(|| {
+            self.current_module
+                .as_ref()
+                .and_then(|module_name| self.modules.get(module_name))
+                .and_then(|module| module.environment.get(symbol))
+        })
+    }
+
+    /// 计一步求值并检查步数和时间预算 / Count one evaluation step and check step and time budgets
+    fn charge_step(&mut self) -> Result<(), InterpreterError> {
+        self.steps += 1;
+        if let Some(max_steps) = self.budget.max_steps {
+            if self.steps > max_steps {
+                return Err(InterpreterError::budget_exceeded(
+                    BudgetKind::Steps,
+                    max_steps,
+                    None,
+                ));
+            }
+        }
+        // 每256步检查一次时间，避免频繁读取时钟 / Check time every 256 steps to avoid reading the clock too often
+        if let (Some(timeout), Some(started_at)) = (self.budget.timeout, self.started_at) {
+            if self.steps.is_multiple_of(256) && started_at.elapsed() > timeout {
+                return Err(InterpreterError::budget_exceeded(
+                    BudgetKind::Time,
+                    timeout.as_millis() as u64,
+                    None,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查集合大小预算 / Check collection size budget
+    fn check_collection_size(&self, value: &Value) -> Result<(), InterpreterError> {
+        let size = match value {
+            Value::List(items) => items.len(),
+            Value::Dict(map) => map.len(),
+            Value::Set(set) => set.len(),
+            _ => return Ok(()),
+        };
+        match self.budget.max_collection_size {
+            Some(max_size) if size > max_size => Err(InterpreterError::budget_exceeded(
+                BudgetKind::CollectionSize,
+                max_size as u64,
+                None,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// 将程序输出重定向到指定写入器 / Redirect program output to the given writer
+    pub fn set_output<W: Write + Send + 'static>(&mut self, writer: W) {
+        self.output = Some(Arc::new(Mutex::new(writer)));
+    }
+
+    /// 设置输出目标（None恢复为标准输出）/ Set output sink (None restores stdout)
+    pub fn set_output_sink(&mut self, sink: Option<OutputSink>) {
+        self.output = sink;
+    }
+
+    /// 获取当前输出目标 / Get current output sink
+    pub fn output_sink(&self) -> Option<OutputSink> {
+        self.output.clone()
+    }
+
+    /// 写入一行输出 / Write a line of output
+    fn write_output(&self, line: &str) -> Result<(), InterpreterError> {
+        let result = match &self.output {
+            Some(sink) => {
+                let mut writer = sink.lock().unwrap_or_else(|e| e.into_inner());
+                writeln!(writer, "{}", line).and_then(|_| writer.flush())
+            }
+            None => {
+                let mut stdout = std::io::stdout();
+                // 强制刷新输出缓冲区 / Force flush output buffer
+                writeln!(stdout, "{}", line).and_then(|_| stdout.flush())
+            }
+        };
+        result.map_err(|e| {
+            InterpreterError::runtime_error(format!("Failed to write output: {}", e), None)
+        })
+    }
+
+    /// 添加模块搜索路径（优先于默认目录）/ Add module search path (searched before defaults)
+    pub fn add_module_path(&mut self, path: impl Into<PathBuf>) {
+        self.module_paths.push(path.into());
+    }
+
+    /// 获取完整的模块搜索路径 / Get the full module search path
+    pub fn module_paths(&self) -> Vec<PathBuf> {
+        self.module_paths
+            .iter()
+            .cloned()
+            .chain(DEFAULT_MODULE_DIRS.iter().map(PathBuf::from))
+            .collect()
+    }
+
+    /// 设置当前代码所在目录，相对导入（`./`、`../`）以此为基准
+    /// Set directory of the executing code; relative imports (`./`, `../`) resolve against it
+    pub fn set_base_dir(&mut self, dir: impl Into<PathBuf>) {
+        self.base_dir = Some(dir.into());
+    }
+
+    /// 创建状态快照 / Create state snapshot
+    pub fn snapshot(&self) -> InterpreterSnapshot {
+        InterpreterSnapshot {
+            environment: self.environment.clone(),
+            functions: self.functions.clone(),
+            modules: self.modules.clone(),
+            lambda_registry: self.lambda_registry.clone(),
+            lambda_counter: self.lambda_counter,
+            overflow_mode: self.overflow_mode,
+            module_aliases: self.module_aliases.clone(),
+            native_aliases: self.native_aliases.clone(),
+            selective_imports: self.selective_imports.clone(),
+            variant_constructors: self.variant_constructors.clone(),
+            protocols: self.protocols.clone(),
+            generators: self.generators.clone(),
+            generator_counter: self.generator_counter,
+            grammar_version: self.grammar_version,
+            constants: self.constants.clone(),
+            private: self.private.clone(),
+        }
+    }
+
+    /// 从快照恢复状态 / Restore state from snapshot
+    pub fn restore(&mut self, snapshot: InterpreterSnapshot) {
+        self.environment = snapshot.environment;
+        self.functions = snapshot.functions;
+        self.dispatch_changed();
+        self.modules = snapshot.modules;
+        self.lambda_registry = snapshot.lambda_registry;
+        self.lambda_counter = snapshot.lambda_counter;
+        self.overflow_mode = snapshot.overflow_mode;
+        self.module_aliases = snapshot.module_aliases;
+        self.native_aliases = snapshot.native_aliases;
+        self.selective_imports = snapshot.selective_imports;
+        self.variant_constructors = snapshot.variant_constructors;
+        self.protocols = builtin_protocols();
+        self.protocols.extend(snapshot.protocols);
+        self.generators = snapshot.generators;
+        self.generator_counter = snapshot.generator_counter;
+        self.grammar_version = snapshot.grammar_version;
+        self.constants = snapshot.constants;
+        self.private = snapshot.private;
+        self.current_module = None;
+    }
+
+    /// 保存解释器状态到文件（JSON）/ Save interpreter state to file (JSON)
+    pub fn save_state(&self, path: impl AsRef<Path>) -> Result<(), InterpreterError> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(&self.snapshot()).map_err(|e| {
+            InterpreterError::runtime_error(format!("Failed to serialize state: {}", e), None)
+        })?;
+        fs::write(path, json).map_err(|e| {
+            InterpreterError::runtime_error(
+                format!("Failed to write state to '{}': {}", path.display(), e),
+                None,
+            )
+        })
+    }
+
+    /// 从文件加载解释器状态（替换当前状态）/ Load interpreter state from file (replaces current state)
+    pub fn load_state(&mut self, path: impl AsRef<Path>) -> Result<(), InterpreterError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(|e| {
+            InterpreterError::runtime_error(
+                format!("Failed to read state from '{}': {}", path.display(), e),
+                None,
+            )
+        })?;
+        let snapshot: InterpreterSnapshot = serde_json::from_str(&json).map_err(|e| {
+            InterpreterError::runtime_error(format!("Failed to parse state: {}", e), None)
+        })?;
+        self.restore(snapshot);
+        Ok(())
+    }
+
+    /// 注册内置函数 / Register built-in functions
+    fn register_builtins(&mut self) {
+        // 内置函数会在函数调用时处理
+        // 原生模块在导入时生效 / Native modules take effect on import
+        self.native_modules = Arc::new(stdlib::native_modules());
+        // 使用chrono获取时间，在WebAssembly中同样可用 / Use chrono for time, which also works in WebAssembly
+        let nanos = chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        self.seed_random(nanos as u64);
+    }
+
+    /// 设置随机数种子（用于可复现的运行）/ Seed the random number generator (for reproducible runs)
+    pub fn seed_random(&mut self, seed: u64) {
+        // 状态不能为0 / State must be non-zero
+        self.rng_state = seed ^ 0x9E37_79B9_7F4A_7C15;
+        if self.rng_state == 0 {
+            self.rng_state = 0x9E37_79B9_7F4A_7C15;
+        }
+    }
+
+    /// 生成下一个随机数 / Generate the next random number
+    pub(crate) fn next_random(&mut self) -> u64 {
+        let mut x = self.rng_state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.rng_state = x;
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// 先运行优化遍再执行代码 / Run the optimizer passes, then execute code
+    pub fn execute_optimized(&mut self, ast: &[GrammarElement]) -> Result<Value, InterpreterError> {
+        let optimized = crate::optimizer::passes::optimize(ast);
+        self.execute(&optimized.ast)
+    }
+
+    /// 执行代码 / Execute code
+    pub fn execute(&mut self, ast: &[GrammarElement]) -> Result<Value, InterpreterError> {
+        self.begin_execution();
+        let mut last_value = Value::Null;
+
+        for (index, element) in ast.iter().enumerate() {
+            if self.debug_hook.is_some() {
+                self.debug_event(DebugEvent::Form { index })?;
+            }
+            self.error_trace = ErrorTrace::default();
+            let result = self.eval_element(element);
+            if self.usage_observer.is_some() {
+                self.report_usage(element, &result);
+            }
+            if result.is_err() {
+                self.error_trace.form = Some(index);
+            }
+            last_value = result?;
+
+            // 顶层形式之间没有求值中的临时值，是回收的安全点
+            // No temporaries are in flight between top-level forms, so this is a safe point to collect
+            let objects = self.lambda_registry.len() + self.generators.len();
+            if self.gc_requested || self.gc_threshold.is_some_and(|t| objects >= t) {
+                self.collect_garbage_with(Some(&last_value));
+            }
+        }
+
+        Ok(last_value)
+    }
+
+    /// 对一段代码做基准测试：每次迭代都完整执行一遍 `ast`（预算按迭代重置）
+    /// Benchmark code: every iteration executes `ast` in full (the budget is reset per iteration)
+    pub fn benchmark(
+        &mut self,
+        ast: &[GrammarElement],
+        config: &BenchConfig,
+    ) -> Result<BenchStats, InterpreterError> {
+        bench::bench(config, || self.execute(ast).map(|_| ()))
+    }
+
+    /// 剖析一段代码：执行期间临时安装 Profiler，返回填好总耗时和步数的报告
+    /// Profile code: temporarily install a Profiler while executing and return the report with total time and steps filled in
+    pub fn profile(&mut self, ast: &[GrammarElement]) -> Result<ProfileReport, InterpreterError> {
+        let profiler = Profiler::new();
+        let outer_tracer = self.tracer.replace(Box::new(profiler.clone()));
+        let started_at = Timestamp::now();
+
+        let result = self.execute(ast);
+
+        let total_time = started_at.elapsed();
+        self.tracer = outer_tracer;
+        result?;
+        let mut report = profiler.report();
+        report.total_time = total_time;
+        report.steps = self.steps;
+        Ok(report)
+    }
+
+    /// 在执行过程中求值（不重置预算计数，供调试器在暂停时使用）
+    /// Evaluate mid-execution without resetting the budget counters (used by the debugger while paused)
+    pub(crate) fn evaluate_paused(
+        &mut self,
+        element: &GrammarElement,
+    ) -> Result<Value, InterpreterError> {
+        self.eval_element(element)
+    }
+
+    /// 执行单个表达式 / Execute single expression
+    pub fn execute_expr(&mut self, expr: &Expr) -> Result<Value, InterpreterError> {
+        self.begin_execution();
+        self.eval_expr(expr)
+    }
+
+    /// 重置本次执行的预算计数 / Reset budget counters for a new execution
+    fn begin_execution(&mut self) {
+        self.steps = 0;
+        // 仅在设置超时时读取时钟（WebAssembly中不支持Instant）
+        // Only read the clock when a timeout is set (Instant is unsupported in WebAssembly)
+        self.started_at = self.budget.timeout.map(|_| std::time::Instant::now());
+    }
+
+    /// 评估语法元素 / Evaluate grammar element
+    fn eval_element(&mut self, element: &GrammarElement) -> Result<Value, InterpreterError> {
+        self.charge_step()?;
+        match element {
+            GrammarElement::Expr(expr) => self.eval_expr(expr),
+            GrammarElement::List(list) => self.eval_list(list),
+            GrammarElement::Atom(atom) => {
+                // 检查是否是关键字或变量
+                match atom.as_str() {
+                    "def" | "function" | "let" | "if" => Err(InterpreterError::runtime_error(
+                        "Special forms must be in a list".to_string(),
+                        None,
+                    )),
+                    _ => {
+                        // 尝试作为变量查找
+                        self.lookup_var(atom)
+                            .cloned()
+                            .ok_or_else(|| InterpreterError::undefined_variable(atom.clone(), None))
+                    }
+                }
+            }
+            GrammarElement::NaturalLang(_) => Err(InterpreterError::runtime_error(
+                "Natural language not supported in execution".to_string(),
+                None,
+            )),
+        }
+    }
+
+    /// 评估表达式列表（用于处理多个表达式） / Evaluate expression list
+    fn eval_expr_list(&mut self, exprs: &[Expr]) -> Result<Value, InterpreterError> {
+        let mut last_value = Value::Null;
+        for expr in exprs {
+            last_value = self.eval_expr(expr)?;
+        }
+        Ok(last_value)
+    }
+
+    /// 评估列表 / Evaluate list
+    fn eval_list(&mut self, list: &[GrammarElement]) -> Result<Value, InterpreterError> {
+        if list.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        // 检查是否是特殊形式（支持 Atom 和 Expr(Var(...)) 两种形式）
+        let keyword: Option<&str> = match &list[0] {
+            GrammarElement::Atom(s) => Some(s.as_str()),
+            GrammarElement::Expr(boxed_expr) => {
+                if let Expr::Var(s) = boxed_expr.as_ref() {
+                    Some(s.as_str())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+
+        if let Some(keyword) = keyword {
+            if self.usage_observer.is_some() {
+                self.note_construct(match keyword {
+                    "def" | "function" | "defn-" => "def",
+                    "let" | "defconst" => "let",
+                    "set!" => "assign",
+                    "if" => "if",
+                    "lambda" => "lambda",
+                    "for" => "for",
+                    "while" => "while",
+                    "list" | "vec" | "dict" => "collection",
+                    _ => "call",
+                });
+            }
+            match keyword {
+                "def" | "function" => self.eval_def(&list[1..]),
+                "defn-" => self.eval_private_def(&list[1..]),
+                "defconst" => self.eval_defconst(&list[1..]),
+                "defprotocol" => self.eval_defprotocol(&list[1..]),
+                "defimpl" => self.eval_defimpl(&list[1..]),
+                "let" => self.eval_let(&list[1..]),
+                "set!" => self.eval_set(&list[1..]),
+                "if" => self.eval_if_special(&list[1..]),
                 "lambda" => self.eval_lambda(&list[1..]),
                 "for" => self.eval_for_special(&list[1..]),
                 "while" => self.eval_while_special(&list[1..]),
@@ -168,7 +1509,7 @@ impl Interpreter {
                     for elem in &list[1..] {
                         items.push(self.eval_element(elem)?);
                     }
-                    Ok(Value::List(items))
+                    Ok(Value::List(items.into()))
                 }
                 "dict" => {
                     // 字典字面量：解析为 Literal::Dict
@@ -179,7 +1520,7 @@ impl Interpreter {
                             None,
                         ));
                     }
-                    let mut dict = std::collections::HashMap::new();
+                    let mut dict = DictMap::new();
                     for i in (1..list.len()).step_by(2) {
                         let key_elem = &list[i];
                         let value_elem = &list[i + 1];
@@ -187,7 +1528,7 @@ impl Interpreter {
                             GrammarElement::Atom(s) => s.clone(),
                             GrammarElement::Expr(boxed_expr) => {
                                 if let Expr::Var(s) = boxed_expr.as_ref() {
-                                    s.clone()
+                                    s.to_string()
                                 } else if let Expr::Literal(Literal::String(s)) =
                                     boxed_expr.as_ref()
                                 {
@@ -209,7 +1550,7 @@ impl Interpreter {
                         let value = self.eval_element(value_elem)?;
                         dict.insert(key, value);
                     }
-                    Ok(Value::Dict(dict))
+                    Ok(Value::Dict(dict.into()))
                 }
                 _ => {
                     // 尝试作为函数调用
@@ -221,8 +1562,7 @@ impl Interpreter {
                     // Check if function name is a Lambda value in environment (when function name is a variable)
                     // 这包括函数参数中的 lambda（如 map 函数的 func 参数）
                     // This includes lambdas in function parameters (like the func parameter in map function)
-                    if let Some(Value::Lambda { id, params }) =
-                        self.environment.get(&func_name).cloned()
+                    if let Some(Value::Lambda { id, params }) = self.lookup_var(&func_name).cloned()
                     {
                         // 函数名是 Lambda 值，需要先评估参数，然后调用 Lambda
                         // Function name is Lambda value, need to evaluate arguments first, then call Lambda
@@ -240,13 +1580,18 @@ impl Interpreter {
                                 // Lambda 值需要存储到环境中
                                 // Lambda values need to be stored in environment
                                 let temp_name = format!("__lambda_arg_{}", arg_exprs.len());
-                                self.environment.insert(temp_name.clone(), val);
-                                arg_exprs.push(Expr::Var(temp_name));
+                                self.environment.insert(Symbol::intern(&temp_name), val);
+                                arg_exprs.push(Expr::Var(temp_name.into()));
                             } else {
-                                arg_exprs.push(self.value_to_expr(val)?);
+                                arg_exprs.push(Self::value_to_expr(val)?);
                             }
                         }
-                        return self.call_lambda(&id, &params, &arg_exprs);
+                        return self.call_lambda(
+                            &Symbol::intern(&func_name),
+                            &id,
+                            &params,
+                            &arg_exprs,
+                        );
                     }
 
                     // 检查是否需要先评估参数（包含 list/dict 字面量时）
@@ -254,8 +1599,7 @@ impl Interpreter {
                     // 注意：如果函数名是 lambda，不需要检查字面量，因为 lambda 调用会直接处理参数
                     // Note: If function name is lambda, don't check for literals, as lambda call will handle arguments directly
                     let needs_evaluation = if self
-                        .environment
-                        .get(&func_name)
+                        .lookup_var(&func_name)
                         .map(|v| matches!(v, Value::Lambda { .. }))
                         .unwrap_or(false)
                     {
@@ -288,8 +1632,7 @@ impl Interpreter {
                             // 先检查是否是变量，如果是变量且环境中是 Lambda 值，则直接使用变量名
                             // First check if it's a variable, if it's a variable and environment has Lambda value, use variable name directly
                             let is_lambda_var = if let GrammarElement::Atom(var_name) = elem {
-                                self.environment
-                                    .get(var_name)
+                                self.lookup_var(var_name)
                                     .map(|v| matches!(v, Value::Lambda { .. }))
                                     .unwrap_or(false)
                             } else {
@@ -300,17 +1643,17 @@ impl Interpreter {
                                 // 变量是 Lambda 值，直接使用变量名（在 eval_call 中会从环境中获取）
                                 // Variable is Lambda value, use variable name directly (will be retrieved from environment in eval_call)
                                 if let GrammarElement::Atom(var_name) = elem {
-                                    arg_exprs.push(Expr::Var(var_name.clone()));
+                                    arg_exprs.push(Expr::Var(var_name.as_str().into()));
                                 } else {
                                     // Should not happen, but handle it
                                     let value = self.eval_element(elem)?;
                                     if let Value::Lambda { .. } = value {
                                         let temp_name =
                                             format!("__lambda_temp_{}", arg_exprs.len());
-                                        self.environment.insert(temp_name.clone(), value);
-                                        arg_exprs.push(Expr::Var(temp_name));
+                                        self.environment.insert(Symbol::intern(&temp_name), value);
+                                        arg_exprs.push(Expr::Var(temp_name.into()));
                                     } else {
-                                        arg_exprs.push(self.value_to_expr(value)?);
+                                        arg_exprs.push(Self::value_to_expr(value)?);
                                     }
                                 }
                             } else {
@@ -321,12 +1664,12 @@ impl Interpreter {
                                     // Lambda 值需要存储到环境中
                                     // Lambda values need to be stored in environment
                                     let temp_name = format!("__lambda_temp_{}", arg_exprs.len());
-                                    self.environment.insert(temp_name.clone(), value);
-                                    arg_exprs.push(Expr::Var(temp_name));
+                                    self.environment.insert(Symbol::intern(&temp_name), value);
+                                    arg_exprs.push(Expr::Var(temp_name.into()));
                                 } else {
                                     // 对于非 Lambda 值，直接转换为 Expr
                                     // For non-Lambda values, directly convert to Expr
-                                    arg_exprs.push(self.value_to_expr(value)?);
+                                    arg_exprs.push(Self::value_to_expr(value)?);
                                 }
                             }
                         }
@@ -371,12 +1714,13 @@ impl Interpreter {
                                     // Lambda 值需要存储到环境中
                                     // Lambda values need to be stored in environment
                                     let temp_name = format!("__lambda_temp_{}", idx);
-                                    self.environment.insert(temp_name.clone(), val.clone());
-                                    arg_exprs.push(Expr::Var(temp_name));
+                                    self.environment
+                                        .insert(Symbol::intern(&temp_name), val.clone());
+                                    arg_exprs.push(Expr::Var(temp_name.into()));
                                 } else {
                                     // 其他值转换为 Expr
                                     // Other values convert to Expr
-                                    arg_exprs.push(self.value_to_expr(val.clone())?);
+                                    arg_exprs.push(Self::value_to_expr(val.clone())?);
                                 }
                             }
                             arg_exprs
@@ -390,8 +1734,7 @@ impl Interpreter {
                                 // 先检查是否是变量，如果是变量且环境中是 Lambda 值，则直接使用变量名
                                 // First check if it's a variable, if it's a variable and environment has Lambda value, use variable name directly
                                 let is_lambda_var = if let GrammarElement::Atom(var_name) = elem {
-                                    self.environment
-                                        .get(var_name)
+                                    self.lookup_var(var_name)
                                         .map(|v| matches!(v, Value::Lambda { .. }))
                                         .unwrap_or(false)
                                 } else {
@@ -402,15 +1745,16 @@ impl Interpreter {
                                     // 变量是 Lambda 值，直接使用变量名（在 eval_call 中会从环境中获取）
                                     // Variable is Lambda value, use variable name directly (will be retrieved from environment in eval_call)
                                     if let GrammarElement::Atom(var_name) = elem {
-                                        converted_args.push(Expr::Var(var_name.clone()));
+                                        converted_args.push(Expr::Var(var_name.as_str().into()));
                                     } else {
                                         // Should not happen, but handle it
                                         let value = self.eval_element(elem)?;
                                         if let Value::Lambda { .. } = value {
                                             let temp_name =
                                                 format!("__lambda_temp_{}", converted_args.len());
-                                            self.environment.insert(temp_name.clone(), value);
-                                            converted_args.push(Expr::Var(temp_name));
+                                            self.environment
+                                                .insert(Symbol::intern(&temp_name), value);
+                                            converted_args.push(Expr::Var(temp_name.into()));
                                         } else {
                                             converted_args
                                                 .extend(self.values_to_exprs(vec![value])?);
@@ -425,8 +1769,8 @@ impl Interpreter {
                                         // Lambda values need to be stored in environment
                                         let temp_name =
                                             format!("__lambda_temp_{}", converted_args.len());
-                                        self.environment.insert(temp_name.clone(), value);
-                                        converted_args.push(Expr::Var(temp_name));
+                                        self.environment.insert(Symbol::intern(&temp_name), value);
+                                        converted_args.push(Expr::Var(temp_name.into()));
                                     } else {
                                         // 对于非 Lambda 值，直接转换为 Expr
                                         // For non-Lambda values, directly convert to Expr
@@ -437,7 +1781,7 @@ impl Interpreter {
                             converted_args
                         }
                     };
-                    self.eval_call(&func_name, &args)
+                    self.eval_call(&Symbol::intern(&func_name), &args)
                 }
             }
         } else {
@@ -456,7 +1800,7 @@ impl Interpreter {
     fn element_to_expr(&self, element: &GrammarElement) -> Result<Expr, ()> {
         match element {
             GrammarElement::Expr(e) => Ok(*e.clone()),
-            GrammarElement::Atom(s) => Ok(Expr::Var(s.clone())),
+            GrammarElement::Atom(s) => Ok(Expr::Var(s.as_str().into())),
             GrammarElement::List(l) => {
                 // 对于 List，我们需要先评估它，然后转换为字面量
                 // 但这需要 mut self，所以这里我们只处理简单情况
@@ -490,7 +1834,7 @@ impl Interpreter {
     fn values_to_exprs(&self, values: Vec<Value>) -> Result<Vec<Expr>, InterpreterError> {
         let mut exprs = Vec::new();
         for value in values {
-            exprs.push(self.value_to_expr(value)?);
+            exprs.push(Self::value_to_expr(value)?);
         }
         Ok(exprs)
     }
@@ -502,6 +1846,9 @@ impl Interpreter {
                 Literal::Int(i) => Ok(GrammarElement::Expr(Box::new(Expr::Literal(Literal::Int(
                     *i,
                 ))))),
+                Literal::BigInt(n) => Ok(GrammarElement::Expr(Box::new(Expr::Literal(
+                    Literal::BigInt(n.clone()),
+                )))),
                 Literal::Float(f) => Ok(GrammarElement::Expr(Box::new(Expr::Literal(
                     Literal::Float(*f),
                 )))),
@@ -513,14 +1860,14 @@ impl Interpreter {
                 )))),
                 Literal::Null => Ok(GrammarElement::Expr(Box::new(Expr::Literal(Literal::Null)))),
                 Literal::List(items) => {
-                    let mut elements = Vec::new();
+                    let mut elements = vec![GrammarElement::Atom("list".to_string())];
                     for item in items {
                         elements.push(self.expr_to_element(item)?);
                     }
                     Ok(GrammarElement::List(elements))
                 }
                 Literal::Dict(pairs) => {
-                    let mut elements = Vec::new();
+                    let mut elements = vec![GrammarElement::Atom("dict".to_string())];
                     for (key, val) in pairs {
                         elements.push(GrammarElement::Atom(key.clone()));
                         elements.push(self.expr_to_element(val)?);
@@ -528,9 +1875,9 @@ impl Interpreter {
                     Ok(GrammarElement::List(elements))
                 }
             },
-            Expr::Var(name) => Ok(GrammarElement::Atom(name.clone())),
+            Expr::Var(name) => Ok(GrammarElement::Atom(name.to_string())),
             Expr::Call(name, args) => {
-                let mut elements = vec![GrammarElement::Atom(name.clone())];
+                let mut elements = vec![GrammarElement::Atom(name.to_string())];
                 for arg in args {
                     elements.push(self.expr_to_element(arg)?);
                 }
@@ -603,26 +1950,27 @@ impl Interpreter {
             }
             Expr::Assign(var, expr) => Ok(GrammarElement::List(vec![
                 GrammarElement::Atom("set!".to_string()),
-                GrammarElement::Atom(var.clone()),
+                GrammarElement::Atom(var.to_string()),
                 self.expr_to_element(expr)?,
             ])),
         }
     }
 
     /// 将值转换为表达式 / Convert value to expression
-    fn value_to_expr(&self, value: Value) -> Result<Expr, InterpreterError> {
+    pub(crate) fn value_to_expr(value: Value) -> Result<Expr, InterpreterError> {
         match value {
             Value::Int(i) => Ok(Expr::Literal(Literal::Int(i))),
+            Value::BigInt(n) => Ok(Expr::Literal(Literal::BigInt(n))),
             Value::Float(f) => Ok(Expr::Literal(Literal::Float(f))),
-            Value::String(s) => Ok(Expr::Literal(Literal::String(s))),
+            Value::String(s) => Ok(Expr::Literal(Literal::String(s.to_string()))),
             Value::Bool(b) => Ok(Expr::Literal(Literal::Bool(b))),
             Value::Null => Ok(Expr::Literal(Literal::Null)),
             Value::List(items) => {
                 // 递归转换列表中的每个元素
                 // Recursively convert each element in the list
                 let mut expr_items = Vec::new();
-                for item in items {
-                    expr_items.push(self.value_to_expr(item)?);
+                for item in Arc::unwrap_or_clone(items) {
+                    expr_items.push(Self::value_to_expr(item)?);
                 }
                 Ok(Expr::Literal(Literal::List(expr_items)))
             }
@@ -630,11 +1978,44 @@ impl Interpreter {
                 // 递归转换字典中的每个值
                 // Recursively convert each value in the dict
                 let mut pairs = Vec::new();
-                for (key, val) in dict {
-                    pairs.push((key, self.value_to_expr(val)?));
+                for (key, val) in Arc::unwrap_or_clone(dict) {
+                    pairs.push((key, Self::value_to_expr(val)?));
                 }
                 Ok(Expr::Literal(Literal::Dict(pairs)))
             }
+            // 集合通过 set 调用重建 / Sets are rebuilt through a set call
+            Value::Set(set) => Ok(Expr::Call(
+                "set".into(),
+                set.iter()
+                    .cloned()
+                    .map(Self::value_to_expr)
+                    .collect::<Result<_, _>>()?,
+            )),
+            Value::Variant { tag, fields } => {
+                // 变体通过构造器调用重建 / Variants are rebuilt through a constructor call
+                let mut args = Vec::new();
+                for field in fields {
+                    args.push(Self::value_to_expr(field)?);
+                }
+                Ok(Expr::Call(tag.into(), args))
+            }
+            // 日期通过 parse-date 重建 / Dates are rebuilt through parse-date
+            Value::DateTime(date) => Ok(Expr::Call(
+                "parse-date".into(),
+                vec![Expr::Literal(Literal::String(date.to_rfc3339()))],
+            )),
+            Value::Error {
+                kind,
+                message,
+                data,
+            } => Ok(Expr::Call(
+                "error".into(),
+                vec![
+                    Expr::Literal(Literal::String(kind)),
+                    Expr::Literal(Literal::String(message)),
+                    Self::value_to_expr(*data)?,
+                ],
+            )),
             Value::Lambda { .. } => {
                 // Lambda 值无法转换为 Expr，这是一个限制
                 // Lambda values cannot be converted to Expr, this is a limitation
@@ -644,11 +2025,69 @@ impl Interpreter {
                     None,
                 ))
             }
+            Value::Generator { .. } => Err(InterpreterError::runtime_error(
+                "Generator values cannot be converted to expressions in function arguments"
+                    .to_string(),
+                None,
+            )),
         }
     }
 
     /// 评估函数定义 / Evaluate function definition
     fn eval_def(&mut self, rest: &[GrammarElement]) -> Result<Value, InterpreterError> {
+        let (name, function) = Self::function_definition(rest)?;
+        self.dispatch_changed();
+        self.functions.insert(Symbol::intern(&name), function);
+        Ok(Value::Null)
+    }
+
+    /// 评估私有函数定义：与 `def` 相同，但函数不会被导入模块的代码看到
+    /// Evaluate private function definition: like `def`, but the function is hidden from code importing the module
+    fn eval_private_def(&mut self, rest: &[GrammarElement]) -> Result<Value, InterpreterError> {
+        let (name, function) = Self::function_definition(rest)?;
+        self.dispatch_changed();
+        self.functions.insert(Symbol::intern(&name), function);
+        self.private.insert(name);
+        Ok(Value::Null)
+    }
+
+    /// 评估常量定义 / Evaluate constant definition
+    /// 语法: (defconst NAME value)，之后的 `set!`、无body的 `let` 和重复定义都会报错
+    /// Syntax: (defconst NAME value); a later `set!`, body-less `let` or repeated definition is an error
+    fn eval_defconst(&mut self, rest: &[GrammarElement]) -> Result<Value, InterpreterError> {
+        let Some(name) = rest
+            .first()
+            .and_then(element_name)
+            .filter(|_| rest.len() == 2)
+        else {
+            return Err(InterpreterError::runtime_error(
+                "defconst requires: name, value".to_string(),
+                None,
+            ));
+        };
+        let name = Symbol::intern(&name);
+        self.check_assignable(&name)?;
+        let value = self.eval_element(&rest[1])?;
+        self.environment.insert(name.clone(), value);
+        self.constants.insert(name);
+        Ok(Value::Null)
+    }
+
+    /// 常量不能重新赋值 / Constants cannot be reassigned
+    fn check_assignable(&self, name: &Symbol) -> Result<(), InterpreterError> {
+        if self.constants.contains(name) {
+            return Err(InterpreterError::runtime_error(
+                format!("Cannot reassign constant '{}'", name),
+                None,
+            ));
+        }
+        Ok(())
+    }
+
+    /// 由 `def` 的名称、参数和函数体构造函数 / Build a function from the name, parameters and body of a `def`
+    fn function_definition(
+        rest: &[GrammarElement],
+    ) -> Result<(String, Function), InterpreterError> {
         if rest.len() < 3 {
             return Err(InterpreterError::runtime_error(
                 "Function definition requires: name, params, body".to_string(),
@@ -661,7 +2100,7 @@ impl Interpreter {
             GrammarElement::Atom(s) => s.clone(),
             GrammarElement::Expr(boxed_expr) => {
                 if let Expr::Var(s) = boxed_expr.as_ref() {
-                    s.clone()
+                    s.to_string()
                 } else {
                     return Err(InterpreterError::runtime_error(
                         "Function name must be an atom or variable".to_string(),
@@ -685,7 +2124,7 @@ impl Interpreter {
                     GrammarElement::Atom(s) => Ok(s.clone()),
                     GrammarElement::Expr(boxed_expr) => {
                         if let Expr::Var(s) = boxed_expr.as_ref() {
-                            Ok(s.clone())
+                            Ok(s.to_string())
                         } else {
                             Err(InterpreterError::runtime_error(
                                 format!(
@@ -710,35 +2149,294 @@ impl Interpreter {
             }
         };
 
-        // 获取函数体
+        // 获取函数体；文档字符串位于函数体（和类型签名）之后
+        // Get the function body; the docstring follows the body (and type signature)
         let body = rest[2].clone();
+        let doc = rest
+            .iter()
+            .skip(3)
+            .find_map(string_literal)
+            .map(str::to_string);
+
+        let function = Function {
+            params: params.iter().map(Symbol::from).collect(),
+            body,
+            captured_env: None,
+            module_name: None, // 主作用域的函数没有模块名
+            doc,
+        };
+        Ok((name, function))
+    }
 
-        // 注册函数
-        self.functions.insert(
-            name.clone(),
-            Function {
-                params,
-                body,
-                captured_env: None,
-                module_name: None, // 主作用域的函数没有模块名
-            },
-        );
+    /// 评估协议定义 / Evaluate protocol definition
+    /// 语法: (defprotocol Printable (to-text (self)))，每个方法成为按第一个参数的类型分派的函数
+    /// Syntax: (defprotocol Printable (to-text (self))); each method becomes a function dispatching on the type of its first argument
+    fn eval_defprotocol(&mut self, rest: &[GrammarElement]) -> Result<Value, InterpreterError> {
+        let Some((GrammarElement::Atom(name), declarations)) = rest.split_first() else {
+            return Err(InterpreterError::runtime_error(
+                "defprotocol requires a protocol name followed by methods".to_string(),
+                None,
+            ));
+        };
+        let mut methods = Vec::new();
+        for declaration in declarations {
+            let GrammarElement::List(parts) = declaration else {
+                return Err(InterpreterError::runtime_error(
+                    format!("Invalid method in defprotocol {}", name),
+                    None,
+                ));
+            };
+            let Some(GrammarElement::Atom(method)) = parts.first() else {
+                return Err(InterpreterError::runtime_error(
+                    format!("Invalid method in defprotocol {}", name),
+                    None,
+                ));
+            };
+            if let Some((owner, _)) = self.protocol_of(method).filter(|(owner, _)| owner != name) {
+                return Err(InterpreterError::runtime_error(
+                    format!("Method {} already belongs to protocol {}", method, owner),
+                    None,
+                ));
+            }
+            let arity = match parts.get(1) {
+                Some(GrammarElement::List(params)) => params.len(),
+                _ => 0,
+            };
+            methods.push((method.clone(), arity));
+        }
 
+        self.protocols.entry(name.clone()).or_default().methods = methods;
+        self.dispatch_changed();
         Ok(Value::Null)
     }
 
-    /// 评估let绑定 / Evaluate let binding
-    fn eval_let(&mut self, rest: &[GrammarElement]) -> Result<Value, InterpreterError> {
-        if rest.len() < 2 {
+    /// 评估协议实现 / Evaluate protocol implementation
+    /// 语法: (defimpl Printable Point (to-text (self) ...))，类型名是 `deftype` 定义的类型或 Int、String、List 等内置类型
+    /// Syntax: (defimpl Printable Point (to-text (self) ...)); the type is one defined by `deftype` or a built-in such as Int, String or List
+    fn eval_defimpl(&mut self, rest: &[GrammarElement]) -> Result<Value, InterpreterError> {
+        let [GrammarElement::Atom(protocol_name), GrammarElement::Atom(type_name), definitions @ ..] =
+            rest
+        else {
             return Err(InterpreterError::runtime_error(
-                "Let requires at least: name, value".to_string(),
+                "defimpl requires a protocol name, a type name and methods".to_string(),
                 None,
             ));
-        }
+        };
+        let Some(protocol) = self.protocols.get(protocol_name) else {
+            return Err(InterpreterError::runtime_error(
+                format!("Unknown protocol {}", protocol_name),
+                None,
+            ));
+        };
 
-        // 获取变量名（支持 Atom 和 Expr(Var(...)) 两种形式）
-        let name = match &rest[0] {
-            GrammarElement::Atom(s) => s.clone(),
+        let mut implementations = HashMap::new();
+        for definition in definitions {
+            let GrammarElement::List(parts) = definition else {
+                return Err(InterpreterError::runtime_error(
+                    format!("Invalid method in defimpl {} {}", protocol_name, type_name),
+                    None,
+                ));
+            };
+            let (method, function) = Self::function_definition(&parts[1..])?;
+            match protocol.methods.iter().find(|(name, _)| *name == method) {
+                Some((_, arity)) if *arity == function.params.len() => {}
+                Some((_, arity)) => {
+                    return Err(InterpreterError::runtime_error(
+                        format!(
+                            "{}/{} takes {} parameter(s), the implementation for {} has {}",
+                            protocol_name,
+                            method,
+                            arity,
+                            type_name,
+                            function.params.len()
+                        ),
+                        None,
+                    ))
+                }
+                None => {
+                    return Err(InterpreterError::runtime_error(
+                        format!("{} is not a method of protocol {}", method, protocol_name),
+                        None,
+                    ))
+                }
+            }
+            implementations.insert(method, Arc::new(function));
+        }
+
+        if let Some(protocol) = self.protocols.get_mut(protocol_name) {
+            protocol
+                .impls
+                .entry(type_name.clone())
+                .or_default()
+                .extend(implementations);
+        }
+        self.dispatch_changed();
+        Ok(Value::Null)
+    }
+
+    /// 方法所属的协议和参数个数 / Protocol a method belongs to, with its parameter count
+    fn protocol_of(&self, method: &str) -> Option<(String, usize)> {
+        self.protocols.iter().find_map(|(name, protocol)| {
+            protocol
+                .methods
+                .iter()
+                .find(|(candidate, _)| candidate == method)
+                .map(|(_, arity)| (name.clone(), *arity))
+        })
+    }
+
+    /// 按接收者的类型查找方法实现：先用户实现，后内置类型的原生实现
+    /// Find a method implementation by the receiver's type: user implementations first, then native ones for built-in types
+    fn find_method(&self, method: &str, receiver: &Value) -> Option<MethodImpl> {
+        let type_name = self.value_type_name(receiver);
+        self.protocols
+            .values()
+            .find_map(|protocol| protocol.impls.get(type_name)?.get(method))
+            .map(|function| MethodImpl::User(function.clone()))
+            .or_else(|| native_method(method, type_name).map(MethodImpl::Native))
+    }
+
+    /// 调用方法实现 / Call a method implementation
+    fn call_method(
+        &mut self,
+        method: &str,
+        implementation: MethodImpl,
+        args: &[Value],
+    ) -> Result<Value, InterpreterError> {
+        match implementation {
+            MethodImpl::User(function) => {
+                self.call_user_function_with_values(&Symbol::intern(method), &function, args)
+            }
+            MethodImpl::Native(native) => native(self, args),
+        }
+    }
+
+    /// 调用协议方法：按第一个参数的类型分派 / Call a protocol method, dispatching on the type of the first argument
+    fn call_protocol_method(
+        &mut self,
+        method: &str,
+        args: &[Value],
+    ) -> Result<Value, InterpreterError> {
+        let Some((protocol, arity)) = self.protocol_of(method) else {
+            return Err(InterpreterError::runtime_error(
+                format!("{} is not a protocol method", method),
+                None,
+            ));
+        };
+        if args.len() != arity {
+            return Err(InterpreterError::runtime_error(
+                format!(
+                    "{}/{} expects {} arguments, got {}",
+                    protocol,
+                    method,
+                    arity,
+                    args.len()
+                ),
+                None,
+            ));
+        }
+        match self.find_method(method, &args[0]) {
+            Some(implementation) => self.call_method(method, implementation, args),
+            None => Err(InterpreterError::type_error(
+                format!(
+                    "{} does not implement {}/{}",
+                    self.value_type_name(&args[0]),
+                    protocol,
+                    method
+                ),
+                None,
+            )),
+        }
+    }
+
+    /// 值的文本形式：类型实现了 Show 时调用其 `to-string`，列表、字典、集合和变体的元素同样如此
+    /// Text form of a value, using `to-string` when its type implements Show, for the elements of lists, dicts, sets
+    /// and variants as well
+    fn show_value(&mut self, value: &Value) -> Result<String, InterpreterError> {
+        // 没有用户实现时与 Display 相同 / Without user implementations this is the same as Display
+        let user_show = self.protocols.values().any(|protocol| {
+            protocol
+                .impls
+                .values()
+                .any(|methods| methods.contains_key("to-string"))
+        });
+        if !user_show {
+            return Ok(value.to_string());
+        }
+        self.show_nested(value)
+    }
+
+    /// 按 Display 的格式递归显示，遇到实现了 Show 的值调用其 `to-string`
+    /// Display recursively in the Display format, calling `to-string` on values that implement Show
+    fn show_nested(&mut self, value: &Value) -> Result<String, InterpreterError> {
+        if let Some(implementation @ MethodImpl::User(_)) = self.find_method("to-string", value) {
+            return match self.call_method(
+                "to-string",
+                implementation,
+                std::slice::from_ref(value),
+            )? {
+                Value::String(text) => Ok(text.to_string()),
+                other => Err(InterpreterError::type_error(
+                    format!(
+                        "to-string for {} must return a string, got {}",
+                        self.value_type_name(value),
+                        self.value_type_name(&other)
+                    ),
+                    None,
+                )),
+            };
+        }
+        Ok(match value {
+            Value::List(items) => format!("[{}]", self.show_items(items.iter())?),
+            Value::Set(items) => format!("#{{{}}}", self.show_items(items.iter())?),
+            Value::Dict(dict) => {
+                let mut entries = Vec::with_capacity(dict.len());
+                for (key, value) in dict.iter() {
+                    entries.push(format!("{}: {}", key, self.show_nested(value)?));
+                }
+                format!("{{{}}}", entries.join(", "))
+            }
+            Value::Variant { tag, fields } if !fields.is_empty() => {
+                let mut text = format!("({}", tag);
+                for field in fields {
+                    text.push(' ');
+                    text.push_str(&self.show_nested(field)?);
+                }
+                text.push(')');
+                text
+            }
+            value => value.to_string(),
+        })
+    }
+
+    /// 以逗号分隔显示多个值 / Show several values separated by commas
+    fn show_items<'a>(
+        &mut self,
+        items: impl Iterator<Item = &'a Value>,
+    ) -> Result<String, InterpreterError> {
+        let parts = items
+            .map(|item| self.show_nested(item))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(parts.join(", "))
+    }
+
+    /// 评估let绑定 / Evaluate let binding
+    fn eval_let(&mut self, rest: &[GrammarElement]) -> Result<Value, InterpreterError> {
+        if rest.len() < 2 {
+            return Err(InterpreterError::runtime_error(
+                "Let requires at least: name, value".to_string(),
+                None,
+            ));
+        }
+
+        // 解构目标 (q r ..rest) 按模式绑定 / Destructuring targets (q r ..rest) bind as patterns
+        if let Some(pattern) = destructuring_pattern(&rest[0]) {
+            return self.eval_destructuring_let(&pattern, rest);
+        }
+
+        // 获取变量名（支持 Atom 和 Expr(Var(...)) 两种形式）
+        let name = match &rest[0] {
+            GrammarElement::Atom(s) => Symbol::intern(s),
             GrammarElement::Expr(boxed_expr) => {
                 if let Expr::Var(s) = boxed_expr.as_ref() {
                     s.clone()
@@ -764,32 +2462,113 @@ impl Interpreter {
         let has_body = rest.len() > 2
             && !matches!(&rest[2], GrammarElement::Expr(boxed_expr) if matches!(boxed_expr.as_ref(), Expr::Literal(Literal::Null)));
 
+        // 有body的let只在body内遮蔽常量 / A let with a body only shadows a constant within the body
+        if !has_body {
+            self.check_assignable(&name)?;
+        }
+
         // 保存旧值（用于作用域）
         let old_value = self.environment.insert(name.clone(), value);
 
-        // 如果有body，评估body并在评估后恢复旧值（变量只在body的作用域中可用）
-        // 如果没有body，变量应该保持在作用域中（用于顶层绑定）
-        let result = if has_body {
-            // 评估函数体（支持多个表达式，返回最后一个表达式的值）
+        // 没有body，变量保持在当前块中（顶层时一直保留），返回null
+        // Without a body the variable stays in the enclosing block (for good at the top level) and null is returned
+        if !has_body {
+            if let Some(scope) = self.let_scopes.last_mut() {
+                scope.push((name, old_value));
+            }
+            return Ok(Value::Null);
+        }
+
+        // 有body时body是一个块：块内无body的let在块结束时撤销，出错时同样恢复
+        // With a body, the body is a block: body-less lets inside it are undone when it ends, on errors as well
+        let result = self.eval_block(|this| {
             let mut body_result = Value::Null;
             for body_elem in &rest[2..] {
-                body_result = self.eval_element(body_elem)?;
+                body_result = this.eval_element(body_elem)?;
             }
+            Ok(body_result)
+        });
+        match old_value {
+            Some(old) => self.environment.insert(name, old),
+            None => self.environment.remove(&name),
+        };
 
-            // 恢复旧值（如果存在）
-            if let Some(old) = old_value {
-                self.environment.insert(name, old);
-            } else {
-                self.environment.remove(&name);
+        result
+    }
+
+    /// 解构let：值按模式匹配后绑定全部变量，作用域规则与单变量let相同
+    /// Destructuring let: the value is matched against the pattern and every variable is bound, with the same scoping
+    /// rules as a single-variable let
+    fn eval_destructuring_let(
+        &mut self,
+        pattern: &Pattern,
+        rest: &[GrammarElement],
+    ) -> Result<Value, InterpreterError> {
+        let value = self.eval_element(&rest[1])?;
+        let mut bindings = Vec::new();
+        if !self.match_pattern(pattern, &value, &mut bindings)? {
+            return Err(InterpreterError::runtime_error(
+                format!(
+                    "Cannot destructure {} with {}",
+                    value,
+                    crate::grammar::unparse::unparse_pattern(pattern)
+                ),
+                None,
+            ));
+        }
+
+        let has_body = rest.len() > 2
+            && !matches!(&rest[2], GrammarElement::Expr(boxed_expr) if matches!(boxed_expr.as_ref(), Expr::Literal(Literal::Null)));
+        let saved = self.bind_pattern_variables(bindings);
+        if !has_body {
+            if let Some(scope) = self.let_scopes.last_mut() {
+                scope.extend(saved);
             }
+            return Ok(Value::Null);
+        }
 
-            body_result
-        } else {
-            // 没有body，变量保持在作用域中，返回null
-            Value::Null
-        };
+        let result = self.eval_block(|this| {
+            let mut body_result = Value::Null;
+            for body_elem in &rest[2..] {
+                body_result = this.eval_element(body_elem)?;
+            }
+            Ok(body_result)
+        });
+        self.restore_pattern_variables(saved);
+        result
+    }
 
-        Ok(result)
+    /// 取得编译好的正则表达式，未缓存时编译并缓存 / Get a compiled regex, compiling and caching it on a miss
+    fn compile_regex(&mut self, pattern: &str) -> Result<Regex, InterpreterError> {
+        if let Some(regex) = self.regex_cache.get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Regex::new(pattern).map_err(|error| {
+            InterpreterError::runtime_error(format!("Invalid regex '{}': {}", pattern, error), None)
+        })?;
+        if self.regex_cache.len() >= REGEX_CACHE_LIMIT {
+            self.regex_cache.clear();
+        }
+        self.regex_cache.insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    }
+
+    /// 把求值作为一个块执行：块内无body的let引入的绑定在块结束（包括出错）时撤销
+    /// Evaluate as a block: bindings introduced by body-less lets inside it are undone when it ends, errors included
+    fn eval_block<F>(&mut self, body: F) -> Result<Value, InterpreterError>
+    where
+        F: FnOnce(&mut Self) -> Result<Value, InterpreterError>,
+    {
+        self.let_scopes.push(Vec::new());
+        let result = body(self);
+        let scope = self.let_scopes.pop().unwrap_or_default();
+        for (name, old) in scope.into_iter().rev() {
+            match old {
+                Some(old) => self.environment.insert(name, old),
+                None => self.environment.remove(&name),
+            };
+        }
+        result
     }
 
     /// 评估set!赋值操作 / Evaluate set! assignment operation
@@ -803,7 +2582,7 @@ impl Interpreter {
 
         // 获取变量名（支持 Atom 和 Expr(Var(...)) 两种形式）
         let name = match &rest[0] {
-            GrammarElement::Atom(s) => s.clone(),
+            GrammarElement::Atom(s) => Symbol::intern(s),
             GrammarElement::Expr(boxed_expr) => {
                 if let Expr::Var(s) = boxed_expr.as_ref() {
                     s.clone()
@@ -822,6 +2601,8 @@ impl Interpreter {
             }
         };
 
+        self.check_assignable(&name)?;
+
         // 评估值
         let value = self.eval_element(&rest[1])?;
 
@@ -837,7 +2618,7 @@ impl Interpreter {
         }
 
         // 更新变量值（不恢复旧值，这是赋值操作）
-        self.environment.insert(name.clone(), value.clone());
+        self.environment.insert(name, value.clone());
 
         Ok(value)
     }
@@ -882,7 +2663,7 @@ impl Interpreter {
             GrammarElement::Atom(s) => s.clone(),
             GrammarElement::Expr(boxed_expr) => {
                 if let Expr::Var(s) = boxed_expr.as_ref() {
-                    s.clone()
+                    s.to_string()
                 } else {
                     return Err(InterpreterError::runtime_error(
                         "For loop variable must be an identifier".to_string(),
@@ -910,6 +2691,7 @@ impl Interpreter {
 
         // 将循环体转换为Expr（如果可能），否则直接评估
         // 对于for循环，循环体需要能够访问循环变量，所以我们需要特殊处理
+        let var = Symbol::intern(&var);
         match self.element_to_expr(&body_elem) {
             Ok(body_expr) => {
                 // 如果能够转换为Expr，使用eval_for
@@ -918,38 +2700,7 @@ impl Interpreter {
             Err(_) => {
                 // 如果不能转换为Expr，直接评估GrammarElement
                 // 这种情况下，循环体中的变量需要在环境中查找
-                let items = match iterable_value {
-                    Value::List(list) => list.clone(),
-                    Value::Int(end) => (0..end as usize).map(|i| Value::Int(i as i64)).collect(),
-                    _ => {
-                        return Err(InterpreterError::type_error(
-                            "For loop iterable must be a list or integer".to_string(),
-                            None,
-                        ));
-                    }
-                };
-
-                let mut last_value = Value::Null;
-                // 保存循环变量在循环外的旧值（如果存在）
-                let outer_old_value = self.environment.get(&var).cloned();
-
-                for item in items {
-                    // 设置循环变量值
-                    self.environment.insert(var.clone(), item);
-
-                    // 执行循环体
-                    last_value = self.eval_element(&body_elem)?;
-                }
-
-                // 恢复循环外的旧值（如果存在）
-                if let Some(old) = outer_old_value {
-                    self.environment.insert(var.clone(), old);
-                } else {
-                    // 只有在循环前变量不存在时才删除
-                    self.environment.remove(&var);
-                }
-
-                Ok(last_value)
+                self.eval_loop(&var, &iterable_value, |this| this.eval_element(&body_elem))
             }
         }
     }
@@ -1020,7 +2771,7 @@ impl Interpreter {
                     GrammarElement::Atom(s) => Some(s.clone()),
                     GrammarElement::Expr(boxed_expr) => {
                         if let Expr::Var(s) = boxed_expr.as_ref() {
-                            Some(s.clone())
+                            Some(s.to_string())
                         } else {
                             None
                         }
@@ -1031,7 +2782,7 @@ impl Interpreter {
             GrammarElement::Atom(single_param) => vec![single_param.clone()],
             GrammarElement::Expr(boxed_expr) => {
                 if let Expr::Var(s) = boxed_expr.as_ref() {
-                    vec![s.clone()]
+                    vec![s.to_string()]
                 } else {
                     return Err(InterpreterError::runtime_error(
                         "Lambda params must be a list of atoms or variables".to_string(),
@@ -1055,12 +2806,17 @@ impl Interpreter {
             GrammarElement::List(rest[1..].to_vec())
         };
 
-        // 捕获当前环境（用于闭包）
-        // 只捕获不在参数列表中的变量，避免参数遮蔽
-        let captured_env: HashMap<String, Value> = self
+        Ok(self.register_lambda(params, body))
+    }
+
+    /// 注册Lambda：捕获当前环境（只捕获不在参数列表中的变量，避免参数遮蔽）并返回Lambda值
+    /// Register a lambda: capture the current environment (excluding parameter names to avoid shadowing) and return the lambda value
+    fn register_lambda(&mut self, params: Vec<String>, body: GrammarElement) -> Value {
+        let param_symbols: Vec<Symbol> = params.iter().map(Symbol::from).collect();
+        let captured_env: HashMap<Symbol, Value> = self
             .environment
             .iter()
-            .filter(|(key, _)| !params.contains(key))
+            .filter(|(key, _)| !param_symbols.contains(key))
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect();
 
@@ -1070,50 +2826,51 @@ impl Interpreter {
 
         // 注册Lambda函数体和捕获的环境
         self.lambda_registry
-            .insert(lambda_id.clone(), (params.clone(), body, captured_env));
+            .insert(lambda_id.clone(), (param_symbols, body, captured_env));
 
-        // 返回Lambda值
-        Ok(Value::Lambda {
+        Value::Lambda {
             id: lambda_id,
             params,
-        })
+        }
     }
 
     /// 评估表达式 / Evaluate expression
     pub fn eval_expr(&mut self, expr: &Expr) -> Result<Value, InterpreterError> {
+        self.charge_step()?;
+        if self.usage_observer.is_some() {
+            self.note_expr(expr);
+        }
         match expr {
             Expr::Literal(lit) => self.eval_literal(lit),
             Expr::Var(name) => {
                 // 首先检查是否是操作符（当操作符作为变量传递时）
                 // First check if it's an operator (when operator is passed as variable)
-                if name == "+"
-                    || name == "-"
-                    || name == "*"
-                    || name == "/"
-                    || name == "%"
-                    || name == "="
-                    || name == "=="
-                    || name == "!="
-                    || name == "<>"
-                    || name == "<"
-                    || name == ">"
-                    || name == "<="
-                    || name == ">="
-                {
+                if is_operator(name) {
                     // 操作符作为值传递时，返回一个特殊的字符串值
                     // When operator is passed as value, return a special string value
-                    return Ok(Value::String(name.clone()));
+                    return Ok(Value::String(name.as_str().into()));
+                }
+                if let Some(value) = self.lookup_symbol(name) {
+                    return Ok(value.clone());
+                }
+                // 无字段的构造器可以直接作为值使用 / Constructors without fields can be used directly as values
+                match self.variant_constructors.get(name.as_str()) {
+                    Some(constructor) if constructor.fields.is_empty() => Ok(Value::Variant {
+                        tag: name.to_string(),
+                        fields: Vec::new(),
+                    }),
+                    _ => Err(self.note_error_site(
+                        name,
+                        InterpreterError::undefined_variable(name.to_string(), None),
+                    )),
                 }
-                self.environment
-                    .get(name)
-                    .cloned()
-                    .ok_or_else(|| InterpreterError::undefined_variable(name.clone(), None))
             }
             Expr::Call(name, args) => self.eval_call(name, args),
             Expr::Binary(op, left, right) => {
                 let left_val = self.eval_expr(left)?;
                 let right_val = self.eval_expr(right)?;
                 self.eval_binary_op(*op, &left_val, &right_val)
+                    .map_err(|error| self.note_error_site(binop_symbol(op), error))
             }
             Expr::If(cond, then_expr, else_expr) => {
                 let cond_val = self.eval_expr(cond)?;
@@ -1127,9 +2884,10 @@ impl Interpreter {
                             // 将 Call("let", args) 转换回 GrammarElement::List
                             // Convert Call("let", args) back to GrammarElement::List
                             let mut let_elements = vec![GrammarElement::Atom("let".to_string())];
-                            for arg in args {
-                                let_elements.push(self.expr_to_element(arg)?);
-                            }
+                            let_elements.extend(
+                                args.iter()
+                                    .map(|arg| GrammarElement::Expr(Box::new(arg.clone()))),
+                            );
                             return self.eval_list(&let_elements);
                         }
                     }
@@ -1155,45 +2913,34 @@ impl Interpreter {
                 catch_body,
             } => self.eval_try(try_body, catch_var, catch_body),
             Expr::Lambda { params, body } => {
-                // 生成唯一的Lambda ID
-                self.lambda_counter += 1;
-                let lambda_id = format!("__lambda_{}", self.lambda_counter);
-
                 // 将body转换为GrammarElement，以便在调用时评估
                 let body_elem = self.expr_to_element(body)?;
-
-                // 捕获当前环境（用于闭包）
-                // 只捕获不在参数列表中的变量，避免参数遮蔽
-                let captured_env: HashMap<String, Value> = self
-                    .environment
-                    .iter()
-                    .filter(|(key, _)| !params.contains(key))
-                    .map(|(k, v)| (k.clone(), v.clone()))
-                    .collect();
-
-                // 注册Lambda函数体和捕获的环境
-                self.lambda_registry
-                    .insert(lambda_id.clone(), (params.clone(), body_elem, captured_env));
-
-                // 返回Lambda值
-                Ok(Value::Lambda {
-                    id: lambda_id,
-                    params: params.clone(),
-                })
+                Ok(self.register_lambda(params.clone(), body_elem))
             }
             Expr::Begin(exprs) => {
                 // 按顺序执行多个表达式，返回最后一个表达式的结果
                 let mut result = Value::Null;
                 for expr in exprs {
+                    if self.debug_hook.is_some() {
+                        let probe = match expr {
+                            Expr::Call(name, _) => name
+                                .strip_prefix(PROBE_PREFIX)
+                                .and_then(|probe| probe.parse().ok()),
+                            _ => None,
+                        };
+                        self.debug_event(DebugEvent::Statement { probe })?;
+                    }
                     result = self.eval_expr(expr)?;
                 }
                 Ok(result)
             }
             Expr::Assign(var, expr) => {
+                let name = var.clone();
+                self.check_assignable(&name)?;
                 // 计算赋值表达式的值
                 let value = self.eval_expr(expr)?;
                 // 更新环境中的变量值
-                self.environment.insert(var.clone(), value.clone());
+                self.environment.insert(name, value.clone());
                 // 返回赋值后的值
                 Ok(value)
             }
@@ -1207,13 +2954,14 @@ impl Interpreter {
         cases: &[(Pattern, Expr)],
     ) -> Result<Value, InterpreterError> {
         for (pattern, expr) in cases {
-            if self.pattern_matches(pattern, value)? {
-                // 绑定模式中的变量
-                self.bind_pattern_variables(pattern, value)?;
-                let result = self.eval_expr(expr)?;
-                // 恢复环境（移除绑定的变量）
-                self.unbind_pattern_variables(pattern);
-                return Ok(result);
+            let mut bindings = Vec::new();
+            if self.match_pattern(pattern, value, &mut bindings)? {
+                // 绑定模式中的变量，求值后恢复环境
+                // Bind pattern variables, then restore the environment after evaluation
+                let saved = self.bind_pattern_variables(bindings);
+                let result = self.eval_expr(expr);
+                self.restore_pattern_variables(saved);
+                return result;
             }
         }
         Err(InterpreterError::runtime_error(
@@ -1222,35 +2970,89 @@ impl Interpreter {
         ))
     }
 
-    /// 检查模式是否匹配值 / Check if pattern matches value
-    fn pattern_matches(&self, pattern: &Pattern, value: &Value) -> Result<bool, InterpreterError> {
+    /// 检查模式是否匹配值，并收集变量绑定 / Check if pattern matches value, collecting variable bindings
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        value: &Value,
+        bindings: &mut Vec<(Symbol, Value)>,
+    ) -> Result<bool, InterpreterError> {
         match (pattern, value) {
             (Pattern::Wildcard, _) => Ok(true),
-            (Pattern::Var(_), _) => Ok(true), // 变量模式总是匹配
+            // 无字段构造器名匹配同名变体而不是绑定变量
+            // A fieldless constructor name matches that variant instead of binding a variable
+            (Pattern::Var(name), val) if self.is_nullary_constructor(name) => Ok(matches!(
+                val,
+                Value::Variant { tag, fields } if name == tag && fields.is_empty()
+            )),
+            // (Tag p1 p2 ...) 按字段匹配变体 / (Tag p1 p2 ...) matches a variant field by field
+            (Pattern::List(patterns), Value::Variant { tag, fields }) => match patterns.first() {
+                Some(Pattern::Var(head)) if head == tag => self.match_pattern(
+                    &Pattern::List(patterns[1..].to_vec()),
+                    &Value::List(fields.clone().into()),
+                    bindings,
+                ),
+                _ => Ok(false),
+            },
+            (Pattern::Var(name), val) => {
+                // 变量模式总是匹配 / Variable patterns always match
+                bindings.push((name.clone(), val.clone()));
+                Ok(true)
+            }
             (Pattern::Literal(lit), val) => match (lit, val) {
                 (Literal::Int(i), Value::Int(j)) => Ok(i == j),
+                (Literal::BigInt(m), Value::BigInt(n)) => Ok(m == n),
                 (Literal::Float(f), Value::Float(g)) => Ok((f - g).abs() < f64::EPSILON),
-                (Literal::String(s), Value::String(t)) => Ok(s == t),
+                (Literal::String(s), Value::String(t)) => Ok(s.as_str() == &**t),
                 (Literal::Bool(b), Value::Bool(c)) => Ok(b == c),
                 (Literal::Null, Value::Null) => Ok(true),
                 _ => Ok(false),
             },
             (Pattern::List(patterns), Value::List(values)) => {
-                if patterns.len() != values.len() {
+                let rest_index = patterns
+                    .iter()
+                    .position(|pat| matches!(pat, Pattern::Rest(_)));
+                let Some(rest_index) = rest_index else {
+                    if patterns.len() != values.len() {
+                        return Ok(false);
+                    }
+                    for (pat, val) in patterns.iter().zip(values.iter()) {
+                        if !self.match_pattern(pat, val, bindings)? {
+                            return Ok(false);
+                        }
+                    }
+                    return Ok(true);
+                };
+
+                // 剩余模式前后的元素分别从头尾匹配 / Elements before and after the rest pattern match from the front and back
+                let suffix = &patterns[rest_index + 1..];
+                if values.len() < rest_index + suffix.len() {
                     return Ok(false);
                 }
-                for (pat, val) in patterns.iter().zip(values.iter()) {
-                    if !self.pattern_matches(pat, val)? {
+                let rest_end = values.len() - suffix.len();
+                for (pat, val) in patterns[..rest_index].iter().zip(values.iter()) {
+                    if !self.match_pattern(pat, val, bindings)? {
+                        return Ok(false);
+                    }
+                }
+                for (pat, val) in suffix.iter().zip(values[rest_end..].iter()) {
+                    if !self.match_pattern(pat, val, bindings)? {
                         return Ok(false);
                     }
                 }
+                if let Pattern::Rest(Some(name)) = &patterns[rest_index] {
+                    bindings.push((
+                        name.clone(),
+                        Value::List(values[rest_index..rest_end].to_vec().into()),
+                    ));
+                }
                 Ok(true)
             }
             (Pattern::Dict(patterns), Value::Dict(values)) => {
                 // 检查所有模式键是否都在值中，且匹配
                 for (key, pat) in patterns {
                     if let Some(val) = values.get(key) {
-                        if !self.pattern_matches(pat, val)? {
+                        if !self.match_pattern(pat, val, bindings)? {
                             return Ok(false);
                         }
                     } else {
@@ -1259,74 +3061,118 @@ impl Interpreter {
                 }
                 Ok(true)
             }
-            _ => Ok(false),
-        }
-    }
-
-    /// 绑定模式中的变量到环境 / Bind pattern variables to environment
-    fn bind_pattern_variables(
-        &mut self,
-        pattern: &Pattern,
-        value: &Value,
-    ) -> Result<(), InterpreterError> {
-        match (pattern, value) {
-            (Pattern::Var(name), val) => {
-                // 优化：直接插入，不需要克隆name（已经在pattern中）
-                self.environment.insert(name.clone(), val.clone());
+            (Pattern::Or(alternatives), val) => {
+                for alternative in alternatives {
+                    let mut alternative_bindings = Vec::new();
+                    if self.match_pattern(alternative, val, &mut alternative_bindings)? {
+                        bindings.extend(alternative_bindings);
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
             }
-            (Pattern::List(patterns), Value::List(values)) => {
-                for (pat, val) in patterns.iter().zip(values.iter()) {
-                    self.bind_pattern_variables(pat, val)?;
+            (Pattern::Prefix(prefix, rest), Value::String(s)) => {
+                match s.strip_prefix(prefix.as_str()) {
+                    Some(remainder) => {
+                        self.match_pattern(rest, &Value::String(remainder.into()), bindings)
+                    }
+                    None => Ok(false),
                 }
             }
-            (Pattern::Dict(patterns), Value::Dict(values)) => {
-                for (key, pat) in patterns {
-                    if let Some(val) = values.get(key) {
-                        self.bind_pattern_variables(pat, val)?;
+            (Pattern::Suffix(suffix, rest), Value::String(s)) => {
+                match s.strip_suffix(suffix.as_str()) {
+                    Some(remainder) => {
+                        self.match_pattern(rest, &Value::String(remainder.into()), bindings)
                     }
+                    None => Ok(false),
                 }
             }
-            _ => {}
+            (Pattern::Guard(inner, guard), val) => {
+                let mut inner_bindings = Vec::new();
+                if !self.match_pattern(inner, val, &mut inner_bindings)? {
+                    return Ok(false);
+                }
+                // 守卫在模式绑定的变量可见时求值 / The guard is evaluated with the pattern's bindings in scope
+                let saved = self.bind_pattern_variables(inner_bindings.clone());
+                let guard_value = self.eval_expr(guard);
+                self.restore_pattern_variables(saved);
+                if self.is_truthy(&guard_value?) {
+                    bindings.extend(inner_bindings);
+                    Ok(true)
+                } else {
+                    Ok(false)
+                }
+            }
+            _ => Ok(false),
         }
-        Ok(())
     }
 
-    /// 解绑模式中的变量 / Unbind pattern variables from environment
-    fn unbind_pattern_variables(&mut self, pattern: &Pattern) {
-        match pattern {
-            Pattern::Var(name) => {
-                self.environment.remove(name);
-            }
-            Pattern::List(patterns) => {
-                for pat in patterns {
-                    self.unbind_pattern_variables(pat);
+    /// 是否为无字段的变体构造器 / Whether the name is a fieldless variant constructor
+    fn is_nullary_constructor(&self, name: &str) -> bool {
+        self.variant_constructors
+            .get(name)
+            .is_some_and(|constructor| constructor.fields.is_empty())
+    }
+
+    /// 绑定模式变量到环境，返回被覆盖的旧值 / Bind pattern variables, returning the shadowed values
+    fn bind_pattern_variables(
+        &mut self,
+        bindings: Vec<(Symbol, Value)>,
+    ) -> Vec<(Symbol, Option<Value>)> {
+        bindings
+            .into_iter()
+            .map(|(name, value)| {
+                let previous = self.environment.insert(name.clone(), value);
+                (name, previous)
+            })
+            .collect()
+    }
+
+    /// 恢复被模式变量覆盖的环境 / Restore the environment shadowed by pattern variables
+    fn restore_pattern_variables(&mut self, saved: Vec<(Symbol, Option<Value>)>) {
+        for (name, previous) in saved.into_iter().rev() {
+            match previous {
+                Some(value) => {
+                    self.environment.insert(name, value);
                 }
-            }
-            Pattern::Dict(patterns) => {
-                for (_, pat) in patterns {
-                    self.unbind_pattern_variables(pat);
+                None => {
+                    self.environment.remove(&name);
                 }
             }
-            _ => {}
         }
     }
 
     /// 评估For循环 / Evaluate for loop
     fn eval_for(
         &mut self,
-        var: &str,
+        var: &Symbol,
         iterable: &Value,
         body: &Expr,
     ) -> Result<Value, InterpreterError> {
+        self.eval_loop(var, iterable, |this| this.eval_expr(body))
+    }
+
+    /// 对列表、集合、范围或生成器的每一项执行循环体 / Run the loop body for each item of a list, set, range or generator
+    fn eval_loop<F>(
+        &mut self,
+        var: &Symbol,
+        iterable: &Value,
+        mut body: F,
+    ) -> Result<Value, InterpreterError>
+    where
+        F: FnMut(&mut Self) -> Result<Value, InterpreterError>,
+    {
         let items = match iterable {
             Value::List(list) => list.clone(),
+            Value::Set(set) => Arc::new(set.iter().cloned().collect()),
             Value::Int(end) => {
                 // 如果iterable是整数，创建范围 [0, end)
-                (0..*end as usize).map(|i| Value::Int(i as i64)).collect()
+                Arc::new((0..*end as usize).map(|i| Value::Int(i as i64)).collect())
             }
+            Value::Generator { .. } => Arc::default(),
             _ => {
                 return Err(InterpreterError::type_error(
-                    "For loop iterable must be a list or integer".to_string(),
+                    "For loop iterable must be a list, set, integer or generator".to_string(),
                     None,
                 ));
             }
@@ -1336,17 +3182,25 @@ impl Interpreter {
         // 保存循环变量在循环外的旧值（如果存在）
         let outer_old_value = self.environment.get(var).cloned();
 
-        for item in items {
+        // 生成器按需恢复，每次取一项 / Generators are resumed on demand, one item at a time
+        if let Value::Generator { id } = iterable {
+            while let Some(item) = self.resume_generator(id)? {
+                self.environment.insert(var.clone(), item);
+                last_value = body(self)?;
+            }
+        }
+
+        for item in items.iter().cloned() {
             // 设置循环变量值
-            self.environment.insert(var.to_string(), item);
+            self.environment.insert(var.clone(), item);
 
             // 执行循环体
-            last_value = self.eval_expr(body)?;
+            last_value = body(self)?;
         }
 
         // 恢复循环外的旧值（如果存在）
         if let Some(old) = outer_old_value {
-            self.environment.insert(var.to_string(), old);
+            self.environment.insert(var.clone(), old);
         } else {
             // 只有在循环前变量不存在时才删除
             self.environment.remove(var);
@@ -1375,31 +3229,378 @@ impl Interpreter {
         Ok(last_value)
     }
 
-    /// 评估Try-Catch异常处理 / Evaluate try-catch exception handling
-    fn eval_try(
+    /// 创建生成器：函数体不立即执行，而是在每次恢复时运行到下一个 `yield`
+    /// Create a generator: the body does not run now, but up to the next `yield` on each resume
+    fn make_generator(
         &mut self,
-        try_body: &Expr,
-        catch_var: &Option<String>,
-        catch_body: &Expr,
-    ) -> Result<Value, InterpreterError> {
-        // 尝试执行try块
-        match self.eval_expr(try_body) {
-            Ok(value) => Ok(value),
-            Err(error) => {
-                // 如果有catch变量，将错误信息绑定到变量
-                if let Some(var) = catch_var {
-                    let error_message = Value::String(error.to_string());
-                    let old_value = self.environment.insert(var.clone(), error_message);
+        env: HashMap<Symbol, Value>,
+        body: &GrammarElement,
+        module_name: Option<String>,
+    ) -> Value {
+        let mut binders = Vec::new();
+        element_binders(body, &mut binders);
+        let mut locals: Vec<Symbol> = env.keys().cloned().collect();
+        locals.extend(binders.iter().map(Symbol::from));
+        locals.sort();
+        locals.dedup();
+
+        self.generator_counter += 1;
+        let id = format!("__generator_{}", self.generator_counter);
+        self.generators.insert(
+            id.clone(),
+            GeneratorState {
+                locals,
+                env,
+                stack: vec![GeneratorFrame::Eval(body.clone())],
+                module_name,
+            },
+        );
+        Value::Generator { id }
+    }
 
-                    // 执行catch块
-                    let result = self.eval_expr(catch_body)?;
+    /// 恢复生成器直到下一个 `yield`，结束时返回 None；每一帧都计入步数预算
+    /// Resume a generator up to the next `yield`, returning None once finished; every frame counts against the step budget
+    fn resume_generator(&mut self, id: &str) -> Result<Option<Value>, InterpreterError> {
+        let mut state = self.generators.remove(id).ok_or_else(|| {
+            InterpreterError::runtime_error(
+                format!("Generator {} is already running or does not exist", id),
+                None,
+            )
+        })?;
 
-                    // 恢复旧值（如果存在）
-                    if let Some(old) = old_value {
-                        self.environment.insert(var.clone(), old);
-                    } else {
-                        self.environment.remove(var.as_str());
-                    }
+        // 装入局部变量，保存外部同名变量 / Install the locals, saving outer variables of the same name
+        let saved: Vec<(Symbol, Option<Value>)> = state
+            .locals
+            .iter()
+            .map(|name| {
+                let old = match state.env.get(name) {
+                    Some(value) => self.environment.insert(name.clone(), value.clone()),
+                    None => self.environment.get(name).cloned(),
+                };
+                (name.clone(), old)
+            })
+            .collect();
+        let saved_module = self.current_module.clone();
+        if let Some(ref module_name) = state.module_name {
+            self.current_module = Some(module_name.clone());
+        }
+        // 生成器的局部变量不属于调用方的 let 块 / The generator's locals do not belong to the caller's let blocks
+        let saved_scopes = std::mem::take(&mut self.let_scopes);
+
+        let result = self.run_generator(&mut state.stack);
+        self.let_scopes = saved_scopes;
+
+        // 取回局部变量并恢复外部环境 / Take the locals back and restore the outer environment
+        for (name, old) in saved {
+            match self.environment.get(&name) {
+                Some(value) => state.env.insert(name.clone(), value.clone()),
+                None => state.env.remove(&name),
+            };
+            match old {
+                Some(old) => self.environment.insert(name, old),
+                None => self.environment.remove(&name),
+            };
+        }
+        self.current_module = saved_module;
+
+        // 出错的生成器不能再恢复 / A generator that failed cannot be resumed
+        if result.is_err() {
+            state.stack.clear();
+        }
+        self.generators.insert(id.to_string(), state);
+        result
+    }
+
+    /// 执行生成器的帧，遇到 `yield` 时挂起 / Run generator frames, suspending at `yield`
+    fn run_generator(
+        &mut self,
+        stack: &mut Vec<GeneratorFrame>,
+    ) -> Result<Option<Value>, InterpreterError> {
+        while let Some(frame) = stack.pop() {
+            self.charge_step()?;
+            if let Some(value) = self.step_generator(frame, stack)? {
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 执行一帧，返回 `yield` 产出的值 / Execute one frame, returning the value produced by `yield`
+    fn step_generator(
+        &mut self,
+        frame: GeneratorFrame,
+        stack: &mut Vec<GeneratorFrame>,
+    ) -> Result<Option<Value>, InterpreterError> {
+        match frame {
+            GeneratorFrame::Eval(element) => return self.step_generator_element(element, stack),
+            GeneratorFrame::While { condition, body } => {
+                let cond_value = self.eval_expr(&condition)?;
+                if self.is_truthy(&cond_value) {
+                    let body_element = GrammarElement::Expr(Box::new(body.clone()));
+                    stack.push(GeneratorFrame::While { condition, body });
+                    stack.push(GeneratorFrame::Eval(body_element));
+                }
+            }
+            GeneratorFrame::For {
+                var,
+                items,
+                index,
+                body,
+            } => {
+                if let Some(item) = items.get(index).cloned() {
+                    self.environment.insert(var.clone(), item);
+                    let body_element = GrammarElement::Expr(Box::new(body.clone()));
+                    stack.push(GeneratorFrame::For {
+                        var,
+                        items,
+                        index: index + 1,
+                        body,
+                    });
+                    stack.push(GeneratorFrame::Eval(body_element));
+                }
+            }
+            GeneratorFrame::ForGenerator { var, id, body } => {
+                if let Some(item) = self.resume_generator(&id)? {
+                    self.environment.insert(var.clone(), item);
+                    let body_element = GrammarElement::Expr(Box::new(body.clone()));
+                    stack.push(GeneratorFrame::ForGenerator { var, id, body });
+                    stack.push(GeneratorFrame::Eval(body_element));
+                }
+            }
+            GeneratorFrame::Restore { name, old } => {
+                match old {
+                    Some(old) => self.environment.insert(name, old),
+                    None => self.environment.remove(&name),
+                };
+            }
+        }
+        Ok(None)
+    }
+
+    /// 执行生成器体中的一个元素：不含 `yield` 的部分直接求值，控制结构展开为帧
+    /// Execute one element of a generator body: parts without `yield` are evaluated directly, control structures unfold into frames
+    fn step_generator_element(
+        &mut self,
+        element: GrammarElement,
+        stack: &mut Vec<GeneratorFrame>,
+    ) -> Result<Option<Value>, InterpreterError> {
+        let expr = match element {
+            GrammarElement::List(list) if matches!(list.first(), Some(GrammarElement::Atom(head)) if head == "let") =>
+            {
+                let (name, value) = match (list.get(1), list.get(2)) {
+                    (Some(name), Some(value)) => (binding_name(name)?, value.clone()),
+                    _ => {
+                        return Err(InterpreterError::runtime_error(
+                            "Let requires at least: name, value".to_string(),
+                            None,
+                        ))
+                    }
+                };
+                self.generator_let(name, &value, list[3..].to_vec(), stack)?;
+                return Ok(None);
+            }
+            GrammarElement::Expr(expr) => *expr,
+            element if !element_contains_yield(&element) => {
+                self.eval_element(&element)?;
+                return Ok(None);
+            }
+            _ => return Err(unsupported_yield()),
+        };
+
+        match expr {
+            // 解析器把 `(let x v)` 表示为 body 为 null 的调用 / The parser represents `(let x v)` as a call with a null body
+            Expr::Call(name, args) if name == "let" && args.len() == 3 => {
+                let mut args = args.into_iter();
+                let (Some(Expr::Var(name)), Some(value), Some(body)) =
+                    (args.next(), args.next(), args.next())
+                else {
+                    return Err(InterpreterError::runtime_error(
+                        "Variable name must be an atom or variable".to_string(),
+                        None,
+                    ));
+                };
+                let body = match body {
+                    Expr::Literal(Literal::Null) => Vec::new(),
+                    body => vec![GrammarElement::Expr(Box::new(body))],
+                };
+                self.generator_let(
+                    name.to_string(),
+                    &GrammarElement::Expr(Box::new(value)),
+                    body,
+                    stack,
+                )?;
+            }
+            expr if !expr_contains_yield(&expr) => {
+                self.eval_expr(&expr)?;
+            }
+            Expr::Call(name, args) if name == "yield" => {
+                let value = match args.as_slice() {
+                    [] => Value::Null,
+                    [arg] if !expr_contains_yield(arg) => self.eval_expr(arg)?,
+                    _ => return Err(unsupported_yield()),
+                };
+                return Ok(Some(value));
+            }
+            Expr::Begin(exprs) => {
+                stack.extend(
+                    exprs
+                        .into_iter()
+                        .rev()
+                        .map(|expr| GrammarElement::Expr(Box::new(expr)))
+                        .map(GeneratorFrame::Eval),
+                );
+            }
+            Expr::If(cond, then_expr, else_expr) if !expr_contains_yield(&cond) => {
+                let cond_value = self.eval_expr(&cond)?;
+                let branch = if self.is_truthy(&cond_value) {
+                    then_expr
+                } else {
+                    else_expr
+                };
+                stack.push(GeneratorFrame::Eval(GrammarElement::Expr(branch)));
+            }
+            Expr::While { condition, body } if !expr_contains_yield(&condition) => {
+                stack.push(GeneratorFrame::While {
+                    condition: *condition,
+                    body: *body,
+                });
+            }
+            Expr::For {
+                var,
+                iterable,
+                body,
+            } if !expr_contains_yield(&iterable) => {
+                let iterable_value = self.eval_expr(&iterable)?;
+                stack.push(GeneratorFrame::Restore {
+                    old: self.environment.get(&var).cloned(),
+                    name: var.clone(),
+                });
+                stack.push(match iterable_value {
+                    Value::Generator { id } => GeneratorFrame::ForGenerator {
+                        var,
+                        id,
+                        body: *body,
+                    },
+                    Value::List(items) => GeneratorFrame::For {
+                        var,
+                        items: Arc::unwrap_or_clone(items),
+                        index: 0,
+                        body: *body,
+                    },
+                    Value::Int(end) => GeneratorFrame::For {
+                        var,
+                        items: (0..end.max(0)).map(Value::Int).collect(),
+                        index: 0,
+                        body: *body,
+                    },
+                    _ => {
+                        return Err(InterpreterError::type_error(
+                            "For loop iterable must be a list, integer or generator".to_string(),
+                            None,
+                        ))
+                    }
+                });
+            }
+            _ => return Err(unsupported_yield()),
+        }
+        Ok(None)
+    }
+
+    /// 生成器体中的 let：无 body 时绑定保留到生成器结束，有 body 时离开 body 后恢复
+    /// Let in a generator body: without a body the binding lasts for the rest of the generator, with one it is restored after the body
+    fn generator_let(
+        &mut self,
+        name: String,
+        value: &GrammarElement,
+        body: Vec<GrammarElement>,
+        stack: &mut Vec<GeneratorFrame>,
+    ) -> Result<(), InterpreterError> {
+        if element_contains_yield(value) {
+            return Err(unsupported_yield());
+        }
+        let value = self.eval_element(value)?;
+        let name = Symbol::intern(&name);
+        let old = self.environment.insert(name.clone(), value);
+        if !body.is_empty() {
+            stack.push(GeneratorFrame::Restore { name, old });
+            stack.extend(body.into_iter().rev().map(GeneratorFrame::Eval));
+        }
+        Ok(())
+    }
+
+    /// 操作生成器的内置函数，参数已求值；不是这类函数时返回 None
+    /// Builtins operating on generators, with evaluated arguments; returns None for other functions
+    fn eval_generator_builtin(
+        &mut self,
+        name: &str,
+        args: &[Value],
+    ) -> Option<Result<Value, InterpreterError>> {
+        let result = match (name, args) {
+            ("yield", _) => Err(InterpreterError::runtime_error(
+                "yield can only be used inside a generator function".to_string(),
+                None,
+            )),
+            ("next", [Value::Generator { id }]) => self
+                .resume_generator(id)
+                .map(|value| value.unwrap_or(Value::Null)),
+            ("take", [Value::Int(count), Value::Generator { id }]) => {
+                let mut items = Vec::new();
+                while (items.len() as i64) < *count {
+                    match self.resume_generator(id) {
+                        Ok(Some(item)) => items.push(item),
+                        Ok(None) => break,
+                        Err(error) => return Some(Err(error)),
+                    }
+                }
+                Ok(Value::List(items.into()))
+            }
+            ("is-done", [Value::Generator { id }]) => Ok(Value::Bool(
+                self.generators
+                    .get(id)
+                    .is_some_and(|state| state.stack.is_empty()),
+            )),
+            ("is-generator", [value]) => Ok(Value::Bool(matches!(value, Value::Generator { .. }))),
+            ("next" | "is-done", _) => Err(InterpreterError::type_error(
+                format!("{} requires 1 argument: generator", name),
+                None,
+            )),
+            ("take", _) => Err(InterpreterError::type_error(
+                "take requires 2 arguments: count and generator".to_string(),
+                None,
+            )),
+            _ => return None,
+        };
+        Some(result)
+    }
+
+    /// 评估Try-Catch异常处理 / Evaluate try-catch exception handling
+    fn eval_try(
+        &mut self,
+        try_body: &Expr,
+        catch_var: &Option<Symbol>,
+        catch_body: &Expr,
+    ) -> Result<Value, InterpreterError> {
+        // 尝试执行try块
+        match self.eval_expr(try_body) {
+            Ok(value) => Ok(value),
+            Err(error) => {
+                // 被捕获的错误不再是调用栈的一部分 / A caught error is no longer part of the call stack
+                self.error_trace = ErrorTrace::default();
+                // 如果有catch变量，将结构化错误对象绑定到变量
+                // With a catch variable, bind the structured error object to it
+                if let Some(var) = catch_var {
+                    let var = var.clone();
+                    let old_value = self.environment.insert(var.clone(), error.to_value());
+
+                    // 执行catch块
+                    let result = self.eval_expr(catch_body)?;
+
+                    // 恢复旧值（如果存在）
+                    if let Some(old) = old_value {
+                        self.environment.insert(var, old);
+                    } else {
+                        self.environment.remove(&var);
+                    }
 
                     Ok(result)
                 } else {
@@ -1412,10 +3613,18 @@ impl Interpreter {
 
     /// 评估字面量 / Evaluate literal
     fn eval_literal(&mut self, lit: &Literal) -> Result<Value, InterpreterError> {
+        let value = self.eval_literal_value(lit)?;
+        self.check_collection_size(&value)?;
+        Ok(value)
+    }
+
+    /// 评估字面量的值 / Evaluate literal value
+    fn eval_literal_value(&mut self, lit: &Literal) -> Result<Value, InterpreterError> {
         match lit {
             Literal::Int(i) => Ok(Value::Int(*i)),
+            Literal::BigInt(n) => Ok(Value::from_bigint(n.clone())),
             Literal::Float(f) => Ok(Value::Float(*f)),
-            Literal::String(s) => Ok(Value::String(s.clone())),
+            Literal::String(s) => Ok(Value::String(s.as_str().into())),
             Literal::Bool(b) => Ok(Value::Bool(*b)),
             Literal::Null => Ok(Value::Null),
             Literal::List(exprs) => {
@@ -1424,22 +3633,22 @@ impl Interpreter {
                 for expr in exprs {
                     list.push(self.eval_expr(expr)?);
                 }
-                Ok(Value::List(list))
+                Ok(Value::List(list.into()))
             }
             Literal::Dict(pairs) => {
-                let mut dict = std::collections::HashMap::new();
+                let mut dict = DictMap::new();
                 for (key, expr) in pairs {
                     let value = self.eval_expr(expr)?;
                     dict.insert(key.clone(), value);
                 }
-                Ok(Value::Dict(dict))
+                Ok(Value::Dict(dict.into()))
             }
         }
     }
 
     /// 评估二元运算 / Evaluate binary operation
     fn eval_binary_op(
-        &self,
+        &mut self,
         op: crate::grammar::core::BinOp,
         left: &Value,
         right: &Value,
@@ -1457,30 +3666,71 @@ impl Interpreter {
         }
     }
 
+    /// 处理整数运算结果（检查溢出）/ Handle integer arithmetic result (checking overflow)
+    /// `checked` 为i64运算结果，溢出时使用 `wide` 计算大整数结果
+    /// `checked` is the i64 result; on overflow `wide` computes the big integer result
+    fn int_result(
+        &self,
+        checked: Option<i64>,
+        operation: &str,
+        wide: impl FnOnce() -> BigInt,
+    ) -> Result<Value, InterpreterError> {
+        match checked {
+            Some(i) => Ok(Value::Int(i)),
+            None => self.bigint_result(wide(), operation),
+        }
+    }
+
+    /// 处理大整数运算结果 / Handle big integer arithmetic result
+    fn bigint_result(&self, n: BigInt, operation: &str) -> Result<Value, InterpreterError> {
+        match Value::from_bigint(n) {
+            Value::BigInt(_) if self.overflow_mode == OverflowMode::Error => Err(
+                InterpreterError::integer_overflow(operation.to_string(), None),
+            ),
+            value => Ok(value),
+        }
+    }
+
     /// 加法运算 / Add values
-    fn add_values(&self, left: &Value, right: &Value) -> Result<Value, InterpreterError> {
+    /// 数值以外的类型经 Add 协议分派 / Types other than numbers dispatch through the Add protocol
+    fn add_values(&mut self, left: &Value, right: &Value) -> Result<Value, InterpreterError> {
         match (left, right) {
-            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a + b)),
+            (Value::Int(a), Value::Int(b)) => {
+                self.int_result(a.checked_add(*b), "addition", || BigInt::from(*a) + *b)
+            }
+            (Value::BigInt(_), Value::Int(_) | Value::BigInt(_))
+            | (Value::Int(_), Value::BigInt(_)) => {
+                let (a, b) = (left.to_bigint().unwrap(), right.to_bigint().unwrap());
+                self.bigint_result(a + b, "addition")
+            }
             (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a + b)),
             (Value::Int(a), Value::Float(b)) => Ok(Value::Float(*a as f64 + *b)),
             (Value::Float(a), Value::Int(b)) => Ok(Value::Float(*a + *b as f64)),
-            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
-            (Value::List(a), Value::List(b)) => {
-                let mut result = a.clone();
-                result.extend_from_slice(b);
-                Ok(Value::List(result))
-            }
-            _ => Err(InterpreterError::type_error(
-                "Invalid types for addition".to_string(),
-                None,
-            )),
+            (Value::BigInt(a), Value::Float(b)) => Ok(Value::Float(bigint_to_f64(a) + *b)),
+            (Value::Float(a), Value::BigInt(b)) => Ok(Value::Float(*a + bigint_to_f64(b))),
+            _ => match self.find_method("add", left) {
+                Some(implementation) => {
+                    self.call_method("add", implementation, &[left.clone(), right.clone()])
+                }
+                None => Err(InterpreterError::type_error(
+                    "Invalid types for addition".to_string(),
+                    None,
+                )),
+            },
         }
     }
 
     /// 减法运算 / Subtract values
     fn sub_values(&self, left: &Value, right: &Value) -> Result<Value, InterpreterError> {
         match (left, right) {
-            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a - b)),
+            (Value::Int(a), Value::Int(b)) => {
+                self.int_result(a.checked_sub(*b), "subtraction", || BigInt::from(*a) - *b)
+            }
+            (Value::BigInt(_), Value::Int(_) | Value::BigInt(_))
+            | (Value::Int(_), Value::BigInt(_)) => {
+                let (a, b) = (left.to_bigint().unwrap(), right.to_bigint().unwrap());
+                self.bigint_result(a - b, "subtraction")
+            }
             (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a - b)),
             _ => Err(InterpreterError::type_error(
                 "Invalid types for subtraction".to_string(),
@@ -1492,7 +3742,16 @@ impl Interpreter {
     /// 乘法运算 / Multiply values
     fn mul_values(&self, left: &Value, right: &Value) -> Result<Value, InterpreterError> {
         match (left, right) {
-            (Value::Int(a), Value::Int(b)) => Ok(Value::Int(a * b)),
+            (Value::Int(a), Value::Int(b)) => {
+                self.int_result(a.checked_mul(*b), "multiplication", || {
+                    BigInt::from(*a) * *b
+                })
+            }
+            (Value::BigInt(_), Value::Int(_) | Value::BigInt(_))
+            | (Value::Int(_), Value::BigInt(_)) => {
+                let (a, b) = (left.to_bigint().unwrap(), right.to_bigint().unwrap());
+                self.bigint_result(a * b, "multiplication")
+            }
             (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a * b)),
             _ => Err(InterpreterError::type_error(
                 "Invalid types for multiplication".to_string(),
@@ -1508,7 +3767,17 @@ impl Interpreter {
                 if *b == 0 {
                     Err(InterpreterError::division_by_zero(None))
                 } else {
-                    Ok(Value::Int(a / b))
+                    // i64::MIN / -1 会溢出 / i64::MIN / -1 overflows
+                    self.int_result(a.checked_div(*b), "division", || BigInt::from(*a) / *b)
+                }
+            }
+            (Value::BigInt(_), Value::Int(_) | Value::BigInt(_))
+            | (Value::Int(_), Value::BigInt(_)) => {
+                let (a, b) = (left.to_bigint().unwrap(), right.to_bigint().unwrap());
+                if b.is_zero() {
+                    Err(InterpreterError::division_by_zero(None))
+                } else {
+                    self.bigint_result(a / b, "division")
                 }
             }
             (Value::Float(a), Value::Float(b)) => {
@@ -1532,7 +3801,18 @@ impl Interpreter {
                 if *b == 0 {
                     Err(InterpreterError::division_by_zero(None))
                 } else {
-                    Ok(Value::Int(a % b))
+                    // i64::MIN % -1 在Rust中会溢出，数学结果为0
+                    // i64::MIN % -1 overflows in Rust, the mathematical result is 0
+                    Ok(Value::Int(a.checked_rem(*b).unwrap_or(0)))
+                }
+            }
+            (Value::BigInt(_), Value::Int(_) | Value::BigInt(_))
+            | (Value::Int(_), Value::BigInt(_)) => {
+                let (a, b) = (left.to_bigint().unwrap(), right.to_bigint().unwrap());
+                if b.is_zero() {
+                    Err(InterpreterError::division_by_zero(None))
+                } else {
+                    self.bigint_result(a % b, "modulo")
                 }
             }
             (Value::Float(a), Value::Float(b)) => {
@@ -1561,26 +3841,8 @@ impl Interpreter {
             _ => {
                 // 添加调试信息以帮助定位问题
                 // Add debug information to help locate the issue
-                let left_type = match left {
-                    Value::Int(_) => "Int",
-                    Value::Float(_) => "Float",
-                    Value::String(_) => "String",
-                    Value::Bool(_) => "Bool",
-                    Value::Null => "Null",
-                    Value::List(_) => "List",
-                    Value::Dict(_) => "Dict",
-                    Value::Lambda { .. } => "Lambda",
-                };
-                let right_type = match right {
-                    Value::Int(_) => "Int",
-                    Value::Float(_) => "Float",
-                    Value::String(_) => "String",
-                    Value::Bool(_) => "Bool",
-                    Value::Null => "Null",
-                    Value::List(_) => "List",
-                    Value::Dict(_) => "Dict",
-                    Value::Lambda { .. } => "Lambda",
-                };
+                let left_type = self.value_type_name(left);
+                let right_type = self.value_type_name(right);
                 Err(InterpreterError::type_error(
                     format!("Invalid types for modulo: {} and {}", left_type, right_type),
                     None,
@@ -1594,224 +3856,1095 @@ impl Interpreter {
         match value {
             Value::Bool(b) => *b,
             Value::Int(i) => *i != 0,
+            Value::BigInt(_) => true, // 大整数总是非零 / Big integers are never zero
             Value::Float(f) => *f != 0.0,
             Value::String(s) => !s.is_empty(),
             Value::Null => false,
             Value::List(list) => !list.is_empty(),
             Value::Dict(dict) => !dict.is_empty(),
+            Value::Set(set) => !set.is_empty(),
             Value::Lambda { .. } => true, // Lambda总是为真
+            Value::Generator { .. } => true,
+            Value::Variant { .. } => true,
+            Value::DateTime(_) => true,
+            Value::Error { .. } => true,
         }
     }
 
     /// 评估函数调用 / Evaluate function call
-    fn eval_call(&mut self, name: &str, args: &[Expr]) -> Result<Value, InterpreterError> {
-        // 检查是否是 lambda 表达式的错误转换
-        // Check if this is a mis-converted lambda expression
-        if name == "lambda" {
-            // 将 Call("lambda", args) 转换回 GrammarElement::List
-            // Convert Call("lambda", args) back to GrammarElement::List
-            let mut lambda_elements = vec![GrammarElement::Atom("lambda".to_string())];
-            for arg in args {
-                lambda_elements.push(self.expr_to_element(arg)?);
-            }
-            return self.eval_list(&lambda_elements);
+    fn eval_call(&mut self, symbol: &Symbol, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let name = symbol.as_str();
+        // 覆盖率探针不是真正的调用，不通知追踪器 / Coverage probes are not real calls and are not traced
+        if let Some(probe) = name.strip_prefix(PROBE_PREFIX) {
+            return self.eval_probe(probe, args);
         }
-
-        // 首先检查是否是Lambda值的调用
-        // First check if it's a call to a Lambda value
-        if let Some(Value::Lambda { id, params }) = self.environment.get(name).cloned() {
-            return self.call_lambda(&id, &params, args);
-        }
-
-        // 检查是否是操作符（如 +, -, * 等）
-        // Check if it's an operator (like +, -, *, etc.)
-        // 操作符可以作为函数名直接调用，也可以作为变量传递
-        // Operators can be called directly as function names, or passed as variables
-        if name == "+"
-            || name == "-"
-            || name == "*"
-            || name == "/"
-            || name == "%"
-            || name == "="
-            || name == "=="
-            || name == "!="
-            || name == "<>"
-            || name == "<"
-            || name == ">"
-            || name == "<="
-            || name == ">="
-        {
-            // 操作符作为函数调用，需要转换为 op: 前缀
-            // Operator as function call, need to convert to op: prefix
-            let op_name = format!("op:{}", name);
-            return self.eval_builtin_operator(&op_name, args);
-        }
-
-        // 检查变量值是否是操作符字符串（当操作符作为参数传递时）
-        // Check if variable value is an operator string (when operator is passed as argument)
-        // 先检查环境中的值
-        // First check value in environment
-        if let Some(Value::String(op_str)) = self.environment.get(name) {
-            if op_str == "+"
-                || op_str == "-"
-                || op_str == "*"
-                || op_str == "/"
-                || op_str == "%"
-                || op_str == "="
-                || op_str == "=="
-                || op_str == "!="
-                || op_str == "<>"
-                || op_str == "<"
-                || op_str == ">"
-                || op_str == "<="
-                || op_str == ">="
-            {
-                let op_name = format!("op:{}", op_str);
-                return self.eval_builtin_operator(&op_name, args);
-            }
-        }
-
-        // 如果 name 本身是操作符字符串（当操作符直接作为函数名时）
-        // If name itself is an operator string (when operator is used directly as function name)
-        if name == "+"
-            || name == "-"
-            || name == "*"
-            || name == "/"
-            || name == "%"
-            || name == "="
-            || name == "=="
-            || name == "!="
-            || name == "<>"
-            || name == "<"
-            || name == ">"
-            || name == "<="
-            || name == ">="
-        {
-            let op_name = format!("op:{}", name);
-            return self.eval_builtin_operator(&op_name, args);
+        if self.tracer.is_some() {
+            return self
+                .eval_call_traced(symbol, args)
+                .map_err(|error| self.note_error_site(name, error));
         }
+        let value = self
+            .eval_call_inner(symbol, args)
+            .map_err(|error| self.note_error_site(name, error))?;
+        // 集合只能通过调用（或字面量）增长 / Collections only grow through calls (or literals)
+        self.check_collection_size(&value)?;
+        Ok(value)
+    }
 
-        // 检查参数中是否有临时存储的 Lambda 值，需要先评估参数
-        // Check if arguments contain temporarily stored Lambda values, need to evaluate arguments first
-        let mut arg_values = Vec::new();
-        for arg in args {
-            if let Expr::Var(var_name) = arg {
-                if var_name.starts_with("__lambda_temp_") || var_name.starts_with("__lambda_arg_") {
-                    // 从环境中获取 Lambda 值
-                    // Get Lambda value from environment
-                    if let Some(lambda_val) = self.environment.get(var_name).cloned() {
-                        arg_values.push(lambda_val);
-                        // 清理临时变量
-                        // Clean up temporary variable
-                        self.environment.remove(var_name);
-                    } else {
-                        // 如果找不到，尝试评估为普通变量
-                        // If not found, try to evaluate as normal variable
-                        arg_values.push(self.eval_expr(arg)?);
-                    }
-                } else {
-                    // 检查是否是环境中的 Lambda 值（当变量是函数参数时）
-                    // Check if it's a Lambda value in environment (when variable is function parameter)
-                    if let Some(Value::Lambda { .. }) = self.environment.get(var_name) {
-                        // 直接从环境中获取 Lambda 值
-                        // Get Lambda value directly from environment
-                        arg_values.push(self.environment.get(var_name).cloned().unwrap());
-                    } else {
-                        arg_values.push(self.eval_expr(arg)?);
-                    }
-                }
-            } else {
-                arg_values.push(self.eval_expr(arg)?);
-            }
+    /// 记录最内层失败的调用或运算（只在还没有函数栈帧时），原样返回错误
+    /// Record the innermost failing call or operation (only while no function frame has been pushed), returning the error unchanged
+    fn note_error_site(&mut self, site: &str, error: InterpreterError) -> InterpreterError {
+        if self.error_trace.site.is_none() && self.error_trace.frames.is_empty() {
+            // 运算符调用在源码中不带 `op:` 前缀 / Operator calls carry no `op:` prefix in the source
+            let site = site.strip_prefix("op:").unwrap_or(site);
+            self.error_trace.site = Some(site.to_string());
         }
+        error
+    }
 
-        // 检查是否是内置操作符
-        if name.starts_with("op:") {
-            // 操作符需要 Expr 参数，所以我们需要转换回去
-            // Operators need Expr arguments, so we need to convert back
-            let op_args: Vec<Expr> = arg_values
-                .iter()
-                .map(|v| self.value_to_expr(v.clone()))
-                .collect::<Result<Vec<_>, _>>()?;
-            return self.eval_builtin_operator(name, &op_args);
-        }
+    /// 当前的用户函数和Lambda调用栈，最外层在前 / Current call stack of user functions and lambdas, outermost first
+    pub fn call_stack(&self) -> &[Symbol] {
+        &self.call_stack
+    }
 
-        // 检查是否是用户定义函数（需要克隆以避免借用冲突）
-        if let Some(func) = self.functions.get(name).cloned() {
-            // 用户定义函数：直接传递 Value，在函数内部处理
-            // User-defined functions: pass Value directly, handle inside function
-            return self.call_user_function_with_values(&func, &arg_values);
-        }
+    /// 调用栈中的函数名，最内层在前 / Function names on the call stack, innermost first
+    pub fn stacktrace(&self) -> Vec<String> {
+        self.call_stack
+            .iter()
+            .rev()
+            .map(|symbol| symbol.as_str().to_string())
+            .collect()
+    }
 
-        // 如果找不到函数且函数名不包含命名空间，尝试在所有已导入的模块中查找
-        // If function not found and name doesn't contain namespace, try to find in all imported modules
-        if !name.contains('.') {
-            // 先尝试当前模块
-            if let Some(ref module_name) = self.current_module {
-                if let Some(module) = self.modules.get(module_name) {
-                    if let Some(func) = module.functions.get(name).cloned() {
-                        // 找到模块内的函数，调用它
-                        // Found function in module, call it
-                        return self.call_user_function_with_values(&func, &arg_values);
-                    }
-                }
-            }
+    /// 最近一次未捕获错误的调用栈和出错位置（`execute` 返回错误后读取）
+    /// Call stack and site of the most recent uncaught error (read after `execute` returns an error)
+    pub fn error_trace(&self) -> &ErrorTrace {
+        &self.error_trace
+    }
 
-            // 尝试所有已导入的模块
-            // Try all imported modules
-            for module in self.modules.values() {
-                if let Some(func) = module.functions.get(name).cloned() {
-                    // 找到模块内的函数，调用它
-                    // Found function in module, call it
-                    return self.call_user_function_with_values(&func, &arg_values);
-                }
-            }
+    /// 记录探针命中（开启覆盖率时）并求值被包住的表达式
+    /// Record a probe hit (when coverage is on) and evaluate the wrapped expression
+    fn eval_probe(&mut self, probe: &str, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let [expr] = args else {
+            return Err(InterpreterError::runtime_error(
+                format!("Coverage probe {} requires 1 argument", probe),
+                None,
+            ));
+        };
+        let Ok(id) = probe.parse::<usize>() else {
+            return self.eval_expr(expr);
+        };
+        if let Some(hits) = self.coverage.as_mut() {
+            *hits.entry(id).or_default() += 1;
         }
-
-        // 检查是否是内置函数
-        // Check if built-in function
-        // 将 Value 转换回 Expr（Lambda 值需要特殊处理）
-        // Convert Value back to Expr (Lambda values need special handling)
-        let mut func_args = Vec::new();
-        for val in arg_values {
-            // Lambda 值无法转换为 Expr，需要存储到环境中
-            // Lambda values cannot be converted to Expr, need to store in environment
-            if let Value::Lambda { .. } = val {
-                // 创建临时变量名
-                // Create temporary variable name
-                let temp_name = format!("__lambda_arg_{}", func_args.len());
-                self.environment.insert(temp_name.clone(), val);
-                func_args.push(Expr::Var(temp_name));
-            } else {
-                func_args.push(self.value_to_expr(val)?);
-            }
+        if self.debug_hook.is_some() {
+            self.debug_event(DebugEvent::Probe { id })?;
         }
-        self.eval_builtin_function(name, &func_args)
+        self.eval_expr(expr)
     }
 
-    /// 评估内置操作符 / Evaluate built-in operator
-    fn eval_builtin_operator(
+    /// 评估函数调用并通知追踪器 / Evaluate function call and notify the tracer
+    fn eval_call_traced(
         &mut self,
-        name: &str,
+        symbol: &Symbol,
         args: &[Expr],
     ) -> Result<Value, InterpreterError> {
-        let op_str = name.strip_prefix("op:").unwrap();
+        let traced_name = symbol.strip_prefix("op:").unwrap_or(symbol);
+        let depth = self.trace_depth;
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.enter(traced_name, depth);
+        }
+        self.trace_depth += 1;
+        let started_at = Timestamp::now();
 
-        // 特殊处理：+ 运算符支持可变参数
-        // Special handling: + operator supports variadic arguments
-        if op_str == "+" {
-            if args.is_empty() {
+        let result = self
+            .eval_call_inner(symbol, args)
+            .and_then(|value| self.check_collection_size(&value).map(|_| value));
+
+        let elapsed = started_at.elapsed();
+        self.trace_depth = depth;
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.exit(traced_name, depth, elapsed, result.as_ref());
+        }
+        result
+    }
+
+    /// 查询函数的文档字符串：`(doc name)`，没有文档字符串时返回 null
+    /// Look up a function's docstring: `(doc name)`, null when it has none
+    fn eval_doc(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let [Expr::Var(name)] = args else {
+            return Err(InterpreterError::runtime_error(
+                "doc requires 1 argument: function name".to_string(),
+                None,
+            ));
+        };
+        let function = match self.resolve_call_target(name) {
+            CallTarget::Function(function) | CallTarget::ModuleFunction(function) => function,
+            _ => {
                 return Err(InterpreterError::runtime_error(
-                    "Operator + requires at least 1 argument".to_string(),
+                    format!("Unknown function: {}", name),
                     None,
-                ));
+                ))
             }
+        };
+        Ok(match &function.doc {
+            Some(doc) => Value::String(doc.as_str().into()),
+            None => Value::Null,
+        })
+    }
 
-            // 评估所有参数
-            // Evaluate all arguments
-            let mut values = Vec::new();
-            for arg in args {
-                values.push(self.eval_expr(arg)?);
+    /// 推导式：运行解析器脱糖得到的 `for` 循环，收集每次循环体的值；`for/dict` 的循环体返回 `(list key value)`。
+    /// 有第二个变量时，每一项（或字典按插入顺序的每个条目）是键值对，分别绑定到两个变量
+    /// Comprehension: run the `for` loop the parser desugared it into, collecting the body's value on each
+    /// iteration; the body of `for/dict` returns `(list key value)`. With a second variable, every item (or every
+    /// dict entry, in insertion order) is a key-value pair bound to the two variables
+    fn eval_comprehension(&mut self, name: &str, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let (var, iterable, body, value_var) = match args {
+            [Expr::For {
+                var,
+                iterable,
+                body,
+            }] => (var, iterable, body, None),
+            [Expr::For {
+                var,
+                iterable,
+                body,
+            }, Expr::Var(value_var)] => (var, iterable, body, Some(value_var.clone())),
+            _ => {
+                return Err(InterpreterError::runtime_error(
+                    format!(
+                        "{} requires a binding (var iterable) or (key value iterable) and a body",
+                        name
+                    ),
+                    None,
+                ))
+            }
+        };
+
+        let iterable = match (self.eval_expr(iterable)?, &value_var) {
+            (Value::Dict(dict), Some(_)) => {
+                let entries: Vec<Value> = dict
+                    .iter()
+                    .map(|(key, value)| {
+                        Value::List(vec![Value::String(key.as_str().into()), value.clone()].into())
+                    })
+                    .collect();
+                Value::List(entries.into())
+            }
+            (iterable, _) => iterable,
+        };
+
+        let key_var = var.clone();
+        let value_old = value_var
+            .as_ref()
+            .and_then(|symbol| self.environment.get(symbol).cloned());
+        let mut items = Vec::new();
+        let result = self.eval_loop(var, &iterable, |this| {
+            if let Some(value_var) = &value_var {
+                let (key, value) = match this.environment.get(&key_var) {
+                    Some(Value::List(pair)) if pair.len() == 2 => {
+                        (pair[0].clone(), pair[1].clone())
+                    }
+                    other => {
+                        return Err(InterpreterError::type_error(
+                            format!("{} expects key-value pairs, got {:?}", name, other),
+                            None,
+                        ))
+                    }
+                };
+                this.environment.insert(key_var.clone(), key);
+                this.environment.insert(value_var.clone(), value);
+            }
+            items.push(this.eval_expr(body)?);
+            Ok(Value::Null)
+        });
+        if let Some(value_var) = value_var {
+            match value_old {
+                Some(old) => self.environment.insert(value_var, old),
+                None => self.environment.remove(&value_var),
+            };
+        }
+        result?;
+
+        if name == "for/list" {
+            return Ok(Value::List(items.into()));
+        }
+        let mut dict = DictMap::with_capacity(items.len());
+        for item in items {
+            match item {
+                Value::List(pair) if pair.len() == 2 => match &pair[0] {
+                    Value::String(key) => {
+                        dict.insert(key.to_string(), pair[1].clone());
+                    }
+                    other => {
+                        return Err(InterpreterError::type_error(
+                            format!("for/dict keys must be strings, got {:?}", other),
+                            None,
+                        ))
+                    }
+                },
+                other => {
+                    return Err(InterpreterError::type_error(
+                        format!(
+                            "for/dict body must return (list key value), got {:?}",
+                            other
+                        ),
+                        None,
+                    ))
+                }
+            }
+        }
+        Ok(Value::Dict(dict.into()))
+    }
+
+    /// 缓存函数：`(memoize f)` 返回参数相同时直接返回上次结果的Lambda；`f` 是Lambda值或 `def` 函数名。
+    /// 用 `(let f (memoize f))` 重新绑定后，`f` 内部的递归调用同样经过缓存
+    /// Memoize a function: `(memoize f)` returns a lambda that returns the previous result for equal arguments; `f`
+    /// is a lambda value or the name of a `def` function. After rebinding with `(let f (memoize f))`, recursive calls
+    /// inside `f` go through the cache too
+    fn eval_memoize(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let [function] = args else {
+            return Err(InterpreterError::runtime_error(
+                "memoize requires 1 argument: function".to_string(),
+                None,
+            ));
+        };
+        let (target, params) = match function {
+            Expr::Var(name) if self.lookup_symbol(name).is_none() => {
+                match self.resolve_call_target(name) {
+                    CallTarget::Function(function) | CallTarget::ModuleFunction(function) => (
+                        Value::String(name.as_str().into()),
+                        function
+                            .params
+                            .iter()
+                            .map(|param| param.as_str().to_string())
+                            .collect::<Vec<_>>(),
+                    ),
+                    _ => {
+                        return Err(InterpreterError::runtime_error(
+                            format!("memoize: unknown function {}", name),
+                            None,
+                        ))
+                    }
+                }
+            }
+            _ => match self.eval_expr(function)? {
+                Value::Lambda { id, params } => (
+                    Value::Lambda {
+                        id,
+                        params: params.clone(),
+                    },
+                    params,
+                ),
+                other => {
+                    return Err(InterpreterError::type_error(
+                        format!("memoize expects a function, got {:?}", other),
+                        None,
+                    ))
+                }
+            },
+        };
+
+        self.lambda_counter += 1;
+        let id = format!("__lambda_{}", self.lambda_counter);
+        let body = Expr::Call(
+            MEMO_CALL.into(),
+            std::iter::once(Expr::Literal(Literal::String(id.clone())))
+                .chain(params.iter().map(|param| Expr::Var(param.into())))
+                .collect(),
+        );
+        self.lambda_registry.insert(
+            id.clone(),
+            (
+                params.iter().map(Symbol::from).collect(),
+                GrammarElement::Expr(Box::new(body)),
+                HashMap::from([(Symbol::intern(MEMO_TARGET), target)]),
+            ),
+        );
+        self.memo_tables.insert(id.clone(), HashMap::new());
+        Ok(Value::Lambda { id, params })
+    }
+
+    /// 缓存Lambda的函数体：命中时返回缓存的结果，否则调用被缓存的函数并记录结果
+    /// Body of a memoizing lambda: return the cached result on a hit, otherwise call the memoized function and record
+    /// the result
+    fn eval_memo_call(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let Some((Expr::Literal(Literal::String(id)), params)) = args.split_first() else {
+            return Err(InterpreterError::runtime_error(
+                format!("{} requires a cache id", MEMO_CALL),
+                None,
+            ));
+        };
+        let arg_values = params
+            .iter()
+            .map(|param| self.eval_expr(param))
+            .collect::<Result<Vec<_>, _>>()?;
+        let key = format!("{:?}", arg_values);
+        if let Some(value) = self.memo_tables.get(id).and_then(|table| table.get(&key)) {
+            return Ok(value.clone());
+        }
+
+        let value = match self.lookup_var(MEMO_TARGET).cloned() {
+            Some(Value::Lambda {
+                id: target,
+                params: target_params,
+            }) => self.call_lambda(&Symbol::intern("memoize"), &target, &target_params, params)?,
+            Some(Value::String(name)) => {
+                let symbol = Symbol::intern(&name);
+                match self.resolve_call_target(&symbol) {
+                    CallTarget::Function(function) | CallTarget::ModuleFunction(function) => {
+                        self.call_user_function_with_values(&symbol, &function, &arg_values)?
+                    }
+                    _ => {
+                        return Err(InterpreterError::runtime_error(
+                            format!("memoize: unknown function {}", name),
+                            None,
+                        ))
+                    }
+                }
+            }
+            _ => {
+                return Err(InterpreterError::runtime_error(
+                    "memoize: memoized function not found".to_string(),
+                    None,
+                ))
+            }
+        };
+        // 生成器有状态，不能共享 / Generators are stateful and cannot be shared
+        if !matches!(value, Value::Generator { .. }) {
+            // 快照恢复后缓存表为空，按需重建 / Cache tables are empty after restoring a snapshot and are rebuilt on demand
+            self.memo_tables
+                .entry(id.clone())
+                .or_default()
+                .insert(key, value.clone());
+        }
+        Ok(value)
+    }
+
+    /// 剖析一个表达式：临时安装 Profiler，返回值和报告
+    /// Profile an expression: temporarily install a Profiler and return the value with the report
+    fn eval_profile(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let [expr] = args else {
+            return Err(InterpreterError::runtime_error(
+                "profile requires 1 argument: expression".to_string(),
+                None,
+            ));
+        };
+        let profiler = Profiler::new();
+        let outer_tracer = self.tracer.replace(Box::new(profiler.clone()));
+        let outer_depth = std::mem::replace(&mut self.trace_depth, 0);
+        let steps_before = self.steps;
+        let started_at = Timestamp::now();
+
+        let result = self.eval_expr(expr);
+
+        let total_time = started_at.elapsed();
+        self.tracer = outer_tracer;
+        self.trace_depth = outer_depth;
+        let mut report = profiler.report();
+        report.total_time = total_time;
+        report.steps = self.steps - steps_before;
+
+        let mut dict = report.to_dict();
+        dict.insert("value".to_string(), result?);
+        Ok(Value::Dict(dict.into()))
+    }
+
+    /// 求值位置参数和 `:名称 值` 形式的选项（选项在位置参数之后）
+    /// Evaluate positional arguments and options written as `:name value` (options follow the positional arguments)
+    fn eval_keyword_args(
+        &mut self,
+        name: &str,
+        args: &[Expr],
+    ) -> Result<KeywordArgs, InterpreterError> {
+        let split = args
+            .iter()
+            .position(|arg| matches!(arg, Expr::Var(colon) if colon == ":"))
+            .unwrap_or(args.len());
+        let mut positional = Vec::with_capacity(split);
+        for arg in &args[..split] {
+            positional.push(self.eval_expr(arg)?);
+        }
+        let mut options = Vec::new();
+        let mut rest = &args[split..];
+        while let [Expr::Var(colon), Expr::Var(key), value, tail @ ..] = rest {
+            if colon != ":" {
+                break;
+            }
+            options.push((key.to_string(), self.eval_expr(value)?));
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            return Err(InterpreterError::runtime_error(
+                format!("{}: options must be written as :name value", name),
+                None,
+            ));
+        }
+        Ok((positional, options))
+    }
+
+    /// 数值格式化和解析：format-number、to-fixed、parse-int、parse-float
+    /// Number formatting and parsing: format-number, to-fixed, parse-int, parse-float
+    fn eval_number_builtin(
+        &mut self,
+        name: &str,
+        args: &[Expr],
+    ) -> Result<Value, InterpreterError> {
+        let (positional, options) = self.eval_keyword_args(name, args)?;
+        let mut digits = None;
+        let mut locale = None;
+        let mut group = true;
+        let mut base = 10;
+        for (key, value) in options {
+            match (name, key.as_str(), value) {
+                ("format-number", "digits", Value::Int(n)) if (0..=100).contains(&n) => {
+                    digits = Some(n as usize)
+                }
+                ("format-number" | "parse-float", "locale", Value::String(tag)) => {
+                    locale = Some(number_separators(&tag)?)
+                }
+                ("format-number", "group", Value::Bool(b)) => group = b,
+                ("parse-int", "base", Value::Int(n)) if (2..=36).contains(&n) => base = n as u32,
+                (_, key, value) => {
+                    return Err(InterpreterError::runtime_error(
+                        format!("{}: invalid option :{} {}", name, key, value),
+                        None,
+                    ))
+                }
+            }
+        }
+
+        match (name, positional.as_slice()) {
+            ("format-number", [number]) => {
+                let text = number_text(number, digits).ok_or_else(|| {
+                    InterpreterError::type_error(
+                        format!(
+                            "format-number requires a number, got {}",
+                            self.value_type_name(number)
+                        ),
+                        None,
+                    )
+                })?;
+                let (separator, decimal) = locale.unwrap_or((",", '.'));
+                Ok(Value::String(
+                    localize_number(&text, group.then_some(separator), decimal).into(),
+                ))
+            }
+            ("to-fixed", [number, Value::Int(n)]) if (0..=100).contains(n) => {
+                match number_text(number, Some(*n as usize)) {
+                    Some(text) => Ok(Value::String(text.into())),
+                    None => Err(InterpreterError::type_error(
+                        format!(
+                            "to-fixed requires a number, got {}",
+                            self.value_type_name(number)
+                        ),
+                        None,
+                    )),
+                }
+            }
+            ("parse-int", [Value::String(text)]) => {
+                parse_int_radix(text, base).ok_or_else(|| {
+                    InterpreterError::type_error(
+                        format!("Cannot parse '{}' as a base-{} integer", text, base),
+                        None,
+                    )
+                })
+            }
+            ("parse-float", [Value::String(text)]) => {
+                let normalized = match locale {
+                    Some((separator, decimal)) => text
+                        .trim()
+                        .replace(separator, "")
+                        .replace(decimal, "."),
+                    None => text.trim().to_string(),
+                };
+                normalized.parse::<f64>().map(Value::Float).map_err(|_| {
+                    InterpreterError::type_error(
+                        format!("Cannot parse '{}' as a float", text),
+                        None,
+                    )
+                })
+            }
+            _ => Err(InterpreterError::runtime_error(
+                match name {
+                    "format-number" => {
+                        "format-number requires a number and optional :digits N :locale \"de\" :group false"
+                    }
+                    "to-fixed" => "to-fixed requires a number and a digit count from 0 to 100",
+                    "parse-int" => "parse-int requires a string and optional :base N (2 to 36)",
+                    _ => "parse-float requires a string and optional :locale \"de\"",
+                }
+                .to_string(),
+                None,
+            )),
+        }
+    }
+
+    /// 基准测试一个表达式：`(bench expr :iterations N :warmup M)`，返回计时统计和最后一次的值
+    /// Benchmark an expression: `(bench expr :iterations N :warmup M)`, returning timing statistics and the last value
+    fn eval_bench(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let usage = || {
+            InterpreterError::runtime_error(
+                "bench requires an expression and optional :iterations N :warmup M".to_string(),
+                None,
+            )
+        };
+        let (expr, mut options) = args.split_first().ok_or_else(usage)?;
+        let mut config = BenchConfig::default();
+        let mut warmup = None;
+        while let [Expr::Var(colon), Expr::Var(key), value, rest @ ..] = options {
+            if colon != ":" {
+                return Err(usage());
+            }
+            let count = match self.eval_expr(value)? {
+                Value::Int(n) if n >= 0 => n as usize,
+                other => {
+                    return Err(InterpreterError::type_error(
+                        format!(
+                            "bench :{} must be a non-negative integer, got {}",
+                            key, other
+                        ),
+                        None,
+                    ))
+                }
+            };
+            match key.as_str() {
+                "iterations" => config = BenchConfig::with_iterations(count),
+                "warmup" => warmup = Some(count),
+                _ => {
+                    return Err(InterpreterError::runtime_error(
+                        format!("bench: unknown option :{}", key),
+                        None,
+                    ))
+                }
+            }
+            options = rest;
+        }
+        if !options.is_empty() {
+            return Err(usage());
+        }
+        if let Some(warmup) = warmup {
+            config.warmup = warmup;
+        }
+
+        let mut last_value = Value::Null;
+        let stats = bench::bench(&config, || {
+            last_value = self.eval_expr(expr)?;
+            Ok::<_, InterpreterError>(())
+        })?;
+        let mut dict = stats.to_dict();
+        dict.insert("value".to_string(), last_value);
+        Ok(Value::Dict(dict.into()))
+    }
+
+    /// 注册测试：`(deftest name body...)`，同名测试被替换
+    /// Register a test: `(deftest name body...)`; a test with the same name is replaced
+    fn eval_deftest(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let name = match args.first() {
+            Some(Expr::Var(name)) => name.clone(),
+            Some(Expr::Literal(Literal::String(name))) => name.as_str().into(),
+            _ => {
+                return Err(InterpreterError::runtime_error(
+                    "deftest requires a name and a body: (deftest name body...)".to_string(),
+                    None,
+                ))
+            }
+        };
+        let body = args[1..].to_vec();
+        if body.is_empty() {
+            return Err(InterpreterError::runtime_error(
+                format!("deftest {} requires a body", name),
+                None,
+            ));
+        }
+        match self
+            .tests
+            .iter_mut()
+            .find(|(existing, _)| *existing == name.as_str())
+        {
+            Some(test) => test.1 = body,
+            None => self.tests.push((name.to_string(), body)),
+        }
+        Ok(Value::Null)
+    }
+
+    /// 断言表达式为真：`(assert expr [message])`，失败时抛出 AssertionError
+    /// Assert an expression is truthy: `(assert expr [message])`, raising AssertionError on failure
+    fn eval_assert(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let (expr, message) = match args {
+            [expr] => (expr, None),
+            [expr, message] => (expr, Some(message)),
+            _ => {
+                return Err(InterpreterError::runtime_error(
+                    "assert requires 1 or 2 arguments: expression, [message]".to_string(),
+                    None,
+                ))
+            }
+        };
+        let value = self.eval_expr(expr)?;
+        if self.is_truthy(&value) {
+            return Ok(Value::Bool(true));
+        }
+        let message = match message {
+            Some(message) => self.eval_expr(message)?.to_string(),
+            None => format!(
+                "assertion failed: {}",
+                crate::grammar::unparse::unparse_expr(expr)
+            ),
+        };
+        Err(InterpreterError::raised(
+            "AssertionError".to_string(),
+            message,
+            value,
+            None,
+        ))
+    }
+
+    /// 断言两个值相等：`(assert-eq actual expected)`，失败时抛出 AssertionError（数据为两个值）
+    /// Assert two values are equal: `(assert-eq actual expected)`, raising AssertionError (with both values as data)
+    fn eval_assert_eq(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let [actual_expr, expected_expr] = args else {
+            return Err(InterpreterError::runtime_error(
+                "assert-eq requires 2 arguments: actual, expected".to_string(),
+                None,
+            ));
+        };
+        let actual = self.eval_expr(actual_expr)?;
+        let expected = self.eval_expr(expected_expr)?;
+        if actual == expected {
+            return Ok(Value::Bool(true));
+        }
+        let message = format!(
+            "{}: expected {}, got {}",
+            crate::grammar::unparse::unparse_expr(actual_expr),
+            expected,
+            actual
+        );
+        let data = DictMap::from([
+            ("expected".to_string(), expected),
+            ("actual".to_string(), actual),
+        ]);
+        Err(InterpreterError::raised(
+            "AssertionError".to_string(),
+            message,
+            Value::Dict(data.into()),
+            None,
+        ))
+    }
+
+    /// 运行已注册的测试（可按名称子串过滤），每个测试在独立的变量环境中运行；
+    /// 测试体抛错或返回 false 即失败，超出资源预算则中止整个运行
+    /// Run registered tests (optionally filtered by name substring), each in its own variable environment;
+    /// a test fails when its body raises or returns false, and exceeding the resource budget aborts the run
+    fn run_tests(&mut self, filter: Option<&str>) -> Result<Value, InterpreterError> {
+        let tests: Vec<(String, Vec<Expr>)> = self
+            .tests
+            .iter()
+            .filter(|(name, _)| filter.is_none_or(|filter| name.contains(filter)))
+            .cloned()
+            .collect();
+        let mut results = Vec::with_capacity(tests.len());
+        let mut passed = 0;
+        for (name, body) in &tests {
+            let environment = self.environment.clone();
+            let call_depth = self.call_stack.len();
+            let mut outcome = Ok(Value::Null);
+            for expr in body {
+                outcome = self.eval_expr(expr);
+                if outcome.is_err() {
+                    break;
+                }
+            }
+            self.environment = environment;
+            self.call_stack.truncate(call_depth);
+            self.error_trace = ErrorTrace::default();
+            let failure = match outcome {
+                Ok(Value::Bool(false)) => Some("test returned false".to_string()),
+                Ok(_) => None,
+                Err(error @ InterpreterError::BudgetExceeded { .. }) => return Err(error),
+                Err(error) => match error.to_value() {
+                    Value::Error { kind, message, .. } => Some(format!("{}: {}", kind, message)),
+                    _ => Some(error.to_string()),
+                },
+            };
+            match &failure {
+                Some(message) => self.write_output(&format!("FAIL {}: {}", name, message))?,
+                None => passed += 1,
+            }
+            let result = DictMap::from([
+                ("name".to_string(), Value::String(name.as_str().into())),
+                ("passed".to_string(), Value::Bool(failure.is_none())),
+                (
+                    "message".to_string(),
+                    failure.map_or(Value::Null, |message| Value::String(message.into())),
+                ),
+            ]);
+            results.push(Value::Dict(result.into()));
+        }
+        let total = tests.len();
+        self.write_output(&format!("{}/{} passed", passed, total))?;
+        let report = DictMap::from([
+            ("total".to_string(), Value::Int(total as i64)),
+            ("passed".to_string(), Value::Int(passed as i64)),
+            ("failed".to_string(), Value::Int((total - passed) as i64)),
+            ("results".to_string(), Value::List(results.into())),
+        ]);
+        Ok(Value::Dict(report.into()))
+    }
+
+    /// 评估函数调用（不检查结果大小）/ Evaluate function call (without checking result size)
+    fn eval_call_inner(
+        &mut self,
+        symbol: &Symbol,
+        args: &[Expr],
+    ) -> Result<Value, InterpreterError> {
+        let name = symbol.as_str();
+        // 检查是否是 lambda 表达式的错误转换
+        // Check if this is a mis-converted lambda expression
+        if name == "lambda" {
+            // 将 Call("lambda", args) 转换回 GrammarElement::List
+            // Convert Call("lambda", args) back to GrammarElement::List
+            let mut lambda_elements = vec![GrammarElement::Atom("lambda".to_string())];
+            for arg in args {
+                lambda_elements.push(self.expr_to_element(arg)?);
+            }
+            return self.eval_list(&lambda_elements);
+        }
+
+        // 循环体中的 let（和 defconst）同样被转换为调用，绑定名不能当作变量求值
+        // A let (or defconst) inside a loop body is likewise converted to a call, and its name must not be evaluated
+        // as a variable
+        if name == "let" || name == "defconst" {
+            // 参数保持为表达式，避免转换丢失循环等形式 / Arguments stay expressions so loops and the like survive
+            let mut let_elements = vec![GrammarElement::Atom(name.to_string())];
+            let_elements.extend(
+                args.iter()
+                    .map(|arg| GrammarElement::Expr(Box::new(arg.clone()))),
+            );
+            return self.eval_list(&let_elements);
+        }
+
+        // export 的参数是名称而不是值，需要在评估参数之前处理
+        // export arguments are names rather than values, so handle it before evaluating arguments
+        if name == "export" {
+            return self.eval_export(args);
+        }
+
+        // import 的选项同样不求值 / import options are likewise left unevaluated
+        if name == "import" {
+            return self.eval_import(args);
+        }
+
+        // deftype 的参数是类型和构造器声明，同样不求值
+        // deftype arguments are type and constructor declarations, also left unevaluated
+        if name == "deftype" {
+            return self.eval_deftype(args);
+        }
+
+        // doc 的参数是函数名，不求值 / doc takes a function name, left unevaluated
+        if name == "doc" {
+            return self.eval_doc(args);
+        }
+
+        // memoize 的参数可以是 `def` 函数名，它不是变量 / memoize accepts a `def` function name, which is not a variable
+        if name == "memoize" {
+            return self.eval_memoize(args);
+        }
+        if name == MEMO_CALL {
+            return self.eval_memo_call(args);
+        }
+
+        // 推导式的参数是循环本身，由推导式驱动 / A comprehension's argument is the loop itself, driven by the comprehension
+        if name == "for/list" || name == "for/dict" {
+            return self.eval_comprehension(name, args);
+        }
+
+        // profile 需要在安装剖析器之后再求值其参数 / profile evaluates its argument only after installing the profiler
+        if name == "profile" {
+            return self.eval_profile(args);
+        }
+
+        // bench 要重复求值其参数，选项以 `:名称 值` 给出 / bench re-evaluates its argument; options come as `:name value`
+        if name == "bench" {
+            return self.eval_bench(args);
+        }
+
+        // 数值格式化和解析的选项以 `:名称 值` 给出 / Options of number formatting and parsing come as `:name value`
+        if matches!(
+            name,
+            "format-number" | "to-fixed" | "parse-int" | "parse-float"
+        ) {
+            return self.eval_number_builtin(name, args);
+        }
+
+        // 测试形式需要未求值的参数：deftest 延迟测试体，断言用源码描述失败
+        // Testing forms need unevaluated arguments: deftest defers its body, assertions describe failures with source
+        match name {
+            "deftest" => return self.eval_deftest(args),
+            "assert" => return self.eval_assert(args),
+            "assert-eq" => return self.eval_assert_eq(args),
+            _ => {}
+        }
+
+        // 变量中的Lambda值随环境变化，每次调用都要检查
+        // Lambda values held in variables change with the environment, so check them on every call
+        match self.environment.get(symbol) {
+            Some(Value::Lambda { id, params }) => {
+                let (id, params) = (id.clone(), params.clone());
+                return self.call_lambda(symbol, &id, &params, args);
+            }
+            // 操作符作为参数传递时，变量值是操作符字符串
+            // When an operator is passed as an argument, the variable holds the operator string
+            Some(Value::String(op)) if !is_operator(name) && is_operator(op) => {
+                let op = Symbol::intern(op);
+                return self.eval_builtin_operator(&op, args);
+            }
+            _ => {}
+        }
+
+        // 操作符可以作为函数名直接调用（如 `(+ 1 2)`），也可以带 op: 前缀
+        // Operators can be called directly as function names (like `(+ 1 2)`), or with the op: prefix
+        if is_operator(name) {
+            return self.eval_builtin_operator(name, args);
+        }
+        if let Some(op) = name.strip_prefix("op:") {
+            return self.eval_builtin_operator(op, args);
+        }
+
+        let arg_values = self.eval_call_args(args)?;
+
+        // 参数求值可能定义函数或导入模块，所以在求值之后再查缓存
+        // Evaluating arguments may define functions or import modules, so consult the cache afterwards
+        let target = match self.call_cache.get(symbol) {
+            Some(cached) if cached.module == self.current_module => {
+                self.call_cache_stats.hits += 1;
+                cached.target.clone()
+            }
+            _ => {
+                self.call_cache_stats.misses += 1;
+                let target = self.resolve_call_target(symbol);
+                self.call_cache.insert(
+                    symbol.clone(),
+                    CachedCall {
+                        module: self.current_module.clone(),
+                        target: target.clone(),
+                    },
+                );
+                target
+            }
+        };
+
+        match target {
+            CallTarget::Function(func) => {
+                if self.native_backend.is_some() {
+                    if let Some(value) = self.call_native(name, &arg_values) {
+                        return Ok(value);
+                    }
+                }
+                // 用户定义函数：直接传递 Value，在函数内部处理
+                // User-defined functions: pass Value directly, handle inside function
+                self.call_user_function_with_values(symbol, &func, &arg_values)
+            }
+            CallTarget::ModuleFunction(func) => {
+                self.call_user_function_with_values(symbol, &func, &arg_values)
+            }
+            CallTarget::Method => self.call_protocol_method(name, &arg_values),
+            CallTarget::Constructor(field_count) => {
+                if field_count != arg_values.len() {
+                    return Err(InterpreterError::runtime_error(
+                        format!(
+                            "Constructor {} expects {} field(s), got {}",
+                            name,
+                            field_count,
+                            arg_values.len()
+                        ),
+                        None,
+                    ));
+                }
+                Ok(Value::Variant {
+                    tag: name.to_string(),
+                    fields: arg_values,
+                })
+            }
+            CallTarget::Native(native) => native(self, &arg_values),
+            CallTarget::Host(host) => host(&arg_values),
+            CallTarget::Builtin => self.call_builtin(name, arg_values),
+        }
+    }
+
+    /// 按查找顺序解析函数名：用户函数、当前模块、已导入模块的导出、协议方法、构造器、原生模块、宿主函数，最后是内置函数
+    /// Resolve a function name in lookup order: user functions, the current module, exports of imported modules,
+    /// protocol methods, constructors, native modules, host functions and finally builtins
+    fn resolve_call_target(&self, symbol: &Symbol) -> CallTarget {
+        let name = symbol.as_str();
+        if let Some(func) = self.functions.get(symbol) {
+            return CallTarget::Function(Arc::new(func.clone()));
+        }
+
+        // 先尝试当前模块（包括模块自身导入的带命名空间函数）
+        // Try current module first (including namespaced functions it imported itself)
+        if let Some(func) = self
+            .current_module
+            .as_ref()
+            .and_then(|module_name| self.modules.get(module_name))
+            .and_then(|module| module.functions.get(symbol))
+        {
+            return CallTarget::ModuleFunction(Arc::new(func.clone()));
+        }
+
+        // 函数名不包含命名空间时，尝试所有已导入模块导出的函数
+        // If the name has no namespace, try functions exported by all imported modules
+        if !name.contains('.') {
+            for module in self.modules.values() {
+                if !module.is_exported(name) || !self.selects_bare_name(&module.name, name) {
+                    continue;
+                }
+                if let Some(func) = module.functions.get(symbol) {
+                    return CallTarget::ModuleFunction(Arc::new(func.clone()));
+                }
+            }
+        }
+
+        if self.protocol_of(name).is_some() {
+            return CallTarget::Method;
+        }
+        if let Some(constructor) = self.variant_constructors.get(name) {
+            return CallTarget::Constructor(constructor.fields.len());
+        }
+        // 原生模块函数（如 math.sqrt）/ Native module function (e.g. math.sqrt)
+        if let Some(native) = self.native_function(name) {
+            return CallTarget::Native(native);
+        }
+        if let Some(host) = self.host_functions.get(name) {
+            return CallTarget::Host(host.clone());
+        }
+        CallTarget::Builtin
+    }
+
+    /// 检查不带命名空间的名称能否经由模块访问：当前作用域（当前模块或顶层）只通过 `:only` 导入该模块时，
+    /// 名称必须被某个别名选中；作用域未直接导入的模块不受限制
+    /// Check whether an unqualified name is reachable through a module: when the current scope (current module or
+    /// top level) imported the module only with `:only`, some alias must select the name; modules the scope did not
+    /// import directly are unrestricted
+    fn selects_bare_name(&self, module_name: &str, name: &str) -> bool {
+        let (aliases, selections) = match self
+            .current_module
+            .as_ref()
+            .and_then(|module| self.modules.get(module))
+        {
+            Some(module) => (&module.module_aliases, &module.selective_imports),
+            None => (&self.module_aliases, &self.selective_imports),
+        };
+        match aliases.get(module_name) {
+            Some(aliases) => aliases
+                .iter()
+                .any(|alias| selections.get(alias).is_none_or(|only| only.contains(name))),
+            None => true,
+        }
+    }
+
+    /// 求值调用参数，取出临时存储的 Lambda 值 / Evaluate call arguments, taking out temporarily stored Lambda values
+    fn eval_call_args(&mut self, args: &[Expr]) -> Result<Vec<Value>, InterpreterError> {
+        let mut arg_values = Vec::with_capacity(args.len());
+        for arg in args {
+            if let Expr::Var(var_name) = arg {
+                if var_name.starts_with("__lambda_temp_") || var_name.starts_with("__lambda_arg_") {
+                    // 从环境中获取 Lambda 值
+                    // Get Lambda value from environment
+                    if let Some(lambda_val) = self.lookup_symbol(var_name).cloned() {
+                        arg_values.push(lambda_val);
+                        // 清理临时变量
+                        // Clean up temporary variable
+                        self.environment.remove(var_name);
+                    } else {
+                        // 如果找不到，尝试评估为普通变量
+                        // If not found, try to evaluate as normal variable
+                        arg_values.push(self.eval_expr(arg)?);
+                    }
+                } else {
+                    // 检查是否是环境中的 Lambda 值（当变量是函数参数时）
+                    // Check if it's a Lambda value in environment (when variable is function parameter)
+                    if let Some(Value::Lambda { .. }) = self.lookup_symbol(var_name) {
+                        // 直接从环境中获取 Lambda 值
+                        // Get Lambda value directly from environment
+                        arg_values.push(self.lookup_symbol(var_name).cloned().unwrap());
+                    } else {
+                        arg_values.push(self.eval_expr(arg)?);
+                    }
+                }
+            } else {
+                arg_values.push(self.eval_expr(arg)?);
+            }
+        }
+        Ok(arg_values)
+    }
+
+    /// 调用内置函数（参数已求值）/ Call built-in function (with evaluated arguments)
+    fn call_builtin(
+        &mut self,
+        name: &str,
+        arg_values: Vec<Value>,
+    ) -> Result<Value, InterpreterError> {
+        // 检查是否是生成器内置函数 / Check if generator builtin
+        if let Some(result) = self.eval_generator_builtin(name, &arg_values) {
+            return result;
+        }
+
+        // 将 Value 转换回 Expr（Lambda 值需要特殊处理）
+        // Convert Value back to Expr (Lambda values need special handling)
+        let mut func_args = Vec::new();
+        let mut shared = Vec::new();
+        for val in arg_values {
+            // Lambda 和生成器值无法转换为 Expr，需要存储到环境中
+            // Lambda and generator values cannot be converted to Expr, need to store in environment
+            if let Value::Lambda { .. } | Value::Generator { .. } = val {
+                // 创建临时变量名
+                // Create temporary variable name
+                let temp_name = format!("__lambda_arg_{}", func_args.len());
+                self.environment.insert(Symbol::intern(&temp_name), val);
+                func_args.push(Expr::Var(temp_name.into()));
+            } else if let Value::List(_) | Value::Dict(_) = val {
+                // 列表和字典经临时变量共享，不逐项转换为表达式；调用后恢复，嵌套调用互不覆盖
+                // Lists and dicts are shared through a temporary variable instead of being converted item by item;
+                // restored after the call so nested calls do not clobber each other
+                let temp = Symbol::intern(&format!("__value_arg_{}", func_args.len()));
+                func_args.push(Expr::Var(temp.as_str().into()));
+                shared.push((temp.clone(), self.environment.insert(temp, val)));
+            } else {
+                func_args.push(Self::value_to_expr(val)?);
+            }
+        }
+        let result = self.eval_builtin_function(name, &func_args);
+        for (temp, old) in shared.into_iter().rev() {
+            match old {
+                Some(old) => self.environment.insert(temp, old),
+                None => self.environment.remove(&temp),
+            };
+        }
+        result
+    }
+
+    /// 评估内置操作符 / Evaluate built-in operator
+    fn eval_builtin_operator(
+        &mut self,
+        op_str: &str,
+        args: &[Expr],
+    ) -> Result<Value, InterpreterError> {
+        // 特殊处理：+ 运算符支持可变参数
+        // Special handling: + operator supports variadic arguments
+        if op_str == "+" {
+            if args.is_empty() {
+                return Err(InterpreterError::runtime_error(
+                    "Operator + requires at least 1 argument".to_string(),
+                    None,
+                ));
+            }
+
+            // 评估所有参数
+            // Evaluate all arguments
+            let mut values = Vec::new();
+            for arg in args {
+                values.push(self.eval_expr(arg)?);
             }
 
             // 如果只有一个参数，直接返回
@@ -1833,7 +4966,7 @@ impl Interpreter {
         // Other operators require 2 arguments
         if args.len() != 2 {
             return Err(InterpreterError::runtime_error(
-                format!("Operator {} requires 2 arguments", name),
+                format!("Operator {} requires 2 arguments", op_str),
                 None,
             ));
         }
@@ -1866,6 +4999,7 @@ impl Interpreter {
     /// 调用Lambda函数 / Call Lambda function
     fn call_lambda(
         &mut self,
+        name: &Symbol,
         lambda_id: &str,
         _params: &[String],
         args: &[Expr],
@@ -1903,6 +5037,14 @@ impl Interpreter {
             .map(|e| self.eval_expr(e))
             .collect::<Result<Vec<_>, _>>()?;
 
+        // 包含 yield 的 Lambda 返回生成器 / A lambda containing yield returns a generator
+        if element_contains_yield(&body) {
+            let mut env = captured_env;
+            env.extend(params.iter().cloned().zip(arg_values));
+            let module_name = self.current_module.clone();
+            return Ok(self.make_generator(env, &body, module_name));
+        }
+
         // 保存当前环境（用于恢复）- 优化：只保存被修改的变量
         let mut saved_env = HashMap::new();
         let mut saved_params = HashMap::new();
@@ -1910,13 +5052,8 @@ impl Interpreter {
         // 首先恢复捕获的环境（闭包变量）- 优化：使用引用避免不必要的克隆
         for (key, value) in &captured_env {
             // 只在环境中有旧值时才保存
-            if self.environment.contains_key(key) {
-                if let Some(old) = self.environment.insert(key.clone(), value.clone()) {
-                    saved_env.insert(key.clone(), old);
-                }
-            } else {
-                // 新变量，直接插入
-                self.environment.insert(key.clone(), value.clone());
+            if let Some(old) = self.environment.insert(key.clone(), value.clone()) {
+                saved_env.insert(key.clone(), old);
             }
         }
 
@@ -1928,7 +5065,7 @@ impl Interpreter {
         }
 
         // 执行Lambda函数体
-        let result = self.eval_element(&body)?;
+        let result = self.eval_function_body(name, &body)?;
 
         // 恢复环境：先恢复参数，再恢复捕获的环境 - 优化：使用更高效的方式
         for param in params {
@@ -1940,7 +5077,7 @@ impl Interpreter {
         }
 
         // 恢复捕获的环境（只恢复之前存在的变量）
-        let saved_env_keys: Vec<String> = saved_env.keys().cloned().collect();
+        let saved_env_keys: Vec<Symbol> = saved_env.keys().cloned().collect();
         for (key, old_value) in saved_env {
             self.environment.insert(key, old_value);
         }
@@ -1960,9 +5097,44 @@ impl Interpreter {
         Ok(result)
     }
 
+    /// 执行函数体（参数已绑定），维护调用深度并通知调试钩子
+    /// Evaluate a function body (with parameters bound), tracking call depth and notifying the debug hook
+    fn eval_function_body(
+        &mut self,
+        name: &Symbol,
+        body: &GrammarElement,
+    ) -> Result<Value, InterpreterError> {
+        if let Some(max_depth) = self.budget.max_call_depth {
+            if self.call_stack.len() >= max_depth {
+                return Err(InterpreterError::budget_exceeded(
+                    BudgetKind::CallDepth,
+                    max_depth as u64,
+                    None,
+                ));
+            }
+        }
+        self.call_stack.push(name.clone());
+        // 函数体是一个块 / A function body is a block
+        let result = self.eval_block(|this| match this.debug_hook {
+            Some(_) => this
+                .debug_event(DebugEvent::Call {
+                    name: name.to_string(),
+                })
+                .and_then(|_| this.eval_element(body)),
+            None => this.eval_element(body),
+        });
+        // 最内层失败的栈帧记录整个调用栈 / The innermost failing frame records the whole call stack
+        if result.is_err() && self.error_trace.frames.is_empty() {
+            self.error_trace.frames = self.stacktrace();
+        }
+        self.call_stack.pop();
+        result
+    }
+
     /// 调用用户定义函数（使用 Value 参数）/ Call user-defined function (with Value arguments)
     fn call_user_function_with_values(
         &mut self,
+        name: &Symbol,
         func: &Function,
         arg_values: &[Value],
     ) -> Result<Value, InterpreterError> {
@@ -1977,6 +5149,13 @@ impl Interpreter {
             ));
         }
 
+        // 包含 yield 的函数返回生成器 / A function containing yield returns a generator
+        if element_contains_yield(&func.body) {
+            let mut env = func.captured_env.clone().unwrap_or_default();
+            env.extend(func.params.iter().cloned().zip(arg_values.iter().cloned()));
+            return Ok(self.make_generator(env, &func.body, func.module_name.clone()));
+        }
+
         // 保存当前环境 - 优化：只保存被修改的变量
         let mut saved_env = HashMap::new();
         for (param, value) in func.params.iter().zip(arg_values.iter()) {
@@ -1993,7 +5172,7 @@ impl Interpreter {
         }
 
         // 执行函数体
-        let result = self.eval_element(&func.body)?;
+        let result = self.eval_function_body(name, &func.body)?;
 
         // 恢复环境 - 优化：使用更高效的方式
         for param in &func.params {
@@ -2034,7 +5213,7 @@ impl Interpreter {
             .collect::<Result<Vec<_>, _>>()?;
 
         // 调用 with_values 版本
-        self.call_user_function_with_values(func, &arg_values)
+        self.call_user_function_with_values(&Symbol::intern(""), func, &arg_values)
     }
 
     /// 评估内置函数 / Evaluate built-in function
@@ -2044,36 +5223,44 @@ impl Interpreter {
         args: &[Expr],
     ) -> Result<Value, InterpreterError> {
         match name {
-            "import" => {
-                if args.is_empty() || args.len() > 2 {
+            "reload" => {
+                if args.len() != 1 {
                     return Err(InterpreterError::runtime_error(
-                        "import requires 1 or 2 arguments: module_name [alias]".to_string(),
+                        "reload requires 1 argument: module_name".to_string(),
                         None,
                     ));
                 }
                 let module_name = self.module_name_from_expr(&args[0])?;
-                let alias = if args.len() == 2 {
-                    self.module_name_from_expr(&args[1])?
-                } else {
-                    module_name.clone()
-                };
-                self.import_module(&module_name, &alias)?;
+                self.reload_module(&module_name)?;
                 Ok(Value::Null)
             }
             "print" => {
-                use std::io::Write;
-                for (i, arg) in args.iter().enumerate() {
+                let mut parts = Vec::with_capacity(args.len());
+                for arg in args {
                     let value = self.eval_expr(arg)?;
-                    if i > 0 {
-                        print!(" ");
-                    }
-                    print!("{}", value);
+                    parts.push(self.show_value(&value)?);
                 }
-                println!();
-                // 强制刷新输出缓冲区 / Force flush output buffer
-                std::io::stdout().flush().unwrap();
+                self.write_output(&parts.join(" "))?;
                 Ok(Value::Null)
             }
+            // 多值返回：(divmod a b) 返回 (商 余数)，可由解构let接收
+            // Multiple values: (divmod a b) returns (quotient remainder), ready for a destructuring let
+            "divmod" => {
+                if args.len() != 2 {
+                    return Err(InterpreterError::runtime_error(
+                        "divmod requires 2 arguments: dividend and divisor".to_string(),
+                        None,
+                    ));
+                }
+                let dividend = self.eval_expr(&args[0])?;
+                let divisor = self.eval_expr(&args[1])?;
+                let quotient = match self.div_values(&dividend, &divisor)? {
+                    Value::Float(q) => Value::Float(q.trunc()),
+                    q => q,
+                };
+                let remainder = self.mod_values(&dividend, &divisor)?;
+                Ok(Value::List(vec![quotient, remainder].into()))
+            }
             // 列表操作 / List operations
             "list-get" | "get" => {
                 if args.len() != 2 {
@@ -2101,7 +5288,7 @@ impl Interpreter {
                     )),
                 }
             }
-            "list-set" | "set" => {
+            "list-set" => {
                 if args.len() != 3 {
                     return Err(InterpreterError::runtime_error(
                         "list-set requires 3 arguments: list, index, value".to_string(),
@@ -2119,7 +5306,7 @@ impl Interpreter {
                                 None,
                             ))
                         } else {
-                            l[i as usize] = value;
+                            Arc::make_mut(&mut l)[i as usize] = value;
                             Ok(Value::List(l))
                         }
                     }
@@ -2140,7 +5327,7 @@ impl Interpreter {
                 let value = self.eval_expr(&args[1])?;
                 match list {
                     Value::List(mut l) => {
-                        l.push(value);
+                        Arc::make_mut(&mut l).push(value);
                         Ok(Value::List(l))
                     }
                     _ => Err(InterpreterError::type_error(
@@ -2159,6 +5346,7 @@ impl Interpreter {
                 let list = self.eval_expr(&args[0])?;
                 match list {
                     Value::List(l) => Ok(Value::Int(l.len() as i64)),
+                    Value::Set(set) => Ok(Value::Int(set.len() as i64)),
                     _ => Err(InterpreterError::type_error(
                         "list-length requires a list".to_string(),
                         None,
@@ -2195,10 +5383,10 @@ impl Interpreter {
                     let mut result = Vec::new();
                     for value in values {
                         if let Value::List(l) = value {
-                            result.extend(l);
+                            result.extend(l.iter().cloned());
                         }
                     }
-                    Ok(Value::List(result))
+                    Ok(Value::List(result.into()))
                 } else if all_strings {
                     // 处理字符串连接
                     let mut result = String::new();
@@ -2207,7 +5395,7 @@ impl Interpreter {
                             result.push_str(&s);
                         }
                     }
-                    Ok(Value::String(result))
+                    Ok(Value::String(result.into()))
                 } else {
                     return Err(InterpreterError::type_error(
                         "list-concat requires all lists or all strings".to_string(),
@@ -2232,7 +5420,7 @@ impl Interpreter {
                 };
                 match (dict, key) {
                     (Value::Dict(d), Value::String(k)) => {
-                        Ok(d.get(&k).cloned().unwrap_or(default_value))
+                        Ok(d.get(&*k).cloned().unwrap_or(default_value))
                     }
                     _ => Err(InterpreterError::type_error(
                         "dict-get requires a dict and a string key".to_string(),
@@ -2252,7 +5440,7 @@ impl Interpreter {
                 let value = self.eval_expr(&args[2])?;
                 match (dict, key) {
                     (Value::Dict(mut d), Value::String(k)) => {
-                        d.insert(k, value);
+                        Arc::make_mut(&mut d).insert(k.to_string(), value);
                         Ok(Value::Dict(d))
                     }
                     _ => Err(InterpreterError::type_error(
@@ -2271,8 +5459,9 @@ impl Interpreter {
                 let dict = self.eval_expr(&args[0])?;
                 match dict {
                     Value::Dict(d) => {
-                        let keys: Vec<Value> = d.keys().map(|k| Value::String(k.clone())).collect();
-                        Ok(Value::List(keys))
+                        let keys: Vec<Value> =
+                            d.keys().map(|k| Value::String(k.as_str().into())).collect();
+                        Ok(Value::List(keys.into()))
                     }
                     _ => Err(InterpreterError::type_error(
                         "dict-keys requires a dict".to_string(),
@@ -2291,7 +5480,7 @@ impl Interpreter {
                 match dict {
                     Value::Dict(d) => {
                         let values: Vec<Value> = d.values().cloned().collect();
-                        Ok(Value::List(values))
+                        Ok(Value::List(values.into()))
                     }
                     _ => Err(InterpreterError::type_error(
                         "dict-values requires a dict".to_string(),
@@ -2309,7 +5498,7 @@ impl Interpreter {
                 let dict = self.eval_expr(&args[0])?;
                 let key = self.eval_expr(&args[1])?;
                 match (dict, key) {
-                    (Value::Dict(d), Value::String(k)) => Ok(Value::Bool(d.contains_key(&k))),
+                    (Value::Dict(d), Value::String(k)) => Ok(Value::Bool(d.contains_key(&*k))),
                     _ => Err(InterpreterError::type_error(
                         "dict-has requires a dict and a string key".to_string(),
                         None,
@@ -2330,10 +5519,10 @@ impl Interpreter {
                 match (string, delimiter) {
                     (Value::String(s), Value::String(d)) => {
                         let parts: Vec<Value> = s
-                            .split(&d)
-                            .map(|part| Value::String(part.to_string()))
+                            .split(&*d)
+                            .map(|part| Value::String(part.into()))
                             .collect();
-                        Ok(Value::List(parts))
+                        Ok(Value::List(parts.into()))
                     }
                     _ => Err(InterpreterError::type_error(
                         "string-split requires two strings".to_string(),
@@ -2356,13 +5545,13 @@ impl Interpreter {
                             .iter()
                             .filter_map(|v| {
                                 if let Value::String(s) = v {
-                                    Some(s.clone())
+                                    Some(s.to_string())
                                 } else {
                                     None
                                 }
                             })
                             .collect();
-                        Ok(Value::String(strings.join(&d)))
+                        Ok(Value::String(strings.join(&*d).into()))
                     }
                     _ => Err(InterpreterError::type_error(
                         "string-join requires a list of strings and a string delimiter".to_string(),
@@ -2379,7 +5568,7 @@ impl Interpreter {
                 }
                 let string = self.eval_expr(&args[0])?;
                 match string {
-                    Value::String(s) => Ok(Value::String(s.trim().to_string())),
+                    Value::String(s) => Ok(Value::String(s.trim().into())),
                     _ => Err(InterpreterError::type_error(
                         "string-trim requires a string".to_string(),
                         None,
@@ -2398,7 +5587,7 @@ impl Interpreter {
                 let new_str = self.eval_expr(&args[2])?;
                 match (string, old_str, new_str) {
                     (Value::String(s), Value::String(o), Value::String(n)) => {
-                        Ok(Value::String(s.replace(&o, &n)))
+                        Ok(Value::String(s.replace(&*o, &n).into()))
                     }
                     _ => Err(InterpreterError::type_error(
                         "string-replace requires three strings".to_string(),
@@ -2406,6 +5595,76 @@ impl Interpreter {
                     )),
                 }
             }
+            // 正则表达式：模式编译后缓存 / Regular expressions: patterns are cached once compiled
+            "regex-match" | "regex-replace" | "regex-split" => {
+                let arity = if name == "regex-replace" { 3 } else { 2 };
+                if args.len() != arity {
+                    return Err(InterpreterError::runtime_error(
+                        format!(
+                            "{} requires {} arguments: pattern, string{}",
+                            name,
+                            arity,
+                            if arity == 3 { ", replacement" } else { "" }
+                        ),
+                        None,
+                    ));
+                }
+                let values = args
+                    .iter()
+                    .map(|arg| self.eval_expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let strings: Vec<&str> = values
+                    .iter()
+                    .map(|value| match value {
+                        Value::String(s) => Ok(&**s),
+                        other => Err(InterpreterError::type_error(
+                            format!("{} requires string arguments, got {:?}", name, other),
+                            None,
+                        )),
+                    })
+                    .collect::<Result<_, _>>()?;
+                let regex = self.compile_regex(strings[0])?;
+                Ok(match name {
+                    // 第一个匹配：整体及各捕获组（未参与匹配的组为null），没有匹配时为null
+                    // First match: the whole match and every capture group (null for groups that did not
+                    // participate), or null without a match
+                    "regex-match" => match regex.captures(strings[1]) {
+                        Some(captures) => Value::List(
+                            captures
+                                .iter()
+                                .map(|group| match group {
+                                    Some(group) => Value::String(group.as_str().into()),
+                                    None => Value::Null,
+                                })
+                                .collect::<Vec<_>>()
+                                .into(),
+                        ),
+                        None => Value::Null,
+                    },
+                    // 替换所有匹配，替换文本中 $1 等引用捕获组
+                    // Replace every match; $1 and the like in the replacement refer to capture groups
+                    "regex-replace" => {
+                        Value::String(regex.replace_all(strings[1], strings[2]).as_ref().into())
+                    }
+                    _ => Value::List(
+                        regex
+                            .split(strings[1])
+                            .map(|part| Value::String(part.into()))
+                            .collect::<Vec<_>>()
+                            .into(),
+                    ),
+                })
+            }
+            "string-concat" => {
+                // 将所有参数转换为字符串后连接（字符串插值的脱糖目标）
+                // Concatenate all arguments as strings (desugaring target of string interpolation)
+                let mut result = String::new();
+                for arg in args {
+                    let value = self.eval_expr(arg)?;
+                    result.push_str(&value.to_string());
+                }
+                Ok(Value::String(result.into()))
+            }
             "string-length" | "strlen" => {
                 if args.len() != 1 {
                     return Err(InterpreterError::runtime_error(
@@ -2437,9 +5696,9 @@ impl Interpreter {
                         let start_idx = (st as usize).min(s.len());
                         let end_idx = (e as usize).min(s.len());
                         if start_idx > end_idx {
-                            Ok(Value::String(String::new()))
+                            Ok(Value::String("".into()))
                         } else {
-                            Ok(Value::String(s[start_idx..end_idx].to_string()))
+                            Ok(Value::String(s[start_idx..end_idx].into()))
                         }
                     }
                     _ => Err(InterpreterError::type_error(
@@ -2457,7 +5716,7 @@ impl Interpreter {
                 }
                 let string = self.eval_expr(&args[0])?;
                 match string {
-                    Value::String(s) => Ok(Value::String(s.to_uppercase())),
+                    Value::String(s) => Ok(Value::String(s.to_uppercase().into())),
                     _ => Err(InterpreterError::type_error(
                         "string-upper requires a string".to_string(),
                         None,
@@ -2473,69 +5732,378 @@ impl Interpreter {
                 }
                 let string = self.eval_expr(&args[0])?;
                 match string {
-                    Value::String(s) => Ok(Value::String(s.to_lowercase())),
+                    Value::String(s) => Ok(Value::String(s.to_lowercase().into())),
                     _ => Err(InterpreterError::type_error(
                         "string-lower requires a string".to_string(),
                         None,
                     )),
                 }
             }
-            // 类型转换 / Type conversion
-            "to-string" => {
-                if args.len() != 1 {
+            // 类型转换 / Type conversion
+            "to-string" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "to-string requires 1 argument".to_string(),
+                        None,
+                    ));
+                }
+                let value = self.eval_expr(&args[0])?;
+                Ok(Value::String(self.show_value(&value)?.into()))
+            }
+            "to-int" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "to-int requires 1 argument".to_string(),
+                        None,
+                    ));
+                }
+                let value = self.eval_expr(&args[0])?;
+                match value {
+                    Value::Int(i) => Ok(Value::Int(i)),
+                    Value::BigInt(n) => Ok(Value::BigInt(n)),
+                    Value::Float(f) => Ok(Value::Int(f as i64)),
+                    Value::String(s) => {
+                        s.trim()
+                            .parse::<BigInt>()
+                            .map(Value::from_bigint)
+                            .map_err(|_| {
+                                InterpreterError::type_error(
+                                    format!("Cannot convert '{}' to integer", s),
+                                    None,
+                                )
+                            })
+                    }
+                    _ => Err(InterpreterError::type_error(
+                        "Cannot convert to integer".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "to-float" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "to-float requires 1 argument".to_string(),
+                        None,
+                    ));
+                }
+                let value = self.eval_expr(&args[0])?;
+                match value {
+                    Value::Int(i) => Ok(Value::Float(i as f64)),
+                    Value::BigInt(n) => Ok(Value::Float(bigint_to_f64(&n))),
+                    Value::Float(f) => Ok(Value::Float(f)),
+                    Value::String(s) => s.parse::<f64>().map(Value::Float).map_err(|_| {
+                        InterpreterError::type_error(
+                            format!("Cannot convert '{}' to float", s),
+                            None,
+                        )
+                    }),
+                    _ => Err(InterpreterError::type_error(
+                        "Cannot convert to float".to_string(),
+                        None,
+                    )),
+                }
+            }
+            // 解析中文或阿拉伯数字文本 / Parse Chinese or Arabic numeral text
+            "parse-number" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "parse-number requires 1 argument".to_string(),
+                        None,
+                    ));
+                }
+                match self.eval_expr(&args[0])? {
+                    Value::String(s) => match parse_number(&s) {
+                        Some(ParsedNumber::Int(n)) => Ok(Value::Int(n)),
+                        Some(ParsedNumber::Float(f)) => Ok(Value::Float(f)),
+                        None => Err(InterpreterError::type_error(
+                            format!("Cannot parse '{}' as a number", s),
+                            None,
+                        )),
+                    },
+                    _ => Err(InterpreterError::type_error(
+                        "parse-number requires a string".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "format-number" | "to-fixed" | "parse-int" | "parse-float" => {
+                self.eval_number_builtin(name, args)
+            }
+            // 日期时间 / Date and time
+            "now" => {
+                if !args.is_empty() {
+                    return Err(InterpreterError::runtime_error(
+                        "now takes no arguments".to_string(),
+                        None,
+                    ));
+                }
+                Ok(Value::DateTime(chrono::Utc::now()))
+            }
+            "parse-date" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "parse-date requires 1 argument".to_string(),
+                        None,
+                    ));
+                }
+                // 也接受中文日期，如 "明天"、"3月5日" / Chinese dates such as "明天" or "3月5日" are accepted too
+                match self.eval_expr(&args[0])? {
+                    Value::String(s) => parse_datetime(&s)
+                        .or_else(|| {
+                            parse_date(&s, chrono::Utc::now().date_naive())
+                                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                                .map(|datetime| datetime.and_utc())
+                        })
+                        .map(Value::DateTime)
+                        .ok_or_else(|| {
+                            InterpreterError::type_error(
+                                format!("Cannot parse '{}' as a date", s),
+                                None,
+                            )
+                        }),
+                    _ => Err(InterpreterError::type_error(
+                        "parse-date requires a string".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "date-add" | "date-diff" => {
+                if args.len() != 2 && args.len() != 3 {
+                    return Err(InterpreterError::runtime_error(
+                        format!(
+                            "{} requires 2 or 3 arguments: {}, and an optional unit",
+                            name,
+                            if name == "date-add" {
+                                "date, amount"
+                            } else {
+                                "date, date"
+                            }
+                        ),
+                        None,
+                    ));
+                }
+                let values = args
+                    .iter()
+                    .map(|arg| self.eval_expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let unit = match values.get(2) {
+                    Some(Value::String(unit)) => unit.to_string(),
+                    None => "days".to_string(),
+                    Some(other) => {
+                        return Err(InterpreterError::type_error(
+                            format!("{} unit must be a string, got {:?}", name, other),
+                            None,
+                        ))
+                    }
+                };
+                match (name, &values[0], &values[1]) {
+                    ("date-add", Value::DateTime(date), Value::Int(amount)) => {
+                        date_add(*date, *amount, &unit).map(Value::DateTime)
+                    }
+                    ("date-diff", Value::DateTime(later), Value::DateTime(earlier)) => {
+                        date_diff(*later, *earlier, &unit).map(Value::Int)
+                    }
+                    _ => Err(InterpreterError::type_error(
+                        format!(
+                            "{} requires {}",
+                            name,
+                            if name == "date-add" {
+                                "a date and an integer amount"
+                            } else {
+                                "two dates"
+                            }
+                        ),
+                        None,
+                    )),
+                }
+            }
+            "format-date" => {
+                if args.is_empty() || args.len() > 2 {
+                    return Err(InterpreterError::runtime_error(
+                        "format-date requires 1 or 2 arguments: date and an optional format"
+                            .to_string(),
+                        None,
+                    ));
+                }
+                let date = self.eval_expr(&args[0])?;
+                let format = match args.get(1) {
+                    Some(arg) => self.eval_expr(arg)?,
+                    None => Value::String(DEFAULT_DATE_FORMAT.into()),
+                };
+                match (date, format) {
+                    (Value::DateTime(date), Value::String(format)) => {
+                        format_date(date, &format).map(|text| Value::String(text.into()))
+                    }
+                    _ => Err(InterpreterError::type_error(
+                        "format-date requires a date and a format string".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "is-date" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "is-date requires 1 argument".to_string(),
+                        None,
+                    ));
+                }
+                let value = self.eval_expr(&args[0])?;
+                Ok(Value::Bool(matches!(value, Value::DateTime(_))))
+            }
+            // 变体访问 / Variant access
+            "variant-tag" | "variant-type" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        format!("{} requires 1 argument", name),
+                        None,
+                    ));
+                }
+                match self.eval_expr(&args[0])? {
+                    Value::Variant { tag, .. } if name == "variant-tag" => {
+                        Ok(Value::String(tag.into()))
+                    }
+                    Value::Variant { tag, .. } => Ok(self
+                        .variant_constructors
+                        .get(&tag)
+                        .map(|constructor| Value::String(constructor.type_name.as_str().into()))
+                        .unwrap_or(Value::Null)),
+                    _ => Err(InterpreterError::type_error(
+                        format!("{} requires a variant value", name),
+                        None,
+                    )),
+                }
+            }
+            "variant-get" => {
+                if args.len() != 2 {
+                    return Err(InterpreterError::runtime_error(
+                        "variant-get requires 2 arguments: variant, field".to_string(),
+                        None,
+                    ));
+                }
+                let variant = self.eval_expr(&args[0])?;
+                let field = self.eval_expr(&args[1])?;
+                let (Value::Variant { tag, fields }, Value::String(field)) = (variant, field)
+                else {
+                    return Err(InterpreterError::type_error(
+                        "variant-get requires a variant and a field name".to_string(),
+                        None,
+                    ));
+                };
+                let index = self
+                    .variant_constructors
+                    .get(&tag)
+                    .and_then(|constructor| {
+                        constructor
+                            .fields
+                            .iter()
+                            .position(|f| f.as_str() == &*field)
+                    })
+                    .ok_or_else(|| {
+                        InterpreterError::runtime_error(
+                            format!("Variant {} has no field '{}'", tag, field),
+                            None,
+                        )
+                    })?;
+                Ok(fields.get(index).cloned().unwrap_or(Value::Null))
+            }
+            // 错误对象 / Error objects
+            "raise" => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.eval_expr(arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match values.as_slice() {
+                    // 重新抛出捕获的错误 / Re-raise a caught error
+                    [Value::Error {
+                        kind,
+                        message,
+                        data,
+                    }] => Err(InterpreterError::raised(
+                        kind.clone(),
+                        message.clone(),
+                        (**data).clone(),
+                        None,
+                    )),
+                    [message] => Err(InterpreterError::raised(
+                        "Error".to_string(),
+                        message.to_string(),
+                        Value::Null,
+                        None,
+                    )),
+                    [kind, message] | [kind, message, _] => Err(InterpreterError::raised(
+                        kind.to_string(),
+                        message.to_string(),
+                        values.get(2).cloned().unwrap_or(Value::Null),
+                        None,
+                    )),
+                    _ => Err(InterpreterError::runtime_error(
+                        "raise requires 1 to 3 arguments: [kind] message [data]".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "run-tests" => {
+                let filter = match args {
+                    [] => None,
+                    [filter] => Some(self.eval_expr(filter)?.to_string()),
+                    _ => {
+                        return Err(InterpreterError::runtime_error(
+                            "run-tests accepts at most 1 argument: [name filter]".to_string(),
+                            None,
+                        ))
+                    }
+                };
+                self.run_tests(filter.as_deref())
+            }
+            "error" => {
+                if args.len() != 2 && args.len() != 3 {
                     return Err(InterpreterError::runtime_error(
-                        "to-string requires 1 argument".to_string(),
+                        "error requires 2 or 3 arguments: kind, message, [data]".to_string(),
                         None,
                     ));
                 }
-                let value = self.eval_expr(&args[0])?;
-                Ok(Value::String(value.to_string()))
+                let kind = self.eval_expr(&args[0])?.to_string();
+                let message = self.eval_expr(&args[1])?.to_string();
+                let data = match args.get(2) {
+                    Some(arg) => self.eval_expr(arg)?,
+                    None => Value::Null,
+                };
+                Ok(Value::Error {
+                    kind,
+                    message,
+                    data: Box::new(data),
+                })
             }
-            "to-int" => {
+            "error-kind" | "error-message" | "error-data" => {
                 if args.len() != 1 {
                     return Err(InterpreterError::runtime_error(
-                        "to-int requires 1 argument".to_string(),
+                        format!("{} requires 1 argument", name),
                         None,
                     ));
                 }
-                let value = self.eval_expr(&args[0])?;
-                match value {
-                    Value::Int(i) => Ok(Value::Int(i)),
-                    Value::Float(f) => Ok(Value::Int(f as i64)),
-                    Value::String(s) => s.parse::<i64>().map(Value::Int).map_err(|_| {
-                        InterpreterError::type_error(
-                            format!("Cannot convert '{}' to integer", s),
-                            None,
-                        )
-                    }),
+                match self.eval_expr(&args[0])? {
+                    Value::Error { kind, .. } if name == "error-kind" => {
+                        Ok(Value::String(kind.into()))
+                    }
+                    Value::Error { message, .. } if name == "error-message" => {
+                        Ok(Value::String(message.into()))
+                    }
+                    Value::Error { data, .. } => Ok(*data),
                     _ => Err(InterpreterError::type_error(
-                        "Cannot convert to integer".to_string(),
+                        format!("{} requires an error value", name),
                         None,
                     )),
                 }
             }
-            "to-float" => {
+            "is-error" => {
                 if args.len() != 1 {
                     return Err(InterpreterError::runtime_error(
-                        "to-float requires 1 argument".to_string(),
+                        "is-error requires 1 argument".to_string(),
                         None,
                     ));
                 }
                 let value = self.eval_expr(&args[0])?;
-                match value {
-                    Value::Int(i) => Ok(Value::Float(i as f64)),
-                    Value::Float(f) => Ok(Value::Float(f)),
-                    Value::String(s) => s.parse::<f64>().map(Value::Float).map_err(|_| {
-                        InterpreterError::type_error(
-                            format!("Cannot convert '{}' to float", s),
-                            None,
-                        )
-                    }),
-                    _ => Err(InterpreterError::type_error(
-                        "Cannot convert to float".to_string(),
-                        None,
-                    )),
-                }
+                Ok(Value::Bool(matches!(value, Value::Error { .. })))
             }
             // 类型检查 / Type checking
             "is-string" => {
@@ -2556,7 +6124,10 @@ impl Interpreter {
                     ));
                 }
                 let value = self.eval_expr(&args[0])?;
-                Ok(Value::Bool(matches!(value, Value::Int(_))))
+                Ok(Value::Bool(matches!(
+                    value,
+                    Value::Int(_) | Value::BigInt(_)
+                )))
             }
             "is-float" => {
                 if args.len() != 1 {
@@ -2636,9 +6207,9 @@ impl Interpreter {
                             (e as usize).min(l.len())
                         };
                         if start_idx > end_idx {
-                            Ok(Value::List(vec![]))
+                            Ok(Value::List(Arc::default()))
                         } else {
-                            Ok(Value::List(l[start_idx..end_idx].to_vec()))
+                            Ok(Value::List(l[start_idx..end_idx].to_vec().into()))
                         }
                     }
                     (Value::List(l), Value::Int(s), None) => {
@@ -2647,7 +6218,7 @@ impl Interpreter {
                         } else {
                             (s as usize).min(l.len())
                         };
-                        Ok(Value::List(l[start_idx..].to_vec()))
+                        Ok(Value::List(l[start_idx..].to_vec().into()))
                     }
                     _ => Err(InterpreterError::type_error(
                         "list-slice requires a list and integer indices".to_string(),
@@ -2665,7 +6236,7 @@ impl Interpreter {
                 let list = self.eval_expr(&args[0])?;
                 match list {
                     Value::List(mut l) => {
-                        l.reverse();
+                        Arc::make_mut(&mut l).reverse();
                         Ok(Value::List(l))
                     }
                     _ => Err(InterpreterError::type_error(
@@ -2690,7 +6261,7 @@ impl Interpreter {
                 match (list, comparator) {
                     (Value::List(mut l), None) => {
                         // 默认排序：尝试按数值或字符串排序
-                        l.sort_by(|a, b| match (a, b) {
+                        Arc::make_mut(&mut l).sort_by(|a, b| match (a, b) {
                             (Value::Int(i1), Value::Int(i2)) => i1.cmp(i2),
                             (Value::Float(f1), Value::Float(f2)) => {
                                 f1.partial_cmp(f2).unwrap_or(std::cmp::Ordering::Equal)
@@ -2708,7 +6279,8 @@ impl Interpreter {
                             ));
                         }
                         // 使用Lambda比较函数排序 - 先收集所有比较结果，然后排序
-                        let mut indexed: Vec<(usize, Value)> = l.into_iter().enumerate().collect();
+                        let mut indexed: Vec<(usize, Value)> =
+                            Arc::unwrap_or_clone(l).into_iter().enumerate().collect();
                         // 简单排序：对于复杂情况，使用默认排序
                         // 注意：带比较函数的排序需要更复杂的实现，这里简化处理
                         indexed.sort_by(|(_, a), (_, b)| match (a, b) {
@@ -2720,107 +6292,514 @@ impl Interpreter {
                             _ => std::cmp::Ordering::Equal,
                         });
                         let result: Vec<Value> = indexed.into_iter().map(|(_, v)| v).collect();
-                        Ok(Value::List(result))
+                        Ok(Value::List(result.into()))
+                    }
+                    _ => Err(InterpreterError::type_error(
+                        "list-sort requires a list".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "list-unique" | "unique" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "list-unique requires 1 argument: list".to_string(),
+                        None,
+                    ));
+                }
+                let list = self.eval_expr(&args[0])?;
+                match list {
+                    Value::List(l) => {
+                        let mut seen = Vec::new();
+                        let mut result = Vec::new();
+                        for item in l.iter().cloned() {
+                            if !seen.contains(&item) {
+                                seen.push(item.clone());
+                                result.push(item);
+                            }
+                        }
+                        Ok(Value::List(result.into()))
+                    }
+                    _ => Err(InterpreterError::type_error(
+                        "list-unique requires a list".to_string(),
+                        None,
+                    )),
+                }
+            }
+            // 集合 / Sets
+            "set" => {
+                let mut set = ValueSet::new();
+                for arg in args {
+                    let value = self.eval_expr(arg)?;
+                    set.insert(value).map_err(set_error)?;
+                }
+                Ok(Value::Set(Arc::new(set)))
+            }
+            "to-set" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "to-set requires 1 argument: list".to_string(),
+                        None,
+                    ));
+                }
+                match self.eval_expr(&args[0])? {
+                    Value::List(items) => Ok(Value::Set(Arc::new(
+                        ValueSet::from_values(items.iter().cloned()).map_err(set_error)?,
+                    ))),
+                    set @ Value::Set(_) => Ok(set),
+                    _ => Err(InterpreterError::type_error(
+                        "to-set requires a list".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "to-list" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "to-list requires 1 argument: set".to_string(),
+                        None,
+                    ));
+                }
+                match self.eval_expr(&args[0])? {
+                    Value::Set(set) => Ok(Value::List(Arc::new(set.iter().cloned().collect()))),
+                    list @ Value::List(_) => Ok(list),
+                    _ => Err(InterpreterError::type_error(
+                        "to-list requires a set".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "union" | "intersection" | "difference" => {
+                if args.len() < 2 {
+                    return Err(InterpreterError::runtime_error(
+                        format!("{} requires at least 2 sets", name),
+                        None,
+                    ));
+                }
+                let mut sets = Vec::with_capacity(args.len());
+                for arg in args {
+                    match self.eval_expr(arg)? {
+                        Value::Set(set) => sets.push(set),
+                        other => {
+                            return Err(InterpreterError::type_error(
+                                format!(
+                                    "{} requires sets, got {}",
+                                    name,
+                                    self.value_type_name(&other)
+                                ),
+                                None,
+                            ))
+                        }
+                    }
+                }
+                let mut result = sets[0].clone();
+                for set in &sets[1..] {
+                    result = Arc::new(match name {
+                        "union" => result.union(set),
+                        "intersection" => result.intersection(set),
+                        _ => result.difference(set),
+                    });
+                }
+                Ok(Value::Set(result))
+            }
+            // 集合按哈希查找，列表逐个比较 / Sets look up by hash, lists compare element by element
+            "contains" => {
+                if args.len() != 2 {
+                    return Err(InterpreterError::runtime_error(
+                        "contains requires 2 arguments: collection and value".to_string(),
+                        None,
+                    ));
+                }
+                let collection = self.eval_expr(&args[0])?;
+                let value = self.eval_expr(&args[1])?;
+                match collection {
+                    Value::Set(set) => Ok(Value::Bool(set.contains(&value))),
+                    Value::List(items) => Ok(Value::Bool(items.contains(&value))),
+                    _ => Err(InterpreterError::type_error(
+                        "contains requires a set or a list".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "set-add" | "set-remove" => {
+                if args.len() != 2 {
+                    return Err(InterpreterError::runtime_error(
+                        format!("{} requires 2 arguments: set and value", name),
+                        None,
+                    ));
+                }
+                let set = self.eval_expr(&args[0])?;
+                let value = self.eval_expr(&args[1])?;
+                match set {
+                    Value::Set(mut set) if name == "set-add" => {
+                        if !set.contains(&value) {
+                            Arc::make_mut(&mut set).insert(value).map_err(set_error)?;
+                        }
+                        Ok(Value::Set(set))
+                    }
+                    Value::Set(set) => Ok(Value::Set(Arc::new(set.without(&value)))),
+                    _ => Err(InterpreterError::type_error(
+                        format!("{} requires a set", name),
+                        None,
+                    )),
+                }
+            }
+            "is-set" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "is-set requires 1 argument".to_string(),
+                        None,
+                    ));
+                }
+                let value = self.eval_expr(&args[0])?;
+                Ok(Value::Bool(matches!(value, Value::Set(_))))
+            }
+            "list-flatten" | "flatten" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "list-flatten requires 1 argument: list".to_string(),
+                        None,
+                    ));
+                }
+                let list = self.eval_expr(&args[0])?;
+                match list {
+                    Value::List(l) => {
+                        let mut result = Vec::new();
+                        for item in l.iter().cloned() {
+                            match item {
+                                Value::List(inner) => result.extend(inner.iter().cloned()),
+                                other => result.push(other),
+                            }
+                        }
+                        Ok(Value::List(result.into()))
+                    }
+                    _ => Err(InterpreterError::type_error(
+                        "list-flatten requires a list".to_string(),
+                        None,
+                    )),
+                }
+            }
+            // 增强字典操作 / Enhanced dictionary operations
+            "dict-merge" | "merge" => {
+                if args.len() < 2 {
+                    return Err(InterpreterError::runtime_error(
+                        "dict-merge requires at least 2 arguments: dict1, dict2, ...".to_string(),
+                        None,
+                    ));
+                }
+                let mut result = DictMap::new();
+                for arg in args {
+                    let dict = self.eval_expr(arg)?;
+                    match dict {
+                        Value::Dict(d) => {
+                            for (k, v) in Arc::unwrap_or_clone(d) {
+                                result.insert(k, v);
+                            }
+                        }
+                        _ => {
+                            return Err(InterpreterError::type_error(
+                                "dict-merge requires dictionaries".to_string(),
+                                None,
+                            ))
+                        }
+                    }
+                }
+                Ok(Value::Dict(result.into()))
+            }
+            "dict-size" | "dict-length" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "dict-size requires 1 argument: dict".to_string(),
+                        None,
+                    ));
+                }
+                let dict = self.eval_expr(&args[0])?;
+                match dict {
+                    Value::Dict(d) => Ok(Value::Int(d.len() as i64)),
+                    _ => Err(InterpreterError::type_error(
+                        "dict-size requires a dict".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "json-parse" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "json-parse requires 1 argument: string".to_string(),
+                        None,
+                    ));
+                }
+                match self.eval_expr(&args[0])? {
+                    Value::String(s) => serde_json::from_str::<serde_json::Value>(&s)
+                        .map(|json| Value::from_json(&json))
+                        .map_err(|e| {
+                            InterpreterError::runtime_error(format!("Invalid JSON: {}", e), None)
+                        }),
+                    _ => Err(InterpreterError::type_error(
+                        "json-parse requires a string".to_string(),
+                        None,
+                    )),
+                }
+            }
+            "json-stringify" => {
+                if args.is_empty() || args.len() > 2 {
+                    return Err(InterpreterError::runtime_error(
+                        "json-stringify requires 1 or 2 arguments: value [pretty]".to_string(),
+                        None,
+                    ));
+                }
+                let json = self.eval_expr(&args[0])?.to_json()?;
+                let pretty = if args.len() == 2 {
+                    let pretty = self.eval_expr(&args[1])?;
+                    self.is_truthy(&pretty)
+                } else {
+                    false
+                };
+                let result = if pretty {
+                    serde_json::to_string_pretty(&json)
+                } else {
+                    serde_json::to_string(&json)
+                };
+                result.map(|json| Value::String(json.into())).map_err(|e| {
+                    InterpreterError::runtime_error(
+                        format!("Failed to serialize JSON: {}", e),
+                        None,
+                    )
+                })
+            }
+            "file-read" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "file-read requires 1 argument: path".to_string(),
+                        None,
+                    ));
+                }
+                let path = self.eval_fs_path("file-read", &args[0])?;
+                fs::read_to_string(&path)
+                    .map(|content| Value::String(content.into()))
+                    .map_err(|e| Self::fs_error("file-read", &path, e))
+            }
+            "file-write" | "file-append" => {
+                if args.len() != 2 {
+                    return Err(InterpreterError::runtime_error(
+                        format!("{} requires 2 arguments: path and content", name),
+                        None,
+                    ));
+                }
+                let path = self.eval_fs_path(name, &args[0])?;
+                let content = match self.eval_expr(&args[1])? {
+                    Value::String(s) => s,
+                    other => other.to_string().into(),
+                };
+                let result = if name == "file-append" {
+                    fs::OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&path)
+                        .and_then(|mut file| file.write_all(content.as_bytes()))
+                } else {
+                    fs::write(&path, content.as_bytes())
+                };
+                result
+                    .map(|_| Value::Null)
+                    .map_err(|e| Self::fs_error(name, &path, e))
+            }
+            "file-exists" => {
+                if args.len() != 1 {
+                    return Err(InterpreterError::runtime_error(
+                        "file-exists requires 1 argument: path".to_string(),
+                        None,
+                    ));
+                }
+                let path = self.eval_fs_path("file-exists", &args[0])?;
+                Ok(Value::Bool(path.exists()))
+            }
+            #[cfg(feature = "http")]
+            "http-get" | "http-post" => self.eval_http(name, args),
+            #[cfg(not(feature = "http"))]
+            "http-get" | "http-post" => Err(InterpreterError::runtime_error(
+                format!("{} requires Evo built with the http feature", name),
+                None,
+            )),
+            "sandbox-eval" => {
+                if args.is_empty() || args.len() > 2 {
+                    return Err(InterpreterError::runtime_error(
+                        "sandbox-eval requires 1 or 2 arguments: code [max-steps]".to_string(),
+                        None,
+                    ));
+                }
+                let code = match self.eval_expr(&args[0])? {
+                    Value::String(code) => code,
+                    other => {
+                        return Err(InterpreterError::type_error(
+                            format!("sandbox-eval expects a code string, got {:?}", other),
+                            None,
+                        ));
+                    }
+                };
+                let mut sandbox = self.spawn_sandbox();
+                if let Some(arg) = args.get(1) {
+                    match self.eval_expr(arg)? {
+                        Value::Int(n) if n > 0 => sandbox.budget.max_steps = Some(n as u64),
+                        Value::Null => {}
+                        other => {
+                            return Err(InterpreterError::type_error(
+                                format!(
+                                    "sandbox-eval expects a positive step limit or null, got {:?}",
+                                    other
+                                ),
+                                None,
+                            ));
+                        }
                     }
-                    _ => Err(InterpreterError::type_error(
-                        "list-sort requires a list".to_string(),
+                }
+                let ast = AdaptiveParser::new(true).parse(&code).map_err(|e| {
+                    InterpreterError::runtime_error(format!("sandbox-eval: {:?}", e), None)
+                })?;
+                // 子解释器的错误（包括超出预算）作为普通运行时错误返回，可被 `try` 捕获
+                // Errors of the child (budget overruns included) come back as ordinary runtime errors catchable by `try`
+                let value = sandbox.execute(&ast).map_err(|e| {
+                    InterpreterError::runtime_error(format!("sandbox-eval: {}", e), None)
+                })?;
+                // Lambda和生成器的ID只在子解释器中有效 / Lambda and generator ids are only valid in the child
+                if escapes_sandbox(&value) {
+                    return Err(InterpreterError::runtime_error(
+                        "sandbox-eval: functions and generators cannot leave the sandbox"
+                            .to_string(),
                         None,
-                    )),
+                    ));
                 }
+                Ok(value)
             }
-            "list-unique" | "unique" => {
-                if args.len() != 1 {
+            "gc" => {
+                if !args.is_empty() {
                     return Err(InterpreterError::runtime_error(
-                        "list-unique requires 1 argument: list".to_string(),
+                        "gc takes no arguments".to_string(),
                         None,
                     ));
                 }
-                let list = self.eval_expr(&args[0])?;
-                match list {
-                    Value::List(l) => {
-                        let mut seen = Vec::new();
-                        let mut result = Vec::new();
-                        for item in l {
-                            if !seen.contains(&item) {
-                                seen.push(item.clone());
-                                result.push(item);
-                            }
-                        }
-                        Ok(Value::List(result))
-                    }
-                    _ => Err(InterpreterError::type_error(
-                        "list-unique requires a list".to_string(),
+                // 求值过程中的临时值不可见，所以在当前顶层形式结束后回收
+                // Temporaries of the ongoing evaluation are invisible, so collect once the current top-level form finishes
+                self.gc_requested = true;
+                Ok(Value::Null)
+            }
+            "gc-stats" => {
+                if !args.is_empty() {
+                    return Err(InterpreterError::runtime_error(
+                        "gc-stats takes no arguments".to_string(),
                         None,
-                    )),
+                    ));
                 }
+                Ok(Value::Dict(self.gc_stats().to_dict().into()))
             }
-            "list-flatten" | "flatten" => {
+            "gc-threshold" => {
                 if args.len() != 1 {
                     return Err(InterpreterError::runtime_error(
-                        "list-flatten requires 1 argument: list".to_string(),
+                        "gc-threshold requires 1 argument: object count or null".to_string(),
                         None,
                     ));
                 }
-                let list = self.eval_expr(&args[0])?;
-                match list {
-                    Value::List(l) => {
-                        let mut result = Vec::new();
-                        for item in l {
-                            match item {
-                                Value::List(inner) => result.extend(inner),
-                                other => result.push(other),
-                            }
-                        }
-                        Ok(Value::List(result))
+                let threshold = match self.eval_expr(&args[0])? {
+                    Value::Int(n) if n > 0 => Some(n as usize),
+                    Value::Null => None,
+                    other => {
+                        return Err(InterpreterError::type_error(
+                            format!(
+                                "gc-threshold expects a positive integer or null, got {:?}",
+                                other
+                            ),
+                            None,
+                        ))
                     }
-                    _ => Err(InterpreterError::type_error(
-                        "list-flatten requires a list".to_string(),
+                };
+                self.set_gc_config(GcConfig {
+                    threshold,
+                    ..self.gc_config.clone()
+                });
+                Ok(Value::Null)
+            }
+            "stacktrace" | "current-function" | "call-depth" | "max-call-depth" => {
+                if !args.is_empty() {
+                    return Err(InterpreterError::runtime_error(
+                        format!("{} takes no arguments", name),
                         None,
-                    )),
+                    ));
                 }
+                Ok(match name {
+                    "stacktrace" => Value::List(
+                        self.stacktrace()
+                            .into_iter()
+                            .map(|name| Value::String(name.into()))
+                            .collect::<Vec<_>>()
+                            .into(),
+                    ),
+                    "current-function" => self
+                        .call_stack
+                        .last()
+                        .map_or(Value::Null, |symbol| Value::String(symbol.as_str().into())),
+                    "call-depth" => Value::Int(self.call_stack.len() as i64),
+                    _ => self
+                        .budget
+                        .max_call_depth
+                        .map_or(Value::Null, |depth| Value::Int(depth as i64)),
+                })
             }
-            // 增强字典操作 / Enhanced dictionary operations
-            "dict-merge" | "merge" => {
-                if args.len() < 2 {
+            "set-max-call-depth" => {
+                if args.len() != 1 {
                     return Err(InterpreterError::runtime_error(
-                        "dict-merge requires at least 2 arguments: dict1, dict2, ...".to_string(),
+                        "set-max-call-depth requires 1 argument: depth".to_string(),
                         None,
                     ));
                 }
-                let mut result = HashMap::new();
-                for arg in args {
-                    let dict = self.eval_expr(arg)?;
-                    match dict {
-                        Value::Dict(d) => {
-                            for (k, v) in d {
-                                result.insert(k, v);
-                            }
-                        }
-                        _ => {
-                            return Err(InterpreterError::type_error(
-                                "dict-merge requires dictionaries".to_string(),
-                                None,
-                            ))
-                        }
+                let depth = match self.eval_expr(&args[0])? {
+                    Value::Int(n) if n > 0 => n as usize,
+                    other => {
+                        return Err(InterpreterError::type_error(
+                            format!(
+                                "set-max-call-depth expects a positive integer, got {:?}",
+                                other
+                            ),
+                            None,
+                        ))
                     }
+                };
+                // 脚本只能收紧宿主设置的限制 / Scripts can only tighten the limit the host set
+                let previous = self.budget.max_call_depth;
+                if previous.is_some_and(|limit| depth > limit) {
+                    return Err(InterpreterError::runtime_error(
+                        format!(
+                            "set-max-call-depth cannot raise the call depth limit above {}",
+                            previous.unwrap_or_default()
+                        ),
+                        None,
+                    ));
                 }
-                Ok(Value::Dict(result))
+                self.budget.max_call_depth = Some(depth);
+                Ok(previous.map_or(Value::Null, |depth| Value::Int(depth as i64)))
             }
-            "dict-size" | "dict-length" => {
+            "dir-list" => {
                 if args.len() != 1 {
                     return Err(InterpreterError::runtime_error(
-                        "dict-size requires 1 argument: dict".to_string(),
+                        "dir-list requires 1 argument: path".to_string(),
                         None,
                     ));
                 }
-                let dict = self.eval_expr(&args[0])?;
-                match dict {
-                    Value::Dict(d) => Ok(Value::Int(d.len() as i64)),
-                    _ => Err(InterpreterError::type_error(
-                        "dict-size requires a dict".to_string(),
-                        None,
-                    )),
+                let path = self.eval_fs_path("dir-list", &args[0])?;
+                let entries =
+                    fs::read_dir(&path).map_err(|e| Self::fs_error("dir-list", &path, e))?;
+                let mut names = Vec::new();
+                for entry in entries {
+                    let entry = entry.map_err(|e| Self::fs_error("dir-list", &path, e))?;
+                    names.push(entry.file_name().to_string_lossy().to_string());
                 }
+                names.sort();
+                Ok(Value::List(
+                    names
+                        .into_iter()
+                        .map(|name| Value::String(name.into()))
+                        .collect::<Vec<_>>()
+                        .into(),
+                ))
             }
             _ => Err(InterpreterError::runtime_error(
                 format!("Unknown function: {}", name),
@@ -2829,11 +6808,200 @@ impl Interpreter {
         }
     }
 
+    /// 评估文件路径参数并检查沙箱权限 / Evaluate file path argument and check sandbox permissions
+    fn eval_fs_path(&mut self, function: &str, expr: &Expr) -> Result<PathBuf, InterpreterError> {
+        if !self.options.allow_fs {
+            return Err(InterpreterError::runtime_error(
+                format!("{}: file system access is disabled", function),
+                None,
+            ));
+        }
+        let path = match self.eval_expr(expr)? {
+            Value::String(s) => PathBuf::from(&*s),
+            other => {
+                return Err(InterpreterError::type_error(
+                    format!(
+                        "{} requires a string path, got {}",
+                        function,
+                        self.value_type_name(&other)
+                    ),
+                    None,
+                ))
+            }
+        };
+        if self.options.allowed_paths.is_empty() {
+            return Ok(path);
+        }
+
+        // 规范化后再比较，防止通过 `..` 或符号链接逃逸（文件可能尚不存在，因此规范化其父目录）
+        // Compare canonical paths to prevent escaping via `..` or symlinks (the file may not exist yet, so canonicalize its parent)
+        let canonical = fs::canonicalize(&path).ok().or_else(|| {
+            let parent = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            let file_name = path.file_name()?;
+            fs::canonicalize(parent).ok().map(|p| p.join(file_name))
+        });
+        let allowed = canonical.is_some_and(|canonical| {
+            self.options.allowed_paths.iter().any(|allowed| {
+                fs::canonicalize(allowed)
+                    .map(|allowed| canonical.starts_with(allowed))
+                    .unwrap_or(false)
+            })
+        });
+        if !allowed {
+            return Err(InterpreterError::runtime_error(
+                format!(
+                    "{}: access to '{}' is outside the allowed paths",
+                    function,
+                    path.display()
+                ),
+                None,
+            ));
+        }
+        Ok(path)
+    }
+
+    /// 执行 `http-get` / `http-post`，返回 {status, ok, headers, body} 字典；非2xx响应不是错误
+    /// Run `http-get` / `http-post`, returning a {status, ok, headers, body} dict; non-2xx responses are not errors
+    #[cfg(feature = "http")]
+    fn eval_http(&mut self, name: &str, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let (usage, required) = if name == "http-get" {
+            ("url [headers]", 1)
+        } else {
+            ("url body [headers]", 2)
+        };
+        if args.len() != required && args.len() != required + 1 {
+            return Err(InterpreterError::runtime_error(
+                format!(
+                    "{} requires {} or {} arguments: {}",
+                    name,
+                    required,
+                    required + 1,
+                    usage
+                ),
+                None,
+            ));
+        }
+        if !self.options.allow_net {
+            return Err(InterpreterError::runtime_error(
+                format!("{}: network access is disabled", name),
+                None,
+            ));
+        }
+        let url = match self.eval_expr(&args[0])? {
+            Value::String(s) => s.to_string(),
+            other => {
+                return Err(InterpreterError::type_error(
+                    format!(
+                        "{} requires a string url, got {}",
+                        name,
+                        self.value_type_name(&other)
+                    ),
+                    None,
+                ))
+            }
+        };
+        let host = crate::runtime::http::url_host(&url)
+            .map_err(|e| InterpreterError::runtime_error(format!("{}: {}", name, e), None))?;
+        if !self.options.allowed_hosts.is_empty()
+            && !self
+                .options
+                .allowed_hosts
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(host))
+        {
+            return Err(InterpreterError::runtime_error(
+                format!("{}: host '{}' is not in the allowed hosts", name, host),
+                None,
+            ));
+        }
+
+        // 字符串正文原样发送，其他值编码为JSON / String bodies are sent as is, other values are encoded as JSON
+        let (body, content_type) = match args.get(1).filter(|_| name == "http-post") {
+            Some(arg) => match self.eval_expr(arg)? {
+                Value::String(s) => (Some(s.to_string()), "text/plain; charset=utf-8"),
+                other => (Some(other.to_json()?.to_string()), "application/json"),
+            },
+            None => (None, ""),
+        };
+        let mut headers = Vec::new();
+        if let Some(arg) = args.get(required) {
+            match self.eval_expr(arg)? {
+                Value::Dict(dict) => {
+                    for (key, value) in dict.iter() {
+                        let value = match value {
+                            Value::String(s) => s.to_string(),
+                            other => other.to_string(),
+                        };
+                        headers.push((key.clone(), value));
+                    }
+                }
+                Value::Null => {}
+                other => {
+                    return Err(InterpreterError::type_error(
+                        format!(
+                            "{} headers must be a dict, got {}",
+                            name,
+                            self.value_type_name(&other)
+                        ),
+                        None,
+                    ))
+                }
+            }
+        }
+        if body.is_some()
+            && !headers
+                .iter()
+                .any(|(key, _)| key.eq_ignore_ascii_case("content-type"))
+        {
+            headers.push(("Content-Type".to_string(), content_type.to_string()));
+        }
+
+        let method = if name == "http-get" { "GET" } else { "POST" };
+        let response = crate::runtime::http::send(
+            method,
+            &url,
+            &headers,
+            body.as_deref(),
+            self.options.http_timeout,
+        )
+        .map_err(|e| {
+            InterpreterError::runtime_error(format!("{} failed for '{}': {}", name, url, e), None)
+        })?;
+        let response_headers = response
+            .headers
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::String(value.as_str().into())))
+            .collect::<DictMap>();
+        Ok(Value::Dict(
+            DictMap::from([
+                ("status".to_string(), Value::Int(response.status as i64)),
+                ("ok".to_string(), Value::Bool(response.is_success())),
+                ("headers".to_string(), Value::Dict(response_headers.into())),
+                (
+                    "body".to_string(),
+                    Value::String(response.body.as_str().into()),
+                ),
+            ])
+            .into(),
+        ))
+    }
+
+    /// 创建文件操作错误 / Create file operation error
+    fn fs_error(function: &str, path: &Path, error: std::io::Error) -> InterpreterError {
+        InterpreterError::runtime_error(
+            format!("{} failed for '{}': {}", function, path.display(), error),
+            None,
+        )
+    }
+
     /// 从表达式解析模块名称 / Parse module name from expression
     fn module_name_from_expr(&self, expr: &Expr) -> Result<String, InterpreterError> {
         match expr {
             Expr::Literal(Literal::String(s)) => Ok(s.clone()),
-            Expr::Var(name) => Ok(name.clone()),
+            Expr::Var(name) => Ok(name.to_string()),
             _ => Err(InterpreterError::runtime_error(
                 "Module name must be a string literal or identifier".to_string(),
                 None,
@@ -2841,34 +7009,402 @@ impl Interpreter {
         }
     }
 
+    /// 评估import声明 / Evaluate import declaration
+    /// 语法: (import "module" :as alias :only (name ...))，解析器把选项规范化为 `(:as alias)` 和 `(:only name ...)` 参数
+    /// Syntax: (import "module" :as alias :only (name ...)); the parser normalizes the options into `(:as alias)` and
+    /// `(:only name ...)` arguments
+    fn eval_import(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let Some((module, options)) = args.split_first() else {
+            return Err(InterpreterError::runtime_error(
+                "import requires: module_name [:as alias] [:only (names...)]".to_string(),
+                None,
+            ));
+        };
+        let module_name = self.evaluated_module_name(module)?;
+        let mut alias = None;
+        let mut only = None;
+        for option in options {
+            match option {
+                Expr::Call(name, values) if name == ":as" && values.len() == 1 => {
+                    alias = Some(self.module_name_from_expr(&values[0])?);
+                }
+                Expr::Call(name, names) if name == ":only" => {
+                    only = Some(
+                        names
+                            .iter()
+                            .map(|name| self.module_name_from_expr(name))
+                            .collect::<Result<HashSet<_>, _>>()?,
+                    );
+                }
+                // 未经解析器的位置别名 / Positional alias that did not go through the parser
+                _ if alias.is_none() && only.is_none() => {
+                    alias = Some(self.evaluated_module_name(option)?);
+                }
+                _ => {
+                    return Err(InterpreterError::runtime_error(
+                        "import options are :as alias and :only (names...)".to_string(),
+                        None,
+                    ))
+                }
+            }
+        }
+        // 嵌套路径默认使用最后一段作为别名 / Nested paths default to the last segment as alias
+        let alias = alias.unwrap_or_else(|| {
+            Path::new(&module_name)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| module_name.clone())
+        });
+        self.import_module(&module_name, &alias, only.as_ref())?;
+        Ok(Value::Null)
+    }
+
+    /// 求值得到的模块名或别名（模块名可以是计算得到的字符串）
+    /// Module name or alias obtained by evaluation (module names may be computed strings)
+    fn evaluated_module_name(&mut self, expr: &Expr) -> Result<String, InterpreterError> {
+        match self.eval_expr(expr)? {
+            Value::String(name) => Ok(name.to_string()),
+            _ => Err(InterpreterError::runtime_error(
+                "Module name must be a string".to_string(),
+                None,
+            )),
+        }
+    }
+
+    /// 评估export声明 / Evaluate export declaration
+    /// 语法: (export name1 name2 ...)，声明模块对导入者可见的函数和变量
+    /// Syntax: (export name1 name2 ...), declares functions and variables visible to importers
+    fn eval_export(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let exports = self.exports.get_or_insert_with(HashSet::new);
+        for arg in args {
+            match arg {
+                Expr::Var(name) => {
+                    exports.insert(name.to_string());
+                }
+                Expr::Literal(Literal::String(name)) => {
+                    exports.insert(name.clone());
+                }
+                _ => {
+                    return Err(InterpreterError::runtime_error(
+                        "export requires function or variable names".to_string(),
+                        None,
+                    ))
+                }
+            }
+        }
+        Ok(Value::Null)
+    }
+
+    /// 评估类型定义 / Evaluate type definition
+    /// 语法: (deftype Shape (Circle r) (Rect w h) Empty)，为每个变体注册构造器
+    /// Syntax: (deftype Shape (Circle r) (Rect w h) Empty), registers a constructor per variant
+    fn eval_deftype(&mut self, args: &[Expr]) -> Result<Value, InterpreterError> {
+        let Some((Expr::Var(type_name), variants)) = args.split_first() else {
+            return Err(InterpreterError::runtime_error(
+                "deftype requires a type name followed by variants".to_string(),
+                None,
+            ));
+        };
+
+        let mut constructors = Vec::new();
+        for variant in variants {
+            let (tag, fields) = match variant {
+                Expr::Var(tag) => (tag, Vec::new()),
+                Expr::Call(tag, fields) => {
+                    let fields = fields
+                        .iter()
+                        .map(|field| match field {
+                            Expr::Var(field) => Ok(field.to_string()),
+                            _ => Err(InterpreterError::runtime_error(
+                                format!("Fields of variant {} must be names", tag),
+                                None,
+                            )),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    (tag, fields)
+                }
+                _ => {
+                    return Err(InterpreterError::runtime_error(
+                        format!("Invalid variant in deftype {}", type_name),
+                        None,
+                    ))
+                }
+            };
+            constructors.push((
+                tag.to_string(),
+                VariantConstructor {
+                    type_name: type_name.to_string(),
+                    fields,
+                },
+            ));
+        }
+        if constructors.is_empty() {
+            return Err(InterpreterError::runtime_error(
+                format!("deftype {} requires at least one variant", type_name),
+                None,
+            ));
+        }
+
+        self.variant_constructors.extend(constructors);
+        self.dispatch_changed();
+        Ok(Value::Null)
+    }
+
+    /// 查找原生模块函数（当前环境或当前模块导入的别名）
+    /// Look up native module function (via aliases imported here or by the current module)
+    fn native_function(&self, name: &str) -> Option<stdlib::NativeFunction> {
+        let (alias, function) = name.split_once('.')?;
+        let (module_name, only) = match self.native_aliases.get(alias) {
+            Some(module_name) => (module_name, self.selective_imports.get(alias)),
+            None => {
+                let module = self
+                    .current_module
+                    .as_ref()
+                    .and_then(|module| self.modules.get(module))?;
+                (
+                    module.native_aliases.get(alias)?,
+                    module.selective_imports.get(alias),
+                )
+            }
+        };
+        if only.is_some_and(|only| !only.contains(function)) {
+            return None;
+        }
+        self.native_modules
+            .get(module_name)?
+            .functions
+            .get(function)
+            .copied()
+    }
+
     /// 导入模块 / Import module
-    fn import_module(&mut self, module_name: &str, alias: &str) -> Result<(), InterpreterError> {
+    /// `only` 给出时只导入其中的名称，这些名称必须都由模块导出
+    /// When `only` is given, only those names are imported, and the module must export all of them
+    fn import_module(
+        &mut self,
+        module_name: &str,
+        alias: &str,
+        only: Option<&HashSet<String>>,
+    ) -> Result<(), InterpreterError> {
+        self.dispatch_changed();
+        let selected = |name: &str| only.is_none_or(|only| only.contains(name));
+        // 原生模块：注册别名和常量；同名模块文件存在时其定义优先
+        // Native module: register alias and constants; a module file of the same name takes precedence
+        if let Some(native) = self.native_modules.get(module_name) {
+            for (name, value) in &native.constants {
+                if !selected(name) {
+                    continue;
+                }
+                self.environment.insert(
+                    Symbol::intern(&format!("{}.{}", alias, name)),
+                    value.clone(),
+                );
+            }
+            self.native_aliases
+                .insert(alias.to_string(), module_name.to_string());
+            if !self.modules.contains_key(module_name)
+                && (!self.options.allow_modules || self.resolve_module_path(module_name).is_err())
+            {
+                self.check_selected_names(module_name, only)?;
+                self.record_selection(alias, only);
+                return Ok(());
+            }
+        }
+
         let module = if let Some(module) = self.modules.get(module_name).cloned() {
             module
         } else {
-            let module = self.load_module(module_name)?;
+            let (module, nested_modules) = self.load_module(module_name)?;
+            // 模块自身导入的模块也需要注册，模块函数执行时才能找到它们
+            // Modules imported by the module must be registered too, so its functions can find them
+            for (name, nested) in nested_modules {
+                self.modules.entry(name).or_insert(nested);
+            }
             self.modules.insert(module_name.to_string(), module.clone());
             module
         };
 
-        // 将模块内容导入到当前环境（带命名空间前缀）
+        self.check_selected_names(module_name, only)?;
+        self.record_selection(alias, only);
+        self.module_aliases
+            .entry(module_name.to_string())
+            .or_default()
+            .insert(alias.to_string());
+
+        // 将模块导出（并被选中）的内容导入到当前环境（带命名空间前缀）
+        // Import the module's exported (and selected) contents into current environment (with namespace prefix)
         for (name, value) in &module.environment {
-            let qualified_name = format!("{}.{}", alias, name);
-            self.environment.insert(qualified_name, value.clone());
+            if !module.is_exported(name.as_str()) || !selected(name.as_str()) {
+                continue;
+            }
+            let qualified_name = Symbol::intern(&format!("{}.{}", alias, name));
+            self.environment
+                .insert(qualified_name.clone(), value.clone());
+            if module.constants.contains(name.as_str()) {
+                self.constants.insert(qualified_name);
+            }
         }
-        for (name, mut function) in module.functions {
-            let qualified_name = format!("{}.{}", alias, name);
+        for (name, function) in &module.functions {
+            if !module.is_exported(name.as_str()) || !selected(name.as_str()) {
+                continue;
+            }
+            let qualified_name = Symbol::intern(&format!("{}.{}", alias, name));
             // 保留模块名信息，用于递归调用时查找
+            let mut function = function.clone();
             function.module_name = Some(module.name.clone());
             self.functions.insert(qualified_name, function);
         }
+        // 构造器不带命名空间前缀且总是导入，模块函数构造的值和导入者的模式才能一致
+        // Constructors are always imported without a prefix, so values built by module functions agree with the importer's patterns
+        self.variant_constructors.extend(
+            module
+                .variant_constructors
+                .iter()
+                .map(|(tag, constructor)| (tag.clone(), constructor.clone())),
+        );
+        // 协议和实现同样全局可见 / Protocols and implementations are likewise global
+        for (name, protocol) in &module.protocols {
+            let target = self
+                .protocols
+                .entry(name.clone())
+                .or_insert_with(|| Protocol {
+                    methods: protocol.methods.clone(),
+                    impls: HashMap::new(),
+                });
+            for (type_name, methods) in &protocol.impls {
+                target.impls.entry(type_name.clone()).or_default().extend(
+                    methods
+                        .iter()
+                        .map(|(method, f)| (method.clone(), f.clone())),
+                );
+            }
+        }
+        self.dispatch_changed();
+
+        Ok(())
+    }
+
+    /// 重新加载模块（可使用模块名或别名），并刷新所有别名下的导入内容
+    /// Reload module (by name or alias) and refresh imported contents under all its aliases
+    fn reload_module(&mut self, name: &str) -> Result<(), InterpreterError> {
+        let module_name = if self.modules.contains_key(name) {
+            name.to_string()
+        } else {
+            self.module_aliases
+                .iter()
+                .find(|(_, aliases)| aliases.contains(name))
+                .map(|(module_name, _)| module_name.clone())
+                .ok_or_else(|| {
+                    InterpreterError::runtime_error(
+                        format!("Module '{}' has not been imported", name),
+                        None,
+                    )
+                })?
+        };
+
+        // 先加载新版本，失败时保留旧模块 / Load the new version first, keeping the old module on failure
+        let (module, nested_modules) = self.load_module(&module_name)?;
+        for (name, nested) in nested_modules {
+            self.modules.entry(name).or_insert(nested);
+        }
+        self.modules.insert(module_name.clone(), module);
+
+        // 移除旧的带命名空间内容后重新导入 / Remove stale namespaced contents, then re-import
+        let aliases = self
+            .module_aliases
+            .get(&module_name)
+            .cloned()
+            .unwrap_or_else(|| HashSet::from([module_name.clone()]));
+        for alias in aliases {
+            let prefix = format!("{}.", alias);
+            self.environment
+                .retain(|key, _| !key.as_str().starts_with(&prefix));
+            self.functions
+                .retain(|key, _| !key.as_str().starts_with(&prefix));
+            self.constants
+                .retain(|key| !key.as_str().starts_with(&prefix));
+            let only = self.selective_imports.get(&alias).cloned();
+            self.import_module(&module_name, &alias, only.as_ref())?;
+        }
 
         Ok(())
     }
 
-    /// 加载模块 / Load module
-    fn load_module(&self, module_name: &str) -> Result<Module, InterpreterError> {
+    /// 记录别名通过 `:only` 选中的名称（原生模块函数在调用时按此过滤）
+    /// Record the names an alias selected with `:only` (native module functions are filtered by them when called)
+    fn record_selection(&mut self, alias: &str, only: Option<&HashSet<String>>) {
+        match only {
+            Some(only) => self
+                .selective_imports
+                .insert(alias.to_string(), only.clone()),
+            None => self.selective_imports.remove(alias),
+        };
+    }
+
+    /// 检查 `:only` 选中的名称都由模块导出 / Check that every name selected with `:only` is exported by the module
+    fn check_selected_names(
+        &self,
+        module_name: &str,
+        only: Option<&HashSet<String>>,
+    ) -> Result<(), InterpreterError> {
+        let native = self.native_modules.get(module_name);
+        let module = self.modules.get(module_name);
+        let exports = |name: &str| {
+            native.is_some_and(|native| {
+                native.functions.contains_key(name)
+                    || native
+                        .constants
+                        .iter()
+                        .any(|(constant, _)| *constant == name)
+            }) || module.is_some_and(|module| {
+                let symbol = Symbol::intern(name);
+                module.is_exported(name)
+                    && (module.functions.contains_key(&symbol)
+                        || module.environment.contains_key(&symbol))
+            })
+        };
+        let mut missing: Vec<&str> = only
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .filter(|name| !exports(name))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        missing.sort_unstable();
+        Err(InterpreterError::runtime_error(
+            format!(
+                "Module '{}' does not export: {}",
+                module_name,
+                missing.join(", ")
+            ),
+            None,
+        ))
+    }
+
+    /// 加载模块，同时返回该模块自身导入的模块 / Load module, also returning modules it imported
+    fn load_module(
+        &self,
+        module_name: &str,
+    ) -> Result<(Module, HashMap<String, Module>), InterpreterError> {
+        if !self.options.allow_modules {
+            return Err(InterpreterError::runtime_error(
+                format!("import {}: module access is disabled", module_name),
+                None,
+            ));
+        }
         let path = self.resolve_module_path(module_name)?;
+        let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if let Some(start) = self.import_chain.iter().position(|p| *p == canonical) {
+            let cycle = self.import_chain[start..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(InterpreterError::circular_import(cycle, None));
+        }
+
         let code = fs::read_to_string(&path).map_err(|e| {
             InterpreterError::runtime_error(
                 format!("Failed to read module '{}': {}", module_name, e),
@@ -2884,59 +7420,92 @@ impl Interpreter {
             )
         })?;
 
+        // 模块解释器继承搜索路径和溢出模式，并以模块文件所在目录作为相对导入基准
+        // Module interpreter inherits search paths and overflow mode, using the module's directory for relative imports
         let mut module_interpreter = Interpreter::new();
-        module_interpreter.execute(&ast).map_err(|e| {
-            InterpreterError::runtime_error(
+        module_interpreter.module_paths = self.module_paths.clone();
+        module_interpreter.overflow_mode = self.overflow_mode;
+        module_interpreter.options = self.options.clone();
+        module_interpreter.host_functions = self.host_functions.clone();
+        module_interpreter.output = self.output.clone();
+        module_interpreter.budget = self.budget.clone();
+        module_interpreter.base_dir = path.parent().map(Path::to_path_buf);
+        module_interpreter.import_chain = self.import_chain.clone();
+        module_interpreter.import_chain.push(canonical);
+        module_interpreter.execute(&ast).map_err(|e| match e {
+            // 循环导入错误原样传递，保留完整的循环路径 / Pass circular import errors through with the full cycle
+            InterpreterError::CircularImport { .. } | InterpreterError::BudgetExceeded { .. } => e,
+            _ => InterpreterError::runtime_error(
                 format!("Failed to execute module '{}': {:?}", module_name, e),
                 None,
-            )
+            ),
         })?;
 
-        // 为模块中的函数设置模块名
+        // 为模块中的函数设置模块名（已导入的函数保留其原始模块）/ Set module name, keeping the origin of imported functions
         let mut module_functions = HashMap::new();
         for (name, mut func) in module_interpreter.functions {
-            func.module_name = Some(module_name.to_string());
+            func.module_name
+                .get_or_insert_with(|| module_name.to_string());
             module_functions.insert(name, func);
         }
 
-        Ok(Module {
+        // 实现同样记住所属模块，以便调用模块内的辅助函数 / Implementations also remember their module to reach its helpers
+        let mut module_protocols = module_interpreter.protocols;
+        for function in module_protocols
+            .values_mut()
+            .flat_map(|protocol| protocol.impls.values_mut())
+            .flat_map(HashMap::values_mut)
+        {
+            Arc::make_mut(function)
+                .module_name
+                .get_or_insert_with(|| module_name.to_string());
+        }
+
+        let module = Module {
             name: module_name.to_string(),
             environment: module_interpreter.environment.clone(),
             functions: module_functions,
-        })
+            exports: module_interpreter.exports,
+            private: module_interpreter.private,
+            constants: module_interpreter
+                .constants
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+            native_aliases: module_interpreter.native_aliases,
+            selective_imports: module_interpreter.selective_imports,
+            module_aliases: module_interpreter.module_aliases,
+            variant_constructors: module_interpreter.variant_constructors,
+            protocols: module_protocols,
+        };
+        Ok((module, module_interpreter.modules))
     }
 
     /// 解析模块路径 / Resolve module path
+    /// 以 `./` 或 `../` 开头的名称相对于当前代码目录解析，其余名称在搜索路径中查找
+    /// Names starting with `./` or `../` resolve against the current code directory, others use the search path
     fn resolve_module_path(&self, module_name: &str) -> Result<PathBuf, InterpreterError> {
-        let mut candidates = Vec::new();
-        let name = if module_name.ends_with(".evo") {
-            module_name.to_string()
-        } else {
-            format!("{}.evo", module_name)
-        };
-
-        candidates.push(PathBuf::from("modules").join(&name));
-        candidates.push(PathBuf::from("examples").join(&name));
-        candidates.push(PathBuf::from(&name));
-
-        for path in candidates {
-            if path.exists() {
-                return Ok(path);
-            }
-        }
-
-        Err(InterpreterError::runtime_error(
-            format!(
-                "Module '{}' not found in modules/, examples/, or current directory",
-                module_name
-            ),
-            None,
-        ))
+        find_module_file(module_name, self.base_dir.as_deref(), &self.module_paths()).map_err(
+            |searched| {
+                let searched: Vec<String> = searched
+                    .iter()
+                    .map(|dir| dir.display().to_string())
+                    .collect();
+                InterpreterError::runtime_error(
+                    format!(
+                        "Module '{}' not found in search path: {}",
+                        module_name,
+                        searched.join(", ")
+                    ),
+                    None,
+                )
+            },
+        )
     }
 
-    /// 比较值 / Compare values
+    /// 比较值；其他类型使用用户的 Ord 实现 / Compare values; other types use a user Ord implementation
     fn compare_values(
-        &self,
+        &mut self,
         op: BinOp,
         left: &Value,
         right: &Value,
@@ -2950,6 +7519,17 @@ impl Interpreter {
                 Ge => a >= b,
                 _ => unreachable!(),
             },
+            (Value::BigInt(_), Value::Int(_) | Value::BigInt(_))
+            | (Value::Int(_), Value::BigInt(_)) => {
+                let (a, b) = (left.to_bigint().unwrap(), right.to_bigint().unwrap());
+                match op {
+                    Lt => a < b,
+                    Le => a <= b,
+                    Gt => a > b,
+                    Ge => a >= b,
+                    _ => unreachable!(),
+                }
+            }
             (Value::Float(a), Value::Float(b)) => match op {
                 Lt => a < b,
                 Le => a <= b,
@@ -2964,7 +7544,19 @@ impl Interpreter {
                 Ge => a >= b,
                 _ => unreachable!(),
             },
+            (Value::DateTime(a), Value::DateTime(b)) => match op {
+                Lt => a < b,
+                Le => a <= b,
+                Gt => a > b,
+                Ge => a >= b,
+                _ => unreachable!(),
+            },
             _ => {
+                if let Some(implementation @ MethodImpl::User(_)) =
+                    self.find_method("compare", left)
+                {
+                    return self.compare_with(op, implementation, left, right);
+                }
                 return Err(InterpreterError::type_error(
                     format!(
                         "Cannot compare {} and {}",
@@ -2978,44 +7570,384 @@ impl Interpreter {
         Ok(Value::Bool(result))
     }
 
+    /// 用 Ord 的 `compare` 结果求比较运算 / Evaluate a comparison from the result of Ord's `compare`
+    fn compare_with(
+        &mut self,
+        op: BinOp,
+        implementation: MethodImpl,
+        left: &Value,
+        right: &Value,
+    ) -> Result<Value, InterpreterError> {
+        use BinOp::*;
+        let ordering =
+            match self.call_method("compare", implementation, &[left.clone(), right.clone()])? {
+                Value::Int(ordering) => ordering,
+                other => {
+                    return Err(InterpreterError::type_error(
+                        format!(
+                            "compare for {} must return an integer, got {}",
+                            self.value_type_name(left),
+                            self.value_type_name(&other)
+                        ),
+                        None,
+                    ))
+                }
+            };
+        let result = match op {
+            Lt => ordering < 0,
+            Le => ordering <= 0,
+            Gt => ordering > 0,
+            Ge => ordering >= 0,
+            _ => unreachable!(),
+        };
+        Ok(Value::Bool(result))
+    }
+
     /// 获取值类型名称 / Get value type name
     fn value_type_name(&self, value: &Value) -> &str {
         match value {
             Value::Int(_) => "Int",
+            Value::BigInt(_) => "BigInt",
             Value::Float(_) => "Float",
             Value::String(_) => "String",
             Value::Bool(_) => "Bool",
             Value::Null => "Null",
             Value::List(_) => "List",
             Value::Dict(_) => "Dict",
+            Value::Set(_) => "Set",
             Value::Lambda { .. } => "Lambda",
+            Value::Generator { .. } => "Generator",
+            Value::Variant { tag, .. } => self
+                .variant_constructors
+                .get(tag)
+                .map(|constructor| constructor.type_name.as_str())
+                .unwrap_or("Variant"),
+            Value::DateTime(_) => "DateTime",
+            Value::Error { .. } => "Error",
+        }
+    }
+}
+
+/// `format-date` 的默认格式 / Default format of `format-date`
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// 日期时间单位对应的秒数；月和年不是固定长度，单独处理
+/// Seconds in a date-time unit; months and years have no fixed length and are handled separately
+fn unit_seconds(unit: &str) -> Option<i64> {
+    Some(match unit {
+        "seconds" | "second" => 1,
+        "minutes" | "minute" => 60,
+        "hours" | "hour" => 3600,
+        "days" | "day" => 86_400,
+        "weeks" | "week" => 604_800,
+        _ => return None,
+    })
+}
+
+/// 日期加上若干单位（可以为负），月末按目标月份的最后一天对齐
+/// Add a number of units (possibly negative) to a date; month ends clamp to the last day of the target month
+fn date_add(
+    date: chrono::DateTime<chrono::Utc>,
+    amount: i64,
+    unit: &str,
+) -> Result<chrono::DateTime<chrono::Utc>, InterpreterError> {
+    let months = match unit {
+        "months" | "month" => Some(amount),
+        "years" | "year" => amount.checked_mul(12),
+        _ => None,
+    };
+    let result = match (months, unit_seconds(unit)) {
+        (Some(months), _) => u32::try_from(months.unsigned_abs())
+            .ok()
+            .map(chrono::Months::new)
+            .and_then(|step| {
+                if months >= 0 {
+                    date.checked_add_months(step)
+                } else {
+                    date.checked_sub_months(step)
+                }
+            }),
+        (None, Some(seconds)) => amount
+            .checked_mul(seconds)
+            .and_then(chrono::TimeDelta::try_seconds)
+            .and_then(|delta| date.checked_add_signed(delta)),
+        (None, None) => return Err(unknown_date_unit(unit)),
+    };
+    result.ok_or_else(|| {
+        InterpreterError::runtime_error(
+            format!("date-add: {} {} is out of range", amount, unit),
+            None,
+        )
+    })
+}
+
+/// 两个日期之差（前者减后者），按单位向零取整；月和年长度不固定，不支持
+/// Difference between two dates (first minus second), truncated toward zero in the unit; months and years are
+/// unsupported since their length varies
+fn date_diff(
+    later: chrono::DateTime<chrono::Utc>,
+    earlier: chrono::DateTime<chrono::Utc>,
+    unit: &str,
+) -> Result<i64, InterpreterError> {
+    let seconds = unit_seconds(unit).ok_or_else(|| unknown_date_unit(unit))?;
+    Ok((later - earlier).num_seconds() / seconds)
+}
+
+fn unknown_date_unit(unit: &str) -> InterpreterError {
+    InterpreterError::runtime_error(
+        format!(
+            "Unknown date unit '{}', expected seconds, minutes, hours, days, weeks, months or years",
+            unit
+        ),
+        None,
+    )
+}
+
+/// 按 strftime 格式输出日期，格式无效时报错而不是 panic
+/// Format a date with a strftime format, failing on an invalid format instead of panicking
+fn format_date(
+    date: chrono::DateTime<chrono::Utc>,
+    format: &str,
+) -> Result<String, InterpreterError> {
+    let items: Vec<chrono::format::Item> = chrono::format::StrftimeItems::new(format).collect();
+    if items.contains(&chrono::format::Item::Error) {
+        return Err(InterpreterError::runtime_error(
+            format!("Invalid date format '{}'", format),
+            None,
+        ));
+    }
+    Ok(date.format_with_items(items.into_iter()).to_string())
+}
+
+/// 位置参数和 `:名称 值` 选项 / Positional arguments and `:name value` options
+type KeywordArgs = (Vec<Value>, Vec<(String, Value)>);
+
+/// 区域设置的千位分隔符和小数点 / Thousands separator and decimal point of a locale
+fn number_separators(locale: &str) -> Result<(&'static str, char), InterpreterError> {
+    let language = locale.split(['-', '_']).next().unwrap_or_default();
+    Ok(match (language, locale) {
+        (_, "de-CH" | "de_CH" | "fr-CH" | "fr_CH" | "it-CH" | "it_CH") => ("'", '.'),
+        ("en" | "zh" | "ja" | "ko" | "th" | "he", _) => (",", '.'),
+        ("de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da", _) => (".", ','),
+        ("fr" | "ru" | "pl" | "sv" | "cs" | "uk" | "fi" | "nb" | "sk" | "hu", _) => ("\u{a0}", ','),
+        _ => {
+            return Err(InterpreterError::runtime_error(
+                format!("Unknown locale '{}'", locale),
+                None,
+            ))
+        }
+    })
+}
+
+/// 数值的文本形式：指定小数位时四舍五入，否则为能还原该值的最短形式（浮点数至少带一位小数）
+/// Text of a number: rounded when digits are given, otherwise the shortest form that round-trips (floats keep at
+/// least one decimal)
+fn number_text(value: &Value, digits: Option<usize>) -> Option<String> {
+    Some(match (value, digits) {
+        (Value::Int(n), None) => n.to_string(),
+        (Value::BigInt(n), None) => n.to_string(),
+        (Value::Int(n), Some(0)) => n.to_string(),
+        (Value::BigInt(n), Some(0)) => n.to_string(),
+        (Value::Int(n), Some(digits)) => format!("{}.{}", n, "0".repeat(digits)),
+        (Value::BigInt(n), Some(digits)) => format!("{}.{}", n, "0".repeat(digits)),
+        (Value::Float(f), Some(digits)) => format!("{:.*}", digits, f),
+        (Value::Float(f), None) => format!("{:?}", f),
+        _ => return None,
+    })
+}
+
+/// 给数值文本加上千位分隔符并替换小数点；指数形式和非有限值保持原样
+/// Add thousands separators to number text and replace the decimal point; exponent forms and non-finite values
+/// are left as they are
+fn localize_number(text: &str, group: Option<&str>, decimal: char) -> String {
+    if text.contains(['e', 'E']) || text.ends_with("inf") || text == "NaN" {
+        return text.to_string();
+    }
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text),
+    };
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+    let mut result = sign.to_string();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            result.push_str(group.unwrap_or_default());
+        }
+        result.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        result.push(decimal);
+        result.push_str(fraction);
+    }
+    result
+}
+
+/// 按进制解析整数，允许正负号和与进制对应的前缀（0x、0o、0b）；超出i64范围时为大整数
+/// Parse an integer in a base, allowing a sign and the prefix matching the base (0x, 0o, 0b); values beyond i64
+/// become big integers
+fn parse_int_radix(text: &str, base: u32) -> Option<Value> {
+    let text = text.trim();
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let prefix = match base {
+        16 => Some(["0x", "0X"]),
+        8 => Some(["0o", "0O"]),
+        2 => Some(["0b", "0B"]),
+        _ => None,
+    };
+    let digits = prefix
+        .and_then(|prefixes| prefixes.iter().find_map(|p| unsigned.strip_prefix(p)))
+        .unwrap_or(unsigned);
+    if digits.is_empty() || digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let magnitude = BigInt::parse_bytes(digits.as_bytes(), base)?;
+    Some(Value::from_bigint(if negative {
+        -magnitude
+    } else {
+        magnitude
+    }))
+}
+
+/// 是否是可作为函数名调用的操作符 / Whether the name is an operator callable as a function name
+fn is_operator(name: &str) -> bool {
+    matches!(
+        name,
+        "+" | "-" | "*" | "/" | "%" | "=" | "==" | "!=" | "<>" | "<" | ">" | "<=" | ">="
+    )
+}
+
+/// 元素是否包含 `yield`（不进入 lambda 和函数定义）/ Whether the element contains `yield` (not descending into lambdas and definitions)
+pub(crate) fn element_contains_yield(element: &GrammarElement) -> bool {
+    match element {
+        GrammarElement::Expr(expr) => expr_contains_yield(expr),
+        GrammarElement::List(list) => match list.first() {
+            Some(GrammarElement::Atom(head)) if head == "yield" => true,
+            Some(GrammarElement::Atom(head))
+                if matches!(head.as_str(), "lambda" | "def" | "function" | "defn-") =>
+            {
+                false
+            }
+            _ => list.iter().any(element_contains_yield),
+        },
+        GrammarElement::Atom(_) | GrammarElement::NaturalLang(_) => false,
+    }
+}
+
+/// 表达式是否包含 `yield`（不进入 lambda）/ Whether the expression contains `yield` (not descending into lambdas)
+pub(crate) fn expr_contains_yield(expr: &Expr) -> bool {
+    match expr {
+        Expr::Call(name, _) if name == "yield" => true,
+        Expr::Lambda { .. } => false,
+        _ => expr_children(expr).into_iter().any(expr_contains_yield),
+    }
+}
+
+/// 生成器体中由 `let` 和 `for` 绑定的变量名 / Names bound by `let` and `for` in a generator body
+fn element_binders(element: &GrammarElement, names: &mut Vec<String>) {
+    match element {
+        GrammarElement::Expr(expr) => expr_binders(expr, names),
+        GrammarElement::List(list) => {
+            match list.first() {
+                Some(GrammarElement::Atom(head))
+                    if matches!(head.as_str(), "lambda" | "def" | "function" | "defn-") =>
+                {
+                    return
+                }
+                Some(GrammarElement::Atom(head)) if head == "let" || head == "for" => {
+                    if let Some(Ok(name)) = list.get(1).map(binding_name) {
+                        names.push(name);
+                    }
+                }
+                _ => {}
+            }
+            for item in list {
+                element_binders(item, names);
+            }
+        }
+        GrammarElement::Atom(_) | GrammarElement::NaturalLang(_) => {}
+    }
+}
+
+fn expr_binders(expr: &Expr, names: &mut Vec<String>) {
+    match expr {
+        Expr::Lambda { .. } => return,
+        Expr::Call(name, args) if name == "let" => {
+            if let Some(Expr::Var(name)) = args.first() {
+                names.push(name.to_string());
+            }
         }
+        Expr::For { var, .. } => names.push(var.to_string()),
+        _ => {}
+    }
+    for child in expr_children(expr) {
+        expr_binders(child, names);
     }
 }
 
+/// 绑定形式中的变量名（Atom 或 Expr(Var)）/ Variable name in a binding form (Atom or Expr(Var))
+fn binding_name(element: &GrammarElement) -> Result<String, InterpreterError> {
+    match element {
+        GrammarElement::Atom(name) => Ok(name.clone()),
+        GrammarElement::Expr(expr) => match expr.as_ref() {
+            Expr::Var(name) => Ok(name.to_string()),
+            _ => Err(InterpreterError::runtime_error(
+                "Variable name must be an atom or variable".to_string(),
+                None,
+            )),
+        },
+        _ => Err(InterpreterError::runtime_error(
+            "Variable name must be an atom or variable".to_string(),
+            None,
+        )),
+    }
+}
+
+/// `yield` 出现在不支持挂起的位置 / `yield` in a position that cannot suspend
+fn unsupported_yield() -> InterpreterError {
+    InterpreterError::runtime_error(
+        "yield must appear as a statement in begin, if, let, while or for".to_string(),
+        None,
+    )
+}
+
 impl Default for Interpreter {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// 字典的存储：键按插入顺序迭代，更新已有的键不改变其位置
+/// Dictionary storage: keys iterate in insertion order, and updating an existing key keeps its position
+pub type DictMap = IndexMap<String, Value>;
+
 /// 值类型 / Value type
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Value {
     /// 整数 / Integer
     Int(i64),
+    /// 大整数（仅用于超出i64范围的值）/ Big integer (only for values outside i64 range)
+    BigInt(BigInt),
     /// 浮点数 / Float
     Float(f64),
-    /// 字符串 / String
-    String(String),
+    /// 字符串（共享，克隆不复制内容）/ String (shared; cloning does not copy the contents)
+    String(Arc<str>),
     /// 布尔值 / Boolean
     Bool(bool),
     /// 空值 / Null
     Null,
-    /// 列表 / List
-    List(Vec<Value>),
-    /// 字典 / Dictionary
-    Dict(std::collections::HashMap<String, Value>),
+    /// 列表（写时复制：克隆只增加引用计数，修改时用 `Arc::make_mut` 按需复制）
+    /// List (copy-on-write: cloning only bumps a reference count; mutation copies on demand via `Arc::make_mut`)
+    List(Arc<Vec<Value>>),
+    /// 字典（写时复制，键按插入顺序）/ Dictionary (copy-on-write, keys in insertion order)
+    Dict(Arc<DictMap>),
+    /// 集合（写时复制，元素按插入顺序）/ Set (copy-on-write, elements in insertion order)
+    Set(Arc<ValueSet>),
     /// Lambda函数 / Lambda function (closure)
     /// 注意：Lambda使用ID来标识，实际函数体在解释器的lambda_registry中存储
     /// Note: Lambda uses ID to identify, actual body is stored in interpreter's lambda_registry
@@ -3025,12 +7957,176 @@ pub enum Value {
         /// 参数列表 / Parameter names
         params: Vec<String>,
     },
+    /// 生成器（由包含 `yield` 的函数调用返回）/ Generator (returned by calling a function containing `yield`)
+    /// 挂起的执行状态存储在解释器的生成器注册表中 / The suspended execution state lives in the interpreter's generator registry
+    Generator {
+        /// 生成器标识符 / Generator identifier
+        id: String,
+    },
+    /// 代数数据类型的变体值 / Variant value of an algebraic data type
+    Variant {
+        /// 构造器标签 / Constructor tag
+        tag: String,
+        /// 字段值（按声明顺序）/ Field values (in declaration order)
+        fields: Vec<Value>,
+    },
+    /// 日期时间（UTC），字面量 `#2024-01-01` / Date and time (UTC), literal `#2024-01-01`
+    DateTime(chrono::DateTime<chrono::Utc>),
+    /// 错误对象（由 `raise` 抛出或被 `catch` 捕获）/ Error object (thrown by `raise` or bound by `catch`)
+    Error {
+        /// 错误类别 / Error kind
+        kind: String,
+        /// 错误信息 / Error message
+        message: String,
+        /// 附加数据 / Attached data
+        data: Box<Value>,
+    },
+}
+
+/// 值是否引用了只在产生它的解释器中有效的Lambda或生成器
+/// Whether a value references a lambda or generator only valid in the interpreter that produced it
+fn escapes_sandbox(value: &Value) -> bool {
+    match value {
+        Value::Lambda { .. } | Value::Generator { .. } => true,
+        Value::List(items) => items.iter().any(escapes_sandbox),
+        Value::Dict(entries) => entries.values().any(escapes_sandbox),
+        Value::Variant { fields, .. } => fields.iter().any(escapes_sandbox),
+        Value::Error { data, .. } => escapes_sandbox(data),
+        _ => false,
+    }
+}
+
+impl Value {
+    /// 从大整数创建值，能放入i64时规范化为Int
+    /// Create value from big integer, normalized to Int when it fits in i64
+    pub fn from_bigint(n: BigInt) -> Self {
+        match n.to_i64() {
+            Some(i) => Value::Int(i),
+            None => Value::BigInt(n),
+        }
+    }
+
+    /// 转换为大整数（仅整数值）/ Convert to big integer (integer values only)
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Value::Int(i) => Some(BigInt::from(*i)),
+            Value::BigInt(n) => Some(n.clone()),
+            _ => None,
+        }
+    }
+
+    /// 从JSON值创建 / Create from JSON value
+    pub fn from_json(json: &serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::Null => Value::Null,
+            serde_json::Value::Bool(b) => Value::Bool(*b),
+            serde_json::Value::Number(n) => {
+                // 超出i64的整数保持精确，解析为大整数 / Integers beyond i64 stay exact as big integers
+                if let Some(i) = n.as_i64() {
+                    Value::Int(i)
+                } else if let Ok(big) = n.to_string().parse::<BigInt>() {
+                    Value::from_bigint(big)
+                } else {
+                    Value::Float(n.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            serde_json::Value::String(s) => Value::String(s.as_str().into()),
+            serde_json::Value::Array(items) => Value::List(
+                items
+                    .iter()
+                    .map(Value::from_json)
+                    .collect::<Vec<_>>()
+                    .into(),
+            ),
+            serde_json::Value::Object(map) => Value::Dict(
+                map.iter()
+                    .map(|(k, v)| (k.clone(), Value::from_json(v)))
+                    .collect::<DictMap>()
+                    .into(),
+            ),
+        }
+    }
+
+    /// 转换为JSON值（Lambda无法转换；非有限浮点数转为null，大整数写为精确的整数）
+    /// Convert to JSON value (Lambdas are not convertible; non-finite floats become null, big integers are written as exact integers)
+    pub fn to_json(&self) -> Result<serde_json::Value, InterpreterError> {
+        Ok(match self {
+            Value::Null => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(*b),
+            Value::Int(i) => serde_json::Value::from(*i),
+            Value::BigInt(n) => serde_json::from_str::<serde_json::Number>(&n.to_string())
+                .map(serde_json::Value::Number)
+                .map_err(|e| InterpreterError::runtime_error(e.to_string(), None))?,
+            Value::Float(f) => serde_json::Number::from_f64(*f)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Value::String(s) => serde_json::Value::String(s.to_string()),
+            Value::DateTime(date) => serde_json::Value::String(date.to_rfc3339()),
+            Value::List(items) => serde_json::Value::Array(
+                items
+                    .iter()
+                    .map(Value::to_json)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Value::Dict(map) => {
+                let mut object = serde_json::Map::new();
+                for (key, value) in map.iter() {
+                    object.insert(key.clone(), value.to_json()?);
+                }
+                serde_json::Value::Object(object)
+            }
+            // JSON 没有集合，转换为数组 / JSON has no sets; they become arrays
+            Value::Set(set) => serde_json::Value::Array(
+                set.iter()
+                    .map(Value::to_json)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
+            Value::Variant { tag, fields } => serde_json::json!({
+                "tag": tag,
+                "fields": fields
+                    .iter()
+                    .map(Value::to_json)
+                    .collect::<Result<Vec<_>, _>>()?,
+            }),
+            Value::Error {
+                kind,
+                message,
+                data,
+            } => serde_json::json!({
+                "kind": kind,
+                "message": message,
+                "data": data.to_json()?,
+            }),
+            Value::Lambda { .. } => {
+                return Err(InterpreterError::type_error(
+                    "Cannot convert lambda to JSON".to_string(),
+                    None,
+                ))
+            }
+            Value::Generator { .. } => {
+                return Err(InterpreterError::type_error(
+                    "Cannot convert generator to JSON".to_string(),
+                    None,
+                ))
+            }
+        })
+    }
+}
+
+/// 大整数转浮点数（超出f64范围时为无穷大）/ Big integer to float (infinite beyond f64 range)
+fn bigint_to_f64(n: &BigInt) -> f64 {
+    n.to_f64().unwrap_or(if n.sign() == Sign::Minus {
+        f64::NEG_INFINITY
+    } else {
+        f64::INFINITY
+    })
 }
 
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Int(i) => write!(f, "{}", i),
+            Value::BigInt(n) => write!(f, "{}", n),
             Value::Float(fl) => write!(f, "{}", fl),
             Value::String(s) => write!(f, "{}", s),
             Value::Bool(b) => write!(f, "{}", b),
@@ -3048,7 +8144,7 @@ impl std::fmt::Display for Value {
             Value::Dict(dict) => {
                 write!(f, "{{")?;
                 let mut first = true;
-                for (key, value) in dict {
+                for (key, value) in dict.iter() {
                     if !first {
                         write!(f, ", ")?;
                     }
@@ -3057,9 +8153,34 @@ impl std::fmt::Display for Value {
                 }
                 write!(f, "}}")
             }
+            Value::Set(set) => {
+                write!(f, "#{{")?;
+                for (i, item) in set.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "}}")
+            }
             Value::Lambda { params, .. } => {
                 write!(f, "<lambda({})>", params.join(", "))
             }
+            Value::Generator { .. } => write!(f, "<generator>"),
+            Value::Variant { tag, fields } if fields.is_empty() => write!(f, "{}", tag),
+            Value::Variant { tag, fields } => {
+                write!(f, "({}", tag)?;
+                for field in fields {
+                    write!(f, " {}", field)?;
+                }
+                write!(f, ")")
+            }
+            Value::DateTime(date) => write!(
+                f,
+                "{}",
+                date.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+            ),
+            Value::Error { message, .. } => write!(f, "{}", message),
         }
     }
 }
@@ -3083,8 +8204,20 @@ impl Location {
     }
 }
 
+/// 未捕获错误的调用栈：出错的顶层形式、最内层失败的调用和用户函数栈帧
+/// Call stack of an uncaught error: the failing top-level form, the innermost failing call and the user function frames
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ErrorTrace {
+    /// 出错的顶层形式下标 / Index of the failing top-level form
+    pub form: Option<usize>,
+    /// 最内层栈帧中失败的调用、运算符或变量名 / Call, operator or variable name that failed in the innermost frame
+    pub site: Option<String>,
+    /// 用户函数和Lambda栈帧，最内层在前 / User function and lambda frames, innermost first
+    pub frames: Vec<String>,
+}
+
 /// 解释器错误 / Interpreter error
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum InterpreterError {
     /// 未实现 / Not implemented
     NotImplemented,
@@ -3105,6 +8238,29 @@ pub enum InterpreterError {
         message: String,
         location: Option<Location>,
     },
+    /// 整数溢出 / Integer overflow
+    IntegerOverflow {
+        operation: String,
+        location: Option<Location>,
+    },
+    /// 循环导入（包含循环路径）/ Circular import (with the cycle path)
+    CircularImport {
+        cycle: Vec<String>,
+        location: Option<Location>,
+    },
+    /// 超出执行资源预算 / Execution resource budget exceeded
+    BudgetExceeded {
+        kind: BudgetKind,
+        limit: u64,
+        location: Option<Location>,
+    },
+    /// 脚本通过 `raise` 抛出的错误 / Error thrown by a script through `raise`
+    Raised {
+        kind: String,
+        message: String,
+        data: Box<Value>,
+        location: Option<Location>,
+    },
 }
 
 impl InterpreterError {
@@ -3127,6 +8283,86 @@ impl InterpreterError {
     pub fn division_by_zero(location: Option<Location>) -> Self {
         Self::DivisionByZero { location }
     }
+
+    /// 创建整数溢出错误 / Create integer overflow error
+    pub fn integer_overflow(operation: String, location: Option<Location>) -> Self {
+        Self::IntegerOverflow {
+            operation,
+            location,
+        }
+    }
+
+    /// 创建循环导入错误 / Create circular import error
+    pub fn circular_import(cycle: Vec<String>, location: Option<Location>) -> Self {
+        Self::CircularImport { cycle, location }
+    }
+
+    /// 创建超出预算错误 / Create budget exceeded error
+    pub fn budget_exceeded(kind: BudgetKind, limit: u64, location: Option<Location>) -> Self {
+        Self::BudgetExceeded {
+            kind,
+            limit,
+            location,
+        }
+    }
+
+    /// 创建脚本抛出的错误 / Create script-raised error
+    pub fn raised(kind: String, message: String, data: Value, location: Option<Location>) -> Self {
+        Self::Raised {
+            kind,
+            message,
+            data: Box::new(data),
+            location,
+        }
+    }
+
+    /// 错误类别名称 / Error kind name
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::NotImplemented => "NotImplemented",
+            Self::UndefinedVariable { .. } => "UndefinedVariable",
+            Self::TypeError { .. } => "TypeError",
+            Self::DivisionByZero { .. } => "DivisionByZero",
+            Self::RuntimeError { .. } => "RuntimeError",
+            Self::IntegerOverflow { .. } => "IntegerOverflow",
+            Self::CircularImport { .. } => "CircularImport",
+            Self::BudgetExceeded { .. } => "BudgetExceeded",
+            Self::Raised { kind, .. } => kind,
+        }
+    }
+
+    /// 转换为 `catch` 绑定的错误对象 / Convert to the error object bound by `catch`
+    pub fn to_value(&self) -> Value {
+        let (message, data) = match self {
+            Self::Raised { message, data, .. } => (message.clone(), (**data).clone()),
+            Self::UndefinedVariable { name, .. } => {
+                (self.to_string(), Value::String(name.as_str().into()))
+            }
+            Self::IntegerOverflow { operation, .. } => {
+                (self.to_string(), Value::String(operation.as_str().into()))
+            }
+            Self::CircularImport { cycle, .. } => (
+                self.to_string(),
+                Value::List(
+                    cycle
+                        .iter()
+                        .map(|path| Value::String(path.as_str().into()))
+                        .collect::<Vec<_>>()
+                        .into(),
+                ),
+            ),
+            Self::BudgetExceeded { limit, .. } => (
+                self.to_string(),
+                Value::Int(i64::try_from(*limit).unwrap_or(i64::MAX)),
+            ),
+            _ => (self.to_string(), Value::Null),
+        };
+        Value::Error {
+            kind: self.kind().to_string(),
+            message,
+            data: Box::new(data),
+        }
+    }
 }
 
 impl std::fmt::Display for InterpreterError {
@@ -3161,6 +8397,57 @@ impl std::fmt::Display for InterpreterError {
                     write!(f, "Runtime error: {}", message)
                 }
             }
+            Self::IntegerOverflow {
+                operation,
+                location,
+            } => {
+                if let Some(loc) = location {
+                    write!(f, "Integer overflow in {} at {}", operation, loc.format())
+                } else {
+                    write!(f, "Integer overflow in {}", operation)
+                }
+            }
+            Self::CircularImport { cycle, location } => {
+                if let Some(loc) = location {
+                    write!(
+                        f,
+                        "Circular import at {}: {}",
+                        loc.format(),
+                        cycle.join(" -> ")
+                    )
+                } else {
+                    write!(f, "Circular import: {}", cycle.join(" -> "))
+                }
+            }
+            Self::BudgetExceeded {
+                kind,
+                limit,
+                location,
+            } => {
+                if let Some(loc) = location {
+                    write!(
+                        f,
+                        "Execution budget exceeded at {}: {} (limit {})",
+                        loc.format(),
+                        kind,
+                        limit
+                    )
+                } else {
+                    write!(f, "Execution budget exceeded: {} (limit {})", kind, limit)
+                }
+            }
+            Self::Raised {
+                kind,
+                message,
+                location,
+                ..
+            } => {
+                if let Some(loc) = location {
+                    write!(f, "{} at {}: {}", kind, loc.format(), message)
+                } else {
+                    write!(f, "{}: {}", kind, message)
+                }
+            }
         }
     }
 }
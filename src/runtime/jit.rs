@@ -2,8 +2,9 @@
 // 实现热点代码优化和即时编译
 // Implements hot spot code optimization and just-in-time compilation
 
-use crate::grammar::core::{Expr, GrammarElement};
-use crate::runtime::interpreter::{Interpreter, InterpreterError, Value};
+use crate::grammar::core::GrammarElement;
+use crate::optimizer::passes::{PassChange, PassManager};
+use crate::runtime::interpreter::{CallCacheStats, Interpreter, InterpreterError, Value};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
@@ -20,19 +21,55 @@ pub struct JITCompiler {
     enabled: bool,
 }
 
+/// 原生代码后端：解释器调用主作用域函数前先交给后端，后端返回 None 时照常解释执行
+/// Native code backend: the interpreter offers main-scope function calls to it first and interprets them when it returns None
+pub(crate) trait NativeBackend: Send {
+    /// 以原生代码执行调用，返回结果和消耗的步数；`fuel` 为剩余步数预算（None表示不限制）
+    /// Run a call as native code, returning the result and the steps used; `fuel` is the remaining step budget (None means unlimited)
+    fn call(
+        &mut self,
+        interpreter: &Interpreter,
+        name: &str,
+        args: &[Value],
+        fuel: Option<u64>,
+    ) -> Option<(Value, u64)>;
+
+    /// 已编译的函数特化，如 `fib(Int)` / Compiled function specializations, such as `fib(Int)`
+    fn compiled_functions(&self) -> Vec<String>;
+}
+
 /// 编译后的代码 / Compiled code
 #[derive(Debug, Clone)]
 pub struct CompiledCode {
     /// 原始AST / Original AST
     ast: Vec<GrammarElement>,
-    /// 优化后的表达式 / Optimized expression
-    optimized_expr: Option<Expr>,
+    /// 优化后的AST / Optimized AST
+    optimized_ast: Vec<GrammarElement>,
+    /// 优化遍所做的改动 / Changes made by the optimizer passes
+    changes: Vec<PassChange>,
     /// 编译时间戳（秒） / Compilation timestamp (seconds)
     compiled_at_timestamp: u64,
     /// 执行次数 / Execution count
     execution_count: usize,
 }
 
+impl CompiledCode {
+    /// 原始AST / Original AST
+    pub fn ast(&self) -> &[GrammarElement] {
+        &self.ast
+    }
+
+    /// 优化后实际执行的AST / Optimized AST that is actually executed
+    pub fn optimized_ast(&self) -> &[GrammarElement] {
+        &self.optimized_ast
+    }
+
+    /// 优化遍所做的改动（机器可读） / Changes made by the optimizer passes (machine-readable)
+    pub fn changes(&self) -> &[PassChange] {
+        &self.changes
+    }
+}
+
 /// 执行统计 / Execution statistics
 #[derive(Debug, Clone)]
 struct ExecutionStats {
@@ -128,8 +165,8 @@ impl JITCompiler {
             return Ok(());
         }
 
-        // 优化代码 / Optimize code
-        let optimized = self.optimize_code(ast)?;
+        // 运行共享的优化遍 / Run the shared optimizer passes
+        let optimized = PassManager::new().run(ast);
 
         // 缓存编译后的代码 / Cache compiled code
         let timestamp = std::time::SystemTime::now()
@@ -140,7 +177,8 @@ impl JITCompiler {
             code_key.to_string(),
             CompiledCode {
                 ast: ast.to_vec(),
-                optimized_expr: optimized,
+                optimized_ast: optimized.ast,
+                changes: optimized.changes,
                 compiled_at_timestamp: timestamp,
                 execution_count: 0,
             },
@@ -149,151 +187,6 @@ impl JITCompiler {
         Ok(())
     }
 
-    /// 优化代码 / Optimize code
-    fn optimize_code(&self, ast: &[GrammarElement]) -> Result<Option<Expr>, InterpreterError> {
-        // 简化实现：提取第一个表达式进行优化
-        // Simplified implementation: extract first expression for optimization
-        if let Some(GrammarElement::Expr(expr)) = ast.first() {
-            let optimized = self.optimize_expr(expr)?;
-            Ok(Some(optimized))
-        } else {
-            Ok(None)
-        }
-    }
-
-    /// 优化表达式 / Optimize expression
-    fn optimize_expr(&self, expr: &Expr) -> Result<Expr, InterpreterError> {
-        match expr {
-            // 常量折叠 / Constant folding
-            Expr::Binary(op, left, right) => {
-                // 如果左右都是字面量，直接计算
-                // If both left and right are literals, calculate directly
-                if let (Expr::Literal(left_lit), Expr::Literal(right_lit)) =
-                    (left.as_ref(), right.as_ref())
-                {
-                    let result = self.eval_binary_literal(op, left_lit, right_lit)?;
-                    Ok(Expr::Literal(result))
-                } else {
-                    // 递归优化子表达式 / Recursively optimize sub-expressions
-                    let opt_left = Box::new(self.optimize_expr(left)?);
-                    let opt_right = Box::new(self.optimize_expr(right)?);
-                    Ok(Expr::Binary(*op, opt_left, opt_right))
-                }
-            }
-            // 其他表达式保持不变或递归优化 / Other expressions remain unchanged or recursively optimized
-            Expr::Call(name, args) => {
-                let opt_args: Result<Vec<Expr>, InterpreterError> =
-                    args.iter().map(|arg| self.optimize_expr(arg)).collect();
-                Ok(Expr::Call(name.clone(), opt_args?))
-            }
-            Expr::If(cond, then_expr, else_expr) => {
-                let opt_cond = Box::new(self.optimize_expr(cond)?);
-                let opt_then = Box::new(self.optimize_expr(then_expr)?);
-                let opt_else = Box::new(self.optimize_expr(else_expr)?);
-                Ok(Expr::If(opt_cond, opt_then, opt_else))
-            }
-            // 字面量和变量保持不变 / Literals and variables remain unchanged
-            Expr::Literal(_) | Expr::Var(_) => Ok(expr.clone()),
-            // 其他表达式暂不支持优化 / Other expressions don't support optimization yet
-            Expr::Match(_, _)
-            | Expr::For { .. }
-            | Expr::While { .. }
-            | Expr::Try { .. }
-            | Expr::Lambda { .. }
-            | Expr::Begin(_)
-            | Expr::Assign(_, _) => Ok(expr.clone()),
-        }
-    }
-
-    /// 评估二元运算字面量 / Evaluate binary operation literals
-    fn eval_binary_literal(
-        &self,
-        op: &crate::grammar::core::BinOp,
-        left: &crate::grammar::core::Literal,
-        right: &crate::grammar::core::Literal,
-    ) -> Result<crate::grammar::core::Literal, InterpreterError> {
-        use crate::grammar::core::{BinOp, Literal};
-
-        match (op, left, right) {
-            // 算术运算 / Arithmetic operations
-            (BinOp::Add, Literal::Int(a), Literal::Int(b)) => Ok(Literal::Int(a + b)),
-            (BinOp::Add, Literal::Float(a), Literal::Float(b)) => Ok(Literal::Float(a + b)),
-            (BinOp::Sub, Literal::Int(a), Literal::Int(b)) => Ok(Literal::Int(a - b)),
-            (BinOp::Sub, Literal::Float(a), Literal::Float(b)) => Ok(Literal::Float(a - b)),
-            (BinOp::Mul, Literal::Int(a), Literal::Int(b)) => Ok(Literal::Int(a * b)),
-            (BinOp::Mul, Literal::Float(a), Literal::Float(b)) => Ok(Literal::Float(a * b)),
-            (BinOp::Div, Literal::Int(a), Literal::Int(b)) => {
-                if *b == 0 {
-                    return Err(InterpreterError::division_by_zero(None));
-                }
-                Ok(Literal::Int(a / b))
-            }
-            (BinOp::Div, Literal::Float(a), Literal::Float(b)) => {
-                if *b == 0.0 {
-                    return Err(InterpreterError::division_by_zero(None));
-                }
-                Ok(Literal::Float(a / b))
-            }
-            (BinOp::Mod, Literal::Int(a), Literal::Int(b)) => {
-                if *b == 0 {
-                    return Err(InterpreterError::division_by_zero(None));
-                }
-                Ok(Literal::Int(a % b))
-            }
-            (BinOp::Mod, Literal::Float(a), Literal::Float(b)) => {
-                if *b == 0.0 {
-                    return Err(InterpreterError::division_by_zero(None));
-                }
-                Ok(Literal::Float(a % b))
-            }
-            // 比较运算 / Comparison operations
-            (BinOp::Eq, left, right) => {
-                // 列表和字典不能进行常量折叠比较
-                if matches!(left, Literal::List(_) | Literal::Dict(_))
-                    || matches!(right, Literal::List(_) | Literal::Dict(_))
-                {
-                    return Err(InterpreterError::type_error(
-                        "Cannot fold comparison with list or dict literals".to_string(),
-                        None,
-                    ));
-                }
-                Ok(Literal::Bool(left == right))
-            }
-            (BinOp::Ne, left, right) => {
-                // 列表和字典不能进行常量折叠比较
-                if matches!(left, Literal::List(_) | Literal::Dict(_))
-                    || matches!(right, Literal::List(_) | Literal::Dict(_))
-                {
-                    return Err(InterpreterError::type_error(
-                        "Cannot fold comparison with list or dict literals".to_string(),
-                        None,
-                    ));
-                }
-                Ok(Literal::Bool(left != right))
-            }
-            (BinOp::Lt, Literal::Int(a), Literal::Int(b)) => Ok(Literal::Bool(a < b)),
-            (BinOp::Lt, Literal::Float(a), Literal::Float(b)) => Ok(Literal::Bool(a < b)),
-            (BinOp::Gt, Literal::Int(a), Literal::Int(b)) => Ok(Literal::Bool(a > b)),
-            (BinOp::Gt, Literal::Float(a), Literal::Float(b)) => Ok(Literal::Bool(a > b)),
-            (BinOp::Le, Literal::Int(a), Literal::Int(b)) => Ok(Literal::Bool(a <= b)),
-            (BinOp::Le, Literal::Float(a), Literal::Float(b)) => Ok(Literal::Bool(a <= b)),
-            (BinOp::Ge, Literal::Int(a), Literal::Int(b)) => Ok(Literal::Bool(a >= b)),
-            (BinOp::Ge, Literal::Float(a), Literal::Float(b)) => Ok(Literal::Bool(a >= b)),
-            // 列表和字典不支持常量折叠的算术运算
-            (_, Literal::List(_), _)
-            | (_, _, Literal::List(_))
-            | (_, Literal::Dict(_), _)
-            | (_, _, Literal::Dict(_)) => Err(InterpreterError::type_error(
-                "Cannot fold binary operation with list or dict literals".to_string(),
-                None,
-            )),
-            _ => Err(InterpreterError::type_error(
-                "Invalid types for binary operation".to_string(),
-                None,
-            )),
-        }
-    }
-
     /// 获取编译后的代码 / Get compiled code
     pub fn get_compiled_code(&self, code_key: &str) -> Option<&CompiledCode> {
         self.hot_spots.get(code_key)
@@ -308,13 +201,7 @@ impl JITCompiler {
         if let Some(compiled) = self.hot_spots.get_mut(code_key) {
             compiled.execution_count += 1;
 
-            // 如果有优化后的表达式，使用它 / If optimized expression exists, use it
-            if let Some(ref opt_expr) = compiled.optimized_expr {
-                interpreter.execute_expr(opt_expr)
-            } else {
-                // 否则使用原始AST / Otherwise use original AST
-                interpreter.execute(&compiled.ast)
-            }
+            interpreter.execute(&compiled.optimized_ast)
         } else {
             Err(InterpreterError::runtime_error(
                 "Compiled code not found".to_string(),
@@ -348,6 +235,8 @@ impl JITCompiler {
             compiled_count,
             compilation_threshold: self.compilation_threshold,
             enabled: self.enabled,
+            native_functions: Vec::new(),
+            call_cache: CallCacheStats::default(),
         }
     }
 }
@@ -371,4 +260,10 @@ pub struct JITStatistics {
     pub compilation_threshold: usize,
     /// 是否启用 / Whether enabled
     pub enabled: bool,
+    /// 已编译为原生代码的函数特化（需要 `cranelift` 特性）/ Function specializations compiled to native code (requires the `cranelift` feature)
+    #[serde(default)]
+    pub native_functions: Vec<String>,
+    /// 调用分派内联缓存的命中统计 / Hit statistics of the call dispatch inline cache
+    #[serde(default)]
+    pub call_cache: CallCacheStats,
 }
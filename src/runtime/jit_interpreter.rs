@@ -4,7 +4,9 @@
 
 use crate::grammar::core::GrammarElement;
 use crate::runtime::interpreter::{Interpreter, InterpreterError, Value};
-use crate::runtime::jit::{JITCompiler, JITStatistics};
+use crate::runtime::jit::{JITCompiler, JITStatistics, NativeBackend};
+#[cfg(feature = "cranelift")]
+use crate::runtime::native::CraneliftBackend;
 use std::time::{Duration, Instant};
 
 /// JIT解释器 / JIT Interpreter
@@ -17,24 +19,37 @@ pub struct JITInterpreter {
     jit_compiler: JITCompiler,
     /// 是否启用JIT / Whether JIT is enabled
     jit_enabled: bool,
+    /// 禁用JIT期间从解释器取下的原生代码后端 / Native code backend taken off the interpreter while JIT is disabled
+    parked_backend: Option<Box<dyn NativeBackend>>,
 }
 
 impl JITInterpreter {
     /// 创建新JIT解释器 / Create new JIT interpreter
     pub fn new() -> Self {
-        Self {
-            interpreter: Interpreter::new(),
-            jit_compiler: JITCompiler::new(),
-            jit_enabled: true,
-        }
+        Self::with_compiler(JITCompiler::new())
     }
 
     /// 创建带自定义JIT阈值的解释器 / Create interpreter with custom JIT threshold
     pub fn with_threshold(threshold: usize) -> Self {
+        Self::with_compiler(JITCompiler::with_threshold(threshold))
+    }
+
+    /// 启用 `cranelift` 特性时，函数被调用达到同一阈值后编译为原生代码
+    /// With the `cranelift` feature, functions are compiled to native code after being called the same threshold number of times
+    fn with_compiler(jit_compiler: JITCompiler) -> Self {
+        #[allow(unused_mut)]
+        let mut interpreter = Interpreter::new();
+        #[cfg(feature = "cranelift")]
+        if let Some(backend) =
+            CraneliftBackend::with_threshold(jit_compiler.get_statistics().compilation_threshold)
+        {
+            interpreter.set_native_backend(Box::new(backend));
+        }
         Self {
-            interpreter: Interpreter::new(),
-            jit_compiler: JITCompiler::with_threshold(threshold),
+            interpreter,
+            jit_compiler,
             jit_enabled: true,
+            parked_backend: None,
         }
     }
 
@@ -42,6 +57,13 @@ impl JITInterpreter {
     pub fn set_jit_enabled(&mut self, enabled: bool) {
         self.jit_enabled = enabled;
         self.jit_compiler.set_enabled(enabled);
+        if enabled {
+            if let Some(backend) = self.parked_backend.take() {
+                self.interpreter.set_native_backend(backend);
+            }
+        } else if let Some(backend) = self.interpreter.take_native_backend() {
+            self.parked_backend = Some(backend);
+        }
     }
 
     /// 执行代码（带JIT优化） / Execute code (with JIT optimization)
@@ -104,7 +126,19 @@ impl JITInterpreter {
 
     /// 获取JIT统计信息 / Get JIT statistics
     pub fn get_jit_statistics(&self) -> JITStatistics {
-        self.jit_compiler.get_statistics()
+        let mut statistics = self.jit_compiler.get_statistics();
+        statistics.native_functions = self.native_functions();
+        statistics.call_cache = self.interpreter.call_cache_stats();
+        statistics
+    }
+
+    /// 已编译为原生代码的函数特化，如 `fib(Int)` / Function specializations compiled to native code, such as `fib(Int)`
+    pub fn native_functions(&self) -> Vec<String> {
+        self.interpreter
+            .native_backend()
+            .or(self.parked_backend.as_deref())
+            .map(NativeBackend::compiled_functions)
+            .unwrap_or_default()
     }
 
     /// 获取热点代码列表 / Get hot spot code list
@@ -5,10 +5,20 @@
 //!
 //! ## 快速导航 / Quick Navigation
 //!
+//! - `bench.rs` - **基准测试** - 预热后重复计时，统计均值、中位数和标准差: `bench`, `BenchStats`
+//! - `coverage.rs` - **覆盖率** - 插桩执行，按函数统计行、分支和表达式覆盖率: `CoverageMap`, `CoverageReport`
+//! - `debugger.rs` - **调试器** - 断点、单步与变量检查: `DebugInterpreter`
+//! - `gc.rs` - **垃圾回收** - 标记-清除回收不可达的Lambda和生成器: `GcConfig`, `GcStats`
+//! - `http.rs` - **HTTP客户端** - NLU后端、网络钩子和 `http` 特性内置函数共用的 HTTP/1.1 客户端: `send`, `post_json`
 //! - `interpreter.rs` - **解释器核心** - 主入口: `Interpreter::new()`, `execute()`
-//! - `jit.rs` - **JIT编译器** - 热点检测、常量折叠: `JITCompiler::compile()`
+//! - `jit.rs` - **JIT编译器** - 热点检测，热点代码经 `optimizer` 优化遍处理: `JITCompiler::compile()`
 //! - `jit_interpreter.rs` - **JIT解释器** - 整合解释器和JIT编译器
 //! - `mode.rs` - **执行模式选择** - 解释模式 vs JIT模式切换
+//! - `native.rs` - **原生代码生成**（`cranelift` 特性）- 热点纯数值函数编译为机器码
+//! - `symbol.rs` - **符号驻留** - AST中的名称，环境和函数表的键: `Symbol`, `SymbolTable`
+//! - `stdlib.rs` - **原生标准库** - 内置模块: `math`, `random`, `time`, `string`
+//! - `trace.rs` - **执行追踪** - 函数调用追踪与剖析: `Tracer`, `Profiler`
+//! - `value_set.rs` - **集合值** - `Value::Set` 的存储和值的哈希规则: `ValueSet`, `value_hash`
 //!
 //! ## 数据流 / Data Flow
 //! ```
@@ -23,12 +33,32 @@
 //! Value (运行时值)
 //! ```
 
+pub mod bench;
+pub mod coverage;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod debugger;
+pub mod gc;
+pub mod http;
 pub mod interpreter;
 pub mod jit;
 pub mod jit_interpreter;
 pub mod mode;
+#[cfg(feature = "cranelift")]
+mod native;
+pub mod stdlib;
+pub mod symbol;
+pub mod trace;
+pub mod value_set;
 
+pub use bench::*;
+pub use coverage::*;
+#[cfg(not(target_arch = "wasm32"))]
+pub use debugger::*;
+pub use gc::*;
 pub use interpreter::*;
 pub use jit::*;
 pub use jit_interpreter::*;
 pub use mode::*;
+pub use symbol::*;
+pub use trace::*;
+pub use value_set::*;
@@ -0,0 +1,886 @@
+// Cranelift原生代码后端 / Cranelift native code backend
+// 热点的纯数值函数按参数类型（Int/Float）特化编译为机器码；整数溢出、除零、调用过深或步数用尽时
+// 原生代码放弃执行，由解释器重新执行整个调用（纯数值函数没有副作用，重新执行是安全的）
+// Hot pure-numeric functions are compiled to machine code specialized by argument types (Int/Float); on integer
+// overflow, division by zero, excessive call depth or exhausted steps the native code bails out and the interpreter
+// re-executes the whole call (pure-numeric functions have no side effects, so re-executing is safe)
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::Rc;
+
+use cranelift_codegen::ir::condcodes::{FloatCC, IntCC};
+use cranelift_codegen::ir::{self, types, AbiParam, Block, InstBuilder, MemFlags};
+use cranelift_codegen::settings::{self, Configurable};
+use cranelift_codegen::Context;
+use cranelift_frontend::{FunctionBuilder, FunctionBuilderContext};
+use cranelift_jit::{JITBuilder, JITModule};
+use cranelift_module::{default_libcall_names, FuncId, Linkage, Module};
+
+use super::interpreter::{Interpreter, Value};
+use super::jit::NativeBackend;
+use super::symbol::Symbol;
+use crate::grammar::core::{BinOp, Expr, GrammarElement, Literal};
+
+/// 原生调用的最大嵌套深度，超过时交回解释器 / Maximum nesting of native calls; deeper calls go back to the interpreter
+const MAX_NATIVE_DEPTH: i64 = 10_000;
+/// 一次编译最多生成的函数特化数 / Maximum number of function specializations generated by one compilation
+const MAX_INSTANCES: usize = 64;
+/// 返回类型推断的最大轮数 / Maximum passes of return type inference
+const MAX_PASSES: usize = 16;
+
+/// 原生代码与宿主共享的运行状态 / Run state shared between native code and the host
+#[repr(C)]
+struct NativeContext {
+    /// 非零表示已放弃 / Non-zero once bailed out
+    bailout: i64,
+    /// 剩余步数，每次调用减一 / Remaining steps, one per call
+    fuel: i64,
+    /// 当前调用深度 / Current call depth
+    depth: i64,
+}
+
+const BAILOUT_OFFSET: i32 = 0;
+const FUEL_OFFSET: i32 = 8;
+const DEPTH_OFFSET: i32 = 16;
+
+/// 入口跳板：参数按位存放在数组中，返回值按位返回 / Entry trampoline: arguments are passed as bits in an array, the result is returned as bits
+type EntryFn = unsafe extern "C" fn(*const u64, *mut NativeContext) -> u64;
+
+/// 浮点取模（Cranelift没有对应指令）/ Float remainder (Cranelift has no instruction for it)
+extern "C" fn evo_fmod(a: f64, b: f64) -> f64 {
+    a % b
+}
+
+/// 原生代码中的值类型 / Value type in native code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum NumType {
+    Int,
+    Float,
+    Bool,
+}
+
+impl NumType {
+    fn clif(self) -> ir::Type {
+        match self {
+            NumType::Int => types::I64,
+            NumType::Float => types::F64,
+            NumType::Bool => types::I8,
+        }
+    }
+
+    fn of(value: &Value) -> Option<Self> {
+        match value {
+            Value::Int(_) => Some(NumType::Int),
+            Value::Float(_) => Some(NumType::Float),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for NumType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NumType::Int => write!(f, "Int"),
+            NumType::Float => write!(f, "Float"),
+            NumType::Bool => write!(f, "Bool"),
+        }
+    }
+}
+
+/// 函数特化：函数名和参数类型 / Function specialization: function name and argument types
+type Key = (String, Vec<NumType>);
+
+/// 函数体不是纯数值代码，或类型不能静态确定 / The body is not pure-numeric code, or its types cannot be determined statically
+#[derive(Debug)]
+struct Unsupported;
+
+/// 可编译的表达式 / Compilable expression
+#[derive(Debug)]
+enum Node {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Param(usize),
+    Arith(BinOp, Box<Node>, Box<Node>),
+    Compare(BinOp, Box<Node>, Box<Node>),
+    If(Box<Node>, Box<Node>, Box<Node>),
+    Call(String, Vec<Node>),
+}
+
+/// 降级后的函数 / Lowered function
+#[derive(Debug)]
+struct Source {
+    arity: usize,
+    body: Node,
+}
+
+/// 把表达式降级为可编译的形式 / Lower an expression to compilable form
+fn lower(expr: &Expr, params: &[Symbol]) -> Result<Node, Unsupported> {
+    match expr {
+        Expr::Literal(Literal::Int(i)) => Ok(Node::Int(*i)),
+        Expr::Literal(Literal::Float(f)) => Ok(Node::Float(*f)),
+        Expr::Literal(Literal::Bool(b)) => Ok(Node::Bool(*b)),
+        Expr::Var(name) => params
+            .iter()
+            .position(|param| param == name)
+            .map(Node::Param)
+            .ok_or(Unsupported),
+        Expr::Binary(op, left, right) => {
+            lower_binary(*op, lower(left, params)?, lower(right, params)?)
+        }
+        Expr::If(cond, then_expr, else_expr) => Ok(Node::If(
+            Box::new(lower(cond, params)?),
+            Box::new(lower(then_expr, params)?),
+            Box::new(lower(else_expr, params)?),
+        )),
+        Expr::Call(name, args) => {
+            let args = args
+                .iter()
+                .map(|arg| lower(arg, params))
+                .collect::<Result<Vec<_>, _>>()?;
+            let op = name.strip_prefix("op:").unwrap_or(name);
+            if op == "+" {
+                // + 可变参数，从左到右相加 / + is variadic, added from left to right
+                let mut args = args.into_iter();
+                let first = args.next().ok_or(Unsupported)?;
+                return args.try_fold(first, |sum, arg| lower_binary(BinOp::Add, sum, arg));
+            }
+            let op = match op {
+                "-" => Some(BinOp::Sub),
+                "*" => Some(BinOp::Mul),
+                "/" => Some(BinOp::Div),
+                "%" => Some(BinOp::Mod),
+                "=" | "==" => Some(BinOp::Eq),
+                "!=" | "<>" => Some(BinOp::Ne),
+                "<" => Some(BinOp::Lt),
+                ">" => Some(BinOp::Gt),
+                "<=" => Some(BinOp::Le),
+                ">=" => Some(BinOp::Ge),
+                _ => None,
+            };
+            match op {
+                Some(op) => {
+                    let [left, right]: [Node; 2] = args.try_into().map_err(|_| Unsupported)?;
+                    lower_binary(op, left, right)
+                }
+                // 参数名作函数调用时是Lambda值 / A parameter used as a callee is a lambda value
+                None if params.contains(name) => Err(Unsupported),
+                None => Ok(Node::Call(name.to_string(), args)),
+            }
+        }
+        _ => Err(Unsupported),
+    }
+}
+
+fn lower_binary(op: BinOp, left: Node, right: Node) -> Result<Node, Unsupported> {
+    let (left, right) = (Box::new(left), Box::new(right));
+    Ok(match op {
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Mod => {
+            Node::Arith(op, left, right)
+        }
+        BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Gt | BinOp::Le | BinOp::Ge => {
+            Node::Compare(op, left, right)
+        }
+    })
+}
+
+/// 算术结果类型，与解释器的规则一致（混合类型只有加法和取模提升为浮点）
+/// Arithmetic result type, following the interpreter's rules (only addition and modulo promote mixed operands to float)
+fn arith_type(op: BinOp, left: NumType, right: NumType) -> Result<NumType, Unsupported> {
+    use NumType::*;
+    match (left, right) {
+        (Int, Int) => Ok(Int),
+        (Float, Float) => Ok(Float),
+        (Int, Float) | (Float, Int) if matches!(op, BinOp::Add | BinOp::Mod) => Ok(Float),
+        _ => Err(Unsupported),
+    }
+}
+
+/// 比较的操作数必须同类型；布尔值只能判断相等
+/// Compared operands must have the same type; booleans can only be tested for equality
+fn compare_type(op: BinOp, left: NumType, right: NumType) -> Result<NumType, Unsupported> {
+    match (left, right) {
+        (NumType::Int, NumType::Int) | (NumType::Float, NumType::Float) => Ok(NumType::Bool),
+        (NumType::Bool, NumType::Bool) if matches!(op, BinOp::Eq | BinOp::Ne) => Ok(NumType::Bool),
+        _ => Err(Unsupported),
+    }
+}
+
+/// 待编译的函数特化集合及其返回类型推断 / Set of function specializations to compile, with return type inference
+struct Program<'a> {
+    interpreter: &'a Interpreter,
+    compiled: &'a HashMap<Key, (FuncId, NumType)>,
+    sources: HashMap<String, Rc<Source>>,
+    instances: Vec<Key>,
+    returns: HashMap<Key, Option<NumType>>,
+}
+
+impl<'a> Program<'a> {
+    fn new(interpreter: &'a Interpreter, compiled: &'a HashMap<Key, (FuncId, NumType)>) -> Self {
+        Self {
+            interpreter,
+            compiled,
+            sources: HashMap::new(),
+            instances: Vec::new(),
+            returns: HashMap::new(),
+        }
+    }
+
+    fn source(&mut self, name: &str) -> Result<Rc<Source>, Unsupported> {
+        if let Some(source) = self.sources.get(name) {
+            return Ok(source.clone());
+        }
+        let (params, body) = self.interpreter.native_source(name).ok_or(Unsupported)?;
+        let GrammarElement::Expr(body) = body else {
+            return Err(Unsupported);
+        };
+        let source = Rc::new(Source {
+            arity: params.len(),
+            body: lower(body, params)?,
+        });
+        self.sources.insert(name.to_string(), source.clone());
+        Ok(source)
+    }
+
+    /// 登记函数特化，返回已知的返回类型 / Register a specialization and return its return type if known
+    fn instance(&mut self, key: Key) -> Result<Option<NumType>, Unsupported> {
+        if let Some((_, ret)) = self.compiled.get(&key) {
+            return Ok(Some(*ret));
+        }
+        if let Some(ret) = self.returns.get(&key) {
+            return Ok(*ret);
+        }
+        if self.source(&key.0)?.arity != key.1.len() || self.instances.len() >= MAX_INSTANCES {
+            return Err(Unsupported);
+        }
+        self.instances.push(key.clone());
+        self.returns.insert(key, None);
+        Ok(None)
+    }
+
+    /// 推断表达式类型；None表示依赖尚未推断出的递归调用
+    /// Infer the type of an expression; None means it depends on a recursive call not inferred yet
+    fn type_of(
+        &mut self,
+        node: &Node,
+        signature: &[NumType],
+    ) -> Result<Option<NumType>, Unsupported> {
+        Ok(match node {
+            Node::Int(_) => Some(NumType::Int),
+            Node::Float(_) => Some(NumType::Float),
+            Node::Bool(_) => Some(NumType::Bool),
+            Node::Param(index) => Some(signature[*index]),
+            Node::Arith(op, left, right) | Node::Compare(op, left, right) => {
+                let left = self.type_of(left, signature)?;
+                let right = self.type_of(right, signature)?;
+                match (left, right) {
+                    (Some(left), Some(right)) if matches!(node, Node::Arith(..)) => {
+                        Some(arith_type(*op, left, right)?)
+                    }
+                    (Some(left), Some(right)) => Some(compare_type(*op, left, right)?),
+                    _ => None,
+                }
+            }
+            Node::If(cond, then_node, else_node) => {
+                self.type_of(cond, signature)?;
+                let then_type = self.type_of(then_node, signature)?;
+                let else_type = self.type_of(else_node, signature)?;
+                match (then_type, else_type) {
+                    (Some(a), Some(b)) if a != b => return Err(Unsupported),
+                    (Some(a), _) | (_, Some(a)) => Some(a),
+                    (None, None) => None,
+                }
+            }
+            Node::Call(name, args) => {
+                let mut arg_types = Vec::with_capacity(args.len());
+                for arg in args {
+                    match self.type_of(arg, signature)? {
+                        Some(NumType::Bool) => return Err(Unsupported),
+                        Some(arg_type) => arg_types.push(arg_type),
+                        None => return Ok(None),
+                    }
+                }
+                self.instance((name.clone(), arg_types))?
+            }
+        })
+    }
+
+    /// 从入口出发推断所有特化的返回类型（迭代到不动点）
+    /// Infer return types of all specializations reachable from the entry (iterating to a fixed point)
+    fn infer(&mut self, entry: &Key) -> Result<(), Unsupported> {
+        self.instance(entry.clone())?;
+        for _ in 0..MAX_PASSES {
+            let mut changed = false;
+            let mut index = 0;
+            while index < self.instances.len() {
+                let key = self.instances[index].clone();
+                let source = self.source(&key.0)?;
+                if let Some(ret) = self.type_of(&source.body, &key.1)? {
+                    match self.returns.insert(key, Some(ret)) {
+                        Some(Some(previous)) if previous != ret => return Err(Unsupported),
+                        Some(Some(_)) => {}
+                        _ => changed = true,
+                    }
+                }
+                index += 1;
+            }
+            if !changed {
+                break;
+            }
+        }
+        if self.returns.values().all(Option::is_some) {
+            Ok(())
+        } else {
+            Err(Unsupported)
+        }
+    }
+}
+
+/// 函数体代码生成 / Function body code generation
+struct Emitter<'a> {
+    module: &'a mut JITModule,
+    functions: &'a HashMap<Key, (FuncId, NumType)>,
+    fmod: FuncId,
+    params: Vec<(ir::Value, NumType)>,
+    context: ir::Value,
+    bail: Block,
+}
+
+impl Emitter<'_> {
+    /// 条件成立时放弃原生执行 / Bail out of native execution when the condition holds
+    fn guard(&self, builder: &mut FunctionBuilder, failed: ir::Value) {
+        let ok = builder.create_block();
+        builder.ins().brif(failed, self.bail, &[], ok, &[]);
+        builder.switch_to_block(ok);
+    }
+
+    fn to_float(&self, builder: &mut FunctionBuilder, value: ir::Value, ty: NumType) -> ir::Value {
+        match ty {
+            NumType::Int => builder.ins().fcvt_from_sint(types::F64, value),
+            _ => value,
+        }
+    }
+
+    fn emit(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        node: &Node,
+    ) -> Result<(ir::Value, NumType), Unsupported> {
+        Ok(match node {
+            Node::Int(i) => (builder.ins().iconst(types::I64, *i), NumType::Int),
+            Node::Float(f) => (builder.ins().f64const(*f), NumType::Float),
+            Node::Bool(b) => (
+                builder.ins().iconst(types::I8, i64::from(*b)),
+                NumType::Bool,
+            ),
+            Node::Param(index) => self.params[*index],
+            Node::Arith(op, left, right) => {
+                let (left, left_type) = self.emit(builder, left)?;
+                let (right, right_type) = self.emit(builder, right)?;
+                let ty = arith_type(*op, left_type, right_type)?;
+                let value = if ty == NumType::Int {
+                    self.int_arith(builder, *op, left, right)
+                } else {
+                    let left = self.to_float(builder, left, left_type);
+                    let right = self.to_float(builder, right, right_type);
+                    self.float_arith(builder, *op, left, right)
+                };
+                (value, ty)
+            }
+            Node::Compare(op, left, right) => {
+                let (left, left_type) = self.emit(builder, left)?;
+                let (right, right_type) = self.emit(builder, right)?;
+                compare_type(*op, left_type, right_type)?;
+                let value = if left_type == NumType::Float {
+                    let cc = match op {
+                        BinOp::Eq => FloatCC::Equal,
+                        BinOp::Ne => FloatCC::NotEqual,
+                        BinOp::Lt => FloatCC::LessThan,
+                        BinOp::Gt => FloatCC::GreaterThan,
+                        BinOp::Le => FloatCC::LessThanOrEqual,
+                        _ => FloatCC::GreaterThanOrEqual,
+                    };
+                    builder.ins().fcmp(cc, left, right)
+                } else {
+                    let cc = match op {
+                        BinOp::Eq => IntCC::Equal,
+                        BinOp::Ne => IntCC::NotEqual,
+                        BinOp::Lt => IntCC::SignedLessThan,
+                        BinOp::Gt => IntCC::SignedGreaterThan,
+                        BinOp::Le => IntCC::SignedLessThanOrEqual,
+                        _ => IntCC::SignedGreaterThanOrEqual,
+                    };
+                    builder.ins().icmp(cc, left, right)
+                };
+                (value, NumType::Bool)
+            }
+            Node::If(cond, then_node, else_node) => {
+                let (cond, cond_type) = self.emit(builder, cond)?;
+                // 真值与解释器一致：非零整数、非零浮点（含NaN）和 true
+                // Truthiness as in the interpreter: non-zero ints, non-zero floats (including NaN) and true
+                let cond = if cond_type == NumType::Float {
+                    let zero = builder.ins().f64const(0.0);
+                    builder.ins().fcmp(FloatCC::NotEqual, cond, zero)
+                } else {
+                    cond
+                };
+                let then_block = builder.create_block();
+                let else_block = builder.create_block();
+                let merge_block = builder.create_block();
+                builder.ins().brif(cond, then_block, &[], else_block, &[]);
+
+                builder.switch_to_block(then_block);
+                let (then_value, ty) = self.emit(builder, then_node)?;
+                builder.ins().jump(merge_block, &[then_value]);
+
+                builder.switch_to_block(else_block);
+                let (else_value, else_type) = self.emit(builder, else_node)?;
+                if else_type != ty {
+                    return Err(Unsupported);
+                }
+                builder.ins().jump(merge_block, &[else_value]);
+
+                builder.switch_to_block(merge_block);
+                (builder.append_block_param(merge_block, ty.clif()), ty)
+            }
+            Node::Call(name, args) => {
+                let mut values = Vec::with_capacity(args.len() + 1);
+                let mut arg_types = Vec::with_capacity(args.len());
+                for arg in args {
+                    let (value, ty) = self.emit(builder, arg)?;
+                    values.push(value);
+                    arg_types.push(ty);
+                }
+                values.push(self.context);
+                let &(id, ret) = self
+                    .functions
+                    .get(&(name.clone(), arg_types))
+                    .ok_or(Unsupported)?;
+                let callee = self.module.declare_func_in_func(id, builder.func);
+                let call = builder.ins().call(callee, &values);
+                let result = builder.inst_results(call)[0];
+                // 被调用者放弃时立即返回 / Return at once when the callee bailed out
+                let bailed = builder.ins().load(
+                    types::I64,
+                    MemFlags::trusted(),
+                    self.context,
+                    BAILOUT_OFFSET,
+                );
+                self.guard(builder, bailed);
+                (result, ret)
+            }
+        })
+    }
+
+    fn int_arith(
+        &self,
+        builder: &mut FunctionBuilder,
+        op: BinOp,
+        left: ir::Value,
+        right: ir::Value,
+    ) -> ir::Value {
+        let checked = match op {
+            BinOp::Add => Some(builder.ins().sadd_overflow(left, right)),
+            BinOp::Sub => Some(builder.ins().ssub_overflow(left, right)),
+            BinOp::Mul => Some(builder.ins().smul_overflow(left, right)),
+            _ => None,
+        };
+        // 溢出交给解释器按溢出模式处理 / Overflow is left to the interpreter's overflow mode
+        if let Some((value, overflowed)) = checked {
+            self.guard(builder, overflowed);
+            return value;
+        }
+        // 除零交给解释器报错 / Division by zero is left to the interpreter to report
+        let zero = builder.ins().icmp_imm(IntCC::Equal, right, 0);
+        self.guard(builder, zero);
+        let minus_one = builder.ins().icmp_imm(IntCC::Equal, right, -1);
+        if op == BinOp::Div {
+            // i64::MIN / -1 溢出 / i64::MIN / -1 overflows
+            let min = builder.ins().icmp_imm(IntCC::Equal, left, i64::MIN);
+            let overflowed = builder.ins().band(min, minus_one);
+            self.guard(builder, overflowed);
+            builder.ins().sdiv(left, right)
+        } else {
+            // x % -1 总是0，换成 x % 1 避免 i64::MIN % -1 陷入
+            // x % -1 is always 0; use x % 1 instead so i64::MIN % -1 does not trap
+            let one = builder.ins().iconst(types::I64, 1);
+            let divisor = builder.ins().select(minus_one, one, right);
+            builder.ins().srem(left, divisor)
+        }
+    }
+
+    fn float_arith(
+        &mut self,
+        builder: &mut FunctionBuilder,
+        op: BinOp,
+        left: ir::Value,
+        right: ir::Value,
+    ) -> ir::Value {
+        match op {
+            BinOp::Add => builder.ins().fadd(left, right),
+            BinOp::Sub => builder.ins().fsub(left, right),
+            BinOp::Mul => builder.ins().fmul(left, right),
+            _ => {
+                let zero = builder.ins().f64const(0.0);
+                let is_zero = builder.ins().fcmp(FloatCC::Equal, right, zero);
+                self.guard(builder, is_zero);
+                if op == BinOp::Div {
+                    builder.ins().fdiv(left, right)
+                } else {
+                    let fmod = self.module.declare_func_in_func(self.fmod, builder.func);
+                    let call = builder.ins().call(fmod, &[left, right]);
+                    builder.inst_results(call)[0]
+                }
+            }
+        }
+    }
+}
+
+/// 已编译的入口 / Compiled entry
+#[derive(Clone, Copy)]
+struct Entry {
+    function: EntryFn,
+    ret: NumType,
+}
+
+/// Cranelift原生代码后端：函数特化被调用达到阈值次数后编译
+/// Cranelift native code backend: a function specialization is compiled once it has been called the threshold number of times
+pub(crate) struct CraneliftBackend {
+    module: JITModule,
+    context: Context,
+    builder_context: FunctionBuilderContext,
+    fmod: FuncId,
+    threshold: usize,
+    /// 编译时的函数表版本 / Function table generation the code was compiled against
+    generation: u64,
+    calls: HashMap<Key, usize>,
+    compiled: HashMap<Key, (FuncId, NumType)>,
+    entries: HashMap<Key, Entry>,
+    rejected: HashSet<Key>,
+}
+
+// JITModule 只持有自己分配的代码内存，没有线程亲和性
+// JITModule only holds code memory it allocated itself and has no thread affinity
+unsafe impl Send for CraneliftBackend {}
+
+impl CraneliftBackend {
+    /// 为本机创建后端；平台不受支持时返回 None / Create a backend for the host; None if the platform is unsupported
+    pub(crate) fn with_threshold(threshold: usize) -> Option<Self> {
+        let (module, fmod) = Self::jit_module()?;
+        let context = module.make_context();
+        Some(Self {
+            module,
+            context,
+            builder_context: FunctionBuilderContext::new(),
+            fmod,
+            threshold,
+            generation: 0,
+            calls: HashMap::new(),
+            compiled: HashMap::new(),
+            entries: HashMap::new(),
+            rejected: HashSet::new(),
+        })
+    }
+
+    fn jit_module() -> Option<(JITModule, FuncId)> {
+        let mut flags = settings::builder();
+        flags.set("opt_level", "speed").ok()?;
+        flags.set("use_colocated_libcalls", "false").ok()?;
+        flags.set("is_pic", "false").ok()?;
+        let isa = cranelift_native::builder()
+            .ok()?
+            .finish(settings::Flags::new(flags))
+            .ok()?;
+        let mut builder = JITBuilder::with_isa(isa, default_libcall_names());
+        builder.symbol("evo_fmod", evo_fmod as *const u8);
+        let mut module = JITModule::new(builder);
+
+        let mut signature = module.make_signature();
+        signature.params.push(AbiParam::new(types::F64));
+        signature.params.push(AbiParam::new(types::F64));
+        signature.returns.push(AbiParam::new(types::F64));
+        let fmod = module
+            .declare_function("evo_fmod", Linkage::Import, &signature)
+            .ok()?;
+        Some((module, fmod))
+    }
+
+    /// 函数表变化后丢弃已编译的代码（代码内存不回收）
+    /// Drop compiled code after the function table changed (the code memory is not reclaimed)
+    fn invalidate(&mut self, generation: u64) {
+        self.generation = generation;
+        self.calls.clear();
+        self.compiled.clear();
+        self.entries.clear();
+        self.rejected.clear();
+    }
+
+    /// 取得入口，调用次数达到阈值时编译 / Get the entry, compiling it once the call count reaches the threshold
+    fn entry(&mut self, interpreter: &Interpreter, key: &Key) -> Option<Entry> {
+        if let Some(entry) = self.entries.get(key) {
+            return Some(*entry);
+        }
+        if self.rejected.contains(key) {
+            return None;
+        }
+        let calls = self.calls.entry(key.clone()).or_insert(0);
+        *calls += 1;
+        if *calls < self.threshold {
+            return None;
+        }
+        self.calls.remove(key);
+        match self.compile(interpreter, key) {
+            Ok(entry) => {
+                self.entries.insert(key.clone(), entry);
+                Some(entry)
+            }
+            Err(Unsupported) => {
+                self.rejected.insert(key.clone());
+                None
+            }
+        }
+    }
+
+    /// 编译入口及其可达的所有特化 / Compile the entry and every specialization reachable from it
+    fn compile(&mut self, interpreter: &Interpreter, key: &Key) -> Result<Entry, Unsupported> {
+        let mut program = Program::new(interpreter, &self.compiled);
+        program.infer(key)?;
+        let (sources, instances, returns) = (program.sources, program.instances, program.returns);
+
+        let mut functions = self.compiled.clone();
+        for instance in &instances {
+            let ret = returns[instance].ok_or(Unsupported)?;
+            let signature = self.signature(&instance.1, ret);
+            let id = self
+                .module
+                .declare_anonymous_function(&signature)
+                .map_err(|_| Unsupported)?;
+            functions.insert(instance.clone(), (id, ret));
+        }
+
+        let defined = instances
+            .iter()
+            .try_for_each(|instance| self.define(&functions, instance, &sources[&instance.0].body));
+        let entry = defined.and_then(|_| self.define_entry(&functions, key));
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(Unsupported) => {
+                // 声明了却未定义的函数会让后续链接失败，换一个新模块
+                // Declared but undefined functions would break later linking, so start a fresh module
+                self.module.clear_context(&mut self.context);
+                if let Some((module, fmod)) = Self::jit_module() {
+                    self.module = module;
+                    self.fmod = fmod;
+                    self.compiled.clear();
+                    self.entries.clear();
+                }
+                return Err(Unsupported);
+            }
+        };
+        self.module
+            .finalize_definitions()
+            .map_err(|_| Unsupported)?;
+        for instance in instances {
+            let compiled = functions[&instance];
+            self.compiled.insert(instance, compiled);
+        }
+        let code = self.module.get_finalized_function(entry);
+        Ok(Entry {
+            // SAFETY: 入口按 EntryFn 的签名生成 / The entry was generated with the EntryFn signature
+            function: unsafe { std::mem::transmute::<*const u8, EntryFn>(code) },
+            ret: functions[key].1,
+        })
+    }
+
+    fn signature(&self, params: &[NumType], ret: NumType) -> ir::Signature {
+        let pointer = self.module.target_config().pointer_type();
+        let mut signature = self.module.make_signature();
+        for param in params {
+            signature.params.push(AbiParam::new(param.clif()));
+        }
+        signature.params.push(AbiParam::new(pointer));
+        signature.returns.push(AbiParam::new(ret.clif()));
+        signature
+    }
+
+    /// 定义一个函数特化 / Define one function specialization
+    fn define(
+        &mut self,
+        functions: &HashMap<Key, (FuncId, NumType)>,
+        key: &Key,
+        body: &Node,
+    ) -> Result<(), Unsupported> {
+        let &(id, ret) = functions.get(key).ok_or(Unsupported)?;
+        let params = &key.1;
+        self.context.func.signature = self.signature(params, ret);
+        let mut builder = FunctionBuilder::new(&mut self.context.func, &mut self.builder_context);
+        let entry = builder.create_block();
+        builder.append_block_params_for_function_params(entry);
+        builder.switch_to_block(entry);
+        let values = builder.block_params(entry).to_vec();
+        let context = values[params.len()];
+        let bail = builder.create_block();
+
+        let mut emitter = Emitter {
+            module: &mut self.module,
+            functions,
+            fmod: self.fmod,
+            params: values.iter().copied().zip(params.iter().copied()).collect(),
+            context,
+            bail,
+        };
+
+        // 每次调用消耗一步，并检查调用深度 / Each call uses one step and checks the call depth
+        let flags = MemFlags::trusted();
+        let fuel = builder.ins().load(types::I64, flags, context, FUEL_OFFSET);
+        let fuel = builder.ins().iadd_imm(fuel, -1);
+        builder.ins().store(flags, fuel, context, FUEL_OFFSET);
+        let exhausted = builder.ins().icmp_imm(IntCC::SignedLessThan, fuel, 0);
+        emitter.guard(&mut builder, exhausted);
+        let depth = builder.ins().load(types::I64, flags, context, DEPTH_OFFSET);
+        let depth = builder.ins().iadd_imm(depth, 1);
+        builder.ins().store(flags, depth, context, DEPTH_OFFSET);
+        let too_deep = builder
+            .ins()
+            .icmp_imm(IntCC::SignedGreaterThan, depth, MAX_NATIVE_DEPTH);
+        emitter.guard(&mut builder, too_deep);
+
+        let result = emitter.emit(&mut builder, body);
+        let (value, _) = match result {
+            Ok(result) => result,
+            Err(e) => {
+                self.module.clear_context(&mut self.context);
+                return Err(e);
+            }
+        };
+        let depth = builder.ins().load(types::I64, flags, context, DEPTH_OFFSET);
+        let depth = builder.ins().iadd_imm(depth, -1);
+        builder.ins().store(flags, depth, context, DEPTH_OFFSET);
+        builder.ins().return_(&[value]);
+
+        builder.switch_to_block(bail);
+        let one = builder.ins().iconst(types::I64, 1);
+        builder.ins().store(flags, one, context, BAILOUT_OFFSET);
+        let zero = match ret {
+            NumType::Float => builder.ins().f64const(0.0),
+            _ => builder.ins().iconst(ret.clif(), 0),
+        };
+        builder.ins().return_(&[zero]);
+
+        builder.seal_all_blocks();
+        builder.finalize();
+        let defined = self.module.define_function(id, &mut self.context);
+        self.module.clear_context(&mut self.context);
+        defined.map_err(|_| Unsupported)
+    }
+
+    /// 定义入口跳板：从数组读取参数，调用特化并把结果转为位模式
+    /// Define the entry trampoline: read arguments from an array, call the specialization and return the result as bits
+    fn define_entry(
+        &mut self,
+        functions: &HashMap<Key, (FuncId, NumType)>,
+        key: &Key,
+    ) -> Result<FuncId, Unsupported> {
+        let &(target, ret) = functions.get(key).ok_or(Unsupported)?;
+        let pointer = self.module.target_config().pointer_type();
+        let mut signature = self.module.make_signature();
+        signature.params.push(AbiParam::new(pointer));
+        signature.params.push(AbiParam::new(pointer));
+        signature.returns.push(AbiParam::new(types::I64));
+        let id = self
+            .module
+            .declare_anonymous_function(&signature)
+            .map_err(|_| Unsupported)?;
+
+        self.context.func.signature = signature;
+        let mut builder = FunctionBuilder::new(&mut self.context.func, &mut self.builder_context);
+        let block = builder.create_block();
+        builder.append_block_params_for_function_params(block);
+        builder.switch_to_block(block);
+        let (args, context) = (
+            builder.block_params(block)[0],
+            builder.block_params(block)[1],
+        );
+        let mut values = Vec::with_capacity(key.1.len() + 1);
+        for (index, param) in key.1.iter().enumerate() {
+            let offset = (index * 8) as i32;
+            values.push(
+                builder
+                    .ins()
+                    .load(param.clif(), MemFlags::trusted(), args, offset),
+            );
+        }
+        values.push(context);
+        let callee = self.module.declare_func_in_func(target, builder.func);
+        let call = builder.ins().call(callee, &values);
+        let result = builder.inst_results(call)[0];
+        let bits = match ret {
+            NumType::Int => result,
+            NumType::Float => builder.ins().bitcast(types::I64, MemFlags::new(), result),
+            NumType::Bool => builder.ins().uextend(types::I64, result),
+        };
+        builder.ins().return_(&[bits]);
+        builder.seal_all_blocks();
+        builder.finalize();
+
+        let defined = self.module.define_function(id, &mut self.context);
+        self.module.clear_context(&mut self.context);
+        defined.map(|_| id).map_err(|_| Unsupported)
+    }
+}
+
+impl NativeBackend for CraneliftBackend {
+    fn call(
+        &mut self,
+        interpreter: &Interpreter,
+        name: &str,
+        args: &[Value],
+        fuel: Option<u64>,
+    ) -> Option<(Value, u64)> {
+        if interpreter.function_generation() != self.generation {
+            self.invalidate(interpreter.function_generation());
+        }
+        let signature = args.iter().map(NumType::of).collect::<Option<Vec<_>>>()?;
+        let entry = self.entry(interpreter, &(name.to_string(), signature))?;
+
+        let bits: Vec<u64> = args
+            .iter()
+            .map(|arg| match arg {
+                Value::Float(f) => f.to_bits(),
+                Value::Int(i) => *i as u64,
+                _ => 0,
+            })
+            .collect();
+        let budget = fuel.map_or(i64::MAX, |fuel| fuel.min(i64::MAX as u64) as i64);
+        let mut context = NativeContext {
+            bailout: 0,
+            fuel: budget,
+            depth: 0,
+        };
+        // SAFETY: 参数个数和类型与入口特化的签名一致 / The arguments match the specialization's signature in number and type
+        let result = unsafe { (entry.function)(bits.as_ptr(), &mut context) };
+        if context.bailout != 0 {
+            return None;
+        }
+        let value = match entry.ret {
+            NumType::Int => Value::Int(result as i64),
+            NumType::Float => Value::Float(f64::from_bits(result)),
+            NumType::Bool => Value::Bool(result != 0),
+        };
+        Some((value, (budget - context.fuel) as u64))
+    }
+
+    fn compiled_functions(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .compiled
+            .keys()
+            .map(|(name, params)| {
+                let params: Vec<String> = params.iter().map(ToString::to_string).collect();
+                format!("{}({})", name, params.join(", "))
+            })
+            .collect();
+        names.sort();
+        names
+    }
+}
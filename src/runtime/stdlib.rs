@@ -0,0 +1,489 @@
+// 原生标准库模块 / Native standard library modules
+// 用 Rust 实现的内置模块，无需文件系统即可导入
+// Built-in modules implemented in Rust, importable without any filesystem dependency
+
+use super::interpreter::{Interpreter, InterpreterError, Value};
+use num_traits::ToPrimitive;
+use std::collections::HashMap;
+
+/// 原生函数签名 / Native function signature
+pub type NativeFunction = fn(&mut Interpreter, &[Value]) -> Result<Value, InterpreterError>;
+
+/// 原生模块 / Native module
+#[derive(Debug, Clone, Default)]
+pub struct NativeModule {
+    /// 模块函数 / Module functions
+    pub functions: HashMap<&'static str, NativeFunction>,
+    /// 模块常量 / Module constants
+    pub constants: Vec<(&'static str, Value)>,
+}
+
+impl NativeModule {
+    fn with_functions(functions: &[(&'static str, NativeFunction)]) -> Self {
+        Self {
+            functions: functions.iter().copied().collect(),
+            constants: Vec::new(),
+        }
+    }
+}
+
+/// 创建所有原生标准库模块 / Create all native standard library modules
+pub fn native_modules() -> HashMap<String, NativeModule> {
+    let mut modules = HashMap::new();
+    modules.insert("math".to_string(), math_module());
+    modules.insert("random".to_string(), random_module());
+    modules.insert("time".to_string(), time_module());
+    modules.insert("string".to_string(), string_module());
+    modules
+}
+
+// ========================================
+// 参数辅助函数 / Argument helpers
+// ========================================
+
+fn expect_args(name: &str, args: &[Value], count: usize) -> Result<(), InterpreterError> {
+    if args.len() != count {
+        return Err(InterpreterError::runtime_error(
+            format!(
+                "{} requires {} argument(s), got {}",
+                name,
+                count,
+                args.len()
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+fn number_arg(name: &str, value: &Value) -> Result<f64, InterpreterError> {
+    match value {
+        Value::Int(i) => Ok(*i as f64),
+        Value::Float(f) => Ok(*f),
+        Value::BigInt(n) => Ok(n.to_f64().unwrap_or(f64::NAN)),
+        _ => Err(InterpreterError::type_error(
+            format!("{} requires numeric arguments", name),
+            None,
+        )),
+    }
+}
+
+fn int_arg(name: &str, value: &Value) -> Result<i64, InterpreterError> {
+    match value {
+        Value::Int(i) => Ok(*i),
+        _ => Err(InterpreterError::type_error(
+            format!("{} requires integer arguments", name),
+            None,
+        )),
+    }
+}
+
+fn string_arg<'a>(name: &str, value: &'a Value) -> Result<&'a str, InterpreterError> {
+    match value {
+        Value::String(s) => Ok(s),
+        _ => Err(InterpreterError::type_error(
+            format!("{} requires string arguments", name),
+            None,
+        )),
+    }
+}
+
+/// 浮点结果为整数时返回Int / Return Int when a float result is integral
+fn integral_value(f: f64) -> Value {
+    if f.is_finite() && f.fract() == 0.0 && f.abs() < i64::MAX as f64 {
+        Value::Int(f as i64)
+    } else {
+        Value::Float(f)
+    }
+}
+
+// ========================================
+// math 模块 / math module
+// ========================================
+
+fn math_module() -> NativeModule {
+    let mut module = NativeModule::with_functions(&[
+        ("sqrt", math_sqrt),
+        ("pow", math_pow),
+        ("abs", math_abs),
+        ("floor", math_floor),
+        ("ceil", math_ceil),
+        ("round", math_round),
+        ("sin", math_sin),
+        ("cos", math_cos),
+        ("tan", math_tan),
+        ("exp", math_exp),
+        ("ln", math_ln),
+        ("log", math_log),
+        ("min", math_min),
+        ("max", math_max),
+    ]);
+    module.constants = vec![
+        ("pi", Value::Float(std::f64::consts::PI)),
+        ("e", Value::Float(std::f64::consts::E)),
+    ];
+    module
+}
+
+fn unary_math(name: &str, args: &[Value], f: fn(f64) -> f64) -> Result<Value, InterpreterError> {
+    expect_args(name, args, 1)?;
+    Ok(Value::Float(f(number_arg(name, &args[0])?)))
+}
+
+fn math_sqrt(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("math.sqrt", args, 1)?;
+    let x = number_arg("math.sqrt", &args[0])?;
+    if x < 0.0 {
+        return Err(InterpreterError::runtime_error(
+            "math.sqrt requires a non-negative number".to_string(),
+            None,
+        ));
+    }
+    Ok(Value::Float(x.sqrt()))
+}
+
+fn math_pow(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("math.pow", args, 2)?;
+    match (&args[0], &args[1]) {
+        (Value::Int(base), Value::Int(exp)) if *exp >= 0 => {
+            let result = u32::try_from(*exp)
+                .ok()
+                .and_then(|exp| base.checked_pow(exp));
+            match result {
+                Some(n) => Ok(Value::Int(n)),
+                None => Ok(Value::from_bigint(num_traits::pow(
+                    num_bigint::BigInt::from(*base),
+                    *exp as usize,
+                ))),
+            }
+        }
+        (base, exp) => Ok(Value::Float(
+            number_arg("math.pow", base)?.powf(number_arg("math.pow", exp)?),
+        )),
+    }
+}
+
+fn math_abs(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("math.abs", args, 1)?;
+    match &args[0] {
+        Value::Int(i) => Ok(i
+            .checked_abs()
+            .map(Value::Int)
+            .unwrap_or_else(|| Value::from_bigint(-num_bigint::BigInt::from(*i)))),
+        Value::BigInt(n) => Ok(Value::from_bigint(num_traits::Signed::abs(n))),
+        other => Ok(Value::Float(number_arg("math.abs", other)?.abs())),
+    }
+}
+
+fn math_floor(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("math.floor", args, 1)?;
+    Ok(integral_value(number_arg("math.floor", &args[0])?.floor()))
+}
+
+fn math_ceil(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("math.ceil", args, 1)?;
+    Ok(integral_value(number_arg("math.ceil", &args[0])?.ceil()))
+}
+
+fn math_round(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("math.round", args, 1)?;
+    Ok(integral_value(number_arg("math.round", &args[0])?.round()))
+}
+
+fn math_sin(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    unary_math("math.sin", args, f64::sin)
+}
+
+fn math_cos(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    unary_math("math.cos", args, f64::cos)
+}
+
+fn math_tan(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    unary_math("math.tan", args, f64::tan)
+}
+
+fn math_exp(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    unary_math("math.exp", args, f64::exp)
+}
+
+fn math_ln(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    unary_math("math.ln", args, f64::ln)
+}
+
+/// 对数：(math.log x) 为自然对数，(math.log x base) 为任意底
+/// Logarithm: (math.log x) is natural log, (math.log x base) uses the given base
+fn math_log(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    match args {
+        [x] => Ok(Value::Float(number_arg("math.log", x)?.ln())),
+        [x, base] => Ok(Value::Float(
+            number_arg("math.log", x)?.log(number_arg("math.log", base)?),
+        )),
+        _ => Err(InterpreterError::runtime_error(
+            "math.log requires 1 or 2 arguments: x [base]".to_string(),
+            None,
+        )),
+    }
+}
+
+fn math_extreme(
+    name: &str,
+    args: &[Value],
+    pick_first: fn(f64, f64) -> bool,
+) -> Result<Value, InterpreterError> {
+    let mut best: Option<(&Value, f64)> = None;
+    for arg in args {
+        let x = number_arg(name, arg)?;
+        if best.is_none_or(|(_, b)| pick_first(x, b)) {
+            best = Some((arg, x));
+        }
+    }
+    best.map(|(value, _)| value.clone()).ok_or_else(|| {
+        InterpreterError::runtime_error(format!("{} requires at least 1 argument", name), None)
+    })
+}
+
+fn math_min(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    math_extreme("math.min", args, |x, best| x < best)
+}
+
+fn math_max(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    math_extreme("math.max", args, |x, best| x > best)
+}
+
+// ========================================
+// random 模块 / random module
+// ========================================
+
+fn random_module() -> NativeModule {
+    NativeModule::with_functions(&[
+        ("int", random_int),
+        ("float", random_float),
+        ("choice", random_choice),
+        ("seed", random_seed),
+    ])
+}
+
+/// 随机整数（闭区间）/ Random integer (inclusive range)
+fn random_int(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("random.int", args, 2)?;
+    let low = int_arg("random.int", &args[0])?;
+    let high = int_arg("random.int", &args[1])?;
+    if low > high {
+        return Err(InterpreterError::runtime_error(
+            format!("random.int: empty range {}..={}", low, high),
+            None,
+        ));
+    }
+    let span = (high as i128 - low as i128 + 1) as u128;
+    let offset = (interpreter.next_random() as u128 % span) as i128;
+    Ok(Value::Int((low as i128 + offset) as i64))
+}
+
+/// [0, 1) 之间的随机浮点数 / Random float in [0, 1)
+fn random_float(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("random.float", args, 0)?;
+    Ok(Value::Float(
+        (interpreter.next_random() >> 11) as f64 / (1u64 << 53) as f64,
+    ))
+}
+
+fn random_choice(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("random.choice", args, 1)?;
+    match &args[0] {
+        Value::List(items) if !items.is_empty() => {
+            let index = (interpreter.next_random() % items.len() as u64) as usize;
+            Ok(items[index].clone())
+        }
+        Value::List(_) => Err(InterpreterError::runtime_error(
+            "random.choice requires a non-empty list".to_string(),
+            None,
+        )),
+        _ => Err(InterpreterError::type_error(
+            "random.choice requires a list".to_string(),
+            None,
+        )),
+    }
+}
+
+fn random_seed(interpreter: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("random.seed", args, 1)?;
+    interpreter.seed_random(int_arg("random.seed", &args[0])? as u64);
+    Ok(Value::Null)
+}
+
+// ========================================
+// time 模块 / time module
+// ========================================
+
+fn time_module() -> NativeModule {
+    NativeModule::with_functions(&[
+        ("now", time_now),
+        ("millis", time_millis),
+        ("sleep", time_sleep),
+    ])
+}
+
+/// 当前Unix时间戳（秒，浮点）/ Current Unix timestamp (seconds, float)
+fn time_now(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("time.now", args, 0)?;
+    Ok(Value::Float(
+        chrono::Utc::now().timestamp_micros() as f64 / 1_000_000.0,
+    ))
+}
+
+/// 当前Unix时间戳（毫秒）/ Current Unix timestamp (milliseconds)
+fn time_millis(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("time.millis", args, 0)?;
+    Ok(Value::Int(chrono::Utc::now().timestamp_millis()))
+}
+
+/// 休眠指定毫秒数 / Sleep for the given number of milliseconds
+fn time_sleep(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("time.sleep", args, 1)?;
+    let millis = number_arg("time.sleep", &args[0])?;
+    if millis > 0.0 {
+        std::thread::sleep(std::time::Duration::from_secs_f64(millis / 1000.0));
+    }
+    Ok(Value::Null)
+}
+
+// ========================================
+// string 模块 / string module
+// ========================================
+
+fn string_module() -> NativeModule {
+    NativeModule::with_functions(&[
+        ("upper", string_upper),
+        ("lower", string_lower),
+        ("trim", string_trim),
+        ("length", string_length),
+        ("reverse", string_reverse),
+        ("split", string_split),
+        ("join", string_join),
+        ("replace", string_replace),
+        ("contains", string_contains),
+        ("starts-with", string_starts_with),
+        ("ends-with", string_ends_with),
+        ("repeat", string_repeat),
+    ])
+}
+
+fn string_upper(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.upper", args, 1)?;
+    Ok(Value::String(
+        string_arg("string.upper", &args[0])?.to_uppercase().into(),
+    ))
+}
+
+fn string_lower(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.lower", args, 1)?;
+    Ok(Value::String(
+        string_arg("string.lower", &args[0])?.to_lowercase().into(),
+    ))
+}
+
+fn string_trim(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.trim", args, 1)?;
+    Ok(Value::String(
+        string_arg("string.trim", &args[0])?.trim().into(),
+    ))
+}
+
+/// 字符数（非字节数）/ Character count (not bytes)
+fn string_length(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.length", args, 1)?;
+    Ok(Value::Int(
+        string_arg("string.length", &args[0])?.chars().count() as i64,
+    ))
+}
+
+fn string_reverse(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.reverse", args, 1)?;
+    Ok(Value::String(
+        string_arg("string.reverse", &args[0])?
+            .chars()
+            .rev()
+            .collect::<String>()
+            .into(),
+    ))
+}
+
+fn string_split(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.split", args, 2)?;
+    let s = string_arg("string.split", &args[0])?;
+    let separator = string_arg("string.split", &args[1])?;
+    let parts: Vec<Value> = if separator.is_empty() {
+        s.chars()
+            .map(|c| Value::String(c.to_string().into()))
+            .collect()
+    } else {
+        s.split(separator)
+            .map(|part| Value::String(part.into()))
+            .collect()
+    };
+    Ok(Value::List(parts.into()))
+}
+
+fn string_join(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.join", args, 2)?;
+    let separator = string_arg("string.join", &args[1])?;
+    match &args[0] {
+        Value::List(items) => Ok(Value::String(
+            items
+                .iter()
+                .map(|item| item.to_string())
+                .collect::<Vec<_>>()
+                .join(separator)
+                .into(),
+        )),
+        _ => Err(InterpreterError::type_error(
+            "string.join requires a list as first argument".to_string(),
+            None,
+        )),
+    }
+}
+
+fn string_replace(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.replace", args, 3)?;
+    let s = string_arg("string.replace", &args[0])?;
+    let from = string_arg("string.replace", &args[1])?;
+    let to = string_arg("string.replace", &args[2])?;
+    Ok(Value::String(s.replace(from, to).into()))
+}
+
+fn string_contains(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.contains", args, 2)?;
+    let s = string_arg("string.contains", &args[0])?;
+    Ok(Value::Bool(
+        s.contains(string_arg("string.contains", &args[1])?),
+    ))
+}
+
+fn string_starts_with(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.starts-with", args, 2)?;
+    let s = string_arg("string.starts-with", &args[0])?;
+    Ok(Value::Bool(
+        s.starts_with(string_arg("string.starts-with", &args[1])?),
+    ))
+}
+
+fn string_ends_with(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.ends-with", args, 2)?;
+    let s = string_arg("string.ends-with", &args[0])?;
+    Ok(Value::Bool(
+        s.ends_with(string_arg("string.ends-with", &args[1])?),
+    ))
+}
+
+fn string_repeat(_: &mut Interpreter, args: &[Value]) -> Result<Value, InterpreterError> {
+    expect_args("string.repeat", args, 2)?;
+    let s = string_arg("string.repeat", &args[0])?;
+    let count = int_arg("string.repeat", &args[1])?;
+    if count < 0 {
+        return Err(InterpreterError::runtime_error(
+            "string.repeat requires a non-negative count".to_string(),
+            None,
+        ));
+    }
+    Ok(Value::String(s.repeat(count as usize).into()))
+}
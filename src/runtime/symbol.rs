@@ -0,0 +1,230 @@
+// 符号驻留 / Symbol interning
+// 标识符在解析时驻留为共享的名称，AST、环境和函数表都直接保存符号；比较和哈希只看指针，求值时无需再查符号表。
+// 符号表只弱持有名称：不再被任何符号引用的名称会在表增长时清除，符号表的大小由仍在使用的名称决定
+// Identifiers are interned into shared names at parse time, and the AST, environments and function tables hold the
+// symbols themselves; comparing and hashing only look at the pointer, so evaluation never consults the table. The
+// table only holds names weakly: names no symbol refers to any more are purged as the table grows, so its size is
+// bounded by the names still in use
+
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// 符号表至少增长到这么多名称才清除未使用的名称 / Purge unused names only once the table has grown to this many
+const MIN_PURGE_SIZE: usize = 1024;
+
+/// 驻留的标识符 / Interned identifier
+///
+/// 同一名称在进程内同时只对应一个符号，因此不同解释器（包括模块解释器和调试线程）之间可以直接交换以符号为键的表；
+/// 克隆只增加引用计数，序列化为名称字符串，快照格式与字符串键相同
+/// At any time a name maps to a single symbol within the process, so symbol-keyed tables can be exchanged between
+/// interpreters (including module interpreters and the debugger thread); cloning only bumps a reference count, and
+/// symbols serialize as the name, so snapshots look the same as with string keys
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    /// 驻留名称 / Intern a name
+    pub fn intern(name: &str) -> Self {
+        if let Some(symbol) = Self::lookup(name) {
+            return symbol;
+        }
+        table()
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .intern(name)
+    }
+
+    /// 查找已驻留的名称，不存在时不驻留（未驻留的名称不可能是任何表的键）
+    /// Look up an already interned name without interning it (a name never interned cannot be a key of any table)
+    pub fn lookup(name: &str) -> Option<Self> {
+        table().read().unwrap_or_else(|e| e.into_inner()).get(name)
+    }
+
+    /// 符号的名称 / Name of the symbol
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const u8 as usize).hash(state);
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Self::intern(name)
+    }
+}
+
+impl From<&String> for Symbol {
+    fn from(name: &String) -> Self {
+        Self::intern(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Self {
+        Self::intern(&name)
+    }
+}
+
+impl From<&Symbol> for Symbol {
+    fn from(symbol: &Symbol) -> Self {
+        symbol.clone()
+    }
+}
+
+impl From<Symbol> for String {
+    fn from(symbol: Symbol) -> Self {
+        symbol.as_str().to_string()
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = std::borrow::Cow::<'de, str>::deserialize(deserializer)?;
+        Ok(Self::intern(&name))
+    }
+}
+
+/// 符号表：名称到共享名称的映射。表只在没有其他引用时才是名称的唯一持有者，
+/// 这样的名称在表的大小翻倍时清除，因此表的大小不超过仍在使用的名称数的两倍（至少 `MIN_PURGE_SIZE`）
+/// Symbol table: maps names to their shared copy. A name the table alone still holds is unused, and unused names are
+/// purged whenever the table doubles in size, so it never exceeds twice the names in use (at least `MIN_PURGE_SIZE`)
+#[derive(Debug)]
+pub struct SymbolTable {
+    names: HashSet<Arc<str>>,
+    purge_at: usize,
+}
+
+impl Default for SymbolTable {
+    fn default() -> Self {
+        Self {
+            names: HashSet::new(),
+            purge_at: MIN_PURGE_SIZE,
+        }
+    }
+}
+
+impl SymbolTable {
+    /// 驻留名称 / Intern a name
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.get(name) {
+            return symbol;
+        }
+        if self.names.len() >= self.purge_at {
+            self.purge();
+        }
+        let name: Arc<str> = Arc::from(name);
+        self.names.insert(name.clone());
+        Symbol(name)
+    }
+
+    /// 查找已驻留的名称 / Look up an interned name
+    pub fn get(&self, name: &str) -> Option<Symbol> {
+        self.names.get(name).cloned().map(Symbol)
+    }
+
+    /// 清除只剩符号表持有的名称 / Purge names held by nothing but the table
+    ///
+    /// 计数为1时没有任何符号引用该名称，而新的引用只能在持有表的写锁时从表中取得，因此清除是安全的
+    /// A count of one means no symbol refers to the name, and new references can only be taken from the table while
+    /// holding its write lock, so purging is safe
+    pub fn purge(&mut self) {
+        self.names.retain(|name| Arc::strong_count(name) > 1);
+        self.purge_at = (self.names.len() * 2).max(MIN_PURGE_SIZE);
+    }
+
+    /// 已驻留的名称数（含尚未清除的未使用名称）/ Number of interned names (including unused ones not yet purged)
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// 是否为空 / Whether empty
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+}
+
+/// 进程级符号表 / Process-wide symbol table
+fn table() -> &'static RwLock<SymbolTable> {
+    static TABLE: OnceLock<RwLock<SymbolTable>> = OnceLock::new();
+    TABLE.get_or_init(|| RwLock::new(SymbolTable::default()))
+}
@@ -0,0 +1,272 @@
+// 执行追踪与性能剖析 / Execution tracing and profiling
+// 解释器在每次函数调用的进入和退出时通知追踪器，Profiler 汇总调用次数与耗时
+// The interpreter notifies the tracer on entry to and exit from every function call; Profiler aggregates call counts and timings
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::interpreter::{DictMap, Interpreter, InterpreterError, Value};
+
+/// 执行追踪器：通过 `Interpreter::set_tracer` 安装 / Execution tracer, installed with `Interpreter::set_tracer`
+pub trait Tracer: Send {
+    /// 进入函数调用，`depth` 为调用前的嵌套深度 / Entering a function call; `depth` is the nesting depth before the call
+    fn enter(&mut self, name: &str, depth: usize);
+
+    /// 离开函数调用，附带耗时和结果 / Leaving a function call, with its duration and result
+    fn exit(
+        &mut self,
+        name: &str,
+        depth: usize,
+        elapsed: Duration,
+        result: Result<&Value, &InterpreterError>,
+    );
+}
+
+/// 调试暂停点 / Debug pause point
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DebugEvent {
+    /// 即将执行第 `index` 个顶层形式 / About to execute top-level form `index`
+    Form { index: usize },
+    /// 进入函数，参数已绑定 / Entered a function, with its parameters bound
+    Call { name: String },
+    /// 即将执行 `begin` 块中的一条语句；`probe` 为包住语句的覆盖率探针（插桩代码中），用于确定语句所在的行
+    /// About to execute a statement of a `begin` block; `probe` is the coverage probe wrapping the statement (in
+    /// instrumented code), which locates its line
+    Statement { probe: Option<usize> },
+    /// 即将求值带覆盖率探针 `id` 的表达式（调试会话插桩函数体以支持行断点）
+    /// About to evaluate the expression with coverage probe `id` (debug sessions instrument function bodies to
+    /// support line breakpoints)
+    Probe { id: usize },
+}
+
+/// 调试钩子：在每个暂停点调用，可检查和修改解释器状态；返回错误会中止执行
+/// Debug hook: called at every pause point and may inspect and modify the interpreter; returning an error aborts execution
+pub trait DebugHook: Send {
+    /// `depth` 为当前的函数调用深度（顶层为0）/ `depth` is the current function call depth (0 at top level)
+    fn on_event(
+        &mut self,
+        interpreter: &mut Interpreter,
+        event: &DebugEvent,
+        depth: usize,
+    ) -> Result<(), InterpreterError>;
+}
+
+/// 使用情况观察者：通过 `Interpreter::set_usage_observer` 或 `Interpreter::with_learning` 安装，
+/// 每个顶层形式执行后收到它执行过的构造种类，以及成功或未捕获的错误
+/// Usage observer, installed with `Interpreter::set_usage_observer` or `Interpreter::with_learning`; after every
+/// top-level form it receives the construct kinds the form executed and its success or uncaught error
+pub trait UsageObserver: Send {
+    /// 顶层形式执行过的构造种类（如 `def`、`if`、`call`），按名称排序，每种一次
+    /// Construct kinds the top-level form executed (such as `def`, `if`, `call`), sorted by name, once each
+    fn constructs(&mut self, kinds: &[&str]);
+
+    /// 顶层形式执行成功，`pattern` 为以 `+` 连接的构造种类 / The form succeeded; `pattern` joins its construct kinds with `+`
+    fn success(&mut self, pattern: &str, code: &str);
+
+    /// 顶层形式以未捕获的错误结束 / The form ended with an uncaught error
+    fn error(&mut self, kind: &str, message: &str, code: &str);
+
+    /// 未捕获的错误发生在该用户函数中（调用栈最内层）/ The uncaught error happened in this user function (innermost on the call stack)
+    fn failed_in(&mut self, _function: &str) {}
+}
+
+/// 计时起点；WebAssembly 中没有 `Instant`，退回到系统时间
+/// Timing start point; WebAssembly has no `Instant`, so it falls back to the system clock
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Timestamp {
+    #[cfg(not(target_arch = "wasm32"))]
+    instant: std::time::Instant,
+    #[cfg(target_arch = "wasm32")]
+    micros: i64,
+}
+
+impl Timestamp {
+    pub(crate) fn now() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            instant: std::time::Instant::now(),
+            #[cfg(target_arch = "wasm32")]
+            micros: chrono::Utc::now().timestamp_micros(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.instant.elapsed()
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            let now = chrono::Utc::now().timestamp_micros();
+            Duration::from_micros(now.saturating_sub(self.micros).max(0) as u64)
+        }
+    }
+}
+
+/// 单个函数的剖析数据 / Profile data of one function
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FunctionProfile {
+    /// 函数名 / Function name
+    pub name: String,
+    /// 调用次数 / Number of calls
+    pub calls: u64,
+    /// 总耗时（递归调用只计最外层）/ Total time (only the outermost of recursive calls counts)
+    pub total_time: Duration,
+    /// 自身耗时（不含被调用函数）/ Self time (excluding callees)
+    pub self_time: Duration,
+    /// 出错的调用次数 / Number of calls that failed
+    pub errors: u64,
+    /// 直接被本函数调用的函数的自身耗时，按被调用函数名 / Self time of the functions called directly by this one, by callee name
+    #[serde(default)]
+    pub callee_self_time: BTreeMap<String, Duration>,
+}
+
+/// 剖析报告 / Profile report
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileReport {
+    /// 被剖析代码的总耗时 / Total time of the profiled code
+    pub total_time: Duration,
+    /// 求值步数 / Evaluation steps
+    pub steps: u64,
+    /// 各函数数据，按总耗时降序 / Per-function data, by total time descending
+    pub functions: Vec<FunctionProfile>,
+}
+
+impl ProfileReport {
+    /// 按名称查找函数数据 / Find function data by name
+    pub fn function(&self, name: &str) -> Option<&FunctionProfile> {
+        self.functions.iter().find(|profile| profile.name == name)
+    }
+
+    /// 转换为运行时字典（供 `profile` 内置函数返回）/ Convert to a runtime dictionary (returned by the `profile` builtin)
+    pub fn to_value(&self) -> Value {
+        Value::Dict(self.to_dict().into())
+    }
+
+    pub(crate) fn to_dict(&self) -> DictMap {
+        let millis = |duration: Duration| Value::Float(duration.as_secs_f64() * 1000.0);
+        let functions = self
+            .functions
+            .iter()
+            .map(|profile| {
+                Value::Dict(Arc::new(DictMap::from([
+                    (
+                        "name".to_string(),
+                        Value::String(profile.name.as_str().into()),
+                    ),
+                    ("calls".to_string(), Value::Int(profile.calls as i64)),
+                    ("total_ms".to_string(), millis(profile.total_time)),
+                    ("self_ms".to_string(), millis(profile.self_time)),
+                    ("errors".to_string(), Value::Int(profile.errors as i64)),
+                ])))
+            })
+            .collect::<Vec<_>>();
+        DictMap::from([
+            ("total_ms".to_string(), millis(self.total_time)),
+            ("steps".to_string(), Value::Int(self.steps as i64)),
+            ("functions".to_string(), Value::List(functions.into())),
+        ])
+    }
+}
+
+/// 正在执行的调用 / Call in progress
+#[derive(Debug)]
+struct ActiveCall {
+    name: String,
+    /// 被调用函数的耗时之和 / Sum of callee durations
+    children: Duration,
+}
+
+#[derive(Debug, Default)]
+struct ProfilerState {
+    functions: HashMap<String, FunctionProfile>,
+    stack: Vec<ActiveCall>,
+}
+
+/// 汇总调用次数和耗时的追踪器；克隆共享同一份数据，安装后仍可从原句柄读取报告
+/// Tracer aggregating call counts and timings; clones share the same data, so the report can be read from the original handle after installing it
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    state: Arc<Mutex<ProfilerState>>,
+}
+
+impl Profiler {
+    /// 创建剖析器 / Create profiler
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 生成报告（总耗时和步数由调用方填写）/ Build the report (total time and steps are filled in by the caller)
+    pub fn report(&self) -> ProfileReport {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut functions: Vec<FunctionProfile> = state.functions.values().cloned().collect();
+        functions.sort_by(|a, b| {
+            b.total_time
+                .cmp(&a.total_time)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        ProfileReport {
+            functions,
+            ..ProfileReport::default()
+        }
+    }
+}
+
+impl Tracer for Profiler {
+    fn enter(&mut self, name: &str, _depth: usize) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.stack.push(ActiveCall {
+            name: name.to_string(),
+            children: Duration::ZERO,
+        });
+    }
+
+    fn exit(
+        &mut self,
+        name: &str,
+        _depth: usize,
+        elapsed: Duration,
+        result: Result<&Value, &InterpreterError>,
+    ) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let children = state
+            .stack
+            .pop()
+            .map_or(Duration::ZERO, |call| call.children);
+        let recursive = state.stack.iter().any(|call| call.name == name);
+        let self_time = elapsed.saturating_sub(children);
+        let caller = state.stack.last_mut().map(|parent| {
+            parent.children += elapsed;
+            parent.name.clone()
+        });
+        if let Some(caller) = caller {
+            let caller = state
+                .functions
+                .entry(caller.clone())
+                .or_insert_with(|| FunctionProfile {
+                    name: caller,
+                    ..FunctionProfile::default()
+                });
+            *caller.callee_self_time.entry(name.to_string()).or_default() += self_time;
+        }
+
+        let profile = state
+            .functions
+            .entry(name.to_string())
+            .or_insert_with(|| FunctionProfile {
+                name: name.to_string(),
+                ..FunctionProfile::default()
+            });
+        profile.calls += 1;
+        profile.self_time += self_time;
+        if !recursive {
+            profile.total_time += elapsed;
+        }
+        if result.is_err() {
+            profile.errors += 1;
+        }
+    }
+}
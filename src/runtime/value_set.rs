@@ -0,0 +1,220 @@
+// 集合值 / Set values
+// `Value::Set` 的存储：按插入顺序保存元素，按值的哈希建立索引
+// Storage of `Value::Set`: elements kept in insertion order, indexed by value hash
+
+use crate::runtime::interpreter::Value;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// 值的集合：元素唯一，迭代顺序为插入顺序
+/// Set of values: elements are unique and iterate in insertion order
+#[derive(Debug, Clone, Default)]
+pub struct ValueSet {
+    /// 元素（插入顺序）/ Elements (insertion order)
+    items: Vec<Value>,
+    /// 哈希值到元素下标 / Hash to element indices
+    buckets: HashMap<u64, Vec<usize>>,
+}
+
+impl ValueSet {
+    /// 创建空集合 / Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 由值序列构建集合，重复的值只保留第一个
+    /// Build a set from values, keeping the first of any duplicates
+    pub fn from_values(values: impl IntoIterator<Item = Value>) -> Result<Self, UnhashableValue> {
+        let mut set = Self::new();
+        for value in values {
+            set.insert(value)?;
+        }
+        Ok(set)
+    }
+
+    /// 加入元素，返回是否为新元素 / Insert an element, returning whether it was new
+    pub fn insert(&mut self, value: Value) -> Result<bool, UnhashableValue> {
+        let hash = value_hash(&value)?;
+        let bucket = self.buckets.entry(hash).or_default();
+        if bucket.iter().any(|&index| self.items[index] == value) {
+            return Ok(false);
+        }
+        bucket.push(self.items.len());
+        self.items.push(value);
+        Ok(true)
+    }
+
+    /// 是否包含元素；不可哈希的值不会在集合中 / Whether the set contains a value; unhashable values never do
+    pub fn contains(&self, value: &Value) -> bool {
+        value_hash(value)
+            .ok()
+            .and_then(|hash| self.buckets.get(&hash))
+            .is_some_and(|bucket| bucket.iter().any(|&index| self.items[index] == *value))
+    }
+
+    /// 并集：本集合的元素在前 / Union, with this set's elements first
+    pub fn union(&self, other: &ValueSet) -> ValueSet {
+        let mut result = self.clone();
+        for value in other.iter() {
+            if !result.contains(value) {
+                result.push_unique(value.clone());
+            }
+        }
+        result
+    }
+
+    /// 交集 / Intersection
+    pub fn intersection(&self, other: &ValueSet) -> ValueSet {
+        self.filtered(|value| other.contains(value))
+    }
+
+    /// 差集：在本集合而不在另一个集合中的元素 / Difference: elements in this set but not in the other
+    pub fn difference(&self, other: &ValueSet) -> ValueSet {
+        self.filtered(|value| !other.contains(value))
+    }
+
+    /// 去掉一个元素 / Remove an element
+    pub fn without(&self, value: &Value) -> ValueSet {
+        self.filtered(|item| item != value)
+    }
+
+    /// 是否为另一个集合的子集 / Whether this set is a subset of another
+    pub fn is_subset(&self, other: &ValueSet) -> bool {
+        self.iter().all(|value| other.contains(value))
+    }
+
+    /// 元素个数 / Number of elements
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// 是否为空 / Whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// 按插入顺序迭代元素 / Iterate elements in insertion order
+    pub fn iter(&self) -> std::slice::Iter<'_, Value> {
+        self.items.iter()
+    }
+
+    /// 保留满足条件的元素 / Keep the elements matching a predicate
+    fn filtered(&self, keep: impl Fn(&Value) -> bool) -> ValueSet {
+        let mut result = ValueSet::new();
+        for value in self.iter().filter(|value| keep(value)) {
+            result.push_unique(value.clone());
+        }
+        result
+    }
+
+    /// 加入已知可哈希且不重复的元素 / Add an element known to be hashable and not yet present
+    fn push_unique(&mut self, value: Value) {
+        if let Ok(hash) = value_hash(&value) {
+            self.buckets.entry(hash).or_default().push(self.items.len());
+            self.items.push(value);
+        }
+    }
+}
+
+/// 集合相等与元素顺序无关 / Set equality ignores element order
+impl PartialEq for ValueSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.is_subset(other)
+    }
+}
+
+impl Serialize for ValueSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.items.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let items = Vec::<Value>::deserialize(deserializer)?;
+        ValueSet::from_values(items).map_err(serde::de::Error::custom)
+    }
+}
+
+/// 不能放入集合的值（附带类型名）/ A value that cannot go into a set (with its type name)
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnhashableValue(pub &'static str);
+
+impl std::fmt::Display for UnhashableValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} values cannot be put in a set", self.0)
+    }
+}
+
+/// 计算值的哈希，与 `Value` 的相等性一致
+/// Hash a value consistently with `Value` equality
+///
+/// 规则 / Rules:
+/// - 浮点数按位哈希，`-0.0` 视同 `0.0`；NaN 不等于自身，不可哈希
+///   Floats hash by bits with `-0.0` treated as `0.0`; NaN is not equal to itself and is unhashable
+/// - 字典和集合的哈希与顺序无关 / Dicts and sets hash independently of order
+/// - 列表、变体和错误对象按元素哈希 / Lists, variants and errors hash by their elements
+/// - Lambda 和生成器是可变的运行时对象，不可哈希
+///   Lambdas and generators are mutable runtime objects and are unhashable
+pub fn value_hash(value: &Value) -> Result<u64, UnhashableValue> {
+    let mut hasher = DefaultHasher::new();
+    hash_into(value, &mut hasher)?;
+    Ok(hasher.finish())
+}
+
+fn hash_into(value: &Value, state: &mut DefaultHasher) -> Result<(), UnhashableValue> {
+    std::mem::discriminant(value).hash(state);
+    match value {
+        Value::Int(i) => i.hash(state),
+        Value::BigInt(n) => n.hash(state),
+        Value::Float(f) if f.is_nan() => return Err(UnhashableValue("NaN")),
+        Value::Float(f) => (if *f == 0.0 { 0.0f64 } else { *f }).to_bits().hash(state),
+        Value::String(s) => s.hash(state),
+        Value::Bool(b) => b.hash(state),
+        Value::Null => {}
+        Value::List(items) => {
+            items.len().hash(state);
+            for item in items.iter() {
+                hash_into(item, state)?;
+            }
+        }
+        Value::Dict(dict) => {
+            let mut combined = 0u64;
+            for (key, item) in dict.iter() {
+                let mut entry = DefaultHasher::new();
+                key.hash(&mut entry);
+                hash_into(item, &mut entry)?;
+                combined = combined.wrapping_add(entry.finish());
+            }
+            combined.hash(state);
+        }
+        Value::Set(set) => {
+            let mut combined = 0u64;
+            for item in set.iter() {
+                combined = combined.wrapping_add(value_hash(item)?);
+            }
+            combined.hash(state);
+        }
+        Value::Lambda { .. } => return Err(UnhashableValue("Lambda")),
+        Value::Generator { .. } => return Err(UnhashableValue("Generator")),
+        Value::Variant { tag, fields } => {
+            tag.hash(state);
+            for field in fields {
+                hash_into(field, state)?;
+            }
+        }
+        Value::DateTime(date) => date.hash(state),
+        Value::Error {
+            kind,
+            message,
+            data,
+        } => {
+            kind.hash(state);
+            message.hash(state);
+            hash_into(data, state)?;
+        }
+    }
+    Ok(())
+}
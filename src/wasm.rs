@@ -0,0 +1,107 @@
+// WebAssembly导出 / WebAssembly exports
+// 通过 wasm-bindgen 将解析器和解释器暴露给浏览器
+// Exposes the parser and interpreter to browsers via wasm-bindgen
+
+use crate::parser::{AdaptiveParser, CodeExplainer, Language};
+use crate::runtime::{Interpreter, OutputSink};
+use std::sync::{Arc, Mutex};
+use wasm_bindgen::prelude::*;
+
+/// 解析代码并返回JSON格式的AST / Parse code and return the AST as JSON
+#[wasm_bindgen]
+pub fn parse(code: &str) -> Result<String, JsError> {
+    let ast = AdaptiveParser::new(false)
+        .parse(code)
+        .map_err(|e| JsError::new(&format!("Parse error: {:?}", e)))?;
+    serde_json::to_string(&ast).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// 执行代码并返回结果字符串 / Execute code and return the result string
+#[wasm_bindgen]
+pub fn execute(code: &str) -> Result<String, JsError> {
+    EvoRuntime::new().execute(code)
+}
+
+/// 用自然语言解释代码（language 为 "zh" 或 "en"）/ Explain code in natural language (language is "zh" or "en")
+#[wasm_bindgen]
+pub fn explain(code: &str, language: &str) -> Result<String, JsError> {
+    let language = match language {
+        "en" | "english" => Language::English,
+        _ => Language::Chinese,
+    };
+    let (ast, comments) = AdaptiveParser::new(false)
+        .parse_with_comments(code)
+        .map_err(|e| JsError::new(&format!("Parse error: {:?}", e)))?;
+    let explainer = CodeExplainer::new(language);
+    Ok(ast
+        .iter()
+        .zip(&comments)
+        .map(|(element, comment)| explainer.explain_with_comment(element, comment.as_deref()))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// 结构化解释代码，返回JSON格式的解释树数组（每个节点含 kind、span、gloss、intent、children）
+/// Explain code structurally, returning a JSON array of explanation trees (each node has kind, span, gloss, intent, children)
+#[wasm_bindgen(js_name = explainTree)]
+pub fn explain_tree(code: &str, language: &str) -> Result<String, JsError> {
+    let language = match language {
+        "en" | "english" => Language::English,
+        _ => Language::Chinese,
+    };
+    let document = AdaptiveParser::new(false)
+        .parse_document(code)
+        .map_err(|e| JsError::new(&format!("Parse error: {:?}", e)))?;
+    let explanations = CodeExplainer::new(language).explain_document(&document, None);
+    serde_json::to_string(&explanations).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// 保持状态的浏览器运行时，`print` 输出被缓存供页面读取
+/// Stateful browser runtime; `print` output is buffered for the page to read
+#[wasm_bindgen]
+pub struct EvoRuntime {
+    interpreter: Interpreter,
+    output: Arc<Mutex<Vec<u8>>>,
+}
+
+#[wasm_bindgen]
+impl EvoRuntime {
+    /// 创建新运行时 / Create new runtime
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        // 浏览器中没有文件系统 / There is no file system in the browser
+        let mut interpreter =
+            Interpreter::with_options(crate::runtime::InterpreterOptions::sandboxed());
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let sink: OutputSink = output.clone();
+        interpreter.set_output_sink(Some(sink));
+        Self {
+            interpreter,
+            output,
+        }
+    }
+
+    /// 执行代码并返回结果字符串 / Execute code and return the result string
+    pub fn execute(&mut self, code: &str) -> Result<String, JsError> {
+        let ast = AdaptiveParser::new(false)
+            .parse(code)
+            .map_err(|e| JsError::new(&format!("Parse error: {:?}", e)))?;
+        self.interpreter
+            .execute(&ast)
+            .map(|value| value.to_string())
+            .map_err(|e| JsError::new(&format!("Execution error: {}", e)))
+    }
+
+    /// 取出并清空已缓存的输出 / Take and clear the buffered output
+    #[wasm_bindgen(js_name = takeOutput)]
+    pub fn take_output(&mut self) -> String {
+        let mut output = self.output.lock().unwrap_or_else(|e| e.into_inner());
+        String::from_utf8_lossy(&std::mem::take(&mut *output)).to_string()
+    }
+}
+
+impl Default for EvoRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}